@@ -0,0 +1,110 @@
+//! # Cancellation
+//!
+//! Cooperative cancellation of long compiles. A [`CancellationToken`] travels
+//! with the `CompilerOptions`; the parser, the semantic analyzer and the code
+//! generators check it at every item and statement and stop with
+//! [`Cancelled`] once it has been cancelled or its deadline has passed. This
+//! is how `albayan build --timeout` and the language server interrupt
+//! runaway analyses without killing the process.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Why a compile stopped early
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum Cancelled {
+    #[error("compilation cancelled")]
+    Requested,
+
+    #[error("compilation timed out after {}s", .0.as_secs_f64())]
+    TimedOut(Duration),
+}
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    /// Time limit and the instant it runs out
+    deadline: Option<(Duration, Instant)>,
+}
+
+/// Shared flag telling a compile to stop; clones observe the same flag
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<TokenState>,
+}
+
+impl CancellationToken {
+    /// A token that only stops when [`cancel`](Self::cancel) is called
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token that also stops once `timeout` has elapsed from now
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            state: Arc::new(TokenState {
+                cancelled: AtomicBool::new(false),
+                deadline: Some((timeout, Instant::now() + timeout)),
+            }),
+        }
+    }
+
+    /// Ask every holder of the token to stop at its next checkpoint
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.check().is_err()
+    }
+
+    /// Checkpoint: `Err` once the token is cancelled or past its deadline
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.state.cancelled.load(Ordering::Relaxed) {
+            return Err(Cancelled::Requested);
+        }
+        match self.state.deadline {
+            Some((timeout, deadline)) if Instant::now() >= deadline => Err(Cancelled::TimedOut(timeout)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Cancel the token when the returned guard is dropped, e.g. when the
+    /// future waiting for a background analysis is aborted
+    pub fn cancel_on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+}
+
+/// Cancels its token when dropped
+#[derive(Debug)]
+pub struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_cancel_explicitly_on_drop_and_on_timeout() {
+        let token = CancellationToken::new();
+        let observer = token.clone();
+        assert_eq!(observer.check(), Ok(()));
+        token.cancel();
+        assert_eq!(observer.check(), Err(Cancelled::Requested));
+
+        let token = CancellationToken::new();
+        drop(token.cancel_on_drop());
+        assert!(token.is_cancelled());
+
+        let token = CancellationToken::with_timeout(Duration::ZERO);
+        assert_eq!(token.check(), Err(Cancelled::TimedOut(Duration::ZERO)));
+        assert!(!CancellationToken::with_timeout(Duration::from_secs(3600)).is_cancelled());
+    }
+}
@@ -0,0 +1,168 @@
+//! # Localized Diagnostics
+//!
+//! Arabic wording of the diagnostics a program meets most often: invalid
+//! tokens, syntax errors, undefined names, type errors, moves, assignment and
+//! the unused-name warnings. A diagnostic without a translation keeps its
+//! English text; its code is the same in every language.
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+use super::Diagnostic;
+use crate::lexer::LexerError;
+use crate::parser::ParseError;
+use crate::semantic::warnings::WarningKind;
+use crate::semantic::{SemanticError, SemanticWarning};
+use crate::CompilerError;
+
+/// Language diagnostics are worded in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    English,
+    Arabic,
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "en" | "english" => Ok(Language::English),
+            "ar" | "arabic" | "العربية" => Ok(Language::Arabic),
+            other => Err(format!("unknown language `{}`; expected `en` or `ar`", other)),
+        }
+    }
+}
+
+/// Environment variable naming the language of diagnostics, `en` or `ar`
+pub const LANGUAGE_ENV: &str = "ALBAYAN_LANG";
+
+/// Language of the diagnostics this process prints
+static LANGUAGE: OnceLock<AtomicU8> = OnceLock::new();
+
+fn global_language() -> &'static AtomicU8 {
+    LANGUAGE.get_or_init(|| {
+        let language: Language = std::env::var(LANGUAGE_ENV)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or_default();
+        AtomicU8::new(language as u8)
+    })
+}
+
+/// Print the diagnostics of `emit` and `emit_warning` in `language`
+pub fn set_language(language: Language) {
+    global_language().store(language as u8, Ordering::Relaxed);
+}
+
+/// The language set with [`set_language`], else the one `ALBAYAN_LANG`
+/// names, else English
+pub fn language() -> Language {
+    match global_language().load(Ordering::Relaxed) {
+        1 => Language::Arabic,
+        _ => Language::English,
+    }
+}
+
+/// Severity and footer words: `error`, `warning`, `note` and `help`
+pub(super) fn word(word: &'static str, language: Language) -> &'static str {
+    match (language, word) {
+        (Language::Arabic, "error") => "خطأ",
+        (Language::Arabic, "warning") => "تحذير",
+        (Language::Arabic, "note") => "ملاحظة",
+        (Language::Arabic, "help") => "مساعدة",
+        _ => word,
+    }
+}
+
+/// The Arabic diagnostic of `error`, if its wording is translated
+pub(super) fn arabic(error: &CompilerError) -> Option<Diagnostic> {
+    match error {
+        CompilerError::LexicalError(error) => arabic_lexer(error),
+        CompilerError::ParseError(error) => arabic_parser(error),
+        CompilerError::SemanticError(error) => arabic_semantic(error),
+        _ => None,
+    }
+}
+
+fn arabic_lexer(error: &LexerError) -> Option<Diagnostic> {
+    Some(match error {
+        LexerError::InvalidToken { .. } => Diagnostic::error("AB0001", "رمز غير صالح").with_label("حرف غير معروف"),
+        LexerError::UnterminatedInterpolation { .. } => Diagnostic::error("AB0003", "تعبير غير مغلق داخل نص")
+            .with_label("هذا القوس `{` لا يُغلق")
+            .with_suggestion("أغلق التعبير بـ `}`، أو اكتب `{{` لقوس حرفي"),
+        LexerError::UnexpectedEof => Diagnostic::error("AB0002", "نهاية غير متوقعة للملف"),
+    })
+}
+
+fn arabic_parser(error: &ParseError) -> Option<Diagnostic> {
+    match error {
+        ParseError::UnexpectedToken { expected, found } => {
+            let diagnostic = Diagnostic::error("AB0100", format!("رمز غير متوقع {:?}", found.token_type))
+                .with_label("لم يُتوقع هذا الرمز هنا");
+            match expected.strip_prefix("Expected '").and_then(|rest| rest.split_once('\'')) {
+                Some((token, _)) => Some(diagnostic.with_suggestion(format!("أدخل `{}` قبل هذا الرمز", token))),
+                None => Some(diagnostic.with_note(expected.clone())),
+            }
+        }
+        ParseError::UnexpectedEof => Some(Diagnostic::error("AB0101", "نهاية غير متوقعة للملف")
+            .with_suggestion("ابحث عن `{` أو `(` أو `[` لم يُغلق")),
+        ParseError::InvalidSyntax { .. } | ParseError::Cancelled(_) => None,
+    }
+}
+
+fn arabic_semantic(error: &SemanticError) -> Option<Diagnostic> {
+    let diagnostic = match error.inner() {
+        SemanticError::UndefinedVariable(name) => Diagnostic::error("AB0201", format!("المتغير `{}` غير معرّف", name))
+            .with_label("غير موجود في هذا النطاق")
+            .with_suggestion(format!("عرّفه أولًا بـ `دع {} = ...;`", name)),
+        SemanticError::UndefinedRelation(name) => Diagnostic::error("AB0202", format!("العلاقة `{}` غير معرّفة", name))
+            .with_suggestion(format!("أعلنها بـ `علاقة {}(...);`", name)),
+        SemanticError::UndefinedType(name) => Diagnostic::error("AB0203", format!("النوع `{}` غير معرّف", name)),
+        SemanticError::UndefinedField { struct_name, field_name } => Diagnostic::error(
+            "AB0204", format!("الهيكل `{}` ليس فيه حقل `{}`", struct_name, field_name)),
+        SemanticError::MissingField { struct_name, field_name } => Diagnostic::error(
+            "AB0205", format!("الحقل `{}` ناقص في إنشاء `{}`", field_name, struct_name)),
+        SemanticError::Redefinition(name) => Diagnostic::error("AB0207", format!("`{}` معرّف أكثر من مرة", name)),
+        SemanticError::TypeMismatch { expected, found } => Diagnostic::error("AB0210", "الأنواع غير متطابقة")
+            .with_label(format!("المتوقع `{:?}`، والموجود `{:?}`", expected, found)),
+        SemanticError::ArityMismatch { expected, found } => Diagnostic::error(
+            "AB0211", format!("هذا الاستدعاء يأخذ {} معاملًا لكنه أُعطي {}", expected, found)),
+        SemanticError::CannotInferType(name) => Diagnostic::error("AB0212", format!("لا يمكن استنتاج نوع `{}`", name))
+            .with_suggestion(format!("اذكر نوعه: `دع {}: int = ...;`", name)),
+        SemanticError::MissingReturn(name) => Diagnostic::error("AB0214", format!("الدالة `{}` لا ترجع قيمة في كل المسارات", name))
+            .with_suggestion("أضف `أرجع` في آخر الدالة"),
+        SemanticError::NonExhaustiveMatch { missing_patterns } => Diagnostic::error("AB0217", "المطابقة لا تغطي كل الحالات")
+            .with_label(format!("الأنماط {} غير مغطاة", missing_patterns.join("، ")))
+            .with_suggestion("أضف الفروع الناقصة أو فرعًا عامًا `_ => ...`"),
+        SemanticError::ControlFlowOutsideLoop(keyword) => Diagnostic::error("AB0218", format!("`{}` خارج حلقة", keyword))
+            .with_note(format!("لا تُستعمل `{}` إلا داخل `بينما` أو `لكل` أو `loop`", keyword)),
+        SemanticError::UseAfterMove(message) => Diagnostic::error("AB0230", format!("استعمال بعد النقل: {}", message))
+            .with_suggestion("استعر القيمة بـ `&` بدل نقلها"),
+        SemanticError::AssignToImmutable(name) => Diagnostic::error(
+            "AB0237", format!("لا يمكن الإسناد مرتين إلى المتغير الثابت `{}`", name))
+            .with_suggestion(format!("اجعله قابلًا للتغيير: `دع متغير {}`", name)),
+        SemanticError::UseOfUninitialized(message) => Diagnostic::error(
+            "AB0251", format!("استعمال متغير قد لا تكون له قيمة: {}", message))
+            .with_suggestion("أسند إليه قيمة في كل مسار قبل استعماله"),
+        _ => return None,
+    };
+    Some(match error {
+        SemanticError::InModule { module, .. } => diagnostic.with_note(format!("في الوحدة `{}`", module)),
+        _ => diagnostic,
+    })
+}
+
+/// The Arabic diagnostic of `warning`
+pub(super) fn arabic_warning(warning: &SemanticWarning) -> Diagnostic {
+    match warning.kind {
+        WarningKind::UnusedVariable => Diagnostic::warning("AB0245", format!("المتغير `{}` غير مستعمل", warning.name))
+            .with_suggestion(format!("احذفه، أو سمّه `_{}` إن كان ذلك مقصودًا", warning.name)),
+        WarningKind::UnusedParameter => Diagnostic::warning("AB0246", format!("المعامل `{}` غير مستعمل", warning.name))
+            .with_suggestion(format!("سمّه `_{}` إن كان ذلك مقصودًا", warning.name)),
+        WarningKind::UnusedImport => Diagnostic::warning("AB0247", format!("الاستيراد `{}` غير مستعمل", warning.name))
+            .with_suggestion("احذف تصريح `using`"),
+    }
+}
@@ -0,0 +1,549 @@
+//! # Diagnostics Module
+//!
+//! Renders compiler errors rustc-style: an error code, the offending source
+//! line with a caret underline, and optional help/notes.
+//!
+//! ```text
+//! error[AB0201]: undefined variable `y`
+//!  --> main.ab:3:5
+//!   |
+//! 3 |     return y;
+//!   |     ^^^^^^^^^ not found in this scope
+//!   |
+//!   = help: declare it first with `let y = ...;`
+//! ```
+//!
+//! [`Diagnostic::localized`] words the common diagnostics in Arabic instead;
+//! see [`locale`].
+
+use std::fmt;
+
+pub mod locale;
+
+pub use locale::{language, set_language, Language};
+
+use crate::cancellation::Cancelled;
+use crate::lexer::{LexerError, LineIndex, Span};
+use crate::modules::graph::ModuleError;
+use crate::parser::ParseError;
+use crate::semantic::warnings::WarningKind;
+use crate::semantic::{SemanticError, SemanticWarning};
+use crate::CompilerError;
+
+/// Diagnostic severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+/// A renderable compiler diagnostic
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable error code, e.g. `AB0201`
+    pub code: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+    /// Text shown next to the caret underline
+    pub label: Option<String>,
+    pub suggestion: Option<String>,
+    pub notes: Vec<String>,
+}
+
+/// Renders diagnostics against a source file
+pub struct DiagnosticRenderer<'a> {
+    file_name: &'a str,
+    line_index: LineIndex,
+    color: bool,
+    language: Language,
+}
+
+impl Severity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+
+    fn color_code(&self) -> &'static str {
+        match self {
+            Severity::Error => "\x1b[1;31m",
+            Severity::Warning => "\x1b[1;33m",
+            Severity::Note => "\x1b[1;32m",
+        }
+    }
+}
+
+impl Severity {
+    fn name(&self, language: Language) -> &'static str {
+        locale::word(self.as_str(), language)
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Diagnostic {
+    /// Create an error diagnostic
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+            span: None,
+            label: None,
+            suggestion: None,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Create a warning diagnostic
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, message)
+        }
+    }
+
+    pub fn with_span(mut self, span: Option<Span>) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// The diagnostic of `error` worded in `language`; one without a
+    /// translation stays in English
+    pub fn localized(error: &CompilerError, language: Language) -> Self {
+        let english = Diagnostic::from(error);
+        match language {
+            Language::English => english,
+            Language::Arabic => match locale::arabic(error) {
+                Some(arabic) => Diagnostic { severity: english.severity, span: english.span, ..arabic },
+                None => english,
+            },
+        }
+    }
+
+    /// The diagnostic of `warning` worded in `language`
+    pub fn localized_warning(warning: &SemanticWarning, language: Language) -> Self {
+        let english = Diagnostic::from(warning);
+        match language {
+            Language::English => english,
+            Language::Arabic => Diagnostic { severity: english.severity, span: english.span, ..locale::arabic_warning(warning) },
+        }
+    }
+}
+
+impl From<&LexerError> for Diagnostic {
+    fn from(error: &LexerError) -> Self {
+        match error {
+            LexerError::InvalidToken { .. } => Diagnostic::error("AB0001", "invalid token")
+                .with_span(error.span())
+                .with_label("unrecognized character"),
+            LexerError::UnterminatedInterpolation { .. } => Diagnostic::error("AB0003", "unterminated interpolation in string")
+                .with_span(error.span())
+                .with_label("this `{` is never closed")
+                .with_suggestion("close the expression with `}`, or write `{{` for a literal brace"),
+            LexerError::UnexpectedEof => Diagnostic::error("AB0002", "unexpected end of input"),
+        }
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        match error {
+            ParseError::UnexpectedToken { expected, found } => {
+                let diagnostic = Diagnostic::error("AB0100", format!("{}, found {:?}", expected, found.token_type))
+                    .with_span(error.span())
+                    .with_label("unexpected token");
+                // "Expected ';' after ..." -> suggest inserting the missing token
+                match expected.strip_prefix("Expected '").and_then(|rest| rest.split_once('\'')) {
+                    Some((token, _)) => diagnostic.with_suggestion(format!("insert `{}` before this token", token)),
+                    None => diagnostic,
+                }
+            }
+            ParseError::UnexpectedEof => Diagnostic::error("AB0101", "unexpected end of input")
+                .with_suggestion("check for an unclosed `{`, `(` or `[`"),
+            ParseError::InvalidSyntax { message, .. } => Diagnostic::error("AB0102", message.clone())
+                .with_span(error.span()),
+            ParseError::Cancelled(cancelled) => cancelled.into(),
+        }
+    }
+}
+
+impl From<&Cancelled> for Diagnostic {
+    fn from(cancelled: &Cancelled) -> Self {
+        match cancelled {
+            Cancelled::Requested => Diagnostic::error("AB0600", cancelled.to_string()),
+            Cancelled::TimedOut(_) => Diagnostic::error("AB0601", cancelled.to_string())
+                .with_suggestion("raise the limit with `--timeout <SECONDS>`"),
+        }
+    }
+}
+
+impl From<&SemanticError> for Diagnostic {
+    fn from(error: &SemanticError) -> Self {
+        let diagnostic = match error.inner() {
+            SemanticError::UndefinedVariable(name) => Diagnostic::error("AB0201", format!("undefined variable `{}`", name))
+                .with_label("not found in this scope")
+                .with_suggestion(format!("declare it first with `let {} = ...;`", name)),
+            SemanticError::UndefinedRelation(name) => Diagnostic::error("AB0202", format!("undefined relation `{}`", name))
+                .with_suggestion(format!("declare it with `relation {}(...);`", name)),
+            SemanticError::UndefinedType(name) => Diagnostic::error("AB0203", format!("undefined type `{}`", name)),
+            SemanticError::UndefinedField { struct_name, field_name } => Diagnostic::error(
+                "AB0204", format!("struct `{}` has no field `{}`", struct_name, field_name)),
+            SemanticError::MissingField { struct_name, field_name } => Diagnostic::error(
+                "AB0205", format!("missing field `{}` in initializer of `{}`", field_name, struct_name)),
+            SemanticError::UndefinedVariant { enum_name, variant_name } => Diagnostic::error(
+                "AB0206", format!("enum `{}` has no variant `{}`", enum_name, variant_name)),
+            SemanticError::TypeMismatch { expected, found } => Diagnostic::error("AB0210", "mismatched types")
+                .with_label(format!("expected `{:?}`, found `{:?}`", expected, found)),
+            SemanticError::ArityMismatch { expected, found } => Diagnostic::error(
+                "AB0211", format!("this call takes {} argument(s) but {} were supplied", expected, found)),
+            SemanticError::UnboundVariable(name) => Diagnostic::error("AB0220", format!("unbound variable `{}` in rule head", name))
+                .with_suggestion(format!("use `{}` in the rule body too", name)),
+            SemanticError::UnsafeNegation { variable, relation } => Diagnostic::error(
+                "AB0248", format!("variable `{}` of `not {}(...)` is not bound by a positive goal", variable, relation))
+                .with_note("a negated goal can only test values that the other goals of the rule have found"),
+            SemanticError::UnstratifiedNegation { relation, negated } => Diagnostic::error(
+                "AB0249", format!("`{}` depends on `not {}(...)`, which depends on `{}` again", relation, negated, relation))
+                .with_note("a relation cannot be defined through the negation of itself"),
+            SemanticError::MisplacedGoal(goal) => Diagnostic::error(
+                "AB0250", format!("`{}` is used as an argument but only aggregates take a goal", goal))
+                .with_note("findall, count, sum_of, min_of and max_of take the goal they solve as an argument"),
+            SemanticError::CannotInferType(name) => Diagnostic::error("AB0212", format!("cannot infer the type of `{}`", name))
+                .with_suggestion(format!("add a type annotation: `let {}: int = ...;`", name)),
+            SemanticError::Redefinition(name) => Diagnostic::error("AB0207", format!("`{}` is defined multiple times", name)),
+            SemanticError::InvalidBinaryOperation(operator, left, right) => Diagnostic::error(
+                "AB0213", format!("cannot apply `{:?}` to `{:?}` and `{:?}`", operator, left, right)),
+            SemanticError::MissingReturn(name) => Diagnostic::error("AB0214", format!("function `{}` does not return on every path", name))
+                .with_suggestion("add a `return` statement at the end of the function"),
+            SemanticError::UnreachableCode(message) => Diagnostic::error("AB0215", format!("unreachable code: {}", message)),
+            SemanticError::PatternTypeMismatch { expected, found } => Diagnostic::error("AB0216", "mismatched pattern type")
+                .with_label(format!("expected `{:?}`, found pattern for `{:?}`", expected, found)),
+            SemanticError::NonExhaustiveMatch { missing_patterns } => Diagnostic::error("AB0217", "non-exhaustive match")
+                .with_label(format!("patterns {} not covered", missing_patterns.join(", ")))
+                .with_suggestion("add the missing arms or a wildcard arm `_ => ...`"),
+            SemanticError::ControlFlowOutsideLoop(keyword) => Diagnostic::error("AB0218", format!("`{}` outside of a loop", keyword))
+                .with_note(format!("`{}` can only be used inside a `while`, `for` or `loop` body", keyword)),
+            SemanticError::NotDisplayable(ty) => Diagnostic::error("AB0208", format!("`{:?}` cannot be formatted into a string", ty))
+                .with_label("not displayable")
+                .with_note("only int, float, bool, char and string values can be interpolated"),
+            SemanticError::InvalidFormatString(message) => Diagnostic::error("AB0209", "invalid format string")
+                .with_label(message.clone())
+                .with_note("`{}` takes the next value, `{:.N}` a float with N decimals; write `{{` and `}}` for braces"),
+            SemanticError::InvalidTupleIndex { index: Some(index), len } => Diagnostic::error(
+                "AB0219", format!("tuple index {} is out of range for a tuple of {} element(s)", index, len)),
+            SemanticError::InvalidTupleIndex { index: None, .. } => Diagnostic::error("AB0219", "tuple index is not a constant")
+                .with_note("tuple elements have different types, so they are indexed with a literal like `t.0` or `t[0]`"),
+            SemanticError::VariableInFact(name) => Diagnostic::error("AB0221", format!("variable `{}` in fact", name))
+                .with_note("facts must be ground; use a rule to introduce variables"),
+            SemanticError::UndefinedAssociatedType { trait_name, name } => Diagnostic::error(
+                "AB0222", format!("trait `{}` has no associated type `{}`", trait_name, name)),
+            SemanticError::MissingAssociatedType { trait_name, type_name, name } => Diagnostic::error(
+                "AB0223", format!("impl of `{}` for `{}` is missing associated type `{}`", trait_name, type_name, name))
+                .with_suggestion(format!("add `type {} = ...;` to the impl", name)),
+            SemanticError::ConflictingImpl { trait_name, type_name } => Diagnostic::error(
+                "AB0224", format!("conflicting implementations of trait `{}` for type `{}`", trait_name, type_name))
+                .with_note("a type may implement each trait only once"),
+            SemanticError::MissingTraitMethod { trait_name, type_name, method } => Diagnostic::error(
+                "AB0225", format!("impl of `{}` for `{}` is missing method `{}`", trait_name, type_name, method)),
+            SemanticError::TraitMethodMismatch { trait_name, method, expected, found } => Diagnostic::error(
+                "AB0226", format!("method `{}` has an incompatible signature for trait `{}`", method, trait_name))
+                .with_label(format!("expected `{:?}`, found `{:?}`", expected, found)),
+            SemanticError::ConstEvaluation(message) => Diagnostic::error(
+                "AB0227", format!("cannot evaluate constant expression: {}", message))
+                .with_note("constants may use literals, other constants, arithmetic, comparison and logical operators, and `+` on strings"),
+            SemanticError::IndexOutOfBounds { index, length } => Diagnostic::error(
+                "AB0228", format!("index {} is out of bounds for an array of length {}", index, length))
+                .with_label("this index is always out of bounds"),
+            SemanticError::UnhashableKey(found) => Diagnostic::error(
+                "AB0229", format!("`{:?}` cannot be a map key or set element", found))
+                .with_note("keys and set elements must be `int`, `bool`, `char` or `string`"),
+            SemanticError::UseAfterMove(message) => Diagnostic::error("AB0230", format!("use after move: {}", message))
+                .with_suggestion("borrow the value with `&` instead of moving it"),
+            SemanticError::UseOfUninitialized(message) => Diagnostic::error(
+                "AB0251", format!("use of possibly-uninitialized variable: {}", message))
+                .with_suggestion("assign it a value on every path before this use"),
+            SemanticError::ConflictingBorrow(message) => Diagnostic::error("AB0231", format!("conflicting borrow: {}", message)),
+            SemanticError::WriteWhileBorrowed(message) => Diagnostic::error("AB0232", format!("write while borrowed: {}", message)),
+            SemanticError::BorrowMutableFromImmutable(message) => Diagnostic::error(
+                "AB0233", format!("cannot borrow as mutable: {}", message)),
+            SemanticError::AssignToImmutable(name) => Diagnostic::error(
+                "AB0237", format!("cannot assign twice to immutable variable `{}`", name))
+                .with_suggestion(format!("make the binding mutable: `let mut {}`", name)),
+            SemanticError::AssignThroughSharedReference(name) => Diagnostic::error(
+                "AB0238", format!("cannot assign through `{}`, which is a `&` reference", name))
+                .with_suggestion("take a `&mut` reference instead"),
+            SemanticError::PrivateItem { kind, name, module } => Diagnostic::error(
+                "AB0239", format!("{} `{}` is private to module `{}`", kind, name, module))
+                .with_suggestion(format!("mark it `pub` in module `{}` to use it here", module)),
+            SemanticError::InvalidBorrow(message) => Diagnostic::error("AB0234", format!("invalid borrow: {}", message)),
+            SemanticError::BorrowConflict { variable, existing_borrow, new_borrow } => Diagnostic::error(
+                "AB0235", format!("cannot borrow `{}` as {:?} because it is already borrowed as {:?}",
+                                  variable, new_borrow, existing_borrow)),
+            SemanticError::DanglingReference { message, .. } => Diagnostic::error("AB0236", format!("dangling reference: {}", message))
+                .with_suggestion("return an owned value instead of a reference to a local"),
+            SemanticError::ConceptCycle(name) => Diagnostic::error(
+                "AB0243", format!("concept `{}` is below itself in the `is_a` hierarchy", name))
+                .with_note("a concept's parents may not include the concept or any concept below it"),
+            SemanticError::FeatureDisabled { feature, name } => Diagnostic::error(
+                "AB0244", format!("`{}` is not available in this build", name))
+                .with_note(format!("it needs AlBayan built with the `{}` cargo feature", feature)),
+            SemanticError::DeniedWarning(warning) => Diagnostic { severity: Severity::Error, ..Diagnostic::from(warning) }
+                .with_note(format!("`{}` is denied; `-W {}` reports it as a warning instead", warning.kind.name(), warning.kind.name())),
+            SemanticError::Cancelled(cancelled) => cancelled.into(),
+            other => Diagnostic::error("AB0299", other.to_string()),
+        };
+        match error {
+            // The span is in the module's file, not the one being rendered
+            SemanticError::InModule { module, .. } => diagnostic.with_note(format!("in module `{}`", module)),
+            _ => diagnostic.with_span(error.span()),
+        }
+    }
+}
+
+impl From<&SemanticWarning> for Diagnostic {
+    fn from(warning: &SemanticWarning) -> Self {
+        let diagnostic = match warning.kind {
+            WarningKind::UnusedVariable => Diagnostic::warning("AB0245", warning.to_string())
+                .with_suggestion(format!("remove it, or name it `_{}` if it is unused on purpose", warning.name)),
+            WarningKind::UnusedParameter => Diagnostic::warning("AB0246", warning.to_string())
+                .with_suggestion(format!("name it `_{}` if it is unused on purpose", warning.name)),
+            WarningKind::UnusedImport => Diagnostic::warning("AB0247", warning.to_string())
+                .with_suggestion("remove the `using` declaration"),
+        };
+        diagnostic.with_span(warning.span)
+    }
+}
+
+impl From<&ModuleError> for Diagnostic {
+    fn from(error: &ModuleError) -> Self {
+        match error {
+            ModuleError::Cycle(_) => Diagnostic::error("AB0700", error.to_string())
+                .with_note("modules cannot import each other; move the shared items into a module both import"),
+            ModuleError::NotFound { candidates, .. } => {
+                let tried: Vec<String> = candidates.iter().map(|path| format!("`{}`", path.display())).collect();
+                Diagnostic::error("AB0701", error.to_string())
+                    .with_note(format!("looked for {}", tried.join(", ")))
+            }
+            ModuleError::Syntax { .. } => Diagnostic::error("AB0702", error.to_string()),
+        }
+    }
+}
+
+impl From<&CompilerError> for Diagnostic {
+    fn from(error: &CompilerError) -> Self {
+        match error {
+            CompilerError::LexicalError(error) => error.into(),
+            CompilerError::ParseError(error) => error.into(),
+            CompilerError::SemanticError(error) => error.into(),
+            CompilerError::ModuleError(error) => error.into(),
+            CompilerError::CodeGenError(message) => Diagnostic::error("AB0300", message.clone()),
+            CompilerError::RuntimeError(message) => Diagnostic::error("AB0400", message.clone()),
+            CompilerError::IoError(error) => Diagnostic::error("AB0500", error.to_string()),
+            CompilerError::Cancelled(cancelled) => cancelled.into(),
+        }
+    }
+}
+
+impl<'a> DiagnosticRenderer<'a> {
+    /// Create a renderer for a source file
+    pub fn new(file_name: &'a str, source: &str) -> Self {
+        Self {
+            file_name,
+            line_index: LineIndex::new(source),
+            color: false,
+            language: Language::English,
+        }
+    }
+
+    /// Word severities, `help` and `note` in `language`
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Enable ANSI colors
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}\x1b[0m", style, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Render a diagnostic to a string (ends with a newline)
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let blue = "\x1b[1;34m";
+        let severity_style = diagnostic.severity.color_code();
+        let mut out = format!(
+            "{}{}\n",
+            self.paint(severity_style, &format!("{}[{}]", diagnostic.severity.name(self.language), diagnostic.code)),
+            self.paint("\x1b[1m", &format!(": {}", diagnostic.message)),
+        );
+
+        let Some(span) = diagnostic.span else {
+            out.push_str(&format!(" {} {}\n", self.paint(blue, "-->"), self.file_name));
+            self.render_footer(diagnostic, "", &mut out);
+            return out;
+        };
+
+        let start = self.line_index.line_col(span.start);
+        let end = self.line_index.line_col(span.end.max(span.start));
+        let line_text = self.line_index.line_text(start.line).unwrap_or("");
+        let gutter = " ".repeat(start.line.to_string().len());
+
+        // Multi-line spans are underlined to the end of their first line
+        let line_width = line_text.chars().count();
+        let underline_end = if end.line == start.line { end.column } else { line_width + 1 };
+        let underline = "^".repeat(underline_end.saturating_sub(start.column).max(1));
+
+        out.push_str(&format!("{}{} {}:{}:{}\n", gutter, self.paint(blue, "-->"), self.file_name, start.line, start.column));
+        out.push_str(&format!("{} {}\n", gutter, self.paint(blue, "|")));
+        out.push_str(&format!("{} {} {}\n", self.paint(blue, &start.line.to_string()), self.paint(blue, "|"), line_text));
+        out.push_str(&format!(
+            "{} {} {}{}{}\n",
+            gutter,
+            self.paint(blue, "|"),
+            " ".repeat(start.column - 1),
+            self.paint(severity_style, &underline),
+            diagnostic.label.as_ref()
+                .map(|label| format!(" {}", self.paint(severity_style, label)))
+                .unwrap_or_default(),
+        ));
+        self.render_footer(diagnostic, &gutter, &mut out);
+        out
+    }
+
+    fn render_footer(&self, diagnostic: &Diagnostic, gutter: &str, out: &mut String) {
+        if diagnostic.suggestion.is_none() && diagnostic.notes.is_empty() {
+            return;
+        }
+        let blue = "\x1b[1;34m";
+        out.push_str(&format!("{} {}\n", gutter, self.paint(blue, "|")));
+        if let Some(suggestion) = &diagnostic.suggestion {
+            let help = format!("{}: {}", locale::word("help", self.language), suggestion);
+            out.push_str(&format!("{} {} {}\n", gutter, self.paint(blue, "="), self.paint("\x1b[1m", &help)));
+        }
+        for note in &diagnostic.notes {
+            out.push_str(&format!("{} {} {}: {}\n", gutter, self.paint(blue, "="), locale::word("note", self.language), note));
+        }
+    }
+}
+
+/// Print a compiler error as a rendered diagnostic on stderr, in the
+/// language set with [`set_language`]
+pub fn emit(error: &CompilerError, file_name: &str, source: &str) {
+    emit_diagnostic(&Diagnostic::localized(error, language()), file_name, source);
+}
+
+/// Print a warning like [`emit`] does an error
+pub fn emit_warning(warning: &SemanticWarning, file_name: &str, source: &str) {
+    emit_diagnostic(&Diagnostic::localized_warning(warning, language()), file_name, source);
+}
+
+/// Print a diagnostic rendered against its source file on stderr
+pub fn emit_diagnostic(diagnostic: &Diagnostic, file_name: &str, source: &str) {
+    use std::io::IsTerminal;
+
+    let renderer = DiagnosticRenderer::new(file_name, source)
+        .with_color(std::io::stderr().is_terminal())
+        .with_language(language());
+    eprint!("{}", renderer.render(diagnostic));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompilerOptions, CompilerResult, Lexer, Parser, SemanticAnalyzer};
+
+    fn compile_error(source: &str) -> CompilerError {
+        let check = || -> CompilerResult<()> {
+            let ast = Parser::new(Lexer::new(source).tokenize()?).parse()?;
+            SemanticAnalyzer::new(&CompilerOptions::default()).analyze(ast)?;
+            Ok(())
+        };
+        check().expect_err("source should fail to check")
+    }
+
+    #[test]
+    fn test_render_semantic_error() {
+        let source = "fn main() -> int {\n    let x: int = 42;\n    return y;\n}";
+        let diagnostic = Diagnostic::from(&compile_error(source));
+        assert_eq!(diagnostic.code, "AB0201");
+
+        let rendered = DiagnosticRenderer::new("main.ab", source).render(&diagnostic);
+        assert_eq!(rendered, "\
+error[AB0201]: undefined variable `y`
+ --> main.ab:3:5
+  |
+3 |     return y;
+  |     ^^^^^^^^^ not found in this scope
+  |
+  = help: declare it first with `let y = ...;`
+");
+    }
+
+    #[test]
+    fn test_render_in_arabic() {
+        let source = "دالة رئيسية() -> int {\n    أرجع ص;\n}";
+        let diagnostic = Diagnostic::localized(&compile_error(source), Language::Arabic);
+        assert_eq!(diagnostic.code, "AB0201");
+
+        let rendered = DiagnosticRenderer::new("main.ab", source).with_language(Language::Arabic).render(&diagnostic);
+        assert_eq!(rendered, "\
+خطأ[AB0201]: المتغير `ص` غير معرّف
+ --> main.ab:2:5
+  |
+2 |     أرجع ص;
+  |     ^^^^^^^ غير موجود في هذا النطاق
+  |
+  = مساعدة: عرّفه أولًا بـ `دع ص = ...;`
+");
+
+        // Diagnostics without a translation keep their English wording
+        let error = CompilerError::CodeGenError("unsupported".to_string());
+        assert_eq!(Diagnostic::localized(&error, Language::Arabic), Diagnostic::from(&error));
+        assert_eq!("ar".parse::<Language>(), Ok(Language::Arabic));
+    }
+
+    #[test]
+    fn test_render_parse_error_with_suggestion() {
+        let source = "struct Point {\n    x: int\n    y: int\n}";
+        let diagnostic = Diagnostic::from(&compile_error(source));
+        assert_eq!(diagnostic.code, "AB0100");
+        assert_eq!(diagnostic.suggestion.as_deref(), Some("insert `,` before this token"));
+
+        let rendered = DiagnosticRenderer::new("point.ab", source).render(&diagnostic);
+        // the missing `,` is reported right after `int`
+        assert!(rendered.contains(" --> point.ab:2:11\n"), "{}", rendered);
+        assert!(rendered.contains("2 |     x: int\n  |           ^ unexpected token\n"), "{}", rendered);
+    }
+}
@@ -0,0 +1,630 @@
+//! # Lexical Analysis Module
+//!
+//! This module implements the lexer (tokenizer) for the AlBayan programming language.
+//! It converts source code text into a stream of tokens that can be processed by the parser.
+//!
+//! Keywords have Arabic spellings that lex to the same tokens, e.g. `دالة` for
+//! `fn` and `دع` for `let`, and identifiers may use Arabic letters, so a program
+//! can be written in either language or a mix of both.
+
+pub mod span;
+
+pub use span::{LineCol, LineIndex, Span};
+
+use logos::Logos;
+use std::fmt;
+
+/// Token types for the AlBayan language
+#[derive(Logos, Debug, Clone, PartialEq)]
+pub enum TokenType {
+    // Keywords - Definitions
+    #[token("fn")]
+    #[token("دالة")]
+    Fn,
+    #[token("class")]
+    Class,
+    #[token("interface")]
+    Interface,
+    #[token("trait")]
+    #[token("سمة")]
+    Trait,          // NEWLY ADDED: Expert recommendation
+    #[token("impl")]
+    #[token("تنفيذ")]
+    Impl,           // NEWLY ADDED: Expert recommendation
+    #[token("for")]
+    #[token("لكل")]
+    For,            // NEWLY ADDED: Expert recommendation (for impl Trait for Type)
+    #[token("dyn")]
+    Dyn,            // NEWLY ADDED: Expert recommendation (for dyn Trait)
+    #[token("enum")]
+    Enum,
+    #[token("struct")]
+    #[token("هيكل")]
+    Struct,
+    #[token("let")]
+    #[token("دع")]
+    Let,
+    #[token("mut")]
+    #[token("متغير")]
+    Mut,
+    #[token("const")]
+    #[token("ثابت")]
+    Const,
+    #[token("type")]
+    Type,
+
+    // Keywords - Control Flow
+    #[token("if")]
+    #[token("إذا")]
+    If,
+    #[token("else")]
+    #[token("وإلا")]
+    Else,
+    #[token("match")]
+    #[token("طابق")]
+    Match,
+    #[token("in")]
+    #[token("في")]
+    In,
+    #[token("while")]
+    #[token("بينما")]
+    While,
+    #[token("loop")]
+    Loop,
+    #[token("return")]
+    #[token("أرجع")]
+    Return,
+    #[token("break")]
+    #[token("توقف")]
+    Break,
+    #[token("continue")]
+    #[token("استمر")]
+    Continue,
+
+    // Keywords - Logic Programming
+    #[token("relation")]
+    #[token("علاقة")]
+    Relation,
+    #[token("fact")]
+    #[token("حقيقة")]
+    Fact,
+    #[token("rule")]
+    #[token("قاعدة")]
+    Rule,
+    #[token("query_solve")]
+    QuerySolve,
+    #[token("query_prove")]
+    QueryProve,
+    #[token("assert")]
+    Assert,
+    #[token("retract")]
+    Retract,
+
+    // Keywords - PyTorch (Expert recommendation: Priority 2) - Must come before AI keywords
+    #[token("TorchModel")]
+    TorchModel,
+    #[token("TorchOptimizer")]
+    TorchOptimizer,
+    #[token("TorchTensor")]
+    TorchTensor,
+    #[token("TrainingResult")]
+    TrainingResult,
+
+    // Keywords - AI
+    #[token("Model")]
+    Model,
+    #[token("Tensor")]
+    Tensor,
+
+    // Keywords - Tensor Literals (Expert recommendation: Priority 3)
+    #[token("tensor")]
+    TensorLiteral,
+
+    // Keywords - Concurrency
+    #[token("async")]
+    Async,
+    #[token("await")]
+    Await,
+    #[token("gpu")]
+    Gpu,
+
+    // Keywords - Modules
+    #[token("module")]
+    Module,
+    #[token("using")]
+    Using,
+    #[token("pub")]
+    Pub,
+
+    // Keywords - Natural Language Understanding
+    #[token("semantic")]
+    Semantic,
+    #[token("priv")]
+    Priv,
+
+    // Keywords - Special
+    #[token("self")]
+    #[token("ذات")]
+    SelfKeyword,
+    #[token("super")]
+    Super,
+    #[token("init")]
+    Init,
+    #[token("dynamic")]
+    Dynamic,
+    #[token("true")]
+    #[token("صحيح")]
+    True,
+    #[token("false")]
+    #[token("خطأ")]
+    False,
+    #[token("null")]
+    Null,
+
+    // Identifiers and Literals; Arabic letters, marks and digits are allowed too
+    #[regex(r"[a-zA-Z_\x{0621}-\x{064A}][a-zA-Z0-9_\x{0621}-\x{0652}\x{0660}-\x{0669}]*", |lex| lex.slice().to_owned())]
+    Identifier(String),
+
+    #[regex(r"-?[0-9]+", |lex| lex.slice().parse::<i64>().ok())]
+    IntegerLiteral(Option<i64>),
+
+    #[regex(r"-?[0-9]+\.[0-9]+", |lex| lex.slice().parse::<f64>().ok())]
+    FloatLiteral(Option<f64>),
+
+    #[regex(r#""([^"\\]|\\.)*""#, |lex| {
+        let s = lex.slice();
+        s[1..s.len()-1].to_owned() // Remove quotes
+    })]
+    StringLiteral(String),
+
+    #[regex(r"'([^'\\]|\\.)'", |lex| {
+        let s = lex.slice();
+        s.chars().nth(1) // Get character between quotes
+    })]
+    CharLiteral(Option<char>),
+
+    /// String literal with `{expression}` parts, e.g. "hello {name}".
+    /// Produced by `Lexer::tokenize` from string literals; never matched directly.
+    InterpolatedString(Vec<InterpolationPart>),
+
+    // Operators - Arithmetic
+    #[token("+")]
+    Plus,
+    #[token("-")]
+    Minus,
+    #[token("*")]
+    Multiply,
+    #[token("/")]
+    Divide,
+    #[token("%")]
+    Modulo,
+    #[token("**")]
+    Power,
+
+    // Operators - Comparison
+    #[token("==")]
+    Equal,
+    #[token("!=")]
+    NotEqual,
+    #[token("<")]
+    Less,
+    #[token("<=")]
+    LessEqual,
+    #[token(">")]
+    Greater,
+    #[token(">=")]
+    GreaterEqual,
+
+    // Operators - Logical
+    #[token("&&")]
+    And,
+    #[token("||")]
+    Or,
+    #[token("!")]
+    Not,
+
+    // Operators - Assignment
+    #[token("=")]
+    Assign,
+    #[token("+=")]
+    PlusAssign,
+    #[token("-=")]
+    MinusAssign,
+    #[token("*=")]
+    MultiplyAssign,
+    #[token("/=")]
+    DivideAssign,
+
+    // Operators - Logic Programming
+    #[token(":-")]
+    Implies, // For rules: head :- body
+
+    // Delimiters
+    #[token("(")]
+    LeftParen,
+    #[token(")")]
+    RightParen,
+    #[token("{")]
+    LeftBrace,
+    #[token("}")]
+    RightBrace,
+    #[token("[")]
+    LeftBracket,
+    #[token("]")]
+    RightBracket,
+
+    // Punctuation
+    #[token(";")]
+    Semicolon,
+    #[token(",")]
+    Comma,
+    #[token(".")]
+    Dot,
+    #[token(":")]
+    Colon,
+    #[token("::")]
+    DoubleColon,
+    #[token("->")]
+    Arrow,
+    #[token("=>")]
+    FatArrow,
+    #[token("|")]
+    Pipe,
+    #[token("&")]
+    Ampersand,
+    #[token("#")]
+    Hash,
+    #[token("_", priority = 3)]
+    Underscore,
+
+    // Comments, with their `//` or `/* */` delimiters; only
+    // `Lexer::tokenize_with_comments` keeps them
+    #[regex(r"//[^\n]*", |lex| lex.slice().to_string())]
+    #[regex(r"/\*([^*]|\*[^/])*\*/", |lex| lex.slice().to_string())]
+    Comment(String),
+
+    // Special tokens
+    #[regex(r"[ \t\f]+", logos::skip)] // Skip whitespace
+    #[token("\n")]
+    Newline,
+
+    // Error token
+    Error,
+
+    // End of file
+    Eof,
+}
+
+/// A piece of an interpolated string literal
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpolationPart {
+    /// Literal text, with `{{` and `}}` already unescaped
+    Text(String),
+    /// Tokens of an embedded expression, ending with `Eof`
+    Expression(Vec<Token>),
+}
+
+/// A token with position information
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub token_type: TokenType,
+    pub span: Span,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} at {}:{}", self.token_type, self.line, self.column)
+    }
+}
+
+/// Lexer for the AlBayan language
+pub struct Lexer<'a> {
+    input: &'a str,
+    line_index: LineIndex,
+}
+
+impl<'a> Lexer<'a> {
+    /// Create a new lexer for the given input
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            line_index: LineIndex::new(input),
+        }
+    }
+
+    /// Line index of the input, for mapping spans to positions
+    pub fn line_index(&self) -> &LineIndex {
+        &self.line_index
+    }
+
+    /// Tokenize the entire input
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, LexerError> {
+        self.tokenize_range(0, self.input.len(), false)
+    }
+
+    /// Tokenize the entire input, keeping comments as `TokenType::Comment`
+    /// tokens; the parser attaches them to the program as trivia
+    pub fn tokenize_with_comments(&mut self) -> Result<Vec<Token>, LexerError> {
+        self.tokenize_range(0, self.input.len(), true)
+    }
+
+    /// Tokenize `input[start..end]`, keeping spans relative to the whole input
+    fn tokenize_range(&self, start: usize, end: usize, keep_comments: bool) -> Result<Vec<Token>, LexerError> {
+        let mut tokens = Vec::new();
+        let mut lexer = TokenType::lexer(&self.input[start..end]);
+
+        while let Some(token_type) = lexer.next() {
+            let range = lexer.span();
+            let span = Span::new(start + range.start, start + range.end);
+            let LineCol { line, column } = self.line_index.line_col(span.start);
+
+            match token_type {
+                Ok(TokenType::StringLiteral(content)) => {
+                    // Content starts after the opening quote
+                    let token_type = match self.interpolation_parts(&content, span.start + 1)? {
+                        Some(parts) => TokenType::InterpolatedString(parts),
+                        None => TokenType::StringLiteral(content),
+                    };
+                    tokens.push(Token { token_type, span, line, column });
+                }
+                Ok(TokenType::Comment(_)) if !keep_comments => {}
+                Ok(token_type) => {
+                    if !matches!(token_type, TokenType::Error) {
+                        tokens.push(Token {
+                            token_type,
+                            span,
+                            line,
+                            column,
+                        });
+                    }
+                }
+                Err(_) => {
+                    return Err(LexerError::InvalidToken {
+                        position: span.start,
+                        span,
+                        line,
+                        column,
+                    });
+                }
+            }
+        }
+
+        // Add EOF token
+        let LineCol { line, column } = self.line_index.line_col(end);
+        tokens.push(Token {
+            token_type: TokenType::Eof,
+            span: Span::new(end, end),
+            line,
+            column,
+        });
+
+        Ok(tokens)
+    }
+
+    /// Split a string literal into text and `{expression}` parts.
+    ///
+    /// A `{` starts an expression only when followed by a letter, `_` or `(`, so
+    /// format templates such as `"{}"` or `"{:.2}"` stay plain strings. Returns
+    /// `None` when the literal has no expression parts.
+    fn interpolation_parts(
+        &self,
+        content: &str,
+        offset: usize,
+    ) -> Result<Option<Vec<InterpolationPart>>, LexerError> {
+        let bytes = content.as_bytes();
+        let starts_expression = |i: usize| {
+            bytes[i] == b'{'
+                && content[i + 1..]
+                    .chars()
+                    .next()
+                    .is_some_and(|next| next.is_alphabetic() || next == '_' || next == '(')
+        };
+        if !(0..bytes.len()).any(starts_expression) {
+            return Ok(None);
+        }
+
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'{' | b'}' if bytes.get(i + 1) == Some(&bytes[i]) => {
+                    text.push(bytes[i] as char);
+                    i += 2;
+                }
+                b'{' if starts_expression(i) => {
+                    let close = Self::closing_brace(bytes, i).ok_or_else(|| {
+                        let position = offset + i;
+                        let LineCol { line, column } = self.line_index.line_col(position);
+                        LexerError::UnterminatedInterpolation {
+                            span: Span::new(position, offset + bytes.len()),
+                            line,
+                            column,
+                        }
+                    })?;
+                    if !text.is_empty() {
+                        parts.push(InterpolationPart::Text(std::mem::take(&mut text)));
+                    }
+                    let tokens = self.tokenize_range(offset + i + 1, offset + close, false)?;
+                    parts.push(InterpolationPart::Expression(tokens));
+                    i = close + 1;
+                }
+                _ => {
+                    let ch = content[i..].chars().next().unwrap_or_default();
+                    text.push(ch);
+                    i += ch.len_utf8();
+                }
+            }
+        }
+        if !text.is_empty() {
+            parts.push(InterpolationPart::Text(text));
+        }
+        Ok(Some(parts))
+    }
+
+    /// Index of the `}` closing the `{` at `open`, allowing nested braces
+    fn closing_brace(bytes: &[u8], open: usize) -> Option<usize> {
+        let mut depth = 0;
+        for (i, &byte) in bytes.iter().enumerate().skip(open) {
+            match byte {
+                b'{' => depth += 1,
+                b'}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// Lexer error types
+#[derive(Debug, thiserror::Error)]
+pub enum LexerError {
+    #[error("Invalid token at line {line}, column {column} (position {position})")]
+    InvalidToken {
+        position: usize,
+        span: Span,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("Unterminated `{{` in string at line {line}, column {column}")]
+    UnterminatedInterpolation {
+        span: Span,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("Unexpected end of input")]
+    UnexpectedEof,
+}
+
+impl LexerError {
+    /// Source location of the error, if known
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            LexerError::InvalidToken { span, .. } => Some(*span),
+            LexerError::UnterminatedInterpolation { span, .. } => Some(*span),
+            LexerError::UnexpectedEof => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_tokens() {
+        let mut lexer = Lexer::new("fn main() { let x = 42; }");
+        let tokens = lexer.tokenize().unwrap();
+
+        assert_eq!(tokens[0].token_type, TokenType::Fn);
+        assert_eq!(tokens[1].token_type, TokenType::Identifier("main".to_string()));
+        assert_eq!(tokens[2].token_type, TokenType::LeftParen);
+        assert_eq!(tokens[3].token_type, TokenType::RightParen);
+    }
+
+    #[test]
+    fn test_token_positions() {
+        let mut lexer = Lexer::new("fn main() {\n    return 42;\n}");
+        let tokens = lexer.tokenize().unwrap();
+
+        let ret = tokens.iter().find(|t| t.token_type == TokenType::Return).unwrap();
+        assert_eq!((ret.line, ret.column), (2, 5));
+        assert_eq!(ret.span, Span::new(16, 22));
+
+        let eof = tokens.last().unwrap();
+        assert_eq!((eof.line, eof.column), (3, 2));
+    }
+
+    #[test]
+    fn test_logic_tokens() {
+        let mut lexer = Lexer::new("relation Parent(string, string); rule Grandparent(GP, GC) :- Parent(GP, P), Parent(P, GC);");
+        let tokens = lexer.tokenize().unwrap();
+
+        // Print tokens for debugging
+        for (i, token) in tokens.iter().enumerate() {
+            println!("Token {}: {:?}", i, token.token_type);
+        }
+
+        assert_eq!(tokens[0].token_type, TokenType::Relation);
+        // Find the Implies token
+        let implies_pos = tokens.iter().position(|t| matches!(t.token_type, TokenType::Implies));
+        assert!(implies_pos.is_some(), "Implies token not found");
+    }
+
+    #[test]
+    fn test_interpolated_string() {
+        let source = r#"let s = "hi {name}, {{literal}} {x + 1}";"#;
+        let tokens = Lexer::new(source).tokenize().unwrap();
+
+        let TokenType::InterpolatedString(parts) = &tokens[3].token_type else {
+            panic!("expected an interpolated string, got {:?}", tokens[3].token_type);
+        };
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[0], InterpolationPart::Text("hi ".to_string()));
+        assert_eq!(parts[2], InterpolationPart::Text(", {literal} ".to_string()));
+        let InterpolationPart::Expression(name) = &parts[1] else {
+            panic!("expected an expression part");
+        };
+        assert_eq!(name[0].token_type, TokenType::Identifier("name".to_string()));
+        assert_eq!(&source[name[0].span.start..name[0].span.end], "name");
+        assert_eq!(name.last().unwrap().token_type, TokenType::Eof);
+
+        // Format templates are left alone
+        let tokens = Lexer::new(r#""{} {:.2}""#).tokenize().unwrap();
+        assert_eq!(tokens[0].token_type, TokenType::StringLiteral("{} {:.2}".to_string()));
+
+        assert!(matches!(
+            Lexer::new(r#""open {name""#).tokenize(),
+            Err(LexerError::UnterminatedInterpolation { .. })
+        ));
+    }
+
+    #[test]
+    fn test_arabic_keywords_and_identifiers() {
+        let arabic = Lexer::new("دالة مربع(ن: int) -> int { دع متغير س = ن; إذا صحيح { أرجع س * ن; } وإلا { أرجع 0; } }")
+            .tokenize()
+            .unwrap();
+        let english = Lexer::new("fn مربع(ن: int) -> int { let mut س = ن; if true { return س * ن; } else { return 0; } }")
+            .tokenize()
+            .unwrap();
+        let types = |tokens: &[Token]| tokens.iter().map(|token| token.token_type.clone()).collect::<Vec<_>>();
+        assert_eq!(types(&arabic), types(&english));
+        assert_eq!(arabic[1].token_type, TokenType::Identifier("مربع".to_string()));
+        assert_eq!((arabic[1].line, arabic[1].column), (1, 6));
+
+        // A keyword is only a keyword as a whole word
+        let tokens = Lexer::new("دعوة فيل لكلمة").tokenize().unwrap();
+        assert!(tokens[..3].iter().all(|token| matches!(token.token_type, TokenType::Identifier(_))));
+
+        let tokens = Lexer::new(r#""مرحبًا {الاسم}""#).tokenize().unwrap();
+        assert!(matches!(tokens[0].token_type, TokenType::InterpolatedString(_)));
+    }
+
+    #[test]
+    fn test_comments_are_kept_on_request() {
+        let source = "/// Doc\nfn f() {} // trailing\n/* block\n comment */";
+        let comments: Vec<String> = Lexer::new(source)
+            .tokenize_with_comments()
+            .unwrap()
+            .into_iter()
+            .filter_map(|token| match token.token_type {
+                TokenType::Comment(text) => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(comments, ["/// Doc", "// trailing", "/* block\n comment */"]);
+
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        assert!(!tokens.iter().any(|token| matches!(token.token_type, TokenType::Comment(_))));
+        assert_eq!(tokens[1].token_type, TokenType::Fn);
+    }
+}
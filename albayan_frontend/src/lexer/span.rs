@@ -0,0 +1,110 @@
+//! # Source Spans
+//!
+//! Byte-offset spans attached to tokens and AST nodes, and a line index for
+//! turning them into line/column positions for diagnostics.
+
+use serde::{Deserialize, Serialize};
+
+/// Half-open byte range `start..end` in the source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// Create a new span
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Span covering both `self` and `other`
+    pub fn merge(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// Length in bytes
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Whether the span covers no text
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl From<std::ops::Range<usize>> for Span {
+    fn from(range: std::ops::Range<usize>) -> Self {
+        Self::new(range.start, range.end)
+    }
+}
+
+/// 1-based line and column (in characters) of a source position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Maps byte offsets to line/column positions
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    source: String,
+}
+
+impl LineIndex {
+    /// Build the index for a source text
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+        Self {
+            line_starts,
+            source: source.to_string(),
+        }
+    }
+
+    /// Line and column of a byte offset (offsets past the end clamp to the end)
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let column = self.source
+            .get(line_start..offset)
+            .map(|text| text.chars().count())
+            .unwrap_or(offset - line_start);
+
+        LineCol {
+            line: line + 1,
+            column: column + 1,
+        }
+    }
+
+    /// Text of a 1-based line, without the trailing newline
+    pub fn line_text(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self.line_starts.get(line).map(|&next| next - 1).unwrap_or(self.source.len());
+        self.source.get(start..end).map(|text| text.trim_end_matches('\r'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_col() {
+        let index = LineIndex::new("fn main() {\n    دع x = 1;\n}");
+        assert_eq!(index.line_col(0), LineCol { line: 1, column: 1 });
+        assert_eq!(index.line_col(12), LineCol { line: 2, column: 1 });
+        // الأحرف العربية متعددة البايتات تحسب عموداً واحداً
+        let x = "fn main() {\n    دع ".len();
+        assert_eq!(index.line_col(x), LineCol { line: 2, column: 8 });
+        assert_eq!(index.line_text(2), Some("    دع x = 1;"));
+        assert_eq!(index.line_col(1000).line, 3);
+    }
+}
@@ -0,0 +1,118 @@
+//! # AlBayan Front End
+//!
+//! The stable public API of the compiler's front end, for tools that read,
+//! check or rewrite AlBayan source without running it: the lexer with its
+//! source spans, the parser and its AST, the semantic analyzer, the module
+//! graph `using` declarations build, and the diagnostics all of them report.
+//!
+//! `albayan_lib` depends on this crate and re-exports its modules under their
+//! old paths, so `albayan_lib::parser` keeps working; the code generator, the
+//! runtime and the tools stay there.
+//!
+//! The crate follows semantic versioning: items exported here change
+//! incompatibly only with a new minor version while the crate is at `0.x`.
+
+pub mod lexer;
+pub mod parser;
+pub mod semantic;
+pub mod modules;
+pub mod diagnostics;
+pub mod cancellation;
+
+pub use lexer::{Lexer, Token, TokenType};
+pub use parser::Parser;
+pub use semantic::{SemanticAnalyzer, TypeChecker};
+
+/// Main compiler error type
+#[derive(Debug, thiserror::Error)]
+pub enum CompilerError {
+    #[error("Lexical error: {0}")]
+    LexicalError(#[from] lexer::LexerError),
+
+    #[error("Parse error: {0}")]
+    ParseError(#[from] parser::ParseError),
+
+    #[error("Semantic error: {0}")]
+    SemanticError(#[from] semantic::SemanticError),
+
+    #[error("Module error: {0}")]
+    ModuleError(#[from] modules::graph::ModuleError),
+
+    #[error("Code generation error: {0}")]
+    CodeGenError(String),
+
+    #[error("Runtime error: {0}")]
+    RuntimeError(String),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Cancelled(#[from] cancellation::Cancelled),
+}
+
+impl CompilerError {
+    /// Why the compile was interrupted, if it was cancelled in any phase
+    pub fn cancelled(&self) -> Option<cancellation::Cancelled> {
+        match self {
+            CompilerError::ParseError(parser::ParseError::Cancelled(cancelled)) => Some(*cancelled),
+            CompilerError::SemanticError(error) => match error.inner() {
+                semantic::SemanticError::Cancelled(cancelled) => Some(*cancelled),
+                _ => None,
+            },
+            CompilerError::Cancelled(cancelled) => Some(*cancelled),
+            _ => None,
+        }
+    }
+}
+
+
+/// Result type for compiler operations
+pub type CompilerResult<T> = Result<T, CompilerError>;
+
+/// Compiler configuration options
+#[derive(Debug, Clone)]
+pub struct CompilerOptions {
+    /// Optimization level (0-3)
+    pub optimization_level: u8,
+    /// Target triple (e.g., "x86_64-unknown-linux-gnu")
+    pub target_triple: Option<String>,
+    /// Enable debug information
+    pub debug_info: bool,
+    /// Output file path
+    pub output_path: Option<std::path::PathBuf>,
+    /// Enable logic programming features
+    pub enable_logic: bool,
+    /// Enable AI features
+    pub enable_ai: bool,
+    /// Use LLVM backend for code generation
+    pub use_llvm: bool,
+    /// Checked by every phase; cancel it or give it a deadline to stop a long compile
+    pub cancellation: cancellation::CancellationToken,
+    /// Dependency packages `using` can import, by name, mapped to their entry files
+    pub packages: std::collections::HashMap<String, std::path::PathBuf>,
+    /// Directory of the incremental compilation cache; `None` compiles
+    /// everything every time. See `albayan_lib::codegen::cache`
+    pub cache_dir: Option<std::path::PathBuf>,
+    /// Which semantic warnings are reported, and which fail the analysis
+    pub warnings: semantic::WarningLevels,
+}
+
+impl Default for CompilerOptions {
+    fn default() -> Self {
+        Self {
+            optimization_level: 0,
+            target_triple: None,
+            debug_info: true,
+            output_path: None,
+            enable_logic: true,
+            enable_ai: true,
+            use_llvm: false,
+            cancellation: cancellation::CancellationToken::default(),
+            packages: std::collections::HashMap::new(),
+            cache_dir: None,
+            warnings: semantic::WarningLevels::default(),
+        }
+    }
+}
+
@@ -0,0 +1,344 @@
+//! # Module Graph
+//!
+//! Resolves the `using` declarations of a program's entry file to the other
+//! `.ab` files they name, so a program can span several files. Paths are
+//! looked up next to the entry file: `using geometry::shapes;` loads
+//! `geometry/shapes.ab`, or `geometry/shapes/mod.ab` for a directory module.
+//! A path whose last segment is not a module imports one item of the module
+//! before it, so `using geometry::shapes::area;` brings `area` into scope.
+//! `std` paths are provided by the compiler and are not loaded.
+//!
+//! A path that starts with the name of a dependency package (see
+//! `cli::deps`) is looked up in that package instead: `using geometry;` loads
+//! the package's entry file and `using geometry::shapes;` loads `shapes.ab`
+//! next to it. `using` paths inside a package are relative to the package,
+//! so its modules are all named `geometry::...`.
+//!
+//! Imported modules are loaded recursively, relative to the same directory,
+//! and an import cycle is an error. [`ModuleGraph::modules`] lists every
+//! module after the ones it imports, ending with the entry file, which is
+//! the order `SemanticAnalyzer::analyze_modules` declares them in. Functions
+//! and constants of an imported module are qualified with its name in the
+//! symbol table (`geometry::shapes::area`) and reached through the name its
+//! `using` declaration binds: `shapes::area(2, 3)`, or `area(2, 3)` when the
+//! item itself was imported. Types keep their names, so they must be unique
+//! across the program.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::cancellation::CancellationToken;
+use crate::lexer::Lexer;
+use crate::parser::ast::{Item, Program};
+use crate::parser::Parser;
+use crate::CompilerResult;
+
+/// Why a program's modules could not be loaded
+#[derive(Debug, thiserror::Error)]
+pub enum ModuleError {
+    #[error("module `{path}` not found (imported by `{importer}`)")]
+    NotFound {
+        path: String,
+        importer: String,
+        /// Files that were tried, relative to the entry file's directory
+        /// when they are inside it
+        candidates: Vec<PathBuf>,
+    },
+
+    #[error("import cycle: {} -> {}", .0.join(" -> "), .0[0])]
+    Cycle(Vec<String>),
+
+    /// A lexer or parser error in an imported module
+    #[error("in module `{module}` ({}): {message}", .path.display())]
+    Syntax {
+        module: String,
+        path: PathBuf,
+        message: String,
+    },
+}
+
+/// One file of a program
+#[derive(Debug, Clone)]
+pub struct SourceModule {
+    /// Module name, e.g. `geometry::shapes`; the entry file is named after
+    /// its file stem
+    pub name: String,
+    pub path: PathBuf,
+    pub program: Program,
+    pub imports: Vec<ModuleImport>,
+}
+
+/// A `using` declaration resolved to a module of the program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleImport {
+    pub module: String,
+    /// Item imported from the module, or `None` for the module itself
+    pub item: Option<String>,
+    pub alias: Option<String>,
+}
+
+impl ModuleImport {
+    /// The name the declaration binds in the importing module
+    pub fn local_name(&self) -> &str {
+        self.alias
+            .as_deref()
+            .or(self.item.as_deref())
+            .unwrap_or_else(|| self.module.rsplit("::").next().unwrap_or(&self.module))
+    }
+
+    /// The qualified path the local name stands for
+    pub fn target(&self) -> String {
+        match &self.item {
+            Some(item) => format!("{}::{}", self.module, item),
+            None => self.module.clone(),
+        }
+    }
+}
+
+impl SourceModule {
+    /// Local names bound by the module's imports, mapped to qualified paths
+    pub fn scope(&self) -> HashMap<String, String> {
+        self.imports.iter().map(|import| (import.local_name().to_string(), import.target())).collect()
+    }
+}
+
+/// The modules of a program, each after the ones it imports
+#[derive(Debug, Clone)]
+pub struct ModuleGraph {
+    /// Directory of the entry file, which `using` paths are relative to
+    pub root: PathBuf,
+    modules: Vec<SourceModule>,
+}
+
+impl ModuleGraph {
+    /// Load the entry file at `path` and every module it imports
+    pub fn load(path: &Path, cancellation: &CancellationToken) -> CompilerResult<Self> {
+        Self::load_with_packages(path, &HashMap::new(), cancellation)
+    }
+
+    /// Like [`load`](Self::load), with dependency packages `using` can
+    /// import, by name, mapped to their entry files
+    pub fn load_with_packages(
+        path: &Path,
+        packages: &HashMap<String, PathBuf>,
+        cancellation: &CancellationToken,
+    ) -> CompilerResult<Self> {
+        let source = std::fs::read_to_string(path)?;
+        let program = parse(&source, cancellation)?;
+        Self::from_program(path, program, packages, cancellation)
+    }
+
+    /// Resolve the imports of an already parsed entry file at `path`
+    pub fn from_program(
+        path: &Path,
+        program: Program,
+        packages: &HashMap<String, PathBuf>,
+        cancellation: &CancellationToken,
+    ) -> CompilerResult<Self> {
+        let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        let mut loader =
+            Loader { root: root.clone(), packages, cancellation, loaded: HashSet::new(), modules: Vec::new() };
+        loader.visit(name, path.to_path_buf(), program, None, &mut Vec::new())?;
+        Ok(Self { root, modules: loader.modules })
+    }
+
+    /// Every module after the modules it imports; the entry file is last
+    pub fn modules(&self) -> &[SourceModule] {
+        &self.modules
+    }
+
+    pub fn entry(&self) -> &SourceModule {
+        self.modules.last().expect("a module graph holds its entry file")
+    }
+
+    pub fn module(&self, name: &str) -> Option<&SourceModule> {
+        self.modules.iter().find(|module| module.name == name)
+    }
+
+    /// The prefix `module`'s items are qualified with; `None` for the entry file
+    pub fn prefix<'a>(&self, module: &'a SourceModule) -> Option<&'a str> {
+        (module.path != self.entry().path).then_some(module.name.as_str())
+    }
+}
+
+impl fmt::Display for ModuleGraph {
+    /// One line per module with the modules it imports
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for module in &self.modules {
+            let mut imports: Vec<&str> = module.imports.iter().map(|import| import.module.as_str()).collect();
+            imports.dedup();
+            if imports.is_empty() {
+                writeln!(f, "{}", module.name)?;
+            } else {
+                writeln!(f, "{} -> {}", module.name, imports.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Loader<'a> {
+    root: PathBuf,
+    /// Entry files of the dependency packages, by package name
+    packages: &'a HashMap<String, PathBuf>,
+    cancellation: &'a CancellationToken,
+    /// Names of the modules already in `modules`
+    loaded: HashSet<String>,
+    modules: Vec<SourceModule>,
+}
+
+impl Loader<'_> {
+    /// Load the imports of `program`, then add it; `package` is the
+    /// dependency it belongs to and `stack` holds the modules being loaded, to
+    /// detect cycles
+    fn visit(
+        &mut self,
+        name: String,
+        path: PathBuf,
+        program: Program,
+        package: Option<&str>,
+        stack: &mut Vec<String>,
+    ) -> CompilerResult<()> {
+        stack.push(name.clone());
+        let mut imports = Vec::new();
+        for item in &program.items {
+            let Item::Using(using) = item else {
+                continue;
+            };
+            if using.path.first().is_some_and(|segment| segment == "std") {
+                continue;
+            }
+            let (import, file) = self.resolve(&using.path, &name, package)?;
+            let import = ModuleImport { alias: using.alias.clone(), ..import };
+            if let Some(start) = stack.iter().position(|module| *module == import.module) {
+                return Err(ModuleError::Cycle(stack[start..].to_vec()).into());
+            }
+            if !self.loaded.contains(&import.module) {
+                let source = std::fs::read_to_string(&file)?;
+                let imported = match parse(&source, self.cancellation) {
+                    Ok(program) => program,
+                    Err(error) if error.cancelled().is_some() => return Err(error),
+                    Err(error) => {
+                        let message = error.to_string();
+                        return Err(ModuleError::Syntax { module: import.module, path: file, message }.into());
+                    }
+                };
+                let package = import.module.split("::").next().filter(|first| self.packages.contains_key(*first));
+                self.visit(import.module.clone(), file, imported, package.map(str::to_string).as_deref(), stack)?;
+            }
+            imports.push(import);
+        }
+        stack.pop();
+
+        self.loaded.insert(name.clone());
+        self.modules.push(SourceModule { name, path, program, imports });
+        Ok(())
+    }
+
+    /// The module a `using` path imports, with its file: the whole path, or
+    /// all but its last segment, which then names an item of the module.
+    /// Inside `package`, paths not naming another package are relative to it.
+    fn resolve(&self, path: &[String], importer: &str, package: Option<&str>) -> CompilerResult<(ModuleImport, PathBuf)> {
+        let qualified: Vec<String>;
+        let path = match package {
+            Some(package) if !self.packages.contains_key(&path[0]) => {
+                qualified = std::iter::once(package.to_string()).chain(path.iter().cloned()).collect();
+                &qualified[..]
+            }
+            _ => path,
+        };
+        let package_entry = self.packages.get(&path[0]);
+
+        let mut candidates = Vec::new();
+        for length in (path.len().saturating_sub(1).max(1)..=path.len()).rev() {
+            let files = match package_entry {
+                Some(entry) if length == 1 => vec![entry.clone()],
+                Some(entry) => {
+                    let dir = entry.parent().unwrap_or(Path::new(""));
+                    let relative: PathBuf = path[1..length].iter().collect();
+                    vec![dir.join(relative.with_extension("ab")), dir.join(relative.join("mod.ab"))]
+                }
+                None => {
+                    let relative: PathBuf = path[..length].iter().collect();
+                    vec![self.root.join(relative.with_extension("ab")), self.root.join(relative.join("mod.ab"))]
+                }
+            };
+            for file in files {
+                if file.is_file() {
+                    let item = (length < path.len()).then(|| path[length].clone());
+                    let import = ModuleImport { module: path[..length].join("::"), item, alias: None };
+                    return Ok((import, file));
+                }
+                candidates.push(file.strip_prefix(&self.root).map(Path::to_path_buf).unwrap_or(file));
+            }
+        }
+        Err(ModuleError::NotFound { path: path.join("::"), importer: importer.to_string(), candidates }.into())
+    }
+}
+
+fn parse(source: &str, cancellation: &CancellationToken) -> CompilerResult<Program> {
+    let tokens = Lexer::new(source).tokenize()?;
+    Ok(Parser::new(tokens).with_cancellation(cancellation.clone()).parse()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompilerError;
+
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("albayan_modules_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (path, source) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, source).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_imports_are_loaded_before_their_importers() {
+        let root = project("order", &[
+            ("main.ab", "using geometry::shapes;\nusing util::twice as double;\nusing std::io;\nfn main() -> int { return 0; }\n"),
+            ("geometry/shapes.ab", "using util;\nfn area(w: int, h: int) -> int { return w * h; }\n"),
+            ("util/mod.ab", "fn twice(x: int) -> int { return x * 2; }\n"),
+        ]);
+        let graph = ModuleGraph::load(&root.join("main.ab"), &CancellationToken::default()).unwrap();
+
+        let names: Vec<&str> = graph.modules().iter().map(|module| module.name.as_str()).collect();
+        assert_eq!(names, vec!["util", "geometry::shapes", "main"]);
+        assert_eq!(graph.prefix(graph.entry()), None);
+        assert_eq!(graph.prefix(&graph.modules()[0]), Some("util"));
+
+        let scope = graph.entry().scope();
+        assert_eq!(scope["shapes"], "geometry::shapes");
+        assert_eq!(scope["double"], "util::twice");
+        assert_eq!(scope.len(), 2);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_missing_modules_and_cycles_are_errors() {
+        let root = project("errors", &[
+            ("main.ab", "using a;\nfn main() {}\n"),
+            ("a.ab", "using b;\n"),
+            ("b.ab", "using a;\n"),
+            ("lonely.ab", "using nowhere::to_be_found;\n"),
+        ]);
+        let token = CancellationToken::default();
+
+        let error = ModuleGraph::load(&root.join("main.ab"), &token).unwrap_err();
+        let CompilerError::ModuleError(module_error) = &error else {
+            panic!("expected a module error, got {}", error);
+        };
+        assert!(matches!(module_error, ModuleError::Cycle(cycle) if cycle == &["a", "b"]));
+        assert_eq!(module_error.to_string(), "import cycle: a -> b -> a");
+
+        let error = ModuleGraph::load(&root.join("lonely.ab"), &token).unwrap_err();
+        assert!(matches!(error, CompilerError::ModuleError(ModuleError::NotFound { ref path, .. }) if path == "nowhere::to_be_found"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
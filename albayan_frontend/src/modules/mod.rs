@@ -0,0 +1,7 @@
+//! # Modules
+//!
+//! The graph of modules a program's `using` declarations import. Packages,
+//! module resolution and the import/export system live in
+//! `albayan_lib::modules`.
+
+pub mod graph;
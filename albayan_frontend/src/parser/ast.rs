@@ -0,0 +1,1131 @@
+//! # Abstract Syntax Tree (AST) Definitions
+//!
+//! This module defines the AST node types for the AlBayan programming language.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+pub use crate::lexer::Span;
+
+/// Represents a qualified path (e.g., `std::collections::HashMap`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Path {
+    pub segments: Vec<String>,
+}
+
+impl Path {
+    /// Create a new path from a single segment
+    pub fn single(segment: String) -> Self {
+        Self {
+            segments: vec![segment],
+        }
+    }
+
+    /// Create a new path from multiple segments
+    pub fn from_segments(segments: Vec<String>) -> Self {
+        Self { segments }
+    }
+
+    /// Parse a path from a string like "std::collections::HashMap"
+    pub fn from_string(path_str: &str) -> Self {
+        Self {
+            segments: path_str.split("::").map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Convert path back to string representation
+    pub fn to_string(&self) -> String {
+        self.segments.join("::")
+    }
+
+    pub fn as_str(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Root node of the AST - represents a complete program
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Program {
+    pub items: Vec<Item>,
+    /// Comments of the source, in order; empty unless the tokens came from
+    /// `Lexer::tokenize_with_comments`
+    #[serde(default)]
+    pub comments: Vec<Comment>,
+}
+
+impl Program {
+    /// Comments on the lines right before the node starting `span`
+    pub fn leading_comments(&self, span: Span) -> impl Iterator<Item = &Comment> {
+        self.comments
+            .iter()
+            .filter(move |comment| comment.placement == CommentPlacement::Leading { before: span.start })
+    }
+
+    /// Comment following the node ending `span` on its last line
+    pub fn trailing_comment(&self, span: Span) -> Option<&Comment> {
+        self.comments
+            .iter()
+            .find(|comment| comment.placement == CommentPlacement::Trailing { after: span.end })
+    }
+
+    /// Text of the `///` comments leading the node starting `span`, one line each
+    pub fn doc_comment(&self, span: Span) -> Option<String> {
+        let lines: Vec<&str> = self.leading_comments(span).filter_map(Comment::doc_text).collect();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+/// A source comment, kept as trivia next to the AST
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    /// The comment with its `//` or `/* */` delimiters
+    pub text: String,
+    pub span: Span,
+    pub placement: CommentPlacement,
+}
+
+impl Comment {
+    /// Text of a `///` doc comment, without the slashes and the space after them
+    pub fn doc_text(&self) -> Option<&str> {
+        let text = self.text.strip_prefix("///")?;
+        // `////` rules are not doc comments
+        (!text.starts_with('/')).then(|| text.strip_prefix(' ').unwrap_or(text))
+    }
+}
+
+/// Where a comment attaches, by the offset of the token it attaches to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommentPlacement {
+    /// On its own lines, before the token starting at `before`
+    Leading { before: usize },
+    /// On the line of the token ending at `after`, after it
+    Trailing { after: usize },
+}
+
+/// Top-level items in a program
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Item {
+    Function(FunctionDecl),
+    Struct(StructDecl),
+    Enum(EnumDecl),
+    Class(ClassDecl),
+    Interface(InterfaceDecl),
+    Trait(TraitDecl),        // NEWLY ADDED: Expert recommendation
+    Impl(ImplDecl),          // NEWLY ADDED: Expert recommendation
+    Relation(RelationDecl),
+    Rule(RuleDecl),
+    Fact(FactDecl),
+    Concept(ConceptDecl),
+    Constraint(ConstraintDecl),
+    Module(ModuleDecl),
+    Using(UsingDecl),
+    Const(ConstDecl),
+    /// Natural Language Understanding block - كتلة فهم اللغة الطبيعية
+    Semantic(SemanticBlock),
+}
+
+impl Item {
+    /// Source location of the item, if the parser recorded one
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Item::Function(decl) => Some(decl.span),
+            Item::Struct(decl) => Some(decl.span),
+            Item::Enum(decl) => Some(decl.span),
+            Item::Class(decl) => Some(decl.span),
+            Item::Interface(decl) => Some(decl.span),
+            Item::Trait(decl) => Some(decl.span),
+            Item::Impl(decl) => Some(decl.span),
+            Item::Relation(decl) => Some(decl.span),
+            Item::Rule(decl) => Some(decl.span),
+            Item::Fact(decl) => Some(decl.span),
+            Item::Concept(decl) => Some(decl.span),
+            Item::Constraint(decl) => Some(decl.span),
+            Item::Const(decl) => Some(decl.span),
+            Item::Using(decl) => Some(decl.span),
+            Item::Module(_) | Item::Semantic(_) => None,
+        }
+        .filter(|span| !span.is_empty())
+    }
+}
+
+/// Whether an item or field can be used outside the module declaring it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Visibility {
+    /// Declared with `pub`
+    Public,
+    #[default]
+    Private,
+}
+
+/// Function declaration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionDecl {
+    pub name: String,
+    #[serde(default)]
+    pub visibility: Visibility,
+    pub generic_params: Option<Vec<GenericParam>>,  // NEWLY ADDED: Expert recommendation
+    pub parameters: Vec<Parameter>,
+    pub return_type: Option<Type>,
+    pub body: Block,
+    /// Marked `#[test]`: run by `albayan test`
+    #[serde(default)]
+    pub is_test: bool,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Function parameter (Expert recommendation: Priority 2 - &self and &mut self support)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Parameter {
+    /// Regular parameter: name: Type
+    Regular {
+        name: String,
+        param_type: Type,
+    },
+    /// Self parameter: self
+    SelfValue,
+    /// Immutable self reference: &self
+    SelfRef,
+    /// Mutable self reference: &mut self
+    SelfMutRef,
+}
+
+/// Struct declaration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructDecl {
+    pub name: String,
+    #[serde(default)]
+    pub visibility: Visibility,
+    pub generic_params: Option<Vec<GenericParam>>,  // NEWLY ADDED: Expert recommendation
+    pub fields: Vec<StructField>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Struct field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructField {
+    pub name: String,
+    pub field_type: Type,
+    #[serde(default)]
+    pub visibility: Visibility,
+}
+
+/// Enum declaration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumDecl {
+    pub name: String,
+    #[serde(default)]
+    pub visibility: Visibility,
+    pub variants: Vec<EnumVariant>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Enum variant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumVariant {
+    pub name: String,
+    pub fields: Option<Vec<Type>>,
+}
+
+/// Class declaration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassDecl {
+    pub name: String,
+    #[serde(default)]
+    pub visibility: Visibility,
+    pub fields: Vec<StructField>,
+    pub methods: Vec<FunctionDecl>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Interface declaration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterfaceDecl {
+    pub name: String,
+    pub methods: Vec<FunctionSignature>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Trait declaration (Expert recommendation: Priority 1)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraitDecl {
+    pub name: String,
+    pub generic_params: Option<Vec<GenericParam>>,
+    /// Associated type declarations (`type Item;`)
+    #[serde(default)]
+    pub associated_types: Vec<String>,
+    pub methods: Vec<TraitMethod>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Trait method (can be required or have default implementation)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraitMethod {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub return_type: Option<Type>,
+    pub body: Option<Block>,  // None for required methods, Some for default implementations
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Impl declaration (Expert recommendation: Priority 1)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImplDecl {
+    pub trait_name: Option<String>,  // None for inherent impl, Some for trait impl
+    pub type_name: String,
+    pub generic_params: Option<Vec<GenericParam>>,
+    /// Associated type definitions (`type Item = int;`)
+    #[serde(default)]
+    pub associated_types: Vec<(String, Type)>,
+    pub methods: Vec<FunctionDecl>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Generic parameter (Expert recommendation: Priority 1)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenericParam {
+    pub name: String,
+    pub bounds: Vec<TraitBound>,  // Trait bounds like T: Display + Clone
+}
+
+/// Trait bound (Expert recommendation: Priority 1)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraitBound {
+    pub trait_name: String,
+}
+
+/// Function signature (for interfaces)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionSignature {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub return_type: Option<Type>,
+}
+
+/// `const NAME: type = value;`, evaluated at compile time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConstDecl {
+    pub name: String,
+    #[serde(default)]
+    pub visibility: Visibility,
+    pub const_type: Type,
+    pub value: Expression,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Relation declaration (for logic programming)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationDecl {
+    pub name: String,
+    #[serde(default)]
+    pub visibility: Visibility,
+    pub arg_types: Vec<Type>,
+    /// `#[untrusted]` or `#[sensitive]`, for the taint audit of rules
+    #[serde(default)]
+    pub security: Option<SecurityLabel>,
+    /// `#[table]`: solve calls by tabled resolution, so recursive rules
+    /// over the relation terminate
+    #[serde(default)]
+    pub tabled: bool,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Trust annotation of a relation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecurityLabel {
+    /// Populated from untrusted input: NLU output, the network, users
+    Untrusted,
+    /// Conclusions that must not rest on untrusted input alone
+    Sensitive,
+}
+
+/// Rule declaration (for logic programming)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleDecl {
+    pub head: LogicTerm,
+    pub body: Vec<LogicTerm>,
+    /// `#[no_reorder]`: solve the body in the written order
+    #[serde(default)]
+    pub no_reorder: bool,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Fact declaration (for logic programming)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FactDecl {
+    pub term: LogicTerm,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Ontology concept declaration: `concept cat: mammal, pet;`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConceptDecl {
+    pub name: String,
+    /// Direct parents in the `is_a` hierarchy
+    pub parents: Vec<String>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Integrity constraint: `constraint no parent(X, X);` forbids the goals
+/// from ever holding together
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConstraintDecl {
+    pub goals: Vec<LogicTerm>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Logic term (predicate with arguments)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogicTerm {
+    pub name: String,
+    pub args: Vec<LogicArg>,
+    /// `not` before a rule body goal: it holds when the goal has no solution
+    #[serde(default)]
+    pub negated: bool,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Name of the cut, the `!` goal of a rule body
+pub const CUT: &str = "!";
+
+/// Comparisons a rule body may use as goals, written between their two
+/// arguments: `X > 5`, `Y is X + 1`, `X = Y`
+pub const LOGIC_BUILTINS: [&str; 7] = ["=", "!=", "<", "<=", ">", ">=", "is"];
+
+/// Goals that solve the goal given as an argument and bind their last
+/// argument to what its solutions give: `findall(X, parent(P, X), Children)`,
+/// `count(parent(P, _), N)`, and `sum_of`, `min_of` and `max_of` like `findall`
+pub const AGGREGATES: [&str; 5] = ["findall", "count", "sum_of", "min_of", "max_of"];
+
+/// Position of the goal among the arguments of one of the [`AGGREGATES`]
+pub fn aggregate_goal_index(aggregate: &str) -> usize {
+    if aggregate == "count" {
+        0
+    } else {
+        1
+    }
+}
+
+impl LogicTerm {
+    /// The cut `!`, which commits a rule to the choices made before it
+    pub fn cut(span: Span) -> Self {
+        LogicTerm { name: CUT.to_string(), args: Vec::new(), negated: false, span }
+    }
+
+    pub fn is_cut(&self) -> bool {
+        self.name == CUT
+    }
+
+    /// Whether the goal is one of the [`LOGIC_BUILTINS`]
+    pub fn is_builtin(&self) -> bool {
+        LOGIC_BUILTINS.contains(&self.name.as_str())
+    }
+
+    /// Whether the goal is one of the [`AGGREGATES`] applied to a goal, so
+    /// that a relation of the same name can still be used
+    pub fn is_aggregate(&self) -> bool {
+        AGGREGATES.contains(&self.name.as_str()) && self.args.iter().any(|arg| matches!(arg, LogicArg::Goal(_)))
+    }
+
+    /// Whether the goal calls a relation, as all goals but the cut, the
+    /// built-ins and the aggregates do
+    pub fn calls_relation(&self) -> bool {
+        !self.is_cut() && !self.is_builtin() && !self.is_aggregate()
+    }
+}
+
+/// Logic argument (variable, constant or structured term)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LogicArg {
+    Variable(String),
+    Constant(String),
+    StringConstant(String),
+    IntConstant(i64),
+    FloatConstant(f64),
+    /// Arithmetic on two arguments, `+`, `-`, `*`, `/` or `%`, as in the
+    /// right side of `Y is X + 1`
+    Operation { op: String, left: Box<LogicArg>, right: Box<LogicArg> },
+    /// The goal an aggregate solves, as in `count(parent(X, _), N)`
+    Goal(Box<LogicTerm>),
+    /// A functor applied to arguments, as in `at(robot, Point(1, 2))`
+    Compound(Box<LogicTerm>),
+    /// `[a, b, c]`, or `[H | T]` when the list continues with `tail`
+    List { items: Vec<LogicArg>, tail: Option<Box<LogicArg>> },
+}
+
+// Logic declarations display in the syntax the logic engine parses
+
+impl fmt::Display for LogicTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_cut() {
+            return write!(f, "{}", CUT);
+        }
+        if self.negated {
+            write!(f, "not ")?;
+        }
+        write!(f, "{}(", self.name)?;
+        for (index, arg) in self.args.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", arg)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Display for LogicArg {
+    /// Operations display as compound terms, `+(X, 1)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogicArg::Variable(name) | LogicArg::Constant(name) => write!(f, "{}", name),
+            LogicArg::StringConstant(text) => write!(f, "\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"")),
+            LogicArg::IntConstant(value) => write!(f, "{}", value),
+            LogicArg::FloatConstant(value) => write!(f, "{:?}", value),
+            LogicArg::Operation { op, left, right } => write!(f, "{}({}, {})", op, left, right),
+            LogicArg::Goal(term) | LogicArg::Compound(term) => write!(f, "{}", term),
+            LogicArg::List { items, tail } => {
+                let items: Vec<String> = items.iter().map(LogicArg::to_string).collect();
+                match tail {
+                    Some(tail) => write!(f, "[{} | {}]", items.join(", "), tail),
+                    None => write!(f, "[{}]", items.join(", ")),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for FactDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.", self.term)
+    }
+}
+
+impl fmt::Display for RuleDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.no_reorder {
+            write!(f, "#[no_reorder] ")?;
+        }
+        let body: Vec<String> = self.body.iter().map(LogicTerm::to_string).collect();
+        write!(f, "{} :- {}.", self.head, body.join(", "))
+    }
+}
+
+impl fmt::Display for ConstraintDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let goals: Vec<String> = self.goals.iter().map(LogicTerm::to_string).collect();
+        write!(f, ":- {}.", goals.join(", "))
+    }
+}
+
+/// Module declaration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleDecl {
+    pub name: String,
+    pub items: Vec<Item>,
+}
+
+/// Using declaration (imports)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsingDecl {
+    pub path: Vec<String>,
+    pub alias: Option<String>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Type annotations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Type {
+    /// Named type using qualified path (int, string, std::collections::HashMap, etc.)
+    Named(Path),
+    /// Generic type with parameters (Vec<T>, HashMap<K, V>, etc.)
+    Generic(Path, Vec<Type>),
+    /// Generic type parameter (T, U, K, V, etc.)
+    GenericParam(String),
+    /// Function type
+    Function(Vec<Type>, Box<Type>),
+    /// Tuple type
+    Tuple(Vec<Type>),
+    /// Array type `[T]`, or `[T; N]` with a constant size expression
+    Array(Box<Type>, Option<Box<Expression>>),
+    /// Trait object type (dyn Trait) - Expert recommendation: Priority 1
+    TraitObject(Vec<Path>),
+    /// Reference type (&T, &mut T) - Expert recommendation: Priority 1
+    Reference(Box<Type>, bool), // bool: true for mutable (&mut), false for immutable (&)
+    /// Multi-dimensional array
+    Matrix(Box<Type>, Vec<usize>),
+    /// Vector type with fixed size
+    Vector(Box<Type>, usize),
+    /// Set type for unique elements
+    Set(Box<Type>),
+    /// Map/Dictionary type
+    Map(Box<Type>, Box<Type>),
+    /// Queue type (FIFO)
+    Queue(Box<Type>),
+    /// Stack type (LIFO)
+    Stack(Box<Type>),
+    /// Tree structure
+    Tree(Box<Type>),
+    /// Graph with nodes and edges
+    Graph(Box<Type>, Box<Type>),
+    /// Union type (one of several types)
+    Union(Vec<Type>),
+    /// Result type for error handling
+    Result(Box<Type>, Box<Type>),
+    /// Channel for concurrent communication
+    Channel(Box<Type>),
+    /// Mutex for thread safety
+    Mutex(Box<Type>),
+    /// Atomic type for lock-free operations
+    Atomic(Box<Type>),
+
+    /// Optional type
+    Optional(Box<Type>),
+    /// Tensor type for AI operations
+    Tensor(Vec<usize>),
+    /// Dataset type for machine learning
+    Dataset(Box<Type>),
+    /// Model type for AI models
+    Model(String),
+}
+
+/// Block of statements
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Block {
+    pub statements: Vec<Statement>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Statements
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Statement {
+    Expression(ExpressionStatement),
+    Let(LetStatement),
+    Return(ReturnStatement),
+    If(IfStatement),
+    While(WhileStatement),
+    For(ForStatement),
+    Match(MatchStatement),
+    Block(Block),
+    Query(QueryStatement),
+    Assert(AssertStatement),
+    Retract(RetractStatement),
+    Semantic(SemanticBlock),
+}
+
+impl Statement {
+    /// Source location of the statement, if the parser recorded one
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Statement::Expression(stmt) => stmt.expression.span(),
+            Statement::Let(stmt) => Some(stmt.span),
+            Statement::Return(stmt) => Some(stmt.span),
+            Statement::If(stmt) => Some(stmt.span),
+            Statement::While(stmt) => Some(stmt.span),
+            Statement::For(stmt) => Some(stmt.span),
+            Statement::Match(stmt) => Some(stmt.span),
+            Statement::Block(block) => Some(block.span),
+            Statement::Query(stmt) => Some(stmt.span),
+            Statement::Assert(stmt) => Some(stmt.span),
+            Statement::Retract(stmt) => Some(stmt.span),
+            Statement::Semantic(_) => None,
+        }
+        .filter(|span| !span.is_empty())
+    }
+
+    /// Whether the statement, last in a block, is the block's value: an
+    /// expression without a `;`, an `if` with an `else`, or a `match`
+    pub fn is_value(&self) -> bool {
+        match self {
+            Statement::Expression(stmt) => !stmt.semicolon,
+            Statement::If(stmt) => stmt.else_block.is_some(),
+            Statement::Match(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Expression statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpressionStatement {
+    pub expression: Expression,
+    /// Ended by a `;`; without one, the last statement of a block is the
+    /// block's value
+    #[serde(default)]
+    pub semicolon: bool,
+}
+
+impl ExpressionStatement {
+    /// An expression ended by a `;`
+    pub fn new(expression: Expression) -> Self {
+        ExpressionStatement { expression, semicolon: true }
+    }
+}
+
+/// Let statement (variable declaration)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LetStatement {
+    pub name: String,
+    /// Declared with `let mut`, so it may be assigned
+    #[serde(default)]
+    pub is_mutable: bool,
+    /// Tuple pattern of a destructuring `let (q, r) = divmod(7, 2);`, whose
+    /// `name` is empty
+    #[serde(default)]
+    pub pattern: Option<Pattern>,
+    pub var_type: Option<Type>,
+    pub initializer: Option<Expression>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Return statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReturnStatement {
+    pub value: Option<Expression>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// If statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IfStatement {
+    pub condition: Expression,
+    pub then_block: Block,
+    pub else_block: Option<Block>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// While statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhileStatement {
+    pub condition: Expression,
+    pub body: Block,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// For statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForStatement {
+    pub variable: String,
+    pub iterable: Expression,
+    pub body: Block,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Match statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchStatement {
+    pub expression: Expression,
+    pub arms: Vec<MatchArm>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Match arm
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expression>,
+    pub body: Block,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Pattern for match statements
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Pattern {
+    Wildcard,
+    Literal(Literal),
+    Identifier(String),
+    Tuple(Vec<Pattern>),
+    Struct(String, Vec<(String, Pattern)>),
+    Enum(String, Option<Vec<Pattern>>),
+}
+
+impl Pattern {
+    /// Names the pattern binds, left to right
+    pub fn bindings(&self) -> Vec<&str> {
+        match self {
+            Pattern::Identifier(name) => vec![name.as_str()],
+            Pattern::Tuple(patterns) => patterns.iter().flat_map(Pattern::bindings).collect(),
+            Pattern::Struct(_, fields) => fields.iter().flat_map(|(_, pattern)| pattern.bindings()).collect(),
+            Pattern::Enum(_, Some(patterns)) => patterns.iter().flat_map(Pattern::bindings).collect(),
+            Pattern::Wildcard | Pattern::Literal(_) | Pattern::Enum(_, None) => Vec::new(),
+        }
+    }
+}
+
+/// Query statement (logic programming)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryStatement {
+    pub query_type: QueryType,
+    pub goals: Vec<LogicTerm>,
+    pub handler: Option<Block>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Type of logic query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum QueryType {
+    Solve,  // query_solve
+    Prove,  // query_prove
+}
+
+/// Assert statement (add fact to knowledge base): `assert parent(name, "sara");`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssertStatement {
+    pub relation: String,
+    /// Values of the fact's arguments, evaluated when the statement runs
+    pub args: Vec<Expression>,
+    pub span: Span,
+}
+
+/// Retract statement (remove fact from knowledge base): `retract parent(name, "sara");`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetractStatement {
+    pub relation: String,
+    /// Values of the fact's arguments, evaluated when the statement runs
+    pub args: Vec<Expression>,
+    pub span: Span,
+}
+
+/// Expressions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Expression {
+    Literal(Literal),
+    Identifier(String),
+    Binary(BinaryExpression),
+    Unary(UnaryExpression),
+    Call(CallExpression),
+    FieldAccess(FieldAccessExpression),
+    Index(IndexExpression),
+    Array(ArrayExpression),
+    ArrayRepeat(ArrayRepeatExpression),
+    Map(MapExpression),
+    Set(SetExpression),
+    Tuple(TupleExpression),
+    Struct(StructExpression),
+    Enum(EnumExpression),
+    Lambda(LambdaExpression),
+    Async(AsyncExpression),
+    Await(AwaitExpression),
+    Match(Box<MatchStatement>), // Match can be both statement and expression
+    If(Box<IfStatement>),       // Likewise `let x = if c { 1 } else { 2 };`
+    Query(Box<QueryStatement>), // `query ancestor(X, "ali")`, the solutions of one goal
+}
+
+impl Expression {
+    /// Source location of the expression, if the parser recorded one
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Expression::Binary(expr) => Some(expr.span),
+            Expression::Unary(expr) => Some(expr.span),
+            Expression::Call(expr) => Some(expr.span),
+            Expression::FieldAccess(expr) => Some(expr.span),
+            Expression::Index(expr) => Some(expr.span),
+            Expression::Array(expr) => Some(expr.span),
+            Expression::ArrayRepeat(expr) => Some(expr.span),
+            Expression::Map(expr) => Some(expr.span),
+            Expression::Set(expr) => Some(expr.span),
+            Expression::Tuple(expr) => Some(expr.span),
+            Expression::Struct(expr) => Some(expr.span),
+            Expression::Enum(expr) => Some(expr.span),
+            Expression::Match(stmt) => Some(stmt.span),
+            Expression::If(stmt) => Some(stmt.span),
+            Expression::Query(stmt) => Some(stmt.span),
+            _ => None,
+        }
+        .filter(|span| !span.is_empty())
+    }
+}
+
+/// Literal values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Literal {
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Char(char),
+    Null,
+
+    // Tensor Literal (Expert recommendation: Priority 3)
+    /// Tensor literal: tensor [[1.0, 2.0], [3.0, 4.0]]
+    Tensor(Vec<Vec<f64>>),
+}
+
+/// Binary expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BinaryExpression {
+    pub left: Box<Expression>,
+    pub operator: BinaryOperator,
+    pub right: Box<Expression>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Binary operators
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BinaryOperator {
+    // Arithmetic
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Power,
+
+    // Comparison
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+
+    // Logical
+    And,
+    Or,
+
+    // Assignment
+    Assign,
+    AddAssign,
+    SubtractAssign,
+    MultiplyAssign,
+    DivideAssign,
+}
+
+/// Unary expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnaryExpression {
+    pub operator: UnaryOperator,
+    pub operand: Box<Expression>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Unary operators
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UnaryOperator {
+    Not,
+    Negate,
+    Reference,
+    MutableReference,
+    Dereference,
+}
+
+/// Function call expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallExpression {
+    pub callee: Box<Expression>,
+    pub arguments: Vec<Expression>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Field access expression (obj.field)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldAccessExpression {
+    pub object: Box<Expression>,
+    pub field: String,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Index expression (arr[index])
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexExpression {
+    pub object: Box<Expression>,
+    pub index: Box<Expression>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Array literal expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArrayExpression {
+    pub elements: Vec<Expression>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Fixed-size array literal `[value; count]`, with a constant count
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArrayRepeatExpression {
+    pub value: Box<Expression>,
+    pub count: Box<Expression>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Map literal `{ key: value, ... }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapExpression {
+    pub entries: Vec<(Expression, Expression)>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Set literal `#{a, b, c}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SetExpression {
+    pub elements: Vec<Expression>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Tuple literal expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TupleExpression {
+    pub elements: Vec<Expression>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Struct literal expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructExpression {
+    pub name: String,
+    pub fields: Vec<(String, Expression)>,
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Enum variant construction expression (Expert recommendation)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnumExpression {
+    pub enum_name: String,
+    pub variant_name: String,
+    pub fields: Option<Vec<Expression>>, // For tuple-like variants: Some(Color::RGB(255, 0, 0))
+    /// Source location
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// Lambda expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LambdaExpression {
+    pub parameters: Vec<Parameter>,
+    pub body: Box<Expression>,
+}
+
+/// Async expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AsyncExpression {
+    pub body: Block,
+}
+
+/// Await expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AwaitExpression {
+    pub expression: Box<Expression>,
+}
+
+/// Trait for AST node visitors
+pub trait AstVisitor<T> {
+    fn visit_program(&mut self, program: &Program) -> T;
+    fn visit_item(&mut self, item: &Item) -> T;
+    fn visit_statement(&mut self, statement: &Statement) -> T;
+    fn visit_expression(&mut self, expression: &Expression) -> T;
+}
+
+/// Trait for mutable AST node visitors
+pub trait AstVisitorMut<T> {
+    fn visit_program(&mut self, program: &mut Program) -> T;
+    fn visit_item(&mut self, item: &mut Item) -> T;
+    fn visit_statement(&mut self, statement: &mut Statement) -> T;
+    fn visit_expression(&mut self, expression: &mut Expression) -> T;
+}
+
+/// Semantic block for Natural Language Understanding
+/// كتلة دلالية لفهم اللغة الطبيعية
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SemanticBlock {
+    /// Natural language sentences to be parsed
+    /// الجمل الطبيعية المراد تحليلها
+    pub sentences: Vec<String>,
+    /// Generated API calls from NLU parsing
+    /// استدعاءات API المولدة من تحليل اللغة الطبيعية
+    pub generated_calls: Vec<String>,
+    /// Confidence scores for each sentence
+    /// درجات الثقة لكل جملة
+    pub confidence_scores: Vec<f64>,
+}
+
+impl SemanticBlock {
+    /// Create a new semantic block
+    pub fn new(sentences: Vec<String>) -> Self {
+        Self {
+            sentences,
+            generated_calls: Vec::new(),
+            confidence_scores: Vec::new(),
+        }
+    }
+
+    /// Add a generated API call
+    pub fn add_generated_call(&mut self, call: String, confidence: f64) {
+        self.generated_calls.push(call);
+        self.confidence_scores.push(confidence);
+    }
+}
+
+// Re-export the main AST node type
+pub use Program as AstNode;
@@ -196,7 +196,7 @@ impl ChumskyParser {
         let program = item
             .clone()
             .repeated()
-            .map(|items| Program { items })
+            .map(|items| Program { items, comments: Vec::new() })
             .then_ignore(end());
         
         // --- Define the recursive parsers ---
@@ -0,0 +1,2966 @@
+//! # Parser Module
+//!
+//! This module implements the parser for the AlBayan programming language.
+//! It converts a stream of tokens into an Abstract Syntax Tree (AST).
+
+pub mod ast;
+
+use crate::cancellation::{CancellationToken, Cancelled};
+use crate::lexer::{InterpolationPart, Token, TokenType};
+use ast::*;
+use std::collections::HashMap;
+
+/// Parser for the AlBayan language
+pub struct Parser {
+    tokens: Vec<Token>,
+    /// Comment tokens taken out of the stream, attached to their neighbours
+    comments: Vec<Comment>,
+    current: usize,
+    cancellation: CancellationToken,
+}
+
+impl Parser {
+    /// Create a new parser with the given tokens
+    pub fn new(tokens: Vec<Token>) -> Self {
+        let (tokens, comments) = Self::split_comments(tokens);
+        Self {
+            tokens,
+            comments,
+            current: 0,
+            cancellation: CancellationToken::default(),
+        }
+    }
+
+    /// Stop with `ParseError::Cancelled` at the next item or statement once `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Parse the tokens into an AST
+    pub fn parse(&mut self) -> Result<Program, ParseError> {
+        let mut items = Vec::new();
+
+        while !self.is_at_end() {
+            if self.match_token(&TokenType::Newline) {
+                continue; // Skip newlines at top level
+            }
+
+            self.cancellation.check()?;
+            let start = self.current_start();
+            let item = self.parse_item()?;
+            // Comments before `pub` or attributes lead the item itself
+            if let Some(span) = item.span() {
+                self.move_leading_comments(start, span.start);
+            }
+            items.push(item);
+        }
+
+        Ok(Program {
+            items,
+            comments: std::mem::take(&mut self.comments),
+        })
+    }
+
+    /// Whether the input starts with an item declaration rather than a
+    /// statement, which decides how a REPL line is parsed
+    pub fn starts_with_item(&self) -> bool {
+        let mut tokens = self.tokens[self.current..]
+            .iter()
+            .map(|token| &token.token_type)
+            .filter(|token_type| !matches!(token_type, TokenType::Newline));
+        match tokens.next() {
+            Some(
+                TokenType::Fn
+                | TokenType::Struct
+                | TokenType::Enum
+                | TokenType::Class
+                | TokenType::Interface
+                | TokenType::Trait
+                | TokenType::Impl
+                | TokenType::Relation
+                | TokenType::Rule
+                | TokenType::Hash
+                | TokenType::Fact
+                | TokenType::Module
+                | TokenType::Using
+                | TokenType::Const
+                | TokenType::Pub
+                | TokenType::Semantic,
+            ) => true,
+            Some(TokenType::Identifier(word)) if word == "concept" || word == "constraint" => {
+                matches!(tokens.next(), Some(TokenType::Identifier(_)))
+            }
+            _ => false,
+        }
+    }
+
+    /// Parse statements up to the end of the input, as a REPL line runs them;
+    /// the last expression may omit its `;`
+    pub fn parse_statements(&mut self) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = Vec::new();
+        while !self.is_at_end() {
+            if self.match_token(&TokenType::Newline) {
+                continue;
+            }
+            self.cancellation.check()?;
+            statements.push(self.parse_statement()?);
+        }
+        Ok(statements)
+    }
+
+    /// Take the comment tokens out of `tokens`. A comment after a token on
+    /// the same line trails that token; any other comment leads the next
+    /// token that is not a newline.
+    fn split_comments(tokens: Vec<Token>) -> (Vec<Token>, Vec<Comment>) {
+        let mut code = Vec::with_capacity(tokens.len());
+        let mut comments = Vec::new();
+        let mut pending = Vec::new();
+        let mut line_end = None;
+        for token in tokens {
+            match token.token_type {
+                TokenType::Comment(text) => match line_end {
+                    Some(after) => comments.push(Comment {
+                        text,
+                        span: token.span,
+                        placement: CommentPlacement::Trailing { after },
+                    }),
+                    None => pending.push((text, token.span)),
+                },
+                TokenType::Newline => {
+                    line_end = None;
+                    code.push(token);
+                }
+                _ => {
+                    let before = token.span.start;
+                    comments.extend(pending.drain(..).map(|(text, span)| Comment {
+                        text,
+                        span,
+                        placement: CommentPlacement::Leading { before },
+                    }));
+                    line_end = Some(token.span.end);
+                    code.push(token);
+                }
+            }
+        }
+        (code, comments)
+    }
+
+    fn move_leading_comments(&mut self, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+        for comment in &mut self.comments {
+            if comment.placement == (CommentPlacement::Leading { before: from }) {
+                comment.placement = CommentPlacement::Leading { before: to };
+            }
+        }
+    }
+
+    /// Parse a top-level item (function, struct, relation, etc.)
+    fn parse_item(&mut self) -> Result<Item, ParseError> {
+        match &self.peek().token_type {
+            TokenType::Fn => self.parse_function(),
+            TokenType::Struct => self.parse_struct(),
+            TokenType::Enum => self.parse_enum(),
+            TokenType::Class => self.parse_class(),
+            TokenType::Interface => self.parse_interface(),
+            TokenType::Trait => self.parse_trait(), // NEWLY ADDED: Expert recommendation
+            TokenType::Impl => self.parse_impl(),   // NEWLY ADDED: Expert recommendation
+            TokenType::Relation => self.parse_relation(),
+            TokenType::Rule => self.parse_rule(),
+            TokenType::Hash => self.parse_attributed_item(),
+            TokenType::Fact => self.parse_fact(),
+            // `concept` is only a keyword before a name, so fields may keep it
+            TokenType::Identifier(word) if word == "concept" && self.peek_next_is(&TokenType::Identifier(String::new())) => {
+                self.parse_concept()
+            }
+            TokenType::Identifier(word) if word == "constraint" && self.peek_next_is(&TokenType::Identifier(String::new())) => {
+                self.parse_constraint()
+            }
+            TokenType::Module => self.parse_module(),
+            TokenType::Using => self.parse_using(),
+            TokenType::Const => self.parse_const(),
+            TokenType::Pub => self.parse_public_item(),
+            TokenType::Semantic => {
+                let semantic_block = self.parse_semantic_block()?;
+                Ok(Item::Semantic(semantic_block))
+            } // NEWLY ADDED: NLU support
+            _ => Err(ParseError::UnexpectedToken {
+                expected: "item declaration".to_string(),
+                found: self.peek().clone(),
+            }),
+        }
+    }
+
+    /// Parse a function declaration
+    fn parse_function(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Fn, "Expected 'fn'")?;
+
+        let name = self.consume_identifier("Expected function name")?;
+
+        // Parse optional generic parameters
+        let generic_params = if self.check(&TokenType::Less) {
+            Some(self.parse_generic_params()?)
+        } else {
+            None
+        };
+
+        self.consume(&TokenType::LeftParen, "Expected '(' after function name")?;
+        let parameters = self.parse_parameters()?;
+        self.consume(&TokenType::RightParen, "Expected ')' after parameters")?;
+
+        let return_type = if self.match_token(&TokenType::Arrow) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+
+        let body = self.parse_block()?;
+
+        Ok(Item::Function(FunctionDecl {
+            name,
+            visibility: Visibility::Private,
+            generic_params,
+            parameters,
+            return_type,
+            body,
+            is_test: false,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse a parameter list up to the closing ')': `name: Type`, or a
+    /// receiver written `self`, `&self` or `&mut self`
+    fn parse_parameters(&mut self) -> Result<Vec<Parameter>, ParseError> {
+        let mut parameters = Vec::new();
+        if self.check(&TokenType::RightParen) {
+            return Ok(parameters);
+        }
+
+        loop {
+            if self.match_token(&TokenType::SelfKeyword) {
+                parameters.push(Parameter::SelfValue);
+            } else if self.check(&TokenType::Ampersand) {
+                self.advance(); // consume '&'
+                let is_mutable = self.match_token(&TokenType::Mut);
+                self.consume(&TokenType::SelfKeyword, "Expected 'self' after '&'")?;
+                parameters.push(if is_mutable { Parameter::SelfMutRef } else { Parameter::SelfRef });
+            } else {
+                let param_name = self.consume_identifier("Expected parameter name")?;
+                self.consume(&TokenType::Colon, "Expected ':' after parameter name")?;
+                let param_type = self.parse_type()?;
+
+                parameters.push(Parameter::Regular {
+                    name: param_name,
+                    param_type,
+                });
+            }
+
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
+        }
+
+        Ok(parameters)
+    }
+
+    /// Parse a struct declaration
+    fn parse_struct(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Struct, "Expected 'struct'")?;
+        let name = self.consume_identifier("Expected struct name")?;
+
+        // Parse optional generic parameters
+        let generic_params = if self.check(&TokenType::Less) {
+            Some(self.parse_generic_params()?)
+        } else {
+            None
+        };
+
+        self.consume(&TokenType::LeftBrace, "Expected '{' after struct name")?;
+
+        let mut fields = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            // Skip newlines
+            while self.check(&TokenType::Newline) {
+                self.advance();
+            }
+
+            // Check again after skipping newlines
+            if self.check(&TokenType::RightBrace) || self.is_at_end() {
+                break;
+            }
+
+            let visibility = self.parse_visibility();
+            let field_name = self.consume_identifier("Expected field name")?;
+            self.consume(&TokenType::Colon, "Expected ':' after field name")?;
+            let field_type = self.parse_type()?;
+            self.consume_field_separator()?;
+
+            fields.push(StructField {
+                name: field_name,
+                field_type: field_type,
+                visibility,
+            });
+        }
+
+        self.consume(&TokenType::RightBrace, "Expected '}' after struct fields")?;
+
+        Ok(Item::Struct(StructDecl {
+            name,
+            visibility: Visibility::Private,
+            generic_params,
+            fields,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse a constant declaration: `const NAME: type = value;`
+    fn parse_const(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Const, "Expected 'const'")?;
+        let name = self.consume_identifier("Expected constant name")?;
+        self.consume(&TokenType::Colon, "Expected ':' and a type after constant name")?;
+        let const_type = self.parse_type()?;
+        self.consume(&TokenType::Assign, "Expected '=' after constant type")?;
+        let value = self.parse_expression()?;
+        self.consume(&TokenType::Semicolon, "Expected ';' after constant value")?;
+
+        Ok(Item::Const(ConstDecl {
+            name,
+            visibility: Visibility::Private,
+            const_type,
+            value,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse a relation declaration
+    fn parse_relation(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Relation, "Expected 'relation'")?;
+        let name = self.consume_identifier("Expected relation name")?;
+
+        self.consume(&TokenType::LeftParen, "Expected '(' after relation name")?;
+
+        let mut arg_types = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                let arg_type = self.parse_type()?;
+                arg_types.push(arg_type);
+
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(
+            &TokenType::RightParen,
+            "Expected ')' after relation arguments",
+        )?;
+        self.consume(
+            &TokenType::Semicolon,
+            "Expected ';' after relation declaration",
+        )?;
+
+        Ok(Item::Relation(RelationDecl {
+            name,
+            visibility: Visibility::Private,
+            arg_types,
+            security: None,
+            tabled: false,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse a rule declaration
+    fn parse_rule(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Rule, "Expected 'rule'")?;
+
+        let head = self.parse_logic_term()?;
+        self.consume(&TokenType::Implies, "Expected ':-' in rule")?;
+
+        let mut body = Vec::new();
+        loop {
+            body.push(self.parse_body_goal()?);
+
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
+        }
+
+        self.consume(&TokenType::Semicolon, "Expected ';' after rule")?;
+
+        Ok(Item::Rule(RuleDecl {
+            head,
+            body,
+            no_reorder: false,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse a rule preceded by `#[no_reorder]`, a relation preceded by
+    /// `#[untrusted]`, `#[sensitive]` or `#[table]`, or a function preceded
+    /// by `#[test]`
+    fn parse_attributed_item(&mut self) -> Result<Item, ParseError> {
+        self.consume(&TokenType::Hash, "Expected '#'")?;
+        self.consume(&TokenType::LeftBracket, "Expected '[' after '#'")?;
+        let attribute_span = self.peek().span;
+        let attribute = self.consume_identifier("Expected attribute name")?;
+        self.consume(&TokenType::RightBracket, "Expected ']' after attribute")?;
+
+        if attribute == "test" {
+            while self.match_token(&TokenType::Newline) {}
+            return match self.parse_function()? {
+                Item::Function(function) => Ok(Item::Function(FunctionDecl { is_test: true, ..function })),
+                _ => unreachable!("parse_function returns a function"),
+            };
+        }
+
+        let security = match attribute.as_str() {
+            "no_reorder" | "table" => None,
+            "untrusted" => Some(SecurityLabel::Untrusted),
+            "sensitive" => Some(SecurityLabel::Sensitive),
+            _ => {
+                return Err(ParseError::InvalidSyntax {
+                    message: format!("Unknown attribute '{}'", attribute),
+                    span: attribute_span,
+                })
+            }
+        };
+
+        while self.match_token(&TokenType::Newline) {}
+        match security {
+            None if attribute == "table" => match self.parse_relation()? {
+                Item::Relation(relation) => Ok(Item::Relation(RelationDecl { tabled: true, ..relation })),
+                _ => unreachable!("parse_relation returns a relation"),
+            },
+            None => match self.parse_rule()? {
+                Item::Rule(rule) => Ok(Item::Rule(RuleDecl { no_reorder: true, ..rule })),
+                _ => unreachable!("parse_rule returns a rule"),
+            },
+            Some(label) => match self.parse_relation()? {
+                Item::Relation(relation) => Ok(Item::Relation(RelationDecl { security: Some(label), ..relation })),
+                _ => unreachable!("parse_relation returns a relation"),
+            },
+        }
+    }
+
+    /// Parse an item marked `pub`, which other modules may use
+    fn parse_public_item(&mut self) -> Result<Item, ParseError> {
+        self.consume(&TokenType::Pub, "Expected 'pub'")?;
+        while self.match_token(&TokenType::Newline) {}
+        let span = self.peek().span;
+        let mut item = match self.peek().token_type {
+            TokenType::Fn | TokenType::Struct | TokenType::Enum | TokenType::Class | TokenType::Const
+            | TokenType::Relation => self.parse_item()?,
+            _ => {
+                return Err(ParseError::InvalidSyntax {
+                    message: "`pub` must be followed by a function, struct, enum, class, constant or relation".to_string(),
+                    span,
+                })
+            }
+        };
+        match &mut item {
+            Item::Function(decl) => decl.visibility = Visibility::Public,
+            Item::Struct(decl) => decl.visibility = Visibility::Public,
+            Item::Enum(decl) => decl.visibility = Visibility::Public,
+            Item::Class(decl) => decl.visibility = Visibility::Public,
+            Item::Const(decl) => decl.visibility = Visibility::Public,
+            Item::Relation(decl) => decl.visibility = Visibility::Public,
+            _ => unreachable!("only items that take a visibility are parsed after `pub`"),
+        }
+        Ok(item)
+    }
+
+    /// Parse an optional `pub` before a field
+    fn parse_visibility(&mut self) -> Visibility {
+        if self.match_token(&TokenType::Pub) {
+            Visibility::Public
+        } else {
+            Visibility::Private
+        }
+    }
+
+    /// Parse a goal of a rule body: a logic term or a comparison, either
+    /// negated with `not`, or the cut `!`
+    fn parse_body_goal(&mut self) -> Result<LogicTerm, ParseError> {
+        let start = self.current_start();
+        if self.match_token(&TokenType::Not) {
+            return Ok(LogicTerm::cut(self.span_from(start)));
+        }
+        let negated = matches!(&self.peek().token_type, TokenType::Identifier(word) if word == "not")
+            && self.peek_next_is(&TokenType::Identifier(String::new()));
+        if negated {
+            self.advance();
+            let term = self.parse_positive_goal()?;
+            return Ok(LogicTerm { negated: true, span: self.span_from(start), ..term });
+        }
+        self.parse_positive_goal()
+    }
+
+    /// Parse a logic term, or a comparison such as `X > 5` or `Y is X + 1`
+    /// when the goal does not start with a name and `(`
+    fn parse_positive_goal(&mut self) -> Result<LogicTerm, ParseError> {
+        let is_term = matches!(self.peek().token_type, TokenType::Identifier(_))
+            && self.peek_next_is(&TokenType::LeftParen);
+        if is_term {
+            return self.parse_logic_term();
+        }
+
+        let start = self.current_start();
+        let left = self.parse_logic_expression()?;
+        let name = match &self.peek().token_type {
+            TokenType::Assign | TokenType::Equal => "=",
+            TokenType::NotEqual => "!=",
+            TokenType::Less => "<",
+            TokenType::LessEqual => "<=",
+            TokenType::Greater => ">",
+            TokenType::GreaterEqual => ">=",
+            TokenType::Identifier(word) if word == "is" => "is",
+            _ => {
+                return Err(ParseError::UnexpectedToken {
+                    expected: "comparison operator".to_string(),
+                    found: self.peek().clone(),
+                })
+            }
+        };
+        self.advance();
+        let right = self.parse_logic_expression()?;
+
+        Ok(LogicTerm {
+            name: name.to_string(),
+            args: vec![left, right],
+            negated: false,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parse arithmetic on logic arguments; `*`, `/` and `%` bind tighter
+    /// than `+` and `-`
+    fn parse_logic_expression(&mut self) -> Result<LogicArg, ParseError> {
+        let mut left = self.parse_logic_product()?;
+        loop {
+            let op = match self.peek().token_type {
+                TokenType::Plus => "+",
+                TokenType::Minus => "-",
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = self.parse_logic_product()?;
+            left = LogicArg::Operation { op: op.to_string(), left: Box::new(left), right: Box::new(right) };
+        }
+    }
+
+    fn parse_logic_product(&mut self) -> Result<LogicArg, ParseError> {
+        let mut left = self.parse_logic_operand()?;
+        loop {
+            let op = match self.peek().token_type {
+                TokenType::Multiply => "*",
+                TokenType::Divide => "/",
+                TokenType::Modulo => "%",
+                _ => return Ok(left),
+            };
+            self.advance();
+            let right = self.parse_logic_operand()?;
+            left = LogicArg::Operation { op: op.to_string(), left: Box::new(left), right: Box::new(right) };
+        }
+    }
+
+    fn parse_logic_operand(&mut self) -> Result<LogicArg, ParseError> {
+        if self.match_token(&TokenType::LeftParen) {
+            let inner = self.parse_logic_expression()?;
+            self.consume(&TokenType::RightParen, "Expected ')' after arithmetic")?;
+            return Ok(inner);
+        }
+        self.parse_logic_arg()
+    }
+
+    /// Parse a logic term (for relations, rules, queries)
+    fn parse_logic_term(&mut self) -> Result<LogicTerm, ParseError> {
+        let start = self.current_start();
+        let name = self.consume_identifier("Expected term name")?;
+
+        self.consume(&TokenType::LeftParen, "Expected '(' after term name")?;
+
+        let mut args = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                let arg = self.parse_logic_arg()?;
+                args.push(arg);
+
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(&TokenType::RightParen, "Expected ')' after term arguments")?;
+
+        // The nested term an aggregate solves is its goal, not a data term
+        let goal_index = aggregate_goal_index(&name);
+        if let (true, Some(LogicArg::Compound(goal))) = (AGGREGATES.contains(&name.as_str()), args.get(goal_index)) {
+            args[goal_index] = LogicArg::Goal(goal.clone());
+        }
+
+        Ok(LogicTerm {
+            name,
+            args,
+            negated: false,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parse a logic argument: a variable, a constant, a compound term or a
+    /// list
+    fn parse_logic_arg(&mut self) -> Result<LogicArg, ParseError> {
+        if matches!(self.peek().token_type, TokenType::Identifier(_)) && self.peek_next_is(&TokenType::LeftParen) {
+            return Ok(LogicArg::Compound(Box::new(self.parse_logic_term()?)));
+        }
+        match &self.peek().token_type {
+            TokenType::LeftBracket => {
+                self.advance();
+                let mut items = Vec::new();
+                let mut tail = None;
+                if !self.check(&TokenType::RightBracket) {
+                    loop {
+                        items.push(self.parse_logic_arg()?);
+                        if self.match_token(&TokenType::Pipe) {
+                            tail = Some(Box::new(self.parse_logic_arg()?));
+                            break;
+                        }
+                        if !self.match_token(&TokenType::Comma) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(&TokenType::RightBracket, "Expected ']' after list items")?;
+                Ok(LogicArg::List { items, tail })
+            }
+            TokenType::Identifier(name) => {
+                let name = name.clone();
+                self.advance();
+
+                // Variables start with uppercase, constants with lowercase
+                if name.chars().next().unwrap().is_uppercase() {
+                    Ok(LogicArg::Variable(name))
+                } else {
+                    Ok(LogicArg::Constant(name))
+                }
+            }
+            TokenType::StringLiteral(s) => {
+                let s = s.clone();
+                self.advance();
+                Ok(LogicArg::StringConstant(s))
+            }
+            TokenType::IntegerLiteral(Some(n)) => {
+                let n = *n;
+                self.advance();
+                Ok(LogicArg::IntConstant(n))
+            }
+            TokenType::FloatLiteral(Some(value)) => {
+                let value = *value;
+                self.advance();
+                Ok(LogicArg::FloatConstant(value))
+            }
+            _ => Err(ParseError::UnexpectedToken {
+                expected: "logic argument".to_string(),
+                found: self.peek().clone(),
+            }),
+        }
+    }
+
+    /// Parse a type annotation (Enhanced for generics, trait objects, and references)
+    fn parse_type(&mut self) -> Result<Type, ParseError> {
+        match &self.peek().token_type {
+            // Parse reference type: &T or &mut T (Expert recommendation: Priority 1)
+            TokenType::Ampersand => {
+                self.advance(); // consume '&'
+
+                // Check for 'mut' keyword
+                let is_mutable = if self.match_token(&TokenType::Mut) {
+                    true
+                } else {
+                    false
+                };
+
+                // Parse the referenced type
+                let referenced_type = self.parse_type()?;
+
+                Ok(Type::Reference(Box::new(referenced_type), is_mutable))
+            }
+            // Parse trait object: dyn Trait
+            TokenType::Dyn => {
+                self.advance(); // consume 'dyn'
+
+                let mut traits = Vec::new();
+
+                // Parse first trait
+                if let TokenType::Identifier(trait_name) = &self.peek().token_type {
+                    let trait_name = trait_name.clone();
+                    self.advance();
+                    traits.push(Path::single(trait_name));
+
+                    // Parse additional traits separated by '+'
+                    while self.match_token(&TokenType::Plus) {
+                        if let TokenType::Identifier(trait_name) = &self.peek().token_type {
+                            let trait_name = trait_name.clone();
+                            self.advance();
+                            traits.push(Path::single(trait_name));
+                        } else {
+                            return Err(ParseError::UnexpectedToken {
+                                expected: "trait name".to_string(),
+                                found: self.peek().clone(),
+                            });
+                        }
+                    }
+                } else {
+                    return Err(ParseError::UnexpectedToken {
+                        expected: "trait name after 'dyn'".to_string(),
+                        found: self.peek().clone(),
+                    });
+                }
+
+                Ok(Type::TraitObject(traits))
+            }
+            TokenType::Identifier(name) => {
+                let name = name.clone();
+                self.advance();
+
+                // Associated type path (e.g., Self::Item)
+                if self.check(&TokenType::DoubleColon) {
+                    let mut segments = vec![name];
+                    while self.match_token(&TokenType::DoubleColon) {
+                        segments.push(self.consume_identifier("Expected type name after '::'")?);
+                    }
+                    return Ok(Type::Named(Path::from_segments(segments)));
+                }
+
+                // Check if this is a generic type (e.g., Vec<T>)
+                if self.check(&TokenType::Less) {
+                    self.advance(); // consume '<'
+
+                    let mut type_params = Vec::new();
+
+                    // Parse first type parameter
+                    if !self.check(&TokenType::Greater) {
+                        type_params.push(self.parse_type()?);
+
+                        // Parse additional type parameters separated by commas
+                        while self.match_token(&TokenType::Comma) {
+                            type_params.push(self.parse_type()?);
+                        }
+                    }
+
+                    self.consume(
+                        &TokenType::Greater,
+                        "Expected '>' after generic type parameters",
+                    )?;
+
+                    Ok(Type::Generic(Path::single(name), type_params))
+                } else {
+                    Ok(Type::Named(Path::single(name)))
+                }
+            }
+            // Array type: [int] or [int; SIZE]
+            TokenType::LeftBracket => {
+                self.advance();
+                let element_type = self.parse_type()?;
+                let size = if self.match_token(&TokenType::Semicolon) {
+                    Some(Box::new(self.parse_expression()?))
+                } else {
+                    None
+                };
+                self.consume(&TokenType::RightBracket, "Expected ']' after array type")?;
+                Ok(Type::Array(Box::new(element_type), size))
+            }
+            // Tuple type: (int, string)
+            TokenType::LeftParen => {
+                self.advance();
+                let mut element_types = Vec::new();
+                while !self.check(&TokenType::RightParen) {
+                    element_types.push(self.parse_type()?);
+                    if !self.match_token(&TokenType::Comma) {
+                        break;
+                    }
+                }
+                self.consume(&TokenType::RightParen, "Expected ')' after tuple element types")?;
+                Ok(Type::Tuple(element_types))
+            }
+            // PyTorch types (Expert recommendation: Priority 2)
+            TokenType::TorchModel => {
+                self.advance();
+                Ok(Type::Named(Path::single("TorchModel".to_string())))
+            }
+            TokenType::TorchOptimizer => {
+                self.advance();
+                Ok(Type::Named(Path::single("TorchOptimizer".to_string())))
+            }
+            TokenType::TorchTensor => {
+                self.advance();
+                Ok(Type::Named(Path::single("TorchTensor".to_string())))
+            }
+            TokenType::TrainingResult => {
+                self.advance();
+                Ok(Type::Named(Path::single("TrainingResult".to_string())))
+            }
+
+            // Tensor Literal (Expert recommendation: Priority 3)
+            TokenType::TensorLiteral => {
+                self.advance();
+                Ok(Type::Named(Path::single("TensorLiteral".to_string())))
+            }
+            _ => Err(ParseError::UnexpectedToken {
+                expected: "type".to_string(),
+                found: self.peek().clone(),
+            }),
+        }
+    }
+
+    /// Parse generic parameters like <T, U: Display, V: Clone + Send>
+    fn parse_generic_params(&mut self) -> Result<Vec<GenericParam>, ParseError> {
+        if !self.match_token(&TokenType::Less) {
+            return Ok(Vec::new());
+        }
+
+        let mut generic_params = Vec::new();
+
+        if !self.check(&TokenType::Greater) {
+            loop {
+                // Parse generic parameter name
+                let name = self.consume_identifier("Expected generic parameter name")?;
+
+                // Parse optional trait bounds (T: Display + Clone)
+                let mut bounds = Vec::new();
+                if self.match_token(&TokenType::Colon) {
+                    // Parse first trait bound
+                    let trait_name = self.consume_identifier("Expected trait name")?;
+                    bounds.push(TraitBound { trait_name });
+
+                    // Parse additional trait bounds separated by '+'
+                    while self.match_token(&TokenType::Plus) {
+                        let trait_name =
+                            self.consume_identifier("Expected trait name after '+'")?;
+                        bounds.push(TraitBound { trait_name });
+                    }
+                }
+
+                generic_params.push(GenericParam { name, bounds });
+
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(&TokenType::Greater, "Expected '>' after generic parameters")?;
+        Ok(generic_params)
+    }
+
+    /// Parse a block statement
+    fn parse_block(&mut self) -> Result<Block, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::LeftBrace, "Expected '{'")?;
+
+        let mut statements = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            if self.match_token(&TokenType::Newline) {
+                continue;
+            }
+            self.cancellation.check()?;
+            let stmt = self.parse_statement()?;
+            statements.push(stmt);
+        }
+
+        self.consume(&TokenType::RightBrace, "Expected '}'")?;
+
+        Ok(Block {
+            statements,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parse a statement
+    fn parse_statement(&mut self) -> Result<Statement, ParseError> {
+        match &self.peek().token_type {
+            TokenType::Let => self.parse_let_statement(),
+            TokenType::Return => self.parse_return_statement(),
+            TokenType::If => self.parse_if_statement(),
+            TokenType::Match => self.parse_match_statement(),
+            TokenType::While => self.parse_while_statement(),
+            TokenType::For => self.parse_for_statement(),
+            TokenType::Loop => self.parse_loop_statement(),
+            TokenType::Break => self.parse_break_statement(),
+            TokenType::Continue => self.parse_continue_statement(),
+            TokenType::Assert if !self.peek_next_is(&TokenType::LeftParen) => self.parse_fact_update(),
+            TokenType::Retract => self.parse_fact_update(),
+            TokenType::Semantic => {
+                let semantic_block = self.parse_semantic_block()?;
+                self.consume(&TokenType::Semicolon, "Expected ';' after semantic block")?;
+                Ok(Statement::Semantic(semantic_block))
+            }
+            _ => self.parse_expression_statement(),
+        }
+    }
+
+    /// Parse an expression statement, or an assignment to a variable, a field
+    /// (`p.x = 5;`), an element (`arr[0] += 1;`) or through a reference (`*r = 1;`)
+    fn parse_expression_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.current_start();
+        let expr = self.parse_expression()?;
+
+        let operator = match &self.peek().token_type {
+            TokenType::Assign => BinaryOperator::Assign,
+            TokenType::PlusAssign => BinaryOperator::AddAssign,
+            TokenType::MinusAssign => BinaryOperator::SubtractAssign,
+            TokenType::MultiplyAssign => BinaryOperator::MultiplyAssign,
+            TokenType::DivideAssign => BinaryOperator::DivideAssign,
+            _ => {
+                // A trailing expression may omit the `;`: it is the value of the block
+                let semicolon = !self.check(&TokenType::RightBrace) && !self.is_at_end();
+                if semicolon {
+                    self.consume(&TokenType::Semicolon, "Expected ';' after expression")?;
+                }
+                return Ok(Statement::Expression(ExpressionStatement { expression: expr, semicolon }));
+            }
+        };
+
+        let is_place = match &expr {
+            Expression::Identifier(_) | Expression::FieldAccess(_) | Expression::Index(_) => true,
+            Expression::Unary(unary) => unary.operator == UnaryOperator::Dereference,
+            _ => false,
+        };
+        if !is_place {
+            return Err(ParseError::InvalidSyntax {
+                message: "invalid assignment target: expected a variable, field, element or `*reference`".to_string(),
+                span: expr.span().unwrap_or(self.span_from(start)),
+            });
+        }
+        self.advance();
+
+        let value = self.parse_expression()?;
+        self.consume(&TokenType::Semicolon, "Expected ';' after assignment")?;
+
+        Ok(Statement::Expression(ExpressionStatement::new(Expression::Binary(
+            BinaryExpression {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(value),
+                span: self.span_from(start),
+            },
+        ))))
+    }
+
+    /// Parse a let statement
+    fn parse_let_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Let, "Expected 'let'")?;
+
+        let is_mutable = self.match_token(&TokenType::Mut);
+
+        let (name, pattern) = if self.check(&TokenType::LeftParen) {
+            (String::new(), Some(self.parse_binding_pattern()?))
+        } else {
+            (self.consume_identifier("Expected variable name")?, None)
+        };
+
+        let var_type = if self.match_token(&TokenType::Colon) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+
+        let initializer = if self.match_token(&TokenType::Assign) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+
+        self.consume(&TokenType::Semicolon, "Expected ';' after let statement")?;
+
+        Ok(Statement::Let(LetStatement {
+            name,
+            is_mutable,
+            pattern,
+            var_type,
+            initializer,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse the irrefutable pattern of a destructuring `let`: names, `_` and
+    /// parenthesized tuples of them
+    fn parse_binding_pattern(&mut self) -> Result<Pattern, ParseError> {
+        if self.match_token(&TokenType::Underscore) {
+            return Ok(Pattern::Wildcard);
+        }
+        if !self.match_token(&TokenType::LeftParen) {
+            return Ok(Pattern::Identifier(self.consume_identifier("Expected variable name or tuple pattern")?));
+        }
+
+        let mut elements = Vec::new();
+        while !self.check(&TokenType::RightParen) {
+            elements.push(self.parse_binding_pattern()?);
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(&TokenType::RightParen, "Expected ')' after tuple pattern")?;
+        Ok(Pattern::Tuple(elements))
+    }
+
+    /// Parse a return statement
+    fn parse_return_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Return, "Expected 'return'")?;
+
+        let value = if !self.check(&TokenType::Semicolon) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+
+        self.consume(&TokenType::Semicolon, "Expected ';' after return")?;
+
+        Ok(Statement::Return(ReturnStatement {
+            value,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse `assert relation(args);` or `retract relation(args);`
+    fn parse_fact_update(&mut self) -> Result<Statement, ParseError> {
+        let start = self.current_start();
+        let retract = self.advance().token_type == TokenType::Retract;
+        let relation = self.consume_identifier("Expected relation name")?;
+        self.consume(&TokenType::LeftParen, "Expected '(' after relation name")?;
+        let mut args = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                args.push(self.parse_expression()?);
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+        self.consume(&TokenType::RightParen, "Expected ')' after fact arguments")?;
+        self.consume(&TokenType::Semicolon, "Expected ';' after fact")?;
+
+        let span = self.span_from(start);
+        Ok(if retract {
+            Statement::Retract(RetractStatement { relation, args, span })
+        } else {
+            Statement::Assert(AssertStatement { relation, args, span })
+        })
+    }
+
+    /// Parse an if statement
+    fn parse_if_statement(&mut self) -> Result<Statement, ParseError> {
+        self.parse_if().map(Statement::If)
+    }
+
+    /// Parse `if cond { ... }` with its `else` and `else if` branches; shared by
+    /// if statements and if expressions
+    fn parse_if(&mut self) -> Result<IfStatement, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::If, "Expected 'if'")?;
+        let condition = self.parse_expression()?;
+        let then_block = self.parse_block()?;
+
+        let else_block = if self.match_token(&TokenType::Else) {
+            if self.check(&TokenType::If) {
+                // `else if` chains nest: the else block holds the next `if` of the chain
+                let else_if_start = self.current_start();
+                let else_if = self.parse_if()?;
+                Some(Block {
+                    statements: vec![Statement::If(else_if)],
+                    span: self.span_from(else_if_start),
+                })
+            } else {
+                Some(self.parse_block()?)
+            }
+        } else {
+            None
+        };
+
+        Ok(IfStatement {
+            condition,
+            then_block,
+            else_block,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parse a match statement (Expert recommendation: Priority 1 - Complete match support)
+    fn parse_match_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Match, "Expected 'match'")?;
+
+        let expression = self.parse_expression()?;
+
+        self.consume(&TokenType::LeftBrace, "Expected '{' after match expression")?;
+
+        let mut arms = Vec::new();
+
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            if self.match_token(&TokenType::Newline) {
+                continue;
+            }
+
+            let arm = self.parse_match_arm()?;
+            arms.push(arm);
+        }
+
+        self.consume(&TokenType::RightBrace, "Expected '}' after match arms")?;
+
+        Ok(Statement::Match(MatchStatement {
+            expression,
+            arms,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse a match arm (Expert recommendation: Pattern parsing)
+    /// Parse a while statement
+    fn parse_while_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::While, "Expected 'while'")?;
+        let condition = self.parse_expression()?;
+        let body = self.parse_block()?;
+        Ok(Statement::While(WhileStatement {
+            condition,
+            body,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse a break statement (represented as a special identifier expression)
+    fn parse_break_statement(&mut self) -> Result<Statement, ParseError> {
+        self.consume(&TokenType::Break, "Expected 'break'")?;
+        self.consume(&TokenType::Semicolon, "Expected ';' after break")?;
+        Ok(Statement::Expression(ExpressionStatement::new(Expression::Identifier(
+            "__break__".to_string(),
+        ))))
+    }
+
+    /// Parse a continue statement (represented as a special identifier expression)
+    fn parse_continue_statement(&mut self) -> Result<Statement, ParseError> {
+        self.consume(&TokenType::Continue, "Expected 'continue'")?;
+        self.consume(&TokenType::Semicolon, "Expected ';' after continue")?;
+        Ok(Statement::Expression(ExpressionStatement::new(Expression::Identifier(
+            "__continue__".to_string(),
+        ))))
+    }
+
+    /// Parse a for statement: for <ident> in <expr> { <block> }
+    fn parse_for_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::For, "Expected 'for'")?;
+        let var_name = self.consume_identifier("Expected loop variable after 'for'")?;
+        self.consume(&TokenType::In, "Expected 'in' after loop variable")?;
+        let iterable = self.parse_expression()?;
+        let body = self.parse_block()?;
+        Ok(Statement::For(ForStatement {
+            variable: var_name,
+            iterable,
+            body,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse a loop statement (desugared to while true)
+    fn parse_loop_statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Loop, "Expected 'loop'")?;
+        let body = self.parse_block()?;
+        let condition = Expression::Literal(Literal::Boolean(true));
+        Ok(Statement::While(WhileStatement {
+            condition,
+            body,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn parse_match_arm(&mut self) -> Result<MatchArm, ParseError> {
+        let start = self.current_start();
+        let pattern = self.parse_pattern()?;
+
+        // Parse optional guard
+        let guard = if self.match_token(&TokenType::If) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+
+        self.consume(&TokenType::FatArrow, "Expected '=>' after pattern")?;
+
+        // Parse body - can be either a block or a single expression
+        let body = if self.check(&TokenType::LeftBrace) {
+            // Block syntax: { ... }
+            self.parse_block()?
+        } else {
+            // Expression syntax: expr,
+            let body_start = self.current_start();
+            let expr = self.parse_expression()?;
+            let body_span = self.span_from(body_start);
+            // Consume optional comma
+            self.match_token(&TokenType::Comma);
+            // Create a block with single expression statement
+            Block {
+                statements: vec![Statement::Expression(ExpressionStatement { expression: expr, semicolon: false })],
+                span: body_span,
+            }
+        };
+
+        Ok(MatchArm {
+            pattern,
+            guard,
+            body,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Check if the current position looks like a struct literal
+    /// by looking ahead to see if we have identifier: pattern after {
+    fn is_struct_literal(&self) -> bool {
+        if !self.check(&TokenType::LeftBrace) {
+            return false;
+        }
+
+        // Look ahead to see if we have field_name: pattern
+        let mut lookahead = self.current + 1; // Skip the '{'
+
+        // Skip newlines
+        while lookahead < self.tokens.len() {
+            match &self.tokens[lookahead].token_type {
+                TokenType::Newline => {
+                    lookahead += 1;
+                    continue;
+                }
+                TokenType::Identifier(_) => {
+                    // Check if next token after identifier is ':'
+                    lookahead += 1;
+                    if lookahead < self.tokens.len() {
+                        return matches!(self.tokens[lookahead].token_type, TokenType::Colon);
+                    }
+                    return false;
+                }
+                _ => return false,
+            }
+        }
+
+        false
+    }
+
+    /// Parse a pattern (Expert recommendation: Pattern parsing)
+    fn parse_pattern(&mut self) -> Result<Pattern, ParseError> {
+        match &self.peek().token_type {
+            TokenType::Underscore => {
+                self.advance();
+                Ok(Pattern::Wildcard)
+            }
+            TokenType::IntegerLiteral(Some(value)) => {
+                let value = *value;
+                self.advance();
+                Ok(Pattern::Literal(Literal::Integer(value)))
+            }
+            TokenType::FloatLiteral(Some(value)) => {
+                let value = *value;
+                self.advance();
+                Ok(Pattern::Literal(Literal::Float(value)))
+            }
+            TokenType::StringLiteral(value) => {
+                let value = value.clone();
+                self.advance();
+                Ok(Pattern::Literal(Literal::String(value)))
+            }
+            TokenType::True => {
+                self.advance();
+                Ok(Pattern::Literal(Literal::Boolean(true)))
+            }
+            TokenType::False => {
+                self.advance();
+                Ok(Pattern::Literal(Literal::Boolean(false)))
+            }
+            TokenType::Identifier(name) => {
+                let name = name.clone();
+                self.advance();
+
+                // Check if this is an enum pattern (EnumName::VariantName)
+                if self.check(&TokenType::DoubleColon) {
+                    self.advance(); // consume '::'
+                    let variant_name = self.consume_identifier("Expected variant name")?;
+
+                    // Check if variant has fields (tuple-like patterns)
+                    let fields = if self.check(&TokenType::LeftParen) {
+                        self.advance(); // consume '('
+                        let mut field_patterns = Vec::new();
+
+                        if !self.check(&TokenType::RightParen) {
+                            loop {
+                                field_patterns.push(self.parse_pattern()?);
+                                if !self.match_token(&TokenType::Comma) {
+                                    break;
+                                }
+                            }
+                        }
+
+                        self.consume(
+                            &TokenType::RightParen,
+                            "Expected ')' after enum variant fields",
+                        )?;
+                        Some(field_patterns)
+                    } else {
+                        None
+                    };
+
+                    // Store the full enum pattern as "EnumName::VariantName" in the first String
+                    Ok(Pattern::Enum(format!("{}::{}", name, variant_name), fields))
+                } else {
+                    Ok(Pattern::Identifier(name))
+                }
+            }
+            _ => Err(ParseError::UnexpectedToken {
+                expected: "pattern".to_string(),
+                found: self.peek().clone(),
+            }),
+        }
+    }
+
+    /// Parse an expression
+    fn parse_expression(&mut self) -> Result<Expression, ParseError> {
+        self.parse_logical_or()
+    }
+
+    /// Parse logical OR expressions
+    fn parse_logical_or(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current_start();
+        let mut expr = self.parse_logical_and()?;
+
+        while self.match_token(&TokenType::Or) {
+            let right = self.parse_logical_and()?;
+            expr = Expression::Binary(BinaryExpression {
+                left: Box::new(expr),
+                operator: BinaryOperator::Or,
+                right: Box::new(right),
+                span: self.span_from(start),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    /// Parse logical AND expressions
+    fn parse_logical_and(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current_start();
+        let mut expr = self.parse_equality()?;
+
+        while self.match_token(&TokenType::And) {
+            let right = self.parse_equality()?;
+            expr = Expression::Binary(BinaryExpression {
+                left: Box::new(expr),
+                operator: BinaryOperator::And,
+                right: Box::new(right),
+                span: self.span_from(start),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    /// Parse equality expressions (==, !=)
+    fn parse_equality(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current_start();
+        let mut expr = self.parse_comparison()?;
+
+        while self.match_tokens(&[TokenType::Equal, TokenType::NotEqual]) {
+            let operator = self.previous().token_type.clone();
+            let right = self.parse_comparison()?;
+            expr = Expression::Binary(BinaryExpression {
+                left: Box::new(expr),
+                operator: match operator {
+                    TokenType::Equal => BinaryOperator::Equal,
+                    TokenType::NotEqual => BinaryOperator::NotEqual,
+                    _ => unreachable!(),
+                },
+                right: Box::new(right),
+                span: self.span_from(start),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    /// Parse comparison expressions (<, <=, >, >=)
+    fn parse_comparison(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current_start();
+        let mut expr = self.parse_term()?;
+
+        while self.match_tokens(&[
+            TokenType::Greater,
+            TokenType::GreaterEqual,
+            TokenType::Less,
+            TokenType::LessEqual,
+        ]) {
+            let operator = self.previous().token_type.clone();
+            let right = self.parse_term()?;
+            expr = Expression::Binary(BinaryExpression {
+                left: Box::new(expr),
+                operator: match operator {
+                    TokenType::Greater => BinaryOperator::Greater,
+                    TokenType::GreaterEqual => BinaryOperator::GreaterEqual,
+                    TokenType::Less => BinaryOperator::Less,
+                    TokenType::LessEqual => BinaryOperator::LessEqual,
+                    _ => unreachable!(),
+                },
+                right: Box::new(right),
+                span: self.span_from(start),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    /// Parse term expressions (+, -)
+    fn parse_term(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current_start();
+        let mut expr = self.parse_factor()?;
+
+        while self.match_tokens(&[TokenType::Minus, TokenType::Plus]) {
+            let operator = self.previous().token_type.clone();
+            let right = self.parse_factor()?;
+            expr = Expression::Binary(BinaryExpression {
+                left: Box::new(expr),
+                operator: match operator {
+                    TokenType::Plus => BinaryOperator::Add,
+                    TokenType::Minus => BinaryOperator::Subtract,
+                    _ => unreachable!(),
+                },
+                right: Box::new(right),
+                span: self.span_from(start),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    /// Parse factor expressions (*, /, %)
+    fn parse_factor(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current_start();
+        let mut expr = self.parse_unary()?;
+
+        while self.match_tokens(&[TokenType::Divide, TokenType::Multiply, TokenType::Modulo]) {
+            let operator = self.previous().token_type.clone();
+            let right = self.parse_unary()?;
+            expr = Expression::Binary(BinaryExpression {
+                left: Box::new(expr),
+                operator: match operator {
+                    TokenType::Multiply => BinaryOperator::Multiply,
+                    TokenType::Divide => BinaryOperator::Divide,
+                    TokenType::Modulo => BinaryOperator::Modulo,
+                    _ => unreachable!(),
+                },
+                right: Box::new(right),
+                span: self.span_from(start),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    /// Parse unary expressions (!, -, &, &mut)
+    fn parse_unary(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current_start();
+        if self.match_tokens(&[TokenType::Not, TokenType::Minus, TokenType::Ampersand, TokenType::Multiply]) {
+            let operator = self.previous().token_type.clone();
+
+            // Handle &mut case
+            if operator == TokenType::Ampersand && self.check(&TokenType::Mut) {
+                self.advance(); // consume 'mut'
+                let right = self.parse_unary()?;
+                return Ok(Expression::Unary(UnaryExpression {
+                    operator: UnaryOperator::MutableReference,
+                    operand: Box::new(right),
+                    span: self.span_from(start),
+                }));
+            }
+
+            let right = self.parse_unary()?;
+            return Ok(Expression::Unary(UnaryExpression {
+                operator: match operator {
+                    TokenType::Not => UnaryOperator::Not,
+                    TokenType::Minus => UnaryOperator::Negate,
+                    TokenType::Ampersand => UnaryOperator::Reference,
+                    TokenType::Multiply => UnaryOperator::Dereference,
+                    _ => unreachable!(),
+                },
+                operand: Box::new(right),
+                span: self.span_from(start),
+            }));
+        }
+
+        self.parse_primary()
+    }
+
+    /// Parse primary expressions (literals, identifiers, parentheses)
+    fn parse_primary(&mut self) -> Result<Expression, ParseError> {
+        let start = self.current_start();
+        let mut expr = match &self.peek().token_type {
+            TokenType::True => {
+                self.advance();
+                Expression::Literal(Literal::Boolean(true))
+            }
+            TokenType::False => {
+                self.advance();
+                Expression::Literal(Literal::Boolean(false))
+            }
+            TokenType::Null => {
+                self.advance();
+                Expression::Literal(Literal::Null)
+            }
+            TokenType::IntegerLiteral(Some(n)) => {
+                let n = *n;
+                self.advance();
+                Expression::Literal(Literal::Integer(n))
+            }
+            TokenType::FloatLiteral(Some(f)) => {
+                let f = *f;
+                self.advance();
+                Expression::Literal(Literal::Float(f))
+            }
+            TokenType::StringLiteral(s) => {
+                let s = s.clone();
+                self.advance();
+                Expression::Literal(Literal::String(s))
+            }
+            TokenType::InterpolatedString(parts) => {
+                let parts = parts.clone();
+                self.advance();
+                self.desugar_interpolation(parts, self.span_from(start))?
+            }
+            TokenType::CharLiteral(Some(c)) => {
+                let c = *c;
+                self.advance();
+                Expression::Literal(Literal::Char(c))
+            }
+
+            // Tensor Literal (Expert recommendation: Priority 3)
+            TokenType::TensorLiteral => {
+                self.advance(); // consume 'tensor'
+                self.parse_tensor_literal()?
+            }
+            TokenType::SelfKeyword => {
+                self.advance();
+                Expression::Identifier("self".to_string())
+            }
+            // `assert(condition, message)` is a built-in call despite the keyword
+            TokenType::Assert if self.peek_next_is(&TokenType::LeftParen) => {
+                self.advance();
+                Expression::Identifier("assert".to_string())
+            }
+            // `query` is only a keyword before a goal, so variables may keep it
+            TokenType::Identifier(word) if word == "query" && self.peek_next_is(&TokenType::Identifier(String::new())) => {
+                self.advance();
+                let goal = self.parse_logic_term()?;
+                Expression::Query(Box::new(QueryStatement {
+                    query_type: QueryType::Solve,
+                    goals: vec![goal],
+                    handler: None,
+                    span: self.span_from(start),
+                }))
+            }
+            TokenType::Identifier(name) => {
+                let name = name.clone();
+                self.advance();
+
+                // Check if this is an enum expression (EnumName::VariantName)
+                if self.check(&TokenType::DoubleColon) {
+                    self.advance(); // consume '::'
+                    let variant_name = self.consume_identifier("Expected variant name")?;
+
+                    // Check if variant has fields (tuple-like)
+                    let fields = if self.check(&TokenType::LeftParen) {
+                        self.advance(); // consume '('
+                        let mut field_exprs = Vec::new();
+
+                        if !self.check(&TokenType::RightParen) {
+                            loop {
+                                field_exprs.push(self.parse_expression()?);
+                                if !self.match_token(&TokenType::Comma) {
+                                    break;
+                                }
+                            }
+                        }
+
+                        self.consume(
+                            &TokenType::RightParen,
+                            "Expected ')' after enum variant fields",
+                        )?;
+                        Some(field_exprs)
+                    } else {
+                        None
+                    };
+
+                    Expression::Enum(EnumExpression {
+                        enum_name: name,
+                        variant_name,
+                        fields,
+                        span: self.span_from(start),
+                    })
+                }
+                // Check if this is a struct literal
+                // Only parse as struct literal if we see field_name: pattern
+                else if self.check(&TokenType::LeftBrace) && self.is_struct_literal() {
+                    self.advance(); // consume '{'
+
+                    let mut fields = Vec::new();
+                    while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+                        let field_name = self.consume_identifier("Expected field name")?;
+                        self.consume(&TokenType::Colon, "Expected ':' after field name")?;
+                        let field_value = self.parse_expression()?;
+
+                        fields.push((field_name, field_value));
+
+                        if !self.match_token(&TokenType::Comma) {
+                            break;
+                        }
+                    }
+
+                    self.consume(&TokenType::RightBrace, "Expected '}' after struct fields")?;
+                    Expression::Struct(StructExpression {
+                        name,
+                        fields,
+                        span: self.span_from(start),
+                    })
+                } else {
+                    Expression::Identifier(name)
+                }
+            }
+            TokenType::LeftParen => {
+                self.advance();
+                let inner_expr = self.parse_expression()?;
+                if self.match_token(&TokenType::Comma) {
+                    // Tuple literal: (a, b, c) or (a,) for a single element
+                    let mut elements = vec![inner_expr];
+                    while !self.check(&TokenType::RightParen) {
+                        elements.push(self.parse_expression()?);
+                        if !self.match_token(&TokenType::Comma) {
+                            break;
+                        }
+                    }
+                    self.consume(&TokenType::RightParen, "Expected ')' after tuple elements")?;
+                    Expression::Tuple(TupleExpression {
+                        elements,
+                        span: self.span_from(start),
+                    })
+                } else {
+                    self.consume(&TokenType::RightParen, "Expected ')' after expression")?;
+                    inner_expr
+                }
+            }
+            TokenType::LeftBracket => {
+                // Array literal: [a, b, c] or [value; count]
+                self.advance();
+                let mut elements = Vec::new();
+                let mut count = None;
+
+                if !self.check(&TokenType::RightBracket) {
+                    elements.push(self.parse_expression()?);
+                    if self.match_token(&TokenType::Semicolon) {
+                        count = Some(self.parse_expression()?);
+                    } else {
+                        while self.match_token(&TokenType::Comma) {
+                            elements.push(self.parse_expression()?);
+                        }
+                    }
+                }
+
+                self.consume(
+                    &TokenType::RightBracket,
+                    "Expected ']' after array elements",
+                )?;
+                match count {
+                    Some(count) => Expression::ArrayRepeat(ArrayRepeatExpression {
+                        value: Box::new(elements.remove(0)),
+                        count: Box::new(count),
+                        span: self.span_from(start),
+                    }),
+                    None => Expression::Array(ArrayExpression {
+                        elements,
+                        span: self.span_from(start),
+                    }),
+                }
+            }
+            TokenType::LeftBrace => {
+                // Map literal: { key: value, ... }
+                self.advance();
+                let mut entries = Vec::new();
+                while self.match_token(&TokenType::Newline) {}
+                while !self.check(&TokenType::RightBrace) {
+                    let key = self.parse_expression()?;
+                    self.consume(&TokenType::Colon, "Expected ':' after map key")?;
+                    entries.push((key, self.parse_expression()?));
+                    while self.match_token(&TokenType::Newline) {}
+                    if !self.match_token(&TokenType::Comma) {
+                        break;
+                    }
+                    while self.match_token(&TokenType::Newline) {}
+                }
+                while self.match_token(&TokenType::Newline) {}
+                self.consume(&TokenType::RightBrace, "Expected '}' after map entries")?;
+                Expression::Map(MapExpression {
+                    entries,
+                    span: self.span_from(start),
+                })
+            }
+            TokenType::Hash => {
+                // Set literal: #{a, b, c}
+                self.advance();
+                self.consume(&TokenType::LeftBrace, "Expected '{' after '#' of a set literal")?;
+                let mut elements = Vec::new();
+                while self.match_token(&TokenType::Newline) {}
+                while !self.check(&TokenType::RightBrace) {
+                    elements.push(self.parse_expression()?);
+                    while self.match_token(&TokenType::Newline) {}
+                    if !self.match_token(&TokenType::Comma) {
+                        break;
+                    }
+                    while self.match_token(&TokenType::Newline) {}
+                }
+                while self.match_token(&TokenType::Newline) {}
+                self.consume(&TokenType::RightBrace, "Expected '}' after set elements")?;
+                Expression::Set(SetExpression {
+                    elements,
+                    span: self.span_from(start),
+                })
+            }
+            TokenType::If => Expression::If(Box::new(self.parse_if()?)),
+            TokenType::Match => {
+                // Match expression (Expert recommendation: Priority 1 - Complete match support)
+                self.advance(); // consume 'match'
+
+                let expression = self.parse_expression()?;
+
+                self.consume(&TokenType::LeftBrace, "Expected '{' after match expression")?;
+
+                let mut arms = Vec::new();
+
+                while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+                    if self.match_token(&TokenType::Newline) {
+                        continue;
+                    }
+
+                    let arm = self.parse_match_arm()?;
+                    arms.push(arm);
+                }
+
+                self.consume(&TokenType::RightBrace, "Expected '}' after match arms")?;
+
+                Expression::Match(Box::new(MatchStatement {
+                    expression,
+                    arms,
+                    span: self.span_from(start),
+                }))
+            }
+            _ => {
+                return Err(ParseError::UnexpectedToken {
+                    expected: "expression".to_string(),
+                    found: self.peek().clone(),
+                })
+            }
+        };
+
+        // Handle postfix operations (function calls, field access, indexing)
+        loop {
+            match &self.peek().token_type {
+                TokenType::LeftParen => {
+                    // Function call
+                    self.advance();
+                    let mut arguments = Vec::new();
+
+                    if !self.check(&TokenType::RightParen) {
+                        loop {
+                            arguments.push(self.parse_expression()?);
+                            if !self.match_token(&TokenType::Comma) {
+                                break;
+                            }
+                        }
+                    }
+
+                    self.consume(
+                        &TokenType::RightParen,
+                        "Expected ')' after function arguments",
+                    )?;
+                    expr = Expression::Call(CallExpression {
+                        callee: Box::new(expr),
+                        arguments,
+                        span: self.span_from(start),
+                    });
+                }
+                TokenType::Dot => {
+                    // Field access, or tuple element access (t.0)
+                    self.advance();
+                    for field in self.parse_field_names()? {
+                        expr = Expression::FieldAccess(FieldAccessExpression {
+                            object: Box::new(expr),
+                            field,
+                            span: self.span_from(start),
+                        });
+                    }
+                }
+                TokenType::LeftBracket => {
+                    // Index access
+                    self.advance();
+                    let index = self.parse_expression()?;
+                    self.consume(&TokenType::RightBracket, "Expected ']' after index")?;
+                    expr = Expression::Index(IndexExpression {
+                        object: Box::new(expr),
+                        index: Box::new(index),
+                        span: self.span_from(start),
+                    });
+                }
+                _ => break,
+            }
+        }
+
+        Ok(expr)
+    }
+
+    // Helper methods for parsing
+
+    /// The `,` or `;` after a field, which the last field may leave out
+    fn consume_field_separator(&mut self) -> Result<(), ParseError> {
+        let closes = self.tokens[self.current..]
+            .iter()
+            .find(|token| token.token_type != TokenType::Newline)
+            .is_some_and(|token| token.token_type == TokenType::RightBrace);
+        if closes || self.match_tokens(&[TokenType::Comma, TokenType::Semicolon]) {
+            return Ok(());
+        }
+        self.consume(&TokenType::Comma, "Expected ',' or ';' after field")?;
+        Ok(())
+    }
+
+    fn match_token(&mut self, token_type: &TokenType) -> bool {
+        if self.check(token_type) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn match_tokens(&mut self, token_types: &[TokenType]) -> bool {
+        for token_type in token_types {
+            if self.check(token_type) {
+                self.advance();
+                return true;
+            }
+        }
+        false
+    }
+
+    fn check(&self, token_type: &TokenType) -> bool {
+        if self.is_at_end() {
+            false
+        } else {
+            std::mem::discriminant(&self.peek().token_type) == std::mem::discriminant(token_type)
+        }
+    }
+
+    fn advance(&mut self) -> &Token {
+        if !self.is_at_end() {
+            self.current += 1;
+        }
+        self.previous()
+    }
+
+    fn is_at_end(&self) -> bool {
+        matches!(self.peek().token_type, TokenType::Eof)
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.current]
+    }
+
+    /// Whether the token after the next one is a `token_type`
+    fn peek_next_is(&self, token_type: &TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| std::mem::discriminant(&token.token_type) == std::mem::discriminant(token_type))
+    }
+
+    fn previous(&self) -> &Token {
+        &self.tokens[self.current - 1]
+    }
+
+    /// Byte offset where the next node starts
+    fn current_start(&self) -> usize {
+        self.peek().span.start
+    }
+
+    /// Span from `start` to the end of the last consumed token
+    fn span_from(&self, start: usize) -> Span {
+        let end = if self.current > 0 { self.previous().span.end } else { start };
+        Span::new(start, end.max(start))
+    }
+
+    fn consume(&mut self, token_type: &TokenType, message: &str) -> Result<&Token, ParseError> {
+        if self.check(token_type) {
+            Ok(self.advance())
+        } else {
+            Err(ParseError::UnexpectedToken {
+                expected: message.to_string(),
+                found: self.peek().clone(),
+            })
+        }
+    }
+
+    /// `"a {x} b {y}"` becomes `format("a {} b {}", x, y)`; literal braces in the
+    /// text are escaped again so the template means the same thing
+    fn desugar_interpolation(
+        &mut self,
+        parts: Vec<InterpolationPart>,
+        span: Span,
+    ) -> Result<Expression, ParseError> {
+        let mut template = String::new();
+        let mut arguments = Vec::new();
+        for part in parts {
+            match part {
+                InterpolationPart::Text(text) => {
+                    template.push_str(&text.replace('{', "{{").replace('}', "}}"));
+                }
+                InterpolationPart::Expression(tokens) => {
+                    let mut parser = Parser::new(tokens);
+                    let expression = parser.parse_expression()?;
+                    if !parser.is_at_end() {
+                        return Err(ParseError::UnexpectedToken {
+                            expected: "'}' after interpolated expression".to_string(),
+                            found: parser.peek().clone(),
+                        });
+                    }
+                    template.push_str("{}");
+                    arguments.push(expression);
+                }
+            }
+        }
+
+        arguments.insert(0, Expression::Literal(Literal::String(template)));
+        Ok(Expression::Call(CallExpression {
+            callee: Box::new(Expression::Identifier("format".to_string())),
+            arguments,
+            span,
+        }))
+    }
+
+    /// Parse the field name after a '.', where tuple elements are numbered.
+    /// `t.0.1` lexes its indices as the float `0.1`, so that yields two names.
+    fn parse_field_names(&mut self) -> Result<Vec<String>, ParseError> {
+        match self.peek().token_type {
+            TokenType::IntegerLiteral(Some(index)) if index >= 0 => {
+                self.advance();
+                Ok(vec![index.to_string()])
+            }
+            TokenType::FloatLiteral(Some(value)) if value >= 0.0 => {
+                let span = self.advance().span;
+                let whole = value.trunc().to_string();
+                let decimals = (span.end - span.start).saturating_sub(whole.len() + 1);
+                let text = format!("{:.*}", decimals, value);
+                Ok(text.split('.').map(str::to_string).collect())
+            }
+            _ => Ok(vec![self.consume_identifier("Expected field name after '.'")?]),
+        }
+    }
+
+    fn consume_identifier(&mut self, message: &str) -> Result<String, ParseError> {
+        match &self.peek().token_type {
+            TokenType::Identifier(name) => {
+                let name = name.clone();
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(ParseError::UnexpectedToken {
+                expected: message.to_string(),
+                found: self.peek().clone(),
+            }),
+        }
+    }
+
+    // Placeholder implementations for missing items
+    fn parse_enum(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Enum, "Expected 'enum'")?;
+        let name = self.consume_identifier("Expected enum name")?;
+
+        self.consume(&TokenType::LeftBrace, "Expected '{' after enum name")?;
+
+        let mut variants = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            let variant_name = self.consume_identifier("Expected variant name")?;
+
+            let fields = if self.match_token(&TokenType::LeftParen) {
+                let mut variant_fields = Vec::new();
+                if !self.check(&TokenType::RightParen) {
+                    loop {
+                        let field_type = self.parse_type()?;
+                        variant_fields.push(field_type);
+
+                        if !self.match_token(&TokenType::Comma) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(&TokenType::RightParen, "Expected ')' after variant fields")?;
+                Some(variant_fields)
+            } else {
+                None
+            };
+
+            variants.push(EnumVariant {
+                name: variant_name,
+                fields,
+            });
+
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
+        }
+
+        self.consume(&TokenType::RightBrace, "Expected '}' after enum variants")?;
+
+        Ok(Item::Enum(EnumDecl {
+            name,
+            visibility: Visibility::Private,
+            variants,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn parse_class(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Class, "Expected 'class'")?;
+        let name = self.consume_identifier("Expected class name")?;
+
+        self.consume(&TokenType::LeftBrace, "Expected '{' after class name")?;
+
+        let mut fields = Vec::new();
+        let mut methods = Vec::new();
+
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            if self.check(&TokenType::Fn) {
+                // Parse method
+                if let Item::Function(func) = self.parse_function()? {
+                    methods.push(func);
+                }
+            } else {
+                // Parse field
+                let visibility = self.parse_visibility();
+                let field_name = self.consume_identifier("Expected field name")?;
+                self.consume(&TokenType::Colon, "Expected ':' after field name")?;
+                let field_type = self.parse_type()?;
+                self.consume_field_separator()?;
+
+                fields.push(StructField {
+                    name: field_name,
+                    field_type,
+                    visibility,
+                });
+            }
+        }
+
+        self.consume(&TokenType::RightBrace, "Expected '}' after class body")?;
+
+        Ok(Item::Class(ClassDecl {
+            name,
+            visibility: Visibility::Private,
+            fields,
+            methods,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn parse_interface(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Interface, "Expected 'interface'")?;
+        let name = self.consume_identifier("Expected interface name")?;
+
+        self.consume(&TokenType::LeftBrace, "Expected '{' after interface name")?;
+
+        let mut methods = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            self.consume(&TokenType::Fn, "Expected 'fn' in interface")?;
+            let method_name = self.consume_identifier("Expected method name")?;
+
+            self.consume(&TokenType::LeftParen, "Expected '(' after method name")?;
+
+            let mut parameters = Vec::new();
+            if !self.check(&TokenType::RightParen) {
+                loop {
+                    let param_name = self.consume_identifier("Expected parameter name")?;
+                    self.consume(&TokenType::Colon, "Expected ':' after parameter name")?;
+                    let param_type = self.parse_type()?;
+
+                    parameters.push(Parameter::Regular {
+                        name: param_name,
+                        param_type,
+                    });
+
+                    if !self.match_token(&TokenType::Comma) {
+                        break;
+                    }
+                }
+            }
+
+            self.consume(&TokenType::RightParen, "Expected ')' after parameters")?;
+
+            let return_type = if self.match_token(&TokenType::Arrow) {
+                Some(self.parse_type()?)
+            } else {
+                None
+            };
+
+            self.consume(&TokenType::Semicolon, "Expected ';' after method signature")?;
+
+            methods.push(FunctionSignature {
+                name: method_name,
+                parameters,
+                return_type,
+            });
+        }
+
+        self.consume(
+            &TokenType::RightBrace,
+            "Expected '}' after interface methods",
+        )?;
+
+        Ok(Item::Interface(InterfaceDecl {
+            name,
+            methods,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn parse_fact(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Fact, "Expected 'fact'")?;
+        let term = self.parse_logic_term()?;
+        self.consume(&TokenType::Semicolon, "Expected ';' after fact")?;
+
+        Ok(Item::Fact(FactDecl {
+            term,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse `concept name;` or `concept name: parent, other;`
+    fn parse_concept(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.advance();
+        let name = self.consume_identifier("Expected concept name")?;
+        let mut parents = Vec::new();
+        if self.match_token(&TokenType::Colon) {
+            loop {
+                parents.push(self.consume_identifier("Expected parent concept")?);
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+        self.consume(&TokenType::Semicolon, "Expected ';' after concept")?;
+
+        Ok(Item::Concept(ConceptDecl {
+            name,
+            parents,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse `constraint no goal, other;`
+    fn parse_constraint(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.advance();
+        if self.consume_identifier("Expected 'no' after 'constraint'")? != "no" {
+            return Err(ParseError::UnexpectedToken {
+                expected: "'no' after 'constraint'".to_string(),
+                found: self.previous().clone(),
+            });
+        }
+        let mut goals = vec![self.parse_logic_term()?];
+        while self.match_token(&TokenType::Comma) {
+            goals.push(self.parse_logic_term()?);
+        }
+        self.consume(&TokenType::Semicolon, "Expected ';' after constraint")?;
+
+        Ok(Item::Constraint(ConstraintDecl {
+            goals,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn parse_module(&mut self) -> Result<Item, ParseError> {
+        self.consume(&TokenType::Module, "Expected 'module'")?;
+        let name = self.consume_identifier("Expected module name")?;
+
+        self.consume(&TokenType::LeftBrace, "Expected '{' after module name")?;
+
+        let mut items = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            if self.match_token(&TokenType::Newline) {
+                continue;
+            }
+            let item = self.parse_item()?;
+            items.push(item);
+        }
+
+        self.consume(&TokenType::RightBrace, "Expected '}' after module body")?;
+
+        Ok(Item::Module(ModuleDecl { name, items }))
+    }
+
+    fn parse_using(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Using, "Expected 'using'")?;
+
+        let mut path = Vec::new();
+        path.push(self.consume_identifier("Expected module path")?);
+
+        while self.match_token(&TokenType::DoubleColon) {
+            path.push(self.consume_identifier("Expected module path component")?);
+        }
+
+        let alias = if self.check(&TokenType::Identifier("as".to_string())) {
+            self.advance();
+            Some(self.consume_identifier("Expected alias name")?)
+        } else {
+            None
+        };
+
+        self.consume(
+            &TokenType::Semicolon,
+            "Expected ';' after using declaration",
+        )?;
+
+        Ok(Item::Using(UsingDecl { path, alias, span: self.span_from(start) }))
+    }
+
+    /// Parse a trait declaration (Expert recommendation: Priority 1)
+    fn parse_trait(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Trait, "Expected 'trait'")?;
+        let name = self.consume_identifier("Expected trait name")?;
+
+        // Parse optional generic parameters
+        let generic_params = if self.check(&TokenType::Less) {
+            Some(self.parse_generic_params()?)
+        } else {
+            None
+        };
+
+        self.consume(&TokenType::LeftBrace, "Expected '{' after trait name")?;
+
+        let mut methods = Vec::new();
+        let mut associated_types = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            // Skip newlines
+            while self.check(&TokenType::Newline) {
+                self.advance();
+            }
+
+            // Check again after skipping newlines
+            if self.check(&TokenType::RightBrace) || self.is_at_end() {
+                break;
+            }
+
+            // Associated type declaration: type Item;
+            if self.match_token(&TokenType::Type) {
+                associated_types.push(self.consume_identifier("Expected associated type name")?);
+                self.consume(&TokenType::Semicolon, "Expected ';' after associated type")?;
+                continue;
+            }
+
+            // Expect 'fn' keyword for trait methods
+            let method_start = self.current_start();
+            self.consume(&TokenType::Fn, "Expected 'fn' for trait method")?;
+            let method_name = self.consume_identifier("Expected method name")?;
+
+            self.consume(&TokenType::LeftParen, "Expected '(' after method name")?;
+            let parameters = self.parse_parameters()?;
+            self.consume(&TokenType::RightParen, "Expected ')' after parameters")?;
+
+            let return_type = if self.match_token(&TokenType::Arrow) {
+                Some(self.parse_type()?)
+            } else {
+                None
+            };
+
+            // Check for default implementation
+            let body = if self.check(&TokenType::LeftBrace) {
+                Some(self.parse_block()?)
+            } else {
+                self.consume(&TokenType::Semicolon, "Expected ';' after method signature")?;
+                None
+            };
+
+            methods.push(TraitMethod {
+                name: method_name,
+                parameters,
+                return_type,
+                body,
+                span: self.span_from(method_start),
+            });
+        }
+
+        self.consume(&TokenType::RightBrace, "Expected '}' after trait methods")?;
+
+        Ok(Item::Trait(TraitDecl {
+            name,
+            generic_params,
+            associated_types,
+            methods,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse an impl declaration (Expert recommendation: Priority 1)
+    fn parse_impl(&mut self) -> Result<Item, ParseError> {
+        let start = self.current_start();
+        self.consume(&TokenType::Impl, "Expected 'impl'")?;
+
+        // Parse optional generic parameters for impl
+        let generic_params = if self.check(&TokenType::Less) {
+            Some(self.parse_generic_params()?)
+        } else {
+            None
+        };
+
+        // Parse either "impl Type" or "impl Trait for Type"
+        // First, parse the type/trait name (which might be generic like Container<T>)
+        let first_type = self.parse_type()?;
+        let first_name = match &first_type {
+            Type::Named(path) => path.segments.last().unwrap().clone(),
+            Type::Generic(path, _) => path.segments.last().unwrap().clone(),
+            _ => {
+                return Err(ParseError::UnexpectedToken {
+                    expected: "type or trait name".to_string(),
+                    found: self.peek().clone(),
+                })
+            }
+        };
+
+        let (trait_name, type_name) = if self.match_token(&TokenType::For) {
+            // impl Trait for Type
+            let type_name = self.consume_identifier("Expected type name after 'for'")?;
+            (Some(first_name), type_name)
+        } else {
+            // impl Type
+            (None, first_name)
+        };
+
+        self.consume(&TokenType::LeftBrace, "Expected '{' after impl declaration")?;
+
+        let mut methods = Vec::new();
+        let mut associated_types = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            // Skip newlines
+            while self.check(&TokenType::Newline) {
+                self.advance();
+            }
+
+            // Check again after skipping newlines
+            if self.check(&TokenType::RightBrace) || self.is_at_end() {
+                break;
+            }
+
+            // Associated type definition: type Item = int;
+            if self.match_token(&TokenType::Type) {
+                let type_name = self.consume_identifier("Expected associated type name")?;
+                self.consume(&TokenType::Assign, "Expected '=' after associated type name")?;
+                associated_types.push((type_name, self.parse_type()?));
+                self.consume(&TokenType::Semicolon, "Expected ';' after associated type")?;
+                continue;
+            }
+
+            if let Item::Function(func) = self.parse_function()? {
+                methods.push(func);
+            }
+        }
+
+        self.consume(&TokenType::RightBrace, "Expected '}' after impl methods")?;
+
+        Ok(Item::Impl(ImplDecl {
+            trait_name,
+            type_name,
+            generic_params,
+            associated_types,
+            methods,
+            span: self.span_from(start),
+        }))
+    }
+
+    /// Parse tensor literal: tensor [[1.0, 2.0], [3.0, 4.0]]
+    /// (Expert recommendation: Priority 3)
+    fn parse_tensor_literal(&mut self) -> Result<Expression, ParseError> {
+        // Expect opening bracket
+        self.consume(&TokenType::LeftBracket, "Expected '[' after 'tensor'")?;
+
+        let mut rows = Vec::new();
+
+        // Parse rows
+        if !self.check(&TokenType::RightBracket) {
+            loop {
+                // Parse a row: [1.0, 2.0, 3.0]
+                self.consume(&TokenType::LeftBracket, "Expected '[' for tensor row")?;
+
+                let mut row = Vec::new();
+
+                // Parse elements in the row
+                if !self.check(&TokenType::RightBracket) {
+                    loop {
+                        // Parse a number (float or int)
+                        let value = match &self.peek().token_type {
+                            TokenType::FloatLiteral(Some(f)) => {
+                                let f = *f;
+                                self.advance();
+                                f
+                            }
+                            TokenType::IntegerLiteral(Some(i)) => {
+                                let i = *i;
+                                self.advance();
+                                i as f64
+                            }
+                            _ => {
+                                return Err(ParseError::InvalidSyntax {
+                                    message: "Expected number in tensor literal".to_string(),
+                                    span: self.peek().span,
+                                })
+                            }
+                        };
+
+                        row.push(value);
+
+                        if !self.match_token(&TokenType::Comma) {
+                            break;
+                        }
+                    }
+                }
+
+                self.consume(&TokenType::RightBracket, "Expected ']' after tensor row")?;
+                rows.push(row);
+
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(
+            &TokenType::RightBracket,
+            "Expected ']' after tensor literal",
+        )?;
+
+        Ok(Expression::Literal(Literal::Tensor(rows)))
+    }
+
+    /// Parse a semantic block for Natural Language Understanding
+    /// تحليل كتلة دلالية لفهم اللغة الطبيعية
+    fn parse_semantic_block(&mut self) -> Result<SemanticBlock, ParseError> {
+        self.consume(&TokenType::Semantic, "Expected 'semantic'")?;
+        self.consume(&TokenType::LeftBrace, "Expected '{' after 'semantic'")?;
+
+        let mut sentences = Vec::new();
+
+        // Parse natural language sentences
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            if self.match_token(&TokenType::Newline) {
+                continue;
+            }
+
+            // Expect string literals containing natural language
+            if matches!(self.peek().token_type, TokenType::StringLiteral(_)) {
+                let sentence_token = self.advance();
+                if let TokenType::StringLiteral(content) = &sentence_token.token_type {
+                    sentences.push(content.clone());
+                }
+            } else {
+                return Err(ParseError::UnexpectedToken {
+                    expected: "string literal with natural language sentence".to_string(),
+                    found: self.peek().clone(),
+                });
+            }
+
+            // Optional semicolon
+            self.match_token(&TokenType::Semicolon);
+        }
+
+        self.consume(&TokenType::RightBrace, "Expected '}' after semantic block")?;
+
+        let semantic_block = SemanticBlock::new(sentences);
+        Ok(semantic_block)
+    }
+}
+
+/// Parser error types
+#[derive(Debug, thiserror::Error)]
+pub enum ParseError {
+    #[error("Unexpected token: expected {expected}, found {found}")]
+    UnexpectedToken { expected: String, found: Token },
+
+    #[error("Unexpected end of input")]
+    UnexpectedEof,
+
+    #[error("Invalid syntax: {message}")]
+    InvalidSyntax { message: String, span: Span },
+
+    #[error(transparent)]
+    Cancelled(#[from] Cancelled),
+}
+
+impl ParseError {
+    /// Source location of the error, if known
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ParseError::UnexpectedToken { found, .. } => Some(found.span),
+            ParseError::InvalidSyntax { span, .. } => Some(*span),
+            ParseError::UnexpectedEof | ParseError::Cancelled(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    #[test]
+    fn test_parse_simple_function() {
+        let source = "fn main() { return 42; }";
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+
+        assert_eq!(ast.items.len(), 1);
+        assert!(matches!(ast.items[0], Item::Function(_)));
+    }
+
+    #[test]
+    fn test_node_spans() {
+        let source = "fn main() {\n    let x = 1 + 2;\n}";
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens);
+        let ast = parser.parse().unwrap();
+
+        let Item::Function(func) = &ast.items[0] else {
+            panic!("expected function");
+        };
+        assert_eq!(&source[func.span.start..func.span.end], source);
+
+        let Statement::Let(let_stmt) = &func.body.statements[0] else {
+            panic!("expected let statement");
+        };
+        assert_eq!(&source[let_stmt.span.start..let_stmt.span.end], "let x = 1 + 2;");
+        assert!(!let_stmt.is_mutable);
+        let init = let_stmt.initializer.as_ref().unwrap().span().unwrap();
+        assert_eq!(&source[init.start..init.end], "1 + 2");
+    }
+
+    #[test]
+    fn test_destructuring_let() {
+        let source = "fn main() { let mut (q, (r, _)) = f(); }";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let Item::Function(func) = &ast.items[0] else {
+            panic!("expected function");
+        };
+        let Statement::Let(let_stmt) = &func.body.statements[0] else {
+            panic!("expected let statement");
+        };
+        assert!(let_stmt.is_mutable && let_stmt.name.is_empty());
+        let pattern = let_stmt.pattern.as_ref().unwrap();
+        assert_eq!(
+            *pattern,
+            Pattern::Tuple(vec![
+                Pattern::Identifier("q".to_string()),
+                Pattern::Tuple(vec![Pattern::Identifier("r".to_string()), Pattern::Wildcard]),
+            ])
+        );
+        assert_eq!(pattern.bindings(), vec!["q", "r"]);
+    }
+
+    #[test]
+    fn test_assignment_targets() {
+        let source = "fn main() { p.x = 5; arr[0] += 1; *r = 2; count -= 1; }";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let Item::Function(func) = &ast.items[0] else {
+            panic!("expected function");
+        };
+        let targets: Vec<&Expression> = func
+            .body
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::Expression(ExpressionStatement { expression: Expression::Binary(assignment), .. }) => {
+                    assignment.left.as_ref()
+                }
+                other => panic!("expected assignment, found {:?}", other),
+            })
+            .collect();
+        assert!(matches!(targets[0], Expression::FieldAccess(access) if access.field == "x"));
+        assert!(matches!(targets[1], Expression::Index(_)));
+        assert!(matches!(targets[2], Expression::Unary(unary) if unary.operator == UnaryOperator::Dereference));
+        assert!(matches!(targets[3], Expression::Identifier(name) if name == "count"));
+
+        let invalid = Parser::new(Lexer::new("fn main() { f() = 1; }").tokenize().unwrap()).parse();
+        assert!(matches!(invalid, Err(ParseError::InvalidSyntax { .. })));
+    }
+
+    #[test]
+    fn test_else_if_chain() {
+        let source = "fn sign(x: int) -> int { if x < 0 { return -1; } else if x == 0 { return 0; } else { return 1; } }";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let Item::Function(func) = &ast.items[0] else {
+            panic!("expected function");
+        };
+        let Statement::If(if_stmt) = &func.body.statements[0] else {
+            panic!("expected if statement");
+        };
+
+        let else_block = if_stmt.else_block.as_ref().unwrap();
+        let [Statement::If(else_if)] = else_block.statements.as_slice() else {
+            panic!("expected `else if` to nest an if statement");
+        };
+        assert_eq!(&source[else_block.span.start..else_block.span.end], "if x == 0 { return 0; } else { return 1; }");
+        assert_eq!(else_if.else_block.as_ref().unwrap().statements.len(), 1);
+    }
+
+    #[test]
+    fn test_no_reorder_attribute() {
+        let source = "#[no_reorder]\nrule admin_user(X) :- user(X), admin(X);";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let Item::Rule(rule) = &ast.items[0] else {
+            panic!("expected rule");
+        };
+        assert!(rule.no_reorder);
+
+        let tokens = Lexer::new("#[inline] rule a(X) :- b(X);").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_table_attribute() {
+        let tokens = Lexer::new("#[table]\nrelation ancestor(string, string);").tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let Item::Relation(relation) = &ast.items[0] else {
+            panic!("expected relation");
+        };
+        assert!(relation.tabled);
+        assert_eq!(relation.security, None);
+
+        let tokens = Lexer::new("#[table] rule a(X) :- b(X);").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_negation_and_cut_in_rule_bodies() {
+        let source = "rule single(X) :- person(X), not married(X), !;";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let Item::Rule(rule) = &ast.items[0] else {
+            panic!("expected rule");
+        };
+        let negated: Vec<bool> = rule.body.iter().map(|goal| goal.negated).collect();
+        assert_eq!(negated, [false, true, false]);
+        assert!(rule.body[2].is_cut());
+        assert_eq!(&source[rule.body[1].span.start..rule.body[1].span.end], "not married(X)");
+        assert_eq!(rule.to_string(), "single(X) :- person(X), not married(X), !.");
+
+        let tokens = Lexer::new("rule a(X) :- not(X);").tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let Item::Rule(rule) = &ast.items[0] else {
+            panic!("expected rule");
+        };
+        assert_eq!((rule.body[0].name.as_str(), rule.body[0].negated), ("not", false));
+    }
+
+    #[test]
+    fn test_comparisons_in_rule_bodies() {
+        let source = "rule next_age(X, Y) :- age(X, A), A >= 18, Y is (A + 1) * 2, not X = bob;";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let Item::Rule(rule) = &ast.items[0] else {
+            panic!("expected rule");
+        };
+        let builtins: Vec<bool> = rule.body.iter().map(LogicTerm::is_builtin).collect();
+        assert_eq!(builtins, [false, true, true, true]);
+        assert_eq!(&source[rule.body[2].span.start..rule.body[2].span.end], "Y is (A + 1) * 2");
+        assert_eq!(
+            rule.to_string(),
+            "next_age(X, Y) :- age(X, A), >=(A, 18), is(Y, *(+(A, 1), 2)), not =(X, bob)."
+        );
+
+        let tokens = Lexer::new("rule a(X) :- b(X), X + 1;").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_aggregates_take_goals() {
+        let source = "rule family(P, N, Kids) :- person(P), count(parent(P, C), N), findall(C, parent(P, C), Kids);";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let Item::Rule(rule) = &ast.items[0] else {
+            panic!("expected rule");
+        };
+        let aggregates: Vec<bool> = rule.body.iter().map(LogicTerm::is_aggregate).collect();
+        assert_eq!(aggregates, [false, true, true]);
+        assert!(matches!(&rule.body[1].args[0], LogicArg::Goal(goal) if goal.name == "parent"));
+        assert_eq!(
+            rule.to_string(),
+            "family(P, N, Kids) :- person(P), count(parent(P, C), N), findall(C, parent(P, C), Kids)."
+        );
+
+        // Without a goal argument `count` is an ordinary relation
+        let tokens = Lexer::new("rule a(X) :- count(X, 2);").tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let Item::Rule(rule) = &ast.items[0] else {
+            panic!("expected rule");
+        };
+        assert!(rule.body[0].calls_relation());
+    }
+
+    #[test]
+    fn test_compound_and_list_terms() {
+        let source = "fact route(home, [a, Point(1, 2), []]);
+rule first(X) :- route(R, [X | Rest]);";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let [Item::Fact(fact), Item::Rule(rule)] = ast.items.as_slice() else {
+            panic!("expected a fact and a rule");
+        };
+        let LogicArg::List { items, tail: None } = &fact.term.args[1] else {
+            panic!("expected a list, got {:?}", fact.term.args[1]);
+        };
+        assert!(matches!(&items[1], LogicArg::Compound(term) if term.name == "Point" && term.args.len() == 2));
+        assert!(matches!(&items[2], LogicArg::List { items, tail: None } if items.is_empty()));
+        assert_eq!(fact.to_string(), "route(home, [a, Point(1, 2), []]).");
+        assert_eq!(rule.to_string(), "first(X) :- route(R, [X | Rest]).");
+
+        let tokens = Lexer::new("fact route(home, [a | b | c]);").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_concept_declarations() {
+        let source = "concept animal;\nconcept bat: mammal, flyer;\nstruct Tag { concept: string, }";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let [Item::Concept(animal), Item::Concept(bat), Item::Struct(_)] = ast.items.as_slice() else {
+            panic!("{:?}", ast.items);
+        };
+        assert_eq!(animal.name, "animal");
+        assert!(animal.parents.is_empty());
+        assert_eq!(bat.parents, ["mammal", "flyer"]);
+
+        let tokens = Lexer::new("concept bat: ;").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_constraint_declarations() {
+        let source = "constraint no parent(X, X);\nconstraint no alive(X), dead(X);";
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let [Item::Constraint(reflexive), Item::Constraint(both)] = ast.items.as_slice() else {
+            panic!("{:?}", ast.items);
+        };
+        assert_eq!(reflexive.goals[0].name, "parent");
+        assert_eq!(reflexive.goals[0].args, [LogicArg::Variable("X".to_string()), LogicArg::Variable("X".to_string())]);
+        let names: Vec<&str> = both.goals.iter().map(|goal| goal.name.as_str()).collect();
+        assert_eq!(names, ["alive", "dead"]);
+        assert_eq!(&source[reflexive.span.start..reflexive.span.end], "constraint no parent(X, X);");
+
+        let tokens = Lexer::new("constraint any parent(X, X);").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_test_attribute_marks_functions() {
+        let source = "#[test]\nfn adds() { assert_eq(1 + 1, 2); }\nfn helper() {}";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let [Item::Function(test), Item::Function(helper)] = ast.items.as_slice() else {
+            panic!("expected two functions");
+        };
+        assert!(test.is_test && !helper.is_test);
+        assert_eq!(test.name, "adds");
+
+        let tokens = Lexer::new("#[test] rule a(X) :- b(X);").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_assert_call() {
+        let source = "fn check(x: int) { assert(x > 0, \"x must be positive\"); }";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let Item::Function(func) = &ast.items[0] else {
+            panic!("expected function");
+        };
+        let [Statement::Expression(ExpressionStatement { expression: Expression::Call(call), .. })] =
+            func.body.statements.as_slice()
+        else {
+            panic!("expected a call statement");
+        };
+        assert_eq!(*call.callee, Expression::Identifier("assert".to_string()));
+        assert_eq!(call.arguments.len(), 2);
+    }
+
+    #[test]
+    fn test_trailing_semicolon_is_recorded() {
+        let ast = Parser::new(Lexer::new("fn f() { g(); g() }").tokenize().unwrap()).parse().unwrap();
+        let Item::Function(func) = &ast.items[0] else {
+            panic!("expected function");
+        };
+        let ends: Vec<bool> = func.body.statements.iter().map(Statement::is_value).collect();
+        assert_eq!(ends, vec![false, true]);
+    }
+
+    #[test]
+    fn test_assert_and_retract_statements() {
+        let source = "fn move_to(room: string) { retract at(\"robot\", \"hall\"); assert at(\"robot\", room + \"!\"); }";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let Item::Function(func) = &ast.items[0] else {
+            panic!("expected function");
+        };
+        let [Statement::Retract(retract), Statement::Assert(assert)] = func.body.statements.as_slice() else {
+            panic!("expected a retract and an assert");
+        };
+        assert_eq!((retract.relation.as_str(), retract.args.len()), ("at", 2));
+        assert!(matches!(assert.args[1], Expression::Binary(_)));
+        assert_eq!(&source[assert.span.start..assert.span.end], "assert at(\"robot\", room + \"!\");");
+    }
+
+    #[test]
+    fn test_const_items_and_array_types() {
+        let source = "const SIZE: int = 2 * 4;\nfn sum(values: [int; SIZE], rest: [float]) {}";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let Item::Const(decl) = &ast.items[0] else {
+            panic!("expected constant");
+        };
+        assert_eq!(decl.name, "SIZE");
+        assert!(matches!(decl.value, Expression::Binary(_)));
+        assert_eq!(&source[decl.span.start..decl.span.end], "const SIZE: int = 2 * 4;");
+
+        let Item::Function(func) = &ast.items[1] else {
+            panic!("expected function");
+        };
+        let Parameter::Regular { param_type: Type::Array(_, Some(size)), .. } = &func.parameters[0] else {
+            panic!("expected a sized array parameter");
+        };
+        assert_eq!(**size, Expression::Identifier("SIZE".to_string()));
+        assert!(matches!(&func.parameters[1], Parameter::Regular { param_type: Type::Array(_, None), .. }));
+
+        let tokens = Lexer::new("const LIMIT = 3;").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_struct_fields_end_with_commas_or_semicolons() {
+        for source in ["struct P { x: int, y: int }", "struct P { x: int; y: int; }", "struct P { x: int, y: int, }"] {
+            let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+            let Item::Struct(decl) = &ast.items[0] else {
+                panic!("expected struct");
+            };
+            let names: Vec<&str> = decl.fields.iter().map(|field| field.name.as_str()).collect();
+            assert_eq!(names, ["x", "y"], "{}", source);
+        }
+
+        let tokens = Lexer::new("struct P { x: int y: int }").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_map_and_set_literals() {
+        let source = "fn main() { let ages = { \"ali\": 30, \"sara\": 25, }; let primes = #{2, 3, 5}; let none = {}; }";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let Item::Function(func) = &ast.items[0] else {
+            panic!("expected function");
+        };
+        let initializers: Vec<&Expression> = func
+            .body
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::Let(let_stmt) => let_stmt.initializer.as_ref().unwrap(),
+                other => panic!("expected let statement, found {:?}", other),
+            })
+            .collect();
+        let Expression::Map(ages) = initializers[0] else {
+            panic!("expected map literal");
+        };
+        assert_eq!(ages.entries.len(), 2);
+        assert_eq!(ages.entries[1].0, Expression::Literal(Literal::String("sara".to_string())));
+        assert_eq!(&source[ages.span.start..ages.span.end], "{ \"ali\": 30, \"sara\": 25, }");
+        assert!(matches!(initializers[1], Expression::Set(primes) if primes.elements.len() == 3));
+        assert!(matches!(initializers[2], Expression::Map(none) if none.entries.is_empty()));
+
+        let tokens = Lexer::new("fn main() { let m = { 1 2 }; }").tokenize().unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_query_expression() {
+        let source = "fn main() { let rows = query ancestor(X, \"ali\"); let query = 1; }";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let Item::Function(func) = &ast.items[0] else {
+            panic!("expected function");
+        };
+        let Statement::Let(rows) = &func.body.statements[0] else {
+            panic!("expected let statement");
+        };
+        let Some(Expression::Query(query)) = &rows.initializer else {
+            panic!("expected query expression, found {:?}", rows.initializer);
+        };
+        assert_eq!(query.query_type, QueryType::Solve);
+        assert_eq!(query.goals[0].to_string(), "ancestor(X, \"ali\")");
+        assert_eq!(&source[query.span.start..query.span.end], "query ancestor(X, \"ali\")");
+        // Before anything but a goal, `query` is a name like any other
+        assert!(matches!(&func.body.statements[1], Statement::Let(variable) if variable.name == "query"));
+    }
+
+    #[test]
+    fn test_ast_round_trips_through_json() {
+        let source = "struct Point { x: int, y: int }\n\
+                      fn main() -> int {\n    let p = Point { x: 1, y: 2 };\n    if p.x < p.y { return p.y; }\n    return 0;\n}";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let json = serde_json::to_string(&ast).unwrap();
+        assert_eq!(serde_json::from_str::<Program>(&json).unwrap(), ast);
+    }
+
+    #[test]
+    fn test_comments_attach_to_neighbouring_nodes() {
+        let source = "// License\n\n/// Adds one.\n/// Pure.\npub fn inc(n: int) -> int {\n    // Before the return\n    return n + 1; // after it\n}\n";
+        let ast = Parser::new(Lexer::new(source).tokenize_with_comments().unwrap()).parse().unwrap();
+        assert_eq!(ast.comments.len(), 5);
+
+        let Item::Function(func) = &ast.items[0] else {
+            panic!("expected function");
+        };
+        let leading: Vec<&str> = ast.leading_comments(func.span).map(|comment| comment.text.as_str()).collect();
+        assert_eq!(leading, ["// License", "/// Adds one.", "/// Pure."]);
+        assert_eq!(ast.doc_comment(func.span).as_deref(), Some("Adds one.\nPure."));
+
+        let Statement::Return(ret) = &func.body.statements[0] else {
+            panic!("expected return statement");
+        };
+        assert_eq!(ast.leading_comments(ret.span).next().unwrap().text, "// Before the return");
+        assert_eq!(ast.trailing_comment(ret.span).unwrap().text, "// after it");
+
+        // Without comment tokens the program carries none
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        assert!(ast.comments.is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+//! # Built-in Artistic Types
+//!
+//! Registers the artistic renderer and shape inference result types
+//! (`BasicShape`, `ShapeProperty`, `Pixel`, `RenderedImage`, `InferenceResult`)
+//! as ordinary AlBayan structs and enums, so programs can access their fields
+//! and match on their variants like user-defined types.
+//!
+//! The variants are listed by name: the Rust types live in the builtin
+//! libraries of `albayan_lib`, whose tests check the two lists agree.
+
+use super::symbol_table::{EnumVariantInfo, StructFieldInfo, SymbolTable, TypeInfo, TypeKind};
+use super::ResolvedType;
+
+/// Names of the built-in artistic types, in registration order
+pub const ARTISTIC_TYPE_NAMES: [&str; 5] = [
+    "BasicShape",
+    "ShapeProperty",
+    "Pixel",
+    "RenderedImage",
+    "InferenceResult",
+];
+
+/// Variants of `BasicShape`, in the order of `BasicShape::ALL`
+pub const BASIC_SHAPE_VARIANTS: [&str; 28] = [
+    "Cat", "Dog", "Human", "Lion", "Bird", "Fish", "Tree", "Flower",
+    "Grass", "Leaf", "Circle", "Square", "Triangle", "Rectangle", "Pentagon", "Hexagon",
+    "Paper", "Blackboard", "Pen", "Book", "Table", "Chair", "Mountain", "River",
+    "Cloud", "Sun", "Moon", "Star",
+];
+
+/// Variants of `ShapeProperty`, each with whether its payload is a number
+/// (otherwise it is a string)
+pub const SHAPE_PROPERTY_VARIANTS: [(&str, bool); 15] = [
+    ("Position", false),
+    ("Orientation", true),
+    ("Movement", false),
+    ("Color", false),
+    ("Brightness", true),
+    ("Transparency", true),
+    ("Texture", false),
+    ("Size", true),
+    ("Width", true),
+    ("Height", true),
+    ("Depth", true),
+    ("Expression", false),
+    ("Style", false),
+    ("Age", false),
+    ("Material", false),
+];
+
+fn field(name: &str, field_type: ResolvedType) -> StructFieldInfo {
+    StructFieldInfo {
+        name: name.to_string(),
+        field_type,
+    }
+}
+
+/// Register the built-in artistic types in the symbol table
+pub fn register_artistic_types(symbol_table: &mut SymbolTable) {
+    let basic_shape_variants = BASIC_SHAPE_VARIANTS
+        .iter()
+        .map(|shape| EnumVariantInfo {
+            name: shape.to_string(),
+            fields: None,
+        })
+        .collect();
+
+    let shape_property_variants = SHAPE_PROPERTY_VARIANTS
+        .iter()
+        .map(|&(name, numeric)| EnumVariantInfo {
+            name: name.to_string(),
+            fields: Some(vec![if numeric {
+                ResolvedType::Float
+            } else {
+                ResolvedType::String
+            }]),
+        })
+        .collect();
+
+    let pixel_fields = vec![
+        field("red", ResolvedType::Int),
+        field("green", ResolvedType::Int),
+        field("blue", ResolvedType::Int),
+        field("alpha", ResolvedType::Int),
+    ];
+
+    let image_fields = vec![
+        field("width", ResolvedType::Int),
+        field("height", ResolvedType::Int),
+        field(
+            "pixels",
+            ResolvedType::List(Box::new(ResolvedType::List(Box::new(ResolvedType::Struct(
+                "Pixel".to_string(),
+            ))))),
+        ),
+    ];
+
+    let inference_fields = vec![
+        field("predicted_shape", ResolvedType::Enum("BasicShape".to_string())),
+        field("confidence", ResolvedType::Float),
+        field("image", ResolvedType::Struct("RenderedImage".to_string())),
+    ];
+
+    let kinds = [
+        TypeKind::Enum(basic_shape_variants),
+        TypeKind::Enum(shape_property_variants),
+        TypeKind::Struct(pixel_fields),
+        TypeKind::Struct(image_fields),
+        TypeKind::Struct(inference_fields),
+    ];
+
+    for (name, kind) in ARTISTIC_TYPE_NAMES.iter().zip(kinds) {
+        symbol_table.add_type_info(
+            name,
+            TypeInfo {
+                name: name.to_string(),
+                kind,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_shape_variants_registered() {
+        let mut symbol_table = SymbolTable::new();
+        register_artistic_types(&mut symbol_table);
+
+        let info = symbol_table.lookup_type("BasicShape").unwrap();
+        let TypeKind::Enum(variants) = &info.kind else {
+            panic!("BasicShape should be an enum");
+        };
+        assert_eq!(variants.len(), BASIC_SHAPE_VARIANTS.len());
+        assert!(variants.iter().all(|variant| variant.fields.is_none()));
+    }
+
+    #[test]
+    fn test_shape_property_payload_types() {
+        let mut symbol_table = SymbolTable::new();
+        register_artistic_types(&mut symbol_table);
+
+        let info = symbol_table.lookup_type("ShapeProperty").unwrap();
+        let TypeKind::Enum(variants) = &info.kind else {
+            panic!("ShapeProperty should be an enum");
+        };
+        let payload = |name: &str| {
+            variants.iter().find(|v| v.name == name).and_then(|v| v.fields.clone())
+        };
+        assert_eq!(payload("Color"), Some(vec![ResolvedType::String]));
+        assert_eq!(payload("Size"), Some(vec![ResolvedType::Float]));
+        assert_eq!(variants.len(), 15);
+    }
+}
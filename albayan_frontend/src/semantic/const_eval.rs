@@ -0,0 +1,264 @@
+//! # Constant Evaluation
+//!
+//! Evaluates the values of `const` items at compile time. A constant
+//! expression is built from literals and other constants with the arithmetic,
+//! comparison and logical operators, `-` and `!`, and `+` on strings; integer
+//! overflow and division by zero are compile errors.
+//!
+//! The analyzer replaces every use of a constant with its value as a literal,
+//! so constants have no runtime cost and may appear where only compile-time
+//! values are allowed: the size of an array type `[T; N]` and match patterns.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{ResolvedType, SemanticError};
+use crate::parser::ast::{BinaryOperator, ConstDecl, Expression, Literal, UnaryOperator};
+
+/// The value of a constant expression
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    String(String),
+}
+
+impl ConstValue {
+    pub fn value_type(&self) -> ResolvedType {
+        match self {
+            ConstValue::Int(_) => ResolvedType::Int,
+            ConstValue::Float(_) => ResolvedType::Float,
+            ConstValue::Bool(_) => ResolvedType::Bool,
+            ConstValue::Char(_) => ResolvedType::Char,
+            ConstValue::String(_) => ResolvedType::String,
+        }
+    }
+
+    /// The literal a use of the constant is replaced with
+    pub fn to_literal(&self) -> Literal {
+        match self {
+            ConstValue::Int(value) => Literal::Integer(*value),
+            ConstValue::Float(value) => Literal::Float(*value),
+            ConstValue::Bool(value) => Literal::Boolean(*value),
+            ConstValue::Char(value) => Literal::Char(*value),
+            ConstValue::String(value) => Literal::String(value.clone()),
+        }
+    }
+
+    fn from_literal(literal: &Literal) -> Result<Self, SemanticError> {
+        match literal {
+            Literal::Integer(value) => Ok(ConstValue::Int(*value)),
+            Literal::Float(value) => Ok(ConstValue::Float(*value)),
+            Literal::Boolean(value) => Ok(ConstValue::Bool(*value)),
+            Literal::Char(value) => Ok(ConstValue::Char(*value)),
+            Literal::String(value) => Ok(ConstValue::String(value.clone())),
+            other => Err(not_constant(&format!("the literal {:?}", other))),
+        }
+    }
+}
+
+/// Evaluate a constant expression; `constant` gives the value of a named
+/// constant, or `None` when the name is not one
+pub fn evaluate<F>(expr: &Expression, constant: &mut F) -> Result<ConstValue, SemanticError>
+where
+    F: FnMut(&str) -> Result<Option<ConstValue>, SemanticError>,
+{
+    match expr {
+        Expression::Literal(literal) => ConstValue::from_literal(literal),
+        Expression::Identifier(name) => {
+            constant(name)?.ok_or_else(|| SemanticError::ConstEvaluation(format!("`{}` is not a constant", name)))
+        }
+        Expression::Unary(unary) => {
+            let operand = evaluate(&unary.operand, constant)?;
+            match (&unary.operator, operand) {
+                (UnaryOperator::Negate, ConstValue::Int(value)) => value.checked_neg().map(ConstValue::Int).ok_or_else(overflow),
+                (UnaryOperator::Negate, ConstValue::Float(value)) => Ok(ConstValue::Float(-value)),
+                (UnaryOperator::Not, ConstValue::Bool(value)) => Ok(ConstValue::Bool(!value)),
+                (operator, operand) => Err(SemanticError::ConstEvaluation(format!(
+                    "cannot apply `{:?}` to {:?}",
+                    operator,
+                    operand.value_type()
+                ))),
+            }
+        }
+        Expression::Binary(binary) => {
+            let left = evaluate(&binary.left, constant)?;
+            let right = evaluate(&binary.right, constant)?;
+            binary_operation(&binary.operator, left, right)
+        }
+        Expression::Call(_) => Err(not_constant("a function call")),
+        _ => Err(not_constant("this expression")),
+    }
+}
+
+fn binary_operation(operator: &BinaryOperator, left: ConstValue, right: ConstValue) -> Result<ConstValue, SemanticError> {
+    use BinaryOperator::*;
+    use ConstValue::*;
+
+    let value = match (operator, &left, &right) {
+        (Add, Int(a), Int(b)) => Int(a.checked_add(*b).ok_or_else(overflow)?),
+        (Subtract, Int(a), Int(b)) => Int(a.checked_sub(*b).ok_or_else(overflow)?),
+        (Multiply, Int(a), Int(b)) => Int(a.checked_mul(*b).ok_or_else(overflow)?),
+        (Divide | Modulo, Int(_), Int(0)) => return Err(SemanticError::ConstEvaluation("division by zero".to_string())),
+        (Divide, Int(a), Int(b)) => Int(a.checked_div(*b).ok_or_else(overflow)?),
+        (Modulo, Int(a), Int(b)) => Int(a.checked_rem(*b).ok_or_else(overflow)?),
+        (Power, Int(a), Int(b)) => {
+            let exponent = u32::try_from(*b)
+                .map_err(|_| SemanticError::ConstEvaluation(format!("negative exponent {}", b)))?;
+            Int(a.checked_pow(exponent).ok_or_else(overflow)?)
+        }
+
+        (Add, Float(a), Float(b)) => Float(a + b),
+        (Subtract, Float(a), Float(b)) => Float(a - b),
+        (Multiply, Float(a), Float(b)) => Float(a * b),
+        (Divide, Float(a), Float(b)) => Float(a / b),
+        (Modulo, Float(a), Float(b)) => Float(a % b),
+        (Power, Float(a), Float(b)) => Float(a.powf(*b)),
+
+        (Add, String(a), String(b)) => String(format!("{}{}", a, b)),
+        (And, Bool(a), Bool(b)) => Bool(*a && *b),
+        (Or, Bool(a), Bool(b)) => Bool(*a || *b),
+
+        (Equal, a, b) if a.value_type() == b.value_type() => Bool(a == b),
+        (NotEqual, a, b) if a.value_type() == b.value_type() => Bool(a != b),
+        (Less | LessEqual | Greater | GreaterEqual, a, b) => {
+            let ordering = match (a, b) {
+                (Int(a), Int(b)) => a.partial_cmp(b),
+                (Float(a), Float(b)) => a.partial_cmp(b),
+                (Char(a), Char(b)) => a.partial_cmp(b),
+                (String(a), String(b)) => a.partial_cmp(b),
+                _ => return Err(mismatch(operator, &left, &right)),
+            };
+            Bool(match (operator, ordering) {
+                (_, None) => false,
+                (Less, Some(ordering)) => ordering.is_lt(),
+                (LessEqual, Some(ordering)) => ordering.is_le(),
+                (Greater, Some(ordering)) => ordering.is_gt(),
+                (_, Some(ordering)) => ordering.is_ge(),
+            })
+        }
+        _ => return Err(mismatch(operator, &left, &right)),
+    };
+    Ok(value)
+}
+
+/// Evaluate `const` items, each after the constants its value names, so
+/// they may be declared in any order. An error carries the span of the
+/// item whose value failed.
+pub fn evaluate_items<'a>(
+    items: impl IntoIterator<Item = &'a ConstDecl>,
+) -> Result<HashMap<String, ConstValue>, SemanticError> {
+    let mut evaluator = ItemEvaluator {
+        items: items.into_iter().map(|decl| (decl.name.as_str(), decl)).collect(),
+        values: HashMap::new(),
+        evaluating: Vec::new(),
+    };
+    let mut names: Vec<&str> = evaluator.items.keys().copied().collect();
+    names.sort_unstable();
+    for name in names {
+        evaluator.value(name)?;
+    }
+    Ok(evaluator.values)
+}
+
+struct ItemEvaluator<'a> {
+    items: HashMap<&'a str, &'a ConstDecl>,
+    values: HashMap<String, ConstValue>,
+    /// Constants whose values are being evaluated, outermost first
+    evaluating: Vec<&'a str>,
+}
+
+impl<'a> ItemEvaluator<'a> {
+    fn value(&mut self, name: &str) -> Result<Option<ConstValue>, SemanticError> {
+        if let Some(value) = self.values.get(name) {
+            return Ok(Some(value.clone()));
+        }
+        let Some((&name, &decl)) = self.items.get_key_value(name) else {
+            return Ok(None);
+        };
+        if let Some(start) = self.evaluating.iter().position(|evaluating| *evaluating == name) {
+            let cycle: Vec<String> = self.evaluating[start..]
+                .iter()
+                .chain(std::iter::once(&name))
+                .map(|name| format!("`{}`", name))
+                .collect();
+            return Err(SemanticError::ConstEvaluation(format!(
+                "`{}` depends on itself: {}",
+                name,
+                cycle.join(" -> ")
+            )));
+        }
+
+        self.evaluating.push(name);
+        let value = evaluate(&decl.value, &mut |other| self.value(other));
+        self.evaluating.pop();
+
+        let value = value.map_err(|e| e.with_span(Some(decl.span)))?;
+        self.values.insert(name.to_string(), value.clone());
+        Ok(Some(value))
+    }
+}
+
+fn not_constant(what: &str) -> SemanticError {
+    SemanticError::ConstEvaluation(format!("{} cannot be evaluated at compile time", what))
+}
+
+fn overflow() -> SemanticError {
+    SemanticError::ConstEvaluation("integer overflow".to_string())
+}
+
+fn mismatch(operator: &BinaryOperator, left: &ConstValue, right: &ConstValue) -> SemanticError {
+    SemanticError::ConstEvaluation(format!(
+        "cannot apply `{:?}` to {:?} and {:?}",
+        operator,
+        left.value_type(),
+        right.value_type()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast::Item;
+    use crate::{Lexer, Parser};
+
+    fn constants(source: &str) -> Result<HashMap<String, ConstValue>, SemanticError> {
+        let program = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        evaluate_items(program.items.iter().filter_map(|item| match item {
+            Item::Const(decl) => Some(decl),
+            _ => None,
+        }))
+    }
+
+    #[test]
+    fn test_constants_fold_in_dependency_order() {
+        let values = constants(
+            "const AREA: int = SIDE * SIDE - 1;
+             const SIDE: int = 2 + 3;
+             const HALF: float = -1.0 / 2.0;
+             const GREETING: string = \"as-salamu \" + \"alaykum\";
+             const BIG: bool = AREA >= 24 && !(SIDE == 4) || false;",
+        )
+        .unwrap();
+        assert_eq!(values["SIDE"], ConstValue::Int(5));
+        assert_eq!(values["AREA"], ConstValue::Int(24));
+        assert_eq!(values["HALF"], ConstValue::Float(-0.5));
+        assert_eq!(values["GREETING"], ConstValue::String("as-salamu alaykum".to_string()));
+        assert_eq!(values["BIG"], ConstValue::Bool(true));
+        assert_eq!(values["AREA"].to_literal(), Literal::Integer(24));
+    }
+
+    #[test]
+    fn test_invalid_constants() {
+        let message = |source: &str| constants(source).unwrap_err().inner().to_string();
+        assert!(message("const A: int = B;\nconst B: int = A + 1;").contains("`A` depends on itself: `A` -> `B` -> `A`"));
+        assert!(message("const A: int = 1 / (2 - 2);").contains("division by zero"));
+        assert!(message("const A: int = 9223372036854775807 + 1;").contains("integer overflow"));
+        assert!(message("const A: int = 1 + 2.0;").contains("cannot apply `Add` to Int and Float"));
+        assert!(message("const A: int = answer();").contains("a function call cannot be evaluated"));
+        assert!(message("const A: int = x;").contains("`x` is not a constant"));
+    }
+}
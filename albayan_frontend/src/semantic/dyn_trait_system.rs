@@ -1,4 +1,4 @@
-// src/codegen/dyn_trait_system.rs
+// albayan_frontend/src/semantic/dyn_trait_system.rs
 // نظام تعدد الأشكال الديناميكي - الأولوية القصوى للخبير
 // Expert recommendation: "تعدد الأشكال الديناميكي (dyn Trait و V-Tables)"
 // "هذا سيكمل قصة Traits ويجعل اللغة قوية ومرنة بشكل هائل"
@@ -0,0 +1,92 @@
+//! # Format Strings
+//!
+//! Parsing of the templates taken by `format`, `print` and `println`:
+//! `{}` is replaced by the next argument, `{:.N}` prints a float with `N`
+//! decimals, and `{{` / `}}` stand for literal braces. The semantic analyzer
+//! checks templates with this parser and the LLVM backend lowers its pieces.
+
+/// A piece of a parsed format template
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatPiece {
+    /// Literal text, with escaped braces resolved
+    Text(String),
+    /// Placeholder for the next argument, with an optional float precision
+    Argument { precision: Option<usize> },
+}
+
+/// Parse a format template into text and placeholder pieces
+pub fn parse_format_string(template: &str) -> Result<Vec<FormatPiece>, String> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let spec: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let precision = match spec.as_str() {
+                    "" => None,
+                    _ => Some(
+                        spec.strip_prefix(":.")
+                            .and_then(|digits| digits.parse().ok())
+                            .ok_or_else(|| format!("unsupported format specifier `{{{}}}`", spec))?,
+                    ),
+                };
+                if !text.is_empty() {
+                    pieces.push(FormatPiece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(FormatPiece::Argument { precision });
+            }
+            '}' => return Err("unmatched `}` in format string; use `}}` for a literal brace".to_string()),
+            _ => text.push(ch),
+        }
+    }
+
+    if !text.is_empty() {
+        pieces.push(FormatPiece::Text(text));
+    }
+    Ok(pieces)
+}
+
+/// Number of arguments a parsed template expects
+pub fn argument_count(pieces: &[FormatPiece]) -> usize {
+    pieces
+        .iter()
+        .filter(|piece| matches!(piece, FormatPiece::Argument { .. }))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_placeholders_and_escapes() {
+        let pieces = parse_format_string("x = {}, {{y}} = {:.2}").unwrap();
+        assert_eq!(
+            pieces,
+            vec![
+                FormatPiece::Text("x = ".to_string()),
+                FormatPiece::Argument { precision: None },
+                FormatPiece::Text(", {y} = ".to_string()),
+                FormatPiece::Argument { precision: Some(2) },
+            ]
+        );
+        assert_eq!(argument_count(&pieces), 2);
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(parse_format_string("{:?}").is_err());
+        assert!(parse_format_string("closing } alone").is_err());
+        assert_eq!(parse_format_string("plain").unwrap(), vec![FormatPiece::Text("plain".to_string())]);
+    }
+}
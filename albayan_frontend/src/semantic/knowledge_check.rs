@@ -0,0 +1,255 @@
+//! # Workspace Knowledge Checks
+//!
+//! Project-level consistency checks over the facts, rules and relation
+//! declarations of several source files at once. Per-file semantic analysis
+//! cannot see these problems: a relation declared with different arities in two
+//! files, or a rule whose body depends on a relation that no file ever populates.
+//!
+//! The knowledge base is treated as closed over the checked files, so rules that
+//! can never fire are reported as warnings (facts may still be asserted at runtime).
+//! The taint audit of [`super::taint_check`] runs as part of these checks.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::diagnostics::Diagnostic;
+use crate::lexer::Span;
+use crate::parser::ast::{Item, LogicArg, LogicTerm, Program};
+
+/// A source location in one of the checked files
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnowledgeLocation {
+    pub file: String,
+    pub span: Span,
+}
+
+/// A workspace diagnostic, located in one file and optionally pointing at another
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnowledgeIssue {
+    pub location: KnowledgeLocation,
+    pub diagnostic: Diagnostic,
+    /// Related location and its description, e.g. the first use of a relation
+    pub related: Option<(KnowledgeLocation, String)>,
+}
+
+/// One use of a relation name with a given arity
+struct Occurrence {
+    arity: usize,
+    location: KnowledgeLocation,
+}
+
+type Predicate = (String, usize);
+
+struct Rule<'a> {
+    head: &'a LogicTerm,
+    body: &'a [LogicTerm],
+    file: &'a str,
+}
+
+/// Run the workspace knowledge checks over `(file name, program)` pairs
+pub fn check_knowledge(files: &[(String, &Program)]) -> Vec<KnowledgeIssue> {
+    let mut issues = Vec::new();
+    let mut occurrences: Vec<(&str, Occurrence)> = Vec::new();
+    let mut facts: HashMap<Predicate, Vec<&[LogicArg]>> = HashMap::new();
+    let mut rules: Vec<Rule> = Vec::new();
+
+    for (file, program) in files {
+        let location = |span: Span| KnowledgeLocation { file: file.clone(), span };
+        let occurrence = |term: &LogicTerm| Occurrence { arity: term.args.len(), location: location(term.span) };
+        for item in &program.items {
+            match item {
+                Item::Relation(relation) => occurrences.push((
+                    &relation.name,
+                    Occurrence { arity: relation.arg_types.len(), location: location(relation.span) },
+                )),
+                Item::Fact(fact) => {
+                    occurrences.push((&fact.term.name, occurrence(&fact.term)));
+                    facts.entry(predicate(&fact.term)).or_default().push(&fact.term.args);
+                }
+                Item::Rule(rule) => {
+                    for term in std::iter::once(&rule.head).chain(&rule.body).filter(|term| term.calls_relation()) {
+                        occurrences.push((&term.name, occurrence(term)));
+                    }
+                    rules.push(Rule { head: &rule.head, body: &rule.body, file });
+                }
+                Item::Constraint(constraint) => {
+                    for term in &constraint.goals {
+                        occurrences.push((&term.name, occurrence(term)));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Conflicting arities: every use that differs from the first one is reported
+    let mut first_use: HashMap<&str, &Occurrence> = HashMap::new();
+    let mut conflicting: HashSet<&str> = HashSet::new();
+    for (name, occurrence) in &occurrences {
+        let first = *first_use.entry(name).or_insert(occurrence);
+        if first.arity != occurrence.arity {
+            conflicting.insert(name);
+            issues.push(KnowledgeIssue {
+                location: occurrence.location.clone(),
+                diagnostic: Diagnostic::error(
+                    "AB0240",
+                    format!("relation `{}` is used with {} argument(s) here but {} elsewhere",
+                            name, occurrence.arity, first.arity),
+                )
+                .with_span(Some(occurrence.location.span))
+                .with_label(format!("`{}/{}`", name, occurrence.arity))
+                .with_note(format!("first used as `{}/{}` in {}", name, first.arity, first.location.file)),
+                related: Some((first.location.clone(), format!("first used as `{}/{}`", name, first.arity))),
+            });
+        }
+    }
+
+    // Derivable predicates: those with facts, plus rule heads whose bodies can be satisfied
+    let ruled: HashSet<Predicate> = rules.iter().map(|rule| predicate(rule.head)).collect();
+    let mut derivable: HashSet<Predicate> = facts.keys().cloned().collect();
+    loop {
+        let before = derivable.len();
+        for rule in &rules {
+            if rule.body.iter().all(|goal| unsatisfiable(goal, &derivable, &facts, &ruled).is_none()) {
+                derivable.insert(predicate(rule.head));
+            }
+        }
+        if derivable.len() == before {
+            break;
+        }
+    }
+
+    for rule in &rules {
+        let blocking = rule.body.iter()
+            .filter(|goal| !conflicting.contains(goal.name.as_str()))
+            .find_map(|goal| unsatisfiable(goal, &derivable, &facts, &ruled).map(|reason| (goal, reason)));
+        if let Some((goal, reason)) = blocking {
+            let head = predicate(rule.head);
+            issues.push(KnowledgeIssue {
+                location: KnowledgeLocation { file: rule.file.to_string(), span: goal.span },
+                diagnostic: Diagnostic::warning("AB0241", format!("rule for `{}/{}` can never fire", head.0, head.1))
+                    .with_span(Some(goal.span))
+                    .with_label(reason)
+                    .with_note("facts asserted at runtime are not taken into account"),
+                related: None,
+            });
+        }
+    }
+
+    issues.extend(super::taint_check::check_taint(files));
+    issues
+}
+
+fn predicate(term: &LogicTerm) -> Predicate {
+    (term.name.clone(), term.args.len())
+}
+
+/// Why a goal can never succeed, or `None` if it may
+fn unsatisfiable(
+    goal: &LogicTerm,
+    derivable: &HashSet<Predicate>,
+    facts: &HashMap<Predicate, Vec<&[LogicArg]>>,
+    ruled: &HashSet<Predicate>,
+) -> Option<String> {
+    // A negation holds when its goal cannot, the cut always does, and
+    // comparisons depend on the values reaching them
+    if goal.negated || !goal.calls_relation() {
+        return None;
+    }
+    let key = predicate(goal);
+    let signature = format!("`{}/{}`", key.0, key.1);
+    let known_facts = facts.get(&key);
+
+    if known_facts.is_none() && !ruled.contains(&key) {
+        return Some(format!("no facts or rules define {}", signature));
+    }
+    if !derivable.contains(&key) {
+        return Some(format!("{} can never be derived", signature));
+    }
+    // Without rules the facts are the whole relation, so the goal must match one of them
+    match known_facts {
+        Some(known) if !ruled.contains(&key) && !known.iter().any(|fact| matches_fact(&goal.args, fact)) => {
+            Some(format!("no {} fact matches these arguments", signature))
+        }
+        _ => None,
+    }
+}
+
+/// Whether a goal unifies with a ground fact (repeated variables must agree)
+fn matches_fact(goal: &[LogicArg], fact: &[LogicArg]) -> bool {
+    let mut bound: HashMap<&str, &LogicArg> = HashMap::new();
+    goal.iter().zip(fact).all(|(pattern, value)| matches_arg(pattern, value, &mut bound))
+}
+
+/// Whether a pattern matches a ground argument, looking into compound terms
+/// and lists
+fn matches_arg<'a>(pattern: &'a LogicArg, value: &'a LogicArg, bound: &mut HashMap<&'a str, &'a LogicArg>) -> bool {
+    match (pattern, value) {
+        (LogicArg::Variable(name) | LogicArg::Constant(name), _) if name == "_" => true,
+        (LogicArg::Variable(name), _) => *bound.entry(name).or_insert(value) == value,
+        (LogicArg::Compound(pattern), LogicArg::Compound(value)) => {
+            pattern.name == value.name
+                && pattern.args.len() == value.args.len()
+                && pattern.args.iter().zip(&value.args).all(|(pattern, value)| matches_arg(pattern, value, bound))
+        }
+        (LogicArg::List { items, tail }, LogicArg::List { items: values, tail: None }) => {
+            let enough = match tail {
+                Some(_) => values.len() >= items.len(),
+                None => values.len() == items.len(),
+            };
+            // The items a tail stands for are not one argument of the fact,
+            // so the tail is taken to match whatever is left over
+            enough && items.iter().zip(values).all(|(pattern, value)| matches_arg(pattern, value, bound))
+        }
+        (constant, _) => constant == value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Severity;
+    use crate::{Lexer, Parser};
+
+    fn parse(source: &str) -> Program {
+        Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap()
+    }
+
+    #[test]
+    fn test_conflicting_arity_across_files() {
+        let family = parse("relation parent(string, string);\nfact parent(\"ali\", \"omar\");");
+        let other = parse("relation parent(string);\nfact parent(\"zaid\");");
+        let issues = check_knowledge(&[("family.ab".to_string(), &family), ("other.ab".to_string(), &other)]);
+
+        let conflicts: Vec<_> = issues.iter().filter(|issue| issue.diagnostic.code == "AB0240").collect();
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts.iter().all(|issue| issue.location.file == "other.ab"));
+        let (related, _) = conflicts[0].related.as_ref().unwrap();
+        assert_eq!(related.file, "family.ab");
+    }
+
+    #[test]
+    fn test_rules_that_can_never_fire() {
+        let program = parse(
+            "relation parent(string, string);
+             relation ancestor(string, string);
+             relation grandparent(string, string);
+             relation loop_rel(string);
+             relation orphan(string);
+             fact parent(\"ali\", \"omar\");
+             rule grandparent(X, Z) :- parent(X, Y), parent(Y, Z);
+             rule ancestor(X, Y) :- parent(\"zaid\", Y), parent(X, Y);
+             rule loop_rel(X) :- loop_rel(X);
+             rule orphan(X) :- parent(X, \"omar\");",
+        );
+        let issues = check_knowledge(&[("kb.ab".to_string(), &program)]);
+        let labels: Vec<_> = issues.iter()
+            .map(|issue| (issue.diagnostic.message.clone(), issue.diagnostic.label.clone().unwrap()))
+            .collect();
+
+        assert!(issues.iter().all(|issue| issue.diagnostic.severity == Severity::Warning));
+        assert_eq!(labels.len(), 2, "{:?}", labels);
+        assert_eq!(labels[0].0, "rule for `ancestor/2` can never fire");
+        assert_eq!(labels[0].1, "no `parent/2` fact matches these arguments");
+        assert_eq!(labels[1].1, "`loop_rel/1` can never be derived");
+    }
+}
@@ -2,9 +2,13 @@
 //!
 //! This module implements analysis for logic programming constructs in AlBayan.
 //! It validates relations, rules, facts, and queries for correctness and safety.
+//!
+//! Rules may negate body goals with `not`. [`check_stratification`] rejects
+//! programs where a relation depends on the negation of itself, whose
+//! meaning negation as failure cannot settle.
 
 use crate::parser::ast::*;
-use super::{ResolvedType, SemanticError, RelationInfo};
+use super::{AnnotatedItem, ResolvedType, SemanticError, RelationInfo};
 use std::collections::{HashMap, HashSet};
 
 /// Logic analyzer for validating logic programming constructs
@@ -38,6 +42,7 @@ pub struct ValidatedTerm {
     pub relation: String,
     pub args: Vec<ValidatedArg>,
     pub arg_types: Vec<ResolvedType>,
+    pub negated: bool,
 }
 
 /// A validated logic argument with type information
@@ -80,9 +85,11 @@ impl LogicAnalyzer {
             arg_types.push(resolved_type);
         }
 
+        let arg_concepts = vec![None; arg_types.len()];
         self.relations.insert(relation.name.clone(), RelationInfo {
             name: relation.name.clone(),
             arg_types,
+            arg_concepts,
         });
 
         Ok(())
@@ -93,9 +100,9 @@ impl LogicAnalyzer {
         // Validate the head term
         let validated_head = self.validate_term(&rule.head)?;
 
-        // Validate body terms
+        // Validate body terms; the cut and the comparisons have no relation
         let mut validated_body = Vec::new();
-        for term in &rule.body {
+        for term in rule.body.iter().filter(|term| term.calls_relation()) {
             let validated_term = self.validate_term(term)?;
             validated_body.push(validated_term);
         }
@@ -107,7 +114,8 @@ impl LogicAnalyzer {
             self.extract_variables(term, &mut variables);
         }
 
-        // Check rule safety: all variables in head must appear in body
+        // Check rule safety: all variables in head and in negated goals must
+        // appear in a positive goal
         self.check_rule_safety(&validated_head, &validated_body)?;
 
         let validated_rule = ValidatedRule {
@@ -160,6 +168,7 @@ impl LogicAnalyzer {
             relation: term.name.clone(),
             args: validated_args,
             arg_types: relation_info.arg_types.clone(),
+            negated: term.negated,
         })
     }
 
@@ -221,6 +230,25 @@ impl LogicAnalyzer {
                     const_type: ResolvedType::Float,
                 })
             }
+
+            // Arithmetic only appears in comparisons, which have no relation
+            LogicArg::Operation { .. } => Err(SemanticError::TypeMismatch {
+                expected: expected_type.clone(),
+                found: ResolvedType::Int,
+            }),
+
+            LogicArg::Goal(goal) => Err(SemanticError::MisplacedGoal(goal.to_string())),
+
+            // Structured terms are checked against struct and list types by
+            // the semantic analyzer, which this validator does not resolve
+            LogicArg::Compound(term) => Err(SemanticError::TypeMismatch {
+                expected: expected_type.clone(),
+                found: ResolvedType::Struct(term.name.clone()),
+            }),
+            LogicArg::List { .. } => Err(SemanticError::TypeMismatch {
+                expected: expected_type.clone(),
+                found: ResolvedType::List(Box::new(ResolvedType::Unit)),
+            }),
         }
     }
 
@@ -233,13 +261,14 @@ impl LogicAnalyzer {
         }
     }
 
-    /// Check rule safety: all head variables must appear in body
+    /// Check rule safety: all head variables must appear in a positive body
+    /// goal, and so must the variables of negated goals
     fn check_rule_safety(&self, head: &ValidatedTerm, body: &[ValidatedTerm]) -> Result<(), SemanticError> {
         let mut head_vars = HashSet::new();
         self.extract_variables(head, &mut head_vars);
 
         let mut body_vars = HashSet::new();
-        for term in body {
+        for term in body.iter().filter(|term| !term.negated) {
             self.extract_variables(term, &mut body_vars);
         }
 
@@ -248,6 +277,13 @@ impl LogicAnalyzer {
                 return Err(SemanticError::UnboundVariable(head_var.clone()));
             }
         }
+        for term in body.iter().filter(|term| term.negated) {
+            let mut negated_vars = HashSet::new();
+            self.extract_variables(term, &mut negated_vars);
+            if let Some(variable) = negated_vars.into_iter().find(|var| !body_vars.contains(var)) {
+                return Err(SemanticError::UnsafeNegation { variable, relation: term.relation.clone() });
+            }
+        }
 
         Ok(())
     }
@@ -327,6 +363,42 @@ impl LogicAnalyzer {
     }
 }
 
+/// Check that no relation depends on the negation of itself through the
+/// rules of `items`: for every negated goal, the relation negated must not
+/// lead back to the head of the rule negating it
+pub fn check_stratification(items: &[AnnotatedItem]) -> Result<(), SemanticError> {
+    let mut depends_on: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut negations = Vec::new();
+    for item in items {
+        let AnnotatedItem::Rule(rule) = item else { continue };
+        for goal in rule.body.iter().filter(|goal| goal.calls_relation()) {
+            depends_on.entry(rule.head.name.as_str()).or_default().push(goal.name.as_str());
+            if goal.negated {
+                negations.push((rule.head.name.as_str(), goal.name.as_str()));
+            }
+        }
+    }
+
+    for (relation, negated) in negations {
+        let mut reached = HashSet::from([negated]);
+        let mut pending = vec![negated];
+        while let Some(next) = pending.pop() {
+            if next == relation {
+                return Err(SemanticError::UnstratifiedNegation {
+                    relation: relation.to_string(),
+                    negated: negated.to_string(),
+                });
+            }
+            for dependency in depends_on.get(next).into_iter().flatten() {
+                if reached.insert(dependency) {
+                    pending.push(dependency);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 // Add new error type for variables in facts
 impl SemanticError {
     pub fn VariableInFact(var: String) -> Self {
@@ -344,10 +416,14 @@ mod tests {
 
         let relation = RelationDecl {
             name: "Parent".to_string(),
+            visibility: Visibility::Private,
             arg_types: vec![
                 Type::Named(Path::single("string".to_string())),
                 Type::Named(Path::single("string".to_string())),
             ],
+            security: None,
+            tabled: false,
+            span: Span::default(),
         };
 
         let result = analyzer.register_relation(&relation);
@@ -362,10 +438,14 @@ mod tests {
         // Register relation first
         let relation = RelationDecl {
             name: "Parent".to_string(),
+            visibility: Visibility::Private,
             arg_types: vec![
                 Type::Named(Path::single("string".to_string())),
                 Type::Named(Path::single("string".to_string())),
             ],
+            security: None,
+            tabled: false,
+            span: Span::default(),
         };
         analyzer.register_relation(&relation).unwrap();
 
@@ -377,7 +457,10 @@ mod tests {
                     LogicArg::StringConstant("john".to_string()),
                     LogicArg::StringConstant("mary".to_string()),
                 ],
+                negated: false,
+                span: Span::default(),
             },
+            span: Span::default(),
         };
 
         let result = analyzer.validate_fact(&fact);
@@ -391,19 +474,27 @@ mod tests {
         // Register relations
         let parent_relation = RelationDecl {
             name: "Parent".to_string(),
+            visibility: Visibility::Private,
             arg_types: vec![
                 Type::Named(Path::single("string".to_string())),
                 Type::Named(Path::single("string".to_string())),
             ],
+            security: None,
+            tabled: false,
+            span: Span::default(),
         };
         analyzer.register_relation(&parent_relation).unwrap();
 
         let grandparent_relation = RelationDecl {
             name: "Grandparent".to_string(),
+            visibility: Visibility::Private,
             arg_types: vec![
                 Type::Named(Path::single("string".to_string())),
                 Type::Named(Path::single("string".to_string())),
             ],
+            security: None,
+            tabled: false,
+            span: Span::default(),
         };
         analyzer.register_relation(&grandparent_relation).unwrap();
 
@@ -415,6 +506,8 @@ mod tests {
                     LogicArg::Variable("GP".to_string()),
                     LogicArg::Variable("GC".to_string()),
                 ],
+                negated: false,
+                span: Span::default(),
             },
             body: vec![
                 LogicTerm {
@@ -423,6 +516,8 @@ mod tests {
                         LogicArg::Variable("GP".to_string()),
                         LogicArg::Variable("P".to_string()),
                     ],
+                    negated: false,
+                    span: Span::default(),
                 },
                 LogicTerm {
                     name: "Parent".to_string(),
@@ -430,8 +525,12 @@ mod tests {
                         LogicArg::Variable("P".to_string()),
                         LogicArg::Variable("GC".to_string()),
                     ],
+                    negated: false,
+                    span: Span::default(),
                 },
             ],
+            no_reorder: false,
+            span: Span::default(),
         };
 
         let result = analyzer.validate_rule(&rule);
@@ -0,0 +1,1184 @@
+//! # MIR and Non-Lexical Borrow Checking
+//!
+//! Annotated function bodies lowered to a control-flow graph close to Rust's
+//! MIR: every basic block is a list of assignments of an rvalue to a place,
+//! ended by a terminator that jumps, branches or returns. Moves and borrows are
+//! checked on this graph instead of on lexical scopes:
+//!
+//! - a borrow lasts only while a reference holding it may still be used, so
+//!   `let r = &x; print(r); x = 2;` is accepted;
+//! - a value is maybe-moved after any path that moves it, so a move in one
+//!   branch of an `if` is reported at a use after the merge, while moves in
+//!   both branches of an `if`/`else` are not;
+//! - a `let` without a value is maybe-uninitialized until every path to a use
+//!   assigns it, and unless it is `mut` no path may assign it twice, so
+//!   `let x: int; if c { x = 1; } else { x = 2; }` is accepted.
+//!
+//! Values that are not Copy move when assigned, returned, or passed by value
+//! to the program's own functions and methods; arguments of built-in
+//! functions such as `print` and `format` are only read. A method taking
+//! `&self` or `&mut self` borrows its receiver for the call, after the other
+//! arguments have been evaluated.
+
+use std::collections::{HashMap, HashSet};
+
+use super::ownership::BorrowKind;
+use super::{
+    AnnotatedBlock, AnnotatedExpression, AnnotatedExpressionKind, AnnotatedFunction, AnnotatedItem,
+    AnnotatedMatchArm, AnnotatedPattern, AnnotatedStatement, ResolvedType, SemanticError,
+};
+use crate::parser::ast::{BinaryOperator, Span, UnaryOperator};
+
+/// Index of a local in [`Body::locals`]
+pub type Local = usize;
+/// Index of a block in [`Body::blocks`]; block 0 is the entry
+pub type BlockId = usize;
+
+/// The local holding the return value; the parameters follow it
+pub const RETURN_PLACE: Local = 0;
+
+#[derive(Debug, Clone)]
+pub struct LocalDecl {
+    /// Source name; `None` for temporaries and the return place
+    pub name: Option<String>,
+    pub ty: ResolvedType,
+    /// A `let` without a value or `mut`, which each path may assign only once
+    pub assign_once: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Projection {
+    Field(String),
+    /// Some element of a list or array
+    Index,
+    Deref,
+}
+
+/// A memory location: a local and a path into it, such as `line.start.x`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Place {
+    pub local: Local,
+    pub projection: Vec<Projection>,
+}
+
+impl Place {
+    pub fn local(local: Local) -> Self {
+        Place { local, projection: Vec::new() }
+    }
+
+    fn project(mut self, projection: Projection) -> Self {
+        self.projection.push(projection);
+        self
+    }
+
+    /// Whether the places may share memory: one is a prefix of the other
+    pub fn overlaps(&self, other: &Place) -> bool {
+        self.local == other.local && self.projection.iter().zip(&other.projection).all(|(a, b)| a == b)
+    }
+
+    fn is_prefix_of(&self, other: &Place) -> bool {
+        self.local == other.local && other.projection.starts_with(&self.projection)
+    }
+
+    /// Moves out of references and list elements are treated as copies
+    fn is_movable(&self) -> bool {
+        !self.projection.iter().any(|projection| matches!(projection, Projection::Deref | Projection::Index))
+    }
+
+    fn through_reference(&self) -> bool {
+        self.projection.contains(&Projection::Deref)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Copy(Place),
+    Move(Place),
+    Constant,
+}
+
+impl Operand {
+    pub fn place(&self) -> Option<&Place> {
+        match self {
+            Operand::Copy(place) | Operand::Move(place) => Some(place),
+            Operand::Constant => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rvalue {
+    Use(Operand),
+    /// Arithmetic, calls and aggregates: a new value computed from the operands
+    Compute(Vec<Operand>),
+    Ref(BorrowKind, Place),
+    /// The value of a `let` without an initializer
+    Uninit,
+}
+
+impl Rvalue {
+    fn operands(&self) -> &[Operand] {
+        match self {
+            Rvalue::Use(operand) => std::slice::from_ref(operand),
+            Rvalue::Compute(operands) => operands,
+            Rvalue::Ref(..) | Rvalue::Uninit => &[],
+        }
+    }
+}
+
+/// `place = rvalue`
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub place: Place,
+    pub rvalue: Rvalue,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Goto(BlockId),
+    /// Continue at one of the targets depending on the operand
+    Switch(Operand, Vec<BlockId>),
+    Return,
+    /// End of a block no path reaches, such as the code after `return`
+    Unreachable,
+}
+
+impl Terminator {
+    pub fn successors(&self) -> &[BlockId] {
+        match self {
+            Terminator::Goto(target) => std::slice::from_ref(target),
+            Terminator::Switch(_, targets) => targets,
+            Terminator::Return | Terminator::Unreachable => &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BasicBlock {
+    pub statements: Vec<Statement>,
+    pub terminator: Terminator,
+    /// Source location of the terminator
+    pub span: Span,
+}
+
+/// A function body as a control-flow graph
+#[derive(Debug, Clone)]
+pub struct Body {
+    pub name: String,
+    pub locals: Vec<LocalDecl>,
+    pub blocks: Vec<BasicBlock>,
+}
+
+impl Body {
+    /// Source form of a place, such as `*r` or `line.start.x`; temporaries are
+    /// `_3`, and a dereference followed by a field or index is left implicit
+    /// as it is in source (`r.x`)
+    pub fn describe(&self, place: &Place) -> String {
+        let mut text = match &self.locals[place.local].name {
+            Some(name) => name.clone(),
+            None => format!("_{}", place.local),
+        };
+        for (index, projection) in place.projection.iter().enumerate() {
+            match projection {
+                Projection::Field(field) => {
+                    text.push('.');
+                    text.push_str(field);
+                }
+                Projection::Index => text.push_str("[_]"),
+                Projection::Deref if index + 1 < place.projection.len() => {}
+                Projection::Deref => text = format!("*{}", text),
+            }
+        }
+        text
+    }
+}
+
+/// Parameter types of the program's own functions and methods, by the name
+/// calls use (`area`, `Point::shift`)
+pub fn signatures(items: &[AnnotatedItem]) -> HashMap<String, Vec<ResolvedType>> {
+    let mut signatures = HashMap::new();
+    for item in items {
+        match item {
+            AnnotatedItem::Function(function) => {
+                signatures.insert(function.name.clone(), parameter_types(function));
+            }
+            AnnotatedItem::Impl(impl_decl) => {
+                for method in &impl_decl.methods {
+                    let name = format!("{}::{}", impl_decl.type_name, method.name);
+                    signatures.insert(name, parameter_types(method));
+                }
+            }
+            _ => {}
+        }
+    }
+    signatures
+}
+
+fn parameter_types(function: &AnnotatedFunction) -> Vec<ResolvedType> {
+    function.parameters.iter().map(|parameter| parameter.param_type.clone()).collect()
+}
+
+/// Every function and method body of the program
+pub fn functions(items: &[AnnotatedItem]) -> impl Iterator<Item = &AnnotatedFunction> {
+    items.iter().flat_map(|item| match item {
+        AnnotatedItem::Function(function) => std::slice::from_ref(function),
+        AnnotatedItem::Impl(impl_decl) => impl_decl.methods.as_slice(),
+        _ => &[],
+    })
+}
+
+/// Whether values of the type are copied rather than moved
+fn is_copy(ty: &ResolvedType) -> bool {
+    match ty {
+        ResolvedType::Int
+        | ResolvedType::Float
+        | ResolvedType::Bool
+        | ResolvedType::Char
+        | ResolvedType::Null
+        | ResolvedType::Unit
+        | ResolvedType::Function(..)
+        | ResolvedType::Reference(..) => true,
+        ResolvedType::Tuple(elements) => elements.iter().all(is_copy),
+        _ => false,
+    }
+}
+
+/// Whether values of the type may hold a borrow
+fn may_hold_reference(ty: &ResolvedType) -> bool {
+    match ty {
+        ResolvedType::Reference(..) | ResolvedType::GenericParam(_) => true,
+        ResolvedType::Tuple(elements) | ResolvedType::Generic(_, elements) => elements.iter().any(may_hold_reference),
+        ResolvedType::List(element)
+        | ResolvedType::Array(element)
+        | ResolvedType::Vector(element, _)
+        | ResolvedType::Optional(element) => {
+            may_hold_reference(element)
+        }
+        _ => false,
+    }
+}
+
+/// Lower a function body to its control-flow graph
+pub fn lower(function: &AnnotatedFunction, signatures: &HashMap<String, Vec<ResolvedType>>) -> Body {
+    let return_place = LocalDecl {
+        name: None,
+        ty: function.return_type.clone().unwrap_or(ResolvedType::Unit),
+        assign_once: false,
+    };
+    let mut builder = Builder {
+        signatures,
+        body: Body {
+            name: function.name.clone(),
+            locals: vec![return_place],
+            blocks: Vec::new(),
+        },
+        current: 0,
+        scopes: vec![HashMap::new()],
+        loops: Vec::new(),
+        span: function.span,
+    };
+    builder.current = builder.new_block();
+    for parameter in &function.parameters {
+        builder.declare(&parameter.name, parameter.param_type.clone());
+    }
+    builder.block(&function.body, None);
+    builder.terminate(Terminator::Return);
+    builder.body
+}
+
+struct Builder<'a> {
+    signatures: &'a HashMap<String, Vec<ResolvedType>>,
+    body: Body,
+    current: BlockId,
+    scopes: Vec<HashMap<String, Local>>,
+    /// `continue` and `break` targets of the enclosing loops
+    loops: Vec<(BlockId, BlockId)>,
+    span: Span,
+}
+
+impl Builder<'_> {
+    fn new_block(&mut self) -> BlockId {
+        self.body.blocks.push(BasicBlock {
+            statements: Vec::new(),
+            terminator: Terminator::Unreachable,
+            span: self.span,
+        });
+        self.body.blocks.len() - 1
+    }
+
+    fn terminate(&mut self, terminator: Terminator) {
+        let block = &mut self.body.blocks[self.current];
+        block.terminator = terminator;
+        block.span = self.span;
+    }
+
+    /// End the current block and continue in a fresh one no edge reaches
+    fn diverge(&mut self, terminator: Terminator) {
+        self.terminate(terminator);
+        self.current = self.new_block();
+    }
+
+    fn push(&mut self, place: Place, rvalue: Rvalue) {
+        let span = self.span;
+        self.body.blocks[self.current].statements.push(Statement { place, rvalue, span });
+    }
+
+    fn temp(&mut self, ty: ResolvedType) -> Local {
+        self.body.locals.push(LocalDecl { name: None, ty, assign_once: false });
+        self.body.locals.len() - 1
+    }
+
+    fn declare(&mut self, name: &str, ty: ResolvedType) -> Local {
+        self.body.locals.push(LocalDecl { name: Some(name.to_string()), ty, assign_once: false });
+        let local = self.body.locals.len() - 1;
+        self.scopes.last_mut().expect("function scope").insert(name.to_string(), local);
+        local
+    }
+
+    fn lookup(&self, name: &str) -> Option<Local> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name).copied())
+    }
+
+    /// Lower a block; a trailing expression no `;` ends is stored in `result` when given
+    fn block(&mut self, block: &AnnotatedBlock, result: Option<Local>) {
+        self.scopes.push(HashMap::new());
+        for (index, statement) in block.statements.iter().enumerate() {
+            if let Some(span) = block.spans.get(index).filter(|span| !span.is_empty()) {
+                self.span = *span;
+            }
+            let is_tail = index + 1 == block.statements.len() && !block.trailing_semicolon;
+            let tail = if is_tail { result } else { None };
+            self.statement(statement, tail);
+        }
+        self.scopes.pop();
+    }
+
+    fn statement(&mut self, statement: &AnnotatedStatement, result: Option<Local>) {
+        match statement {
+            AnnotatedStatement::Let(let_stmt) => {
+                self.span = let_stmt.span;
+                let Some(initializer) = &let_stmt.initializer else {
+                    let local = self.declare(&let_stmt.name, let_stmt.var_type.clone());
+                    self.body.locals[local].assign_once = !let_stmt.is_mutable;
+                    self.push(Place::local(local), Rvalue::Uninit);
+                    return;
+                };
+                // The initializer is lowered first, so `let x = x;` reads the outer `x`
+                let rvalue = self.rvalue(initializer);
+                match &let_stmt.pattern {
+                    Some(pattern) => {
+                        let value = self.temp(let_stmt.var_type.clone());
+                        self.push(Place::local(value), rvalue);
+                        self.bind(pattern, Place::local(value), true);
+                    }
+                    None => {
+                        let local = self.declare(&let_stmt.name, let_stmt.var_type.clone());
+                        self.push(Place::local(local), rvalue);
+                    }
+                }
+            }
+            AnnotatedStatement::Return(ret) => {
+                self.span = ret.span;
+                if let Some(value) = &ret.value {
+                    let rvalue = self.rvalue(value);
+                    self.push(Place::local(RETURN_PLACE), rvalue);
+                }
+                self.diverge(Terminator::Return);
+            }
+            AnnotatedStatement::Expression(expr) => match result {
+                Some(result) => {
+                    let rvalue = self.rvalue(expr);
+                    self.push(Place::local(result), rvalue);
+                }
+                None => self.effect(expr),
+            },
+            AnnotatedStatement::If(if_stmt) => {
+                self.span = if_stmt.span;
+                self.branch(&if_stmt.condition, &if_stmt.then_block, if_stmt.else_block.as_ref(), None);
+            }
+            AnnotatedStatement::Match(match_stmt) => {
+                self.span = match_stmt.span;
+                self.match_arms(&match_stmt.expression, &match_stmt.arms, None);
+            }
+            AnnotatedStatement::While(while_stmt) => {
+                self.span = while_stmt.span;
+                let header = self.new_block();
+                self.terminate(Terminator::Goto(header));
+                self.current = header;
+                let condition = self.read(&while_stmt.condition);
+                let (body, exit) = (self.new_block(), self.new_block());
+                self.terminate(Terminator::Switch(condition, vec![body, exit]));
+                self.lower_loop(header, body, exit, |builder| builder.block(&while_stmt.body, None));
+            }
+            AnnotatedStatement::For(for_stmt) => {
+                self.span = for_stmt.span;
+                let iterable = self.read(&for_stmt.iterable);
+                let elements = self.temp(for_stmt.iterable.result_type.clone());
+                self.push(Place::local(elements), Rvalue::Use(iterable));
+                let header = self.new_block();
+                self.terminate(Terminator::Goto(header));
+                self.current = header;
+                let (body, exit) = (self.new_block(), self.new_block());
+                self.terminate(Terminator::Switch(Operand::Constant, vec![body, exit]));
+                self.lower_loop(header, body, exit, |builder| {
+                    builder.scopes.push(HashMap::new());
+                    let variable = builder.declare(&for_stmt.variable, for_stmt.element_type.clone());
+                    let element = Rvalue::Compute(vec![Operand::Copy(Place::local(elements))]);
+                    builder.push(Place::local(variable), element);
+                    builder.block(&for_stmt.body, None);
+                    builder.scopes.pop();
+                });
+            }
+            AnnotatedStatement::Assert(update) | AnnotatedStatement::Retract(update) => {
+                self.span = update.span;
+                // The knowledge base stores copies of the values
+                let operands = update.values().map(|value| self.read(value)).collect();
+                let ignored = self.temp(ResolvedType::Unit);
+                self.push(Place::local(ignored), Rvalue::Compute(operands));
+            }
+            AnnotatedStatement::Break => {
+                if let Some(&(_, exit)) = self.loops.last() {
+                    self.diverge(Terminator::Goto(exit));
+                }
+            }
+            AnnotatedStatement::Continue => {
+                if let Some(&(header, _)) = self.loops.last() {
+                    self.diverge(Terminator::Goto(header));
+                }
+            }
+        }
+    }
+
+    /// Lower a loop body starting at `body` that jumps back to `header`
+    fn lower_loop(&mut self, header: BlockId, body: BlockId, exit: BlockId, lower_body: impl FnOnce(&mut Self)) {
+        self.current = body;
+        self.loops.push((header, exit));
+        lower_body(self);
+        self.loops.pop();
+        self.terminate(Terminator::Goto(header));
+        self.current = exit;
+    }
+
+    fn branch(
+        &mut self,
+        condition: &AnnotatedExpression,
+        then_block: &AnnotatedBlock,
+        else_block: Option<&AnnotatedBlock>,
+        result: Option<Local>,
+    ) {
+        let condition = self.read(condition);
+        let (then_start, else_start, join) = (self.new_block(), self.new_block(), self.new_block());
+        self.terminate(Terminator::Switch(condition, vec![then_start, else_start]));
+        self.current = then_start;
+        self.block(then_block, result);
+        self.terminate(Terminator::Goto(join));
+        self.current = else_start;
+        if let Some(else_block) = else_block {
+            self.block(else_block, result);
+        }
+        self.terminate(Terminator::Goto(join));
+        self.current = join;
+    }
+
+    /// Arms bind from the scrutinee without moving it
+    fn match_arms(&mut self, scrutinee: &AnnotatedExpression, arms: &[AnnotatedMatchArm], result: Option<Local>) {
+        let scrutinee = self.place_of(scrutinee);
+        let targets: Vec<BlockId> = arms.iter().map(|_| self.new_block()).collect();
+        let join = self.new_block();
+        self.terminate(Terminator::Switch(Operand::Copy(scrutinee.clone()), targets.clone()));
+        for (arm, target) in arms.iter().zip(targets) {
+            self.current = target;
+            self.scopes.push(HashMap::new());
+            self.bind(&arm.pattern, scrutinee.clone(), false);
+            if let Some(guard) = &arm.guard {
+                let guard = self.read(guard);
+                let passed = self.temp(ResolvedType::Bool);
+                self.push(Place::local(passed), Rvalue::Use(guard));
+            }
+            self.block(&arm.body, result);
+            self.scopes.pop();
+            self.terminate(Terminator::Goto(join));
+        }
+        self.current = join;
+    }
+
+    /// Bind the names of a pattern to the parts of `place`
+    fn bind(&mut self, pattern: &AnnotatedPattern, place: Place, moving: bool) {
+        match pattern {
+            AnnotatedPattern::Identifier(name, ty) => {
+                let operand = if moving { use_place(place, ty) } else { Operand::Copy(place) };
+                let local = self.declare(name, ty.clone());
+                self.push(Place::local(local), Rvalue::Use(operand));
+            }
+            AnnotatedPattern::Tuple(elements, _) | AnnotatedPattern::Enum(_, Some(elements), _) => {
+                for (index, element) in elements.iter().enumerate() {
+                    self.bind(element, place.clone().project(Projection::Field(index.to_string())), moving);
+                }
+            }
+            AnnotatedPattern::Struct(_, fields, _) => {
+                for (field, element) in fields {
+                    self.bind(element, place.clone().project(Projection::Field(field.clone())), moving);
+                }
+            }
+            AnnotatedPattern::Wildcard | AnnotatedPattern::Literal(..) | AnnotatedPattern::Enum(_, None, _) => {}
+        }
+    }
+
+    /// Lower an expression evaluated only for its effects
+    fn effect(&mut self, expr: &AnnotatedExpression) {
+        match &expr.expr {
+            AnnotatedExpressionKind::Binary { left, operator, right } if is_assignment(operator) => {
+                self.assign(left, operator, right)
+            }
+            AnnotatedExpressionKind::If { condition, then_block, else_block } => {
+                self.branch(condition, then_block, else_block.as_ref(), None)
+            }
+            AnnotatedExpressionKind::Match { expression, arms } => self.match_arms(expression, arms, None),
+            _ => {
+                let rvalue = self.rvalue(expr);
+                let ignored = self.temp(expr.result_type.clone());
+                self.push(Place::local(ignored), rvalue);
+            }
+        }
+    }
+
+    fn assign(&mut self, left: &AnnotatedExpression, operator: &BinaryOperator, right: &AnnotatedExpression) {
+        if *operator == BinaryOperator::Assign {
+            let rvalue = self.rvalue(right);
+            let place = self.place_of(left);
+            self.push(place, rvalue);
+        } else {
+            let right = self.read(right);
+            let place = self.place_of(left);
+            self.push(place.clone(), Rvalue::Compute(vec![Operand::Copy(place), right]));
+        }
+    }
+
+    fn rvalue(&mut self, expr: &AnnotatedExpression) -> Rvalue {
+        match &expr.expr {
+            AnnotatedExpressionKind::Literal(_) => Rvalue::Use(Operand::Constant),
+            AnnotatedExpressionKind::Identifier(name) if self.lookup(name).is_none() => Rvalue::Use(Operand::Constant),
+            AnnotatedExpressionKind::Identifier(_)
+            | AnnotatedExpressionKind::FieldAccess { .. }
+            | AnnotatedExpressionKind::Index { .. } => {
+                let place = self.place_of(expr);
+                Rvalue::Use(use_place(place, &expr.result_type))
+            }
+            AnnotatedExpressionKind::Unary(unary) => match unary.operator {
+                UnaryOperator::Reference | UnaryOperator::MutableReference => {
+                    let kind = if unary.operator == UnaryOperator::Reference {
+                        BorrowKind::Immutable
+                    } else {
+                        BorrowKind::Mutable
+                    };
+                    Rvalue::Ref(kind, self.place_of(&unary.operand))
+                }
+                UnaryOperator::Dereference => {
+                    let place = self.place_of(expr);
+                    Rvalue::Use(use_place(place, &expr.result_type))
+                }
+                _ => Rvalue::Compute(vec![self.read(&unary.operand)]),
+            },
+            AnnotatedExpressionKind::Binary { left, operator, right } => {
+                if is_assignment(operator) {
+                    self.assign(left, operator, right);
+                    Rvalue::Use(Operand::Constant)
+                } else {
+                    Rvalue::Compute(vec![self.read(left), self.read(right)])
+                }
+            }
+            AnnotatedExpressionKind::StructLiteral { fields, .. } => {
+                Rvalue::Compute(fields.iter().map(|(_, value)| self.operand(value)).collect())
+            }
+            AnnotatedExpressionKind::EnumLiteral { fields, .. } => {
+                Rvalue::Compute(fields.iter().flatten().map(|value| self.operand(value)).collect())
+            }
+            AnnotatedExpressionKind::Array { elements } | AnnotatedExpressionKind::Tuple { elements } => {
+                Rvalue::Compute(elements.iter().map(|element| self.operand(element)).collect())
+            }
+            AnnotatedExpressionKind::ArrayRepeat { value, .. } => Rvalue::Compute(vec![self.operand(value)]),
+            AnnotatedExpressionKind::MapLiteral { entries } => Rvalue::Compute(
+                entries
+                    .iter()
+                    .flat_map(|(key, value)| [key, value])
+                    .map(|entry| self.operand(entry))
+                    .collect(),
+            ),
+            AnnotatedExpressionKind::SetLiteral { elements } => {
+                Rvalue::Compute(elements.iter().map(|element| self.operand(element)).collect())
+            }
+            AnnotatedExpressionKind::Call { function, arguments } => self.call(function, arguments),
+            AnnotatedExpressionKind::Query { .. } => Rvalue::Compute(Vec::new()),
+            AnnotatedExpressionKind::If { condition, then_block, else_block } => {
+                let result = self.temp(expr.result_type.clone());
+                self.branch(condition, then_block, else_block.as_ref(), Some(result));
+                Rvalue::Use(use_place(Place::local(result), &expr.result_type))
+            }
+            AnnotatedExpressionKind::Match { expression, arms } => {
+                let result = self.temp(expr.result_type.clone());
+                self.match_arms(expression, arms, Some(result));
+                Rvalue::Use(use_place(Place::local(result), &expr.result_type))
+            }
+        }
+    }
+
+    /// Calls to the program's own functions move their by-value arguments;
+    /// built-ins only read theirs. Collection and string methods borrow their
+    /// receiver, mutably for `push`, `pop`, `insert` and `remove`; only `push`
+    /// and `insert` move their arguments, into the collection.
+    fn call(&mut self, function: &str, arguments: &[AnnotatedExpression]) -> Rvalue {
+        let signatures = self.signatures;
+        let collection_method = ["list::", "map::", "set::", "string::"]
+            .iter()
+            .find_map(|prefix| function.strip_prefix(prefix));
+        let (receiver, moves_arguments) = if let Some(method) = collection_method {
+            let receiver = arguments
+                .first()
+                .filter(|receiver| !matches!(receiver.result_type, ResolvedType::Reference(..)));
+            let mutable = matches!(method, "push" | "pop" | "insert" | "remove");
+            (receiver.map(|receiver| (receiver, mutable)), matches!(method, "push" | "insert"))
+        } else {
+            let Some(parameters) = signatures.get(function) else {
+                return Rvalue::Compute(arguments.iter().map(|argument| self.read(argument)).collect());
+            };
+            let receiver = match (function.contains("::"), parameters.first(), arguments.first()) {
+                (true, Some(ResolvedType::Reference(_, mutable)), Some(receiver))
+                    if !matches!(receiver.result_type, ResolvedType::Reference(..)) =>
+                {
+                    Some((receiver, *mutable))
+                }
+                _ => None,
+            };
+            (receiver, true)
+        };
+        let Some((receiver, mutable)) = receiver else {
+            return Rvalue::Compute(
+                arguments
+                    .iter()
+                    .map(|argument| if moves_arguments { self.operand(argument) } else { self.read(argument) })
+                    .collect(),
+            );
+        };
+
+        // Two-phase borrow: `v.push(v.len())` evaluates the arguments before
+        // `v` is borrowed for the call
+        let receiver_place = self.place_of(receiver);
+        let mut operands = vec![Operand::Constant];
+        for argument in &arguments[1..] {
+            let operand = if moves_arguments { self.operand(argument) } else { self.read(argument) };
+            let value = self.temp(argument.result_type.clone());
+            self.push(Place::local(value), Rvalue::Use(operand));
+            operands.push(use_place(Place::local(value), &argument.result_type));
+        }
+        let (kind, reference_type) = if mutable {
+            (BorrowKind::Mutable, ResolvedType::Reference(Box::new(receiver.result_type.clone()), true))
+        } else {
+            (BorrowKind::Immutable, ResolvedType::Reference(Box::new(receiver.result_type.clone()), false))
+        };
+        let reference = self.temp(reference_type);
+        self.push(Place::local(reference), Rvalue::Ref(kind, receiver_place));
+        operands[0] = Operand::Copy(Place::local(reference));
+        Rvalue::Compute(operands)
+    }
+
+    /// The value of an expression, moved out of its place unless Copy
+    fn operand(&mut self, expr: &AnnotatedExpression) -> Operand {
+        match self.rvalue(expr) {
+            Rvalue::Use(operand) => operand,
+            rvalue => {
+                let value = self.temp(expr.result_type.clone());
+                self.push(Place::local(value), rvalue);
+                use_place(Place::local(value), &expr.result_type)
+            }
+        }
+    }
+
+    /// The value of an expression, read without moving it
+    fn read(&mut self, expr: &AnnotatedExpression) -> Operand {
+        match self.operand(expr) {
+            Operand::Move(place) => Operand::Copy(place),
+            operand => operand,
+        }
+    }
+
+    /// The place an expression denotes; other expressions are evaluated into a temporary
+    fn place_of(&mut self, expr: &AnnotatedExpression) -> Place {
+        match &expr.expr {
+            AnnotatedExpressionKind::Identifier(name) if self.lookup(name).is_some() => {
+                Place::local(self.lookup(name).expect("declared local"))
+            }
+            AnnotatedExpressionKind::FieldAccess { object, field } => {
+                self.deref_place(object).project(Projection::Field(field.clone()))
+            }
+            AnnotatedExpressionKind::Index { object, index } => {
+                if let Operand::Copy(index) = self.read(index) {
+                    let position = self.temp(ResolvedType::Int);
+                    self.push(Place::local(position), Rvalue::Use(Operand::Copy(index)));
+                }
+                self.deref_place(object).project(Projection::Index)
+            }
+            AnnotatedExpressionKind::Unary(unary) if unary.operator == UnaryOperator::Dereference => {
+                self.place_of(&unary.operand).project(Projection::Deref)
+            }
+            _ => {
+                let rvalue = self.rvalue(expr);
+                let value = self.temp(expr.result_type.clone());
+                self.push(Place::local(value), rvalue);
+                Place::local(value)
+            }
+        }
+    }
+
+    /// The place of an object whose fields or elements are accessed; `r.x`
+    /// goes through the reference `r`
+    fn deref_place(&mut self, object: &AnnotatedExpression) -> Place {
+        let place = self.place_of(object);
+        match object.result_type {
+            ResolvedType::Reference(..) => place.project(Projection::Deref),
+            _ => place,
+        }
+    }
+}
+
+fn is_assignment(operator: &BinaryOperator) -> bool {
+    matches!(
+        operator,
+        BinaryOperator::Assign
+            | BinaryOperator::AddAssign
+            | BinaryOperator::SubtractAssign
+            | BinaryOperator::MultiplyAssign
+            | BinaryOperator::DivideAssign
+    )
+}
+
+fn use_place(place: Place, ty: &ResolvedType) -> Operand {
+    if !is_copy(ty) && place.is_movable() {
+        Operand::Move(place)
+    } else {
+        Operand::Copy(place)
+    }
+}
+
+/// A `&` or `&mut` taken at a statement
+#[derive(Debug, Clone)]
+struct Loan {
+    place: Place,
+    kind: BorrowKind,
+}
+
+/// Dataflow state before a statement
+#[derive(Debug, Clone, Default, PartialEq)]
+struct State {
+    /// Loans each local may hold, directly or through the references it contains
+    holders: Vec<HashSet<usize>>,
+    /// Places moved out of on some path here
+    moved: HashSet<Place>,
+    /// Locals of a `let` without a value that some path here leaves unassigned
+    uninit: HashSet<Local>,
+    /// Assign-once locals some path here has assigned
+    assigned: HashSet<Local>,
+}
+
+impl State {
+    fn join(&mut self, other: &State) -> bool {
+        let before = self.size();
+        for (holder, loans) in self.holders.iter_mut().zip(&other.holders) {
+            holder.extend(loans);
+        }
+        self.moved.extend(other.moved.iter().cloned());
+        self.uninit.extend(&other.uninit);
+        self.assigned.extend(&other.assigned);
+        before != self.size()
+    }
+
+    fn size(&self) -> (usize, usize, usize, usize) {
+        let loans = self.holders.iter().map(HashSet::len).sum::<usize>();
+        (loans, self.moved.len(), self.uninit.len(), self.assigned.len())
+    }
+}
+
+/// Check the moves and borrows of a lowered body, reporting the first error
+pub fn borrow_check(body: &Body) -> Result<(), SemanticError> {
+    BorrowChecker::new(body).check()
+}
+
+struct BorrowChecker<'a> {
+    body: &'a Body,
+    loans: Vec<Loan>,
+    /// Loan taken by the statement at each `(block, index)`
+    loan_at: HashMap<(BlockId, usize), usize>,
+    /// Locals live before each statement of each block, and before its terminator
+    live: Vec<Vec<HashSet<Local>>>,
+}
+
+impl<'a> BorrowChecker<'a> {
+    fn new(body: &'a Body) -> Self {
+        let mut loans = Vec::new();
+        let mut loan_at = HashMap::new();
+        for (block_id, block) in body.blocks.iter().enumerate() {
+            for (index, statement) in block.statements.iter().enumerate() {
+                if let Rvalue::Ref(kind, place) = &statement.rvalue {
+                    loan_at.insert((block_id, index), loans.len());
+                    loans.push(Loan { place: place.clone(), kind: kind.clone() });
+                }
+            }
+        }
+        let live = liveness(body);
+        BorrowChecker { body, loans, loan_at, live }
+    }
+
+    fn check(&self) -> Result<(), SemanticError> {
+        let entry_states = self.entry_states();
+        for (block_id, block) in self.body.blocks.iter().enumerate() {
+            let Some(mut state) = entry_states[block_id].clone() else {
+                continue;
+            };
+            for (index, statement) in block.statements.iter().enumerate() {
+                self.check_statement(&state, (block_id, index), statement)
+                    .map_err(|error| error.with_span(Some(statement.span).filter(|span| !span.is_empty())))?;
+                self.transfer(&mut state, (block_id, index), statement);
+            }
+            if let Terminator::Switch(operand, _) = &block.terminator {
+                let live_loans = self.live_loans(&state, &self.live[block_id][block.statements.len()]);
+                self.check_operand(&state, &live_loans, operand)
+                    .map_err(|error| error.with_span(Some(block.span).filter(|span| !span.is_empty())))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forward dataflow to a fixpoint; `None` for blocks no path reaches
+    fn entry_states(&self) -> Vec<Option<State>> {
+        let mut states: Vec<Option<State>> = vec![None; self.body.blocks.len()];
+        states[0] = Some(State {
+            holders: vec![HashSet::new(); self.body.locals.len()],
+            ..State::default()
+        });
+        let mut pending = vec![0];
+        while let Some(block_id) = pending.pop() {
+            let mut state = states[block_id].clone().expect("reached block");
+            let block = &self.body.blocks[block_id];
+            for (index, statement) in block.statements.iter().enumerate() {
+                self.transfer(&mut state, (block_id, index), statement);
+            }
+            for &successor in block.terminator.successors() {
+                let changed = match &mut states[successor] {
+                    Some(existing) => existing.join(&state),
+                    None => {
+                        states[successor] = Some(state.clone());
+                        true
+                    }
+                };
+                if changed {
+                    pending.push(successor);
+                }
+            }
+        }
+        states
+    }
+
+    fn transfer(&self, state: &mut State, location: (BlockId, usize), statement: &Statement) {
+        let mut loans = HashSet::new();
+        if let Rvalue::Ref(_, place) = &statement.rvalue {
+            loans.insert(self.loan_at[&location]);
+            // A reborrow through `r` keeps the loans of `r`
+            if place.through_reference() {
+                loans.extend(state.holders[place.local].iter().copied());
+            }
+        }
+        for operand in statement.rvalue.operands() {
+            if let Some(place) = operand.place() {
+                loans.extend(state.holders[place.local].iter().copied());
+            }
+            if let Operand::Move(place) = operand {
+                state.moved.insert(place.clone());
+            }
+        }
+
+        let place = &statement.place;
+        state.moved.retain(|moved| !place.is_prefix_of(moved));
+        if statement.rvalue == Rvalue::Uninit {
+            state.uninit.insert(place.local);
+            state.assigned.remove(&place.local);
+        } else if place.projection.is_empty() {
+            state.uninit.remove(&place.local);
+            if self.body.locals[place.local].assign_once {
+                state.assigned.insert(place.local);
+            }
+        }
+        let holder = &mut state.holders[place.local];
+        if place.projection.is_empty() {
+            holder.clear();
+        }
+        if may_hold_reference(&self.body.locals[place.local].ty) {
+            holder.extend(loans);
+        }
+    }
+
+    /// Loans some live local may still use
+    fn live_loans(&self, state: &State, live: &HashSet<Local>) -> Vec<&Loan> {
+        let mut loans: Vec<usize> = live.iter().flat_map(|local| state.holders[*local].iter().copied()).collect();
+        loans.sort_unstable();
+        loans.dedup();
+        loans.into_iter().map(|loan| &self.loans[loan]).collect()
+    }
+
+    fn check_statement(&self, state: &State, location: (BlockId, usize), statement: &Statement) -> Result<(), SemanticError> {
+        let live_loans = self.live_loans(state, &self.live[location.0][location.1]);
+        for operand in statement.rvalue.operands() {
+            self.check_operand(state, &live_loans, operand)?;
+        }
+
+        if let Rvalue::Ref(kind, place) = &statement.rvalue {
+            self.check_initialized(state, place)?;
+            self.check_moved(state, place)?;
+            let conflict = live_loans.iter().find(|loan| {
+                loan.place.overlaps(place) && (*kind == BorrowKind::Mutable || loan.kind == BorrowKind::Mutable)
+            });
+            if let Some(loan) = conflict {
+                return Err(SemanticError::ConflictingBorrow(format!(
+                    "cannot borrow `{}` as {} because it is also borrowed as {}",
+                    self.body.describe(place),
+                    kind_name(kind),
+                    kind_name(&loan.kind)
+                )));
+            }
+        }
+
+        let place = &statement.place;
+        if !place.projection.is_empty() {
+            self.check_initialized(state, place)?;
+        } else if statement.rvalue != Rvalue::Uninit && state.assigned.contains(&place.local) {
+            return Err(SemanticError::AssignToImmutable(self.body.describe(place)));
+        }
+        if let Some(moved) = state.moved.iter().find(|moved| moved.is_prefix_of(place) && *moved != place) {
+            return Err(SemanticError::UseAfterMove(format!(
+                "`{}` is assigned after `{}` was moved",
+                self.body.describe(place),
+                self.body.describe(moved)
+            )));
+        }
+        if live_loans.iter().any(|loan| loan.place.overlaps(place)) {
+            return Err(SemanticError::WriteWhileBorrowed(format!(
+                "`{}` is assigned while it is borrowed",
+                self.body.describe(place)
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_operand(&self, state: &State, live_loans: &[&Loan], operand: &Operand) -> Result<(), SemanticError> {
+        match operand {
+            Operand::Copy(place) => {
+                self.check_initialized(state, place)?;
+                self.check_moved(state, place)?;
+                if live_loans.iter().any(|loan| loan.kind == BorrowKind::Mutable && loan.place.overlaps(place)) {
+                    return Err(SemanticError::ConflictingBorrow(format!(
+                        "`{}` is used while it is mutably borrowed",
+                        self.body.describe(place)
+                    )));
+                }
+            }
+            Operand::Move(place) => {
+                self.check_initialized(state, place)?;
+                self.check_moved(state, place)?;
+                if live_loans.iter().any(|loan| loan.place.overlaps(place)) {
+                    return Err(SemanticError::ConflictingBorrow(format!(
+                        "`{}` is moved while it is borrowed",
+                        self.body.describe(place)
+                    )));
+                }
+            }
+            Operand::Constant => {}
+        }
+        Ok(())
+    }
+
+    fn check_initialized(&self, state: &State, place: &Place) -> Result<(), SemanticError> {
+        if state.uninit.contains(&place.local) {
+            return Err(SemanticError::UseOfUninitialized(format!(
+                "`{}` is used before it is assigned",
+                self.body.describe(&Place::local(place.local))
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_moved(&self, state: &State, place: &Place) -> Result<(), SemanticError> {
+        match state.moved.iter().find(|moved| moved.overlaps(place)) {
+            Some(moved) if moved == place => Err(SemanticError::UseAfterMove(format!(
+                "`{}` is used after it was moved",
+                self.body.describe(place)
+            ))),
+            Some(moved) => Err(SemanticError::UseAfterMove(format!(
+                "`{}` is used after `{}` was moved",
+                self.body.describe(place),
+                self.body.describe(moved)
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn kind_name(kind: &BorrowKind) -> &'static str {
+    match kind {
+        BorrowKind::Immutable => "immutable",
+        BorrowKind::Mutable => "mutable",
+    }
+}
+
+/// Backward liveness: the locals that may be used again before each statement
+/// and terminator
+fn liveness(body: &Body) -> Vec<Vec<HashSet<Local>>> {
+    let live_before_block = |block: &BasicBlock, live_out: HashSet<Local>| -> Vec<HashSet<Local>> {
+        let mut live = live_out;
+        match &block.terminator {
+            Terminator::Switch(operand, _) => live.extend(operand.place().map(|place| place.local)),
+            Terminator::Return => {
+                live.insert(RETURN_PLACE);
+            }
+            Terminator::Goto(_) | Terminator::Unreachable => {}
+        }
+        let mut points = vec![live.clone()];
+        for statement in block.statements.iter().rev() {
+            // `p.x = ...` and `*r = ...` need the local; only `p = ...` replaces it
+            if statement.place.projection.is_empty() {
+                live.remove(&statement.place.local);
+            } else {
+                live.insert(statement.place.local);
+            }
+            live.extend(statement.rvalue.operands().iter().filter_map(|operand| operand.place()).map(|place| place.local));
+            if let Rvalue::Ref(_, place) = &statement.rvalue {
+                live.insert(place.local);
+            }
+            points.push(live.clone());
+        }
+        points.reverse();
+        points
+    };
+
+    let mut live_in: Vec<HashSet<Local>> = vec![HashSet::new(); body.blocks.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (block_id, block) in body.blocks.iter().enumerate().rev() {
+            let live_out = block.terminator.successors().iter().flat_map(|successor| live_in[*successor].iter().copied()).collect();
+            let entry = live_before_block(block, live_out).swap_remove(0);
+            if entry != live_in[block_id] {
+                live_in[block_id] = entry;
+                changed = true;
+            }
+        }
+    }
+
+    body.blocks
+        .iter()
+        .map(|block| {
+            let live_out = block.terminator.successors().iter().flat_map(|successor| live_in[*successor].iter().copied()).collect();
+            live_before_block(block, live_out)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompilerOptions, Lexer, Parser, SemanticAnalyzer};
+
+    fn check(source: &str) -> Result<(), SemanticError> {
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        SemanticAnalyzer::new(&CompilerOptions::default()).analyze(ast).map(|_| ())
+    }
+
+    fn main_body(body: &str) -> String {
+        format!(
+            "struct Point {{ x: int, y: int }}
+             fn consume(p: Point) -> int {{ return p.x; }}
+             fn main() -> int {{
+                 {}
+                 return 0;
+             }}",
+            body
+        )
+    }
+
+    #[test]
+    fn test_borrows_end_at_their_last_use() {
+        for body in [
+            "let mut x = 1; let r = &x; let y = *r; x = 2;",
+            "let mut x = 1; let r = &mut x; *r += 1; let s = &x; let y = *s;",
+            "let mut p = Point { x: 1, y: 2 }; let r = &mut p; r.x = 3; let q = &p; let y = q.x;",
+            "let p = Point { x: 1, y: 2 }; let c = true; if c { consume(p); } else { consume(p); }",
+            "let mut p = Point { x: 1, y: 2 }; consume(p); p = Point { x: 3, y: 4 }; consume(p);",
+        ] {
+            assert!(check(&main_body(body)).is_ok(), "{}: {:?}", body, check(&main_body(body)));
+        }
+    }
+
+    #[test]
+    fn test_moves_and_conflicts_on_paths() {
+        let cases = [
+            ("let mut x = 1; let r = &x; x = 2; let y = *r;", "`x` is assigned while it is borrowed"),
+            ("let mut x = 1; let r = &mut x; let y = x; *r = 2;", "`x` is used while it is mutably borrowed"),
+            ("let mut x = 1; let r = &mut x; let s = &x; *r = 2;", "cannot borrow `x` as immutable because it is also borrowed as mutable"),
+            ("let p = Point { x: 1, y: 2 }; let c = true; if c { consume(p); } consume(p);", "`p` is used after it was moved"),
+            ("let p = Point { x: 1, y: 2 }; let mut i = 0; while i < 2 { consume(p); i += 1; }", "`p` is used after it was moved"),
+            ("let p = Point { x: 1, y: 2 }; let q = p; let y = p.x;", "`p.x` is used after `p` was moved"),
+        ];
+        for (body, message) in cases {
+            let error = check(&main_body(body)).unwrap_err();
+            assert_eq!(error.inner().to_string().split_once(": ").map(|(_, text)| text), Some(message), "{}", body);
+            assert!(error.span().is_some(), "{}", body);
+        }
+    }
+
+    #[test]
+    fn test_let_without_a_value_is_assigned_once_on_every_path() {
+        for body in [
+            "let x: int; let c = true; if c { x = 1; } else { x = 2; } let y = x;",
+            "let mut x: int; x = 1; x = 2; let y = x;",
+            "let mut i = 0; while i < 2 { let x: int; x = i; i += x + 1; }",
+        ] {
+            assert!(check(&main_body(body)).is_ok(), "{}: {:?}", body, check(&main_body(body)));
+        }
+
+        let cases = [
+            ("let x: int; let y = x;", "`x` is used before it is assigned"),
+            ("let x: int; let c = true; if c { x = 1; } let y = x;", "`x` is used before it is assigned"),
+            ("let p: Point; let r = &p;", "`p` is used before it is assigned"),
+        ];
+        for (body, message) in cases {
+            let error = check(&main_body(body)).unwrap_err();
+            assert!(matches!(error.inner(), SemanticError::UseOfUninitialized(_)), "{}: {:?}", body, error);
+            assert_eq!(error.inner().to_string().split_once(": ").map(|(_, text)| text), Some(message), "{}", body);
+        }
+
+        for body in [
+            "let x: int; x = 1; x = 2;",
+            "let x: int; let c = true; if c { x = 1; } x = 2;",
+            "let x: int; let mut i = 0; while i < 2 { x = i; i += 1; }",
+        ] {
+            let error = check(&main_body(body)).unwrap_err();
+            assert!(matches!(error.inner(), SemanticError::AssignToImmutable(name) if name == "x"), "{}: {:?}", body, error);
+        }
+    }
+
+    #[test]
+    fn test_lowering_builds_a_cfg() {
+        let tokens = Lexer::new("fn pick(c: bool) -> int { let mut x = 0; if c { x = 1; } else { x = 2; } return x; }").tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        let program = SemanticAnalyzer::new(&CompilerOptions::default()).analyze(ast).unwrap();
+        let function = functions(&program.items).next().unwrap();
+        let body = lower(function, &signatures(&program.items));
+
+        assert!(matches!(body.blocks[0].terminator, Terminator::Switch(_, ref targets) if targets.len() == 2));
+        assert_eq!(body.locals[1].name.as_deref(), Some("c"));
+        let joins = body.blocks.iter().filter(|block| block.terminator == Terminator::Goto(3)).count();
+        assert_eq!(joins, 2);
+        let through_x = Place::local(2).project(Projection::Deref);
+        assert_eq!(body.describe(&through_x), "*x");
+        assert_eq!(body.describe(&through_x.project(Projection::Field("y".to_string()))), "x.y");
+    }
+}
@@ -3,21 +3,38 @@
 //! This module implements semantic analysis for the AlBayan programming language.
 //! It performs type checking, scope resolution, ownership analysis, and logic validation.
 
+pub mod builtin_types;
+pub mod const_eval;
+pub mod dyn_trait_system;
+pub mod format_string;
+pub mod knowledge_check;
 pub mod logic_analyzer;
+pub mod mir;
 pub mod ownership;
 pub mod symbol_table;
+pub mod taint_check;
 pub mod type_checker;
+pub mod warnings;
 
+use crate::lexer::LineIndex;
+use crate::modules::graph::ModuleGraph;
 use crate::parser::ast::*;
 use crate::CompilerOptions;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
+pub use format_string::FormatPiece;
 pub use ownership::{BorrowKind, DestroyInfo, OwnershipAnalyzer};
-pub use symbol_table::{FunctionInfo, StructFieldInfo, SymbolTable};
-pub use type_checker::TypeChecker;
+pub use symbol_table::{FunctionInfo, StructFieldInfo, SymbolTable, DROP_TRAIT};
+pub use type_checker::{CollectionMethod, Substitution, TypeChecker};
+pub use warnings::{LintLevel, SemanticWarning, WarningKind, WarningLevels};
 
 // نظام تعدد الأشكال الديناميكي - الأولوية القصوى للخبير
-use crate::codegen::{DynTraitCodeGenerator, VTableManager};
+use dyn_trait_system::{DynTraitCodeGenerator, VTableManager};
+
+/// Built-ins that stop the program with a message and source location when
+/// they fail; see `SemanticAnalyzer::analyze_panic_call`
+pub const PANIC_INTRINSICS: [&str; 5] = ["panic", "assert", "assert_true", "assert_eq", "assert_ne"];
 
 /// Main semantic analyzer
 pub struct SemanticAnalyzer {
@@ -28,6 +45,12 @@ pub struct SemanticAnalyzer {
     dyn_trait_codegen: DynTraitCodeGenerator,
     options: CompilerOptions,
     errors: Vec<SemanticError>,
+    /// Warnings of the files analyzed so far, at their configured levels
+    warnings: Vec<SemanticWarning>,
+    /// Number of enclosing `while`/`for` loops, for validating `break`/`continue`
+    loop_depth: usize,
+    /// Path and line index of the file being analyzed, for the locations of panics
+    source: Option<(String, LineIndex)>,
 }
 
 impl SemanticAnalyzer {
@@ -41,13 +64,17 @@ impl SemanticAnalyzer {
             dyn_trait_codegen: DynTraitCodeGenerator::new(),
             options: options.clone(),
             errors: Vec::new(),
+            warnings: Vec::new(),
+            loop_depth: 0,
+            source: None,
         };
 
-        // Register std::ai functions (Expert recommendation: Priority 1)
-        analyzer.register_std_ai_functions();
-
-        // Register std::torch functions (Expert recommendation: Priority 2)
-        analyzer.register_std_torch_functions();
+        // Register std::ai and std::torch functions; builds without the `ai`
+        // feature report them as unavailable instead
+        if cfg!(feature = "ai") {
+            analyzer.register_std_ai_functions();
+            analyzer.register_std_torch_functions();
+        }
 
         // Register std::math_ai functions (Expert specification: Priority 1)
         analyzer.register_std_math_ai_functions();
@@ -58,12 +85,29 @@ impl SemanticAnalyzer {
         // Register std::thinking functions (Expert Priority 3: ThinkingCore)
         analyzer.register_thinking_core_functions();
 
+        // Register std::math_ai::adaptive_equations model selection functions
+        analyzer.register_adaptive_equation_functions();
+
         // Register built-in functions (Expert fix: print function)
         analyzer.register_builtin_functions();
 
+        // Register BasicShape, ShapeProperty, RenderedImage and InferenceResult
+        builtin_types::register_artistic_types(&mut analyzer.symbol_table);
+
+        // `impl Drop for T` needs the built-in trait known to dynamic dispatch too
+        analyzer
+            .dyn_trait_codegen
+            .vtable_manager
+            .register_trait_methods(DROP_TRAIT, vec!["drop".to_string()]);
+
         analyzer
     }
 
+    /// How often the symbol table reused a memoized resolution
+    pub fn cache_stats(&self) -> symbol_table::CacheStats {
+        self.symbol_table.cache_stats()
+    }
+
     /// Analyze the AST and return an annotated version
     pub fn analyze(&mut self, program: Program) -> Result<AnnotatedProgram, SemanticError> {
         // Two-pass analysis
@@ -75,8 +119,70 @@ impl SemanticAnalyzer {
         self.analyze_program(&program)
     }
 
+    /// Analyze the modules of a multi-file program as one program. Each
+    /// module is declared and analyzed after the modules it imports, with its
+    /// functions and constants qualified by its name; an error in an imported
+    /// module says which one it is in.
+    pub fn analyze_modules(&mut self, graph: &ModuleGraph) -> Result<AnnotatedProgram, SemanticError> {
+        let mut items = Vec::new();
+        for module in graph.modules() {
+            let prefix = graph.prefix(module);
+            self.symbol_table.enter_module(prefix, module.scope());
+            self.source = std::fs::read_to_string(&module.path)
+                .ok()
+                .map(|text| (module.path.display().to_string(), LineIndex::new(&text)));
+            let analyzed = self
+                .collect_symbols(&module.program)
+                .and_then(|_| self.analyze_items(&module.program))
+                .and_then(|items| self.collect_warnings(&module.program).map(|_| items));
+            match (analyzed, prefix) {
+                (Ok(analyzed), _) => items.extend(analyzed),
+                (Err(error), None) => return Err(error),
+                (Err(error), Some(name)) => {
+                    return Err(SemanticError::InModule { module: name.to_string(), error: Box::new(error) })
+                }
+            }
+        }
+        self.symbol_table.enter_module(None, HashMap::new());
+        self.source = None;
+        self.check_bodies(items)
+    }
+
     /// First pass: collect all top-level declarations
     fn collect_symbols(&mut self, program: &Program) -> Result<(), SemanticError> {
+        // Constants come first so array sizes in signatures and fields can use them
+        let const_decls: Vec<&ConstDecl> = program
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Const(decl) => Some(decl),
+                _ => None,
+            })
+            .collect();
+        let mut values = const_eval::evaluate_items(const_decls.iter().copied())?;
+        for decl in const_decls {
+            let span = Some(decl.span);
+            let const_type = self.symbol_table.resolve_type_name(&decl.const_type).map_err(|e| e.with_span(span))?;
+            // A second item with the same name finds the value already taken
+            let Some(value) = values.remove(&decl.name) else {
+                return Err(SemanticError::Redefinition(decl.name.clone()).with_span(span));
+            };
+            if value.value_type() != const_type {
+                return Err(SemanticError::TypeMismatch { expected: const_type, found: value.value_type() }.with_span(span));
+            }
+            self.symbol_table
+                .declare_constant(&decl.name, decl.visibility, const_type, value)
+                .map_err(|e| e.with_span(span))?;
+        }
+
+        // Concepts come before relations, which may use them as argument types
+        for item in &program.items {
+            if let Item::Concept(concept) = item {
+                self.symbol_table.declare_concept(concept).map_err(|e| e.with_span(Some(concept.span)))?;
+            }
+        }
+        self.symbol_table.declare_is_a();
+
         for item in &program.items {
             match item {
                 Item::Function(func) => {
@@ -104,32 +210,106 @@ impl SemanticAnalyzer {
                 Item::Impl(impl_decl) => {
                     self.symbol_table.declare_impl(impl_decl)?; // NEWLY ADDED: Expert recommendation
                 }
-                Item::Relation(relation_decl) => {
-                    self.symbol_table
-                        .declare_relation(&relation_decl.name, relation_decl)?;
-                }
                 _ => {} // Rules, facts, modules, etc. handled in second pass
             }
         }
+
+        // Relations come after the structs their compound arguments name
+        for item in &program.items {
+            if let Item::Relation(relation_decl) = item {
+                self.symbol_table.declare_relation(&relation_decl.name, relation_decl)?;
+            }
+        }
+
+        // Trait impls are checked once every trait and impl has been declared
+        for item in &program.items {
+            if let Item::Impl(impl_decl) = item {
+                self.symbol_table
+                    .check_trait_impl(impl_decl)
+                    .map_err(|e| e.with_span(item.span()))?;
+            }
+        }
         Ok(())
     }
 
     /// Second pass: detailed analysis
     fn analyze_program(&mut self, program: &Program) -> Result<AnnotatedProgram, SemanticError> {
+        let annotated_items = self.analyze_items(program)?;
+        self.collect_warnings(program)?;
+        self.check_bodies(annotated_items)
+    }
+
+    /// Add the warnings of the file just analyzed to those of the program
+    /// at their levels; a denied warning fails the analysis
+    fn collect_warnings(&mut self, program: &Program) -> Result<(), SemanticError> {
+        let mut warnings = self.symbol_table.take_warnings();
+        for name in self.symbol_table.unused_imports() {
+            let using = program.items.iter().find_map(|item| match item {
+                Item::Using(using) if using.alias.as_ref().or(using.path.last()) == Some(&name) => Some(using),
+                _ => None,
+            });
+            warnings.push(SemanticWarning {
+                kind: WarningKind::UnusedImport,
+                name: using.map_or(name, |using| using.path.join("::")),
+                span: using.map(|using| using.span),
+                file: None,
+            });
+        }
+
+        let file = self.source.as_ref().map(|(path, _)| path.clone());
+        for warning in warnings {
+            match self.options.warnings.level(warning.kind) {
+                LintLevel::Allow => {}
+                LintLevel::Warn => self.warnings.push(SemanticWarning { file: file.clone(), ..warning }),
+                LintLevel::Deny => {
+                    let span = warning.span;
+                    return Err(SemanticError::DeniedWarning(warning).with_span(span));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Analyze each item of a program
+    fn analyze_items(&mut self, program: &Program) -> Result<Vec<AnnotatedItem>, SemanticError> {
         let mut annotated_items = Vec::new();
 
         for item in &program.items {
-            let annotated_item = self.analyze_item(item)?;
+            self.options.cancellation.check()?;
+            // The sentences of a `semantic` block are read by the NLU layer
+            if let Item::Semantic(_) = item {
+                continue;
+            }
+            let annotated_item = self
+                .analyze_item(item)
+                .map_err(|e| e.with_span(item.span()))?;
             annotated_items.push(annotated_item);
         }
 
         if !self.errors.is_empty() {
             return Err(self.errors.remove(0)); // Return first error for now
         }
+        Ok(annotated_items)
+    }
+
+    /// Check the analyzed program as a whole: the negations of its rules,
+    /// then the moves and borrows of its function bodies
+    fn check_bodies(&mut self, annotated_items: Vec<AnnotatedItem>) -> Result<AnnotatedProgram, SemanticError> {
+        // Negation must not run through recursion across all the rules
+        logic_analyzer::check_stratification(&annotated_items)?;
+
+        // Moves and borrows are checked on each body's control-flow graph
+        let signatures = mir::signatures(&annotated_items);
+        for function in mir::functions(&annotated_items) {
+            self.options.cancellation.check()?;
+            let body = mir::lower(function, &signatures);
+            mir::borrow_check(&body).map_err(|e| e.with_span(Some(function.span)))?;
+        }
 
         Ok(AnnotatedProgram {
             items: annotated_items,
             symbol_table: self.symbol_table.clone(),
+            warnings: std::mem::take(&mut self.warnings),
         })
     }
 
@@ -137,7 +317,8 @@ impl SemanticAnalyzer {
     fn analyze_item(&mut self, item: &Item) -> Result<AnnotatedItem, SemanticError> {
         match item {
             Item::Function(func) => {
-                let annotated_func = self.analyze_function(func)?;
+                let mut annotated_func = self.analyze_function(func, false)?;
+                annotated_func.name = self.symbol_table.qualify(&func.name);
                 Ok(AnnotatedItem::Function(annotated_func))
             }
             Item::Struct(struct_decl) => {
@@ -152,6 +333,18 @@ impl SemanticAnalyzer {
                 let annotated_rule = self.analyze_rule(rule_decl)?;
                 Ok(AnnotatedItem::Rule(annotated_rule))
             }
+            Item::Fact(fact_decl) => {
+                let annotated_fact = self.analyze_fact(fact_decl)?;
+                Ok(AnnotatedItem::Fact(annotated_fact))
+            }
+            Item::Concept(concept_decl) => {
+                let annotated_concept = self.analyze_concept(concept_decl)?;
+                Ok(AnnotatedItem::Concept(annotated_concept))
+            }
+            Item::Constraint(constraint_decl) => {
+                let annotated_constraint = self.analyze_constraint(constraint_decl)?;
+                Ok(AnnotatedItem::Constraint(annotated_constraint))
+            }
             Item::Enum(enum_decl) => {
                 let annotated_enum = self.analyze_enum(enum_decl)?;
                 Ok(AnnotatedItem::Enum(annotated_enum))
@@ -168,14 +361,28 @@ impl SemanticAnalyzer {
                 let annotated_using = self.analyze_using(using_decl)?; // NEWLY ADDED: Expert fix for using statements
                 Ok(AnnotatedItem::Using(annotated_using))
             }
+            Item::Const(const_decl) => {
+                let (value_type, value) = self
+                    .symbol_table
+                    .lookup_constant(&const_decl.name)
+                    .cloned()
+                    .ok_or_else(|| SemanticError::UndefinedVariable(const_decl.name.clone()))?;
+                Ok(AnnotatedItem::Const(AnnotatedConst {
+                    name: self.symbol_table.qualify(&const_decl.name),
+                    value_type,
+                    value: value.to_literal(),
+                }))
+            }
             _ => todo!("Analysis for other item types not yet implemented"),
         }
     }
 
-    /// Analyze a function
+    /// Analyze a function; unread parameters are reported unless the
+    /// signature is `fixed`
     fn analyze_function(
         &mut self,
         func: &FunctionDecl,
+        fixed: bool,
     ) -> Result<AnnotatedFunction, SemanticError> {
         // Enter function scope
         self.symbol_table.enter_scope();
@@ -198,12 +405,17 @@ impl SemanticAnalyzer {
         };
 
         // Add parameters to scope (Expert recommendation: Priority 2 - &self and &mut self support)
+        // `self` takes the type bound to `Self` by the enclosing trait or impl
+        let self_type = self.symbol_table.self_type().cloned().unwrap_or(ResolvedType::Unit);
         let mut annotated_params = Vec::new();
         for param in &func.parameters {
             match param {
                 Parameter::Regular { name, param_type } => {
                     let resolved_type = self.symbol_table.resolve_type_name(param_type)?;
                     self.symbol_table.declare_variable(name, &resolved_type)?;
+                    if !fixed {
+                        self.symbol_table.warn_if_unread(name, WarningKind::UnusedParameter, Some(func.span));
+                    }
                     // Declare in ownership analyzer too (Expert recommendation)
                     self.ownership_analyzer
                         .declare_variable(name, resolved_type.clone(), false)?;
@@ -219,9 +431,7 @@ impl SemanticAnalyzer {
                     });
                 }
                 Parameter::SelfValue => {
-                    // self parameter - type will be determined by the impl context
-                    // For now, we'll use a placeholder type
-                    let self_type = ResolvedType::Unit; // TODO: Get actual self type from context
+                    let self_type = self_type.clone();
                     self.symbol_table.declare_variable("self", &self_type)?;
                     self.ownership_analyzer
                         .declare_variable("self", self_type.clone(), false)?;
@@ -232,8 +442,7 @@ impl SemanticAnalyzer {
                 }
                 Parameter::SelfRef => {
                     // &self parameter
-                    let self_type = ResolvedType::Unit; // TODO: Get actual self type from context
-                    let self_ref_type = ResolvedType::Reference(Box::new(self_type), false);
+                    let self_ref_type = ResolvedType::Reference(Box::new(self_type.clone()), false);
                     self.symbol_table.declare_variable("self", &self_ref_type)?;
                     self.ownership_analyzer.declare_variable(
                         "self",
@@ -247,8 +456,7 @@ impl SemanticAnalyzer {
                 }
                 Parameter::SelfMutRef => {
                     // &mut self parameter
-                    let self_type = ResolvedType::Unit; // TODO: Get actual self type from context
-                    let self_mut_ref_type = ResolvedType::Reference(Box::new(self_type), true);
+                    let self_mut_ref_type = ResolvedType::Reference(Box::new(self_type.clone()), true);
                     self.symbol_table
                         .declare_variable("self", &self_mut_ref_type)?;
                     self.ownership_analyzer.declare_variable(
@@ -294,6 +502,7 @@ impl SemanticAnalyzer {
             parameters: annotated_params,
             return_type,
             body: annotated_body,
+            span: func.span,
         })
     }
 
@@ -402,46 +611,28 @@ impl SemanticAnalyzer {
             None
         };
 
+        // `Self` and `Self::Item` stay abstract until the trait is implemented
+        self.symbol_table.declare_trait_types(trait_decl)?;
+        let self_type = ResolvedType::GenericParam("Self".to_string());
+
         let mut annotated_methods = Vec::new();
 
         for method in &trait_decl.methods {
             let mut annotated_params = Vec::new();
             for param in &method.parameters {
-                match param {
+                let (name, param_type) = match param {
                     Parameter::Regular { name, param_type } => {
-                        let resolved_type = self.symbol_table.resolve_type_name(param_type)?;
-                        annotated_params.push(AnnotatedParameter {
-                            name: name.clone(),
-                            param_type: resolved_type,
-                        });
-                    }
-                    Parameter::SelfValue => {
-                        // self parameter in trait method
-                        let self_type = ResolvedType::Unit; // Will be resolved when trait is implemented
-                        annotated_params.push(AnnotatedParameter {
-                            name: "self".to_string(),
-                            param_type: self_type,
-                        });
+                        (name.clone(), self.symbol_table.resolve_type_name(param_type)?)
                     }
+                    Parameter::SelfValue => ("self".to_string(), self_type.clone()),
                     Parameter::SelfRef => {
-                        // &self parameter in trait method
-                        let self_type = ResolvedType::Unit; // Will be resolved when trait is implemented
-                        let self_ref_type = ResolvedType::Reference(Box::new(self_type), false);
-                        annotated_params.push(AnnotatedParameter {
-                            name: "self".to_string(),
-                            param_type: self_ref_type,
-                        });
+                        ("self".to_string(), ResolvedType::Reference(Box::new(self_type.clone()), false))
                     }
                     Parameter::SelfMutRef => {
-                        // &mut self parameter in trait method
-                        let self_type = ResolvedType::Unit; // Will be resolved when trait is implemented
-                        let self_mut_ref_type = ResolvedType::Reference(Box::new(self_type), true);
-                        annotated_params.push(AnnotatedParameter {
-                            name: "self".to_string(),
-                            param_type: self_mut_ref_type,
-                        });
+                        ("self".to_string(), ResolvedType::Reference(Box::new(self_type.clone()), true))
                     }
-                }
+                };
+                annotated_params.push(AnnotatedParameter { name, param_type });
             }
 
             let return_type = if let Some(ret_type) = &method.return_type {
@@ -450,8 +641,15 @@ impl SemanticAnalyzer {
                 None
             };
 
+            // Default bodies see their parameters, with `self` typed as `Self`
             let body = if let Some(method_body) = &method.body {
-                Some(self.analyze_block(method_body)?)
+                self.symbol_table.enter_scope();
+                let body = annotated_params
+                    .iter()
+                    .try_for_each(|param| self.symbol_table.declare_variable(&param.name, &param.param_type))
+                    .and_then(|_| self.analyze_block(method_body));
+                self.symbol_table.exit_scope();
+                Some(body?)
             } else {
                 None
             };
@@ -489,10 +687,17 @@ impl SemanticAnalyzer {
             None
         };
 
+        // Bind `Self` to the implementing type and `Self::Item` to the impl's definitions
+        self.symbol_table.declare_impl_types(impl_decl)?;
+        if let Some(trait_name) = &impl_decl.trait_name {
+            self.check_associated_types(trait_name, impl_decl)?;
+        }
+
         let mut annotated_methods = Vec::new();
 
         for method in &impl_decl.methods {
-            let annotated_method = self.analyze_function(method)?;
+            // A trait fixes the parameters of the methods implementing it
+            let annotated_method = self.analyze_function(method, impl_decl.trait_name.is_some())?;
             annotated_methods.push(annotated_method);
         }
 
@@ -522,6 +727,32 @@ impl SemanticAnalyzer {
         Ok(annotated_impl)
     }
 
+    /// Check that a trait impl defines exactly the associated types its trait declares
+    fn check_associated_types(&self, trait_name: &str, impl_decl: &ImplDecl) -> Result<(), SemanticError> {
+        let Some(trait_info) = self.symbol_table.lookup_trait(trait_name) else {
+            return Ok(());
+        };
+
+        for (name, _) in &impl_decl.associated_types {
+            if !trait_info.associated_types.contains(name) {
+                return Err(SemanticError::UndefinedAssociatedType {
+                    trait_name: trait_name.to_string(),
+                    name: name.clone(),
+                });
+            }
+        }
+        for name in &trait_info.associated_types {
+            if !impl_decl.associated_types.iter().any(|(defined, _)| defined == name) {
+                return Err(SemanticError::MissingAssociatedType {
+                    trait_name: trait_name.to_string(),
+                    type_name: impl_decl.type_name.clone(),
+                    name: name.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Analyze generic parameters (Expert recommendation: Priority 1)
     fn analyze_generic_params(
         &mut self,
@@ -551,20 +782,38 @@ impl SemanticAnalyzer {
         &mut self,
         relation: &RelationDecl,
     ) -> Result<AnnotatedRelation, SemanticError> {
-        let mut resolved_arg_types = Vec::new();
-
-        for arg_type in &relation.arg_types {
-            let resolved_type = self.type_checker.resolve_type(arg_type)?;
-            resolved_arg_types.push(resolved_type);
-        }
-
-        // Register relation in symbol table for logic programming (Expert recommendation)
-        self.symbol_table
-            .declare_relation(&relation.name, relation)?;
+        // The relation was registered in the symbol table by the declaration
+        // pass, which resolved its concept-typed arguments as well
+        let info = self
+            .symbol_table
+            .lookup_relation(&relation.name)
+            .cloned()
+            .ok_or_else(|| SemanticError::UndefinedRelation(relation.name.clone()))?;
 
         Ok(AnnotatedRelation {
             name: relation.name.clone(),
-            arg_types: resolved_arg_types,
+            arg_types: info.arg_types,
+            arg_concepts: info.arg_concepts,
+            tabled: relation.tabled,
+        })
+    }
+
+    /// Analyze a concept declaration: no concept may end up below itself
+    fn analyze_concept(&mut self, concept: &ConceptDecl) -> Result<AnnotatedConcept, SemanticError> {
+        let mut seen = HashSet::new();
+        let mut pending: Vec<&str> = concept.parents.iter().map(String::as_str).collect();
+        while let Some(next) = pending.pop() {
+            if next == concept.name {
+                return Err(SemanticError::ConceptCycle(concept.name.clone()));
+            }
+            if seen.insert(next) {
+                pending.extend(self.symbol_table.concept_parents(next).iter().map(String::as_str));
+            }
+        }
+
+        Ok(AnnotatedConcept {
+            name: concept.name.clone(),
+            parents: concept.parents.clone(),
         })
     }
 
@@ -572,14 +821,38 @@ impl SemanticAnalyzer {
     fn analyze_rule(&mut self, rule: &RuleDecl) -> Result<AnnotatedRule, SemanticError> {
         // Analyze head and body terms
         let annotated_head = self.analyze_logic_term(&rule.head)?;
+        let relation_goals = rule
+            .body
+            .iter()
+            .filter(|term| term.calls_relation())
+            .map(|term| self.analyze_logic_term(term))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The relations give the variables the types the built-ins are checked with
+        let mut variable_types = HashMap::new();
+        for term in std::iter::once(&annotated_head).chain(&relation_goals) {
+            for (name, var_type) in term.args.iter().flat_map(AnnotatedLogicArg::typed_variables) {
+                variable_types.entry(name.to_string()).or_insert_with(|| var_type.clone());
+            }
+        }
 
+        let mut relation_goals = relation_goals.into_iter();
         let mut annotated_body = Vec::new();
         for term in &rule.body {
-            let annotated_term = self.analyze_logic_term(term)?;
+            let annotated_term = if term.is_cut() {
+                AnnotatedLogicTerm::cut()
+            } else if term.is_builtin() {
+                self.analyze_builtin_goal(term, &mut variable_types)?
+            } else if term.is_aggregate() {
+                self.analyze_aggregate_goal(term, &mut variable_types)?
+            } else {
+                relation_goals.next().expect("relation goals are analyzed in body order")
+            };
             annotated_body.push(annotated_term);
         }
 
-        // Check that all variables in the head are bound in the body
+        // Check that all variables in the head, and in negated goals, are
+        // bound by a positive goal of the body
         self.check_rule_safety(&annotated_head, &annotated_body)?;
 
         // Validate that all relations in the rule exist (Expert recommendation)
@@ -588,9 +861,26 @@ impl SemanticAnalyzer {
         Ok(AnnotatedRule {
             head: annotated_head,
             body: annotated_body,
+            no_reorder: rule.no_reorder,
         })
     }
 
+    /// Analyze a fact: its relation must exist and the argument types must match
+    fn analyze_fact(&mut self, fact: &FactDecl) -> Result<AnnotatedFact, SemanticError> {
+        let term = self.analyze_logic_term(&fact.term)?;
+        Ok(AnnotatedFact { term })
+    }
+
+    /// Analyze an integrity constraint: its goals are checked like a rule body
+    fn analyze_constraint(&mut self, constraint: &ConstraintDecl) -> Result<AnnotatedConstraint, SemanticError> {
+        let goals = constraint
+            .goals
+            .iter()
+            .map(|goal| self.analyze_logic_term(goal))
+            .collect::<Result<_, _>>()?;
+        Ok(AnnotatedConstraint { goals })
+    }
+
     /// Validate that all relations in a rule exist (Expert recommendation: Priority 2)
     fn validate_rule_relations(
         &self,
@@ -603,7 +893,7 @@ impl SemanticAnalyzer {
         }
 
         // Check body relations
-        for term in body {
+        for term in body.iter().filter(|term| term.calls_relation()) {
             if self.symbol_table.lookup_relation(&term.name).is_none() {
                 return Err(SemanticError::UndefinedRelation(term.name.clone()));
             }
@@ -623,6 +913,7 @@ impl SemanticAnalyzer {
                 .symbol_table
                 .lookup_relation(&term.name)
                 .ok_or_else(|| SemanticError::UndefinedRelation(term.name.clone()))?;
+            self.symbol_table.check_access(symbol_table::ItemKind::Relation, &term.name)?;
 
             // Check argument count
             if term.args.len() != info.arg_types.len() {
@@ -646,6 +937,7 @@ impl SemanticAnalyzer {
             name: term.name.clone(),
             args: annotated_args,
             relation_type: relation_info,
+            negated: term.negated,
         })
     }
 
@@ -694,6 +986,226 @@ impl SemanticAnalyzer {
                 }
                 Ok(AnnotatedLogicArg::FloatConstant(*f))
             }
+            LogicArg::Operation { .. } => self.analyze_logic_operand(arg, Some(expected_type), &HashMap::new()),
+            LogicArg::Goal(goal) => Err(SemanticError::MisplacedGoal(goal.to_string())),
+            // A compound term is a value of the struct its functor names,
+            // with an argument for each field in declaration order
+            LogicArg::Compound(term) => {
+                let fields = self.struct_term_fields(term)?;
+                let term_type = ResolvedType::Struct(term.name.clone());
+                if *expected_type != term_type {
+                    return Err(SemanticError::TypeMismatch { expected: expected_type.clone(), found: term_type });
+                }
+                let args = term
+                    .args
+                    .iter()
+                    .zip(&fields)
+                    .map(|(arg, field)| self.analyze_logic_arg(arg, &field.field_type))
+                    .collect::<Result<_, _>>()?;
+                Ok(AnnotatedLogicArg::Compound { name: term.name.clone(), args, term_type })
+            }
+            LogicArg::List { items, tail } => {
+                let ResolvedType::List(item_type) = expected_type else {
+                    return Err(SemanticError::TypeMismatch {
+                        expected: expected_type.clone(),
+                        found: ResolvedType::List(Box::new(expected_type.clone())),
+                    });
+                };
+                let items = items
+                    .iter()
+                    .map(|item| self.analyze_logic_arg(item, item_type))
+                    .collect::<Result<_, _>>()?;
+                let tail = match tail {
+                    Some(tail) => Some(Box::new(self.analyze_logic_arg(tail, expected_type)?)),
+                    None => None,
+                };
+                Ok(AnnotatedLogicArg::List { items, tail, list_type: expected_type.clone() })
+            }
+        }
+    }
+
+    /// Fields of the struct a compound term names, one per argument; a term
+    /// naming no struct is taken for a goal outside an aggregate
+    fn struct_term_fields(&self, term: &LogicTerm) -> Result<Vec<symbol_table::StructFieldInfo>, SemanticError> {
+        let Some(symbol_table::TypeKind::Struct(fields)) = self.symbol_table.lookup_type(&term.name).map(|info| &info.kind)
+        else {
+            return Err(SemanticError::MisplacedGoal(term.to_string()));
+        };
+        self.symbol_table.check_access(symbol_table::ItemKind::Type, &term.name)?;
+        if term.args.len() != fields.len() {
+            return Err(SemanticError::ArityMismatch { expected: fields.len(), found: term.args.len() });
+        }
+        Ok(fields.clone())
+    }
+
+    /// Analyze an aggregate. Its goal is checked like a rule body goal; its
+    /// variables are its own, so only the result binds a variable of the
+    /// rule. The result of `findall` is a list of its template's type, of
+    /// `count` an int, and of `sum_of`, `min_of` and `max_of` the number the
+    /// template is.
+    fn analyze_aggregate_goal(
+        &mut self,
+        term: &LogicTerm,
+        variable_types: &mut HashMap<String, ResolvedType>,
+    ) -> Result<AnnotatedLogicTerm, SemanticError> {
+        let goal_index = aggregate_goal_index(&term.name);
+        if term.args.len() != goal_index + 2 {
+            return Err(SemanticError::ArityMismatch { expected: goal_index + 2, found: term.args.len() });
+        }
+        let LogicArg::Goal(goal) = &term.args[goal_index] else {
+            let misplaced = term.args.iter().find(|arg| matches!(arg, LogicArg::Goal(_)));
+            return Err(SemanticError::MisplacedGoal(misplaced.map_or_else(String::new, LogicArg::to_string)));
+        };
+        if !goal.calls_relation() {
+            return Err(SemanticError::MisplacedGoal(goal.to_string()));
+        }
+        let goal = self.analyze_logic_term(goal)?;
+
+        let mut goal_types = variable_types.clone();
+        for (name, var_type) in goal.args.iter().flat_map(AnnotatedLogicArg::typed_variables) {
+            goal_types.entry(name.to_string()).or_insert_with(|| var_type.clone());
+        }
+        let template = match goal_index {
+            0 => None,
+            _ => Some(self.analyze_logic_operand(&term.args[0], None, &goal_types)?),
+        };
+        let result_type = match (term.name.as_str(), &template) {
+            ("findall", Some(template)) => ResolvedType::List(Box::new(template.value_type())),
+            (_, Some(template)) if !is_number(&template.value_type()) => {
+                return Err(SemanticError::TypeMismatch { expected: ResolvedType::Int, found: template.value_type() })
+            }
+            (_, Some(template)) => template.value_type(),
+            (_, None) => ResolvedType::Int,
+        };
+
+        let result = term.args.last().expect("aggregates have a result");
+        if let LogicArg::Variable(name) = result {
+            variable_types.entry(name.clone()).or_insert_with(|| result_type.clone());
+        }
+        let result = self.analyze_logic_operand(result, Some(&result_type), variable_types)?;
+
+        let mut args: Vec<AnnotatedLogicArg> = template.into_iter().collect();
+        args.push(AnnotatedLogicArg::Goal(Box::new(goal)));
+        args.push(result);
+        let relation_type = RelationInfo {
+            name: term.name.clone(),
+            arg_types: args.iter().map(AnnotatedLogicArg::value_type).collect(),
+            arg_concepts: vec![None; args.len()],
+        };
+        Ok(AnnotatedLogicTerm { name: term.name.clone(), args, relation_type, negated: term.negated })
+    }
+
+    /// Analyze a comparison of a rule body. Its variables have the types the
+    /// relation goals give them; `is` and `=` give a variable none of them
+    /// binds the type of their right side. Both sides must have the same
+    /// type, and a number for ordering and `is`.
+    fn analyze_builtin_goal(
+        &self,
+        term: &LogicTerm,
+        variable_types: &mut HashMap<String, ResolvedType>,
+    ) -> Result<AnnotatedLogicTerm, SemanticError> {
+        let [left, right] = term.args.as_slice() else {
+            return Err(SemanticError::ArityMismatch { expected: 2, found: term.args.len() });
+        };
+        if let (LogicArg::Variable(name), "is" | "=") = (left, term.name.as_str()) {
+            if !variable_types.contains_key(name) {
+                let value = self.analyze_logic_operand(right, None, variable_types)?;
+                variable_types.insert(name.clone(), value.value_type());
+            }
+        }
+
+        // A constant takes the type of the other side
+        let (left, right) = if matches!(left, LogicArg::Constant(_)) {
+            let right = self.analyze_logic_operand(right, None, variable_types)?;
+            (self.analyze_logic_operand(left, Some(&right.value_type()), variable_types)?, right)
+        } else {
+            let left = self.analyze_logic_operand(left, None, variable_types)?;
+            let right = self.analyze_logic_operand(right, Some(&left.value_type()), variable_types)?;
+            (left, right)
+        };
+        let value_type = left.value_type();
+        if !matches!(term.name.as_str(), "=" | "!=") && !is_number(&value_type) {
+            return Err(SemanticError::TypeMismatch { expected: ResolvedType::Int, found: value_type });
+        }
+
+        let relation_type = RelationInfo {
+            name: term.name.clone(),
+            arg_types: vec![value_type.clone(), value_type],
+            arg_concepts: vec![None, None],
+        };
+        Ok(AnnotatedLogicTerm { name: term.name.clone(), args: vec![left, right], relation_type, negated: term.negated })
+    }
+
+    /// Analyze a side of a comparison, or an operand of its arithmetic; with
+    /// `expected` its type must be that one
+    fn analyze_logic_operand(
+        &self,
+        arg: &LogicArg,
+        expected: Option<&ResolvedType>,
+        variable_types: &HashMap<String, ResolvedType>,
+    ) -> Result<AnnotatedLogicArg, SemanticError> {
+        let annotated = match arg {
+            LogicArg::Variable(name) => {
+                let var_type = variable_types
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| SemanticError::CannotInferType(name.clone()))?;
+                AnnotatedLogicArg::Variable { name: name.clone(), var_type }
+            }
+            LogicArg::Constant(name) => AnnotatedLogicArg::Constant {
+                name: name.clone(),
+                const_type: expected.cloned().unwrap_or(ResolvedType::String),
+            },
+            LogicArg::StringConstant(text) => AnnotatedLogicArg::StringConstant(text.clone()),
+            LogicArg::IntConstant(value) => AnnotatedLogicArg::IntConstant(*value),
+            LogicArg::FloatConstant(value) => AnnotatedLogicArg::FloatConstant(*value),
+            LogicArg::Operation { op, left, right } => {
+                let left = self.analyze_logic_operand(left, expected, variable_types)?;
+                let value_type = left.value_type();
+                if !is_number(&value_type) {
+                    return Err(SemanticError::TypeMismatch { expected: ResolvedType::Int, found: value_type });
+                }
+                let right = self.analyze_logic_operand(right, Some(&value_type), variable_types)?;
+                AnnotatedLogicArg::Operation { op: op.clone(), left: Box::new(left), right: Box::new(right), value_type }
+            }
+            LogicArg::Goal(goal) => return Err(SemanticError::MisplacedGoal(goal.to_string())),
+            LogicArg::Compound(term) => {
+                let fields = self.struct_term_fields(term)?;
+                let args = term
+                    .args
+                    .iter()
+                    .zip(&fields)
+                    .map(|(arg, field)| self.analyze_logic_operand(arg, Some(&field.field_type), variable_types))
+                    .collect::<Result<_, _>>()?;
+                AnnotatedLogicArg::Compound { name: term.name.clone(), args, term_type: ResolvedType::Struct(term.name.clone()) }
+            }
+            // The items have the type of the first, unless `expected` gives it
+            LogicArg::List { items, tail } => {
+                let mut item_type = match expected {
+                    Some(ResolvedType::List(item_type)) => Some(item_type.as_ref().clone()),
+                    _ => None,
+                };
+                let mut annotated_items = Vec::new();
+                for item in items {
+                    let item = self.analyze_logic_operand(item, item_type.as_ref(), variable_types)?;
+                    item_type.get_or_insert_with(|| item.value_type());
+                    annotated_items.push(item);
+                }
+                let list_type = ResolvedType::List(Box::new(
+                    item_type.ok_or_else(|| SemanticError::CannotInferType(arg.to_string()))?,
+                ));
+                let tail = match tail {
+                    Some(tail) => Some(Box::new(self.analyze_logic_operand(tail, Some(&list_type), variable_types)?)),
+                    None => None,
+                };
+                AnnotatedLogicArg::List { items: annotated_items, tail, list_type }
+            }
+        };
+        match expected {
+            Some(expected) if annotated.value_type() != *expected => {
+                Err(SemanticError::TypeMismatch { expected: expected.clone(), found: annotated.value_type() })
+            }
+            _ => Ok(annotated),
         }
     }
 
@@ -705,19 +1217,17 @@ impl SemanticAnalyzer {
     ) -> Result<(), SemanticError> {
         // Extract variables from head
         let mut head_vars = std::collections::HashSet::new();
-        for arg in &head.args {
-            if let AnnotatedLogicArg::Variable { name, .. } = arg {
-                head_vars.insert(name.clone());
-            }
+        for name in head.args.iter().flat_map(AnnotatedLogicArg::variables) {
+            head_vars.insert(name.to_string());
         }
 
-        // Extract variables from body
+        // Extract variables from the positive goals of the body, which
+        // include those `is` and `=` bind; an aggregate binds its result only
         let mut body_vars = std::collections::HashSet::new();
-        for term in body {
-            for arg in &term.args {
-                if let AnnotatedLogicArg::Variable { name, .. } = arg {
-                    body_vars.insert(name.clone());
-                }
+        for term in body.iter().filter(|term| !term.negated) {
+            let binding = if term.is_aggregate() { &term.args[term.args.len() - 1..] } else { &term.args[..] };
+            for arg in binding {
+                body_vars.extend(arg.variables().into_iter().map(str::to_string));
             }
         }
 
@@ -728,18 +1238,48 @@ impl SemanticAnalyzer {
             }
         }
 
+        // A negated goal only tests values the positive goals found
+        for term in body.iter().filter(|term| term.negated) {
+            for name in term.args.iter().flat_map(AnnotatedLogicArg::variables) {
+                if !body_vars.contains(name) {
+                    return Err(SemanticError::UnsafeNegation { variable: name.to_string(), relation: term.name.clone() });
+                }
+            }
+        }
+
         Ok(())
     }
 
     /// Analyze a block of statements
     fn analyze_block(&mut self, block: &Block) -> Result<AnnotatedBlock, SemanticError> {
+        self.analyze_block_with(block, false)
+    }
+
+    /// Analyze a block whose value is used, the branch of an if expression or
+    /// the arm of a match: a trailing `if` with an `else` is its value
+    fn analyze_value_block(&mut self, block: &Block) -> Result<AnnotatedBlock, SemanticError> {
+        self.analyze_block_with(block, true)
+    }
+
+    fn analyze_block_with(&mut self, block: &Block, is_value: bool) -> Result<AnnotatedBlock, SemanticError> {
         self.symbol_table.enter_scope();
         self.ownership_analyzer.enter_scope();
 
         let mut annotated_statements = Vec::new();
-        for stmt in &block.statements {
-            let annotated_stmt = self.analyze_statement(stmt)?;
+        let mut spans = Vec::new();
+        for (position, stmt) in block.statements.iter().enumerate() {
+            self.options.cancellation.check()?;
+            let annotated_stmt = match stmt {
+                Statement::If(if_stmt)
+                    if is_value && if_stmt.else_block.is_some() && position + 1 == block.statements.len() =>
+                {
+                    self.analyze_if_expression(if_stmt).map(AnnotatedStatement::Expression)
+                }
+                _ => self.analyze_statement(stmt),
+            }
+            .map_err(|e| e.with_span(stmt.span()))?;
             annotated_statements.push(annotated_stmt);
+            spans.push(stmt.span().unwrap_or_default());
         }
 
         // Exit scope and get variables that need destruction (Expert recommendation: Priority 1)
@@ -748,8 +1288,13 @@ impl SemanticAnalyzer {
 
         Ok(AnnotatedBlock {
             statements: annotated_statements,
+            spans,
             // Store variables that need destruction for IRGenerator (Expert recommendation)
             variables_to_destroy: Some(variables_to_destroy),
+            trailing_semicolon: matches!(
+                block.statements.last(),
+                Some(Statement::Expression(ExpressionStatement { semicolon: true, .. }))
+            ),
         })
     }
 
@@ -764,18 +1309,32 @@ impl SemanticAnalyzer {
                 let annotated_ret = self.analyze_return_statement(ret_stmt)?;
                 Ok(AnnotatedStatement::Return(annotated_ret))
             }
-            // Special-case break/continue pseudo-statements early to avoid any lookup paths
-            Statement::Expression(Expression::Identifier(name)) if name == "__break__" || name == "__continue__" => {
-                let annotated_expr = AnnotatedExpression {
-                    expr: AnnotatedExpressionKind::Identifier(name.clone()),
-                    result_type: ResolvedType::Unit,
-                };
-                Ok(AnnotatedStatement::Expression(annotated_expr))
+            // break/continue are parsed as pseudo-identifier statements
+            Statement::Expression(ExpressionStatement { expression: Expression::Identifier(name), .. })
+                if name == "__break__" || name == "__continue__" =>
+            {
+                let keyword = name.trim_matches('_');
+                if self.loop_depth == 0 {
+                    return Err(SemanticError::ControlFlowOutsideLoop(keyword.to_string()));
+                }
+                Ok(if keyword == "break" {
+                    AnnotatedStatement::Break
+                } else {
+                    AnnotatedStatement::Continue
+                })
             }
-            Statement::Expression(expr) => {
-                let annotated_expr = self.analyze_expression(expr)?;
+            Statement::Expression(stmt) => {
+                let annotated_expr = self.analyze_expression(&stmt.expression)?;
                 Ok(AnnotatedStatement::Expression(annotated_expr))
             }
+            Statement::Assert(assert) => {
+                let update = self.analyze_fact_update(&assert.relation, &assert.args, assert.span)?;
+                Ok(AnnotatedStatement::Assert(update))
+            }
+            Statement::Retract(retract) => {
+                let update = self.analyze_fact_update(&retract.relation, &retract.args, retract.span)?;
+                Ok(AnnotatedStatement::Retract(update))
+            }
             Statement::Match(match_stmt) => {
                 let annotated_match = self.analyze_match_statement(match_stmt)?;
                 Ok(AnnotatedStatement::Match(annotated_match))
@@ -792,10 +1351,6 @@ impl SemanticAnalyzer {
                     });
                 }
 
-                // Analyze if statement with control flow merging in ownership analyzer
-                self.ownership_analyzer
-                    .analyze_if_statement_ownership(if_stmt)?;
-
                 // Analyze blocks for semantic correctness
                 let then_block = self.analyze_block(&if_stmt.then_block)?;
                 let else_block = if let Some(ref else_block) = if_stmt.else_block {
@@ -809,27 +1364,35 @@ impl SemanticAnalyzer {
                     condition,
                     then_block,
                     else_block,
+                    span: if_stmt.span,
                 };
                 Ok(AnnotatedStatement::If(annotated_if))
             }
             Statement::While(while_stmt) => {
-                // For now, treat while statements as expressions
-                let _condition = self.analyze_expression(&while_stmt.condition)?;
-                let _body = self.analyze_block(&while_stmt.body)?;
+                let condition = self.analyze_expression(&while_stmt.condition)?;
+                if !matches!(condition.result_type, ResolvedType::Bool) {
+                    return Err(SemanticError::TypeMismatch {
+                        expected: ResolvedType::Bool,
+                        found: condition.result_type,
+                    });
+                }
 
-                // Create a dummy expression for the while statement
-                let while_expr = AnnotatedExpression {
-                    result_type: ResolvedType::Int, // Placeholder
-                    expr: AnnotatedExpressionKind::Literal(Literal::Integer(0)),
-                };
-                Ok(AnnotatedStatement::Expression(while_expr))
+                self.loop_depth += 1;
+                let body = self.analyze_block(&while_stmt.body);
+                self.loop_depth -= 1;
+
+                Ok(AnnotatedStatement::While(AnnotatedWhileStatement {
+                    condition,
+                    body: body?,
+                    span: while_stmt.span,
+                }))
             }
             Statement::For(for_stmt) => {
                 // Semantic analysis for `for <var> in <iterable> { <body> }`
                 // 1) Analyze iterable and extract element type
-                let annotated_iterable = self.analyze_expression(&for_stmt.iterable)?;
-                let element_type = match &annotated_iterable.result_type {
-                    ResolvedType::List(inner) => (**inner).clone(),
+                let iterable = self.analyze_expression(&for_stmt.iterable)?;
+                let element_type = match &iterable.result_type {
+                    ResolvedType::List(inner) | ResolvedType::Vector(inner, _) => (**inner).clone(),
                     // In the future we can support ranges, strings, maps, etc.
                     other => {
                         return Err(SemanticError::TypeMismatch {
@@ -858,18 +1421,21 @@ impl SemanticAnalyzer {
                 );
 
                 // 4) Analyze loop body with loop variable in scope
-                let _annotated_body = self.analyze_block(&for_stmt.body)?;
+                self.loop_depth += 1;
+                let body = self.analyze_block(&for_stmt.body);
+                self.loop_depth -= 1;
 
-                // 5) Exit loop scope
+                // 5) Exit loop scope (also on error, so the scopes stay balanced)
                 let _vars_to_destroy = self.ownership_analyzer.exit_scope();
                 self.symbol_table.exit_scope();
 
-                // 6) For now, the for-loop is represented as a dummy expression result
-                let for_expr = AnnotatedExpression {
-                    result_type: ResolvedType::Int, // Placeholder
-                    expr: AnnotatedExpressionKind::Literal(Literal::Integer(0)),
-                };
-                Ok(AnnotatedStatement::Expression(for_expr))
+                Ok(AnnotatedStatement::For(AnnotatedForStatement {
+                    variable: for_stmt.variable.clone(),
+                    element_type,
+                    iterable,
+                    body: body?,
+                    span: for_stmt.span,
+                }))
             }
             Statement::Block(block) => {
                 // Analyze the block and create a dummy expression
@@ -891,13 +1457,63 @@ impl SemanticAnalyzer {
         }
     }
 
+    /// Analyze the fact of an `assert` or `retract` statement: the relation
+    /// must be declared and each argument a value of its type. A bare name
+    /// that is neither a variable nor a constant names an individual, like a
+    /// constant of a fact does, and so does a string given for an argument
+    /// typed by a concept or of `is_a`
+    fn analyze_fact_update(
+        &mut self,
+        relation: &str,
+        args: &[Expression],
+        span: Span,
+    ) -> Result<AnnotatedFactUpdate, SemanticError> {
+        let info = self
+            .symbol_table
+            .lookup_relation(relation)
+            .cloned()
+            .ok_or_else(|| SemanticError::UndefinedRelation(relation.to_string()).with_span(Some(span)))?;
+        self.symbol_table.check_access(symbol_table::ItemKind::Relation, relation)?;
+        if args.len() != info.arg_types.len() {
+            return Err(SemanticError::ArityMismatch { expected: info.arg_types.len(), found: args.len() }
+                .with_span(Some(span)));
+        }
+
+        let mut annotated_args = Vec::new();
+        for ((arg, expected), concept) in args.iter().zip(&info.arg_types).zip(&info.arg_concepts) {
+            if let Expression::Identifier(name) = arg {
+                let bound = self.symbol_table.lookup_variable(name).is_some()
+                    || self.symbol_table.lookup_constant(name).is_some();
+                if !bound && matches!(expected, ResolvedType::String) {
+                    annotated_args.push(AnnotatedFactArg::Atom(name.clone()));
+                    continue;
+                }
+            }
+            let value = self.analyze_expression(arg)?;
+            if !self.type_checker.types_compatible(expected, &value.result_type) {
+                return Err(SemanticError::TypeMismatch { expected: expected.clone(), found: value.result_type }
+                    .with_span(arg.span()));
+            }
+            annotated_args.push(if concept.is_some() || relation == "is_a" {
+                AnnotatedFactArg::Individual(value)
+            } else {
+                AnnotatedFactArg::Value(value)
+            });
+        }
+        Ok(AnnotatedFactUpdate { relation: relation.to_string(), args: annotated_args, location: self.location(span), span })
+    }
+
     /// Analyze a let statement
     fn analyze_let_statement(
         &mut self,
         let_stmt: &LetStatement,
     ) -> Result<AnnotatedLetStatement, SemanticError> {
+        if let Some(pattern) = &let_stmt.pattern {
+            return self.analyze_destructuring_let(let_stmt, pattern);
+        }
+
         let var_type = if let Some(type_annotation) = &let_stmt.var_type {
-            self.type_checker.resolve_type(type_annotation)?
+            self.resolve_annotation(type_annotation)?
         } else if let Some(initializer) = &let_stmt.initializer {
             let annotated_init = self.analyze_expression(initializer)?;
             annotated_init.result_type.clone()
@@ -905,14 +1521,21 @@ impl SemanticAnalyzer {
             return Err(SemanticError::CannotInferType(let_stmt.name.clone()));
         };
 
-        // Declare variable in current scope
-        self.symbol_table
-            .declare_variable(&let_stmt.name, &var_type)?;
+
+        // Declare variable in current scope; only `let mut` bindings, and a
+        // `let` without a value once, may be assigned
+        if let_stmt.initializer.is_some() {
+            self.symbol_table
+                .declare_binding(&let_stmt.name, &var_type, let_stmt.is_mutable)?;
+        } else {
+            self.symbol_table
+                .declare_uninitialized(&let_stmt.name, &var_type, let_stmt.is_mutable)?;
+        }
+        self.symbol_table.warn_if_unread(&let_stmt.name, WarningKind::UnusedVariable, Some(let_stmt.span));
 
         // Declare in ownership analyzer too (Expert recommendation)
-        // For now, assume variables are immutable by default
         self.ownership_analyzer
-            .declare_variable(&let_stmt.name, var_type.clone(), false)?;
+            .declare_variable(&let_stmt.name, var_type.clone(), let_stmt.is_mutable)?;
 
         // Register for destruction if needed (Expert recommendation: Priority 1)
         self.ownership_analyzer.register_for_destruction(
@@ -921,19 +1544,149 @@ impl SemanticAnalyzer {
             self.ownership_analyzer.get_scope_depth(),
         );
 
-        let annotated_initializer = if let Some(initializer) = &let_stmt.initializer {
+        let mut annotated_initializer = if let Some(initializer) = &let_stmt.initializer {
             Some(self.analyze_expression(initializer)?)
         } else {
             None
         };
 
+        // An empty `{}` or `#{}` has the key and value types of the annotation
+        if let Some(init) = annotated_initializer.as_mut() {
+            let empty = match &init.expr {
+                AnnotatedExpressionKind::MapLiteral { entries } => entries.is_empty(),
+                AnnotatedExpressionKind::SetLiteral { elements } => elements.is_empty(),
+                _ => false,
+            };
+            if empty && matches!(var_type, ResolvedType::Map(..) | ResolvedType::Set(_)) {
+                init.result_type = var_type.clone();
+            }
+        }
+
+        // The value must have the annotated type; an array literal is a list
+        // whose length is checked below
+        if let (Some(_), Some(init)) = (&let_stmt.var_type, annotated_initializer.as_ref()) {
+            let compatible = match (&var_type, &init.result_type) {
+                (ResolvedType::Vector(element, _), ResolvedType::List(found)) => {
+                    self.type_checker.types_compatible(element, found)
+                }
+                (expected, found) => self.type_checker.types_compatible(expected, found),
+            };
+            if !compatible {
+                return Err(SemanticError::TypeMismatch {
+                    expected: var_type.clone(),
+                    found: init.result_type.clone(),
+                });
+            }
+        }
+
+        // `let grid: [int; N] = ...` needs exactly N elements
+        if let ResolvedType::Vector(element_type, length) = &var_type {
+            let found = match (&let_stmt.initializer, annotated_initializer.as_ref().map(|init| &init.result_type)) {
+                (Some(Expression::Array(array)), _) => Some(array.elements.len()),
+                (_, Some(ResolvedType::Vector(_, found))) => Some(*found),
+                _ => None,
+            };
+            if let Some(found) = found.filter(|found| found != length) {
+                return Err(SemanticError::TypeMismatch {
+                    expected: var_type.clone(),
+                    found: ResolvedType::Vector(element_type.clone(), found),
+                });
+            }
+        }
+
         Ok(AnnotatedLetStatement {
             name: let_stmt.name.clone(),
+            pattern: None,
             var_type: var_type,
             initializer: annotated_initializer,
+            is_mutable: let_stmt.is_mutable,
+            span: let_stmt.span,
         })
     }
 
+    /// Resolve the type annotation of a `let`; array types go through the
+    /// symbol table, which knows the constants their sizes may name
+    fn resolve_annotation(&self, type_annotation: &Type) -> Result<ResolvedType, SemanticError> {
+        match type_annotation {
+            Type::Array(..) => self.symbol_table.resolve_type_name(type_annotation),
+            _ => self.type_checker.resolve_type(type_annotation),
+        }
+    }
+
+    /// Analyze `let (q, r) = value;`, binding every name of the pattern to
+    /// its element of the tuple
+    fn analyze_destructuring_let(
+        &mut self,
+        let_stmt: &LetStatement,
+        pattern: &Pattern,
+    ) -> Result<AnnotatedLetStatement, SemanticError> {
+        let initializer = let_stmt
+            .initializer
+            .as_ref()
+            .ok_or_else(|| SemanticError::CannotInferType(format!("({})", pattern.bindings().join(", "))))?;
+        let annotated_initializer = self.analyze_expression(initializer)?;
+        let var_type = match &let_stmt.var_type {
+            Some(type_annotation) => {
+                let declared = self.resolve_annotation(type_annotation)?;
+                if !self.type_checker.types_compatible(&declared, &annotated_initializer.result_type) {
+                    return Err(SemanticError::TypeMismatch {
+                        expected: declared,
+                        found: annotated_initializer.result_type,
+                    });
+                }
+                declared
+            }
+            None => annotated_initializer.result_type.clone(),
+        };
+
+        let annotated_pattern = self.bind_let_pattern(pattern, &var_type, let_stmt.is_mutable)?;
+        Ok(AnnotatedLetStatement {
+            name: let_stmt.name.clone(),
+            pattern: Some(annotated_pattern),
+            var_type,
+            initializer: Some(annotated_initializer),
+            is_mutable: let_stmt.is_mutable,
+            span: let_stmt.span,
+        })
+    }
+
+    fn bind_let_pattern(
+        &mut self,
+        pattern: &Pattern,
+        value_type: &ResolvedType,
+        is_mutable: bool,
+    ) -> Result<AnnotatedPattern, SemanticError> {
+        match (pattern, value_type) {
+            (Pattern::Wildcard, _) => Ok(AnnotatedPattern::Wildcard),
+            (Pattern::Identifier(name), _) => {
+                self.symbol_table.declare_binding(name, value_type, is_mutable)?;
+                self.ownership_analyzer
+                    .declare_variable(name, value_type.clone(), is_mutable)?;
+                self.ownership_analyzer.register_for_destruction(
+                    name,
+                    value_type.clone(),
+                    self.ownership_analyzer.get_scope_depth(),
+                );
+                Ok(AnnotatedPattern::Identifier(name.clone(), value_type.clone()))
+            }
+            (Pattern::Tuple(patterns), ResolvedType::Tuple(element_types))
+                if patterns.len() == element_types.len() =>
+            {
+                let elements = patterns
+                    .iter()
+                    .zip(element_types)
+                    .map(|(pattern, element_type)| self.bind_let_pattern(pattern, element_type, is_mutable))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(AnnotatedPattern::Tuple(elements, value_type.clone()))
+            }
+            (Pattern::Tuple(patterns), _) => Err(SemanticError::PatternTypeMismatch {
+                expected: value_type.clone(),
+                found: ResolvedType::Tuple(vec![ResolvedType::Unit; patterns.len()]),
+            }),
+            (other, _) => Err(SemanticError::Other(format!("refutable pattern {:?} in let", other))),
+        }
+    }
+
     /// Analyze a return statement (Expert recommendation: Priority 1 - Dangling Reference Prevention)
     fn analyze_return_statement(
         &mut self,
@@ -950,13 +1703,24 @@ impl SemanticAnalyzer {
             None
         };
 
-        Ok(AnnotatedReturnStatement { value })
+        Ok(AnnotatedReturnStatement {
+            value,
+            span: ret_stmt.span,
+        })
     }
 
     /// Analyze an expression
     fn analyze_expression(
         &mut self,
         expr: &Expression,
+    ) -> Result<AnnotatedExpression, SemanticError> {
+        self.analyze_expression_kind(expr)
+            .map_err(|e| e.with_span(expr.span()))
+    }
+
+    fn analyze_expression_kind(
+        &mut self,
+        expr: &Expression,
     ) -> Result<AnnotatedExpression, SemanticError> {
         match expr {
             Expression::Literal(lit) => {
@@ -977,14 +1741,23 @@ impl SemanticAnalyzer {
                     });
                 }
 
+                // Locals shadow constants; a constant is replaced with its value
+                if self.symbol_table.lookup_variable(name).is_none() {
+                    if let Some((const_type, value)) = self.symbol_table.lookup_constant(name) {
+                        self.symbol_table.check_access(symbol_table::ItemKind::Constant, name)?;
+                        return Ok(AnnotatedExpression {
+                            expr: AnnotatedExpressionKind::Literal(value.to_literal()),
+                            result_type: const_type.clone(),
+                        });
+                    }
+                }
+
+                self.symbol_table.mark_read(name);
                 let var_info = self
                     .symbol_table
                     .lookup_variable(name)
                     .ok_or_else(|| SemanticError::UndefinedVariable(name.clone()))?;
 
-                // Check read access (Expert recommendation)
-                self.ownership_analyzer.check_read_access(name)?;
-
                 Ok(AnnotatedExpression {
                     expr: AnnotatedExpressionKind::Identifier(name.clone()),
                     result_type: var_info.var_type.clone(),
@@ -995,8 +1768,14 @@ impl SemanticAnalyzer {
             Expression::Enum(enum_expr) => self.analyze_enum_expression(enum_expr),
             Expression::FieldAccess(field_access) => self.analyze_field_access(field_access),
             Expression::Array(array_expr) => self.analyze_array_literal(array_expr),
+            Expression::ArrayRepeat(repeat_expr) => self.analyze_array_repeat(repeat_expr),
+            Expression::Map(map_expr) => self.analyze_map_literal(map_expr),
+            Expression::Set(set_expr) => self.analyze_set_literal(set_expr),
+            Expression::Tuple(tuple_expr) => self.analyze_tuple_literal(tuple_expr),
             Expression::Index(index_expr) => self.analyze_index_access(index_expr),
             Expression::Match(match_expr) => self.analyze_match_expression(match_expr),
+            Expression::If(if_expr) => self.analyze_if_expression(if_expr),
+            Expression::Query(query) => self.analyze_query_expression(query),
             Expression::Call(call_expr) => self.analyze_call_expression(call_expr),
             Expression::Unary(unary_expr) => self.analyze_unary_expression(unary_expr),
             _ => todo!("Analysis for other expression types not yet implemented"),
@@ -1011,6 +1790,17 @@ impl SemanticAnalyzer {
         let left = self.analyze_expression(&bin_expr.left)?;
         let right = self.analyze_expression(&bin_expr.right)?;
 
+        if matches!(
+            bin_expr.operator,
+            BinaryOperator::Assign
+                | BinaryOperator::AddAssign
+                | BinaryOperator::SubtractAssign
+                | BinaryOperator::MultiplyAssign
+                | BinaryOperator::DivideAssign
+        ) {
+            self.check_assignable(&bin_expr.left)?;
+        }
+
         let result_type = self.type_checker.check_binary_operation(
             &bin_expr.operator,
             &left.result_type,
@@ -1027,6 +1817,43 @@ impl SemanticAnalyzer {
         })
     }
 
+    /// Check that an assignment target may be written: a `let mut` binding, or
+    /// a field or element of one or of a `&mut` reference. Writes to borrowed
+    /// places are reported by the MIR borrow checker.
+    fn check_assignable(&self, target: &Expression) -> Result<(), SemanticError> {
+        // Walk from `p.items[0].x` down to `p`
+        let mut projected = false;
+        let mut root = target;
+        loop {
+            root = match root {
+                Expression::FieldAccess(access) => access.object.as_ref(),
+                Expression::Index(index) => index.object.as_ref(),
+                Expression::Unary(unary) if unary.operator == UnaryOperator::Dereference => unary.operand.as_ref(),
+                _ => break,
+            };
+            projected = true;
+        }
+        let Expression::Identifier(name) = root else {
+            return Ok(());
+        };
+        let Some(var_info) = self.symbol_table.lookup_variable(name) else {
+            return Ok(());
+        };
+
+        match &var_info.var_type {
+            ResolvedType::Reference(_, false) if projected => {
+                return Err(SemanticError::AssignThroughSharedReference(name.clone()).with_span(target.span()));
+            }
+            ResolvedType::Reference(_, true) if projected => {}
+            // A `let` without a value may be assigned once on every path; the MIR checks that
+            _ if !var_info.is_mutable && var_info.is_initialized => {
+                return Err(SemanticError::AssignToImmutable(name.clone()).with_span(target.span()));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     /// Analyze a struct literal expression (as recommended by expert)
     fn analyze_struct_literal(
         &mut self,
@@ -1038,6 +1865,7 @@ impl SemanticAnalyzer {
                 .symbol_table
                 .lookup_type(&struct_expr.name)
                 .ok_or_else(|| SemanticError::UndefinedType(struct_expr.name.clone()))?;
+            self.symbol_table.check_access(symbol_table::ItemKind::Type, &struct_expr.name)?;
 
             match &struct_info.kind {
                 symbol_table::TypeKind::Struct(fields) => fields.clone(),
@@ -1053,6 +1881,7 @@ impl SemanticAnalyzer {
         // Check that all required fields are provided
         let mut provided_fields = std::collections::HashMap::new();
         let mut annotated_fields = Vec::new();
+        let mut substitution = Substitution::new();
 
         for (field_name, field_expr) in &struct_expr.fields {
             // Check if field exists in struct definition
@@ -1063,20 +1892,20 @@ impl SemanticAnalyzer {
                     struct_name: struct_expr.name.clone(),
                     field_name: field_name.clone(),
                 })?;
+            self.symbol_table
+                .check_access(symbol_table::ItemKind::Field, &format!("{}.{}", struct_expr.name, field_name))?;
 
             // Analyze field expression
             let annotated_field_expr = self.analyze_expression(field_expr)?;
 
-            // Check type compatibility
-            if !self
-                .type_checker
-                .types_compatible(&field_info.field_type, &annotated_field_expr.result_type)
-            {
-                return Err(SemanticError::TypeMismatch {
-                    expected: field_info.field_type.clone(),
-                    found: annotated_field_expr.result_type,
-                });
-            }
+            // Check type compatibility, inferring generic parameters from the values
+            self.type_checker
+                .unify(
+                    &field_info.field_type,
+                    &annotated_field_expr.result_type,
+                    &mut substitution,
+                )
+                .map_err(|e| e.with_span(field_expr.span()))?;
 
             provided_fields.insert(field_name.clone(), annotated_field_expr.clone());
             annotated_fields.push((field_name.clone(), annotated_field_expr));
@@ -1092,12 +1921,29 @@ impl SemanticAnalyzer {
             }
         }
 
+        // `Box { value: 5 }` has type Box<int>; parameters no field mentions stay generic
+        let result_type = match self.symbol_table.struct_generic_params(&struct_expr.name) {
+            Some(generics) => ResolvedType::Generic(
+                struct_expr.name.clone(),
+                generics
+                    .iter()
+                    .map(|generic| {
+                        substitution
+                            .get(generic)
+                            .cloned()
+                            .unwrap_or_else(|| ResolvedType::GenericParam(generic.clone()))
+                    })
+                    .collect(),
+            ),
+            None => ResolvedType::Struct(struct_expr.name.clone()),
+        };
+
         Ok(AnnotatedExpression {
             expr: AnnotatedExpressionKind::StructLiteral {
                 name: struct_expr.name.clone(),
                 fields: annotated_fields,
             },
-            result_type: ResolvedType::Struct(struct_expr.name.clone()),
+            result_type,
         })
     }
 
@@ -1106,12 +1952,30 @@ impl SemanticAnalyzer {
         &mut self,
         enum_expr: &EnumExpression,
     ) -> Result<AnnotatedExpression, SemanticError> {
+        // `shapes::area(2, 3)` and `shapes::UNIT` name a function or constant
+        // of an imported module rather than an enum variant
+        if self.symbol_table.lookup_type(&enum_expr.enum_name).is_none() {
+            let path = format!("{}::{}", enum_expr.enum_name, enum_expr.variant_name);
+            if let Some(arguments) = &enum_expr.fields {
+                if self.symbol_table.lookup_function(&path).is_some() {
+                    return self.analyze_function_call(&path, arguments);
+                }
+            } else if let Some((const_type, value)) = self.symbol_table.lookup_constant(&path) {
+                self.symbol_table.check_access(symbol_table::ItemKind::Constant, &path)?;
+                return Ok(AnnotatedExpression {
+                    expr: AnnotatedExpressionKind::Literal(value.to_literal()),
+                    result_type: const_type.clone(),
+                });
+            }
+        }
+
         // Look up enum definition in TypeSystem and clone the variants
         let enum_variants = {
             let enum_info = self
                 .symbol_table
                 .lookup_type(&enum_expr.enum_name)
                 .ok_or_else(|| SemanticError::UndefinedType(enum_expr.enum_name.clone()))?;
+            self.symbol_table.check_access(symbol_table::ItemKind::Type, &enum_expr.enum_name)?;
 
             match &enum_info.kind {
                 symbol_table::TypeKind::Enum(variants) => variants.clone(),
@@ -1196,9 +2060,38 @@ impl SemanticAnalyzer {
         // Analyze the object expression
         let annotated_object = self.analyze_expression(&field_access.object)?;
 
-        // Get the struct type
-        let struct_name = match &annotated_object.result_type {
+        // Get the struct type and, for generic structs, the bindings of its parameters.
+        // Fields are reached through references, as with `self.field` in a `&self` method.
+        let object_type = match annotated_object.result_type.clone() {
+            ResolvedType::Reference(inner, _) => *inner,
+            other => other,
+        };
+        let mut substitution = Substitution::new();
+        let struct_name = match &object_type {
             ResolvedType::Struct(name) => name,
+            ResolvedType::Generic(name, args) => {
+                if let Some(generics) = self.symbol_table.struct_generic_params(name) {
+                    substitution.extend(generics.iter().cloned().zip(args.iter().cloned()));
+                }
+                name
+            }
+            ResolvedType::Tuple(element_types) => {
+                // Tuple element access: t.0, t.1, ...
+                let index = field_access.field.parse::<usize>().map_err(|_| {
+                    SemanticError::UndefinedField {
+                        struct_name: format!("{:?}", annotated_object.result_type),
+                        field_name: field_access.field.clone(),
+                    }
+                })?;
+                let result_type = Self::tuple_element_type(element_types, index as i64)?;
+                return Ok(AnnotatedExpression {
+                    expr: AnnotatedExpressionKind::FieldAccess {
+                        object: Box::new(annotated_object),
+                        field: field_access.field.clone(),
+                    },
+                    result_type,
+                });
+            }
             _ => {
                 return Err(SemanticError::TypeMismatch {
                     expected: ResolvedType::Struct("any".to_string()),
@@ -1232,16 +2125,148 @@ impl SemanticAnalyzer {
                 struct_name: struct_name.clone(),
                 field_name: field_access.field.clone(),
             })?;
+        self.symbol_table
+            .check_access(symbol_table::ItemKind::Field, &format!("{}.{}", struct_name, field_access.field))?;
+
+        let result_type = self
+            .type_checker
+            .apply_substitution(&field_info.field_type, &substitution);
+
+        Ok(AnnotatedExpression {
+            expr: AnnotatedExpressionKind::FieldAccess {
+                object: Box::new(annotated_object),
+                field: field_access.field.clone(),
+            },
+            result_type,
+        })
+    }
+
+    /// Analyze a tuple literal expression; its type is the tuple of the element types
+    fn analyze_tuple_literal(
+        &mut self,
+        tuple_expr: &TupleExpression,
+    ) -> Result<AnnotatedExpression, SemanticError> {
+        let mut annotated_elements = Vec::new();
+        for element in &tuple_expr.elements {
+            annotated_elements.push(self.analyze_expression(element)?);
+        }
+        let element_types = annotated_elements
+            .iter()
+            .map(|element| element.result_type.clone())
+            .collect();
+
+        Ok(AnnotatedExpression {
+            expr: AnnotatedExpressionKind::Tuple {
+                elements: annotated_elements,
+            },
+            result_type: ResolvedType::Tuple(element_types),
+        })
+    }
+
+    /// Type of the element at a constant tuple index
+    fn tuple_element_type(element_types: &[ResolvedType], index: i64) -> Result<ResolvedType, SemanticError> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| element_types.get(i))
+            .cloned()
+            .ok_or(SemanticError::InvalidTupleIndex {
+                index: Some(index),
+                len: element_types.len(),
+            })
+    }
+
+    /// Analyze `[value; count]`, a fixed-size array of `count` copies of `value`
+    fn analyze_array_repeat(
+        &mut self,
+        repeat_expr: &ArrayRepeatExpression,
+    ) -> Result<AnnotatedExpression, SemanticError> {
+        let count = self.symbol_table.array_length(&repeat_expr.count)?;
+        let value = self.analyze_expression(&repeat_expr.value)?;
+        let result_type = ResolvedType::Vector(Box::new(value.result_type.clone()), count);
+        Ok(AnnotatedExpression {
+            expr: AnnotatedExpressionKind::ArrayRepeat {
+                value: Box::new(value),
+                count,
+            },
+            result_type,
+        })
+    }
+
+    /// Analyze `{ key: value, ... }`. Every key has the type of the first
+    /// one, which must be hashable, and every value that of the first value.
+    /// An empty map takes its types from the annotation of its `let`.
+    fn analyze_map_literal(&mut self, map_expr: &MapExpression) -> Result<AnnotatedExpression, SemanticError> {
+        let mut entries = Vec::new();
+        let mut entry_types: Option<(ResolvedType, ResolvedType)> = None;
+        for (key, value) in &map_expr.entries {
+            let annotated_key = self.analyze_expression(key)?;
+            let annotated_value = self.analyze_expression(value)?;
+            match &entry_types {
+                None => {
+                    let key_type = self
+                        .type_checker
+                        .key_type(&annotated_key.result_type)
+                        .map_err(|e| e.with_span(key.span()))?;
+                    entry_types = Some((key_type, annotated_value.result_type.clone()));
+                }
+                Some((key_type, value_type)) => {
+                    self.check_element_type(key_type, &annotated_key.result_type, key)?;
+                    self.check_element_type(value_type, &annotated_value.result_type, value)?;
+                }
+            }
+            entries.push((annotated_key, annotated_value));
+        }
+
+        let (key_type, value_type) = entry_types.unwrap_or((ResolvedType::Int, ResolvedType::Int));
+        Ok(AnnotatedExpression {
+            expr: AnnotatedExpressionKind::MapLiteral { entries },
+            result_type: ResolvedType::Map(Box::new(key_type), Box::new(value_type)),
+        })
+    }
+
+    /// Analyze `#{a, b, c}`, whose elements share the hashable type of the first
+    fn analyze_set_literal(&mut self, set_expr: &SetExpression) -> Result<AnnotatedExpression, SemanticError> {
+        let mut elements = Vec::new();
+        let mut element_type: Option<ResolvedType> = None;
+        for element in &set_expr.elements {
+            let annotated_element = self.analyze_expression(element)?;
+            match &element_type {
+                None => {
+                    let key_type = self
+                        .type_checker
+                        .key_type(&annotated_element.result_type)
+                        .map_err(|e| e.with_span(element.span()))?;
+                    element_type = Some(key_type);
+                }
+                Some(expected) => self.check_element_type(expected, &annotated_element.result_type, element)?,
+            }
+            elements.push(annotated_element);
+        }
 
         Ok(AnnotatedExpression {
-            expr: AnnotatedExpressionKind::FieldAccess {
-                object: Box::new(annotated_object),
-                field: field_access.field.clone(),
-            },
-            result_type: field_info.field_type.clone(),
+            expr: AnnotatedExpressionKind::SetLiteral { elements },
+            result_type: ResolvedType::Set(Box::new(element_type.unwrap_or(ResolvedType::Int))),
         })
     }
 
+    /// An element of a collection literal must have the type of the first
+    fn check_element_type(
+        &self,
+        expected: &ResolvedType,
+        found: &ResolvedType,
+        element: &Expression,
+    ) -> Result<(), SemanticError> {
+        if self.type_checker.types_compatible(expected, found) {
+            Ok(())
+        } else {
+            Err(SemanticError::TypeMismatch {
+                expected: expected.clone(),
+                found: found.clone(),
+            }
+            .with_span(element.span()))
+        }
+    }
+
     /// Analyze an array literal expression (Expert recommendation: List<T> support)
     fn analyze_array_literal(
         &mut self,
@@ -1308,13 +2333,28 @@ impl SemanticAnalyzer {
         // Determine the result type based on the object type
         let result_type = match &annotated_object.result_type {
             ResolvedType::List(element_type) => (**element_type).clone(),
+            ResolvedType::Vector(element_type, length) => {
+                // A constant index is checked now; any other one when the program runs
+                if let AnnotatedExpressionKind::Literal(Literal::Integer(index)) = &annotated_index.expr {
+                    if usize::try_from(*index).map_or(true, |index| index >= *length) {
+                        return Err(SemanticError::IndexOutOfBounds { index: *index, length: *length });
+                    }
+                }
+                (**element_type).clone()
+            }
             ResolvedType::Tuple(element_types) => {
-                // For tuple indexing, we'd need to check if the index is a compile-time constant
-                // For now, return the first element type or error
-                if element_types.is_empty() {
-                    return Err(SemanticError::Other("Cannot index empty tuple".to_string()));
+                // Each element has its own type, so the index must be a compile-time constant
+                match &annotated_index.expr {
+                    AnnotatedExpressionKind::Literal(Literal::Integer(index)) => {
+                        Self::tuple_element_type(element_types, *index)?
+                    }
+                    _ => {
+                        return Err(SemanticError::InvalidTupleIndex {
+                            index: None,
+                            len: element_types.len(),
+                        })
+                    }
                 }
-                element_types[0].clone() // Simplified - should check constant index
             }
             _ => {
                 return Err(SemanticError::Other(format!(
@@ -1353,6 +2393,102 @@ impl SemanticAnalyzer {
         })
     }
 
+    /// Analyze `query goal(...)`. The goals are checked like a rule body;
+    /// the solutions are a `List<Map<string, string>>` from each variable of
+    /// the goals to its value as text.
+    fn analyze_query_expression(&mut self, query: &QueryStatement) -> Result<AnnotatedExpression, SemanticError> {
+        let goals = query
+            .goals
+            .iter()
+            .map(|goal| {
+                let analyzed = if goal.is_aggregate() {
+                    self.analyze_aggregate_goal(goal, &mut HashMap::new())
+                } else {
+                    self.analyze_logic_term(goal)
+                };
+                analyzed.map_err(|e| e.with_span(Some(goal.span)))
+            })
+            .collect::<Result<_, _>>()?;
+        let text: Vec<String> = query.goals.iter().map(LogicTerm::to_string).collect();
+        let solution = ResolvedType::Map(Box::new(ResolvedType::String), Box::new(ResolvedType::String));
+        Ok(AnnotatedExpression {
+            expr: AnnotatedExpressionKind::Query {
+                goals,
+                query: text.join(", "),
+            },
+            result_type: ResolvedType::List(Box::new(solution)),
+        })
+    }
+
+    /// Analyze an if expression; its type is the common type of the branch values
+    fn analyze_if_expression(&mut self, if_expr: &IfStatement) -> Result<AnnotatedExpression, SemanticError> {
+        let condition = self.analyze_expression(&if_expr.condition)?;
+        if !matches!(condition.result_type, ResolvedType::Bool) {
+            return Err(SemanticError::TypeMismatch {
+                expected: ResolvedType::Bool,
+                found: condition.result_type,
+            });
+        }
+
+        let then_block = self.analyze_value_block(&if_expr.then_block)?;
+        let else_block = match &if_expr.else_block {
+            Some(block) => match block.statements.as_slice() {
+                // `else if`: the nested if expression is the value of the else branch
+                [Statement::If(else_if)] => {
+                    let value = self
+                        .analyze_if_expression(else_if)
+                        .map_err(|e| e.with_span(Some(else_if.span)))?;
+                    Some(AnnotatedBlock {
+                        statements: vec![AnnotatedStatement::Expression(value)],
+                        spans: vec![else_if.span],
+                        variables_to_destroy: None,
+                        trailing_semicolon: false,
+                    })
+                }
+                _ => Some(self.analyze_value_block(block)?),
+            },
+            None => None,
+        };
+
+        // A branch that returns or leaves a loop has no value to merge
+        let then_type = Self::block_value_type(&then_block);
+        let else_type = match &else_block {
+            Some(block) => Self::block_value_type(block),
+            None => Some(ResolvedType::Unit),
+        };
+        let result_type = match (then_type, else_type) {
+            (Some(then_type), Some(else_type)) => self
+                .type_checker
+                .common_super_type(&then_type, &else_type)
+                .ok_or(SemanticError::TypeMismatch {
+                    expected: then_type,
+                    found: else_type,
+                })?,
+            (Some(value_type), None) | (None, Some(value_type)) => value_type,
+            (None, None) => ResolvedType::Unit,
+        };
+
+        Ok(AnnotatedExpression {
+            expr: AnnotatedExpressionKind::If {
+                condition: Box::new(condition),
+                then_block,
+                else_block,
+            },
+            result_type,
+        })
+    }
+
+    /// Type of the value a block produces through its trailing expression or
+    /// match, or `None` when the block always leaves through `return`, `break` or `continue`
+    fn block_value_type(block: &AnnotatedBlock) -> Option<ResolvedType> {
+        match block.statements.last() {
+            Some(AnnotatedStatement::Expression(expr)) if !block.trailing_semicolon => Some(expr.result_type.clone()),
+            Some(AnnotatedStatement::Match(annotated_match)) => Some(annotated_match.result_type.clone()),
+            Some(AnnotatedStatement::Return(_) | AnnotatedStatement::Break | AnnotatedStatement::Continue) => None,
+            _ => Some(ResolvedType::Unit),
+        }
+    }
+
     /// Analyze a call expression (Expert recommendation: Priority 1 - Method Resolution)
     fn analyze_call_expression(
         &mut self,
@@ -1360,6 +2496,9 @@ impl SemanticAnalyzer {
     ) -> Result<AnnotatedExpression, SemanticError> {
         match call_expr.callee.as_ref() {
             // Simple function call: function_name(args)
+            Expression::Identifier(function_name) if PANIC_INTRINSICS.contains(&function_name.as_str()) => {
+                self.analyze_panic_call(function_name, &call_expr.arguments, call_expr.span)
+            }
             Expression::Identifier(function_name) => {
                 self.analyze_function_call(function_name, &call_expr.arguments)
             }
@@ -1380,12 +2519,20 @@ impl SemanticAnalyzer {
         function_name: &str,
         arguments: &[Expression],
     ) -> Result<AnnotatedExpression, SemanticError> {
+        match function_name {
+            "format" => return self.analyze_format_call(arguments),
+            "print" | "println" => return self.analyze_print_call(function_name, arguments),
+            "on_exit" => return self.analyze_on_exit_call(arguments),
+            _ => {}
+        }
+
         // Look up the function in the symbol table
         let func_info = self
             .symbol_table
             .lookup_function(function_name)
-            .ok_or_else(|| SemanticError::UndefinedVariable(function_name.to_string()))?
+            .ok_or_else(|| undefined_function(function_name))?
             .clone(); // Clone to avoid borrowing issues
+        self.symbol_table.check_access(symbol_table::ItemKind::Function, function_name)?;
 
         // Check argument count
 
@@ -1396,34 +2543,225 @@ impl SemanticAnalyzer {
             });
         }
 
-        // Analyze arguments and check types
+        // Analyze arguments and unify them with the parameter types, so generic
+        // parameters are bound consistently across arguments
         let mut annotated_args = Vec::new();
+        let mut substitution = Substitution::new();
         for (i, arg) in arguments.iter().enumerate() {
             let annotated_arg = self.analyze_expression(arg)?;
             let expected_type = &func_info.parameters[i];
 
-            // Type compatibility check with generic parameter support (Expert fix: print function)
-            if !self.is_type_compatible(&annotated_arg.result_type, expected_type) {
-                return Err(SemanticError::TypeMismatch {
-                    expected: expected_type.clone(),
-                    found: annotated_arg.result_type.clone(),
-                });
+            // `&dog` is passed as `&dyn Display`; code generation builds the trait object
+            if !self.coerces_to_trait_object(expected_type, &annotated_arg.result_type) {
+                self.type_checker
+                    .unify(expected_type, &annotated_arg.result_type, &mut substitution)
+                    .map_err(|e| e.with_span(arg.span()))?;
             }
 
             annotated_args.push(annotated_arg);
         }
 
-        let return_type = func_info.return_type.clone().unwrap_or(ResolvedType::Unit);
+        // `identity(5)` binds T = int, so the call has type int
+        let return_type = func_info
+            .return_type
+            .as_ref()
+            .map(|ty| self.type_checker.apply_substitution(ty, &substitution))
+            .unwrap_or(ResolvedType::Unit);
+
+        let function = self.symbol_table.function_symbol(function_name).unwrap_or_else(|| function_name.to_string());
+        Ok(AnnotatedExpression {
+            expr: AnnotatedExpressionKind::Call { function, arguments: annotated_args },
+            result_type: return_type,
+        })
+    }
+
+    /// Analyze `format(template, args...)`, which interpolated strings desugar to.
+    /// The template must be a string literal whose placeholders match the
+    /// arguments, and every argument must be displayable.
+    fn analyze_format_call(&mut self, arguments: &[Expression]) -> Result<AnnotatedExpression, SemanticError> {
+        let annotated_args = self.analyze_format_arguments(arguments)?;
+        Ok(AnnotatedExpression {
+            expr: AnnotatedExpressionKind::Call {
+                function: "format".to_string(),
+                arguments: annotated_args,
+            },
+            result_type: ResolvedType::String,
+        })
+    }
+
+    /// Analyze `panic(message)`, `assert(condition, message)`,
+    /// `assert_true(condition)`, `assert_eq(left, right)` or
+    /// `assert_ne(left, right)`. The compared values must be displayable so a
+    /// failure can show them, and the call gets a trailing string argument with
+    /// its `file:line:column`, which code generation passes to `albayan_rt_panic`.
+    fn analyze_panic_call(
+        &mut self,
+        function_name: &str,
+        arguments: &[Expression],
+        span: Span,
+    ) -> Result<AnnotatedExpression, SemanticError> {
+        let mut call = self.analyze_function_call(function_name, arguments)?;
+        let AnnotatedExpressionKind::Call { arguments: annotated_args, .. } = &mut call.expr else {
+            unreachable!("a function call is annotated as a call");
+        };
+        if matches!(function_name, "assert_eq" | "assert_ne") {
+            for (annotated, argument) in annotated_args.iter().zip(arguments) {
+                if !Self::is_displayable(&annotated.result_type) {
+                    return Err(SemanticError::NotDisplayable(annotated.result_type.clone()).with_span(argument.span()));
+                }
+            }
+        }
+
+        annotated_args.push(AnnotatedExpression {
+            expr: AnnotatedExpressionKind::Literal(Literal::String(self.location(span))),
+            result_type: ResolvedType::String,
+        });
+        Ok(call)
+    }
+
+    /// `path:line:column` of `span` for a panic at run time; empty where unknown
+    fn location(&self, span: Span) -> String {
+        match &self.source {
+            Some((path, index)) if !span.is_empty() => {
+                let position = index.line_col(span.start);
+                format!("{}:{}:{}", path, position.line, position.column)
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// Analyze `print`/`println`: a single argument is displayed as is, several
+    /// arguments are a format template followed by its values
+    fn analyze_print_call(
+        &mut self,
+        function_name: &str,
+        arguments: &[Expression],
+    ) -> Result<AnnotatedExpression, SemanticError> {
+        let annotated_args = if arguments.len() > 1 {
+            self.analyze_format_arguments(arguments)?
+        } else {
+            let mut annotated_args = Vec::new();
+            for value in arguments {
+                let annotated_value = self.analyze_expression(value)?;
+                if !Self::is_displayable(&annotated_value.result_type) {
+                    return Err(SemanticError::NotDisplayable(annotated_value.result_type).with_span(value.span()));
+                }
+                annotated_args.push(annotated_value);
+            }
+            annotated_args
+        };
 
         Ok(AnnotatedExpression {
             expr: AnnotatedExpressionKind::Call {
                 function: function_name.to_string(),
                 arguments: annotated_args,
             },
-            result_type: return_type,
+            result_type: ResolvedType::Unit,
+        })
+    }
+
+    /// Analyze `on_exit(callback)`, which registers a function without
+    /// parameters to run when the program shuts down
+    fn analyze_on_exit_call(&mut self, arguments: &[Expression]) -> Result<AnnotatedExpression, SemanticError> {
+        let expected = ResolvedType::Function(Vec::new(), Box::new(ResolvedType::Unit));
+        let [callback] = arguments else {
+            return Err(SemanticError::TypeMismatch { expected, found: ResolvedType::Unit });
+        };
+        let Expression::Identifier(name) = callback else {
+            let found = self.analyze_expression(callback)?.result_type;
+            return Err(SemanticError::TypeMismatch { expected, found }.with_span(callback.span()));
+        };
+        let func_info = self
+            .symbol_table
+            .lookup_function(name)
+            .ok_or_else(|| SemanticError::UndefinedVariable(name.clone()).with_span(callback.span()))?;
+        let callback_type = ResolvedType::Function(
+            func_info.parameters.clone(),
+            Box::new(func_info.return_type.clone().unwrap_or(ResolvedType::Unit)),
+        );
+        if !func_info.parameters.is_empty() {
+            return Err(SemanticError::TypeMismatch { expected, found: callback_type }.with_span(callback.span()));
+        }
+
+        Ok(AnnotatedExpression {
+            expr: AnnotatedExpressionKind::Call {
+                function: "on_exit".to_string(),
+                arguments: vec![AnnotatedExpression {
+                    expr: AnnotatedExpressionKind::Identifier(name.clone()),
+                    result_type: callback_type,
+                }],
+            },
+            result_type: ResolvedType::Unit,
         })
     }
 
+    /// Check a format template and its values, returning them annotated
+    fn analyze_format_arguments(&mut self, arguments: &[Expression]) -> Result<Vec<AnnotatedExpression>, SemanticError> {
+        let Some((template, values)) = arguments.split_first() else {
+            return Err(SemanticError::ArityMismatch { expected: 1, found: 0 });
+        };
+
+        let annotated_template = self.analyze_expression(template)?;
+        if annotated_template.result_type != ResolvedType::String {
+            return Err(SemanticError::TypeMismatch {
+                expected: ResolvedType::String,
+                found: annotated_template.result_type,
+            }
+            .with_span(template.span()));
+        }
+        let Expression::Literal(Literal::String(text)) = template else {
+            return Err(SemanticError::InvalidFormatString("the format template must be a string literal".to_string())
+                .with_span(template.span()));
+        };
+        let pieces = format_string::parse_format_string(text)
+            .map_err(|message| SemanticError::InvalidFormatString(message).with_span(template.span()))?;
+        let placeholders = format_string::argument_count(&pieces);
+        if placeholders != values.len() {
+            return Err(SemanticError::InvalidFormatString(format!(
+                "the template has {} placeholder(s) but {} value(s) were supplied",
+                placeholders,
+                values.len()
+            ))
+            .with_span(template.span()));
+        }
+
+        let precisions = pieces.iter().filter_map(|piece| match piece {
+            FormatPiece::Argument { precision } => Some(precision),
+            FormatPiece::Text(_) => None,
+        });
+        let mut annotated_args = vec![annotated_template];
+        for (value, precision) in values.iter().zip(precisions) {
+            let annotated_value = self.analyze_expression(value)?;
+            if !Self::is_displayable(&annotated_value.result_type) {
+                return Err(SemanticError::NotDisplayable(annotated_value.result_type).with_span(value.span()));
+            }
+            if precision.is_some() && annotated_value.result_type != ResolvedType::Float {
+                return Err(SemanticError::TypeMismatch {
+                    expected: ResolvedType::Float,
+                    found: annotated_value.result_type,
+                }
+                .with_span(value.span()));
+            }
+            annotated_args.push(annotated_value);
+        }
+        Ok(annotated_args)
+    }
+
+    /// Types that can be formatted into a string
+    fn is_displayable(ty: &ResolvedType) -> bool {
+        match ty {
+            ResolvedType::Int
+            | ResolvedType::Float
+            | ResolvedType::Bool
+            | ResolvedType::Char
+            | ResolvedType::String
+            | ResolvedType::Null
+            | ResolvedType::GenericParam(_) => true,
+            ResolvedType::Reference(inner, _) => Self::is_displayable(inner),
+            _ => false,
+        }
+    }
+
     /// Analyze a method call (Expert recommendation: Priority 2 - &self and &mut self support)
     fn analyze_method_call(
         &mut self,
@@ -1437,13 +2775,6 @@ impl SemanticAnalyzer {
         // Get the object type (clone to avoid borrowing issues)
         let object_type = annotated_object.result_type.clone();
 
-        // Check if we need to create a borrow for method call (Expert recommendation: Priority 2)
-        if let Expression::Identifier(var_name) = field_access.object.as_ref() {
-            // We'll determine the borrow type based on the method signature
-            // For now, we'll check the method and create appropriate borrow
-            self.check_method_call_borrowing(var_name, method_name, &object_type)?;
-        }
-
         // Check if this is a trait object method call (Expert recommendation: Priority 1 - Dynamic Dispatch)
         if let ResolvedType::TraitObject(trait_names) = &object_type {
             return self.analyze_trait_object_method_call(
@@ -1471,6 +2802,21 @@ impl SemanticAnalyzer {
             return self.analyze_ai_model_method_call(method_name, arguments, annotated_object);
         }
 
+        // Built-in methods of lists, fixed-size arrays, maps, sets and strings
+        if let Some((collection, method)) = self.collection_method(&object_type, method_name) {
+            let method = method.ok_or_else(|| {
+                SemanticError::UndefinedVariable(format!("Method {} not found for type {:?}", method_name, object_type))
+            })?;
+            return self.analyze_collection_method_call(
+                &field_access.object,
+                collection,
+                method,
+                method_name,
+                arguments,
+                annotated_object,
+            );
+        }
+
         // Try to find the method in impl blocks (Expert recommendation: Priority 1)
         if let Some(method_info) = self.find_method_in_impls(&object_type, method_name) {
             // Check argument count (including self parameter)
@@ -1508,9 +2854,11 @@ impl SemanticAnalyzer {
                 .clone()
                 .unwrap_or(ResolvedType::Unit);
 
+            // `Type::method`, the name code generation gives the method's function
+            let type_name = Self::receiver_type_name(&object_type).unwrap_or_default();
             Ok(AnnotatedExpression {
                 expr: AnnotatedExpressionKind::Call {
-                    function: format!("{:?}::{}", object_type, method_name), // Mangled method name
+                    function: format!("{}::{}", type_name, method_name),
                     arguments: annotated_args,
                 },
                 result_type: return_type,
@@ -1523,16 +2871,134 @@ impl SemanticAnalyzer {
         }
     }
 
+    /// The collection kind (`list`, `map`, `set` or `string`) of a list,
+    /// fixed-size array, map, set or string, also behind a reference, with its
+    /// built-in method `name` if it has one. Methods that change the
+    /// collection are not available on fixed-size arrays.
+    fn collection_method(&self, object_type: &ResolvedType, name: &str) -> Option<(&'static str, Option<CollectionMethod>)> {
+        match object_type {
+            ResolvedType::List(element) => Some(("list", self.type_checker.list_method(element, name))),
+            ResolvedType::Vector(element, _) => {
+                Some(("list", self.type_checker.list_method(element, name).filter(|method| !method.mutates)))
+            }
+            ResolvedType::Map(key, value) => Some(("map", self.type_checker.map_method(key, value, name))),
+            ResolvedType::Set(element) => Some(("set", self.type_checker.set_method(element, name))),
+            ResolvedType::String => Some(("string", self.type_checker.string_method(name))),
+            ResolvedType::Reference(inner, _) => self.collection_method(inner, name),
+            _ => None,
+        }
+    }
+
+    /// Analyze a call of a built-in collection method such as `items.push(x)`,
+    /// marked with the collection kind, e.g. `list::push`. Methods that change
+    /// the collection need a mutable one.
+    fn analyze_collection_method_call(
+        &mut self,
+        object: &Expression,
+        collection: &str,
+        method: CollectionMethod,
+        method_name: &str,
+        arguments: &[Expression],
+        annotated_object: AnnotatedExpression,
+    ) -> Result<AnnotatedExpression, SemanticError> {
+        let object_type = annotated_object.result_type.clone();
+        if arguments.len() != method.parameters.len() {
+            return Err(SemanticError::ArityMismatch {
+                expected: method.parameters.len(),
+                found: arguments.len(),
+            });
+        }
+        if method.mutates {
+            match &object_type {
+                ResolvedType::Reference(_, true) => {}
+                ResolvedType::Reference(_, false) => {
+                    let name = match object {
+                        Expression::Identifier(name) => name.clone(),
+                        _ => method_name.to_string(),
+                    };
+                    return Err(SemanticError::AssignThroughSharedReference(name).with_span(object.span()));
+                }
+                _ => self.check_assignable(object)?,
+            }
+        }
+
+        let mut substitution = Substitution::new();
+        let mut annotated_args = vec![annotated_object];
+        for (argument, parameter) in arguments.iter().zip(&method.parameters) {
+            let annotated_arg = match (argument, parameter) {
+                (Expression::Identifier(name), ResolvedType::Function(_, _))
+                    if self.symbol_table.lookup_variable(name).is_none() =>
+                {
+                    self.analyze_function_value(name).map_err(|e| e.with_span(argument.span()))?
+                }
+                _ => self.analyze_expression(argument)?,
+            };
+            self.type_checker
+                .unify(parameter, &annotated_arg.result_type, &mut substitution)
+                .map_err(|e| e.with_span(argument.span()))?;
+            annotated_args.push(annotated_arg);
+        }
+
+        Ok(AnnotatedExpression {
+            expr: AnnotatedExpressionKind::Call {
+                function: format!("{}::{}", collection, method_name),
+                arguments: annotated_args,
+            },
+            result_type: self.type_checker.apply_substitution(&method.return_type, &substitution),
+        })
+    }
+
+    /// A function named as a value, e.g. the `double` of `items.map(double)`
+    fn analyze_function_value(&self, name: &str) -> Result<AnnotatedExpression, SemanticError> {
+        let func_info = self
+            .symbol_table
+            .lookup_function(name)
+            .ok_or_else(|| SemanticError::UndefinedVariable(name.to_string()))?;
+        Ok(AnnotatedExpression {
+            expr: AnnotatedExpressionKind::Identifier(name.to_string()),
+            result_type: ResolvedType::Function(
+                func_info.parameters.clone(),
+                Box::new(func_info.return_type.clone().unwrap_or(ResolvedType::Unit)),
+            ),
+        })
+    }
+
+    /// Name of the struct or enum whose impl blocks provide a receiver's methods
+    fn receiver_type_name(object_type: &ResolvedType) -> Option<&str> {
+        match object_type {
+            ResolvedType::Struct(name) | ResolvedType::Enum(name) => Some(name),
+            ResolvedType::Reference(inner, _) => Self::receiver_type_name(inner),
+            _ => None,
+        }
+    }
+
+    /// Whether `&T` may be passed where `&dyn Trait` is expected: `T` must
+    /// implement every trait of the object, and a shared reference cannot
+    /// become a mutable one
+    fn coerces_to_trait_object(&self, expected: &ResolvedType, actual: &ResolvedType) -> bool {
+        let (ResolvedType::Reference(target, target_mutable), ResolvedType::Reference(source, source_mutable)) =
+            (expected, actual)
+        else {
+            return false;
+        };
+        let (ResolvedType::TraitObject(traits), ResolvedType::Struct(type_name) | ResolvedType::Enum(type_name)) =
+            (target.as_ref(), source.as_ref())
+        else {
+            return false;
+        };
+        (*source_mutable || !*target_mutable)
+            && traits
+                .iter()
+                .all(|trait_name| self.symbol_table.find_impl(type_name, Some(trait_name)).is_some())
+    }
+
     /// Find method in impl blocks (Expert recommendation: Priority 1)
     fn find_method_in_impls(
         &self,
         object_type: &ResolvedType,
         method_name: &str,
     ) -> Option<FunctionInfo> {
-        let type_name = match object_type {
-            ResolvedType::Struct(name) => name,
-            _ => return None,
-        };
+        let type_name = Self::receiver_type_name(object_type)?;
 
         // Look for inherent impl (impl TypeName)
         for impl_info in self.symbol_table.get_impls() {
@@ -1545,9 +3011,11 @@ impl SemanticAnalyzer {
             }
         }
 
-        // Look for trait impl (impl TraitName for TypeName) - Expert recommendation: Priority 1
+        // Look for trait impl (impl TraitName for TypeName) - Expert recommendation: Priority 1.
+        // Destructors only run implicitly, so `Drop::drop` cannot be called by name.
         for impl_info in self.symbol_table.get_impls() {
-            if impl_info.type_name == *type_name && impl_info.trait_name.is_some() {
+            let callable = impl_info.trait_name.as_deref().is_some_and(|name| name != DROP_TRAIT);
+            if impl_info.type_name == *type_name && callable {
                 for method in &impl_info.methods {
                     if method.name == method_name {
                         return Some(method.clone());
@@ -1597,7 +3065,8 @@ impl SemanticAnalyzer {
         };
 
         // Check argument count (trait object method calls don't need explicit self)
-        let expected_param_count = method_info.parameters.len();
+        let parameters = &method_info.parameters[usize::from(method_info.has_receiver)..];
+        let expected_param_count = parameters.len();
         let actual_arg_count = arguments.len();
 
         if actual_arg_count != expected_param_count {
@@ -1615,8 +3084,8 @@ impl SemanticAnalyzer {
             let annotated_arg = self.analyze_expression(arg)?;
 
             // Only check types if we have parameter info
-            if i < method_info.parameters.len() {
-                let expected_type = &method_info.parameters[i];
+            if i < parameters.len() {
+                let expected_type = &parameters[i];
 
                 // Simple type compatibility check
                 if annotated_arg.result_type != *expected_type {
@@ -1707,24 +3176,15 @@ impl SemanticAnalyzer {
     ) -> Result<AnnotatedExpression, SemanticError> {
         match &unary_expr.operator {
             UnaryOperator::Reference => {
-                // For &var, we need to check that var exists and create an immutable borrow
+                // For &var, we need to check that var exists; the borrow is checked on the MIR
                 if let Expression::Identifier(var_name) = &*unary_expr.operand {
                     // Check that variable exists
+                    self.symbol_table.mark_read(var_name);
                     let var_info = self
                         .symbol_table
                         .lookup_variable(var_name)
                         .ok_or_else(|| SemanticError::UndefinedVariable(var_name.clone()))?;
 
-                    // Add immutable borrow (Expert recommendation)
-                    let scope_depth = self.ownership_analyzer.get_scope_depth();
-                    self.ownership_analyzer
-                        .get_borrow_check_state_mut()
-                        .add_borrow(
-                            var_name,
-                            crate::semantic::ownership::BorrowKind::Immutable,
-                            scope_depth,
-                        )?;
-
                     // Return reference type
                     let ref_type =
                         ResolvedType::Reference(Box::new(var_info.var_type.clone()), false);
@@ -1745,9 +3205,10 @@ impl SemanticAnalyzer {
                 }
             }
             UnaryOperator::MutableReference => {
-                // For &mut var, we need to check that var is mutable and create a mutable borrow
+                // For &mut var, we need to check that var is mutable; the borrow is checked on the MIR
                 if let Expression::Identifier(var_name) = &*unary_expr.operand {
                     // Check that variable exists
+                    self.symbol_table.mark_read(var_name);
                     let var_info = self
                         .symbol_table
                         .lookup_variable(var_name)
@@ -1759,16 +3220,6 @@ impl SemanticAnalyzer {
                         return Err(SemanticError::BorrowMutableFromImmutable(var_name.clone()));
                     }
 
-                    // Add mutable borrow (Expert recommendation)
-                    let scope_depth = self.ownership_analyzer.get_scope_depth();
-                    self.ownership_analyzer
-                        .get_borrow_check_state_mut()
-                        .add_borrow(
-                            var_name,
-                            crate::semantic::ownership::BorrowKind::Mutable,
-                            scope_depth,
-                        )?;
-
                     // Return mutable reference type
                     let ref_type =
                         ResolvedType::Reference(Box::new(var_info.var_type.clone()), true);
@@ -1822,6 +3273,7 @@ impl SemanticAnalyzer {
         for arm in &match_stmt.arms {
             // Enter new scope for pattern variables
             self.symbol_table.enter_scope();
+            self.ownership_analyzer.enter_scope();
 
             // Analyze the pattern and bind variables
             let annotated_pattern = self.check_pattern(&arm.pattern, &match_type)?;
@@ -1842,23 +3294,18 @@ impl SemanticAnalyzer {
             };
 
             // Analyze the arm body
-            let annotated_body = self.analyze_block(&arm.body)?;
-
-            // Determine body type (last expression or unit)
-            let body_type = if let Some(last_stmt) = annotated_body.statements.last() {
-                match last_stmt {
-                    AnnotatedStatement::Expression(expr) => expr.result_type.clone(),
-                    AnnotatedStatement::Return(ret) => {
-                        if let Some(ret_expr) = &ret.value {
-                            ret_expr.result_type.clone()
-                        } else {
-                            ResolvedType::Unit
-                        }
+            let annotated_body = self.analyze_value_block(&arm.body)?;
+
+            // Determine body type (the block's value, or what it returns)
+            let body_type = match annotated_body.statements.last() {
+                Some(AnnotatedStatement::Return(ret)) => {
+                    if let Some(ret_expr) = &ret.value {
+                        ret_expr.result_type.clone()
+                    } else {
+                        ResolvedType::Unit
                     }
-                    _ => ResolvedType::Unit,
                 }
-            } else {
-                ResolvedType::Unit
+                _ => Self::block_value_type(&annotated_body).unwrap_or(ResolvedType::Unit),
             };
 
             arm_types.push(body_type.clone());
@@ -1871,6 +3318,7 @@ impl SemanticAnalyzer {
             });
 
             // Exit scope
+            let _vars_to_destroy = self.ownership_analyzer.exit_scope();
             self.symbol_table.exit_scope();
         }
 
@@ -1907,6 +3355,7 @@ impl SemanticAnalyzer {
             expression: annotated_expr,
             arms: annotated_arms,
             result_type,
+            span: match_stmt.span,
         })
     }
 
@@ -1987,17 +3436,18 @@ impl SemanticAnalyzer {
                 Ok(false)
             }
             Statement::Match(match_stmt) => {
-                let annotated_match = self.analyze_match_statement(match_stmt)?;
-
                 // Match guarantees return only if:
                 // 1. All patterns are covered (exhaustive) - already checked in analyze_match_statement
                 // 2. All arms guarantee return
-                let all_arms_return = annotated_match.arms.iter().all(|arm| {
-                    self.analyze_annotated_block_for_return(&arm.body, func_ret_type)
-                        .unwrap_or(false)
-                });
+                // The arms are not re-analyzed here: the block scope that declared the
+                // matched variables has already been exited
+                for arm in &match_stmt.arms {
+                    if !self.analyze_block_for_return(&arm.body, func_ret_type)? {
+                        return Ok(false);
+                    }
+                }
 
-                Ok(all_arms_return)
+                Ok(true)
             }
             Statement::Expression(_) | Statement::Let(_) => {
                 // These statements never guarantee return
@@ -2047,13 +3497,16 @@ impl SemanticAnalyzer {
 
 /// Annotated AST types (with type information)
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedProgram {
     pub items: Vec<AnnotatedItem>,
     pub symbol_table: SymbolTable,
+    /// Warnings of the analysis that were not allowed; see [`warnings`]
+    #[serde(default)]
+    pub warnings: Vec<SemanticWarning>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AnnotatedItem {
     Function(AnnotatedFunction),
     Struct(AnnotatedStruct),
@@ -2062,51 +3515,56 @@ pub enum AnnotatedItem {
     Impl(AnnotatedImpl),   // NEWLY ADDED: Expert recommendation
     Relation(AnnotatedRelation),
     Rule(AnnotatedRule),
+    Fact(AnnotatedFact),
+    Concept(AnnotatedConcept),
+    Constraint(AnnotatedConstraint),
     Using(AnnotatedUsing), // NEWLY ADDED: Expert fix for using statements
+    Const(AnnotatedConst),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedFunction {
     pub name: String,
     pub generic_params: Option<Vec<AnnotatedGenericParam>>, // Expert recommendation: Priority 1
     pub parameters: Vec<AnnotatedParameter>,
     pub return_type: Option<ResolvedType>,
     pub body: AnnotatedBlock,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedParameter {
     pub name: String,
     pub param_type: ResolvedType,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedStruct {
     pub name: String,
     pub generic_params: Option<Vec<AnnotatedGenericParam>>, // Expert recommendation: Priority 1
     pub fields: Vec<AnnotatedStructField>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedStructField {
     pub name: String,
     pub field_type: ResolvedType,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedEnum {
     pub name: String,
     pub variants: Vec<AnnotatedEnumVariant>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedEnumVariant {
     pub name: String,
     pub fields: Option<Vec<ResolvedType>>,
 }
 
 /// Annotated trait declaration (Expert recommendation: Priority 1)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedTrait {
     pub name: String,
     pub generic_params: Option<Vec<AnnotatedGenericParam>>,
@@ -2114,7 +3572,7 @@ pub struct AnnotatedTrait {
 }
 
 /// Annotated trait method (Expert recommendation: Priority 1)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedTraitMethod {
     pub name: String,
     pub parameters: Vec<AnnotatedParameter>,
@@ -2123,7 +3581,7 @@ pub struct AnnotatedTraitMethod {
 }
 
 /// Annotated impl declaration (Expert recommendation: Priority 1)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedImpl {
     pub trait_name: Option<String>, // None for inherent impl
     pub type_name: String,
@@ -2132,44 +3590,105 @@ pub struct AnnotatedImpl {
 }
 
 /// Annotated generic parameter (Expert recommendation: Priority 1)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedGenericParam {
     pub name: String,
     pub bounds: Vec<AnnotatedTraitBound>,
 }
 
 /// Annotated trait bound (Expert recommendation: Priority 1)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedTraitBound {
     pub trait_name: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedRelation {
     pub name: String,
     pub arg_types: Vec<ResolvedType>,
+    /// Concept each argument was declared with, if any
+    pub arg_concepts: Vec<Option<String>>,
+    /// Whether the relation was declared `#[table]`
+    pub tabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedConcept {
+    pub name: String,
+    pub parents: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedRule {
     pub head: AnnotatedLogicTerm,
     pub body: Vec<AnnotatedLogicTerm>,
+    pub no_reorder: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedFact {
+    pub term: AnnotatedLogicTerm,
+}
+
+/// Goals that must never hold together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedConstraint {
+    pub goals: Vec<AnnotatedLogicTerm>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedUsing {
     pub module_path: String,
     pub imports: Vec<String>, // List of imported items
 }
 
-#[derive(Debug, Clone)]
+/// A `const` item; its uses are already replaced with `value`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedConst {
+    pub name: String,
+    pub value_type: ResolvedType,
+    pub value: Literal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedLogicTerm {
     pub name: String,
     pub args: Vec<AnnotatedLogicArg>,
     pub relation_type: RelationInfo,
+    /// A rule body goal negated with `not`
+    #[serde(default)]
+    pub negated: bool,
 }
 
-#[derive(Debug, Clone)]
+impl AnnotatedLogicTerm {
+    /// The cut `!` of a rule body, which has no relation
+    fn cut() -> Self {
+        let relation_type = RelationInfo { name: CUT.to_string(), arg_types: Vec::new(), arg_concepts: Vec::new() };
+        AnnotatedLogicTerm { name: CUT.to_string(), args: Vec::new(), relation_type, negated: false }
+    }
+
+    pub fn is_cut(&self) -> bool {
+        self.name == CUT
+    }
+
+    /// Whether the goal is one of the comparisons in [`LOGIC_BUILTINS`]
+    pub fn is_builtin(&self) -> bool {
+        LOGIC_BUILTINS.contains(&self.name.as_str())
+    }
+
+    /// Whether the goal is one of the [`AGGREGATES`] applied to a goal
+    pub fn is_aggregate(&self) -> bool {
+        AGGREGATES.contains(&self.name.as_str()) && self.args.iter().any(|arg| matches!(arg, AnnotatedLogicArg::Goal(_)))
+    }
+
+    /// Whether the goal calls a relation, as all goals but the cut, the
+    /// built-ins and the aggregates do
+    pub fn calls_relation(&self) -> bool {
+        !self.is_cut() && !self.is_builtin() && !self.is_aggregate()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AnnotatedLogicArg {
     Variable {
         name: String,
@@ -2182,58 +3701,195 @@ pub enum AnnotatedLogicArg {
     StringConstant(String),
     IntConstant(i64),
     FloatConstant(f64),
+    /// Arithmetic of a comparison; both operands have `value_type`
+    Operation {
+        op: String,
+        left: Box<AnnotatedLogicArg>,
+        right: Box<AnnotatedLogicArg>,
+        value_type: ResolvedType,
+    },
+    /// The goal of an aggregate
+    Goal(Box<AnnotatedLogicTerm>),
+    /// A value of the struct `term_type`, one argument per field
+    Compound {
+        name: String,
+        args: Vec<AnnotatedLogicArg>,
+        term_type: ResolvedType,
+    },
+    /// Items of `list_type`, followed by the list `tail` if there is one
+    List {
+        items: Vec<AnnotatedLogicArg>,
+        tail: Option<Box<AnnotatedLogicArg>>,
+        list_type: ResolvedType,
+    },
+}
+
+/// Whether logic arithmetic and ordering apply to values of the type
+fn is_number(ty: &ResolvedType) -> bool {
+    matches!(ty, ResolvedType::Int | ResolvedType::Float)
+}
+
+impl AnnotatedLogicArg {
+    pub fn value_type(&self) -> ResolvedType {
+        match self {
+            AnnotatedLogicArg::Variable { var_type, .. } => var_type.clone(),
+            AnnotatedLogicArg::Constant { const_type, .. } => const_type.clone(),
+            AnnotatedLogicArg::StringConstant(_) => ResolvedType::String,
+            AnnotatedLogicArg::IntConstant(_) => ResolvedType::Int,
+            AnnotatedLogicArg::FloatConstant(_) => ResolvedType::Float,
+            AnnotatedLogicArg::Operation { value_type, .. } => value_type.clone(),
+            AnnotatedLogicArg::Goal(_) => ResolvedType::Bool,
+            AnnotatedLogicArg::Compound { term_type, .. } => term_type.clone(),
+            AnnotatedLogicArg::List { list_type, .. } => list_type.clone(),
+        }
+    }
+
+    /// Names of the variables in the argument, operands and nested terms
+    /// included; those of an aggregate's goal are its own and left out
+    pub fn variables(&self) -> Vec<&str> {
+        self.typed_variables().into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// The [`variables`](Self::variables) with the types their positions
+    /// give them
+    pub fn typed_variables(&self) -> Vec<(&str, &ResolvedType)> {
+        match self {
+            AnnotatedLogicArg::Variable { name, var_type } => vec![(name.as_str(), var_type)],
+            AnnotatedLogicArg::Operation { left, right, .. } => {
+                let mut variables = left.typed_variables();
+                variables.extend(right.typed_variables());
+                variables
+            }
+            AnnotatedLogicArg::Compound { args: items, .. } | AnnotatedLogicArg::List { items, tail: None, .. } => {
+                items.iter().flat_map(AnnotatedLogicArg::typed_variables).collect()
+            }
+            AnnotatedLogicArg::List { items, tail: Some(tail), .. } => {
+                items.iter().chain(std::iter::once(tail.as_ref())).flat_map(AnnotatedLogicArg::typed_variables).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedBlock {
     pub statements: Vec<AnnotatedStatement>,
+    /// Source location of each statement; empty where unknown
+    pub spans: Vec<Span>,
     /// Variables that need destruction at end of this block (Expert recommendation: Priority 1)
     pub variables_to_destroy: Option<Vec<DestroyInfo>>,
+    /// The trailing expression ends in a `;`, so the block has no value
+    #[serde(default)]
+    pub trailing_semicolon: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AnnotatedStatement {
     Let(AnnotatedLetStatement),
     Return(AnnotatedReturnStatement),
     Expression(AnnotatedExpression),
     Match(AnnotatedMatchStatement),
     If(AnnotatedIfStatement), // Expert recommendation: Priority 2 - Control flow analysis
+    While(AnnotatedWhileStatement),
+    For(AnnotatedForStatement),
+    Assert(AnnotatedFactUpdate),
+    Retract(AnnotatedFactUpdate),
+    Break,
+    Continue,
+}
+
+/// The fact an `assert` or `retract` statement adds or removes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedFactUpdate {
+    pub relation: String,
+    pub args: Vec<AnnotatedFactArg>,
+    /// Where the statement is, reported when the update fails at run time
+    #[serde(default)]
+    pub location: String,
+    pub span: Span,
+}
+
+impl AnnotatedFactUpdate {
+    /// The arguments computed when the statement runs
+    pub fn values(&self) -> impl Iterator<Item = &AnnotatedExpression> {
+        self.args.iter().filter_map(|arg| match arg {
+            AnnotatedFactArg::Value(value) | AnnotatedFactArg::Individual(value) => Some(value),
+            AnnotatedFactArg::Atom(_) => None,
+        })
+    }
+}
+
+/// An argument of an asserted or retracted fact
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AnnotatedFactArg {
+    /// A value stored as the term of its type
+    Value(AnnotatedExpression),
+    /// A string naming an individual, stored as an atom
+    Individual(AnnotatedExpression),
+    /// A bare name in the source, stored as an atom
+    Atom(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedLetStatement {
     pub name: String,
+    /// Names bound by a destructuring `let`
+    pub pattern: Option<AnnotatedPattern>,
     pub var_type: ResolvedType,
     pub initializer: Option<AnnotatedExpression>,
+    #[serde(default)]
+    pub is_mutable: bool,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedReturnStatement {
     pub value: Option<AnnotatedExpression>,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedExpression {
     pub expr: AnnotatedExpressionKind,
     pub result_type: ResolvedType,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedMatchStatement {
     pub expression: AnnotatedExpression,
     pub arms: Vec<AnnotatedMatchArm>,
     pub result_type: ResolvedType, // Type of the match expression/statement
+    pub span: Span,
 }
 
 /// Annotated if statement with control flow analysis (Expert recommendation: Priority 2)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedIfStatement {
     pub condition: AnnotatedExpression,
     pub then_block: AnnotatedBlock,
     pub else_block: Option<AnnotatedBlock>,
+    pub span: Span,
+}
+
+/// Annotated `while` loop; the condition is always `Bool`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedWhileStatement {
+    pub condition: AnnotatedExpression,
+    pub body: AnnotatedBlock,
+    pub span: Span,
+}
+
+/// Annotated `for <variable> in <iterable>` loop over a `List<element_type>`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedForStatement {
+    pub variable: String,
+    pub element_type: ResolvedType,
+    pub iterable: AnnotatedExpression,
+    pub body: AnnotatedBlock,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedMatchArm {
     pub pattern: AnnotatedPattern,
     pub guard: Option<AnnotatedExpression>,
@@ -2241,7 +3897,7 @@ pub struct AnnotatedMatchArm {
     pub body_type: ResolvedType,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AnnotatedPattern {
     Wildcard,
     Literal(Literal, ResolvedType),
@@ -2251,7 +3907,7 @@ pub enum AnnotatedPattern {
     Enum(String, Option<Vec<AnnotatedPattern>>, ResolvedType),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AnnotatedExpressionKind {
     Literal(Literal),
     Identifier(String),
@@ -2273,7 +3929,23 @@ pub enum AnnotatedExpressionKind {
         object: Box<AnnotatedExpression>,
         field: String,
     },
-    Array {
+    Array {
+        elements: Vec<AnnotatedExpression>,
+    },
+    /// `[value; count]`; `value` is evaluated once
+    ArrayRepeat {
+        value: Box<AnnotatedExpression>,
+        count: usize,
+    },
+    /// `{ key: value, ... }`, inserted in order so a repeated key keeps its last value
+    MapLiteral {
+        entries: Vec<(AnnotatedExpression, AnnotatedExpression)>,
+    },
+    /// `#{a, b, c}`
+    SetLiteral {
+        elements: Vec<AnnotatedExpression>,
+    },
+    Tuple {
         elements: Vec<AnnotatedExpression>,
     },
     Index {
@@ -2284,22 +3956,32 @@ pub enum AnnotatedExpressionKind {
         expression: Box<AnnotatedExpression>,
         arms: Vec<AnnotatedMatchArm>,
     },
+    If {
+        condition: Box<AnnotatedExpression>,
+        then_block: AnnotatedBlock,
+        else_block: Option<AnnotatedBlock>,
+    },
     Call {
         function: String,
         arguments: Vec<AnnotatedExpression>,
     },
     Unary(AnnotatedUnaryExpression),
+    /// `query goal(...)`; `query` is the goals in the syntax of the logic engine
+    Query {
+        goals: Vec<AnnotatedLogicTerm>,
+        query: String,
+    },
 }
 
 /// Annotated unary expression (Expert recommendation: &/&mut support)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotatedUnaryExpression {
     pub operator: UnaryOperator,
     pub operand: Box<AnnotatedExpression>,
 }
 
 /// Resolved type information
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ResolvedType {
     // Primitive types
     Int,
@@ -2370,10 +4052,12 @@ pub enum ResolvedType {
 }
 
 /// Information about a relation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RelationInfo {
     pub name: String,
     pub arg_types: Vec<ResolvedType>,
+    /// Concept each argument was declared with, if any
+    pub arg_concepts: Vec<Option<String>>,
 }
 
 /// Semantic analysis errors
@@ -2385,9 +4069,19 @@ pub enum SemanticError {
     #[error("Undefined relation: {0}")]
     UndefinedRelation(String),
 
+    #[error("Concept {0} is below itself in the is_a hierarchy")]
+    ConceptCycle(String),
+
+    #[error("{name} needs the `{feature}` feature, which this build of AlBayan leaves out")]
+    FeatureDisabled { feature: &'static str, name: String },
+
     #[error("Undefined type: {0}")]
     UndefinedType(String),
 
+    /// A warning that `-D` turned into an error
+    #[error("{0}")]
+    DeniedWarning(SemanticWarning),
+
     #[error("Undefined field {field_name} in struct {struct_name}")]
     UndefinedField {
         struct_name: String,
@@ -2418,6 +4112,15 @@ pub enum SemanticError {
     #[error("Unbound variable in rule head: {0}")]
     UnboundVariable(String),
 
+    #[error("Variable {variable} of negated goal {relation} is not bound by a positive goal")]
+    UnsafeNegation { variable: String, relation: String },
+
+    #[error("Relation {relation} depends on the negation of {negated}, which depends on {relation}")]
+    UnstratifiedNegation { relation: String, negated: String },
+
+    #[error("Goal {0} is not the goal of an aggregate")]
+    MisplacedGoal(String),
+
     #[error("Cannot infer type for variable: {0}")]
     CannotInferType(String),
 
@@ -2433,6 +4136,55 @@ pub enum SemanticError {
     #[error("Unreachable code: {0}")]
     UnreachableCode(String),
 
+    #[error("`{0}` outside of a loop")]
+    ControlFlowOutsideLoop(String),
+
+    #[error("Type {0:?} cannot be displayed")]
+    NotDisplayable(ResolvedType),
+
+    #[error("Invalid format string: {0}")]
+    InvalidFormatString(String),
+
+    #[error("Invalid tuple index {index:?} for a tuple of {len} element(s)")]
+    InvalidTupleIndex { index: Option<i64>, len: usize },
+
+    #[error("Trait {trait_name} has no associated type {name}")]
+    UndefinedAssociatedType { trait_name: String, name: String },
+
+    #[error("Impl of {trait_name} for {type_name} is missing associated type {name}")]
+    MissingAssociatedType {
+        trait_name: String,
+        type_name: String,
+        name: String,
+    },
+
+    #[error("Conflicting implementations of trait {trait_name} for type {type_name}")]
+    ConflictingImpl { trait_name: String, type_name: String },
+
+    #[error("Impl of {trait_name} for {type_name} is missing method {method}")]
+    MissingTraitMethod {
+        trait_name: String,
+        type_name: String,
+        method: String,
+    },
+
+    #[error("Method {method} does not match trait {trait_name}: expected {expected:?}, found {found:?}")]
+    TraitMethodMismatch {
+        trait_name: String,
+        method: String,
+        expected: ResolvedType,
+        found: ResolvedType,
+    },
+
+    #[error("Index {index} out of bounds for an array of length {length}")]
+    IndexOutOfBounds { index: i64, length: usize },
+
+    #[error("Type {0:?} cannot be a map key or set element")]
+    UnhashableKey(ResolvedType),
+
+    #[error("Cannot evaluate constant: {0}")]
+    ConstEvaluation(String),
+
     #[error("Other error: {0}")]
     Other(String),
 
@@ -2451,6 +4203,9 @@ pub enum SemanticError {
     #[error("Use after move: {0}")]
     UseAfterMove(String),
 
+    #[error("Use of possibly-uninitialized variable: {0}")]
+    UseOfUninitialized(String),
+
     #[error("Conflicting borrow: {0}")]
     ConflictingBorrow(String),
 
@@ -2460,6 +4215,12 @@ pub enum SemanticError {
     #[error("Borrow mutable from immutable: {0}")]
     BorrowMutableFromImmutable(String),
 
+    #[error("Cannot assign twice to immutable variable '{0}'")]
+    AssignToImmutable(String),
+
+    #[error("Cannot assign through '{0}', which is a & reference")]
+    AssignThroughSharedReference(String),
+
     #[error("Invalid borrow: {0}")]
     InvalidBorrow(String),
 
@@ -2475,6 +4236,74 @@ pub enum SemanticError {
         variable_name: String,
         message: String,
     },
+
+    #[error(transparent)]
+    Cancelled(#[from] crate::cancellation::Cancelled),
+
+    /// An error located at a span of the source
+    #[error("{error}")]
+    Spanned {
+        span: Span,
+        error: Box<SemanticError>,
+    },
+
+    /// An item used outside the module that declared it without `pub`
+    #[error("{kind} `{name}` is private to module `{module}`")]
+    PrivateItem {
+        kind: symbol_table::ItemKind,
+        name: String,
+        module: String,
+    },
+
+    /// An error in an imported module of a multi-file program
+    #[error("in module `{module}`: {error}")]
+    InModule {
+        module: String,
+        error: Box<SemanticError>,
+    },
+}
+
+/// Whether `name` is one of the `ai::` or `torch_` built-ins of the `ai` feature
+pub fn is_ai_builtin(name: &str) -> bool {
+    name.starts_with("ai::") || name.starts_with("torch_")
+}
+
+/// The error for calling `name` when no function of that name is in scope
+fn undefined_function(name: &str) -> SemanticError {
+    if !cfg!(feature = "ai") && is_ai_builtin(name) {
+        SemanticError::FeatureDisabled { feature: "ai", name: name.to_string() }
+    } else {
+        SemanticError::UndefinedVariable(name.to_string())
+    }
+}
+
+impl SemanticError {
+    /// Attach a source location; an error that already has one keeps the innermost
+    pub fn with_span(self, span: Option<Span>) -> Self {
+        match (self, span) {
+            (error @ SemanticError::Spanned { .. }, _) | (error, None) => error,
+            (error, Some(span)) => SemanticError::Spanned {
+                span,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Source location of the error, if known
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            SemanticError::Spanned { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+
+    /// The error without its source location or module
+    pub fn inner(&self) -> &SemanticError {
+        match self {
+            SemanticError::Spanned { error, .. } | SemanticError::InModule { error, .. } => error.inner(),
+            error => error,
+        }
+    }
 }
 
 impl SemanticAnalyzer {
@@ -2504,8 +4333,17 @@ impl SemanticAnalyzer {
                 Ok(AnnotatedPattern::Literal(literal.clone(), literal_type))
             }
             Pattern::Identifier(name) => {
+                // A constant matches its value rather than binding a new name
+                if let Some((_, value)) = self.symbol_table.lookup_constant(name) {
+                    self.symbol_table.check_access(symbol_table::ItemKind::Constant, name)?;
+                    let literal = Pattern::Literal(value.to_literal());
+                    return self.check_pattern(&literal, match_type);
+                }
+
                 // Bind the identifier to the match type in current scope
                 self.symbol_table.declare_variable(name, match_type)?;
+                self.ownership_analyzer
+                    .declare_variable(name, match_type.clone(), false)?;
                 // Register for destruction if needed (Expert recommendation: Priority 1)
                 self.ownership_analyzer.register_for_destruction(
                     name,
@@ -2559,15 +4397,27 @@ impl SemanticAnalyzer {
                             });
                         }
 
-                        // TODO: Check field patterns against struct definition
-                        // For now, just return the pattern as-is
+                        let struct_fields = match self.symbol_table.lookup_type(struct_name) {
+                            Some(symbol_table::TypeInfo {
+                                kind: symbol_table::TypeKind::Struct(fields),
+                                ..
+                            }) => fields.clone(),
+                            _ => return Err(SemanticError::UndefinedType(struct_name.clone())),
+                        };
+
+                        // Each field pattern is checked against the declared field type
                         let annotated_field_patterns = field_patterns
                             .iter()
                             .map(|(field_name, pattern)| {
-                                // For simplicity, assume field type is the same as match type
-                                // In a real implementation, we'd look up the struct definition
+                                let field_info = struct_fields
+                                    .iter()
+                                    .find(|f| &f.name == field_name)
+                                    .ok_or_else(|| SemanticError::UndefinedField {
+                                        struct_name: struct_name.clone(),
+                                        field_name: field_name.clone(),
+                                    })?;
                                 let field_pattern =
-                                    self.check_pattern(pattern, &ResolvedType::Int)?;
+                                    self.check_pattern(pattern, &field_info.field_type)?;
                                 Ok((field_name.clone(), field_pattern))
                             })
                             .collect::<Result<Vec<_>, SemanticError>>()?;
@@ -2625,11 +4475,19 @@ impl SemanticAnalyzer {
                                 variant_name: variant_name.to_string(),
                             })?;
 
-                        // Check variant patterns
+                        // Check variant patterns against the variant's payload types
+                        let payload_types = variant_info.fields.clone().unwrap_or_default();
                         let annotated_variant_patterns = if let Some(patterns) = variant_patterns {
+                            if patterns.len() != payload_types.len() {
+                                return Err(SemanticError::ArityMismatch {
+                                    expected: payload_types.len(),
+                                    found: patterns.len(),
+                                });
+                            }
                             let annotated_patterns = patterns
                                 .iter()
-                                .map(|pattern| self.check_pattern(pattern, &ResolvedType::Int)) // placeholder
+                                .zip(payload_types.iter())
+                                .map(|(pattern, payload_type)| self.check_pattern(pattern, payload_type))
                                 .collect::<Result<Vec<_>, SemanticError>>()?;
                             Some(annotated_patterns)
                         } else {
@@ -2784,50 +4642,6 @@ impl SemanticAnalyzer {
         }
     }
 
-    /// Check method call borrowing requirements (Expert recommendation: Priority 2)
-    fn check_method_call_borrowing(
-        &mut self,
-        var_name: &str,
-        method_name: &str,
-        object_type: &ResolvedType,
-    ) -> Result<(), SemanticError> {
-        // Find the method in impl blocks to determine self parameter type
-        if let Some(method_info) = self.find_method_in_impls(object_type, method_name) {
-            // Check the first parameter to see if it's a self parameter
-            if !method_info.parameters.is_empty() {
-                let first_param = &method_info.parameters[0];
-
-                match first_param {
-                    // &self - create immutable borrow
-                    ResolvedType::Reference(_, false) => {
-                        let scope_depth = self.ownership_analyzer.get_current_scope_depth();
-                        self.ownership_analyzer.add_method_borrow(
-                            var_name,
-                            crate::semantic::ownership::BorrowKind::Immutable,
-                            scope_depth,
-                        )?;
-                    }
-                    // &mut self - create mutable borrow
-                    ResolvedType::Reference(_, true) => {
-                        let scope_depth = self.ownership_analyzer.get_current_scope_depth();
-                        self.ownership_analyzer.add_method_borrow(
-                            var_name,
-                            crate::semantic::ownership::BorrowKind::Mutable,
-                            scope_depth,
-                        )?;
-                    }
-                    // self (by value) - create move
-                    _ => {
-                        // For self by value, we need to move the variable
-                        self.ownership_analyzer.mark_as_moved(var_name)?;
-                    }
-                }
-            }
-        }
-
-        Ok(())
-    }
-
     /// Check match exhaustiveness (Expert recommendation: Enhanced exhaustiveness checking)
     fn check_match_exhaustiveness(
         &self,
@@ -3373,68 +5187,6 @@ impl SemanticAnalyzer {
         })
     }
 
-    /// Check if two types are compatible (Expert fix: print function - Generic Type Parameter Unification)
-    fn is_type_compatible(&self, actual_type: &ResolvedType, expected_type: &ResolvedType) -> bool {
-        match (actual_type, expected_type) {
-            // Exact match
-            (a, b) if a == b => true,
-
-            // Generic parameter can match any type
-            (_, ResolvedType::GenericParam(_)) => true,
-
-            // Generic parameter can be unified with any type
-            (ResolvedType::GenericParam(_), _) => true,
-
-            // Reference types compatibility
-            (
-                ResolvedType::Reference(inner1, mutable1),
-                ResolvedType::Reference(inner2, mutable2),
-            ) => mutable1 == mutable2 && self.is_type_compatible(inner1, inner2),
-
-            // Collection types compatibility
-            (ResolvedType::List(inner1), ResolvedType::List(inner2)) => {
-                self.is_type_compatible(inner1, inner2)
-            }
-
-            // Array types compatibility
-            (ResolvedType::Array(inner1), ResolvedType::Array(inner2)) => {
-                self.is_type_compatible(inner1, inner2)
-            }
-
-            // Generic types compatibility
-            (ResolvedType::Generic(name1, params1), ResolvedType::Generic(name2, params2)) => {
-                name1 == name2
-                    && params1.len() == params2.len()
-                    && params1
-                        .iter()
-                        .zip(params2.iter())
-                        .all(|(p1, p2)| self.is_type_compatible(p1, p2))
-            }
-
-            // Function types compatibility
-            (ResolvedType::Function(params1, ret1), ResolvedType::Function(params2, ret2)) => {
-                params1.len() == params2.len()
-                    && params1
-                        .iter()
-                        .zip(params2.iter())
-                        .all(|(p1, p2)| self.is_type_compatible(p1, p2))
-                    && self.is_type_compatible(ret1, ret2)
-            }
-
-            // Tuple types compatibility
-            (ResolvedType::Tuple(types1), ResolvedType::Tuple(types2)) => {
-                types1.len() == types2.len()
-                    && types1
-                        .iter()
-                        .zip(types2.iter())
-                        .all(|(t1, t2)| self.is_type_compatible(t1, t2))
-            }
-
-            // No match
-            _ => false,
-        }
-    }
-
     /// Register built-in functions (Expert fix: print function)
     fn register_builtin_functions(&mut self) {
         // print<T: Display>(value: T) -> ()
@@ -3448,6 +5200,17 @@ impl SemanticAnalyzer {
             },
         );
 
+        // println<T: Display>(value: T) -> ()
+        // Same as print; both also accept `(template, args...)` like format
+        self.symbol_table.add_function_info(
+            "println",
+            FunctionInfo {
+                name: "println".to_string(),
+                parameters: vec![ResolvedType::GenericParam("T".to_string())], // T
+                return_type: Some(ResolvedType::Unit),
+            },
+        );
+
         // format(template: string, args: ...) -> string
         self.symbol_table.add_function_info(
             "format",
@@ -3490,6 +5253,196 @@ impl SemanticAnalyzer {
                 return_type: Some(ResolvedType::Unit),
             },
         );
+
+        // assert_eq<T>(left: T, right: T) -> (), assert_ne likewise
+        // Panics unless the values are equal (or differ); used by `#[test]` functions
+        for name in ["assert_eq", "assert_ne"] {
+            self.symbol_table.add_function_info(
+                name,
+                FunctionInfo {
+                    name: name.to_string(),
+                    parameters: vec![
+                        ResolvedType::GenericParam("T".to_string()),
+                        ResolvedType::GenericParam("T".to_string()),
+                    ],
+                    return_type: Some(ResolvedType::Unit),
+                },
+            );
+        }
+
+        // assert(condition: bool, message: string) -> ()
+        self.symbol_table.add_function_info(
+            "assert",
+            FunctionInfo {
+                name: "assert".to_string(),
+                parameters: vec![ResolvedType::Bool, ResolvedType::String],
+                return_type: Some(ResolvedType::Unit),
+            },
+        );
+
+        // assert_true(condition: bool) -> ()
+        self.symbol_table.add_function_info(
+            "assert_true",
+            FunctionInfo {
+                name: "assert_true".to_string(),
+                parameters: vec![ResolvedType::Bool],
+                return_type: Some(ResolvedType::Unit),
+            },
+        );
+
+        // panic(message: string) -> ()
+        self.symbol_table.add_function_info(
+            "panic",
+            FunctionInfo {
+                name: "panic".to_string(),
+                parameters: vec![ResolvedType::String],
+                return_type: Some(ResolvedType::Unit),
+            },
+        );
+
+        // animate(equation: string, param_range: string, frames: int) -> string
+        // Renders a GIF by sweeping shape equation parameters, returns the file path
+        self.symbol_table.add_function_info(
+            "animate",
+            FunctionInfo {
+                name: "animate".to_string(),
+                parameters: vec![ResolvedType::String, ResolvedType::String, ResolvedType::Int],
+                return_type: Some(ResolvedType::String),
+            },
+        );
+
+        // canvas_show(equation: string) -> bool
+        // Streams a rendered equation or basic shape to the live canvas (albayan run --canvas)
+        self.symbol_table.add_function_info(
+            "canvas_show",
+            FunctionInfo {
+                name: "canvas_show".to_string(),
+                parameters: vec![ResolvedType::String],
+                return_type: Some(ResolvedType::Bool),
+            },
+        );
+
+        // canvas_infer(equation: string) -> float
+        // Renders the shape, runs shape inference on it and streams both; returns the confidence
+        self.symbol_table.add_function_info(
+            "canvas_infer",
+            FunctionInfo {
+                name: "canvas_infer".to_string(),
+                parameters: vec![ResolvedType::String],
+                return_type: Some(ResolvedType::Float),
+            },
+        );
+
+        // canvas_clear() -> bool
+        self.symbol_table.add_function_info(
+            "canvas_clear",
+            FunctionInfo {
+                name: "canvas_clear".to_string(),
+                parameters: vec![],
+                return_type: Some(ResolvedType::Bool),
+            },
+        );
+
+        // render_shape(equation: string) -> RenderedImage
+        // Renders a registered equation or basic shape and returns the image itself
+        self.symbol_table.add_function_info(
+            "render_shape",
+            FunctionInfo {
+                name: "render_shape".to_string(),
+                parameters: vec![ResolvedType::String],
+                return_type: Some(ResolvedType::Struct("RenderedImage".to_string())),
+            },
+        );
+
+        // scene_extract(source: string) -> int
+        // Detects the shapes in an image file (or registered equation) and asserts spatial
+        // facts such as circle(circle1), above(circle1, square1); returns the object count
+        self.symbol_table.add_function_info(
+            "scene_extract",
+            FunctionInfo {
+                name: "scene_extract".to_string(),
+                parameters: vec![ResolvedType::String],
+                return_type: Some(ResolvedType::Int),
+            },
+        );
+
+        // scene_query(query: string) -> int
+        // Queries the scene facts, e.g. "above(X, square1)"; returns the number of solutions
+        self.symbol_table.add_function_info(
+            "scene_query",
+            FunctionInfo {
+                name: "scene_query".to_string(),
+                parameters: vec![ResolvedType::String],
+                return_type: Some(ResolvedType::Int),
+            },
+        );
+
+        // scene_assert(fact: string) -> bool
+        // Adds a fact such as "فوق(ش1, ش2)" or "color(sun1, yellow)" to the scene facts
+        self.symbol_table.add_function_info(
+            "scene_assert",
+            FunctionInfo {
+                name: "scene_assert".to_string(),
+                parameters: vec![ResolvedType::String],
+                return_type: Some(ResolvedType::Bool),
+            },
+        );
+
+        // render_scene(query: string) -> int
+        // Lays out the objects selected by the query (all objects if empty) according to their
+        // above/left_of/larger relations and streams the picture; returns the object count
+        self.symbol_table.add_function_info(
+            "render_scene",
+            FunctionInfo {
+                name: "render_scene".to_string(),
+                parameters: vec![ResolvedType::String],
+                return_type: Some(ResolvedType::Int),
+            },
+        );
+
+        // random_seed(seed: int) -> ()
+        // Restarts the shared random generator so the following draws are reproducible
+        self.symbol_table.add_function_info(
+            "random_seed",
+            FunctionInfo {
+                name: "random_seed".to_string(),
+                parameters: vec![ResolvedType::Int],
+                return_type: Some(ResolvedType::Unit),
+            },
+        );
+
+        // random_int(low: int, high: int) -> int
+        // Uniform integer in [low, high] from the seeded generator
+        self.symbol_table.add_function_info(
+            "random_int",
+            FunctionInfo {
+                name: "random_int".to_string(),
+                parameters: vec![ResolvedType::Int, ResolvedType::Int],
+                return_type: Some(ResolvedType::Int),
+            },
+        );
+
+        // random_float() -> float
+        // Uniform float in [0, 1) from the seeded generator
+        self.symbol_table.add_function_info(
+            "random_float",
+            FunctionInfo {
+                name: "random_float".to_string(),
+                parameters: vec![],
+                return_type: Some(ResolvedType::Float),
+            },
+        );
+
+        // infer_shape(equation: string) -> InferenceResult
+        // Renders the shape and runs shape inference on it; the result can be matched on
+        self.symbol_table.add_function_info(
+            "infer_shape",
+            FunctionInfo {
+                name: "infer_shape".to_string(),
+                parameters: vec![ResolvedType::String],
+                return_type: Some(ResolvedType::Struct("InferenceResult".to_string())),
+            },
+        );
     }
 
     /// Register std::math_ai::shape_inference functions (Expert Priority 4)
@@ -3607,6 +5560,31 @@ impl SemanticAnalyzer {
         );
     }
 
+    /// Register std::math_ai::adaptive_equations cross-validation and model selection functions
+    fn register_adaptive_equation_functions(&mut self) {
+        use crate::semantic::FunctionInfo;
+
+        // equation_cross_validate(equation: string, data: string, folds: int) -> float
+        self.symbol_table.add_function_info(
+            "equation_cross_validate",
+            FunctionInfo {
+                name: "equation_cross_validate".to_string(),
+                parameters: vec![ResolvedType::String, ResolvedType::String, ResolvedType::Int],
+                return_type: Some(ResolvedType::Float),
+            },
+        );
+
+        // equation_select_model(equation: string, data: string, folds: int) -> float
+        self.symbol_table.add_function_info(
+            "equation_select_model",
+            FunctionInfo {
+                name: "equation_select_model".to_string(),
+                parameters: vec![ResolvedType::String, ResolvedType::String, ResolvedType::Int],
+                return_type: Some(ResolvedType::Float),
+            },
+        );
+    }
+
     /// Register std::thinking functions (Expert Priority 3: ThinkingCore)
     /// Expert Request: Create tests for ThinkingCore that call find_semantically_similar_words
     fn register_thinking_core_functions(&mut self) {
@@ -3741,5 +5719,55 @@ impl SemanticAnalyzer {
                 return_type: Some(ResolvedType::Bool),
             },
         );
+
+        // thinking_pipeline_create(name: string) -> bool
+        self.symbol_table.add_function_info(
+            "thinking_pipeline_create",
+            FunctionInfo {
+                name: "thinking_pipeline_create".to_string(),
+                parameters: vec![ResolvedType::String],
+                return_type: Some(ResolvedType::Bool),
+            },
+        );
+
+        // thinking_pipeline_add_layer(pipeline: string, layer: string) -> bool
+        self.symbol_table.add_function_info(
+            "thinking_pipeline_add_layer",
+            FunctionInfo {
+                name: "thinking_pipeline_add_layer".to_string(),
+                parameters: vec![ResolvedType::String, ResolvedType::String],
+                return_type: Some(ResolvedType::Bool),
+            },
+        );
+
+        // thinking_pipeline_configure_layer(pipeline: string, layer: string, weight: float, threshold: float) -> bool
+        self.symbol_table.add_function_info(
+            "thinking_pipeline_configure_layer",
+            FunctionInfo {
+                name: "thinking_pipeline_configure_layer".to_string(),
+                parameters: vec![ResolvedType::String, ResolvedType::String, ResolvedType::Float, ResolvedType::Float],
+                return_type: Some(ResolvedType::Bool),
+            },
+        );
+
+        // thinking_pipeline_set_layer_enabled(pipeline: string, layer: string, enabled: bool) -> bool
+        self.symbol_table.add_function_info(
+            "thinking_pipeline_set_layer_enabled",
+            FunctionInfo {
+                name: "thinking_pipeline_set_layer_enabled".to_string(),
+                parameters: vec![ResolvedType::String, ResolvedType::String, ResolvedType::Bool],
+                return_type: Some(ResolvedType::Bool),
+            },
+        );
+
+        // thinking_pipeline_apply(core: string, pipeline: string) -> bool
+        self.symbol_table.add_function_info(
+            "thinking_pipeline_apply",
+            FunctionInfo {
+                name: "thinking_pipeline_apply".to_string(),
+                parameters: vec![ResolvedType::String, ResolvedType::String],
+                return_type: Some(ResolvedType::Bool),
+            },
+        );
     }
 }
@@ -0,0 +1,1427 @@
+//! # Symbol Table Implementation
+//!
+//! This module implements the symbol table for tracking variables, functions,
+//! types, and other symbols during semantic analysis.
+//!
+//! Large programs resolve the same type names and look up the same functions
+//! and constants over and over, so the table memoizes `resolve_type_name` for
+//! named types and the keys that function and constant names resolve to. The
+//! memos are dropped whenever a declaration, the current module or a generic
+//! scope changes what a name could resolve to; entering and leaving plain
+//! block scopes keeps them. [`SymbolTable::cache_stats`] reports how often they
+//! were hit.
+//!
+//! The table also notes which variables are read and which imports are used,
+//! for the warnings of [`super::warnings`]: a variable declared with
+//! [`SymbolTable::warn_if_unread`] that leaves its scope unread, and an
+//! import no name resolved through, are reported by
+//! [`SymbolTable::take_warnings`] and [`SymbolTable::unused_imports`].
+
+use crate::parser::ast::*;
+use super::const_eval::{self, ConstValue};
+use super::warnings::{SemanticWarning, WarningKind};
+use super::{ResolvedType, RelationInfo, SemanticError, Substitution, TypeChecker};
+use crate::lexer::Span;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// The built-in trait whose `drop(&mut self)` runs when an owned value of the
+/// implementing type goes out of scope
+pub const DROP_TRAIT: &str = "Drop";
+
+/// Symbol table for managing scopes and symbol resolution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolTable {
+    /// Stack of scopes (global scope at bottom)
+    scopes: Vec<Scope>,
+    /// Global type definitions
+    types: HashMap<String, TypeInfo>,
+    /// Global function definitions
+    functions: HashMap<String, FunctionInfo>,
+    /// Global relation definitions
+    relations: HashMap<String, RelationInfo>,
+    /// Declared ontology concepts with their direct parents
+    concepts: HashMap<String, Vec<String>>,
+    /// Global trait definitions (Expert recommendation: Priority 1)
+    traits: HashMap<String, TraitInfo>,
+    /// Global impl definitions (Expert recommendation: Priority 1)
+    impls: Vec<ImplInfo>,
+    /// Built-in struct/enum types that user declarations may replace, kept
+    /// sorted so a cached analysis serializes the same way every time
+    builtin_types: BTreeSet<String>,
+    /// Generic parameter names of generic structs, in declaration order
+    struct_generic_params: HashMap<String, Vec<String>>,
+    /// Global `const` items with their declared types and values
+    constants: HashMap<String, (ResolvedType, ConstValue)>,
+    /// Module whose items are being declared and analyzed; its functions and
+    /// constants are qualified with it, e.g. `geometry::area`
+    module: Option<String>,
+    /// Names the current module's `using` declarations bring into scope,
+    /// mapped to the qualified path each stands for
+    imports: HashMap<String, String>,
+    /// Items and fields declared without `pub`, with the module that
+    /// declared them (`None` for the entry file), the only one that may use them
+    #[serde(with = "private_item_entries")]
+    private_items: HashMap<(ItemKind, String), Option<String>>,
+    /// Named types resolved since the last change to the declarations
+    #[serde(skip)]
+    resolved_types: RefCell<HashMap<String, ResolvedType>>,
+    /// Keys function and constant names resolved to since the last change
+    #[serde(skip)]
+    resolved_keys: RefCell<HashMap<(ItemKind, String), Option<String>>>,
+    #[serde(skip)]
+    cache_stats: Cell<CacheStats>,
+    /// Imports of the current module some name resolved through
+    #[serde(skip)]
+    used_imports: RefCell<HashSet<String>>,
+    /// Warnings for variables that left their scope unread
+    #[serde(skip)]
+    warnings: Vec<SemanticWarning>,
+}
+
+/// How often the memoized resolutions were reused
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Times a declaration or scope change dropped the memos
+    pub invalidations: u64,
+}
+
+/// The kinds of declarations a module can keep private. Fields are named
+/// `Type.field`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ItemKind {
+    Function,
+    Constant,
+    Type,
+    Field,
+    Relation,
+}
+
+impl std::fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ItemKind::Function => "function",
+            ItemKind::Constant => "constant",
+            ItemKind::Type => "type",
+            ItemKind::Field => "field",
+            ItemKind::Relation => "relation",
+        })
+    }
+}
+
+/// A single scope containing local symbols
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Scope {
+    /// Variables in this scope
+    variables: HashMap<String, VariableInfo>,
+    /// Generic type parameters in this scope (Expert recommendation: Priority 1)
+    generic_params: HashMap<String, String>,  // name -> name (for now)
+    /// Type names bound to a concrete type in this scope (`Self`, `Self::Item`)
+    type_aliases: HashMap<String, ResolvedType>,
+    /// Scope type (function, block, etc.)
+    scope_type: ScopeType,
+}
+
+/// Type of scope
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum ScopeType {
+    Global,
+    Function,
+    Block,
+    Loop,
+}
+
+/// Information about a variable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariableInfo {
+    pub name: String,
+    pub var_type: ResolvedType,
+    pub is_mutable: bool,
+    pub is_initialized: bool,
+    /// Whether an expression has read the variable
+    #[serde(default)]
+    pub is_read: bool,
+    /// Warning to report if the variable leaves its scope unread
+    #[serde(skip)]
+    pub unread_warning: Option<(WarningKind, Option<Span>)>,
+}
+
+/// Information about a function
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionInfo {
+    pub name: String,
+    pub parameters: Vec<ResolvedType>,
+    pub return_type: Option<ResolvedType>,
+}
+
+/// Information about a type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeInfo {
+    pub name: String,
+    pub kind: TypeKind,
+}
+
+/// Kind of type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TypeKind {
+    Struct(Vec<StructFieldInfo>),
+    Enum(Vec<EnumVariantInfo>),
+    Class(Vec<StructFieldInfo>, Vec<FunctionInfo>),
+    Interface(Vec<FunctionInfo>),
+    Primitive,
+}
+
+/// Information about a struct field
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructFieldInfo {
+    pub name: String,
+    pub field_type: ResolvedType,
+}
+
+/// Information about an enum variant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnumVariantInfo {
+    pub name: String,
+    pub fields: Option<Vec<ResolvedType>>,
+}
+
+/// Information about a trait (Expert recommendation: Priority 1)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraitInfo {
+    pub name: String,
+    /// Names of the associated types declared with `type Name;`
+    pub associated_types: Vec<String>,
+    pub methods: Vec<TraitMethodInfo>,
+}
+
+/// Information about a trait method (Expert recommendation: Priority 1)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraitMethodInfo {
+    pub name: String,
+    pub parameters: Vec<ResolvedType>,
+    pub return_type: Option<ResolvedType>,
+    pub has_default_impl: bool,
+    /// Whether the first parameter is `self`, `&self` or `&mut self`
+    pub has_receiver: bool,
+}
+
+/// Information about an impl block (Expert recommendation: Priority 1)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImplInfo {
+    pub trait_name: Option<String>,  // None for inherent impl
+    pub type_name: String,
+    /// Associated types defined by the impl, by name
+    pub associated_types: HashMap<String, ResolvedType>,
+    pub methods: Vec<FunctionInfo>,
+    /// Implemented for one of the impl's own generic parameters (`impl<T> Trait for T`)
+    pub is_blanket: bool,
+}
+
+impl SymbolTable {
+    /// Create a new symbol table with global scope
+    pub fn new() -> Self {
+        let mut symbol_table = Self {
+            scopes: vec![Scope {
+                variables: HashMap::new(),
+                generic_params: HashMap::new(),
+                type_aliases: HashMap::new(),
+                scope_type: ScopeType::Global,
+            }],
+            types: HashMap::new(),
+            functions: HashMap::new(),
+            relations: HashMap::new(),
+            concepts: HashMap::new(),
+            traits: HashMap::new(),  // NEWLY ADDED: Expert recommendation
+            impls: Vec::new(),       // NEWLY ADDED: Expert recommendation
+            builtin_types: BTreeSet::new(),
+            struct_generic_params: HashMap::new(),
+            constants: HashMap::new(),
+            module: None,
+            imports: HashMap::new(),
+            private_items: HashMap::new(),
+            resolved_types: RefCell::new(HashMap::new()),
+            resolved_keys: RefCell::new(HashMap::new()),
+            cache_stats: Cell::new(CacheStats::default()),
+            used_imports: RefCell::new(HashSet::new()),
+            warnings: Vec::new(),
+        };
+
+        // Add built-in types
+        symbol_table.add_builtin_types();
+        symbol_table.add_builtin_traits();
+
+        symbol_table
+    }
+
+    /// Add built-in types to the symbol table
+    fn add_builtin_types(&mut self) {
+        let builtin_types = [
+            ("int", TypeKind::Primitive),
+            ("float", TypeKind::Primitive),
+            ("bool", TypeKind::Primitive),
+            ("string", TypeKind::Primitive),
+            ("char", TypeKind::Primitive),
+        ];
+
+        for (name, kind) in builtin_types {
+            self.types.insert(name.to_string(), TypeInfo {
+                name: name.to_string(),
+                kind,
+            });
+        }
+    }
+
+    /// Add the built-in traits to the symbol table
+    fn add_builtin_traits(&mut self) {
+        let drop = TraitMethodInfo {
+            name: "drop".to_string(),
+            parameters: vec![ResolvedType::Reference(
+                Box::new(ResolvedType::GenericParam("Self".to_string())),
+                true,
+            )],
+            return_type: None,
+            has_default_impl: false,
+            has_receiver: true,
+        };
+        self.traits.insert(DROP_TRAIT.to_string(), TraitInfo {
+            name: DROP_TRAIT.to_string(),
+            associated_types: Vec::new(),
+            methods: vec![drop],
+        });
+    }
+
+    /// Enter a new scope
+    pub fn enter_scope(&mut self) {
+        self.scopes.push(Scope {
+            variables: HashMap::new(),
+            generic_params: HashMap::new(),
+            type_aliases: HashMap::new(),
+            scope_type: ScopeType::Block,
+        });
+    }
+
+    /// Enter a function scope
+    pub fn enter_function_scope(&mut self) {
+        self.scopes.push(Scope {
+            variables: HashMap::new(),
+            generic_params: HashMap::new(),
+            type_aliases: HashMap::new(),
+            scope_type: ScopeType::Function,
+        });
+    }
+
+    /// Exit the current scope
+    pub fn exit_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            let scope = self.scopes.pop().unwrap();
+            if !scope.generic_params.is_empty() || !scope.type_aliases.is_empty() {
+                self.invalidate_caches();
+            }
+            for variable in scope.variables.into_values().filter(|variable| !variable.is_read) {
+                if let Some((kind, span)) = variable.unread_warning {
+                    self.warnings.push(SemanticWarning { kind, name: variable.name, span, file: None });
+                }
+            }
+        }
+    }
+
+    /// Forget the memoized resolutions, which a new declaration or binding may change
+    fn invalidate_caches(&mut self) {
+        self.resolved_types.get_mut().clear();
+        self.resolved_keys.get_mut().clear();
+        let stats = self.cache_stats.get_mut();
+        stats.invalidations += 1;
+    }
+
+    /// Hits and misses of the memoized resolutions so far
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache_stats.get()
+    }
+
+    fn count_lookup(&self, hit: bool) {
+        let mut stats = self.cache_stats.get();
+        if hit {
+            stats.hits += 1;
+        } else {
+            stats.misses += 1;
+        }
+        self.cache_stats.set(stats);
+    }
+
+    /// Declare a variable in the current scope
+    pub fn declare_variable(&mut self, name: &str, var_type: &ResolvedType) -> Result<(), SemanticError> {
+        self.declare_binding(name, var_type, false)
+    }
+
+    /// Declare a variable that assignments may change when `is_mutable`
+    pub fn declare_binding(&mut self, name: &str, var_type: &ResolvedType, is_mutable: bool) -> Result<(), SemanticError> {
+        let current_scope = self.scopes.last_mut().unwrap();
+
+        if current_scope.variables.contains_key(name) {
+            return Err(SemanticError::Redefinition(name.to_string()));
+        }
+
+        current_scope.variables.insert(name.to_string(), VariableInfo {
+            name: name.to_string(),
+            var_type: var_type.clone(),
+            is_mutable,
+            is_initialized: true,
+            is_read: false,
+            unread_warning: None,
+        });
+
+        Ok(())
+    }
+
+    /// Declare `name` by a `let` without a value, which its first assignment initializes
+    pub fn declare_uninitialized(&mut self, name: &str, var_type: &ResolvedType, is_mutable: bool) -> Result<(), SemanticError> {
+        self.declare_binding(name, var_type, is_mutable)?;
+        if let Some(variable) = self.scopes.last_mut().unwrap().variables.get_mut(name) {
+            variable.is_initialized = false;
+        }
+        Ok(())
+    }
+
+    /// Report the variable `name` of the current scope with a `kind` warning
+    /// at `span` if it leaves the scope unread; `_` names are exempt
+    pub fn warn_if_unread(&mut self, name: &str, kind: WarningKind, span: Option<Span>) {
+        if name.starts_with('_') {
+            return;
+        }
+        if let Some(variable) = self.scopes.last_mut().unwrap().variables.get_mut(name) {
+            variable.unread_warning = Some((kind, span));
+        }
+    }
+
+    /// Note that the variable `name` in scope has been read
+    pub fn mark_read(&mut self, name: &str) {
+        if let Some(variable) = self.scopes.iter_mut().rev().find_map(|scope| scope.variables.get_mut(name)) {
+            variable.is_read = true;
+        }
+    }
+
+    /// Warnings for the variables that left their scope unread so far,
+    /// ordered by position
+    pub fn take_warnings(&mut self) -> Vec<SemanticWarning> {
+        let mut warnings = std::mem::take(&mut self.warnings);
+        warnings.sort_by_key(|warning| warning.span.map(|span| span.start));
+        warnings
+    }
+
+    /// Local names of the current module's imports that no name resolved through
+    pub fn unused_imports(&self) -> Vec<String> {
+        let used = self.used_imports.borrow();
+        let mut unused: Vec<String> = self.imports.keys().filter(|name| !used.contains(*name)).cloned().collect();
+        unused.sort();
+        unused
+    }
+
+    /// Note that a name starting with `head` was looked up, which uses the
+    /// import binding `head` if there is one
+    fn note_import_use(&self, head: &str) {
+        if self.imports.contains_key(head) {
+            self.used_imports.borrow_mut().insert(head.to_string());
+        }
+    }
+
+    /// Look up a variable in all scopes (starting from current)
+    pub fn lookup_variable(&self, name: &str) -> Option<&VariableInfo> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(var_info) = scope.variables.get(name) {
+                return Some(var_info);
+            }
+        }
+        None
+    }
+
+    /// Declare a generic type parameter in the current scope (Expert recommendation: Priority 1)
+    pub fn declare_generic_param(&mut self, name: &str) -> Result<(), SemanticError> {
+        let current_scope = self.scopes.last_mut().unwrap();
+
+        if current_scope.generic_params.contains_key(name) {
+            return Err(SemanticError::Redefinition(name.to_string()));
+        }
+
+
+        current_scope.generic_params.insert(name.to_string(), name.to_string());
+        self.invalidate_caches();
+        Ok(())
+    }
+
+    /// Look up a generic type parameter in all scopes (Expert recommendation: Priority 1)
+    pub fn lookup_generic_param(&self, name: &str) -> bool {
+        for scope in self.scopes.iter().rev() {
+            if scope.generic_params.contains_key(name) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Bind `Self` in the current scope to the type a trait or impl is about
+    pub fn declare_self_type(&mut self, self_type: ResolvedType) {
+        let current_scope = self.scopes.last_mut().unwrap();
+        current_scope.type_aliases.insert("Self".to_string(), self_type);
+        self.invalidate_caches();
+    }
+
+    /// Bind the associated type `Self::name` in the current scope
+    pub fn declare_associated_type(&mut self, name: &str, resolved: ResolvedType) -> Result<(), SemanticError> {
+        let current_scope = self.scopes.last_mut().unwrap();
+        let path = format!("Self::{}", name);
+        if current_scope.type_aliases.contains_key(&path) {
+            return Err(SemanticError::Redefinition(path));
+        }
+        current_scope.type_aliases.insert(path, resolved);
+        self.invalidate_caches();
+        Ok(())
+    }
+
+    /// Type bound to `Self` in the innermost trait or impl, if any
+    pub fn self_type(&self) -> Option<&ResolvedType> {
+        self.lookup_type_alias("Self")
+    }
+
+    /// Look up a type alias (`Self`, `Self::Item`) in all scopes
+    fn lookup_type_alias(&self, name: &str) -> Option<&ResolvedType> {
+        self.scopes.iter().rev().find_map(|scope| scope.type_aliases.get(name))
+    }
+
+    /// Type that `Self` stands for inside `impl ... for type_name`
+    pub fn impl_self_type(&self, type_name: &str) -> ResolvedType {
+        match self.types.get(type_name).map(|info| &info.kind) {
+            Some(TypeKind::Enum(_)) => ResolvedType::Enum(type_name.to_string()),
+            _ => ResolvedType::Struct(type_name.to_string()),
+        }
+    }
+
+    /// Declare and analyze the items of `module` from now on (`None` for the
+    /// program's entry file), with `imports` mapping the names its `using`
+    /// declarations bring into scope to qualified paths
+    pub fn enter_module(&mut self, module: Option<&str>, imports: HashMap<String, String>) {
+        self.module = module.map(str::to_string);
+        self.imports = imports;
+        self.used_imports.get_mut().clear();
+        self.invalidate_caches();
+    }
+
+    /// `name` qualified with the current module
+    pub fn qualify(&self, name: &str) -> String {
+        match &self.module {
+            Some(module) => format!("{}::{}", module, name),
+            None => name.to_string(),
+        }
+    }
+
+    /// The key `name` stands for in `symbols`: the current module's own item,
+    /// then an imported one (`area`, or `shapes::area` after
+    /// `using geometry::shapes;`), then the name as written
+    fn resolve_key<T>(&self, kind: ItemKind, symbols: &HashMap<String, T>, name: &str) -> Option<String> {
+        self.note_import_use(name.split("::").next().unwrap_or(name));
+        let cache_key = (kind, name.to_string());
+        if let Some(key) = self.resolved_keys.borrow().get(&cache_key) {
+            self.count_lookup(true);
+            return key.clone();
+        }
+        self.count_lookup(false);
+        let (head, rest) = match name.split_once("::") {
+            Some((head, rest)) => (head, Some(rest)),
+            None => (name, None),
+        };
+        let imported = self.imports.get(head).map(|path| match rest {
+            Some(rest) => format!("{}::{}", path, rest),
+            None => path.clone(),
+        });
+        let key = [Some(self.qualify(name)), imported, Some(name.to_string())]
+            .into_iter()
+            .flatten()
+            .find(|key| symbols.contains_key(key));
+        self.resolved_keys.borrow_mut().insert(cache_key, key.clone());
+        key
+    }
+
+    /// Record the visibility of the item `key` declared by the current module
+    fn declare_visibility(&mut self, kind: ItemKind, key: &str, visibility: Visibility) {
+        if visibility == Visibility::Private {
+            self.private_items.insert((kind, key.to_string()), self.module.clone());
+            self.invalidate_caches();
+        }
+    }
+
+    /// Check that the current module may use the item `name` refers to: it
+    /// is `pub`, or private to this module
+    pub fn check_access(&self, kind: ItemKind, name: &str) -> Result<(), SemanticError> {
+        let key = match kind {
+            ItemKind::Function => self.function_symbol(name),
+            ItemKind::Constant => self.resolve_key(ItemKind::Constant, &self.constants, name),
+            ItemKind::Type | ItemKind::Field | ItemKind::Relation => None,
+        }
+        .unwrap_or_else(|| name.to_string());
+        match self.private_items.get(&(kind, key.clone())) {
+            Some(owner) if *owner != self.module => Err(SemanticError::PrivateItem {
+                kind,
+                name: key,
+                module: owner.clone().unwrap_or_default(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Declare a function
+    pub fn declare_function(&mut self, name: &str, func: &FunctionDecl) -> Result<(), SemanticError> {
+        let name = &self.qualify(name);
+        if self.functions.contains_key(name) {
+            return Err(SemanticError::Redefinition(name.to_string()));
+        }
+
+        // Generic parameters are in scope only while resolving the signature,
+        // so calls can unify them with argument types
+        let signature = if let Some(generics) = &func.generic_params {
+            self.enter_scope();
+            let signature = generics
+                .iter()
+                .try_for_each(|generic| self.declare_generic_param(&generic.name))
+                .and_then(|_| self.resolve_function_signature(func));
+            self.exit_scope();
+            signature
+        } else {
+            self.resolve_function_signature(func)
+        };
+        let (parameters, return_type) = signature?;
+
+        self.invalidate_caches();
+        self.functions.insert(name.to_string(), FunctionInfo {
+            name: name.to_string(),
+            parameters,
+            return_type,
+        });
+        self.declare_visibility(ItemKind::Function, name, func.visibility);
+
+        Ok(())
+    }
+
+    /// Resolve the parameter and return types of a function declaration
+    fn resolve_function_signature(
+        &self,
+        func: &FunctionDecl,
+    ) -> Result<(Vec<ResolvedType>, Option<ResolvedType>), SemanticError> {
+        self.resolve_signature(&func.parameters, func.return_type.as_ref())
+    }
+
+    /// Resolve a parameter list and return type. `self` parameters take the
+    /// type bound to `Self`, or unit outside a trait or impl.
+    fn resolve_signature(
+        &self,
+        params: &[Parameter],
+        return_type: Option<&Type>,
+    ) -> Result<(Vec<ResolvedType>, Option<ResolvedType>), SemanticError> {
+        let self_type = self.self_type().cloned().unwrap_or(ResolvedType::Unit);
+        let mut parameters = Vec::new();
+        for param in params {
+            match param {
+                Parameter::Regular { name: _, param_type } => {
+                    let resolved_type = self.resolve_type_name(param_type)?;
+                    parameters.push(resolved_type);
+                }
+                Parameter::SelfValue => parameters.push(self_type.clone()),
+                Parameter::SelfRef => {
+                    parameters.push(ResolvedType::Reference(Box::new(self_type.clone()), false));
+                }
+                Parameter::SelfMutRef => {
+                    parameters.push(ResolvedType::Reference(Box::new(self_type.clone()), true));
+                }
+            }
+        }
+
+        let return_type = if let Some(ret_type) = return_type {
+            Some(self.resolve_type_name(ret_type)?)
+        } else {
+            None
+        };
+
+        Ok((parameters, return_type))
+    }
+
+    /// Look up a function
+    pub fn lookup_function(&self, name: &str) -> Option<&FunctionInfo> {
+        self.function_symbol(name).and_then(|key| self.functions.get(&key))
+    }
+
+    /// The qualified name a call to `name` resolves to
+    pub fn function_symbol(&self, name: &str) -> Option<String> {
+        self.resolve_key(ItemKind::Function, &self.functions, name)
+    }
+
+    /// Add a function info directly (Expert recommendation: for built-in functions)
+    pub fn add_function_info(&mut self, name: &str, func_info: FunctionInfo) {
+        self.invalidate_caches();
+        self.functions.insert(name.to_string(), func_info);
+    }
+
+    /// Add pre-built type info for a built-in struct or enum
+    pub fn add_type_info(&mut self, name: &str, type_info: TypeInfo) {
+        self.invalidate_caches();
+        self.builtin_types.insert(name.to_string());
+        self.types.insert(name.to_string(), type_info);
+    }
+
+    /// Whether declaring `name` would redefine an existing type.
+    /// A user declaration shadows a built-in type of the same name.
+    fn is_type_redefinition(&mut self, name: &str) -> bool {
+        self.types.contains_key(name) && !self.builtin_types.remove(name)
+    }
+
+    /// Record the visibility of each field of the type `type_name`
+    fn declare_field_visibility(&mut self, type_name: &str, fields: &[StructField]) {
+        for field in fields {
+            self.declare_visibility(ItemKind::Field, &format!("{}.{}", type_name, field.name), field.visibility);
+        }
+    }
+
+    /// Declare a struct
+    pub fn declare_struct(&mut self, name: &str, struct_decl: &StructDecl) -> Result<(), SemanticError> {
+        if self.is_type_redefinition(name) {
+            return Err(SemanticError::Redefinition(name.to_string()));
+        }
+        self.invalidate_caches();
+
+        self.declare_visibility(ItemKind::Type, name, struct_decl.visibility);
+        self.declare_field_visibility(name, &struct_decl.fields);
+
+        // For generic structs, we need to defer type resolution until analysis phase
+        // because generic parameters are not in scope during symbol collection
+        if let Some(generics) = &struct_decl.generic_params {
+            self.struct_generic_params.insert(
+                name.to_string(),
+                generics.iter().map(|generic| generic.name.clone()).collect(),
+            );
+
+            // For now, just register the struct name without field type resolution
+            self.types.insert(name.to_string(), TypeInfo {
+                name: name.to_string(),
+                kind: TypeKind::Struct(Vec::new()), // Will be resolved later
+            });
+            return Ok(());
+        }
+
+        let mut fields = Vec::new();
+        for field in &struct_decl.fields {
+            let resolved_type = self.resolve_type_name(&field.field_type)?;
+            fields.push(StructFieldInfo {
+                name: field.name.clone(),
+                field_type: resolved_type,
+            });
+        }
+
+        self.types.insert(name.to_string(), TypeInfo {
+            name: name.to_string(),
+            kind: TypeKind::Struct(fields),
+        });
+
+        Ok(())
+    }
+
+    /// Declare an enum
+    pub fn declare_enum(&mut self, name: &str, enum_decl: &EnumDecl) -> Result<(), SemanticError> {
+        if self.is_type_redefinition(name) {
+            return Err(SemanticError::Redefinition(name.to_string()));
+        }
+        self.invalidate_caches();
+
+        let mut variants = Vec::new();
+        for variant in &enum_decl.variants {
+            let fields = if let Some(field_types) = &variant.fields {
+                let mut resolved_fields = Vec::new();
+                for field_type in field_types {
+                    let resolved_type = self.resolve_type_name(field_type)?;
+                    resolved_fields.push(resolved_type);
+                }
+                Some(resolved_fields)
+            } else {
+                None
+            };
+
+            variants.push(EnumVariantInfo {
+                name: variant.name.clone(),
+                fields,
+            });
+        }
+
+        self.types.insert(name.to_string(), TypeInfo {
+            name: name.to_string(),
+            kind: TypeKind::Enum(variants),
+        });
+        self.declare_visibility(ItemKind::Type, name, enum_decl.visibility);
+
+        Ok(())
+    }
+
+    /// Declare a class
+    pub fn declare_class(&mut self, name: &str, class_decl: &ClassDecl) -> Result<(), SemanticError> {
+        if self.is_type_redefinition(name) {
+            return Err(SemanticError::Redefinition(name.to_string()));
+        }
+        self.invalidate_caches();
+
+        let mut fields = Vec::new();
+        for field in &class_decl.fields {
+            let resolved_type = self.resolve_type_name(&field.field_type)?;
+            fields.push(StructFieldInfo {
+                name: field.name.clone(),
+                field_type: resolved_type,
+            });
+        }
+
+        let mut methods = Vec::new();
+        for method in &class_decl.methods {
+            let mut parameters = Vec::new();
+            for param in &method.parameters {
+                match param {
+                    Parameter::Regular { name: _, param_type } => {
+                        let resolved_type = self.resolve_type_name(param_type)?;
+                        parameters.push(resolved_type);
+                    }
+                    Parameter::SelfValue => {
+                        let self_type = ResolvedType::Struct(class_decl.name.clone());
+                        parameters.push(self_type);
+                    }
+                    Parameter::SelfRef => {
+                        let self_type = ResolvedType::Struct(class_decl.name.clone());
+                        let self_ref_type = ResolvedType::Reference(Box::new(self_type), false);
+                        parameters.push(self_ref_type);
+                    }
+                    Parameter::SelfMutRef => {
+                        let self_type = ResolvedType::Struct(class_decl.name.clone());
+                        let self_mut_ref_type = ResolvedType::Reference(Box::new(self_type), true);
+                        parameters.push(self_mut_ref_type);
+                    }
+                }
+            }
+
+            let return_type = if let Some(ret_type) = &method.return_type {
+                Some(self.resolve_type_name(ret_type)?)
+            } else {
+                None
+            };
+
+            methods.push(FunctionInfo {
+                name: method.name.clone(),
+                parameters,
+                return_type,
+            });
+        }
+
+        self.types.insert(name.to_string(), TypeInfo {
+            name: name.to_string(),
+            kind: TypeKind::Class(fields, methods),
+        });
+        self.declare_visibility(ItemKind::Type, name, class_decl.visibility);
+        self.declare_field_visibility(name, &class_decl.fields);
+
+        Ok(())
+    }
+
+    /// Declare an interface
+    pub fn declare_interface(&mut self, name: &str, interface_decl: &InterfaceDecl) -> Result<(), SemanticError> {
+        if self.is_type_redefinition(name) {
+            return Err(SemanticError::Redefinition(name.to_string()));
+        }
+        self.invalidate_caches();
+
+        let mut methods = Vec::new();
+        for method in &interface_decl.methods {
+            let mut parameters = Vec::new();
+            for param in &method.parameters {
+                match param {
+                    Parameter::Regular { name: _, param_type } => {
+                        let resolved_type = self.resolve_type_name(param_type)?;
+                        parameters.push(resolved_type);
+                    }
+                    Parameter::SelfValue => {
+                        parameters.push(ResolvedType::Unit); // Interface self type is abstract
+                    }
+                    Parameter::SelfRef => {
+                        let self_ref_type = ResolvedType::Reference(Box::new(ResolvedType::Unit), false);
+                        parameters.push(self_ref_type);
+                    }
+                    Parameter::SelfMutRef => {
+                        let self_mut_ref_type = ResolvedType::Reference(Box::new(ResolvedType::Unit), true);
+                        parameters.push(self_mut_ref_type);
+                    }
+                }
+            }
+
+            let return_type = if let Some(ret_type) = &method.return_type {
+                Some(self.resolve_type_name(ret_type)?)
+            } else {
+                None
+            };
+
+            methods.push(FunctionInfo {
+                name: method.name.clone(),
+                parameters,
+                return_type,
+            });
+        }
+
+        self.types.insert(name.to_string(), TypeInfo {
+            name: name.to_string(),
+            kind: TypeKind::Interface(methods),
+        });
+
+        Ok(())
+    }
+
+    /// Declare a relation
+    pub fn declare_relation(&mut self, name: &str, relation_decl: &RelationDecl) -> Result<(), SemanticError> {
+        if self.relations.contains_key(name) {
+            return Err(SemanticError::Redefinition(name.to_string()));
+        }
+
+        // An argument typed by a concept holds the name of an individual
+        let mut arg_types = Vec::new();
+        let mut arg_concepts = Vec::new();
+        for arg_type in &relation_decl.arg_types {
+            match arg_type {
+                Type::Named(path) if self.is_concept(&path.to_string()) => {
+                    arg_types.push(ResolvedType::String);
+                    arg_concepts.push(Some(path.to_string()));
+                }
+                _ => {
+                    arg_types.push(self.resolve_type_name(arg_type)?);
+                    arg_concepts.push(None);
+                }
+            }
+        }
+
+        self.relations.insert(name.to_string(), RelationInfo {
+            name: name.to_string(),
+            arg_types,
+            arg_concepts,
+        });
+        self.declare_visibility(ItemKind::Relation, name, relation_decl.visibility);
+
+        Ok(())
+    }
+
+    /// Declare an ontology concept. Its parents need no declaration of their
+    /// own; one that has none is a root concept
+    pub fn declare_concept(&mut self, concept: &ConceptDecl) -> Result<(), SemanticError> {
+        if self.concepts.contains_key(&concept.name) {
+            return Err(SemanticError::Redefinition(concept.name.clone()));
+        }
+        self.concepts.insert(concept.name.clone(), concept.parents.clone());
+        Ok(())
+    }
+
+    /// Declare the `is_a(individual, concept)` relation concepts place
+    /// individuals with, unless the program declared it itself
+    pub fn declare_is_a(&mut self) {
+        if self.concepts.is_empty() || self.relations.contains_key("is_a") {
+            return;
+        }
+        self.relations.insert("is_a".to_string(), RelationInfo {
+            name: "is_a".to_string(),
+            arg_types: vec![ResolvedType::String, ResolvedType::String],
+            arg_concepts: vec![None, None],
+        });
+    }
+
+    /// Whether `name` is a declared concept or the parent of one
+    pub fn is_concept(&self, name: &str) -> bool {
+        self.concepts.contains_key(name) || self.concepts.values().flatten().any(|parent| parent == name)
+    }
+
+    /// Direct parents of a concept
+    pub fn concept_parents(&self, name: &str) -> &[String] {
+        self.concepts.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Declare a trait (Expert recommendation: Priority 1)
+    pub fn declare_trait(&mut self, name: &str, trait_decl: &TraitDecl) -> Result<(), SemanticError> {
+        if self.traits.contains_key(name) {
+            return Err(SemanticError::Redefinition(name.to_string()));
+        }
+
+        // For generic traits, we need to defer type resolution until analysis phase
+        // because generic parameters are not in scope during symbol collection
+        if trait_decl.generic_params.is_some() {
+
+            // For now, just register the trait name without method type resolution
+            self.traits.insert(name.to_string(), TraitInfo {
+                name: name.to_string(),
+                associated_types: trait_decl.associated_types.clone(),
+                methods: Vec::new(), // Will be resolved later
+            });
+            return Ok(());
+        }
+
+        // Inside the trait, `Self` and its associated types stay abstract
+        // until an impl binds them
+        self.enter_scope();
+        let methods = self.resolve_trait_methods(trait_decl);
+        self.exit_scope();
+
+        self.traits.insert(name.to_string(), TraitInfo {
+            name: name.to_string(),
+            associated_types: trait_decl.associated_types.clone(),
+            methods: methods?,
+        });
+
+        Ok(())
+    }
+
+    /// Bind `Self` and the associated types of a trait to abstract parameters
+    /// in the current scope
+    pub fn declare_trait_types(&mut self, trait_decl: &TraitDecl) -> Result<(), SemanticError> {
+        self.declare_self_type(ResolvedType::GenericParam("Self".to_string()));
+        for associated in &trait_decl.associated_types {
+            self.declare_associated_type(associated, ResolvedType::GenericParam(format!("Self::{}", associated)))?;
+        }
+        Ok(())
+    }
+
+    fn resolve_trait_methods(&mut self, trait_decl: &TraitDecl) -> Result<Vec<TraitMethodInfo>, SemanticError> {
+        self.declare_trait_types(trait_decl)?;
+
+        let mut methods = Vec::new();
+        for method in &trait_decl.methods {
+            let (parameters, return_type) = self.resolve_signature(&method.parameters, method.return_type.as_ref())?;
+            methods.push(TraitMethodInfo {
+                name: method.name.clone(),
+                parameters,
+                return_type,
+                has_default_impl: method.body.is_some(),
+                has_receiver: matches!(
+                    method.parameters.first(),
+                    Some(Parameter::SelfValue | Parameter::SelfRef | Parameter::SelfMutRef)
+                ),
+            });
+        }
+        Ok(methods)
+    }
+
+    /// Declare an impl block (Expert recommendation: Priority 1)
+    pub fn declare_impl(&mut self, impl_decl: &ImplDecl) -> Result<(), SemanticError> {
+        let is_blanket = impl_decl
+            .generic_params
+            .iter()
+            .flatten()
+            .any(|generic| generic.name == impl_decl.type_name);
+
+        // Coherence: at most one impl of a trait may apply to any type
+        if let Some(trait_name) = &impl_decl.trait_name {
+            let overlaps = self.impls.iter().any(|existing| {
+                existing.trait_name.as_ref() == Some(trait_name)
+                    && (existing.type_name == impl_decl.type_name || existing.is_blanket || is_blanket)
+            });
+            if overlaps {
+                return Err(SemanticError::ConflictingImpl {
+                    trait_name: trait_name.clone(),
+                    type_name: impl_decl.type_name.clone(),
+                });
+            }
+        }
+
+        // For generic impl blocks, we need to defer type resolution until analysis phase
+        // because generic parameters are not in scope during symbol collection
+        if impl_decl.generic_params.is_some() {
+
+            // For now, just register the impl without method type resolution
+            self.impls.push(ImplInfo {
+                trait_name: impl_decl.trait_name.clone(),
+                type_name: impl_decl.type_name.clone(),
+                associated_types: HashMap::new(),
+                methods: Vec::new(), // Will be resolved later
+                is_blanket,
+            });
+            return Ok(());
+        }
+
+        // `Self` and `Self::Item` resolve to the implementing type and the
+        // impl's own associated type definitions
+        self.enter_scope();
+        let resolved = self.resolve_impl_items(impl_decl);
+        self.exit_scope();
+        let (associated_types, methods) = resolved?;
+
+        self.impls.push(ImplInfo {
+            trait_name: impl_decl.trait_name.clone(),
+            type_name: impl_decl.type_name.clone(),
+            associated_types,
+            methods,
+            is_blanket,
+        });
+
+        Ok(())
+    }
+
+    /// Check a trait impl against its trait once all declarations are known:
+    /// every required method must be provided, with the trait's signature after
+    /// `Self` and the associated types are replaced by the impl's own types
+    pub fn check_trait_impl(&self, impl_decl: &ImplDecl) -> Result<(), SemanticError> {
+        // Generic impls are resolved during analysis
+        if impl_decl.generic_params.is_some() {
+            return Ok(());
+        }
+        let Some(trait_name) = &impl_decl.trait_name else {
+            return Ok(());
+        };
+        let (Some(trait_info), Some(impl_info)) = (
+            self.traits.get(trait_name),
+            self.find_impl(&impl_decl.type_name, Some(trait_name)),
+        ) else {
+            return Ok(());
+        };
+
+        let mut substitution: Substitution = impl_info
+            .associated_types
+            .iter()
+            .map(|(name, resolved)| (format!("Self::{}", name), resolved.clone()))
+            .collect();
+        substitution.insert("Self".to_string(), self.impl_self_type(&impl_decl.type_name));
+
+        let signature = |parameters: &[ResolvedType], return_type: &Option<ResolvedType>| {
+            ResolvedType::Function(parameters.to_vec(), Box::new(return_type.clone().unwrap_or(ResolvedType::Unit)))
+        };
+        let type_checker = TypeChecker::new();
+
+        for method in &trait_info.methods {
+            let Some(provided) = impl_info.methods.iter().find(|provided| provided.name == method.name) else {
+                if method.has_default_impl {
+                    continue;
+                }
+                return Err(SemanticError::MissingTraitMethod {
+                    trait_name: trait_name.clone(),
+                    type_name: impl_decl.type_name.clone(),
+                    method: method.name.clone(),
+                });
+            };
+
+            let expected = type_checker.apply_substitution(&signature(&method.parameters, &method.return_type), &substitution);
+            let found = signature(&provided.parameters, &provided.return_type);
+            if expected != found {
+                return Err(SemanticError::TraitMethodMismatch {
+                    trait_name: trait_name.clone(),
+                    method: method.name.clone(),
+                    expected,
+                    found,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Bind `Self` and the associated types of an impl in the current scope
+    pub fn declare_impl_types(&mut self, impl_decl: &ImplDecl) -> Result<HashMap<String, ResolvedType>, SemanticError> {
+        self.declare_self_type(self.impl_self_type(&impl_decl.type_name));
+
+        let mut associated_types = HashMap::new();
+        for (name, type_annotation) in &impl_decl.associated_types {
+            let resolved = self.resolve_type_name(type_annotation)?;
+            self.declare_associated_type(name, resolved.clone())?;
+            associated_types.insert(name.clone(), resolved);
+        }
+        Ok(associated_types)
+    }
+
+    fn resolve_impl_items(
+        &mut self,
+        impl_decl: &ImplDecl,
+    ) -> Result<(HashMap<String, ResolvedType>, Vec<FunctionInfo>), SemanticError> {
+        let associated_types = self.declare_impl_types(impl_decl)?;
+
+        let mut methods = Vec::new();
+        for method in &impl_decl.methods {
+            let (parameters, return_type) = self.resolve_function_signature(method)?;
+            methods.push(FunctionInfo {
+                name: method.name.clone(),
+                parameters,
+                return_type,
+            });
+        }
+        Ok((associated_types, methods))
+    }
+
+    /// Declare a `const` item with its evaluated value
+    pub fn declare_constant(
+        &mut self,
+        name: &str,
+        visibility: Visibility,
+        const_type: ResolvedType,
+        value: ConstValue,
+    ) -> Result<(), SemanticError> {
+        let name = &self.qualify(name);
+        if self.constants.contains_key(name) {
+            return Err(SemanticError::Redefinition(name.to_string()));
+        }
+        self.invalidate_caches();
+        self.constants.insert(name.to_string(), (const_type, value));
+        self.declare_visibility(ItemKind::Constant, name, visibility);
+        Ok(())
+    }
+
+    /// Look up a `const` item's type and value
+    pub fn lookup_constant(&self, name: &str) -> Option<&(ResolvedType, ConstValue)> {
+        self.resolve_key(ItemKind::Constant, &self.constants, name).and_then(|key| self.constants.get(&key))
+    }
+
+    /// Evaluate the length of a fixed-size array, a constant expression
+    pub fn array_length(&self, length: &Expression) -> Result<usize, SemanticError> {
+        let length = const_eval::evaluate(length, &mut |name| {
+            Ok(self.lookup_constant(name).map(|(_, value)| value.clone()))
+        })?;
+        match length {
+            ConstValue::Int(length) if length >= 0 => Ok(length as usize),
+            _ => Err(SemanticError::ConstEvaluation(
+                "array size must be a non-negative int".to_string(),
+            )),
+        }
+    }
+
+    /// Look up a relation
+    pub fn lookup_relation(&self, name: &str) -> Option<&RelationInfo> {
+        self.relations.get(name)
+    }
+
+    /// Look up a trait (Expert recommendation: Priority 1)
+    pub fn lookup_trait(&self, name: &str) -> Option<&TraitInfo> {
+        self.traits.get(name)
+    }
+
+    /// Find impl for a type and trait (Expert recommendation: Priority 1)
+    pub fn find_impl(&self, type_name: &str, trait_name: Option<&str>) -> Option<&ImplInfo> {
+        self.impls.iter().find(|impl_info| {
+            impl_info.type_name == type_name && impl_info.trait_name.as_deref() == trait_name
+        })
+    }
+
+    /// Get all impl blocks (Expert recommendation: Priority 1 - Method Resolution)
+    pub fn get_impls(&self) -> &Vec<ImplInfo> {
+        &self.impls
+    }
+
+    /// Look up a type
+    pub fn lookup_type(&self, name: &str) -> Option<&TypeInfo> {
+        self.types.get(name)
+    }
+
+    /// Generic parameter names of a generic struct, or `None` for other types
+    pub fn struct_generic_params(&self, name: &str) -> Option<&[String]> {
+        self.struct_generic_params.get(name).map(Vec::as_slice)
+    }
+
+    /// Update struct info after analysis (Expert recommendation: Priority 1)
+    pub fn update_struct_info(&mut self, name: &str, fields: Vec<StructFieldInfo>) -> Result<(), SemanticError> {
+        self.invalidate_caches();
+        if let Some(type_info) = self.types.get_mut(name) {
+            type_info.kind = TypeKind::Struct(fields);
+            Ok(())
+        } else {
+            Err(SemanticError::UndefinedType(name.to_string()))
+        }
+    }
+
+    /// Resolve a type name to a ResolvedType
+    pub fn resolve_type_name(&self, type_annotation: &Type) -> Result<ResolvedType, SemanticError> {
+        match type_annotation {
+            Type::Named(name) => {
+                match name.to_string().as_str() {
+                    "int" => Ok(ResolvedType::Int),
+                    "float" => Ok(ResolvedType::Float),
+                    "bool" => Ok(ResolvedType::Bool),
+                    "string" => Ok(ResolvedType::String),
+                    "char" => Ok(ResolvedType::Char),
+                    _ => {
+                        let name_str = name.to_string();
+                        self.note_import_use(&name.segments[0]);
+                        if let Some(resolved) = self.resolved_types.borrow().get(&name_str) {
+                            self.count_lookup(true);
+                            return Ok(resolved.clone());
+                        }
+                        self.count_lookup(false);
+                        let resolved = self.resolve_named_type(name_str.clone())?;
+                        self.resolved_types.borrow_mut().insert(name_str, resolved.clone());
+                        Ok(resolved)
+                    }
+                }
+            }
+            Type::Generic(name, args) => {
+                let mut resolved_args = Vec::new();
+                for arg in args {
+                    resolved_args.push(self.resolve_type_name(arg)?);
+                }
+                TypeChecker::new().collection_type(&name.to_string(), resolved_args)
+            }
+            Type::GenericParam(name) => {
+                // Generic type parameters are valid during semantic analysis
+                Ok(ResolvedType::GenericParam(name.clone()))
+            }
+            Type::TraitObject(traits) => {
+                // Trait object types (dyn Trait) - Expert recommendation: Priority 1
+                let mut trait_names = Vec::new();
+                for trait_path in traits {
+                    let trait_name = trait_path.to_string();
+
+                    // Verify that the trait exists
+                    if !self.traits.contains_key(&trait_name) {
+                        return Err(SemanticError::UndefinedType(trait_name));
+                    }
+
+                    trait_names.push(trait_name);
+                }
+                Ok(ResolvedType::TraitObject(trait_names))
+            }
+            Type::Reference(referenced_type, is_mutable) => {
+                // Reference types (&T, &mut T) - Expert recommendation: Priority 1
+                let resolved_referenced_type = self.resolve_type_name(referenced_type)?;
+                Ok(ResolvedType::Reference(Box::new(resolved_referenced_type), *is_mutable))
+            }
+            Type::Function(params, ret) => {
+                let mut resolved_params = Vec::new();
+                for param in params {
+                    resolved_params.push(self.resolve_type_name(param)?);
+                }
+                let resolved_ret = self.resolve_type_name(ret)?;
+                Ok(ResolvedType::Function(resolved_params, Box::new(resolved_ret)))
+            }
+            Type::Tuple(element_types) if element_types.is_empty() => Ok(ResolvedType::Unit),
+            Type::Tuple(element_types) => {
+                let mut resolved_elements = Vec::new();
+                for element_type in element_types {
+                    resolved_elements.push(self.resolve_type_name(element_type)?);
+                }
+                Ok(ResolvedType::Tuple(resolved_elements))
+            }
+            Type::Array(element_type, None) => Ok(ResolvedType::List(Box::new(self.resolve_type_name(element_type)?))),
+            Type::Array(element_type, Some(size)) => {
+                let element_type = self.resolve_type_name(element_type)?;
+                Ok(ResolvedType::Vector(Box::new(element_type), self.array_length(size)?))
+            }
+            _ => todo!("Other type resolution not yet implemented"),
+        }
+    }
+
+    /// Resolve a type name other than a primitive one
+    fn resolve_named_type(&self, name_str: String) -> Result<ResolvedType, SemanticError> {
+        // `Self` and associated types bound by the enclosing trait or impl
+        if let Some(aliased) = self.lookup_type_alias(&name_str) {
+            return Ok(aliased.clone());
+        }
+
+        // Check if it's a generic type parameter first (Expert recommendation: Priority 1)
+        if self.lookup_generic_param(&name_str) {
+            return Ok(ResolvedType::GenericParam(name_str));
+        }
+
+        if self.types.contains_key(&name_str) {
+            self.check_access(ItemKind::Type, &name_str)?;
+            match &self.types[&name_str].kind {
+                TypeKind::Struct(_) => Ok(ResolvedType::Struct(name_str)),
+                TypeKind::Enum(_) => Ok(ResolvedType::Enum(name_str)),
+                TypeKind::Class(_, _) => Ok(ResolvedType::Struct(name_str)), // Treat class as struct for now
+                _ => Ok(ResolvedType::Struct(name_str)),
+            }
+        } else {
+            Err(SemanticError::UndefinedVariable(name_str))
+        }
+    }
+}
+
+
+/// `private_items` as a list of entries, since JSON keys must be strings
+mod private_item_entries {
+    use super::*;
+
+    type PrivateItems = HashMap<(ItemKind, String), Option<String>>;
+
+    pub fn serialize<S: Serializer>(items: &PrivateItems, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(items.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PrivateItems, D::Error> {
+        Ok(Vec::<((ItemKind, String), Option<String>)>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_table_creation() {
+        let symbol_table = SymbolTable::new();
+        assert_eq!(symbol_table.scopes.len(), 1);
+        assert!(symbol_table.lookup_type("int").is_some());
+        assert!(symbol_table.lookup_type("string").is_some());
+    }
+
+    #[test]
+    fn test_variable_declaration() {
+        let mut symbol_table = SymbolTable::new();
+
+        let result = symbol_table.declare_variable("x", &ResolvedType::Int);
+        assert!(result.is_ok());
+
+        let var_info = symbol_table.lookup_variable("x");
+        assert!(var_info.is_some());
+        assert_eq!(var_info.unwrap().var_type, ResolvedType::Int);
+    }
+
+    #[test]
+    fn test_scope_management() {
+        let mut symbol_table = SymbolTable::new();
+
+        // Declare variable in global scope
+        symbol_table.declare_variable("global_var", &ResolvedType::Int).unwrap();
+
+        // Enter new scope
+        symbol_table.enter_scope();
+        symbol_table.declare_variable("local_var", &ResolvedType::String).unwrap();
+
+        // Both variables should be visible
+        assert!(symbol_table.lookup_variable("global_var").is_some());
+        assert!(symbol_table.lookup_variable("local_var").is_some());
+
+        // Exit scope
+        symbol_table.exit_scope();
+
+        // Only global variable should be visible
+        assert!(symbol_table.lookup_variable("global_var").is_some());
+        assert!(symbol_table.lookup_variable("local_var").is_none());
+    }
+
+    #[test]
+    fn test_resolutions_are_memoized_until_declarations_change() {
+        let mut symbol_table = SymbolTable::new();
+        let param = Type::Named(Path::single("T".to_string()));
+        symbol_table.enter_function_scope();
+        symbol_table.declare_generic_param("T").unwrap();
+        let before = symbol_table.cache_stats();
+
+        assert_eq!(symbol_table.resolve_type_name(&param).unwrap(), ResolvedType::GenericParam("T".to_string()));
+        // Plain block scopes keep the memos
+        symbol_table.enter_scope();
+        assert_eq!(symbol_table.resolve_type_name(&param).unwrap(), ResolvedType::GenericParam("T".to_string()));
+        symbol_table.exit_scope();
+        let stats = symbol_table.cache_stats();
+        assert_eq!((stats.hits - before.hits, stats.misses - before.misses), (1, 1));
+        assert_eq!(stats.invalidations, before.invalidations);
+
+        // Leaving the scope that bound `T` forgets what it resolved to
+        symbol_table.exit_scope();
+        assert!(symbol_table.resolve_type_name(&param).is_err());
+        assert_eq!(symbol_table.cache_stats().invalidations, before.invalidations + 1);
+
+        assert!(symbol_table.lookup_function("helper").is_none());
+        symbol_table.add_function_info(
+            "helper",
+            FunctionInfo { name: "helper".to_string(), parameters: Vec::new(), return_type: None },
+        );
+        assert!(symbol_table.lookup_function("helper").is_some());
+    }
+}
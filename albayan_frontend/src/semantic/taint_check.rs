@@ -0,0 +1,150 @@
+//! # Taint Audit of Rules
+//!
+//! Relations declared `#[untrusted]` hold facts from untrusted sources — NLU
+//! output, the network, user input. Their taint flows through rules: a
+//! relation is tainted when every way to derive it rests on tainted relations
+//! only. A rule concluding a `#[sensitive]` relation from tainted goals alone
+//! lets untrusted input decide a security-relevant fact (granting access,
+//! approving a payment, ...) and is reported as a warning.
+//!
+//! Like the other workspace knowledge checks, the knowledge base is treated as
+//! closed over the checked files.
+
+use std::collections::{HashMap, HashSet};
+
+use super::knowledge_check::{KnowledgeIssue, KnowledgeLocation};
+use crate::diagnostics::Diagnostic;
+use crate::parser::ast::{Item, LogicTerm, Program, SecurityLabel};
+
+type Predicate = (String, usize);
+
+/// Report rules whose `#[sensitive]` conclusion depends on untrusted facts only
+pub fn check_taint(files: &[(String, &Program)]) -> Vec<KnowledgeIssue> {
+    let mut labels: HashMap<&str, SecurityLabel> = HashMap::new();
+    let mut facts: HashSet<Predicate> = HashSet::new();
+    let mut rules: Vec<(&str, &LogicTerm, Vec<&LogicTerm>)> = Vec::new();
+
+    for (file, program) in files {
+        for item in &program.items {
+            match item {
+                Item::Relation(relation) => {
+                    if let Some(label) = relation.security {
+                        labels.insert(&relation.name, label);
+                    }
+                }
+                Item::Fact(fact) => {
+                    facts.insert(predicate(&fact.term));
+                }
+                Item::Rule(rule) => {
+                    // Facts reach a rule through its positive goals; negations, comparisons
+                    // and the cut only filter
+                    let goals = rule.body.iter().filter(|goal| !goal.negated && goal.calls_relation()).collect();
+                    rules.push((file.as_str(), &rule.head, goals));
+                }
+                _ => {}
+            }
+        }
+    }
+    if !labels.values().any(|label| *label == SecurityLabel::Untrusted) {
+        return Vec::new();
+    }
+
+    // Tainted relations: the untrusted ones, then relations without trusted facts
+    // whose every rule has only tainted goals
+    let untrusted = |name: &str| labels.get(name) == Some(&SecurityLabel::Untrusted);
+    let mut tainted: HashSet<Predicate> = rules
+        .iter()
+        .flat_map(|(_, head, body)| std::iter::once(*head).chain(body.iter().copied()))
+        .map(predicate)
+        .filter(|(name, _)| untrusted(name))
+        .collect();
+    loop {
+        let candidates: HashSet<Predicate> = rules
+            .iter()
+            .map(|(_, head, _)| predicate(head))
+            .filter(|head| !tainted.contains(head) && !facts.contains(head))
+            .filter(|head| {
+                rules
+                    .iter()
+                    .filter(|(_, other, _)| predicate(other) == *head)
+                    .all(|(_, _, body)| body.iter().all(|goal| tainted.contains(&predicate(goal))))
+            })
+            .collect();
+        if candidates.is_empty() {
+            break;
+        }
+        tainted.extend(candidates);
+    }
+
+    rules
+        .iter()
+        .filter(|(_, head, body)| {
+            labels.get(head.name.as_str()) == Some(&SecurityLabel::Sensitive)
+                && body.iter().all(|goal| tainted.contains(&predicate(goal)))
+        })
+        .map(|(file, head, body)| {
+            let sources: Vec<String> = body.iter().map(|goal| format!("`{}/{}`", goal.name, goal.args.len())).collect();
+            let mut diagnostic = Diagnostic::warning(
+                "AB0242",
+                format!("sensitive `{}/{}` is derived from untrusted facts only", head.name, head.args.len()),
+            )
+            .with_span(Some(head.span))
+            .with_label("every goal of this rule is tainted")
+            .with_suggestion("add a goal on a trusted relation, e.g. one populated by an administrator");
+            for goal in body.iter().filter(|goal| !untrusted(&goal.name)) {
+                diagnostic = diagnostic.with_note(format!(
+                    "`{}/{}` is derived only from `#[untrusted]` relations",
+                    goal.name,
+                    goal.args.len()
+                ));
+            }
+            KnowledgeIssue {
+                location: KnowledgeLocation { file: file.to_string(), span: head.span },
+                diagnostic: diagnostic.with_note(format!("tainted goals: {}", sources.join(", "))),
+                related: None,
+            }
+        })
+        .collect()
+}
+
+fn predicate(term: &LogicTerm) -> Predicate {
+    (term.name.clone(), term.args.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Severity;
+    use crate::{Lexer, Parser};
+
+    fn parse(source: &str) -> Program {
+        Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap()
+    }
+
+    #[test]
+    fn test_sensitive_rules_on_tainted_facts() {
+        let program = parse(
+            "#[untrusted] relation claims_role(string, string);
+             #[sensitive] relation grant_admin(string);
+             #[sensitive] relation approve(string);
+             relation admin_request(string);
+             relation verified(string);
+             fact verified(\"sara\");
+             rule admin_request(U) :- claims_role(U, \"admin\");
+             rule grant_admin(U) :- admin_request(U);
+             rule approve(U) :- claims_role(U, \"manager\"), verified(U);",
+        );
+        let issues = check_taint(&[("policy.ab".to_string(), &program)]);
+
+        assert_eq!(issues.len(), 1, "{:?}", issues);
+        let diagnostic = &issues[0].diagnostic;
+        assert_eq!(diagnostic.code, "AB0242");
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(diagnostic.message, "sensitive `grant_admin/1` is derived from untrusted facts only");
+        assert!(diagnostic.notes.iter().any(|note| note.contains("`admin_request/1`")));
+
+        // Without untrusted relations there is nothing to audit
+        let trusted = parse("#[sensitive] relation grant(string);\nrelation ok(string);\nrule grant(U) :- ok(U);");
+        assert!(check_taint(&[("policy.ab".to_string(), &trusted)]).is_empty());
+    }
+}
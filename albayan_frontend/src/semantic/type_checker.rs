@@ -4,6 +4,23 @@
 
 use super::{ResolvedType, SemanticError};
 use crate::parser::ast::*;
+use std::collections::HashMap;
+
+/// Bindings from generic parameter names to the types inferred for them
+pub type Substitution = HashMap<String, ResolvedType>;
+
+/// Result type parameter of the function passed to `map`, named so that it
+/// cannot clash with the generic parameters of a program
+const MAP_OUTPUT: &str = "'U";
+
+/// Signature of a built-in method of a list, map, set or string
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionMethod {
+    pub parameters: Vec<ResolvedType>,
+    pub return_type: ResolvedType,
+    /// Whether the method changes the collection, so it must be mutable
+    pub mutates: bool,
+}
 
 /// Type checker for the AlBayan language
 #[derive(Debug, Clone)]
@@ -35,6 +52,11 @@ impl TypeChecker {
                     "bool" => Ok(ResolvedType::Bool),
                     "string" => Ok(ResolvedType::String),
                     "char" => Ok(ResolvedType::Char),
+                    "Model" | "Tensor" | "TorchModel" | "TorchOptimizer" | "TorchTensor" | "TrainingResult"
+                        if !cfg!(feature = "ai") =>
+                    {
+                        Err(SemanticError::FeatureDisabled { feature: "ai", name: name.to_string() })
+                    }
                     // AI types (Expert recommendation: Priority 1)
                     "Model" => Ok(ResolvedType::Model("Model".to_string())),
                     "Tensor" => Ok(ResolvedType::Tensor(vec![])),
@@ -55,7 +77,7 @@ impl TypeChecker {
                 for arg in args {
                     resolved_args.push(self.resolve_type(arg)?);
                 }
-                Ok(ResolvedType::Generic(name.to_string(), resolved_args))
+                self.collection_type(&name.to_string(), resolved_args)
             }
             Type::GenericParam(name) => {
                 // Generic type parameters are valid during parsing/semantic analysis
@@ -73,6 +95,14 @@ impl TypeChecker {
                     Box::new(resolved_ret),
                 ))
             }
+            Type::Tuple(element_types) if element_types.is_empty() => Ok(ResolvedType::Unit),
+            Type::Tuple(element_types) => {
+                let mut resolved_elements = Vec::new();
+                for element_type in element_types {
+                    resolved_elements.push(self.resolve_type(element_type)?);
+                }
+                Ok(ResolvedType::Tuple(resolved_elements))
+            }
             _ => todo!("Other type resolution not yet implemented"),
         }
     }
@@ -123,6 +153,17 @@ impl TypeChecker {
             (ResolvedType::List(inner1), ResolvedType::List(inner2)) => {
                 self.types_compatible(inner1, inner2)
             }
+            // A fixed-size array is a list of known length
+            (ResolvedType::Vector(inner1, size1), ResolvedType::Vector(inner2, size2)) => {
+                size1 == size2 && self.types_compatible(inner1, inner2)
+            }
+            (ResolvedType::List(inner1), ResolvedType::Vector(inner2, _)) => {
+                self.types_compatible(inner1, inner2)
+            }
+            (ResolvedType::Set(inner1), ResolvedType::Set(inner2)) => self.types_compatible(inner1, inner2),
+            (ResolvedType::Map(key1, value1), ResolvedType::Map(key2, value2)) => {
+                self.types_compatible(key1, key2) && self.types_compatible(value1, value2)
+            }
             (ResolvedType::Tuple(elems1), ResolvedType::Tuple(elems2)) => {
                 elems1.len() == elems2.len()
                     && elems1
@@ -175,6 +216,112 @@ impl TypeChecker {
         }
     }
 
+    /// Unify a declared type that may mention generic parameters with the type of
+    /// an actual value, recording each parameter's binding in `substitution`.
+    ///
+    /// A parameter that is already bound must be matched consistently, so
+    /// `pair<T>(a: T, b: T)` called with `(1, "s")` is rejected.
+    pub fn unify(
+        &self,
+        pattern: &ResolvedType,
+        actual: &ResolvedType,
+        substitution: &mut Substitution,
+    ) -> Result<(), SemanticError> {
+        match (pattern, actual) {
+            (ResolvedType::GenericParam(name), _) => match substitution.get(name) {
+                Some(bound) if self.types_compatible(bound, actual) => Ok(()),
+                // A later argument may widen an earlier binding, e.g. T = Int then Float
+                Some(bound) if self.types_compatible(actual, bound) => {
+                    substitution.insert(name.clone(), actual.clone());
+                    Ok(())
+                }
+                Some(bound) => Err(SemanticError::TypeMismatch {
+                    expected: bound.clone(),
+                    found: actual.clone(),
+                }),
+                None => {
+                    substitution.insert(name.clone(), actual.clone());
+                    Ok(())
+                }
+            },
+            (ResolvedType::List(inner1), ResolvedType::List(inner2))
+            | (ResolvedType::Array(inner1), ResolvedType::Array(inner2))
+            | (ResolvedType::Set(inner1), ResolvedType::Set(inner2)) => {
+                self.unify(inner1, inner2, substitution)
+            }
+            // A shared reference accepts either kind, `&mut` requires `&mut`
+            (ResolvedType::Reference(inner1, mutable1), ResolvedType::Reference(inner2, mutable2))
+                if !mutable1 || *mutable2 =>
+            {
+                self.unify(inner1, inner2, substitution)
+            }
+            (ResolvedType::Map(key1, value1), ResolvedType::Map(key2, value2)) => {
+                self.unify(key1, key2, substitution)?;
+                self.unify(value1, value2, substitution)
+            }
+            (ResolvedType::Tuple(elems1), ResolvedType::Tuple(elems2))
+                if elems1.len() == elems2.len() =>
+            {
+                elems1
+                    .iter()
+                    .zip(elems2)
+                    .try_for_each(|(e1, e2)| self.unify(e1, e2, substitution))
+            }
+            (ResolvedType::Generic(name1, args1), ResolvedType::Generic(name2, args2))
+                if name1 == name2 && args1.len() == args2.len() =>
+            {
+                args1
+                    .iter()
+                    .zip(args2)
+                    .try_for_each(|(a1, a2)| self.unify(a1, a2, substitution))
+            }
+            // A generic struct value whose parameters were never inferred
+            (ResolvedType::Generic(name1, _), ResolvedType::Struct(name2)) if name1 == name2 => Ok(()),
+            (ResolvedType::Function(params1, ret1), ResolvedType::Function(params2, ret2))
+                if params1.len() == params2.len() =>
+            {
+                params1
+                    .iter()
+                    .zip(params2)
+                    .try_for_each(|(p1, p2)| self.unify(p1, p2, substitution))?;
+                self.unify(ret1, ret2, substitution)
+            }
+            _ if pattern == actual || self.types_compatible(pattern, actual) => Ok(()),
+            _ => Err(SemanticError::TypeMismatch {
+                expected: self.apply_substitution(pattern, substitution),
+                found: actual.clone(),
+            }),
+        }
+    }
+
+    /// Replace bound generic parameters in `ty`; unbound parameters are kept
+    pub fn apply_substitution(&self, ty: &ResolvedType, substitution: &Substitution) -> ResolvedType {
+        let apply = |inner: &ResolvedType| Box::new(self.apply_substitution(inner, substitution));
+        match ty {
+            ResolvedType::GenericParam(name) => {
+                substitution.get(name).cloned().unwrap_or_else(|| ty.clone())
+            }
+            ResolvedType::List(inner) => ResolvedType::List(apply(inner)),
+            ResolvedType::Vector(inner, size) => ResolvedType::Vector(apply(inner), *size),
+            ResolvedType::Array(inner) => ResolvedType::Array(apply(inner)),
+            ResolvedType::Set(inner) => ResolvedType::Set(apply(inner)),
+            ResolvedType::Reference(inner, mutable) => ResolvedType::Reference(apply(inner), *mutable),
+            ResolvedType::Map(key, value) => ResolvedType::Map(apply(key), apply(value)),
+            ResolvedType::Tuple(elems) => ResolvedType::Tuple(
+                elems.iter().map(|e| self.apply_substitution(e, substitution)).collect(),
+            ),
+            ResolvedType::Generic(name, args) => ResolvedType::Generic(
+                name.clone(),
+                args.iter().map(|a| self.apply_substitution(a, substitution)).collect(),
+            ),
+            ResolvedType::Function(params, ret) => ResolvedType::Function(
+                params.iter().map(|p| self.apply_substitution(p, substitution)).collect(),
+                apply(ret),
+            ),
+            _ => ty.clone(),
+        }
+    }
+
     /// Check a binary operation and return the result type
     pub fn check_binary_operation(
         &self,
@@ -436,6 +583,97 @@ impl TypeChecker {
         }
     }
 
+    /// The built-in method `name` of a list of `element`s: `push`, `pop`,
+    /// `len`, `contains`, `map` and `filter`. The result type of `map` is a
+    /// generic parameter bound by unifying the arguments with the parameters.
+    pub fn list_method(&self, element: &ResolvedType, name: &str) -> Option<CollectionMethod> {
+        let element = element.clone();
+        let list = |element| ResolvedType::List(Box::new(element));
+        let function = |result| ResolvedType::Function(vec![element.clone()], Box::new(result));
+        let (parameters, return_type, mutates) = match name {
+            "push" => (vec![element.clone()], ResolvedType::Unit, true),
+            "pop" => (Vec::new(), element.clone(), true),
+            "len" => (Vec::new(), ResolvedType::Int, false),
+            "contains" => (vec![element.clone()], ResolvedType::Bool, false),
+            "map" => (
+                vec![function(ResolvedType::GenericParam(MAP_OUTPUT.to_string()))],
+                list(ResolvedType::GenericParam(MAP_OUTPUT.to_string())),
+                false,
+            ),
+            "filter" => (vec![function(ResolvedType::Bool)], list(element.clone()), false),
+            _ => return None,
+        };
+        Some(CollectionMethod { parameters, return_type, mutates })
+    }
+
+    /// The built-in method `name` of a map: `insert`, `get`, `remove`,
+    /// `contains` and `len`. `get` of a missing key is a runtime error.
+    pub fn map_method(&self, key: &ResolvedType, value: &ResolvedType, name: &str) -> Option<CollectionMethod> {
+        let (parameters, return_type, mutates) = match name {
+            "insert" => (vec![key.clone(), value.clone()], ResolvedType::Unit, true),
+            "get" => (vec![key.clone()], value.clone(), false),
+            "remove" => (vec![key.clone()], ResolvedType::Bool, true),
+            "contains" => (vec![key.clone()], ResolvedType::Bool, false),
+            "len" => (Vec::new(), ResolvedType::Int, false),
+            _ => return None,
+        };
+        Some(CollectionMethod { parameters, return_type, mutates })
+    }
+
+    /// The built-in method `name` of a set: `insert` and `remove`, which
+    /// report whether the set changed, `contains` and `len`
+    pub fn set_method(&self, element: &ResolvedType, name: &str) -> Option<CollectionMethod> {
+        let (parameters, return_type, mutates) = match name {
+            "insert" | "remove" => (vec![element.clone()], ResolvedType::Bool, true),
+            "contains" => (vec![element.clone()], ResolvedType::Bool, false),
+            "len" => (Vec::new(), ResolvedType::Int, false),
+            _ => return None,
+        };
+        Some(CollectionMethod { parameters, return_type, mutates })
+    }
+
+    /// The built-in method `name` of a string. Lengths and positions count
+    /// characters; `slice` out of range and `to_int` of text that is not an
+    /// integer are runtime errors.
+    pub fn string_method(&self, name: &str) -> Option<CollectionMethod> {
+        let string = ResolvedType::String;
+        let (parameters, return_type) = match name {
+            "len" => (Vec::new(), ResolvedType::Int),
+            "contains" => (vec![string.clone()], ResolvedType::Bool),
+            "concat" => (vec![string.clone()], string),
+            "slice" => (vec![ResolvedType::Int, ResolvedType::Int], string),
+            "split" => (vec![string.clone()], ResolvedType::List(Box::new(string))),
+            "to_int" => (Vec::new(), ResolvedType::Int),
+            _ => return None,
+        };
+        Some(CollectionMethod { parameters, return_type, mutates: false })
+    }
+
+    /// Whether values of `ty` can be map keys and set elements
+    pub fn is_hashable(&self, ty: &ResolvedType) -> bool {
+        matches!(ty, ResolvedType::Int | ResolvedType::Bool | ResolvedType::Char | ResolvedType::String)
+    }
+
+    /// `List<T>`, `Map<K, V>` and `Set<T>` are the built-in collections; any
+    /// other generic type is kept by name
+    pub fn collection_type(&self, name: &str, args: Vec<ResolvedType>) -> Result<ResolvedType, SemanticError> {
+        match (name, args.as_slice()) {
+            ("List", [element]) => Ok(ResolvedType::List(Box::new(element.clone()))),
+            ("Map", [key, value]) => Ok(ResolvedType::Map(Box::new(self.key_type(key)?), Box::new(value.clone()))),
+            ("Set", [element]) => Ok(ResolvedType::Set(Box::new(self.key_type(element)?))),
+            _ => Ok(ResolvedType::Generic(name.to_string(), args)),
+        }
+    }
+
+    /// `ty` if it can be a map key or set element
+    pub fn key_type(&self, ty: &ResolvedType) -> Result<ResolvedType, SemanticError> {
+        if self.is_hashable(ty) {
+            Ok(ty.clone())
+        } else {
+            Err(SemanticError::UnhashableKey(ty.clone()))
+        }
+    }
+
     /// Check if a type can be used in a boolean context
     pub fn is_truthy_type(&self, type_: &ResolvedType) -> bool {
         match type_ {
@@ -636,4 +874,93 @@ mod tests {
         );
         assert_eq!(result.unwrap(), ResolvedType::Bool);
     }
+
+    #[test]
+    fn test_unify_binds_generic_params() {
+        let type_checker = TypeChecker::new();
+        let t = ResolvedType::GenericParam("T".to_string());
+        let mut substitution = Substitution::new();
+
+        let pattern = ResolvedType::Tuple(vec![t.clone(), ResolvedType::List(Box::new(t.clone()))]);
+        let actual = ResolvedType::Tuple(vec![
+            ResolvedType::Int,
+            ResolvedType::List(Box::new(ResolvedType::Int)),
+        ]);
+        type_checker.unify(&pattern, &actual, &mut substitution).unwrap();
+        assert_eq!(substitution.get("T"), Some(&ResolvedType::Int));
+
+        let boxed = ResolvedType::Generic("Box".to_string(), vec![t.clone()]);
+        assert_eq!(
+            type_checker.apply_substitution(&boxed, &substitution),
+            ResolvedType::Generic("Box".to_string(), vec![ResolvedType::Int])
+        );
+    }
+
+    #[test]
+    fn test_list_method_signatures() {
+        let type_checker = TypeChecker::new();
+        let push = type_checker.list_method(&ResolvedType::Int, "push").unwrap();
+        assert_eq!(push.parameters, vec![ResolvedType::Int]);
+        assert!(push.mutates);
+        assert!(!type_checker.list_method(&ResolvedType::Int, "len").unwrap().mutates);
+        assert!(type_checker.list_method(&ResolvedType::Int, "sort").is_none());
+
+        // `map` takes its result type from the function it is given
+        let map = type_checker.list_method(&ResolvedType::Int, "map").unwrap();
+        let to_string = ResolvedType::Function(vec![ResolvedType::Int], Box::new(ResolvedType::String));
+        let mut substitution = Substitution::new();
+        type_checker.unify(&map.parameters[0], &to_string, &mut substitution).unwrap();
+        assert_eq!(
+            type_checker.apply_substitution(&map.return_type, &substitution),
+            ResolvedType::List(Box::new(ResolvedType::String))
+        );
+    }
+
+    #[test]
+    fn test_map_and_set_types() {
+        let type_checker = TypeChecker::new();
+        let map = type_checker.collection_type("Map", vec![ResolvedType::String, ResolvedType::Float]).unwrap();
+        assert_eq!(map, ResolvedType::Map(Box::new(ResolvedType::String), Box::new(ResolvedType::Float)));
+        let set = type_checker.collection_type("Set", vec![ResolvedType::Char]).unwrap();
+        assert_eq!(set, ResolvedType::Set(Box::new(ResolvedType::Char)));
+        let list = type_checker.collection_type("List", vec![ResolvedType::Int]).unwrap();
+        assert_eq!(list, ResolvedType::List(Box::new(ResolvedType::Int)));
+        assert!(matches!(
+            type_checker.collection_type("Set", vec![ResolvedType::Float]),
+            Err(SemanticError::UnhashableKey(ResolvedType::Float))
+        ));
+        assert!(matches!(type_checker.collection_type("Pair", vec![ResolvedType::Int]), Ok(ResolvedType::Generic(..))));
+
+        let insert = type_checker.map_method(&ResolvedType::String, &ResolvedType::Float, "insert").unwrap();
+        assert_eq!(insert.parameters, vec![ResolvedType::String, ResolvedType::Float]);
+        assert!(insert.mutates);
+        assert_eq!(type_checker.map_method(&ResolvedType::String, &ResolvedType::Float, "get").unwrap().return_type, ResolvedType::Float);
+        assert!(type_checker.set_method(&ResolvedType::Char, "get").is_none());
+        assert_eq!(type_checker.set_method(&ResolvedType::Char, "insert").unwrap().return_type, ResolvedType::Bool);
+    }
+
+    #[test]
+    fn test_unify_rejects_conflicting_bindings() {
+        let type_checker = TypeChecker::new();
+        let t = ResolvedType::GenericParam("T".to_string());
+        let mut substitution = Substitution::new();
+
+        type_checker.unify(&t, &ResolvedType::Int, &mut substitution).unwrap();
+        // Int widens to Float, but String cannot share T with a number
+        type_checker.unify(&t, &ResolvedType::Float, &mut substitution).unwrap();
+        assert_eq!(substitution.get("T"), Some(&ResolvedType::Float));
+        assert!(type_checker.unify(&t, &ResolvedType::String, &mut substitution).is_err());
+    }
+
+    #[test]
+    fn test_ai_types_need_the_ai_feature() {
+        let tensor = TypeChecker::new().resolve_type(&Type::Named(Path::single("Tensor".to_string())));
+        if cfg!(feature = "ai") {
+            assert_eq!(tensor.unwrap(), ResolvedType::Tensor(vec![]));
+        } else {
+            assert!(matches!(tensor, Err(SemanticError::FeatureDisabled { feature: "ai", .. })));
+        }
+        assert!(crate::semantic::is_ai_builtin("torch_forward"));
+        assert!(!crate::semantic::is_ai_builtin("math_ai::shape::init"));
+    }
 }
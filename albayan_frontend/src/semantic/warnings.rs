@@ -0,0 +1,126 @@
+//! # Semantic Warnings
+//!
+//! Problems that do not stop a program from compiling: a `let` binding that
+//! is never read, a function parameter that is never read, and a `using`
+//! declaration whose name is never used. The analyzer reports them with the
+//! analyzed program, in [`AnnotatedProgram::warnings`](super::AnnotatedProgram).
+//! Names starting with `_` are never reported, nor are the parameters of
+//! methods implementing a trait, whose signature the trait fixes.
+//!
+//! Each kind of warning has a name (`unused_variable`, `unused_parameter`,
+//! `unused_import`) that `-A`, `-W` and `-D` of `albayan build` and
+//! `albayan check` set the [`WarningLevels`] of; `warnings` names them all.
+//! An allowed warning is not reported, and a denied one fails the analysis
+//! with `SemanticError::DeniedWarning`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::lexer::Span;
+
+/// Name that sets the level of every kind of warning at once
+pub const ALL_WARNINGS: &str = "warnings";
+
+/// What a warning's, or a lint rule's, findings are reported as
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    /// Not checked
+    Allow,
+    /// Reported as warnings
+    #[default]
+    Warn,
+    /// Reported as errors; `albayan lint` fails
+    Deny,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum WarningKind {
+    UnusedVariable,
+    UnusedParameter,
+    UnusedImport,
+}
+
+impl WarningKind {
+    pub const ALL: [WarningKind; 3] = [WarningKind::UnusedVariable, WarningKind::UnusedParameter, WarningKind::UnusedImport];
+
+    /// Name of the warning for `-A`, `-W` and `-D`
+    pub fn name(self) -> &'static str {
+        match self {
+            WarningKind::UnusedVariable => "unused_variable",
+            WarningKind::UnusedParameter => "unused_parameter",
+            WarningKind::UnusedImport => "unused_import",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// A warning of the analysis
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SemanticWarning {
+    pub kind: WarningKind,
+    /// The variable or parameter, or the path of the import
+    pub name: String,
+    pub span: Option<Span>,
+    /// File of the module the warning is in, when the analysis covered a
+    /// module graph
+    pub file: Option<String>,
+}
+
+impl fmt::Display for SemanticWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            WarningKind::UnusedVariable => write!(f, "unused variable `{}`", self.name),
+            WarningKind::UnusedParameter => write!(f, "unused parameter `{}`", self.name),
+            WarningKind::UnusedImport => write!(f, "unused import `{}`", self.name),
+        }
+    }
+}
+
+/// What each kind of warning is reported as; kinds not set are warnings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WarningLevels {
+    levels: BTreeMap<WarningKind, LintLevel>,
+}
+
+impl WarningLevels {
+    /// Set the level of the warning named `name`, or of all of them for
+    /// [`ALL_WARNINGS`]
+    pub fn set(&mut self, name: &str, level: LintLevel) -> Result<(), String> {
+        if name == ALL_WARNINGS {
+            self.levels.extend(WarningKind::ALL.map(|kind| (kind, level)));
+            return Ok(());
+        }
+        let kind = WarningKind::from_name(name).ok_or_else(|| {
+            let names: Vec<&str> = WarningKind::ALL.iter().map(|kind| kind.name()).collect();
+            format!("unknown warning `{}`; expected {} or `{}`", name, names.join(", "), ALL_WARNINGS)
+        })?;
+        self.levels.insert(kind, level);
+        Ok(())
+    }
+
+    pub fn level(&self, kind: WarningKind) -> LintLevel {
+        self.levels.get(&kind).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_by_name() {
+        let mut levels = WarningLevels::default();
+        assert_eq!(levels.level(WarningKind::UnusedImport), LintLevel::Warn);
+        levels.set(ALL_WARNINGS, LintLevel::Allow).unwrap();
+        levels.set("unused_variable", LintLevel::Deny).unwrap();
+        assert_eq!(levels.level(WarningKind::UnusedVariable), LintLevel::Deny);
+        assert_eq!(levels.level(WarningKind::UnusedParameter), LintLevel::Allow);
+        assert!(levels.set("unused_thing", LintLevel::Warn).unwrap_err().contains("unused_import"));
+    }
+}
@@ -5,6 +5,7 @@ pub mod knowledge_base;
 pub mod unification;
 pub mod solver;
 pub mod api;
+#[cfg(feature = "onnx")]
 pub mod ai;  // Expert recommendation: Priority 1 - AI Module
 pub mod torch;
 pub mod shape_inference;  // Expert recommendation: Priority 2 - PyTorch Training
@@ -21,6 +22,7 @@ pub use knowledge_base::*;
 pub use unification::*;
 pub use solver::*;
 pub use api::*;
+#[cfg(feature = "onnx")]
 pub use ai::*;
 pub use torch::*;
 pub use knowledge_engine::*;
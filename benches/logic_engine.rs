@@ -0,0 +1,88 @@
+//! Logic engine benchmarks on a large knowledge base: constant lookups,
+//! a two-goal join through a rule, the same joins spread over threads
+//! querying snapshots of one engine, and atom interning.
+
+use std::sync::Mutex;
+use std::thread;
+
+use albayan_lib::runtime::Atom;
+use albayan_lib::LogicEngine;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const PEOPLE: usize = 20_000;
+
+/// Rule joins solved per iteration of the parallel benchmark, whatever the
+/// number of threads sharing them
+const PARALLEL_QUERIES: usize = 512;
+
+/// A chain of `parent` facts plus a `city` for every person
+fn large_knowledge_base() -> LogicEngine {
+    let mut engine = LogicEngine::new();
+    for person in 0..PEOPLE {
+        engine.assert_fact(&format!("parent(p{}, p{}).", person, person + 1)).unwrap();
+        engine.assert_fact(&format!("city(p{}, c{}).", person, person % 100)).unwrap();
+    }
+    engine.add_rule("grandparent(X, Z) :- parent(X, Y), parent(Y, Z).").unwrap();
+    engine
+}
+
+fn bench_queries(c: &mut Criterion) {
+    let mut engine = large_knowledge_base();
+
+    c.bench_function("constant lookup", |b| {
+        b.iter(|| engine.solve_query(black_box("parent(p15000, Child)")).unwrap())
+    });
+    c.bench_function("rule join", |b| {
+        b.iter(|| engine.solve_query(black_box("grandparent(p15000, Who)")).unwrap())
+    });
+    c.bench_function("selective join", |b| {
+        b.iter(|| engine.solve_query(black_box("city(P, c42), parent(p4242, P)")).unwrap())
+    });
+}
+
+/// The engine sits behind a mutex as in the runtime, and each thread solves
+/// its share of the joins on a snapshot, so throughput should grow with the
+/// threads up to the cores available
+fn bench_parallel_queries(c: &mut Criterion) {
+    let engine = Mutex::new(large_knowledge_base());
+    let mut group = c.benchmark_group("parallel rule joins");
+    group.throughput(Throughput::Elements(PARALLEL_QUERIES as u64));
+    for threads in [1, 2, 4, 8] {
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, &threads| {
+            b.iter(|| {
+                thread::scope(|scope| {
+                    for thread in 0..threads {
+                        let engine = &engine;
+                        scope.spawn(move || {
+                            let mut snapshot = engine.lock().unwrap().snapshot();
+                            for query in (thread..PARALLEL_QUERIES).step_by(threads) {
+                                let goal = format!("grandparent(p{}, Who)", query * 37 % PEOPLE);
+                                black_box(snapshot.solve_query(&goal).unwrap());
+                            }
+                            engine.lock().unwrap().absorb_queries(snapshot);
+                        });
+                    }
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_interning(c: &mut Criterion) {
+    let names: Vec<String> = (0..PEOPLE).map(|person| format!("p{}", person)).collect();
+    for name in &names {
+        Atom::new(name);
+    }
+
+    c.bench_function("intern existing atoms", |b| {
+        b.iter(|| names.iter().map(|name| Atom::new(black_box(name)).id()).max())
+    });
+    c.bench_function("compare atoms", |b| {
+        let atoms: Vec<Atom> = names.iter().map(|name| Atom::new(name)).collect();
+        b.iter(|| atoms.iter().filter(|&&atom| atom == black_box(atoms[42])).count())
+    });
+}
+
+criterion_group!(benches, bench_interning, bench_queries, bench_parallel_queries);
+criterion_main!(benches);
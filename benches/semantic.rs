@@ -0,0 +1,49 @@
+//! Semantic analysis benchmark on a synthetic program of about 10,000 lines:
+//! 200 structs and 1,600 functions whose parameters, locals and calls resolve
+//! the same type and function names over and over, the load the symbol
+//! table's memoized resolutions are for. `DevTools::analyze_file` reports the
+//! cache hits and misses of a file in the profiler's counters.
+
+use albayan_lib::lexer::Lexer;
+use albayan_lib::parser::ast::Program;
+use albayan_lib::parser::Parser;
+use albayan_lib::semantic::SemanticAnalyzer;
+use albayan_lib::CompilerOptions;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+const STRUCTS: usize = 200;
+const FUNCTIONS: usize = 1_600;
+
+fn synthetic_source() -> String {
+    let mut source = String::from("fn helper(n: int) -> int {\n    return n + 1;\n}\n");
+    for index in 0..STRUCTS {
+        source.push_str(&format!("struct S{} {{\n    a: int,\n    b: float,\n}}\n", index));
+    }
+    for index in 0..FUNCTIONS {
+        let ty = format!("S{}", index % STRUCTS);
+        source.push_str(&format!(
+            "fn f{index}(p: {ty}, n: int) -> int {{\n    let copy: {ty} = p;\n    let total: int = helper(n) + {index};\n    return total * 2;\n}}\n"
+        ));
+    }
+    source
+}
+
+fn parse(source: &str) -> Program {
+    Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap()
+}
+
+fn bench_analysis(c: &mut Criterion) {
+    let program = parse(&synthetic_source());
+    let options = CompilerOptions::default();
+
+    c.bench_function("analyze 10k lines", |b| {
+        b.iter_batched(
+            || program.clone(),
+            |program| SemanticAnalyzer::new(&options).analyze(program).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_analysis);
+criterion_main!(benches);
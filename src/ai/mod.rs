@@ -4,12 +4,19 @@ use std::path::Path;
 use anyhow::{Result, anyhow};
 
 pub mod neural_networks;
-// pub mod transformers;
+pub mod transformers;
 // pub mod computer_vision;
 pub mod natural_language;
-// pub mod reinforcement_learning;
+pub mod numerics;
+pub mod quantization;
+pub mod reinforcement_learning;
 // pub mod optimization;
 
+pub use numerics::{LossCurve, TrainingError, TrainingStage};
+pub use quantization::{Precision, QuantizedDense, QuantizedTensor, SavedWeights};
+pub use reinforcement_learning::{Environment, KnowledgeEnvironment, PolicyGradient, QLearning};
+pub use transformers::{EncoderBlock, LayerNorm, MultiHeadAttention, TransformerEncoder};
+
 /// Advanced tensor operations for AlBayan AI
 #[derive(Debug, Clone)]
 pub struct Tensor {
@@ -62,11 +69,13 @@ impl Tensor {
     }
     
     /// Create a tensor with random values
+    /// Values come from the seeded generator in `runtime::random`
     pub fn random(shape: Vec<usize>) -> Self {
         use rand::Rng;
-        let mut rng = rand::thread_rng();
         let total_elements: usize = shape.iter().product();
-        let data: Vec<f32> = (0..total_elements).map(|_| rng.gen()).collect();
+        let data: Vec<f32> = crate::runtime::random::with_rng(|rng| {
+            (0..total_elements).map(|_| rng.gen()).collect()
+        });
         Self::new(data, shape).unwrap()
     }
     
@@ -194,6 +203,11 @@ pub trait Layer {
     fn backward(&mut self, grad_output: &Tensor) -> Result<Tensor>;
     fn parameters(&self) -> Vec<&Tensor>;
     fn parameters_mut(&mut self) -> Vec<&mut Tensor>;
+
+    /// Layer type, for error messages
+    fn name(&self) -> &'static str {
+        "Layer"
+    }
 }
 
 /// Dense/Linear layer
@@ -222,7 +236,12 @@ impl Dense {
 impl Layer for Dense {
     fn forward(&self, input: &Tensor) -> Result<Tensor> {
         let output = input.matmul(&self.weights)?;
-        output.add(&self.bias)
+        // The bias is added to every row of a batch
+        Ok(Tensor {
+            data: &output.data + &self.bias.data,
+            shape: output.shape,
+            device: output.device,
+        })
     }
     
     fn backward(&mut self, _grad_output: &Tensor) -> Result<Tensor> {
@@ -237,6 +256,10 @@ impl Layer for Dense {
     fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
         vec![&mut self.weights, &mut self.bias]
     }
+
+    fn name(&self) -> &'static str {
+        "Dense"
+    }
 }
 
 /// Convolutional layer
@@ -289,6 +312,10 @@ impl Layer for Conv2D {
     fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
         vec![&mut self.weights, &mut self.bias]
     }
+
+    fn name(&self) -> &'static str {
+        "Conv2D"
+    }
 }
 
 /// Neural network model
@@ -296,6 +323,7 @@ pub struct NeuralNetwork {
     layers: Vec<Box<dyn Layer>>,
     loss_function: LossFunction,
     optimizer: Optimizer,
+    loss_curve: LossCurve,
 }
 
 #[derive(Debug, Clone)]
@@ -318,6 +346,7 @@ impl NeuralNetwork {
             layers: Vec::new(),
             loss_function: LossFunction::MeanSquaredError,
             optimizer: Optimizer::SGD { learning_rate: 0.01 },
+            loss_curve: LossCurve::default(),
         }
     }
     
@@ -341,31 +370,76 @@ impl NeuralNetwork {
         Ok(output)
     }
     
+    /// Train on `inputs` and `targets`, one pair per batch. Stops with a
+    /// [`TrainingError`] as soon as a layer output, the loss or a parameter is
+    /// NaN or infinite.
     pub fn train(&mut self, inputs: &[Tensor], targets: &[Tensor], epochs: usize) -> Result<()> {
-        for epoch in 0..epochs {
+        for epoch in 1..=epochs {
             let mut total_loss = 0.0;
             
-            for (input, target) in inputs.iter().zip(targets.iter()) {
-                let prediction = self.forward(input)?;
+            for (batch, (input, target)) in inputs.iter().zip(targets.iter()).enumerate() {
+                let batch = batch + 1;
+                let mut prediction = input.clone();
+                for (index, layer) in self.layers.iter().enumerate() {
+                    prediction = layer.forward(&prediction)?;
+                    Self::check_finite(prediction.data(), TrainingStage::Forward, Some((index, layer.name())), epoch, batch)?;
+                }
                 let loss = self.compute_loss(&prediction, target)?;
+                Self::check_finite(&[loss], TrainingStage::Loss, None, epoch, batch)?;
                 total_loss += loss;
                 
                 // Backward pass (simplified)
                 self.backward(&prediction, target)?;
+                for (index, layer) in self.layers.iter().enumerate() {
+                    for parameter in layer.parameters() {
+                        Self::check_finite(parameter.data(), TrainingStage::Parameters, Some((index, layer.name())), epoch, batch)?;
+                    }
+                }
             }
             
-            println!("Epoch {}: Loss = {:.6}", epoch + 1, total_loss / inputs.len() as f32);
+            let mean_loss = total_loss / inputs.len() as f32;
+            self.loss_curve.push(mean_loss);
+            println!("Epoch {}: Loss = {:.6}", epoch, mean_loss);
         }
         
         Ok(())
     }
     
+    fn check_finite(
+        values: &[f32],
+        stage: TrainingStage,
+        layer: Option<(usize, &'static str)>,
+        epoch: usize,
+        batch: usize,
+    ) -> Result<()> {
+        match numerics::first_non_finite(values) {
+            Some((index, value)) => Err(TrainingError {
+                stage,
+                layer: layer.map(|(index, _)| index),
+                layer_name: layer.map_or("loss", |(_, name)| name),
+                epoch,
+                batch,
+                index,
+                value,
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+    
+    /// Mean loss of every epoch trained so far
+    pub fn loss_curve(&self) -> &LossCurve {
+        &self.loss_curve
+    }
+    
     fn compute_loss(&self, prediction: &Tensor, target: &Tensor) -> Result<f32> {
         match self.loss_function {
             LossFunction::MeanSquaredError => {
-                let diff = prediction.add(&target.mul(&Tensor::ones(target.shape().to_vec()).mul(&Tensor::new(vec![-1.0], vec![1])?)?)?)?;
-                let squared = diff.mul(&diff)?;
-                Ok(squared.data().iter().sum::<f32>() / squared.data().len() as f32)
+                if prediction.shape() != target.shape() {
+                    return Err(anyhow!("Prediction shape {:?} does not match target shape {:?}", prediction.shape(), target.shape()));
+                }
+                let squared: f32 = prediction.data().iter().zip(target.data()).map(|(p, t)| (p - t).powi(2)).sum();
+                Ok(squared / prediction.data().len() as f32)
             }
             _ => Ok(0.0) // Simplified
         }
@@ -129,6 +129,10 @@ impl Layer for BatchNorm {
     fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
         vec![&mut self.gamma, &mut self.beta]
     }
+    
+    fn name(&self) -> &'static str {
+        "BatchNorm"
+    }
 }
 
 /// Dropout layer for regularization
@@ -158,11 +162,11 @@ impl Layer for Dropout {
         }
         
         use rand::Rng;
-        let mut rng = rand::thread_rng();
-        
-        let mask: Vec<f32> = input.data().iter()
-            .map(|_| if rng.gen::<f32>() > self.probability { 1.0 / (1.0 - self.probability) } else { 0.0 })
-            .collect();
+        let mask: Vec<f32> = crate::runtime::random::with_rng(|rng| {
+            input.data().iter()
+                .map(|_| if rng.gen::<f32>() > self.probability { 1.0 / (1.0 - self.probability) } else { 0.0 })
+                .collect()
+        });
         
         let mask_tensor = Tensor::new(mask, input.shape().to_vec())?;
         input.mul(&mask_tensor)
@@ -179,6 +183,10 @@ impl Layer for Dropout {
     fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
         vec![]
     }
+    
+    fn name(&self) -> &'static str {
+        "Dropout"
+    }
 }
 
 /// LSTM cell for recurrent neural networks
@@ -296,6 +304,10 @@ impl Layer for LSTM {
             &mut self.b_hi, &mut self.b_hf, &mut self.b_hg, &mut self.b_ho,
         ]
     }
+    
+    fn name(&self) -> &'static str {
+        "LSTM"
+    }
 }
 
 /// Attention mechanism
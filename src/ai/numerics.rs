@@ -0,0 +1,257 @@
+//! Numerical debugging for neural networks
+//!
+//! [`check_gradient`] compares a gradient computed by hand (or by a layer's
+//! `backward`) with central finite differences of the function it claims to
+//! differentiate. `NeuralNetwork::train` checks every layer output, loss and
+//! updated parameter for NaN and infinity and stops with a [`TrainingError`]
+//! that names the layer, epoch and batch where the value appeared. The mean
+//! loss of each epoch is kept in a [`LossCurve`], which can be written as CSV.
+
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use super::Tensor;
+
+/// Where in a training step a value was checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainingStage {
+    /// Output of a layer's forward pass
+    Forward,
+    Loss,
+    /// A parameter after the optimizer step
+    Parameters,
+}
+
+/// A NaN or infinity found while training
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingError {
+    pub stage: TrainingStage,
+    /// Index of the layer in the network; `None` for the loss
+    pub layer: Option<usize>,
+    pub layer_name: &'static str,
+    /// Epoch and batch, counted from 1
+    pub epoch: usize,
+    pub batch: usize,
+    /// Index of the first non-finite element
+    pub index: usize,
+    pub value: f32,
+}
+
+/// Outcome of [`check_gradient`]
+#[derive(Debug, Clone)]
+pub struct GradientCheck {
+    pub numerical: Tensor,
+    pub max_abs_error: f32,
+    /// Largest `|analytic - numerical| / max(|analytic| + |numerical|, 1e-8)`
+    pub max_relative_error: f32,
+    /// Element with the largest relative error
+    pub worst_index: usize,
+    pub passed: bool,
+}
+
+/// Mean training loss per epoch
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LossCurve {
+    pub losses: Vec<f32>,
+}
+
+impl TrainingStage {
+    fn advice(self) -> &'static str {
+        match self {
+            TrainingStage::Forward => "check the layer's inputs and weights, or normalize the training data",
+            TrainingStage::Loss => "check the targets and that predictions stay in the loss function's domain",
+            TrainingStage::Parameters => "lower the learning rate or clip the gradients",
+        }
+    }
+}
+
+impl TrainingError {
+    fn location(&self) -> String {
+        match (self.stage, self.layer) {
+            (TrainingStage::Loss, _) | (_, None) => "the loss".to_string(),
+            (TrainingStage::Forward, Some(layer)) => format!("the output of layer {} ({})", layer, self.layer_name),
+            (TrainingStage::Parameters, Some(layer)) => format!("the parameters of layer {} ({})", layer, self.layer_name),
+        }
+    }
+}
+
+impl fmt::Display for TrainingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in {} at epoch {}, batch {} (element {}); {}",
+            self.value,
+            self.location(),
+            self.epoch,
+            self.batch,
+            self.index,
+            self.stage.advice()
+        )
+    }
+}
+
+impl std::error::Error for TrainingError {}
+
+/// The first NaN or infinite element of `values`, with its index
+pub fn first_non_finite(values: &[f32]) -> Option<(usize, f32)> {
+    values.iter().copied().enumerate().find(|(_, value)| !value.is_finite())
+}
+
+/// Central finite differences of `f` at `at`, one element at a time
+pub fn numerical_gradient<F>(mut f: F, at: &Tensor, epsilon: f32) -> Result<Tensor>
+where
+    F: FnMut(&Tensor) -> Result<f32>,
+{
+    let mut point = at.data().to_vec();
+    let mut gradient = Vec::with_capacity(point.len());
+    for index in 0..point.len() {
+        let original = point[index];
+        point[index] = original + epsilon;
+        let above = f(&Tensor::new(point.clone(), at.shape().to_vec())?)?;
+        point[index] = original - epsilon;
+        let below = f(&Tensor::new(point.clone(), at.shape().to_vec())?)?;
+        point[index] = original;
+        gradient.push((above - below) / (2.0 * epsilon));
+    }
+    Tensor::new(gradient, at.shape().to_vec())
+}
+
+/// Compare `analytic`, the claimed gradient of `f` at `at`, with central
+/// differences. Differences are computed in `f32`, so `epsilon` around 1e-2
+/// and `tolerance` around 1e-2 suit functions of moderate curvature.
+pub fn check_gradient<F>(f: F, analytic: &Tensor, at: &Tensor, epsilon: f32, tolerance: f32) -> Result<GradientCheck>
+where
+    F: FnMut(&Tensor) -> Result<f32>,
+{
+    if analytic.shape() != at.shape() {
+        return Err(anyhow!("Gradient shape {:?} does not match the input shape {:?}", analytic.shape(), at.shape()));
+    }
+    let numerical = numerical_gradient(f, at, epsilon)?;
+    let mut check = GradientCheck {
+        numerical: numerical.clone(),
+        max_abs_error: 0.0,
+        max_relative_error: 0.0,
+        worst_index: 0,
+        passed: true,
+    };
+    for (index, (&a, &n)) in analytic.data().iter().zip(numerical.data()).enumerate() {
+        let error = (a - n).abs();
+        let relative = error / (a.abs() + n.abs()).max(1e-8);
+        check.max_abs_error = check.max_abs_error.max(error);
+        if relative > check.max_relative_error || !relative.is_finite() {
+            check.max_relative_error = relative;
+            check.worst_index = index;
+        }
+    }
+    // Tiny gradients have large relative errors from rounding alone
+    check.passed = check.max_relative_error <= tolerance || check.max_abs_error <= tolerance * 1e-2;
+    Ok(check)
+}
+
+impl LossCurve {
+    pub fn push(&mut self, loss: f32) {
+        self.losses.push(loss);
+    }
+
+    /// `epoch,loss` rows, epochs counted from 1
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("epoch,loss\n");
+        for (epoch, loss) in self.losses.iter().enumerate() {
+            csv.push_str(&format!("{},{}\n", epoch + 1, loss));
+        }
+        csv
+    }
+
+    pub fn write_csv(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_csv())
+            .map_err(|error| anyhow!("Cannot write the loss curve to '{}': {}", path.display(), error))
+    }
+}
+
+impl fmt::Display for GradientCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "gradient check {}: max abs error {:.3e}, max relative error {:.3e} at element {}",
+            if self.passed { "passed" } else { "FAILED" },
+            self.max_abs_error,
+            self.max_relative_error,
+            self.worst_index
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{Dense, Layer, NeuralNetwork};
+
+    fn sum_of_squares(x: &Tensor) -> Result<f32> {
+        Ok(x.data().iter().map(|v| v * v).sum())
+    }
+
+    #[test]
+    fn test_gradient_check() {
+        let at = Tensor::new(vec![1.0, -2.0, 0.5], vec![3]).unwrap();
+        let correct = Tensor::new(vec![2.0, -4.0, 1.0], vec![3]).unwrap();
+        let check = check_gradient(sum_of_squares, &correct, &at, 1e-2, 1e-3).unwrap();
+        assert!(check.passed, "{}", check);
+
+        let wrong = Tensor::new(vec![2.0, 4.0, 1.0], vec![3]).unwrap();
+        let check = check_gradient(sum_of_squares, &wrong, &at, 1e-2, 1e-3).unwrap();
+        assert!(!check.passed);
+        assert_eq!(check.worst_index, 1);
+        assert!(check_gradient(sum_of_squares, &correct, &Tensor::zeros(vec![2]), 1e-2, 1e-3).is_err());
+    }
+
+    /// Passes its input through, except in one batch, where it produces NaN
+    struct FailsAt {
+        batch: std::cell::Cell<usize>,
+        failing: usize,
+    }
+
+    impl Layer for FailsAt {
+        fn forward(&self, input: &Tensor) -> Result<Tensor> {
+            let batch = self.batch.replace(self.batch.get() + 1);
+            if batch == self.failing {
+                Tensor::new(input.data().iter().map(|_| f32::NAN).collect(), input.shape().to_vec())
+            } else {
+                Ok(input.clone())
+            }
+        }
+        fn backward(&mut self, grad_output: &Tensor) -> Result<Tensor> {
+            Ok(grad_output.clone())
+        }
+        fn parameters(&self) -> Vec<&Tensor> {
+            vec![]
+        }
+        fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+            vec![]
+        }
+        fn name(&self) -> &'static str {
+            "FailsAt"
+        }
+    }
+
+    #[test]
+    fn test_training_reports_where_values_stop_being_finite() {
+        let inputs: Vec<Tensor> = (0..3).map(|i| Tensor::new(vec![i as f32, 1.0], vec![1, 2]).unwrap()).collect();
+        let targets: Vec<Tensor> = (0..3).map(|i| Tensor::new(vec![i as f32], vec![1, 1]).unwrap()).collect();
+
+        let mut network = NeuralNetwork::new();
+        network.add_layer(Box::new(Dense::new(2, 1)));
+        network.train(&inputs, &targets, 2).unwrap();
+        assert_eq!(network.loss_curve().losses.len(), 2);
+        assert!(network.loss_curve().to_csv().starts_with("epoch,loss\n1,"));
+
+        network.add_layer(Box::new(FailsAt { batch: std::cell::Cell::new(0), failing: 4 }));
+        let error = network.train(&inputs, &targets, 3).unwrap_err();
+        let error = error.downcast_ref::<TrainingError>().unwrap();
+        assert_eq!((error.stage, error.layer, error.epoch, error.batch), (TrainingStage::Forward, Some(1), 2, 2));
+        assert!(error.value.is_nan());
+        assert!(error.to_string().starts_with("NaN in the output of layer 1 (FailsAt) at epoch 2, batch 2"));
+        assert_eq!(first_non_finite(&[1.0, f32::INFINITY]), Some((1, f32::INFINITY)));
+    }
+}
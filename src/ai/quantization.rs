@@ -0,0 +1,461 @@
+//! Quantized tensors for inference
+//!
+//! Weights stored as int8 take a quarter of the memory of `f32` weights, and
+//! fp16 weights half. [`QuantizedTensor`] holds a tensor in any of the three
+//! precisions; int8 tensors use an affine mapping `value = scale * (q -
+//! zero_point)` chosen from the tensor's range. The matmul kernels work on
+//! the quantized values directly: int8 products accumulate in `i32` and are
+//! scaled once per output element, fp16 products accumulate in `f32`.
+//!
+//! [`quantize_file`] is the post-training step: it reads a weights file
+//! ([`SavedWeights`]), quantizes every matrix in it and leaves vectors such
+//! as biases in `f32`, where rounding errors matter most and savings least.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{Dense, Tensor};
+
+/// Element type of a stored tensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Precision {
+    F32,
+    F16,
+    Int8,
+}
+
+/// Elements of a tensor in one of the precisions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "precision", rename_all = "lowercase")]
+pub enum TensorData {
+    F32 { values: Vec<f32> },
+    /// IEEE 754 half precision bit patterns
+    F16 { values: Vec<u16> },
+    /// `value = scale * (q - zero_point)`
+    Int8 { values: Vec<i8>, scale: f32, zero_point: i8 },
+}
+
+/// A tensor whose elements may be quantized
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuantizedTensor {
+    pub shape: Vec<usize>,
+    #[serde(flatten)]
+    pub data: TensorData,
+}
+
+/// A dense layer with quantized weights; the bias stays in `f32`
+#[derive(Debug, Clone)]
+pub struct QuantizedDense {
+    weights: QuantizedTensor,
+    bias: Tensor,
+}
+
+/// Named tensors of a saved model, stored as JSON
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedWeights {
+    pub tensors: BTreeMap<String, QuantizedTensor>,
+}
+
+/// What [`quantize_weights`] did
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuantizationReport {
+    pub quantized: usize,
+    pub kept: usize,
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+    /// Largest difference between a weight and its quantized value
+    pub max_error: f32,
+}
+
+impl Precision {
+    pub fn bytes_per_element(self) -> usize {
+        match self {
+            Precision::F32 => 4,
+            Precision::F16 => 2,
+            Precision::Int8 => 1,
+        }
+    }
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Precision::F32 => "f32",
+            Precision::F16 => "f16",
+            Precision::Int8 => "int8",
+        })
+    }
+}
+
+impl FromStr for Precision {
+    type Err = String;
+
+    fn from_str(text: &str) -> std::result::Result<Self, Self::Err> {
+        match text {
+            "f32" => Ok(Precision::F32),
+            "f16" | "fp16" => Ok(Precision::F16),
+            "int8" | "i8" => Ok(Precision::Int8),
+            other => Err(format!("unknown precision `{}`, expected f32, f16 or int8", other)),
+        }
+    }
+}
+
+/// Round an `f32` to the nearest half precision value, ties to even
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Infinity stays infinite and NaN stays NaN
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    let round = |value: u32, shift: u32| {
+        let truncated = value >> shift;
+        let remainder = value & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        if remainder > halfway || (remainder == halfway && truncated & 1 == 1) {
+            truncated + 1
+        } else {
+            truncated
+        }
+    };
+    if exponent <= 0 {
+        // Subnormal, or too small for half precision
+        if exponent < -10 {
+            return sign;
+        }
+        return sign | round(mantissa | 0x80_0000, (14 - exponent) as u32) as u16;
+    }
+    // A carry out of the mantissa correctly bumps the exponent, up to infinity
+    sign | round(((exponent as u32) << 23) | mantissa, 13) as u16
+}
+
+pub fn f16_to_f32(bits: u16) -> f32 {
+    let negative = bits & 0x8000 != 0;
+    let exponent = ((bits >> 10) & 0x1f) as u32;
+    let mantissa = (bits & 0x3ff) as u32;
+    let magnitude = match exponent {
+        0 => mantissa as f32 * 2f32.powi(-24),
+        0x1f => f32::from_bits(0x7f80_0000 | (mantissa << 13)),
+        _ => f32::from_bits(((exponent + 127 - 15) << 23) | (mantissa << 13)),
+    };
+    if negative {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Scale and zero point mapping `values` onto the int8 range; the range
+/// always includes zero, so that zero is represented exactly
+fn int8_parameters(values: &[f32]) -> (f32, i8) {
+    let min = values.iter().copied().fold(0.0f32, f32::min);
+    let max = values.iter().copied().fold(0.0f32, f32::max);
+    let scale = if max > min { (max - min) / 255.0 } else { 1.0 };
+    let zero_point = (-128.0 - min / scale).round().clamp(-128.0, 127.0) as i8;
+    (scale, zero_point)
+}
+
+fn quantize_int8(value: f32, scale: f32, zero_point: i8) -> i8 {
+    (value / scale + zero_point as f32).round().clamp(-128.0, 127.0) as i8
+}
+
+impl QuantizedTensor {
+    /// Convert `tensor` to `precision`
+    pub fn quantize(tensor: &Tensor, precision: Precision) -> Self {
+        let values = tensor.data();
+        let data = match precision {
+            Precision::F32 => TensorData::F32 { values: values.to_vec() },
+            Precision::F16 => TensorData::F16 { values: values.iter().map(|&value| f32_to_f16(value)).collect() },
+            Precision::Int8 => {
+                let (scale, zero_point) = int8_parameters(values);
+                let values = values.iter().map(|&value| quantize_int8(value, scale, zero_point)).collect();
+                TensorData::Int8 { values, scale, zero_point }
+            }
+        };
+        Self { shape: tensor.shape().to_vec(), data }
+    }
+
+    pub fn precision(&self) -> Precision {
+        match self.data {
+            TensorData::F32 { .. } => Precision::F32,
+            TensorData::F16 { .. } => Precision::F16,
+            TensorData::Int8 { .. } => Precision::Int8,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.data {
+            TensorData::F32 { values } => values.len(),
+            TensorData::F16 { values } => values.len(),
+            TensorData::Int8 { values, .. } => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bytes taken by the elements
+    pub fn size_in_bytes(&self) -> usize {
+        self.len() * self.precision().bytes_per_element()
+    }
+
+    /// The elements as `f32`
+    pub fn values(&self) -> Vec<f32> {
+        match &self.data {
+            TensorData::F32 { values } => values.clone(),
+            TensorData::F16 { values } => values.iter().map(|&bits| f16_to_f32(bits)).collect(),
+            TensorData::Int8 { values, scale, zero_point } => {
+                values.iter().map(|&q| scale * (q as i32 - *zero_point as i32) as f32).collect()
+            }
+        }
+    }
+
+    pub fn dequantize(&self) -> Result<Tensor> {
+        Tensor::new(self.values(), self.shape.clone())
+    }
+
+    /// Matrix product of two 2D tensors of the same precision
+    pub fn matmul(&self, other: &QuantizedTensor) -> Result<Tensor> {
+        let (rows, inner, columns) = matmul_dimensions(&self.shape, &other.shape)?;
+        let output = match (&self.data, &other.data) {
+            (
+                TensorData::Int8 { values: a, scale: a_scale, zero_point: a_zero },
+                TensorData::Int8 { values: b, scale: b_scale, zero_point: b_zero },
+            ) => {
+                let scale = a_scale * b_scale;
+                let (a_zero, b_zero) = (*a_zero as i32, *b_zero as i32);
+                matmul_kernel(rows, inner, columns, |i, k, j| {
+                    (a[i * inner + k] as i32 - a_zero) * (b[k * columns + j] as i32 - b_zero)
+                })
+                .into_iter()
+                .map(|sum: i32| sum as f32 * scale)
+                .collect()
+            }
+            (TensorData::F16 { values: a }, TensorData::F16 { values: b }) => {
+                matmul_kernel(rows, inner, columns, |i, k, j| {
+                    f16_to_f32(a[i * inner + k]) * f16_to_f32(b[k * columns + j])
+                })
+            }
+            (TensorData::F32 { values: a }, TensorData::F32 { values: b }) => {
+                matmul_kernel(rows, inner, columns, |i, k, j| a[i * inner + k] * b[k * columns + j])
+            }
+            _ => {
+                return Err(anyhow!(
+                    "Matrix multiplication of {} and {} tensors; quantize both to the same precision",
+                    self.precision(),
+                    other.precision()
+                ))
+            }
+        };
+        Tensor::new(output, vec![rows, columns])
+    }
+}
+
+fn matmul_dimensions(a: &[usize], b: &[usize]) -> Result<(usize, usize, usize)> {
+    if a.len() != 2 || b.len() != 2 {
+        return Err(anyhow!("Matrix multiplication requires 2D tensors"));
+    }
+    if a[1] != b[0] {
+        return Err(anyhow!("Incompatible shapes for matrix multiplication"));
+    }
+    Ok((a[0], a[1], b[1]))
+}
+
+/// `rows × columns` sums over `inner` of `product(i, k, j)`, row by row
+fn matmul_kernel<T, F>(rows: usize, inner: usize, columns: usize, product: F) -> Vec<T>
+where
+    T: Copy + Default + std::ops::AddAssign,
+    F: Fn(usize, usize, usize) -> T,
+{
+    let mut output = vec![T::default(); rows * columns];
+    for i in 0..rows {
+        // Walking k in the outer loop reads the right operand row by row
+        for k in 0..inner {
+            for j in 0..columns {
+                output[i * columns + j] += product(i, k, j);
+            }
+        }
+    }
+    output
+}
+
+impl Tensor {
+    /// Convert to `precision`; see [`QuantizedTensor::quantize`]
+    pub fn quantize(&self, precision: Precision) -> QuantizedTensor {
+        QuantizedTensor::quantize(self, precision)
+    }
+
+    /// Multiply by quantized weights, quantizing this tensor to their
+    /// precision first
+    pub fn matmul_quantized(&self, weights: &QuantizedTensor) -> Result<Tensor> {
+        self.quantize(weights.precision()).matmul(weights)
+    }
+}
+
+impl Dense {
+    /// The layer with its weights in `precision`, for inference
+    pub fn quantize(&self, precision: Precision) -> QuantizedDense {
+        QuantizedDense { weights: self.weights.quantize(precision), bias: self.bias.clone() }
+    }
+}
+
+impl QuantizedDense {
+    pub fn weights(&self) -> &QuantizedTensor {
+        &self.weights
+    }
+
+    /// `input × weights + bias`, with the bias added to every row
+    pub fn forward(&self, input: &Tensor) -> Result<Tensor> {
+        let output = input.matmul_quantized(&self.weights)?;
+        let bias = self.bias.data();
+        let values = output.data().chunks(bias.len().max(1))
+            .flat_map(|row| row.iter().zip(bias).map(|(value, bias)| value + bias))
+            .collect();
+        Tensor::new(values, output.shape().to_vec())
+    }
+}
+
+impl SavedWeights {
+    pub fn insert(&mut self, name: &str, tensor: &Tensor) {
+        self.tensors.insert(name.to_string(), tensor.quantize(Precision::F32));
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|error| anyhow!("Cannot read weights '{}': {}", path.display(), error))?;
+        serde_json::from_str(&source).map_err(|error| anyhow!("Invalid weights file '{}': {}", path.display(), error))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        std::fs::write(path, json).map_err(|error| anyhow!("Cannot write weights '{}': {}", path.display(), error))
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        self.tensors.values().map(QuantizedTensor::size_in_bytes).sum()
+    }
+}
+
+impl fmt::Display for QuantizationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ratio = if self.bytes_before == 0 { 1.0 } else { self.bytes_after as f64 / self.bytes_before as f64 };
+        writeln!(f, "Quantized {} tensors, kept {} in f32", self.quantized, self.kept)?;
+        writeln!(f, "Size: {} -> {} bytes ({:.0}%)", self.bytes_before, self.bytes_after, ratio * 100.0)?;
+        write!(f, "Max error: {:.6}", self.max_error)
+    }
+}
+
+/// Post-training quantization: every matrix of `weights` in `precision`, and
+/// every other tensor in `f32`
+pub fn quantize_weights(weights: &SavedWeights, precision: Precision) -> (SavedWeights, QuantizationReport) {
+    let mut report = QuantizationReport { bytes_before: weights.size_in_bytes(), ..Default::default() };
+    let mut quantized = SavedWeights::default();
+    for (name, tensor) in &weights.tensors {
+        let values = tensor.values();
+        let target = if tensor.shape.len() >= 2 { precision } else { Precision::F32 };
+        let converted = Tensor::new(values.clone(), tensor.shape.clone())
+            .map(|tensor| tensor.quantize(target))
+            .unwrap_or_else(|_| tensor.clone());
+        if converted.precision() == tensor.precision() {
+            report.kept += 1;
+        } else {
+            report.quantized += 1;
+        }
+        let error = values.iter().zip(converted.values()).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max);
+        report.max_error = report.max_error.max(error);
+        quantized.tensors.insert(name.clone(), converted);
+    }
+    report.bytes_after = quantized.size_in_bytes();
+    (quantized, report)
+}
+
+/// Quantize the weights file at `input` and save the result to `output`
+pub fn quantize_file(input: &Path, output: &Path, precision: Precision) -> Result<QuantizationReport> {
+    let (quantized, report) = quantize_weights(&SavedWeights::load(input)?, precision);
+    quantized.save(output)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(rows: usize, columns: usize) -> Tensor {
+        let values = (0..rows * columns).map(|i| (i as f32 * 0.37).sin() * 2.0).collect();
+        Tensor::new(values, vec![rows, columns]).unwrap()
+    }
+
+    #[test]
+    fn test_f16_conversion() {
+        for value in [0.0, 1.0, -2.5, 65504.0, 0.1, 6.1e-5, 3.0e-7] {
+            let round_trip = f16_to_f32(f32_to_f16(value));
+            assert!((round_trip - value).abs() <= value.abs() * 1e-3 + 6e-8, "{} -> {}", value, round_trip);
+        }
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(1e6), 0x7c00);
+        assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+    }
+
+    #[test]
+    fn test_quantized_matmul_matches_f32() {
+        let (a, b) = (matrix(3, 8), matrix(8, 4));
+        let exact = a.matmul(&b).unwrap();
+        for (precision, tolerance) in [(Precision::F32, 1e-5), (Precision::F16, 1e-2), (Precision::Int8, 0.15)] {
+            let product = a.quantize(precision).matmul(&b.quantize(precision)).unwrap();
+            assert_eq!(product.shape(), &[3, 4]);
+            for (x, y) in exact.data().iter().zip(product.data()) {
+                assert!((x - y).abs() < tolerance, "{}: {} vs {}", precision, x, y);
+            }
+        }
+
+        let int8 = b.quantize(Precision::Int8);
+        assert_eq!(int8.size_in_bytes() * 4, b.quantize(Precision::F32).size_in_bytes());
+        assert!(int8.values().iter().zip(b.data()).all(|(x, y)| (x - y).abs() <= 4.0 / 255.0));
+        assert!(a.quantize(Precision::F16).matmul(&int8).is_err());
+    }
+
+    #[test]
+    fn test_quantized_dense_layer_and_weights_file() {
+        let layer = Dense::new(4, 3);
+        let input = Tensor::new(vec![0.5, -1.0, 0.25, 2.0, 1.0, 1.0, -0.5, 0.0], vec![2, 4]).unwrap();
+        let exact = input.matmul(&layer.weights).unwrap();
+        let output = layer.quantize(Precision::Int8).forward(&input).unwrap();
+        assert_eq!(output.shape(), &[2, 3]);
+        assert!(exact.data().iter().zip(output.data()).all(|(x, y)| (x - y).abs() < 0.05));
+
+        let mut weights = SavedWeights::default();
+        weights.insert("dense.weights", &layer.weights);
+        weights.insert("dense.bias", &layer.bias);
+        let dir = std::env::temp_dir();
+        let (input_path, output_path) = (
+            dir.join(format!("albayan_weights_{}.json", std::process::id())),
+            dir.join(format!("albayan_weights_int8_{}.json", std::process::id())),
+        );
+        weights.save(&input_path).unwrap();
+
+        let report = quantize_file(&input_path, &output_path, Precision::Int8).unwrap();
+        assert_eq!((report.quantized, report.kept), (1, 1));
+        assert_eq!(report.bytes_before, 15 * 4);
+        assert_eq!(report.bytes_after, 12 + 3 * 4);
+        let quantized = SavedWeights::load(&output_path).unwrap();
+        assert_eq!(quantized.tensors["dense.weights"].precision(), Precision::Int8);
+        assert_eq!(quantized.tensors["dense.bias"].precision(), Precision::F32);
+        std::fs::remove_file(&input_path).unwrap();
+        std::fs::remove_file(&output_path).unwrap();
+    }
+}
@@ -0,0 +1,469 @@
+//! Reinforcement learning
+//!
+//! An [`Environment`] is anything an agent can act in: `reset` starts an
+//! episode, `step` applies an action and `observe` returns the current
+//! observation as a [`Tensor`]. Actions are indices below `action_count`.
+//!
+//! Two baseline agents learn from any environment:
+//!
+//! - [`QLearning`] keeps a table of action values per observation, so it
+//!   suits environments with few distinct observations;
+//! - [`PolicyGradient`] (REINFORCE) learns a linear softmax policy over the
+//!   observation's features.
+//!
+//! [`KnowledgeEnvironment`] is an environment whose world model lives in a
+//! logic knowledge base: `state/1`, `action/1`, `start/1`,
+//! `transition(State, Action, Next)`, `reward(State, Action, Reward)` and
+//! `terminal/1` facts or rules describe it, so an agent can be trained
+//! against rules written in AlBayan. The FFI in `builtin_libraries::ffi`
+//! (`albayan_rt_rl_*`) trains agents over the built-in knowledge base.
+//! Exploration draws from `runtime::random`, so `--seed` makes training
+//! reproducible.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use rand::Rng;
+
+use super::Tensor;
+use crate::runtime::random::with_rng;
+use crate::runtime::LogicEngine;
+
+/// What an action led to
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub observation: Tensor,
+    pub reward: f32,
+    /// Whether the episode ended
+    pub done: bool,
+}
+
+/// A world an agent acts in
+pub trait Environment {
+    /// Start a new episode and return its first observation
+    fn reset(&mut self) -> Result<Tensor>;
+    /// Apply `action`, an index below [`Environment::action_count`]
+    fn step(&mut self, action: usize) -> Result<Step>;
+    /// The current observation
+    fn observe(&self) -> Tensor;
+    fn action_count(&self) -> usize;
+}
+
+/// Totals of one training episode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Episode {
+    pub total_reward: f32,
+    pub steps: usize,
+    /// Whether the environment ended the episode (rather than the step limit)
+    pub finished: bool,
+}
+
+/// Tabular Q-learning with epsilon-greedy exploration
+#[derive(Debug, Clone)]
+pub struct QLearning {
+    pub learning_rate: f32,
+    pub discount: f32,
+    /// Probability of a random action while training
+    pub epsilon: f32,
+    actions: usize,
+    /// Action values by observation, keyed on the bits of its elements
+    table: HashMap<Vec<u32>, Vec<f32>>,
+}
+
+/// REINFORCE with a linear softmax policy and the mean return as baseline
+#[derive(Debug, Clone)]
+pub struct PolicyGradient {
+    pub learning_rate: f32,
+    pub discount: f32,
+    /// `[features, actions]`
+    weights: Tensor,
+}
+
+/// A grid in which the agent walks from the top-left corner to the
+/// bottom-right one. Observations are one-hot over the cells; actions are
+/// up, down, left and right. Reaching the goal pays 1 and every other step
+/// costs 0.01.
+#[derive(Debug, Clone)]
+pub struct GridWorld {
+    pub width: usize,
+    pub height: usize,
+    position: (usize, usize),
+}
+
+/// An environment described by facts and rules in a knowledge base
+pub struct KnowledgeEnvironment<'a> {
+    logic: &'a mut LogicEngine,
+    states: Vec<String>,
+    actions: Vec<String>,
+    start: usize,
+    current: usize,
+}
+
+fn key(observation: &Tensor) -> Vec<u32> {
+    observation.data().iter().map(|value| value.to_bits()).collect()
+}
+
+fn argmax(values: &[f32]) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |best, (index, &value)| if value > best.1 { (index, value) } else { best })
+        .0
+}
+
+impl QLearning {
+    pub fn new(actions: usize) -> Self {
+        Self { learning_rate: 0.1, discount: 0.95, epsilon: 0.1, actions, table: HashMap::new() }
+    }
+
+    /// Action values for `observation`; zero until it has been visited
+    pub fn q_values(&self, observation: &Tensor) -> Vec<f32> {
+        self.table.get(&key(observation)).cloned().unwrap_or_else(|| vec![0.0; self.actions])
+    }
+
+    /// The best known action for `observation`
+    pub fn best_action(&self, observation: &Tensor) -> usize {
+        argmax(&self.q_values(observation))
+    }
+
+    /// The best action, or with probability `epsilon` a random one
+    pub fn act(&self, observation: &Tensor) -> usize {
+        with_rng(|rng| {
+            if rng.gen::<f32>() < self.epsilon {
+                Some(rng.gen_range(0..self.actions))
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| self.best_action(observation))
+    }
+
+    /// Move the value of `action` towards the reward plus the discounted
+    /// value of the best action from `next`
+    pub fn update(&mut self, observation: &Tensor, action: usize, reward: f32, next: &Tensor, done: bool) {
+        let future = if done { 0.0 } else { self.q_values(next).into_iter().fold(f32::NEG_INFINITY, f32::max) };
+        let target = reward + self.discount * future;
+        let values = self.table.entry(key(observation)).or_insert_with(|| vec![0.0; self.actions]);
+        values[action] += self.learning_rate * (target - values[action]);
+    }
+
+    /// Run and learn from one episode of at most `max_steps` steps
+    pub fn train_episode(&mut self, environment: &mut dyn Environment, max_steps: usize) -> Result<Episode> {
+        let mut observation = environment.reset()?;
+        let mut episode = Episode { total_reward: 0.0, steps: 0, finished: false };
+        while episode.steps < max_steps {
+            let action = self.act(&observation);
+            let step = environment.step(action)?;
+            self.update(&observation, action, step.reward, &step.observation, step.done);
+            episode.total_reward += step.reward;
+            episode.steps += 1;
+            observation = step.observation;
+            if step.done {
+                episode.finished = true;
+                break;
+            }
+        }
+        Ok(episode)
+    }
+
+    /// Number of observations with learned values
+    pub fn states_seen(&self) -> usize {
+        self.table.len()
+    }
+}
+
+impl PolicyGradient {
+    /// A uniform policy over `actions` for observations of `features` elements
+    pub fn new(features: usize, actions: usize) -> Self {
+        Self { learning_rate: 0.1, discount: 0.99, weights: Tensor::zeros(vec![features, actions]) }
+    }
+
+    pub fn weights(&self) -> &Tensor {
+        &self.weights
+    }
+
+    /// The policy's probability of each action for `observation`
+    pub fn action_probabilities(&self, observation: &Tensor) -> Result<Vec<f32>> {
+        let features = self.weights.shape()[0];
+        if observation.data().len() != features {
+            return Err(anyhow!("Observation has {} elements, the policy expects {}", observation.data().len(), features));
+        }
+        let logits = observation.reshape(vec![1, features])?.matmul(&self.weights)?;
+        Ok(logits.softmax(1)?.data().to_vec())
+    }
+
+    /// Sample an action from the policy
+    pub fn act(&self, observation: &Tensor) -> Result<usize> {
+        let probabilities = self.action_probabilities(observation)?;
+        let draw: f32 = with_rng(|rng| rng.gen());
+        let mut cumulative = 0.0;
+        for (action, probability) in probabilities.iter().enumerate() {
+            cumulative += probability;
+            if draw < cumulative {
+                return Ok(action);
+            }
+        }
+        Ok(probabilities.len() - 1)
+    }
+
+    /// The most probable action
+    pub fn best_action(&self, observation: &Tensor) -> Result<usize> {
+        Ok(argmax(&self.action_probabilities(observation)?))
+    }
+
+    /// Run one episode of at most `max_steps` steps, then follow the
+    /// gradient of the log-probability of each action taken, scaled by the
+    /// discounted return from that step minus the episode's mean return
+    pub fn train_episode(&mut self, environment: &mut dyn Environment, max_steps: usize) -> Result<Episode> {
+        let mut observation = environment.reset()?;
+        let mut trajectory = Vec::new();
+        let mut episode = Episode { total_reward: 0.0, steps: 0, finished: false };
+        while episode.steps < max_steps {
+            let action = self.act(&observation)?;
+            let step = environment.step(action)?;
+            episode.total_reward += step.reward;
+            episode.steps += 1;
+            trajectory.push((observation, action, step.reward));
+            observation = step.observation;
+            if step.done {
+                episode.finished = true;
+                break;
+            }
+        }
+
+        let mut returns = vec![0.0; trajectory.len()];
+        let mut running = 0.0;
+        for (index, (_, _, reward)) in trajectory.iter().enumerate().rev() {
+            running = reward + self.discount * running;
+            returns[index] = running;
+        }
+        let baseline = returns.iter().sum::<f32>() / returns.len().max(1) as f32;
+
+        let actions = self.weights.shape()[1];
+        let mut weights = self.weights.data().to_vec();
+        for ((observation, action, _), ret) in trajectory.iter().zip(&returns) {
+            let probabilities = self.action_probabilities(observation)?;
+            let advantage = ret - baseline;
+            for (feature, &x) in observation.data().iter().enumerate().filter(|(_, x)| **x != 0.0) {
+                for (k, probability) in probabilities.iter().enumerate() {
+                    let indicator = if k == *action { 1.0 } else { 0.0 };
+                    weights[feature * actions + k] += self.learning_rate * advantage * x * (indicator - probability);
+                }
+            }
+        }
+        self.weights = Tensor::new(weights, self.weights.shape().to_vec())?;
+        Ok(episode)
+    }
+}
+
+impl GridWorld {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width: width.max(1), height: height.max(1), position: (0, 0) }
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        self.position
+    }
+
+    fn goal(&self) -> (usize, usize) {
+        (self.width - 1, self.height - 1)
+    }
+}
+
+impl Environment for GridWorld {
+    fn reset(&mut self) -> Result<Tensor> {
+        self.position = (0, 0);
+        Ok(self.observe())
+    }
+
+    fn step(&mut self, action: usize) -> Result<Step> {
+        let (x, y) = self.position;
+        self.position = match action {
+            0 => (x, y.saturating_sub(1)),
+            1 => (x, (y + 1).min(self.height - 1)),
+            2 => (x.saturating_sub(1), y),
+            3 => ((x + 1).min(self.width - 1), y),
+            _ => return Err(anyhow!("Grid world has 4 actions, got action {}", action)),
+        };
+        let done = self.position == self.goal();
+        Ok(Step { observation: self.observe(), reward: if done { 1.0 } else { -0.01 }, done })
+    }
+
+    fn observe(&self) -> Tensor {
+        let mut cells = vec![0.0; self.width * self.height];
+        cells[self.position.1 * self.width + self.position.0] = 1.0;
+        Tensor::new(cells, vec![self.width * self.height]).expect("one value per cell")
+    }
+
+    fn action_count(&self) -> usize {
+        4
+    }
+}
+
+impl<'a> KnowledgeEnvironment<'a> {
+    /// Read the states, actions and start state from `logic`. States and
+    /// actions are listed in the order the knowledge base gives them.
+    pub fn new(logic: &'a mut LogicEngine) -> Result<Self> {
+        let mut column = |query: &str, variable: &str| -> Result<Vec<String>> {
+            let mut values = Vec::new();
+            for solution in logic.solve_query(query)? {
+                if let Some(value) = solution.get(variable) {
+                    if !values.contains(value) {
+                        values.push(value.clone());
+                    }
+                }
+            }
+            Ok(values)
+        };
+        let states = column("state(S)", "S")?;
+        let actions = column("action(A)", "A")?;
+        let start = column("start(S)", "S")?;
+        if states.is_empty() || actions.is_empty() {
+            return Err(anyhow!("The knowledge base needs state/1 and action/1 facts to describe an environment"));
+        }
+        let start = match start.first() {
+            Some(name) => states
+                .iter()
+                .position(|state| state == name)
+                .ok_or_else(|| anyhow!("Start state '{}' is not a state/1", name))?,
+            None => 0,
+        };
+        Ok(Self { logic, states, actions, start, current: start })
+    }
+
+    pub fn states(&self) -> &[String] {
+        &self.states
+    }
+
+    pub fn actions(&self) -> &[String] {
+        &self.actions
+    }
+
+    pub fn state(&self) -> &str {
+        &self.states[self.current]
+    }
+
+    /// The one-hot observation of the state named `name`
+    pub fn observation_of(&self, name: &str) -> Option<Tensor> {
+        let index = self.states.iter().position(|state| state == name)?;
+        Some(self.one_hot(index))
+    }
+
+    fn one_hot(&self, index: usize) -> Tensor {
+        let mut values = vec![0.0; self.states.len()];
+        values[index] = 1.0;
+        Tensor::new(values, vec![self.states.len()]).expect("one value per state")
+    }
+}
+
+impl Environment for KnowledgeEnvironment<'_> {
+    fn reset(&mut self) -> Result<Tensor> {
+        self.current = self.start;
+        Ok(self.observe())
+    }
+
+    /// Follow `transition(State, Action, Next)`; an action without a
+    /// transition leaves the state as it is. The reward is the first
+    /// `reward(State, Action, R)`, or 0.
+    fn step(&mut self, action: usize) -> Result<Step> {
+        let name = self.actions.get(action).ok_or_else(|| anyhow!("No action {}", action))?.clone();
+        let state = self.states[self.current].clone();
+
+        let next = self.logic.solve_query(&format!("transition({}, {}, Next)", state, name))?;
+        if let Some(next) = next.first().and_then(|solution| solution.get("Next")) {
+            self.current = self
+                .states
+                .iter()
+                .position(|candidate| candidate == next)
+                .ok_or_else(|| anyhow!("Transition to '{}', which is not a state/1", next))?;
+        }
+        let reward = self.logic.solve_query(&format!("reward({}, {}, R)", state, name))?;
+        let reward = match reward.first().and_then(|solution| solution.get("R")) {
+            Some(value) => value.parse().map_err(|_| anyhow!("Reward '{}' is not a number", value))?,
+            None => 0.0,
+        };
+        let done = !self.logic.solve_query(&format!("terminal({})", self.states[self.current]))?.is_empty();
+        Ok(Step { observation: self.observe(), reward, done })
+    }
+
+    fn observe(&self) -> Tensor {
+        self.one_hot(self.current)
+    }
+
+    fn action_count(&self) -> usize {
+        self.actions.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Steps `policy` takes from the start to the end of an episode, or
+    /// `usize::MAX` if it takes more than `limit`
+    fn greedy_path(environment: &mut dyn Environment, mut policy: impl FnMut(&Tensor) -> usize, limit: usize) -> usize {
+        let mut observation = environment.reset().unwrap();
+        for steps in 1..=limit {
+            let step = environment.step(policy(&observation)).unwrap();
+            if step.done {
+                return steps;
+            }
+            observation = step.observation;
+        }
+        usize::MAX
+    }
+
+    #[test]
+    fn test_q_learning_finds_the_shortest_path() {
+        let mut grid = GridWorld::new(4, 3);
+        let mut agent = QLearning::new(grid.action_count());
+        agent.learning_rate = 0.5;
+        agent.epsilon = 0.2;
+        for _ in 0..300 {
+            agent.train_episode(&mut grid, 100).unwrap();
+        }
+        assert_eq!(greedy_path(&mut grid, |observation| agent.best_action(observation), 20), 5);
+        assert_eq!(agent.states_seen(), 11);
+    }
+
+    #[test]
+    fn test_policy_gradient_prefers_rewarded_actions() {
+        let mut grid = GridWorld::new(4, 1);
+        let mut agent = PolicyGradient::new(4, grid.action_count());
+        agent.learning_rate = 0.5;
+        for _ in 0..300 {
+            agent.train_episode(&mut grid, 30).unwrap();
+        }
+        let start = grid.reset().unwrap();
+        assert_eq!(agent.best_action(&start).unwrap(), 3);
+        assert_eq!(greedy_path(&mut grid, |observation| agent.best_action(observation).unwrap(), 10), 3);
+        assert!(agent.action_probabilities(&Tensor::zeros(vec![3])).is_err());
+    }
+
+    #[test]
+    fn test_agents_learn_from_a_knowledge_base_world_model() {
+        let mut logic = LogicEngine::new();
+        for fact in [
+            "state(home).", "state(road).", "state(cliff).", "state(market).",
+            "action(walk).", "action(jump).", "start(home).",
+            "transition(home, walk, road).", "transition(road, walk, market).",
+            "transition(home, jump, cliff).", "transition(road, jump, cliff).",
+            "reward(road, walk, 10).", "terminal(market).", "terminal(cliff).",
+        ] {
+            logic.assert_fact(fact).unwrap();
+        }
+
+        let mut world = KnowledgeEnvironment::new(&mut logic).unwrap();
+        assert_eq!(world.states(), ["home", "road", "cliff", "market"]);
+        let mut agent = QLearning::new(world.action_count());
+        agent.learning_rate = 0.5;
+        agent.epsilon = 0.3;
+        for _ in 0..200 {
+            agent.train_episode(&mut world, 10).unwrap();
+        }
+        let road = world.observation_of("road").unwrap();
+        assert_eq!(world.actions()[agent.best_action(&road)], "walk");
+        assert_eq!(greedy_path(&mut world, |observation| agent.best_action(observation), 5), 2);
+        assert_eq!(world.state(), "market");
+    }
+}
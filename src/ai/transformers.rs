@@ -0,0 +1,496 @@
+//! Transformer building blocks
+//!
+//! [`MultiHeadAttention`], [`LayerNorm`], [`FeedForward`] and the post-norm
+//! [`EncoderBlock`] that combines them, stacked with sinusoidal
+//! [`PositionalEncoding`]s into a [`TransformerEncoder`]. Every block is a
+//! [`Layer`] over a sequence `[seq_len, d_model]` or a batch of sequences
+//! `[batch, seq_len, d_model]`, and keeps the input's shape, so blocks can be
+//! combined freely in a `NeuralNetwork`. They are sized for the tiny sequence
+//! models of the NLU pipeline: everything runs on the CPU in `f32`, and like
+//! the other layers, `backward` does not propagate gradients yet.
+
+use anyhow::{anyhow, Result};
+
+use super::neural_networks::Activation;
+use super::{Layer, Tensor};
+
+pub use super::natural_language::PositionalEncoding;
+
+/// Scaled dot-product attention over several heads
+#[derive(Debug, Clone)]
+pub struct MultiHeadAttention {
+    query_weights: Tensor,
+    key_weights: Tensor,
+    value_weights: Tensor,
+    output_weights: Tensor,
+    d_model: usize,
+    num_heads: usize,
+    /// Whether each position only attends to itself and earlier positions
+    causal: bool,
+}
+
+/// Normalization of each position's features to zero mean and unit
+/// variance, then a learned scale and shift
+#[derive(Debug, Clone)]
+pub struct LayerNorm {
+    gamma: Tensor,
+    beta: Tensor,
+    eps: f32,
+}
+
+/// Two linear maps with a GELU between them, applied to each position
+#[derive(Debug, Clone)]
+pub struct FeedForward {
+    hidden_weights: Tensor,
+    hidden_bias: Tensor,
+    output_weights: Tensor,
+    output_bias: Tensor,
+}
+
+/// Self-attention and a feed-forward network, each followed by a residual
+/// connection and layer normalization
+#[derive(Debug, Clone)]
+pub struct EncoderBlock {
+    pub attention: MultiHeadAttention,
+    pub attention_norm: LayerNorm,
+    pub feed_forward: FeedForward,
+    pub feed_forward_norm: LayerNorm,
+}
+
+/// Positional encodings followed by a stack of encoder blocks
+#[derive(Debug, Clone)]
+pub struct TransformerEncoder {
+    positional: PositionalEncoding,
+    blocks: Vec<EncoderBlock>,
+    d_model: usize,
+}
+
+/// Weights drawn uniformly from the Glorot range, so activations keep their scale
+fn glorot(rows: usize, columns: usize) -> Tensor {
+    let limit = (6.0 / (rows + columns) as f32).sqrt();
+    let random = Tensor::random(vec![rows, columns]);
+    let data = random.data().iter().map(|x| (2.0 * x - 1.0) * limit).collect();
+    Tensor::new(data, vec![rows, columns]).expect("shape matches the data")
+}
+
+fn transpose(matrix: &Tensor) -> Tensor {
+    let (rows, columns) = (matrix.shape()[0], matrix.shape()[1]);
+    let data = matrix.data();
+    let transposed = (0..columns).flat_map(|c| (0..rows).map(move |r| data[r * columns + c])).collect();
+    Tensor::new(transposed, vec![columns, rows]).expect("shape matches the data")
+}
+
+/// Columns `start..start + width` of a matrix
+fn columns(matrix: &Tensor, start: usize, width: usize) -> Tensor {
+    let total = matrix.shape()[1];
+    let data = matrix.data().chunks(total).flat_map(|row| &row[start..start + width]).copied().collect();
+    Tensor::new(data, vec![matrix.shape()[0], width]).expect("shape matches the data")
+}
+
+/// Softmax of each row, shifted by the row maximum for stability
+fn softmax_rows(matrix: &Tensor) -> Tensor {
+    let width = matrix.shape()[1];
+    let mut data = matrix.data().to_vec();
+    for row in data.chunks_mut(width) {
+        let max = row.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let mut sum = 0.0;
+        for value in row.iter_mut() {
+            *value = if max == f32::NEG_INFINITY { 0.0 } else { (*value - max).exp() };
+            sum += *value;
+        }
+        if sum > 0.0 {
+            row.iter_mut().for_each(|value| *value /= sum);
+        }
+    }
+    Tensor::new(data, matrix.shape().to_vec()).expect("shape matches the data")
+}
+
+/// Add a bias to every row of a matrix
+fn add_bias(matrix: &Tensor, bias: &Tensor) -> Tensor {
+    let width = bias.data().len();
+    let data = matrix.data().chunks(width).flat_map(|row| row.iter().zip(bias.data()).map(|(x, b)| x + b)).collect();
+    Tensor::new(data, matrix.shape().to_vec()).expect("shape matches the data")
+}
+
+/// Apply `f` to a sequence `[seq_len, width]`, or to each sequence of a
+/// batch `[batch, seq_len, width]`
+fn per_sequence<F>(input: &Tensor, width: usize, layer: &str, mut f: F) -> Result<Tensor>
+where
+    F: FnMut(&Tensor) -> Result<Tensor>,
+{
+    let shape = input.shape();
+    if !(shape.len() == 2 || shape.len() == 3) || shape[shape.len() - 1] != width {
+        return Err(anyhow!("{} expects [seq_len, {}] or [batch, seq_len, {}], got {:?}", layer, width, width, shape));
+    }
+    if shape.len() == 2 {
+        return f(input);
+    }
+    let (batch, seq_len) = (shape[0], shape[1]);
+    let mut data = Vec::with_capacity(input.data().len());
+    for sequence in input.data().chunks(seq_len * width).take(batch) {
+        let output = f(&Tensor::new(sequence.to_vec(), vec![seq_len, width])?)?;
+        data.extend_from_slice(output.data());
+    }
+    Tensor::new(data, shape.to_vec())
+}
+
+impl MultiHeadAttention {
+    /// Attention over `num_heads` heads, which must divide `d_model`
+    pub fn new(d_model: usize, num_heads: usize) -> Result<Self> {
+        if num_heads == 0 || d_model % num_heads != 0 {
+            return Err(anyhow!("{} heads do not divide d_model {}", num_heads, d_model));
+        }
+        Ok(Self {
+            query_weights: glorot(d_model, d_model),
+            key_weights: glorot(d_model, d_model),
+            value_weights: glorot(d_model, d_model),
+            output_weights: glorot(d_model, d_model),
+            d_model,
+            num_heads,
+            causal: false,
+        })
+    }
+
+    /// Mask later positions, as a decoder or left-to-right model needs
+    pub fn with_causal_mask(mut self, causal: bool) -> Self {
+        self.causal = causal;
+        self
+    }
+
+    pub fn num_heads(&self) -> usize {
+        self.num_heads
+    }
+
+    /// Attend from each row of `query` `[query_len, d_model]` to the rows of
+    /// `key` and `value` `[key_len, d_model]`. Returns the output
+    /// `[query_len, d_model]` and the attention weights
+    /// `[num_heads, query_len, key_len]`, whose rows sum to 1.
+    pub fn attend(&self, query: &Tensor, key: &Tensor, value: &Tensor) -> Result<(Tensor, Tensor)> {
+        for (name, input) in [("query", query), ("key", key), ("value", value)] {
+            if input.shape().len() != 2 || input.shape()[1] != self.d_model {
+                return Err(anyhow!("Attention {} must be [len, {}], got {:?}", name, self.d_model, input.shape()));
+            }
+        }
+        if key.shape()[0] != value.shape()[0] {
+            return Err(anyhow!("Attention key and value lengths differ: {} and {}", key.shape()[0], value.shape()[0]));
+        }
+
+        let (query_len, key_len) = (query.shape()[0], key.shape()[0]);
+        let head_size = self.d_model / self.num_heads;
+        let scale = 1.0 / (head_size as f32).sqrt();
+        let q = query.matmul(&self.query_weights)?;
+        let k = key.matmul(&self.key_weights)?;
+        let v = value.matmul(&self.value_weights)?;
+
+        let mut heads = vec![0.0; query_len * self.d_model];
+        let mut weights = Vec::with_capacity(self.num_heads * query_len * key_len);
+        for head in 0..self.num_heads {
+            let start = head * head_size;
+            let scores = columns(&q, start, head_size).matmul(&transpose(&columns(&k, start, head_size)))?;
+            let mut scores: Vec<f32> = scores.data().iter().map(|score| score * scale).collect();
+            if self.causal {
+                for (row, scores) in scores.chunks_mut(key_len).enumerate() {
+                    scores.iter_mut().skip(row + 1).for_each(|score| *score = f32::NEG_INFINITY);
+                }
+            }
+            let attention = softmax_rows(&Tensor::new(scores, vec![query_len, key_len])?);
+            let output = attention.matmul(&columns(&v, start, head_size))?;
+            for (row, values) in output.data().chunks(head_size).enumerate() {
+                heads[row * self.d_model + start..row * self.d_model + start + head_size].copy_from_slice(values);
+            }
+            weights.extend_from_slice(attention.data());
+        }
+
+        let output = Tensor::new(heads, vec![query_len, self.d_model])?.matmul(&self.output_weights)?;
+        Ok((output, Tensor::new(weights, vec![self.num_heads, query_len, key_len])?))
+    }
+}
+
+impl Layer for MultiHeadAttention {
+    /// Self-attention
+    fn forward(&self, input: &Tensor) -> Result<Tensor> {
+        per_sequence(input, self.d_model, self.name(), |sequence| Ok(self.attend(sequence, sequence, sequence)?.0))
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Result<Tensor> {
+        // Simplified backward pass
+        Ok(Tensor::zeros(grad_output.shape().to_vec()))
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        vec![&self.query_weights, &self.key_weights, &self.value_weights, &self.output_weights]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        vec![&mut self.query_weights, &mut self.key_weights, &mut self.value_weights, &mut self.output_weights]
+    }
+
+    fn name(&self) -> &'static str {
+        "MultiHeadAttention"
+    }
+}
+
+impl LayerNorm {
+    pub fn new(features: usize) -> Self {
+        Self { gamma: Tensor::ones(vec![features]), beta: Tensor::zeros(vec![features]), eps: 1e-5 }
+    }
+}
+
+impl Layer for LayerNorm {
+    /// Normalize over the last dimension, whatever the rank
+    fn forward(&self, input: &Tensor) -> Result<Tensor> {
+        let features = self.gamma.data().len();
+        if input.shape().last() != Some(&features) {
+            return Err(anyhow!("LayerNorm over {} features got shape {:?}", features, input.shape()));
+        }
+        let mut data = input.data().to_vec();
+        for row in data.chunks_mut(features) {
+            let mean = row.iter().sum::<f32>() / features as f32;
+            let variance = row.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / features as f32;
+            let deviation = (variance + self.eps).sqrt();
+            for ((x, gamma), beta) in row.iter_mut().zip(self.gamma.data()).zip(self.beta.data()) {
+                *x = (*x - mean) / deviation * gamma + beta;
+            }
+        }
+        Tensor::new(data, input.shape().to_vec())
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Result<Tensor> {
+        // Simplified backward pass
+        Ok(Tensor::zeros(grad_output.shape().to_vec()))
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        vec![&self.gamma, &self.beta]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        vec![&mut self.gamma, &mut self.beta]
+    }
+
+    fn name(&self) -> &'static str {
+        "LayerNorm"
+    }
+}
+
+impl FeedForward {
+    pub fn new(d_model: usize, hidden_size: usize) -> Self {
+        Self {
+            hidden_weights: glorot(d_model, hidden_size),
+            hidden_bias: Tensor::zeros(vec![hidden_size]),
+            output_weights: glorot(hidden_size, d_model),
+            output_bias: Tensor::zeros(vec![d_model]),
+        }
+    }
+}
+
+impl Layer for FeedForward {
+    fn forward(&self, input: &Tensor) -> Result<Tensor> {
+        let d_model = self.output_bias.data().len();
+        per_sequence(input, d_model, self.name(), |sequence| {
+            let hidden = add_bias(&sequence.matmul(&self.hidden_weights)?, &self.hidden_bias);
+            let hidden = Activation::GELU.apply(&hidden)?;
+            Ok(add_bias(&hidden.matmul(&self.output_weights)?, &self.output_bias))
+        })
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Result<Tensor> {
+        // Simplified backward pass
+        Ok(Tensor::zeros(grad_output.shape().to_vec()))
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        vec![&self.hidden_weights, &self.hidden_bias, &self.output_weights, &self.output_bias]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        vec![&mut self.hidden_weights, &mut self.hidden_bias, &mut self.output_weights, &mut self.output_bias]
+    }
+
+    fn name(&self) -> &'static str {
+        "FeedForward"
+    }
+}
+
+impl EncoderBlock {
+    pub fn new(d_model: usize, num_heads: usize, hidden_size: usize) -> Result<Self> {
+        Ok(Self {
+            attention: MultiHeadAttention::new(d_model, num_heads)?,
+            attention_norm: LayerNorm::new(d_model),
+            feed_forward: FeedForward::new(d_model, hidden_size),
+            feed_forward_norm: LayerNorm::new(d_model),
+        })
+    }
+}
+
+impl Layer for EncoderBlock {
+    fn forward(&self, input: &Tensor) -> Result<Tensor> {
+        let attended = self.attention_norm.forward(&input.add(&self.attention.forward(input)?)?)?;
+        self.feed_forward_norm.forward(&attended.add(&self.feed_forward.forward(&attended)?)?)
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Result<Tensor> {
+        // Simplified backward pass
+        Ok(Tensor::zeros(grad_output.shape().to_vec()))
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        let mut parameters = self.attention.parameters();
+        parameters.extend(self.attention_norm.parameters());
+        parameters.extend(self.feed_forward.parameters());
+        parameters.extend(self.feed_forward_norm.parameters());
+        parameters
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        let mut parameters = self.attention.parameters_mut();
+        parameters.extend(self.attention_norm.parameters_mut());
+        parameters.extend(self.feed_forward.parameters_mut());
+        parameters.extend(self.feed_forward_norm.parameters_mut());
+        parameters
+    }
+
+    fn name(&self) -> &'static str {
+        "EncoderBlock"
+    }
+}
+
+impl TransformerEncoder {
+    /// `num_layers` encoder blocks for sequences of up to `max_length` positions
+    pub fn new(d_model: usize, num_heads: usize, hidden_size: usize, num_layers: usize, max_length: usize) -> Result<Self> {
+        let blocks = (0..num_layers)
+            .map(|_| EncoderBlock::new(d_model, num_heads, hidden_size))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { positional: PositionalEncoding::new(max_length, d_model), blocks, d_model })
+    }
+
+    pub fn blocks(&self) -> &[EncoderBlock] {
+        &self.blocks
+    }
+
+    /// The mean of the encoded positions of a sequence `[seq_len, d_model]`:
+    /// one `[d_model]` vector for the whole sequence, e.g. to classify it
+    pub fn pooled(&self, sequence: &Tensor) -> Result<Tensor> {
+        if sequence.shape().len() != 2 {
+            return Err(anyhow!("Pooling expects one sequence [seq_len, {}], got {:?}", self.d_model, sequence.shape()));
+        }
+        let encoded = self.forward(sequence)?;
+        let seq_len = encoded.shape()[0].max(1) as f32;
+        let mut mean = vec![0.0; self.d_model];
+        for row in encoded.data().chunks(self.d_model) {
+            mean.iter_mut().zip(row).for_each(|(sum, x)| *sum += x / seq_len);
+        }
+        Tensor::new(mean, vec![self.d_model])
+    }
+}
+
+impl Layer for TransformerEncoder {
+    fn forward(&self, input: &Tensor) -> Result<Tensor> {
+        let mut output = self.positional.forward(input)?;
+        for block in &self.blocks {
+            output = block.forward(&output)?;
+        }
+        Ok(output)
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Result<Tensor> {
+        // Simplified backward pass
+        Ok(Tensor::zeros(grad_output.shape().to_vec()))
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        self.blocks.iter().flat_map(|block| block.parameters()).collect()
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        self.blocks.iter_mut().flat_map(|block| block.parameters_mut()).collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "TransformerEncoder"
+    }
+}
+
+impl Layer for PositionalEncoding {
+    /// Add the encoding of each position to a sequence or batch of sequences
+    fn forward(&self, input: &Tensor) -> Result<Tensor> {
+        let d_model = *input.shape().last().unwrap_or(&0);
+        per_sequence(input, d_model, self.name(), |sequence| self.add_encoding(sequence))
+    }
+
+    fn backward(&mut self, grad_output: &Tensor) -> Result<Tensor> {
+        // The encoding is added, so gradients pass through unchanged
+        Ok(grad_output.clone())
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        vec![]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        vec![]
+    }
+
+    fn name(&self) -> &'static str {
+        "PositionalEncoding"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(seq_len: usize, d_model: usize) -> Tensor {
+        let data = (0..seq_len * d_model).map(|i| (i as f32 * 0.37).sin()).collect();
+        Tensor::new(data, vec![seq_len, d_model]).unwrap()
+    }
+
+    #[test]
+    fn test_attention_shapes_and_weights() {
+        let attention = MultiHeadAttention::new(8, 2).unwrap();
+        let query = sequence(3, 8);
+        let memory = sequence(5, 8);
+        let (output, weights) = attention.attend(&query, &memory, &memory).unwrap();
+        assert_eq!(output.shape(), [3, 8]);
+        assert_eq!(weights.shape(), [2, 3, 5]);
+        for row in weights.data().chunks(5) {
+            assert!((row.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        }
+
+        let causal = attention.clone().with_causal_mask(true);
+        let (_, weights) = causal.attend(&query, &query, &query).unwrap();
+        // Position 0 only sees itself; position 1 sees 0 and 1
+        assert_eq!(&weights.data()[..3], &[1.0, 0.0, 0.0]);
+        assert_eq!(weights.data()[5], 0.0);
+
+        assert_eq!(attention.forward(&Tensor::zeros(vec![4, 3, 8])).unwrap().shape(), [4, 3, 8]);
+        assert!(attention.forward(&sequence(3, 6)).is_err());
+        assert!(MultiHeadAttention::new(8, 3).is_err());
+    }
+
+    #[test]
+    fn test_layer_norm_normalizes_each_position() {
+        let norm = LayerNorm::new(4);
+        let output = norm.forward(&Tensor::new(vec![1.0, 2.0, 3.0, 4.0, 10.0, 10.0, 10.0, 10.0], vec![2, 4]).unwrap()).unwrap();
+        let first = &output.data()[..4];
+        assert!(first.iter().sum::<f32>().abs() < 1e-5);
+        assert!((first.iter().map(|x| x * x).sum::<f32>() / 4.0 - 1.0).abs() < 1e-3);
+        assert!(output.data()[4..].iter().all(|x| x.abs() < 1e-5));
+        assert!(norm.forward(&sequence(2, 3)).is_err());
+    }
+
+    #[test]
+    fn test_encoder_keeps_the_sequence_shape() {
+        let encoder = TransformerEncoder::new(8, 2, 16, 2, 10).unwrap();
+        assert_eq!(encoder.blocks().len(), 2);
+        assert_eq!(encoder.parameters().len(), 2 * (4 + 2 + 4 + 2));
+
+        let encoded = encoder.forward(&sequence(6, 8)).unwrap();
+        assert_eq!(encoded.shape(), [6, 8]);
+        assert!(encoded.data().iter().all(|x| x.is_finite()));
+        assert_eq!(encoder.forward(&Tensor::zeros(vec![3, 6, 8])).unwrap().shape(), [3, 6, 8]);
+        assert_eq!(encoder.pooled(&sequence(6, 8)).unwrap().shape(), [8]);
+        // Longer than the positional encodings
+        assert!(encoder.forward(&sequence(11, 8)).is_err());
+    }
+}
@@ -0,0 +1,544 @@
+//! # Project Builds
+//!
+//! `Compiler::compile_project` builds a whole directory: it finds every `.ab`
+//! file under it (as `albayan check` does), reads the `using` declarations
+//! of each one to build the module dependency graph, and compiles the files
+//! so that every module comes after the modules it imports. A file whose
+//! imports failed is skipped, and the modules of an import cycle fail.
+//!
+//! A file's module is its path relative to the directory without the `.ab`
+//! extension, so `geometry/shapes.ab` is `geometry::shapes`. A `using` path
+//! imports the project module that is its longest prefix; paths outside the
+//! project (such as `std::...`) are not part of the graph.
+//!
+//! Object files are written under the `output_path` of the options, or
+//! `<dir>/target/albayan`, mirroring the source tree. Files are compiled
+//! one at a time; the [`BuildReport`] records each one's phase timings,
+//! diagnostics and artifact for the CLI and the language server.
+//!
+//! With a `cache_dir` in the options, a module whose source, imported
+//! modules and options are unchanged since an earlier build gets its object
+//! file from the [`crate::codegen::cache`] and is not analyzed again. The
+//! cache keeps each module's analysis too, which a check reuses the same way.
+//!
+//! A build leaves the phase timings of its files in [`TIMINGS_FILE`] in the
+//! output directory, where `albayan status` reads them back as the
+//! [`BuildTimings`] of the last build.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::codegen::cache::{CacheKey, CacheKeyBuilder, CompilationCache};
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::lexer::Lexer;
+use crate::parser::ast::{Item, Program};
+use crate::parser::Parser;
+use crate::semantic::SemanticAnalyzer;
+use crate::{metrics, Compiler, CompilerError, CompilerResult};
+
+/// Directory under the project that receives object files by default
+pub const DEFAULT_OUTPUT_DIR: &str = "target/albayan";
+
+/// File in the output directory with the phase timings of the last build
+pub const TIMINGS_FILE: &str = "last_build.json";
+
+/// Result of building a project
+#[derive(Debug, Clone)]
+pub struct BuildReport {
+    pub root: PathBuf,
+    /// Every file, in the order it was compiled
+    pub files: Vec<FileReport>,
+    /// Import cycles between modules, each listed from its first module on
+    pub cycles: Vec<Vec<String>>,
+    pub elapsed: Duration,
+}
+
+/// How one file of a project build went
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub path: PathBuf,
+    /// Module name, e.g. `geometry::shapes`
+    pub module: String,
+    /// Project modules the file imports
+    pub dependencies: Vec<String>,
+    pub status: FileStatus,
+    pub timings: PhaseTimings,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Object file written for the module
+    pub artifact: Option<PathBuf>,
+    /// Whether the module was not analyzed again: its analysis, and in a
+    /// build its object file, came from the compilation cache
+    pub cached: bool,
+}
+
+impl FileReport {
+    pub fn error_count(&self) -> usize {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Error).count()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileStatus {
+    Compiled,
+    Failed,
+    /// Not compiled because a module it imports failed
+    Skipped { dependency: String },
+}
+
+/// Time spent in each phase of a file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseTimings {
+    /// Lexing and parsing
+    pub parse: Duration,
+    pub analysis: Duration,
+    pub codegen: Duration,
+}
+
+impl PhaseTimings {
+    pub fn total(&self) -> Duration {
+        self.parse + self.analysis + self.codegen
+    }
+}
+
+/// Phase timings of a whole build, as kept in [`TIMINGS_FILE`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildTimings {
+    pub elapsed: Duration,
+    /// Module and timings of every file, in build order
+    pub files: Vec<(String, PhaseTimings)>,
+}
+
+impl BuildTimings {
+    /// Timings of the last build that wrote to `output_dir`, if one recorded them
+    pub fn load(output_dir: &Path) -> Option<Self> {
+        let text = std::fs::read_to_string(output_dir.join(TIMINGS_FILE)).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    /// Time spent in each phase summed over the files
+    pub fn phases(&self) -> PhaseTimings {
+        self.files.iter().fold(PhaseTimings::default(), |sum, (_, timings)| PhaseTimings {
+            parse: sum.parse + timings.parse,
+            analysis: sum.analysis + timings.analysis,
+            codegen: sum.codegen + timings.codegen,
+        })
+    }
+}
+
+impl BuildReport {
+    /// Whether every file compiled
+    pub fn succeeded(&self) -> bool {
+        self.files.iter().all(|file| file.status == FileStatus::Compiled)
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.files
+            .iter()
+            .map(FileReport::error_count)
+            .sum()
+    }
+
+    pub fn file(&self, module: &str) -> Option<&FileReport> {
+        self.files.iter().find(|file| file.module == module)
+    }
+
+    pub fn timings(&self) -> BuildTimings {
+        BuildTimings {
+            elapsed: self.elapsed,
+            files: self.files.iter().map(|file| (file.module.clone(), file.timings)).collect(),
+        }
+    }
+}
+
+impl fmt::Display for BuildReport {
+    /// The build graph: one line per file in build order with its imports,
+    /// outcome and time, then the cycles and a summary
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for file in &self.files {
+            let outcome = match &file.status {
+                FileStatus::Compiled if file.cached => "ok (cached)".to_string(),
+                FileStatus::Compiled => "ok".to_string(),
+                FileStatus::Failed => format!("failed ({} errors)", file.error_count()),
+                FileStatus::Skipped { dependency } => format!("skipped ({} failed)", dependency),
+            };
+            write!(f, "{} [{}]", file.module, file.path.display())?;
+            if !file.dependencies.is_empty() {
+                write!(f, " <- {}", file.dependencies.join(", "))?;
+            }
+            writeln!(f, ": {} in {:.1?}", outcome, file.timings.total())?;
+        }
+        for cycle in &self.cycles {
+            writeln!(f, "import cycle: {} -> {}", cycle.join(" -> "), cycle[0])?;
+        }
+        let compiled = self.files.iter().filter(|file| file.status == FileStatus::Compiled).count();
+        write!(
+            f,
+            "{} of {} files compiled, {} errors, in {:.1?}",
+            compiled,
+            self.files.len(),
+            self.error_count(),
+            self.elapsed
+        )
+    }
+}
+
+/// A file whose source has been read and parsed
+struct ParsedFile {
+    report: FileReport,
+    program: Option<Program>,
+    /// Every `using` path of the file
+    imports: Vec<Vec<String>>,
+    /// Hash of the source
+    source_key: CacheKey,
+}
+
+/// Build the project in `root`; `generate` turns code generation and
+/// artifacts on
+pub(crate) fn build_project(compiler: &Compiler, root: &Path, generate: bool) -> CompilerResult<BuildReport> {
+    let started = Instant::now();
+    let output_dir = compiler.options.output_path.clone().unwrap_or_else(|| root.join(DEFAULT_OUTPUT_DIR));
+    let cache = CompilationCache::from_options(&compiler.options);
+
+    let mut files = Vec::new();
+    for path in crate::modules::collect_sources(&[root.to_path_buf()])? {
+        files.push(parse_file(compiler, root, path)?);
+    }
+
+    let modules: HashMap<String, usize> =
+        files.iter().enumerate().map(|(index, file)| (file.report.module.clone(), index)).collect();
+    for file in &mut files {
+        let mut dependencies: Vec<String> = file
+            .imports
+            .iter()
+            .filter_map(|path| project_module(path, &modules))
+            .filter(|module| *module != file.report.module)
+            .collect();
+        dependencies.sort();
+        dependencies.dedup();
+        file.report.dependencies = dependencies;
+    }
+
+    let (order, cycles) = build_order(&files, &modules);
+    let mut outcomes: HashMap<String, bool> = HashMap::new();
+    let mut keys: HashMap<String, CacheKey> = HashMap::new();
+    let mut reports = Vec::with_capacity(files.len());
+    let mut slots: Vec<Option<ParsedFile>> = files.into_iter().map(Some).collect();
+    for index in order {
+        let ParsedFile { mut report, program, source_key, .. } = slots[index].take().expect("each file is built once");
+        // Imports are built first unless they wait on a cycle
+        let failed_dependency = report.dependencies.iter().find(|module| outcomes.get(*module) != Some(&true));
+        match (program, failed_dependency) {
+            (_, Some(dependency)) => report.status = FileStatus::Skipped { dependency: dependency.clone() },
+            (None, None) => {}
+            (Some(program), None) => {
+                // A module's key covers the keys of its imports, so changing
+                // a module invalidates everything that imports it
+                let mut key = CacheKeyBuilder::new(&compiler.options);
+                key.add(report.module.as_bytes()).add_key(source_key);
+                for dependency in &report.dependencies {
+                    key.add_key(keys[dependency]);
+                }
+                let key = key.finish();
+                keys.insert(report.module.clone(), key);
+
+                let artifact = generate.then(|| output_dir.join(&report.path).with_extension("o"));
+                let cached = cache.as_ref().zip(artifact.as_ref()).and_then(|(cache, artifact)| {
+                    let object_code = cache.get(key)?;
+                    Some((artifact.clone(), object_code))
+                });
+                match cached {
+                    Some((artifact, object_code)) => {
+                        write_artifact(&artifact, &object_code)?;
+                        report.status = FileStatus::Compiled;
+                        report.artifact = Some(artifact);
+                        report.cached = true;
+                    }
+                    None => compile_file(compiler, program, &mut report, artifact, cache.as_ref().map(|cache| (cache, key)))?,
+                }
+            }
+        }
+        outcomes.insert(report.module.clone(), report.status == FileStatus::Compiled);
+        reports.push(report);
+    }
+
+    for cycle in &cycles {
+        let message = format!("import cycle: {} -> {}", cycle.join(" -> "), cycle[0]);
+        for module in cycle {
+            let mut report = slots[modules[module]].take().expect("each file is built once").report;
+            report.status = FileStatus::Failed;
+            report.diagnostics.push(Diagnostic::error("AB0700", message.clone()));
+            reports.push(report);
+        }
+    }
+
+    for report in &mut reports {
+        report.path = root.join(&report.path);
+    }
+    let report = BuildReport { root: root.to_path_buf(), files: reports, cycles, elapsed: started.elapsed() };
+    if generate {
+        // Timings that cannot be written only leave `albayan status` without them
+        if let Ok(json) = serde_json::to_string(&report.timings()) {
+            let _ = write_artifact(&output_dir.join(TIMINGS_FILE), json.as_bytes());
+        }
+    }
+    Ok(report)
+}
+
+/// Read and parse a file; its report's path is relative to `root` until the build ends
+fn parse_file(compiler: &Compiler, root: &Path, path: PathBuf) -> CompilerResult<ParsedFile> {
+    let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+    let module = relative
+        .with_extension("")
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("::");
+    let mut report = FileReport {
+        path: relative,
+        module,
+        dependencies: Vec::new(),
+        status: FileStatus::Failed,
+        timings: PhaseTimings::default(),
+        diagnostics: Vec::new(),
+        artifact: None,
+        cached: false,
+    };
+
+    let source = std::fs::read_to_string(&path)?;
+    let source_key = CacheKeyBuilder::new(&compiler.options).add(source.as_bytes()).finish();
+    let started = Instant::now();
+    let parsed = Lexer::new(&source).tokenize().map_err(CompilerError::from).and_then(|tokens| {
+        let mut parser = Parser::new(tokens).with_cancellation(compiler.options.cancellation.clone());
+        Ok(parser.parse()?)
+    });
+    report.timings.parse = started.elapsed();
+
+    let program = match parsed {
+        Ok(program) => program,
+        Err(error) => {
+            stop_if_cancelled(&error)?;
+            report.diagnostics.push(Diagnostic::from(&error));
+            return Ok(ParsedFile { report, program: None, imports: Vec::new(), source_key });
+        }
+    };
+    let imports = program
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Using(using) => Some(using.path.clone()),
+            _ => None,
+        })
+        .collect();
+    Ok(ParsedFile { report, program: Some(program), imports, source_key })
+}
+
+/// Analyze a parsed file, or take its analysis from `cache`, and generate its
+/// object file into `artifact`, storing both in the cache under the file's key
+fn compile_file(
+    compiler: &Compiler,
+    program: Program,
+    report: &mut FileReport,
+    artifact: Option<PathBuf>,
+    cache: Option<(&CompilationCache, CacheKey)>,
+) -> CompilerResult<()> {
+    let started = Instant::now();
+    let cached_analysis = cache.and_then(|(cache, key)| cache.get_analysis(key));
+    report.cached = cached_analysis.is_some();
+    let result = match cached_analysis {
+        Some(analyzed) => Ok(analyzed),
+        None => SemanticAnalyzer::new(&compiler.options).analyze(program).map_err(CompilerError::from).map(|analyzed| {
+            if let Some((cache, key)) = cache {
+                let _ = cache.put_analysis(key, &analyzed);
+            }
+            analyzed
+        }),
+    };
+    report.timings.analysis = started.elapsed();
+
+    let result = result.and_then(|mut program| {
+        let warnings = std::mem::take(&mut program.warnings);
+        report.diagnostics.extend(warnings.iter().map(Diagnostic::from));
+        let Some(artifact) = &artifact else {
+            return Ok(());
+        };
+        let started = Instant::now();
+        let object_code = compiler.generate(program);
+        report.timings.codegen = started.elapsed();
+        let object_code = object_code?;
+        if let Some((cache, key)) = cache {
+            // A cache that cannot be written only costs the next build time
+            let _ = cache.put(key, &object_code);
+        }
+        write_artifact(artifact, &object_code)
+    });
+    metrics::record_compilation(report.timings.total(), result.is_ok());
+
+    match result {
+        Ok(()) => {
+            report.status = FileStatus::Compiled;
+            report.artifact = artifact;
+        }
+        Err(error) => {
+            stop_if_cancelled(&error)?;
+            report.diagnostics.push(Diagnostic::from(&error));
+        }
+    }
+    Ok(())
+}
+
+fn write_artifact(artifact: &Path, object_code: &[u8]) -> CompilerResult<()> {
+    if let Some(directory) = artifact.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    Ok(std::fs::write(artifact, object_code)?)
+}
+
+/// A cancelled build stops instead of reporting the file as failed
+fn stop_if_cancelled(error: &CompilerError) -> CompilerResult<()> {
+    match error.cancelled() {
+        Some(cancelled) => Err(cancelled.into()),
+        None => Ok(()),
+    }
+}
+
+/// The project module a `using` path imports: its longest prefix that names one
+fn project_module(path: &[String], modules: &HashMap<String, usize>) -> Option<String> {
+    (1..=path.len()).rev().map(|length| path[..length].join("::")).find(|module| modules.contains_key(module))
+}
+
+/// Files in an order that puts every module after the ones it imports, and
+/// the import cycles whose modules cannot be ordered. Among the files that
+/// are ready, the one found first goes first.
+fn build_order(files: &[ParsedFile], modules: &HashMap<String, usize>) -> (Vec<usize>, Vec<Vec<String>>) {
+    let mut waiting_on: Vec<usize> = files.iter().map(|file| file.report.dependencies.len()).collect();
+    let mut importers: Vec<Vec<usize>> = vec![Vec::new(); files.len()];
+    for (index, file) in files.iter().enumerate() {
+        for dependency in &file.report.dependencies {
+            importers[modules[dependency]].push(index);
+        }
+    }
+
+    let mut ready: BTreeSet<usize> = (0..files.len()).filter(|&index| waiting_on[index] == 0).collect();
+    let mut order = Vec::with_capacity(files.len());
+    while let Some(index) = ready.pop_first() {
+        order.push(index);
+        for &importer in &importers[index] {
+            waiting_on[importer] -= 1;
+            if waiting_on[importer] == 0 {
+                ready.insert(importer);
+            }
+        }
+    }
+
+    // What is left waits on a cycle; follow unbuilt imports from each such
+    // file until a module repeats
+    let mut cycles = Vec::new();
+    let mut in_cycle = vec![false; files.len()];
+    for start in (0..files.len()).filter(|&index| waiting_on[index] > 0) {
+        let mut path = vec![start];
+        let next_unbuilt = |index: usize| {
+            files[index].report.dependencies.iter().map(|module| modules[module]).find(|&next| waiting_on[next] > 0)
+        };
+        while let Some(next) = next_unbuilt(*path.last().expect("the path starts with a file")) {
+            if let Some(position) = path.iter().position(|&index| index == next) {
+                if path[position..].iter().all(|&index| !in_cycle[index]) {
+                    path[position..].iter().for_each(|&index| in_cycle[index] = true);
+                    cycles.push(path[position..].iter().map(|&index| files[index].report.module.clone()).collect());
+                }
+                break;
+            }
+            path.push(next);
+        }
+    }
+
+    // Files that import a cycle without being part of one come last, to be skipped
+    order.extend((0..files.len()).filter(|&index| waiting_on[index] > 0 && !in_cycle[index]));
+    (order, cycles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_is_built_in_dependency_order() {
+        let root = std::env::temp_dir().join(format!("albayan_build_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("geometry")).unwrap();
+        std::fs::write(root.join("main.ab"), "using geometry::shapes;\nusing std::io;\nfn main() -> int { return 0; }\n").unwrap();
+        std::fs::write(root.join("geometry/shapes.ab"), "using util;\nfn area(w: int, h: int) -> int { return w * h; }\n").unwrap();
+        std::fs::write(root.join("util.ab"), "fn twice(x: int) -> int { return x * 2; }\n").unwrap();
+        std::fs::write(root.join("broken.ab"), "fn broken() -> int { return missing; }\n").unwrap();
+        std::fs::write(root.join("uses_broken.ab"), "using broken;\nfn main() {}\n").unwrap();
+        std::fs::write(root.join("a.ab"), "using b;\n").unwrap();
+        std::fs::write(root.join("b.ab"), "using a;\n").unwrap();
+
+        let report = Compiler::new().compile_project(&root).unwrap();
+        let modules: Vec<&str> = report.files.iter().map(|file| file.module.as_str()).collect();
+        let position = |module: &str| modules.iter().position(|name| *name == module).unwrap();
+        assert!(position("util") < position("geometry::shapes"), "{:?}", modules);
+        assert!(position("geometry::shapes") < position("main"), "{:?}", modules);
+        assert_eq!(report.file("main").unwrap().dependencies, vec!["geometry::shapes"]);
+
+        let main = report.file("main").unwrap();
+        assert_eq!(main.status, FileStatus::Compiled);
+        assert!(main.artifact.as_ref().unwrap().ends_with("target/albayan/main.o"));
+        assert!(main.artifact.as_ref().unwrap().exists());
+
+        assert_eq!(report.file("broken").unwrap().status, FileStatus::Failed);
+        assert_eq!(report.file("broken").unwrap().diagnostics[0].code, "AB0201");
+        assert_eq!(
+            report.file("uses_broken").unwrap().status,
+            FileStatus::Skipped { dependency: "broken".to_string() }
+        );
+        assert_eq!(report.cycles, vec![vec!["a".to_string(), "b".to_string()]]);
+        assert_eq!(report.file("b").unwrap().diagnostics[0].code, "AB0700");
+        assert!(!report.succeeded());
+        assert!(report.to_string().contains("import cycle: a -> b -> a"), "{}", report);
+
+        let checked = Compiler::new().check_project(&root).unwrap();
+        assert!(checked.file("util").unwrap().artifact.is_none());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_unchanged_modules_come_from_the_cache() {
+        let root = std::env::temp_dir().join(format!("albayan_build_cache_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("main.ab"), "using util;\nfn main() -> int { return 0; }\n").unwrap();
+        std::fs::write(root.join("util.ab"), "fn twice(x: int) -> int { return x * 2; }\n").unwrap();
+        std::fs::write(root.join("other.ab"), "fn other() -> int { return 1; }\n").unwrap();
+        std::fs::write(root.join("broken.ab"), "fn broken() -> int { return missing; }\n").unwrap();
+
+        let compiler = Compiler::with_options(crate::CompilerOptions {
+            cache_dir: Some(root.join("cache")),
+            ..Default::default()
+        });
+        let cached = |report: &BuildReport, module: &str| report.file(module).unwrap().cached;
+        let first = compiler.compile_project(&root).unwrap();
+        assert!(!cached(&first, "main") && !cached(&first, "util"));
+
+        let second = compiler.compile_project(&root).unwrap();
+        assert!(cached(&second, "main") && cached(&second, "util") && cached(&second, "other"));
+        assert_eq!(second.file("main").unwrap().status, FileStatus::Compiled);
+        assert_eq!(second.file("broken").unwrap().status, FileStatus::Failed);
+        assert!(second.to_string().contains("ok (cached)"), "{}", second);
+
+        // Changing a module recompiles it and the modules importing it
+        std::fs::write(root.join("util.ab"), "fn twice(x: int) -> int { return x + x; }\n").unwrap();
+        let third = compiler.compile_project(&root).unwrap();
+        assert!(!cached(&third, "util") && !cached(&third, "main"));
+        assert!(cached(&third, "other"));
+
+        // A check reuses the analyses the builds stored
+        let checked = compiler.check_project(&root).unwrap();
+        assert!(cached(&checked, "util") && cached(&checked, "main"));
+        assert!(checked.file("util").unwrap().artifact.is_none());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -3,6 +3,10 @@
 
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
+use crate::runtime::random::random_f64;
+
+/// الدقة العددية الافتراضية للمقارنات والتقريب
+pub const DEFAULT_PRECISION: f64 = 1e-6;
 
 /// عدد مركب للمعادلات المتقدمة
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -38,6 +42,12 @@ pub struct GeneralShapeEquation {
     pub global_parameters: GlobalParameters,
     pub adaptation_history: Vec<AdaptationEvent>,
     pub performance_metrics: PerformanceMetrics,
+    #[serde(default)]
+    pub online_config: OnlineAdaptationConfig,
+    #[serde(default)]
+    pub history_summary: HistorySummary,
+    #[serde(default)]
+    pub drift_detector: DriftDetector,
 }
 
 /// المعاملات العامة
@@ -48,6 +58,12 @@ pub struct GlobalParameters {
     pub convergence_threshold: f64,
     pub max_iterations: u32,
     pub learning_rate: f64,
+    #[serde(default = "default_precision")]
+    pub precision: f64,               // التسامح العددي في المقارنات وفحص التقارب
+}
+
+fn default_precision() -> f64 {
+    DEFAULT_PRECISION
 }
 
 /// حدث تكيف
@@ -99,6 +115,63 @@ pub enum OptimizationStrategy {
     FastConvergence,   // تقارب سريع
 }
 
+/// إعدادات التكيف المستمر (التعلم عينة بعينة)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OnlineAdaptationConfig {
+    pub history_limit: usize,            // الحد الأقصى لسجل التكيف
+    pub drift_delta: f64,                // التسامح في اختبار Page-Hinkley
+    pub drift_threshold: f64,            // عتبة اكتشاف الانجراف
+    pub min_samples_for_drift: u64,      // أقل عدد عينات قبل فحص الانجراف
+    pub max_sigmoid_components: usize,   // الحد الأقصى لمكونات السيغمويد
+    pub max_linear_components: usize,    // الحد الأقصى للمكونات الخطية
+    pub prune_threshold: f64,            // حذف المكونات ذات التأثير الأقل من هذه القيمة
+    pub error_smoothing: f64,            // معامل التنعيم الأسي لمتوسط الخطأ
+}
+
+/// ملخص إحصائي لكل العينات التي مرت بالمعادلة (حتى بعد حذفها من السجل)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistorySummary {
+    pub total_samples: u64,
+    pub evicted_events: u64,
+    pub mean_error: f64,
+    pub error_variance: f64,
+    pub mean_absolute_error: f64,
+    pub max_absolute_error: f64,
+    pub structure_changes: u64,
+    pub drift_events: u64,
+    #[serde(default)]
+    m2: f64,
+}
+
+/// كاشف الانجراف (اختبار Page-Hinkley على الخطأ المطلق)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DriftDetector {
+    pub samples: u64,
+    pub mean: f64,
+    pub cumulative: f64,
+    pub minimum: f64,
+}
+
+/// تغيير في بنية المعادلة أثناء التكيف المستمر
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StructureChange {
+    AddedSigmoid { x0: f64, alpha: f64 },
+    AddedLinear { intercept: f64 },
+    RemovedSigmoid(usize),
+    RemovedLinear(usize),
+}
+
+/// نتيجة تحديث المعادلة بعينة واحدة
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateResult {
+    pub predicted_output: f64,
+    pub error: f64,
+    pub smoothed_error: f64,
+    pub drift_detected: bool,
+    pub structure_changes: Vec<StructureChange>,
+}
+
 impl ComplexNumber {
     pub fn new(real: f64, imaginary: f64) -> Self {
         Self { real, imaginary }
@@ -245,9 +318,24 @@ impl GeneralShapeEquation {
             global_parameters: GlobalParameters::default(),
             adaptation_history: Vec::new(),
             performance_metrics: PerformanceMetrics::default(),
+            online_config: OnlineAdaptationConfig::default(),
+            history_summary: HistorySummary::default(),
+            drift_detector: DriftDetector::default(),
         }
     }
 
+    /// تعيين إعدادات التكيف المستمر
+    pub fn with_online_config(mut self, config: OnlineAdaptationConfig) -> Self {
+        self.online_config = config;
+        self
+    }
+
+    /// تعيين التسامح العددي للمقارنات وفحص التقارب
+    pub fn with_precision(mut self, precision: f64) -> Self {
+        self.global_parameters.precision = precision;
+        self
+    }
+
     /// إضافة مكون سيغمويد
     pub fn add_sigmoid_component(&mut self, alpha: f64, k_complex: ComplexNumber, x0: f64) -> &mut Self {
         self.sigmoid_components.push(GeneralizedSigmoidComponent::new(alpha, k_complex, x0));
@@ -297,7 +385,10 @@ impl GeneralShapeEquation {
 
     /// تدريب المعادلة على البيانات
     pub fn train(&mut self, training_data: Vec<(f64, f64)>, adaptation_type: AdaptationType) {
-        for epoch in 0..self.global_parameters.max_iterations {
+        let precision = self.global_parameters.precision;
+        let mut previous_mse = f64::INFINITY;
+
+        for _epoch in 0..self.global_parameters.max_iterations {
             let mut total_error = 0.0;
 
             for (input, target) in &training_data {
@@ -320,11 +411,99 @@ impl GeneralShapeEquation {
             let mse = total_error / training_data.len() as f64;
             self.performance_metrics.mean_squared_error = mse;
 
-            // فحص التقارب
-            if mse < self.global_parameters.convergence_threshold {
+            // فحص التقارب: بلوغ العتبة أو توقف تحسن الخطأ ضمن الدقة
+            if mse < self.global_parameters.convergence_threshold || approx_eq(mse, previous_mse, precision) {
                 break;
             }
+            previous_mse = mse;
+        }
+    }
+
+    /// تحديث المعادلة بعينة واحدة (تعلم مستمر)
+    pub fn update(&mut self, input: f64, target: f64, adaptation_type: AdaptationType) -> UpdateResult {
+        let predicted = self.evaluate(input).output_value;
+        let error = target - predicted;
+
+        match adaptation_type {
+            AdaptationType::Gradient => self.gradient_adaptation(input, target),
+            AdaptationType::Evolutionary => self.evolutionary_adaptation(input, target),
+            AdaptationType::Bayesian => self.bayesian_adaptation(input, target),
+            AdaptationType::Hybrid => self.hybrid_adaptation(input, target),
+        }
+        self.record_adaptation_event(input, target, predicted, error);
+
+        // متوسط الخطأ التربيعي المتحرك
+        let smoothing = self.online_config.error_smoothing;
+        let squared_error = error * error;
+        self.performance_metrics.mean_squared_error = if self.performance_metrics.mean_squared_error.is_finite() {
+            (1.0 - smoothing) * self.performance_metrics.mean_squared_error + smoothing * squared_error
+        } else {
+            squared_error
+        };
+
+        let drift_detected = self.drift_detector.observe(error.abs(), &self.online_config);
+        let mut structure_changes = Vec::new();
+        if drift_detected {
+            self.history_summary.drift_events += 1;
+            structure_changes = self.adapt_structure(input, target);
+            self.drift_detector.reset();
+        }
+
+        UpdateResult {
+            predicted_output: predicted,
+            error,
+            smoothed_error: self.performance_metrics.mean_squared_error,
+            drift_detected,
+            structure_changes,
+        }
+    }
+
+    /// تكيف البنية عند اكتشاف الانجراف: إضافة مكون للخطأ المتبقي وحذف المكونات الخاملة
+    pub fn adapt_structure(&mut self, input: f64, target: f64) -> Vec<StructureChange> {
+        let mut changes = Vec::new();
+        let scaling = self.global_parameters.overall_scaling;
+        let residual = if !approx_eq(scaling, 0.0, self.global_parameters.precision) {
+            (target - self.evaluate(input).output_value) / scaling
+        } else {
+            0.0
+        };
+
+        // مكون السيغمويد ينتج قيماً موجبة فقط، والخطأ السالب يعالج بمكون خطي
+        if residual > 0.1 && self.sigmoid_components.len() < self.online_config.max_sigmoid_components {
+            self.add_sigmoid_component(residual * 2.0, ComplexNumber::new(1.0, 0.0), input);
+            changes.push(StructureChange::AddedSigmoid { x0: input, alpha: residual * 2.0 });
+        } else if residual.abs() > self.global_parameters.noise_tolerance
+            && self.linear_components.len() < self.online_config.max_linear_components
+        {
+            self.add_linear_component(0.0, residual, 1.0);
+            changes.push(StructureChange::AddedLinear { intercept: residual });
+        }
+
+        let threshold = self.online_config.prune_threshold;
+        for index in (0..self.sigmoid_components.len()).rev() {
+            let component = &self.sigmoid_components[index];
+            if self.component_count() > 1 && (component.alpha * component.cutting_factor).abs() < threshold {
+                self.sigmoid_components.remove(index);
+                changes.push(StructureChange::RemovedSigmoid(index));
+            }
+        }
+        for index in (0..self.linear_components.len()).rev() {
+            let component = &self.linear_components[index];
+            let magnitude = component.weight * (component.slope.abs() + component.intercept.abs());
+            if self.component_count() > 1 && magnitude.abs() < threshold {
+                self.linear_components.remove(index);
+                changes.push(StructureChange::RemovedLinear(index));
+            }
         }
+
+        self.history_summary.structure_changes += changes.len() as u64;
+        self.performance_metrics.complexity_score = self.component_count() as f64;
+        changes
+    }
+
+    /// عدد المكونات الكلي
+    pub fn component_count(&self) -> usize {
+        self.sigmoid_components.len() + self.linear_components.len()
     }
 
     /// تكيف بالتدرج
@@ -354,9 +533,9 @@ impl GeneralShapeEquation {
             let old_x0 = self.sigmoid_components[i].x0;
 
             // تطبيق الطفرة
-            self.sigmoid_components[i].alpha += (rand::random::<f64>() - 0.5) * mutation_strength;
-            self.sigmoid_components[i].k_complex.real += (rand::random::<f64>() - 0.5) * mutation_strength;
-            self.sigmoid_components[i].x0 += (rand::random::<f64>() - 0.5) * mutation_strength;
+            self.sigmoid_components[i].alpha += (random_f64() - 0.5) * mutation_strength;
+            self.sigmoid_components[i].k_complex.real += (random_f64() - 0.5) * mutation_strength;
+            self.sigmoid_components[i].x0 += (random_f64() - 0.5) * mutation_strength;
 
             // فحص إذا كانت الطفرة تحسن الأداء
             let new_error = (target - self.evaluate(input).output_value).abs();
@@ -387,7 +566,7 @@ impl GeneralShapeEquation {
         // دمج التدرج والتطوري
         self.gradient_adaptation(input, target);
 
-        if rand::random::<f64>() < 0.1 { // 10% احتمال للطفرة
+        if random_f64() < 0.1 { // 10% احتمال للطفرة
             self.evolutionary_adaptation(input, target);
         }
     }
@@ -433,6 +612,143 @@ impl GeneralShapeEquation {
         };
 
         self.adaptation_history.push(event);
+        self.history_summary.record(error);
+
+        // إبقاء السجل محدوداً - الإحصائيات محفوظة في الملخص
+        let limit = self.online_config.history_limit;
+        if self.adaptation_history.len() > limit {
+            let excess = self.adaptation_history.len() - limit;
+            self.adaptation_history.drain(0..excess);
+            self.history_summary.evicted_events += excess as u64;
+        }
+    }
+}
+
+impl EvaluationResult {
+    /// مقارنة نتيجتين ضمن التسامح العددي المعطى
+    pub fn approx_eq(&self, other: &EvaluationResult, precision: f64) -> bool {
+        approx_eq(self.output_value, other.output_value, precision)
+            && approx_eq(self.confidence_score, other.confidence_score, precision)
+            && self.component_contributions.len() == other.component_contributions.len()
+            && self.component_contributions.iter().all(|(name, value)| {
+                other.component_contributions.get(name)
+                    .is_some_and(|other_value| approx_eq(*value, *other_value, precision))
+            })
+            && self.gradient_info.len() == other.gradient_info.len()
+            && self.gradient_info.iter().zip(&other.gradient_info)
+                .all(|(value, other_value)| approx_eq(*value, *other_value, precision))
+    }
+
+    /// النتيجة كـ JSON بقيم مقربة إلى الدقة المعطاة
+    pub fn to_stable_json(&self, precision: f64) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or(serde_json::Value::Null);
+        stable_json(&mut value, precision);
+        value
+    }
+}
+
+/// تساوي عددين ضمن التسامح: مطلق للقيم الصغيرة ونسبي للكبيرة
+pub fn approx_eq(a: f64, b: f64, precision: f64) -> bool {
+    a == b || (a - b).abs() <= precision * 1.0f64.max(a.abs()).max(b.abs())
+}
+
+/// عدد الخانات العشرية التي تمثلها الدقة
+fn precision_decimals(precision: f64) -> usize {
+    if precision > 0.0 && precision.is_finite() {
+        (-precision.log10()).ceil().clamp(0.0, 17.0) as usize
+    } else {
+        17
+    }
+}
+
+/// تقريب القيمة إلى أقرب مضاعف للدقة، مع تحويل الصفر السالب إلى صفر
+pub fn round_to_precision(value: f64, precision: f64) -> f64 {
+    if !value.is_finite() || !(precision > 0.0 && precision.is_finite()) {
+        return value;
+    }
+    let snapped = (value / precision).round() * precision;
+    // المرور بالنص يزيل بقايا الضرب العشري مثل 0.30000000000000004
+    let rounded: f64 = format!("{:.*}", precision_decimals(precision), snapped).parse().unwrap_or(snapped);
+    rounded + 0.0
+}
+
+/// تنسيق ثابت للأعداد العشرية لا يعتمد على المنصة أو الإعدادات المحلية:
+/// نقطة عشرية دائماً، دون فواصل آلاف، ودون أصفار زائدة
+pub fn format_float(value: f64, precision: f64) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "inf".to_string() } else { "-inf".to_string() };
+    }
+    let text = format!("{:.*}", precision_decimals(precision), round_to_precision(value, precision));
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+/// تقريب كل الأعداد العشرية في قيمة JSON إلى الدقة المعطاة، حتى تكون
+/// الحالات المحفوظة والتقارير متطابقة بين المنصات
+pub fn stable_json(value: &mut serde_json::Value, precision: f64) {
+    match value {
+        serde_json::Value::Number(number) if number.is_f64() => {
+            let rounded = number.as_f64().map(|float| round_to_precision(float, precision));
+            if let Some(rounded) = rounded.and_then(serde_json::Number::from_f64) {
+                *number = rounded;
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|item| stable_json(item, precision)),
+        serde_json::Value::Object(fields) => fields.values_mut().for_each(|field| stable_json(field, precision)),
+        _ => {}
+    }
+}
+
+impl Default for OnlineAdaptationConfig {
+    fn default() -> Self {
+        Self {
+            history_limit: 1000,
+            drift_delta: 0.005,
+            drift_threshold: 5.0,
+            min_samples_for_drift: 30,
+            max_sigmoid_components: 8,
+            max_linear_components: 4,
+            prune_threshold: 1e-3,
+            error_smoothing: 0.05,
+        }
+    }
+}
+
+impl HistorySummary {
+    /// إضافة خطأ عينة واحدة إلى الإحصائيات (خوارزمية Welford)
+    pub fn record(&mut self, error: f64) {
+        self.total_samples += 1;
+        let n = self.total_samples as f64;
+        let delta = error - self.mean_error;
+        self.mean_error += delta / n;
+        self.m2 += delta * (error - self.mean_error);
+        self.error_variance = if self.total_samples > 1 { self.m2 / (n - 1.0) } else { 0.0 };
+        self.mean_absolute_error += (error.abs() - self.mean_absolute_error) / n;
+        self.max_absolute_error = self.max_absolute_error.max(error.abs());
+    }
+}
+
+impl DriftDetector {
+    /// إضافة خطأ مطلق جديد، وإرجاع true عند اكتشاف انجراف
+    pub fn observe(&mut self, absolute_error: f64, config: &OnlineAdaptationConfig) -> bool {
+        self.samples += 1;
+        self.mean += (absolute_error - self.mean) / self.samples as f64;
+        self.cumulative += absolute_error - self.mean - config.drift_delta;
+        self.minimum = self.minimum.min(self.cumulative);
+
+        self.samples >= config.min_samples_for_drift
+            && self.cumulative - self.minimum > config.drift_threshold
+    }
+
+    /// إعادة الكاشف بعد التكيف
+    pub fn reset(&mut self) {
+        *self = Self::default();
     }
 }
 
@@ -444,6 +760,7 @@ impl GlobalParameters {
             convergence_threshold: 0.001,
             max_iterations: 1000,
             learning_rate: 0.01,
+            precision: DEFAULT_PRECISION,
         }
     }
 }
@@ -460,19 +777,78 @@ impl PerformanceMetrics {
     }
 }
 
-// استخدام rand للطفرات العشوائية
-mod rand {
-    pub fn random<T>() -> T
-    where
-        T: From<f64>
-    {
-        // مولد أرقام عشوائية مبسط
-        let seed = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos() as u64;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_online_update_bounds_history() {
+        let mut equation = GeneralShapeEquation::new().with_online_config(OnlineAdaptationConfig {
+            history_limit: 20,
+            ..OnlineAdaptationConfig::default()
+        });
+        equation.add_linear_component(1.0, 0.0, 1.0);
+
+        for i in 0..100 {
+            let x = i as f64 / 100.0;
+            equation.update(x, 2.0 * x, AdaptationType::Gradient);
+        }
+
+        assert_eq!(equation.adaptation_history.len(), 20);
+        assert_eq!(equation.history_summary.total_samples, 100);
+        assert_eq!(equation.history_summary.evicted_events, 80);
+        assert!(equation.history_summary.max_absolute_error >= equation.history_summary.mean_absolute_error);
+    }
+
+    #[test]
+    fn test_drift_triggers_structure_adaptation() {
+        let mut equation = GeneralShapeEquation::new().with_online_config(OnlineAdaptationConfig {
+            min_samples_for_drift: 10,
+            drift_threshold: 2.0,
+            ..OnlineAdaptationConfig::default()
+        });
+        equation.add_linear_component(0.0, 0.0, 1.0);
+
+        // توزيع ثابت ثم قفزة مفاجئة في الهدف
+        for _ in 0..20 {
+            equation.update(0.5, 0.0, AdaptationType::Gradient);
+        }
+        let results: Vec<UpdateResult> = (0..20)
+            .map(|_| equation.update(0.5, 5.0, AdaptationType::Gradient))
+            .collect();
+
+        assert!(results.iter().any(|r| r.drift_detected));
+        assert!(equation.history_summary.structure_changes > 0);
+        assert!(equation.component_count() > 1);
+    }
+
+    #[test]
+    fn test_precision_comparisons_and_stable_formatting() {
+        assert!(approx_eq(0.1 + 0.2, 0.3, DEFAULT_PRECISION));
+        assert!(!approx_eq(0.3, 0.31, DEFAULT_PRECISION));
+        assert!(approx_eq(1e9, 1e9 + 1.0, DEFAULT_PRECISION));
+        assert_eq!(round_to_precision(0.1 + 0.2, 1e-6), 0.3);
+        assert_eq!(format_float(-0.0000001, 1e-6), "0");
+        assert_eq!(format_float(1234.5, 1e-3), "1234.5");
+        assert_eq!(format_float(2.0 / 3.0, 1e-4), "0.6667");
+        assert_eq!(format_float(f64::NEG_INFINITY, 1e-6), "-inf");
+
+        let mut equation = GeneralShapeEquation::new().with_precision(1e-3);
+        equation.add_linear_component(1.0, 0.0, 1.0);
+        let result = equation.evaluate(0.5);
+        let mut nudged = result.clone();
+        nudged.output_value += 1e-4;
+        assert!(result.approx_eq(&nudged, 1e-3));
+        assert!(!result.approx_eq(&nudged, 1e-6));
+        assert_eq!(nudged.to_stable_json(1e-3)["output_value"], serde_json::json!(0.5));
+    }
 
-        let random_value = ((seed.wrapping_mul(1103515245).wrapping_add(12345)) >> 16) as f64 / 32768.0;
-        T::from(random_value)
+    #[test]
+    fn test_training_stops_when_error_stalls_within_precision() {
+        let mut equation = GeneralShapeEquation::new().with_precision(1e-2);
+        equation.global_parameters.convergence_threshold = 0.0;
+        equation.add_linear_component(0.0, 0.0, 1.0);
+        equation.train(vec![(0.0, 1.0), (0.0, -1.0)], AdaptationType::Gradient);
+        assert!(equation.history_summary.total_samples < 2 * equation.global_parameters.max_iterations as u64);
     }
 }
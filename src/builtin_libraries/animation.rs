@@ -0,0 +1,337 @@
+// الرسوم المتحركة من معادلات الأشكال - تسلسل إطارات بتغيير المعاملات عبر الزمن
+// Animation Sequences from Parameterized Shape Equations
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use super::adaptive_equations::GeneralShapeEquation;
+use super::artistic_renderer::{ArtisticRenderer, RenderedImage};
+
+/// معامل قابل للتحريك في معادلة الشكل العام
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnimatedParameter {
+    SigmoidAlpha(usize),
+    SigmoidK(usize),
+    SigmoidX0(usize),
+    SigmoidCutting(usize),
+    LinearSlope(usize),
+    LinearIntercept(usize),
+    LinearWeight(usize),
+    OverallScaling,
+}
+
+/// دالة التوقيت بين بداية المدى ونهايته
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,     // تغير منتظم
+    EaseInOut,  // بطيء في البداية والنهاية
+    PingPong,   // ذهاب وإياب لحلقة سلسة
+}
+
+/// مدى تغير معامل واحد
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterRange {
+    pub parameter: AnimatedParameter,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// مواصفات الرسم المتحرك
+#[derive(Debug, Clone)]
+pub struct AnimationSpec {
+    pub ranges: Vec<ParameterRange>,
+    pub frames: usize,
+    pub frame_delay_ms: u32,
+    pub easing: Easing,
+}
+
+/// تسلسل الإطارات المرسومة
+#[derive(Debug, Clone)]
+pub struct AnimationSequence {
+    pub frames: Vec<RenderedImage>,
+    pub frame_delay_ms: u32,
+}
+
+impl AnimatedParameter {
+    /// تحليل اسم المعامل مثل "sigmoid_0.alpha" أو "linear_1.slope" أو "scaling"
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        let name = name.trim();
+        if name == "scaling" || name == "overall_scaling" {
+            return Ok(AnimatedParameter::OverallScaling);
+        }
+
+        let (component, field) = name.split_once('.')
+            .ok_or_else(|| format!("Invalid animated parameter '{}': expected 'component_N.field'", name))?;
+        let (kind, index) = component.rsplit_once('_')
+            .ok_or_else(|| format!("Invalid component '{}': expected 'sigmoid_N' or 'linear_N'", component))?;
+        let index: usize = index.parse()
+            .map_err(|_| format!("Invalid component index in '{}'", component))?;
+
+        match (kind, field) {
+            ("sigmoid", "alpha") => Ok(AnimatedParameter::SigmoidAlpha(index)),
+            ("sigmoid", "k") => Ok(AnimatedParameter::SigmoidK(index)),
+            ("sigmoid", "x0") => Ok(AnimatedParameter::SigmoidX0(index)),
+            ("sigmoid", "cutting_factor") => Ok(AnimatedParameter::SigmoidCutting(index)),
+            ("linear", "slope") => Ok(AnimatedParameter::LinearSlope(index)),
+            ("linear", "intercept") => Ok(AnimatedParameter::LinearIntercept(index)),
+            ("linear", "weight") => Ok(AnimatedParameter::LinearWeight(index)),
+            _ => Err(format!("Unknown animated parameter '{}'", name)),
+        }
+    }
+
+    /// تعيين قيمة المعامل في المعادلة
+    pub fn apply(&self, equation: &mut GeneralShapeEquation, value: f64) -> Result<(), String> {
+        let missing = |kind: &str, index: usize| format!("Equation has no {} component {}", kind, index);
+
+        match *self {
+            AnimatedParameter::SigmoidAlpha(i) => {
+                equation.sigmoid_components.get_mut(i).ok_or_else(|| missing("sigmoid", i))?.alpha = value;
+            }
+            AnimatedParameter::SigmoidK(i) => {
+                equation.sigmoid_components.get_mut(i).ok_or_else(|| missing("sigmoid", i))?.k_complex.real = value;
+            }
+            AnimatedParameter::SigmoidX0(i) => {
+                equation.sigmoid_components.get_mut(i).ok_or_else(|| missing("sigmoid", i))?.x0 = value;
+            }
+            AnimatedParameter::SigmoidCutting(i) => {
+                equation.sigmoid_components.get_mut(i).ok_or_else(|| missing("sigmoid", i))?.cutting_factor = value;
+            }
+            AnimatedParameter::LinearSlope(i) => {
+                equation.linear_components.get_mut(i).ok_or_else(|| missing("linear", i))?.slope = value;
+            }
+            AnimatedParameter::LinearIntercept(i) => {
+                equation.linear_components.get_mut(i).ok_or_else(|| missing("linear", i))?.intercept = value;
+            }
+            AnimatedParameter::LinearWeight(i) => {
+                equation.linear_components.get_mut(i).ok_or_else(|| missing("linear", i))?.weight = value;
+            }
+            AnimatedParameter::OverallScaling => equation.global_parameters.overall_scaling = value,
+        }
+        Ok(())
+    }
+}
+
+impl Easing {
+    /// تحويل التقدم الخطي t ∈ [0, 1] حسب دالة التوقيت
+    pub fn apply(&self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::PingPong => 1.0 - (2.0 * t - 1.0).abs(),
+        }
+    }
+}
+
+impl ParameterRange {
+    /// تحليل مدى بالصيغة "sigmoid_0.alpha:0.5:2.0"
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = text.trim().split(':').collect();
+        if parts.len() != 3 {
+            return Err(format!("Invalid parameter range '{}': expected 'parameter:start:end'", text));
+        }
+        let start = parts[1].trim().parse::<f64>().map_err(|e| format!("Invalid start in '{}': {}", text, e))?;
+        let end = parts[2].trim().parse::<f64>().map_err(|e| format!("Invalid end in '{}': {}", text, e))?;
+
+        Ok(Self {
+            parameter: AnimatedParameter::from_name(parts[0])?,
+            start,
+            end,
+        })
+    }
+
+    pub fn value_at(&self, t: f64) -> f64 {
+        self.start + (self.end - self.start) * t
+    }
+}
+
+impl AnimationSpec {
+    pub fn new(ranges: Vec<ParameterRange>, frames: usize) -> Self {
+        Self {
+            ranges,
+            frames,
+            frame_delay_ms: 40,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// تحليل عدة مدايات مفصولة بـ ';'
+    pub fn parse(param_ranges: &str, frames: usize) -> Result<Self, String> {
+        let ranges = param_ranges.split(';')
+            .filter(|range| !range.trim().is_empty())
+            .map(ParameterRange::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::new(ranges, frames))
+    }
+
+    pub fn with_frame_delay(mut self, frame_delay_ms: u32) -> Self {
+        self.frame_delay_ms = frame_delay_ms;
+        self
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// التقدم الزمني للإطار (بعد تطبيق دالة التوقيت)
+    pub fn progress(&self, frame: usize) -> f64 {
+        let t = if self.frames > 1 { frame as f64 / (self.frames - 1) as f64 } else { 0.0 };
+        self.easing.apply(t)
+    }
+
+    /// المعادلة في إطار معين
+    pub fn equation_at(&self, base: &GeneralShapeEquation, frame: usize) -> Result<GeneralShapeEquation, String> {
+        let t = self.progress(frame);
+        let mut equation = base.clone();
+        for range in &self.ranges {
+            range.parameter.apply(&mut equation, range.value_at(t))?;
+        }
+        Ok(equation)
+    }
+}
+
+impl RenderedImage {
+    /// تحويل الصورة إلى مصفوفة RGBA متتالية
+    pub fn to_rgba_bytes(&self) -> Vec<u8> {
+        self.pixels.iter()
+            .flat_map(|row| row.iter())
+            .flat_map(|pixel| [pixel.red, pixel.green, pixel.blue, pixel.alpha])
+            .collect()
+    }
+}
+
+impl ArtisticRenderer {
+    /// رسم تسلسل إطارات بتغيير معاملات المعادلة عبر الزمن
+    pub fn animate(&self, equation: &GeneralShapeEquation, spec: &AnimationSpec) -> Result<AnimationSequence, String> {
+        if spec.frames == 0 {
+            return Err("Animation needs at least one frame".to_string());
+        }
+        if spec.ranges.is_empty() {
+            return Err("Animation needs at least one parameter range".to_string());
+        }
+
+        // الإطارات لا تمر عبر ذاكرة الأشكال المؤقتة حتى لا تمتلئ بصور وسيطة
+        let frames = (0..spec.frames)
+            .map(|frame| {
+                spec.equation_at(equation, frame)
+                    .map(|frame_equation| self.rendering_engine.render_equation(&frame_equation))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(AnimationSequence {
+            frames,
+            frame_delay_ms: spec.frame_delay_ms,
+        })
+    }
+}
+
+impl AnimationSequence {
+    /// الحفظ حسب امتداد الملف (gif أو mp4)
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+            Some("gif") => self.save_gif(path),
+            Some("mp4") => self.save_mp4(path),
+            other => Err(format!("Unsupported animation format: {:?} (use .gif or .mp4)", other.unwrap_or(""))),
+        }
+    }
+
+    /// تصدير صورة GIF متحركة متكررة
+    pub fn save_gif(&self, path: &Path) -> Result<(), String> {
+        use image::codecs::gif::{GifEncoder, Repeat};
+        use image::{Delay, Frame, RgbaImage};
+
+        let file = std::fs::File::create(path)
+            .map_err(|e| format!("Failed to create '{}': {}", path.display(), e))?;
+        let mut encoder = GifEncoder::new(file);
+        encoder.set_repeat(Repeat::Infinite)
+            .map_err(|e| format!("Failed to encode GIF: {}", e))?;
+
+        for frame in &self.frames {
+            let buffer = RgbaImage::from_raw(frame.width, frame.height, frame.to_rgba_bytes())
+                .ok_or_else(|| "Frame size does not match its pixel data".to_string())?;
+            let delay = Delay::from_numer_denom_ms(self.frame_delay_ms, 1);
+            encoder.encode_frame(Frame::from_parts(buffer, 0, 0, delay))
+                .map_err(|e| format!("Failed to encode GIF frame: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// تصدير فيديو MP4 عبر ffmpeg (يجب أن يكون مثبتاً في PATH)
+    pub fn save_mp4(&self, path: &Path) -> Result<(), String> {
+        let first = self.frames.first().ok_or_else(|| "Animation has no frames".to_string())?;
+        let fps = 1000.0 / self.frame_delay_ms.max(1) as f64;
+
+        let mut child = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &format!("{}x{}", first.width, first.height)])
+            .args(["-r", &format!("{}", fps), "-i", "-"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+            // libx264 يتطلب أبعاداً زوجية
+            .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start ffmpeg for MP4 export: {}", e))?;
+
+        {
+            let stdin = child.stdin.as_mut().ok_or_else(|| "Failed to open ffmpeg stdin".to_string())?;
+            for frame in &self.frames {
+                stdin.write_all(&frame.to_rgba_bytes())
+                    .map_err(|e| format!("Failed to stream frame to ffmpeg: {}", e))?;
+            }
+        }
+
+        let output = child.wait_with_output()
+            .map_err(|e| format!("ffmpeg did not finish: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("ffmpeg failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_libraries::{ComplexNumber, RenderingEngine};
+
+    #[test]
+    fn test_parse_ranges_and_interpolate() {
+        let spec = AnimationSpec::parse("sigmoid_0.alpha:0.5:2.0; scaling:1:3", 4).unwrap();
+        assert_eq!(spec.ranges[0].parameter, AnimatedParameter::SigmoidAlpha(0));
+        assert_eq!(spec.ranges[1].parameter, AnimatedParameter::OverallScaling);
+
+        let mut equation = GeneralShapeEquation::new();
+        equation.add_sigmoid_component(1.0, ComplexNumber::new(1.0, 0.0), 0.0);
+        let last = spec.equation_at(&equation, 3).unwrap();
+        assert_eq!(last.sigmoid_components[0].alpha, 2.0);
+        assert_eq!(last.global_parameters.overall_scaling, 3.0);
+
+        assert!(AnimationSpec::parse("linear_0.alpha:0:1", 2).is_err());
+        assert!(spec.with_easing(Easing::PingPong).progress(3).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_animate_and_export_gif() {
+        let mut renderer = ArtisticRenderer::new();
+        renderer.rendering_engine = RenderingEngine::new(16, 16);
+
+        let mut equation = GeneralShapeEquation::new();
+        equation.add_linear_component(0.0, 0.0, 1.0);
+        let spec = AnimationSpec::parse("linear_0.intercept:-2:2", 3).unwrap();
+
+        let sequence = renderer.animate(&equation, &spec).unwrap();
+        assert_eq!(sequence.frames.len(), 3);
+        assert_eq!(sequence.frames[0].to_rgba_bytes().len(), 16 * 16 * 4);
+
+        let path = std::env::temp_dir().join(format!("albayan_animation_{}.gif", std::process::id()));
+        sequence.save(&path).unwrap();
+        assert!(std::fs::metadata(&path).unwrap().len() > 0);
+        let _ = std::fs::remove_file(&path);
+
+        let missing = AnimationSpec::parse("sigmoid_3.alpha:0:1", 2).unwrap();
+        assert!(renderer.animate(&equation, &missing).is_err());
+    }
+}
@@ -2,6 +2,7 @@
 // تحويل المعادلات إلى صور والعكس
 
 use std::collections::HashMap;
+use super::shape_constraints::{self, ConstrainedShape, ConstraintError, PropertyConstraint};
 use super::adaptive_equations::{GeneralShapeEquation, ComplexNumber, GeneralizedSigmoidComponent, LinearComponent};
 
 // ========== المعادلة الأم - Mother Equation ==========
@@ -97,6 +98,54 @@ pub enum BasicShape {
     Star,          // نجمة
 }
 
+impl BasicShape {
+    /// جميع الأشكال الأساسية بترتيب تعريفها
+    pub const ALL: [BasicShape; 28] = [
+        BasicShape::Cat, BasicShape::Dog, BasicShape::Human, BasicShape::Lion,
+        BasicShape::Bird, BasicShape::Fish, BasicShape::Tree, BasicShape::Flower,
+        BasicShape::Grass, BasicShape::Leaf, BasicShape::Circle, BasicShape::Square,
+        BasicShape::Triangle, BasicShape::Rectangle, BasicShape::Pentagon, BasicShape::Hexagon,
+        BasicShape::Paper, BasicShape::Blackboard, BasicShape::Pen, BasicShape::Book,
+        BasicShape::Table, BasicShape::Chair, BasicShape::Mountain, BasicShape::River,
+        BasicShape::Cloud, BasicShape::Sun, BasicShape::Moon, BasicShape::Star,
+    ];
+
+    /// تحويل اسم نصي إلى شكل أساسي
+    pub fn from_name(name: &str) -> Option<BasicShape> {
+        match name.trim().to_lowercase().as_str() {
+            "cat" => Some(BasicShape::Cat),
+            "dog" => Some(BasicShape::Dog),
+            "human" => Some(BasicShape::Human),
+            "lion" => Some(BasicShape::Lion),
+            "bird" => Some(BasicShape::Bird),
+            "fish" => Some(BasicShape::Fish),
+            "tree" => Some(BasicShape::Tree),
+            "flower" => Some(BasicShape::Flower),
+            "grass" => Some(BasicShape::Grass),
+            "leaf" => Some(BasicShape::Leaf),
+            "circle" => Some(BasicShape::Circle),
+            "square" => Some(BasicShape::Square),
+            "triangle" => Some(BasicShape::Triangle),
+            "rectangle" => Some(BasicShape::Rectangle),
+            "pentagon" => Some(BasicShape::Pentagon),
+            "hexagon" => Some(BasicShape::Hexagon),
+            "paper" => Some(BasicShape::Paper),
+            "blackboard" => Some(BasicShape::Blackboard),
+            "pen" => Some(BasicShape::Pen),
+            "book" => Some(BasicShape::Book),
+            "table" => Some(BasicShape::Table),
+            "chair" => Some(BasicShape::Chair),
+            "mountain" => Some(BasicShape::Mountain),
+            "river" => Some(BasicShape::River),
+            "cloud" => Some(BasicShape::Cloud),
+            "sun" => Some(BasicShape::Sun),
+            "moon" => Some(BasicShape::Moon),
+            "star" => Some(BasicShape::Star),
+            _ => None,
+        }
+    }
+}
+
 // ========== خصائص الأشكال - Shape Properties ==========
 #[derive(Debug, Clone)]
 pub enum ShapeProperty {
@@ -124,6 +173,71 @@ pub enum ShapeProperty {
     Material(String),     // "wood", "metal", "plastic", "fabric"
 }
 
+impl ShapeProperty {
+    /// اسم المتغير كما يظهر في لغة البيان (مثل `ShapeProperty::Color`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShapeProperty::Position(_) => "Position",
+            ShapeProperty::Orientation(_) => "Orientation",
+            ShapeProperty::Movement(_) => "Movement",
+            ShapeProperty::Color(_) => "Color",
+            ShapeProperty::Brightness(_) => "Brightness",
+            ShapeProperty::Transparency(_) => "Transparency",
+            ShapeProperty::Texture(_) => "Texture",
+            ShapeProperty::Size(_) => "Size",
+            ShapeProperty::Width(_) => "Width",
+            ShapeProperty::Height(_) => "Height",
+            ShapeProperty::Depth(_) => "Depth",
+            ShapeProperty::Expression(_) => "Expression",
+            ShapeProperty::Style(_) => "Style",
+            ShapeProperty::Age(_) => "Age",
+            ShapeProperty::Material(_) => "Material",
+        }
+    }
+
+    /// هل قيمة الخاصية رقمية (وإلا فهي نصية)
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            ShapeProperty::Orientation(_)
+                | ShapeProperty::Brightness(_)
+                | ShapeProperty::Transparency(_)
+                | ShapeProperty::Size(_)
+                | ShapeProperty::Width(_)
+                | ShapeProperty::Height(_)
+                | ShapeProperty::Depth(_)
+        )
+    }
+
+    /// قيمة الخاصية الرقمية
+    pub fn numeric_value(&self) -> Option<f64> {
+        match self {
+            ShapeProperty::Orientation(value)
+            | ShapeProperty::Brightness(value)
+            | ShapeProperty::Transparency(value)
+            | ShapeProperty::Size(value)
+            | ShapeProperty::Width(value)
+            | ShapeProperty::Height(value)
+            | ShapeProperty::Depth(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// بناء خاصية رقمية من اسمها (الإنجليزي أو العربي، مثل `width` أو `عرض`)
+    pub fn numeric(name: &str, value: f64) -> Option<ShapeProperty> {
+        match name.trim().to_lowercase().as_str() {
+            "orientation" | "اتجاه" => Some(ShapeProperty::Orientation(value)),
+            "brightness" | "سطوع" => Some(ShapeProperty::Brightness(value)),
+            "transparency" | "شفافية" => Some(ShapeProperty::Transparency(value)),
+            "size" | "حجم" => Some(ShapeProperty::Size(value)),
+            "width" | "عرض" => Some(ShapeProperty::Width(value)),
+            "height" | "ارتفاع" => Some(ShapeProperty::Height(value)),
+            "depth" | "عمق" => Some(ShapeProperty::Depth(value)),
+            _ => None,
+        }
+    }
+}
+
 // ========== تحويل الخصائص - Property Transform ==========
 #[derive(Debug, Clone)]
 pub struct PropertyTransform {
@@ -566,4 +680,63 @@ impl ArtisticRenderer {
             None
         }
     }
+
+    /// حل القيود بين خصائص الأشكال: تُكمل خصائص كل شكل بالقيم التي تحققها
+    pub fn solve_constraints(
+        &self,
+        shapes: &mut [ConstrainedShape],
+        constraints: &[PropertyConstraint],
+    ) -> Result<usize, ConstraintError> {
+        shape_constraints::solve(shapes, constraints)
+    }
+
+    /// رسم أشكال بعد حل القيود بين خصائصها، بترتيبها
+    pub fn create_constrained_shapes(
+        &mut self,
+        mut shapes: Vec<ConstrainedShape>,
+        constraints: &[PropertyConstraint],
+    ) -> Result<Vec<(String, RenderedImage)>, ConstraintError> {
+        self.solve_constraints(&mut shapes, constraints)?;
+        shapes
+            .into_iter()
+            .map(|shape| {
+                let image = self
+                    .create_shape_with_properties(shape.shape, shape.properties)
+                    .ok_or_else(|| ConstraintError::UnknownShape(shape.id.clone()))?;
+                Ok((shape.id, image))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semantic::builtin_types::{BASIC_SHAPE_VARIANTS, SHAPE_PROPERTY_VARIANTS};
+
+    #[test]
+    fn test_semantic_analyzer_knows_every_variant() {
+        let shapes: Vec<String> = BasicShape::ALL.iter().map(|shape| format!("{:?}", shape)).collect();
+        assert_eq!(shapes, BASIC_SHAPE_VARIANTS);
+
+        let properties = [
+            ShapeProperty::Position(String::new()),
+            ShapeProperty::Orientation(0.0),
+            ShapeProperty::Movement(String::new()),
+            ShapeProperty::Color(String::new()),
+            ShapeProperty::Brightness(0.0),
+            ShapeProperty::Transparency(0.0),
+            ShapeProperty::Texture(String::new()),
+            ShapeProperty::Size(0.0),
+            ShapeProperty::Width(0.0),
+            ShapeProperty::Height(0.0),
+            ShapeProperty::Depth(0.0),
+            ShapeProperty::Expression(String::new()),
+            ShapeProperty::Style(String::new()),
+            ShapeProperty::Age(String::new()),
+            ShapeProperty::Material(String::new()),
+        ];
+        let properties: Vec<(&str, bool)> = properties.iter().map(|property| (property.name(), property.is_numeric())).collect();
+        assert_eq!(properties, SHAPE_PROPERTY_VARIANTS);
+    }
 }
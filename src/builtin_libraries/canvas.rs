@@ -0,0 +1,436 @@
+// لوحة العرض التفاعلية - بث الإطارات المرسومة إلى المحرر أو المتصفح
+// Interactive Canvas - streams rendered frames to an editor webview or a localhost page
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use super::artistic_renderer::RenderedImage;
+use super::shape_inference::InferenceResult;
+
+/// إصدار بروتوكول اللوحة
+pub const CANVAS_PROTOCOL_VERSION: u32 = 1;
+
+/// العنوان الافتراضي لخادم اللوحة المحلي
+pub const DEFAULT_CANVAS_ADDRESS: &str = "127.0.0.1:7878";
+
+/// مصدر الإطار
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameSource {
+    ArtisticRenderer,
+    ShapeInference,
+    Animation,
+}
+
+/// إطار مرسوم بترميز PNG (base64)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CanvasFrame {
+    pub id: u64,
+    pub label: String,
+    pub source: FrameSource,
+    pub width: u32,
+    pub height: u32,
+    pub encoding: String, // دائماً "png" في الإصدار 1
+    pub data: String,
+}
+
+/// رسائل بروتوكول اللوحة - سطر JSON واحد لكل رسالة
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CanvasMessage {
+    /// أول رسالة في كل اتصال
+    Hello { protocol_version: u32, session: String },
+    /// إطار جديد للعرض
+    Frame(CanvasFrame),
+    /// نتيجة استنباط الشكل المرتبطة بإطار معروض
+    Inference {
+        frame_id: u64,
+        predicted_shape: String,
+        confidence: f64,
+    },
+    /// مسح اللوحة
+    Clear,
+    /// انتهاء البرنامج - لن تصل إطارات أخرى
+    End,
+}
+
+/// وجهة لرسائل اللوحة
+pub trait CanvasSink: Send {
+    fn send(&mut self, message: &CanvasMessage) -> std::io::Result<()>;
+}
+
+/// كتابة الرسائل كأسطر JSON (NDJSON) إلى ملف أو مجرى
+pub struct JsonLinesSink<W: Write + Send> {
+    writer: W,
+}
+
+/// خادم HTTP محلي يبث الرسائل عبر Server-Sent Events ويقدم صفحة عرض
+pub struct HttpCanvasServer {
+    address: SocketAddr,
+    clients: Arc<Mutex<Vec<TcpStream>>>,
+    replay: Arc<Mutex<Vec<String>>>, // رسالة الترحيب وآخر إطار للمتصلين الجدد
+}
+
+/// اللوحة - توزع الرسائل على جميع الوجهات
+pub struct Canvas {
+    session: String,
+    sinks: Vec<Box<dyn CanvasSink>>,
+    next_frame_id: u64,
+}
+
+impl CanvasMessage {
+    /// ترميز الرسالة كسطر JSON
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl CanvasFrame {
+    /// ترميز صورة مرسومة كإطار PNG
+    pub fn from_image(id: u64, label: &str, source: FrameSource, image: &RenderedImage) -> Result<Self, String> {
+        Ok(Self {
+            id,
+            label: label.to_string(),
+            source,
+            width: image.width,
+            height: image.height,
+            encoding: "png".to_string(),
+            data: base64::engine::general_purpose::STANDARD.encode(image.to_png_bytes()?),
+        })
+    }
+}
+
+impl RenderedImage {
+    /// ترميز الصورة بصيغة PNG
+    pub fn to_png_bytes(&self) -> Result<Vec<u8>, String> {
+        use image::codecs::png::PngEncoder;
+        use image::{ExtendedColorType, ImageEncoder};
+
+        let mut bytes = Vec::new();
+        PngEncoder::new(&mut bytes)
+            .write_image(&self.to_rgba_bytes(), self.width, self.height, ExtendedColorType::Rgba8)
+            .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+        Ok(bytes)
+    }
+}
+
+impl<W: Write + Send> JsonLinesSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write + Send> CanvasSink for JsonLinesSink<W> {
+    fn send(&mut self, message: &CanvasMessage) -> std::io::Result<()> {
+        writeln!(self.writer, "{}", message.to_json_line())?;
+        self.writer.flush()
+    }
+}
+
+impl HttpCanvasServer {
+    /// ربط الخادم بعنوان محلي وبدء قبول الاتصالات في خيط منفصل
+    pub fn bind(address: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let server = Self {
+            address: listener.local_addr()?,
+            clients: Arc::new(Mutex::new(Vec::new())),
+            replay: Arc::new(Mutex::new(Vec::new())),
+        };
+
+        let clients = Arc::clone(&server.clients);
+        let replay = Arc::clone(&server.replay);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let clients = Arc::clone(&clients);
+                let replay = Arc::clone(&replay);
+                thread::spawn(move || {
+                    let _ = Self::handle_connection(stream, &clients, &replay);
+                });
+            }
+        });
+
+        Ok(server)
+    }
+
+    /// العنوان الفعلي (مفيد عند الربط بالمنفذ 0)
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// رابط صفحة العرض
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.address)
+    }
+
+    fn handle_connection(stream: TcpStream,
+                         clients: &Mutex<Vec<TcpStream>>,
+                         replay: &Mutex<Vec<String>>) -> std::io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        // تجاهل بقية الترويسات
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+
+        let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+        let mut stream = stream;
+        match path {
+            "/events" => {
+                stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+                                   Cache-Control: no-cache\r\nAccess-Control-Allow-Origin: *\r\n\r\n")?;
+                for line in replay.lock().unwrap_or_else(|p| p.into_inner()).iter() {
+                    write!(stream, "data: {}\n\n", line)?;
+                }
+                stream.flush()?;
+                clients.lock().unwrap_or_else(|p| p.into_inner()).push(stream);
+            }
+            "/" | "/index.html" => {
+                write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n\
+                                Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                       CANVAS_PAGE.len(), CANVAS_PAGE)?;
+            }
+            _ => {
+                stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CanvasSink for HttpCanvasServer {
+    fn send(&mut self, message: &CanvasMessage) -> std::io::Result<()> {
+        let line = message.to_json_line();
+
+        {
+            let mut replay = self.replay.lock().unwrap_or_else(|p| p.into_inner());
+            match message {
+                CanvasMessage::Hello { .. } => *replay = vec![line.clone()],
+                CanvasMessage::Frame(_) | CanvasMessage::Clear => replay.truncate(1),
+                _ => {}
+            }
+            if !matches!(message, CanvasMessage::Hello { .. } | CanvasMessage::Clear) {
+                replay.push(line.clone());
+            }
+        }
+
+        // المتصفحات المغلقة تُحذف عند أول فشل في الكتابة
+        let event = format!("data: {}\n\n", line);
+        self.clients.lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .retain_mut(|client| client.write_all(event.as_bytes()).and_then(|_| client.flush()).is_ok());
+        Ok(())
+    }
+}
+
+impl Canvas {
+    /// إنشاء لوحة بلا وجهات
+    pub fn new(session: &str) -> Self {
+        Self {
+            session: session.to_string(),
+            sinks: Vec::new(),
+            next_frame_id: 1,
+        }
+    }
+
+    /// إضافة وجهة وإرسال رسالة الترحيب إليها
+    pub fn with_sink(mut self, mut sink: Box<dyn CanvasSink>) -> Self {
+        let hello = CanvasMessage::Hello {
+            protocol_version: CANVAS_PROTOCOL_VERSION,
+            session: self.session.clone(),
+        };
+        if sink.send(&hello).is_ok() {
+            self.sinks.push(sink);
+        }
+        self
+    }
+
+    /// إنشاء لوحة من وصف الوجهة:
+    /// "http" أو "http://host:port" أو "host:port" لخادم محلي، "stdout"/"stderr"، أو مسار ملف NDJSON
+    pub fn from_target(target: &str, session: &str) -> Result<(Self, String), String> {
+        let target = target.trim();
+        let (sink, description): (Box<dyn CanvasSink>, String) = match target {
+            "" | "http" => Self::http_sink(DEFAULT_CANVAS_ADDRESS)?,
+            "stdout" | "-" => (Box::new(JsonLinesSink::new(std::io::stdout())), "stdout".to_string()),
+            "stderr" => (Box::new(JsonLinesSink::new(std::io::stderr())), "stderr".to_string()),
+            _ if target.starts_with("http://") => {
+                Self::http_sink(target.trim_start_matches("http://").trim_end_matches('/'))?
+            }
+            _ if target.parse::<SocketAddr>().is_ok() => Self::http_sink(target)?,
+            _ => {
+                let file = std::fs::File::create(target)
+                    .map_err(|e| format!("Failed to create canvas stream '{}': {}", target, e))?;
+                (Box::new(JsonLinesSink::new(file)), target.to_string())
+            }
+        };
+        Ok((Self::new(session).with_sink(sink), description))
+    }
+
+    fn http_sink(address: &str) -> Result<(Box<dyn CanvasSink>, String), String> {
+        let server = HttpCanvasServer::bind(address)
+            .map_err(|e| format!("Failed to start canvas server on {}: {}", address, e))?;
+        let url = server.url();
+        Ok((Box::new(server), url))
+    }
+
+    /// عدد الوجهات النشطة
+    pub fn sink_count(&self) -> usize {
+        self.sinks.len()
+    }
+
+    /// إرسال رسالة إلى جميع الوجهات، مع حذف الوجهات المعطلة
+    pub fn broadcast(&mut self, message: &CanvasMessage) {
+        self.sinks.retain_mut(|sink| sink.send(message).is_ok());
+    }
+
+    /// عرض صورة مرسومة وإرجاع رقم الإطار
+    pub fn show(&mut self, label: &str, source: FrameSource, image: &RenderedImage) -> Result<u64, String> {
+        let id = self.next_frame_id;
+        let frame = CanvasFrame::from_image(id, label, source, image)?;
+        self.next_frame_id += 1;
+        self.broadcast(&CanvasMessage::Frame(frame));
+        Ok(id)
+    }
+
+    /// عرض صورة مع نتيجة استنباط الشكل منها
+    pub fn show_inference(&mut self, label: &str, image: &RenderedImage, result: &InferenceResult) -> Result<u64, String> {
+        let frame_id = self.show(label, FrameSource::ShapeInference, image)?;
+        self.broadcast(&CanvasMessage::Inference {
+            frame_id,
+            predicted_shape: format!("{:?}", result.predicted_shape).to_lowercase(),
+            confidence: result.confidence,
+        });
+        Ok(frame_id)
+    }
+
+    /// مسح اللوحة
+    pub fn clear(&mut self) {
+        self.broadcast(&CanvasMessage::Clear);
+    }
+
+    /// إعلام الوجهات بانتهاء البث
+    pub fn finish(&mut self) {
+        self.broadcast(&CanvasMessage::End);
+    }
+}
+
+/// صفحة العرض التي يقدمها الخادم المحلي
+pub const CANVAS_PAGE: &str = r##"<!DOCTYPE html>
+<html lang="ar" dir="rtl">
+<head>
+<meta charset="utf-8">
+<title>لوحة البيان - AlBayan Canvas</title>
+<style>
+  body { font-family: sans-serif; background: #1e1e1e; color: #ddd; margin: 20px; }
+  #frame { image-rendering: pixelated; background: #fff; max-width: 100%; border: 1px solid #444; }
+  #status, #inference { margin: 8px 0; }
+</style>
+</head>
+<body>
+<h2>لوحة البيان - AlBayan Canvas</h2>
+<div id="status">في انتظار البرنامج... (waiting for program)</div>
+<img id="frame" alt="">
+<div id="inference"></div>
+<script>
+  const events = new EventSource("/events");
+  const status = document.getElementById("status");
+  const frame = document.getElementById("frame");
+  const inference = document.getElementById("inference");
+  events.onmessage = (event) => {
+    const message = JSON.parse(event.data);
+    if (message.type === "hello") {
+      status.textContent = "متصل (connected): " + message.session;
+    } else if (message.type === "frame") {
+      frame.src = "data:image/png;base64," + message.data;
+      status.textContent = "#" + message.id + " " + message.label + " (" + message.source + ")";
+      inference.textContent = "";
+    } else if (message.type === "inference") {
+      inference.textContent = message.predicted_shape + " - " + (message.confidence * 100).toFixed(1) + "%";
+    } else if (message.type === "clear") {
+      frame.removeAttribute("src");
+      inference.textContent = "";
+    } else if (message.type === "end") {
+      status.textContent += " - انتهى (finished)";
+      events.close();
+    }
+  };
+</script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_libraries::{ArtisticRenderer, BasicShape};
+    use std::io::Read;
+
+    struct RecordingSink(Arc<Mutex<Vec<CanvasMessage>>>);
+
+    impl CanvasSink for RecordingSink {
+        fn send(&mut self, message: &CanvasMessage) -> std::io::Result<()> {
+            self.0.lock().unwrap().push(message.clone());
+            Ok(())
+        }
+    }
+
+    fn circle() -> RenderedImage {
+        let mut renderer = ArtisticRenderer::new();
+        renderer.create_shape_with_properties(BasicShape::Circle, Vec::new()).unwrap()
+    }
+
+    #[test]
+    fn test_canvas_protocol_messages() {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let mut canvas = Canvas::new("test").with_sink(Box::new(RecordingSink(Arc::clone(&messages))));
+
+        let id = canvas.show("circle", FrameSource::ArtisticRenderer, &circle()).unwrap();
+        canvas.finish();
+
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(messages[0], CanvasMessage::Hello { protocol_version: CANVAS_PROTOCOL_VERSION, .. }));
+        let CanvasMessage::Frame(frame) = &messages[1] else {
+            panic!("expected a frame");
+        };
+        assert_eq!(frame.id, id);
+        let png = base64::engine::general_purpose::STANDARD.decode(&frame.data).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+        assert_eq!(messages[2], CanvasMessage::End);
+
+        // سطر JSON يحمل نوع الرسالة ويمكن قراءته مجدداً
+        let line = messages[1].to_json_line();
+        assert!(line.starts_with(r#"{"type":"frame""#));
+        assert_eq!(serde_json::from_str::<CanvasMessage>(&line).unwrap(), messages[1]);
+    }
+
+    #[test]
+    fn test_http_server_streams_events() {
+        let server = HttpCanvasServer::bind("127.0.0.1:0").unwrap();
+        let address = server.address();
+        let mut canvas = Canvas::new("http-test").with_sink(Box::new(server));
+        canvas.show("circle", FrameSource::ArtisticRenderer, &circle()).unwrap();
+
+        // المتصل المتأخر يستقبل رسالة الترحيب وآخر إطار
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        stream.set_read_timeout(Some(std::time::Duration::from_secs(5))).unwrap();
+
+        let mut received = String::new();
+        let mut buffer = [0u8; 4096];
+        while received.matches("data: ").count() < 2 {
+            let read = stream.read(&mut buffer).unwrap();
+            assert!(read > 0, "server closed the event stream");
+            received.push_str(&String::from_utf8_lossy(&buffer[..read]));
+        }
+        assert!(received.starts_with("HTTP/1.1 200 OK"));
+        assert!(received.contains(r#""type":"hello""#));
+        assert!(received.contains(r#""type":"frame""#));
+    }
+}
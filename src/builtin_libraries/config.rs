@@ -0,0 +1,326 @@
+// تحميل ملفات الإعدادات إلى هياكل البيان مع التحقق من المخطط
+// Config Loading - TOML/JSON files mapped onto AlBayan structs with schema validation
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::parser::ast::{Item, Program, StructDecl, Type};
+use crate::{Lexer, Parser};
+
+/// صيغة ملف الإعدادات
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// تحديد الصيغة من امتداد الملف
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Some(ConfigFormat::Json),
+            Some("toml") => Some(ConfigFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// نوع قيمة في مخطط الإعدادات
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigType {
+    Int,
+    Float,
+    Bool,
+    String,
+    List(Box<ConfigType>),
+    /// حقل اختياري: يمكن حذفه أو إعطاؤه القيمة null
+    Optional(Box<ConfigType>),
+    Struct(ConfigSchema),
+}
+
+impl fmt::Display for ConfigType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigType::Int => write!(f, "int"),
+            ConfigType::Float => write!(f, "float"),
+            ConfigType::Bool => write!(f, "bool"),
+            ConfigType::String => write!(f, "string"),
+            ConfigType::List(element) => write!(f, "list<{}>", element),
+            ConfigType::Optional(inner) => write!(f, "Option<{}>", inner),
+            ConfigType::Struct(schema) => write!(f, "{}", schema.name),
+        }
+    }
+}
+
+/// حقل في مخطط الإعدادات
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigField {
+    pub name: String,
+    pub field_type: ConfigType,
+}
+
+/// مخطط الإعدادات: حقول الهيكل المطلوب ملؤه
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigSchema {
+    pub name: String,
+    pub fields: Vec<ConfigField>,
+}
+
+/// مشكلة في مفتاح واحد من ملف الإعدادات
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigIssue {
+    /// مفتاح مطلوب غير موجود
+    Missing { path: String, expected: String },
+    /// مفتاح قيمته من نوع خاطئ
+    Mistyped { path: String, expected: String, found: String },
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigIssue::Missing { path, expected } => write!(f, "missing key `{}` ({})", path, expected),
+            ConfigIssue::Mistyped { path, expected, found } => {
+                write!(f, "key `{}` should be {}, found {}", path, expected, found)
+            }
+        }
+    }
+}
+
+/// أخطاء تحميل الإعدادات
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Cannot read config file '{path}': {message}")]
+    Io { path: String, message: String },
+
+    #[error("Invalid {format:?} config: {message}")]
+    Parse { format: ConfigFormat, message: String },
+
+    #[error("Invalid config schema: {0}")]
+    Schema(String),
+
+    #[error("Config does not match `{schema}`:\n{}", .issues.iter().map(|issue| format!("  - {}", issue)).collect::<Vec<_>>().join("\n"))]
+    Invalid { schema: String, issues: Vec<ConfigIssue> },
+}
+
+impl ConfigSchema {
+    /// إنشاء مخطط فارغ
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), fields: Vec::new() }
+    }
+
+    /// إضافة حقل إلى المخطط
+    pub fn with_field(mut self, name: &str, field_type: ConfigType) -> Self {
+        self.fields.push(ConfigField { name: name.to_string(), field_type });
+        self
+    }
+
+    /// بناء المخطط تلقائياً من تعريف هيكل في برنامج البيان
+    /// (الهياكل المتداخلة تُحل من نفس البرنامج)
+    pub fn from_program(program: &Program, struct_name: &str) -> Result<Self, ConfigError> {
+        let structs: Vec<&StructDecl> = program.items.iter()
+            .filter_map(|item| match item {
+                Item::Struct(decl) => Some(decl),
+                _ => None,
+            })
+            .collect();
+        Self::from_struct(&structs, struct_name, &mut HashSet::new()).map_err(ConfigError::Schema)
+    }
+
+    /// بناء المخطط من شيفرة البيان التي تعرّف الهيكل
+    pub fn from_source(source: &str, struct_name: &str) -> Result<Self, ConfigError> {
+        let tokens = Lexer::new(source).tokenize()
+            .map_err(|e| ConfigError::Schema(e.to_string()))?;
+        let program = Parser::new(tokens).parse()
+            .map_err(|e| ConfigError::Schema(e.to_string()))?;
+        Self::from_program(&program, struct_name)
+    }
+
+    fn from_struct(structs: &[&StructDecl], name: &str, visiting: &mut HashSet<String>) -> Result<Self, String> {
+        let decl = structs.iter().find(|decl| decl.name == name)
+            .ok_or_else(|| format!("struct `{}` is not defined", name))?;
+        if !visiting.insert(name.to_string()) {
+            return Err(format!("struct `{}` contains itself", name));
+        }
+
+        let mut schema = Self::new(name);
+        for field in &decl.fields {
+            let field_type = Self::config_type(structs, &field.field_type, visiting)
+                .map_err(|e| format!("field `{}.{}`: {}", name, field.name, e))?;
+            schema = schema.with_field(&field.name, field_type);
+        }
+        visiting.remove(name);
+        Ok(schema)
+    }
+
+    /// ربط نوع حقل في البيان بنوع في مخطط الإعدادات
+    fn config_type(structs: &[&StructDecl], ty: &Type, visiting: &mut HashSet<String>) -> Result<ConfigType, String> {
+        match ty {
+            Type::Named(path) => match path.to_string().as_str() {
+                "int" | "i32" | "i64" | "u32" | "u64" | "usize" => Ok(ConfigType::Int),
+                "float" | "f32" | "f64" => Ok(ConfigType::Float),
+                "bool" => Ok(ConfigType::Bool),
+                "string" | "str" | "String" | "char" => Ok(ConfigType::String),
+                other => Self::from_struct(structs, other, visiting).map(ConfigType::Struct),
+            },
+            Type::Generic(path, args) if args.len() == 1 => {
+                let inner = Box::new(Self::config_type(structs, &args[0], visiting)?);
+                match path.to_string().as_str() {
+                    "list" | "List" | "Vec" => Ok(ConfigType::List(inner)),
+                    "Option" => Ok(ConfigType::Optional(inner)),
+                    other => Err(format!("unsupported config type `{}`", other)),
+                }
+            }
+            Type::Array(element, _) => Ok(ConfigType::List(Box::new(Self::config_type(structs, element, visiting)?))),
+            Type::Optional(inner) => Ok(ConfigType::Optional(Box::new(Self::config_type(structs, inner, visiting)?))),
+            other => Err(format!("unsupported config type {:?}", other)),
+        }
+    }
+
+    /// التحقق من قيمة الإعدادات وإرجاعها بعد التطبيع (الأعداد الصحيحة في حقول float تصبح عشرية)
+    /// تُجمع كل المشاكل بدلاً من التوقف عند أولها
+    pub fn validate(&self, value: &Value) -> Result<Value, ConfigError> {
+        let mut issues = Vec::new();
+        let normalized = check_struct(self, value, "", &mut issues);
+        if issues.is_empty() {
+            Ok(normalized)
+        } else {
+            Err(ConfigError::Invalid { schema: self.name.clone(), issues })
+        }
+    }
+
+    /// تحليل نص إعدادات بصيغة معينة والتحقق منه
+    pub fn parse(&self, source: &str, format: ConfigFormat) -> Result<Value, ConfigError> {
+        let value: Value = match format {
+            ConfigFormat::Toml => toml::from_str(source)
+                .map_err(|e| ConfigError::Parse { format, message: e.to_string() })?,
+            ConfigFormat::Json => serde_json::from_str(source)
+                .map_err(|e| ConfigError::Parse { format, message: e.to_string() })?,
+        };
+        self.validate(&value)
+    }
+
+    /// تحميل ملف إعدادات JSON أو TOML حسب الامتداد والتحقق منه
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> Result<Value, ConfigError> {
+        let path = path.as_ref();
+        let io_error = |message: String| ConfigError::Io { path: path.display().to_string(), message };
+        let format = ConfigFormat::from_path(path)
+            .ok_or_else(|| io_error("unsupported format, expected .json or .toml".to_string()))?;
+        let source = std::fs::read_to_string(path).map_err(|e| io_error(e.to_string()))?;
+        self.parse(&source, format)
+    }
+}
+
+fn check_struct(schema: &ConfigSchema, value: &Value, path: &str, issues: &mut Vec<ConfigIssue>) -> Value {
+    let Some(table) = value.as_object() else {
+        issues.push(mistyped(path, &schema.name, value));
+        return Value::Null;
+    };
+
+    let mut normalized = Map::new();
+    for field in &schema.fields {
+        let field_path = if path.is_empty() { field.name.clone() } else { format!("{}.{}", path, field.name) };
+        match (table.get(&field.name), &field.field_type) {
+            (None, ConfigType::Optional(_)) => {
+                normalized.insert(field.name.clone(), Value::Null);
+            }
+            (None, expected) => issues.push(ConfigIssue::Missing { path: field_path, expected: expected.to_string() }),
+            (Some(field_value), expected) => {
+                normalized.insert(field.name.clone(), check_value(expected, field_value, &field_path, issues));
+            }
+        }
+    }
+    Value::Object(normalized)
+}
+
+fn check_value(expected: &ConfigType, value: &Value, path: &str, issues: &mut Vec<ConfigIssue>) -> Value {
+    match (expected, value) {
+        (ConfigType::Int, Value::Number(number)) if number.is_i64() || number.is_u64() => value.clone(),
+        (ConfigType::Float, Value::Number(number)) => number.as_f64().map(Value::from).unwrap_or(Value::Null),
+        (ConfigType::Bool, Value::Bool(_)) | (ConfigType::String, Value::String(_)) => value.clone(),
+        (ConfigType::Optional(_), Value::Null) => Value::Null,
+        (ConfigType::Optional(inner), _) => check_value(inner, value, path, issues),
+        (ConfigType::List(element), Value::Array(items)) => Value::Array(
+            items.iter().enumerate()
+                .map(|(index, item)| check_value(element, item, &format!("{}[{}]", path, index), issues))
+                .collect(),
+        ),
+        (ConfigType::Struct(schema), _) => check_struct(schema, value, path, issues),
+        (expected, _) => {
+            issues.push(mistyped(path, &expected.to_string(), value));
+            Value::Null
+        }
+    }
+}
+
+fn mistyped(path: &str, expected: &str, value: &Value) -> ConfigIssue {
+    let found = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(number) if number.is_f64() => "float",
+        Value::Number(_) => "int",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "table",
+    };
+    ConfigIssue::Mistyped {
+        path: if path.is_empty() { "<root>".to_string() } else { path.to_string() },
+        expected: expected.to_string(),
+        found: found.to_string(),
+    }
+}
+
+/// قراءة قيمة بمسار منقط مثل "model.layers[0]"
+pub fn lookup<'a>(config: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(config, |current, segment| {
+        let (key, indices) = match segment.find('[') {
+            Some(start) => (&segment[..start], &segment[start..]),
+            None => (segment, ""),
+        };
+        let mut current = if key.is_empty() { current } else { current.get(key)? };
+        for index in indices.split(['[', ']']).filter(|part| !part.is_empty()) {
+            current = current.get(index.parse::<usize>().ok()?)?;
+        }
+        Some(current)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AGENT_STRUCTS: &str = "
+        struct ModelConfig { path: string; layers: list<int>; learning_rate: float; }
+        struct AgentConfig { name: string; verbose: bool; model: ModelConfig; knowledge_base: Option<string>; }
+    ";
+
+    #[test]
+    fn test_load_toml_into_struct_schema() {
+        let schema = ConfigSchema::from_source(AGENT_STRUCTS, "AgentConfig").unwrap();
+        assert_eq!(schema.fields.len(), 4);
+
+        let source = "name = \"explorer\"\nverbose = true\n\n[model]\npath = \"models/shapes.onnx\"\nlayers = [64, 32]\nlearning_rate = 1\n";
+        let config = schema.parse(source, ConfigFormat::Toml).unwrap();
+        assert_eq!(lookup(&config, "model.layers[1]"), Some(&Value::from(32)));
+        assert_eq!(lookup(&config, "model.learning_rate"), Some(&Value::from(1.0)));
+        assert_eq!(lookup(&config, "knowledge_base"), Some(&Value::Null));
+    }
+
+    #[test]
+    fn test_validation_lists_every_issue() {
+        let schema = ConfigSchema::from_source(AGENT_STRUCTS, "AgentConfig").unwrap();
+        let source = r#"{"name": 7, "model": {"path": "m.onnx", "layers": [1, "two"], "learning_rate": 0.1}}"#;
+
+        let Err(ConfigError::Invalid { issues, .. }) = schema.parse(source, ConfigFormat::Json) else {
+            panic!("expected validation issues");
+        };
+        assert_eq!(issues, vec![
+            ConfigIssue::Mistyped { path: "name".to_string(), expected: "string".to_string(), found: "int".to_string() },
+            ConfigIssue::Missing { path: "verbose".to_string(), expected: "bool".to_string() },
+            ConfigIssue::Mistyped { path: "model.layers[1]".to_string(), expected: "int".to_string(), found: "string".to_string() },
+        ]);
+        assert!(ConfigSchema::from_source(AGENT_STRUCTS, "Missing").is_err());
+    }
+}
@@ -0,0 +1,422 @@
+// بيانات CSV وJSON للبرامج الموجهة بالبيانات
+// CSV and JSON Data - bridging file data into tensors, facts and equations
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde_json::{Map, Number, Value};
+
+use super::config::{ConfigError, ConfigIssue, ConfigSchema, ConfigType};
+#[cfg(feature = "ai")]
+use crate::ai::Tensor;
+use crate::runtime::{AlbayanList, AlbayanValue, AlbayanValueTag, LogicEngine};
+
+/// جدول بيانات: صف العناوين ثم صفوف من النصوص
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DataTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl DataTable {
+    /// إنشاء جدول فارغ بالأعمدة المعطاة
+    pub fn new(headers: Vec<String>) -> Self {
+        Self { headers, rows: Vec::new() }
+    }
+
+    /// إضافة صف بعدد خلايا مساوٍ لعدد الأعمدة
+    pub fn push_row(&mut self, row: Vec<String>) -> Result<(), String> {
+        if row.len() != self.headers.len() {
+            return Err(format!("Row has {} cells but the table has {} columns", row.len(), self.headers.len()));
+        }
+        self.rows.push(row);
+        Ok(())
+    }
+
+    /// تحليل نص CSV (الصف الأول هو العناوين، والخلايا بين علامتي تنصيص قد تحوي فواصل وأسطراً)
+    pub fn parse_csv(source: &str) -> Result<Self, String> {
+        let mut records = parse_csv_records(source)?.into_iter();
+        let mut table = Self::new(records.next().unwrap_or_default());
+        for (index, row) in records.enumerate() {
+            table.push_row(row).map_err(|e| format!("CSV row {}: {}", index + 1, e))?;
+        }
+        Ok(table)
+    }
+
+    /// قراءة ملف CSV
+    pub fn read_csv<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read CSV file '{}': {}", path.display(), e))?;
+        Self::parse_csv(&source)
+    }
+
+    /// تحويل الجدول إلى نص CSV
+    pub fn to_csv(&self) -> String {
+        std::iter::once(&self.headers)
+            .chain(&self.rows)
+            .map(|row| row.iter().map(|cell| csv_cell(cell)).collect::<Vec<_>>().join(","))
+            .map(|line| line + "\n")
+            .collect()
+    }
+
+    /// كتابة الجدول إلى ملف CSV
+    pub fn write_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_csv())
+            .map_err(|e| format!("Cannot write CSV file '{}': {}", path.display(), e))
+    }
+
+    /// الصفوف كقوائم من الأزواج (اسم العمود ← القيمة)
+    pub fn records(&self) -> Vec<HashMap<String, String>> {
+        self.rows.iter()
+            .map(|row| self.headers.iter().cloned().zip(row.iter().cloned()).collect())
+            .collect()
+    }
+
+    /// الصفوف كهياكل مطابقة للمخطط: تُحوَّل الخلايا إلى أنواع الحقول
+    /// وتُسرد كل خلية مفقودة أو غير صالحة بمسار مثل `row 3.age`
+    pub fn typed_records(&self, schema: &ConfigSchema) -> Result<Vec<Value>, ConfigError> {
+        let mut records = Vec::new();
+        let mut issues = Vec::new();
+        for (index, row) in self.rows.iter().enumerate() {
+            let mut record = Map::new();
+            for field in &schema.fields {
+                if let Some(column) = self.headers.iter().position(|header| *header == field.name) {
+                    record.insert(field.name.clone(), typed_cell(&row[column], &field.field_type));
+                }
+            }
+            match schema.validate(&Value::Object(record)) {
+                Ok(record) => records.push(record),
+                Err(ConfigError::Invalid { issues: row_issues, .. }) => {
+                    issues.extend(row_issues.into_iter().map(|issue| prefix_issue(issue, index + 1)));
+                }
+                Err(other) => return Err(other),
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(records)
+        } else {
+            Err(ConfigError::Invalid { schema: schema.name.clone(), issues })
+        }
+    }
+
+    fn column_index(&self, column: &str) -> Result<usize, String> {
+        self.headers.iter().position(|header| header == column)
+            .ok_or_else(|| format!("Column '{}' not found", column))
+    }
+
+    /// قيم عمود عددي
+    pub fn column_f64(&self, column: &str) -> Result<Vec<f64>, String> {
+        let index = self.column_index(column)?;
+        self.rows.iter().enumerate()
+            .map(|(row, cells)| cells[index].trim().parse::<f64>()
+                .map_err(|_| format!("Row {}: '{}' in column '{}' is not a number", row + 1, cells[index], column)))
+            .collect()
+    }
+
+    /// موتر بشكل [عدد الصفوف، عدد الأعمدة] من الأعمدة العددية المعطاة
+    #[cfg(feature = "ai")]
+    pub fn to_tensor(&self, columns: &[&str]) -> Result<Tensor, String> {
+        let values = columns.iter()
+            .map(|column| self.column_f64(column))
+            .collect::<Result<Vec<_>, _>>()?;
+        let data = (0..self.rows.len())
+            .flat_map(|row| values.iter().map(move |column| column[row] as f32))
+            .collect();
+        Tensor::new(data, vec![self.rows.len(), columns.len()]).map_err(|e| e.to_string())
+    }
+
+    /// عينات (x, y) لتدريب المعادلات التكيفية واختيار النماذج
+    pub fn samples(&self, x_column: &str, y_column: &str) -> Result<Vec<(f64, f64)>, String> {
+        let xs = self.column_f64(x_column)?;
+        let ys = self.column_f64(y_column)?;
+        Ok(xs.into_iter().zip(ys).collect())
+    }
+
+    /// إضافة كل صف كحقيقة relation(خلية1، خلية2، ...) إلى محرك المنطق دفعة واحدة؛ تُعاد عدد الحقائق
+    pub fn assert_facts(&self, engine: &mut LogicEngine, relation: &str) -> Result<usize, String> {
+        engine.assert_facts_bulk(relation, self.fact_rows(), |_, _| {})
+            .map_err(|e| e.to_string())
+    }
+
+    /// معاملات الحقائق لكل صف بصيغة محرك المنطق
+    fn fact_rows(&self) -> Vec<Vec<String>> {
+        self.rows.iter()
+            .map(|row| row.iter().map(|cell| fact_term(cell)).collect())
+            .collect()
+    }
+}
+
+/// تحميل حقائق relation(...) من ملف CSV أو JSON (مصفوفة صفوف) دفعة واحدة مع التحقق من
+/// أنواع العلاقة المعرّفة؛ يُستدعى progress(المضاف، الكل) بعد كل مجموعة
+pub fn load_facts<P, F>(engine: &mut LogicEngine, relation: &str, path: P, progress: F) -> Result<usize, String>
+where
+    P: AsRef<Path>,
+    F: FnMut(usize, usize),
+{
+    let path = path.as_ref();
+    let is_json = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let rows = if is_json {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read JSON file '{}': {}", path.display(), e))?;
+        let value: Value = serde_json::from_str(&source).map_err(|e| format!("Invalid JSON: {}", e))?;
+        json_fact_rows(&value)?
+    } else {
+        DataTable::read_csv(path)?.fact_rows()
+    };
+    engine.assert_facts_bulk(relation, rows, progress).map_err(|e| e.to_string())
+}
+
+/// تحويل مصفوفة JSON من الصفوف ([["a", 1], ["b", 2]]) إلى معاملات حقائق
+pub fn json_fact_rows(value: &Value) -> Result<Vec<Vec<String>>, String> {
+    let Value::Array(rows) = value else {
+        return Err("Facts JSON must be an array of rows".to_string());
+    };
+    rows.iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let Value::Array(cells) = row else {
+                return Err(format!("Facts JSON row {} is not an array", index + 1));
+            };
+            cells.iter()
+                .map(|cell| match cell {
+                    Value::Number(number) => Ok(number.to_string()),
+                    Value::Bool(flag) => Ok(flag.to_string()),
+                    Value::String(text) => Ok(string_term(text)),
+                    _ => Err(format!("Facts JSON row {} has a value that is not a number, string or bool", index + 1)),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn parse_csv_records(source: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = source.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match (ch, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if cell.is_empty() => quoted = true,
+            (',', false) => record.push(std::mem::take(&mut cell)),
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut cell));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => cell.push(ch),
+        }
+    }
+
+    if quoted {
+        return Err("Unterminated quoted CSV cell".to_string());
+    }
+    if !cell.is_empty() || !record.is_empty() {
+        record.push(cell);
+        records.push(record);
+    }
+    records.retain(|record| !(record.len() == 1 && record[0].is_empty()));
+    Ok(records)
+}
+
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// تحويل خلية نصية إلى نوع الحقل؛ الخلايا غير القابلة للتحويل تبقى نصاً ليبلغ عنها التحقق
+fn typed_cell(cell: &str, field_type: &ConfigType) -> Value {
+    let text = cell.trim();
+    match field_type {
+        ConfigType::Optional(_) if text.is_empty() => Value::Null,
+        ConfigType::Optional(inner) => typed_cell(cell, inner),
+        ConfigType::Int => text.parse::<i64>().map(Value::from).unwrap_or_else(|_| Value::from(cell)),
+        ConfigType::Float => text.parse::<f64>().ok().and_then(Number::from_f64).map(Value::Number)
+            .unwrap_or_else(|| Value::from(cell)),
+        ConfigType::Bool => text.parse::<bool>().map(Value::from).unwrap_or_else(|_| Value::from(cell)),
+        _ => Value::from(cell),
+    }
+}
+
+fn prefix_issue(issue: ConfigIssue, row: usize) -> ConfigIssue {
+    match issue {
+        ConfigIssue::Missing { path, expected } => ConfigIssue::Missing { path: format!("row {}.{}", row, path), expected },
+        ConfigIssue::Mistyped { path, expected, found } => {
+            ConfigIssue::Mistyped { path: format!("row {}.{}", row, path), expected, found }
+        }
+    }
+}
+
+/// الأعداد تبقى أعداداً وبقية الخلايا تصبح نصوصاً بين علامتي تنصيص
+fn fact_term(cell: &str) -> String {
+    let text = cell.trim();
+    if text.parse::<f64>().is_ok() {
+        text.to_string()
+    } else {
+        string_term(text)
+    }
+}
+
+fn string_term(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// تحليل نص JSON إلى قيمة البيان (الكائنات تصبح هياكل باسم "Map")
+pub fn parse_json(source: &str) -> Result<AlbayanValue, String> {
+    let value: Value = serde_json::from_str(source).map_err(|e| format!("Invalid JSON: {}", e))?;
+    Ok(json_to_value(&value))
+}
+
+/// تحويل قيمة البيان إلى نص JSON
+pub fn to_json(value: &AlbayanValue) -> Result<String, String> {
+    serde_json::to_string(&value_to_json(value)?).map_err(|e| e.to_string())
+}
+
+/// بناء قيمة البيان من قيمة JSON متداخلة
+pub fn json_to_value(value: &Value) -> AlbayanValue {
+    match value {
+        Value::Null => AlbayanValue::new_null(),
+        Value::Bool(flag) => AlbayanValue::new_bool(*flag),
+        Value::Number(number) => match number.as_i64() {
+            Some(int) => AlbayanValue::new_int(int),
+            None => AlbayanValue::new_float(number.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(text) => AlbayanValue::new_string(text),
+        Value::Array(items) => {
+            let mut list = AlbayanList::new();
+            for item in items {
+                list.push(json_to_value(item));
+            }
+            AlbayanValue::new_list(Box::into_raw(list))
+        }
+        Value::Object(fields) => AlbayanValue::new_struct(
+            "Map",
+            fields.iter().map(|(key, field)| (key.clone(), json_to_value(field))).collect(),
+        ),
+    }
+}
+
+/// تحويل قيمة البيان إلى JSON (الصفوف تصبح قوائم والتعدادات اسم المتغير)
+pub fn value_to_json(value: &AlbayanValue) -> Result<Value, String> {
+    unsafe {
+        match value.tag {
+            AlbayanValueTag::Null => Ok(Value::Null),
+            AlbayanValueTag::Int => Ok(Value::from(value.as_int())),
+            AlbayanValueTag::Float => Number::from_f64(value.as_float()).map(Value::Number)
+                .ok_or_else(|| format!("{} cannot be represented in JSON", value.as_float())),
+            AlbayanValueTag::Bool => Ok(Value::Bool(value.as_bool())),
+            AlbayanValueTag::String => Ok(Value::from(value.as_str())),
+            AlbayanValueTag::List => {
+                let list = &*value.as_list();
+                (0..list.len()).filter_map(|index| list.get(index)).map(value_to_json).collect()
+            }
+            AlbayanValueTag::Tuple => {
+                let tuple = &*value.payload.tuple_val;
+                (0..tuple.len).map(|index| value_to_json(&*tuple.elements.add(index))).collect()
+            }
+            AlbayanValueTag::Struct => {
+                let fields = &*(*value.as_struct()).fields;
+                fields.iter()
+                    .map(|(key, field)| value_to_json(field).map(|field| (key.clone(), field)))
+                    .collect::<Result<Map<_, _>, _>>()
+                    .map(Value::Object)
+            }
+            AlbayanValueTag::Enum => Ok(Value::from((*(*value.as_enum()).variant_name).as_str())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEASUREMENTS: &str = "name,x,y\r\n\"circle, large\",1,2.5\nsquare,2,4.5\n\"say \"\"hi\"\"\",3,6.5\n";
+
+    #[test]
+    fn test_csv_round_trip_and_bridges() {
+        let table = DataTable::parse_csv(MEASUREMENTS).unwrap();
+        assert_eq!(table.headers, vec!["name", "x", "y"]);
+        assert_eq!(table.rows[0][0], "circle, large");
+        assert_eq!(table.rows[2][0], "say \"hi\"");
+        assert_eq!(table.records()[1]["y"], "4.5");
+        assert_eq!(DataTable::parse_csv(&table.to_csv()).unwrap(), table);
+
+        assert_eq!(table.samples("x", "y").unwrap()[2], (3.0, 6.5));
+        #[cfg(feature = "ai")]
+        assert_eq!(table.to_tensor(&["x", "y"]).unwrap().shape(), &[3, 2]);
+        assert!(table.column_f64("name").is_err());
+
+        let mut engine = LogicEngine::new();
+        assert_eq!(table.assert_facts(&mut engine, "measurement").unwrap(), 3);
+        assert_eq!(engine.solve_query("measurement(N, 2, Y)").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_load_facts_validates_declared_relation() {
+        let path = std::env::temp_dir().join(format!("albayan_facts_{}.json", std::process::id()));
+        std::fs::write(&path, r#"[["circle", 1, 2.5], ["square", 2, 4.5], ["triangle", "three", 6]]"#).unwrap();
+
+        let mut engine = LogicEngine::new();
+        engine.declare_relation("measurement", &["string", "int", "float"]);
+        let error = load_facts(&mut engine, "measurement", &path, |_, _| {}).unwrap_err();
+        assert!(error.contains("1 invalid facts for relation measurement: row 3: argument 2"), "{}", error);
+        assert_eq!(engine.facts_count(), 0);
+
+        std::fs::write(&path, r#"[["circle", 1, 2.5], ["square", 2, 4.5]]"#).unwrap();
+        let mut reports = Vec::new();
+        let count = load_facts(&mut engine, "measurement", &path, |done, total| reports.push((done, total))).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(reports, vec![(2, 2)]);
+        assert_eq!(engine.solve_query("measurement(\"square\", X, Y)").unwrap().len(), 1);
+
+        assert!(json_fact_rows(&serde_json::json!([{"name": "circle"}])).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_typed_records_report_every_bad_cell() {
+        let schema = ConfigSchema::new("Measurement")
+            .with_field("name", ConfigType::String)
+            .with_field("x", ConfigType::Int)
+            .with_field("y", ConfigType::Float);
+        let records = DataTable::parse_csv(MEASUREMENTS).unwrap().typed_records(&schema).unwrap();
+        assert_eq!(records[1]["x"], Value::from(2));
+
+        let bad = DataTable::parse_csv("name,x\na,one\nb,2\nc,3.5\n").unwrap();
+        let Err(ConfigError::Invalid { issues, .. }) = bad.typed_records(&schema) else {
+            panic!("expected validation issues");
+        };
+        let paths: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+        assert_eq!(paths, vec![
+            "key `row 1.x` should be int, found string",
+            "missing key `row 1.y` (float)",
+            "missing key `row 2.y` (float)",
+            "key `row 3.x` should be int, found string",
+            "missing key `row 3.y` (float)",
+        ]);
+    }
+
+    #[test]
+    fn test_json_round_trip_over_albayan_values() {
+        let source = r#"{"model":{"layers":[64,32],"rate":0.5,"name":"مصنف"},"tags":["a",null,true]}"#;
+        let value = parse_json(source).unwrap();
+        assert_eq!(value.tag, AlbayanValueTag::Struct);
+
+        let round_trip: Value = serde_json::from_str(&to_json(&value).unwrap()).unwrap();
+        assert_eq!(round_trip, serde_json::from_str::<Value>(source).unwrap());
+        unsafe { value.free() };
+        assert!(parse_json("{oops").is_err());
+    }
+}
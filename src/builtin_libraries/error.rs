@@ -0,0 +1,93 @@
+// أخطاء المكتبات المدمجة
+// Built-in Library Errors for AlBayan Language
+
+use std::fmt;
+
+/// نوع المكون الذي يديره مدير المكتبات
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentKind {
+    ThinkingCore,
+    ExpertExplorer,
+    Equation,
+    Pipeline,
+}
+
+/// أخطاء مدير المكتبات المدمجة
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum BuiltinError {
+    #[error("{kind} '{name}' not found")]
+    NotFound { kind: ComponentKind, name: String },
+
+    #[error("{kind} '{name}' already exists")]
+    AlreadyExists { kind: ComponentKind, name: String },
+
+    #[error("Precision must be a positive number, got {0}")]
+    InvalidPrecision(f64),
+
+    #[error("Invalid pipeline: {0}")]
+    InvalidPipeline(String),
+
+    #[error("Invalid risk policy: {0}")]
+    InvalidRiskPolicy(String),
+
+    /// فشل التدريب أو التحقق المتقاطع أو اختيار النموذج
+    #[error("Training failed: {0}")]
+    TrainingFailed(String),
+
+    #[error("Failed to serialize library state: {0}")]
+    SerializationFailed(String),
+
+    #[error("Failed to parse library state: {0}")]
+    DeserializationFailed(String),
+
+    #[error("Cannot access '{path}': {message}")]
+    Io { path: String, message: String },
+}
+
+impl fmt::Display for ComponentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ComponentKind::ThinkingCore => "Thinking core",
+            ComponentKind::ExpertExplorer => "Expert explorer",
+            ComponentKind::Equation => "Equation",
+            ComponentKind::Pipeline => "Pipeline",
+        })
+    }
+}
+
+impl BuiltinError {
+    pub fn not_found(kind: ComponentKind, name: &str) -> Self {
+        BuiltinError::NotFound { kind, name: name.to_string() }
+    }
+
+    pub fn already_exists(kind: ComponentKind, name: &str) -> Self {
+        BuiltinError::AlreadyExists { kind, name: name.to_string() }
+    }
+
+    pub fn io(path: &str, error: std::io::Error) -> Self {
+        BuiltinError::Io { path: path.to_string(), message: error.to_string() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_libraries::{AdaptationType, BuiltinLibraryManager};
+
+    #[test]
+    fn test_manager_errors_can_be_matched() {
+        let mut manager = BuiltinLibraryManager::new();
+        manager.create_adaptive_equation("line".to_string()).unwrap();
+
+        let error = manager.create_adaptive_equation("line".to_string()).unwrap_err();
+        assert_eq!(error, BuiltinError::already_exists(ComponentKind::Equation, "line"));
+        assert_eq!(error.to_string(), "Equation 'line' already exists");
+
+        let error = manager.train_equation("curve", vec![(0.0, 0.0)], AdaptationType::Gradient).unwrap_err();
+        assert!(matches!(error, BuiltinError::NotFound { kind: ComponentKind::Equation, ref name } if name == "curve"));
+        assert!(matches!(manager.set_default_precision(-1.0), Err(BuiltinError::InvalidPrecision(_))));
+        assert!(matches!(manager.apply_pipeline("core", "missing"),
+                         Err(BuiltinError::NotFound { kind: ComponentKind::Pipeline, .. })));
+        assert!(matches!(manager.load_state("/nonexistent/albayan_state.json"), Err(BuiltinError::Io { .. })));
+    }
+}
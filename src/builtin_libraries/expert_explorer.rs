@@ -2,8 +2,14 @@
 // Expert/Explorer System - Built-in Library for AlBayan Language
 
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use super::thinking_core::{ThinkingCore, AnalysisResult};
+use crate::runtime::random::random_f64;
+use super::risk_policy::{
+    RiskPolicy, RiskPolicyConfig, RiskContext, RiskLevel, RiskPlugins, RiskScoringPlugin
+};
 
 /// نظام الخبير/المستكشف الرئيسي
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,10 +17,20 @@ pub struct ExpertExplorer {
     pub current_mode: OperationMode,
     pub confidence_threshold: f64,
     pub exploration_depth: u32,
+    /// سعة الضجيج العشوائي في مسارات الاستكشاف (0 = استكشاف حتمي)
+    /// يُسحب من المولد المشترك `runtime::random` فتتكرر النتائج مع نفس البذرة
+    #[serde(default)]
+    pub exploration_noise: f64,
     pub expertise_domains: Vec<ExpertiseDomain>,
     pub decision_history: Vec<DecisionRecord>,
     pub learning_parameters: LearningParameters,
     pub thinking_core: Option<ThinkingCore>,
+    /// سياسات المخاطر - تُطبق أول سياسة مطابقة، وإلا السياسة الافتراضية
+    #[serde(default)]
+    pub risk_policies: Vec<RiskPolicy>,
+    /// إضافات حساب المخاطر التي يحقنها المضيف
+    #[serde(skip)]
+    pub risk_plugins: RiskPlugins,
 }
 
 /// أنماط التشغيل
@@ -27,7 +43,7 @@ pub enum OperationMode {
 }
 
 /// مجالات الخبرة
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ExpertiseDomain {
     Mathematics,     // الرياضيات
     Language,        // اللغة
@@ -51,6 +67,11 @@ pub struct DecisionRecord {
     pub exploration_paths: Vec<ExplorationPath>,
     pub expert_reasoning: Vec<String>,
     pub success_rate: f64,
+    /// اسم سياسة المخاطر التي طُبقت على القرار
+    #[serde(default)]
+    pub applied_policy: Option<String>,
+    #[serde(default)]
+    pub overall_risk: f64,
 }
 
 /// مسار الاستكشاف
@@ -120,6 +141,14 @@ pub struct RiskAssessment {
     pub uncertainty_factors: Vec<String>,
     pub mitigation_strategies: Vec<String>,
     pub confidence_interval: (f64, f64),
+    #[serde(default)]
+    pub risk_level: RiskLevel,
+    /// اسم السياسة التي حسبت المخاطر
+    #[serde(default)]
+    pub policy_name: String,
+    /// درجات الإضافات (الاسم، الدرجة)
+    #[serde(default)]
+    pub plugin_scores: Vec<(String, f64)>,
 }
 
 impl ExpertExplorer {
@@ -129,10 +158,13 @@ impl ExpertExplorer {
             current_mode: OperationMode::Adaptive,
             confidence_threshold: 0.7,
             exploration_depth: 3,
+            exploration_noise: 0.0,
             expertise_domains: vec![ExpertiseDomain::General],
             decision_history: Vec::new(),
             learning_parameters: LearningParameters::default(),
             thinking_core: None,
+            risk_policies: Vec::new(),
+            risk_plugins: Vec::new(),
         }
     }
 
@@ -154,6 +186,12 @@ impl ExpertExplorer {
         self
     }
 
+    /// تحديد سعة الضجيج العشوائي في الاستكشاف
+    pub fn set_exploration_noise(mut self, noise: f64) -> Self {
+        self.exploration_noise = noise.max(0.0);
+        self
+    }
+
     /// إضافة مجال خبرة
     pub fn add_expertise_domain(mut self, domain: ExpertiseDomain) -> Self {
         if !self.expertise_domains.contains(&domain) {
@@ -162,6 +200,38 @@ impl ExpertExplorer {
         self
     }
 
+    /// إضافة سياسة مخاطر
+    pub fn with_risk_policy(mut self, policy: RiskPolicy) -> Self {
+        self.risk_policies.push(policy);
+        self
+    }
+
+    /// إضافة إضافة لحساب المخاطر
+    pub fn with_risk_plugin(mut self, plugin: Arc<dyn RiskScoringPlugin>) -> Self {
+        self.risk_plugins.push(plugin);
+        self
+    }
+
+    /// تسجيل إضافة لحساب المخاطر
+    pub fn register_risk_plugin(&mut self, plugin: Arc<dyn RiskScoringPlugin>) {
+        self.risk_plugins.push(plugin);
+    }
+
+    /// تحميل سياسات المخاطر من ملف JSON أو TOML (تحل محل السياسات الحالية)
+    pub fn load_risk_policies<P: AsRef<Path>>(&mut self, path: P) -> Result<(), String> {
+        let config = RiskPolicyConfig::load(path)?;
+        self.risk_policies = config.policies;
+        Ok(())
+    }
+
+    /// السياسة المطبقة على نمط معين
+    pub fn active_policy(&self, mode: OperationMode) -> RiskPolicy {
+        self.risk_policies.iter()
+            .find(|policy| policy.matches(&self.expertise_domains, mode))
+            .cloned()
+            .unwrap_or_default()
+    }
+
     /// اتخاذ قرار ذكي
     pub fn make_decision(&mut self, input_data: Vec<f64>) -> DecisionResult {
         // تحليل البيانات باستخدام النواة التفكيرية إن وجدت
@@ -208,12 +278,15 @@ impl ExpertExplorer {
                 "قرار سريع مبني على الخبرة السابقة".to_string(),
             ],
             alternative_options: vec![],
-            risk_assessment: RiskAssessment {
-                overall_risk: 0.2, // مخاطر منخفضة للقرارات الخبيرة
-                uncertainty_factors: vec!["تغيرات في البيئة".to_string()],
-                mitigation_strategies: vec!["مراقبة مستمرة".to_string()],
-                confidence_interval: (decision - 0.1, decision + 0.1),
-            },
+            risk_assessment: self.assess_risk(
+                input_data,
+                OperationMode::Expert,
+                confidence,
+                decision,
+                vec!["تغيرات في البيئة".to_string()],
+                vec!["مراقبة مستمرة".to_string()],
+                (decision - 0.1, decision + 0.1),
+            ),
             recommendation: "قرار موثوق مبني على الخبرة".to_string(),
         }
     }
@@ -254,18 +327,21 @@ impl ExpertExplorer {
                 "اختيار أفضل مسار مكتشف".to_string(),
             ],
             alternative_options,
-            risk_assessment: RiskAssessment {
-                overall_risk: 0.6, // مخاطر أعلى للاستكشاف
-                uncertainty_factors: vec![
+            risk_assessment: self.assess_risk(
+                input_data,
+                OperationMode::Explorer,
+                best_confidence,
+                best_result,
+                vec![
                     "عدم اليقين في المسارات الجديدة".to_string(),
                     "احتمالية فشل التجارب".to_string(),
                 ],
-                mitigation_strategies: vec![
+                vec![
                     "تنويع المسارات".to_string(),
                     "تقييم مستمر للنتائج".to_string(),
                 ],
-                confidence_interval: (best_result - 0.3, best_result + 0.3),
-            },
+                (best_result - 0.3, best_result + 0.3),
+            ),
             recommendation: "قرار استكشافي مع إمكانيات ابتكارية".to_string(),
         }
     }
@@ -296,8 +372,12 @@ impl ExpertExplorer {
         limited_exploration.exploration_depth = 2;
         let explorer_result = limited_exploration.explorer_decision(input_data);
 
-        // دمج النتائج
-        let combined_decision = (expert_result.final_decision * 0.7) + (explorer_result.final_decision * 0.3);
+        // دمج النتائج حسب جدول الاستكشاف في السياسة
+        let exploration_ratio = self.active_policy(OperationMode::Hybrid)
+            .exploration_schedule
+            .ratio_at(self.decision_history.len() as u64);
+        let combined_decision = (expert_result.final_decision * (1.0 - exploration_ratio))
+            + (explorer_result.final_decision * exploration_ratio);
         let combined_confidence = (expert_result.confidence_level + explorer_result.confidence_level) / 2.0;
 
         DecisionResult {
@@ -306,21 +386,28 @@ impl ExpertExplorer {
             confidence_level: combined_confidence,
             reasoning_chain: vec![
                 "دمج قرار الخبير مع الاستكشاف".to_string(),
-                "وزن 70% للخبرة، 30% للاستكشاف".to_string(),
+                format!(
+                    "وزن {:.0}% للخبرة، {:.0}% للاستكشاف",
+                    (1.0 - exploration_ratio) * 100.0,
+                    exploration_ratio * 100.0
+                ),
                 "توازن بين الموثوقية والابتكار".to_string(),
             ],
             alternative_options: explorer_result.alternative_options,
-            risk_assessment: RiskAssessment {
-                overall_risk: (expert_result.risk_assessment.overall_risk + explorer_result.risk_assessment.overall_risk) / 2.0,
-                uncertainty_factors: vec![
+            risk_assessment: self.assess_risk(
+                input_data,
+                OperationMode::Hybrid,
+                combined_confidence,
+                combined_decision,
+                vec![
                     "تعارض محتمل بين الخبرة والاستكشاف".to_string(),
                 ],
-                mitigation_strategies: vec![
+                vec![
                     "مراقبة توازن القرارات".to_string(),
                     "تعديل الأوزان حسب النتائج".to_string(),
                 ],
-                confidence_interval: (combined_decision - 0.2, combined_decision + 0.2),
-            },
+                (combined_decision - 0.2, combined_decision + 0.2),
+            ),
             recommendation: "قرار متوازن يجمع بين الموثوقية والابتكار".to_string(),
         }
     }
@@ -387,6 +474,42 @@ impl ExpertExplorer {
         }
     }
 
+    /// تقييم المخاطر باستخدام السياسة المطابقة والإضافات المسجلة
+    #[allow(clippy::too_many_arguments)]
+    fn assess_risk(&self,
+                   input_data: &[f64],
+                   mode: OperationMode,
+                   confidence: f64,
+                   decision: f64,
+                   uncertainty_factors: Vec<String>,
+                   mut mitigation_strategies: Vec<String>,
+                   confidence_interval: (f64, f64)) -> RiskAssessment {
+        let policy = self.active_policy(mode);
+        let context = RiskContext {
+            input_data,
+            mode,
+            confidence,
+            decision,
+            base_risk: policy.base_risk_for(mode),
+            domains: &self.expertise_domains,
+        };
+        let evaluation = policy.evaluate(&context, &self.risk_plugins);
+
+        if evaluation.risk_level == RiskLevel::High {
+            mitigation_strategies.push("مراجعة القرار قبل التنفيذ".to_string());
+        }
+
+        RiskAssessment {
+            overall_risk: evaluation.overall_risk,
+            uncertainty_factors,
+            mitigation_strategies,
+            confidence_interval,
+            risk_level: evaluation.risk_level,
+            policy_name: evaluation.policy_name,
+            plugin_scores: evaluation.plugin_scores,
+        }
+    }
+
     // الدوال المساعدة
     fn apply_expert_knowledge(&self, input_data: &[f64]) -> f64 {
         // تطبيق معرفة خبيرة مبسطة
@@ -403,7 +526,12 @@ impl ExpertExplorer {
 
     fn generate_transformation(&self, data: &[f64], path_index: u32, step_index: u32) -> Vec<f64> {
         let factor = 1.0 + (path_index as f64 * 0.1) + (step_index as f64 * 0.05);
-        data.iter().map(|x| x * factor).collect()
+        if self.exploration_noise == 0.0 {
+            return data.iter().map(|x| x * factor).collect();
+        }
+        data.iter()
+            .map(|x| x * (factor + self.exploration_noise * (2.0 * random_f64() - 1.0)))
+            .collect()
     }
 
     fn calculate_novelty_score(&self, steps: &[ExplorationStep]) -> f64 {
@@ -423,6 +551,8 @@ impl ExpertExplorer {
             exploration_paths: vec![], // سيتم ملؤها في التطبيق الفعلي
             expert_reasoning: result.reasoning_chain.clone(),
             success_rate: 0.8, // سيتم حسابها من التاريخ
+            applied_policy: Some(result.risk_assessment.policy_name.clone()),
+            overall_risk: result.risk_assessment.overall_risk,
         };
 
         self.decision_history.push(record);
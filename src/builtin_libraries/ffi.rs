@@ -0,0 +1,847 @@
+// واجهة FFI للمكتبات المدمجة - استدعاؤها من شيفرة البيان
+// FFI interface for the built-in libraries - callable from AlBayan code
+
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use super::config::lookup;
+use super::data::{load_facts, parse_json, to_json};
+use super::{
+    AdaptationType, ArtisticRenderer, BasicShape, BuiltinError, BuiltinLibraryManager, ConfigSchema, DataTable,
+    GeneralShapeEquation, LayerConfig, LayerType, ModelSelector, PipelineConfig, ProcessingMode, RenderedImage,
+    SceneDescription, SceneExtractor, SceneRenderer, ShapeInference,
+};
+#[cfg(feature = "render")]
+use super::{AnimationSpec, Canvas, FrameSource};
+#[cfg(feature = "ai")]
+use crate::ai::reinforcement_learning::{Environment, KnowledgeEnvironment, QLearning};
+use crate::runtime::{AlbayanValue, LogicEngine};
+
+/// مجلد حفظ الرسوم المتحركة (الافتراضي هو المجلد الحالي)
+pub const ANIMATION_DIR_ENV: &str = "ALBAYAN_ANIMATION_DIR";
+
+/// وجهة لوحة العرض التفاعلية (انظر `Canvas::from_target`)
+pub const CANVAS_ENV: &str = "ALBAYAN_CANVAS";
+
+/// ملف حالة المكتبات: تحمل منه عند أول استخدام وتحفظ فيه عند إيقاف التشغيل
+pub const LIBRARY_STATE_ENV: &str = "ALBAYAN_LIBRARY_STATE";
+
+/// المدير العام للمكتبات المدمجة
+static GLOBAL_LIBRARY_MANAGER: OnceLock<Mutex<BuiltinLibraryManager>> = OnceLock::new();
+
+/// خطوط المعالجة قيد الإنشاء (لم يتم التحقق منها بعد)
+static PIPELINE_DRAFTS: OnceLock<Mutex<HashMap<String, PipelineConfig>>> = OnceLock::new();
+
+/// لوحة العرض النشطة (إن وجدت)
+#[cfg(feature = "render")]
+static GLOBAL_CANVAS: OnceLock<Mutex<Option<Canvas>>> = OnceLock::new();
+
+/// قاعدة معرفة المشاهد: الحقائق المكانية المستخرجة من الصور
+static SCENE_LOGIC: OnceLock<Mutex<LogicEngine>> = OnceLock::new();
+
+/// ملفات الإعدادات المحملة بالاسم، أو رسالة الخطأ إن فشل التحميل
+static LOADED_CONFIGS: OnceLock<Mutex<HashMap<String, Result<serde_json::Value, String>>>> = OnceLock::new();
+
+/// جداول البيانات المحملة بالاسم
+static DATA_TABLES: OnceLock<Mutex<HashMap<String, DataTable>>> = OnceLock::new();
+
+/// وكلاء التعلم المعزز بالاسم، يتعلمون من نموذج العالم في قاعدة المعرفة
+#[cfg(feature = "ai")]
+static RL_AGENTS: OnceLock<Mutex<HashMap<String, QLearning>>> = OnceLock::new();
+
+/// الحصول على المدير العام للمكتبات
+pub fn global_manager() -> MutexGuard<'static, BuiltinLibraryManager> {
+    GLOBAL_LIBRARY_MANAGER
+        .get_or_init(|| {
+            let mut manager = BuiltinLibraryManager::new();
+            if let Ok(path) = std::env::var(LIBRARY_STATE_ENV) {
+                if std::path::Path::new(&path).exists() {
+                    if let Err(error) = manager.load_state(&path) {
+                        eprintln!("Ignoring library state: {}", error);
+                    }
+                }
+            }
+            Mutex::new(manager)
+        })
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn pipeline_drafts() -> MutexGuard<'static, HashMap<String, PipelineConfig>> {
+    PIPELINE_DRAFTS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn loaded_configs() -> MutexGuard<'static, HashMap<String, Result<serde_json::Value, String>>> {
+    LOADED_CONFIGS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn data_tables() -> MutexGuard<'static, HashMap<String, DataTable>> {
+    DATA_TABLES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(feature = "ai")]
+fn rl_agents() -> MutexGuard<'static, HashMap<String, QLearning>> {
+    RL_AGENTS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(feature = "render")]
+fn canvas() -> MutexGuard<'static, Option<Canvas>> {
+    GLOBAL_CANVAS
+        .get_or_init(|| {
+            let canvas = std::env::var(CANVAS_ENV).ok()
+                .and_then(|target| Canvas::from_target(&target, "albayan").ok())
+                .map(|(canvas, _)| canvas);
+            Mutex::new(canvas)
+        })
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// الحصول على محرك المنطق الذي تضاف إليه حقائق المشاهد
+pub fn scene_logic() -> MutexGuard<'static, LogicEngine> {
+    SCENE_LOGIC
+        .get_or_init(|| Mutex::new(LogicEngine::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// تفعيل لوحة العرض لبقية التنفيذ وإرجاع وصف الوجهة (مثل رابط الصفحة)
+#[cfg(feature = "render")]
+pub fn open_canvas(target: &str, session: &str) -> Result<String, String> {
+    let (new_canvas, description) = Canvas::from_target(target, session)?;
+    *canvas() = Some(new_canvas);
+    Ok(description)
+}
+
+/// لوحة العرض تحتاج ميزة `render`
+#[cfg(not(feature = "render"))]
+pub fn open_canvas(_target: &str, _session: &str) -> Result<String, String> {
+    Err("The canvas needs AlBayan built with the `render` feature".to_string())
+}
+
+/// إنهاء البث وإغلاق لوحة العرض
+pub fn close_canvas() {
+    #[cfg(feature = "render")]
+    if let Some(mut active) = canvas().take() {
+        active.finish();
+    }
+}
+
+/// قراءة صورة من ملف؛ قراءة الصور تحتاج ميزة `render`
+#[cfg(feature = "render")]
+fn open_image(path: &str) -> Option<RenderedImage> {
+    RenderedImage::open(std::path::Path::new(path)).ok()
+}
+
+#[cfg(not(feature = "render"))]
+fn open_image(_path: &str) -> Option<RenderedImage> {
+    None
+}
+
+/// حفظ حالة المكتبات في الملف المحدد بـ `ALBAYAN_LIBRARY_STATE`، إن كان محددا واستخدمت المكتبات
+pub fn save_library_state() -> Result<(), BuiltinError> {
+    match (std::env::var(LIBRARY_STATE_ENV), GLOBAL_LIBRARY_MANAGER.get()) {
+        (Ok(path), Some(manager)) => manager
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .save_state(&path),
+        _ => Ok(()),
+    }
+}
+
+/// معادلة مسجلة بالاسم أو معادلة شكل أساسي
+fn resolve_equation(renderer: &ArtisticRenderer, name: &str) -> Option<GeneralShapeEquation> {
+    let registered = global_manager().equations.get(name).cloned();
+    registered.or_else(|| BasicShape::from_name(name).and_then(|shape| renderer.get_shape(shape).cloned()))
+}
+
+fn c_str<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_str().ok()
+}
+
+/// اسم ملف GIF لمعادلة، أو `None` إن كان فيه فاصل مسار أو كان `.` أو `..`،
+/// كي لا يُكتب الملف خارج مجلد الرسوم المتحركة
+#[cfg(feature = "render")]
+fn animation_file_name(equation_name: &str) -> Option<String> {
+    let mut components = std::path::Path::new(equation_name).components();
+    let single = matches!((components.next(), components.next()), (Some(std::path::Component::Normal(_)), None));
+    (single && !equation_name.contains(['/', '\\'])).then(|| format!("{}.gif", equation_name))
+}
+
+/// تحليل بيانات التدريب بالصيغة "x1,y1;x2,y2;..."
+pub fn parse_samples(data: &str) -> Result<Vec<(f64, f64)>, String> {
+    data.split(';')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (x, y) = pair.split_once(',')
+                .ok_or_else(|| format!("Invalid sample '{}': expected 'x,y'", pair))?;
+            let x = x.trim().parse::<f64>().map_err(|e| format!("Invalid x in '{}': {}", pair, e))?;
+            let y = y.trim().parse::<f64>().map_err(|e| format!("Invalid y in '{}': {}", pair, e))?;
+            Ok((x, y))
+        })
+        .collect()
+}
+
+fn with_draft_layer<F>(pipeline: *const c_char, layer: *const c_char, update: F) -> c_int
+where
+    F: FnOnce(&mut LayerConfig),
+{
+    let (Some(pipeline_name), Some(layer_type)) = (c_str(pipeline), c_str(layer).and_then(LayerType::from_name)) else {
+        return 0;
+    };
+
+    let mut drafts = pipeline_drafts();
+    match drafts.get_mut(pipeline_name)
+        .and_then(|draft| draft.stages.iter_mut().find(|stage| stage.layer_type == layer_type))
+    {
+        Some(stage) => {
+            update(stage);
+            1
+        }
+        None => 0,
+    }
+}
+
+/// بدء إنشاء خط معالجة جديد - واجهة FFI
+/// Start a new (empty) thinking pipeline - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_thinking_pipeline_create(name: *const c_char) -> c_int {
+    let Some(name) = c_str(name) else {
+        return 0;
+    };
+
+    pipeline_drafts().insert(name.to_string(), PipelineConfig {
+        name: name.to_string(),
+        stages: Vec::new(),
+        processing_mode: ProcessingMode::Sequential,
+    });
+    1
+}
+
+/// إضافة طبقة إلى نهاية خط المعالجة - واجهة FFI
+/// Append a layer to a pipeline - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_thinking_pipeline_add_layer(
+    pipeline: *const c_char,
+    layer: *const c_char,
+) -> c_int {
+    let (Some(pipeline_name), Some(layer_type)) = (c_str(pipeline), c_str(layer).and_then(LayerType::from_name)) else {
+        return 0;
+    };
+
+    match pipeline_drafts().get_mut(pipeline_name) {
+        Some(draft) => {
+            draft.stages.push(LayerConfig::new(layer_type));
+            1
+        }
+        None => 0,
+    }
+}
+
+/// ضبط وزن الطبقة وعتبة تفعيلها - واجهة FFI
+/// Set a layer's processing weight and activation threshold - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_thinking_pipeline_configure_layer(
+    pipeline: *const c_char,
+    layer: *const c_char,
+    weight: f64,
+    threshold: f64,
+) -> c_int {
+    with_draft_layer(pipeline, layer, |stage| {
+        stage.processing_weight = Some(weight);
+        stage.activation_threshold = Some(threshold);
+    })
+}
+
+/// تفعيل أو تعطيل طبقة في خط المعالجة - واجهة FFI
+/// Enable or disable a layer in a pipeline - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_thinking_pipeline_set_layer_enabled(
+    pipeline: *const c_char,
+    layer: *const c_char,
+    enabled: bool,
+) -> c_int {
+    with_draft_layer(pipeline, layer, |stage| stage.enabled = enabled)
+}
+
+/// التحقق من خط المعالجة وتطبيقه على نواة تفكيرية - واجهة FFI
+/// Validate a pipeline and apply it to a thinking core (created if missing) - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_thinking_pipeline_apply(
+    core: *const c_char,
+    pipeline: *const c_char,
+) -> c_int {
+    let (Some(core_name), Some(pipeline_name)) = (c_str(core), c_str(pipeline)) else {
+        return 0;
+    };
+
+    let Some(draft) = pipeline_drafts().get(pipeline_name).cloned() else {
+        return 0;
+    };
+
+    let mut manager = global_manager();
+    if manager.register_pipeline(draft).is_err() {
+        return 0;
+    }
+    if !manager.thinking_cores.contains_key(core_name)
+        && manager.create_thinking_core(core_name.to_string()).is_err()
+    {
+        return 0;
+    }
+
+    match manager.apply_pipeline(core_name, pipeline_name) {
+        Ok(()) => 1,
+        Err(_) => 0,
+    }
+}
+
+/// التحقق المتقاطع لمعادلة مسجلة - واجهة FFI
+/// k-fold cross-validation of a registered equation; returns the mean error or -1.0 - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_equation_cross_validate(
+    equation: *const c_char,
+    data: *const c_char,
+    folds: c_int,
+) -> f64 {
+    let (Some(equation_name), Some(data)) = (c_str(equation), c_str(data)) else {
+        return -1.0;
+    };
+    let Ok(samples) = parse_samples(data) else {
+        return -1.0;
+    };
+
+    match global_manager().cross_validate_equation(equation_name, &samples, folds.max(0) as usize, AdaptationType::Gradient) {
+        Ok(result) => result.mean_error,
+        Err(_) => -1.0,
+    }
+}
+
+/// اختيار أفضل نموذج بالبحث الشبكي وحفظه بالاسم المعطى - واجهة FFI
+/// Grid-search model selection; stores the best equation and returns its score or -1.0 - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_equation_select_model(
+    equation: *const c_char,
+    data: *const c_char,
+    folds: c_int,
+) -> f64 {
+    let (Some(equation_name), Some(data)) = (c_str(equation), c_str(data)) else {
+        return -1.0;
+    };
+    let Ok(samples) = parse_samples(data) else {
+        return -1.0;
+    };
+
+    let selector = ModelSelector::new().with_folds(folds.max(0) as usize);
+    match global_manager().select_equation_model(equation_name, &samples, &selector) {
+        Ok(result) => result.result_data["selection_report"]["best_score"].as_f64().unwrap_or(-1.0),
+        Err(_) => -1.0,
+    }
+}
+
+/// رسم متحرك لمعادلة مسجلة أو شكل أساسي وحفظه كـ GIF - واجهة FFI
+/// Animate a registered equation or basic shape by sweeping parameter ranges ("sigmoid_0.alpha:0.5:2.0;...")
+/// and save it as `<equation>.gif`; returns the file path, or null on failure or when the name is not
+/// a plain file name - FFI interface
+#[cfg(feature = "render")]
+#[no_mangle]
+pub extern "C" fn albayan_rt_animate(
+    equation: *const c_char,
+    param_range: *const c_char,
+    frames: c_int,
+) -> *mut c_char {
+    let (Some(equation_name), Some(param_range)) = (c_str(equation), c_str(param_range)) else {
+        return std::ptr::null_mut();
+    };
+    let Some(file_name) = animation_file_name(equation_name) else {
+        return std::ptr::null_mut();
+    };
+    let Ok(spec) = AnimationSpec::parse(param_range, frames.max(0) as usize) else {
+        return std::ptr::null_mut();
+    };
+
+    let renderer = ArtisticRenderer::new();
+    let Some(base) = resolve_equation(&renderer, equation_name) else {
+        return std::ptr::null_mut();
+    };
+
+    let output_dir = std::env::var_os(ANIMATION_DIR_ENV)
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| std::path::PathBuf::from("."));
+    let output = output_dir.join(file_name);
+
+    let saved = renderer.animate(&base, &spec).and_then(|sequence| {
+        if let Some(active) = canvas().as_mut() {
+            let total = sequence.frames.len();
+            for (index, frame) in sequence.frames.iter().enumerate() {
+                let label = format!("{} {}/{}", equation_name, index + 1, total);
+                active.show(&label, FrameSource::Animation, frame)?;
+            }
+        }
+        sequence.save(&output)
+    });
+    match saved.ok().and_then(|_| CString::new(output.display().to_string()).ok()) {
+        Some(path) => path.into_raw(),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// عرض معادلة مسجلة أو شكل أساسي على لوحة العرض - واجهة FFI
+/// Render a registered equation or basic shape to the live canvas; returns 0 if no canvas is open - FFI interface
+#[cfg(feature = "render")]
+#[no_mangle]
+pub extern "C" fn albayan_rt_canvas_show(equation: *const c_char) -> c_int {
+    let Some(equation_name) = c_str(equation) else {
+        return 0;
+    };
+    let mut renderer = ArtisticRenderer::new();
+    let Some(equation) = resolve_equation(&renderer, equation_name) else {
+        return 0;
+    };
+
+    let image = renderer.equation_to_image(&equation);
+    match canvas().as_mut() {
+        Some(active) => active.show(equation_name, FrameSource::ArtisticRenderer, &image).is_ok() as c_int,
+        None => 0,
+    }
+}
+
+/// رسم شكل ثم استنباط معادلته وعرض النتيجة على لوحة العرض - واجهة FFI
+/// Render a shape, run shape inference on it and stream both to the canvas; returns the confidence or -1.0 - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_canvas_infer(equation: *const c_char) -> f64 {
+    let Some(equation_name) = c_str(equation) else {
+        return -1.0;
+    };
+    let mut renderer = ArtisticRenderer::new();
+    let Some(equation) = resolve_equation(&renderer, equation_name) else {
+        return -1.0;
+    };
+
+    let image = renderer.equation_to_image(&equation);
+    let result = ShapeInference::new().image_to_equation(&image);
+    #[cfg(feature = "render")]
+    if let Some(active) = canvas().as_mut() {
+        let _ = active.show_inference(equation_name, &image, &result);
+    }
+    result.confidence
+}
+
+/// مسح لوحة العرض - واجهة FFI
+/// Clear the live canvas - FFI interface
+#[cfg(feature = "render")]
+#[no_mangle]
+pub extern "C" fn albayan_rt_canvas_clear() -> c_int {
+    match canvas().as_mut() {
+        Some(active) => {
+            active.clear();
+            1
+        }
+        None => 0,
+    }
+}
+
+/// استخراج أجسام مشهد من ملف صورة (أو معادلة مسجلة) وإضافة حقائقه المكانية إلى قاعدة المعرفة - واجهة FFI
+/// Detect the objects in an image file (or registered equation) and assert their spatial facts; returns the object count or -1 - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_scene_extract(source: *const c_char) -> c_int {
+    let Some(source) = c_str(source) else {
+        return -1;
+    };
+
+    let image = if std::path::Path::new(source).is_file() {
+        match open_image(source) {
+            Some(image) => image,
+            None => return -1,
+        }
+    } else {
+        let mut renderer = ArtisticRenderer::new();
+        let Some(equation) = resolve_equation(&renderer, source) else {
+            return -1;
+        };
+        renderer.equation_to_image(&equation)
+    };
+
+    let scene = SceneExtractor::new().extract(&image, &mut ShapeInference::new());
+    match scene.assert_into(&mut scene_logic()) {
+        Ok(_) => scene.objects.len() as c_int,
+        Err(_) => -1,
+    }
+}
+
+/// الاستعلام عن حقائق المشاهد - واجهة FFI
+/// Query the scene knowledge base; returns the number of solutions or -1 - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_scene_query(query: *const c_char) -> c_int {
+    let Some(query) = c_str(query) else {
+        return -1;
+    };
+    match scene_logic().solve_query(query) {
+        Ok(solutions) => solutions.len() as c_int,
+        Err(_) => -1,
+    }
+}
+
+/// خطة تنفيذ استعلام على قاعدة معرفة المشاهد مع عدد النتائج الفعلي لكل خطوة - واجهة FFI
+/// Explain a query against the scene knowledge base; returns the printed plan - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_logic_explain(query: *const c_char) -> *mut c_char {
+    let plan = c_str(query)
+        .map(|query| match scene_logic().explain_query(query) {
+            Ok(plan) => plan.to_string(),
+            Err(e) => format!("EXPLAIN failed: {}", e),
+        })
+        .unwrap_or_default();
+    CString::new(plan).map(CString::into_raw).unwrap_or(std::ptr::null_mut())
+}
+
+/// أبطأ استعلامات الجلسة، سطر لكل استعلام - واجهة FFI
+/// The session's slowest queries, one line each - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_logic_slowest_queries(limit: c_int) -> *mut c_char {
+    let limit = usize::try_from(limit).unwrap_or(0);
+    let report = scene_logic()
+        .slowest_queries(limit)
+        .iter()
+        .map(|stats| stats.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    CString::new(report).map(CString::into_raw).unwrap_or(std::ptr::null_mut())
+}
+
+/// إضافة حقيقة إلى قاعدة معرفة المشاهد (مثل "فوق(ش1, ش2)") - واجهة FFI
+/// Assert a fact into the scene knowledge base - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_scene_assert(fact: *const c_char) -> c_int {
+    match c_str(fact) {
+        Some(fact) => scene_logic().assert_fact(fact).is_ok() as c_int,
+        None => 0,
+    }
+}
+
+/// رسم المشهد الموصوف بحقائق قاعدة المعرفة وعرضه على لوحة العرض - واجهة FFI
+/// Lay out and render the objects selected by a query from the scene facts and stream the
+/// picture to the canvas; returns the object count or -1 - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_render_scene(query: *const c_char) -> c_int {
+    let Some(query) = c_str(query) else {
+        return -1;
+    };
+    let Ok(description) = SceneDescription::from_logic(&mut scene_logic(), query) else {
+        return -1;
+    };
+    let Ok((scene, image)) = SceneRenderer::new().render(&description, &mut ArtisticRenderer::new()) else {
+        return -1;
+    };
+
+    #[cfg(not(feature = "render"))]
+    let _ = image;
+    #[cfg(feature = "render")]
+    if let Some(active) = canvas().as_mut() {
+        let label = if query.trim().is_empty() { "scene" } else { query };
+        if active.show(label, FrameSource::ArtisticRenderer, &image).is_err() {
+            return -1;
+        }
+    }
+    scene.objects.len() as c_int
+}
+
+/// تحرير نص أعادته دوال المكتبات المدمجة - واجهة FFI
+/// Free a string returned by a built-in library function - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_builtin_free_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        unsafe {
+            let _ = CString::from_raw(ptr);
+        }
+    }
+}
+
+/// قيمة من إعدادات محملة بمسار منقط مثل "model.layers[0]"
+fn config_value(name: *const c_char, key: *const c_char) -> Option<serde_json::Value> {
+    let (name, key) = (c_str(name)?, c_str(key)?);
+    let configs = loaded_configs();
+    let config = configs.get(name)?.as_ref().ok()?;
+    lookup(config, key).cloned()
+}
+
+/// تحميل ملف إعدادات JSON/TOML والتحقق منه مقابل هيكل في البيان - واجهة FFI
+/// Load a config file and validate it against an AlBayan struct; `schema` is a `.ab` file or
+/// source defining `struct_name`. Returns 1 on success, 0 otherwise (see `albayan_rt_config_error`) - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_config_load(
+    name: *const c_char,
+    path: *const c_char,
+    schema: *const c_char,
+    struct_name: *const c_char,
+) -> c_int {
+    let (Some(name), Some(path), Some(schema), Some(struct_name)) =
+        (c_str(name), c_str(path), c_str(schema), c_str(struct_name)) else {
+        return 0;
+    };
+
+    let schema_source = if std::path::Path::new(schema).is_file() {
+        match std::fs::read_to_string(schema) {
+            Ok(source) => source,
+            Err(e) => {
+                loaded_configs().insert(name.to_string(), Err(format!("Cannot read schema '{}': {}", schema, e)));
+                return 0;
+            }
+        }
+    } else {
+        schema.to_string()
+    };
+
+    let result = ConfigSchema::from_source(&schema_source, struct_name)
+        .and_then(|schema| schema.load(path))
+        .map_err(|e| e.to_string());
+    let loaded = result.is_ok();
+    loaded_configs().insert(name.to_string(), result);
+    loaded as c_int
+}
+
+/// رسالة خطأ آخر تحميل للإعدادات (فارغة عند النجاح) - واجهة FFI
+/// Error listing every missing or mistyped key of the last load, or "" - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_config_error(name: *const c_char) -> *mut c_char {
+    let message = match c_str(name).and_then(|name| loaded_configs().get(name).cloned()) {
+        Some(Err(message)) => message,
+        Some(Ok(_)) => String::new(),
+        None => "config was not loaded".to_string(),
+    };
+    CString::new(message.replace('\0', "")).map(CString::into_raw).unwrap_or(std::ptr::null_mut())
+}
+
+/// قراءة عدد صحيح من الإعدادات (0 إن لم يوجد) - واجهة FFI
+#[no_mangle]
+pub extern "C" fn albayan_rt_config_get_int(name: *const c_char, key: *const c_char) -> i64 {
+    config_value(name, key).and_then(|value| value.as_i64()).unwrap_or(0)
+}
+
+/// قراءة عدد عشري من الإعدادات (0.0 إن لم يوجد) - واجهة FFI
+#[no_mangle]
+pub extern "C" fn albayan_rt_config_get_float(name: *const c_char, key: *const c_char) -> f64 {
+    config_value(name, key).and_then(|value| value.as_f64()).unwrap_or(0.0)
+}
+
+/// قراءة قيمة منطقية من الإعدادات - واجهة FFI
+#[no_mangle]
+pub extern "C" fn albayan_rt_config_get_bool(name: *const c_char, key: *const c_char) -> c_int {
+    config_value(name, key).and_then(|value| value.as_bool()).unwrap_or(false) as c_int
+}
+
+/// قراءة نص من الإعدادات (فارغ إن لم يوجد)؛ يحرر بـ albayan_rt_builtin_free_string - واجهة FFI
+#[no_mangle]
+pub extern "C" fn albayan_rt_config_get_string(name: *const c_char, key: *const c_char) -> *mut c_char {
+    let text = config_value(name, key)
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default();
+    CString::new(text.replace('\0', "")).map(CString::into_raw).unwrap_or(std::ptr::null_mut())
+}
+
+/// عدد عناصر قائمة في الإعدادات (-1 إن لم تكن قائمة) - واجهة FFI
+#[no_mangle]
+pub extern "C" fn albayan_rt_config_len(name: *const c_char, key: *const c_char) -> c_int {
+    config_value(name, key)
+        .and_then(|value| value.as_array().map(|items| items.len() as c_int))
+        .unwrap_or(-1)
+}
+
+/// قراءة ملف CSV وحفظه باسم؛ تُعاد عدد الصفوف أو -1 - واجهة FFI
+/// Read a CSV file into the named table; returns the row count or -1 - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_data_read_csv(name: *const c_char, path: *const c_char) -> c_int {
+    let (Some(name), Some(path)) = (c_str(name), c_str(path)) else {
+        return -1;
+    };
+    match DataTable::read_csv(path) {
+        Ok(table) => {
+            let rows = table.rows.len() as c_int;
+            data_tables().insert(name.to_string(), table);
+            rows
+        }
+        Err(_) => -1,
+    }
+}
+
+/// كتابة جدول محفوظ إلى ملف CSV - واجهة FFI
+#[no_mangle]
+pub extern "C" fn albayan_rt_data_write_csv(name: *const c_char, path: *const c_char) -> c_int {
+    let (Some(name), Some(path)) = (c_str(name), c_str(path)) else {
+        return 0;
+    };
+    match data_tables().get(name) {
+        Some(table) => table.write_csv(path).is_ok() as c_int,
+        None => 0,
+    }
+}
+
+/// عدد صفوف جدول محفوظ (-1 إن لم يوجد) - واجهة FFI
+#[no_mangle]
+pub extern "C" fn albayan_rt_data_row_count(name: *const c_char) -> c_int {
+    c_str(name)
+        .and_then(|name| data_tables().get(name).map(|table| table.rows.len() as c_int))
+        .unwrap_or(-1)
+}
+
+/// قيمة خلية بالصف واسم العمود (فارغة إن لم توجد)؛ تحرر بـ albayan_rt_builtin_free_string - واجهة FFI
+#[no_mangle]
+pub extern "C" fn albayan_rt_data_get(name: *const c_char, row: c_int, column: *const c_char) -> *mut c_char {
+    let cell = (|| {
+        let tables = data_tables();
+        let table = tables.get(c_str(name)?)?;
+        let index = table.headers.iter().position(|header| Some(header.as_str()) == c_str(column))?;
+        table.rows.get(usize::try_from(row).ok()?).map(|cells| cells[index].clone())
+    })();
+    CString::new(cell.unwrap_or_default()).map(CString::into_raw).unwrap_or(std::ptr::null_mut())
+}
+
+/// إضافة صفوف جدول كحقائق relation(...) إلى قاعدة معرفة المشاهد؛ تُعاد عدد الحقائق أو -1 - واجهة FFI
+/// Assert every row of a table as a fact, queryable with albayan_rt_scene_query - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_data_assert_facts(name: *const c_char, relation: *const c_char) -> c_int {
+    let (Some(name), Some(relation)) = (c_str(name), c_str(relation)) else {
+        return -1;
+    };
+    let tables = data_tables();
+    let Some(table) = tables.get(name) else {
+        return -1;
+    };
+    match table.assert_facts(&mut scene_logic(), relation) {
+        Ok(count) => count as c_int,
+        Err(_) => -1,
+    }
+}
+
+/// تعريف أنواع معاملات علاقة بصيغة "string, int" للتحقق منها عند التحميل الجماعي - واجهة FFI
+#[no_mangle]
+pub extern "C" fn albayan_rt_data_declare_relation(relation: *const c_char, types: *const c_char) -> c_int {
+    let (Some(relation), Some(types)) = (c_str(relation), c_str(types)) else {
+        return 0;
+    };
+    let types: Vec<&str> = types.split(',').map(str::trim).filter(|ty| !ty.is_empty()).collect();
+    scene_logic().declare_relation(relation, &types);
+    1
+}
+
+/// تحميل حقائق relation(...) من ملف CSV أو JSON دفعة واحدة؛ تُعاد عدد الحقائق أو -1 - واجهة FFI
+/// Bulk-load facts from a CSV or JSON file, validated against the declared relation - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_data_load_facts(relation: *const c_char, path: *const c_char) -> c_int {
+    let (Some(relation), Some(path)) = (c_str(relation), c_str(path)) else {
+        return -1;
+    };
+    match load_facts(&mut scene_logic(), relation, path, |_, _| {}) {
+        Ok(count) => count as c_int,
+        Err(_) => -1,
+    }
+}
+
+/// عينات "x1,y1;x2,y2;..." من عمودين، لتمريرها إلى دوال المعادلات - واجهة FFI
+/// Samples from two numeric columns in the format taken by albayan_rt_equation_* - FFI interface
+#[no_mangle]
+pub extern "C" fn albayan_rt_data_samples(name: *const c_char, x_column: *const c_char, y_column: *const c_char) -> *mut c_char {
+    let samples = (|| {
+        let tables = data_tables();
+        let samples = tables.get(c_str(name)?)?.samples(c_str(x_column)?, c_str(y_column)?).ok()?;
+        Some(samples.iter().map(|(x, y)| format!("{},{}", x, y)).collect::<Vec<_>>().join(";"))
+    })();
+    CString::new(samples.unwrap_or_default()).map(CString::into_raw).unwrap_or(std::ptr::null_mut())
+}
+
+/// تحليل نص JSON إلى قيمة متداخلة (null عند الخطأ)؛ تحرر بـ albayan_rt_value_free - واجهة FFI
+#[no_mangle]
+pub extern "C" fn albayan_rt_data_parse_json(text: *const c_char) -> AlbayanValue {
+    c_str(text)
+        .and_then(|text| parse_json(text).ok())
+        .unwrap_or_else(AlbayanValue::new_null)
+}
+
+/// تحويل قيمة إلى نص JSON (فارغ عند الخطأ)؛ يحرر بـ albayan_rt_builtin_free_string - واجهة FFI
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn albayan_rt_data_to_json(value: *const AlbayanValue) -> *mut c_char {
+    let json = unsafe { value.as_ref() }.and_then(|value| to_json(value).ok());
+    CString::new(json.unwrap_or_default()).map(CString::into_raw).unwrap_or(std::ptr::null_mut())
+}
+
+/// تدريب وكيل Q-learning على نموذج العالم في قاعدة المعرفة (state/action/start/transition/reward/terminal)؛
+/// يُنشأ الوكيل عند أول استخدام وتعاد متوسط المكافأة لكل حلقة أو NaN عند الخطأ - واجهة FFI
+/// Train the Q-learning agent `agent` for `episodes` episodes of at most `max_steps` steps on the world
+/// model in the knowledge base; returns the mean episode reward, or NaN - FFI interface
+#[cfg(feature = "ai")]
+#[no_mangle]
+pub extern "C" fn albayan_rt_rl_train(agent: *const c_char, episodes: c_int, max_steps: c_int) -> f64 {
+    let (Some(name), Ok(episodes), Ok(max_steps)) = (c_str(agent), usize::try_from(episodes), usize::try_from(max_steps)) else {
+        return f64::NAN;
+    };
+    let mut logic = scene_logic();
+    let Ok(mut world) = KnowledgeEnvironment::new(&mut logic) else {
+        return f64::NAN;
+    };
+    let mut agents = rl_agents();
+    let agent = agents.entry(name.to_string()).or_insert_with(|| QLearning::new(world.action_count()));
+    let mut total = 0.0;
+    for _ in 0..episodes {
+        match agent.train_episode(&mut world, max_steps) {
+            Ok(episode) => total += episode.total_reward as f64,
+            Err(_) => return f64::NAN,
+        }
+    }
+    total / episodes.max(1) as f64
+}
+
+/// أفضل فعل تعلمه الوكيل في حالة معينة (نص فارغ إن لم يوجد) - واجهة FFI
+/// The best action the agent has learned for `state`, or an empty string - FFI interface
+#[cfg(feature = "ai")]
+#[no_mangle]
+pub extern "C" fn albayan_rt_rl_best_action(agent: *const c_char, state: *const c_char) -> *mut c_char {
+    let action = (|| {
+        let mut logic = scene_logic();
+        let world = KnowledgeEnvironment::new(&mut logic).ok()?;
+        let observation = world.observation_of(c_str(state)?)?;
+        let agents = rl_agents();
+        let agent = agents.get(c_str(agent)?)?;
+        world.actions().get(agent.best_action(&observation)).cloned()
+    })();
+    CString::new(action.unwrap_or_default()).map(CString::into_raw).unwrap_or(std::ptr::null_mut())
+}
+
+/// نسيان ما تعلمه الوكيل - واجهة FFI
+/// Forget the agent; returns 1 if it existed - FFI interface
+#[cfg(feature = "ai")]
+#[no_mangle]
+pub extern "C" fn albayan_rt_rl_reset(agent: *const c_char) -> c_int {
+    c_str(agent).is_some_and(|name| rl_agents().remove(name).is_some()) as c_int
+}
+
+#[cfg(all(test, feature = "render"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_animation_file_name_stays_in_the_output_directory() {
+        assert_eq!(animation_file_name("circle").as_deref(), Some("circle.gif"));
+        assert_eq!(animation_file_name("موجة").as_deref(), Some("موجة.gif"));
+        for name in ["", ".", "..", "../escape", "nested/name", "trailing/", "/etc/passwd", "..\\escape"] {
+            assert_eq!(animation_file_name(name), None, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,296 @@
+// إدارة ذاكرة المكتبات المدمجة - تقدير الاستخدام وسياسات الاحتفاظ بالسجلات
+// Memory Accounting - Built-in Library Memory Budget for AlBayan Language
+
+use std::collections::HashMap;
+use std::mem::{size_of, size_of_val};
+use serde::{Deserialize, Serialize};
+use super::adaptive_equations::{AdaptationEvent, GeneralShapeEquation, GeneralizedSigmoidComponent, LinearComponent};
+use super::expert_explorer::{DecisionRecord, ExpertExplorer};
+use super::thinking_core::{LayerDatabase, LearningEvent, ThinkingCore};
+
+/// عدد أحداث الحذف الأخيرة المحفوظة في المحاسبة
+const RECENT_EVICTIONS: usize = 32;
+
+/// تقدير تقريبي لحجم البيانات في الذاكرة بالبايت
+pub trait MemoryFootprint {
+    fn memory_footprint(&self) -> usize;
+}
+
+/// سياسة الاحتفاظ بسجل: عند تجاوز `max_records` تُحذف أقدم السجلات حتى يبقى `retain_records`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub max_records: usize,
+    pub retain_records: usize,
+}
+
+/// سياسات الاحتفاظ لكل نوع من السجلات
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryRetention {
+    pub learning_history: RetentionPolicy,   // سجل تعلم طبقات النواة التفكيرية
+    pub decision_history: RetentionPolicy,   // سجل قرارات الخبير/المستكشف
+    pub adaptation_history: RetentionPolicy, // سجل تكيف المعادلات
+}
+
+/// نوع السجل
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HistoryKind {
+    Learning,
+    Decision,
+    Adaptation,
+}
+
+/// سبب حذف السجلات
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvictionReason {
+    Retention, // تجاوز سياسة الاحتفاظ
+    Budget,    // تجاوز ميزانية الذاكرة
+}
+
+/// حدث حذف سجلات لتحرير الذاكرة
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvictionEvent {
+    pub timestamp: u64,
+    pub owner: String, // اسم النواة أو النظام أو المعادلة
+    pub history: HistoryKind,
+    pub reason: EvictionReason,
+    pub records: usize,
+    pub bytes_freed: usize,
+}
+
+/// الاستخدام المقدر للذاكرة لكل نوع من المكونات
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    pub thinking_cores: usize,
+    pub expert_explorers: usize,
+    pub equations: usize,
+}
+
+/// محاسبة الذاكرة في مدير المكتبات: الذروة وإحصائيات الحذف
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryAccounting {
+    pub peak_usage: usize,
+    pub eviction_events: u64,
+    pub evicted_records: u64,
+    pub bytes_freed: u64,
+    pub recent_evictions: Vec<EvictionEvent>,
+}
+
+impl RetentionPolicy {
+    pub fn new(max_records: usize, retain_records: usize) -> Self {
+        Self { max_records, retain_records: retain_records.min(max_records) }
+    }
+
+    /// عدد أقدم السجلات التي يجب حذفها من سجل بهذا الطول
+    pub fn excess(&self, len: usize) -> usize {
+        if len > self.max_records {
+            len - self.retain_records.min(self.max_records)
+        } else {
+            0
+        }
+    }
+
+    pub fn policy_for(retention: &HistoryRetention, kind: HistoryKind) -> Self {
+        match kind {
+            HistoryKind::Learning => retention.learning_history,
+            HistoryKind::Decision => retention.decision_history,
+            HistoryKind::Adaptation => retention.adaptation_history,
+        }
+    }
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        // القيم السابقة المثبتة في cleanup_memory: الحد 1000 والإبقاء على آخر 500
+        Self {
+            learning_history: RetentionPolicy::new(1000, 500),
+            decision_history: RetentionPolicy::new(1000, 500),
+            adaptation_history: RetentionPolicy::new(1000, 500),
+        }
+    }
+}
+
+impl EvictionEvent {
+    pub fn new(owner: String, history: HistoryKind, reason: EvictionReason, records: usize, bytes_freed: usize) -> Self {
+        Self {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0),
+            owner,
+            history,
+            reason,
+            records,
+            bytes_freed,
+        }
+    }
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.thinking_cores + self.expert_explorers + self.equations
+    }
+}
+
+impl MemoryAccounting {
+    /// تسجيل قياس جديد للاستخدام
+    pub fn observe(&mut self, usage: usize) {
+        self.peak_usage = self.peak_usage.max(usage);
+    }
+
+    /// تسجيل حدث حذف، مع إبقاء آخر الأحداث فقط
+    pub fn record(&mut self, event: EvictionEvent) {
+        self.eviction_events += 1;
+        self.evicted_records += event.records as u64;
+        self.bytes_freed += event.bytes_freed as u64;
+        self.recent_evictions.push(event);
+        if self.recent_evictions.len() > RECENT_EVICTIONS {
+            let excess = self.recent_evictions.len() - RECENT_EVICTIONS;
+            self.recent_evictions.drain(0..excess);
+        }
+    }
+}
+
+/// حذف أقدم `count` سجلات، وإرجاع الحجم المحرر بالبايت
+pub fn evict_oldest<T: MemoryFootprint>(history: &mut Vec<T>, count: usize) -> usize {
+    let count = count.min(history.len());
+    history.drain(0..count).map(|record| record.memory_footprint()).sum()
+}
+
+fn floats(values: &[f64]) -> usize {
+    values.len() * size_of::<f64>()
+}
+
+fn map_entries<K, V>(map: &HashMap<K, V>) -> usize {
+    map.len() * (size_of::<K>() + size_of::<V>())
+}
+
+fn history<T: MemoryFootprint>(records: &[T]) -> usize {
+    records.iter().map(MemoryFootprint::memory_footprint).sum()
+}
+
+impl MemoryFootprint for LearningEvent {
+    fn memory_footprint(&self) -> usize {
+        size_of::<Self>() + floats(&self.input_pattern) + map_entries(&self.layer_contribution)
+    }
+}
+
+impl MemoryFootprint for DecisionRecord {
+    fn memory_footprint(&self) -> usize {
+        let paths: usize = self.exploration_paths.iter()
+            .map(|path| {
+                size_of_val(path) + path.path_id.len()
+                    + path.exploration_steps.iter()
+                        .map(|step| size_of_val(step) + floats(&step.input_transformation))
+                        .sum::<usize>()
+            })
+            .sum();
+        let reasoning: usize = self.expert_reasoning.iter().map(|line| size_of::<String>() + line.len()).sum();
+        size_of::<Self>() + floats(&self.input_data) + paths + reasoning
+    }
+}
+
+impl MemoryFootprint for AdaptationEvent {
+    fn memory_footprint(&self) -> usize {
+        let changes: usize = self.parameter_changes.keys().map(|name| size_of::<(String, f64)>() + name.len()).sum();
+        size_of::<Self>() + floats(&self.input_data) + changes
+    }
+}
+
+impl MemoryFootprint for LayerDatabase {
+    fn memory_footprint(&self) -> usize {
+        let knowledge: usize = self.knowledge_base.keys().map(|key| size_of::<(String, f64)>() + key.len()).sum();
+        let patterns: usize = self.pattern_memory.iter().map(|pattern| size_of::<Vec<f64>>() + floats(pattern)).sum();
+        let parameters: usize = self.optimization_parameters.keys().map(|key| size_of::<(String, f64)>() + key.len()).sum();
+        size_of::<Self>() + knowledge + patterns + history(&self.learning_history) + parameters
+    }
+}
+
+impl MemoryFootprint for ThinkingCore {
+    fn memory_footprint(&self) -> usize {
+        let layers: usize = self.layers.values()
+            .map(|layer| {
+                size_of_val(layer) + floats(&layer.current_state.processing_data)
+                    + map_entries(&layer.current_state.connections)
+            })
+            .sum();
+        let databases: usize = self.databases.values().map(MemoryFootprint::memory_footprint).sum();
+        size_of::<Self>() + layers + databases
+    }
+}
+
+impl MemoryFootprint for ExpertExplorer {
+    fn memory_footprint(&self) -> usize {
+        size_of::<Self>()
+            + history(&self.decision_history)
+            + self.thinking_core.as_ref().map_or(0, MemoryFootprint::memory_footprint)
+    }
+}
+
+impl MemoryFootprint for GeneralShapeEquation {
+    fn memory_footprint(&self) -> usize {
+        size_of::<Self>()
+            + self.sigmoid_components.len() * size_of::<GeneralizedSigmoidComponent>()
+            + self.linear_components.len() * size_of::<LinearComponent>()
+            + history(&self.adaptation_history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_libraries::{AdaptationType, BuiltinLibraryManager};
+
+    #[test]
+    fn test_retention_policy_excess() {
+        let policy = RetentionPolicy::new(10, 4);
+        assert_eq!(policy.excess(10), 0);
+        assert_eq!(policy.excess(11), 7);
+        assert_eq!(RetentionPolicy::new(5, 50).excess(6), 1);
+    }
+
+    #[test]
+    fn test_budget_evicts_oldest_history_and_reports_it() {
+        let mut manager = BuiltinLibraryManager::new();
+        manager.create_adaptive_equation("line".to_string()).unwrap();
+        manager.equations.get_mut("line").unwrap().add_linear_component(1.0, 0.0, 1.0);
+
+        for i in 0..200 {
+            manager.update_equation("line", i as f64, 2.0 * i as f64, AdaptationType::Gradient).unwrap();
+        }
+        let unbounded = manager.memory_usage().total();
+        assert_eq!(manager.get_performance_stats().eviction_events, 0);
+
+        manager.global_config.max_memory_usage = unbounded / 4;
+        manager.update_equation("line", 1.0, 2.0, AdaptationType::Gradient).unwrap();
+
+        let equation = &manager.equations["line"];
+        assert!(manager.memory_usage().total() <= unbounded / 4);
+        assert!(equation.adaptation_history.len() < 200);
+        assert_eq!(equation.history_summary.total_samples, 201);
+        assert_eq!(equation.history_summary.evicted_events, 201 - equation.adaptation_history.len() as u64);
+
+        let stats = manager.get_performance_stats();
+        assert!(stats.eviction_events > 0);
+        assert_eq!(stats.recent_evictions[0].reason, EvictionReason::Budget);
+        assert_eq!(stats.recent_evictions[0].history, HistoryKind::Adaptation);
+        assert!(stats.peak_memory_usage >= unbounded);
+    }
+
+    #[test]
+    fn test_cleanup_applies_configured_retention() {
+        let mut manager = BuiltinLibraryManager::new();
+        manager.create_adaptive_equation("line".to_string()).unwrap();
+        for i in 0..30 {
+            manager.update_equation("line", i as f64, 1.0, AdaptationType::Gradient).unwrap();
+        }
+        assert_eq!(manager.equations["line"].adaptation_history.len(), 30);
+
+        manager.global_config.history_retention.adaptation_history = RetentionPolicy::new(20, 5);
+        let events = manager.cleanup_memory();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].records, 25);
+        assert_eq!(events[0].reason, EvictionReason::Retention);
+        assert_eq!(manager.equations["line"].adaptation_history.len(), 5);
+    }
+}
@@ -3,14 +3,31 @@
 
 pub mod thinking_core;
 pub mod expert_explorer;
+pub mod risk_policy;
 pub mod adaptive_equations;
 pub mod artistic_renderer;
+#[cfg(feature = "render")]
+pub mod animation;
+#[cfg(feature = "render")]
+pub mod canvas;
 pub mod shape_inference;
+#[cfg(feature = "render")]
+pub mod shape_dataset;
+pub mod scene_facts;
+pub mod scene_render;
+pub mod shape_constraints;
+pub mod model_selection;
+pub mod config;
+pub mod data;
+pub mod error;
+pub mod memory;
+pub mod ffi;
 
 // إعادة تصدير الهياكل الرئيسية للوصول السهل
 pub use thinking_core::{
     ThinkingCore, ThinkingLayer, LayerType, AnalysisResult,
-    AnalysisDomain, ProcessingMode, GlobalThinkingState
+    AnalysisDomain, ProcessingMode, GlobalThinkingState,
+    LayerConfig, PipelineConfig, PipelineBuilder
 };
 
 pub use expert_explorer::{
@@ -18,9 +35,21 @@ pub use expert_explorer::{
     DecisionRecord, ExplorationPath, RiskAssessment
 };
 
+pub use risk_policy::{
+    RiskPolicy, RiskPolicyConfig, RiskThresholds, ExplorationSchedule, RiskLevel,
+    RiskContext, RiskScoringPlugin
+};
+
 pub use adaptive_equations::{
     GeneralShapeEquation, GeneralizedSigmoidComponent, LinearComponent,
-    ComplexNumber, AdaptationType, OptimizationStrategy, EvaluationResult
+    ComplexNumber, AdaptationType, OptimizationStrategy, EvaluationResult,
+    OnlineAdaptationConfig, HistorySummary, DriftDetector, StructureChange, UpdateResult,
+    DEFAULT_PRECISION, approx_eq, round_to_precision, format_float, stable_json
+};
+
+pub use model_selection::{
+    CrossValidationResult, HyperParameters, SearchSpace, SearchMethod,
+    CandidateScore, SelectionReport, ModelSelector
 };
 
 pub use artistic_renderer::{
@@ -28,11 +57,52 @@ pub use artistic_renderer::{
     RenderedImage, RenderingEngine, PropertyTransform
 };
 
+#[cfg(feature = "render")]
+pub use animation::{
+    AnimatedParameter, Easing, ParameterRange, AnimationSpec, AnimationSequence
+};
+
+#[cfg(feature = "render")]
+pub use canvas::{
+    Canvas, CanvasMessage, CanvasFrame, CanvasSink, FrameSource, JsonLinesSink, HttpCanvasServer,
+    CANVAS_PROTOCOL_VERSION
+};
+
 pub use shape_inference::{
     ShapeInference, InferenceResult, ImageFeatures, FeatureExtractor,
     ConfidenceEvaluator, EquationGenerator
 };
 
+#[cfg(feature = "render")]
+pub use shape_dataset::{
+    DatasetConfig, LabeledShape, ShapeDataset, ClassAccuracy, EvaluationReport
+};
+
+pub use scene_facts::{
+    BoundingBox, SceneObject, Scene, SceneExtractor
+};
+
+pub use scene_render::{
+    ObjectSpec, SpatialRelation, SceneDescription, SceneRenderer
+};
+
+pub use shape_constraints::{
+    PropertyRef, PropertyConstraint, ConstrainedShape, ConstraintConflict, ConstraintError
+};
+
+pub use config::{
+    ConfigFormat, ConfigType, ConfigField, ConfigSchema, ConfigIssue, ConfigError
+};
+
+pub use data::DataTable;
+
+pub use error::{BuiltinError, ComponentKind};
+
+pub use memory::{
+    MemoryFootprint, RetentionPolicy, HistoryRetention, HistoryKind, EvictionReason,
+    EvictionEvent, MemoryUsage, MemoryAccounting
+};
+
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
@@ -43,6 +113,21 @@ pub struct BuiltinLibraryManager {
     pub expert_explorers: HashMap<String, ExpertExplorer>,
     pub equations: HashMap<String, GeneralShapeEquation>,
     pub global_config: GlobalLibraryConfig,
+    /// خطوط المعالجة المخصصة المسجلة بالاسم
+    pub custom_pipelines: HashMap<String, PipelineConfig>,
+    /// محاسبة الذاكرة: الذروة وأحداث حذف السجلات
+    pub memory: MemoryAccounting,
+}
+
+/// الحالة المحفوظة للمكتبات
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedLibraryState {
+    pub global_config: GlobalLibraryConfig,
+    #[serde(default)]
+    pub custom_pipelines: HashMap<String, PipelineConfig>,
+    /// خط المعالجة الحالي لكل نواة تفكيرية
+    #[serde(default)]
+    pub core_pipelines: HashMap<String, PipelineConfig>,
 }
 
 /// إعدادات المكتبات العامة
@@ -54,6 +139,9 @@ pub struct GlobalLibraryConfig {
     pub default_precision: f64,
     pub max_memory_usage: usize,
     pub performance_monitoring: bool,
+    /// سياسات الاحتفاظ بسجلات التعلم والقرارات والتكيف
+    #[serde(default)]
+    pub history_retention: HistoryRetention,
 }
 
 /// نتيجة عملية مدمجة
@@ -75,6 +163,19 @@ pub struct PerformanceStats {
     pub average_execution_time: f64,
     pub peak_memory_usage: usize,
     pub error_rate: f64,
+    #[serde(default)]
+    pub current_memory_usage: usize,
+    #[serde(default)]
+    pub memory_budget: usize,
+    #[serde(default)]
+    pub eviction_events: u64,
+    #[serde(default)]
+    pub evicted_records: u64,
+    #[serde(default)]
+    pub bytes_freed: u64,
+    /// آخر أحداث حذف السجلات
+    #[serde(default)]
+    pub recent_evictions: Vec<EvictionEvent>,
 }
 
 impl BuiltinLibraryManager {
@@ -85,13 +186,15 @@ impl BuiltinLibraryManager {
             expert_explorers: HashMap::new(),
             equations: HashMap::new(),
             global_config: GlobalLibraryConfig::default(),
+            custom_pipelines: HashMap::new(),
+            memory: MemoryAccounting::default(),
         }
     }
 
     /// إنشاء نواة تفكيرية جديدة
-    pub fn create_thinking_core(&mut self, name: String) -> Result<(), String> {
+    pub fn create_thinking_core(&mut self, name: String) -> Result<(), BuiltinError> {
         if self.thinking_cores.contains_key(&name) {
-            return Err(format!("Thinking core '{}' already exists", name));
+            return Err(BuiltinError::already_exists(ComponentKind::ThinkingCore, &name));
         }
 
         let thinking_core = ThinkingCore::new();
@@ -100,9 +203,9 @@ impl BuiltinLibraryManager {
     }
 
     /// إنشاء نظام خبير/مستكشف جديد
-    pub fn create_expert_explorer(&mut self, name: String) -> Result<(), String> {
+    pub fn create_expert_explorer(&mut self, name: String) -> Result<(), BuiltinError> {
         if self.expert_explorers.contains_key(&name) {
-            return Err(format!("Expert explorer '{}' already exists", name));
+            return Err(BuiltinError::already_exists(ComponentKind::ExpertExplorer, &name));
         }
 
         let expert_explorer = ExpertExplorer::new();
@@ -111,24 +214,72 @@ impl BuiltinLibraryManager {
     }
 
     /// إنشاء معادلة تكيفية جديدة
-    pub fn create_adaptive_equation(&mut self, name: String) -> Result<(), String> {
+    pub fn create_adaptive_equation(&mut self, name: String) -> Result<(), BuiltinError> {
         if self.equations.contains_key(&name) {
-            return Err(format!("Equation '{}' already exists", name));
+            return Err(BuiltinError::already_exists(ComponentKind::Equation, &name));
         }
 
-        let equation = GeneralShapeEquation::new();
+        let equation = GeneralShapeEquation::new().with_precision(self.global_config.default_precision);
         self.equations.insert(name, equation);
         Ok(())
     }
 
+    /// تعيين الدقة العددية الافتراضية وتطبيقها على كل المعادلات
+    pub fn set_default_precision(&mut self, precision: f64) -> Result<(), BuiltinError> {
+        if !(precision > 0.0 && precision.is_finite()) {
+            return Err(BuiltinError::InvalidPrecision(precision));
+        }
+        self.global_config.default_precision = precision;
+        for equation in self.equations.values_mut() {
+            equation.global_parameters.precision = precision;
+        }
+        Ok(())
+    }
+
+    /// تسجيل خط معالجة مخصص بعد التحقق من صحته
+    pub fn register_pipeline(&mut self, pipeline: PipelineConfig) -> Result<(), BuiltinError> {
+        pipeline.validate().map_err(BuiltinError::InvalidPipeline)?;
+        self.custom_pipelines.insert(pipeline.name.clone(), pipeline);
+        Ok(())
+    }
+
+    /// تطبيق خط معالجة مسجل على نواة تفكيرية
+    pub fn apply_pipeline(&mut self, core_name: &str, pipeline_name: &str) -> Result<(), BuiltinError> {
+        let pipeline = self.custom_pipelines.get(pipeline_name)
+            .ok_or_else(|| BuiltinError::not_found(ComponentKind::Pipeline, pipeline_name))?;
+        let thinking_core = self.thinking_cores.get_mut(core_name)
+            .ok_or_else(|| BuiltinError::not_found(ComponentKind::ThinkingCore, core_name))?;
+
+        thinking_core.apply_pipeline(pipeline).map_err(BuiltinError::InvalidPipeline)
+    }
+
+    /// تحميل سياسات المخاطر لنظام خبير/مستكشف من ملف JSON أو TOML
+    pub fn load_risk_policies(&mut self, explorer_name: &str, file_path: &str) -> Result<(), BuiltinError> {
+        let explorer = self.expert_explorers.get_mut(explorer_name)
+            .ok_or_else(|| BuiltinError::not_found(ComponentKind::ExpertExplorer, explorer_name))?;
+
+        explorer.load_risk_policies(file_path).map_err(BuiltinError::InvalidRiskPolicy)
+    }
+
+    /// تسجيل إضافة لحساب المخاطر في نظام خبير/مستكشف
+    pub fn register_risk_plugin(&mut self,
+                                explorer_name: &str,
+                                plugin: std::sync::Arc<dyn RiskScoringPlugin>) -> Result<(), BuiltinError> {
+        let explorer = self.expert_explorers.get_mut(explorer_name)
+            .ok_or_else(|| BuiltinError::not_found(ComponentKind::ExpertExplorer, explorer_name))?;
+
+        explorer.register_risk_plugin(plugin);
+        Ok(())
+    }
+
     /// ربط نواة تفكيرية مع نظام خبير/مستكشف
-    pub fn connect_thinking_to_explorer(&mut self, thinking_name: &str, explorer_name: &str) -> Result<(), String> {
+    pub fn connect_thinking_to_explorer(&mut self, thinking_name: &str, explorer_name: &str) -> Result<(), BuiltinError> {
         let thinking_core = self.thinking_cores.get(thinking_name)
-            .ok_or_else(|| format!("Thinking core '{}' not found", thinking_name))?
+            .ok_or_else(|| BuiltinError::not_found(ComponentKind::ThinkingCore, thinking_name))?
             .clone();
 
         let explorer = self.expert_explorers.get_mut(explorer_name)
-            .ok_or_else(|| format!("Expert explorer '{}' not found", explorer_name))?;
+            .ok_or_else(|| BuiltinError::not_found(ComponentKind::ExpertExplorer, explorer_name))?;
 
         explorer.thinking_core = Some(thinking_core);
         Ok(())
@@ -138,20 +289,21 @@ impl BuiltinLibraryManager {
     pub fn comprehensive_analysis(&mut self,
                                 thinking_name: &str,
                                 explorer_name: &str,
-                                input_data: Vec<f64>) -> Result<BuiltinOperationResult, String> {
+                                input_data: Vec<f64>) -> Result<BuiltinOperationResult, BuiltinError> {
         let start_time = std::time::Instant::now();
+        let usage_before = self.memory_usage().total();
 
         // تحليل بالنواة التفكيرية
         let thinking_result = {
             let thinking_core = self.thinking_cores.get_mut(thinking_name)
-                .ok_or_else(|| format!("Thinking core '{}' not found", thinking_name))?;
+                .ok_or_else(|| BuiltinError::not_found(ComponentKind::ThinkingCore, thinking_name))?;
             thinking_core.analyze(input_data.clone())
         };
 
         // قرار بنظام الخبير/المستكشف
         let decision_result = {
             let explorer = self.expert_explorers.get_mut(explorer_name)
-                .ok_or_else(|| format!("Expert explorer '{}' not found", explorer_name))?;
+                .ok_or_else(|| BuiltinError::not_found(ComponentKind::ExpertExplorer, explorer_name))?;
             explorer.make_decision(input_data)
         };
 
@@ -169,7 +321,9 @@ impl BuiltinLibraryManager {
                 "final_decision": decision_result.final_decision,
                 "chosen_mode": decision_result.chosen_mode,
                 "confidence_level": decision_result.confidence_level,
-                "recommendation": decision_result.recommendation
+                "recommendation": decision_result.recommendation,
+                "risk_policy": decision_result.risk_assessment.policy_name,
+                "overall_risk": decision_result.risk_assessment.overall_risk
             }
         });
 
@@ -178,7 +332,7 @@ impl BuiltinLibraryManager {
             success: true,
             result_data: combined_result,
             execution_time,
-            memory_used: 0, // سيتم حسابها في التطبيق الفعلي
+            memory_used: self.account_memory(usage_before),
             error_message: None,
         })
     }
@@ -187,27 +341,101 @@ impl BuiltinLibraryManager {
     pub fn train_equation(&mut self,
                          equation_name: &str,
                          training_data: Vec<(f64, f64)>,
-                         adaptation_type: AdaptationType) -> Result<BuiltinOperationResult, String> {
+                         adaptation_type: AdaptationType) -> Result<BuiltinOperationResult, BuiltinError> {
         let start_time = std::time::Instant::now();
+        let usage_before = self.memory_usage().total();
 
         let equation = self.equations.get_mut(equation_name)
-            .ok_or_else(|| format!("Equation '{}' not found", equation_name))?;
+            .ok_or_else(|| BuiltinError::not_found(ComponentKind::Equation, equation_name))?;
 
         equation.train(training_data, adaptation_type);
 
         let execution_time = start_time.elapsed().as_millis() as u64;
 
-        let result_data = serde_json::json!({
+        let mut result_data = serde_json::json!({
             "equation_name": equation_name,
             "training_completed": true,
             "performance_metrics": equation.performance_metrics
         });
+        stable_json(&mut result_data, equation.global_parameters.precision);
 
         Ok(BuiltinOperationResult {
             operation_type: "equation_training".to_string(),
             success: true,
             result_data,
             execution_time,
+            memory_used: self.account_memory(usage_before),
+            error_message: None,
+        })
+    }
+
+    /// تحديث معادلة تكيفية بعينة واحدة (تعلم مستمر)
+    pub fn update_equation(&mut self,
+                          equation_name: &str,
+                          input_value: f64,
+                          target_value: f64,
+                          adaptation_type: AdaptationType) -> Result<BuiltinOperationResult, BuiltinError> {
+        let start_time = std::time::Instant::now();
+        let usage_before = self.memory_usage().total();
+
+        let equation = self.equations.get_mut(equation_name)
+            .ok_or_else(|| BuiltinError::not_found(ComponentKind::Equation, equation_name))?;
+
+        let update_result = equation.update(input_value, target_value, adaptation_type);
+
+        let execution_time = start_time.elapsed().as_millis() as u64;
+
+        let mut result_data = serde_json::json!({
+            "equation_name": equation_name,
+            "update_result": update_result,
+            "history_summary": equation.history_summary,
+            "component_count": equation.component_count()
+        });
+        stable_json(&mut result_data, equation.global_parameters.precision);
+
+        Ok(BuiltinOperationResult {
+            operation_type: "equation_update".to_string(),
+            success: true,
+            result_data,
+            execution_time,
+            memory_used: self.account_memory(usage_before),
+            error_message: None,
+        })
+    }
+
+    /// التحقق المتقاطع لمعادلة تكيفية
+    pub fn cross_validate_equation(&self,
+                                  equation_name: &str,
+                                  data: &[(f64, f64)],
+                                  folds: usize,
+                                  adaptation_type: AdaptationType) -> Result<CrossValidationResult, BuiltinError> {
+        let equation = self.equations.get(equation_name)
+            .ok_or_else(|| BuiltinError::not_found(ComponentKind::Equation, equation_name))?;
+
+        model_selection::cross_validate(equation, data, folds, adaptation_type).map_err(BuiltinError::TrainingFailed)
+    }
+
+    /// اختيار أفضل نموذج للبيانات وحفظ المعادلة المدربة بالاسم المعطى
+    pub fn select_equation_model(&mut self,
+                                equation_name: &str,
+                                data: &[(f64, f64)],
+                                selector: &ModelSelector) -> Result<BuiltinOperationResult, BuiltinError> {
+        let start_time = std::time::Instant::now();
+
+        let (equation, report) = selector.fit_best(data).map_err(BuiltinError::TrainingFailed)?;
+        let equation = equation.with_precision(self.global_config.default_precision);
+        self.equations.insert(equation_name.to_string(), equation);
+
+        let execution_time = start_time.elapsed().as_millis() as u64;
+
+        Ok(BuiltinOperationResult {
+            operation_type: "equation_model_selection".to_string(),
+            success: true,
+            result_data: serde_json::json!({
+                "equation_name": equation_name,
+                "selection_report": report
+            }),
+            execution_time,
             memory_used: 0,
             error_message: None,
         })
@@ -216,23 +444,24 @@ impl BuiltinLibraryManager {
     /// تقييم معادلة
     pub fn evaluate_equation(&mut self,
                             equation_name: &str,
-                            input_value: f64) -> Result<BuiltinOperationResult, String> {
+                            input_value: f64) -> Result<BuiltinOperationResult, BuiltinError> {
         let start_time = std::time::Instant::now();
 
         let equation = self.equations.get(equation_name)
-            .ok_or_else(|| format!("Equation '{}' not found", equation_name))?;
+            .ok_or_else(|| BuiltinError::not_found(ComponentKind::Equation, equation_name))?;
 
         let evaluation_result = equation.evaluate(input_value);
 
         let execution_time = start_time.elapsed().as_millis() as u64;
 
-        let result_data = serde_json::json!({
+        let mut result_data = serde_json::json!({
             "input_value": input_value,
             "output_value": evaluation_result.output_value,
             "confidence_score": evaluation_result.confidence_score,
             "component_contributions": evaluation_result.component_contributions,
             "computational_cost": evaluation_result.computational_cost
         });
+        stable_json(&mut result_data, equation.global_parameters.precision);
 
         Ok(BuiltinOperationResult {
             operation_type: "equation_evaluation".to_string(),
@@ -246,48 +475,149 @@ impl BuiltinLibraryManager {
 
     /// إحصائيات الأداء العامة
     pub fn get_performance_stats(&self) -> PerformanceStats {
+        let current_memory_usage = self.memory_usage().total();
         PerformanceStats {
             total_operations: 0, // سيتم حسابها من السجلات
             successful_operations: 0,
             average_execution_time: 0.0,
-            peak_memory_usage: 0,
+            peak_memory_usage: self.memory.peak_usage.max(current_memory_usage),
             error_rate: 0.0,
+            current_memory_usage,
+            memory_budget: self.global_config.max_memory_usage,
+            eviction_events: self.memory.eviction_events,
+            evicted_records: self.memory.evicted_records,
+            bytes_freed: self.memory.bytes_freed,
+            recent_evictions: self.memory.recent_evictions.clone(),
+        }
+    }
+
+    /// الاستخدام المقدر للذاكرة لكل نوع من المكونات
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            thinking_cores: self.thinking_cores.values().map(MemoryFootprint::memory_footprint).sum(),
+            expert_explorers: self.expert_explorers.values().map(MemoryFootprint::memory_footprint).sum(),
+            equations: self.equations.values().map(MemoryFootprint::memory_footprint).sum(),
+        }
+    }
+
+    /// تنظيف الذاكرة بتطبيق سياسات الاحتفاظ المعتمدة
+    pub fn cleanup_memory(&mut self) -> Vec<EvictionEvent> {
+        let retention = self.global_config.history_retention.clone();
+        self.trim_histories(EvictionReason::Retention, |kind, len| {
+            RetentionPolicy::policy_for(&retention, kind).excess(len)
+        })
+    }
+
+    /// فرض ميزانية الذاكرة: تطبيق سياسات الاحتفاظ ثم حذف نصف أقدم السجلات
+    /// حتى يصبح الاستخدام ضمن `max_memory_usage` أو لا يبقى ما يُحذف
+    pub fn enforce_memory_budget(&mut self) -> Vec<EvictionEvent> {
+        let mut events = Vec::new();
+        if self.memory_usage().total() <= self.global_config.max_memory_usage {
+            return events;
+        }
+
+        events.extend(self.cleanup_memory());
+        while self.memory_usage().total() > self.global_config.max_memory_usage {
+            let evicted = self.trim_histories(EvictionReason::Budget, |_, len| len.div_ceil(2));
+            if evicted.is_empty() {
+                break;
+            }
+            events.extend(evicted);
         }
+        events
     }
 
-    /// تنظيف الذاكرة
-    pub fn cleanup_memory(&mut self) {
-        // تنظيف سجلات التاريخ القديمة
-        for thinking_core in self.thinking_cores.values_mut() {
+    /// حذف أقدم السجلات من كل سجل حسب العدد الذي تحدده `excess` لنوعه وطوله
+    fn trim_histories(&mut self,
+                      reason: EvictionReason,
+                      excess: impl Fn(HistoryKind, usize) -> usize) -> Vec<EvictionEvent> {
+        let mut events = Vec::new();
+
+        for (name, thinking_core) in &mut self.thinking_cores {
             for database in thinking_core.databases.values_mut() {
-                if database.learning_history.len() > 1000 {
-                    database.learning_history.drain(0..500); // الاحتفاظ بآخر 500 سجل
+                let count = excess(HistoryKind::Learning, database.learning_history.len());
+                if count > 0 {
+                    let freed = memory::evict_oldest(&mut database.learning_history, count);
+                    events.push(EvictionEvent::new(name.clone(), HistoryKind::Learning, reason, count, freed));
                 }
             }
         }
 
-        for explorer in self.expert_explorers.values_mut() {
-            if explorer.decision_history.len() > 1000 {
-                explorer.decision_history.drain(0..500);
+        for (name, explorer) in &mut self.expert_explorers {
+            let count = excess(HistoryKind::Decision, explorer.decision_history.len());
+            if count > 0 {
+                let freed = memory::evict_oldest(&mut explorer.decision_history, count);
+                events.push(EvictionEvent::new(name.clone(), HistoryKind::Decision, reason, count, freed));
             }
         }
 
-        for equation in self.equations.values_mut() {
-            if equation.adaptation_history.len() > 1000 {
-                equation.adaptation_history.drain(0..500);
+        for (name, equation) in &mut self.equations {
+            let count = excess(HistoryKind::Adaptation, equation.adaptation_history.len());
+            if count > 0 {
+                let freed = memory::evict_oldest(&mut equation.adaptation_history, count);
+                // الإحصائيات محفوظة في الملخص
+                equation.history_summary.evicted_events += count as u64;
+                events.push(EvictionEvent::new(name.clone(), HistoryKind::Adaptation, reason, count, freed));
             }
         }
+
+        for event in &events {
+            self.memory.record(event.clone());
+        }
+        events
+    }
+
+    /// تسجيل استخدام الذاكرة بعد عملية وفرض الميزانية عند تجاوزها،
+    /// وإرجاع الذاكرة التي أضافتها العملية
+    fn account_memory(&mut self, usage_before: usize) -> usize {
+        let usage = self.memory_usage().total();
+        self.memory.observe(usage);
+        if usage > self.global_config.max_memory_usage {
+            self.enforce_memory_budget();
+        }
+        usage.saturating_sub(usage_before)
     }
 
     /// حفظ حالة المكتبات
-    pub fn save_state(&self, file_path: &str) -> Result<(), String> {
-        // سيتم تطبيقها لاحقاً - حفظ الحالة في ملف
-        Ok(())
+    pub fn save_state(&self, file_path: &str) -> Result<(), BuiltinError> {
+        let state = SavedLibraryState {
+            global_config: self.global_config.clone(),
+            custom_pipelines: self.custom_pipelines.clone(),
+            core_pipelines: self.thinking_cores.iter()
+                .map(|(name, core)| (name.clone(), core.pipeline_config(name)))
+                .collect(),
+        };
+
+        // تقريب القيم إلى الدقة المعتمدة حتى يكون الملف متطابقاً بين المنصات
+        let mut value = serde_json::to_value(&state)
+            .map_err(|e| BuiltinError::SerializationFailed(e.to_string()))?;
+        stable_json(&mut value, self.global_config.default_precision);
+        let json = serde_json::to_string_pretty(&value)
+            .map_err(|e| BuiltinError::SerializationFailed(e.to_string()))?;
+        std::fs::write(file_path, json)
+            .map_err(|e| BuiltinError::io(file_path, e))
     }
 
     /// تحميل حالة المكتبات
-    pub fn load_state(&mut self, file_path: &str) -> Result<(), String> {
-        // سيتم تطبيقها لاحقاً - تحميل الحالة من ملف
+    pub fn load_state(&mut self, file_path: &str) -> Result<(), BuiltinError> {
+        let content = std::fs::read_to_string(file_path)
+            .map_err(|e| BuiltinError::io(file_path, e))?;
+        let state: SavedLibraryState = serde_json::from_str(&content)
+            .map_err(|e| BuiltinError::DeserializationFailed(e.to_string()))?;
+
+        for pipeline in state.custom_pipelines.values().chain(state.core_pipelines.values()) {
+            pipeline.validate().map_err(BuiltinError::InvalidPipeline)?;
+        }
+
+        for (name, pipeline) in &state.core_pipelines {
+            self.thinking_cores.entry(name.clone())
+                .or_insert_with(ThinkingCore::new)
+                .apply_pipeline(pipeline)
+                .map_err(BuiltinError::InvalidPipeline)?;
+        }
+        self.set_default_precision(state.global_config.default_precision)?;
+        self.global_config = state.global_config;
+        self.custom_pipelines = state.custom_pipelines;
         Ok(())
     }
 }
@@ -298,9 +628,10 @@ impl GlobalLibraryConfig {
             enable_thinking_core: true,
             enable_expert_explorer: true,
             enable_adaptive_equations: true,
-            default_precision: 1e-6,
+            default_precision: DEFAULT_PRECISION,
             max_memory_usage: 1024 * 1024 * 100, // 100 MB
             performance_monitoring: true,
+            history_retention: HistoryRetention::default(),
         }
     }
 }
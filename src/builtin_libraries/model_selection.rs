@@ -0,0 +1,342 @@
+// اختيار النماذج والتحقق المتقاطع للمعادلات التكيفية
+// Model Selection and Cross-Validation for Adaptive Equations
+
+use serde::{Deserialize, Serialize};
+
+use super::adaptive_equations::{
+    AdaptationType, ComplexNumber, GeneralShapeEquation, OptimizationStrategy,
+};
+
+/// نتيجة التحقق المتقاطع بطريقة k-fold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossValidationResult {
+    pub folds: usize,
+    pub fold_errors: Vec<f64>,  // متوسط الخطأ التربيعي لكل طية
+    pub mean_error: f64,
+    pub std_error: f64,
+}
+
+/// المعاملات الفائقة لمعادلة تكيفية
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HyperParameters {
+    pub sigmoid_components: usize,
+    pub linear_components: usize,
+    pub strategy: OptimizationStrategy,
+    pub adaptation_type: AdaptationType,
+}
+
+/// فضاء البحث عن المعاملات الفائقة
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchSpace {
+    pub sigmoid_counts: Vec<usize>,
+    pub linear_counts: Vec<usize>,
+    pub strategies: Vec<OptimizationStrategy>,
+    pub adaptation_types: Vec<AdaptationType>,
+}
+
+/// طريقة البحث
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SearchMethod {
+    Grid,                                // بحث شبكي شامل
+    Random { trials: usize, seed: u64 }, // بحث عشوائي قابل للتكرار
+}
+
+/// تقييم مرشح واحد
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandidateScore {
+    pub parameters: HyperParameters,
+    pub cross_validation: CrossValidationResult,
+    pub score: f64, // كلما قل كان أفضل
+}
+
+/// تقرير اختيار النموذج
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectionReport {
+    pub method: SearchMethod,
+    pub folds: usize,
+    pub candidates: Vec<CandidateScore>,
+    pub best_parameters: HyperParameters,
+    pub best_score: f64,
+    pub best_cross_validation: CrossValidationResult,
+}
+
+/// أداة اختيار النموذج
+#[derive(Debug, Clone)]
+pub struct ModelSelector {
+    pub search_space: SearchSpace,
+    pub method: SearchMethod,
+    pub folds: usize,
+    pub max_iterations: u32,
+}
+
+impl SearchSpace {
+    /// عدد التركيبات الممكنة
+    pub fn size(&self) -> usize {
+        self.sigmoid_counts.len() * self.linear_counts.len()
+            * self.strategies.len() * self.adaptation_types.len()
+    }
+
+    /// جميع التركيبات بترتيب ثابت
+    pub fn combinations(&self) -> Vec<HyperParameters> {
+        let mut combinations = Vec::with_capacity(self.size());
+        for &sigmoid_components in &self.sigmoid_counts {
+            for &linear_components in &self.linear_counts {
+                for strategy in &self.strategies {
+                    for adaptation_type in &self.adaptation_types {
+                        // معادلة بلا مكونات لا يمكن تدريبها
+                        if sigmoid_components + linear_components == 0 {
+                            continue;
+                        }
+                        combinations.push(HyperParameters {
+                            sigmoid_components,
+                            linear_components,
+                            strategy: strategy.clone(),
+                            adaptation_type: adaptation_type.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        combinations
+    }
+}
+
+impl Default for SearchSpace {
+    fn default() -> Self {
+        Self {
+            sigmoid_counts: vec![0, 1, 2],
+            linear_counts: vec![0, 1],
+            strategies: vec![OptimizationStrategy::MinimizeError, OptimizationStrategy::BalanceComplexity],
+            adaptation_types: vec![AdaptationType::Gradient],
+        }
+    }
+}
+
+impl HyperParameters {
+    /// بناء معادلة أولية بهذه المعاملات، مع توزيع مراكز السيغمويد على مدى البيانات
+    pub fn build_equation(&self, data: &[(f64, f64)], max_iterations: u32) -> GeneralShapeEquation {
+        let mut equation = GeneralShapeEquation::new();
+
+        let (min_x, max_x) = data.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (x, _)| {
+            (lo.min(*x), hi.max(*x))
+        });
+        let (min_x, max_x) = if min_x.is_finite() { (min_x, max_x) } else { (0.0, 1.0) };
+        let mean_target = if data.is_empty() {
+            0.0
+        } else {
+            data.iter().map(|(_, y)| y).sum::<f64>() / data.len() as f64
+        };
+
+        for i in 0..self.sigmoid_components {
+            let x0 = min_x + (max_x - min_x) * (i as f64 + 1.0) / (self.sigmoid_components as f64 + 1.0);
+            equation.add_sigmoid_component(1.0, ComplexNumber::new(1.0, 0.0), x0);
+        }
+        for _ in 0..self.linear_components {
+            equation.add_linear_component(0.0, mean_target / self.linear_components as f64, 1.0);
+        }
+
+        equation.global_parameters.max_iterations = match self.strategy {
+            // التقارب السريع يعني ميزانية تدريب أصغر
+            OptimizationStrategy::FastConvergence => (max_iterations / 4).max(1),
+            _ => max_iterations,
+        };
+        equation
+    }
+
+    /// درجة المرشح حسب استراتيجية التحسين (الأقل أفضل)
+    pub fn score(&self, result: &CrossValidationResult) -> f64 {
+        match self.strategy {
+            OptimizationStrategy::MinimizeError | OptimizationStrategy::FastConvergence => result.mean_error,
+            // الدقة العالية تعني أيضاً ثباتاً بين الطيات
+            OptimizationStrategy::MaximizeAccuracy => result.mean_error + result.std_error,
+            OptimizationStrategy::BalanceComplexity => {
+                let components = (self.sigmoid_components + self.linear_components) as f64;
+                result.mean_error * (1.0 + 0.05 * components)
+            }
+        }
+    }
+}
+
+/// التحقق المتقاطع بطريقة k-fold لمعادلة معطاة
+pub fn cross_validate(equation: &GeneralShapeEquation,
+                      data: &[(f64, f64)],
+                      folds: usize,
+                      adaptation_type: AdaptationType) -> Result<CrossValidationResult, String> {
+    if folds < 2 {
+        return Err(format!("Cross-validation needs at least 2 folds, got {}", folds));
+    }
+    if data.len() < folds {
+        return Err(format!("Cross-validation with {} folds needs at least {} samples, got {}",
+                           folds, folds, data.len()));
+    }
+
+    let mut fold_errors = Vec::with_capacity(folds);
+    for fold in 0..folds {
+        // توزيع العينات على الطيات بالتناوب
+        let (validation, training): (Vec<_>, Vec<_>) = data.iter()
+            .enumerate()
+            .partition(|(index, _)| index % folds == fold);
+        let training: Vec<(f64, f64)> = training.into_iter().map(|(_, sample)| *sample).collect();
+
+        let mut candidate = equation.clone();
+        candidate.train(training, adaptation_type.clone());
+
+        let squared_error: f64 = validation.iter()
+            .map(|(_, (x, y))| {
+                let error = y - candidate.evaluate(*x).output_value;
+                error * error
+            })
+            .sum();
+        let mse = squared_error / validation.len() as f64;
+        fold_errors.push(if mse.is_finite() { mse } else { f64::MAX });
+    }
+
+    let mean_error = fold_errors.iter().sum::<f64>() / folds as f64;
+    let variance = fold_errors.iter().map(|e| (e - mean_error).powi(2)).sum::<f64>() / folds as f64;
+
+    Ok(CrossValidationResult {
+        folds,
+        fold_errors,
+        mean_error,
+        std_error: variance.sqrt(),
+    })
+}
+
+impl ModelSelector {
+    /// إنشاء أداة اختيار بالقيم الافتراضية (بحث شبكي، 5 طيات)
+    pub fn new() -> Self {
+        Self {
+            search_space: SearchSpace::default(),
+            method: SearchMethod::Grid,
+            folds: 5,
+            max_iterations: 200,
+        }
+    }
+
+    pub fn with_search_space(mut self, search_space: SearchSpace) -> Self {
+        self.search_space = search_space;
+        self
+    }
+
+    pub fn with_method(mut self, method: SearchMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn with_folds(mut self, folds: usize) -> Self {
+        self.folds = folds;
+        self
+    }
+
+    pub fn with_max_iterations(mut self, max_iterations: u32) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// المرشحون حسب طريقة البحث
+    pub fn candidates(&self) -> Vec<HyperParameters> {
+        let combinations = self.search_space.combinations();
+        match &self.method {
+            SearchMethod::Grid => combinations,
+            SearchMethod::Random { trials, seed } => {
+                let mut state = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                let mut pool = combinations;
+                let mut selected = Vec::new();
+                while selected.len() < *trials && !pool.is_empty() {
+                    // xorshift64 - أرقام عشوائية حتمية لتكرار النتائج
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    let index = (state % pool.len() as u64) as usize;
+                    selected.push(pool.swap_remove(index));
+                }
+                selected
+            }
+        }
+    }
+
+    /// البحث عن أفضل المعاملات وإرجاع تقرير الاختيار
+    pub fn select(&self, data: &[(f64, f64)]) -> Result<SelectionReport, String> {
+        let candidates = self.candidates();
+        if candidates.is_empty() {
+            return Err("Model selection search space is empty".to_string());
+        }
+
+        let mut scored = Vec::with_capacity(candidates.len());
+        for parameters in candidates {
+            let equation = parameters.build_equation(data, self.max_iterations);
+            let cross_validation = cross_validate(&equation, data, self.folds, parameters.adaptation_type.clone())?;
+            let score = parameters.score(&cross_validation);
+            scored.push(CandidateScore { parameters, cross_validation, score });
+        }
+
+        let best = scored.iter()
+            .min_by(|a, b| a.score.total_cmp(&b.score))
+            .cloned()
+            .expect("at least one candidate was scored");
+
+        Ok(SelectionReport {
+            method: self.method.clone(),
+            folds: self.folds,
+            candidates: scored,
+            best_parameters: best.parameters,
+            best_score: best.score,
+            best_cross_validation: best.cross_validation,
+        })
+    }
+
+    /// اختيار أفضل المعاملات ثم تدريب معادلة نهائية على كامل البيانات
+    pub fn fit_best(&self, data: &[(f64, f64)]) -> Result<(GeneralShapeEquation, SelectionReport), String> {
+        let report = self.select(data)?;
+        let mut equation = report.best_parameters.build_equation(data, self.max_iterations);
+        equation.train(data.to_vec(), report.best_parameters.adaptation_type.clone());
+        Ok((equation, report))
+    }
+}
+
+impl Default for ModelSelector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linear_data() -> Vec<(f64, f64)> {
+        (0..20).map(|i| {
+            let x = i as f64 / 10.0;
+            (x, 0.5 * x + 0.2)
+        }).collect()
+    }
+
+    #[test]
+    fn test_cross_validate_folds() {
+        let mut equation = GeneralShapeEquation::new();
+        equation.add_linear_component(0.0, 0.0, 1.0);
+        equation.global_parameters.max_iterations = 50;
+
+        let result = cross_validate(&equation, &linear_data(), 4, AdaptationType::Gradient).unwrap();
+        assert_eq!(result.fold_errors.len(), 4);
+        assert!(result.mean_error.is_finite());
+
+        assert!(cross_validate(&equation, &linear_data(), 1, AdaptationType::Gradient).is_err());
+        assert!(cross_validate(&equation, &linear_data()[..3], 4, AdaptationType::Gradient).is_err());
+    }
+
+    #[test]
+    fn test_random_search_is_deterministic() {
+        let selector = ModelSelector::new()
+            .with_method(SearchMethod::Random { trials: 3, seed: 42 })
+            .with_folds(3)
+            .with_max_iterations(20);
+
+        assert_eq!(selector.candidates(), selector.candidates());
+        assert_eq!(selector.candidates().len(), 3);
+
+        let report = selector.select(&linear_data()).unwrap();
+        assert_eq!(report.candidates.len(), 3);
+        assert!(report.candidates.iter().all(|c| c.score >= report.best_score));
+    }
+}
@@ -0,0 +1,356 @@
+// سياسات تقييم المخاطر - مكتبة مدمجة في لغة البيان
+// Risk Assessment Policies - Built-in Library for AlBayan Language
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use super::expert_explorer::{ExpertiseDomain, OperationMode};
+
+/// اسم السياسة الافتراضية التي تعيد إنتاج السلوك الأصلي
+pub const DEFAULT_POLICY_NAME: &str = "default";
+
+/// سياسة تقييم المخاطر
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskPolicy {
+    pub name: String,
+    /// المجالات التي تنطبق عليها السياسة (فارغة = كل المجالات)
+    pub applies_to_domains: Vec<ExpertiseDomain>,
+    /// الأنماط التي تنطبق عليها السياسة (فارغة = كل الأنماط)
+    pub applies_to_modes: Vec<OperationMode>,
+    pub thresholds: RiskThresholds,
+    /// المخاطر الأساسية لكل نمط تشغيل
+    pub base_risk: ModeRisk,
+    /// أوزان المجالات - تضرب في المخاطر الأساسية
+    pub domain_weights: HashMap<ExpertiseDomain, f64>,
+    /// أوزان الإضافات حسب الاسم (الافتراضي 1.0)
+    pub plugin_weights: HashMap<String, f64>,
+    pub exploration_schedule: ExplorationSchedule,
+}
+
+/// عتبات تصنيف المخاطر
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskThresholds {
+    pub medium: f64,
+    pub high: f64,
+}
+
+/// المخاطر الأساسية لأنماط التشغيل
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModeRisk {
+    pub expert: f64,
+    pub explorer: f64,
+}
+
+/// جدول نسبة الاستكشاف/الاستغلال حسب عدد القرارات السابقة
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExplorationSchedule {
+    /// نسبة ثابتة
+    Constant { ratio: f64 },
+    /// تناقص خطي من start إلى end خلال steps قرار
+    LinearDecay { start: f64, end: f64, steps: u64 },
+    /// تناقص أسي مع حد أدنى
+    ExponentialDecay { start: f64, decay: f64, min: f64 },
+}
+
+/// مستوى المخاطر
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum RiskLevel {
+    #[default]
+    Low,
+    Medium,
+    High,
+}
+
+/// مجموعة سياسات قابلة للتحميل من ملف
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RiskPolicyConfig {
+    #[serde(default)]
+    pub policies: Vec<RiskPolicy>,
+}
+
+/// سياق تقييم المخاطر الممرر للإضافات
+#[derive(Debug, Clone)]
+pub struct RiskContext<'a> {
+    pub input_data: &'a [f64],
+    pub mode: OperationMode,
+    pub confidence: f64,
+    pub decision: f64,
+    pub base_risk: f64,
+    pub domains: &'a [ExpertiseDomain],
+}
+
+/// إضافة لحساب المخاطر يحقنها المضيف
+pub trait RiskScoringPlugin: std::fmt::Debug + Send + Sync {
+    /// اسم الإضافة - يستخدم لأوزان السياسة ولسجل القرار
+    fn name(&self) -> &str;
+
+    /// درجة المخاطر بين 0 و 1، أو None إذا لم تنطبق الإضافة
+    fn score(&self, context: &RiskContext) -> Option<f64>;
+}
+
+/// الإضافات المسجلة - لا تُحفظ مع حالة النظام
+pub type RiskPlugins = Vec<Arc<dyn RiskScoringPlugin>>;
+
+/// ناتج تطبيق سياسة
+#[derive(Debug, Clone)]
+pub struct PolicyEvaluation {
+    pub policy_name: String,
+    pub overall_risk: f64,
+    pub risk_level: RiskLevel,
+    pub plugin_scores: Vec<(String, f64)>,
+}
+
+impl Default for RiskPolicy {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_POLICY_NAME.to_string(),
+            applies_to_domains: Vec::new(),
+            applies_to_modes: Vec::new(),
+            thresholds: RiskThresholds::default(),
+            base_risk: ModeRisk::default(),
+            domain_weights: HashMap::new(),
+            plugin_weights: HashMap::new(),
+            exploration_schedule: ExplorationSchedule::default(),
+        }
+    }
+}
+
+impl Default for RiskThresholds {
+    fn default() -> Self {
+        Self { medium: 0.3, high: 0.7 }
+    }
+}
+
+impl Default for ModeRisk {
+    fn default() -> Self {
+        Self { expert: 0.2, explorer: 0.6 }
+    }
+}
+
+impl Default for ExplorationSchedule {
+    fn default() -> Self {
+        ExplorationSchedule::Constant { ratio: 0.3 }
+    }
+}
+
+impl ExplorationSchedule {
+    /// نسبة الاستكشاف بعد عدد معين من القرارات
+    pub fn ratio_at(&self, decisions_made: u64) -> f64 {
+        let ratio = match *self {
+            ExplorationSchedule::Constant { ratio } => ratio,
+            ExplorationSchedule::LinearDecay { start, end, steps } => {
+                if steps == 0 || decisions_made >= steps {
+                    end
+                } else {
+                    start + (end - start) * (decisions_made as f64 / steps as f64)
+                }
+            }
+            ExplorationSchedule::ExponentialDecay { start, decay, min } => {
+                (start * (-decay * decisions_made as f64).exp()).max(min)
+            }
+        };
+        ratio.clamp(0.0, 1.0)
+    }
+}
+
+impl RiskPolicy {
+    /// إنشاء سياسة باسم معين وبقيم افتراضية
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// تحميل سياسة واحدة من نص JSON
+    pub fn from_json(source: &str) -> Result<Self, String> {
+        let policy: Self = serde_json::from_str(source)
+            .map_err(|e| format!("Invalid risk policy JSON: {}", e))?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// تحميل سياسة واحدة من نص TOML
+    pub fn from_toml(source: &str) -> Result<Self, String> {
+        let policy: Self = toml::from_str(source)
+            .map_err(|e| format!("Invalid risk policy TOML: {}", e))?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// التحقق من صحة القيم
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("Risk policy name must not be empty".to_string());
+        }
+        let in_unit = |v: f64| (0.0..=1.0).contains(&v);
+        if !in_unit(self.thresholds.medium) || !in_unit(self.thresholds.high)
+            || self.thresholds.medium > self.thresholds.high {
+            return Err(format!(
+                "Risk policy '{}': thresholds must satisfy 0 <= medium <= high <= 1",
+                self.name
+            ));
+        }
+        if !in_unit(self.base_risk.expert) || !in_unit(self.base_risk.explorer) {
+            return Err(format!("Risk policy '{}': base risk must be within [0, 1]", self.name));
+        }
+        if let Some((domain, _)) = self.domain_weights.iter().find(|(_, w)| **w < 0.0) {
+            return Err(format!("Risk policy '{}': negative weight for domain {:?}", self.name, domain));
+        }
+        if let Some((plugin, _)) = self.plugin_weights.iter().find(|(_, w)| **w < 0.0) {
+            return Err(format!("Risk policy '{}': negative weight for plugin '{}'", self.name, plugin));
+        }
+        Ok(())
+    }
+
+    /// هل تنطبق السياسة على المجالات والنمط المعطى
+    pub fn matches(&self, domains: &[ExpertiseDomain], mode: OperationMode) -> bool {
+        let domain_ok = self.applies_to_domains.is_empty()
+            || self.applies_to_domains.iter().any(|d| domains.contains(d));
+        let mode_ok = self.applies_to_modes.is_empty() || self.applies_to_modes.contains(&mode);
+        domain_ok && mode_ok
+    }
+
+    /// المخاطر الأساسية لنمط معين
+    pub fn base_risk_for(&self, mode: OperationMode) -> f64 {
+        match mode {
+            OperationMode::Expert => self.base_risk.expert,
+            OperationMode::Explorer => self.base_risk.explorer,
+            OperationMode::Adaptive | OperationMode::Hybrid => {
+                (self.base_risk.expert + self.base_risk.explorer) / 2.0
+            }
+        }
+    }
+
+    /// متوسط أوزان المجالات المعطاة (1.0 للمجالات غير المذكورة)
+    pub fn domain_factor(&self, domains: &[ExpertiseDomain]) -> f64 {
+        if domains.is_empty() {
+            return 1.0;
+        }
+        domains.iter()
+            .map(|d| self.domain_weights.get(d).copied().unwrap_or(1.0))
+            .sum::<f64>() / domains.len() as f64
+    }
+
+    /// تصنيف قيمة المخاطر حسب العتبات
+    pub fn classify(&self, risk: f64) -> RiskLevel {
+        if risk >= self.thresholds.high {
+            RiskLevel::High
+        } else if risk >= self.thresholds.medium {
+            RiskLevel::Medium
+        } else {
+            RiskLevel::Low
+        }
+    }
+
+    /// تطبيق السياسة والإضافات على سياق القرار
+    pub fn evaluate(&self, context: &RiskContext, plugins: &[Arc<dyn RiskScoringPlugin>]) -> PolicyEvaluation {
+        let weighted_base = (context.base_risk * self.domain_factor(context.domains)).clamp(0.0, 1.0);
+
+        let mut plugin_scores = Vec::new();
+        let mut total = weighted_base;
+        let mut total_weight = 1.0;
+        for plugin in plugins {
+            if let Some(score) = plugin.score(context) {
+                let score = score.clamp(0.0, 1.0);
+                let weight = self.plugin_weights.get(plugin.name()).copied().unwrap_or(1.0);
+                total += score * weight;
+                total_weight += weight;
+                plugin_scores.push((plugin.name().to_string(), score));
+            }
+        }
+
+        let overall_risk = total / total_weight;
+        PolicyEvaluation {
+            policy_name: self.name.clone(),
+            overall_risk,
+            risk_level: self.classify(overall_risk),
+            plugin_scores,
+        }
+    }
+}
+
+impl RiskPolicyConfig {
+    /// تحميل مجموعة سياسات من ملف JSON أو TOML حسب الامتداد
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read risk policy file '{}': {}", path.display(), e))?;
+
+        let config: Self = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&source)
+                .map_err(|e| format!("Invalid risk policy TOML: {}", e))?,
+            Some("json") => serde_json::from_str(&source)
+                .map_err(|e| format!("Invalid risk policy JSON: {}", e))?,
+            _ => return Err(format!("Unsupported risk policy file format: '{}'", path.display())),
+        };
+
+        for policy in &config.policies {
+            policy.validate()?;
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct ConstantPlugin(f64);
+
+    impl RiskScoringPlugin for ConstantPlugin {
+        fn name(&self) -> &str {
+            "constant"
+        }
+
+        fn score(&self, _context: &RiskContext) -> Option<f64> {
+            Some(self.0)
+        }
+    }
+
+    fn context(base_risk: f64) -> RiskContext<'static> {
+        RiskContext {
+            input_data: &[],
+            mode: OperationMode::Expert,
+            confidence: 0.9,
+            decision: 1.0,
+            base_risk,
+            domains: &[],
+        }
+    }
+
+    #[test]
+    fn test_policy_from_json_and_toml() {
+        let json = r#"{
+            "name": "finance",
+            "applies_to_domains": ["Finance"],
+            "domain_weights": { "Finance": 1.5 },
+            "exploration_schedule": { "kind": "linear_decay", "start": 0.5, "end": 0.1, "steps": 10 }
+        }"#;
+        let policy = RiskPolicy::from_json(json).unwrap();
+        assert_eq!(policy.name, "finance");
+        assert!(policy.matches(&[ExpertiseDomain::Finance], OperationMode::Expert));
+        assert!(!policy.matches(&[ExpertiseDomain::Art], OperationMode::Expert));
+        assert!((policy.exploration_schedule.ratio_at(5) - 0.3).abs() < 1e-9);
+
+        let toml_source = "name = \"strict\"\n[thresholds]\nmedium = 0.1\nhigh = 0.4\n";
+        let policy = RiskPolicy::from_toml(toml_source).unwrap();
+        assert_eq!(policy.classify(0.5), RiskLevel::High);
+
+        assert!(RiskPolicy::from_json(r#"{ "thresholds": { "medium": 0.9, "high": 0.1 } }"#).is_err());
+    }
+
+    #[test]
+    fn test_policy_evaluation_with_plugin() {
+        let policy = RiskPolicy::default();
+        let plugins: RiskPlugins = vec![Arc::new(ConstantPlugin(1.0))];
+        let evaluation = policy.evaluate(&context(0.2), &plugins);
+        assert!((evaluation.overall_risk - 0.6).abs() < 1e-9);
+        assert_eq!(evaluation.risk_level, RiskLevel::Medium);
+        assert_eq!(evaluation.plugin_scores, vec![("constant".to_string(), 1.0)]);
+    }
+}
@@ -0,0 +1,385 @@
+// استخراج الحقائق المكانية من نتائج استنباط الأشكال
+// Spatial Fact Extraction from Shape Inference into the Knowledge Base
+
+use std::collections::{HashMap, VecDeque};
+
+use super::artistic_renderer::{BasicShape, Pixel, RenderedImage};
+use super::shape_inference::ShapeInference;
+use crate::runtime::LogicEngine;
+
+/// المستطيل المحيط بجسم في الصورة (إحداثيات شاملة، والمحور y للأسفل)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min_x: u32,
+    pub min_y: u32,
+    pub max_x: u32,
+    pub max_y: u32,
+}
+
+impl BoundingBox {
+    pub fn width(&self) -> u32 {
+        self.max_x - self.min_x + 1
+    }
+
+    pub fn height(&self) -> u32 {
+        self.max_y - self.min_y + 1
+    }
+
+    /// المركز (مقرب لأقرب بكسل)
+    pub fn center(&self) -> (u32, u32) {
+        ((self.min_x + self.max_x) / 2, (self.min_y + self.max_y) / 2)
+    }
+}
+
+/// جسم مكتشف في المشهد
+#[derive(Debug, Clone)]
+pub struct SceneObject {
+    /// معرف الجسم في قاعدة المعرفة (مثل "circle1")
+    pub id: String,
+    pub shape: BasicShape,
+    pub confidence: f64,
+    pub bounds: BoundingBox,
+    /// عدد البكسلات المغطاة
+    pub area: usize,
+}
+
+impl SceneObject {
+    /// الحجم: أكبر بعدي المستطيل المحيط
+    pub fn size(&self) -> u32 {
+        self.bounds.width().max(self.bounds.height())
+    }
+}
+
+/// مشهد مكون من عدة أجسام مع العلاقات المكانية بينها
+#[derive(Debug, Clone)]
+pub struct Scene {
+    pub width: u32,
+    pub height: u32,
+    pub objects: Vec<SceneObject>,
+}
+
+/// مستخرج الأجسام: يقسم الصورة إلى مناطق متصلة ويصنف كل منطقة
+#[derive(Debug, Clone)]
+pub struct SceneExtractor {
+    /// أصغر مساحة (بالبكسل) تعتبر جسماً وليست تشويشاً
+    pub min_area: usize,
+    /// فرق السطوع الذي يفصل الجسم عن الخلفية
+    pub threshold: u8,
+}
+
+impl Default for SceneExtractor {
+    fn default() -> Self {
+        Self {
+            min_area: 16,
+            threshold: 128,
+        }
+    }
+}
+
+impl SceneExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_min_area(mut self, min_area: usize) -> Self {
+        self.min_area = min_area;
+        self
+    }
+
+    pub fn with_threshold(mut self, threshold: u8) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// اكتشاف الأجسام في الصورة وتصنيفها
+    ///
+    /// الأشكال الهندسية (دائرة، مربع، مستطيل، مثلث) تُصنف من نسبة امتلاء المستطيل المحيط،
+    /// وما عداها يُحال إلى وحدة الاستنباط
+    pub fn extract(&self, image: &RenderedImage, inference: &mut ShapeInference) -> Scene {
+        let foreground = self.foreground_mask(image);
+        let mut counters: HashMap<BasicShape, usize> = HashMap::new();
+
+        let objects = connected_regions(&foreground, image.width, image.height)
+            .into_iter()
+            .filter(|region| region.len() >= self.min_area)
+            .map(|region| {
+                let bounds = bounding_box(&region);
+                let (shape, confidence) = classify_geometric(region.len(), &bounds)
+                    .unwrap_or_else(|| {
+                        let result = inference.image_to_equation(&crop(&foreground, &bounds));
+                        (result.predicted_shape, result.confidence)
+                    });
+
+                let counter = counters.entry(shape).or_insert(0);
+                *counter += 1;
+                SceneObject {
+                    id: format!("{}{}", shape_atom(shape), counter),
+                    shape,
+                    confidence,
+                    bounds,
+                    area: region.len(),
+                }
+            })
+            .collect();
+
+        Scene {
+            width: image.width,
+            height: image.height,
+            objects,
+        }
+    }
+
+    /// البكسلات التي يختلف سطوعها عن الخلفية (المقدرة من إطار الصورة)
+    fn foreground_mask(&self, image: &RenderedImage) -> Vec<Vec<bool>> {
+        let brightness = |pixel: &Pixel| {
+            ((pixel.red as u32 + pixel.green as u32 + pixel.blue as u32) / 3) as u8
+        };
+        let is_bright = |pixel: &Pixel| brightness(pixel) >= self.threshold;
+
+        let border: Vec<&Pixel> = image.pixels.iter().enumerate()
+            .flat_map(|(y, row)| {
+                row.iter().enumerate()
+                    .filter(move |(x, _)| y == 0 || y + 1 == image.pixels.len() || *x == 0 || x + 1 == row.len())
+                    .map(|(_, pixel)| pixel)
+            })
+            .collect();
+        let bright_background = border.iter().filter(|pixel| is_bright(pixel)).count() * 2 > border.len();
+
+        image.pixels.iter()
+            .map(|row| row.iter().map(|pixel| is_bright(pixel) != bright_background).collect())
+            .collect()
+    }
+}
+
+impl Scene {
+    /// الحقائق المكانية للمشهد بصيغة قاعدة المعرفة
+    ///
+    /// لكل جسم: `circle(circle1)`, `object(circle1)`, `shape(circle1, circle)`,
+    /// `size(circle1, 40)`, `area(circle1, 1256)`, `position(circle1, 32, 20)`,
+    /// `confidence(circle1, 0.912)`.
+    /// ولكل زوج: `above`, `below`, `left_of`, `right_of` (عند انفصال المستطيلين المحيطين
+    /// تماماً على ذلك المحور) و `larger`.
+    pub fn facts(&self) -> Vec<String> {
+        let mut facts = Vec::new();
+
+        for object in &self.objects {
+            let id = &object.id;
+            let shape = shape_atom(object.shape);
+            let (x, y) = object.bounds.center();
+            facts.push(format!("{}({})", shape, id));
+            facts.push(format!("object({})", id));
+            facts.push(format!("shape({}, {})", id, shape));
+            facts.push(format!("size({}, {})", id, object.size()));
+            facts.push(format!("area({}, {})", id, object.area));
+            facts.push(format!("position({}, {}, {})", id, x, y));
+            facts.push(format!("confidence({}, {:.3})", id, object.confidence));
+        }
+
+        for a in &self.objects {
+            for b in &self.objects {
+                if a.id == b.id {
+                    continue;
+                }
+                if a.bounds.max_y < b.bounds.min_y {
+                    facts.push(format!("above({}, {})", a.id, b.id));
+                    facts.push(format!("below({}, {})", b.id, a.id));
+                }
+                if a.bounds.max_x < b.bounds.min_x {
+                    facts.push(format!("left_of({}, {})", a.id, b.id));
+                    facts.push(format!("right_of({}, {})", b.id, a.id));
+                }
+                if a.area > b.area {
+                    facts.push(format!("larger({}, {})", a.id, b.id));
+                }
+            }
+        }
+
+        facts
+    }
+
+    /// إضافة حقائق المشهد إلى محرك المنطق وإرجاع عددها
+    pub fn assert_into(&self, engine: &mut LogicEngine) -> Result<usize, String> {
+        let facts = self.facts();
+        for fact in &facts {
+            engine.assert_fact(fact)
+                .map_err(|e| format!("Failed to assert '{}': {}", fact, e))?;
+        }
+        Ok(facts.len())
+    }
+}
+
+/// الشكل الهندسي لأكبر جسم في الصورة وثقته، إن كان دائرة أو مربعاً أو مستطيلاً أو مثلثاً
+pub(crate) fn largest_geometric_shape(image: &RenderedImage) -> Option<(BasicShape, f64)> {
+    let foreground = SceneExtractor::new().foreground_mask(image);
+    let region = connected_regions(&foreground, image.width, image.height)
+        .into_iter()
+        .max_by_key(Vec::len)?;
+    classify_geometric(region.len(), &bounding_box(&region))
+}
+
+/// اسم الشكل كذرة منطقية (مثل "circle")
+pub(crate) fn shape_atom(shape: BasicShape) -> String {
+    format!("{:?}", shape).to_lowercase()
+}
+
+/// المناطق المتصلة (اتصال رباعي) من بكسلات المقدمة
+fn connected_regions(mask: &[Vec<bool>], width: u32, height: u32) -> Vec<Vec<(u32, u32)>> {
+    let mut visited = vec![vec![false; width as usize]; height as usize];
+    let mut regions = Vec::new();
+
+    for start_y in 0..height as usize {
+        for start_x in 0..width as usize {
+            if visited[start_y][start_x] || !mask[start_y][start_x] {
+                continue;
+            }
+
+            let mut region = Vec::new();
+            let mut queue = VecDeque::from([(start_x, start_y)]);
+            visited[start_y][start_x] = true;
+            while let Some((x, y)) = queue.pop_front() {
+                region.push((x as u32, y as u32));
+                let neighbours = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                for (nx, ny) in neighbours {
+                    if nx < width as usize && ny < height as usize && !visited[ny][nx] && mask[ny][nx] {
+                        visited[ny][nx] = true;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+            regions.push(region);
+        }
+    }
+
+    regions
+}
+
+fn bounding_box(region: &[(u32, u32)]) -> BoundingBox {
+    let mut bounds = BoundingBox {
+        min_x: u32::MAX,
+        min_y: u32::MAX,
+        max_x: 0,
+        max_y: 0,
+    };
+    for &(x, y) in region {
+        bounds.min_x = bounds.min_x.min(x);
+        bounds.min_y = bounds.min_y.min(y);
+        bounds.max_x = bounds.max_x.max(x);
+        bounds.max_y = bounds.max_y.max(y);
+    }
+    bounds
+}
+
+/// تصنيف شكل هندسي من نسبة الامتلاء ونسبة الأبعاد
+///
+/// نسب الامتلاء المثالية: مربع ومستطيل 1.0، دائرة π/4 ≈ 0.785، مثلث 0.5
+fn classify_geometric(area: usize, bounds: &BoundingBox) -> Option<(BasicShape, f64)> {
+    let (width, height) = (bounds.width() as f64, bounds.height() as f64);
+    let fill = area as f64 / (width * height);
+    let aspect = width.min(height) / width.max(height);
+    let score = |expected: f64, tolerance: f64| 1.0 - (fill - expected).abs() / tolerance;
+
+    let (shape, confidence) = if fill >= 0.88 {
+        let shape = if aspect >= 0.85 { BasicShape::Square } else { BasicShape::Rectangle };
+        (shape, score(1.0, 0.25))
+    } else if (0.68..0.88).contains(&fill) && aspect >= 0.8 {
+        (BasicShape::Circle, score(std::f64::consts::FRAC_PI_4, 0.25))
+    } else if (0.35..0.65).contains(&fill) {
+        (BasicShape::Triangle, score(0.5, 0.3))
+    } else {
+        return None;
+    };
+
+    Some((shape, confidence.clamp(0.0, 1.0)))
+}
+
+/// قص منطقة من القناع كصورة بيضاء على سوداء مع هامش
+fn crop(mask: &[Vec<bool>], bounds: &BoundingBox) -> RenderedImage {
+    const MARGIN: u32 = 2;
+    let width = bounds.width() + 2 * MARGIN;
+    let height = bounds.height() + 2 * MARGIN;
+    let pixel = |on: bool| {
+        let value = if on { 255 } else { 0 };
+        Pixel { red: value, green: value, blue: value, alpha: 255 }
+    };
+
+    let pixels = (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    let source_x = (bounds.min_x + x).checked_sub(MARGIN);
+                    let source_y = (bounds.min_y + y).checked_sub(MARGIN);
+                    let on = match (source_x, source_y) {
+                        (Some(sx), Some(sy)) if sx <= bounds.max_x && sy <= bounds.max_y => {
+                            mask[sy as usize][sx as usize]
+                        }
+                        _ => false,
+                    };
+                    pixel(on)
+                })
+                .collect()
+        })
+        .collect();
+
+    RenderedImage { width, height, pixels }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// مشهد 64x64: دائرة في الأعلى يسار، ومربع أكبر أسفل يمين
+    fn two_shape_scene() -> RenderedImage {
+        let pixels = (0..64)
+            .map(|y| {
+                (0..64)
+                    .map(|x| {
+                        let (dx, dy) = (x as f64 - 15.5, y as f64 - 15.5);
+                        let circle = dx * dx + dy * dy <= 64.0;
+                        let square = (34..58).contains(&x) && (36..60).contains(&y);
+                        let value = if circle || square { 255 } else { 0 };
+                        Pixel { red: value, green: value, blue: value, alpha: 255 }
+                    })
+                    .collect()
+            })
+            .collect();
+        RenderedImage { width: 64, height: 64, pixels }
+    }
+
+    #[test]
+    fn test_extract_scene_objects() {
+        let scene = SceneExtractor::new().extract(&two_shape_scene(), &mut ShapeInference::new());
+
+        assert_eq!(scene.objects.len(), 2);
+        let circle = &scene.objects[0];
+        let square = &scene.objects[1];
+        assert_eq!((circle.shape, circle.id.as_str()), (BasicShape::Circle, "circle1"));
+        assert_eq!((square.shape, square.id.as_str()), (BasicShape::Square, "square1"));
+        assert_eq!(square.size(), 24);
+
+        let facts = scene.facts();
+        for expected in ["circle(circle1)", "above(circle1, square1)", "left_of(circle1, square1)",
+                         "larger(square1, circle1)", "size(square1, 24)"] {
+            assert!(facts.iter().any(|fact| fact == expected), "missing {}", expected);
+        }
+        assert!(!facts.iter().any(|fact| fact == "above(square1, circle1)"));
+    }
+
+    #[test]
+    fn test_scene_rules_over_facts() {
+        let scene = SceneExtractor::new().extract(&two_shape_scene(), &mut ShapeInference::new());
+
+        let mut engine = LogicEngine::new();
+        let asserted = scene.assert_into(&mut engine).unwrap();
+        assert_eq!(asserted, engine.facts_count());
+
+        engine.add_rule("on_top(X, Y) :- above(X, Y), larger(Y, X)").unwrap();
+        let solutions = engine.solve_query("on_top(X, square1)").unwrap();
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].get("X").map(String::as_str), Some("circle1"));
+    }
+}
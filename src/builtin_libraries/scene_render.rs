@@ -0,0 +1,583 @@
+// لغة وصف المشاهد: رسم المشهد من الحقائق المكانية في قاعدة المعرفة
+// Scene Description Language: Laying Out and Rendering Scenes from Logic Facts
+
+use std::collections::{HashMap, HashSet};
+
+use super::artistic_renderer::{ArtisticRenderer, BasicShape, Pixel, RenderedImage};
+use super::scene_facts::{shape_atom, BoundingBox, Scene, SceneObject};
+use crate::runtime::LogicEngine;
+
+/// أسماء الأشكال بالعربية
+const ARABIC_SHAPES: [(&str, BasicShape); 16] = [
+    ("دائرة", BasicShape::Circle),
+    ("مربع", BasicShape::Square),
+    ("مثلث", BasicShape::Triangle),
+    ("مستطيل", BasicShape::Rectangle),
+    ("قطة", BasicShape::Cat),
+    ("كلب", BasicShape::Dog),
+    ("إنسان", BasicShape::Human),
+    ("طائر", BasicShape::Bird),
+    ("سمكة", BasicShape::Fish),
+    ("شجرة", BasicShape::Tree),
+    ("زهرة", BasicShape::Flower),
+    ("جبل", BasicShape::Mountain),
+    ("سحابة", BasicShape::Cloud),
+    ("شمس", BasicShape::Sun),
+    ("قمر", BasicShape::Moon),
+    ("نجمة", BasicShape::Star),
+];
+
+/// أسماء الألوان بالإنجليزية والعربية وقيمها
+const COLORS: [(&str, &str, (u8, u8, u8)); 10] = [
+    ("black", "أسود", (0, 0, 0)),
+    ("white", "أبيض", (255, 255, 255)),
+    ("red", "أحمر", (220, 30, 30)),
+    ("green", "أخضر", (30, 160, 60)),
+    ("blue", "أزرق", (30, 60, 200)),
+    ("yellow", "أصفر", (240, 200, 20)),
+    ("orange", "برتقالي", (240, 130, 20)),
+    ("purple", "بنفسجي", (130, 40, 160)),
+    ("brown", "بني", (120, 70, 30)),
+    ("gray", "رمادي", (128, 128, 128)),
+];
+
+/// المحمولات المفهومة: الاسم الإنجليزي ومرادفه العربي
+const OBJECT: [&str; 2] = ["object", "جسم"];
+const SHAPE: [&str; 2] = ["shape", "شكل"];
+const SIZE: [&str; 2] = ["size", "حجم"];
+const COLOR: [&str; 2] = ["color", "لون"];
+const POSITION: [&str; 2] = ["position", "موضع"];
+const ABOVE: [&str; 2] = ["above", "فوق"];
+const BELOW: [&str; 2] = ["below", "تحت"];
+const LEFT_OF: [&str; 2] = ["left_of", "يسار"];
+const RIGHT_OF: [&str; 2] = ["right_of", "يمين"];
+const LARGER: [&str; 2] = ["larger", "أكبر"];
+const SMALLER: [&str; 2] = ["smaller", "أصغر"];
+
+/// محمول علاقة، وبانيها، وهل يُعكس ترتيب طرفيها
+type RelationKind = (&'static [&'static str; 2], fn(String, String) -> SpatialRelation, bool);
+
+/// تحويل اسم شكل (إنجليزي أو عربي) إلى شكل أساسي
+pub fn shape_from_name(name: &str) -> Option<BasicShape> {
+    BasicShape::from_name(name).or_else(|| {
+        ARABIC_SHAPES.iter()
+            .find(|(arabic, _)| *arabic == name.trim())
+            .map(|(_, shape)| *shape)
+    })
+}
+
+/// تحويل اسم لون (إنجليزي أو عربي) إلى بكسل
+pub fn color_from_name(name: &str) -> Option<Pixel> {
+    let name = name.trim();
+    COLORS.iter()
+        .find(|(english, arabic, _)| english.eq_ignore_ascii_case(name) || *arabic == name)
+        .map(|(_, _, (red, green, blue))| Pixel { red: *red, green: *green, blue: *blue, alpha: 255 })
+}
+
+/// وصف جسم كما ورد في قاعدة المعرفة
+#[derive(Debug, Clone)]
+pub struct ObjectSpec {
+    pub id: String,
+    pub shape: BasicShape,
+    /// الحجم المطلوب (أكبر بعدي الجسم) إن ذُكر
+    pub size: Option<u32>,
+    pub color: Option<Pixel>,
+    /// المركز المطلوب إن ذُكر، ويتقدم على التخطيط
+    pub position: Option<(u32, u32)>,
+}
+
+/// علاقة مكانية بين جسمين (`below` و `right_of` و `smaller` تُحول إلى معكوساتها)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpatialRelation {
+    Above(String, String),
+    LeftOf(String, String),
+    Larger(String, String),
+}
+
+/// وصف مشهد: الأجسام والعلاقات بينها
+#[derive(Debug, Clone, Default)]
+pub struct SceneDescription {
+    pub objects: Vec<ObjectSpec>,
+    pub relations: Vec<SpatialRelation>,
+}
+
+impl SceneDescription {
+    /// قراءة وصف المشهد من قاعدة المعرفة
+    ///
+    /// الأجسام المرسومة هي قيم المتغيرات في حلول `query` (مثل `object(X)` أو
+    /// `circle(X)` أو `فوق(X, Y)`)؛ والاستعلام الفارغ يختار كل الأجسام المعرّفة
+    /// بـ `object` أو `shape` أو بمحمول شكل مثل `circle(c1)`
+    pub fn from_logic(engine: &mut LogicEngine, query: &str) -> Result<Self, String> {
+        let unary_shapes = unary_shape_facts(engine)?;
+        let declared_shapes = pairs(engine, &SHAPE)?;
+
+        let ids = if query.trim().is_empty() {
+            let mut ids: Vec<String> = Vec::new();
+            for name in OBJECT {
+                ids.extend(solve(engine, &format!("{}(X)", name))?.into_iter().filter_map(|mut s| s.remove("X")));
+            }
+            ids.extend(declared_shapes.iter().map(|(id, _)| id.clone()));
+            ids.extend(unary_shapes.iter().map(|(id, _)| id.clone()));
+            ids
+        } else {
+            // قيم المتغيرات التي ليست أجساماً (مثل الأحجام) تُهمل
+            let is_object = |id: &String| {
+                lookup(&declared_shapes, id).is_some() || lookup(&unary_shapes, id).is_some()
+            };
+            solve(engine, query)?
+                .into_iter()
+                .flat_map(|solution| {
+                    let mut bound: Vec<(String, String)> = solution.into_iter().collect();
+                    bound.sort();
+                    bound.into_iter().map(|(_, value)| value)
+                })
+                .filter(is_object)
+                .collect()
+        };
+
+        let mut seen = HashSet::new();
+        let ids: Vec<String> = ids.into_iter().filter(|id| seen.insert(id.clone())).collect();
+
+        let sizes = pairs(engine, &SIZE)?;
+        let colors = pairs(engine, &COLOR)?;
+        let mut positions = HashMap::new();
+        for name in POSITION {
+            for solution in solve(engine, &format!("{}(O, X, Y)", name))? {
+                if let (Some(id), Some(x), Some(y)) = (solution.get("O"), solution.get("X"), solution.get("Y")) {
+                    if let (Ok(x), Ok(y)) = (x.parse::<f64>(), y.parse::<f64>()) {
+                        positions.insert(id.clone(), (x.max(0.0) as u32, y.max(0.0) as u32));
+                    }
+                }
+            }
+        }
+
+        let objects = ids.iter()
+            .map(|id| {
+                let shape = lookup(&declared_shapes, id)
+                    .and_then(|name| shape_from_name(name))
+                    .or_else(|| lookup(&unary_shapes, id).copied())
+                    .ok_or_else(|| format!("Object '{}' has no known shape", id))?;
+                Ok(ObjectSpec {
+                    id: id.clone(),
+                    shape,
+                    size: lookup(&sizes, id).and_then(|size| size.parse::<f64>().ok()).map(|size| size.max(1.0) as u32),
+                    color: lookup(&colors, id).and_then(|name| color_from_name(name)),
+                    position: positions.get(id).copied(),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let selected: HashSet<&String> = ids.iter().collect();
+        let mut relations = Vec::new();
+        let kinds: [RelationKind; 6] = [
+            (&ABOVE, SpatialRelation::Above, false),
+            (&BELOW, SpatialRelation::Above, true),
+            (&LEFT_OF, SpatialRelation::LeftOf, false),
+            (&RIGHT_OF, SpatialRelation::LeftOf, true),
+            (&LARGER, SpatialRelation::Larger, false),
+            (&SMALLER, SpatialRelation::Larger, true),
+        ];
+        for (names, relation, reversed) in kinds {
+            for (a, b) in pairs(engine, names)? {
+                if !selected.contains(&a) || !selected.contains(&b) || a == b {
+                    continue;
+                }
+                let relation = if reversed { relation(b, a) } else { relation(a, b) };
+                if !relations.contains(&relation) {
+                    relations.push(relation);
+                }
+            }
+        }
+
+        Ok(Self { objects, relations })
+    }
+}
+
+fn solve(engine: &mut LogicEngine, query: &str) -> Result<Vec<HashMap<String, String>>, String> {
+    engine.solve_query(query).map_err(|e| format!("Query '{}' failed: {}", query, e))
+}
+
+/// حلول محمول ثنائي بالاسمين الإنجليزي والعربي، بترتيب ورودها
+fn pairs(engine: &mut LogicEngine, names: &[&str; 2]) -> Result<Vec<(String, String)>, String> {
+    let mut result = Vec::new();
+    for name in names {
+        for mut solution in solve(engine, &format!("{}(A, B)", name))? {
+            if let (Some(a), Some(b)) = (solution.remove("A"), solution.remove("B")) {
+                result.push((a, b));
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// الحقائق الأحادية التي تسمي شكل الجسم مثل `circle(c1)` أو `دائرة(ش1)`
+fn unary_shape_facts(engine: &mut LogicEngine) -> Result<Vec<(String, BasicShape)>, String> {
+    let names = BasicShape::ALL.iter()
+        .map(|shape| (shape_atom(*shape), *shape))
+        .chain(ARABIC_SHAPES.iter().map(|(name, shape)| (name.to_string(), *shape)));
+
+    let mut result = Vec::new();
+    for (name, shape) in names {
+        for mut solution in solve(engine, &format!("{}(X)", name))? {
+            if let Some(id) = solution.remove("X") {
+                result.push((id, shape));
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn lookup<'a, T>(pairs: &'a [(String, T)], id: &str) -> Option<&'a T> {
+    pairs.iter().find(|(key, _)| key == id).map(|(_, value)| value)
+}
+
+/// راسم المشاهد: يخطط مواقع الأجسام على شبكة تحقق العلاقات المكانية ثم يرسمها
+#[derive(Debug, Clone)]
+pub struct SceneRenderer {
+    pub width: u32,
+    pub height: u32,
+    /// الفراغ بين الأجسام وحول المشهد
+    pub margin: u32,
+    /// حجم الجسم الذي لم يُذكر حجمه
+    pub default_size: u32,
+    pub background: Pixel,
+    /// لون الجسم الذي لم يُذكر لونه
+    pub foreground: Pixel,
+}
+
+impl Default for SceneRenderer {
+    fn default() -> Self {
+        Self {
+            width: 256,
+            height: 256,
+            margin: 12,
+            default_size: 40,
+            background: Pixel { red: 255, green: 255, blue: 255, alpha: 255 },
+            foreground: Pixel { red: 0, green: 0, blue: 0, alpha: 255 },
+        }
+    }
+}
+
+impl SceneRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn with_margin(mut self, margin: u32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn with_default_size(mut self, default_size: u32) -> Self {
+        self.default_size = default_size;
+        self
+    }
+
+    pub fn with_background(mut self, background: Pixel) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// تخطيط المشهد: كل جسم يأخذ خلية في شبكة؛ `left_of` يحدد الأعمدة و `above` الصفوف
+    /// و `larger` الأحجام، ثم يُصغَّر المشهد ليتسع في اللوحة
+    pub fn layout(&self, description: &SceneDescription) -> Result<Scene, String> {
+        let count = description.objects.len();
+        let index: HashMap<&str, usize> = description.objects.iter()
+            .enumerate()
+            .map(|(i, object)| (object.id.as_str(), i))
+            .collect();
+
+        let mut horizontal = Vec::new();
+        let mut vertical = Vec::new();
+        let mut larger = Vec::new();
+        for relation in &description.relations {
+            let edge = |a: &String, b: &String| (index[a.as_str()], index[b.as_str()]);
+            match relation {
+                SpatialRelation::LeftOf(a, b) => horizontal.push(edge(a, b)),
+                SpatialRelation::Above(a, b) => vertical.push(edge(a, b)),
+                SpatialRelation::Larger(a, b) => larger.push(edge(a, b)),
+            }
+        }
+
+        let sizes = self.resolve_sizes(description, &larger)?;
+
+        // أجسام بلا علاقة تفصلها تتقاسم الخلية نفسها: تُزاح إلى عمود جديد حتى لا تتداخل
+        let (columns, rows) = loop {
+            let columns = longest_paths(count, &horizontal)
+                .ok_or("Contradictory left/right relations in scene description")?;
+            let rows = longest_paths(count, &vertical)
+                .ok_or("Contradictory above/below relations in scene description")?;
+            let collision = (0..count)
+                .flat_map(|a| (a + 1..count).map(move |b| (a, b)))
+                .find(|&(a, b)| columns[a] == columns[b] && rows[a] == rows[b]);
+            match collision {
+                Some(edge) => horizontal.push(edge),
+                None => break (columns, rows),
+            }
+        };
+
+        let column_count = columns.iter().max().map_or(0, |max| max + 1);
+        let row_count = rows.iter().max().map_or(0, |max| max + 1);
+        let mut column_widths = vec![0u32; column_count];
+        let mut row_heights = vec![0u32; row_count];
+        for i in 0..count {
+            let (width, height) = extent(description.objects[i].shape, sizes[i]);
+            column_widths[columns[i]] = column_widths[columns[i]].max(width);
+            row_heights[rows[i]] = row_heights[rows[i]].max(height);
+        }
+
+        // تصغير الشبكة لتتسع في اللوحة
+        let needed = |cells: &[u32]| cells.iter().sum::<u32>() + self.margin * (cells.len() as u32 + 1);
+        let available = |total: u32| total.saturating_sub(self.margin * 2).max(1) as f64;
+        let scale = [
+            available(self.width) / needed(&column_widths).max(1) as f64,
+            available(self.height) / needed(&row_heights).max(1) as f64,
+            1.0,
+        ]
+        .into_iter()
+        .fold(f64::INFINITY, f64::min);
+        let scaled = |value: u32| ((value as f64 * scale).round() as u32).max(1);
+
+        let offsets = |cells: &[u32], total: u32| {
+            let used = cells.iter().map(|&cell| scaled(cell) + scaled(self.margin)).sum::<u32>() + scaled(self.margin);
+            let mut offset = total.saturating_sub(used) / 2 + scaled(self.margin);
+            cells.iter()
+                .map(|&cell| {
+                    let start = offset;
+                    offset += scaled(cell) + scaled(self.margin);
+                    (start, scaled(cell))
+                })
+                .collect::<Vec<_>>()
+        };
+        let column_offsets = offsets(&column_widths, self.width);
+        let row_offsets = offsets(&row_heights, self.height);
+
+        let objects = description.objects.iter()
+            .enumerate()
+            .map(|(i, object)| {
+                let (width, height) = extent(object.shape, scaled(sizes[i]));
+                let (column_start, column_width) = column_offsets[columns[i]];
+                let (row_start, row_height) = row_offsets[rows[i]];
+                let (center_x, center_y) = object.position.unwrap_or((
+                    column_start + column_width / 2,
+                    row_start + row_height / 2,
+                ));
+                let min_x = center_x.saturating_sub(width / 2);
+                let min_y = center_y.saturating_sub(height / 2);
+                let bounds = BoundingBox {
+                    min_x,
+                    min_y,
+                    max_x: (min_x + width - 1).min(self.width.saturating_sub(1)),
+                    max_y: (min_y + height - 1).min(self.height.saturating_sub(1)),
+                };
+                SceneObject {
+                    id: object.id.clone(),
+                    shape: object.shape,
+                    confidence: 1.0,
+                    bounds,
+                    area: (bounds.width() * bounds.height()) as usize,
+                }
+            })
+            .collect();
+
+        Ok(Scene {
+            width: self.width,
+            height: self.height,
+            objects,
+        })
+    }
+
+    /// الأحجام المذكورة تبقى كما هي، ويُكبَّر غيرها حتى تتحقق علاقات `larger`
+    fn resolve_sizes(&self, description: &SceneDescription, larger: &[(usize, usize)]) -> Result<Vec<u32>, String> {
+        let mut sizes: Vec<u32> = description.objects.iter()
+            .map(|object| object.size.unwrap_or(self.default_size))
+            .collect();
+
+        for _ in 0..=description.objects.len() {
+            let mut changed = false;
+            for &(big, small) in larger {
+                if sizes[big] > sizes[small] {
+                    continue;
+                }
+                if description.objects[big].size.is_none() {
+                    sizes[big] = sizes[small] * 3 / 2 + 1;
+                    changed = true;
+                } else if description.objects[small].size.is_none() && sizes[big] > 1 {
+                    sizes[small] = (sizes[big] * 2 / 3).max(1);
+                    changed = true;
+                }
+            }
+            if !changed {
+                return Ok(sizes);
+            }
+        }
+        Err("Contradictory larger/smaller relations in scene description".to_string())
+    }
+
+    /// تخطيط المشهد ورسمه
+    ///
+    /// الأشكال الهندسية (دائرة، مربع، مستطيل، مثلث) تُرسم ممتلئة بدقة حتى يعيد
+    /// `SceneExtractor` استخراج الحقائق نفسها، وبقية الأشكال تُرسم من معادلاتها في
+    /// `ArtisticRenderer` بعد تصغيرها إلى المستطيل المحيط
+    pub fn render(&self, description: &SceneDescription, renderer: &mut ArtisticRenderer) -> Result<(Scene, RenderedImage), String> {
+        let scene = self.layout(description)?;
+        let mut pixels = vec![vec![self.background.clone(); self.width as usize]; self.height as usize];
+
+        for (object, spec) in scene.objects.iter().zip(&description.objects) {
+            let color = spec.color.clone().unwrap_or_else(|| self.foreground.clone());
+            let bounds = object.bounds;
+            let tile = match object.shape {
+                BasicShape::Circle | BasicShape::Square | BasicShape::Rectangle | BasicShape::Triangle => None,
+                shape => Some(
+                    renderer.create_shape_with_properties(shape, Vec::new())
+                        .ok_or_else(|| format!("No equation for shape {:?}", shape))?,
+                ),
+            };
+
+            for y in bounds.min_y..=bounds.max_y {
+                for x in bounds.min_x..=bounds.max_x {
+                    let coverage = match &tile {
+                        None => primitive_coverage(object.shape, &bounds, x, y),
+                        Some(tile) => {
+                            let source_x = ((x - bounds.min_x) as u64 * tile.width as u64 / bounds.width() as u64) as usize;
+                            let source_y = ((y - bounds.min_y) as u64 * tile.height as u64 / bounds.height() as u64) as usize;
+                            tile.pixels[source_y][source_x].red as f64 / 255.0
+                        }
+                    };
+                    if coverage > 0.0 {
+                        let pixel = &mut pixels[y as usize][x as usize];
+                        *pixel = blend(pixel, &color, coverage);
+                    }
+                }
+            }
+        }
+
+        let image = RenderedImage {
+            width: self.width,
+            height: self.height,
+            pixels,
+        };
+        Ok((scene, image))
+    }
+}
+
+/// الحجم المعطى هو البعد الأكبر؛ ارتفاع المستطيل ثلاثة أخماس عرضه
+fn extent(shape: BasicShape, size: u32) -> (u32, u32) {
+    match shape {
+        BasicShape::Rectangle => (size, (size * 3 / 5).max(1)),
+        _ => (size, size),
+    }
+}
+
+/// ترتيب كل جسم في أطول مسار من الحواف (a قبل b)، أو None عند وجود دورة
+fn longest_paths(count: usize, edges: &[(usize, usize)]) -> Option<Vec<usize>> {
+    let mut levels = vec![0usize; count];
+    for _ in 0..=count {
+        let mut changed = false;
+        for &(before, after) in edges {
+            if levels[after] < levels[before] + 1 {
+                levels[after] = levels[before] + 1;
+                changed = true;
+            }
+        }
+        if !changed {
+            return Some(levels);
+        }
+    }
+    None
+}
+
+/// هل يقع البكسل داخل الشكل الهندسي المرسوم في المستطيل المحيط
+fn primitive_coverage(shape: BasicShape, bounds: &BoundingBox, x: u32, y: u32) -> f64 {
+    let half_width = bounds.width() as f64 / 2.0;
+    let half_height = bounds.height() as f64 / 2.0;
+    let dx = x as f64 + 0.5 - (bounds.min_x as f64 + half_width);
+    let dy = y as f64 + 0.5 - (bounds.min_y as f64 + half_height);
+    let inside = match shape {
+        BasicShape::Circle => (dx / half_width).powi(2) + (dy / half_height).powi(2) <= 1.0,
+        // مثلث متساوي الساقين رأسه للأعلى
+        BasicShape::Triangle => dx.abs() <= half_width * (dy + half_height) / (2.0 * half_height),
+        _ => true,
+    };
+    if inside { 1.0 } else { 0.0 }
+}
+
+fn blend(base: &Pixel, color: &Pixel, coverage: f64) -> Pixel {
+    let mix = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * coverage).round() as u8;
+    Pixel {
+        red: mix(base.red, color.red),
+        green: mix(base.green, color.green),
+        blue: mix(base.blue, color.blue),
+        alpha: 255,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_libraries::scene_facts::SceneExtractor;
+    use crate::builtin_libraries::shape_inference::ShapeInference;
+
+    fn engine_with(facts: &[&str]) -> LogicEngine {
+        let mut engine = LogicEngine::new();
+        for fact in facts {
+            engine.assert_fact(fact).unwrap();
+        }
+        engine
+    }
+
+    #[test]
+    fn test_layout_respects_relations() {
+        let mut engine = engine_with(&[
+            "دائرة(ش1)",
+            "مربع(ش2)",
+            "مثلث(ش3)",
+            "فوق(ش1, ش2)",
+            "يسار(ش3, ش2)",
+            "أكبر(ش2, ش1)",
+            "لون(ش1, أحمر)",
+        ]);
+        let description = SceneDescription::from_logic(&mut engine, "").unwrap();
+        assert_eq!(description.objects.len(), 3);
+        assert_eq!(description.objects[0].color.as_ref().map(|c| c.red), Some(220));
+
+        let scene = SceneRenderer::new().layout(&description).unwrap();
+        let bounds = |id: &str| scene.objects.iter().find(|o| o.id == id).unwrap().bounds;
+        assert!(bounds("ش1").max_y < bounds("ش2").min_y);
+        assert!(bounds("ش3").max_x < bounds("ش2").min_x);
+        assert!(bounds("ش2").width() > bounds("ش1").width());
+
+        engine.assert_fact("left_of(ش2, ش3)").unwrap();
+        let contradictory = SceneDescription::from_logic(&mut engine, "").unwrap();
+        assert!(SceneRenderer::new().layout(&contradictory).is_err());
+    }
+
+    #[test]
+    fn test_rendered_scene_round_trips_through_extraction() {
+        let mut engine = engine_with(&[
+            "object(sun1)",
+            "shape(sun1, circle)",
+            "object(box1)",
+            "shape(box1, square)",
+            "above(sun1, box1)",
+            "larger(box1, sun1)",
+        ]);
+        let description = SceneDescription::from_logic(&mut engine, "object(X)").unwrap();
+        let (_, image) = SceneRenderer::new()
+            .with_size(96, 96)
+            .render(&description, &mut ArtisticRenderer::new())
+            .unwrap();
+
+        let extracted = SceneExtractor::new().extract(&image, &mut ShapeInference::new());
+        let facts = extracted.facts();
+        assert_eq!(extracted.objects.len(), 2);
+        assert!(facts.contains(&"above(circle1, square1)".to_string()), "{:?}", facts);
+        assert!(facts.contains(&"larger(square1, circle1)".to_string()), "{:?}", facts);
+    }
+}
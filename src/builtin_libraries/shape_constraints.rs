@@ -0,0 +1,314 @@
+// القيود بين خصائص الأشكال: ربط الرسم بالمنطق
+// Shape Property Constraints: Linking Rendering and Logic
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::adaptive_equations::{approx_eq, DEFAULT_PRECISION};
+use super::artistic_renderer::{BasicShape, ShapeProperty};
+use crate::runtime::LogicEngine;
+
+/// محمول القيد: الاسم الإنجليزي ومرادفه العربي
+///
+/// `constraint(A, size, B, width, 0.5)` تعني أن حجم A يساوي نصف عرض B، ويضيف
+/// معامل سادس إزاحة: `constraint(A, height, B, height, 1, 10)`
+const CONSTRAINT: [&str; 2] = ["constraint", "قيد"];
+
+/// خاصية رقمية لشكل معين
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PropertyRef {
+    pub shape: String,
+    /// اسم الخاصية كما يعيده `ShapeProperty::name` (مثل "Width")
+    pub property: &'static str,
+}
+
+impl PropertyRef {
+    pub fn new(shape: &str, property: &str) -> Result<Self, ConstraintError> {
+        let property = ShapeProperty::numeric(property, 0.0)
+            .ok_or_else(|| ConstraintError::UnknownProperty(property.to_string()))?;
+        Ok(Self { shape: shape.to_string(), property: property.name() })
+    }
+}
+
+impl fmt::Display for PropertyRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.shape, self.property)
+    }
+}
+
+/// قيد خطي: `target = factor * source + offset`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyConstraint {
+    pub target: PropertyRef,
+    pub source: PropertyRef,
+    pub factor: f64,
+    pub offset: f64,
+}
+
+impl PropertyConstraint {
+    pub fn new(target: PropertyRef, source: PropertyRef, factor: f64) -> Self {
+        Self { target, source, factor, offset: 0.0 }
+    }
+
+    pub fn with_offset(mut self, offset: f64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// قراءة القيود المعرفة في قاعدة المعرفة بـ `constraint` أو `قيد`
+    pub fn from_logic(engine: &mut LogicEngine) -> Result<Vec<Self>, ConstraintError> {
+        let mut constraints = Vec::new();
+        for name in CONSTRAINT {
+            for query in [format!("{}(T, P, S, Q, F)", name), format!("{}(T, P, S, Q, F, O)", name)] {
+                let solutions = engine.solve_query(&query)
+                    .map_err(|e| ConstraintError::Logic(format!("Query '{}' failed: {}", query, e)))?;
+                for solution in solutions {
+                    let field = |variable: &str| solution.get(variable).map(String::as_str).unwrap_or_default();
+                    let number = |variable: &str| field(variable).parse::<f64>()
+                        .map_err(|_| ConstraintError::InvalidFact(format!("'{}' in {} is not a number", field(variable), query)));
+                    let constraint = Self::new(
+                        PropertyRef::new(field("T"), field("P"))?,
+                        PropertyRef::new(field("S"), field("Q"))?,
+                        number("F")?,
+                    );
+                    let offset = if solution.contains_key("O") { number("O")? } else { 0.0 };
+                    constraints.push(constraint.with_offset(offset));
+                }
+            }
+        }
+        Ok(constraints)
+    }
+}
+
+impl fmt::Display for PropertyConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {} * {}", self.target, self.factor, self.source)?;
+        if self.offset != 0.0 {
+            write!(f, " + {}", self.offset)?;
+        }
+        Ok(())
+    }
+}
+
+/// شكل يُرسم بعد حل القيود، مع خصائصه المعطاة
+#[derive(Debug, Clone)]
+pub struct ConstrainedShape {
+    /// معرف الشكل الذي تشير إليه القيود
+    pub id: String,
+    pub shape: BasicShape,
+    pub properties: Vec<ShapeProperty>,
+}
+
+impl ConstrainedShape {
+    pub fn new(id: &str, shape: BasicShape, properties: Vec<ShapeProperty>) -> Self {
+        Self { id: id.to_string(), shape, properties }
+    }
+
+    /// قيمة خاصية رقمية (آخر قيمة معطاة لها)
+    pub fn value(&self, property: &str) -> Option<f64> {
+        self.properties.iter().rev()
+            .find(|candidate| candidate.name() == property)
+            .and_then(ShapeProperty::numeric_value)
+    }
+}
+
+/// قيد لا تحققه القيم: كان ينبغي أن يكون الهدف `expected` فكان `actual`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstraintConflict {
+    pub constraint: PropertyConstraint,
+    pub expected: f64,
+    pub actual: f64,
+}
+
+impl fmt::Display for ConstraintConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} requires {} but it is {}", self.constraint, self.expected, self.actual)
+    }
+}
+
+/// أخطاء حل القيود
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ConstraintError {
+    #[error("'{0}' is not a numeric shape property")]
+    UnknownProperty(String),
+
+    #[error("Unknown shape '{0}'")]
+    UnknownShape(String),
+
+    #[error("Invalid constraint fact: {0}")]
+    InvalidFact(String),
+
+    #[error("{0}")]
+    Logic(String),
+
+    /// كل القيود المتعارضة، لا أولها فقط
+    #[error("Unsatisfiable shape constraints: {}", describe(.0))]
+    Unsatisfiable(Vec<ConstraintConflict>),
+}
+
+fn describe(conflicts: &[ConstraintConflict]) -> String {
+    conflicts.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+}
+
+/// حل القيود وإكمال خصائص الأشكال بالقيم المستنتجة
+///
+/// تنتشر القيم المعطاة عبر القيود في الاتجاهين حتى تستقر، ثم تأخذ الخاصية
+/// التي لا يحددها شيء قيمتها الافتراضية (1 للأبعاد و0 للاتجاه) وتنتشر بدورها.
+/// يعاد عدد الخصائص المضافة، أو كل القيود التي لا تتحقق بعد ذلك
+pub fn solve(shapes: &mut [ConstrainedShape], constraints: &[PropertyConstraint]) -> Result<usize, ConstraintError> {
+    let references: Vec<&PropertyRef> = constraints.iter()
+        .flat_map(|constraint| [&constraint.source, &constraint.target])
+        .collect();
+    if let Some(unknown) = references.iter().find(|reference| !shapes.iter().any(|shape| shape.id == reference.shape)) {
+        return Err(ConstraintError::UnknownShape(unknown.shape.clone()));
+    }
+
+    let mut values: HashMap<PropertyRef, f64> = HashMap::new();
+    for shape in shapes.iter() {
+        for property in &shape.properties {
+            if let Some(value) = property.numeric_value() {
+                values.insert(PropertyRef { shape: shape.id.clone(), property: property.name() }, value);
+            }
+        }
+    }
+    let given: HashSet<PropertyRef> = values.keys().cloned().collect();
+
+    propagate(constraints, &mut values);
+    while let Some(free) = references.iter().find(|reference| !values.contains_key(**reference)) {
+        values.insert((*free).clone(), default_value(free.property));
+        propagate(constraints, &mut values);
+    }
+
+    let conflicts: Vec<ConstraintConflict> = constraints.iter()
+        .filter_map(|constraint| {
+            let expected = constraint.factor * values[&constraint.source] + constraint.offset;
+            let actual = values[&constraint.target];
+            (!approx_eq(expected, actual, DEFAULT_PRECISION)).then(|| ConstraintConflict {
+                constraint: constraint.clone(),
+                expected,
+                actual,
+            })
+        })
+        .collect();
+    if !conflicts.is_empty() {
+        return Err(ConstraintError::Unsatisfiable(conflicts));
+    }
+
+    let mut added = HashSet::new();
+    for reference in references {
+        if given.contains(reference) || !added.insert(reference) {
+            continue;
+        }
+        let shape = shapes.iter_mut()
+            .find(|shape| shape.id == reference.shape)
+            .expect("constraint shapes were checked");
+        shape.properties.extend(ShapeProperty::numeric(reference.property, values[reference]));
+    }
+    Ok(added.len())
+}
+
+/// نشر القيم عبر القيود حتى لا تتغير
+fn propagate(constraints: &[PropertyConstraint], values: &mut HashMap<PropertyRef, f64>) {
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for constraint in constraints {
+            match (values.get(&constraint.source).copied(), values.get(&constraint.target).copied()) {
+                (Some(source), None) => {
+                    values.insert(constraint.target.clone(), constraint.factor * source + constraint.offset);
+                    changed = true;
+                }
+                (None, Some(target)) if constraint.factor != 0.0 => {
+                    values.insert(constraint.source.clone(), (target - constraint.offset) / constraint.factor);
+                    changed = true;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// القيمة التي تُرسم بها الخاصية إن لم تُعط
+fn default_value(property: &str) -> f64 {
+    if property == "Orientation" { 0.0 } else { 1.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin_libraries::ArtisticRenderer;
+
+    fn constraint(target: (&str, &str), source: (&str, &str), factor: f64) -> PropertyConstraint {
+        PropertyConstraint::new(
+            PropertyRef::new(target.0, target.1).unwrap(),
+            PropertyRef::new(source.0, source.1).unwrap(),
+            factor,
+        )
+    }
+
+    #[test]
+    fn test_constraints_propagate_both_ways() {
+        let mut shapes = vec![
+            ConstrainedShape::new("a", BasicShape::Circle, vec![]),
+            ConstrainedShape::new("b", BasicShape::Rectangle, vec![ShapeProperty::Width(3.0)]),
+            ConstrainedShape::new("c", BasicShape::Square, vec![ShapeProperty::Height(8.0)]),
+        ];
+        let constraints = [
+            // حجم a نصف عرض b
+            constraint(("a", "size"), ("b", "width"), 0.5),
+            // ارتفاع c ضعف ارتفاع b زائد 2
+            constraint(("c", "height"), ("b", "ارتفاع"), 2.0).with_offset(2.0),
+            // عمق a لا يحدده إلا القيد فيأخذ القيمة الافتراضية
+            constraint(("b", "depth"), ("a", "depth"), 4.0),
+        ];
+        let added = solve(&mut shapes, &constraints).unwrap();
+        assert_eq!(added, 4);
+        assert_eq!(shapes[0].value("Size"), Some(1.5));
+        assert_eq!(shapes[1].value("Height"), Some(3.0));
+        assert_eq!(shapes[0].value("Depth"), Some(1.0));
+        assert_eq!(shapes[1].value("Depth"), Some(4.0));
+
+        let images = ArtisticRenderer::new().create_constrained_shapes(shapes, &constraints).unwrap();
+        assert_eq!(images.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_unsatisfiable_constraints_are_all_reported() {
+        let mut shapes = vec![
+            ConstrainedShape::new("a", BasicShape::Circle, vec![ShapeProperty::Size(2.0)]),
+            ConstrainedShape::new("b", BasicShape::Square, vec![ShapeProperty::Width(2.0)]),
+        ];
+        let constraints = [
+            constraint(("a", "size"), ("b", "width"), 1.0),
+            constraint(("a", "size"), ("b", "width"), 2.0),
+            constraint(("b", "size"), ("b", "size"), 2.0),
+        ];
+        let Err(ConstraintError::Unsatisfiable(conflicts)) = solve(&mut shapes, &constraints) else {
+            panic!("expected unsatisfiable constraints");
+        };
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!((conflicts[0].expected, conflicts[0].actual), (4.0, 2.0));
+        assert_eq!(conflicts[1].constraint.target.to_string(), "b.Size");
+
+        assert_eq!(
+            solve(&mut shapes, &[constraint(("a", "size"), ("z", "width"), 1.0)]),
+            Err(ConstraintError::UnknownShape("z".to_string()))
+        );
+        assert!(matches!(PropertyRef::new("a", "color"), Err(ConstraintError::UnknownProperty(_))));
+    }
+
+    #[test]
+    fn test_constraints_from_logic() {
+        let mut engine = LogicEngine::new();
+        engine.assert_fact("constraint(a, size, b, width, 0.5)").unwrap();
+        engine.assert_fact("قيد(b, height, a, height, 1, 10)").unwrap();
+        let constraints = PropertyConstraint::from_logic(&mut engine).unwrap();
+        assert_eq!(constraints, [
+            constraint(("a", "size"), ("b", "width"), 0.5),
+            constraint(("b", "height"), ("a", "height"), 1.0).with_offset(10.0),
+        ]);
+
+        engine.assert_fact("constraint(a, size, b, width, half)").unwrap();
+        assert!(matches!(PropertyConstraint::from_logic(&mut engine), Err(ConstraintError::InvalidFact(_))));
+    }
+}
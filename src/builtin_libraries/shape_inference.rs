@@ -6,6 +6,7 @@ use super::adaptive_equations::{GeneralShapeEquation, ComplexNumber, Generalized
 use super::artistic_renderer::{BasicShape, ShapeProperty, RenderedImage, Pixel};
 use super::thinking_core::{ThinkingCore, AnalysisResult, LayerType};
 use super::expert_explorer::{ExpertExplorer, DecisionResult, OperationMode};
+use super::scene_facts::largest_geometric_shape;
 
 // ========== تحليل الصورة - Image Analysis ==========
 #[derive(Debug, Clone)]
@@ -486,12 +487,16 @@ impl ShapeInference {
         let decision_input = self.thinking_result_to_decision_input(&thinking_result);
         let decision_result = self.expert_explorer.make_decision(decision_input);
 
-        // توليد المعادلة
-        let predicted_shape = self.decision_to_shape(&decision_result);
+        // الأشكال الهندسية تُصنف من امتلاء المستطيل المحيط بأكبر جسم، وما عداها بقرار الخبير/المستكشف
+        let geometric = largest_geometric_shape(image);
+        let predicted_shape = geometric.map_or_else(|| self.decision_to_shape(&decision_result), |(shape, _)| shape);
         let equation = self.equation_generator.generate_equation(&features, predicted_shape);
 
         // تقييم الثقة
-        let confidence = self.confidence_evaluator.evaluate_confidence(&features, predicted_shape, &equation);
+        let confidence = geometric.map_or_else(
+            || self.confidence_evaluator.evaluate_confidence(&features, predicted_shape, &equation),
+            |(_, confidence)| confidence,
+        );
 
         InferenceResult {
             predicted_shape,
@@ -12,6 +12,13 @@ pub struct ThinkingLayer {
     pub activation_threshold: f64,
     pub memory_capacity: usize,
     pub current_state: LayerState,
+    /// الطبقات المعطلة تُتخطى أثناء التحليل
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 /// أنواع الطبقات التفكيرية الثمانية
@@ -102,16 +109,7 @@ impl ThinkingCore {
         let mut databases = HashMap::new();
 
         // إنشاء الطبقات الثمانية
-        for layer_type in [
-            LayerType::Mathematical,
-            LayerType::Linguistic,
-            LayerType::Logical,
-            LayerType::Physical,
-            LayerType::Semantic,
-            LayerType::Visual,
-            LayerType::Symbolic,
-            LayerType::Interpretive,
-        ] {
+        for layer_type in LayerType::all() {
             layers.insert(layer_type.clone(), ThinkingLayer::new(layer_type.clone()));
             databases.insert(layer_type.clone(), LayerDatabase::new(layer_type.clone()));
         }
@@ -120,19 +118,80 @@ impl ThinkingCore {
             layers,
             databases,
             global_state: GlobalThinkingState::new(),
-            processing_pipeline: vec![
-                LayerType::Mathematical,
-                LayerType::Linguistic,
-                LayerType::Logical,
-                LayerType::Physical,
-                LayerType::Semantic,
-                LayerType::Visual,
-                LayerType::Symbolic,
-                LayerType::Interpretive,
-            ],
+            processing_pipeline: LayerType::all(),
+        }
+    }
+
+    /// إنشاء نواة تفكيرية بخط معالجة مخصص
+    pub fn with_pipeline(config: &PipelineConfig) -> Result<Self, String> {
+        let mut core = Self::new();
+        core.apply_pipeline(config)?;
+        Ok(core)
+    }
+
+    /// تطبيق خط معالجة مخصص على النواة
+    pub fn apply_pipeline(&mut self, config: &PipelineConfig) -> Result<(), String> {
+        config.validate()?;
+
+        for stage in &config.stages {
+            let layer = self.layers.entry(stage.layer_type.clone())
+                .or_insert_with(|| ThinkingLayer::new(stage.layer_type.clone()));
+            layer.enabled = stage.enabled;
+            if let Some(weight) = stage.processing_weight {
+                layer.processing_weight = weight;
+            }
+            if let Some(threshold) = stage.activation_threshold {
+                layer.activation_threshold = threshold;
+            }
+            if let Some(capacity) = stage.memory_capacity {
+                layer.memory_capacity = capacity;
+            }
+            self.databases.entry(stage.layer_type.clone())
+                .or_insert_with(|| LayerDatabase::new(stage.layer_type.clone()));
+        }
+
+        self.processing_pipeline = config.stages.iter().map(|s| s.layer_type.clone()).collect();
+        self.global_state.processing_mode = config.processing_mode.clone();
+        Ok(())
+    }
+
+    /// خط المعالجة الحالي كإعدادات قابلة للحفظ
+    pub fn pipeline_config(&self, name: &str) -> PipelineConfig {
+        let stages = self.processing_pipeline.iter()
+            .map(|layer_type| {
+                let layer = self.layers.get(layer_type);
+                LayerConfig {
+                    layer_type: layer_type.clone(),
+                    enabled: layer.map(|l| l.enabled).unwrap_or(true),
+                    processing_weight: layer.map(|l| l.processing_weight),
+                    activation_threshold: layer.map(|l| l.activation_threshold),
+                    memory_capacity: layer.map(|l| l.memory_capacity),
+                }
+            })
+            .collect();
+
+        PipelineConfig {
+            name: name.to_string(),
+            stages,
+            processing_mode: self.global_state.processing_mode.clone(),
         }
     }
 
+    /// تفعيل أو تعطيل طبقة مع التحقق من بقاء خط المعالجة صالحاً
+    pub fn set_layer_enabled(&mut self, layer_type: &LayerType, enabled: bool) -> Result<(), String> {
+        let mut config = self.pipeline_config("current");
+        let stage = config.stages.iter_mut()
+            .find(|s| &s.layer_type == layer_type)
+            .ok_or_else(|| format!("Layer {:?} is not part of the pipeline", layer_type))?;
+        stage.enabled = enabled;
+        config.validate()?;
+
+        if let Some(layer) = self.layers.get_mut(layer_type) {
+            layer.enabled = enabled;
+        }
+        Ok(())
+    }
+
     /// تحليل البيانات عبر جميع الطبقات
     pub fn analyze(&mut self, input_data: Vec<f64>) -> AnalysisResult {
         let start_time = std::time::SystemTime::now()
@@ -145,7 +204,7 @@ impl ThinkingCore {
 
         // معالجة عبر كل طبقة
         for layer_type in &self.processing_pipeline {
-            if let Some(layer) = self.layers.get_mut(layer_type) {
+            if let Some(layer) = self.layers.get_mut(layer_type).filter(|layer| layer.enabled) {
                 let contribution = layer.process(&processed_data);
                 layer_contributions.insert(layer_type.clone(), contribution);
 
@@ -189,11 +248,7 @@ impl ThinkingCore {
             AnalysisDomain::Visual => vec![
                 LayerType::Visual, LayerType::Physical, LayerType::Mathematical
             ],
-            AnalysisDomain::General => vec![
-                LayerType::Mathematical, LayerType::Linguistic, LayerType::Logical,
-                LayerType::Physical, LayerType::Semantic, LayerType::Visual,
-                LayerType::Symbolic, LayerType::Interpretive,
-            ],
+            AnalysisDomain::General => LayerType::all(),
         };
 
         self.analyze(input_data)
@@ -272,6 +327,220 @@ impl ThinkingCore {
     }
 }
 
+/// إعدادات طبقة واحدة داخل خط المعالجة
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerConfig {
+    pub layer_type: LayerType,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub processing_weight: Option<f64>,
+    #[serde(default)]
+    pub activation_threshold: Option<f64>,
+    #[serde(default)]
+    pub memory_capacity: Option<usize>,
+}
+
+/// خط معالجة مخصص - ترتيب الطبقات ومعاملاتها
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineConfig {
+    pub name: String,
+    pub stages: Vec<LayerConfig>,
+    #[serde(default = "default_processing_mode")]
+    pub processing_mode: ProcessingMode,
+}
+
+fn default_processing_mode() -> ProcessingMode {
+    ProcessingMode::Sequential
+}
+
+/// باني خط المعالجة
+#[derive(Debug, Clone)]
+pub struct PipelineBuilder {
+    config: PipelineConfig,
+}
+
+impl PipelineBuilder {
+    /// إنشاء باني خط معالجة فارغ
+    pub fn new(name: &str) -> Self {
+        Self {
+            config: PipelineConfig {
+                name: name.to_string(),
+                stages: Vec::new(),
+                processing_mode: ProcessingMode::Sequential,
+            },
+        }
+    }
+
+    /// إضافة طبقة في نهاية خط المعالجة
+    pub fn layer(mut self, layer_type: LayerType) -> Self {
+        self.config.stages.push(LayerConfig::new(layer_type));
+        self
+    }
+
+    /// إضافة طبقة بإعدادات كاملة
+    pub fn layer_config(mut self, config: LayerConfig) -> Self {
+        self.config.stages.push(config);
+        self
+    }
+
+    /// وزن المعالجة للطبقة الأخيرة المضافة
+    pub fn weight(mut self, processing_weight: f64) -> Self {
+        if let Some(stage) = self.config.stages.last_mut() {
+            stage.processing_weight = Some(processing_weight);
+        }
+        self
+    }
+
+    /// عتبة التفعيل للطبقة الأخيرة المضافة
+    pub fn threshold(mut self, activation_threshold: f64) -> Self {
+        if let Some(stage) = self.config.stages.last_mut() {
+            stage.activation_threshold = Some(activation_threshold);
+        }
+        self
+    }
+
+    /// سعة الذاكرة للطبقة الأخيرة المضافة
+    pub fn memory_capacity(mut self, capacity: usize) -> Self {
+        if let Some(stage) = self.config.stages.last_mut() {
+            stage.memory_capacity = Some(capacity);
+        }
+        self
+    }
+
+    /// تعطيل أو تفعيل طبقة موجودة في خط المعالجة
+    pub fn enabled(mut self, layer_type: LayerType, enabled: bool) -> Self {
+        if let Some(stage) = self.config.stages.iter_mut().find(|s| s.layer_type == layer_type) {
+            stage.enabled = enabled;
+        }
+        self
+    }
+
+    /// نمط المعالجة
+    pub fn processing_mode(mut self, mode: ProcessingMode) -> Self {
+        self.config.processing_mode = mode;
+        self
+    }
+
+    /// بناء خط المعالجة بعد التحقق من توافق الطبقات
+    pub fn build(self) -> Result<PipelineConfig, String> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+impl LayerConfig {
+    pub fn new(layer_type: LayerType) -> Self {
+        Self {
+            layer_type,
+            enabled: true,
+            processing_weight: None,
+            activation_threshold: None,
+            memory_capacity: None,
+        }
+    }
+}
+
+impl PipelineConfig {
+    /// التحقق من صحة المعاملات وتوافق ترتيب الطبقات
+    pub fn validate(&self) -> Result<(), String> {
+        let active: Vec<&LayerType> = self.stages.iter()
+            .filter(|stage| stage.enabled)
+            .map(|stage| &stage.layer_type)
+            .collect();
+
+        if active.is_empty() {
+            return Err(format!("Pipeline '{}' has no enabled layers", self.name));
+        }
+
+        for (index, stage) in self.stages.iter().enumerate() {
+            if self.stages[..index].iter().any(|s| s.layer_type == stage.layer_type) {
+                return Err(format!("Pipeline '{}': layer {:?} appears more than once", self.name, stage.layer_type));
+            }
+            if let Some(weight) = stage.processing_weight {
+                if !weight.is_finite() {
+                    return Err(format!("Pipeline '{}': weight of {:?} must be finite", self.name, stage.layer_type));
+                }
+            }
+            if let Some(threshold) = stage.activation_threshold {
+                if !(0.0..=1.0).contains(&threshold) {
+                    return Err(format!("Pipeline '{}': threshold of {:?} must be within [0, 1]", self.name, stage.layer_type));
+                }
+            }
+            if stage.memory_capacity == Some(0) {
+                return Err(format!("Pipeline '{}': memory capacity of {:?} must be positive", self.name, stage.layer_type));
+            }
+        }
+
+        for (index, layer) in active.iter().enumerate() {
+            let predecessors = &active[..index];
+            if let Some(required) = layer.required_predecessors() {
+                if !predecessors.iter().any(|p| required.contains(p)) {
+                    return Err(format!(
+                        "Pipeline '{}': layer {:?} requires one of {:?} before it",
+                        self.name, layer, required
+                    ));
+                }
+            }
+            if **layer == LayerType::Interpretive && index + 1 != active.len() {
+                return Err(format!("Pipeline '{}': the Interpretive layer must be the last enabled layer", self.name));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// الطبقات المفعلة بالترتيب
+    pub fn active_layers(&self) -> Vec<LayerType> {
+        self.stages.iter()
+            .filter(|stage| stage.enabled)
+            .map(|stage| stage.layer_type.clone())
+            .collect()
+    }
+}
+
+impl LayerType {
+    /// جميع الطبقات بالترتيب الافتراضي
+    pub fn all() -> Vec<LayerType> {
+        vec![
+            LayerType::Mathematical,
+            LayerType::Linguistic,
+            LayerType::Logical,
+            LayerType::Physical,
+            LayerType::Semantic,
+            LayerType::Visual,
+            LayerType::Symbolic,
+            LayerType::Interpretive,
+        ]
+    }
+
+    /// الطبقات التي يجب أن تسبق هذه الطبقة (واحدة منها على الأقل)
+    pub fn required_predecessors(&self) -> Option<&'static [LayerType]> {
+        match self {
+            // الدلالة تبنى على التحليل اللغوي
+            LayerType::Semantic => Some(&[LayerType::Linguistic]),
+            // الرموز تستخلص من التمثيل الرياضي أو المنطقي أو اللغوي
+            LayerType::Symbolic => Some(&[LayerType::Mathematical, LayerType::Logical, LayerType::Linguistic]),
+            _ => None,
+        }
+    }
+
+    /// تحويل اسم نصي (عربي أو إنجليزي) إلى نوع طبقة
+    pub fn from_name(name: &str) -> Option<LayerType> {
+        match name.trim().to_lowercase().as_str() {
+            "mathematical" | "رياضية" => Some(LayerType::Mathematical),
+            "linguistic" | "لغوية" => Some(LayerType::Linguistic),
+            "logical" | "منطقية" => Some(LayerType::Logical),
+            "physical" | "فيزيائية" => Some(LayerType::Physical),
+            "semantic" | "دلالية" => Some(LayerType::Semantic),
+            "visual" | "بصرية" => Some(LayerType::Visual),
+            "symbolic" | "رمزية" => Some(LayerType::Symbolic),
+            "interpretive" | "تفسيرية" => Some(LayerType::Interpretive),
+            _ => None,
+        }
+    }
+}
+
 /// مجالات التحليل
 #[derive(Debug, Clone, PartialEq)]
 pub enum AnalysisDomain {
@@ -289,6 +558,7 @@ impl ThinkingLayer {
             activation_threshold: 0.5,
             memory_capacity: 1000,
             current_state: LayerState::new(),
+            enabled: true,
         }
     }
 
@@ -347,3 +617,52 @@ impl GlobalThinkingState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_builder_validation() {
+        let pipeline = PipelineBuilder::new("lingua")
+            .layer(LayerType::Linguistic).weight(1.5).threshold(0.2)
+            .layer(LayerType::Semantic)
+            .layer(LayerType::Interpretive)
+            .build()
+            .unwrap();
+        assert_eq!(pipeline.active_layers().len(), 3);
+
+        // الدلالة تحتاج إلى طبقة لغوية قبلها
+        assert!(PipelineBuilder::new("bad").layer(LayerType::Semantic).build().is_err());
+        // التفسير يجب أن يكون آخر طبقة مفعلة
+        assert!(PipelineBuilder::new("bad")
+            .layer(LayerType::Interpretive)
+            .layer(LayerType::Mathematical)
+            .build()
+            .is_err());
+        assert!(PipelineBuilder::new("bad")
+            .layer(LayerType::Logical).threshold(1.5)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_apply_pipeline_skips_disabled_layers() {
+        let pipeline = PipelineBuilder::new("math")
+            .layer(LayerType::Mathematical).weight(2.0)
+            .layer(LayerType::Visual)
+            .enabled(LayerType::Visual, false)
+            .build()
+            .unwrap();
+
+        let mut core = ThinkingCore::with_pipeline(&pipeline).unwrap();
+        let result = core.analyze(vec![0.5, 0.8]);
+        assert!(result.layer_contributions.contains_key(&LayerType::Mathematical));
+        assert!(!result.layer_contributions.contains_key(&LayerType::Visual));
+
+        let saved = core.pipeline_config("math");
+        assert_eq!(saved.stages.len(), 2);
+        assert_eq!(saved.stages[0].processing_weight, Some(2.0));
+        assert!(core.set_layer_enabled(&LayerType::Mathematical, false).is_err());
+    }
+}
@@ -0,0 +1,355 @@
+//! # Dependencies
+//!
+//! Resolves the `[dependencies]` of a project's `Bayan.toml` to packages on
+//! disk. A dependency is another project, given by its directory or by a git
+//! repository:
+//!
+//! ```toml
+//! [dependencies]
+//! geometry = { path = "../geometry" }
+//! charts = { git = "https://example.com/charts.git", tag = "v1.0" }
+//! ```
+//!
+//! Path dependencies are used in place, relative to the manifest that lists
+//! them. Git dependencies are cloned once into `target/deps/git` and reused
+//! from there by later builds; delete that directory to fetch them again.
+//! The dependencies of dependencies are resolved the same way, all cached
+//! under the root project's target directory. There is no package registry
+//! yet, so a dependency with only a `version` is an error.
+//!
+//! Each package is imported under its dependency name: `using geometry;`
+//! loads the package's entry file as module `geometry`, and
+//! `using geometry::shapes;` its `shapes.ab`; see `modules::graph`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::project::{Project, ProjectError};
+use crate::codegen::cache::CacheKeyBuilder;
+use crate::modules::package::DependencySpec;
+
+/// Directory under the target directory that git dependencies are cloned to
+pub const GIT_CACHE_DIR: &str = "deps/git";
+
+/// Why a project's dependencies could not be resolved
+#[derive(Debug, thiserror::Error)]
+pub enum DependencyError {
+    #[error("dependency `{name}`: {reason}")]
+    Unsupported { name: String, reason: String },
+
+    #[error("dependency `{name}` is not a project: {source}")]
+    InvalidPackage { name: String, source: ProjectError },
+
+    #[error("dependency `{name}`: git failed: {message}")]
+    Git { name: String, message: String },
+
+    #[error("dependency `{name}` is required from both {first} and {second}")]
+    Conflict { name: String, first: DependencySource, second: DependencySource },
+
+    #[error(transparent)]
+    Project(#[from] ProjectError),
+}
+
+/// Where a package comes from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DependencySource {
+    Path(PathBuf),
+    Git { url: String, reference: Option<GitReference> },
+}
+
+/// The branch, tag or commit of a git dependency
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GitReference {
+    Branch(String),
+    Tag(String),
+    Rev(String),
+}
+
+/// A dependency found on disk
+#[derive(Debug, Clone)]
+pub struct ResolvedPackage {
+    /// Name the dependency is imported by
+    pub name: String,
+    pub version: String,
+    pub source: DependencySource,
+    pub project: Project,
+}
+
+/// Every package a project depends on, directly or not
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    /// Each package after the packages it depends on
+    pub packages: Vec<ResolvedPackage>,
+}
+
+impl fmt::Display for DependencySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencySource::Path(path) => write!(f, "`{}`", path.display()),
+            DependencySource::Git { url, reference: None } => write!(f, "`{}`", url),
+            DependencySource::Git { url, reference: Some(reference) } => write!(f, "`{}` ({})", url, reference),
+        }
+    }
+}
+
+impl fmt::Display for GitReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitReference::Branch(branch) => write!(f, "branch {}", branch),
+            GitReference::Tag(tag) => write!(f, "tag {}", tag),
+            GitReference::Rev(rev) => write!(f, "rev {}", rev),
+        }
+    }
+}
+
+impl DependencySource {
+    /// Read a manifest entry; `base` is the directory of the manifest
+    pub fn from_spec(name: &str, spec: &DependencySpec, base: &Path) -> Result<Self, DependencyError> {
+        let unsupported = |reason: &str| DependencyError::Unsupported { name: name.to_string(), reason: reason.to_string() };
+        let DependencySpec::Detailed { path, git, branch, tag, rev, .. } = spec else {
+            return Err(unsupported("there is no package registry yet; give a `path` or `git` source"));
+        };
+        match (path, git) {
+            (Some(_), Some(_)) => Err(unsupported("give either `path` or `git`, not both")),
+            (Some(path), None) => Ok(DependencySource::Path(base.join(path))),
+            (None, Some(url)) => {
+                // git would read a leading `-` as one of its options
+                if url.starts_with('-') || [branch, tag, rev].iter().any(|name| name.as_deref().is_some_and(|name| name.starts_with('-'))) {
+                    return Err(unsupported("a git url, branch, tag or rev cannot start with `-`"));
+                }
+                let reference = match (branch, tag, rev) {
+                    (None, None, None) => None,
+                    (Some(branch), None, None) => Some(GitReference::Branch(branch.clone())),
+                    (None, Some(tag), None) => Some(GitReference::Tag(tag.clone())),
+                    (None, None, Some(rev)) => Some(GitReference::Rev(rev.clone())),
+                    _ => return Err(unsupported("give at most one of `branch`, `tag` and `rev`")),
+                };
+                Ok(DependencySource::Git { url: url.clone(), reference })
+            }
+            (None, None) => Err(unsupported("there is no package registry yet; give a `path` or `git` source")),
+        }
+    }
+
+    /// The same source with a canonical path, so that two spellings of one
+    /// directory compare equal
+    fn canonical(self) -> Self {
+        match self {
+            DependencySource::Path(path) => DependencySource::Path(std::fs::canonicalize(&path).unwrap_or(path)),
+            git => git,
+        }
+    }
+}
+
+impl Resolution {
+    /// Entry files of the packages by name, for `CompilerOptions::packages`
+    pub fn entries(&self) -> HashMap<String, PathBuf> {
+        self.packages.iter().map(|package| (package.name.clone(), package.project.entry_path())).collect()
+    }
+}
+
+/// Resolve the dependencies of `project`, cloning git dependencies that are
+/// not cached yet
+pub fn resolve(project: &Project) -> Result<Resolution, DependencyError> {
+    let mut resolver = Resolver { cache: project.target_dir().join(GIT_CACHE_DIR), sources: HashMap::new(), packages: Vec::new() };
+    resolver.visit(project)?;
+    Ok(Resolution { packages: resolver.packages })
+}
+
+struct Resolver {
+    cache: PathBuf,
+    /// Sources of the packages already resolved or being resolved
+    sources: HashMap<String, DependencySource>,
+    packages: Vec<ResolvedPackage>,
+}
+
+impl Resolver {
+    /// Resolve the dependencies of `project`, each before itself
+    fn visit(&mut self, project: &Project) -> Result<(), DependencyError> {
+        for (name, spec) in &project.manifest.dependencies {
+            let source = DependencySource::from_spec(name, spec, &project.root)?.canonical();
+            if let Some(first) = self.sources.get(name) {
+                if *first != source {
+                    let first = first.clone();
+                    return Err(DependencyError::Conflict { name: name.clone(), first, second: source });
+                }
+                // Already resolved, or being resolved higher up a dependency cycle
+                continue;
+            }
+            self.sources.insert(name.clone(), source.clone());
+
+            let root = match &source {
+                DependencySource::Path(path) => path.clone(),
+                DependencySource::Git { url, reference } => self.fetch(name, url, reference.as_ref())?,
+            };
+            let package = Project::load(&root)
+                .map_err(|source| DependencyError::InvalidPackage { name: name.clone(), source })?;
+            self.visit(&package)?;
+            self.packages.push(ResolvedPackage {
+                name: name.clone(),
+                version: package.manifest.package.version.clone(),
+                source,
+                project: package,
+            });
+        }
+        Ok(())
+    }
+
+    /// The checkout of a git dependency, cloned unless it is cached
+    fn fetch(&self, name: &str, url: &str, reference: Option<&GitReference>) -> Result<PathBuf, DependencyError> {
+        let checkout = self.cache.join(cache_key(name, url, reference));
+        if checkout.join(".git").is_dir() {
+            return Ok(checkout);
+        }
+        let git = |args: &[&str]| -> Result<(), DependencyError> {
+            let failed = |message: String| DependencyError::Git { name: name.to_string(), message };
+            let output = Command::new("git").args(args).output().map_err(|error| failed(error.to_string()))?;
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(failed(String::from_utf8_lossy(&output.stderr).trim().to_string()))
+            }
+        };
+
+        std::fs::create_dir_all(&self.cache).map_err(|source| ProjectError::Io { path: self.cache.clone(), source })?;
+        // Clone next to the cache entry and move it in place, so an interrupted
+        // clone is never mistaken for a cached one
+        let partial = checkout.with_extension("partial");
+        let _ = std::fs::remove_dir_all(&partial);
+        let target = partial.to_string_lossy().into_owned();
+        match reference {
+            Some(GitReference::Branch(name) | GitReference::Tag(name)) => {
+                git(&["clone", "--quiet", "--depth", "1", "--branch", name.as_str(), "--", url, target.as_str()])?
+            }
+            _ => git(&["clone", "--quiet", "--", url, target.as_str()])?,
+        }
+        if let Some(GitReference::Rev(rev)) = reference {
+            git(&["-C", target.as_str(), "checkout", "--quiet", "--detach", rev.as_str(), "--"])?;
+        }
+        std::fs::rename(&partial, &checkout).map_err(|source| ProjectError::Io { path: checkout.clone(), source })?;
+        Ok(checkout)
+    }
+}
+
+/// Directory name of a git checkout in the cache: the dependency name and a
+/// hash of where it comes from, which does not change with the toolchain
+fn cache_key(name: &str, url: &str, reference: Option<&GitReference>) -> String {
+    let mut key = CacheKeyBuilder::default();
+    key.add(url.as_bytes());
+    key.add(reference.map(GitReference::to_string).unwrap_or_default().as_bytes());
+    format!("{}-{}", name, key.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancellation::CancellationToken;
+    use crate::modules::graph::ModuleGraph;
+
+    fn workspace(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("albayan_deps_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (path, source) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, source).unwrap();
+        }
+        root
+    }
+
+    fn manifest(name: &str, dependencies: &str) -> String {
+        format!("[package]\nname = \"{}\"\nversion = \"0.2.0\"\n\n[dependencies]\n{}", name, dependencies)
+    }
+
+    #[test]
+    fn test_path_dependencies_are_importable_modules() {
+        let root = workspace("path", &[
+            ("app/Bayan.toml", &manifest("app", "geometry = { path = \"../geometry\" }\n")),
+            ("app/src/main.ab", "using geometry::shapes::area;\nfn main() -> int { return area(2, 3); }\n"),
+            ("geometry/Bayan.toml", &manifest("geometry", "units = { path = \"../units\" }\n")),
+            ("geometry/src/main.ab", "fn pi() -> int { return 3; }\n"),
+            ("geometry/src/shapes.ab", "using units;\npub fn area(w: int, h: int) -> int { return w * h; }\n"),
+            ("units/Bayan.toml", &manifest("units", "")),
+            ("units/src/main.ab", "pub fn metre() -> int { return 1; }\n"),
+        ]);
+        let project = Project::load(&root.join("app")).unwrap();
+        let resolution = resolve(&project).unwrap();
+
+        let names: Vec<&str> = resolution.packages.iter().map(|package| package.name.as_str()).collect();
+        assert_eq!(names, vec!["units", "geometry"]);
+        assert_eq!(resolution.packages[1].version, "0.2.0");
+
+        let graph = ModuleGraph::load_with_packages(&project.entry_path(), &resolution.entries(), &CancellationToken::default())
+            .unwrap();
+        let modules: Vec<&str> = graph.modules().iter().map(|module| module.name.as_str()).collect();
+        assert_eq!(modules, vec!["units", "geometry::shapes", "main"]);
+        assert_eq!(graph.entry().scope()["area"], "geometry::shapes::area");
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_conflicting_and_unsupported_dependencies_are_errors() {
+        let root = workspace("errors", &[
+            ("app/Bayan.toml", &manifest("app", "a = { path = \"../a\" }\nshared = { path = \"../shared_one\" }\n")),
+            ("a/Bayan.toml", &manifest("a", "shared = { path = \"../shared_two\" }\n")),
+            ("shared_one/Bayan.toml", &manifest("shared", "")),
+            ("shared_two/Bayan.toml", &manifest("shared", "")),
+            ("registry/Bayan.toml", &manifest("registry", "geometry = \"1.0\"\n")),
+            ("missing/Bayan.toml", &manifest("missing", "nowhere = { path = \"../nowhere\" }\n")),
+        ]);
+
+        let error = resolve(&Project::load(&root.join("app")).unwrap()).unwrap_err();
+        assert!(matches!(error, DependencyError::Conflict { ref name, .. } if name == "shared"));
+        let error = resolve(&Project::load(&root.join("registry")).unwrap()).unwrap_err();
+        assert!(matches!(error, DependencyError::Unsupported { ref name, .. } if name == "geometry"));
+        let error = resolve(&Project::load(&root.join("missing")).unwrap()).unwrap_err();
+        assert!(matches!(error, DependencyError::InvalidPackage { ref name, .. } if name == "nowhere"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_git_sources_are_cached_by_reference() {
+        let spec = DependencySpec::Detailed {
+            version: None,
+            git: Some("https://example.com/charts.git".to_string()),
+            branch: None,
+            tag: Some("v1.0".to_string()),
+            rev: None,
+            path: None,
+            optional: None,
+            default_features: None,
+            features: None,
+        };
+        let source = DependencySource::from_spec("charts", &spec, Path::new(".")).unwrap();
+        let reference = GitReference::Tag("v1.0".to_string());
+        assert_eq!(source, DependencySource::Git { url: "https://example.com/charts.git".to_string(), reference: Some(reference.clone()) });
+
+        let url = "https://example.com/charts.git";
+        assert_eq!(cache_key("charts", url, Some(&reference)), cache_key("charts", url, Some(&reference)));
+        assert_ne!(cache_key("charts", url, Some(&reference)), cache_key("charts", url, None));
+        assert_ne!(
+            cache_key("charts", url, Some(&reference)),
+            cache_key("charts", url, Some(&GitReference::Branch("v1.0".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_git_arguments_cannot_be_options() {
+        let spec = |git: &str, rev: Option<&str>| DependencySpec::Detailed {
+            version: None,
+            git: Some(git.to_string()),
+            branch: None,
+            tag: None,
+            rev: rev.map(str::to_string),
+            path: None,
+            optional: None,
+            default_features: None,
+            features: None,
+        };
+        for spec in [spec("--upload-pack=touch /tmp/x", None), spec("https://example.com/charts.git", Some("--orphan"))] {
+            let error = DependencySource::from_spec("charts", &spec, Path::new(".")).unwrap_err();
+            assert!(matches!(error, DependencyError::Unsupported { ref name, .. } if name == "charts"));
+        }
+    }
+}
@@ -2,10 +2,16 @@
 //!
 //! This module implements the CLI for the AlBayan compiler and runtime.
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
+use std::time::Duration;
 use crate::{Compiler, CompilerOptions, CompilerResult};
 
+pub mod deps;
+pub mod project;
+
+use project::Project;
+
 /// AlBayan programming language compiler and runtime
 #[derive(Parser)]
 #[command(name = "albayan")]
@@ -22,18 +28,23 @@ pub struct Cli {
     /// Enable debug mode
     #[arg(short, long, global = true)]
     pub debug: bool,
+
+    /// Language of diagnostics: `en` or `ar`; defaults to $ALBAYAN_LANG, or English
+    #[arg(long, global = true, value_name = "LANG")]
+    pub lang: Option<crate::diagnostics::Language>,
 }
 
 /// Available CLI commands
 #[derive(Subcommand)]
 pub enum Commands {
-    /// Compile a source file
+    /// Compile a source file, or every source file of a directory
     Build {
-        /// Source file to compile
+        /// Source file or directory to compile; defaults to the entry file of
+        /// the project (Bayan.toml) containing the current directory
         #[arg(value_name = "FILE")]
-        input: PathBuf,
+        input: Option<PathBuf>,
 
-        /// Output file path
+        /// Output file path (the object file directory when building a directory)
         #[arg(short, long)]
         output: Option<PathBuf>,
 
@@ -60,19 +71,73 @@ pub enum Commands {
         /// Use LLVM backend for code generation
         #[arg(long)]
         llvm: bool,
+
+        /// Artifact to produce (default: exe with --llvm, obj otherwise)
+        #[arg(long, value_enum)]
+        emit: Option<EmitKind>,
+
+        /// Stop the build if parsing, analysis and code generation take longer than SECONDS
+        #[arg(long, value_name = "SECONDS")]
+        timeout: Option<u64>,
+
+        /// Recompile every module instead of reusing unchanged ones from the build cache
+        #[arg(long)]
+        no_cache: bool,
+
+        #[command(flatten)]
+        warnings: WarningArgs,
     },
 
     /// Run a source file directly (JIT compilation)
     Run {
-        /// Source file to run
+        /// Source file to run; defaults to the entry file of the current project
         #[arg(value_name = "FILE")]
-        input: PathBuf,
+        input: Option<PathBuf>,
+
+        /// Stream rendered frames live to a canvas viewer:
+        /// `http` (default 127.0.0.1:7878), `http://HOST:PORT`, `stdout`, `stderr`, or an NDJSON file path
+        #[arg(long, value_name = "TARGET", num_args = 0..=1, default_missing_value = "http")]
+        canvas: Option<String>,
+
+        /// Seed for random numbers (random_int, Tensor::random, exploration...);
+        /// defaults to $ALBAYAN_SEED, or a fresh seed that is printed with --verbose
+        #[arg(long, value_name = "SEED")]
+        seed: Option<u64>,
+
+        /// Trace the call, exit, redo and fail ports of logic goals:
+        /// `stderr` (default), `stdout`, or a JSON trace file path to open in the profiler UI
+        #[arg(long, value_name = "TARGET", num_args = 0..=1, default_missing_value = "stderr")]
+        trace_logic: Option<String>,
+
+        /// Only trace goals of these relations (repeatable or comma-separated)
+        #[arg(long = "trace-relation", value_name = "NAME", value_delimiter = ',', requires = "trace_logic")]
+        trace_relations: Vec<String>,
+
+        /// Only trace goals up to this depth; query goals are at depth 1
+        #[arg(long, value_name = "DEPTH", requires = "trace_logic")]
+        trace_depth: Option<usize>,
 
         /// Arguments to pass to the program
         #[arg(last = true)]
         args: Vec<String>,
     },
 
+    /// Create a project in a new directory
+    New {
+        /// Project name, also used for its directory
+        name: String,
+    },
+
+    /// Make the current directory a project
+    Init {
+        /// Project name (defaults to the directory name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// Resolve the current project's dependencies, cloning git dependencies
+    Fetch,
+
     /// Start an interactive REPL
     Repl {
         /// Enable logic programming mode
@@ -82,13 +147,73 @@ pub enum Commands {
         /// Enable AI mode
         #[arg(long)]
         ai: bool,
+
+        /// Seed for random numbers (defaults to $ALBAYAN_SEED or a fresh seed)
+        #[arg(long, value_name = "SEED")]
+        seed: Option<u64>,
+
+        /// Serve sessions to notebook front ends as JSON lines, on `stdio` or HOST:PORT
+        #[arg(long, value_name = "TARGET", num_args = 0..=1, default_missing_value = crate::repl::server::DEFAULT_ADDRESS)]
+        serve: Option<String>,
+
+        /// Export opt-in usage metrics in the Prometheus text format at http://ADDR/metrics
+        #[arg(long, value_name = "ADDR")]
+        metrics: Option<String>,
     },
 
     /// Check syntax without compilation
     Check {
-        /// Source file to check
+        /// Source files or directories to check; facts and rules are checked across all of them
+        #[arg(value_name = "FILE", required = true)]
+        inputs: Vec<PathBuf>,
+
+        #[command(flatten)]
+        warnings: WarningArgs,
+    },
+
+    /// Load the facts and rules of a source file and print the solutions of a goal
+    Query {
+        /// Source file declaring the relations, facts and rules
         #[arg(value_name = "FILE")]
         input: PathBuf,
+
+        /// Goal to solve, e.g. "ancestor(X, \"ahmad\")"
+        #[arg(value_name = "GOAL")]
+        goal: String,
+
+        /// Print the variable bindings as a table or as JSON
+        #[arg(long, value_enum, default_value = "table")]
+        format: QueryFormat,
+
+        /// Print how each solution was derived: the facts, rules and built-ins
+        /// that established each goal
+        #[arg(long)]
+        explain: bool,
+    },
+
+    /// Run the functions marked #[test] and report which pass
+    Test {
+        /// Source files or directories to test; defaults to the current project
+        #[arg(value_name = "FILE")]
+        inputs: Vec<PathBuf>,
+
+        /// Only run tests whose `module::function` name contains this text
+        #[arg(long, value_name = "TEXT")]
+        filter: Option<String>,
+    },
+
+    /// Report lint issues, with the rule levels of the project's [lints] table
+    Lint {
+        /// Source files or directories to lint; defaults to the current project
+        #[arg(value_name = "FILE")]
+        inputs: Vec<PathBuf>,
+    },
+
+    /// Summarize the project: modules, knowledge, traits, lint findings and the last build
+    Status {
+        /// Project directory; defaults to the current project
+        #[arg(value_name = "DIR")]
+        input: Option<PathBuf>,
     },
 
     /// Format source code
@@ -118,7 +243,156 @@ pub enum Commands {
     },
 
     /// Start Language Server Protocol (LSP) server
+    #[cfg(feature = "lsp")]
     Lsp,
+
+    /// List, run and test the example gallery
+    Examples {
+        #[command(subcommand)]
+        action: ExamplesAction,
+    },
+
+    /// Quantize the matrices of a saved weights file for faster, smaller inference
+    #[cfg(feature = "ai")]
+    Quantize {
+        /// Weights file (JSON) to read
+        #[arg(value_name = "FILE")]
+        input: PathBuf,
+
+        /// File to write the quantized weights to
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Precision of the quantized matrices: int8 or f16
+        #[arg(long, default_value = "int8")]
+        precision: crate::ai::Precision,
+    },
+
+    /// Evaluate shape inference on a labeled shape dataset
+    #[cfg(feature = "render")]
+    EvalShapes {
+        /// Load a dataset saved with --save-dataset instead of generating one
+        #[arg(long, value_name = "DIR")]
+        dataset: Option<PathBuf>,
+
+        /// Generated samples per class (circle, square, triangle)
+        #[arg(long, default_value = "20")]
+        samples: usize,
+
+        /// Pixel noise probability for generated images
+        #[arg(long, default_value = "0.02")]
+        noise: f64,
+
+        /// Seed for the generated dataset
+        #[arg(long, default_value = "42")]
+        seed: u64,
+
+        /// Save the generated dataset (PNG files + labels.csv) to a directory
+        #[arg(long, value_name = "DIR")]
+        save_dataset: Option<PathBuf>,
+    },
+}
+
+/// Subcommands of `albayan examples`
+#[derive(Subcommand)]
+pub enum ExamplesAction {
+    /// List the examples with their descriptions
+    List,
+    /// Run one example
+    Run {
+        /// Example name, as shown by `albayan examples list`
+        name: String,
+    },
+    /// Run every example and fail if any of them does
+    Test,
+}
+
+/// Levels of the semantic warnings, by name (`unused_variable`,
+/// `unused_parameter`, `unused_import`, or `warnings` for all of them)
+#[derive(Args, Debug, Clone, Default)]
+pub struct WarningArgs {
+    /// Do not report the warning
+    #[arg(short = 'A', long = "allow", value_name = "WARNING")]
+    pub allow: Vec<String>,
+
+    /// Report the warning without failing
+    #[arg(short = 'W', long = "warn", value_name = "WARNING")]
+    pub warn: Vec<String>,
+
+    /// Fail on the warning
+    #[arg(short = 'D', long = "deny", value_name = "WARNING")]
+    pub deny: Vec<String>,
+}
+
+impl WarningArgs {
+    /// The levels the flags set; `-D` wins over `-W`, which wins over `-A`
+    pub fn levels(&self) -> Result<crate::semantic::WarningLevels, String> {
+        use crate::tools::linter::LintLevel;
+
+        let mut levels = crate::semantic::WarningLevels::default();
+        for (names, level) in [(&self.allow, LintLevel::Allow), (&self.warn, LintLevel::Warn), (&self.deny, LintLevel::Deny)] {
+            for name in names {
+                levels.set(name, level)?;
+            }
+        }
+        Ok(levels)
+    }
+}
+
+/// Output formats of `albayan query`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QueryFormat {
+    /// A column per variable and a row per solution
+    Table,
+    /// An array with an object of bindings per solution
+    Json,
+}
+
+/// Build artifacts selectable with `--emit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EmitKind {
+    /// Object file as produced by the code generator
+    Obj,
+    /// Linked native executable (requires the LLVM backend)
+    Exe,
+    /// Tokens of the source file, printed one per line
+    Tokens,
+    /// AST of the source file, printed as JSON
+    Ast,
+    /// Analyzed program with its imported modules, printed as JSON
+    AnnotatedAst,
+    /// IR of the code generator, written to a `.ll` file with --llvm and an
+    /// `.ir` file otherwise
+    Ir,
+}
+
+impl EmitKind {
+    /// The intermediate artifact this kind dumps, if it is one
+    fn artifact(self) -> Option<crate::Artifact> {
+        match self {
+            EmitKind::Obj | EmitKind::Exe => None,
+            EmitKind::Tokens => Some(crate::Artifact::Tokens),
+            EmitKind::Ast => Some(crate::Artifact::Ast),
+            EmitKind::AnnotatedAst => Some(crate::Artifact::AnnotatedAst),
+            EmitKind::Ir => Some(crate::Artifact::Ir),
+        }
+    }
+
+    /// Extension of the file written when no output path is given, or
+    /// `None` for an executable (no extension) and for dumps printed to stdout
+    fn extension(self, llvm: bool) -> Option<&'static str> {
+        match self {
+            EmitKind::Obj => Some("o"),
+            EmitKind::Ir if llvm => Some("ll"),
+            EmitKind::Ir => Some("ir"),
+            EmitKind::Exe | EmitKind::Tokens | EmitKind::Ast | EmitKind::AnnotatedAst => None,
+        }
+    }
+
+    /// Whether the artifact goes to stdout unless an output path is given
+    fn prints(self) -> bool {
+        matches!(self, EmitKind::Tokens | EmitKind::Ast | EmitKind::AnnotatedAst)
+    }
 }
 
 /// CLI application
@@ -135,6 +409,9 @@ impl CliApp {
 
     /// Run the CLI application
     pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(language) = self.args.lang {
+            crate::diagnostics::set_language(language);
+        }
         match &self.args.command {
             Commands::Build {
                 input,
@@ -144,21 +421,89 @@ impl CliApp {
                 release,
                 no_logic,
                 no_ai,
-                llvm
+                llvm,
+                emit,
+                timeout,
+                no_cache,
+                warnings
             } => {
-                self.build_command(input, output, *optimization, target, *release, *no_logic, *no_ai, *llvm)
+                let emit = emit.unwrap_or(if *llvm { EmitKind::Exe } else { EmitKind::Obj });
+                let (input, project) = self.resolve_input(input)?;
+                // Project builds write to the target directory rather than next to the sources
+                let output = output.clone().or_else(|| {
+                    project.as_ref().filter(|_| !emit.prints()).map(|project| {
+                        let mut path = project.target_dir().join(project.name());
+                        if let Some(extension) = emit.extension(*llvm) {
+                            path.set_extension(extension);
+                        }
+                        path
+                    })
+                });
+                if let Some(dir) = output.as_ref().and_then(|path| path.parent()) {
+                    std::fs::create_dir_all(dir)?;
+                }
+                self.build_command(&input, project.as_ref(), &output, *optimization, target, *release, *no_logic, *no_ai, *llvm, emit, *timeout, *no_cache, warnings.levels()?)
+            }
+
+            Commands::Run { input, canvas, seed, trace_logic, trace_relations, trace_depth, args } => {
+                self.apply_seed(*seed);
+                if let Some(target) = trace_logic {
+                    let filter = crate::runtime::TraceFilter {
+                        relations: trace_relations.clone(),
+                        max_depth: *trace_depth,
+                    };
+                    let output = crate::runtime::TraceOutput::from_target(target);
+                    crate::runtime::logic_trace::install(crate::runtime::LogicTracer::new(filter, output));
+                }
+                let (input, _) = self.resolve_input(input)?;
+                self.run_command(&input, canvas.as_deref(), args)
+            }
+
+            Commands::New { name } => {
+                let project = Project::create(&std::env::current_dir()?, name)?;
+                println!("Created project `{}` in {}", project.name(), project.root.display());
+                Ok(())
+            }
+
+            Commands::Init { name } => {
+                let project = Project::init(&std::env::current_dir()?, name.as_deref())?;
+                println!("Initialized project `{}` in {}", project.name(), project.root.display());
+                Ok(())
+            }
+
+            Commands::Fetch => {
+                let project = Project::discover(&std::env::current_dir()?)?;
+                let resolution = deps::resolve(&project)?;
+                for package in &resolution.packages {
+                    println!("{} {} from {}", package.name, package.version, package.source);
+                }
+                println!("Resolved {} dependencies of `{}`", resolution.packages.len(), project.name());
+                Ok(())
+            }
+
+            Commands::Repl { logic, ai, seed, serve, metrics } => {
+                self.apply_seed(*seed);
+                self.repl_command(*logic, *ai, serve.as_deref(), metrics.as_deref())
+            }
+
+            Commands::Check { inputs, warnings } => {
+                self.check_command(inputs, warnings.levels()?)
             }
 
-            Commands::Run { input, args } => {
-                self.run_command(input, args)
+            Commands::Query { input, goal, format, explain } => {
+                self.query_command(input, goal, *format, *explain)
             }
 
-            Commands::Repl { logic, ai } => {
-                self.repl_command(*logic, *ai)
+            Commands::Test { inputs, filter } => {
+                self.test_command(inputs, filter.as_deref())
             }
 
-            Commands::Check { input } => {
-                self.check_command(input)
+            Commands::Lint { inputs } => {
+                self.lint_command(inputs)
+            }
+
+            Commands::Status { input } => {
+                self.status_command(input.as_ref())
             }
 
             Commands::Format { input, in_place } => {
@@ -169,16 +514,47 @@ impl CliApp {
                 self.info_command(*version, *features, *targets)
             }
 
+            #[cfg(feature = "lsp")]
             Commands::Lsp => {
                 self.lsp_command().await
             }
+
+            Commands::Examples { action } => {
+                self.examples_command(action)
+            }
+
+            #[cfg(feature = "ai")]
+            Commands::Quantize { input, output, precision } => {
+                let report = crate::ai::quantization::quantize_file(input, output, *precision)?;
+                println!("{}", report);
+                Ok(())
+            }
+
+            #[cfg(feature = "render")]
+            Commands::EvalShapes { dataset, samples, noise, seed, save_dataset } => {
+                self.eval_shapes_command(dataset, *samples, *noise, *seed, save_dataset)
+            }
         }
     }
 
+    /// The file to build or run: `input`, or else the entry file of the
+    /// project containing the current directory, with that project
+    fn resolve_input(&self, input: &Option<PathBuf>) -> Result<(PathBuf, Option<Project>), Box<dyn std::error::Error>> {
+        if let Some(input) = input {
+            return Ok((input.clone(), None));
+        }
+        let project = Project::discover(&std::env::current_dir()?)?;
+        if self.args.verbose {
+            println!("Project: {} ({})", project.name(), project.manifest_path().display());
+        }
+        Ok((project.entry_path(), Some(project)))
+    }
+
     /// Handle build command
     fn build_command(
         &self,
         input: &PathBuf,
+        project: Option<&Project>,
         output: &Option<PathBuf>,
         optimization: u8,
         target: &Option<String>,
@@ -186,11 +562,24 @@ impl CliApp {
         no_logic: bool,
         no_ai: bool,
         llvm: bool,
+        emit: EmitKind,
+        timeout: Option<u64>,
+        no_cache: bool,
+        warnings: crate::semantic::WarningLevels,
     ) -> Result<(), Box<dyn std::error::Error>> {
         if self.args.verbose {
             println!("Building: {}", input.display());
         }
 
+        if emit == EmitKind::Exe && !llvm {
+            eprintln!("Linking an executable requires the LLVM backend (--llvm); use --emit=obj");
+            std::process::exit(1);
+        }
+        if emit.artifact().is_some() && input.is_dir() {
+            eprintln!("--emit={} dumps a single source file, not a directory", emit.to_possible_value().unwrap().get_name());
+            std::process::exit(1);
+        }
+
         let mut options = CompilerOptions {
             optimization_level: if release { 2 } else { optimization },
             target_triple: target.clone(),
@@ -199,25 +588,81 @@ impl CliApp {
             enable_logic: !no_logic,
             enable_ai: !no_ai,
             use_llvm: llvm,
+            cancellation: match timeout {
+                Some(seconds) => crate::cancellation::CancellationToken::with_timeout(Duration::from_secs(seconds)),
+                None => crate::cancellation::CancellationToken::new(),
+            },
+            warnings,
+            ..Default::default()
         };
 
         if self.args.debug {
             options.debug_info = true;
         }
 
+        // Projects and directories keep a cache under their target directory;
+        // a loose file has nowhere to put one
+        if !no_cache {
+            options.cache_dir = match project {
+                Some(project) => Some(project.target_dir().join(crate::codegen::cache::CACHE_DIR)),
+                None if input.is_dir() => {
+                    let output_dir = output.clone().unwrap_or_else(|| input.join(crate::build::DEFAULT_OUTPUT_DIR));
+                    Some(output_dir.join(crate::codegen::cache::CACHE_DIR))
+                }
+                None => None,
+            };
+        }
+
+        if let Some(project) = project {
+            let resolution = deps::resolve(project)?;
+            if self.args.verbose {
+                for package in &resolution.packages {
+                    println!("Dependency: {} {} from {}", package.name, package.version, package.source);
+                }
+            }
+            options.packages = resolution.entries();
+        }
+
+        if input.is_dir() {
+            return self.build_project(input, options);
+        }
+
+        if let Some(artifact) = emit.artifact() {
+            return self.emit_artifact(input, options, artifact, emit, output);
+        }
+
         let mut compiler = Compiler::with_options(options).source_file(input);
 
-        match compiler.compile_file() {
+        let result = compiler.compile_file();
+        // Warnings can be in any module the input imports
+        for warning in &compiler.warnings {
+            let path = warning.file.as_ref().map_or_else(|| input.clone(), PathBuf::from);
+            let source = std::fs::read_to_string(&path).unwrap_or_default();
+            crate::diagnostics::emit_warning(warning, &path.display().to_string(), &source);
+        }
+        match result {
             Ok(object_code) => {
                 let output_path = output.as_ref()
                     .map(|p| p.clone())
-                    .unwrap_or_else(|| {
-                        let mut path = input.clone();
-                        path.set_extension("o");
-                        path
+                    .unwrap_or_else(|| match emit {
+                        EmitKind::Obj => {
+                            let mut path = input.clone();
+                            path.set_extension("o");
+                            path
+                        }
+                        EmitKind::Exe => crate::codegen::link::default_executable_path(input),
+                        _ => unreachable!("intermediate artifacts are emitted by emit_artifact"),
                     });
 
-                std::fs::write(&output_path, object_code)?;
+                match emit {
+                    EmitKind::Obj => std::fs::write(&output_path, object_code)?,
+                    EmitKind::Exe => {
+                        let linker = crate::codegen::Linker::detect()?.with_target(target.clone());
+                        linker.require_runtime_library()?;
+                        linker.link_object_code(&object_code, &output_path)?;
+                    }
+                    _ => unreachable!("intermediate artifacts are emitted by emit_artifact"),
+                }
 
                 if self.args.verbose {
                     println!("Output written to: {}", output_path.display());
@@ -226,19 +671,98 @@ impl CliApp {
                 println!("Compilation successful!");
             }
             Err(e) => {
-                eprintln!("Compilation failed: {}", e);
+                let source = std::fs::read_to_string(input).unwrap_or_default();
+                crate::diagnostics::emit(&e, &input.display().to_string(), &source);
+                eprintln!("Compilation failed");
+                std::process::exit(1);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Dump an intermediate artifact of `input` to `output`, or print it when
+    /// the kind prints and no output path is given
+    fn emit_artifact(
+        &self,
+        input: &PathBuf,
+        options: CompilerOptions,
+        artifact: crate::Artifact,
+        emit: EmitKind,
+        output: &Option<PathBuf>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let llvm = options.use_llvm;
+        match Compiler::with_options(options).source_file(input).emit_artifact(artifact) {
+            Ok(text) => {
+                let output_path = output.clone().or_else(|| {
+                    emit.extension(llvm).map(|extension| input.with_extension(extension))
+                });
+                match output_path {
+                    Some(path) => {
+                        std::fs::write(&path, text)?;
+                        if self.args.verbose {
+                            println!("Output written to: {}", path.display());
+                        }
+                    }
+                    None if text.ends_with('\n') => print!("{}", text),
+                    None => println!("{}", text),
+                }
+            }
+            Err(e) => {
+                let source = std::fs::read_to_string(input).unwrap_or_default();
+                crate::diagnostics::emit(&e, &input.display().to_string(), &source);
+                eprintln!("Compilation failed");
+                std::process::exit(1);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build every source file of a directory into object files and print the build graph
+    fn build_project(&self, root: &PathBuf, options: CompilerOptions) -> Result<(), Box<dyn std::error::Error>> {
+        let report = match Compiler::with_options(options).compile_project(root) {
+            Ok(report) => report,
+            Err(e) => {
+                crate::diagnostics::emit(&e, &root.display().to_string(), "");
+                eprintln!("Compilation failed");
                 std::process::exit(1);
             }
+        };
+
+        for file in &report.files {
+            let source = std::fs::read_to_string(&file.path).unwrap_or_default();
+            for diagnostic in &file.diagnostics {
+                crate::diagnostics::emit_diagnostic(diagnostic, &file.path.display().to_string(), &source);
+            }
+            if self.args.verbose {
+                if let Some(artifact) = &file.artifact {
+                    println!("Output written to: {}", artifact.display());
+                }
+            }
         }
+        println!("{}", report);
 
+        if !report.succeeded() {
+            eprintln!("Compilation failed");
+            std::process::exit(1);
+        }
+        println!("Compilation successful!");
         Ok(())
     }
 
     /// Handle run command
-    fn run_command(&self, input: &PathBuf, _args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    fn run_command(&self, input: &PathBuf, canvas: Option<&str>, _args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
         if self.args.verbose {
             println!("Running: {}", input.display());
         }
+        crate::runtime::shutdown::install_signal_handlers();
+
+        if let Some(target) = canvas {
+            let session = input.display().to_string();
+            let destination = crate::builtin_libraries::ffi::open_canvas(target, &session)?;
+            eprintln!("Canvas: {}", destination);
+        }
 
         let options = CompilerOptions {
             optimization_level: 0,
@@ -249,14 +773,25 @@ impl CliApp {
         let compiler = Compiler::with_options(options);
         let source = std::fs::read_to_string(input)?;
 
-        match compiler.run_jit(&source) {
+        let result = compiler.run_jit(&source);
+        if let Err(error) = crate::runtime::shutdown::shutdown() {
+            eprintln!("Shutdown failed: {}", error);
+        }
+        match crate::runtime::logic_trace::finish() {
+            Ok(Some(path)) => eprintln!("Logic trace: {}", path.display()),
+            Ok(None) => {}
+            Err(error) => eprintln!("Failed to write the logic trace: {}", error),
+        }
+
+        match result {
             Ok(_) => {
                 if self.args.verbose {
                     println!("Execution completed successfully");
                 }
             }
             Err(e) => {
-                eprintln!("Execution failed: {}", e);
+                crate::diagnostics::emit(&e, &input.display().to_string(), &source);
+                eprintln!("Execution failed");
                 std::process::exit(1);
             }
         }
@@ -264,8 +799,45 @@ impl CliApp {
         Ok(())
     }
 
+    /// Seed the shared random generator, and export the seed so that programs
+    /// started from here draw the same numbers
+    fn apply_seed(&self, seed: Option<u64>) {
+        use crate::runtime::random;
+
+        if let Some(seed) = seed {
+            random::set_seed(seed);
+        }
+        let seed = random::seed();
+        std::env::set_var(random::SEED_ENV, seed.to_string());
+        if self.args.verbose {
+            println!("Random seed: {}", seed);
+        }
+    }
+
     /// Handle REPL command
-    fn repl_command(&self, logic: bool, ai: bool) -> Result<(), Box<dyn std::error::Error>> {
+    fn repl_command(&self, logic: bool, ai: bool, serve: Option<&str>, metrics: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let options = CompilerOptions {
+            enable_logic: logic,
+            enable_ai: ai,
+            debug_info: true,
+            ..Default::default()
+        };
+
+        if let Some(address) = metrics {
+            let bound = crate::metrics::serve_prometheus(address)?;
+            eprintln!("Usage metrics at http://{}/metrics", bound);
+        }
+
+        if let Some(target) = serve {
+            crate::runtime::shutdown::install_signal_handlers();
+            let registry = crate::repl::server::SessionRegistry::new(options);
+            return match target {
+                "stdio" => crate::repl::server::serve_stdio(registry),
+                address => crate::repl::server::serve_tcp(registry, address),
+            }
+            .map_err(Into::into);
+        }
+
         println!("البيان (AlBayan) Interactive REPL");
         println!("Version: {}", crate::VERSION);
         println!("Type 'exit' to quit, 'help' for help");
@@ -277,55 +849,212 @@ impl CliApp {
             println!("AI mode enabled");
         }
 
-        let mut repl = repl::ReplSession::new(logic, ai);
+        let mut repl = repl::ReplSession::new(options)?;
         repl.run()?;
 
         Ok(())
     }
 
     /// Handle check command
-    fn check_command(&self, input: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-        if self.args.verbose {
-            println!("Checking: {}", input.display());
-        }
+    fn check_command(&self, inputs: &[PathBuf], warnings: crate::semantic::WarningLevels) -> Result<(), Box<dyn std::error::Error>> {
+        let options = crate::CompilerOptions { warnings, ..Default::default() };
+        let files = crate::modules::collect_sources(inputs)?;
+        let mut failed = false;
+        let mut programs = Vec::new();
 
-        let source = std::fs::read_to_string(input)?;
+        for input in &files {
+            if self.args.verbose {
+                println!("Checking: {}", input.display());
+            }
 
-        // Perform lexical and syntactic analysis only
-        let mut lexer = crate::lexer::Lexer::new(&source);
-        let tokens = match lexer.tokenize() {
-            Ok(tokens) => tokens,
-            Err(e) => {
-                eprintln!("Lexical error: {}", e);
-                std::process::exit(1);
+            let source = std::fs::read_to_string(input)?;
+            let file_name = input.display().to_string();
+
+            // Perform lexical and syntactic analysis
+            let mut lexer = crate::lexer::Lexer::new(&source);
+            let ast = match lexer.tokenize() {
+                Ok(tokens) => crate::parser::Parser::new(tokens).parse().map_err(crate::CompilerError::from),
+                Err(e) => Err(e.into()),
+            };
+            let ast = match ast {
+                Ok(ast) => ast,
+                Err(e) => {
+                    crate::diagnostics::emit(&e, &file_name, &source);
+                    failed = true;
+                    continue;
+                }
+            };
+
+            // Perform semantic analysis
+            let mut semantic_analyzer = crate::semantic::SemanticAnalyzer::new(&options);
+            match semantic_analyzer.analyze(ast.clone()) {
+                Ok(analyzed) => {
+                    for warning in &analyzed.warnings {
+                        crate::diagnostics::emit_warning(warning, &file_name, &source);
+                    }
+                }
+                Err(e) => {
+                    crate::diagnostics::emit(&e.into(), &file_name, &source);
+                    failed = true;
+                }
             }
-        };
+            programs.push((file_name, source, ast));
+        }
 
-        let mut parser = crate::parser::Parser::new(tokens);
-        let ast = match parser.parse() {
-            Ok(ast) => {
-                println!("Syntax check passed!");
-                ast
+        // Knowledge consistency across all checked files
+        let workspace: Vec<(String, &crate::parser::ast::Program)> = programs.iter()
+            .map(|(file_name, _, ast)| (file_name.clone(), ast))
+            .collect();
+        for issue in crate::semantic::knowledge_check::check_knowledge(&workspace) {
+            let source = programs.iter()
+                .find(|(file_name, _, _)| *file_name == issue.location.file)
+                .map_or("", |(_, source, _)| source.as_str());
+            crate::diagnostics::emit_diagnostic(&issue.diagnostic, &issue.location.file, source);
+            failed |= issue.diagnostic.severity == crate::diagnostics::Severity::Error;
+        }
+
+        if failed {
+            std::process::exit(1);
+        }
+        println!("Syntax check passed!");
+        println!("Semantic check passed!");
+        Ok(())
+    }
+
+    /// Handle query command
+    fn query_command(
+        &self,
+        input: &PathBuf,
+        goal: &str,
+        format: QueryFormat,
+        explain: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut session = crate::repl::Session::new(CompilerOptions::default());
+        let loaded = session.load(input);
+        if let Some(error) = loaded.error {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+
+        if explain {
+            let solutions = session.prove(goal).unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            });
+            match format {
+                QueryFormat::Table => println!("{}", crate::repl::proofs_text(&solutions)),
+                QueryFormat::Json => println!("{}", serde_json::to_string_pretty(&solutions)?),
             }
-            Err(e) => {
-                eprintln!("Syntax error: {}", e);
+            if solutions.is_empty() {
                 std::process::exit(1);
             }
+            return Ok(());
+        }
+
+        let result = session.execute(&format!("{} {}", crate::repl::QUERY_PREFIX, goal));
+        if let Some(error) = result.error {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+        match format {
+            QueryFormat::Table => println!("{}", crate::repl::solutions_table(&result.solutions)),
+            QueryFormat::Json => println!("{}", serde_json::to_string_pretty(&result.solutions)?),
+        }
+
+        // Like a failed goal in a shell condition
+        if result.solutions.is_empty() {
+            std::process::exit(1);
+        }
+        Ok(())
+    }
+
+    /// Handle test command
+    fn test_command(&self, inputs: &[PathBuf], filter: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut options = CompilerOptions::default();
+        let inputs = if inputs.is_empty() {
+            let project = Project::discover(&std::env::current_dir()?)?;
+            options.packages = deps::resolve(&project)?.entries();
+            vec![project.root.clone()]
+        } else {
+            inputs.to_vec()
         };
 
-        // Perform semantic analysis
-        let options = crate::CompilerOptions::default();
-        let mut semantic_analyzer = crate::semantic::SemanticAnalyzer::new(&options);
-        match semantic_analyzer.analyze(ast) {
-            Ok(_) => {
-                println!("Semantic check passed!");
+        let report = crate::testing::run_tests(&inputs, &options, filter)?;
+        for failure in &report.compile_errors {
+            let source = std::fs::read_to_string(&failure.path).unwrap_or_default();
+            crate::diagnostics::emit_diagnostic(&failure.diagnostic, &failure.path.display().to_string(), &source);
+        }
+        println!("{}", report);
+
+        if !report.succeeded() {
+            std::process::exit(1);
+        }
+        Ok(())
+    }
+
+    /// Handle lint command
+    fn lint_command(&self, inputs: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+        let inputs = if inputs.is_empty() {
+            vec![Project::discover(&std::env::current_dir()?)?.root]
+        } else {
+            inputs.to_vec()
+        };
+        let config = crate::tools::linter::LinterConfig::discover(&std::fs::canonicalize(&inputs[0])?)?;
+        let linter = crate::tools::linter::Linter::with_config(config);
+        for rule in linter.unknown_rules() {
+            eprintln!("warning: unknown lint `{}` in [lints]", rule);
+        }
+
+        let mut warnings = 0;
+        let mut errors = 0;
+        for input in crate::modules::collect_sources(&inputs)? {
+            if self.args.verbose {
+                println!("Linting: {}", input.display());
             }
-            Err(e) => {
-                eprintln!("Semantic error: {}", e);
-                std::process::exit(1);
+
+            let source = std::fs::read_to_string(&input)?;
+            let file_name = input.display().to_string();
+            let ast = match crate::lexer::Lexer::new(&source).tokenize() {
+                Ok(tokens) => crate::parser::Parser::new(tokens).parse().map_err(crate::CompilerError::from),
+                Err(e) => Err(e.into()),
+            };
+            let ast = match ast {
+                Ok(ast) => ast,
+                Err(e) => {
+                    crate::diagnostics::emit(&e, &file_name, &source);
+                    errors += 1;
+                    continue;
+                }
+            };
+
+            for issue in linter.check_program(&source, &ast) {
+                crate::diagnostics::emit_diagnostic(&issue.to_diagnostic(), &file_name, &source);
+                match issue.severity {
+                    crate::diagnostics::Severity::Error => errors += 1,
+                    _ => warnings += 1,
+                }
             }
         }
 
+        println!("Lint: {} error(s), {} warning(s)", errors, warnings);
+        if errors > 0 {
+            std::process::exit(1);
+        }
+        Ok(())
+    }
+
+    /// Handle status command
+    fn status_command(&self, input: Option<&PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+        let root = match input {
+            Some(input) => input.clone(),
+            None => Project::discover(&std::env::current_dir()?)?.root,
+        };
+        let config = crate::tools::linter::LinterConfig::discover(&std::fs::canonicalize(&root)?)?;
+        let linter = crate::tools::linter::Linter::with_config(config);
+        let output_dir = root.join(crate::build::DEFAULT_OUTPUT_DIR);
+
+        let status = crate::status::ProjectStatus::collect(&root, &linter, &output_dir)?;
+        println!("{}", status);
         Ok(())
     }
 
@@ -375,6 +1104,11 @@ impl CliApp {
             println!("  ✓ High Performance (LLVM backend)");
             println!("  ✓ Concurrent Programming (async/await)");
             println!("  ✓ Cross-platform (Windows, Linux, macOS)");
+
+            println!("\nBuild features:");
+            for (feature, enabled) in crate::FEATURES {
+                println!("  {} {}", if *enabled { "✓" } else { "✗" }, feature);
+            }
         }
 
         if targets {
@@ -390,7 +1124,103 @@ impl CliApp {
         Ok(())
     }
 
+    /// Handle examples command
+    fn examples_command(&self, action: &ExamplesAction) -> Result<(), Box<dyn std::error::Error>> {
+        use crate::gallery::{self, EXAMPLES};
+
+        let options = CompilerOptions::default();
+        match action {
+            ExamplesAction::List => {
+                let width = EXAMPLES.iter().map(|example| example.name.len()).max().unwrap_or(0);
+                for example in EXAMPLES {
+                    println!("{:width$}  {}", example.name, example.description(), width = width);
+                }
+            }
+            ExamplesAction::Run { name } => {
+                let Some(example) = gallery::find(name) else {
+                    eprintln!("No example named `{}`; see `albayan examples list`", name);
+                    std::process::exit(1);
+                };
+                if self.args.verbose {
+                    println!("{}", example.source);
+                }
+                match example.run(&options) {
+                    Ok(lines) => {
+                        for line in lines {
+                            println!("{}", line);
+                        }
+                        println!("Example `{}` ran successfully", name);
+                    }
+                    Err(error) => {
+                        eprint!("{}", error);
+                        eprintln!("Example `{}` failed", name);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            ExamplesAction::Test => {
+                let mut failed = 0;
+                for example in EXAMPLES {
+                    match example.run(&options) {
+                        Ok(_) => println!("example {} ... ok", example.name),
+                        Err(error) => {
+                            println!("example {} ... FAILED", example.name);
+                            eprint!("{}", error);
+                            failed += 1;
+                        }
+                    }
+                }
+                println!("{} passed, {} failed", EXAMPLES.len() - failed, failed);
+                if failed > 0 {
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle eval-shapes command
+    #[cfg(feature = "render")]
+    fn eval_shapes_command(
+        &self,
+        dataset: &Option<PathBuf>,
+        samples: usize,
+        noise: f64,
+        seed: u64,
+        save_dataset: &Option<PathBuf>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        use crate::builtin_libraries::{DatasetConfig, ShapeDataset, ShapeInference};
+
+        let dataset = match dataset {
+            Some(dir) => ShapeDataset::load(dir)?,
+            None => {
+                let config = DatasetConfig {
+                    samples_per_class: samples,
+                    noise_level: noise,
+                    seed,
+                    ..DatasetConfig::default()
+                };
+                ShapeDataset::generate(&config)?
+            }
+        };
+
+        if let Some(dir) = save_dataset {
+            dataset.save(dir)?;
+            if self.args.verbose {
+                println!("Saved dataset to {}", dir.display());
+            }
+        }
+
+        println!("Evaluating shape inference on {} samples", dataset.samples.len());
+        let report = dataset.evaluate(&mut ShapeInference::new());
+        print!("{}", report);
+
+        Ok(())
+    }
+
     /// Handle LSP command
+    #[cfg(feature = "lsp")]
     async fn lsp_command(&self) -> Result<(), Box<dyn std::error::Error>> {
         if self.args.verbose {
             println!("Starting AlBayan Language Server...");
@@ -404,31 +1234,42 @@ impl CliApp {
 /// REPL module
 mod repl {
     use std::io::{self, Write};
-    use crate::{Compiler, CompilerOptions};
+    use std::path::PathBuf;
+    use rustyline::error::ReadlineError;
+    use rustyline::DefaultEditor;
+    use crate::repl::{CellStatus, Session};
+    use crate::CompilerOptions;
+
+    /// File in the home directory the line history is kept in between sessions
+    const HISTORY_FILE: &str = ".albayan_history";
 
     pub struct ReplSession {
-        logic_mode: bool,
-        ai_mode: bool,
+        session: Session,
         history: Vec<String>,
+        editor: DefaultEditor,
+        history_file: Option<PathBuf>,
     }
 
     impl ReplSession {
-        pub fn new(logic_mode: bool, ai_mode: bool) -> Self {
-            Self {
-                logic_mode,
-                ai_mode,
-                history: Vec::new(),
+        pub fn new(options: CompilerOptions) -> rustyline::Result<Self> {
+            let mut editor = DefaultEditor::new()?;
+            let history_file = std::env::var_os("HOME")
+                .or_else(|| std::env::var_os("USERPROFILE"))
+                .map(|home| PathBuf::from(home).join(HISTORY_FILE));
+            if let Some(path) = &history_file {
+                // The first session has no history yet
+                let _ = editor.load_history(path);
             }
+            Ok(Self {
+                session: Session::new(options),
+                history: Vec::new(),
+                editor,
+                history_file,
+            })
         }
 
         pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-            loop {
-                print!("albayan> ");
-                io::stdout().flush()?;
-
-                let mut input = String::new();
-                io::stdin().read_line(&mut input)?;
-
+            while let Some(input) = self.read_input()? {
                 let input = input.trim();
 
                 if input.is_empty() {
@@ -440,6 +1281,10 @@ mod repl {
                     "help" => self.show_help(),
                     "history" => self.show_history(),
                     "clear" => self.clear_screen(),
+                    "reset" => {
+                        self.session.reset();
+                        println!("Session reset");
+                    }
                     _ => {
                         self.history.push(input.to_string());
                         self.execute_input(input)?;
@@ -447,24 +1292,51 @@ mod repl {
                 }
             }
 
+            if let Some(path) = &self.history_file {
+                // A history that cannot be saved only costs the next session its history
+                let _ = self.editor.save_history(path);
+            }
             println!("Goodbye!");
             Ok(())
         }
 
-        fn execute_input(&self, input: &str) -> Result<(), Box<dyn std::error::Error>> {
-            let options = CompilerOptions {
-                enable_logic: self.logic_mode,
-                enable_ai: self.ai_mode,
-                debug_info: true,
-                ..Default::default()
-            };
-
-            let compiler = Compiler::with_options(options);
+        /// Read lines until their braces, parentheses and brackets balance.
+        /// Ctrl-C discards the input read so far; Ctrl-D ends the session.
+        fn read_input(&mut self) -> rustyline::Result<Option<String>> {
+            let mut input = String::new();
+            loop {
+                let prompt = if input.is_empty() { "albayan> " } else { "   ...> " };
+                match self.editor.readline(prompt) {
+                    Ok(line) => {
+                        if !input.is_empty() {
+                            input.push('\n');
+                        }
+                        input.push_str(&line);
+                        if crate::repl::is_complete(&input) {
+                            break;
+                        }
+                    }
+                    Err(ReadlineError::Interrupted) => return Ok(Some(String::new())),
+                    Err(ReadlineError::Eof) => return Ok(None),
+                    Err(error) => return Err(error),
+                }
+            }
+            if !input.trim().is_empty() {
+                self.editor.add_history_entry(input.as_str())?;
+            }
+            Ok(Some(input))
+        }
 
-            match compiler.run_jit(input) {
-                Ok(_) => {}
-                Err(e) => {
-                    eprintln!("Error: {}", e);
+        fn execute_input(&mut self, input: &str) -> Result<(), Box<dyn std::error::Error>> {
+            let result = self.session.execute(input);
+            match result.status {
+                CellStatus::Ok => {
+                    for output in &result.outputs {
+                        println!("{}", output);
+                    }
+                }
+                CellStatus::Error => {
+                    eprintln!("{}", result.error.unwrap_or_default());
                 }
             }
 
@@ -476,9 +1348,22 @@ mod repl {
             println!("  help     - Show this help message");
             println!("  history  - Show command history");
             println!("  clear    - Clear the screen");
+            println!("  reset    - Forget every declaration, variable, fact and rule");
             println!("  exit     - Exit the REPL");
             println!();
-            println!("Enter AlBayan code to execute it directly.");
+            println!("Enter AlBayan declarations to add them to the session;");
+            println!("later input can use them. Statements such as `let x = 5;` run");
+            println!("with the variables of earlier ones, and `x + 1` prints its value.");
+            println!("Enter `?- goal` to query facts and rules.");
+            println!();
+            println!("  :type <expr>  - Show the type of an expression");
+            println!("  :ast          - Show the parse tree of the last input");
+            println!("  :kb           - List the facts and rules loaded so far");
+            println!("  :load <file>  - Add the declarations of a file to the session");
+            println!();
+            println!("Input continues on the next line until its braces, parentheses and");
+            println!("brackets balance; Ctrl-C discards it and Ctrl-D exits. The up and down");
+            println!("arrows recall earlier input, kept in ~/.albayan_history.");
         }
 
         fn show_history(&self) {
@@ -0,0 +1,293 @@
+//! # Projects
+//!
+//! A project is a directory with a `Bayan.toml` manifest:
+//!
+//! ```toml
+//! [package]
+//! name = "hello"
+//! version = "0.1.0"
+//! entry = "src/main.ab"
+//!
+//! [dependencies]
+//! geometry = "1.2"
+//!
+//! [lints]
+//! shadowing = "allow"
+//! ```
+//!
+//! `albayan new NAME` creates such a directory and `albayan init` turns the
+//! current one into a project. Inside a project, `albayan build` and
+//! `albayan run` need no file argument: they use the manifest's entry file,
+//! found by looking for `Bayan.toml` in the current directory and its
+//! parents. Build outputs go to the project's `target` directory.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::modules::package::DependencySpec;
+use crate::tools::linter::LinterConfig;
+
+/// File name of a project manifest
+pub const MANIFEST_FILE: &str = "Bayan.toml";
+
+/// Directory, relative to the project root, that build outputs go to
+pub const TARGET_DIR: &str = "target";
+
+const DEFAULT_ENTRY: &str = "src/main.ab";
+
+const MAIN_TEMPLATE: &str = r#"// {name}
+
+fn main() -> int {
+    print("مرحبا بكم في لغة البيان!");
+    return 0;
+}
+"#;
+
+/// Why a project could not be loaded or created
+#[derive(Debug, thiserror::Error)]
+pub enum ProjectError {
+    #[error("no Bayan.toml found in `{}` or any parent directory", .0.display())]
+    NotFound(PathBuf),
+
+    #[error("invalid manifest `{}`: {message}", .path.display())]
+    InvalidManifest { path: PathBuf, message: String },
+
+    #[error("invalid project name `{0}`: use letters, digits, `_` and `-`, starting with a letter")]
+    InvalidName(String),
+
+    #[error("`{}` already exists", .0.display())]
+    AlreadyExists(PathBuf),
+
+    #[error("cannot access `{}`: {source}", .path.display())]
+    Io { path: PathBuf, source: std::io::Error },
+}
+
+/// The contents of a `Bayan.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectManifest {
+    pub package: ProjectPackage,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, DependencySpec>,
+    /// Rule levels and limits for `albayan lint`
+    #[serde(default, skip_serializing_if = "LinterConfig::is_default")]
+    pub lints: LinterConfig,
+}
+
+/// The `[package]` table of a manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectPackage {
+    pub name: String,
+    pub version: String,
+    /// Entry file, relative to the project root
+    #[serde(default = "default_entry")]
+    pub entry: PathBuf,
+}
+
+/// A project on disk
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// Directory holding the manifest
+    pub root: PathBuf,
+    pub manifest: ProjectManifest,
+}
+
+fn default_entry() -> PathBuf {
+    PathBuf::from(DEFAULT_ENTRY)
+}
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> ProjectError + '_ {
+    move |source| ProjectError::Io { path: path.to_path_buf(), source }
+}
+
+impl ProjectManifest {
+    /// A manifest for a new project
+    pub fn new(name: &str) -> Self {
+        Self {
+            package: ProjectPackage { name: name.to_string(), version: "0.1.0".to_string(), entry: default_entry() },
+            dependencies: BTreeMap::new(),
+            lints: LinterConfig::default(),
+        }
+    }
+
+    /// Parse and validate the manifest at `path` from `source`
+    pub fn parse(source: &str, path: &Path) -> Result<Self, ProjectError> {
+        let invalid = |message: String| ProjectError::InvalidManifest { path: path.to_path_buf(), message };
+        let manifest: Self = toml::from_str(source).map_err(|error| invalid(error.message().to_string()))?;
+        validate_name(&manifest.package.name).map_err(|error| invalid(error.to_string()))?;
+        if manifest.package.version.trim().is_empty() {
+            return Err(invalid("`package.version` is empty".to_string()));
+        }
+        if manifest.package.entry.is_absolute() {
+            return Err(invalid("`package.entry` must be relative to the project directory".to_string()));
+        }
+        Ok(manifest)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("a project manifest is always representable as TOML")
+    }
+}
+
+impl Project {
+    /// Load the project whose manifest is in `root`
+    pub fn load(root: &Path) -> Result<Self, ProjectError> {
+        let path = root.join(MANIFEST_FILE);
+        let source = std::fs::read_to_string(&path).map_err(io_error(&path))?;
+        let manifest = ProjectManifest::parse(&source, &path)?;
+        Ok(Self { root: root.to_path_buf(), manifest })
+    }
+
+    /// The project `start` is in: the nearest directory from `start` upwards
+    /// with a manifest
+    pub fn discover(start: &Path) -> Result<Self, ProjectError> {
+        start
+            .ancestors()
+            .find(|dir| dir.join(MANIFEST_FILE).is_file())
+            .map(Self::load)
+            .unwrap_or_else(|| Err(ProjectError::NotFound(start.to_path_buf())))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.manifest.package.name
+    }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        self.root.join(MANIFEST_FILE)
+    }
+
+    pub fn entry_path(&self) -> PathBuf {
+        self.root.join(&self.manifest.package.entry)
+    }
+
+    pub fn target_dir(&self) -> PathBuf {
+        self.root.join(TARGET_DIR)
+    }
+
+    /// Create a project named `name` in a new directory `parent/name`
+    pub fn create(parent: &Path, name: &str) -> Result<Self, ProjectError> {
+        validate_name(name)?;
+        let root = parent.join(name);
+        if root.exists() {
+            return Err(ProjectError::AlreadyExists(root));
+        }
+        Self::scaffold(&root, name)
+    }
+
+    /// Make the existing directory `root` a project, named after the
+    /// directory unless `name` is given. Existing files are kept.
+    pub fn init(root: &Path, name: Option<&str>) -> Result<Self, ProjectError> {
+        let manifest = root.join(MANIFEST_FILE);
+        if manifest.exists() {
+            return Err(ProjectError::AlreadyExists(manifest));
+        }
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => {
+                let root = std::fs::canonicalize(root).map_err(io_error(root))?;
+                root.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+            }
+        };
+        validate_name(&name)?;
+        Self::scaffold(root, &name)
+    }
+
+    /// Write the manifest, a hello-world entry file unless one exists, and a
+    /// `.gitignore` for the target directory
+    fn scaffold(root: &Path, name: &str) -> Result<Self, ProjectError> {
+        let project = Self { root: root.to_path_buf(), manifest: ProjectManifest::new(name) };
+        let entry = project.entry_path();
+        if let Some(dir) = entry.parent() {
+            std::fs::create_dir_all(dir).map_err(io_error(dir))?;
+        }
+        let manifest = project.manifest_path();
+        std::fs::write(&manifest, project.manifest.to_toml()).map_err(io_error(&manifest))?;
+        if !entry.exists() {
+            std::fs::write(&entry, MAIN_TEMPLATE.replace("{name}", name)).map_err(io_error(&entry))?;
+        }
+        let gitignore = root.join(".gitignore");
+        if !gitignore.exists() {
+            std::fs::write(&gitignore, format!("/{}/\n", TARGET_DIR)).map_err(io_error(&gitignore))?;
+        }
+        Ok(project)
+    }
+}
+
+/// Project names start with a letter and continue with letters, digits, `_`
+/// and `-`; Arabic letters are allowed
+pub fn validate_name(name: &str) -> Result<(), ProjectError> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(char::is_alphabetic)
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(ProjectError::InvalidName(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("albayan_project_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_new_project_is_discovered_from_subdirectories() {
+        let dir = scratch("new");
+        let project = Project::create(&dir, "hello").unwrap();
+        assert_eq!(project.root, dir.join("hello"));
+        assert!(project.entry_path().is_file());
+        assert!(matches!(Project::create(&dir, "hello"), Err(ProjectError::AlreadyExists(_))));
+
+        let found = Project::discover(&project.root.join("src")).unwrap();
+        assert_eq!(found.root, project.root);
+        assert_eq!(found.name(), "hello");
+        assert_eq!(found.manifest.package.version, "0.1.0");
+        assert_eq!(found.entry_path(), project.root.join("src").join("main.ab"));
+        assert!(matches!(Project::discover(&dir), Err(ProjectError::NotFound(_))));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_init_keeps_existing_sources() {
+        let dir = scratch("init");
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src").join("main.ab"), "fn main() -> int { return 7; }\n").unwrap();
+
+        let project = Project::init(&dir, Some("بيان_1")).unwrap();
+        assert_eq!(project.name(), "بيان_1");
+        assert_eq!(std::fs::read_to_string(project.entry_path()).unwrap(), "fn main() -> int { return 7; }\n");
+        assert!(matches!(Project::init(&dir, None), Err(ProjectError::AlreadyExists(_))));
+        assert!(matches!(validate_name("1st"), Err(ProjectError::InvalidName(_))));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_parsing() {
+        let path = Path::new(MANIFEST_FILE);
+        let manifest = ProjectManifest::parse(
+            "[package]\nname = \"shapes\"\nversion = \"1.0.0\"\nentry = \"app.ab\"\n\n[dependencies]\ngeometry = \"1.2\"\n\n[lints]\nshadowing = \"deny\"\n",
+            path,
+        )
+        .unwrap();
+        assert_eq!(manifest.package.entry, PathBuf::from("app.ab"));
+        assert!(matches!(manifest.dependencies["geometry"], DependencySpec::Version(ref version) if version == "1.2"));
+        assert_eq!(manifest.lints.level("shadowing"), crate::tools::linter::LintLevel::Deny);
+
+        let defaulted = ProjectManifest::parse("[package]\nname = \"a\"\nversion = \"0.1.0\"\n", path).unwrap();
+        assert_eq!(defaulted.package.entry, PathBuf::from(DEFAULT_ENTRY));
+        assert!(!defaulted.to_toml().contains("[lints]"));
+        let round_trip = ProjectManifest::parse(&defaulted.to_toml(), path).unwrap();
+        assert_eq!(round_trip.package.name, "a");
+
+        assert!(matches!(ProjectManifest::parse("[package]\nname = \"a\"\n", path),
+                         Err(ProjectError::InvalidManifest { .. })));
+    }
+}
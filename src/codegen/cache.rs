@@ -0,0 +1,215 @@
+//! # Incremental Compilation Cache
+//!
+//! Object code keyed by everything it was generated from: the source of a
+//! module, the keys of the modules it imports, the options that affect code
+//! generation and the compiler version. A build that finds its key in the
+//! cache copies the stored object code instead of analyzing and generating
+//! the module again; any change to the inputs gives a new key, so entries are
+//! never stale, only unused. Only successful compilations are stored, so
+//! failing modules are always analyzed and report their diagnostics.
+//!
+//! Next to the object code, the cache keeps each module's analysis as
+//! `<key>.json`: the serialized [`AnnotatedProgram`]. A check, which
+//! generates no code, and a build whose object code is missing take the
+//! analysis from there instead of analyzing the module again.
+//!
+//! The cache is a directory of `<key>.o` and `<key>.json` files, enabled by setting
+//! `CompilerOptions::cache_dir`; `albayan build` uses `target/albayan/cache`
+//! of the project unless it is given `--no-cache`. Keys are FNV-1a hashes,
+//! which unlike `std`'s hasher are stable across Rust releases.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::semantic::AnnotatedProgram;
+use crate::CompilerOptions;
+
+/// Directory under a build's output directory that holds the cache
+pub const CACHE_DIR: &str = "cache";
+
+/// Identifies one compilation's inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheKey(u64);
+
+/// Builds a [`CacheKey`] from a compilation's inputs
+#[derive(Debug, Clone)]
+pub struct CacheKeyBuilder {
+    hash: u64,
+}
+
+/// Stored object code and analyses
+#[derive(Debug, Clone)]
+pub struct CompilationCache {
+    dir: PathBuf,
+}
+
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// A key of only the inputs added to it, for caches that do not depend on
+/// the compiler options
+impl Default for CacheKeyBuilder {
+    fn default() -> Self {
+        Self { hash: Self::OFFSET }
+    }
+}
+
+impl CacheKeyBuilder {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    /// A key for compiling with `options`, to which the sources are added
+    pub fn new(options: &CompilerOptions) -> Self {
+        let mut builder = Self::default();
+        builder.add(crate::VERSION.as_bytes());
+        builder.add(&[options.optimization_level, options.debug_info as u8, options.enable_logic as u8]);
+        builder.add(&[options.enable_ai as u8, options.use_llvm as u8]);
+        builder.add(options.target_triple.as_deref().unwrap_or("").as_bytes());
+        // Denied warnings fail an analysis that other levels let through
+        builder.add(format!("{:?}", options.warnings).as_bytes());
+        let mut packages: Vec<_> = options.packages.iter().collect();
+        packages.sort();
+        for (name, entry) in packages {
+            builder.add(name.as_bytes());
+            builder.add(entry.to_string_lossy().as_bytes());
+        }
+        builder
+    }
+
+    /// Add an input; each is length-prefixed so that inputs cannot run together
+    pub fn add(&mut self, bytes: &[u8]) -> &mut Self {
+        for byte in (bytes.len() as u64).to_le_bytes().iter().chain(bytes) {
+            self.hash = (self.hash ^ *byte as u64).wrapping_mul(Self::PRIME);
+        }
+        self
+    }
+
+    /// Add the key of a module the compilation depends on
+    pub fn add_key(&mut self, key: CacheKey) -> &mut Self {
+        self.add(&key.0.to_le_bytes())
+    }
+
+    pub fn finish(&self) -> CacheKey {
+        CacheKey(self.hash)
+    }
+}
+
+impl CompilationCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The cache configured in `options`, if any
+    pub fn from_options(options: &CompilerOptions) -> Option<Self> {
+        options.cache_dir.as_ref().map(Self::new)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry(&self, key: CacheKey) -> PathBuf {
+        self.dir.join(format!("{}.o", key))
+    }
+
+    fn analysis_entry(&self, key: CacheKey) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// The object code stored under `key`
+    pub fn get(&self, key: CacheKey) -> Option<Vec<u8>> {
+        std::fs::read(self.entry(key)).ok()
+    }
+
+    /// Store object code under `key`. The file is written next to its entry
+    /// and renamed into place, so a concurrent or interrupted build never
+    /// reads a partial entry.
+    pub fn put(&self, key: CacheKey, object_code: &[u8]) -> std::io::Result<()> {
+        self.write_entry(self.entry(key), object_code)
+    }
+
+    /// The analysis stored under `key`. An entry that no longer reads back,
+    /// such as one written before the annotated AST changed, counts as missing
+    pub fn get_analysis(&self, key: CacheKey) -> Option<AnnotatedProgram> {
+        serde_json::from_slice(&std::fs::read(self.analysis_entry(key)).ok()?).ok()
+    }
+
+    /// Store the analysis of a module under `key`, like [`CompilationCache::put`]
+    pub fn put_analysis(&self, key: CacheKey, program: &AnnotatedProgram) -> std::io::Result<()> {
+        let json = serde_json::to_vec(program).map_err(std::io::Error::from)?;
+        self.write_entry(self.analysis_entry(key), &json)
+    }
+
+    fn write_entry(&self, entry: PathBuf, bytes: &[u8]) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let name = entry.file_name().unwrap_or_default().to_string_lossy();
+        let partial = self.dir.join(format!("{}.{}.partial", name, std::process::id()));
+        std::fs::write(&partial, bytes)?;
+        std::fs::rename(&partial, entry)
+    }
+
+    /// Remove every entry
+    pub fn clear(&self) -> std::io::Result<()> {
+        match std::fs::remove_dir_all(&self.dir) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_change_with_every_input() {
+        let options = CompilerOptions::default();
+        let key = |source: &str, options: &CompilerOptions| CacheKeyBuilder::new(options).add(source.as_bytes()).finish();
+        let base = key("fn main() {}", &options);
+        assert_eq!(base, key("fn main() {}", &options));
+        assert_ne!(base, key("fn main() { }", &options));
+        assert_ne!(base, key("fn main() {}", &CompilerOptions { optimization_level: 2, ..Default::default() }));
+
+        // Inputs are delimited: ("ab", "c") and ("a", "bc") differ
+        let split = |a: &str, b: &str| CacheKeyBuilder::new(&options).add(a.as_bytes()).add(b.as_bytes()).finish();
+        assert_ne!(split("ab", "c"), split("a", "bc"));
+        assert_ne!(CacheKeyBuilder::new(&options).add_key(base).finish(), CacheKeyBuilder::new(&options).finish());
+    }
+
+    #[test]
+    fn test_entries_round_trip() {
+        let dir = std::env::temp_dir().join(format!("albayan_cache_{}", std::process::id()));
+        let cache = CompilationCache::new(&dir);
+        let key = CacheKeyBuilder::new(&CompilerOptions::default()).add(b"source").finish();
+        assert!(cache.get(key).is_none());
+        cache.put(key, b"object code").unwrap();
+        assert_eq!(cache.get(key).unwrap(), b"object code");
+        cache.clear().unwrap();
+        assert!(cache.get(key).is_none());
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn test_analyses_round_trip() {
+        let dir = std::env::temp_dir().join(format!("albayan_cache_analysis_{}", std::process::id()));
+        let cache = CompilationCache::new(&dir);
+        let options = CompilerOptions::default();
+        let key = CacheKeyBuilder::new(&options).add(b"analysis").finish();
+        let tokens = crate::lexer::Lexer::new("fn area(w: int, h: int) -> int { return w * h; }").tokenize().unwrap();
+        let program = crate::parser::Parser::new(tokens).parse().unwrap();
+        let analyzed = crate::semantic::SemanticAnalyzer::new(&options).analyze(program).unwrap();
+
+        assert!(cache.get_analysis(key).is_none());
+        cache.put_analysis(key, &analyzed).unwrap();
+        let restored = cache.get_analysis(key).unwrap();
+        assert_eq!(serde_json::to_value(&restored).unwrap(), serde_json::to_value(&analyzed).unwrap());
+        assert!(cache.get(key).is_none(), "analyses and object code are separate entries");
+
+        std::fs::write(dir.join(format!("{}.json", key)), b"{}").unwrap();
+        assert!(cache.get_analysis(key).is_none());
+        cache.clear().unwrap();
+    }
+}
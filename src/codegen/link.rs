@@ -0,0 +1,283 @@
+//! # Link Phase
+//!
+//! Turns native object code produced by the LLVM backend into a runnable
+//! executable by driving the system C compiler (`cc`, `clang`, `gcc`) or
+//! `lld` through the compiler driver.
+//!
+//! The linker is selected from the `ALBAYAN_LINKER` environment variable
+//! (`cc`, `lld` or a path to a compiler driver), then `CC`, then the first
+//! driver found on `PATH`. The runtime's C-compatible functions (`albayan_rt_*`)
+//! are linked from the static library named by `ALBAYAN_RUNTIME_LIB`, or from
+//! `libalbayan_lib.a` next to the running compiler; building an executable
+//! without either is an error.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Environment variable selecting the linker
+pub const LINKER_ENV: &str = "ALBAYAN_LINKER";
+
+/// Environment variable pointing at the runtime static library
+pub const RUNTIME_LIB_ENV: &str = "ALBAYAN_RUNTIME_LIB";
+
+/// File name of the runtime static library searched next to the compiler
+pub const RUNTIME_LIB_NAME: &str = if cfg!(windows) { "albayan_lib.lib" } else { "libalbayan_lib.a" };
+
+/// Compiler drivers tried in order when none is configured
+const DEFAULT_DRIVERS: &[&str] = &["cc", "clang", "gcc"];
+
+/// How the linker is invoked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkerFlavor {
+    /// System compiler driver with its default linker
+    Cc,
+    /// Compiler driver using `lld` (`-fuse-ld=lld`)
+    Lld,
+}
+
+/// Link phase errors
+#[derive(Debug, thiserror::Error)]
+pub enum LinkError {
+    #[error("No linker found (tried {0}); set ALBAYAN_LINKER or CC")]
+    LinkerNotFound(String),
+
+    #[error("Linker '{program}' failed with {status}: {stderr}")]
+    LinkFailed {
+        program: String,
+        status: String,
+        stderr: String,
+    },
+
+    #[error("Runtime library not found at {}; build it with `cargo build --features llvm` or set {}", .0.display(), RUNTIME_LIB_ENV)]
+    RuntimeLibraryNotFound(PathBuf),
+
+    #[error("IO error during linking: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Native linker abstraction
+#[derive(Debug, Clone)]
+pub struct Linker {
+    program: PathBuf,
+    flavor: LinkerFlavor,
+    runtime_libraries: Vec<PathBuf>,
+    target_triple: Option<String>,
+    extra_args: Vec<String>,
+}
+
+impl Linker {
+    /// Create a linker that invokes the given compiler driver
+    pub fn new<P: Into<PathBuf>>(program: P, flavor: LinkerFlavor) -> Self {
+        Self {
+            program: program.into(),
+            flavor,
+            runtime_libraries: Vec::new(),
+            target_triple: None,
+            extra_args: Vec::new(),
+        }
+    }
+
+    /// Detect the linker from the environment and `PATH`
+    pub fn detect() -> Result<Self, LinkError> {
+        let requested = std::env::var(LINKER_ENV).ok().filter(|v| !v.is_empty());
+        let (candidates, flavor) = match requested.as_deref() {
+            Some("lld") => (DEFAULT_DRIVERS.iter().map(|d| d.to_string()).collect(), LinkerFlavor::Lld),
+            Some("cc") | None => {
+                let mut candidates: Vec<String> = std::env::var("CC").ok().into_iter().collect();
+                candidates.extend(DEFAULT_DRIVERS.iter().map(|d| d.to_string()));
+                (candidates, LinkerFlavor::Cc)
+            }
+            Some(custom) => (vec![custom.to_string()], LinkerFlavor::Cc),
+        };
+
+        let program = candidates
+            .iter()
+            .find_map(|candidate| find_program(candidate))
+            .ok_or_else(|| LinkError::LinkerNotFound(candidates.join(", ")))?;
+
+        let mut linker = Self::new(program, flavor);
+        if let Some(runtime) = find_runtime_library() {
+            linker.runtime_libraries.push(runtime);
+        }
+        Ok(linker)
+    }
+
+    /// Link an additional runtime library
+    pub fn with_runtime_library<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.runtime_libraries.push(path.into());
+        self
+    }
+
+    /// Link for a specific target triple
+    pub fn with_target(mut self, target_triple: Option<String>) -> Self {
+        self.target_triple = target_triple;
+        self
+    }
+
+    /// Pass extra arguments to the linker driver
+    pub fn with_args<I: IntoIterator<Item = String>>(mut self, args: I) -> Self {
+        self.extra_args.extend(args);
+        self
+    }
+
+    /// Runtime libraries that will be linked
+    pub fn runtime_libraries(&self) -> &[PathBuf] {
+        &self.runtime_libraries
+    }
+
+    /// Fail unless a runtime library will be linked, naming where it was expected
+    pub fn require_runtime_library(&self) -> Result<(), LinkError> {
+        if self.runtime_libraries.is_empty() {
+            let expected = runtime_library_path().unwrap_or_else(|| PathBuf::from(RUNTIME_LIB_NAME));
+            return Err(LinkError::RuntimeLibraryNotFound(expected));
+        }
+        Ok(())
+    }
+
+    /// Command line arguments for linking `object` into `output`
+    pub fn command_args(&self, object: &Path, output: &Path) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if self.flavor == LinkerFlavor::Lld {
+            args.push("-fuse-ld=lld".to_string());
+        }
+        if let Some(triple) = &self.target_triple {
+            args.push(format!("--target={}", triple));
+        }
+
+        args.push(object.display().to_string());
+        args.extend(self.runtime_libraries.iter().map(|lib| lib.display().to_string()));
+
+        if !cfg!(windows) {
+            // System libraries used by generated code and the Rust runtime library
+            args.push("-lm".to_string());
+            if !self.runtime_libraries.is_empty() {
+                args.push("-lpthread".to_string());
+                args.push("-ldl".to_string());
+                // The runtime archive also holds the compiler; keep only what the program uses
+                args.push(if cfg!(target_os = "macos") { "-Wl,-dead_strip" } else { "-Wl,--gc-sections" }.to_string());
+            }
+        }
+
+        args.extend(self.extra_args.iter().cloned());
+        args.push("-o".to_string());
+        args.push(output.display().to_string());
+        args
+    }
+
+    /// Link an object file into an executable
+    pub fn link(&self, object: &Path, output: &Path) -> Result<(), LinkError> {
+        let result = Command::new(&self.program)
+            .args(self.command_args(object, output))
+            .output()?;
+
+        if !result.status.success() {
+            return Err(LinkError::LinkFailed {
+                program: self.program.display().to_string(),
+                status: result.status.to_string(),
+                stderr: String::from_utf8_lossy(&result.stderr).trim().to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Write object code to a temporary file and link it into an executable
+    pub fn link_object_code(&self, object_code: &[u8], output: &Path) -> Result<(), LinkError> {
+        let object_path = std::env::temp_dir().join(format!(
+            "albayan_{}_{}.o",
+            std::process::id(),
+            output.file_stem().and_then(|s| s.to_str()).unwrap_or("out")
+        ));
+        std::fs::write(&object_path, object_code)?;
+
+        let result = self.link(&object_path, output);
+        let _ = std::fs::remove_file(&object_path);
+        result
+    }
+}
+
+/// Default executable path for a source file: the input with the platform's
+/// executable extension, or with `.out` when that is the input itself
+pub fn default_executable_path(input: &Path) -> PathBuf {
+    let mut path = input.to_path_buf();
+    path.set_extension(std::env::consts::EXE_EXTENSION);
+    if path == input {
+        path.set_extension("out");
+    }
+    path
+}
+
+/// Resolve a program name or path to an executable file
+fn find_program(name: &str) -> Option<PathBuf> {
+    let candidate = Path::new(name);
+    if candidate.components().count() > 1 {
+        return candidate.is_file().then(|| candidate.to_path_buf());
+    }
+
+    let file_name = if std::env::consts::EXE_EXTENSION.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", name, std::env::consts::EXE_EXTENSION)
+    };
+
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(&file_name))
+        .find(|path| path.is_file())
+}
+
+/// Where the runtime static library is expected: `ALBAYAN_RUNTIME_LIB`, or
+/// next to the running compiler
+fn runtime_library_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(RUNTIME_LIB_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    Some(std::env::current_exe().ok()?.parent()?.join(RUNTIME_LIB_NAME))
+}
+
+/// Locate the runtime static library
+fn find_runtime_library() -> Option<PathBuf> {
+    runtime_library_path().filter(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_args() {
+        let linker = Linker::new("cc", LinkerFlavor::Lld)
+            .with_runtime_library("/lib/libalbayan_lib.a")
+            .with_target(Some("x86_64-unknown-linux-gnu".to_string()));
+        let args = linker.command_args(Path::new("main.o"), Path::new("main"));
+
+        assert_eq!(args[0], "-fuse-ld=lld");
+        assert_eq!(args[1], "--target=x86_64-unknown-linux-gnu");
+        assert!(args.contains(&"main.o".to_string()));
+        assert!(args.contains(&"/lib/libalbayan_lib.a".to_string()));
+        if cfg!(target_os = "linux") {
+            assert!(args.contains(&"-Wl,--gc-sections".to_string()));
+        }
+        assert_eq!(&args[args.len() - 2..], &["-o".to_string(), "main".to_string()]);
+    }
+
+    #[test]
+    fn test_default_executable_path() {
+        let path = default_executable_path(Path::new("examples/hello.ab"));
+        assert_eq!(path.file_stem().unwrap(), "hello");
+
+        // An input without an extension is never overwritten
+        let bare = Path::new("examples/hello");
+        assert_ne!(default_executable_path(bare), bare);
+        if cfg!(unix) {
+            assert_eq!(default_executable_path(bare), Path::new("examples/hello.out"));
+        }
+    }
+
+    #[test]
+    fn test_missing_runtime_library_is_an_error() {
+        let linker = Linker::new("cc", LinkerFlavor::Cc);
+        assert!(matches!(linker.require_runtime_library(), Err(LinkError::RuntimeLibraryNotFound(_))));
+        assert!(linker.with_runtime_library("/lib/libalbayan_lib.a").require_runtime_library().is_ok());
+    }
+}
@@ -0,0 +1,3877 @@
+//! # LLVM Backend
+//!
+//! Lowers an `AnnotatedProgram` to LLVM IR with inkwell, runs the optimization
+//! pipeline selected by `CompilerOptions::optimization_level` and emits native
+//! object code. Only available with the `llvm` cargo feature.
+//!
+//! The backend currently covers the scalar subset of the language: functions,
+//! `let`/`return`/`if`/`match` statements, `while` and `for` loops with
+//! `break`/`continue`, list literals, arithmetic, comparison and logical
+//! operators, calls and the `print`/`println`/`format` builtins. Other constructs
+//! are reported as `CodeGenError::UnsupportedFeature` instead of being silently
+//! dropped.
+//!
+//! Format templates are split at compile time; each piece becomes a call to the
+//! `albayan_rt_format_*` helpers of `runtime::formatting`, and printed text goes
+//! through `albayan_rt_print_string`, so executables link against the runtime library.
+//!
+//! A `List<T>` value is lowered to a `{ i64 length, T* data }` pair whose
+//! elements live on the heap; a fixed-size `[T; N]` array has the same layout.
+//!
+//! Structs become named LLVM structs and `&T` a pointer to `T`. Impl methods
+//! are emitted as `Type::method` (`<Type as Trait>::method` for trait impls).
+//! A `&dyn Trait` value is a fat pointer `{ ptr data, ptr vtable }`; the vtable
+//! of each (trait, type) pair is a constant array of the impl's method
+//! pointers in trait declaration order, emitted the first time a `&T` is
+//! converted to a trait object, and calls through a trait object load their
+//! method from it and call it indirectly.
+//!
+//! The relations, facts and rules of a program are encoded at compile time
+//! into a knowledge image (see `runtime::knowledge_image`) kept in the private
+//! constant `albayan.knowledge`; `main` first passes it to
+//! `albayan_rt_load_knowledge`, which loads it into the runtime's logic engine.
+//! A `query` expression solves its goals in that engine, and the solutions
+//! become a list of `AlbayanMap`s. `assert` and `retract` statements build
+//! their fact argument by argument with the `albayan_rt_fact_*` helpers of
+//! `runtime::fact_updates`, so values reach the engine as terms, not text;
+//! an update the engine rejects panics at the statement.
+//!
+//! Tuples of up to two scalars are returned by value, in registers. Larger
+//! tuples are returned through a caller-allocated buffer passed as a leading
+//! `sret` pointer parameter; `let (a, b, c) = f();` binds the names to the
+//! fields of that buffer instead of copying them out.
+//!
+//! Values whose type implements `Drop`, or holds such values, are dropped
+//! through per-type drop glue (`drop.Type`): the type's own `drop` runs first,
+//! then its fields in declaration order. Each owning variable has a drop flag
+//! that a move clears; at the end of a scope, and before `return`, `break` and
+//! `continue` leave it, the flagged variables are dropped in reverse
+//! declaration order, innermost scope first.
+
+use inkwell::attributes::{Attribute, AttributeLoc};
+use inkwell::basic_block::BasicBlock;
+use inkwell::builder::Builder;
+use inkwell::context::Context;
+use inkwell::module::{Linkage, Module};
+use inkwell::passes::PassBuilderOptions;
+use inkwell::targets::{
+    CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine, TargetTriple,
+};
+use inkwell::types::{AnyType, BasicMetadataTypeEnum, BasicType, BasicTypeEnum, PointerType, StructType};
+use inkwell::values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, GlobalValue, IntValue, PointerValue, StructValue};
+use inkwell::{AddressSpace, FloatPredicate, IntPredicate, OptimizationLevel};
+use std::collections::{HashMap, HashSet};
+
+use super::{CodeGenError, CodeGenerator};
+use crate::cancellation::CancellationToken;
+use crate::runtime::{ImageArg, ImageGoal, KnowledgeImageBuilder};
+use crate::parser::ast::{BinaryOperator, Literal, UnaryOperator};
+use crate::semantic::format_string::{parse_format_string, FormatPiece};
+use crate::semantic::{
+    AnnotatedBlock, AnnotatedExpression, AnnotatedExpressionKind, AnnotatedFactArg, AnnotatedFactUpdate, AnnotatedForStatement,
+    AnnotatedFunction, AnnotatedImpl, AnnotatedItem, AnnotatedLogicArg, AnnotatedLogicTerm, AnnotatedMatchArm, AnnotatedPattern, AnnotatedProgram,
+    AnnotatedStatement, AnnotatedStruct, AnnotatedTrait, AnnotatedUnaryExpression, AnnotatedWhileStatement,
+    ResolvedType, DROP_TRAIT,
+};
+use crate::CompilerOptions;
+
+/// Name of the program entry point, emitted as the C `main` symbol
+const ENTRY_POINT: &str = "main";
+
+/// LLVM code generator producing native object files
+pub struct LLVMBackend {
+    options: CompilerOptions,
+}
+
+impl LLVMBackend {
+    /// Create a new LLVM backend
+    pub fn new(options: &CompilerOptions) -> Self {
+        Self {
+            options: options.clone(),
+        }
+    }
+
+    /// Lower the program and return the optimized textual LLVM IR
+    pub fn emit_ir(&self, program: &AnnotatedProgram) -> Result<String, CodeGenError> {
+        let context = Context::create();
+        let module = self.build_module(&context, program)?;
+        Ok(module.print_to_string().to_string())
+    }
+
+    /// Lower the program and return native object code
+    pub fn emit_object(&self, program: &AnnotatedProgram) -> Result<Vec<u8>, CodeGenError> {
+        self.emit_file(program, FileType::Object)
+    }
+
+    /// Lower the program and return target assembly
+    pub fn emit_assembly(&self, program: &AnnotatedProgram) -> Result<Vec<u8>, CodeGenError> {
+        self.emit_file(program, FileType::Assembly)
+    }
+
+    fn emit_file(&self, program: &AnnotatedProgram, file_type: FileType) -> Result<Vec<u8>, CodeGenError> {
+        let context = Context::create();
+        let module = self.build_module(&context, program)?;
+        let machine = self.target_machine()?;
+
+        let buffer = machine
+            .write_to_memory_buffer(&module, file_type)
+            .map_err(|e| CodeGenError::GenerationError(format!("LLVM emission failed: {}", e)))?;
+        Ok(buffer.as_slice().to_vec())
+    }
+
+    /// Lower, verify and optimize a module for the configured target
+    fn build_module<'ctx>(
+        &self,
+        context: &'ctx Context,
+        program: &AnnotatedProgram,
+    ) -> Result<Module<'ctx>, CodeGenError> {
+        let machine = self.target_machine()?;
+
+        let mut lowering = ModuleLowering::new(context, "albayan_module", self.options.cancellation.clone());
+        lowering.module.set_triple(&machine.get_triple());
+        lowering.module.set_data_layout(&machine.get_target_data().get_data_layout());
+        lowering.lower_program(program)?;
+
+        let module = lowering.module;
+        module
+            .verify()
+            .map_err(|e| CodeGenError::GenerationError(format!("Invalid LLVM module: {}", e)))?;
+
+        if let Some(pipeline) = self.pass_pipeline() {
+            module
+                .run_passes(pipeline, &machine, PassBuilderOptions::create())
+                .map_err(|e| CodeGenError::GenerationError(format!("LLVM optimization failed: {}", e)))?;
+        }
+
+        Ok(module)
+    }
+
+    fn pass_pipeline(&self) -> Option<&'static str> {
+        match self.options.optimization_level {
+            0 => None,
+            1 => Some("default<O1>"),
+            2 => Some("default<O2>"),
+            _ => Some("default<O3>"),
+        }
+    }
+
+    fn llvm_optimization_level(&self) -> OptimizationLevel {
+        match self.options.optimization_level {
+            0 => OptimizationLevel::None,
+            1 => OptimizationLevel::Less,
+            2 => OptimizationLevel::Default,
+            _ => OptimizationLevel::Aggressive,
+        }
+    }
+
+    /// Create a target machine for the requested (or host) triple
+    fn target_machine(&self) -> Result<TargetMachine, CodeGenError> {
+        let triple = match &self.options.target_triple {
+            Some(triple) => {
+                Target::initialize_all(&InitializationConfig::default());
+                TargetTriple::create(triple)
+            }
+            None => {
+                Target::initialize_native(&InitializationConfig::default())
+                    .map_err(|e| CodeGenError::GenerationError(format!("Cannot initialize native target: {}", e)))?;
+                TargetMachine::get_default_triple()
+            }
+        };
+
+        let target = Target::from_triple(&triple)
+            .map_err(|e| CodeGenError::GenerationError(format!("Unknown target: {}", e)))?;
+
+        let (cpu, features) = if self.options.target_triple.is_none() {
+            (
+                TargetMachine::get_host_cpu_name().to_string(),
+                TargetMachine::get_host_cpu_features().to_string(),
+            )
+        } else {
+            ("generic".to_string(), String::new())
+        };
+
+        target
+            .create_target_machine(
+                &triple,
+                &cpu,
+                &features,
+                self.llvm_optimization_level(),
+                RelocMode::PIC,
+                CodeModel::Default,
+            )
+            .ok_or_else(|| CodeGenError::GenerationError("Cannot create LLVM target machine".to_string()))
+    }
+}
+
+impl CodeGenerator for LLVMBackend {
+    fn generate(&mut self, program: AnnotatedProgram) -> Result<Vec<u8>, CodeGenError> {
+        self.emit_object(&program)
+    }
+}
+
+/// A local variable slot
+#[derive(Clone, Copy)]
+struct LocalSlot<'ctx> {
+    pointer: PointerValue<'ctx>,
+    value_type: BasicTypeEnum<'ctx>,
+    /// Set while the slot owns a value that needs dropping
+    drop_flag: Option<PointerValue<'ctx>>,
+}
+
+/// A value its scope drops when it ends, unless a move cleared the flag
+#[derive(Clone, Copy)]
+struct DropSlot<'ctx> {
+    pointer: PointerValue<'ctx>,
+    flag: PointerValue<'ctx>,
+    glue: FunctionValue<'ctx>,
+}
+
+/// A lexical scope with the values it owns in declaration order
+#[derive(Default)]
+struct Scope<'ctx> {
+    locals: HashMap<String, LocalSlot<'ctx>>,
+    drops: Vec<DropSlot<'ctx>>,
+}
+
+/// A declared function with the AlBayan types of its signature
+#[derive(Clone)]
+struct DeclaredFunction<'ctx> {
+    value: FunctionValue<'ctx>,
+    parameters: Vec<ResolvedType>,
+    return_type: ResolvedType,
+}
+
+/// A lowered struct and its field names and types in declaration order
+struct StructLayout<'ctx> {
+    llvm_type: StructType<'ctx>,
+    fields: Vec<String>,
+    field_types: Vec<ResolvedType>,
+}
+
+/// Per-module lowering state
+struct ModuleLowering<'ctx> {
+    context: &'ctx Context,
+    module: Module<'ctx>,
+    builder: Builder<'ctx>,
+
+    /// Declared functions, including impl methods under `Type::method`
+    functions: HashMap<String, DeclaredFunction<'ctx>>,
+
+    structs: HashMap<String, StructLayout<'ctx>>,
+
+    /// Trait declarations, whose method order fixes the vtable layout
+    traits: HashMap<String, AnnotatedTrait>,
+
+    /// Methods of each `impl Trait for Type`, keyed by (trait, type)
+    trait_impls: HashMap<(String, String), HashMap<String, FunctionValue<'ctx>>>,
+
+    /// Vtables emitted so far, keyed by (trait, type)
+    vtables: HashMap<(String, String), GlobalValue<'ctx>>,
+
+    /// Types with an `impl Drop`
+    drop_impls: HashSet<String>,
+
+    /// Drop glue emitted so far, keyed by type
+    drop_glues: HashMap<String, FunctionValue<'ctx>>,
+
+    /// The program's knowledge image and its length, loaded at the start of `main`
+    knowledge: Option<(GlobalValue<'ctx>, u64)>,
+
+    /// Lexical scopes of the function being lowered
+    scopes: Vec<Scope<'ctx>>,
+
+    current_function: Option<FunctionValue<'ctx>>,
+    current_return_type: ResolvedType,
+    /// Caller-allocated result of a function returning through `sret`
+    current_result: Option<PointerValue<'ctx>>,
+
+    /// `(continue, break)` targets of the enclosing loops and the number of
+    /// scopes outside each body, innermost last
+    loop_targets: Vec<(BasicBlock<'ctx>, BasicBlock<'ctx>, usize)>,
+
+    /// Checked before every item and statement
+    cancellation: CancellationToken,
+}
+
+impl<'ctx> ModuleLowering<'ctx> {
+    fn new(context: &'ctx Context, module_name: &str, cancellation: CancellationToken) -> Self {
+        Self {
+            context,
+            module: context.create_module(module_name),
+            builder: context.create_builder(),
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+            traits: HashMap::new(),
+            trait_impls: HashMap::new(),
+            vtables: HashMap::new(),
+            drop_impls: HashSet::new(),
+            drop_glues: HashMap::new(),
+            knowledge: None,
+            scopes: Vec::new(),
+            current_function: None,
+            current_return_type: ResolvedType::Unit,
+            current_result: None,
+            loop_targets: Vec::new(),
+            cancellation,
+        }
+    }
+
+    fn lower_program(&mut self, program: &AnnotatedProgram) -> Result<(), CodeGenError> {
+        self.declare_structs(program)?;
+        self.declare_knowledge(program);
+
+        // Declare every function first so calls may precede definitions
+        for item in &program.items {
+            match item {
+                AnnotatedItem::Function(func) => self.declare_function(func, &func.name)?,
+                AnnotatedItem::Impl(impl_block) => self.declare_impl(impl_block)?,
+                AnnotatedItem::Trait(trait_decl) => {
+                    self.traits.insert(trait_decl.name.clone(), trait_decl.clone());
+                }
+                _ => {}
+            }
+        }
+
+        for item in &program.items {
+            self.cancellation.check()?;
+            match item {
+                AnnotatedItem::Function(func) => self.lower_function(func, &func.name)?,
+                AnnotatedItem::Impl(impl_block) => {
+                    for method in &impl_block.methods {
+                        self.lower_function(method, &Self::method_symbol(impl_block, &method.name))?;
+                    }
+                }
+                // Declarations without runtime code; logic items live in the knowledge image
+                // and constants are inlined at their uses
+                AnnotatedItem::Relation(_)
+                | AnnotatedItem::Rule(_)
+                | AnnotatedItem::Fact(_)
+                | AnnotatedItem::Concept(_)
+                | AnnotatedItem::Constraint(_)
+                | AnnotatedItem::Using(_)
+                | AnnotatedItem::Const(_)
+                | AnnotatedItem::Trait(_)
+                | AnnotatedItem::Struct(_) => {}
+                AnnotatedItem::Enum(e) => {
+                    return Err(unsupported(format!("enum '{}'", e.name)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // ===== Types =====
+
+    fn basic_type(&self, ty: &ResolvedType) -> Result<BasicTypeEnum<'ctx>, CodeGenError> {
+        match ty {
+            ResolvedType::Int => Ok(self.context.i64_type().into()),
+            ResolvedType::Float => Ok(self.context.f64_type().into()),
+            ResolvedType::Bool => Ok(self.context.bool_type().into()),
+            ResolvedType::Char => Ok(self.context.i32_type().into()),
+            ResolvedType::String | ResolvedType::Null => Ok(self.string_type().into()),
+            ResolvedType::List(element) | ResolvedType::Vector(element, _) => Ok(self.list_type(element)?.into()),
+            // Pointer to a runtime `AlbayanMap`
+            ResolvedType::Map(_, _) | ResolvedType::Set(_) => Ok(self.pointer_type().into()),
+            ResolvedType::Struct(name) => self
+                .structs
+                .get(name)
+                .map(|layout| layout.llvm_type.into())
+                .ok_or_else(|| unsupported(format!("struct '{}'", name))),
+            ResolvedType::Reference(inner, _) if matches!(inner.as_ref(), ResolvedType::TraitObject(_)) => {
+                Ok(self.trait_object_type().into())
+            }
+            ResolvedType::Reference(_, _) => Ok(self.pointer_type().into()),
+            ResolvedType::TraitObject(_) => Ok(self.trait_object_type().into()),
+            ResolvedType::Tuple(elements) => {
+                let fields = elements
+                    .iter()
+                    .map(|element| self.basic_type(element))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(self.context.struct_type(&fields, false).into())
+            }
+            other => Err(unsupported(format!("type {:?}", other))),
+        }
+    }
+
+    /// `{ i64 length, T* data }`
+    fn list_type(&self, element: &ResolvedType) -> Result<StructType<'ctx>, CodeGenError> {
+        let data = self.basic_type(element)?.ptr_type(AddressSpace::default());
+        Ok(self
+            .context
+            .struct_type(&[self.context.i64_type().into(), data.into()], false))
+    }
+
+    fn string_type(&self) -> PointerType<'ctx> {
+        self.context.i8_type().ptr_type(AddressSpace::default())
+    }
+
+    fn pointer_type(&self) -> PointerType<'ctx> {
+        self.context.i8_type().ptr_type(AddressSpace::default())
+    }
+
+    /// `{ ptr data, ptr vtable }`
+    fn trait_object_type(&self) -> StructType<'ctx> {
+        let pointer = self.pointer_type().into();
+        self.context.struct_type(&[pointer, pointer], false)
+    }
+
+    /// Create every struct type before filling in the fields, so fields may
+    /// name structs declared later in the program
+    fn declare_structs(&mut self, program: &AnnotatedProgram) -> Result<(), CodeGenError> {
+        let structs: Vec<&AnnotatedStruct> = program
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                AnnotatedItem::Struct(s) if !s.generic_params.as_ref().is_some_and(|params| !params.is_empty()) => {
+                    Some(s)
+                }
+                _ => None,
+            })
+            .collect();
+
+        for s in &structs {
+            let layout = StructLayout {
+                llvm_type: self.context.opaque_struct_type(&s.name),
+                fields: s.fields.iter().map(|field| field.name.clone()).collect(),
+                field_types: s.fields.iter().map(|field| field.field_type.clone()).collect(),
+            };
+            self.structs.insert(s.name.clone(), layout);
+        }
+        for s in &structs {
+            let fields = s
+                .fields
+                .iter()
+                .map(|field| self.basic_type(&field.field_type))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.structs[&s.name].llvm_type.set_body(&fields, false);
+        }
+        Ok(())
+    }
+
+    /// Encode the concepts, relations, constraints, facts and rules of the
+    /// program into the constant knowledge image
+    fn declare_knowledge(&mut self, program: &AnnotatedProgram) {
+        let mut image = KnowledgeImageBuilder::new();
+        for item in &program.items {
+            match item {
+                AnnotatedItem::Concept(concept) => {
+                    let parents: Vec<&str> = concept.parents.iter().map(String::as_str).collect();
+                    image.concept(&concept.name, &parents);
+                }
+                AnnotatedItem::Relation(relation) => {
+                    // A concept-typed argument is checked against the ontology at load time
+                    let arg_types: Vec<&str> = relation
+                        .arg_types
+                        .iter()
+                        .zip(&relation.arg_concepts)
+                        .map(|(ty, concept)| concept.as_deref().unwrap_or_else(|| relation_arg_type(ty)))
+                        .collect();
+                    image.relation(&relation.name, &arg_types, relation.tabled);
+                }
+                AnnotatedItem::Constraint(constraint) => {
+                    let goals: Vec<(&str, Vec<ImageArg>)> =
+                        constraint.goals.iter().map(|goal| (goal.name.as_str(), image_args(goal))).collect();
+                    image.constraint(&goals);
+                }
+                AnnotatedItem::Fact(fact) => image.fact(&fact.term.name, &image_args(&fact.term)),
+                AnnotatedItem::Rule(rule) => {
+                    let goals: Vec<ImageGoal> = rule
+                        .body
+                        .iter()
+                        .map(|goal| ImageGoal { predicate: &goal.name, args: image_args(goal), negated: goal.negated })
+                        .collect();
+                    image.rule((&rule.head.name, &image_args(&rule.head)), &goals, !rule.no_reorder);
+                }
+                _ => {}
+            }
+        }
+        if image.is_empty() {
+            return;
+        }
+
+        let bytes = image.finish();
+        let data = self.context.const_string(&bytes, false);
+        let global = self.module.add_global(data.get_type(), None, "albayan.knowledge");
+        global.set_initializer(&data);
+        global.set_constant(true);
+        global.set_linkage(Linkage::Private);
+        self.knowledge = Some((global, bytes.len() as u64));
+    }
+
+    fn field_index(&self, struct_name: &str, field: &str) -> Result<(StructType<'ctx>, u32), CodeGenError> {
+        let layout = self
+            .structs
+            .get(struct_name)
+            .ok_or_else(|| unsupported(format!("struct '{}'", struct_name)))?;
+        let index = layout
+            .fields
+            .iter()
+            .position(|name| name == field)
+            .ok_or_else(|| CodeGenError::GenerationError(format!("struct '{}' has no field '{}'", struct_name, field)))?;
+        Ok((layout.llvm_type, index as u32))
+    }
+
+    fn is_unit(ty: &Option<ResolvedType>) -> bool {
+        matches!(ty, None | Some(ResolvedType::Unit))
+    }
+
+    /// Whether a function returning `ty` writes its result through an `sret`
+    /// pointer: tuples of more than two elements or of non-scalar elements
+    fn returns_indirectly(ty: &ResolvedType) -> bool {
+        let is_scalar = |element: &ResolvedType| match element {
+            ResolvedType::Int
+            | ResolvedType::Float
+            | ResolvedType::Bool
+            | ResolvedType::Char
+            | ResolvedType::String
+            | ResolvedType::Null => true,
+            ResolvedType::Reference(inner, _) => !matches!(inner.as_ref(), ResolvedType::TraitObject(_)),
+            _ => false,
+        };
+        matches!(ty, ResolvedType::Tuple(elements) if elements.len() > 2 || !elements.iter().all(is_scalar))
+    }
+
+    /// Type of a function taking `params` and returning `return_type`,
+    /// with the leading result pointer of an `sret` return
+    fn function_type(
+        &self,
+        params: &[BasicMetadataTypeEnum<'ctx>],
+        return_type: &ResolvedType,
+    ) -> Result<inkwell::types::FunctionType<'ctx>, CodeGenError> {
+        if matches!(return_type, ResolvedType::Unit) {
+            Ok(self.context.void_type().fn_type(params, false))
+        } else if Self::returns_indirectly(return_type) {
+            let mut with_result: Vec<BasicMetadataTypeEnum<'ctx>> = vec![self.pointer_type().into()];
+            with_result.extend_from_slice(params);
+            Ok(self.context.void_type().fn_type(&with_result, false))
+        } else {
+            Ok(self.basic_type(return_type)?.fn_type(params, false))
+        }
+    }
+
+    fn sret_attribute(&self, return_type: &ResolvedType) -> Result<Attribute, CodeGenError> {
+        let kind = Attribute::get_named_enum_kind_id("sret");
+        Ok(self
+            .context
+            .create_type_attribute(kind, self.basic_type(return_type)?.as_any_type_enum()))
+    }
+
+    // ===== Functions =====
+
+    fn declare_function(&mut self, func: &AnnotatedFunction, name: &str) -> Result<(), CodeGenError> {
+        if func.generic_params.as_ref().is_some_and(|params| !params.is_empty()) {
+            return Err(unsupported(format!("generic function '{}'", func.name)));
+        }
+
+        let params = func
+            .parameters
+            .iter()
+            .map(|p| self.basic_type(&p.param_type).map(BasicMetadataTypeEnum::from))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let return_type = func.return_type.clone().unwrap_or(ResolvedType::Unit);
+        let fn_type = if name == ENTRY_POINT {
+            // The entry point always follows the C `int main()` convention
+            self.context.i32_type().fn_type(&params, false)
+        } else {
+            self.function_type(&params, &return_type)?
+        };
+
+        let function = self.module.add_function(name, fn_type, None);
+        let indirect = name != ENTRY_POINT && Self::returns_indirectly(&return_type);
+        if indirect {
+            function.add_attribute(AttributeLoc::Param(0), self.sret_attribute(&return_type)?);
+            function.get_nth_param(0).expect("sret parameter").set_name("result");
+        }
+        for (param, value) in func.parameters.iter().zip(function.get_param_iter().skip(indirect as usize)) {
+            value.set_name(&param.name);
+        }
+
+        let declared = DeclaredFunction {
+            value: function,
+            parameters: func.parameters.iter().map(|p| p.param_type.clone()).collect(),
+            return_type,
+        };
+        self.functions.insert(name.to_string(), declared);
+        Ok(())
+    }
+
+    /// Symbol of an impl method: `Type::method`, or `<Type as Trait>::method` in a trait impl
+    fn method_symbol(impl_block: &AnnotatedImpl, method: &str) -> String {
+        match &impl_block.trait_name {
+            Some(trait_name) => format!("<{} as {}>::{}", impl_block.type_name, trait_name, method),
+            None => format!("{}::{}", impl_block.type_name, method),
+        }
+    }
+
+    /// Declare the methods of an impl block. Method calls are annotated as
+    /// `Type::method`; an inherent method takes that name before a trait's.
+    fn declare_impl(&mut self, impl_block: &AnnotatedImpl) -> Result<(), CodeGenError> {
+        if impl_block.generic_params.as_ref().is_some_and(|params| !params.is_empty()) {
+            return Err(unsupported(format!("generic impl block for '{}'", impl_block.type_name)));
+        }
+
+        if impl_block.trait_name.as_deref() == Some(DROP_TRAIT) {
+            self.drop_impls.insert(impl_block.type_name.clone());
+        }
+
+        for method in &impl_block.methods {
+            let symbol = Self::method_symbol(impl_block, &method.name);
+            self.declare_function(method, &symbol)?;
+            let declared = self.functions[&symbol].clone();
+
+            let call_name = format!("{}::{}", impl_block.type_name, method.name);
+            match &impl_block.trait_name {
+                Some(trait_name) => {
+                    self.trait_impls
+                        .entry((trait_name.clone(), impl_block.type_name.clone()))
+                        .or_default()
+                        .insert(method.name.clone(), declared.value);
+                    self.functions.entry(call_name).or_insert(declared);
+                }
+                None => {
+                    self.functions.insert(call_name, declared);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn lower_function(&mut self, func: &AnnotatedFunction, name: &str) -> Result<(), CodeGenError> {
+        let DeclaredFunction { value: function, return_type, .. } = self.functions[name].clone();
+        let entry = self.context.append_basic_block(function, "entry");
+        self.builder.position_at_end(entry);
+
+        self.current_function = Some(function);
+        self.current_result = if name != ENTRY_POINT && Self::returns_indirectly(&return_type) {
+            function.get_nth_param(0).map(|result| result.into_pointer_value())
+        } else {
+            None
+        };
+        self.current_return_type = return_type;
+        self.scopes = vec![Scope::default()];
+
+        if name == ENTRY_POINT {
+            self.lower_knowledge_load()?;
+        }
+
+        let skip = self.current_result.is_some() as usize;
+        for (param, value) in func.parameters.iter().zip(function.get_param_iter().skip(skip)) {
+            let slot = self.allocate_local(&param.name, value.get_type())?;
+            self.builder.build_store(slot.pointer, value).map_err(builder_error)?;
+            self.own_local(&param.name, &param.param_type, true)?;
+        }
+
+        self.lower_block(&func.body)?;
+
+        // Fall-through at the end of the body
+        if !self.block_terminated() {
+            self.drop_scopes(0)?;
+            self.build_default_return(func, name)?;
+        }
+
+        self.current_function = None;
+        self.current_result = None;
+        self.scopes.clear();
+        Ok(())
+    }
+
+    /// Load the knowledge image into the runtime before the body of `main`
+    fn lower_knowledge_load(&mut self) -> Result<(), CodeGenError> {
+        let Some((image, length)) = self.knowledge else {
+            return Ok(());
+        };
+        let i64_type = self.context.i64_type();
+        let load = self.runtime_function(
+            "albayan_rt_load_knowledge",
+            self.context
+                .i32_type()
+                .fn_type(&[self.pointer_type().into(), i64_type.into()], false),
+        );
+        let args = [image.as_pointer_value().into(), i64_type.const_int(length, false).into()];
+        self.builder.build_call(load, &args, "knowledge.loaded").map_err(builder_error)?;
+        Ok(())
+    }
+
+    fn build_default_return(&mut self, func: &AnnotatedFunction, name: &str) -> Result<(), CodeGenError> {
+        if name == ENTRY_POINT {
+            let zero = self.context.i32_type().const_zero();
+            self.builder.build_return(Some(&zero)).map_err(builder_error)?;
+        } else if Self::is_unit(&func.return_type) {
+            self.builder.build_return(None).map_err(builder_error)?;
+        } else {
+            // Semantic analysis guarantees a return on every path
+            self.builder.build_unreachable().map_err(builder_error)?;
+        }
+        Ok(())
+    }
+
+    fn allocate_local(&mut self, name: &str, value_type: BasicTypeEnum<'ctx>) -> Result<LocalSlot<'ctx>, CodeGenError> {
+        let pointer = self.entry_alloca(value_type, name)?;
+        let slot = LocalSlot { pointer, value_type, drop_flag: None };
+        self.scopes
+            .last_mut()
+            .expect("no active scope")
+            .locals
+            .insert(name.to_string(), slot);
+        Ok(slot)
+    }
+
+    fn entry_alloca(&self, value_type: BasicTypeEnum<'ctx>, name: &str) -> Result<PointerValue<'ctx>, CodeGenError> {
+        // Allocas live in the entry block so mem2reg can promote them
+        let function = self.current_function.expect("allocation outside of a function");
+        let entry = function.get_first_basic_block().expect("function without entry block");
+        let entry_builder = self.context.create_builder();
+        match entry.get_first_instruction() {
+            Some(first) => entry_builder.position_before(&first),
+            None => entry_builder.position_at_end(entry),
+        }
+        entry_builder.build_alloca(value_type, name).map_err(builder_error)
+    }
+
+    fn lookup_local(&self, name: &str) -> Option<LocalSlot<'ctx>> {
+        self.scopes.iter().rev().find_map(|scope| scope.locals.get(name).copied())
+    }
+
+    fn block_terminated(&self) -> bool {
+        self.builder
+            .get_insert_block()
+            .and_then(|block| block.get_terminator())
+            .is_some()
+    }
+
+    fn append_block(&self, name: &str) -> BasicBlock<'ctx> {
+        let function = self.current_function.expect("block outside of a function");
+        self.context.append_basic_block(function, name)
+    }
+
+    // ===== Statements =====
+
+    /// Lower a block, returning the value of a trailing expression statement
+    /// that no `;` ends
+    fn lower_block(&mut self, block: &AnnotatedBlock) -> Result<Option<BasicValueEnum<'ctx>>, CodeGenError> {
+        self.scopes.push(Scope::default());
+        let mut last_value = None;
+
+        for (position, statement) in block.statements.iter().enumerate() {
+            if self.block_terminated() {
+                // Code after return is unreachable
+                break;
+            }
+            self.cancellation.check()?;
+            last_value = self.lower_statement(statement)?;
+
+            // A trailing expression is the block's value; other values are dropped at once
+            if let AnnotatedStatement::Expression(expr) = statement {
+                if position + 1 == block.statements.len() && !block.trailing_semicolon {
+                    self.consume(expr)?;
+                } else if let Some(value) = last_value.filter(|_| Self::is_temporary(expr)) {
+                    self.drop_value(value, &expr.result_type)?;
+                }
+            }
+        }
+
+        if !self.block_terminated() {
+            self.drop_scopes(self.scopes.len() - 1)?;
+        }
+        self.scopes.pop();
+        Ok(if block.trailing_semicolon { None } else { last_value })
+    }
+
+    fn lower_statement(&mut self, statement: &AnnotatedStatement) -> Result<Option<BasicValueEnum<'ctx>>, CodeGenError> {
+        match statement {
+            AnnotatedStatement::Let(let_stmt) => {
+                if let (Some(pattern), Some(init)) = (&let_stmt.pattern, &let_stmt.initializer) {
+                    self.lower_destructuring_let(pattern, init)?;
+                    return Ok(None);
+                }
+                let value_type = self.basic_type(&let_stmt.var_type)?;
+                let initial = match &let_stmt.initializer {
+                    Some(init) => Some(self.lower_coerced(init, &let_stmt.var_type)?),
+                    None => None,
+                };
+                if let Some(init) = &let_stmt.initializer {
+                    self.consume(init)?;
+                }
+                let slot = self.allocate_local(&let_stmt.name, value_type)?;
+                if let Some(value) = initial {
+                    self.builder.build_store(slot.pointer, value).map_err(builder_error)?;
+                }
+                self.own_local(&let_stmt.name, &let_stmt.var_type, initial.is_some())?;
+                Ok(None)
+            }
+            AnnotatedStatement::Return(ret) => {
+                self.lower_return(ret.value.as_ref())?;
+                Ok(None)
+            }
+            AnnotatedStatement::Expression(expr) => self.lower_expression(expr),
+            AnnotatedStatement::If(if_stmt) => {
+                let condition = self.lower_condition(&if_stmt.condition)?;
+                let then_block = self.append_block("if.then");
+                let else_block = self.append_block("if.else");
+                let merge_block = self.append_block("if.end");
+
+                self.builder
+                    .build_conditional_branch(condition, then_block, else_block)
+                    .map_err(builder_error)?;
+
+                self.builder.position_at_end(then_block);
+                self.lower_block(&if_stmt.then_block)?;
+                if !self.block_terminated() {
+                    self.builder.build_unconditional_branch(merge_block).map_err(builder_error)?;
+                }
+
+                self.builder.position_at_end(else_block);
+                if let Some(else_body) = &if_stmt.else_block {
+                    self.lower_block(else_body)?;
+                }
+                if !self.block_terminated() {
+                    self.builder.build_unconditional_branch(merge_block).map_err(builder_error)?;
+                }
+
+                self.builder.position_at_end(merge_block);
+                Ok(None)
+            }
+            AnnotatedStatement::Match(match_stmt) => {
+                self.lower_match(&match_stmt.expression, &match_stmt.arms, &match_stmt.result_type)
+            }
+            AnnotatedStatement::While(while_stmt) => {
+                self.lower_while(while_stmt)?;
+                Ok(None)
+            }
+            AnnotatedStatement::For(for_stmt) => {
+                self.lower_for(for_stmt)?;
+                Ok(None)
+            }
+            AnnotatedStatement::Assert(update) => {
+                self.lower_fact_update(update, "albayan_rt_fact_assert")?;
+                Ok(None)
+            }
+            AnnotatedStatement::Retract(update) => {
+                self.lower_fact_update(update, "albayan_rt_fact_retract")?;
+                Ok(None)
+            }
+            AnnotatedStatement::Break | AnnotatedStatement::Continue => {
+                let (continue_block, break_block, depth) = *self
+                    .loop_targets
+                    .last()
+                    .ok_or_else(|| CodeGenError::GenerationError("break/continue outside of a loop".to_string()))?;
+                let target = if matches!(statement, AnnotatedStatement::Break) {
+                    break_block
+                } else {
+                    continue_block
+                };
+                self.drop_scopes(depth)?;
+                self.builder.build_unconditional_branch(target).map_err(builder_error)?;
+                Ok(None)
+            }
+        }
+    }
+
+    // ===== Drops =====
+
+    /// Whether dropping a value of `ty` runs code: types with an `impl Drop`
+    /// and structs and tuples holding such values
+    fn needs_drop(&self, ty: &ResolvedType) -> bool {
+        match ty {
+            ResolvedType::Struct(name) => {
+                self.drop_impls.contains(name)
+                    || self
+                        .structs
+                        .get(name)
+                        .is_some_and(|layout| layout.field_types.iter().any(|field| self.needs_drop(field)))
+            }
+            ResolvedType::Tuple(elements) => elements.iter().any(|element| self.needs_drop(element)),
+            _ => false,
+        }
+    }
+
+    /// The function dropping a value of `ty` in place: the type's own `drop`
+    /// runs first, then its fields in declaration order
+    fn drop_glue(&mut self, ty: &ResolvedType) -> Result<FunctionValue<'ctx>, CodeGenError> {
+        let (key, symbol, parts) = match ty {
+            ResolvedType::Struct(name) => {
+                let layout = self
+                    .structs
+                    .get(name)
+                    .ok_or_else(|| unsupported(format!("dropping struct '{}'", name)))?;
+                (name.clone(), format!("drop.{}", name), layout.field_types.clone())
+            }
+            ResolvedType::Tuple(elements) => (format!("{:?}", ty), "drop.tuple".to_string(), elements.clone()),
+            other => return Err(CodeGenError::TypeError(format!("no drop glue for {:?}", other))),
+        };
+        if let Some(glue) = self.drop_glues.get(&key) {
+            return Ok(*glue);
+        }
+
+        let fn_type = self.context.void_type().fn_type(&[self.pointer_type().into()], false);
+        let glue = self.module.add_function(&symbol, fn_type, Some(Linkage::Private));
+        self.drop_glues.insert(key, glue);
+
+        // The glue is built on a builder of its own, in the middle of another function
+        let builder = self.context.create_builder();
+        builder.position_at_end(self.context.append_basic_block(glue, "entry"));
+        let pointer = glue
+            .get_nth_param(0)
+            .expect("drop glue takes the value's address")
+            .into_pointer_value();
+        if let ResolvedType::Struct(name) = ty {
+            let drop = self
+                .trait_impls
+                .get(&(DROP_TRAIT.to_string(), name.clone()))
+                .and_then(|methods| methods.get("drop"))
+                .copied();
+            if let Some(drop) = drop {
+                builder.build_call(drop, &[pointer.into()], "").map_err(builder_error)?;
+            }
+        }
+        let value_type = self.basic_type(ty)?.into_struct_type();
+        for (index, part) in parts.iter().enumerate() {
+            if !self.needs_drop(part) {
+                continue;
+            }
+            let part_glue = self.drop_glue(part)?;
+            let part_pointer = builder
+                .build_struct_gep(value_type, pointer, index as u32, "drop.field")
+                .map_err(builder_error)?;
+            builder.build_call(part_glue, &[part_pointer.into()], "").map_err(builder_error)?;
+        }
+        builder.build_return(None).map_err(builder_error)?;
+        Ok(glue)
+    }
+
+    /// Make the innermost scope drop the variable `name` when it ends
+    fn own_local(&mut self, name: &str, ty: &ResolvedType, initialized: bool) -> Result<(), CodeGenError> {
+        if !self.needs_drop(ty) {
+            return Ok(());
+        }
+        let glue = self.drop_glue(ty)?;
+        let bool_type = self.context.bool_type();
+        let flag = self.entry_alloca(bool_type.into(), &format!("{}.owned", name))?;
+        self.builder
+            .build_store(flag, bool_type.const_int(initialized as u64, false))
+            .map_err(builder_error)?;
+
+        let scope = self.scopes.last_mut().expect("no active scope");
+        let slot = scope.locals.get_mut(name).expect("owned variable without a slot");
+        slot.drop_flag = Some(flag);
+        scope.drops.push(DropSlot { pointer: slot.pointer, flag, glue });
+        Ok(())
+    }
+
+    /// A value moved out of a variable is dropped by its new owner; a variable
+    /// moved out of in part is not dropped at all
+    fn consume(&mut self, expr: &AnnotatedExpression) -> Result<(), CodeGenError> {
+        if !self.needs_drop(&expr.result_type) {
+            return Ok(());
+        }
+        let mut place = expr;
+        while let AnnotatedExpressionKind::FieldAccess { object, .. } | AnnotatedExpressionKind::Index { object, .. } =
+            &place.expr
+        {
+            if matches!(object.result_type, ResolvedType::Reference(_, _)) {
+                return Ok(());
+            }
+            place = object.as_ref();
+        }
+        let AnnotatedExpressionKind::Identifier(name) = &place.expr else {
+            return Ok(());
+        };
+        if let Some(flag) = self.lookup_local(name).and_then(|slot| slot.drop_flag) {
+            let moved = self.context.bool_type().const_zero();
+            self.builder.build_store(flag, moved).map_err(builder_error)?;
+        }
+        Ok(())
+    }
+
+    /// Whether an expression makes a new value rather than reading a place
+    fn is_temporary(expr: &AnnotatedExpression) -> bool {
+        matches!(
+            expr.expr,
+            AnnotatedExpressionKind::Call { .. }
+                | AnnotatedExpressionKind::StructLiteral { .. }
+                | AnnotatedExpressionKind::Tuple { .. }
+                | AnnotatedExpressionKind::If { .. }
+                | AnnotatedExpressionKind::Match { .. }
+                | AnnotatedExpressionKind::Query { .. }
+        )
+    }
+
+    /// Drop the values owned by the scopes from `depth` inward, innermost
+    /// scope and latest declaration first, without leaving the scopes
+    fn drop_scopes(&mut self, depth: usize) -> Result<(), CodeGenError> {
+        let drops: Vec<DropSlot<'ctx>> = self.scopes[depth..]
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.drops.iter().rev().copied())
+            .collect();
+        for slot in drops {
+            self.drop_slot(slot)?;
+        }
+        Ok(())
+    }
+
+    /// Drop a variable's value if it still owns one
+    fn drop_slot(&mut self, slot: DropSlot<'ctx>) -> Result<(), CodeGenError> {
+        let owned = self
+            .builder
+            .build_load(self.context.bool_type(), slot.flag, "owned")
+            .map_err(builder_error)?
+            .into_int_value();
+        let drop_block = self.append_block("drop");
+        let next_block = self.append_block("drop.next");
+        self.builder
+            .build_conditional_branch(owned, drop_block, next_block)
+            .map_err(builder_error)?;
+
+        self.builder.position_at_end(drop_block);
+        self.builder.build_call(slot.glue, &[slot.pointer.into()], "").map_err(builder_error)?;
+        self.builder.build_unconditional_branch(next_block).map_err(builder_error)?;
+        self.builder.position_at_end(next_block);
+        Ok(())
+    }
+
+    fn drop_in_place(&mut self, pointer: PointerValue<'ctx>, ty: &ResolvedType) -> Result<(), CodeGenError> {
+        if self.needs_drop(ty) {
+            let glue = self.drop_glue(ty)?;
+            self.builder.build_call(glue, &[pointer.into()], "").map_err(builder_error)?;
+        }
+        Ok(())
+    }
+
+    /// Drop a value nothing owns, such as the discarded result of a call
+    fn drop_value(&mut self, value: BasicValueEnum<'ctx>, ty: &ResolvedType) -> Result<(), CodeGenError> {
+        if !self.needs_drop(ty) {
+            return Ok(());
+        }
+        let pointer = self.entry_alloca(value.get_type(), "drop.tmp")?;
+        self.builder.build_store(pointer, value).map_err(builder_error)?;
+        self.drop_in_place(pointer, ty)
+    }
+
+    // ===== Loops =====
+
+    fn lower_while(&mut self, while_stmt: &AnnotatedWhileStatement) -> Result<(), CodeGenError> {
+        let cond_block = self.append_block("while.cond");
+        let body_block = self.append_block("while.body");
+        let end_block = self.append_block("while.end");
+
+        self.builder.build_unconditional_branch(cond_block).map_err(builder_error)?;
+        self.builder.position_at_end(cond_block);
+        let condition = self.lower_condition(&while_stmt.condition)?;
+        self.builder
+            .build_conditional_branch(condition, body_block, end_block)
+            .map_err(builder_error)?;
+
+        self.builder.position_at_end(body_block);
+        self.loop_targets.push((cond_block, end_block, self.scopes.len()));
+        let body = self.lower_block(&while_stmt.body);
+        self.loop_targets.pop();
+        body?;
+        if !self.block_terminated() {
+            self.builder.build_unconditional_branch(cond_block).map_err(builder_error)?;
+        }
+
+        self.builder.position_at_end(end_block);
+        Ok(())
+    }
+
+    /// `for x in list` walks the list with a hidden index counter
+    fn lower_for(&mut self, for_stmt: &AnnotatedForStatement) -> Result<(), CodeGenError> {
+        let list = self.lower_value(&for_stmt.iterable)?.into_struct_value();
+        let element_type = self.basic_type(&for_stmt.element_type)?;
+        let i64_type = self.context.i64_type();
+
+        let length = self
+            .builder
+            .build_extract_value(list, 0, "for.len")
+            .map_err(builder_error)?
+            .into_int_value();
+        let data = self
+            .builder
+            .build_extract_value(list, 1, "for.data")
+            .map_err(builder_error)?
+            .into_pointer_value();
+
+        // The loop variable and the counter live in their own scope around the body
+        self.scopes.push(Scope::default());
+        let index = self.allocate_local(&format!("{}.index", for_stmt.variable), i64_type.into())?;
+        let variable = self.allocate_local(&for_stmt.variable, element_type)?;
+        self.builder
+            .build_store(index.pointer, i64_type.const_zero())
+            .map_err(builder_error)?;
+
+        let cond_block = self.append_block("for.cond");
+        let body_block = self.append_block("for.body");
+        let step_block = self.append_block("for.step");
+        let end_block = self.append_block("for.end");
+
+        self.builder.build_unconditional_branch(cond_block).map_err(builder_error)?;
+        self.builder.position_at_end(cond_block);
+        let current = self
+            .builder
+            .build_load(i64_type, index.pointer, "for.i")
+            .map_err(builder_error)?
+            .into_int_value();
+        let in_bounds = self
+            .builder
+            .build_int_compare(IntPredicate::SLT, current, length, "for.check")
+            .map_err(builder_error)?;
+        self.builder
+            .build_conditional_branch(in_bounds, body_block, end_block)
+            .map_err(builder_error)?;
+
+        self.builder.position_at_end(body_block);
+        // SAFETY: `current` is checked against the list length above
+        let element_pointer = unsafe { self.builder.build_gep(element_type, data, &[current], "for.elem.ptr") }
+            .map_err(builder_error)?;
+        let element = self
+            .builder
+            .build_load(element_type, element_pointer, "for.elem")
+            .map_err(builder_error)?;
+        self.builder.build_store(variable.pointer, element).map_err(builder_error)?;
+
+        self.loop_targets.push((step_block, end_block, self.scopes.len()));
+        let body = self.lower_block(&for_stmt.body);
+        self.loop_targets.pop();
+        body?;
+        if !self.block_terminated() {
+            self.builder.build_unconditional_branch(step_block).map_err(builder_error)?;
+        }
+
+        self.builder.position_at_end(step_block);
+        let next = self
+            .builder
+            .build_int_add(current, i64_type.const_int(1, false), "for.next")
+            .map_err(builder_error)?;
+        self.builder.build_store(index.pointer, next).map_err(builder_error)?;
+        self.builder.build_unconditional_branch(cond_block).map_err(builder_error)?;
+
+        self.scopes.pop();
+        self.builder.position_at_end(end_block);
+        Ok(())
+    }
+
+    fn lower_return(&mut self, value: Option<&AnnotatedExpression>) -> Result<(), CodeGenError> {
+        let function = self.current_function.expect("return outside of a function");
+        let is_entry = function.get_name().to_str() == Ok(ENTRY_POINT);
+
+        match value {
+            Some(expr) if !matches!(expr.result_type, ResolvedType::Unit) => {
+                if let Some(result) = self.current_result {
+                    self.lower_into(result, expr)?;
+                    self.drop_scopes(0)?;
+                    self.builder.build_return(None).map_err(builder_error)?;
+                    return Ok(());
+                }
+                let value = self.lower_value(expr)?;
+                self.consume(expr)?;
+                self.drop_scopes(0)?;
+                if is_entry {
+                    let code = self.exit_code(value)?;
+                    self.builder.build_return(Some(&code)).map_err(builder_error)?;
+                } else {
+                    self.builder.build_return(Some(&value)).map_err(builder_error)?;
+                }
+            }
+            other => {
+                if let Some(expr) = other {
+                    self.lower_expression(expr)?;
+                }
+                self.drop_scopes(0)?;
+                if is_entry {
+                    let zero = self.context.i32_type().const_zero();
+                    self.builder.build_return(Some(&zero)).map_err(builder_error)?;
+                } else {
+                    self.builder.build_return(None).map_err(builder_error)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert the value returned from `main` into a C exit code
+    fn exit_code(&self, value: BasicValueEnum<'ctx>) -> Result<IntValue<'ctx>, CodeGenError> {
+        let i32_type = self.context.i32_type();
+        match value {
+            BasicValueEnum::IntValue(int) if int.get_type().get_bit_width() == 1 => {
+                self.builder.build_int_z_extend(int, i32_type, "exit").map_err(builder_error)
+            }
+            BasicValueEnum::IntValue(int) => {
+                self.builder.build_int_truncate_or_bit_cast(int, i32_type, "exit").map_err(builder_error)
+            }
+            BasicValueEnum::FloatValue(float) => {
+                self.builder.build_float_to_signed_int(float, i32_type, "exit").map_err(builder_error)
+            }
+            _ => Ok(i32_type.const_zero()),
+        }
+    }
+
+    /// Write a value to `pointer`, storing the elements of a tuple literal
+    /// one by one instead of assembling the tuple first
+    fn lower_into(&mut self, pointer: PointerValue<'ctx>, expr: &AnnotatedExpression) -> Result<(), CodeGenError> {
+        if let AnnotatedExpressionKind::Tuple { elements } = &expr.expr {
+            let tuple_type = self.basic_type(&expr.result_type)?.into_struct_type();
+            for (position, element) in elements.iter().enumerate() {
+                let field = self
+                    .builder
+                    .build_struct_gep(tuple_type, pointer, position as u32, "tuple.elem")
+                    .map_err(builder_error)?;
+                self.lower_into(field, element)?;
+            }
+            return Ok(());
+        }
+        let value = self.lower_value(expr)?;
+        self.consume(expr)?;
+        self.builder.build_store(pointer, value).map_err(builder_error)?;
+        Ok(())
+    }
+
+    /// Bind the names of `let (q, r) = value;`. The names of a call result
+    /// returned through `sret` refer to its fields in place.
+    fn lower_destructuring_let(
+        &mut self,
+        pattern: &AnnotatedPattern,
+        initializer: &AnnotatedExpression,
+    ) -> Result<(), CodeGenError> {
+        if let AnnotatedExpressionKind::Call { function, arguments } = &initializer.expr {
+            let callee = self.functions.get(function).cloned();
+            if let Some(callee) = callee.filter(|callee| Self::returns_indirectly(&callee.return_type)) {
+                let result = self.lower_indirect_call(&callee, arguments)?;
+                return self.bind_pattern_in_place(pattern, result, &initializer.result_type);
+            }
+        }
+        let value = self.lower_value(initializer)?;
+        self.consume(initializer)?;
+        self.bind_pattern_value(pattern, value, &initializer.result_type)
+    }
+
+    fn bind_pattern_in_place(
+        &mut self,
+        pattern: &AnnotatedPattern,
+        pointer: PointerValue<'ctx>,
+        value_type: &ResolvedType,
+    ) -> Result<(), CodeGenError> {
+        match (pattern, value_type) {
+            (AnnotatedPattern::Wildcard, _) => self.drop_in_place(pointer, value_type),
+            (AnnotatedPattern::Identifier(name, _), _) => {
+                let slot = LocalSlot { pointer, value_type: self.basic_type(value_type)?, drop_flag: None };
+                self.scopes
+                    .last_mut()
+                    .expect("no active scope")
+                    .locals
+                    .insert(name.clone(), slot);
+                self.own_local(name, value_type, true)
+            }
+            (AnnotatedPattern::Tuple(elements, _), ResolvedType::Tuple(element_types)) => {
+                let tuple_type = self.basic_type(value_type)?.into_struct_type();
+                for (position, (element, element_type)) in elements.iter().zip(element_types).enumerate() {
+                    let field = self
+                        .builder
+                        .build_struct_gep(tuple_type, pointer, position as u32, "tuple.elem")
+                        .map_err(builder_error)?;
+                    self.bind_pattern_in_place(element, field, element_type)?;
+                }
+                Ok(())
+            }
+            (other, _) => Err(unsupported(format!("let pattern {:?}", other))),
+        }
+    }
+
+    fn bind_pattern_value(
+        &mut self,
+        pattern: &AnnotatedPattern,
+        value: BasicValueEnum<'ctx>,
+        value_type: &ResolvedType,
+    ) -> Result<(), CodeGenError> {
+        match pattern {
+            AnnotatedPattern::Wildcard => self.drop_value(value, value_type),
+            AnnotatedPattern::Identifier(name, _) => {
+                let slot = self.allocate_local(name, value.get_type())?;
+                self.builder.build_store(slot.pointer, value).map_err(builder_error)?;
+                self.own_local(name, value_type, true)
+            }
+            AnnotatedPattern::Tuple(elements, _) => {
+                let ResolvedType::Tuple(element_types) = value_type else {
+                    return Err(CodeGenError::TypeError(format!("tuple pattern for a value of type {:?}", value_type)));
+                };
+                let tuple = value.into_struct_value();
+                for (position, (element, element_type)) in elements.iter().zip(element_types).enumerate() {
+                    let field = self
+                        .builder
+                        .build_extract_value(tuple, position as u32, "tuple.elem")
+                        .map_err(builder_error)?;
+                    self.bind_pattern_value(element, field, element_type)?;
+                }
+                Ok(())
+            }
+            other => Err(unsupported(format!("let pattern {:?}", other))),
+        }
+    }
+
+    // ===== Match =====
+
+    fn lower_match(
+        &mut self,
+        scrutinee: &AnnotatedExpression,
+        arms: &[AnnotatedMatchArm],
+        result_type: &ResolvedType,
+    ) -> Result<Option<BasicValueEnum<'ctx>>, CodeGenError> {
+        let value = self.lower_value(scrutinee)?;
+        let merge_block = self.append_block("match.end");
+        let produces_value = !matches!(result_type, ResolvedType::Unit);
+        let mut incoming: Vec<(BasicValueEnum<'ctx>, BasicBlock<'ctx>)> = Vec::new();
+
+        for (index, arm) in arms.iter().enumerate() {
+            let body_block = self.append_block(&format!("match.arm{}", index));
+            let next_block = self.append_block(&format!("match.next{}", index));
+
+            // Arm bindings are visible in the guard and the body
+            self.scopes.push(Scope::default());
+            let matches = self.lower_pattern_test(&arm.pattern, value, &scrutinee.result_type)?;
+            let matches = match &arm.guard {
+                Some(guard) => {
+                    let guard_block = self.append_block(&format!("match.guard{}", index));
+                    self.builder
+                        .build_conditional_branch(matches, guard_block, next_block)
+                        .map_err(builder_error)?;
+                    self.builder.position_at_end(guard_block);
+                    self.lower_condition(guard)?
+                }
+                None => matches,
+            };
+            self.builder
+                .build_conditional_branch(matches, body_block, next_block)
+                .map_err(builder_error)?;
+
+            self.builder.position_at_end(body_block);
+            let arm_value = self.lower_block(&arm.body)?;
+            if !self.block_terminated() {
+                if produces_value {
+                    let arm_value = arm_value.ok_or_else(|| {
+                        CodeGenError::GenerationError("match arm does not produce a value".to_string())
+                    })?;
+                    let current = self.builder.get_insert_block().expect("no insert block");
+                    incoming.push((arm_value, current));
+                }
+                self.builder.build_unconditional_branch(merge_block).map_err(builder_error)?;
+            }
+            self.scopes.pop();
+
+            self.builder.position_at_end(next_block);
+        }
+
+        // No arm matched: exhaustiveness is checked by semantic analysis
+        self.builder.build_unreachable().map_err(builder_error)?;
+        self.builder.position_at_end(merge_block);
+
+        if !produces_value {
+            return Ok(None);
+        }
+        if incoming.is_empty() {
+            self.builder.build_unreachable().map_err(builder_error)?;
+            return Ok(None);
+        }
+
+        let phi = self
+            .builder
+            .build_phi(self.basic_type(result_type)?, "match.value")
+            .map_err(builder_error)?;
+        for (value, block) in &incoming {
+            phi.add_incoming(&[(value, *block)]);
+        }
+        Ok(Some(phi.as_basic_value()))
+    }
+
+    /// Lower an if expression, merging the values of the branches with a phi node
+    fn lower_if_expression(
+        &mut self,
+        condition: &AnnotatedExpression,
+        then_body: &AnnotatedBlock,
+        else_body: Option<&AnnotatedBlock>,
+        result_type: &ResolvedType,
+    ) -> Result<Option<BasicValueEnum<'ctx>>, CodeGenError> {
+        let condition = self.lower_condition(condition)?;
+        let then_block = self.append_block("if.then");
+        let else_block = self.append_block("if.else");
+        let merge_block = self.append_block("if.end");
+        self.builder
+            .build_conditional_branch(condition, then_block, else_block)
+            .map_err(builder_error)?;
+
+        let produces_value = !matches!(result_type, ResolvedType::Unit);
+        let mut incoming: Vec<(BasicValueEnum<'ctx>, BasicBlock<'ctx>)> = Vec::new();
+        for (block, body) in [(then_block, Some(then_body)), (else_block, else_body)] {
+            self.builder.position_at_end(block);
+            let value = match body {
+                Some(body) => self.lower_block(body)?,
+                None => None,
+            };
+            if self.block_terminated() {
+                continue;
+            }
+            if produces_value {
+                let value = value.ok_or_else(|| {
+                    CodeGenError::GenerationError("if branch does not produce a value".to_string())
+                })?;
+                let value = self.promote_value(value, result_type)?;
+                let current = self.builder.get_insert_block().expect("no insert block");
+                incoming.push((value, current));
+            }
+            self.builder.build_unconditional_branch(merge_block).map_err(builder_error)?;
+        }
+
+        self.builder.position_at_end(merge_block);
+        if !produces_value {
+            return Ok(None);
+        }
+        if incoming.is_empty() {
+            self.builder.build_unreachable().map_err(builder_error)?;
+            return Ok(None);
+        }
+
+        let phi = self
+            .builder
+            .build_phi(self.basic_type(result_type)?, "if.value")
+            .map_err(builder_error)?;
+        for (value, block) in &incoming {
+            phi.add_incoming(&[(value, *block)]);
+        }
+        Ok(Some(phi.as_basic_value()))
+    }
+
+    /// Convert an `int` value merged into a `float` result
+    fn promote_value(
+        &mut self,
+        value: BasicValueEnum<'ctx>,
+        target: &ResolvedType,
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        match (target, value) {
+            (ResolvedType::Float, BasicValueEnum::IntValue(int)) => Ok(self
+                .builder
+                .build_signed_int_to_float(int, self.context.f64_type(), "promote")
+                .map_err(builder_error)?
+                .into()),
+            _ => Ok(value),
+        }
+    }
+
+    /// Emit a boolean test for a pattern, binding identifiers in the current scope
+    fn lower_pattern_test(
+        &mut self,
+        pattern: &AnnotatedPattern,
+        value: BasicValueEnum<'ctx>,
+        value_type: &ResolvedType,
+    ) -> Result<IntValue<'ctx>, CodeGenError> {
+        let always = self.context.bool_type().const_int(1, false);
+        match pattern {
+            AnnotatedPattern::Wildcard => Ok(always),
+            AnnotatedPattern::Identifier(name, _) => {
+                let slot = self.allocate_local(name, value.get_type())?;
+                self.builder.build_store(slot.pointer, value).map_err(builder_error)?;
+                Ok(always)
+            }
+            AnnotatedPattern::Literal(literal, _) => {
+                let expected = self.lower_literal(literal)?;
+                self.compare(BinaryOperator::Equal, value, expected, value_type)
+            }
+            other => Err(unsupported(format!("match pattern {:?}", other))),
+        }
+    }
+
+    // ===== Expressions =====
+
+    /// Lower an expression that must produce a value
+    fn lower_value(&mut self, expr: &AnnotatedExpression) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        self.lower_expression(expr)?.ok_or_else(|| {
+            CodeGenError::TypeError(format!("expression of type {:?} has no value", expr.result_type))
+        })
+    }
+
+    fn lower_condition(&mut self, expr: &AnnotatedExpression) -> Result<IntValue<'ctx>, CodeGenError> {
+        match self.lower_value(expr)? {
+            BasicValueEnum::IntValue(int) if int.get_type().get_bit_width() == 1 => Ok(int),
+            _ => Err(CodeGenError::TypeError(format!(
+                "condition must be bool, found {:?}",
+                expr.result_type
+            ))),
+        }
+    }
+
+    fn lower_expression(&mut self, expr: &AnnotatedExpression) -> Result<Option<BasicValueEnum<'ctx>>, CodeGenError> {
+        match &expr.expr {
+            AnnotatedExpressionKind::Literal(literal) => self.lower_literal(literal).map(Some),
+            AnnotatedExpressionKind::Identifier(name) => {
+                let slot = self
+                    .lookup_local(name)
+                    .ok_or_else(|| CodeGenError::GenerationError(format!("unknown variable '{}'", name)))?;
+                let value = self
+                    .builder
+                    .build_load(slot.value_type, slot.pointer, name)
+                    .map_err(builder_error)?;
+                Ok(Some(value))
+            }
+            AnnotatedExpressionKind::Binary { left, operator, right } => {
+                self.lower_binary(left, operator, right).map(Some)
+            }
+            AnnotatedExpressionKind::Unary(unary) => self.lower_unary(unary).map(Some),
+            AnnotatedExpressionKind::Call { function, arguments } => {
+                self.lower_call(function, arguments, &expr.result_type)
+            }
+            AnnotatedExpressionKind::Match { expression, arms } => {
+                self.lower_match(expression, arms, &expr.result_type)
+            }
+            AnnotatedExpressionKind::If { condition, then_block, else_block } => {
+                self.lower_if_expression(condition, then_block, else_block.as_ref(), &expr.result_type)
+            }
+            AnnotatedExpressionKind::StructLiteral { name, fields } => self.lower_struct_literal(name, fields).map(Some),
+            AnnotatedExpressionKind::EnumLiteral { enum_name, variant_name, .. } => {
+                Err(unsupported(format!("enum literal '{}::{}'", enum_name, variant_name)))
+            }
+            AnnotatedExpressionKind::FieldAccess { object, field } => match (&object.result_type, field.parse::<u32>()) {
+                (ResolvedType::Tuple(_), Ok(index)) => self.lower_tuple_element(object, index).map(Some),
+                (ResolvedType::Struct(name), _) => {
+                    let (_, index) = self.field_index(name, field)?;
+                    let value = self.lower_value(object)?.into_struct_value();
+                    let field_value = self.builder.build_extract_value(value, index, field).map_err(builder_error)?;
+                    Ok(Some(field_value))
+                }
+                (ResolvedType::Reference(inner, _), _) => {
+                    let ResolvedType::Struct(name) = inner.as_ref() else {
+                        return Err(unsupported(format!("field access '.{}' through {:?}", field, object.result_type)));
+                    };
+                    let (struct_type, index) = self.field_index(name, field)?;
+                    let pointer = self.lower_value(object)?.into_pointer_value();
+                    let field_pointer = self
+                        .builder
+                        .build_struct_gep(struct_type, pointer, index, field)
+                        .map_err(builder_error)?;
+                    let field_type = struct_type
+                        .get_field_type_at_index(index)
+                        .expect("field index checked against the layout");
+                    let field_value = self.builder.build_load(field_type, field_pointer, field).map_err(builder_error)?;
+                    Ok(Some(field_value))
+                }
+                _ => Err(unsupported(format!("field access '.{}'", field))),
+            },
+            AnnotatedExpressionKind::Array { elements } => self.lower_list_literal(elements, &expr.result_type).map(Some),
+            AnnotatedExpressionKind::ArrayRepeat { value, count } => self.lower_array_repeat(value, *count).map(Some),
+            AnnotatedExpressionKind::MapLiteral { entries } => {
+                let map = self.build_map_new(&expr.result_type)?;
+                for (key, value) in entries {
+                    self.build_map_insert(map, key, Some(value))?;
+                }
+                Ok(Some(map.into()))
+            }
+            AnnotatedExpressionKind::SetLiteral { elements } => {
+                let set = self.build_map_new(&expr.result_type)?;
+                for element in elements {
+                    self.build_map_insert(set, element, None)?;
+                }
+                Ok(Some(set.into()))
+            }
+            AnnotatedExpressionKind::Tuple { elements } => self.lower_tuple_literal(elements, &expr.result_type).map(Some),
+            AnnotatedExpressionKind::Query { query, .. } => self.lower_query(query).map(Some),
+            AnnotatedExpressionKind::Index { object, index } => match (&object.result_type, &index.expr) {
+                (ResolvedType::Tuple(_), AnnotatedExpressionKind::Literal(Literal::Integer(index))) => {
+                    self.lower_tuple_element(object, *index as u32).map(Some)
+                }
+                (ResolvedType::List(_) | ResolvedType::Vector(_, _), _) => {
+                    let (pointer, element_type) = self.lower_element_place(object, index)?;
+                    let value = self.builder.build_load(element_type, pointer, "elem").map_err(builder_error)?;
+                    Ok(Some(value))
+                }
+                _ => Err(unsupported("index expression".to_string())),
+            },
+        }
+    }
+
+    fn lower_unary(&mut self, unary: &AnnotatedUnaryExpression) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        match unary.operator {
+            UnaryOperator::Reference | UnaryOperator::MutableReference => {
+                return self.lower_address(&unary.operand).map(Into::into);
+            }
+            UnaryOperator::Dereference => {
+                let ResolvedType::Reference(inner, _) = &unary.operand.result_type else {
+                    return Err(CodeGenError::TypeError(format!(
+                        "cannot dereference a value of type {:?}",
+                        unary.operand.result_type
+                    )));
+                };
+                let pointer = self.lower_value(&unary.operand)?.into_pointer_value();
+                let value_type = self.basic_type(inner)?;
+                return self.builder.build_load(value_type, pointer, "deref").map_err(builder_error);
+            }
+            UnaryOperator::Not | UnaryOperator::Negate => {}
+        }
+
+        let operand = self.lower_value(&unary.operand)?;
+        let value = match (&unary.operator, operand) {
+            (UnaryOperator::Not, BasicValueEnum::IntValue(int)) => {
+                self.builder.build_not(int, "not").map_err(builder_error)?.into()
+            }
+            (UnaryOperator::Negate, BasicValueEnum::IntValue(int)) => {
+                self.builder.build_int_neg(int, "neg").map_err(builder_error)?.into()
+            }
+            (UnaryOperator::Negate, BasicValueEnum::FloatValue(float)) => {
+                self.builder.build_float_neg(float, "neg").map_err(builder_error)?.into()
+            }
+            (operator, _) => return Err(unsupported(format!("unary operator {:?}", operator))),
+        };
+        Ok(value)
+    }
+
+    /// Address of a value: a variable's own slot, or a temporary holding any other value
+    fn lower_address(&mut self, expr: &AnnotatedExpression) -> Result<PointerValue<'ctx>, CodeGenError> {
+        if let AnnotatedExpressionKind::Identifier(name) = &expr.expr {
+            if let Some(slot) = self.lookup_local(name) {
+                return Ok(slot.pointer);
+            }
+        }
+        let value = self.lower_value(expr)?;
+        let slot = self.allocate_local("ref.tmp", value.get_type())?;
+        self.builder.build_store(slot.pointer, value).map_err(builder_error)?;
+        Ok(slot.pointer)
+    }
+
+    /// Structs are first-class values; fields are stored in declaration order
+    fn lower_struct_literal(
+        &mut self,
+        name: &str,
+        fields: &[(String, AnnotatedExpression)],
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let mut value = self.basic_type(&ResolvedType::Struct(name.to_string()))?.into_struct_type().get_undef();
+        for (field, expr) in fields {
+            let (_, index) = self.field_index(name, field)?;
+            let field_value = self.lower_value(expr)?;
+            self.consume(expr)?;
+            value = self
+                .builder
+                .build_insert_value(value, field_value, index, name)
+                .map_err(builder_error)?
+                .into_struct_value();
+        }
+        Ok(value.into())
+    }
+
+    /// Tuples are first-class struct values, built field by field
+    fn lower_tuple_literal(
+        &mut self,
+        elements: &[AnnotatedExpression],
+        tuple_type: &ResolvedType,
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let mut tuple = self.basic_type(tuple_type)?.into_struct_type().get_undef();
+        for (position, element) in elements.iter().enumerate() {
+            let value = self.lower_value(element)?;
+            self.consume(element)?;
+            tuple = self
+                .builder
+                .build_insert_value(tuple, value, position as u32, "tuple")
+                .map_err(builder_error)?
+                .into_struct_value();
+        }
+        Ok(tuple.into())
+    }
+
+    fn lower_tuple_element(
+        &mut self,
+        tuple: &AnnotatedExpression,
+        index: u32,
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let tuple = self.lower_value(tuple)?.into_struct_value();
+        self.builder
+            .build_extract_value(tuple, index, "tuple.elem")
+            .map_err(builder_error)
+    }
+
+    /// Copy the elements of a list literal into a fresh heap buffer
+    fn lower_list_literal(
+        &mut self,
+        elements: &[AnnotatedExpression],
+        list_type: &ResolvedType,
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let (ResolvedType::List(element) | ResolvedType::Vector(element, _)) = list_type else {
+            return Err(CodeGenError::TypeError(format!("list literal of type {:?}", list_type)));
+        };
+        let element_type = self.basic_type(element)?;
+        let i64_type = self.context.i64_type();
+        let (list, data) = self.allocate_list(element, i64_type.const_int(elements.len() as u64, false))?;
+
+        for (position, element) in elements.iter().enumerate() {
+            let value = self.lower_value(element)?;
+            self.consume(element)?;
+            let offset = i64_type.const_int(position as u64, false);
+            // SAFETY: the buffer holds exactly `elements.len()` elements
+            let slot = unsafe { self.builder.build_gep(element_type, data, &[offset], "list.slot") }
+                .map_err(builder_error)?;
+            self.builder.build_store(slot, value).map_err(builder_error)?;
+        }
+        Ok(list.into())
+    }
+
+    /// `query goal(...)`: `albayan_rt_query_solve` solves the goals and
+    /// counts the solutions, which `albayan_rt_query_solutions_into` then
+    /// moves into a list of that length
+    fn lower_query(&mut self, query: &str) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let pointer = self.pointer_type();
+        let i64_type = self.context.i64_type();
+        let text = self.global_string(query, "query")?;
+        let length_slot = self.entry_alloca(i64_type.into(), "query.len")?;
+        let solve = self.runtime_function("albayan_rt_query_solve", pointer.fn_type(&[pointer.into(), pointer.into()], false));
+        let solutions = self
+            .builder
+            .build_call(solve, &[text.into(), length_slot.into()], "query.solutions")
+            .map_err(builder_error)?
+            .try_as_basic_value()
+            .left()
+            .expect("albayan_rt_query_solve returns a pointer");
+        let length = self
+            .builder
+            .build_load(i64_type, length_slot, "query.len")
+            .map_err(builder_error)?
+            .into_int_value();
+
+        let solution = ResolvedType::Map(Box::new(ResolvedType::String), Box::new(ResolvedType::String));
+        let (list, data) = self.allocate_list(&solution, length)?;
+        let solutions_into = self.runtime_function(
+            "albayan_rt_query_solutions_into",
+            self.context.void_type().fn_type(&[pointer.into(), pointer.into()], false),
+        );
+        self.builder
+            .build_call(solutions_into, &[solutions.into(), data.into()], "")
+            .map_err(builder_error)?;
+        Ok(list.into())
+    }
+
+    /// Build the fact of an `assert` or `retract` statement one argument at a
+    /// time with the helper matching its type, then hand it to `finish`
+    fn lower_fact_update(&mut self, update: &AnnotatedFactUpdate, finish: &str) -> Result<(), CodeGenError> {
+        let builder_type = self.string_type();
+        let void_type = self.context.void_type();
+        let begin = self.runtime_function("albayan_rt_fact_begin", builder_type.fn_type(&[builder_type.into()], false));
+        let relation = self.global_string(&update.relation, "fact.relation")?;
+        let fact = self
+            .builder
+            .build_call(begin, &[relation.into()], "fact.builder")
+            .map_err(builder_error)?
+            .try_as_basic_value()
+            .left()
+            .expect("albayan_rt_fact_begin returns a pointer");
+
+        for arg in &update.args {
+            let (name, value, value_type): (&str, BasicMetadataValueEnum<'ctx>, BasicMetadataTypeEnum<'ctx>) = match arg {
+                AnnotatedFactArg::Atom(name) => {
+                    ("albayan_rt_fact_atom", self.global_string(name, "fact.atom")?.into(), builder_type.into())
+                }
+                AnnotatedFactArg::Individual(value) => {
+                    ("albayan_rt_fact_atom", self.lower_value(value)?.into(), builder_type.into())
+                }
+                AnnotatedFactArg::Value(value) => {
+                    let lowered = self.lower_value(value)?;
+                    match &value.result_type {
+                        ResolvedType::Int => ("albayan_rt_fact_int", lowered.into(), self.context.i64_type().into()),
+                        ResolvedType::Float => ("albayan_rt_fact_float", lowered.into(), self.context.f64_type().into()),
+                        ResolvedType::Bool => {
+                            let byte = self
+                                .builder
+                                .build_int_z_extend(lowered.into_int_value(), self.context.i8_type(), "bool.byte")
+                                .map_err(builder_error)?;
+                            ("albayan_rt_fact_bool", byte.into(), self.context.i8_type().into())
+                        }
+                        ResolvedType::String => ("albayan_rt_fact_string", lowered.into(), builder_type.into()),
+                        other => return Err(unsupported(format!("a fact argument of type {:?}", other))),
+                    }
+                }
+            };
+            let add = self.runtime_function(name, void_type.fn_type(&[builder_type.into(), value_type], false));
+            self.builder.build_call(add, &[fact.into(), value], "").map_err(builder_error)?;
+        }
+
+        let i32_type = self.context.i32_type();
+        let action = finish.trim_start_matches("albayan_rt_fact_");
+        let finish = self.runtime_function(finish, i32_type.fn_type(&[builder_type.into()], false));
+        let status = self
+            .builder
+            .build_call(finish, &[fact.into()], "fact.status")
+            .map_err(builder_error)?
+            .try_as_basic_value()
+            .left()
+            .expect("a fact update returns its status")
+            .into_int_value();
+
+        // The runtime has printed why the update failed
+        let rejected = self
+            .builder
+            .build_int_compare(IntPredicate::NE, status, i32_type.const_zero(), "fact.rejected")
+            .map_err(builder_error)?;
+        let failed = self.append_block("fact.failed");
+        let applied = self.append_block("fact.applied");
+        self.builder.build_conditional_branch(rejected, failed, applied).map_err(builder_error)?;
+        self.builder.position_at_end(failed);
+        let message = self.global_string(&format!("cannot {} a `{}` fact", action, update.relation), "fact.message")?;
+        let location = self.global_string(&update.location, "panic.location")?;
+        self.lower_panic(message, location)?;
+        self.builder.position_at_end(applied);
+        Ok(())
+    }
+
+    /// `[value; count]`: the value is evaluated once and stored in every slot
+    fn lower_array_repeat(
+        &mut self,
+        value: &AnnotatedExpression,
+        count: usize,
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        if self.needs_drop(&value.result_type) {
+            return Err(unsupported(format!("repeating a value of type {:?} that is dropped", value.result_type)));
+        }
+        let element_type = self.basic_type(&value.result_type)?;
+        let i64_type = self.context.i64_type();
+        let length = i64_type.const_int(count as u64, false);
+        let (list, data) = self.allocate_list(&value.result_type, length)?;
+        let element = self.lower_value(value)?;
+        self.consume(value)?;
+
+        self.build_counted_loop(length, "repeat", |this, offset| {
+            // SAFETY: `offset` is below the `count` elements the buffer holds
+            let slot = unsafe { this.builder.build_gep(element_type, data, &[offset], "repeat.slot") }
+                .map_err(builder_error)?;
+            this.builder.build_store(slot, element).map_err(builder_error)?;
+            Ok(())
+        })?;
+        Ok(list.into())
+    }
+
+    /// Build `for index in 0..length { body(index) }`; `body` may add blocks
+    /// of its own and ends wherever it leaves the builder
+    fn build_counted_loop(
+        &mut self,
+        length: IntValue<'ctx>,
+        name: &str,
+        mut body: impl FnMut(&mut Self, IntValue<'ctx>) -> Result<(), CodeGenError>,
+    ) -> Result<(), CodeGenError> {
+        let i64_type = self.context.i64_type();
+        let entry = self.builder.get_insert_block().expect("builder is positioned");
+        let cond_block = self.append_block(&format!("{}.cond", name));
+        let body_block = self.append_block(&format!("{}.body", name));
+        let end_block = self.append_block(&format!("{}.end", name));
+        self.builder.build_unconditional_branch(cond_block).map_err(builder_error)?;
+
+        self.builder.position_at_end(cond_block);
+        let position = self
+            .builder
+            .build_phi(i64_type, &format!("{}.index", name))
+            .map_err(builder_error)?;
+        position.add_incoming(&[(&i64_type.const_zero(), entry)]);
+        let index = position.as_basic_value().into_int_value();
+        let more = self
+            .builder
+            .build_int_compare(IntPredicate::ULT, index, length, &format!("{}.more", name))
+            .map_err(builder_error)?;
+        self.builder
+            .build_conditional_branch(more, body_block, end_block)
+            .map_err(builder_error)?;
+
+        self.builder.position_at_end(body_block);
+        body(self, index)?;
+        let latch = self.builder.get_insert_block().expect("builder is positioned");
+        let next = self
+            .builder
+            .build_int_add(index, i64_type.const_int(1, false), &format!("{}.next", name))
+            .map_err(builder_error)?;
+        position.add_incoming(&[(&next, latch)]);
+        self.builder.build_unconditional_branch(cond_block).map_err(builder_error)?;
+
+        self.builder.position_at_end(end_block);
+        Ok(())
+    }
+
+    /// Allocate a list of `length` elements; returns the list and its data pointer
+    fn allocate_list(
+        &mut self,
+        element: &ResolvedType,
+        length: IntValue<'ctx>,
+    ) -> Result<(StructValue<'ctx>, PointerValue<'ctx>), CodeGenError> {
+        let element_type = self.basic_type(element)?;
+        let list_type = self.list_type(element)?;
+        let i64_type = self.context.i64_type();
+
+        let malloc = self.module.get_function("malloc").unwrap_or_else(|| {
+            let fn_type = self.string_type().fn_type(&[i64_type.into()], false);
+            self.module.add_function("malloc", fn_type, Some(Linkage::External))
+        });
+        let element_size = element_type
+            .size_of()
+            .ok_or_else(|| unsupported(format!("list of unsized {:?}", element)))?;
+        let size = self
+            .builder
+            .build_int_mul(element_size, length, "list.size")
+            .map_err(builder_error)?;
+        let raw = self
+            .builder
+            .build_call(malloc, &[size.into()], "list.raw")
+            .map_err(builder_error)?
+            .try_as_basic_value()
+            .left()
+            .expect("malloc returns a pointer")
+            .into_pointer_value();
+        let data = self
+            .builder
+            .build_pointer_cast(raw, element_type.ptr_type(AddressSpace::default()), "list.data")
+            .map_err(builder_error)?;
+
+        let list = self
+            .builder
+            .build_insert_value(list_type.get_undef(), length, 0, "list.len")
+            .map_err(builder_error)?;
+        let list = self
+            .builder
+            .build_insert_value(list, data, 1, "list")
+            .map_err(builder_error)?;
+        Ok((list.into_struct_value(), data))
+    }
+
+    /// Address of element `index` of a list, checked against its length at
+    /// run time unless `in_bounds`; an index past the end panics
+    fn element_pointer(
+        &mut self,
+        list: StructValue<'ctx>,
+        element: &ResolvedType,
+        index: IntValue<'ctx>,
+        in_bounds: bool,
+    ) -> Result<(PointerValue<'ctx>, BasicTypeEnum<'ctx>), CodeGenError> {
+        let element_type = self.basic_type(element)?;
+        let data = self
+            .builder
+            .build_extract_value(list, 1, "list.data")
+            .map_err(builder_error)?
+            .into_pointer_value();
+        if !in_bounds {
+            let length = self
+                .builder
+                .build_extract_value(list, 0, "list.len")
+                .map_err(builder_error)?
+                .into_int_value();
+            // A negative index is a huge unsigned one, so one compare covers both ends
+            let valid = self
+                .builder
+                .build_int_compare(IntPredicate::ULT, index, length, "index.valid")
+                .map_err(builder_error)?;
+            let panic_block = self.append_block("index.panic");
+            let ok_block = self.append_block("index.ok");
+            self.builder
+                .build_conditional_branch(valid, ok_block, panic_block)
+                .map_err(builder_error)?;
+
+            self.builder.position_at_end(panic_block);
+            let i64_type = self.context.i64_type();
+            let fn_type = self
+                .context
+                .void_type()
+                .fn_type(&[i64_type.into(), i64_type.into()], false);
+            let bounds_panic = self.runtime_function("albayan_rt_bounds_panic", fn_type);
+            self.builder
+                .build_call(bounds_panic, &[index.into(), length.into()], "")
+                .map_err(builder_error)?;
+            self.builder.build_unreachable().map_err(builder_error)?;
+
+            self.builder.position_at_end(ok_block);
+        }
+        // SAFETY: the index was checked against the list's length
+        let pointer = unsafe { self.builder.build_gep(element_type, data, &[index], "list.elem") }
+            .map_err(builder_error)?;
+        Ok((pointer, element_type))
+    }
+
+    /// A built-in list method; `arguments` starts with the list. `push` grows
+    /// the buffer with `realloc`, and `pop` on an empty list panics.
+    fn lower_list_method(
+        &mut self,
+        method: &str,
+        arguments: &[AnnotatedExpression],
+    ) -> Result<Option<BasicValueEnum<'ctx>>, CodeGenError> {
+        let (receiver, arguments) = arguments
+            .split_first()
+            .ok_or_else(|| CodeGenError::GenerationError(format!("list::{} without a list", method)))?;
+        let (element, behind_reference) = match &receiver.result_type {
+            ResolvedType::List(element) | ResolvedType::Vector(element, _) => (element.as_ref().clone(), false),
+            ResolvedType::Reference(inner, _) => match inner.as_ref() {
+                ResolvedType::List(element) | ResolvedType::Vector(element, _) => (element.as_ref().clone(), true),
+                other => return Err(CodeGenError::TypeError(format!("list method on {:?}", other))),
+            },
+            other => return Err(CodeGenError::TypeError(format!("list method on {:?}", other))),
+        };
+        let element_type = self.basic_type(&element)?;
+        let list_type = self.list_type(&element)?;
+        let i64_type = self.context.i64_type();
+
+        // Like a call, the argument is evaluated before the list is read
+        let argument = match (method, arguments) {
+            ("push" | "contains", [value]) => Some(self.lower_value(value)?),
+            _ => None,
+        };
+        // `push` and `pop` write the list back to its place
+        let list_pointer = if behind_reference {
+            Some(self.lower_value(receiver)?.into_pointer_value())
+        } else if matches!(method, "push" | "pop") {
+            Some(self.lower_place(receiver)?.0)
+        } else {
+            None
+        };
+        let list = match list_pointer {
+            Some(pointer) => self.builder.build_load(list_type, pointer, "list").map_err(builder_error)?,
+            None => self.lower_value(receiver)?,
+        }
+        .into_struct_value();
+        let length = self
+            .builder
+            .build_extract_value(list, 0, "list.len")
+            .map_err(builder_error)?
+            .into_int_value();
+        let data = self
+            .builder
+            .build_extract_value(list, 1, "list.data")
+            .map_err(builder_error)?
+            .into_pointer_value();
+        let element_at = |this: &mut Self, index: IntValue<'ctx>| {
+            // SAFETY: callers pass an index below the list's length
+            unsafe { this.builder.build_gep(element_type, data, &[index], "list.elem") }.map_err(builder_error)
+        };
+
+        match (method, arguments) {
+            ("len", []) => Ok(Some(length.into())),
+            ("push", [value]) => {
+                let pointer = list_pointer.expect("push writes the list back");
+                let new_value = argument.expect("push has its value lowered");
+                self.consume(value)?;
+                let new_length = self
+                    .builder
+                    .build_int_add(length, i64_type.const_int(1, false), "list.grown")
+                    .map_err(builder_error)?;
+                let element_size = element_type
+                    .size_of()
+                    .ok_or_else(|| unsupported(format!("list of unsized {:?}", element)))?;
+                let size = self
+                    .builder
+                    .build_int_mul(element_size, new_length, "list.size")
+                    .map_err(builder_error)?;
+                let realloc = self.runtime_function(
+                    "realloc",
+                    self.pointer_type().fn_type(&[self.pointer_type().into(), i64_type.into()], false),
+                );
+                let grown = self
+                    .builder
+                    .build_call(realloc, &[data.into(), size.into()], "list.realloc")
+                    .map_err(builder_error)?
+                    .try_as_basic_value()
+                    .left()
+                    .expect("realloc returns a pointer")
+                    .into_pointer_value();
+                // SAFETY: the buffer now holds `length + 1` elements
+                let slot = unsafe { self.builder.build_gep(element_type, grown, &[length], "list.slot") }
+                    .map_err(builder_error)?;
+                self.builder.build_store(slot, new_value).map_err(builder_error)?;
+                self.store_list(pointer, list, new_length, grown)?;
+                Ok(None)
+            }
+            ("pop", []) => {
+                let pointer = list_pointer.expect("pop writes the list back");
+                let empty = self
+                    .builder
+                    .build_int_compare(IntPredicate::EQ, length, i64_type.const_zero(), "list.empty")
+                    .map_err(builder_error)?;
+                let panic_block = self.append_block("pop.empty");
+                let ok_block = self.append_block("pop.ok");
+                self.builder
+                    .build_conditional_branch(empty, panic_block, ok_block)
+                    .map_err(builder_error)?;
+
+                self.builder.position_at_end(panic_block);
+                self.build_panic("pop from an empty list")?;
+
+                self.builder.position_at_end(ok_block);
+                let last = self
+                    .builder
+                    .build_int_sub(length, i64_type.const_int(1, false), "list.last")
+                    .map_err(builder_error)?;
+                let slot = element_at(self, last)?;
+                let value = self.builder.build_load(element_type, slot, "popped").map_err(builder_error)?;
+                self.store_list(pointer, list, last, data)?;
+                Ok(Some(value))
+            }
+            ("contains", [_]) => {
+                let expected = argument.expect("contains has its value lowered");
+                let bool_type = self.context.bool_type();
+                let found = self.entry_alloca(bool_type.into(), "contains.found")?;
+                self.builder.build_store(found, bool_type.const_zero()).map_err(builder_error)?;
+                self.build_counted_loop(length, "contains", |this, index| {
+                    let slot = element_at(this, index)?;
+                    let current = this.builder.build_load(element_type, slot, "contains.elem").map_err(builder_error)?;
+                    let equal = this.compare(BinaryOperator::Equal, current, expected, &element)?;
+                    let so_far = this
+                        .builder
+                        .build_load(bool_type, found, "contains.so_far")
+                        .map_err(builder_error)?
+                        .into_int_value();
+                    let found_now = this.builder.build_or(so_far, equal, "contains.any").map_err(builder_error)?;
+                    this.builder.build_store(found, found_now).map_err(builder_error)?;
+                    Ok(())
+                })?;
+                Ok(Some(self.builder.build_load(bool_type, found, "contains").map_err(builder_error)?))
+            }
+            ("map" | "filter", [callback]) => {
+                if self.needs_drop(&element) {
+                    return Err(unsupported(format!("list::{} over elements of type {:?} that are dropped", method, element)));
+                }
+                let AnnotatedExpressionKind::Identifier(name) = &callback.expr else {
+                    return Err(unsupported(format!("list::{} without a function name", method)));
+                };
+                let callee = self
+                    .functions
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| unsupported(format!("list::{} callback '{}'", method, name)))?;
+                if Self::returns_indirectly(&callee.return_type) {
+                    return Err(unsupported(format!("list::{} callback '{}' returning {:?}", method, name, callee.return_type)));
+                }
+                let call_callback = |this: &mut Self, index: IntValue<'ctx>| {
+                    let slot = element_at(this, index)?;
+                    let current = this.builder.build_load(element_type, slot, "list.elem").map_err(builder_error)?;
+                    let result = this
+                        .builder
+                        .build_call(callee.value, &[current.into()], "callback")
+                        .map_err(builder_error)?
+                        .try_as_basic_value()
+                        .left()
+                        .ok_or_else(|| unsupported(format!("list::{} callback '{}' without a result", method, name)))?;
+                    Ok::<_, CodeGenError>((current, result))
+                };
+
+                if method == "map" {
+                    let (mapped, mapped_data) = self.allocate_list(&callee.return_type, length)?;
+                    let mapped_type = self.basic_type(&callee.return_type)?;
+                    self.build_counted_loop(length, "map", |this, index| {
+                        let (_, result) = call_callback(this, index)?;
+                        // SAFETY: the new buffer holds `length` elements
+                        let slot = unsafe { this.builder.build_gep(mapped_type, mapped_data, &[index], "map.slot") }
+                            .map_err(builder_error)?;
+                        this.builder.build_store(slot, result).map_err(builder_error)?;
+                        Ok(())
+                    })?;
+                    return Ok(Some(mapped.into()));
+                }
+
+                let (kept_list, kept_data) = self.allocate_list(&element, length)?;
+                let kept = self.entry_alloca(i64_type.into(), "filter.kept")?;
+                self.builder.build_store(kept, i64_type.const_zero()).map_err(builder_error)?;
+                self.build_counted_loop(length, "filter", |this, index| {
+                    let (current, keep) = call_callback(this, index)?;
+                    let keep_block = this.append_block("filter.keep");
+                    let next_block = this.append_block("filter.next");
+                    this.builder
+                        .build_conditional_branch(keep.into_int_value(), keep_block, next_block)
+                        .map_err(builder_error)?;
+
+                    this.builder.position_at_end(keep_block);
+                    let position = this
+                        .builder
+                        .build_load(i64_type, kept, "filter.position")
+                        .map_err(builder_error)?
+                        .into_int_value();
+                    // SAFETY: at most `length` elements are kept
+                    let slot = unsafe { this.builder.build_gep(element_type, kept_data, &[position], "filter.slot") }
+                        .map_err(builder_error)?;
+                    this.builder.build_store(slot, current).map_err(builder_error)?;
+                    let count = this
+                        .builder
+                        .build_int_add(position, i64_type.const_int(1, false), "filter.count")
+                        .map_err(builder_error)?;
+                    this.builder.build_store(kept, count).map_err(builder_error)?;
+                    this.builder.build_unconditional_branch(next_block).map_err(builder_error)?;
+
+                    this.builder.position_at_end(next_block);
+                    Ok(())
+                })?;
+                let count = self.builder.build_load(i64_type, kept, "filter.len").map_err(builder_error)?;
+                let filtered = self
+                    .builder
+                    .build_insert_value(kept_list, count, 0, "filtered")
+                    .map_err(builder_error)?;
+                Ok(Some(filtered.into_struct_value().into()))
+            }
+            _ => Err(unsupported(format!("list method '{}'", method))),
+        }
+    }
+
+    /// Store a list with a new length and buffer back to its place
+    fn store_list(
+        &mut self,
+        pointer: PointerValue<'ctx>,
+        list: StructValue<'ctx>,
+        length: IntValue<'ctx>,
+        data: PointerValue<'ctx>,
+    ) -> Result<(), CodeGenError> {
+        let list = self
+            .builder
+            .build_insert_value(list, length, 0, "list.len")
+            .map_err(builder_error)?;
+        let list = self
+            .builder
+            .build_insert_value(list, data, 1, "list")
+            .map_err(builder_error)?;
+        self.builder.build_store(pointer, list).map_err(builder_error)?;
+        Ok(())
+    }
+
+    /// Type of the values of a map, `None` for a set; values that are
+    /// dropped are not supported, as the map copies them byte for byte
+    fn map_value_type(&self, collection: &ResolvedType) -> Result<Option<ResolvedType>, CodeGenError> {
+        match collection {
+            ResolvedType::Map(_, value) if self.needs_drop(value) => {
+                Err(unsupported(format!("map values of type {:?} that are dropped", value)))
+            }
+            ResolvedType::Map(_, value) => Ok(Some(value.as_ref().clone())),
+            ResolvedType::Set(_) => Ok(None),
+            ResolvedType::Reference(inner, _) => self.map_value_type(inner),
+            other => Err(CodeGenError::TypeError(format!("map operation on {:?}", other))),
+        }
+    }
+
+    /// A new empty runtime map (or set) for a value of type `collection`
+    fn build_map_new(&mut self, collection: &ResolvedType) -> Result<PointerValue<'ctx>, CodeGenError> {
+        let i64_type = self.context.i64_type();
+        let value_size = match self.map_value_type(collection)? {
+            Some(value) => self
+                .basic_type(&value)?
+                .size_of()
+                .ok_or_else(|| unsupported(format!("map of unsized {:?}", value)))?,
+            None => i64_type.const_zero(),
+        };
+        let new = self.runtime_function("albayan_rt_map_new", self.pointer_type().fn_type(&[i64_type.into()], false));
+        Ok(self
+            .builder
+            .build_call(new, &[value_size.into()], "map")
+            .map_err(builder_error)?
+            .try_as_basic_value()
+            .left()
+            .expect("albayan_rt_map_new returns a pointer")
+            .into_pointer_value())
+    }
+
+    /// A key as the runtime takes it: an integer for `int`, `bool` and
+    /// `char`, or the text of a string with a null one otherwise
+    fn lower_map_key(&mut self, key: &AnnotatedExpression) -> Result<[BasicMetadataValueEnum<'ctx>; 2], CodeGenError> {
+        let i64_type = self.context.i64_type();
+        let value = self.lower_value(key)?;
+        Ok(match &key.result_type {
+            ResolvedType::String => [i64_type.const_zero().into(), value.into()],
+            ResolvedType::Int => [value.into(), self.string_type().const_null().into()],
+            ResolvedType::Bool | ResolvedType::Char => {
+                let widened = self
+                    .builder
+                    .build_int_z_extend(value.into_int_value(), i64_type, "map.key")
+                    .map_err(builder_error)?;
+                [widened.into(), self.string_type().const_null().into()]
+            }
+            other => return Err(CodeGenError::TypeError(format!("map key of type {:?}", other))),
+        })
+    }
+
+    /// Insert `key`, with `value` unless the map is a set, which keeps no
+    /// values; returns whether the key is new
+    fn build_map_insert(
+        &mut self,
+        map: PointerValue<'ctx>,
+        key: &AnnotatedExpression,
+        value: Option<&AnnotatedExpression>,
+    ) -> Result<IntValue<'ctx>, CodeGenError> {
+        let [key_value, key_text] = self.lower_map_key(key)?;
+        self.consume(key)?;
+        let stored = match value {
+            Some(value) => {
+                let lowered = self.lower_value(value)?;
+                self.consume(value)?;
+                let slot = self.entry_alloca(lowered.get_type(), "map.value")?;
+                self.builder.build_store(slot, lowered).map_err(builder_error)?;
+                slot
+            }
+            None => self.pointer_type().const_null(),
+        };
+        let pointer = self.pointer_type();
+        let insert = self.runtime_function(
+            "albayan_rt_map_insert",
+            self.context.i8_type().fn_type(
+                &[pointer.into(), self.context.i64_type().into(), pointer.into(), pointer.into()],
+                false,
+            ),
+        );
+        let inserted = self
+            .builder
+            .build_call(insert, &[map.into(), key_value, key_text, stored.into()], "map.insert")
+            .map_err(builder_error)?;
+        self.runtime_flag(inserted.try_as_basic_value().left().expect("albayan_rt_map_insert returns a flag"))
+    }
+
+    /// A `0` or `1` byte returned by the runtime as a `bool`
+    fn runtime_flag(&self, flag: BasicValueEnum<'ctx>) -> Result<IntValue<'ctx>, CodeGenError> {
+        let flag = flag.into_int_value();
+        self.builder
+            .build_int_compare(IntPredicate::NE, flag, flag.get_type().const_zero(), "flag")
+            .map_err(builder_error)
+    }
+
+    /// A built-in map or set method; `arguments` starts with the collection.
+    /// `get` of a missing key panics.
+    fn lower_map_method(
+        &mut self,
+        method: &str,
+        arguments: &[AnnotatedExpression],
+    ) -> Result<Option<BasicValueEnum<'ctx>>, CodeGenError> {
+        let (receiver, arguments) = arguments
+            .split_first()
+            .ok_or_else(|| CodeGenError::GenerationError(format!("map method {} without a map", method)))?;
+        let value_type = self.map_value_type(&receiver.result_type)?;
+        let map = self.lower_value(receiver)?.into_pointer_value();
+        let map = if matches!(receiver.result_type, ResolvedType::Reference(..)) {
+            self.builder
+                .build_load(self.pointer_type(), map, "map")
+                .map_err(builder_error)?
+                .into_pointer_value()
+        } else {
+            map
+        };
+        let pointer = self.pointer_type();
+        let i64_type = self.context.i64_type();
+        let key_function = |this: &mut Self, name: &str, return_type: BasicTypeEnum<'ctx>| {
+            this.runtime_function(
+                name,
+                return_type.fn_type(&[pointer.into(), i64_type.into(), pointer.into()], false),
+            )
+        };
+
+        match (method, arguments) {
+            ("len", []) => {
+                let len = self.runtime_function("albayan_rt_map_len", i64_type.fn_type(&[pointer.into()], false));
+                let length = self.builder.build_call(len, &[map.into()], "map.len").map_err(builder_error)?;
+                Ok(length.try_as_basic_value().left())
+            }
+            ("insert", [key, value]) => {
+                self.build_map_insert(map, key, Some(value))?;
+                Ok(None)
+            }
+            ("insert", [element]) => Ok(Some(self.build_map_insert(map, element, None)?.into())),
+            ("remove", [key]) => {
+                let [key_value, key_text] = self.lower_map_key(key)?;
+                let remove = key_function(self, "albayan_rt_map_remove", self.context.i8_type().into());
+                let removed = self
+                    .builder
+                    .build_call(remove, &[map.into(), key_value, key_text], "map.remove")
+                    .map_err(builder_error)?;
+                let removed = removed.try_as_basic_value().left().expect("albayan_rt_map_remove returns a flag");
+                Ok(Some(self.runtime_flag(removed)?.into()))
+            }
+            ("get" | "contains", [key]) => {
+                let [key_value, key_text] = self.lower_map_key(key)?;
+                let get = key_function(self, "albayan_rt_map_get", pointer.into());
+                let found = self
+                    .builder
+                    .build_call(get, &[map.into(), key_value, key_text], "map.get")
+                    .map_err(builder_error)?
+                    .try_as_basic_value()
+                    .left()
+                    .expect("albayan_rt_map_get returns a pointer")
+                    .into_pointer_value();
+                let missing = self.builder.build_is_null(found, "map.missing").map_err(builder_error)?;
+                if method == "contains" {
+                    return Ok(Some(self.builder.build_not(missing, "map.contains").map_err(builder_error)?.into()));
+                }
+
+                let value_type = value_type.ok_or_else(|| CodeGenError::TypeError("get on a set".to_string()))?;
+                let panic_block = self.append_block("get.missing");
+                let ok_block = self.append_block("get.ok");
+                self.builder
+                    .build_conditional_branch(missing, panic_block, ok_block)
+                    .map_err(builder_error)?;
+
+                self.builder.position_at_end(panic_block);
+                self.build_panic("key not found in map")?;
+
+                self.builder.position_at_end(ok_block);
+                let value_type = self.basic_type(&value_type)?;
+                Ok(Some(self.builder.build_load(value_type, found, "map.value").map_err(builder_error)?))
+            }
+            _ => Err(unsupported(format!("map method '{}' with {} arguments", method, arguments.len()))),
+        }
+    }
+
+    /// A built-in string method; `arguments` starts with the string. Each
+    /// method is an `albayan_rt_string_*` call, and `split` fills a list the
+    /// size `albayan_rt_string_split_count` reports.
+    fn lower_string_method(
+        &mut self,
+        method: &str,
+        arguments: &[AnnotatedExpression],
+    ) -> Result<Option<BasicValueEnum<'ctx>>, CodeGenError> {
+        let string = self.string_type();
+        let mut values = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            let value = self.lower_value(argument)?;
+            // A `&string` receiver points at the string
+            let value = match argument.result_type {
+                ResolvedType::Reference(..) => self
+                    .builder
+                    .build_load(string, value.into_pointer_value(), "string")
+                    .map_err(builder_error)?,
+                _ => value,
+            };
+            values.push(BasicMetadataValueEnum::from(value));
+        }
+        let i64_type = self.context.i64_type();
+        let (parameters, return_type): (Vec<BasicMetadataTypeEnum<'ctx>>, BasicTypeEnum<'ctx>) = match method {
+            "len" | "to_int" => (vec![string.into()], i64_type.into()),
+            "contains" => (vec![string.into(), string.into()], self.context.i8_type().into()),
+            "concat" => (vec![string.into(), string.into()], string.into()),
+            "slice" => (vec![string.into(), i64_type.into(), i64_type.into()], string.into()),
+            "split" => (vec![string.into(), string.into()], i64_type.into()),
+            _ => return Err(unsupported(format!("string method '{}'", method))),
+        };
+        if values.len() != parameters.len() {
+            return Err(CodeGenError::GenerationError(format!(
+                "string::{} with {} arguments",
+                method,
+                values.len()
+            )));
+        }
+
+        let name = match method {
+            "split" => "albayan_rt_string_split_count".to_string(),
+            method => format!("albayan_rt_string_{}", method),
+        };
+        let function = self.runtime_function(&name, return_type.fn_type(&parameters, false));
+        let result = self
+            .builder
+            .build_call(function, &values, &format!("string.{}", method))
+            .map_err(builder_error)?
+            .try_as_basic_value()
+            .left()
+            .expect("string methods return a value");
+
+        match method {
+            "contains" => Ok(Some(self.runtime_flag(result)?.into())),
+            "split" => {
+                let (list, data) = self.allocate_list(&ResolvedType::String, result.into_int_value())?;
+                let split_into = self.runtime_function(
+                    "albayan_rt_string_split_into",
+                    self.context
+                        .void_type()
+                        .fn_type(&[string.into(), string.into(), self.pointer_type().into()], false),
+                );
+                self.builder
+                    .build_call(split_into, &[values[0], values[1], data.into()], "")
+                    .map_err(builder_error)?;
+                Ok(Some(list.into()))
+            }
+            _ => Ok(Some(result)),
+        }
+    }
+
+    /// End the current block with a runtime panic reporting `message`
+    fn build_panic(&mut self, message: &str) -> Result<(), CodeGenError> {
+        let i64_type = self.context.i64_type();
+        let panic = self.runtime_function(
+            "albayan_rt_panic",
+            self.context
+                .void_type()
+                .fn_type(&[self.string_type().into(), i64_type.into()], false),
+        );
+        let text = self.global_string(message, "panic.message")?;
+        let length = i64_type.const_int(message.len() as u64, false);
+        self.builder
+            .build_call(panic, &[text.into(), length.into()], "")
+            .map_err(builder_error)?;
+        self.builder.build_unreachable().map_err(builder_error)?;
+        Ok(())
+    }
+
+    /// Address of `object[index]` for a list or fixed-size array
+    fn lower_element_place(
+        &mut self,
+        object: &AnnotatedExpression,
+        index: &AnnotatedExpression,
+    ) -> Result<(PointerValue<'ctx>, BasicTypeEnum<'ctx>), CodeGenError> {
+        let element = match &object.result_type {
+            ResolvedType::List(element) | ResolvedType::Vector(element, _) => element.as_ref().clone(),
+            other => return Err(CodeGenError::TypeError(format!("cannot index a value of type {:?}", other))),
+        };
+        // The analyzer rejects literal indices outside a fixed-size array
+        let in_bounds = matches!(
+            (&object.result_type, &index.expr),
+            (ResolvedType::Vector(_, _), AnnotatedExpressionKind::Literal(Literal::Integer(_)))
+        );
+        let list = self.lower_value(object)?.into_struct_value();
+        let index = self.lower_value(index)?.into_int_value();
+        self.element_pointer(list, &element, index, in_bounds)
+    }
+
+    fn lower_literal(&self, literal: &Literal) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        match literal {
+            Literal::Integer(value) => Ok(self.context.i64_type().const_int(*value as u64, true).into()),
+            Literal::Float(value) => Ok(self.context.f64_type().const_float(*value).into()),
+            Literal::Boolean(value) => Ok(self.context.bool_type().const_int(*value as u64, false).into()),
+            Literal::Char(value) => Ok(self.context.i32_type().const_int(*value as u64, false).into()),
+            Literal::String(value) => Ok(self.global_string(value, "str")?.into()),
+            Literal::Null => Ok(self.string_type().const_null().into()),
+            Literal::Tensor(_) => Err(unsupported("tensor literal".to_string())),
+        }
+    }
+
+    fn global_string(&self, value: &str, name: &str) -> Result<PointerValue<'ctx>, CodeGenError> {
+        let global = self.builder.build_global_string_ptr(value, name).map_err(builder_error)?;
+        Ok(global.as_pointer_value())
+    }
+
+    fn lower_binary(
+        &mut self,
+        left: &AnnotatedExpression,
+        operator: &BinaryOperator,
+        right: &AnnotatedExpression,
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        match operator {
+            BinaryOperator::And | BinaryOperator::Or => return self.lower_short_circuit(left, operator, right),
+            BinaryOperator::Assign
+            | BinaryOperator::AddAssign
+            | BinaryOperator::SubtractAssign
+            | BinaryOperator::MultiplyAssign
+            | BinaryOperator::DivideAssign => return self.lower_assignment(left, operator, right),
+            _ => {}
+        }
+
+        let lhs = self.lower_value(left)?;
+        let rhs = self.lower_value(right)?;
+
+        match operator {
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::Less
+            | BinaryOperator::LessEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual => {
+                self.compare(operator.clone(), lhs, rhs, &left.result_type).map(Into::into)
+            }
+            _ => self.arithmetic(operator, lhs, rhs),
+        }
+    }
+
+    fn arithmetic(
+        &self,
+        operator: &BinaryOperator,
+        lhs: BasicValueEnum<'ctx>,
+        rhs: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let b = &self.builder;
+        let value: BasicValueEnum<'ctx> = match (lhs, rhs) {
+            (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => match operator {
+                BinaryOperator::Add => b.build_int_add(l, r, "add").map_err(builder_error)?.into(),
+                BinaryOperator::Subtract => b.build_int_sub(l, r, "sub").map_err(builder_error)?.into(),
+                BinaryOperator::Multiply => b.build_int_mul(l, r, "mul").map_err(builder_error)?.into(),
+                BinaryOperator::Divide => b.build_int_signed_div(l, r, "div").map_err(builder_error)?.into(),
+                BinaryOperator::Modulo => b.build_int_signed_rem(l, r, "rem").map_err(builder_error)?.into(),
+                BinaryOperator::Power => {
+                    let f64_type = self.context.f64_type();
+                    let lf = b.build_signed_int_to_float(l, f64_type, "powl").map_err(builder_error)?;
+                    let rf = b.build_signed_int_to_float(r, f64_type, "powr").map_err(builder_error)?;
+                    let result = self.call_pow(lf.into(), rf.into())?;
+                    b.build_float_to_signed_int(result, l.get_type(), "pow").map_err(builder_error)?.into()
+                }
+                other => return Err(unsupported(format!("integer operator {:?}", other))),
+            },
+            (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => match operator {
+                BinaryOperator::Add => b.build_float_add(l, r, "fadd").map_err(builder_error)?.into(),
+                BinaryOperator::Subtract => b.build_float_sub(l, r, "fsub").map_err(builder_error)?.into(),
+                BinaryOperator::Multiply => b.build_float_mul(l, r, "fmul").map_err(builder_error)?.into(),
+                BinaryOperator::Divide => b.build_float_div(l, r, "fdiv").map_err(builder_error)?.into(),
+                BinaryOperator::Modulo => b.build_float_rem(l, r, "frem").map_err(builder_error)?.into(),
+                BinaryOperator::Power => self.call_pow(l.into(), r.into())?.into(),
+                other => return Err(unsupported(format!("float operator {:?}", other))),
+            },
+            _ => return Err(unsupported(format!("operator {:?} on these operand types", operator))),
+        };
+        Ok(value)
+    }
+
+    fn call_pow(
+        &self,
+        base: BasicMetadataValueEnum<'ctx>,
+        exponent: BasicMetadataValueEnum<'ctx>,
+    ) -> Result<inkwell::values::FloatValue<'ctx>, CodeGenError> {
+        let pow = self.module.get_function("llvm.pow.f64").unwrap_or_else(|| {
+            let f64_type = self.context.f64_type();
+            let fn_type = f64_type.fn_type(&[f64_type.into(), f64_type.into()], false);
+            self.module.add_function("llvm.pow.f64", fn_type, None)
+        });
+        let call = self.builder.build_call(pow, &[base, exponent], "pow").map_err(builder_error)?;
+        Ok(call
+            .try_as_basic_value()
+            .left()
+            .expect("llvm.pow.f64 returns a value")
+            .into_float_value())
+    }
+
+    fn compare(
+        &self,
+        operator: BinaryOperator,
+        lhs: BasicValueEnum<'ctx>,
+        rhs: BasicValueEnum<'ctx>,
+        operand_type: &ResolvedType,
+    ) -> Result<IntValue<'ctx>, CodeGenError> {
+        match (lhs, rhs) {
+            (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
+                let signed = matches!(operand_type, ResolvedType::Int);
+                let predicate = match operator {
+                    BinaryOperator::Equal => IntPredicate::EQ,
+                    BinaryOperator::NotEqual => IntPredicate::NE,
+                    BinaryOperator::Less if signed => IntPredicate::SLT,
+                    BinaryOperator::Less => IntPredicate::ULT,
+                    BinaryOperator::LessEqual if signed => IntPredicate::SLE,
+                    BinaryOperator::LessEqual => IntPredicate::ULE,
+                    BinaryOperator::Greater if signed => IntPredicate::SGT,
+                    BinaryOperator::Greater => IntPredicate::UGT,
+                    BinaryOperator::GreaterEqual if signed => IntPredicate::SGE,
+                    BinaryOperator::GreaterEqual => IntPredicate::UGE,
+                    other => return Err(unsupported(format!("comparison {:?}", other))),
+                };
+                self.builder.build_int_compare(predicate, l, r, "cmp").map_err(builder_error)
+            }
+            (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
+                let predicate = match operator {
+                    BinaryOperator::Equal => FloatPredicate::OEQ,
+                    BinaryOperator::NotEqual => FloatPredicate::UNE,
+                    BinaryOperator::Less => FloatPredicate::OLT,
+                    BinaryOperator::LessEqual => FloatPredicate::OLE,
+                    BinaryOperator::Greater => FloatPredicate::OGT,
+                    BinaryOperator::GreaterEqual => FloatPredicate::OGE,
+                    other => return Err(unsupported(format!("comparison {:?}", other))),
+                };
+                self.builder.build_float_compare(predicate, l, r, "fcmp").map_err(builder_error)
+            }
+            _ => Err(unsupported(format!("comparison of {:?} values", operand_type))),
+        }
+    }
+
+    fn lower_short_circuit(
+        &mut self,
+        left: &AnnotatedExpression,
+        operator: &BinaryOperator,
+        right: &AnnotatedExpression,
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let lhs = self.lower_condition(left)?;
+        let lhs_block = self.builder.get_insert_block().expect("no insert block");
+        let rhs_block = self.append_block("logic.rhs");
+        let merge_block = self.append_block("logic.end");
+
+        if matches!(operator, BinaryOperator::And) {
+            self.builder.build_conditional_branch(lhs, rhs_block, merge_block)
+        } else {
+            self.builder.build_conditional_branch(lhs, merge_block, rhs_block)
+        }
+        .map_err(builder_error)?;
+
+        self.builder.position_at_end(rhs_block);
+        let rhs = self.lower_condition(right)?;
+        let rhs_end = self.builder.get_insert_block().expect("no insert block");
+        self.builder.build_unconditional_branch(merge_block).map_err(builder_error)?;
+
+        self.builder.position_at_end(merge_block);
+        let phi = self
+            .builder
+            .build_phi(self.context.bool_type(), "logic")
+            .map_err(builder_error)?;
+        phi.add_incoming(&[(&lhs, lhs_block), (&rhs, rhs_end)]);
+        Ok(phi.as_basic_value())
+    }
+
+    fn lower_assignment(
+        &mut self,
+        left: &AnnotatedExpression,
+        operator: &BinaryOperator,
+        right: &AnnotatedExpression,
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let (pointer, value_type) = self.lower_place(left)?;
+
+        let rhs = self.lower_value(right)?;
+        let value = match operator {
+            BinaryOperator::Assign => {
+                // The new value moves in and the old one is dropped
+                self.consume(right)?;
+                match &left.expr {
+                    AnnotatedExpressionKind::Identifier(name) => {
+                        if let Some(flag) = self.lookup_local(name).and_then(|slot| slot.drop_flag) {
+                            let glue = self.drop_glue(&left.result_type)?;
+                            self.drop_slot(DropSlot { pointer, flag, glue })?;
+                            let owned = self.context.bool_type().const_int(1, false);
+                            self.builder.build_store(flag, owned).map_err(builder_error)?;
+                        }
+                    }
+                    _ => self.drop_in_place(pointer, &left.result_type)?,
+                }
+                rhs
+            }
+            compound => {
+                let current = self
+                    .builder
+                    .build_load(value_type, pointer, "current")
+                    .map_err(builder_error)?;
+                let arithmetic_op = match compound {
+                    BinaryOperator::AddAssign => BinaryOperator::Add,
+                    BinaryOperator::SubtractAssign => BinaryOperator::Subtract,
+                    BinaryOperator::MultiplyAssign => BinaryOperator::Multiply,
+                    _ => BinaryOperator::Divide,
+                };
+                self.arithmetic(&arithmetic_op, current, rhs)?
+            }
+        };
+
+        self.builder.build_store(pointer, value).map_err(builder_error)?;
+        Ok(value)
+    }
+
+    /// Address and type of an assignment target: a variable, a struct field or
+    /// tuple element of one, or a place reached through a reference
+    fn lower_place(
+        &mut self,
+        expr: &AnnotatedExpression,
+    ) -> Result<(PointerValue<'ctx>, BasicTypeEnum<'ctx>), CodeGenError> {
+        match &expr.expr {
+            AnnotatedExpressionKind::Identifier(name) => {
+                let slot = self
+                    .lookup_local(name)
+                    .ok_or_else(|| CodeGenError::GenerationError(format!("unknown variable '{}'", name)))?;
+                Ok((slot.pointer, slot.value_type))
+            }
+            AnnotatedExpressionKind::Unary(unary) if unary.operator == UnaryOperator::Dereference => {
+                let ResolvedType::Reference(inner, _) = &unary.operand.result_type else {
+                    return Err(CodeGenError::TypeError(format!(
+                        "cannot dereference a value of type {:?}",
+                        unary.operand.result_type
+                    )));
+                };
+                let pointer = self.lower_value(&unary.operand)?.into_pointer_value();
+                Ok((pointer, self.basic_type(inner)?))
+            }
+            AnnotatedExpressionKind::FieldAccess { object, field } => {
+                let (struct_name, pointer) = match &object.result_type {
+                    ResolvedType::Struct(name) => (name.clone(), self.lower_place(object)?.0),
+                    ResolvedType::Reference(inner, _) => {
+                        let ResolvedType::Struct(name) = inner.as_ref() else {
+                            return Err(unsupported(format!("assignment to field '.{}' through {:?}", field, object.result_type)));
+                        };
+                        (name.clone(), self.lower_value(object)?.into_pointer_value())
+                    }
+                    _ => return Err(unsupported(format!("assignment to field '.{}'", field))),
+                };
+                let (struct_type, index) = self.field_index(&struct_name, field)?;
+                let field_pointer = self
+                    .builder
+                    .build_struct_gep(struct_type, pointer, index, field)
+                    .map_err(builder_error)?;
+                let field_type = struct_type
+                    .get_field_type_at_index(index)
+                    .expect("field index checked against the layout");
+                Ok((field_pointer, field_type))
+            }
+            AnnotatedExpressionKind::Index { object, index } => match (&object.result_type, &index.expr) {
+                (ResolvedType::Tuple(_), AnnotatedExpressionKind::Literal(Literal::Integer(position))) => {
+                    let tuple_type = self.basic_type(&object.result_type)?.into_struct_type();
+                    let position = *position as u32;
+                    let element_type = tuple_type
+                        .get_field_type_at_index(position)
+                        .ok_or_else(|| CodeGenError::TypeError(format!("tuple has no element {}", position)))?;
+                    let pointer = self.lower_place(object)?.0;
+                    let element_pointer = self
+                        .builder
+                        .build_struct_gep(tuple_type, pointer, position, "tuple.elem")
+                        .map_err(builder_error)?;
+                    Ok((element_pointer, element_type))
+                }
+                (ResolvedType::List(_) | ResolvedType::Vector(_, _), _) => self.lower_element_place(object, index),
+                _ => Err(unsupported("assignment to an index expression".to_string())),
+            },
+            _ => Err(unsupported("assignment to this target".to_string())),
+        }
+    }
+
+    fn lower_call(
+        &mut self,
+        function: &str,
+        arguments: &[AnnotatedExpression],
+        result_type: &ResolvedType,
+    ) -> Result<Option<BasicValueEnum<'ctx>>, CodeGenError> {
+        match function {
+            "print" | "println" => {
+                let text = self.lower_formatted(arguments, arguments.len() > 1, true)?;
+                self.lower_print_string(text)?;
+                return Ok(None);
+            }
+            "format" => return Ok(Some(self.lower_formatted(arguments, true, false)?.into())),
+            "on_exit" => {
+                self.lower_on_exit(arguments)?;
+                return Ok(None);
+            }
+            "panic" | "assert" | "assert_true" | "assert_eq" | "assert_ne" => {
+                self.lower_panic_intrinsic(function, arguments)?;
+                return Ok(None);
+            }
+            _ => {}
+        }
+
+        // Built-in collection and string methods are marked `list::method`,
+        // `map::method`, `set::method` and `string::method`
+        if let Some(method) = function.strip_prefix("list::") {
+            return self.lower_list_method(method, arguments);
+        }
+        if let Some(method) = function.strip_prefix("map::").or_else(|| function.strip_prefix("set::")) {
+            return self.lower_map_method(method, arguments);
+        }
+        if let Some(method) = function.strip_prefix("string::") {
+            return self.lower_string_method(method, arguments);
+        }
+
+        // Semantic analysis marks calls through a trait object as `dyn_Trait::method`
+        if let Some((trait_name, method)) = function.strip_prefix("dyn_").and_then(|call| call.split_once("::")) {
+            return self.lower_dynamic_call(trait_name, method, arguments, result_type);
+        }
+
+        let callee = self
+            .functions
+            .get(function)
+            .cloned()
+            .ok_or_else(|| unsupported(format!("call to unknown or builtin function '{}'", function)))?;
+
+        if Self::returns_indirectly(&callee.return_type) {
+            let result = self.lower_indirect_call(&callee, arguments)?;
+            let value_type = self.basic_type(&callee.return_type)?;
+            return self
+                .builder
+                .build_load(value_type, result, "call.result")
+                .map(Some)
+                .map_err(builder_error);
+        }
+
+        let args = arguments
+            .iter()
+            .zip(&callee.parameters)
+            .map(|(arg, param_type)| self.lower_argument(arg, param_type).map(BasicMetadataValueEnum::from))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let call = self.builder.build_call(callee.value, &args, "call").map_err(builder_error)?;
+        if matches!(callee.return_type, ResolvedType::Unit) {
+            Ok(None)
+        } else {
+            Ok(call.try_as_basic_value().left())
+        }
+    }
+
+    /// Call a function returning through `sret`, giving the buffer the
+    /// callee wrote its result to
+    fn lower_indirect_call(
+        &mut self,
+        callee: &DeclaredFunction<'ctx>,
+        arguments: &[AnnotatedExpression],
+    ) -> Result<PointerValue<'ctx>, CodeGenError> {
+        let result = self.entry_alloca(self.basic_type(&callee.return_type)?, "call.result")?;
+        let mut args: Vec<BasicMetadataValueEnum<'ctx>> = vec![result.into()];
+        for (arg, param_type) in arguments.iter().zip(&callee.parameters) {
+            args.push(self.lower_argument(arg, param_type)?.into());
+        }
+
+        let call = self.builder.build_call(callee.value, &args, "").map_err(builder_error)?;
+        call.add_attribute(AttributeLoc::Param(0), self.sret_attribute(&callee.return_type)?);
+        Ok(result)
+    }
+
+    /// Lower an argument of a user function; a value passed by value moves into the callee
+    fn lower_argument(
+        &mut self,
+        arg: &AnnotatedExpression,
+        param_type: &ResolvedType,
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let value = self.lower_coerced(arg, param_type)?;
+        if !matches!(param_type, ResolvedType::Reference(_, _)) {
+            self.consume(arg)?;
+        }
+        Ok(value)
+    }
+
+    /// Lower a value passed where `target` is expected: `&T` becomes a
+    /// `&dyn Trait` fat pointer, and a method receiver is referenced or
+    /// dereferenced to match its `self` parameter
+    fn lower_coerced(
+        &mut self,
+        expr: &AnnotatedExpression,
+        target: &ResolvedType,
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let source = &expr.result_type;
+        if let ResolvedType::Reference(object, _) = target {
+            if let ResolvedType::TraitObject(traits) = object.as_ref() {
+                return match source {
+                    ResolvedType::Reference(inner, _) if matches!(inner.as_ref(), ResolvedType::TraitObject(_)) => {
+                        self.lower_value(expr)
+                    }
+                    ResolvedType::Reference(inner, _) => {
+                        let data = self.lower_value(expr)?.into_pointer_value();
+                        self.build_trait_object(traits, inner, data)
+                    }
+                    concrete => {
+                        let data = self.lower_address(expr)?;
+                        self.build_trait_object(traits, concrete, data)
+                    }
+                };
+            }
+        }
+
+        match (target, source) {
+            (ResolvedType::Reference(_, _), ResolvedType::Reference(_, _)) => self.lower_value(expr),
+            (ResolvedType::Reference(_, _), _) => self.lower_address(expr).map(Into::into),
+            (_, ResolvedType::Reference(inner, _)) if inner.as_ref() == target => {
+                let pointer = self.lower_value(expr)?.into_pointer_value();
+                let value_type = self.basic_type(target)?;
+                self.builder.build_load(value_type, pointer, "deref").map_err(builder_error)
+            }
+            _ => self.lower_value(expr),
+        }
+    }
+
+    /// Pair a data pointer with the vtable of its concrete type
+    fn build_trait_object(
+        &mut self,
+        traits: &[String],
+        concrete: &ResolvedType,
+        data: PointerValue<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, CodeGenError> {
+        let [trait_name] = traits else {
+            return Err(unsupported(format!("trait objects of several traits ({})", traits.join(" + "))));
+        };
+        let ResolvedType::Struct(type_name) = concrete else {
+            return Err(unsupported(format!("trait object of type {:?}", concrete)));
+        };
+        let vtable = self.vtable(trait_name, type_name)?.as_pointer_value();
+
+        let object = self
+            .builder
+            .build_insert_value(self.trait_object_type().get_undef(), data, 0, "dyn.data")
+            .map_err(builder_error)?;
+        let object = self
+            .builder
+            .build_insert_value(object, vtable, 1, "dyn")
+            .map_err(builder_error)?;
+        Ok(object.into_struct_value().into())
+    }
+
+    /// The vtable of `impl trait_name for type_name`, emitted on first use
+    fn vtable(&mut self, trait_name: &str, type_name: &str) -> Result<GlobalValue<'ctx>, CodeGenError> {
+        let key = (trait_name.to_string(), type_name.to_string());
+        if let Some(vtable) = self.vtables.get(&key) {
+            return Ok(*vtable);
+        }
+
+        let trait_decl = self
+            .traits
+            .get(trait_name)
+            .ok_or_else(|| CodeGenError::GenerationError(format!("unknown trait '{}'", trait_name)))?;
+        let methods = self.trait_impls.get(&key).ok_or_else(|| {
+            CodeGenError::TypeError(format!("'{}' does not implement '{}'", type_name, trait_name))
+        })?;
+
+        let entries = trait_decl
+            .methods
+            .iter()
+            .map(|method| match methods.get(&method.name) {
+                Some(function) => Ok(function.as_global_value().as_pointer_value()),
+                None if method.body.is_some() => Err(unsupported(format!(
+                    "default method '{}::{}' in the vtable of '{}'",
+                    trait_name, method.name, type_name
+                ))),
+                None => Err(CodeGenError::GenerationError(format!(
+                    "'{}' does not define '{}::{}'",
+                    type_name, trait_name, method.name
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let table = self.pointer_type().const_array(&entries);
+        let vtable = self
+            .module
+            .add_global(table.get_type(), None, &format!("vtable.{}.{}", trait_name, type_name));
+        vtable.set_initializer(&table);
+        vtable.set_constant(true);
+        vtable.set_linkage(Linkage::Private);
+        self.vtables.insert(key, vtable);
+        Ok(vtable)
+    }
+
+    /// Call `method` through the vtable of a trait object, passing its data
+    /// pointer as `self`
+    fn lower_dynamic_call(
+        &mut self,
+        trait_name: &str,
+        method: &str,
+        arguments: &[AnnotatedExpression],
+        result_type: &ResolvedType,
+    ) -> Result<Option<BasicValueEnum<'ctx>>, CodeGenError> {
+        let trait_decl = self
+            .traits
+            .get(trait_name)
+            .ok_or_else(|| CodeGenError::GenerationError(format!("unknown trait '{}'", trait_name)))?;
+        let index = trait_decl
+            .methods
+            .iter()
+            .position(|m| m.name == method)
+            .ok_or_else(|| CodeGenError::GenerationError(format!("trait '{}' has no method '{}'", trait_name, method)))?;
+        let signature = &trait_decl.methods[index];
+
+        // Only `&self` and `&mut self` methods can take the data pointer
+        let receiver_is_reference = signature
+            .parameters
+            .first()
+            .is_some_and(|param| param.name == "self" && matches!(param.param_type, ResolvedType::Reference(_, _)));
+        if !receiver_is_reference {
+            return Err(unsupported(format!(
+                "calling '{}::{}' through a trait object without a `&self` receiver",
+                trait_name, method
+            )));
+        }
+        let parameter_types: Vec<ResolvedType> =
+            signature.parameters[1..].iter().map(|param| param.param_type.clone()).collect();
+
+        let (object, rest) = arguments
+            .split_first()
+            .ok_or_else(|| CodeGenError::GenerationError("trait object call without a receiver".to_string()))?;
+        let object = match &object.result_type {
+            ResolvedType::Reference(_, _) | ResolvedType::TraitObject(_) => self.lower_value(object)?,
+            other => return Err(CodeGenError::TypeError(format!("{:?} is not a trait object", other))),
+        }
+        .into_struct_value();
+        let data = self
+            .builder
+            .build_extract_value(object, 0, "dyn.data")
+            .map_err(builder_error)?
+            .into_pointer_value();
+        let vtable = self
+            .builder
+            .build_extract_value(object, 1, "dyn.vtable")
+            .map_err(builder_error)?
+            .into_pointer_value();
+
+        let pointer_type = self.pointer_type();
+        // SAFETY: `index` is a method position of the trait, and every vtable of
+        // the trait has one entry per method
+        let entry = unsafe {
+            self.builder.build_gep(
+                pointer_type,
+                vtable,
+                &[self.context.i64_type().const_int(index as u64, false)],
+                "dyn.entry",
+            )
+        }
+        .map_err(builder_error)?;
+        let callee = self
+            .builder
+            .build_load(pointer_type, entry, &format!("{}.{}", trait_name, method))
+            .map_err(builder_error)?
+            .into_pointer_value();
+
+        let result = if Self::returns_indirectly(result_type) {
+            Some(self.entry_alloca(self.basic_type(result_type)?, "dyn.result")?)
+        } else {
+            None
+        };
+        let mut param_types: Vec<BasicMetadataTypeEnum<'ctx>> = vec![pointer_type.into()];
+        let mut args: Vec<BasicMetadataValueEnum<'ctx>> = result.into_iter().map(Into::into).collect();
+        args.push(data.into());
+        for (arg, param_type) in rest.iter().zip(&parameter_types) {
+            param_types.push(self.basic_type(param_type)?.into());
+            args.push(self.lower_coerced(arg, param_type)?.into());
+        }
+        let fn_type = self.function_type(&param_types, result_type)?;
+
+        let call = self
+            .builder
+            .build_indirect_call(fn_type, callee, &args, "dyn.call")
+            .map_err(builder_error)?;
+        if let Some(result) = result {
+            call.add_attribute(AttributeLoc::Param(0), self.sret_attribute(result_type)?);
+            let value_type = self.basic_type(result_type)?;
+            return self
+                .builder
+                .build_load(value_type, result, "dyn.result")
+                .map(Some)
+                .map_err(builder_error);
+        }
+        if matches!(result_type, ResolvedType::Unit) {
+            Ok(None)
+        } else {
+            Ok(call.try_as_basic_value().left())
+        }
+    }
+
+    /// Declare a runtime or libc function on first use
+    fn runtime_function(
+        &self,
+        name: &str,
+        fn_type: inkwell::types::FunctionType<'ctx>,
+    ) -> FunctionValue<'ctx> {
+        self.module
+            .get_function(name)
+            .unwrap_or_else(|| self.module.add_function(name, fn_type, Some(Linkage::External)))
+    }
+
+    /// Build the text of a `format`/`print`/`println` call with the runtime
+    /// formatting helpers. With `templated`, the first argument is a literal
+    /// template split at compile time; otherwise each argument is displayed as is.
+    fn lower_formatted(
+        &mut self,
+        arguments: &[AnnotatedExpression],
+        templated: bool,
+        newline: bool,
+    ) -> Result<PointerValue<'ctx>, CodeGenError> {
+        let text_builder = self.begin_format()?;
+
+        if templated {
+            let (template, values) = arguments
+                .split_first()
+                .ok_or_else(|| CodeGenError::GenerationError("format call without a template".to_string()))?;
+            let AnnotatedExpressionKind::Literal(Literal::String(template)) = &template.expr else {
+                return Err(unsupported("format templates that are not string literals".to_string()));
+            };
+            let pieces = parse_format_string(template).map_err(CodeGenError::GenerationError)?;
+            let mut values = values.iter();
+            for piece in pieces {
+                match piece {
+                    FormatPiece::Text(text) => self.append_text(text_builder, &text)?,
+                    FormatPiece::Argument { precision } => {
+                        let value = values.next().ok_or_else(|| {
+                            CodeGenError::GenerationError("format template has more placeholders than values".to_string())
+                        })?;
+                        self.append_value(text_builder, value, precision)?;
+                    }
+                }
+            }
+        } else {
+            for argument in arguments {
+                self.append_value(text_builder, argument, None)?;
+            }
+        }
+        if newline {
+            self.append_text(text_builder, "\n")?;
+        }
+        self.finish_format(text_builder)
+    }
+
+    /// Start a string builder with `albayan_rt_format_begin`
+    fn begin_format(&mut self) -> Result<PointerValue<'ctx>, CodeGenError> {
+        let begin = self.runtime_function("albayan_rt_format_begin", self.string_type().fn_type(&[], false));
+        Ok(self
+            .builder
+            .build_call(begin, &[], "fmt.builder")
+            .map_err(builder_error)?
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| CodeGenError::GenerationError("albayan_rt_format_begin returned no value".to_string()))?
+            .into_pointer_value())
+    }
+
+    /// Turn a string builder into its NUL-terminated text
+    fn finish_format(&mut self, text_builder: PointerValue<'ctx>) -> Result<PointerValue<'ctx>, CodeGenError> {
+        let finish = self.runtime_function(
+            "albayan_rt_format_finish",
+            self.string_type().fn_type(&[self.string_type().into()], false),
+        );
+        Ok(self
+            .builder
+            .build_call(finish, &[text_builder.into()], "fmt.text")
+            .map_err(builder_error)?
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| CodeGenError::GenerationError("albayan_rt_format_finish returned no value".to_string()))?
+            .into_pointer_value())
+    }
+
+    fn append_text(&mut self, text_builder: PointerValue<'ctx>, text: &str) -> Result<(), CodeGenError> {
+        let fn_type = self
+            .context
+            .void_type()
+            .fn_type(&[self.string_type().into(), self.string_type().into()], false);
+        let append = self.runtime_function("albayan_rt_format_text", fn_type);
+        let text = self.global_string(text, "fmt.piece")?;
+        self.builder
+            .build_call(append, &[text_builder.into(), text.into()], "")
+            .map_err(builder_error)?;
+        Ok(())
+    }
+
+    /// Append one displayable value with the helper matching its type
+    fn append_value(
+        &mut self,
+        text_builder: PointerValue<'ctx>,
+        argument: &AnnotatedExpression,
+        precision: Option<usize>,
+    ) -> Result<(), CodeGenError> {
+        let value = self.lower_value(argument)?;
+        self.append_lowered(text_builder, value, &argument.result_type, precision)
+    }
+
+    /// Append a value that has already been lowered
+    fn append_lowered(
+        &mut self,
+        text_builder: PointerValue<'ctx>,
+        value: BasicValueEnum<'ctx>,
+        value_type: &ResolvedType,
+        precision: Option<usize>,
+    ) -> Result<(), CodeGenError> {
+        let void_type = self.context.void_type();
+        let builder_type = self.string_type();
+
+        let i64_type = self.context.i64_type();
+        let (name, value, value_type): (&str, BasicMetadataValueEnum<'ctx>, BasicMetadataTypeEnum<'ctx>) =
+            match value_type {
+                ResolvedType::Int => ("albayan_rt_format_int", value.into(), i64_type.into()),
+                ResolvedType::Float => ("albayan_rt_format_float", value.into(), self.context.f64_type().into()),
+                ResolvedType::Bool => {
+                    let byte = self
+                        .builder
+                        .build_int_z_extend(value.into_int_value(), self.context.i8_type(), "bool.byte")
+                        .map_err(builder_error)?;
+                    ("albayan_rt_format_bool", byte.into(), self.context.i8_type().into())
+                }
+                ResolvedType::Char => ("albayan_rt_format_char", value.into(), self.context.i32_type().into()),
+                ResolvedType::String => ("albayan_rt_format_text", value.into(), builder_type.into()),
+                other => return Err(unsupported(format!("formatting of {:?}", other))),
+            };
+
+        let mut args: Vec<BasicMetadataValueEnum<'ctx>> = vec![text_builder.into(), value];
+        let mut param_types = vec![builder_type.into(), value_type];
+        if *value_type == ResolvedType::Float {
+            let precision = precision.map_or(-1, |digits| digits as i64);
+            args.push(i64_type.const_int(precision as u64, true).into());
+            param_types.push(i64_type.into());
+        }
+        let append = self.runtime_function(name, void_type.fn_type(&param_types, false));
+        self.builder.build_call(append, &args, "").map_err(builder_error)?;
+        Ok(())
+    }
+
+    /// Write a string from `albayan_rt_format_finish` with `albayan_rt_print_string`, then free it
+    /// Register the function named by the argument with the runtime's exit hooks
+    fn lower_on_exit(&mut self, arguments: &[AnnotatedExpression]) -> Result<(), CodeGenError> {
+        let [AnnotatedExpression { expr: AnnotatedExpressionKind::Identifier(name), .. }] = arguments else {
+            return Err(unsupported("on_exit without a function name".to_string()));
+        };
+        let callback = self
+            .functions
+            .get(name)
+            .ok_or_else(|| unsupported(format!("on_exit callback '{}'", name)))?
+            .value
+            .as_global_value()
+            .as_pointer_value();
+
+        let register = self.runtime_function(
+            "albayan_rt_on_exit",
+            self.context.void_type().fn_type(&[self.pointer_type().into()], false),
+        );
+        self.builder.build_call(register, &[callback.into()], "").map_err(builder_error)?;
+        Ok(())
+    }
+
+    /// Lower `panic` or an assertion. Semantic analysis appended the call's
+    /// location as a last string argument. An assertion branches to a block
+    /// that builds the failure message and calls `albayan_rt_panic`; the values
+    /// `assert_eq` and `assert_ne` show are lowered once, before the check,
+    /// and the message of `assert` only when it fails.
+    fn lower_panic_intrinsic(&mut self, function: &str, arguments: &[AnnotatedExpression]) -> Result<(), CodeGenError> {
+        let Some((AnnotatedExpression { expr: AnnotatedExpressionKind::Literal(Literal::String(location)), .. }, arguments)) =
+            arguments.split_last()
+        else {
+            return Err(CodeGenError::GenerationError(format!("{} call without a source location", function)));
+        };
+        let location = self.global_string(location, "panic.location")?;
+
+        if let ("panic", [message]) = (function, arguments) {
+            let message = self.lower_value(message)?.into_pointer_value();
+            return self.lower_panic(message, location);
+        }
+
+        let (holds, operands) = match (function, arguments) {
+            ("assert" | "assert_true", [condition, ..]) => (self.lower_condition(condition)?, None),
+            ("assert_eq" | "assert_ne", [left, right]) => {
+                let lhs = self.lower_value(left)?;
+                let rhs = self.lower_value(right)?;
+                let operator = if function == "assert_eq" { BinaryOperator::Equal } else { BinaryOperator::NotEqual };
+                let holds = if left.result_type == ResolvedType::String {
+                    self.strings_compare(operator, lhs, rhs)?
+                } else {
+                    self.compare(operator, lhs, rhs, &left.result_type)?
+                };
+                (holds, Some((lhs, rhs, &left.result_type)))
+            }
+            _ => return Err(unsupported(format!("{} with {} arguments", function, arguments.len()))),
+        };
+
+        let failed = self.append_block("assert.failed");
+        let passed = self.append_block("assert.passed");
+        self.builder.build_conditional_branch(holds, passed, failed).map_err(builder_error)?;
+        self.builder.position_at_end(failed);
+        let message = match (operands, arguments) {
+            (Some((lhs, rhs, value_type)), _) => {
+                let relation = if function == "assert_eq" { "==" } else { "!=" };
+                let text_builder = self.begin_format()?;
+                self.append_text(text_builder, &format!("assertion failed: left {} right\n  left: ", relation))?;
+                self.append_lowered(text_builder, lhs, value_type, None)?;
+                self.append_text(text_builder, "\n right: ")?;
+                self.append_lowered(text_builder, rhs, value_type, None)?;
+                self.finish_format(text_builder)?
+            }
+            (None, [_, message]) => {
+                let text_builder = self.begin_format()?;
+                self.append_text(text_builder, "assertion failed: ")?;
+                self.append_value(text_builder, message, None)?;
+                self.finish_format(text_builder)?
+            }
+            (None, _) => self.global_string("assertion failed: expected true", "assert.message")?,
+        };
+        self.lower_panic(message, location)?;
+        self.builder.position_at_end(passed);
+        Ok(())
+    }
+
+    /// `strcmp(lhs, rhs) == 0`, or `!= 0` for `NotEqual`
+    fn strings_compare(
+        &mut self,
+        operator: BinaryOperator,
+        lhs: BasicValueEnum<'ctx>,
+        rhs: BasicValueEnum<'ctx>,
+    ) -> Result<IntValue<'ctx>, CodeGenError> {
+        let i32_type = self.context.i32_type();
+        let string_type = self.string_type();
+        let strcmp = self.runtime_function("strcmp", i32_type.fn_type(&[string_type.into(), string_type.into()], false));
+        let order = self
+            .builder
+            .build_call(strcmp, &[lhs.into(), rhs.into()], "strcmp")
+            .map_err(builder_error)?
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| CodeGenError::GenerationError("strcmp returned no value".to_string()))?;
+        self.compare(operator, order, i32_type.const_zero().into(), &ResolvedType::Int)
+    }
+
+    /// Call `albayan_rt_panic`, which does not return, and end the block
+    fn lower_panic(&mut self, message: PointerValue<'ctx>, location: PointerValue<'ctx>) -> Result<(), CodeGenError> {
+        let string_type = self.string_type();
+        let panic = self.runtime_function(
+            "albayan_rt_panic",
+            self.context.void_type().fn_type(&[string_type.into(), string_type.into()], false),
+        );
+        let noreturn = self.context.create_enum_attribute(Attribute::get_named_enum_kind_id("noreturn"), 0);
+        panic.add_attribute(AttributeLoc::Function, noreturn);
+        self.builder.build_call(panic, &[message.into(), location.into()], "").map_err(builder_error)?;
+        self.builder.build_unreachable().map_err(builder_error)?;
+        Ok(())
+    }
+
+    fn lower_print_string(&mut self, text: PointerValue<'ctx>) -> Result<(), CodeGenError> {
+        let i64_type = self.context.i64_type();
+        let string_type = self.string_type();
+        let void_type = self.context.void_type();
+
+        let strlen = self.runtime_function("strlen", i64_type.fn_type(&[string_type.into()], false));
+        let length = self
+            .builder
+            .build_call(strlen, &[text.into()], "fmt.len")
+            .map_err(builder_error)?
+            .try_as_basic_value()
+            .left()
+            .ok_or_else(|| CodeGenError::GenerationError("strlen returned no value".to_string()))?;
+
+        let print = self.runtime_function(
+            "albayan_rt_print_string",
+            void_type.fn_type(&[string_type.into(), i64_type.into()], false),
+        );
+        self.builder
+            .build_call(print, &[text.into(), length.into()], "")
+            .map_err(builder_error)?;
+
+        let free = self.runtime_function("albayan_rt_string_free", void_type.fn_type(&[string_type.into()], false));
+        self.builder.build_call(free, &[text.into()], "").map_err(builder_error)?;
+        Ok(())
+    }
+}
+
+/// Arguments of a fact, rule head or goal as stored in the knowledge image
+fn image_args(term: &AnnotatedLogicTerm) -> Vec<ImageArg<'_>> {
+    term.args.iter().map(image_arg).collect()
+}
+
+fn image_arg(arg: &AnnotatedLogicArg) -> ImageArg<'_> {
+    match arg {
+        AnnotatedLogicArg::Variable { name, .. } => ImageArg::Variable(name),
+        AnnotatedLogicArg::Constant { name, .. } => ImageArg::Atom(name),
+        AnnotatedLogicArg::StringConstant(text) => ImageArg::String(text),
+        AnnotatedLogicArg::IntConstant(value) => ImageArg::Integer(*value),
+        AnnotatedLogicArg::FloatConstant(value) => ImageArg::Float(*value),
+        AnnotatedLogicArg::Operation { op, left, right, .. } => {
+            ImageArg::Compound(op, vec![image_arg(left), image_arg(right)])
+        }
+        AnnotatedLogicArg::Goal(goal) => ImageArg::Compound(&goal.name, image_args(goal)),
+        AnnotatedLogicArg::Compound { name, args, .. } => ImageArg::Compound(name, args.iter().map(image_arg).collect()),
+        AnnotatedLogicArg::List { items, tail, .. } => {
+            ImageArg::list(items.iter().map(image_arg).collect(), tail.as_deref().map(image_arg))
+        }
+    }
+}
+
+/// Type name of a relation argument checked by the logic engine
+fn relation_arg_type(ty: &ResolvedType) -> &'static str {
+    match ty {
+        ResolvedType::Int => "int",
+        ResolvedType::Float => "float",
+        ResolvedType::String => "string",
+        ResolvedType::Bool => "bool",
+        _ => "any",
+    }
+}
+
+fn unsupported(what: String) -> CodeGenError {
+    CodeGenError::UnsupportedFeature(format!("LLVM backend does not support {} yet", what))
+}
+
+fn builder_error(error: inkwell::builder::BuilderError) -> CodeGenError {
+    CodeGenError::GenerationError(format!("LLVM builder error: {:?}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Lexer, Parser, SemanticAnalyzer};
+
+    fn analyze(source: &str) -> AnnotatedProgram {
+        let options = CompilerOptions::default();
+        let tokens = Lexer::new(source).tokenize().unwrap();
+        let ast = Parser::new(tokens).parse().unwrap();
+        SemanticAnalyzer::new(&options).analyze(ast).unwrap()
+    }
+
+    #[test]
+    fn test_emit_ir_for_arithmetic() {
+        let program = analyze("fn add(a: int, b: int) -> int { return a + b; }\nfn main() -> int { return add(40, 2); }");
+        let backend = LLVMBackend::new(&CompilerOptions::default());
+        let ir = backend.emit_ir(&program).unwrap();
+        assert!(ir.contains("define i64 @add"));
+        assert!(ir.contains("define i32 @main"));
+    }
+
+    #[test]
+    fn test_emit_object_is_native() {
+        let program = analyze("fn main() -> int { let x = 10; if x > 5 { return 1; } return 0; }");
+        let options = CompilerOptions { optimization_level: 2, ..Default::default() };
+        let object = LLVMBackend::new(&options).emit_object(&program).unwrap();
+        assert!(!object.is_empty());
+    }
+
+    #[test]
+    fn test_emit_ir_for_loops() {
+        let program = analyze(
+            "fn main() -> int {
+                let mut total = 0;
+                for x in [1, 2, 3, 4] {
+                    if x == 3 { continue; }
+                    total = total + x;
+                }
+                while total < 100 {
+                    total = total * 2;
+                    if total > 50 { break; }
+                }
+                return total;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("for.cond"));
+        assert!(ir.contains("while.body"));
+        assert!(ir.contains("@malloc"));
+    }
+
+    #[test]
+    fn test_emit_ir_if_expression() {
+        let program = analyze(
+            "fn scale(x: int) -> float {
+                let factor = if x > 10 { 0.5 } else if x == 0 { 0 } else { 2 };
+                return factor;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("phi double"), "{}", ir);
+        assert!(ir.contains("sitofp"), "{}", ir);
+    }
+
+    #[test]
+    fn test_emit_ir_field_assignment() {
+        let program = analyze(
+            "struct Point { x: int, y: int }
+
+            fn shift(p: &mut Point) {
+                p.x += 2;
+            }
+
+            fn main() -> int {
+                let mut point = Point { x: 1, y: 2 };
+                point.y = 5;
+                let mut pair = (1, 2.5);
+                pair.0 = 3;
+                return point.y + pair.0;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("getelementptr inbounds %Point"), "{}", ir);
+        assert!(ir.contains("tuple.elem"), "{}", ir);
+    }
+
+    #[test]
+    fn test_emit_ir_tuple_returns() {
+        let program = analyze(
+            "fn divmod(a: int, b: int) -> (int, int) {
+                return (a / b, a % b);
+            }
+
+            fn stats(a: int, b: int) -> (int, int, float) {
+                return (a + b, a * b, 0.5);
+            }
+
+            fn main() -> int {
+                let (q, r) = divmod(7, 2);
+                let (sum, _, half) = stats(q, r);
+                let all = stats(1, 2);
+                return sum + all.1;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("define { i64, i64 } @divmod(i64 %a, i64 %b)"), "{}", ir);
+        assert!(
+            ir.contains("define void @stats(ptr sret({ i64, i64, double }) %result, i64 %a, i64 %b)"),
+            "{}",
+            ir
+        );
+        assert_eq!(ir.matches("call void @stats(ptr sret({ i64, i64, double })").count(), 2, "{}", ir);
+        // Names destructured from an `sret` result live in the result buffer
+        assert!(ir.contains("%q = alloca i64"), "{}", ir);
+        assert!(!ir.contains("%sum = alloca"), "{}", ir);
+    }
+
+    #[test]
+    fn test_emit_ir_knowledge_image() {
+        let program = analyze(
+            "relation parent(string, string);
+            relation grandparent(string, string);
+            fact parent(\"john\", \"mary\");
+            fact parent(\"mary\", \"ali\");
+            rule grandparent(X, Z) :- parent(X, Y), parent(Y, Z);
+
+            fn main() -> int {
+                return 0;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("@albayan.knowledge = private constant [") && ir.contains("c\"ABKI\\01"), "{}", ir);
+        assert!(ir.contains("call i32 @albayan_rt_load_knowledge(ptr @albayan.knowledge, i64"), "{}", ir);
+
+        let program = analyze("fn main() -> int { return 0; }");
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(!ir.contains("albayan_rt_load_knowledge"), "{}", ir);
+    }
+
+    #[test]
+    fn test_emit_ir_query_expression() {
+        let program = analyze(
+            "relation parent(string, string);
+            fact parent(\"john\", \"mary\");
+
+            fn main() -> int {
+                let children = query parent(\"john\", X);
+                return children.len();
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("c\"parent(\\22john\\22, X)\\00\""), "{}", ir);
+        assert!(ir.contains("call ptr @albayan_rt_query_solve(ptr @query, ptr %query.len)"), "{}", ir);
+        assert!(ir.contains("call void @albayan_rt_query_solutions_into(ptr %query.solutions, ptr "), "{}", ir);
+    }
+
+    #[test]
+    fn test_emit_ir_on_exit() {
+        let program = analyze(
+            "fn cleanup() {
+                println(\"bye\");
+            }
+
+            fn main() -> int {
+                on_exit(cleanup);
+                return 0;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("call void @albayan_rt_on_exit(ptr @cleanup)"), "{}", ir);
+    }
+
+    #[test]
+    fn test_emit_ir_assertions() {
+        let program = analyze(
+            "fn check(x: int, name: string) {
+                assert(x > 0, \"x must be positive\");
+                assert_eq(name, \"ali\");
+                assert_ne(x, 3);
+                if x > 100 {
+                    panic(\"too big\");
+                }
+            }
+
+            fn main() -> int {
+                check(1, \"ali\");
+                return 0;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("declare void @albayan_rt_panic(ptr, ptr) #"), "{}", ir);
+        assert!(ir.contains("call i32 @strcmp(ptr"), "{}", ir);
+        assert!(ir.contains("c\"assertion failed: left != right\\0A  left: \\00\""), "{}", ir);
+        assert_eq!(ir.matches("call void @albayan_rt_panic(").count(), 4, "{}", ir);
+        assert_eq!(ir.matches("unreachable").count(), 4, "{}", ir);
+    }
+
+    #[test]
+    fn test_emit_ir_failed_fact_updates_panic() {
+        let program = analyze(
+            "relation note(string, int);
+
+            fn main() -> int {
+                assert note(\"ali\", 1);
+                retract note(\"ali\", 1);
+                return 0;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("call i32 @albayan_rt_fact_assert(ptr"), "{}", ir);
+        assert!(ir.contains("c\"cannot assert a `note` fact\\00\""), "{}", ir);
+        assert!(ir.contains("c\"cannot retract a `note` fact\\00\""), "{}", ir);
+        assert_eq!(ir.matches("call void @albayan_rt_panic(").count(), 2, "{}", ir);
+    }
+
+    #[test]
+    fn test_emit_ir_tuples() {
+        let program = analyze(
+            "fn divmod(a: int, b: int) -> (int, int) {
+                return (a / b, a % b);
+            }
+
+            fn main() -> int {
+                let result = divmod(17, 5);
+                return result.0 * 10 + result[1];
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("define { i64, i64 } @divmod"), "{}", ir);
+        assert!(ir.contains("insertvalue"));
+        assert!(ir.contains("extractvalue"));
+    }
+
+    #[test]
+    fn test_emit_ir_formatted_print() {
+        let program = analyze(
+            "fn main() -> int {
+                let ratio = 2.0 / 3.0;
+                let label = format(\"ratio = {:.2}\", ratio);
+                println(label);
+                print(\"{} of {} done: {}\", 3, 4, true);
+                return 0;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        for helper in ["albayan_rt_format_float", "albayan_rt_format_int", "albayan_rt_format_bool", "albayan_rt_print_string"] {
+            assert!(ir.contains(&format!("@{}", helper)), "missing {} in\n{}", helper, ir);
+        }
+        assert!(!ir.contains("printf"));
+    }
+
+    #[test]
+    fn test_emit_ir_dyn_trait_dispatch() {
+        let program = analyze(
+            "trait Shape {
+                fn area(&self) -> int;
+                fn scaled(&self, factor: int) -> int;
+            }
+            struct Square { side: int, }
+            struct Rect { w: int, h: int, }
+            impl Shape for Square {
+                fn area(&self) -> int { return self.side * self.side; }
+                fn scaled(&self, factor: int) -> int { return self.area() * factor; }
+            }
+            impl Shape for Rect {
+                fn area(&self) -> int { return self.w * self.h; }
+                fn scaled(&self, factor: int) -> int { return self.w * self.h * factor; }
+            }
+
+            fn measure(shape: &dyn Shape) -> int {
+                return shape.scaled(2) + shape.area();
+            }
+
+            fn main() -> int {
+                let square = Square { side: 3 };
+                let rect = Rect { w: 2, h: 5 };
+                let shape: &dyn Shape = &rect;
+                return measure(&square) + shape.area();
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("@vtable.Shape.Square"), "{}", ir);
+        assert!(ir.contains("@vtable.Shape.Rect"), "{}", ir);
+        assert!(ir.contains("@\"<Square as Shape>::area\""), "{}", ir);
+        assert!(ir.contains("{ ptr, ptr }"), "{}", ir);
+        // `shape.area()` loads its method from the vtable instead of naming it
+        assert!(ir.contains("call i64 %Shape.area"), "{}", ir);
+    }
+
+    #[test]
+    fn test_emit_ir_drop_glue() {
+        let program = analyze(
+            "struct Guard { id: int, }
+            impl Drop for Guard {
+                fn drop(&mut self) { println(\"dropping {}\", self.id); }
+            }
+            struct Pair { left: Guard, right: Guard, }
+
+            fn keep(guard: Guard) -> int { return guard.id; }
+
+            fn main() -> int {
+                let a = Guard { id: 1 };
+                let b = Guard { id: 2 };
+                if b.id > 1 {
+                    let early = Guard { id: 3 };
+                    return early.id;
+                }
+                let kept = keep(a);
+                let pair = Pair { left: Guard { id: 4 }, right: Guard { id: 5 } };
+                return kept + pair.left.id;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("define private void @drop.Guard(ptr"), "{}", ir);
+        assert!(ir.contains("call void @\"<Guard as Drop>::drop\""), "{}", ir);
+        // A pair drops both of its guards
+        let pair_glue = &ir[ir.find("@drop.Pair(ptr").unwrap()..];
+        let pair_glue = &pair_glue[..pair_glue.find("\n}").unwrap()];
+        assert_eq!(pair_glue.matches("call void @drop.Guard").count(), 2, "{}", pair_glue);
+
+        // The early return drops `early`, then `b`, then `a`; moving `a` into `keep` clears its flag
+        let main = &ir[ir.find("define i32 @main").unwrap()..];
+        let early_exit = &main[..main.find("ret i32").unwrap()];
+        let position = |flag: &str| early_exit.find(&format!("load i1, ptr %{}.owned", flag)).unwrap_or(usize::MAX);
+        assert!(position("early") < position("b") && position("b") < position("a"), "{}", main);
+        assert!(main.contains("store i1 false, ptr %a.owned"), "{}", main);
+    }
+
+    #[test]
+    fn test_emit_ir_fixed_size_arrays() {
+        let program = analyze(
+            "fn main() -> int {
+                let mut grid: [int; 4] = [0; 4];
+                let mut i = 0;
+                while i < 4 {
+                    grid[i] = i * i;
+                    i = i + 1;
+                }
+                return grid[3] + grid[i - 1];
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        assert!(ir.contains("repeat.cond"), "{}", ir);
+        assert!(ir.contains("declare void @albayan_rt_bounds_panic(i64, i64)"), "{}", ir);
+        // `grid[i] = ...` and `grid[i - 1]` are checked; the constant `grid[3]` is not
+        let main = &ir[ir.find("define i32 @main").unwrap()..];
+        assert_eq!(main.matches("call void @albayan_rt_bounds_panic").count(), 2, "{}", main);
+    }
+
+    #[test]
+    fn test_emit_ir_list_methods() {
+        let program = analyze(
+            "fn double(x: int) -> int { return x * 2; }
+            fn is_even(x: int) -> bool { return x % 2 == 0; }
+
+            fn main() -> int {
+                let mut items = [1, 2];
+                items.push(3);
+                let last = items.pop();
+                let doubled = items.map(double);
+                let evens = doubled.filter(is_even);
+                if evens.contains(4) {
+                    return evens.len() + last;
+                }
+                return 0;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        let main = &ir[ir.find("define i32 @main").unwrap()..];
+        assert!(main.contains("call ptr @realloc"), "{}", main);
+        assert!(main.contains("call void @albayan_rt_panic"), "{}", main);
+        assert!(main.contains("call i64 @double"), "{}", main);
+        assert!(main.contains("call i1 @is_even"), "{}", main);
+        for block in ["map.body", "filter.keep", "contains.body"] {
+            assert!(main.contains(block), "missing {}: {}", block, main);
+        }
+    }
+
+    #[test]
+    fn test_emit_ir_maps_and_sets() {
+        let program = analyze(
+            "fn main() -> int {
+                let mut ages = { \"ali\": 30 };
+                ages.insert(\"sara\", 25);
+                let mut seen = #{1, 2};
+                if seen.insert(3) && ages.remove(\"ali\") {
+                    return ages.get(\"sara\") + seen.len();
+                }
+                return 0;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        let main = &ir[ir.find("define i32 @main").unwrap()..];
+        assert!(main.contains("call ptr @albayan_rt_map_new(i64 0)"), "{}", main);
+        for call in ["albayan_rt_map_insert", "albayan_rt_map_remove", "albayan_rt_map_get", "albayan_rt_map_len"] {
+            assert!(main.contains(call), "missing {}: {}", call, main);
+        }
+        assert!(main.contains("get.missing"), "{}", main);
+    }
+
+    #[test]
+    fn test_emit_ir_string_methods() {
+        let program = analyze(
+            "fn main() -> int {
+                let line = \"ali,30\";
+                let fields = line.split(\",\");
+                let name = line.slice(0, 3).concat(\"!\");
+                if name.contains(\"ali\") {
+                    return fields.len() + line.len() + \"12\".to_int();
+                }
+                return 0;
+            }",
+        );
+        let ir = LLVMBackend::new(&CompilerOptions::default()).emit_ir(&program).unwrap();
+        let main = &ir[ir.find("define i32 @main").unwrap()..];
+        for call in [
+            "call i64 @albayan_rt_string_split_count",
+            "call void @albayan_rt_string_split_into",
+            "call ptr @albayan_rt_string_slice",
+            "call ptr @albayan_rt_string_concat",
+            "call i8 @albayan_rt_string_contains",
+            "call i64 @albayan_rt_string_len",
+            "call i64 @albayan_rt_string_to_int",
+        ] {
+            assert!(main.contains(call), "missing {}: {}", call, main);
+        }
+    }
+}
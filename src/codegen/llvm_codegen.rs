@@ -2185,7 +2185,7 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
 
         // Error path: call runtime panic function
         self.builder.position_at_end(else_bb);
-        let panic_fn = self.functions.get("albayan_rt_panic")
+        let panic_fn = self.functions.get("albayan_rt_type_panic")
             .ok_or_else(|| anyhow!("Panic function not found"))?;
 
         // Create error message
@@ -2298,12 +2298,12 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
         ], false);
         let list_destroy_fn = self.module.add_function("albayan_rt_list_destroy", list_destroy_fn_type, None);
 
-        // albayan_rt_panic(message_ptr: *const u8, message_len: usize) -> ! (Expert recommendation: Unboxing Safety)
+        // albayan_rt_type_panic(message_ptr: *const u8, message_len: usize) -> ! (Expert recommendation: Unboxing Safety)
         let panic_fn_type = self.context.void_type().fn_type(&[
             i8_ptr_type.into(), // message_ptr
             usize_type.into(),  // message_len
         ], false);
-        let panic_fn = self.module.add_function("albayan_rt_panic", panic_fn_type, None);
+        let panic_fn = self.module.add_function("albayan_rt_type_panic", panic_fn_type, None);
 
         // Additional destroy functions for different types (Expert recommendation: Priority 1)
 
@@ -2398,7 +2398,7 @@ impl<'ctx> LLVMCodeGenerator<'ctx> {
         self.functions.insert("albayan_rt_model_destroy".to_string(), model_destroy_fn);
         self.functions.insert("albayan_rt_tensor_destroy".to_string(), tensor_destroy_fn);
         self.functions.insert("albayan_rt_shape_destroy".to_string(), shape_destroy_fn);
-        self.functions.insert("albayan_rt_panic".to_string(), panic_fn);
+        self.functions.insert("albayan_rt_type_panic".to_string(), panic_fn);
 
         Ok(())
     }
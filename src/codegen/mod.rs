@@ -1,7 +1,8 @@
 //! # Code Generation Module
 //!
 //! This module implements code generation for the AlBayan programming language.
-//! Currently provides a simple text-based code generator as a placeholder.
+//! Provides a simple text-based code generator and, with the `llvm` feature,
+//! a native backend in [`llvm`].
 
 use crate::semantic::{AnnotatedProgram, AnnotatedItem, AnnotatedFunction};
 use crate::CompilerOptions;
@@ -11,8 +12,19 @@ use std::collections::HashMap;
 // pub mod vtable;
 // pub use llvm_codegen::LLVMCodeGenerator;
 
+pub mod cache;
+pub use cache::{CacheKey, CompilationCache};
+
+pub mod link;
+pub use link::{Linker, LinkerFlavor, LinkError};
+
+#[cfg(feature = "llvm")]
+pub mod llvm;
+#[cfg(feature = "llvm")]
+pub use llvm::LLVMBackend;
+
 // نظام تعدد الأشكال الديناميكي - الأولوية القصوى للخبير
-pub mod dyn_trait_system;
+pub use crate::semantic::dyn_trait_system;
 pub use dyn_trait_system::{VTableManager, VTable, FatPointer, DynTraitCodeGenerator};
 
 /// Advanced code generator with optimizations
@@ -91,6 +103,7 @@ impl SimpleCodeGenerator {
 
         // Process all items
         for item in &program.items {
+            self.options.cancellation.check()?;
             match item {
                 AnnotatedItem::Function(func) => {
                     output.push_str(&self.generate_function_code(func)?);
@@ -104,6 +117,15 @@ impl SimpleCodeGenerator {
                 AnnotatedItem::Rule(_) => {
                     output.push_str("// Rule definition\n");
                 }
+                AnnotatedItem::Fact(_) => {
+                    output.push_str("// Fact definition\n");
+                }
+                AnnotatedItem::Concept(_) => {
+                    output.push_str("// Concept definition\n");
+                }
+                AnnotatedItem::Constraint(_) => {
+                    output.push_str("// Constraint definition\n");
+                }
                 AnnotatedItem::Enum(_) => {
                     output.push_str("// Enum definition\n");
                 }
@@ -116,6 +138,9 @@ impl SimpleCodeGenerator {
                 AnnotatedItem::Using(_) => {
                     output.push_str("// Using declaration\n");  // NEWLY ADDED: Expert fix for using statements
                 }
+                AnnotatedItem::Const(_) => {
+                    output.push_str("// Constant definition\n");
+                }
             }
         }
 
@@ -252,6 +277,9 @@ pub enum CodeGenError {
 
     #[error("Type error: {0}")]
     TypeError(String),
+
+    #[error(transparent)]
+    Cancelled(#[from] crate::cancellation::Cancelled),
 }
 
 #[cfg(test)]
@@ -0,0 +1,108 @@
+//! # Example Gallery
+//!
+//! Runnable programs in `examples/gallery/` that demonstrate the language's
+//! logic, AI, natural language and rendering features. They are built into
+//! the compiler, so `albayan examples list` and `albayan examples run <name>`
+//! work wherever it is installed, and `albayan examples test` (and the test
+//! suite) runs every one of them so the examples keep up with the language.
+//!
+//! The first line of an example is a `//` comment describing it. Goals in
+//! `// ?- goal.` comment lines are answered once the example's facts and
+//! rules are loaded, so logic examples show their results.
+
+use crate::diagnostics::{Diagnostic, DiagnosticRenderer};
+use crate::repl::{Session, QUERY_PREFIX};
+use crate::{Compiler, CompilerOptions};
+
+/// An example program of the gallery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Example {
+    pub name: &'static str,
+    pub source: &'static str,
+}
+
+macro_rules! example {
+    ($name:literal) => {
+        Example {
+            name: $name,
+            source: include_str!(concat!("../examples/gallery/", $name, ".ab")),
+        }
+    };
+}
+
+/// Every example, in the order they are listed
+pub const EXAMPLES: &[Example] = &[
+    example!("family_logic"),
+    example!("shape_inference"),
+    example!("related_words"),
+    example!("canvas_shapes"),
+];
+
+/// Find an example by name
+pub fn find(name: &str) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|example| example.name == name)
+}
+
+impl Example {
+    /// The text of the example's first comment line
+    pub fn description(&self) -> &'static str {
+        self.source
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix("//"))
+            .map_or("", str::trim)
+    }
+
+    /// Goals of the example's `// ?- goal.` comment lines
+    pub fn queries(&self) -> Vec<&'static str> {
+        self.source
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("//")?.trim().strip_prefix(QUERY_PREFIX))
+            .map(str::trim)
+            .collect()
+    }
+
+    /// Compile the example like `albayan run` does, then answer its queries.
+    /// Returns the lines to show, or the rendered diagnostic of the failure.
+    pub fn run(&self, options: &CompilerOptions) -> Result<Vec<String>, String> {
+        let file_name = format!("examples/gallery/{}.ab", self.name);
+        Compiler::with_options(options.clone())
+            .compile_string(self.source)
+            .map_err(|error| DiagnosticRenderer::new(&file_name, self.source).render(&Diagnostic::from(&error)))?;
+
+        let queries = self.queries();
+        if queries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut session = Session::new(options.clone());
+        if let Some(error) = session.execute(self.source).error {
+            return Err(error);
+        }
+        let mut lines = Vec::new();
+        for query in queries {
+            let result = session.execute(&format!("{} {}", QUERY_PREFIX, query));
+            if let Some(error) = result.error {
+                return Err(error);
+            }
+            lines.push(format!("{} {}", QUERY_PREFIX, query));
+            lines.extend(result.outputs.into_iter().map(|output| format!("   {}", output)));
+        }
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples_are_described_and_found() {
+        let names: Vec<&str> = EXAMPLES.iter().map(|example| example.name).collect();
+        for (index, example) in EXAMPLES.iter().enumerate() {
+            assert!(!example.description().is_empty(), "{} has no description", example.name);
+            assert!(!names[..index].contains(&example.name), "{} is listed twice", example.name);
+        }
+        assert_eq!(find("family_logic").unwrap().queries(), vec!["ancestor(\"ali\", Who).", "parent(\"omar\", Child)."]);
+        assert!(find("missing").is_none());
+    }
+}
@@ -0,0 +1,1471 @@
+//! # Interpreter
+//!
+//! A tree-walking evaluator for function bodies, used by `albayan test` to
+//! run test functions without generating code. It evaluates the parsed
+//! program after semantic analysis has accepted it, and covers the
+//! expression language: integers, floats, booleans, characters, strings,
+//! arrays, tuples, structs and enums, with `let`, assignment, `if`, `while`,
+//! `for` over arrays and strings, `match`, function calls and the methods of
+//! `impl` blocks. Maps keep their entries in insertion order. A `query`
+//! expression solves its goals against the program's facts and rules, which
+//! are loaded into a [`LogicEngine`] on the first query. Logic statements,
+//! sets, lambdas and `async` are not supported and stop the call with a
+//! [`Panic`].
+//!
+//! Failures are panics: `panic(message)`, a failed `assert_eq`, `assert_ne`
+//! or `assert_true`, integer overflow, division by zero and out-of-bounds
+//! indexing all end the call with the message and the source location.
+//! Recursion is limited to [`MAX_CALL_DEPTH`] calls and loops plus calls to
+//! [`MAX_STEPS`] steps, so runaway tests fail instead of hanging. Output of
+//! `print` and `println` is captured rather than written to stdout.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::modules::graph::ModuleGraph;
+use crate::parser::ast::{
+    BinaryExpression, BinaryOperator, Block, Expression, FunctionDecl, IfStatement, Item, Literal, LogicTerm,
+    MapExpression, MatchStatement, Parameter, Pattern, Program, QueryStatement, Span, Statement, Type, UnaryOperator,
+};
+use crate::runtime::logic_engine::binding_text;
+use crate::runtime::{FactValue, LogicEngine};
+use crate::semantic::format_string::{parse_format_string, FormatPiece};
+
+/// Deepest call nesting before a call panics with a stack overflow
+pub const MAX_CALL_DEPTH: usize = 128;
+
+/// Stack of the thread a top-level call runs on, enough for
+/// [`MAX_CALL_DEPTH`] nested calls in a debug build
+const CALL_STACK_BYTES: usize = 64 * 1024 * 1024;
+
+/// Loop iterations and calls one top-level call may take
+pub const MAX_STEPS: u64 = 10_000_000;
+
+/// A runtime value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Unit,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    String(String),
+    Array(Vec<Value>),
+    Tuple(Vec<Value>),
+    /// Entries in insertion order
+    Map(Vec<(Value, Value)>),
+    Struct {
+        name: String,
+        fields: Vec<(String, Value)>,
+    },
+    /// `name` is the qualified variant, e.g. `Shape::Circle`
+    Enum {
+        name: String,
+        fields: Vec<Value>,
+    },
+}
+
+/// Why a call stopped early
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{message}")]
+pub struct Panic {
+    pub message: String,
+    /// Where in the source the panic happened, if known
+    pub span: Option<Span>,
+    /// Prefix of the module whose source `span` is in; `None` for the
+    /// unprefixed (entry) module
+    pub module: Option<String>,
+    /// The functions the panic left, innermost first
+    pub backtrace: Vec<String>,
+}
+
+/// How evaluation leaves a block other than by finishing it
+enum Flow {
+    Return(Value),
+    /// A panic, and whether its module has been recorded yet
+    Panic(Panic, bool),
+}
+
+type Exec<T> = Result<T, Flow>;
+
+/// A function with the prefix of the module that declares it
+type Callee<'a> = (Option<&'a str>, &'a FunctionDecl);
+
+/// One step of the path from a variable to the value an assignment changes
+enum PlaceStep {
+    Field(String),
+    Index(usize),
+}
+
+/// The locals of one active call
+struct Frame<'a> {
+    /// Prefix of the module of the running function
+    module: Option<&'a str>,
+    /// Innermost last
+    scopes: Vec<HashMap<String, Value>>,
+}
+
+/// The logic engine of an interpreter
+enum Logic<'a> {
+    /// Not needed yet; the program's knowledge is loaded on first use
+    Unloaded,
+    Loaded(LogicEngine),
+    /// An engine that outlives the interpreter, with the knowledge loaded
+    Shared(&'a mut LogicEngine),
+}
+
+/// Evaluates the functions of one or more parsed modules
+pub struct Interpreter<'a> {
+    /// Functions by qualified name; those of the entry module are unqualified
+    functions: HashMap<String, Callee<'a>>,
+    /// Methods by type name and method name
+    methods: HashMap<(String, String), Callee<'a>>,
+    /// Constants, qualified like functions
+    constants: HashMap<String, &'a Expression>,
+    /// Names each module's `using` declarations bind, mapped to qualified paths
+    imports: HashMap<Option<&'a str>, HashMap<String, String>>,
+    /// Relations, facts, rules, concepts and constraints of every module
+    knowledge: Vec<&'a Item>,
+    /// The knowledge `query` expressions solve against and `assert` and
+    /// `retract` statements change
+    logic: Logic<'a>,
+    /// Innermost call last
+    frames: Vec<Frame<'a>>,
+    steps: u64,
+    output: String,
+}
+
+fn panic_at<T>(message: impl Into<String>, span: Option<Span>) -> Exec<T> {
+    Err(Flow::Panic(
+        Panic {
+            message: message.into(),
+            span,
+            module: None,
+            backtrace: Vec::new(),
+        },
+        false,
+    ))
+}
+
+/// Run `body` on a thread with [`CALL_STACK_BYTES`] of stack, so deep
+/// recursion reaches [`MAX_CALL_DEPTH`] before the caller's stack runs out
+fn on_call_stack<T: Send>(body: impl FnOnce() -> T + Send) -> T {
+    std::thread::scope(|scope| {
+        let worker = std::thread::Builder::new()
+            .stack_size(CALL_STACK_BYTES)
+            .spawn_scoped(scope, body)
+            .expect("failed to spawn the interpreter thread");
+        worker.join().unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+    })
+}
+
+fn unsupported<T>(what: &str, span: Option<Span>) -> Exec<T> {
+    panic_at(format!("{} is not supported by the interpreter", what), span)
+}
+
+impl Value {
+    fn type_name(&self) -> &str {
+        match self {
+            Value::Unit => "()",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::Char(_) => "char",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Tuple(_) => "tuple",
+            Value::Map(_) => "map",
+            Value::Struct { name, .. } => name,
+            Value::Enum { name, .. } => name.split("::").next().unwrap_or(name),
+        }
+    }
+
+    fn from_literal(literal: &Literal) -> Value {
+        match literal {
+            Literal::Boolean(value) => Value::Bool(*value),
+            Literal::Integer(value) => Value::Int(*value),
+            Literal::Float(value) => Value::Float(*value),
+            Literal::String(value) => Value::String(value.clone()),
+            Literal::Char(value) => Value::Char(*value),
+            Literal::Null => Value::Unit,
+            Literal::Tensor(rows) => Value::Array(
+                rows.iter()
+                    .map(|row| Value::Array(row.iter().map(|x| Value::Float(*x)).collect()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn list(f: &mut fmt::Formatter<'_>, values: &[Value]) -> fmt::Result {
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", value)?;
+            }
+            Ok(())
+        }
+        match self {
+            Value::Unit => write!(f, "()"),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Char(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Array(values) => {
+                write!(f, "[")?;
+                list(f, values)?;
+                write!(f, "]")
+            }
+            Value::Tuple(values) => {
+                write!(f, "(")?;
+                list(f, values)?;
+                write!(f, ")")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    write!(f, "{}{}: {}", if index > 0 { ", " } else { "" }, key, value)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Struct { name, fields } => {
+                write!(f, "{} {{ ", name)?;
+                for (index, (field, value)) in fields.iter().enumerate() {
+                    write!(f, "{}{}: {}", if index > 0 { ", " } else { "" }, field, value)?;
+                }
+                write!(f, " }}")
+            }
+            Value::Enum { name, fields } if fields.is_empty() => write!(f, "{}", name),
+            Value::Enum { name, fields } => {
+                write!(f, "{}(", name)?;
+                list(f, fields)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl<'a> Interpreter<'a> {
+    /// An interpreter for a single program
+    pub fn new(program: &'a Program) -> Self {
+        Self::with_modules([(None, program, HashMap::new())])
+    }
+
+    /// An interpreter for a single program whose queries, asserts and
+    /// retracts use `logic`, which already holds the program's knowledge, so
+    /// that what one run asserts is there for the next
+    pub fn with_logic(program: &'a Program, logic: &'a mut LogicEngine) -> Self {
+        let mut interpreter = Self::new(program);
+        interpreter.logic = Logic::Shared(logic);
+        interpreter
+    }
+
+    /// An interpreter for every module of `graph`, whose functions call
+    /// each other through their `using` declarations as in a build
+    pub fn for_graph(graph: &'a ModuleGraph) -> Self {
+        Self::with_modules(
+            graph
+                .modules()
+                .iter()
+                .map(|module| (graph.prefix(module), &module.program, module.scope())),
+        )
+    }
+
+    fn with_modules<I>(modules: I) -> Self
+    where
+        I: IntoIterator<Item = (Option<&'a str>, &'a Program, HashMap<String, String>)>,
+    {
+        let mut interpreter = Self {
+            functions: HashMap::new(),
+            methods: HashMap::new(),
+            constants: HashMap::new(),
+            imports: HashMap::new(),
+            knowledge: Vec::new(),
+            logic: Logic::Unloaded,
+            frames: Vec::new(),
+            steps: 0,
+            output: String::new(),
+        };
+        for (prefix, program, imports) in modules {
+            let qualify = |name: &str| match prefix {
+                Some(prefix) => format!("{}::{}", prefix, name),
+                None => name.to_string(),
+            };
+            for item in &program.items {
+                match item {
+                    Item::Function(function) => {
+                        interpreter
+                            .functions
+                            .insert(qualify(&function.name), (prefix, function));
+                    }
+                    Item::Impl(block) => {
+                        for method in &block.methods {
+                            let key = (block.type_name.clone(), method.name.clone());
+                            interpreter.methods.insert(key, (prefix, method));
+                        }
+                    }
+                    Item::Const(constant) => {
+                        interpreter.constants.insert(qualify(&constant.name), &constant.value);
+                    }
+                    Item::Relation(_) | Item::Fact(_) | Item::Rule(_) | Item::Concept(_) | Item::Constraint(_) => {
+                        interpreter.knowledge.push(item);
+                    }
+                    _ => {}
+                }
+            }
+            interpreter.imports.insert(prefix, imports);
+        }
+        interpreter
+    }
+
+    /// Call the function `name` with `arguments`
+    pub fn call(&mut self, name: &str, arguments: Vec<Value>) -> Result<Value, Panic> {
+        self.steps = 0;
+        self.frames.clear();
+        let function = self.lookup(&self.functions, name).ok_or_else(|| Panic {
+            message: format!("no function named `{}`", name),
+            span: None,
+            module: None,
+            backtrace: Vec::new(),
+        })?;
+        match on_call_stack(|| self.call_function(function, arguments, None)) {
+            Ok(value) | Err(Flow::Return(value)) => Ok(value),
+            Err(Flow::Panic(panic, _)) => Err(panic),
+        }
+    }
+
+    /// Run `statements` outside any function, as a REPL line, with
+    /// `variables` in scope; the variables they declare or assign are left in
+    /// it. The value is that of the last statement.
+    pub fn run_statements(
+        &mut self,
+        statements: &'a [Statement],
+        variables: &mut HashMap<String, Value>,
+    ) -> Result<Value, Panic> {
+        self.steps = 0;
+        self.frames.clear();
+        self.frames.push(Frame {
+            module: None,
+            scopes: vec![std::mem::take(variables)],
+        });
+        let result = on_call_stack(|| self.exec_statements(statements));
+        if let Some(mut frame) = self.frames.pop() {
+            *variables = frame.scopes.swap_remove(0);
+        }
+        match result {
+            Ok(value) | Err(Flow::Return(value)) => Ok(value),
+            Err(Flow::Panic(panic, _)) => Err(panic),
+        }
+    }
+
+    /// Output printed so far, which is then cleared
+    pub fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+
+    /// What `name` refers to in the running function's module: an item of
+    /// the module, an item reached through its imports, or a qualified name
+    fn lookup<T: Copy>(&self, table: &HashMap<String, T>, name: &str) -> Option<T> {
+        let module = self.frames.last().and_then(|frame| frame.module);
+        let local = module.map(|prefix| format!("{}::{}", prefix, name));
+        let imported = self.imports.get(&module).and_then(|imports| {
+            let (head, rest) = name
+                .split_once("::")
+                .map_or((name, None), |(head, rest)| (head, Some(rest)));
+            let target = imports.get(head)?;
+            Some(rest.map_or_else(|| target.clone(), |rest| format!("{}::{}", target, rest)))
+        });
+        let found = local
+            .iter()
+            .chain(&imported)
+            .map(String::as_str)
+            .chain([name])
+            .find_map(|candidate| table.get(candidate).copied());
+        found
+    }
+
+    fn step(&mut self, span: Option<Span>) -> Exec<()> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return panic_at(
+                format!("exceeded {} steps; is there an infinite loop?", MAX_STEPS),
+                span,
+            );
+        }
+        Ok(())
+    }
+
+    fn call_function(&mut self, function: Callee<'a>, arguments: Vec<Value>, span: Option<Span>) -> Exec<Value> {
+        self.invoke(function, None, arguments, span).map(|(value, _)| value)
+    }
+
+    /// Run a function and return its value with `self` as the body left it
+    fn invoke(
+        &mut self,
+        (module, function): Callee<'a>,
+        receiver: Option<Value>,
+        arguments: Vec<Value>,
+        span: Option<Span>,
+    ) -> Exec<(Value, Option<Value>)> {
+        self.step(span)?;
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return panic_at(
+                format!("stack overflow: more than {} nested calls", MAX_CALL_DEPTH),
+                span,
+            );
+        }
+
+        let mut scope = HashMap::new();
+        let mut arguments = arguments.into_iter();
+        for parameter in &function.parameters {
+            match parameter {
+                Parameter::Regular { name, .. } => {
+                    let Some(value) = arguments.next() else {
+                        return panic_at(format!("missing argument `{}` of `{}`", name, function.name), span);
+                    };
+                    scope.insert(name.clone(), value);
+                }
+                Parameter::SelfValue | Parameter::SelfRef | Parameter::SelfMutRef => {
+                    let Some(value) = receiver.clone() else {
+                        return panic_at(format!("`{}` needs a receiver", function.name), span);
+                    };
+                    scope.insert("self".to_string(), value);
+                }
+            }
+        }
+
+        self.frames.push(Frame {
+            module,
+            scopes: vec![scope],
+        });
+        let result = self.exec_block(&function.body);
+        let receiver = self
+            .frames
+            .pop()
+            .and_then(|mut frame| frame.scopes.swap_remove(0).remove("self"));
+        match result {
+            Ok(value) | Err(Flow::Return(value)) => Ok((value, receiver)),
+            Err(Flow::Panic(mut panic, located)) => {
+                // The innermost call a panic leaves is the one its span is in
+                if !located {
+                    panic.module = module.map(str::to_string);
+                }
+                panic.backtrace.push(match module {
+                    Some(prefix) => format!("{}::{}", prefix, function.name),
+                    None => function.name.clone(),
+                });
+                Err(Flow::Panic(panic, true))
+            }
+        }
+    }
+
+    fn scopes(&mut self) -> &mut Vec<HashMap<String, Value>> {
+        if self.frames.is_empty() {
+            self.frames.push(Frame {
+                module: None,
+                scopes: vec![HashMap::new()],
+            });
+        }
+        &mut self.frames.last_mut().expect("a frame was just ensured").scopes
+    }
+
+    fn declare(&mut self, name: &str, value: Value) {
+        let scopes = self.scopes();
+        if scopes.is_empty() {
+            scopes.push(HashMap::new());
+        }
+        scopes
+            .last_mut()
+            .expect("a scope was just ensured")
+            .insert(name.to_string(), value);
+    }
+
+    fn variable(&mut self, name: &str) -> Option<&mut Value> {
+        self.scopes().iter_mut().rev().find_map(|scope| scope.get_mut(name))
+    }
+
+    fn exec_block(&mut self, block: &'a Block) -> Exec<Value> {
+        self.scopes().push(HashMap::new());
+        let result = self.exec_statements(&block.statements);
+        self.scopes().pop();
+        result
+    }
+
+    /// Run statements; the value of the last one is the block's value
+    fn exec_statements(&mut self, statements: &'a [Statement]) -> Exec<Value> {
+        let mut value = Value::Unit;
+        for statement in statements {
+            value = self.exec_statement(statement)?;
+        }
+        Ok(value)
+    }
+
+    fn exec_statement(&mut self, statement: &'a Statement) -> Exec<Value> {
+        match statement {
+            Statement::Expression(stmt) => {
+                let value = self.eval(&stmt.expression)?;
+                // A `;` discards the value
+                Ok(if stmt.semicolon { Value::Unit } else { value })
+            }
+            Statement::Let(stmt) => {
+                let value = match &stmt.initializer {
+                    Some(initializer) => self.eval(initializer)?,
+                    None => Value::Unit,
+                };
+                match &stmt.pattern {
+                    Some(pattern) => {
+                        let mut bindings = Vec::new();
+                        if !Self::match_pattern(pattern, &value, &mut bindings) {
+                            return panic_at(format!("`{}` does not match the `let` pattern", value), Some(stmt.span));
+                        }
+                        for (name, value) in bindings {
+                            self.declare(&name, value);
+                        }
+                    }
+                    None => self.declare(&stmt.name, value),
+                }
+                Ok(Value::Unit)
+            }
+            Statement::Return(stmt) => {
+                let value = match &stmt.value {
+                    Some(value) => self.eval(value)?,
+                    None => Value::Unit,
+                };
+                Err(Flow::Return(value))
+            }
+            Statement::If(stmt) => self.exec_if(stmt),
+            Statement::While(stmt) => {
+                while self.condition(&stmt.condition)? {
+                    self.step(Some(stmt.span))?;
+                    self.exec_block(&stmt.body)?;
+                }
+                Ok(Value::Unit)
+            }
+            Statement::For(stmt) => {
+                let items = match self.eval(&stmt.iterable)? {
+                    Value::Array(items) => items,
+                    Value::String(text) => text.chars().map(Value::Char).collect(),
+                    other => return panic_at(format!("cannot iterate over a {}", other.type_name()), Some(stmt.span)),
+                };
+                for item in items {
+                    self.step(Some(stmt.span))?;
+                    self.scopes().push(HashMap::from([(stmt.variable.clone(), item)]));
+                    let result = self.exec_block(&stmt.body);
+                    self.scopes().pop();
+                    result?;
+                }
+                Ok(Value::Unit)
+            }
+            Statement::Match(stmt) => self.exec_match(stmt),
+            Statement::Block(block) => self.exec_block(block),
+            Statement::Query(stmt) => unsupported("a logic query", Some(stmt.span)),
+            Statement::Assert(stmt) => self.update_fact(&stmt.relation, &stmt.args, false, stmt.span),
+            Statement::Retract(stmt) => self.update_fact(&stmt.relation, &stmt.args, true, stmt.span),
+            Statement::Semantic(_) => unsupported("a semantic block", None),
+        }
+    }
+
+    fn exec_if(&mut self, stmt: &'a IfStatement) -> Exec<Value> {
+        if self.condition(&stmt.condition)? {
+            self.exec_block(&stmt.then_block)
+        } else if let Some(else_block) = &stmt.else_block {
+            self.exec_block(else_block)
+        } else {
+            Ok(Value::Unit)
+        }
+    }
+
+    fn exec_match(&mut self, stmt: &'a MatchStatement) -> Exec<Value> {
+        let value = self.eval(&stmt.expression)?;
+        for arm in &stmt.arms {
+            let mut bindings = Vec::new();
+            if !Self::match_pattern(&arm.pattern, &value, &mut bindings) {
+                continue;
+            }
+            self.scopes().push(bindings.into_iter().collect());
+            let guard = match &arm.guard {
+                Some(guard) => self.condition(guard),
+                None => Ok(true),
+            };
+            let result = match guard {
+                Ok(true) => self.exec_block(&arm.body).map(Some),
+                Ok(false) => Ok(None),
+                Err(flow) => Err(flow),
+            };
+            self.scopes().pop();
+            if let Some(value) = result? {
+                return Ok(value);
+            }
+        }
+        panic_at(format!("no match arm matches `{}`", value), Some(stmt.span))
+    }
+
+    fn match_pattern(pattern: &Pattern, value: &Value, bindings: &mut Vec<(String, Value)>) -> bool {
+        match (pattern, value) {
+            (Pattern::Wildcard, _) => true,
+            (Pattern::Literal(literal), value) => &Value::from_literal(literal) == value,
+            // A bare variant name matches that variant rather than binding
+            (Pattern::Identifier(name), Value::Enum { name: variant, fields })
+                if fields.is_empty() && variant.rsplit("::").next() == Some(name.as_str()) =>
+            {
+                true
+            }
+            (Pattern::Identifier(name), value) => {
+                bindings.push((name.clone(), value.clone()));
+                true
+            }
+            (Pattern::Tuple(patterns), Value::Tuple(values)) => {
+                patterns.len() == values.len()
+                    && patterns
+                        .iter()
+                        .zip(values)
+                        .all(|(pattern, value)| Self::match_pattern(pattern, value, bindings))
+            }
+            (Pattern::Struct(name, patterns), Value::Struct { name: actual, fields }) => {
+                name == actual
+                    && patterns.iter().all(|(field, pattern)| {
+                        fields
+                            .iter()
+                            .find(|(name, _)| name == field)
+                            .is_some_and(|(_, value)| Self::match_pattern(pattern, value, bindings))
+                    })
+            }
+            (Pattern::Enum(name, patterns), Value::Enum { name: actual, fields }) => {
+                let same_variant = name == actual || name.rsplit("::").next() == actual.rsplit("::").next();
+                let patterns = patterns.as_deref().unwrap_or(&[]);
+                same_variant
+                    && patterns.len() == fields.len()
+                    && patterns
+                        .iter()
+                        .zip(fields)
+                        .all(|(pattern, value)| Self::match_pattern(pattern, value, bindings))
+            }
+            _ => false,
+        }
+    }
+
+    fn condition(&mut self, expression: &'a Expression) -> Exec<bool> {
+        match self.eval(expression)? {
+            Value::Bool(value) => Ok(value),
+            other => panic_at(
+                format!("expected a bool condition, found {}", other.type_name()),
+                expression.span(),
+            ),
+        }
+    }
+
+    fn eval(&mut self, expression: &'a Expression) -> Exec<Value> {
+        let span = expression.span();
+        match expression {
+            Expression::Literal(literal) => Ok(Value::from_literal(literal)),
+            Expression::Identifier(name) => {
+                if let Some(value) = self.variable(name) {
+                    return Ok(value.clone());
+                }
+                match self.lookup(&self.constants, name) {
+                    Some(constant) => self.eval(constant),
+                    None => panic_at(format!("undefined variable `{}`", name), span),
+                }
+            }
+            Expression::Binary(binary) => self.eval_binary(binary),
+            Expression::Unary(unary) => {
+                let operand = self.eval(&unary.operand)?;
+                match (&unary.operator, operand) {
+                    (UnaryOperator::Not, Value::Bool(value)) => Ok(Value::Bool(!value)),
+                    (UnaryOperator::Negate, Value::Int(value)) => match value.checked_neg() {
+                        Some(value) => Ok(Value::Int(value)),
+                        None => panic_at("attempt to negate with overflow", span),
+                    },
+                    (UnaryOperator::Negate, Value::Float(value)) => Ok(Value::Float(-value)),
+                    // References are plain values here
+                    (
+                        UnaryOperator::Reference | UnaryOperator::MutableReference | UnaryOperator::Dereference,
+                        value,
+                    ) => Ok(value),
+                    (operator, value) => {
+                        panic_at(format!("cannot apply {:?} to a {}", operator, value.type_name()), span)
+                    }
+                }
+            }
+            Expression::Call(call) => match call.callee.as_ref() {
+                Expression::Identifier(name) => {
+                    let mut arguments = Vec::with_capacity(call.arguments.len());
+                    for argument in &call.arguments {
+                        arguments.push(self.eval(argument)?);
+                    }
+                    self.call_named(name, arguments, span)
+                }
+                Expression::FieldAccess(access) => {
+                    let mut arguments = Vec::with_capacity(call.arguments.len());
+                    for argument in &call.arguments {
+                        arguments.push(self.eval(argument)?);
+                    }
+                    self.eval_method_call(&access.object, &access.field, arguments, span)
+                }
+                _ => unsupported("calling a computed function", span),
+            },
+            Expression::FieldAccess(access) => {
+                let object = self.eval(&access.object)?;
+                Self::field(object, &access.field, span)
+            }
+            Expression::Index(index) => {
+                let object = self.eval(&index.object)?;
+                let position = self.eval(&index.index)?;
+                Self::index(object, position, span)
+            }
+            Expression::Array(array) => {
+                let mut values = Vec::with_capacity(array.elements.len());
+                for element in &array.elements {
+                    values.push(self.eval(element)?);
+                }
+                Ok(Value::Array(values))
+            }
+            Expression::ArrayRepeat(repeat) => {
+                let value = self.eval(&repeat.value)?;
+                match self.eval(&repeat.count)? {
+                    Value::Int(count) if count >= 0 => Ok(Value::Array(vec![value; count as usize])),
+                    other => panic_at(format!("invalid array length `{}`", other), span),
+                }
+            }
+            Expression::Tuple(tuple) => {
+                let mut values = Vec::with_capacity(tuple.elements.len());
+                for element in &tuple.elements {
+                    values.push(self.eval(element)?);
+                }
+                Ok(Value::Tuple(values))
+            }
+            Expression::Struct(literal) => {
+                let mut fields = Vec::with_capacity(literal.fields.len());
+                for (name, value) in &literal.fields {
+                    fields.push((name.clone(), self.eval(value)?));
+                }
+                Ok(Value::Struct {
+                    name: literal.name.clone(),
+                    fields,
+                })
+            }
+            Expression::Enum(variant) => {
+                let mut fields = Vec::new();
+                for field in variant.fields.iter().flatten() {
+                    fields.push(self.eval(field)?);
+                }
+                // `Point::new(1, 2)` and `math::square(2)` parse like variants
+                if variant.fields.is_some() {
+                    let key = (variant.enum_name.clone(), variant.variant_name.clone());
+                    let qualified = format!("{}::{}", variant.enum_name, variant.variant_name);
+                    let function = self
+                        .methods
+                        .get(&key)
+                        .copied()
+                        .or_else(|| self.lookup(&self.functions, &qualified));
+                    if let Some(function) = function {
+                        return self.call_function(function, fields, span);
+                    }
+                }
+                Ok(Value::Enum {
+                    name: format!("{}::{}", variant.enum_name, variant.variant_name),
+                    fields,
+                })
+            }
+            Expression::If(stmt) => self.exec_if(stmt),
+            Expression::Match(stmt) => self.exec_match(stmt),
+            Expression::Map(map) => self.eval_map(map),
+            Expression::Query(query) => self.eval_query(query),
+            Expression::Set(_) => unsupported("a set", span),
+            Expression::Lambda(_) => unsupported("a lambda", span),
+            Expression::Async(_) | Expression::Await(_) => unsupported("async code", span),
+        }
+    }
+
+    /// `{ key: value, ... }`; a repeated key keeps its last value
+    fn eval_map(&mut self, map: &'a MapExpression) -> Exec<Value> {
+        let mut entries = Vec::with_capacity(map.entries.len());
+        for (key, value) in &map.entries {
+            let key = self.eval(key)?;
+            let value = self.eval(value)?;
+            Self::map_insert(&mut entries, key, value);
+        }
+        Ok(Value::Map(entries))
+    }
+
+    /// Replace the value of `key`, or add it last; returns whether it is new
+    fn map_insert(entries: &mut Vec<(Value, Value)>, key: Value, value: Value) -> bool {
+        match entries.iter_mut().find(|(existing, _)| *existing == key) {
+            Some(entry) => {
+                entry.1 = value;
+                false
+            }
+            None => {
+                entries.push((key, value));
+                true
+            }
+        }
+    }
+
+    /// The logic engine, loading the program's knowledge into it the first
+    /// time it is needed
+    fn logic(&mut self, span: Option<Span>) -> Exec<&mut LogicEngine> {
+        if let Logic::Unloaded = self.logic {
+            let mut logic = LogicEngine::new();
+            if let Err(error) = logic.load_items(self.knowledge.iter().copied()) {
+                return panic_at(format!("cannot load the knowledge base: {}", error), span);
+            }
+            self.logic = Logic::Loaded(logic);
+        }
+        match &mut self.logic {
+            Logic::Loaded(logic) => Ok(logic),
+            Logic::Shared(logic) => Ok(logic),
+            Logic::Unloaded => unreachable!("the knowledge base was just loaded"),
+        }
+    }
+
+    /// `assert relation(args);` or, with `retract`, `retract relation(args);`
+    fn update_fact(&mut self, relation: &str, args: &'a [Expression], retract: bool, span: Span) -> Exec<Value> {
+        let span = Some(span);
+        let individuals = self.individual_args(relation);
+        let mut values = Vec::with_capacity(args.len());
+        for (index, arg) in args.iter().enumerate() {
+            let value = match arg {
+                Expression::Identifier(name) if self.variable(name).is_none() && self.lookup(&self.constants, name).is_none() => {
+                    FactValue::Atom(name.clone())
+                }
+                _ => match self.eval(arg)? {
+                    Value::Int(value) => FactValue::Int(value),
+                    Value::Float(value) => FactValue::Float(value),
+                    Value::Bool(value) => FactValue::Bool(value),
+                    Value::String(text) if individuals.get(index).copied().unwrap_or(false) => FactValue::Atom(text),
+                    Value::String(text) => FactValue::String(text),
+                    other => return panic_at(format!("a {} cannot be a fact argument", other.type_name()), arg.span()),
+                },
+            };
+            values.push(value);
+        }
+
+        let logic = self.logic(span)?;
+        let updated = if retract {
+            logic.retract_values(relation, &values)
+        } else {
+            logic.assert_values(relation, &values)
+        };
+        match updated {
+            Ok(()) => Ok(Value::Unit),
+            Err(error) => panic_at(format!("cannot {} a `{}` fact: {}", if retract { "retract" } else { "assert" }, relation, error), span),
+        }
+    }
+
+    /// Whether each argument of `relation` names an individual of a concept,
+    /// whose string values are stored as constants like in `fact` declarations
+    fn individual_args(&self, relation: &str) -> Vec<bool> {
+        if relation == "is_a" {
+            return vec![true, true];
+        }
+        let is_concept = |name: &str| {
+            self.knowledge.iter().any(|item| matches!(item, Item::Concept(concept) if concept.name == name))
+        };
+        self.knowledge
+            .iter()
+            .find_map(|item| match item {
+                Item::Relation(decl) if decl.name == relation => Some(
+                    decl.arg_types
+                        .iter()
+                        .map(|ty| matches!(ty, Type::Named(path) if path.segments.len() == 1 && is_concept(&path.segments[0])))
+                        .collect(),
+                ),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// `query goal(...)`: a map per solution from each variable of the goals
+    /// to its value as text, in the order the engine finds them
+    fn eval_query(&mut self, query: &'a QueryStatement) -> Exec<Value> {
+        let span = Some(query.span);
+        let logic = self.logic(span)?;
+
+        let goals: Vec<String> = query.goals.iter().map(LogicTerm::to_string).collect();
+        let solutions = match logic.solve_query(&goals.join(", ")) {
+            Ok(solutions) => solutions,
+            Err(error) => return panic_at(format!("query failed: {}", error), span),
+        };
+        Ok(Value::Array(
+            solutions
+                .into_iter()
+                .map(|solution| {
+                    let sorted: BTreeMap<String, String> = solution.into_iter().collect();
+                    Value::Map(
+                        sorted
+                            .into_iter()
+                            .map(|(variable, value)| (Value::String(variable), Value::String(binding_text(&value))))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        ))
+    }
+
+    fn eval_binary(&mut self, binary: &'a BinaryExpression) -> Exec<Value> {
+        let span = Some(binary.span);
+        let compound = match binary.operator {
+            BinaryOperator::Assign => {
+                let value = self.eval(&binary.right)?;
+                self.assign(&binary.left, value)?;
+                return Ok(Value::Unit);
+            }
+            BinaryOperator::AddAssign => Some(BinaryOperator::Add),
+            BinaryOperator::SubtractAssign => Some(BinaryOperator::Subtract),
+            BinaryOperator::MultiplyAssign => Some(BinaryOperator::Multiply),
+            BinaryOperator::DivideAssign => Some(BinaryOperator::Divide),
+            BinaryOperator::And | BinaryOperator::Or => {
+                let left = self.condition(&binary.left)?;
+                if left == (binary.operator == BinaryOperator::Or) {
+                    return Ok(Value::Bool(left));
+                }
+                return Ok(Value::Bool(self.condition(&binary.right)?));
+            }
+            _ => None,
+        };
+        let left = self.eval(&binary.left)?;
+        let right = self.eval(&binary.right)?;
+        match compound {
+            Some(operator) => {
+                let value = Self::arithmetic(&operator, left, right, span)?;
+                self.assign(&binary.left, value)?;
+                Ok(Value::Unit)
+            }
+            None => Self::arithmetic(&binary.operator, left, right, span),
+        }
+    }
+
+    fn arithmetic(operator: &BinaryOperator, left: Value, right: Value, span: Option<Span>) -> Exec<Value> {
+        use BinaryOperator::*;
+        let overflow = |verb: &str| panic_at(format!("attempt to {} with overflow", verb), span);
+        match (operator, left, right) {
+            (Equal, left, right) => Ok(Value::Bool(left == right)),
+            (NotEqual, left, right) => Ok(Value::Bool(left != right)),
+            (Divide | Modulo, Value::Int(_), Value::Int(0)) => panic_at("attempt to divide by zero", span),
+            (Add, Value::Int(a), Value::Int(b)) => a.checked_add(b).map(Value::Int).map_or_else(|| overflow("add"), Ok),
+            (Subtract, Value::Int(a), Value::Int(b)) => a
+                .checked_sub(b)
+                .map(Value::Int)
+                .map_or_else(|| overflow("subtract"), Ok),
+            (Multiply, Value::Int(a), Value::Int(b)) => a
+                .checked_mul(b)
+                .map(Value::Int)
+                .map_or_else(|| overflow("multiply"), Ok),
+            (Divide, Value::Int(a), Value::Int(b)) => {
+                a.checked_div(b).map(Value::Int).map_or_else(|| overflow("divide"), Ok)
+            }
+            (Modulo, Value::Int(a), Value::Int(b)) => {
+                a.checked_rem(b).map(Value::Int).map_or_else(|| overflow("divide"), Ok)
+            }
+            (Power, Value::Int(a), Value::Int(b)) => match u32::try_from(b).ok().and_then(|b| a.checked_pow(b)) {
+                Some(value) => Ok(Value::Int(value)),
+                None => overflow("raise to a power"),
+            },
+            (operator, Value::Int(a), Value::Float(b)) => {
+                Self::arithmetic(operator, Value::Float(a as f64), Value::Float(b), span)
+            }
+            (operator, Value::Float(a), Value::Int(b)) => {
+                Self::arithmetic(operator, Value::Float(a), Value::Float(b as f64), span)
+            }
+            (Add, Value::Float(a), Value::Float(b)) => Ok(Value::Float(a + b)),
+            (Subtract, Value::Float(a), Value::Float(b)) => Ok(Value::Float(a - b)),
+            (Multiply, Value::Float(a), Value::Float(b)) => Ok(Value::Float(a * b)),
+            (Divide, Value::Float(a), Value::Float(b)) => Ok(Value::Float(a / b)),
+            (Modulo, Value::Float(a), Value::Float(b)) => Ok(Value::Float(a % b)),
+            (Power, Value::Float(a), Value::Float(b)) => Ok(Value::Float(a.powf(b))),
+            (Add, Value::String(a), Value::String(b)) => Ok(Value::String(a + &b)),
+            (Add, Value::String(mut a), Value::Char(b)) => {
+                a.push(b);
+                Ok(Value::String(a))
+            }
+            (Less | LessEqual | Greater | GreaterEqual, left, right) => {
+                let ordering = match (&left, &right) {
+                    (Value::Int(a), Value::Int(b)) => a.partial_cmp(b),
+                    (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+                    (Value::Char(a), Value::Char(b)) => a.partial_cmp(b),
+                    (Value::String(a), Value::String(b)) => a.partial_cmp(b),
+                    _ => {
+                        return panic_at(
+                            format!("cannot compare {} and {}", left.type_name(), right.type_name()),
+                            span,
+                        )
+                    }
+                };
+                let holds = match operator {
+                    Less => ordering.is_some_and(|o| o.is_lt()),
+                    LessEqual => ordering.is_some_and(|o| o.is_le()),
+                    Greater => ordering.is_some_and(|o| o.is_gt()),
+                    _ => ordering.is_some_and(|o| o.is_ge()),
+                };
+                Ok(Value::Bool(holds))
+            }
+            (operator, left, right) => panic_at(
+                format!(
+                    "cannot apply {:?} to {} and {}",
+                    operator,
+                    left.type_name(),
+                    right.type_name()
+                ),
+                span,
+            ),
+        }
+    }
+
+    fn field(object: Value, field: &str, span: Option<Span>) -> Exec<Value> {
+        match object {
+            Value::Struct { name, fields } => match fields.into_iter().find(|(name, _)| name == field) {
+                Some((_, value)) => Ok(value),
+                None => panic_at(format!("`{}` has no field `{}`", name, field), span),
+            },
+            Value::Tuple(values) => match field
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| values.into_iter().nth(index))
+            {
+                Some(value) => Ok(value),
+                None => panic_at(format!("tuple has no field `{}`", field), span),
+            },
+            other => panic_at(format!("a {} has no field `{}`", other.type_name(), field), span),
+        }
+    }
+
+    fn index(object: Value, position: Value, span: Option<Span>) -> Exec<Value> {
+        let (len, item) = match (&object, &position) {
+            (Value::Array(values), Value::Int(index)) => (
+                values.len(),
+                usize::try_from(*index)
+                    .ok()
+                    .and_then(|index| values.get(index).cloned()),
+            ),
+            (Value::String(text), Value::Int(index)) => (
+                text.chars().count(),
+                usize::try_from(*index)
+                    .ok()
+                    .and_then(|index| text.chars().nth(index))
+                    .map(Value::Char),
+            ),
+            _ => return panic_at(format!("cannot index a {} with {}", object.type_name(), position), span),
+        };
+        match item {
+            Some(item) => Ok(item),
+            None => panic_at(
+                format!(
+                    "index out of bounds: the length is {} but the index is {}",
+                    len, position
+                ),
+                span,
+            ),
+        }
+    }
+
+    /// Call a built-in or a function declared in the program
+    fn call_named(&mut self, name: &str, arguments: Vec<Value>, span: Option<Span>) -> Exec<Value> {
+        match name {
+            "print" | "println" => {
+                let mut text = self.format(&arguments, span)?;
+                if name == "println" {
+                    text.push('\n');
+                }
+                self.output.push_str(&text);
+                Ok(Value::Unit)
+            }
+            "format" => Ok(Value::String(self.format(&arguments, span)?)),
+            "panic" => {
+                let message = arguments
+                    .first()
+                    .map(Value::to_string)
+                    .unwrap_or_else(|| "explicit panic".to_string());
+                panic_at(message, span)
+            }
+            "assert" => match arguments.as_slice() {
+                [Value::Bool(true), ..] => Ok(Value::Unit),
+                [_, message] => panic_at(format!("assertion failed: {}", message), span),
+                _ => panic_at("assertion failed", span),
+            },
+            "assert_true" => match arguments.first() {
+                Some(Value::Bool(true)) => Ok(Value::Unit),
+                _ => panic_at("assertion failed: expected true", span),
+            },
+            "assert_eq" | "assert_ne" => {
+                let [left, right] = arguments.as_slice() else {
+                    return panic_at(format!("`{}` takes two values", name), span);
+                };
+                if (left == right) == (name == "assert_eq") {
+                    return Ok(Value::Unit);
+                }
+                let relation = if name == "assert_eq" {
+                    "left == right"
+                } else {
+                    "left != right"
+                };
+                panic_at(
+                    format!("assertion failed: {}\n  left: {}\n right: {}", relation, left, right),
+                    span,
+                )
+            }
+            _ => match self.lookup(&self.functions, name) {
+                Some(function) => self.call_function(function, arguments, span),
+                None => panic_at(format!("no function named `{}`", name), span),
+            },
+        }
+    }
+
+    /// `format`'s template and values, or a single value displayed as is
+    fn format(&self, arguments: &[Value], span: Option<Span>) -> Exec<String> {
+        let (Some(Value::String(template)), true) = (arguments.first(), arguments.len() > 1) else {
+            return Ok(arguments.first().map(Value::to_string).unwrap_or_default());
+        };
+        let pieces = match parse_format_string(template) {
+            Ok(pieces) => pieces,
+            Err(message) => return panic_at(message, span),
+        };
+        let mut values = arguments[1..].iter();
+        let mut text = String::new();
+        for piece in pieces {
+            match piece {
+                FormatPiece::Text(piece) => text.push_str(&piece),
+                FormatPiece::Argument { precision } => match (values.next(), precision) {
+                    (Some(Value::Float(value)), Some(precision)) => text.push_str(&format!("{:.*}", precision, value)),
+                    (Some(value), _) => text.push_str(&value.to_string()),
+                    (None, _) => return panic_at("too few values for the format template", span),
+                },
+            }
+        }
+        Ok(text)
+    }
+
+    fn eval_method_call(
+        &mut self,
+        object: &'a Expression,
+        method: &str,
+        arguments: Vec<Value>,
+        span: Option<Span>,
+    ) -> Exec<Value> {
+        let receiver = self.eval(object)?;
+        match (method, receiver, arguments.as_slice()) {
+            ("len", Value::Array(values), []) => Ok(Value::Int(values.len() as i64)),
+            ("len", Value::String(text), []) => Ok(Value::Int(text.chars().count() as i64)),
+            ("is_empty", Value::Array(values), []) => Ok(Value::Bool(values.is_empty())),
+            ("is_empty", Value::String(text), []) => Ok(Value::Bool(text.is_empty())),
+            ("contains", Value::Array(values), [item]) => Ok(Value::Bool(values.contains(item))),
+            ("contains", Value::String(text), [Value::String(part)]) => Ok(Value::Bool(text.contains(part.as_str()))),
+            ("push", Value::Array(mut values), [item]) => {
+                values.push(item.clone());
+                self.assign(object, Value::Array(values))?;
+                Ok(Value::Unit)
+            }
+            ("pop", Value::Array(mut values), []) => {
+                let Some(last) = values.pop() else {
+                    return panic_at("pop from an empty array", span);
+                };
+                self.assign(object, Value::Array(values))?;
+                Ok(last)
+            }
+            ("len", Value::Map(entries), []) => Ok(Value::Int(entries.len() as i64)),
+            ("contains", Value::Map(entries), [key]) => Ok(Value::Bool(entries.iter().any(|(existing, _)| existing == key))),
+            ("get", Value::Map(entries), [key]) => match entries.into_iter().find(|(existing, _)| existing == key) {
+                Some((_, value)) => Ok(value),
+                None => panic_at("key not found in map", span),
+            },
+            ("insert", Value::Map(mut entries), [key, value]) => {
+                Self::map_insert(&mut entries, key.clone(), value.clone());
+                self.assign(object, Value::Map(entries))?;
+                Ok(Value::Unit)
+            }
+            ("remove", Value::Map(mut entries), [key]) => {
+                let len = entries.len();
+                entries.retain(|(existing, _)| existing != key);
+                let removed = entries.len() < len;
+                self.assign(object, Value::Map(entries))?;
+                Ok(Value::Bool(removed))
+            }
+            (_, receiver, _) => {
+                let key = (receiver.type_name().to_string(), method.to_string());
+                let Some(function) = self.methods.get(&key).copied() else {
+                    return panic_at(format!("no method `{}` on {}", method, receiver.type_name()), span);
+                };
+                let mutates = function.1.parameters.first() == Some(&Parameter::SelfMutRef);
+                let (value, receiver) = self.invoke(function, Some(receiver), arguments, span)?;
+                // `&mut self` methods change the value they were called on
+                if let (true, Some(receiver)) = (mutates, receiver) {
+                    self.assign(object, receiver)?;
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    /// Store `value` in the variable, field or element `target` names
+    fn assign(&mut self, target: &'a Expression, value: Value) -> Exec<()> {
+        let span = target.span();
+        let mut path = Vec::new();
+        let mut current = target;
+        let name = loop {
+            match current {
+                Expression::Identifier(name) => break name,
+                Expression::FieldAccess(access) => {
+                    path.push(PlaceStep::Field(access.field.clone()));
+                    current = &access.object;
+                }
+                Expression::Index(index) => match self.eval(&index.index)? {
+                    Value::Int(position) if position >= 0 => {
+                        path.push(PlaceStep::Index(position as usize));
+                        current = &index.object;
+                    }
+                    other => return panic_at(format!("invalid index `{}`", other), span),
+                },
+                Expression::Unary(unary) if unary.operator == UnaryOperator::Dereference => current = &unary.operand,
+                _ => return unsupported("assigning to this expression", span),
+            }
+        };
+
+        let Some(mut place) = self.variable(name) else {
+            return panic_at(format!("undefined variable `{}`", name), span);
+        };
+        for step in path.iter().rev() {
+            place = match (step, place) {
+                (PlaceStep::Field(field), Value::Struct { fields, .. }) => {
+                    match fields.iter_mut().find(|(name, _)| name == field) {
+                        Some((_, value)) => value,
+                        None => return panic_at(format!("no field `{}`", field), span),
+                    }
+                }
+                (PlaceStep::Field(field), Value::Tuple(values)) => {
+                    match field.parse::<usize>().ok().and_then(|index| values.get_mut(index)) {
+                        Some(value) => value,
+                        None => return panic_at(format!("tuple has no field `{}`", field), span),
+                    }
+                }
+                (PlaceStep::Index(index), Value::Array(values)) => {
+                    let len = values.len();
+                    match values.get_mut(*index) {
+                        Some(value) => value,
+                        None => {
+                            return panic_at(
+                                format!("index out of bounds: the length is {} but the index is {}", len, index),
+                                span,
+                            )
+                        }
+                    }
+                }
+                (_, other) => return panic_at(format!("cannot assign into a {}", other.type_name()), span),
+            };
+        }
+        *place = value;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn program(source: &str) -> Program {
+        Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap()
+    }
+
+    #[test]
+    fn test_functions_evaluate() {
+        let program = program(
+            r#"
+            const BASE: int = 10;
+            struct Counter { count: int }
+            impl Counter {
+                fn bump(&mut self, by: int) { self.count = self.count + by; }
+                fn get(&self) -> int { return self.count; }
+            }
+            fn fib(n: int) -> int {
+                if n < 2 { return n; }
+                return fib(n - 1) + fib(n - 2);
+            }
+            fn sum(values: [int]) -> int {
+                let mut total = BASE;
+                for value in values { total += value; }
+                return total;
+            }
+            fn counter() -> int {
+                let mut c = Counter { count: 1 };
+                c.bump(4);
+                let mut items = [1, 2];
+                items.push(3);
+                items[0] = c.get();
+                println("{} items", items.len());
+                return items[0] + items[2];
+            }
+            "#,
+        );
+        let mut interpreter = Interpreter::new(&program);
+        assert_eq!(interpreter.call("fib", vec![Value::Int(10)]).unwrap(), Value::Int(55));
+        let values = Value::Array(vec![Value::Int(1), Value::Int(2), Value::Int(3)]);
+        assert_eq!(interpreter.call("sum", vec![values]).unwrap(), Value::Int(16));
+        assert_eq!(interpreter.call("counter", vec![]).unwrap(), Value::Int(8));
+        assert_eq!(interpreter.take_output(), "3 items\n");
+    }
+
+    #[test]
+    fn test_blocks_take_the_value_of_a_tail_without_semicolon() {
+        let program = program(
+            r#"
+            fn pick(n: int) -> int {
+                let v = if n > 0 { if n > 10 { 2 } else { 1 } } else { match n { 0 => 0, _ => -1 } };
+                return v;
+            }
+            fn discard() { 5; }
+            "#,
+        );
+        let mut interpreter = Interpreter::new(&program);
+        assert_eq!(interpreter.call("pick", vec![Value::Int(20)]).unwrap(), Value::Int(2));
+        assert_eq!(interpreter.call("pick", vec![Value::Int(-3)]).unwrap(), Value::Int(-1));
+        assert_eq!(interpreter.call("discard", vec![]).unwrap(), Value::Unit);
+    }
+
+    #[test]
+    fn test_failures_panic_with_their_location() {
+        let source = "fn bad() { let xs = [1, 2]; let y = xs[5]; }\n\
+                      fn check() { assert_eq(1 + 1, 3); }\n\
+                      fn forever(n: int) -> int { return forever(n + 1); }\n\
+                      fn divide(a: int) -> int { return a / 0; }\n\
+                      fn outer() { check(); }\n\
+                      fn positive(x: int) { assert(x > 0, \"x must be positive\"); }";
+        let program = program(source);
+        let mut interpreter = Interpreter::new(&program);
+
+        let panic = interpreter.call("bad", vec![]).unwrap_err();
+        assert_eq!(panic.message, "index out of bounds: the length is 2 but the index is 5");
+        assert_eq!(&source[panic.span.unwrap().start..panic.span.unwrap().end], "xs[5]");
+        let panic = interpreter.call("check", vec![]).unwrap_err();
+        assert!(
+            panic
+                .message
+                .starts_with("assertion failed: left == right\n  left: 2\n right: 3"),
+            "{}",
+            panic
+        );
+        assert!(interpreter
+            .call("forever", vec![Value::Int(0)])
+            .unwrap_err()
+            .message
+            .starts_with("stack overflow"));
+        assert_eq!(
+            interpreter.call("divide", vec![Value::Int(1)]).unwrap_err().message,
+            "attempt to divide by zero"
+        );
+        assert!(interpreter.call("missing", vec![]).is_err());
+
+        let panic = interpreter.call("outer", vec![]).unwrap_err();
+        assert_eq!(panic.backtrace, ["check", "outer"]);
+        assert!(interpreter.call("positive", vec![Value::Int(1)]).is_ok());
+        assert_eq!(
+            interpreter.call("positive", vec![Value::Int(0)]).unwrap_err().message,
+            "assertion failed: x must be positive"
+        );
+    }
+
+    #[test]
+    fn test_queries_solve_against_the_program() {
+        let program = program(
+            r#"
+            relation parent(string, string);
+            relation ancestor(string, string);
+            fact parent("ali", "sara");
+            fact parent("sara", "huda");
+            rule ancestor(X, Y) :- parent(X, Y);
+            rule ancestor(X, Z) :- parent(X, Y), ancestor(Y, Z);
+            fn descendants() -> int {
+                let mut count = 0;
+                for row in query ancestor("ali", Who) {
+                    println("{}", row.get("Who"));
+                    count += 1;
+                }
+                return count;
+            }
+            fn orphans() -> List<Map<string, string>> {
+                return query parent(Who, "ali");
+            }
+            fn ages() -> int {
+                let mut ages = {"ali": 40, "sara": 12};
+                ages.insert("sara", 13);
+                return ages.get("sara") + ages.len();
+            }
+            "#,
+        );
+        let mut interpreter = Interpreter::new(&program);
+        assert_eq!(interpreter.call("descendants", vec![]).unwrap(), Value::Int(2));
+        let mut names: Vec<String> = interpreter.take_output().lines().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(names, ["huda", "sara"]);
+        assert_eq!(interpreter.call("orphans", vec![]).unwrap(), Value::Array(Vec::new()));
+        assert_eq!(interpreter.call("ages", vec![]).unwrap(), Value::Int(15));
+    }
+
+    #[test]
+    fn test_assert_and_retract_change_the_knowledge_base() {
+        let program = program(
+            r#"
+            concept person;
+            relation parent(person, person);
+            relation age(string, int);
+            fact parent(ali, sara);
+            fact is_a(ali, person);
+            fact is_a(sara, person);
+            fact is_a(huda, person);
+            fn children() -> int {
+                let rows = query parent(ali, Who);
+                return rows.len();
+            }
+            fn update(child: string) {
+                assert parent("ali", child);
+                assert age(child, 12);
+                retract parent(ali, sara);
+            }
+            fn ages() -> List<Map<string, string>> {
+                return query age("huda", Age);
+            }
+            fn huda_parents() -> List<Map<string, string>> {
+                return query parent(Who, huda);
+            }
+            "#,
+        );
+        let mut interpreter = Interpreter::new(&program);
+        assert_eq!(interpreter.call("children", vec![]).unwrap(), Value::Int(1));
+        interpreter.call("update", vec![Value::String("huda".to_string())]).unwrap();
+        assert_eq!(interpreter.call("children", vec![]).unwrap(), Value::Int(1));
+        let ali = Value::Map(vec![(Value::String("Who".to_string()), Value::String("ali".to_string()))]);
+        assert_eq!(interpreter.call("huda_parents", vec![]).unwrap(), Value::Array(vec![ali]));
+        let age = Value::Map(vec![(Value::String("Age".to_string()), Value::String("12".to_string()))]);
+        assert_eq!(interpreter.call("ages", vec![]).unwrap(), Value::Array(vec![age]));
+
+        // Relations are declared too, so concept-typed arguments are checked
+        let error = interpreter.call("update", vec![Value::String("omar".to_string())]).unwrap_err();
+        assert!(error.message.starts_with("cannot assert a `parent` fact"), "{}", error.message);
+    }
+
+    #[test]
+    fn test_tabled_relations_terminate_left_recursion() {
+        let program = program(
+            r#"
+            relation edge(int, int);
+            #[table]
+            relation path(int, int);
+            fact edge(1, 2);
+            fact edge(2, 3);
+            fact edge(3, 1);
+            rule path(X, Y) :- path(X, Z), edge(Z, Y);
+            rule path(X, Y) :- edge(X, Y);
+            fn reachable() -> int {
+                let rows = query path(1, To);
+                return rows.len();
+            }
+            "#,
+        );
+        let mut interpreter = Interpreter::new(&program);
+        assert_eq!(interpreter.call("reachable", vec![]).unwrap(), Value::Int(3));
+    }
+}
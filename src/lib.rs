@@ -12,20 +12,42 @@
 //! 4. **Code Generation** - Generate LLVM IR
 //! 5. **Runtime** - Provide logic engine, AI support, and system integration
 //!
+//! The first three phases are the `albayan_frontend` crate, re-exported here
+//! under their old paths.
+//!
+//! ## Features
+//!
+//! Everything outside the compiler and the logic engine is behind a default
+//! cargo feature, so embedders can leave out what they don't use:
+//! `ai` (tensors, neural networks and the `ai::` built-ins), `cli`, `lsp`,
+//! `nlu`, `render` (animations, the canvas and image files) and `signals`.
+//! `default-features = false` builds the language and its runtime alone; the
+//! semantic analyzer then reports AI types and built-ins as unavailable.
+//!
 //! ## Modules
 
-pub mod lexer;
-pub mod parser;
-pub mod semantic;
+pub use albayan_frontend::{cancellation, diagnostics, lexer, parser, semantic};
+pub use albayan_frontend::{CompilerError, CompilerOptions, CompilerResult};
 pub mod codegen;
 pub mod runtime;
+#[cfg(feature = "cli")]
 pub mod cli;
 pub mod modules;
 pub mod tools;
+#[cfg(feature = "lsp")]
 pub mod lsp;
+#[cfg(feature = "ai")]
 pub mod ai;
 pub mod builtin_libraries;
+#[cfg(feature = "nlu")]
 pub mod nlu;
+pub mod repl;
+pub mod metrics;
+pub mod gallery;
+pub mod build;
+pub mod interpreter;
+pub mod testing;
+pub mod status;
 
 // Re-export commonly used types
 pub use lexer::{Token, TokenType, Lexer};
@@ -34,35 +56,33 @@ pub use semantic::{SemanticAnalyzer, TypeChecker};
 pub use codegen::{CodeGenerator};
 pub use runtime::{Runtime, LogicEngine};
 pub use builtin_libraries::{
-    BuiltinLibraryManager, ThinkingCore, ExpertExplorer, GeneralShapeEquation,
+    BuiltinLibraryManager, BuiltinError, ThinkingCore, ExpertExplorer, GeneralShapeEquation,
     LayerType, OperationMode, AdaptationType, quick_access,
     ArtisticRenderer, ShapeInference, BasicShape, ShapeProperty, RenderedImage
 };
 
-/// Main compiler error type
-#[derive(Debug, thiserror::Error)]
-pub enum CompilerError {
-    #[error("Lexical error: {0}")]
-    LexicalError(String),
-
-    #[error("Parse error: {0}")]
-    ParseError(String),
-
-    #[error("Semantic error: {0}")]
-    SemanticError(String),
-
-    #[error("Code generation error: {0}")]
-    CodeGenError(String),
-
-    #[error("Runtime error: {0}")]
-    RuntimeError(String),
-
-    #[error("IO error: {0}")]
-    IoError(#[from] std::io::Error),
+impl From<codegen::CodeGenError> for CompilerError {
+    fn from(error: codegen::CodeGenError) -> Self {
+        match error {
+            codegen::CodeGenError::Cancelled(cancelled) => CompilerError::Cancelled(cancelled),
+            error => CompilerError::CodeGenError(error.to_string()),
+        }
+    }
 }
 
-/// Result type for compiler operations
-pub type CompilerResult<T> = Result<T, CompilerError>;
+/// Intermediate output of a compile, for looking inside the compiler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifact {
+    /// The tokens of the source file, one per line after its position
+    Tokens,
+    /// The AST of the source file as JSON
+    Ast,
+    /// The analyzed items of the program and the modules it imports, as JSON
+    AnnotatedAst,
+    /// The IR of the configured backend: LLVM IR with `use_llvm`, otherwise
+    /// the text the simple code generator produces
+    Ir,
+}
 
 /// Main compiler struct that orchestrates the compilation process
 pub struct Compiler {
@@ -70,39 +90,9 @@ pub struct Compiler {
     pub source_path: Option<std::path::PathBuf>,
     /// Compilation options
     pub options: CompilerOptions,
-}
-
-/// Compiler configuration options
-#[derive(Debug, Clone)]
-pub struct CompilerOptions {
-    /// Optimization level (0-3)
-    pub optimization_level: u8,
-    /// Target triple (e.g., "x86_64-unknown-linux-gnu")
-    pub target_triple: Option<String>,
-    /// Enable debug information
-    pub debug_info: bool,
-    /// Output file path
-    pub output_path: Option<std::path::PathBuf>,
-    /// Enable logic programming features
-    pub enable_logic: bool,
-    /// Enable AI features
-    pub enable_ai: bool,
-    /// Use LLVM backend for code generation
-    pub use_llvm: bool,
-}
-
-impl Default for CompilerOptions {
-    fn default() -> Self {
-        Self {
-            optimization_level: 0,
-            target_triple: None,
-            debug_info: true,
-            output_path: None,
-            enable_logic: true,
-            enable_ai: true,
-            use_llvm: false,
-        }
-    }
+    /// Warnings of the last [`Compiler::compile_file`]; none when its object
+    /// code came from the cache
+    pub warnings: Vec<semantic::SemanticWarning>,
 }
 
 impl Compiler {
@@ -111,6 +101,7 @@ impl Compiler {
         Self {
             source_path: None,
             options: CompilerOptions::default(),
+            warnings: Vec::new(),
         }
     }
 
@@ -119,6 +110,7 @@ impl Compiler {
         Self {
             source_path: None,
             options,
+            warnings: Vec::new(),
         }
     }
 
@@ -130,36 +122,158 @@ impl Compiler {
 
     /// Compile a source string directly
     pub fn compile_string(&self, source: &str) -> CompilerResult<Vec<u8>> {
+        let started = std::time::Instant::now();
+        let result = self.compile_source(source);
+        metrics::record_compilation(started.elapsed(), result.is_ok());
+        result
+    }
+
+    fn compile_source(&self, source: &str) -> CompilerResult<Vec<u8>> {
         // Phase 1: Lexical Analysis
         let mut lexer = Lexer::new(source);
-        let tokens = lexer.tokenize()
-            .map_err(|e| CompilerError::LexicalError(e.to_string()))?;
+        let tokens = lexer.tokenize()?;
 
         // Phase 2: Parsing
-        let mut parser = Parser::new(tokens);
-        let ast = parser.parse()
-            .map_err(|e| CompilerError::ParseError(e.to_string()))?;
+        let mut parser = Parser::new(tokens).with_cancellation(self.options.cancellation.clone());
+        let ast = parser.parse()?;
 
         // Phase 3: Semantic Analysis
         let mut analyzer = SemanticAnalyzer::new(&self.options);
-        let analyzed_ast = analyzer.analyze(ast)
-            .map_err(|e| CompilerError::SemanticError(e.to_string()))?;
+        let analyzed_ast = analyzer.analyze(ast)?;
 
         // Phase 4: Code Generation
-        let mut codegen = codegen::SimpleCodeGenerator::new(&self.options);
-        let object_code = codegen.generate(analyzed_ast)
-            .map_err(|e| CompilerError::CodeGenError(e.to_string()))?;
+        self.generate(analyzed_ast)
+    }
 
-        Ok(object_code)
+    /// Generate object code for an analyzed program with the configured backend
+    fn generate(&self, program: semantic::AnnotatedProgram) -> CompilerResult<Vec<u8>> {
+        if self.options.use_llvm {
+            self.generate_llvm(program)
+        } else {
+            let mut codegen = codegen::SimpleCodeGenerator::new(&self.options);
+            Ok(codegen.generate(program)?)
+        }
+    }
+
+    /// Generate native object code with the LLVM backend
+    #[cfg(feature = "llvm")]
+    fn generate_llvm(&self, program: semantic::AnnotatedProgram) -> CompilerResult<Vec<u8>> {
+        let mut codegen = codegen::LLVMBackend::new(&self.options);
+        Ok(codegen.generate(program)?)
+    }
+
+    #[cfg(not(feature = "llvm"))]
+    fn generate_llvm(&self, _program: semantic::AnnotatedProgram) -> CompilerResult<Vec<u8>> {
+        Err(CompilerError::CodeGenError(
+            "LLVM backend not available: rebuild with `--features llvm`".to_string()
+        ))
     }
 
-    /// Compile a source file
+    /// Compile a source file together with the modules its `using`
+    /// declarations import from the files next to it or from the packages in
+    /// [`CompilerOptions::packages`]; see [`modules::graph`]
     pub fn compile_file(&mut self) -> CompilerResult<Vec<u8>> {
         let source_path = self.source_path.as_ref()
-            .ok_or_else(|| CompilerError::SemanticError("No source file specified".to_string()))?;
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No source file specified"))?;
+
+        let started = std::time::Instant::now();
+        let result = self.compile_modules(source_path);
+        metrics::record_compilation(started.elapsed(), result.is_ok());
+        let (object_code, warnings) = result?;
+        self.warnings = warnings;
+        Ok(object_code)
+    }
+
+    /// Object code of the program and the warnings of its analysis
+    fn compile_modules(&self, entry: &std::path::Path) -> CompilerResult<(Vec<u8>, Vec<semantic::SemanticWarning>)> {
+        let graph = modules::graph::ModuleGraph::load_with_packages(entry, &self.options.packages, &self.options.cancellation)?;
+        // The program is keyed on the source of every module it loads
+        let cached = codegen::cache::CompilationCache::from_options(&self.options).map(|cache| {
+            let mut key = codegen::cache::CacheKeyBuilder::new(&self.options);
+            for module in graph.modules() {
+                key.add(module.name.as_bytes());
+                key.add(&std::fs::read(&module.path).unwrap_or_default());
+            }
+            (cache, key.finish())
+        });
+        if let Some(object_code) = cached.as_ref().and_then(|(cache, key)| cache.get(*key)) {
+            return Ok((object_code, Vec::new()));
+        }
+
+        let mut analyzer = SemanticAnalyzer::new(&self.options);
+        let mut analyzed_ast = analyzer.analyze_modules(&graph)?;
+        let warnings = std::mem::take(&mut analyzed_ast.warnings);
+        let object_code = self.generate(analyzed_ast)?;
+        if let Some((cache, key)) = cached {
+            // A cache that cannot be written only costs the next build time
+            let _ = cache.put(key, &object_code);
+        }
+        Ok((object_code, warnings))
+    }
+
+    /// Run the source file through the phases up to `artifact` and render it
+    /// as text. Tokens and the AST cover the source file alone; the annotated
+    /// AST and IR are of the program with the modules it imports
+    pub fn emit_artifact(&self, artifact: Artifact) -> CompilerResult<String> {
+        let source_path = self.source_path.as_ref()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "No source file specified"))?;
+
+        match artifact {
+            Artifact::Tokens | Artifact::Ast => {
+                let source = std::fs::read_to_string(source_path)?;
+                let tokens = Lexer::new(&source).tokenize()?;
+                if artifact == Artifact::Tokens {
+                    return Ok(tokens
+                        .iter()
+                        .map(|token| format!("{:<10}{:?}\n", format!("{}:{}", token.line, token.column), token.token_type))
+                        .collect());
+                }
+                let ast = Parser::new(tokens).with_cancellation(self.options.cancellation.clone()).parse()?;
+                Ok(serde_json::to_string_pretty(&ast).map_err(std::io::Error::from)?)
+            }
+            Artifact::AnnotatedAst | Artifact::Ir => {
+                let graph = modules::graph::ModuleGraph::load_with_packages(source_path, &self.options.packages, &self.options.cancellation)?;
+                let program = SemanticAnalyzer::new(&self.options).analyze_modules(&graph)?;
+                if artifact == Artifact::AnnotatedAst {
+                    return Ok(serde_json::to_string_pretty(&program.items).map_err(std::io::Error::from)?);
+                }
+                self.generate_ir(program)
+            }
+        }
+    }
+
+    /// Generate the IR of an analyzed program with the configured backend
+    fn generate_ir(&self, program: semantic::AnnotatedProgram) -> CompilerResult<String> {
+        if self.options.use_llvm {
+            self.generate_llvm_ir(program)
+        } else {
+            let mut codegen = codegen::SimpleCodeGenerator::new(&self.options);
+            Ok(String::from_utf8_lossy(&codegen.generate(program)?).into_owned())
+        }
+    }
+
+    #[cfg(feature = "llvm")]
+    fn generate_llvm_ir(&self, program: semantic::AnnotatedProgram) -> CompilerResult<String> {
+        Ok(codegen::LLVMBackend::new(&self.options).emit_ir(&program)?)
+    }
+
+    #[cfg(not(feature = "llvm"))]
+    fn generate_llvm_ir(&self, _program: semantic::AnnotatedProgram) -> CompilerResult<String> {
+        Err(CompilerError::CodeGenError(
+            "LLVM backend not available: rebuild with `--features llvm`".to_string()
+        ))
+    }
 
-        let source = std::fs::read_to_string(source_path)?;
-        self.compile_string(&source)
+    /// Compile every `.ab` file under `dir` in module dependency order,
+    /// writing an object file per module; see [`build`]
+    pub fn compile_project<P: AsRef<std::path::Path>>(&self, dir: P) -> CompilerResult<build::BuildReport> {
+        build::build_project(self, dir.as_ref(), true)
+    }
+
+    /// Like [`Compiler::compile_project`], but stop each file after semantic
+    /// analysis and write nothing
+    pub fn check_project<P: AsRef<std::path::Path>>(&self, dir: P) -> CompilerResult<build::BuildReport> {
+        build::build_project(self, dir.as_ref(), false)
     }
 
     /// Run the compiler in JIT mode (for REPL and quick execution)
@@ -176,6 +290,18 @@ impl Compiler {
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const LANGUAGE_NAME: &str = "البيان (AlBayan)";
 
+/// Optional cargo features and whether this build has them
+pub const FEATURES: &[(&str, bool)] = &[
+    ("ai", cfg!(feature = "ai")),
+    ("cli", cfg!(feature = "cli")),
+    ("lsp", cfg!(feature = "lsp")),
+    ("nlu", cfg!(feature = "nlu")),
+    ("render", cfg!(feature = "render")),
+    ("signals", cfg!(feature = "signals")),
+    ("llvm", cfg!(feature = "llvm")),
+    ("sqlite", cfg!(feature = "sqlite")),
+];
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -198,4 +324,49 @@ mod tests {
         assert_eq!(compiler.options.optimization_level, 2);
         assert!(!compiler.options.debug_info);
     }
+
+    #[test]
+    fn test_cancelled_compile_stops_with_cancelled_error() {
+        let source = "fn main() -> int { let x = 1; return x; }";
+        let token = cancellation::CancellationToken::new();
+        let compiler = Compiler::with_options(CompilerOptions {
+            cancellation: token.clone(),
+            ..Default::default()
+        });
+        assert!(compiler.compile_string(source).is_ok());
+
+        token.cancel();
+        let error = compiler.compile_string(source).unwrap_err();
+        assert_eq!(error.cancelled(), Some(cancellation::Cancelled::Requested));
+        assert_eq!(diagnostics::Diagnostic::from(&error).code, "AB0600");
+
+        let timed_out = Compiler::with_options(CompilerOptions {
+            cancellation: cancellation::CancellationToken::with_timeout(std::time::Duration::ZERO),
+            ..Default::default()
+        });
+        assert!(matches!(
+            timed_out.compile_string(source).unwrap_err().cancelled(),
+            Some(cancellation::Cancelled::TimedOut(_))
+        ));
+    }
+
+    #[test]
+    fn test_emit_intermediate_artifacts() {
+        let path = std::env::temp_dir().join(format!("albayan_emit_{}.ab", std::process::id()));
+        std::fs::write(&path, "fn main() -> int {\n    return 1;\n}\n").unwrap();
+        let compiler = Compiler::new().source_file(&path);
+
+        let tokens = compiler.emit_artifact(Artifact::Tokens).unwrap();
+        assert!(tokens.starts_with("1:1       Fn\n"), "{}", tokens);
+        assert!(tokens.contains("2:12      IntegerLiteral(Some(1))"), "{}", tokens);
+
+        let ast: serde_json::Value = serde_json::from_str(&compiler.emit_artifact(Artifact::Ast).unwrap()).unwrap();
+        assert_eq!(ast["items"][0]["Function"]["name"], "main");
+        let annotated: serde_json::Value =
+            serde_json::from_str(&compiler.emit_artifact(Artifact::AnnotatedAst).unwrap()).unwrap();
+        assert_eq!(annotated[0]["Function"]["name"], "main");
+        assert!(!compiler.emit_artifact(Artifact::Ir).unwrap().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
 }
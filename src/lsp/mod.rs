@@ -2,18 +2,30 @@ use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use tokio::sync::RwLock;
 use std::sync::Arc;
 
-use crate::lexer::Lexer;
-use crate::parser::Parser;
-use crate::semantic::SemanticAnalyzer;
-use crate::CompilerOptions;
+use crate::diagnostics::{Diagnostic as CompilerDiagnostic, Severity};
+use crate::lexer::{Lexer, LineIndex, Span};
+use crate::parser::ast::Program;
+use crate::cancellation::CancellationToken;
+use crate::parser::{ParseError, Parser};
+use crate::semantic::knowledge_check::{check_knowledge, KnowledgeIssue};
+use crate::semantic::{SemanticAnalyzer, SemanticError};
+use crate::tools::linter::{LintIssue, Linter, LinterConfig};
+use crate::{Compiler, CompilerOptions};
 
 /// AlBayan Language Server
 pub struct AlBayanLanguageServer {
     client: Client,
     documents: Arc<RwLock<HashMap<Url, DocumentInfo>>>,
+    /// Workspace root; its `.ab` files take part in the knowledge checks even when closed
+    root: Arc<RwLock<Option<PathBuf>>>,
+    /// Token of the latest analysis of each document
+    analyses: Arc<std::sync::Mutex<HashMap<Url, CancellationToken>>>,
+    /// Diagnostics of the workspace files from the project check made on startup
+    project_diagnostics: Arc<RwLock<HashMap<Url, Vec<Diagnostic>>>>,
 }
 
 /// Information about an open document
@@ -29,32 +41,92 @@ impl AlBayanLanguageServer {
         Self {
             client,
             documents: Arc::new(RwLock::new(HashMap::new())),
+            root: Arc::new(RwLock::new(None)),
+            analyses: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            project_diagnostics: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
-    /// Analyze document and return diagnostics
-    async fn analyze_document(&self, uri: &Url, content: &str) -> Vec<Diagnostic> {
+    /// Convert a source span to an LSP range; unknown locations point at the end of the document
+    fn span_to_range(index: &LineIndex, content: &str, span: Option<Span>) -> Range {
+        let span = span.unwrap_or_else(|| Span::new(content.len(), content.len()));
+        let position = |offset| {
+            let line_col = index.line_col(offset);
+            Position {
+                line: (line_col.line - 1) as u32,
+                character: (line_col.column - 1) as u32,
+            }
+        };
+        Range {
+            start: position(span.start),
+            end: position(span.end),
+        }
+    }
+
+    /// Analyze a document in the background and return its diagnostics, or `None`
+    /// when the analysis was cancelled: a newer edit of the same document supersedes
+    /// it, and dropping this future (tower-lsp does so on `$/cancelRequest`) stops it
+    /// at its next checkpoint instead of letting it run to completion
+    async fn analyze_document(&self, uri: &Url, content: &str) -> Option<Vec<Diagnostic>> {
+        let token = CancellationToken::new();
+        let previous = self.analyses
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(uri.clone(), token.clone());
+        if let Some(previous) = previous {
+            previous.cancel();
+        }
+        let _cancel_on_drop = token.cancel_on_drop();
+
+        let content = content.to_string();
+        let analysis_token = token.clone();
+        // Lint with the [lints] of the document's project; a broken table leaves the defaults
+        let lints = uri.to_file_path().ok()
+            .and_then(|path| LinterConfig::discover(&path).ok())
+            .unwrap_or_default();
+        let diagnostics = tokio::task::spawn_blocking(move || {
+            Self::diagnose(&content, &analysis_token, &Linter::with_config(lints))
+        })
+            .await
+            .ok()??;
+        if token.is_cancelled() {
+            return None;
+        }
+        Some(diagnostics)
+    }
+
+    /// Lex, parse, lint and analyze a document, stopping early once `cancellation` is cancelled
+    fn diagnose(content: &str, cancellation: &CancellationToken, linter: &Linter) -> Option<Vec<Diagnostic>> {
         let mut diagnostics = Vec::new();
+        let line_index = LineIndex::new(content);
 
         // Lexical analysis
         let mut lexer = Lexer::new(content);
         match lexer.tokenize() {
             Ok(tokens) => {
                 // Parsing
-                let mut parser = Parser::new(tokens);
+                let mut parser = Parser::new(tokens).with_cancellation(cancellation.clone());
                 match parser.parse() {
                     Ok(ast) => {
+                        diagnostics.extend(
+                            linter.check_program(content, &ast).iter()
+                                .map(|issue| Self::lint_diagnostic(issue, &line_index, content)),
+                        );
+
                         // Semantic analysis
-                        let options = CompilerOptions::default();
+                        let options = CompilerOptions {
+                            cancellation: cancellation.clone(),
+                            ..Default::default()
+                        };
                         let mut analyzer = SemanticAnalyzer::new(&options);
 
                         if let Err(error) = analyzer.analyze(ast) {
+                            if let SemanticError::Cancelled(_) = error.inner() {
+                                return None;
+                            }
                             // Handle single error instead of iterator
                             let diagnostic = Diagnostic {
-                                range: Range {
-                                    start: Position { line: 0, character: 0 },
-                                    end: Position { line: 0, character: 0 },
-                                },
+                                range: Self::span_to_range(&line_index, content, error.span()),
                                 severity: Some(DiagnosticSeverity::ERROR),
                                 code: None,
                                 code_description: None,
@@ -67,12 +139,10 @@ impl AlBayanLanguageServer {
                             diagnostics.push(diagnostic);
                         }
                     }
+                    Err(ParseError::Cancelled(_)) => return None,
                     Err(error) => {
                         let diagnostic = Diagnostic {
-                            range: Range {
-                                start: Position { line: 0, character: 0 },
-                                end: Position { line: 0, character: 0 },
-                            },
+                            range: Self::span_to_range(&line_index, content, error.span()),
                             severity: Some(DiagnosticSeverity::ERROR),
                             code: None,
                             code_description: None,
@@ -88,10 +158,7 @@ impl AlBayanLanguageServer {
             }
             Err(error) => {
                 let diagnostic = Diagnostic {
-                    range: Range {
-                        start: Position { line: 0, character: 0 },
-                        end: Position { line: 0, character: 0 },
-                    },
+                    range: Self::span_to_range(&line_index, content, error.span()),
                     severity: Some(DiagnosticSeverity::ERROR),
                     code: None,
                     code_description: None,
@@ -105,7 +172,156 @@ impl AlBayanLanguageServer {
             }
         }
 
-        diagnostics
+        Some(diagnostics)
+    }
+
+    /// Check the whole workspace as `Compiler::check_project` does, keeping the
+    /// diagnostics of each file for when it is not open
+    async fn check_project(&self) {
+        let Some(root) = self.root.read().await.clone() else { return };
+        let Ok(Ok(report)) = tokio::task::spawn_blocking(move || Compiler::new().check_project(root)).await else {
+            return;
+        };
+
+        let mut project_diagnostics = HashMap::new();
+        for file in &report.files {
+            let Ok(uri) = Url::from_file_path(&file.path) else { continue };
+            let content = std::fs::read_to_string(&file.path).unwrap_or_default();
+            let line_index = LineIndex::new(&content);
+            let diagnostics = file.diagnostics.iter()
+                .map(|diagnostic| Diagnostic {
+                    range: Self::span_to_range(&line_index, &content, diagnostic.span),
+                    severity: Some(Self::severity(diagnostic.severity)),
+                    code: Some(NumberOrString::String(diagnostic.code.to_string())),
+                    code_description: None,
+                    source: Some("albayan".to_string()),
+                    message: diagnostic.message.clone(),
+                    related_information: None,
+                    tags: None,
+                    data: None,
+                })
+                .collect();
+            project_diagnostics.insert(uri, diagnostics);
+        }
+        *self.project_diagnostics.write().await = project_diagnostics;
+
+        let summary = report.to_string();
+        let summary = summary.lines().last().unwrap_or_default();
+        self.client.log_message(MessageType::INFO, format!("Workspace check: {}", summary)).await;
+    }
+
+    fn severity(severity: Severity) -> DiagnosticSeverity {
+        match severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+            Severity::Note => DiagnosticSeverity::INFORMATION,
+        }
+    }
+
+    /// Convert a lint issue to an LSP diagnostic; unused and unreachable code is
+    /// tagged so that editors fade it
+    fn lint_diagnostic(issue: &LintIssue, index: &LineIndex, content: &str) -> Diagnostic {
+        let unnecessary = issue.rule.starts_with("unused_") || issue.rule == "unreachable_code";
+        Diagnostic {
+            range: Self::span_to_range(index, content, Some(issue.span)),
+            severity: Some(Self::severity(issue.severity)),
+            code: Some(NumberOrString::String(issue.rule.to_string())),
+            code_description: None,
+            source: Some("albayan-lint".to_string()),
+            message: match &issue.suggestion {
+                Some(suggestion) => format!("{}\n{}", issue.message, suggestion),
+                None => issue.message.clone(),
+            },
+            related_information: None,
+            tags: unnecessary.then(|| vec![DiagnosticTag::UNNECESSARY]),
+            data: None,
+        }
+    }
+
+    /// Run the knowledge checks over every open document and workspace file, then publish
+    /// each file's own diagnostics together with the workspace ones that land in it
+    async fn publish_workspace_diagnostics(&self) {
+        let documents = self.documents.read().await.clone();
+        let mut sources: Vec<(Url, String)> = documents.iter()
+            .map(|(uri, doc)| (uri.clone(), doc.content.clone()))
+            .collect();
+
+        if let Some(root) = self.root.read().await.clone() {
+            for path in crate::modules::collect_sources(&[root]).unwrap_or_default() {
+                let Ok(uri) = Url::from_file_path(&path) else { continue };
+                if documents.contains_key(&uri) {
+                    continue;
+                }
+                if let Ok(content) = std::fs::read_to_string(&path) {
+                    sources.push((uri, content));
+                }
+            }
+        }
+
+        let programs: Vec<(String, Program)> = sources.iter()
+            .filter_map(|(uri, content)| {
+                let tokens = Lexer::new(content).tokenize().ok()?;
+                let program = Parser::new(tokens).parse().ok()?;
+                Some((uri.to_string(), program))
+            })
+            .collect();
+        let workspace: Vec<(String, &Program)> = programs.iter()
+            .map(|(uri, program)| (uri.clone(), program))
+            .collect();
+        let issues = check_knowledge(&workspace);
+
+        let contents: HashMap<String, &str> = sources.iter()
+            .map(|(uri, content)| (uri.to_string(), content.as_str()))
+            .collect();
+        let project_diagnostics = self.project_diagnostics.read().await;
+        for (uri, _) in &sources {
+            let document = documents.get(uri);
+            let mut diagnostics = match document {
+                Some(doc) => doc.diagnostics.clone(),
+                None => project_diagnostics.get(uri).cloned().unwrap_or_default(),
+            };
+            diagnostics.extend(
+                issues.iter()
+                    .filter(|issue| issue.location.file == uri.as_str())
+                    .map(|issue| Self::knowledge_diagnostic(issue, &contents)),
+            );
+            self.client
+                .publish_diagnostics(uri.clone(), diagnostics, document.map(|doc| doc.version))
+                .await;
+        }
+    }
+
+    /// Convert a workspace knowledge issue to an LSP diagnostic
+    fn knowledge_diagnostic(issue: &KnowledgeIssue, contents: &HashMap<String, &str>) -> Diagnostic {
+        let range_in = |file: &str, span: Span| {
+            let content = contents.get(file).copied().unwrap_or("");
+            Self::span_to_range(&LineIndex::new(content), content, Some(span))
+        };
+        let diagnostic: &CompilerDiagnostic = &issue.diagnostic;
+        let related_information = issue.related.as_ref().and_then(|(location, message)| {
+            Some(vec![DiagnosticRelatedInformation {
+                location: Location {
+                    uri: Url::parse(&location.file).ok()?,
+                    range: range_in(&location.file, location.span),
+                },
+                message: message.clone(),
+            }])
+        });
+
+        Diagnostic {
+            range: range_in(&issue.location.file, issue.location.span),
+            severity: Some(Self::severity(diagnostic.severity)),
+            code: Some(NumberOrString::String(diagnostic.code.to_string())),
+            code_description: None,
+            source: Some("albayan".to_string()),
+            message: match &diagnostic.label {
+                Some(label) => format!("{}: {}", diagnostic.message, label),
+                None => diagnostic.message.clone(),
+            },
+            related_information,
+            tags: None,
+            data: None,
+        }
     }
 
     /// Get completions for the current position
@@ -289,7 +505,14 @@ impl AlBayanLanguageServer {
 
 #[tower_lsp::async_trait]
 impl LanguageServer for AlBayanLanguageServer {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        #[allow(deprecated)]
+        let root = params.workspace_folders
+            .and_then(|folders| folders.into_iter().next().map(|folder| folder.uri))
+            .or(params.root_uri)
+            .and_then(|uri| uri.to_file_path().ok());
+        *self.root.write().await = root;
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
@@ -315,6 +538,8 @@ impl LanguageServer for AlBayanLanguageServer {
         self.client
             .log_message(MessageType::INFO, "AlBayan Language Server initialized!")
             .await;
+        self.check_project().await;
+        self.publish_workspace_diagnostics().await;
     }
 
     async fn shutdown(&self) -> Result<()> {
@@ -326,19 +551,16 @@ impl LanguageServer for AlBayanLanguageServer {
         let content = params.text_document.text;
         let version = params.text_document.version;
 
-        let diagnostics = self.analyze_document(&uri, &content).await;
+        let Some(diagnostics) = self.analyze_document(&uri, &content).await else { return };
 
         let doc_info = DocumentInfo {
-            content: content.clone(),
+            content,
             version,
-            diagnostics: diagnostics.clone(),
+            diagnostics,
         };
 
-        self.documents.write().await.insert(uri.clone(), doc_info);
-
-        self.client
-            .publish_diagnostics(uri, diagnostics, Some(version))
-            .await;
+        self.documents.write().await.insert(uri, doc_info);
+        self.publish_workspace_diagnostics().await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
@@ -347,19 +569,17 @@ impl LanguageServer for AlBayanLanguageServer {
 
         if let Some(change) = params.content_changes.into_iter().next() {
             let content = change.text;
-            let diagnostics = self.analyze_document(&uri, &content).await;
+            // A newer edit cancelled this analysis and publishes its own diagnostics
+            let Some(diagnostics) = self.analyze_document(&uri, &content).await else { return };
 
             let doc_info = DocumentInfo {
-                content: content.clone(),
+                content,
                 version,
-                diagnostics: diagnostics.clone(),
+                diagnostics,
             };
 
-            self.documents.write().await.insert(uri.clone(), doc_info);
-
-            self.client
-                .publish_diagnostics(uri, diagnostics, Some(version))
-                .await;
+            self.documents.write().await.insert(uri, doc_info);
+            self.publish_workspace_diagnostics().await;
         }
     }
 
@@ -0,0 +1,260 @@
+//! # Usage Metrics
+//!
+//! Opt-in, in-process metrics for hosts that embed the compiler and runtime:
+//! compilations, logic queries and their latencies, error counts and the
+//! runtime's memory high-water mark. Nothing is recorded until [`enable`] is
+//! called and nothing ever leaves the process on its own: the host pulls a
+//! [`MetricsSnapshot`] whenever it wants one, and may publish it in the
+//! Prometheus text format with [`MetricsSnapshot::to_prometheus`] or
+//! [`serve_prometheus`].
+
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static COMPILATIONS: AtomicU64 = AtomicU64::new(0);
+static COMPILATION_ERRORS: AtomicU64 = AtomicU64::new(0);
+static COMPILATION_NANOS: AtomicU64 = AtomicU64::new(0);
+static QUERIES: AtomicU64 = AtomicU64::new(0);
+static QUERY_ERRORS: AtomicU64 = AtomicU64::new(0);
+static QUERY_NANOS: AtomicU64 = AtomicU64::new(0);
+static SLOWEST_QUERY_NANOS: AtomicU64 = AtomicU64::new(0);
+static MEMORY_HIGHWATER: AtomicU64 = AtomicU64::new(0);
+
+/// Longest a scrape may take to send its request or read the response;
+/// requests are served one at a time, so a stalled client holds up the rest
+/// for at most this long
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Most bytes of request line and headers read from a scrape
+const MAX_REQUEST_BYTES: u64 = 8 * 1024;
+
+/// Start recording metrics in this process
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stop recording; the values recorded so far are kept
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Clear every recorded value
+pub fn reset() {
+    for metric in [
+        &COMPILATIONS,
+        &COMPILATION_ERRORS,
+        &COMPILATION_NANOS,
+        &QUERIES,
+        &QUERY_ERRORS,
+        &QUERY_NANOS,
+        &SLOWEST_QUERY_NANOS,
+        &MEMORY_HIGHWATER,
+    ] {
+        metric.store(0, Ordering::Relaxed);
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// Record one compilation and whether it succeeded
+pub(crate) fn record_compilation(elapsed: Duration, succeeded: bool) {
+    if !is_enabled() {
+        return;
+    }
+    COMPILATIONS.fetch_add(1, Ordering::Relaxed);
+    COMPILATION_NANOS.fetch_add(nanos(elapsed), Ordering::Relaxed);
+    if !succeeded {
+        COMPILATION_ERRORS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Record one logic query and whether it succeeded
+pub(crate) fn record_query(elapsed: Duration, succeeded: bool) {
+    if !is_enabled() {
+        return;
+    }
+    QUERIES.fetch_add(1, Ordering::Relaxed);
+    QUERY_NANOS.fetch_add(nanos(elapsed), Ordering::Relaxed);
+    SLOWEST_QUERY_NANOS.fetch_max(nanos(elapsed), Ordering::Relaxed);
+    if !succeeded {
+        QUERY_ERRORS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Record the bytes currently allocated by a runtime memory manager
+pub(crate) fn record_memory(allocated: usize) {
+    if is_enabled() {
+        MEMORY_HIGHWATER.fetch_max(allocated as u64, Ordering::Relaxed);
+    }
+}
+
+/// The metrics recorded so far
+pub fn snapshot() -> MetricsSnapshot {
+    let load = |metric: &AtomicU64| metric.load(Ordering::Relaxed);
+    MetricsSnapshot {
+        compilations: load(&COMPILATIONS),
+        compilation_errors: load(&COMPILATION_ERRORS),
+        compilation_time: Duration::from_nanos(load(&COMPILATION_NANOS)),
+        queries: load(&QUERIES),
+        query_errors: load(&QUERY_ERRORS),
+        query_time: Duration::from_nanos(load(&QUERY_NANOS)),
+        slowest_query: Duration::from_nanos(load(&SLOWEST_QUERY_NANOS)),
+        memory_highwater_bytes: load(&MEMORY_HIGHWATER),
+    }
+}
+
+/// Metric values at one point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MetricsSnapshot {
+    pub compilations: u64,
+    pub compilation_errors: u64,
+    /// Total time spent compiling
+    pub compilation_time: Duration,
+    pub queries: u64,
+    pub query_errors: u64,
+    /// Total time spent solving queries
+    pub query_time: Duration,
+    pub slowest_query: Duration,
+    /// Most bytes a runtime memory manager had allocated at once
+    pub memory_highwater_bytes: u64,
+}
+
+impl MetricsSnapshot {
+    /// Fraction of compilations that failed
+    pub fn compilation_error_rate(&self) -> f64 {
+        self.compilation_errors as f64 / self.compilations.max(1) as f64
+    }
+
+    /// Fraction of queries that failed
+    pub fn query_error_rate(&self) -> f64 {
+        self.query_errors as f64 / self.queries.max(1) as f64
+    }
+
+    /// Mean time per query
+    pub fn average_query_time(&self) -> Duration {
+        self.query_time / self.queries.max(1) as u32
+    }
+
+    /// The snapshot in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let metrics: [(&str, &str, &str, f64); 8] = [
+            ("albayan_compilations_total", "counter", "Compilations started", self.compilations as f64),
+            ("albayan_compilation_errors_total", "counter", "Compilations that failed", self.compilation_errors as f64),
+            ("albayan_compilation_seconds_total", "counter", "Time spent compiling", self.compilation_time.as_secs_f64()),
+            ("albayan_queries_total", "counter", "Logic queries solved", self.queries as f64),
+            ("albayan_query_errors_total", "counter", "Logic queries that failed", self.query_errors as f64),
+            ("albayan_query_seconds_total", "counter", "Time spent solving logic queries", self.query_time.as_secs_f64()),
+            ("albayan_query_seconds_max", "gauge", "Slowest logic query", self.slowest_query.as_secs_f64()),
+            ("albayan_memory_highwater_bytes", "gauge", "Most bytes allocated by the runtime at once", self.memory_highwater_bytes as f64),
+        ];
+
+        let mut text = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(text, "# HELP {} {}", name, help);
+            let _ = writeln!(text, "# TYPE {} {}", name, kind);
+            let _ = writeln!(text, "{} {}", name, value);
+        }
+        text
+    }
+}
+
+/// Serve [`snapshot`] in the Prometheus text format at `http://address/metrics`
+/// from a background thread, returning the bound address. Metrics are enabled
+/// by this call.
+pub fn serve_prometheus(address: &str) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(address)?;
+    let bound = listener.local_addr()?;
+    enable();
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = respond(stream);
+        }
+    });
+
+    Ok(bound)
+}
+
+fn respond(mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?.take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the remaining headers
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    if reader.get_ref().limit() == 0 {
+        return stream.write_all(b"HTTP/1.1 431 Request Header Fields Too Large\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    }
+
+    match request_line.split_whitespace().nth(1) {
+        Some("/metrics") => {
+            let body = snapshot().to_prometheus();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        }
+        _ => stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_are_recorded_and_exported() {
+        enable();
+        let before = snapshot();
+        record_query(Duration::from_millis(3), true);
+        record_query(Duration::from_millis(1), false);
+        record_compilation(Duration::from_millis(2), false);
+        record_memory(4096);
+
+        let after = snapshot();
+        assert!(after.queries >= before.queries + 2);
+        assert!(after.query_errors > before.query_errors);
+        assert!(after.compilation_errors > before.compilation_errors);
+        assert!(after.slowest_query >= Duration::from_millis(3));
+        assert!(after.memory_highwater_bytes >= 4096);
+
+        let text = after.to_prometheus();
+        assert!(text.contains("# TYPE albayan_queries_total counter"));
+        assert!(text.contains("albayan_memory_highwater_bytes "));
+
+        let address = serve_prometheus("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("albayan_compilations_total"));
+
+        // A request whose headers never end is cut off at the cap
+        let mut stream = TcpStream::connect(address).unwrap();
+        let request_line = b"GET /metrics HTTP/1.1\r\n";
+        stream.write_all(request_line).unwrap();
+        stream.write_all(&vec![b'x'; MAX_REQUEST_BYTES as usize - request_line.len()]).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 431"), "{}", response);
+    }
+}
@@ -13,6 +13,7 @@ use anyhow::Result;
 
 pub mod resolver;
 pub mod package;
+pub use albayan_frontend::modules::graph;
 
 /// Module information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -290,3 +291,37 @@ impl Default for ModuleLoader {
         Self::new()
     }
 }
+
+/// Expand files and directories into the `.ab` source files they contain
+///
+/// Directories are searched recursively (hidden directories and `target` are
+/// skipped); files are kept in the order given, directory contents sorted.
+pub fn collect_sources(paths: &[PathBuf]) -> std::io::Result<Vec<PathBuf>> {
+    fn walk(dir: &Path, sources: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<_>>()?;
+        entries.sort();
+        for path in entries {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+            if path.is_dir() {
+                if !name.starts_with('.') && name != "target" {
+                    walk(&path, sources)?;
+                }
+            } else if path.extension().is_some_and(|extension| extension == "ab") {
+                sources.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut sources = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            walk(path, &mut sources)?;
+        } else {
+            sources.push(path.clone());
+        }
+    }
+    Ok(sources)
+}
@@ -5,6 +5,8 @@
 
 use std::collections::HashMap;
 
+use crate::runtime::logic_engine::LogicEngine;
+
 /// نوع العلاقة المستخرجة من الجملة
 #[derive(Debug, Clone, PartialEq)]
 pub enum NLURelationType {
@@ -27,6 +29,26 @@ pub enum NLURelationType {
     Unknown(String),
 }
 
+impl NLURelationType {
+    /// اسم العلاقة في محرك المنطق
+    pub fn predicate(&self) -> Option<&'static str> {
+        match self {
+            NLURelationType::Above => Some("above"),
+            NLURelationType::Below => Some("below"),
+            NLURelationType::LeftOf => Some("left_of"),
+            NLURelationType::RightOf => Some("right_of"),
+            NLURelationType::InFrontOf => Some("in_front_of"),
+            NLURelationType::Behind => Some("behind"),
+            NLURelationType::Near => Some("near"),
+            NLURelationType::Far => Some("far"),
+            NLURelationType::Eats => Some("eats"),
+            NLURelationType::Touches => Some("touches"),
+            NLURelationType::Hits => Some("hits"),
+            NLURelationType::Unknown(_) => None,
+        }
+    }
+}
+
 /// عنصر مستخرج من الجملة
 #[derive(Debug, Clone)]
 pub struct ParsedElement {
@@ -241,6 +263,46 @@ impl SimpleNLUParser {
         basic_result
     }
 
+    /// تحليل يستعين بأنطولوجيا محرك المنطق: إن كانت العلاقة معرّفة بمفاهيم
+    /// فالفاعل والمفعول اللذان يناسبانها يرفعان الثقة، ويُبدَّلان إن لم يناسبها
+    /// إلا الترتيب المعكوس، وتنخفض الثقة إن لم يناسبها أيّ منهما
+    /// ("سمك يأكل قط" تُفهم على أن القط هو الآكل)
+    pub fn parse_with_ontology(&self, sentence: &str, engine: &LogicEngine) -> ParseResult {
+        let mut result = self.parse_with_patterns(sentence);
+        let (Some(subject), Some(object)) = (result.subject.clone(), result.object.clone()) else {
+            return result;
+        };
+        let Some(signature) = result.relation_type.predicate().and_then(|name| engine.relation_signature(name)) else {
+            return result;
+        };
+        if signature.len() != 2 || !signature.iter().any(|arg_type| engine.ontology().is_concept(arg_type)) {
+            return result;
+        }
+
+        let fits = |first: &str, second: &str| {
+            [first, second].iter().zip(signature).all(|(name, arg_type)| {
+                !engine.ontology().is_concept(arg_type) || engine.conforms(name, arg_type).unwrap_or(false)
+            })
+        };
+        if fits(&subject, &object) {
+            result.confidence = (result.confidence + 1.0) / 2.0;
+        } else if fits(&object, &subject) {
+            result.subject = Some(object);
+            result.object = Some(subject);
+            for element in &mut result.elements {
+                element.element_type = match element.element_type {
+                    ElementType::Subject => ElementType::Object,
+                    ElementType::Object => ElementType::Subject,
+                    ref other => other.clone(),
+                };
+            }
+            result.suggested_api_call = self.generate_api_call(&result.relation_type, &result.subject, &result.object);
+        } else {
+            result.confidence /= 2.0;
+        }
+        result
+    }
+
     /// فحص مطابقة النمط
     fn matches_pattern(&self, sentence: &str, pattern: &str) -> bool {
         // تنفيذ بسيط لمطابقة الأنماط
@@ -319,6 +381,28 @@ mod tests {
         assert_eq!(result.relation_type, NLURelationType::Eats);
     }
 
+    #[test]
+    fn test_ontology_disambiguation() {
+        let mut engine = LogicEngine::new();
+        engine.define_concept("حيوان", &[]).unwrap();
+        engine.define_concept("طعام", &[]).unwrap();
+        engine.declare_relation("eats", &["حيوان", "طعام"]);
+        engine.assert_facts(&["is_a(قط, حيوان)", "is_a(سمك, طعام)"]).unwrap();
+
+        let parser = SimpleNLUParser::new();
+        let plain = parser.parse_with_patterns("قط يأكل سمك");
+        let fitting = parser.parse_with_ontology("قط يأكل سمك", &engine);
+        assert!(fitting.confidence > plain.confidence);
+
+        let swapped = parser.parse_with_ontology("سمك يأكل قط", &engine);
+        assert_eq!(swapped.subject, Some("قط".to_string()));
+        assert_eq!(swapped.object, Some("سمك".to_string()));
+        assert_eq!(swapped.elements[0].element_type, ElementType::Object);
+
+        let unfitting = parser.parse_with_ontology("حجر يأكل سمك", &engine);
+        assert!(unfitting.confidence < plain.confidence);
+    }
+
     #[test]
     fn test_api_generation() {
         let result = parse_simple_natural_language("كتاب فوق طاولة");
@@ -0,0 +1,949 @@
+//! # Persistent REPL Sessions
+//!
+//! A [`Session`] keeps everything a cell declares — functions, types, traits,
+//! relations, facts and rules — so later cells can use it, the way a notebook
+//! builds on its earlier cells. Each cell is checked together with the
+//! declarations before it and only kept if the whole program still passes
+//! semantic analysis; a failing cell leaves the session as it was.
+//!
+//! A cell that does not start with a declaration is run as statements by the
+//! [`Interpreter`]: `let x = 5;` in one cell and `x + 1` in the next prints
+//! `6`. The statement cells are checked as the body of one function, each
+//! nested in the block of the cell before it, and only the new cell runs,
+//! against the values the earlier ones left. A `let` of a variable an earlier
+//! cell declared replaces it: the earlier cells are checked with their
+//! variable renamed. A trailing expression's value is printed after its output.
+//!
+//! Facts and rules are also loaded into the session's logic engine, which the
+//! `assert`, `retract` and `query` of statement cells use too, and a cell
+//! written `?- goal, goal.` queries it. The [`server`] module exposes
+//! sessions to notebook front ends over a JSON-lines protocol.
+//!
+//! A cell starting with `:` is a command about the session rather than code:
+//!
+//! - `:type expr` prints the type analysis infers for `expr`, with the
+//!   session's declarations and variables in scope
+//! - `:ast` prints the parse tree of the last cell of code
+//! - `:kb` lists the concepts, constraints, facts and rules loaded so far
+//! - `:load file.ab` declares what the file declares, as a cell would
+
+pub mod server;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use serde::Serialize;
+
+use crate::diagnostics::{Diagnostic, DiagnosticRenderer};
+use crate::interpreter::{Interpreter, Panic, Value};
+use crate::lexer::{LineIndex, TokenType};
+use crate::parser::ast::{
+    Block, Expression, FunctionDecl, IfStatement, Item, Literal, MatchStatement, Parameter, Pattern, Program,
+    Statement,
+};
+use crate::runtime::{LogicEngine, Solution};
+use crate::semantic::{AnnotatedItem, AnnotatedProgram, AnnotatedStatement, SemanticError};
+use crate::{CompilerError, CompilerOptions};
+
+/// Prefix of a cell that queries the session's knowledge base
+pub const QUERY_PREFIX: &str = "?-";
+
+/// Prefix of a cell that is a command to the session, such as `:type x`
+pub const COMMAND_PREFIX: char = ':';
+
+/// Name of the function statement cells are checked as; not a name a
+/// declaration can take
+const STATEMENTS_FUNCTION: &str = "<repl>";
+
+type CellOutcome = Result<(Vec<String>, Vec<BTreeMap<String, String>>), String>;
+
+/// Whether `input` closes every brace, parenthesis and bracket it opens, so
+/// that a line-based front end knows whether to keep reading. Input that does
+/// not lex counts as complete, for the session to report the error.
+pub fn is_complete(input: &str) -> bool {
+    let Ok(tokens) = crate::lexer::Lexer::new(input).tokenize() else {
+        return true;
+    };
+    let depth = tokens.iter().fold(0i64, |depth, token| match token.token_type {
+        TokenType::LeftBrace | TokenType::LeftParen | TokenType::LeftBracket => depth + 1,
+        TokenType::RightBrace | TokenType::RightParen | TokenType::RightBracket => depth - 1,
+        _ => depth,
+    });
+    depth <= 0
+}
+
+/// The parse tree of a cell of code
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellInput {
+    Items(Vec<Item>),
+    Statements(Vec<Statement>),
+}
+
+/// Whether a cell was accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CellStatus {
+    Ok,
+    Error,
+}
+
+/// What executing one cell produced
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CellResult {
+    /// Number of the cell in its session, counting from 1
+    pub execution_count: usize,
+    pub status: CellStatus,
+    /// One line per declaration, the output and value of statements, or the
+    /// solutions of a query as text
+    pub outputs: Vec<String>,
+    /// Variable bindings of each solution of a query cell
+    pub solutions: Vec<BTreeMap<String, String>>,
+    /// Rendered diagnostic of a rejected cell
+    pub error: Option<String>,
+}
+
+/// A REPL session whose declarations persist from cell to cell
+pub struct Session {
+    options: CompilerOptions,
+    items: Vec<Item>,
+    /// Statements of each statement cell kept, in order
+    statements: Vec<Vec<Statement>>,
+    /// Values of the variables the statement cells declared
+    variables: HashMap<String, Value>,
+    /// Parse tree of the last cell of code, for `:ast`
+    last_input: Option<CellInput>,
+    /// File the code being compiled came from, when `:load` read it
+    file_name: Option<String>,
+    /// Whether `:load` may read files; off for sessions a server exposes
+    file_access: bool,
+    logic: LogicEngine,
+    execution_count: usize,
+}
+
+impl Session {
+    pub fn new(options: CompilerOptions) -> Self {
+        Self {
+            options,
+            items: Vec::new(),
+            statements: Vec::new(),
+            variables: HashMap::new(),
+            last_input: None,
+            file_name: None,
+            file_access: true,
+            logic: LogicEngine::new(),
+            execution_count: 0,
+        }
+    }
+
+    /// Refuse `:load`, so that clients of the session cannot read the
+    /// files of the machine it runs on
+    pub fn without_file_access(mut self) -> Self {
+        self.file_access = false;
+        self
+    }
+
+    /// Number of cells executed so far
+    pub fn execution_count(&self) -> usize {
+        self.execution_count
+    }
+
+    /// Declarations kept by the session, in the order they were made
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    /// Value of the variable `name` of the statement cells
+    pub fn variable(&self, name: &str) -> Option<&Value> {
+        self.variables.get(name)
+    }
+
+    /// Forget every declaration, variable, fact and rule
+    pub fn reset(&mut self) {
+        self.items.clear();
+        self.statements.clear();
+        self.variables.clear();
+        self.last_input = None;
+        self.logic = LogicEngine::new();
+        self.execution_count = 0;
+    }
+
+    /// Execute a cell: a logic query, a command, declarations added to the
+    /// session, or statements run in it
+    pub fn execute(&mut self, cell: &str) -> CellResult {
+        self.execution_count += 1;
+        let cell = cell.trim();
+        let command = cell
+            .strip_prefix(COMMAND_PREFIX)
+            .filter(|command| command.starts_with(|c: char| c.is_ascii_alphabetic()));
+        let outcome = match (cell.strip_prefix(QUERY_PREFIX), command) {
+            (Some(query), _) => self.query(query),
+            (None, Some(command)) => self.command(command),
+            (None, None) => {
+                let started = std::time::Instant::now();
+                let outcome = self.compile(cell);
+                crate::metrics::record_compilation(started.elapsed(), outcome.is_ok());
+                outcome
+            }
+        };
+        self.result(outcome)
+    }
+
+    /// Declare what the file at `path` declares, like a `:load` cell
+    pub fn load(&mut self, path: &Path) -> CellResult {
+        self.execution_count += 1;
+        let outcome = self.load_file(path);
+        self.result(outcome)
+    }
+
+    fn result(&self, outcome: CellOutcome) -> CellResult {
+        match outcome {
+            Ok((outputs, solutions)) => CellResult {
+                execution_count: self.execution_count,
+                status: CellStatus::Ok,
+                outputs,
+                solutions,
+                error: None,
+            },
+            Err(error) => CellResult {
+                execution_count: self.execution_count,
+                status: CellStatus::Error,
+                outputs: Vec::new(),
+                solutions: Vec::new(),
+                error: Some(error),
+            },
+        }
+    }
+
+    fn query(&mut self, query: &str) -> CellOutcome {
+        let query = query.trim().trim_end_matches('.');
+        let solutions: Vec<BTreeMap<String, String>> = self
+            .logic
+            .solve_query(query)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|bindings| bindings.into_iter().collect())
+            .collect();
+
+        let outputs = match solutions.as_slice() {
+            [] => vec!["false".to_string()],
+            [bindings] if bindings.is_empty() => vec!["true".to_string()],
+            _ => solutions
+                .iter()
+                .map(|bindings| {
+                    bindings
+                        .iter()
+                        .map(|(variable, value)| format!("{} = {}", variable, value))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .collect(),
+        };
+        Ok((outputs, solutions))
+    }
+
+    /// Solve `query` against the session's knowledge base, recording how
+    /// each solution was derived
+    pub fn prove(&mut self, query: &str) -> Result<Vec<Solution>, String> {
+        let query = query.trim().trim_end_matches('.');
+        self.logic.solve_query_with_proofs(query).map_err(|e| e.to_string())
+    }
+
+    fn command(&mut self, command: &str) -> CellOutcome {
+        let (name, argument) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+        let argument = argument.trim();
+        let outputs = match name {
+            "type" => vec![self.type_of(argument)?],
+            "ast" => match &self.last_input {
+                Some(input) => format!("{:#?}", input).lines().map(str::to_string).collect(),
+                None => vec!["no code entered yet".to_string()],
+            },
+            "kb" => self.knowledge(),
+            "load" if !self.file_access => return Err(format!("`{}load` is disabled in this session", COMMAND_PREFIX)),
+            "load" => return self.load_file(Path::new(argument)),
+            _ => {
+                return Err(format!(
+                    "unknown command `{}{}`; expected :type, :ast, :kb or :load",
+                    COMMAND_PREFIX, name
+                ))
+            }
+        };
+        Ok((outputs, Vec::new()))
+    }
+
+    /// Type of `expression` with the declarations and variables of the
+    /// session in scope, as analysis writes it in diagnostics
+    pub fn type_of(&self, expression: &str) -> Result<String, String> {
+        let tokens = crate::lexer::Lexer::new(expression)
+            .tokenize()
+            .map_err(|e| self.render(expression, e.into()))?;
+        let statements = crate::parser::Parser::new(tokens)
+            .parse_statements()
+            .map_err(|e| self.render(expression, e.into()))?;
+        if !matches!(statements.as_slice(), [Statement::Expression(_)]) {
+            return Err(format!("{}type takes one expression", COMMAND_PREFIX));
+        }
+
+        let mut cells = self.statements.clone();
+        cells.push(statements);
+        let program = self
+            .analyze(self.items.clone(), &cells)
+            .map_err(|e| self.render(expression, e.into()))?;
+        let body = program.items.iter().find_map(|item| match item {
+            AnnotatedItem::Function(function) if function.name == STATEMENTS_FUNCTION => Some(&function.body),
+            _ => None,
+        });
+        // The expression is the last cell, nested once per cell before it
+        let mut block = body.expect("statement cells are analyzed as a function");
+        for _ in 1..cells.len() {
+            match block.statements.last() {
+                Some(AnnotatedStatement::If(nested)) => block = &nested.then_block,
+                _ => unreachable!("each statement cell ends in the next one"),
+            }
+        }
+        match block.statements.last() {
+            Some(AnnotatedStatement::Expression(annotated)) => Ok(format!("{:?}", annotated.result_type)),
+            _ => unreachable!("the last cell is the expression"),
+        }
+    }
+
+    /// The concepts, constraints, facts and rules loaded into the logic
+    /// engine, in the order they were declared
+    pub fn knowledge(&self) -> Vec<String> {
+        let knowledge: Vec<String> = self
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Concept(concept) if concept.parents.is_empty() => Some(format!("concept {}", concept.name)),
+                Item::Concept(concept) => Some(format!("concept {}: {}", concept.name, concept.parents.join(", "))),
+                Item::Constraint(constraint) => Some(format!("constraint {}", constraint)),
+                Item::Fact(fact) => Some(format!("fact {}", fact)),
+                Item::Rule(rule) => Some(format!("rule {}", rule)),
+                _ => None,
+            })
+            .collect();
+        if knowledge.is_empty() {
+            return vec!["no facts or rules".to_string()];
+        }
+        knowledge
+    }
+
+    /// Declare what the file at `path` declares, as a cell of its source would
+    fn load_file(&mut self, path: &Path) -> CellOutcome {
+        let source = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        self.file_name = Some(path.display().to_string());
+        let outcome = self.compile(&source);
+        self.file_name = None;
+        outcome
+    }
+
+    /// File name diagnostics of the current cell are rendered with
+    fn cell_name(&self) -> String {
+        self.file_name
+            .clone()
+            .unwrap_or_else(|| format!("cell[{}]", self.execution_count))
+    }
+
+    fn render(&self, cell: &str, error: CompilerError) -> String {
+        let language = crate::diagnostics::language();
+        DiagnosticRenderer::new(&self.cell_name(), cell)
+            .with_language(language)
+            .render(&Diagnostic::localized(&error, language))
+    }
+
+    /// Declare the items of a cell, or run its statements when it does not
+    /// start with a declaration
+    fn compile(&mut self, cell: &str) -> CellOutcome {
+        let tokens = crate::lexer::Lexer::new(cell)
+            .tokenize()
+            .map_err(|e| self.render(cell, e.into()))?;
+        let mut parser = crate::parser::Parser::new(tokens);
+        if parser.starts_with_item() {
+            let items = parser.parse().map_err(|e| self.render(cell, e.into()))?.items;
+            self.last_input = Some(CellInput::Items(items.clone()));
+            self.declare(cell, items)
+        } else {
+            let statements = parser.parse_statements().map_err(|e| self.render(cell, e.into()))?;
+            self.last_input = Some(CellInput::Statements(statements.clone()));
+            self.run(cell, statements)
+        }
+    }
+
+    /// Analyze `items` together with the statement cells `statements`
+    fn analyze(&self, items: Vec<Item>, statements: &[Vec<Statement>]) -> Result<AnnotatedProgram, SemanticError> {
+        let mut program = Program { items, comments: Vec::new() };
+        // Innermost cell first, each ending in an `if true { ... }` around the
+        // one after it, which keeps the nested cells in the annotated program
+        let body = statements.iter().rev().fold(None, |inner: Option<Block>, cell| {
+            let mut block = Block { statements: cell.clone(), span: Default::default() };
+            block.statements.extend(inner.map(|then_block| {
+                Statement::If(IfStatement {
+                    condition: Expression::Literal(Literal::Boolean(true)),
+                    then_block,
+                    else_block: None,
+                    span: Default::default(),
+                })
+            }));
+            Some(block)
+        });
+        if let Some(body) = body {
+            program.items.push(Item::Function(FunctionDecl {
+                name: STATEMENTS_FUNCTION.to_string(),
+                visibility: Default::default(),
+                generic_params: None,
+                parameters: Vec::new(),
+                return_type: None,
+                body,
+                is_test: false,
+                span: Default::default(),
+            }));
+        }
+        crate::semantic::SemanticAnalyzer::new(&self.options).analyze(program)
+    }
+
+    /// Run the statements of a cell with the variables of the earlier ones
+    fn run(&mut self, cell: &str, mut statements: Vec<Statement>) -> CellOutcome {
+        let mut cells = self.statements.clone();
+        let mut variables = self.variables.clone();
+        // A `let` of a session variable replaces it: the earlier cells, and
+        // this one up to that `let`'s value, use the old one under a name no
+        // source can write
+        let mut hidden_names = Vec::new();
+        for (index, name) in declared_names(&statements) {
+            let Some(value) = self.variables.get(&name) else { continue };
+            let hidden = format!("{}#{}", name, self.execution_count);
+            cells.iter_mut().for_each(|cell| rename_in_statements(cell, &name, &hidden));
+            let (before, declaring) = statements.split_at_mut(index);
+            rename_in_statements(before, &name, &hidden);
+            if let Some(Statement::Let(stmt)) = declaring.first_mut() {
+                if let Some(initializer) = &mut stmt.initializer {
+                    rename_in_expression(initializer, &name, &hidden);
+                }
+            }
+            variables.insert(hidden.clone(), value.clone());
+            hidden_names.push(hidden);
+        }
+        cells.push(statements);
+        self.analyze(self.items.clone(), &cells)
+            .map_err(|e| self.render(cell, e.into()))?;
+
+        let program = Program { items: self.items.clone(), comments: Vec::new() };
+        let statements = cells.last().expect("the cell was just added");
+        let mut interpreter = Interpreter::with_logic(&program, &mut self.logic);
+        let result = interpreter.run_statements(statements, &mut variables);
+        let mut outputs: Vec<String> = interpreter.take_output().lines().map(str::to_string).collect();
+        let value = result.map_err(|panic| self.panic_report(cell, &panic))?;
+        if statements.last().is_some_and(Statement::is_value) && value != Value::Unit {
+            outputs.push(value.to_string());
+        }
+
+        for hidden in hidden_names {
+            variables.remove(&hidden);
+        }
+        self.variables = variables;
+        self.statements = cells;
+        Ok((outputs, Vec::new()))
+    }
+
+    /// A panic of a statement cell, located in the cell when it happened in
+    /// its own statements rather than in a function they called
+    fn panic_report(&self, cell: &str, panic: &Panic) -> String {
+        let location = match panic.span {
+            Some(span) if panic.backtrace.is_empty() => {
+                let position = LineIndex::new(cell).line_col(span.start);
+                format!("{}:{}:{}", self.cell_name(), position.line, position.column)
+            }
+            _ => String::new(),
+        };
+        crate::runtime::panic::panic_report(&panic.message, &location)
+    }
+
+    fn declare(&mut self, cell: &str, cell_items: Vec<Item>) -> CellOutcome {
+        // A declaration replaces an earlier one of the same name
+        let keys: Vec<Option<String>> = cell_items.iter().map(item_key).collect();
+        let mut items: Vec<Item> = self
+            .items
+            .iter()
+            .filter(|item| match item_key(item) {
+                Some(key) => !keys.contains(&Some(key)),
+                None => true,
+            })
+            .cloned()
+            .collect();
+        let redefined = items.len() < self.items.len();
+        items.extend(cell_items.iter().cloned());
+
+        self.analyze(items.clone(), &self.statements)
+            .map_err(|e| self.render(cell, e.into()))?;
+
+        self.logic.load_items(&cell_items).map_err(|e| e.to_string())?;
+        let mut outputs = Vec::new();
+        for (item, key) in cell_items.iter().zip(&keys) {
+            match item {
+                Item::Fact(fact) => outputs.push(format!("fact {}", fact)),
+                Item::Rule(rule) => outputs.push(format!("rule {}", rule)),
+                Item::Concept(concept) => outputs.push(format!("concept {}", concept.name)),
+                Item::Constraint(constraint) => outputs.push(format!("constraint {}", constraint)),
+                _ => match key {
+                    Some(key) if redefined && self.items.iter().any(|old| item_key(old).as_ref() == Some(key)) => {
+                        outputs.push(format!("redefined {}", key));
+                    }
+                    Some(key) => outputs.push(format!("defined {}", key)),
+                    None => {}
+                },
+            }
+        }
+
+        self.items = items;
+        Ok((outputs, Vec::new()))
+    }
+}
+
+/// Each solution of a query with its bindings, followed by the derivation
+/// tree of each of its goals indented below it, or `false` when there is none
+pub fn proofs_text(solutions: &[Solution]) -> String {
+    if solutions.is_empty() {
+        return "false".to_string();
+    }
+    let mut lines = Vec::new();
+    for (index, solution) in solutions.iter().enumerate() {
+        let bindings: Vec<String> =
+            solution.bindings.iter().map(|(variable, value)| format!("{} = {}", variable, value)).collect();
+        match bindings.as_slice() {
+            [] => lines.push(format!("Solution {}: true", index + 1)),
+            _ => lines.push(format!("Solution {}: {}", index + 1, bindings.join(", "))),
+        }
+        for tree in solution.proof() {
+            lines.extend(tree.to_string().lines().map(|line| format!("  {}", line)));
+        }
+    }
+    lines.join("\n")
+}
+
+/// The solutions of a query as a table with a column per variable, or
+/// `true`/`false` when there is nothing to bind
+pub fn solutions_table(solutions: &[BTreeMap<String, String>]) -> String {
+    let variables: Vec<&String> = solutions
+        .iter()
+        .flat_map(|bindings| bindings.keys())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    if variables.is_empty() {
+        return if solutions.is_empty() { "false" } else { "true" }.to_string();
+    }
+
+    let cell = |bindings: &BTreeMap<String, String>, variable: &String| bindings.get(variable).cloned().unwrap_or_default();
+    let widths: Vec<usize> = variables
+        .iter()
+        .map(|variable| {
+            let values = solutions.iter().map(|bindings| cell(bindings, variable).chars().count());
+            values.chain([variable.chars().count()]).max().unwrap_or(0)
+        })
+        .collect();
+    let row = |values: Vec<String>| {
+        let padded: Vec<String> =
+            values.iter().zip(&widths).map(|(value, width)| format!("{:<width$}", value, width = *width)).collect();
+        padded.join(" | ").trim_end().to_string()
+    };
+
+    let mut lines = vec![row(variables.iter().map(|variable| variable.to_string()).collect())];
+    lines.push(widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<_>>().join("-+-"));
+    for bindings in solutions {
+        lines.push(row(variables.iter().map(|variable| cell(bindings, variable)).collect()));
+    }
+    lines.join("\n")
+}
+
+/// Variables the top-level `let`s of a statement cell declare, each with
+/// the index of the first `let` declaring it
+fn declared_names(statements: &[Statement]) -> Vec<(usize, String)> {
+    let mut names: Vec<(usize, String)> = Vec::new();
+    for (index, statement) in statements.iter().enumerate() {
+        let Statement::Let(stmt) = statement else { continue };
+        let bindings = match &stmt.pattern {
+            Some(pattern) => pattern.bindings(),
+            None => vec![stmt.name.as_str()],
+        };
+        for name in bindings {
+            if !names.iter().any(|(_, declared)| declared == name) {
+                names.push((index, name.to_string()));
+            }
+        }
+    }
+    names
+}
+
+/// Rename the variable `from` to `to` everywhere in `statements`, where it
+/// is declared as well as where it is used
+fn rename_in_statements(statements: &mut [Statement], from: &str, to: &str) {
+    for statement in statements {
+        rename_in_statement(statement, from, to);
+    }
+}
+
+fn rename_in_statement(statement: &mut Statement, from: &str, to: &str) {
+    let rename = |name: &mut String| {
+        if name == from {
+            *name = to.to_string();
+        }
+    };
+    match statement {
+        Statement::Expression(stmt) => rename_in_expression(&mut stmt.expression, from, to),
+        Statement::Let(stmt) => {
+            rename(&mut stmt.name);
+            if let Some(pattern) = &mut stmt.pattern {
+                rename_in_pattern(pattern, from, to);
+            }
+            if let Some(initializer) = &mut stmt.initializer {
+                rename_in_expression(initializer, from, to);
+            }
+        }
+        Statement::Return(stmt) => {
+            if let Some(value) = &mut stmt.value {
+                rename_in_expression(value, from, to);
+            }
+        }
+        Statement::If(stmt) => rename_in_if(stmt, from, to),
+        Statement::While(stmt) => {
+            rename_in_expression(&mut stmt.condition, from, to);
+            rename_in_statements(&mut stmt.body.statements, from, to);
+        }
+        Statement::For(stmt) => {
+            rename(&mut stmt.variable);
+            rename_in_expression(&mut stmt.iterable, from, to);
+            rename_in_statements(&mut stmt.body.statements, from, to);
+        }
+        Statement::Match(stmt) => rename_in_match(stmt, from, to),
+        Statement::Block(block) => rename_in_statements(&mut block.statements, from, to),
+        Statement::Query(stmt) => {
+            if let Some(handler) = &mut stmt.handler {
+                rename_in_statements(&mut handler.statements, from, to);
+            }
+        }
+        Statement::Assert(stmt) => stmt.args.iter_mut().for_each(|arg| rename_in_expression(arg, from, to)),
+        Statement::Retract(stmt) => stmt.args.iter_mut().for_each(|arg| rename_in_expression(arg, from, to)),
+        Statement::Semantic(_) => {}
+    }
+}
+
+fn rename_in_expression(expression: &mut Expression, from: &str, to: &str) {
+    let each = |expressions: &mut [Expression]| {
+        for expression in expressions {
+            rename_in_expression(expression, from, to);
+        }
+    };
+    match expression {
+        Expression::Identifier(name) if name == from => *name = to.to_string(),
+        Expression::Binary(binary) => {
+            rename_in_expression(&mut binary.left, from, to);
+            rename_in_expression(&mut binary.right, from, to);
+        }
+        Expression::Unary(unary) => rename_in_expression(&mut unary.operand, from, to),
+        Expression::Call(call) => {
+            rename_in_expression(&mut call.callee, from, to);
+            each(&mut call.arguments);
+        }
+        Expression::FieldAccess(access) => rename_in_expression(&mut access.object, from, to),
+        Expression::Index(index) => {
+            rename_in_expression(&mut index.object, from, to);
+            rename_in_expression(&mut index.index, from, to);
+        }
+        Expression::Array(array) => each(&mut array.elements),
+        Expression::ArrayRepeat(repeat) => {
+            rename_in_expression(&mut repeat.value, from, to);
+            rename_in_expression(&mut repeat.count, from, to);
+        }
+        Expression::Map(map) => {
+            for (key, value) in &mut map.entries {
+                rename_in_expression(key, from, to);
+                rename_in_expression(value, from, to);
+            }
+        }
+        Expression::Set(set) => each(&mut set.elements),
+        Expression::Tuple(tuple) => each(&mut tuple.elements),
+        Expression::Struct(literal) => literal.fields.iter_mut().for_each(|(_, value)| rename_in_expression(value, from, to)),
+        Expression::Enum(literal) => literal.fields.iter_mut().flatten().for_each(|value| rename_in_expression(value, from, to)),
+        Expression::Lambda(lambda) => {
+            // A parameter of the same name hides the variable in the body
+            let hidden = lambda.parameters.iter().any(|parameter| {
+                matches!(parameter, Parameter::Regular { name, .. } if name == from)
+            });
+            if !hidden {
+                rename_in_expression(&mut lambda.body, from, to);
+            }
+        }
+        Expression::Async(async_expr) => rename_in_statements(&mut async_expr.body.statements, from, to),
+        Expression::Await(await_expr) => rename_in_expression(&mut await_expr.expression, from, to),
+        Expression::Match(stmt) => rename_in_match(stmt, from, to),
+        Expression::If(stmt) => rename_in_if(stmt, from, to),
+        Expression::Literal(_) | Expression::Identifier(_) | Expression::Query(_) => {}
+    }
+}
+
+fn rename_in_if(stmt: &mut IfStatement, from: &str, to: &str) {
+    rename_in_expression(&mut stmt.condition, from, to);
+    rename_in_statements(&mut stmt.then_block.statements, from, to);
+    if let Some(else_block) = &mut stmt.else_block {
+        rename_in_statements(&mut else_block.statements, from, to);
+    }
+}
+
+fn rename_in_match(stmt: &mut MatchStatement, from: &str, to: &str) {
+    rename_in_expression(&mut stmt.expression, from, to);
+    for arm in &mut stmt.arms {
+        rename_in_pattern(&mut arm.pattern, from, to);
+        if let Some(guard) = &mut arm.guard {
+            rename_in_expression(guard, from, to);
+        }
+        rename_in_statements(&mut arm.body.statements, from, to);
+    }
+}
+
+fn rename_in_pattern(pattern: &mut Pattern, from: &str, to: &str) {
+    match pattern {
+        Pattern::Identifier(name) if name == from => *name = to.to_string(),
+        Pattern::Tuple(patterns) | Pattern::Enum(_, Some(patterns)) => {
+            patterns.iter_mut().for_each(|pattern| rename_in_pattern(pattern, from, to));
+        }
+        Pattern::Struct(_, fields) => fields.iter_mut().for_each(|(_, pattern)| rename_in_pattern(pattern, from, to)),
+        Pattern::Identifier(_) | Pattern::Wildcard | Pattern::Literal(_) | Pattern::Enum(_, None) => {}
+    }
+}
+
+/// Kind and name identifying a declaration; facts, rules and constraints
+/// accumulate instead
+fn item_key(item: &Item) -> Option<String> {
+    match item {
+        Item::Function(decl) => Some(format!("fn {}", decl.name)),
+        Item::Struct(decl) => Some(format!("struct {}", decl.name)),
+        Item::Enum(decl) => Some(format!("enum {}", decl.name)),
+        Item::Class(decl) => Some(format!("class {}", decl.name)),
+        Item::Interface(decl) => Some(format!("interface {}", decl.name)),
+        Item::Trait(decl) => Some(format!("trait {}", decl.name)),
+        Item::Impl(decl) => Some(match &decl.trait_name {
+            Some(trait_name) => format!("impl {} for {}", trait_name, decl.type_name),
+            None => format!("impl {}", decl.type_name),
+        }),
+        Item::Relation(decl) => Some(format!("relation {}", decl.name)),
+        Item::Concept(decl) => Some(format!("concept {}", decl.name)),
+        Item::Module(decl) => Some(format!("module {}", decl.name)),
+        Item::Const(decl) => Some(format!("const {}", decl.name)),
+        Item::Rule(_) | Item::Fact(_) | Item::Constraint(_) | Item::Using(_) | Item::Semantic(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declarations_persist_across_cells() {
+        let mut session = Session::new(CompilerOptions::default());
+
+        let first = session.execute("fn double(x: int) -> int { return x * 2; }");
+        assert_eq!(first.status, CellStatus::Ok, "{:?}", first.error);
+        assert_eq!(first.outputs, vec!["defined fn double"]);
+
+        let second = session.execute("fn quadruple(x: int) -> int { return double(double(x)); }");
+        assert_eq!(second.status, CellStatus::Ok, "{:?}", second.error);
+        assert_eq!(second.execution_count, 2);
+
+        // A rejected cell leaves the session untouched
+        let broken = session.execute("fn broken() -> int { return missing(1); }");
+        assert_eq!(broken.status, CellStatus::Error);
+        assert!(broken.error.unwrap().contains("cell[3]"));
+        assert_eq!(session.items().len(), 2);
+
+        let redefined = session.execute("fn double(x: int) -> int { return x + x; }");
+        assert_eq!(redefined.outputs, vec!["redefined fn double"]);
+        assert_eq!(session.items().len(), 2);
+    }
+
+    #[test]
+    fn test_statement_cells_keep_their_variables() {
+        let mut session = Session::new(CompilerOptions::default());
+        session.execute("fn double(x: int) -> int { return x * 2; }");
+
+        let declared = session.execute("let x = 5;");
+        assert_eq!(declared.status, CellStatus::Ok, "{:?}", declared.error);
+        assert!(declared.outputs.is_empty());
+        assert_eq!(session.execute("x + 1").outputs, vec!["6"]);
+        assert_eq!(session.execute("println(\"{}\", double(x)); x").outputs, vec!["10", "5"]);
+        // A `;` discards the value; a trailing if or match is the value
+        assert!(session.execute("double(x);").outputs.is_empty());
+        assert_eq!(session.execute("if x > 1 { double(x) } else { 0 }").outputs, vec!["10"]);
+        assert_eq!(session.execute("match x { 5 => 1, _ => 0 }").outputs, vec!["1"]);
+
+        // A later cell may shadow a variable, and a rejected one changes nothing
+        assert_eq!(session.execute("let x = x - 1; x").outputs, vec!["4"]);
+        assert_eq!(session.execute("let x = \"five\";").status, CellStatus::Ok);
+        let rejected = session.execute("let y = x * 2;");
+        assert_eq!(rejected.status, CellStatus::Error);
+        assert!(rejected.error.unwrap().contains("cell[10]"));
+        assert_eq!(session.variable("x"), Some(&Value::String("five".to_string())));
+        assert_eq!(session.variable("y"), None);
+
+        let panicked = session.execute("let z = 1; assert(z > 1, \"z is too small\");");
+        let error = panicked.error.unwrap();
+        assert!(error.starts_with("panicked at cell[11]:1:"), "{}", error);
+        assert!(error.ends_with("assertion failed: z is too small"), "{}", error);
+        assert_eq!(session.variable("z"), None);
+
+        // Redefining a function the statements call must keep them valid
+        let redefined = session.execute("fn double(x: string) -> string { return x; }");
+        assert_eq!(redefined.status, CellStatus::Error);
+        session.reset();
+        assert_eq!(session.variable("x"), None);
+    }
+
+    #[test]
+    fn test_commands_introspect_the_session() {
+        let mut session = Session::new(CompilerOptions::default());
+        assert_eq!(session.execute(":kb").outputs, vec!["no facts or rules"]);
+        assert_eq!(session.execute(":ast").outputs, vec!["no code entered yet"]);
+
+        session.execute("struct Point { x: int, y: int }");
+        session.execute("let p = Point { x: 1, y: 2 };");
+        session.execute("let p = p.x > 0;");
+        assert_eq!(session.execute(":type p").outputs, vec!["Bool"]);
+        assert_eq!(session.execute(":type Point { x: 3, y: 4 }").outputs, vec!["Struct(\"Point\")"]);
+        assert!(session.execute(":type missing").error.unwrap().contains("missing"));
+        assert!(session.execute(":ast").outputs[0].starts_with("Statements("));
+
+        let path = std::env::temp_dir().join(format!("albayan_repl_load_{}.ab", std::process::id()));
+        std::fs::write(
+            &path,
+            "relation parent(string, string);\nrelation ancestor(string, string);\nfact parent(\"ali\", \"sara\");\nrule ancestor(X, Y) :- parent(X, Y);\n",
+        )
+        .unwrap();
+        let loaded = session.execute(&format!(":load {}", path.display()));
+        assert_eq!(loaded.status, CellStatus::Ok, "{:?}", loaded.error);
+        assert_eq!(
+            session.execute(":kb").outputs,
+            vec!["fact parent(\"ali\", \"sara\").", "rule ancestor(X, Y) :- parent(X, Y)."]
+        );
+        assert_eq!(session.execute("?- ancestor(\"ali\", Who)").solutions.len(), 1);
+
+        std::fs::write(&path, "fn broken() -> int { return missing; }\n").unwrap();
+        let failed = session.execute(&format!(":load {}", path.display()));
+        assert!(failed.error.unwrap().contains(&path.display().to_string()));
+        std::fs::remove_file(&path).unwrap();
+        assert!(session.execute(":frobnicate").error.unwrap().contains("unknown command"));
+    }
+
+    #[test]
+    fn test_solutions_table() {
+        let solution = |pairs: &[(&str, &str)]| -> BTreeMap<String, String> {
+            pairs.iter().map(|(variable, value)| (variable.to_string(), value.to_string())).collect()
+        };
+        assert_eq!(solutions_table(&[]), "false");
+        assert_eq!(solutions_table(&[solution(&[])]), "true");
+        let table = solutions_table(&[
+            solution(&[("X", "\"ahmad\""), ("Y", "1")]),
+            solution(&[("X", "\"sara\""), ("Y", "12345678")]),
+        ]);
+        assert_eq!(table, "X       | Y\n--------+---------\n\"ahmad\" | 1\n\"sara\"  | 12345678");
+    }
+
+    #[test]
+    fn test_input_is_complete_when_brackets_balance() {
+        assert!(is_complete("let x = 5;"));
+        assert!(!is_complete("fn double(x: int) -> int {"));
+        assert!(!is_complete("fn double(x: int) -> int {\n    return max(x,"));
+        assert!(is_complete("fn double(x: int) -> int {\n    return max(x,\n 2);\n}"));
+        // Brackets in strings and comments do not count
+        assert!(is_complete("println(\"( [\"); // (\n"));
+        assert!(is_complete("}"));
+    }
+
+    #[test]
+    fn test_query_cells_see_earlier_facts_and_rules() {
+        let mut session = Session::new(CompilerOptions::default());
+        let facts = session.execute(
+            "relation parent(string, string);
+             relation grandparent(string, string);
+             fact parent(\"ali\", \"sara\");
+             fact parent(\"sara\", \"huda\");
+             rule grandparent(X, Z) :- parent(X, Y), parent(Y, Z);",
+        );
+        assert_eq!(facts.status, CellStatus::Ok, "{:?}", facts.error);
+
+        let result = session.execute("?- grandparent(\"ali\", Who).");
+        assert_eq!(result.solutions.len(), 1);
+        assert_eq!(result.solutions[0]["Who"], "\"huda\"");
+        assert_eq!(session.execute("?- parent(\"huda\", X)").outputs, vec!["false"]);
+
+        session.reset();
+        assert!(session.items().is_empty());
+        assert_eq!(session.execute("?- parent(\"ali\", X)").outputs, vec!["false"]);
+    }
+
+    #[test]
+    fn test_tabled_relations_are_loaded() {
+        let mut session = Session::new(CompilerOptions::default());
+        let declared = session.execute(
+            "relation edge(int, int);
+             #[table]
+             relation path(int, int);
+             fact edge(1, 2);
+             fact edge(2, 1);
+             rule path(X, Y) :- path(X, Z), edge(Z, Y);
+             rule path(X, Y) :- edge(X, Y);",
+        );
+        assert_eq!(declared.status, CellStatus::Ok, "{:?}", declared.error);
+        assert_eq!(session.execute("?- path(1, To).").solutions.len(), 2);
+    }
+
+    #[test]
+    fn test_statement_cells_update_the_session_knowledge() {
+        let mut session = Session::new(CompilerOptions::default());
+        session.execute("relation parent(string, string);\nfact parent(\"ali\", \"sara\");");
+
+        let result = session.execute("assert parent(\"sara\", \"huda\");");
+        assert_eq!(result.status, CellStatus::Ok, "{:?}", result.error);
+        assert_eq!(session.execute("?- parent(\"sara\", X).").solutions[0]["X"], "\"huda\"");
+
+        session.execute("retract parent(\"ali\", \"sara\");");
+        assert_eq!(session.execute("?- parent(\"ali\", X)").outputs, vec!["false"]);
+        let found = session.execute("query parent(\"sara\", Who)");
+        assert_eq!(found.outputs, vec!["[{Who: huda}]"]);
+    }
+
+    #[test]
+    fn test_proofs_show_the_rules_and_facts_used() {
+        let mut session = Session::new(CompilerOptions::default());
+        session.execute(
+            "relation parent(string, string);
+             relation grandparent(string, string);
+             fact parent(\"ali\", \"sara\");
+             fact parent(\"sara\", \"huda\");
+             rule grandparent(X, Z) :- parent(X, Y), parent(Y, Z);",
+        );
+
+        let solutions = session.prove("grandparent(\"ali\", Who).").unwrap();
+        assert_eq!(
+            proofs_text(&solutions),
+            "Solution 1: Who = \"huda\"\n  \
+             grandparent(\"ali\", \"huda\")  [rule grandparent(X, Z) :- parent(X, Y), parent(Y, Z)]\n    \
+             parent(\"ali\", \"sara\")  [fact]\n    \
+             parent(\"sara\", \"huda\")  [fact]"
+        );
+        assert_eq!(proofs_text(&session.prove("parent(\"huda\", X)").unwrap()), "false");
+    }
+
+    #[test]
+    fn test_constraint_cells_reject_violating_facts() {
+        let mut session = Session::new(CompilerOptions::default());
+        let declared = session.execute(
+            "relation parent(string, string);
+             constraint no parent(X, X);",
+        );
+        assert_eq!(declared.status, CellStatus::Ok, "{:?}", declared.error);
+        assert_eq!(declared.outputs, vec!["defined relation parent", "constraint :- parent(X, X)."]);
+
+        let rejected = session.execute("fact parent(\"ali\", \"ali\");");
+        assert_eq!(rejected.status, CellStatus::Error);
+        let error = rejected.error.unwrap();
+        assert!(error.contains("parent(\"ali\", \"ali\") violates :- parent(X, X)"), "{}", error);
+        assert_eq!(session.execute("?- parent(X, Y)").outputs, vec!["false"]);
+    }
+}
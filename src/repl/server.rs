@@ -0,0 +1,235 @@
+//! # REPL Server
+//!
+//! Serves persistent [`Session`]s to notebook front ends. The protocol is
+//! JSON lines: each request is one JSON object on its own line and gets one
+//! response line back, over TCP or standard input/output.
+//!
+//! ```text
+//! {"id": 1, "session": "notebook", "code": "relation likes(string, string);"}
+//! {"id": 1, "session": "notebook", "execution_count": 1, "status": "ok", "outputs": ["defined relation likes"], ...}
+//! {"id": 2, "session": "notebook", "op": "reset"}
+//! ```
+//!
+//! `op` is `execute` (the default) or `reset`; `session` defaults to
+//! `default`. Every connection may address any session. Cells of one session
+//! run one at a time in arrival order, while different sessions run in parallel.
+//! The server has no authentication, so its sessions refuse `:load`.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{CellResult, Session};
+use crate::CompilerOptions;
+
+/// Address used by `albayan repl --serve` without a value
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8765";
+
+/// What a request asks the session to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    #[default]
+    Execute,
+    Reset,
+}
+
+/// One request line
+#[derive(Debug, Clone, Deserialize)]
+pub struct Request {
+    /// Echoed back so clients can match responses to requests
+    #[serde(default)]
+    pub id: Value,
+    #[serde(default = "default_session")]
+    pub session: String,
+    #[serde(default)]
+    pub op: Operation,
+    #[serde(default)]
+    pub code: String,
+}
+
+fn default_session() -> String {
+    "default".to_string()
+}
+
+/// One response line
+#[derive(Debug, Clone, Serialize)]
+pub struct Response {
+    pub id: Value,
+    pub session: String,
+    #[serde(flatten)]
+    pub result: CellResult,
+}
+
+/// Sessions by name, shared by every connection of a server
+#[derive(Clone)]
+pub struct SessionRegistry {
+    options: CompilerOptions,
+    sessions: Arc<Mutex<HashMap<String, Arc<Mutex<Session>>>>>,
+}
+
+impl SessionRegistry {
+    pub fn new(options: CompilerOptions) -> Self {
+        Self {
+            options,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The named session, created on first use
+    pub fn session(&self, name: &str) -> Arc<Mutex<Session>> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        sessions
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(Session::new(self.options.clone()).without_file_access())))
+            .clone()
+    }
+
+    /// Names of the sessions created so far
+    pub fn session_names(&self) -> Vec<String> {
+        let sessions = self.sessions.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut names: Vec<String> = sessions.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Answer one request line with one response line (without the newline)
+    pub fn handle_line(&self, line: &str) -> String {
+        let response = match serde_json::from_str::<Request>(line) {
+            Ok(request) => serde_json::json!(self.handle(request)),
+            Err(error) => serde_json::json!({
+                "status": "error",
+                "error": format!("invalid request: {}", error),
+            }),
+        };
+        response.to_string()
+    }
+
+    pub fn handle(&self, request: Request) -> Response {
+        let session = self.session(&request.session);
+        // Only this session waits for the cell; other sessions keep running
+        let mut session = session.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let result = match request.op {
+            Operation::Execute => session.execute(&request.code),
+            Operation::Reset => {
+                session.reset();
+                CellResult {
+                    execution_count: 0,
+                    status: super::CellStatus::Ok,
+                    outputs: vec!["session reset".to_string()],
+                    solutions: Vec::new(),
+                    error: None,
+                }
+            }
+        };
+        Response {
+            id: request.id,
+            session: request.session,
+            result,
+        }
+    }
+
+    /// Answer request lines from `reader` on `writer` until the input ends
+    pub fn serve_lines(&self, reader: impl BufRead, mut writer: impl Write) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            writeln!(writer, "{}", self.handle_line(&line))?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Serve sessions on a TCP address, one thread per connection
+pub fn serve_tcp(registry: SessionRegistry, address: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    eprintln!("AlBayan REPL server listening on {}", listener.local_addr()?);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let registry = registry.clone();
+        std::thread::spawn(move || {
+            if let Err(error) = serve_connection(&registry, stream) {
+                eprintln!("REPL connection closed: {}", error);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn serve_connection(registry: &SessionRegistry, stream: TcpStream) -> io::Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    registry.serve_lines(reader, stream)
+}
+
+/// Serve sessions over standard input and output, for kernels that start
+/// the REPL as a child process
+pub fn serve_stdio(registry: SessionRegistry) -> io::Result<()> {
+    registry.serve_lines(io::stdin().lock(), io::stdout().lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sessions_are_isolated_and_shared_across_threads() {
+        let registry = SessionRegistry::new(CompilerOptions::default());
+
+        let workers: Vec<_> = (0..4)
+            .map(|worker| {
+                let registry = registry.clone();
+                std::thread::spawn(move || {
+                    for cell in 0..5 {
+                        let request = format!(
+                            r#"{{"id": {}, "session": "shared", "code": "fn f{}_{}() -> int {{ return {}; }}"}}"#,
+                            cell, worker, cell, cell
+                        );
+                        let response: Value = serde_json::from_str(&registry.handle_line(&request)).unwrap();
+                        assert_eq!(response["status"], "ok", "{}", response);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let shared = registry.session("shared");
+        assert_eq!(shared.lock().unwrap().items().len(), 20);
+        assert_eq!(shared.lock().unwrap().execution_count(), 20);
+
+        let other: Value =
+            serde_json::from_str(&registry.handle_line(r#"{"session": "other", "code": "?- likes(a, b)"}"#)).unwrap();
+        assert_eq!(other["execution_count"], 1);
+        assert_eq!(other["outputs"][0], "false");
+        assert_eq!(registry.session_names(), vec!["other", "shared"]);
+
+        let reset: Value = serde_json::from_str(&registry.handle_line(r#"{"id": "r", "session": "shared", "op": "reset"}"#)).unwrap();
+        assert_eq!(reset["id"], "r");
+        assert!(registry.session("shared").lock().unwrap().items().is_empty());
+
+        let invalid: Value = serde_json::from_str(&registry.handle_line("not json")).unwrap();
+        assert_eq!(invalid["status"], "error");
+    }
+
+    #[test]
+    fn test_sessions_refuse_to_load_files() {
+        let registry = SessionRegistry::new(CompilerOptions::default());
+        let path = std::env::temp_dir().join(format!("albayan_server_load_{}.ab", std::process::id()));
+        std::fs::write(&path, "fn secret() -> int { return 1; }").unwrap();
+
+        let request = serde_json::json!({ "code": format!(":load {}", path.display()) });
+        let response: Value = serde_json::from_str(&registry.handle_line(&request.to_string())).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(response["status"], "error");
+        assert_eq!(response["error"], "`:load` is disabled in this session");
+        assert!(registry.session("default").lock().unwrap().items().is_empty());
+    }
+}
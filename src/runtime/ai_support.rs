@@ -3,6 +3,7 @@
 //! This module provides AI inference capabilities for the AlBayan runtime.
 
 use std::collections::HashMap;
+use super::inference::{InferenceHandle, InferencePool};
 use super::RuntimeError;
 
 /// AI inference engine
@@ -16,6 +17,9 @@ pub struct AIEngine {
     
     /// AI engine configuration
     config: AIConfig,
+
+    /// Workers for `predict_async`, started by its first call
+    inference_pool: Option<InferencePool>,
 }
 
 /// AI model representation
@@ -64,6 +68,9 @@ pub struct AIConfig {
     
     /// Batch size for inference
     pub batch_size: usize,
+
+    /// Worker threads that run `predict_async` requests
+    pub inference_workers: usize,
 }
 
 impl Default for AIConfig {
@@ -72,6 +79,7 @@ impl Default for AIConfig {
             max_models: 10,
             use_gpu: false,
             batch_size: 1,
+            inference_workers: 2,
         }
     }
 }
@@ -98,6 +106,7 @@ impl AIEngine {
             models: HashMap::new(),
             inferences_executed: 0,
             config,
+            inference_pool: None,
         }
     }
     
@@ -111,6 +120,8 @@ impl AIEngine {
     
     /// Shutdown the AI engine
     pub fn shutdown(&mut self) -> Result<(), RuntimeError> {
+        // Lets queued requests finish before the workers stop
+        self.inference_pool = None;
         self.models.clear();
         println!("AI Engine shutdown");
         Ok(())
@@ -146,6 +157,30 @@ impl AIEngine {
     
     /// Run inference on a model
     pub fn predict(&mut self, model_name: &str, input: &Tensor) -> Result<Tensor, RuntimeError> {
+        let model = self.validated_model(model_name, input)?;
+        
+        // Placeholder inference - in a real implementation, this would call
+        // the actual model inference engine (ONNX Runtime, TensorFlow, etc.)
+        let output = Self::run_model_inference(model, input)?;
+        
+        self.inferences_executed += 1;
+        Ok(output)
+    }
+    
+    /// Run inference on a model on the inference workers, returning at once
+    /// with a handle to poll or wait on. Requests for unknown models or with
+    /// the wrong input shape fail here rather than through the handle.
+    pub fn predict_async(&mut self, model_name: &str, input: Tensor) -> Result<InferenceHandle, RuntimeError> {
+        let model = self.validated_model(model_name, &input)?.clone();
+        self.inferences_executed += 1;
+        
+        let workers = self.config.inference_workers;
+        let pool = self.inference_pool.get_or_insert_with(|| InferencePool::new(workers));
+        Ok(pool.submit(move || Self::run_model_inference(&model, &input)))
+    }
+    
+    /// The model named `model_name`, if `input` has its input shape
+    fn validated_model(&self, model_name: &str, input: &Tensor) -> Result<&AIModel, RuntimeError> {
         let model = self.models.get(model_name)
             .ok_or_else(|| RuntimeError::AIError(format!("Model '{}' not found", model_name)))?;
         
@@ -156,18 +191,11 @@ impl AIEngine {
                        model.input_shape, input.shape)
             ));
         }
-        
-        self.inferences_executed += 1;
-        
-        // Placeholder inference - in a real implementation, this would call
-        // the actual model inference engine (ONNX Runtime, TensorFlow, etc.)
-        let output = self.run_model_inference(model, input)?;
-        
-        Ok(output)
+        Ok(model)
     }
     
     /// Run actual model inference (placeholder)
-    fn run_model_inference(&self, model: &AIModel, input: &Tensor) -> Result<Tensor, RuntimeError> {
+    fn run_model_inference(model: &AIModel, input: &Tensor) -> Result<Tensor, RuntimeError> {
         match model.model_type {
             ModelType::NeuralNetwork => {
                 // Placeholder neural network inference
@@ -350,4 +378,27 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(engine.list_models().len(), 1);
     }
+    
+    #[test]
+    fn test_async_prediction() {
+        let mut engine = AIEngine::new();
+        engine.models.insert("mean".to_string(), AIModel {
+            name: "mean".to_string(),
+            model_type: ModelType::LinearRegression,
+            input_shape: vec![4],
+            output_shape: vec![1],
+            data: vec![],
+        });
+        let input = engine.create_tensor(vec![4], vec![1.0, 2.0, 3.0, 6.0]).unwrap();
+        
+        let handle = engine.predict_async("mean", input.clone()).unwrap();
+        assert_eq!(handle.wait().unwrap().data, vec![3.0]);
+        assert_eq!(engine.get_inference_count(), 1);
+        
+        let wrong_shape = engine.create_tensor(vec![2], vec![1.0, 2.0]).unwrap();
+        assert!(engine.predict_async("mean", wrong_shape).is_err());
+        assert!(engine.predict_async("missing", input).is_err());
+        assert_eq!(engine.get_inference_count(), 1);
+        engine.shutdown().unwrap();
+    }
 }
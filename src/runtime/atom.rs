@@ -0,0 +1,130 @@
+//! # Atoms
+//!
+//! Interned symbols for the logic engine. Every distinct atom or predicate
+//! name is stored once in a process-wide table and referred to by a small
+//! id, so terms stay compact and comparing two constants during unification
+//! is a single integer comparison instead of a string comparison.
+//!
+//! Ids are only meaningful inside one process: atoms serialize as their
+//! names, so persisted facts are re-interned when they are read back.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{OnceLock, RwLock};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// An interned symbol: a logic constant like `john` or a predicate name
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Atom(u32);
+
+/// Names in id order, and the id of each name
+#[derive(Default)]
+struct Interner {
+    names: Vec<&'static str>,
+    ids: HashMap<&'static str, u32>,
+}
+
+static INTERNER: OnceLock<RwLock<Interner>> = OnceLock::new();
+
+fn interner() -> &'static RwLock<Interner> {
+    INTERNER.get_or_init(|| RwLock::new(Interner::default()))
+}
+
+impl Atom {
+    /// Intern `name`, returning the existing atom if it was seen before
+    pub fn new(name: &str) -> Self {
+        if let Some(&id) = interner().read().unwrap_or_else(|poisoned| poisoned.into_inner()).ids.get(name) {
+            return Atom(id);
+        }
+
+        let mut interner = interner().write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(&id) = interner.ids.get(name) {
+            return Atom(id);
+        }
+        // Interned names live for the rest of the process
+        let name: &'static str = Box::leak(name.to_owned().into_boxed_str());
+        let id = u32::try_from(interner.names.len()).expect("atom table overflow");
+        interner.names.push(name);
+        interner.ids.insert(name, id);
+        Atom(id)
+    }
+
+    /// The atom's name
+    pub fn as_str(self) -> &'static str {
+        interner().read().unwrap_or_else(|poisoned| poisoned.into_inner()).names[self.0 as usize]
+    }
+
+    /// The atom's id in the process-wide table
+    pub fn id(self) -> u32 {
+        self.0
+    }
+
+    /// Number of distinct atoms interned so far
+    pub fn interned_count() -> usize {
+        interner().read().unwrap_or_else(|poisoned| poisoned.into_inner()).names.len()
+    }
+}
+
+impl From<&str> for Atom {
+    fn from(name: &str) -> Self {
+        Atom::new(name)
+    }
+}
+
+impl PartialEq<str> for Atom {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Atom {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Atom({:?})", self.as_str())
+    }
+}
+
+impl Serialize for Atom {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Atom {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Ok(Atom::new(&name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interning_returns_the_same_atom() {
+        let first = Atom::new("interned_test_atom");
+        let count = Atom::interned_count();
+        let second = Atom::from("interned_test_atom");
+        assert_eq!(first, second);
+        assert_eq!(Atom::interned_count(), count);
+        assert_ne!(first, Atom::new("interned_test_other"));
+        assert_eq!(first.as_str(), "interned_test_atom");
+        assert!(first == "interned_test_atom");
+
+        let json = serde_json::to_string(&first).unwrap();
+        assert_eq!(json, "\"interned_test_atom\"");
+        assert_eq!(serde_json::from_str::<Atom>(&json).unwrap(), first);
+    }
+}
@@ -0,0 +1,128 @@
+//! # Belief Revision
+//!
+//! Integrity constraints added with `LogicEngine::add_constraint` name goals
+//! that must never hold together, such as `:- alive(X), dead(X)`. Asserting a
+//! fact checks every constraint at the engine's time, rules included; when the
+//! new fact lets a constraint's goals be solved, the assert has run into a
+//! [`Contradiction`].
+//!
+//! The contradiction reports a minimal conflicting set: the stored facts that
+//! the violation's proof rests on (the leaves of its proof tree), narrowed
+//! until leaving out any one of them leaves the violation underivable. The
+//! engine's [`RevisionPolicy`] then decides what happens: reject the new fact
+//! with a `RuntimeError::Contradiction`, retract the old facts of the set to
+//! make room for it, or ask the host, which may also keep both.
+//!
+//! Programs declare constraints as `constraint no parent(X, X);`, which the
+//! knowledge image carries. Bulk loads and knowledge images are checked once
+//! the whole batch is stored; a violation always rejects the batch, whatever
+//! the policy. Facts the ontology implies count as the stored facts they are
+//! implied from.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// An assert that violates an integrity constraint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contradiction {
+    /// The fact being asserted
+    pub fact: String,
+    /// The violated constraint, e.g. `:- alive(X), dead(X)`
+    pub constraint: String,
+    /// Stored facts that violate the constraint together with `fact`; with
+    /// any one of them left out the violation can no longer be derived
+    pub conflicting: Vec<String>,
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} violates {}", self.fact, self.constraint)?;
+        if !self.conflicting.is_empty() {
+            write!(f, " together with {}", self.conflicting.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// How one contradiction is resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Leave the knowledge base as it was and fail the assert
+    RejectNew,
+    /// Retract the old facts of the conflicting set, then assert the new fact
+    RetractOld,
+    /// Assert the new fact and live with the contradiction
+    KeepBoth,
+}
+
+/// Host callback deciding how to resolve a contradiction
+pub type RevisionHandler = Arc<dyn Fn(&Contradiction) -> Resolution + Send + Sync>;
+
+/// How the engine resolves the contradictions asserts run into
+#[derive(Clone, Default)]
+pub enum RevisionPolicy {
+    /// Fail the assert with `RuntimeError::Contradiction`
+    #[default]
+    RejectNew,
+    /// Retract the conflicting old facts, as long as the new fact does not
+    /// violate a constraint on its own
+    RetractOld,
+    /// Ask the host for each contradiction
+    Ask(RevisionHandler),
+}
+
+impl RevisionPolicy {
+    pub(super) fn resolve(&self, contradiction: &Contradiction) -> Resolution {
+        match self {
+            RevisionPolicy::RejectNew => Resolution::RejectNew,
+            RevisionPolicy::RetractOld => Resolution::RetractOld,
+            RevisionPolicy::Ask(handler) => handler(contradiction),
+        }
+    }
+}
+
+impl fmt::Debug for RevisionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RevisionPolicy::RejectNew => f.write_str("RejectNew"),
+            RevisionPolicy::RetractOld => f.write_str("RetractOld"),
+            RevisionPolicy::Ask(_) => f.write_str("Ask(..)"),
+        }
+    }
+}
+
+/// What asserting a fact changed besides adding it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Revision {
+    /// Contradictions the assert ran into, in the order they were resolved
+    pub contradictions: Vec<Contradiction>,
+    /// Old facts retracted to make room for the new one
+    pub retracted: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies_resolve_and_contradictions_display() {
+        let contradiction = Contradiction {
+            fact: "dead(tom)".to_string(),
+            constraint: ":- alive(X), dead(X)".to_string(),
+            conflicting: vec!["alive(tom)".to_string()],
+        };
+        assert_eq!(contradiction.to_string(), "dead(tom) violates :- alive(X), dead(X) together with alive(tom)");
+        assert_eq!(RevisionPolicy::default().resolve(&contradiction), Resolution::RejectNew);
+        assert_eq!(RevisionPolicy::RetractOld.resolve(&contradiction), Resolution::RetractOld);
+
+        let ask = RevisionPolicy::Ask(Arc::new(|contradiction| {
+            if contradiction.conflicting.is_empty() {
+                Resolution::RejectNew
+            } else {
+                Resolution::KeepBoth
+            }
+        }));
+        assert_eq!(ask.resolve(&contradiction), Resolution::KeepBoth);
+        assert_eq!(format!("{:?}", ask), "Ask(..)");
+    }
+}
@@ -0,0 +1,150 @@
+//! # Hash Collections
+//!
+//! The maps and sets of natively compiled programs. The LLVM backend keeps a
+//! `Map<K, V>` or `Set<T>` as a pointer to an [`AlbayanMap`] made by
+//! `albayan_rt_map_new` and lowers its literals and built-in methods to the
+//! `albayan_rt_map_*` calls below.
+//!
+//! Keys are `int`, `bool` and `char` values passed as an `i64`, or strings
+//! passed as NUL-terminated text, which the map copies. Values are copied in
+//! as `value_size` raw bytes, so the map works for any value type the backend
+//! lays out. A set is a map whose values are zero bytes long.
+
+// The pointers come from generated code, which only passes maps returned by
+// `albayan_rt_map_new` and values of the size the map was made with
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr};
+
+/// A map key; strings are compared by their text
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Int(i64),
+    String(String),
+}
+
+impl MapKey {
+    /// The key passed as `key`, or as `text` when that is not null
+    fn from_raw(key: i64, text: *const c_char) -> Self {
+        if text.is_null() {
+            MapKey::Int(key)
+        } else {
+            MapKey::String(unsafe { CStr::from_ptr(text) }.to_string_lossy().into_owned())
+        }
+    }
+}
+
+/// Hash map from keys to values of one fixed size
+#[derive(Debug)]
+pub struct AlbayanMap {
+    value_size: usize,
+    entries: HashMap<MapKey, Box<[u8]>>,
+}
+
+impl AlbayanMap {
+    pub fn new(value_size: usize) -> Self {
+        Self { value_size, entries: HashMap::new() }
+    }
+
+    /// Insert or replace the value of `key`; returns whether the key is new
+    pub fn insert(&mut self, key: MapKey, value: &[u8]) -> bool {
+        debug_assert_eq!(value.len(), self.value_size);
+        self.entries.insert(key, value.into()).is_none()
+    }
+
+    pub fn get(&self, key: &MapKey) -> Option<&[u8]> {
+        self.entries.get(key).map(|value| &**value)
+    }
+
+    /// Remove `key`; returns whether it was present
+    pub fn remove(&mut self, key: &MapKey) -> bool {
+        self.entries.remove(key).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Make an empty map whose values are `value_size` bytes; 0 makes a set
+#[no_mangle]
+pub extern "C" fn albayan_rt_map_new(value_size: i64) -> *mut AlbayanMap {
+    Box::into_raw(Box::new(AlbayanMap::new(usize::try_from(value_size).unwrap_or(0))))
+}
+
+/// Copy the value at `value` into the map under the key; returns 1 if the key is new
+#[no_mangle]
+pub extern "C" fn albayan_rt_map_insert(map: *mut AlbayanMap, key: i64, key_text: *const c_char, value: *const u8) -> u8 {
+    let Some(map) = (unsafe { map.as_mut() }) else {
+        return 0;
+    };
+    let value = if map.value_size == 0 || value.is_null() {
+        &[][..]
+    } else {
+        unsafe { std::slice::from_raw_parts(value, map.value_size) }
+    };
+    map.insert(MapKey::from_raw(key, key_text), value) as u8
+}
+
+/// The value stored under the key, or null when there is none
+#[no_mangle]
+pub extern "C" fn albayan_rt_map_get(map: *const AlbayanMap, key: i64, key_text: *const c_char) -> *const u8 {
+    unsafe { map.as_ref() }
+        .and_then(|map| map.get(&MapKey::from_raw(key, key_text)))
+        .map_or(std::ptr::null(), <[u8]>::as_ptr)
+}
+
+/// Remove the key; returns 1 if it was present
+#[no_mangle]
+pub extern "C" fn albayan_rt_map_remove(map: *mut AlbayanMap, key: i64, key_text: *const c_char) -> u8 {
+    unsafe { map.as_mut() }.is_some_and(|map| map.remove(&MapKey::from_raw(key, key_text))) as u8
+}
+
+#[no_mangle]
+pub extern "C" fn albayan_rt_map_len(map: *const AlbayanMap) -> i64 {
+    unsafe { map.as_ref() }.map_or(0, |map| map.len() as i64)
+}
+
+#[no_mangle]
+pub extern "C" fn albayan_rt_map_free(map: *mut AlbayanMap) {
+    if !map.is_null() {
+        drop(unsafe { Box::from_raw(map) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_maps_and_sets() {
+        let map = albayan_rt_map_new(8);
+        let ali = CString::new("ali").unwrap();
+        let age = 30i64.to_ne_bytes();
+        assert_eq!(albayan_rt_map_insert(map, 0, ali.as_ptr(), age.as_ptr()), 1);
+        let older = 31i64.to_ne_bytes();
+        assert_eq!(albayan_rt_map_insert(map, 0, CString::new("ali").unwrap().as_ptr(), older.as_ptr()), 0);
+        assert_eq!(albayan_rt_map_len(map), 1);
+
+        let value = albayan_rt_map_get(map, 0, ali.as_ptr());
+        assert_eq!(unsafe { std::ptr::read_unaligned(value as *const i64) }, 31);
+        assert!(albayan_rt_map_get(map, 0, CString::new("sara").unwrap().as_ptr()).is_null());
+        assert_eq!(albayan_rt_map_remove(map, 0, ali.as_ptr()), 1);
+        assert_eq!(albayan_rt_map_remove(map, 0, ali.as_ptr()), 0);
+        albayan_rt_map_free(map);
+
+        let set = albayan_rt_map_new(0);
+        assert_eq!(albayan_rt_map_insert(set, 7, std::ptr::null(), std::ptr::null()), 1);
+        assert_eq!(albayan_rt_map_insert(set, 7, std::ptr::null(), std::ptr::null()), 0);
+        assert!(!albayan_rt_map_get(set, 7, std::ptr::null()).is_null());
+        assert!(albayan_rt_map_get(set, 8, std::ptr::null()).is_null());
+        assert_eq!(albayan_rt_map_len(set), 1);
+        albayan_rt_map_free(set);
+    }
+}
@@ -53,8 +53,9 @@ impl fmt::Debug for AlbayanValuePayload {
 }
 
 /// Dynamic string type for AlBayan
+/// Owns its bytes, which are freed on drop, so it is deliberately not `Clone`
 #[repr(C)]
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct AlbayanString {
     pub data: *mut u8,
     pub len: usize,
@@ -145,6 +146,26 @@ impl AlbayanValue {
         }
     }
 
+    /// Create a new string value; the text is copied to the heap
+    pub fn new_string(text: &str) -> Self {
+        Self {
+            tag: AlbayanValueTag::String,
+            payload: AlbayanValuePayload { string_val: Box::into_raw(AlbayanString::new(text)) },
+        }
+    }
+
+    /// Create a new struct value from its named fields
+    pub fn new_struct(type_name: &str, fields: HashMap<String, AlbayanValue>) -> Self {
+        let struct_val = Box::into_raw(Box::new(AlbayanStruct {
+            fields: Box::into_raw(Box::new(fields)),
+            type_name: Box::into_raw(AlbayanString::new(type_name)),
+        }));
+        Self {
+            tag: AlbayanValueTag::Struct,
+            payload: AlbayanValuePayload { struct_val },
+        }
+    }
+
     /// Get the integer value (unsafe - caller must ensure tag is Int)
     pub unsafe fn as_int(&self) -> i64 {
         self.payload.int_val
@@ -170,6 +191,75 @@ impl AlbayanValue {
         self.payload.enum_val
     }
 
+    /// Get the text of a string value
+    ///
+    /// # Safety
+    /// The tag must be String and the payload a live `AlbayanString`
+    pub unsafe fn as_str(&self) -> &str {
+        (*self.payload.string_val).as_str()
+    }
+
+    /// Get the struct pointer
+    ///
+    /// # Safety
+    /// The tag must be Struct
+    pub unsafe fn as_struct(&self) -> *mut AlbayanStruct {
+        self.payload.struct_val
+    }
+
+    /// Free this value and everything it owns: strings, list elements and struct fields
+    ///
+    /// # Safety
+    /// The value must own its payload, and neither it nor any copy may be used afterwards
+    pub unsafe fn free(self) {
+        match self.tag {
+            AlbayanValueTag::String => albayan_rt_string_destroy(self.payload.string_val),
+            AlbayanValueTag::List if !self.payload.list_val.is_null() => {
+                let list = Box::from_raw(self.payload.list_val);
+                for index in 0..list.len() {
+                    std::ptr::read(list.data.add(index)).free();
+                }
+            }
+            AlbayanValueTag::Struct if !self.payload.struct_val.is_null() => {
+                let struct_val = Box::from_raw(self.payload.struct_val);
+                for (_, field) in *Box::from_raw(struct_val.fields) {
+                    field.free();
+                }
+                albayan_rt_string_destroy(struct_val.type_name);
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether two values are equal: scalars and strings by value, lists
+    /// element by element, and other values when they are the same object
+    pub fn equals(&self, other: &AlbayanValue) -> bool {
+        if self.tag != other.tag {
+            return false;
+        }
+        unsafe {
+            match self.tag {
+                AlbayanValueTag::Int => self.payload.int_val == other.payload.int_val,
+                AlbayanValueTag::Float => self.payload.float_val == other.payload.float_val,
+                AlbayanValueTag::Bool => self.payload.bool_val == other.payload.bool_val,
+                AlbayanValueTag::Null => true,
+                AlbayanValueTag::String => self.as_str() == other.as_str(),
+                AlbayanValueTag::List => {
+                    let (list, other_list) = (self.payload.list_val, other.payload.list_val);
+                    if list.is_null() || other_list.is_null() {
+                        return list == other_list;
+                    }
+                    let (list, other_list) = (&*list, &*other_list);
+                    list.len() == other_list.len()
+                        && (0..list.len()).all(|index| (*list.data.add(index)).equals(&*other_list.data.add(index)))
+                }
+                AlbayanValueTag::Struct => self.payload.struct_val == other.payload.struct_val,
+                AlbayanValueTag::Tuple => self.payload.tuple_val == other.payload.tuple_val,
+                AlbayanValueTag::Enum => self.payload.enum_val == other.payload.enum_val,
+            }
+        }
+    }
+
     /// Check if this value is of a specific type
     pub fn is_type(&self, tag: AlbayanValueTag) -> bool {
         self.tag == tag
@@ -191,6 +281,38 @@ impl AlbayanValue {
     }
 }
 
+impl AlbayanString {
+    /// Copy `text` into a new heap string
+    pub fn new(text: &str) -> Box<Self> {
+        let bytes = text.as_bytes().to_vec().into_boxed_slice();
+        let len = bytes.len();
+        Box::new(Self {
+            data: Box::into_raw(bytes) as *mut u8,
+            len,
+            capacity: len,
+        })
+    }
+
+    /// View the string as UTF-8 text (invalid UTF-8 yields an empty string)
+    pub fn as_str(&self) -> &str {
+        if self.data.is_null() {
+            return "";
+        }
+        let bytes = unsafe { std::slice::from_raw_parts(self.data, self.len) };
+        std::str::from_utf8(bytes).unwrap_or("")
+    }
+}
+
+impl Drop for AlbayanString {
+    fn drop(&mut self) {
+        if !self.data.is_null() && self.capacity > 0 {
+            unsafe {
+                drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(self.data, self.capacity)));
+            }
+        }
+    }
+}
+
 impl AlbayanList {
     /// Create a new empty list
     pub fn new() -> Box<Self> {
@@ -234,6 +356,46 @@ impl AlbayanList {
         }
     }
 
+    /// Remove and return the last value, or `None` when the list is empty
+    pub fn pop(&mut self) -> Option<AlbayanValue> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        unsafe { Some(std::ptr::read(self.data.add(self.len))) }
+    }
+
+    /// Whether the list holds a value equal to `value`
+    pub fn contains(&self, value: &AlbayanValue) -> bool {
+        (0..self.len).any(|index| unsafe { (*self.data.add(index)).equals(value) })
+    }
+
+    /// A new list of `f` applied to each value; `f` must return values it owns
+    pub fn map(&self, mut f: impl FnMut(&AlbayanValue) -> AlbayanValue) -> Box<Self> {
+        let mut mapped = Self::with_capacity(self.len.max(1));
+        for index in 0..self.len {
+            mapped.push(f(unsafe { &*self.data.add(index) }));
+        }
+        mapped
+    }
+
+    /// Keep the values for which `keep` holds, in order, freeing the others
+    pub fn filter(&mut self, mut keep: impl FnMut(&AlbayanValue) -> bool) {
+        let mut kept = 0;
+        for index in 0..self.len {
+            unsafe {
+                let value = std::ptr::read(self.data.add(index));
+                if keep(&value) {
+                    std::ptr::write(self.data.add(kept), value);
+                    kept += 1;
+                } else {
+                    value.free();
+                }
+            }
+        }
+        self.len = kept;
+    }
+
     /// Get the length of the list
     pub fn len(&self) -> usize {
         self.len
@@ -339,6 +501,49 @@ pub extern "C" fn albayan_rt_list_get(list_ptr: *const AlbayanList, index: usize
     }
 }
 
+/// Remove the last value of the list
+/// Returns the value, or a null value if the list is empty
+#[no_mangle]
+pub extern "C" fn albayan_rt_list_pop(list_ptr: *mut AlbayanList) -> AlbayanValue {
+    if list_ptr.is_null() {
+        return AlbayanValue::new_null();
+    }
+
+    unsafe { (*list_ptr).pop().unwrap_or_else(AlbayanValue::new_null) }
+}
+
+/// Check if the list holds a value equal to `value`
+/// Returns 1 if it does, 0 if not, -1 if a pointer is null
+#[no_mangle]
+pub extern "C" fn albayan_rt_list_contains(list_ptr: *const AlbayanList, value: *const AlbayanValue) -> i32 {
+    if list_ptr.is_null() || value.is_null() {
+        return -1;
+    }
+
+    unsafe { (*list_ptr).contains(&*value) as i32 }
+}
+
+/// Create a new list of `f` applied to each value of the list
+#[no_mangle]
+pub extern "C" fn albayan_rt_list_map(
+    list_ptr: *const AlbayanList,
+    f: extern "C" fn(*const AlbayanValue) -> AlbayanValue,
+) -> *mut AlbayanList {
+    if list_ptr.is_null() {
+        return std::ptr::null_mut();
+    }
+
+    unsafe { Box::into_raw((*list_ptr).map(|value| f(value))) }
+}
+
+/// Keep the values of the list for which `keep` returns true, freeing the others
+#[no_mangle]
+pub extern "C" fn albayan_rt_list_filter(list_ptr: *mut AlbayanList, keep: extern "C" fn(*const AlbayanValue) -> bool) {
+    if !list_ptr.is_null() {
+        unsafe { (*list_ptr).filter(|value| keep(value)) }
+    }
+}
+
 /// Get the length of the list
 /// Returns the length, or 0 if the list pointer is null
 #[no_mangle]
@@ -517,9 +722,15 @@ pub extern "C" fn albayan_rt_value_as_bool(value: *const AlbayanValue) -> bool {
     unsafe { (*value).as_bool() }
 }
 
+/// Free a value together with the strings, list elements and struct fields it owns
+#[no_mangle]
+pub extern "C" fn albayan_rt_value_free(value: AlbayanValue) {
+    unsafe { value.free() }
+}
+
 /// Runtime panic function for type safety violations (Expert recommendation: Unboxing Safety)
 #[no_mangle]
-pub extern "C" fn albayan_rt_panic(message_ptr: *const u8, message_len: usize) -> ! {
+pub extern "C" fn albayan_rt_type_panic(message_ptr: *const u8, message_len: usize) -> ! {
     if message_ptr.is_null() {
         panic!("AlBayan Runtime Panic: Unknown error");
     }
@@ -531,3 +742,9 @@ pub extern "C" fn albayan_rt_panic(message_ptr: *const u8, message_len: usize) -
     let message = String::from_utf8_lossy(message_slice);
     panic!("AlBayan Runtime Panic: {}", message);
 }
+
+/// Runtime panic for indexing an array or list past its end
+#[no_mangle]
+pub extern "C" fn albayan_rt_bounds_panic(index: i64, length: i64) -> ! {
+    panic!("AlBayan Runtime Panic: index {} out of bounds for an array of length {}", index, length);
+}
@@ -0,0 +1,659 @@
+//! # Fact Storage
+//!
+//! Backends holding the facts of the logic engine. Facts live in memory by
+//! default; with the `sqlite` feature they can be persisted in a SQLite
+//! database instead, so knowledge bases larger than RAM stay queryable.
+//! Every backend returns a predicate's facts in assertion order, so the
+//! solver finds the same answers in the same order whichever store is used.
+//! Facts keep their validity interval in every backend; the solver decides
+//! which of them hold at the time it asks about.
+//!
+//! Lookups do not scan a predicate's facts when an argument of the goal is
+//! known: every backend indexes the first argument, and the memory backend
+//! keeps hash indexes on any other argument asked for with
+//! `LogicEngine::index_argument`. [`IndexStats`] counts how lookups went.
+//!
+//! A store is shared by the snapshots of the engine that owns it, so it is
+//! read from several threads at once. The memory backend is copied when the
+//! engine changes it while a snapshot still holds it; a SQLite store stays
+//! one database whose connection each lookup locks in turn.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "sqlite")]
+use std::sync::{Arc, Mutex, PoisonError};
+use indexmap::IndexMap;
+use super::atom::Atom;
+use super::logic_engine::{Fact, Term};
+use super::RuntimeError;
+
+/// Where the logic engine keeps its facts, selected through `RuntimeConfig`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum KnowledgeStorage {
+    /// Keep facts in memory
+    #[default]
+    Memory,
+    /// Persist facts in a SQLite database file (requires the `sqlite` feature)
+    Sqlite(PathBuf),
+}
+
+/// The indexes of a fact store and how its lookups used them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexStats {
+    /// Argument indexes kept across all predicates
+    pub indexes: usize,
+    /// Distinct values held by those indexes
+    pub keys: usize,
+    /// Lookups answered from an index
+    pub indexed_lookups: usize,
+    /// Lookups that went through every fact of their predicate
+    pub scans: usize,
+}
+
+/// Facts indexed by predicate name
+#[derive(Debug, Clone)]
+pub(super) enum FactStore {
+    Memory(MemoryFacts),
+    /// Clones share the database
+    #[cfg(feature = "sqlite")]
+    Sqlite(Arc<Mutex<sqlite::SqliteFactStore>>),
+}
+
+impl Default for FactStore {
+    fn default() -> Self {
+        FactStore::Memory(MemoryFacts::default())
+    }
+}
+
+/// Value of an argument as an index key. Only terms that unify with nothing
+/// but an identical term get one; variables, floats and compounds are left
+/// unindexed and match every goal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum IndexKey {
+    Atom(Atom),
+    Integer(i64),
+    String(String),
+}
+
+impl IndexKey {
+    fn of(term: &Term) -> Option<Self> {
+        match term {
+            Term::Atom(atom) => Some(IndexKey::Atom(*atom)),
+            Term::Integer(value) => Some(IndexKey::Integer(*value)),
+            Term::String(text) => Some(IndexKey::String(text.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Positions of a predicate's facts by the value of one argument
+#[derive(Debug, Clone, Default)]
+struct ArgumentIndex {
+    by_key: HashMap<IndexKey, Vec<usize>>,
+    /// Facts whose argument has no key, which any goal may unify with
+    unkeyed: Vec<usize>,
+}
+
+impl ArgumentIndex {
+    fn insert(&mut self, arg: Option<&Term>, position: usize) {
+        match arg.and_then(IndexKey::of) {
+            Some(key) => self.by_key.entry(key).or_default().push(position),
+            None => self.unkeyed.push(position),
+        }
+    }
+}
+
+/// One predicate's facts in assertion order, with an index per indexed argument
+#[derive(Debug, Clone, Default)]
+struct PredicateFacts {
+    facts: Vec<Fact>,
+    indexes: BTreeMap<usize, ArgumentIndex>,
+}
+
+impl PredicateFacts {
+    fn new(arguments: impl IntoIterator<Item = usize>) -> Self {
+        let indexes = arguments.into_iter().map(|argument| (argument, ArgumentIndex::default())).collect();
+        Self { facts: Vec::new(), indexes }
+    }
+
+    fn push(&mut self, fact: Fact) {
+        let position = self.facts.len();
+        for (argument, index) in &mut self.indexes {
+            index.insert(fact.args.get(*argument), position);
+        }
+        self.facts.push(fact);
+    }
+
+    /// Index the facts again once some were removed
+    fn reindex(&mut self) {
+        let facts = std::mem::take(&mut self.facts);
+        self.indexes.values_mut().for_each(|index| *index = ArgumentIndex::default());
+        facts.into_iter().for_each(|fact| self.push(fact));
+    }
+
+    /// Positions, in assertion order, of the facts that may unify with a goal
+    /// with arguments `args`, from the index leaving the fewest; `None` when
+    /// no indexed argument is known
+    fn candidates(&self, args: &[Term]) -> Option<Vec<usize>> {
+        let (keyed, unkeyed) = self
+            .indexes
+            .iter()
+            .filter_map(|(argument, index)| {
+                let key = IndexKey::of(args.get(*argument)?)?;
+                let keyed = index.by_key.get(&key).map_or(&[][..], Vec::as_slice);
+                Some((keyed, index.unkeyed.as_slice()))
+            })
+            .min_by_key(|(keyed, unkeyed)| keyed.len() + unkeyed.len())?;
+
+        // Both lists are ascending; merge them to keep assertion order
+        let mut positions = Vec::with_capacity(keyed.len() + unkeyed.len());
+        let (mut k, mut u) = (0, 0);
+        while k < keyed.len() || u < unkeyed.len() {
+            if u == unkeyed.len() || (k < keyed.len() && keyed[k] < unkeyed[u]) {
+                positions.push(keyed[k]);
+                k += 1;
+            } else {
+                positions.push(unkeyed[u]);
+                u += 1;
+            }
+        }
+        Some(positions)
+    }
+}
+
+/// Facts held in memory, the first argument of each predicate always indexed
+#[derive(Debug, Clone, Default)]
+pub(super) struct MemoryFacts {
+    predicates: IndexMap<Atom, PredicateFacts>,
+    /// Arguments indexed besides the first, per predicate
+    extra_indexes: HashMap<Atom, Vec<usize>>,
+    lookups: LookupCounts,
+}
+
+/// Lookups answered from an index and lookups that scanned, counted by
+/// whichever thread makes them
+#[derive(Debug, Default)]
+struct LookupCounts {
+    indexed: AtomicUsize,
+    scans: AtomicUsize,
+}
+
+impl LookupCounts {
+    fn count(&self, indexed: bool) {
+        let counter = if indexed { &self.indexed } else { &self.scans };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> (usize, usize) {
+        (self.indexed.load(Ordering::Relaxed), self.scans.load(Ordering::Relaxed))
+    }
+}
+
+impl Clone for LookupCounts {
+    fn clone(&self) -> Self {
+        let (indexed, scans) = self.get();
+        Self { indexed: AtomicUsize::new(indexed), scans: AtomicUsize::new(scans) }
+    }
+}
+
+impl MemoryFacts {
+    fn add(&mut self, fact: Fact) {
+        let predicate = fact.predicate;
+        let extra = self.extra_indexes.get(&predicate).map_or(&[][..], Vec::as_slice);
+        self.predicates
+            .entry(predicate)
+            .or_insert_with(|| PredicateFacts::new(std::iter::once(0).chain(extra.iter().copied())))
+            .push(fact);
+    }
+
+    fn add_index(&mut self, predicate: Atom, argument: usize) {
+        let extra = self.extra_indexes.entry(predicate).or_default();
+        if argument == 0 || extra.contains(&argument) {
+            return;
+        }
+        extra.push(argument);
+        if let Some(facts) = self.predicates.get_mut(&predicate) {
+            facts.indexes.insert(argument, ArgumentIndex::default());
+            facts.reindex();
+        }
+    }
+
+    fn matching(&self, predicate: Atom, args: &[Term]) -> Cow<'_, [Fact]> {
+        let Some(facts) = self.predicates.get(&predicate) else {
+            return Cow::Borrowed(&[]);
+        };
+        match facts.candidates(args) {
+            Some(positions) => {
+                self.lookups.count(true);
+                Cow::Owned(positions.into_iter().map(|position| facts.facts[position].clone()).collect())
+            }
+            None => {
+                self.lookups.count(false);
+                Cow::Borrowed(&facts.facts)
+            }
+        }
+    }
+
+    fn index_stats(&self) -> IndexStats {
+        let (indexed_lookups, scans) = self.lookups.get();
+        let mut stats = IndexStats { indexed_lookups, scans, ..IndexStats::default() };
+        for index in self.predicates.values().flat_map(|facts| facts.indexes.values()) {
+            stats.indexes += 1;
+            stats.keys += index.by_key.len();
+        }
+        stats
+    }
+}
+
+impl FactStore {
+    /// Open the store described by `storage`
+    pub(super) fn open(storage: &KnowledgeStorage) -> Result<Self, RuntimeError> {
+        match storage {
+            KnowledgeStorage::Memory => Ok(Self::default()),
+            #[cfg(feature = "sqlite")]
+            KnowledgeStorage::Sqlite(path) => {
+                sqlite::SqliteFactStore::open(path).map(|store| FactStore::Sqlite(Arc::new(Mutex::new(store))))
+            }
+            #[cfg(not(feature = "sqlite"))]
+            KnowledgeStorage::Sqlite(_) => Err(RuntimeError::FeatureDisabled(
+                "SQLite knowledge storage: rebuild with `--features sqlite`".to_string(),
+            )),
+        }
+    }
+
+    /// Close the store, making sure persisted facts reach the disk
+    pub(super) fn close(self) -> Result<(), RuntimeError> {
+        match self {
+            FactStore::Memory(_) => Ok(()),
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(store) => match Arc::try_unwrap(store) {
+                Ok(store) => store.into_inner().unwrap_or_else(PoisonError::into_inner).close(),
+                // A snapshot still reads from the database, which closes with the last of them
+                Err(_) => Ok(()),
+            },
+        }
+    }
+
+    /// Name of the backend, for diagnostics
+    pub(super) fn backend_name(&self) -> &'static str {
+        match self {
+            FactStore::Memory(_) => "memory",
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(_) => "sqlite",
+        }
+    }
+
+    /// Whether lookups with a known `argument` of `predicate` use an index
+    pub(super) fn indexes_argument(&self, predicate: Atom, argument: usize) -> bool {
+        match self {
+            FactStore::Memory(facts) => {
+                argument == 0 || facts.extra_indexes.get(&predicate).is_some_and(|extra| extra.contains(&argument))
+            }
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(_) => argument == 0,
+        }
+    }
+
+    /// Keep a hash index on `argument` (counting from 0) of `predicate`'s facts
+    pub(super) fn add_index(&mut self, predicate: Atom, argument: usize) -> Result<(), RuntimeError> {
+        match self {
+            FactStore::Memory(facts) => {
+                facts.add_index(predicate, argument);
+                Ok(())
+            }
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(_) if argument == 0 => Ok(()),
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(_) => Err(RuntimeError::LogicError(
+                "SQLite knowledge storage indexes only the first argument".to_string(),
+            )),
+        }
+    }
+
+    /// The indexes kept and how lookups used them
+    pub(super) fn index_stats(&self) -> IndexStats {
+        match self {
+            FactStore::Memory(facts) => facts.index_stats(),
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(store) => sqlite::lock(store).index_stats(),
+        }
+    }
+
+    pub(super) fn add(&mut self, fact: Fact) -> Result<(), RuntimeError> {
+        match self {
+            FactStore::Memory(facts) => {
+                facts.add(fact);
+                Ok(())
+            }
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(store) => sqlite::lock(store).add(&fact),
+        }
+    }
+
+    /// Store a batch of facts, calling `progress(stored)` after every `chunk_size`
+    /// facts and after the last one. A persistent store inserts the batch in one
+    /// transaction and rebuilds its index once at the end.
+    pub(super) fn extend(&mut self, facts: Vec<Fact>, chunk_size: usize, mut progress: impl FnMut(usize)) -> Result<(), RuntimeError> {
+        let total = facts.len();
+        let mut report = move |stored: usize| {
+            if stored % chunk_size == 0 || stored == total {
+                progress(stored);
+            }
+        };
+        match self {
+            FactStore::Memory(store) => {
+                for (index, fact) in facts.into_iter().enumerate() {
+                    store.add(fact);
+                    report(index + 1);
+                }
+                Ok(())
+            }
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(store) => sqlite::lock(store).extend(&facts, report),
+        }
+    }
+
+    /// Remove the facts with the arguments of `fact`, whatever their validity
+    pub(super) fn remove(&mut self, fact: &Fact) -> Result<(), RuntimeError> {
+        match self {
+            FactStore::Memory(facts) => {
+                if let Some(facts) = facts.predicates.get_mut(&fact.predicate) {
+                    let count = facts.facts.len();
+                    facts.facts.retain(|f| f.args != fact.args);
+                    if facts.facts.len() != count {
+                        facts.reindex();
+                    }
+                }
+                Ok(())
+            }
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(store) => sqlite::lock(store).remove(fact),
+        }
+    }
+
+    /// Remove and return the facts that no longer hold at `now` or later
+    pub(super) fn remove_expired(&mut self, now: i64) -> Result<Vec<Fact>, RuntimeError> {
+        match self {
+            FactStore::Memory(facts) => {
+                let mut expired = Vec::new();
+                for facts in facts.predicates.values_mut() {
+                    let (gone, kept): (Vec<Fact>, Vec<Fact>) =
+                        std::mem::take(&mut facts.facts).into_iter().partition(|fact| fact.valid.expired_by(now));
+                    facts.facts = kept;
+                    if !gone.is_empty() {
+                        facts.reindex();
+                    }
+                    expired.extend(gone);
+                }
+                Ok(expired)
+            }
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(store) => sqlite::lock(store).remove_expired(now),
+        }
+    }
+
+    /// Facts of `predicate` in assertion order. When indexed arguments of the
+    /// goal, given as `args` with its bindings applied, are known, the facts
+    /// whose arguments cannot unify with them are skipped.
+    pub(super) fn matching(&self, predicate: Atom, args: &[Term]) -> Result<Cow<'_, [Fact]>, RuntimeError> {
+        match self {
+            FactStore::Memory(facts) => Ok(facts.matching(predicate, args)),
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(store) => sqlite::lock(store).matching(predicate, args.first()).map(Cow::Owned),
+        }
+    }
+
+    /// Number of facts for `predicate`
+    pub(super) fn count(&self, predicate: Atom) -> Result<usize, RuntimeError> {
+        match self {
+            FactStore::Memory(facts) => Ok(facts.predicates.get(&predicate).map_or(0, |facts| facts.facts.len())),
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(store) => sqlite::lock(store).count(Some(predicate.as_str())),
+        }
+    }
+
+    /// Number of facts of each predicate, in the order predicates were first asserted
+    pub(super) fn counts(&self) -> Result<Vec<(String, usize)>, RuntimeError> {
+        match self {
+            FactStore::Memory(facts) => Ok(facts
+                .predicates
+                .iter()
+                .map(|(predicate, facts)| (predicate.as_str().to_string(), facts.facts.len()))
+                .collect()),
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(store) => sqlite::lock(store).counts(),
+        }
+    }
+
+    /// Number of facts across all predicates
+    pub(super) fn len(&self) -> Result<usize, RuntimeError> {
+        match self {
+            FactStore::Memory(facts) => Ok(facts.predicates.values().map(|facts| facts.facts.len()).sum()),
+            #[cfg(feature = "sqlite")]
+            FactStore::Sqlite(store) => sqlite::lock(store).count(None),
+        }
+    }
+}
+
+/// Key under which a first argument is indexed. Only terms that unify with
+/// nothing but an identical term get one; variables, floats and compounds
+/// are left unindexed and match every goal.
+#[cfg(feature = "sqlite")]
+fn index_key(term: &Term) -> Option<String> {
+    match term {
+        Term::Atom(_) | Term::Integer(_) | Term::String(_) => serde_json::to_string(term).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::cell::Cell;
+    use std::path::Path;
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+    use rusqlite::{params, Connection, Statement};
+    use super::{index_key, Atom, Fact, IndexStats, Term};
+    use crate::runtime::temporal::Validity;
+    use crate::runtime::RuntimeError;
+
+    const CREATE_TABLE: &str = "
+        CREATE TABLE IF NOT EXISTS facts (
+            id INTEGER PRIMARY KEY,
+            predicate TEXT NOT NULL,
+            first_arg TEXT,
+            args TEXT NOT NULL,
+            valid_from INTEGER,
+            valid_to INTEGER
+        )";
+    const CREATE_INDEX: &str = "CREATE INDEX IF NOT EXISTS facts_by_predicate ON facts (predicate, first_arg)";
+    const INSERT: &str =
+        "INSERT INTO facts (predicate, first_arg, args, valid_from, valid_to) VALUES (?1, ?2, ?3, ?4, ?5)";
+
+    /// Facts persisted in a single table indexed by predicate and first argument;
+    /// the row id keeps assertion order
+    #[derive(Debug)]
+    pub struct SqliteFactStore {
+        connection: Connection,
+        /// Lookups answered from the index and lookups that scanned
+        lookups: Cell<(usize, usize)>,
+    }
+
+    /// The store behind a shared connection, once no other thread uses it
+    pub(super) fn lock(store: &Mutex<SqliteFactStore>) -> MutexGuard<'_, SqliteFactStore> {
+        store.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn storage_error(error: impl std::fmt::Display) -> RuntimeError {
+        RuntimeError::LogicError(format!("SQLite knowledge storage: {}", error))
+    }
+
+    fn insert_fact(insert: &mut Statement<'_>, fact: &Fact) -> Result<(), RuntimeError> {
+        let args = serde_json::to_string(&fact.args).map_err(storage_error)?;
+        let first_arg = fact.args.first().and_then(index_key);
+        insert
+            .execute(params![fact.predicate.as_str(), first_arg, args, fact.valid.valid_from, fact.valid.valid_to])
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    /// Add the validity columns to a database made before facts had them
+    fn add_validity_columns(connection: &Connection) -> Result<(), RuntimeError> {
+        let has_columns = connection
+            .prepare("SELECT valid_from, valid_to FROM facts LIMIT 0")
+            .is_ok();
+        if !has_columns {
+            connection
+                .execute_batch(
+                    "ALTER TABLE facts ADD COLUMN valid_from INTEGER;
+                     ALTER TABLE facts ADD COLUMN valid_to INTEGER;",
+                )
+                .map_err(storage_error)?;
+        }
+        Ok(())
+    }
+
+    impl SqliteFactStore {
+        pub fn open(path: &Path) -> Result<Self, RuntimeError> {
+            let connection = Connection::open(path).map_err(storage_error)?;
+            connection.execute_batch(CREATE_TABLE).map_err(storage_error)?;
+            add_validity_columns(&connection)?;
+            connection.execute_batch(CREATE_INDEX).map_err(storage_error)?;
+            Ok(Self { connection, lookups: Cell::new((0, 0)) })
+        }
+
+        pub fn close(self) -> Result<(), RuntimeError> {
+            self.connection.close().map_err(|(_, error)| storage_error(error))
+        }
+
+        pub fn add(&mut self, fact: &Fact) -> Result<(), RuntimeError> {
+            let mut insert = self.connection.prepare_cached(INSERT).map_err(storage_error)?;
+            insert_fact(&mut insert, fact)
+        }
+
+        /// Insert facts in one transaction, dropping the index while loading
+        pub fn extend(&mut self, facts: &[Fact], mut progress: impl FnMut(usize)) -> Result<(), RuntimeError> {
+            let transaction = self.connection.transaction().map_err(storage_error)?;
+            transaction.execute_batch("DROP INDEX IF EXISTS facts_by_predicate").map_err(storage_error)?;
+            {
+                let mut insert = transaction.prepare_cached(INSERT).map_err(storage_error)?;
+                for (index, fact) in facts.iter().enumerate() {
+                    insert_fact(&mut insert, fact)?;
+                    progress(index + 1);
+                }
+            }
+            transaction.execute_batch(CREATE_INDEX).map_err(storage_error)?;
+            transaction.commit().map_err(storage_error)
+        }
+
+        pub fn remove(&mut self, fact: &Fact) -> Result<(), RuntimeError> {
+            let args = serde_json::to_string(&fact.args).map_err(storage_error)?;
+            self.connection
+                .prepare_cached("DELETE FROM facts WHERE predicate = ?1 AND args = ?2")
+                .and_then(|mut delete| delete.execute(params![fact.predicate.as_str(), args]))
+                .map_err(storage_error)?;
+            Ok(())
+        }
+
+        pub fn remove_expired(&mut self, now: i64) -> Result<Vec<Fact>, RuntimeError> {
+            let transaction = self.connection.transaction().map_err(storage_error)?;
+            let expired = {
+                let mut select = transaction
+                    .prepare_cached(
+                        "SELECT predicate, args, valid_from, valid_to FROM facts
+                         WHERE valid_to IS NOT NULL AND valid_to <= ?1 ORDER BY id",
+                    )
+                    .map_err(storage_error)?;
+                let rows = select
+                    .query_map(params![now], |row| {
+                        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get(2)?, row.get(3)?))
+                    })
+                    .map_err(storage_error)?;
+                rows.map(|row| {
+                    let (predicate, args, valid_from, valid_to) = row.map_err(storage_error)?;
+                    Ok(Fact {
+                        predicate: Atom::new(&predicate),
+                        args: serde_json::from_str(&args).map_err(storage_error)?,
+                        valid: Validity { valid_from, valid_to },
+                    })
+                })
+                .collect::<Result<Vec<Fact>, RuntimeError>>()?
+            };
+            transaction
+                .execute("DELETE FROM facts WHERE valid_to IS NOT NULL AND valid_to <= ?1", params![now])
+                .map_err(storage_error)?;
+            transaction.commit().map_err(storage_error)?;
+            Ok(expired)
+        }
+
+        pub fn matching(&self, predicate: Atom, first_arg: Option<&Term>) -> Result<Vec<Fact>, RuntimeError> {
+            let key = first_arg.and_then(index_key);
+            let (indexed, scans) = self.lookups.get();
+            self.lookups.set(if key.is_some() { (indexed + 1, scans) } else { (indexed, scans + 1) });
+            let mut select = self
+                .connection
+                .prepare_cached(
+                    "SELECT args, valid_from, valid_to FROM facts
+                     WHERE predicate = ?1 AND (?2 IS NULL OR first_arg IS NULL OR first_arg = ?2)
+                     ORDER BY id",
+                )
+                .map_err(storage_error)?;
+            let rows = select
+                .query_map(params![predicate.as_str(), key], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?))
+                })
+                .map_err(storage_error)?;
+
+            let facts = rows
+                .map(|row| {
+                    let (args, valid_from, valid_to) = row.map_err(storage_error)?;
+                    Ok(Fact {
+                        predicate,
+                        args: serde_json::from_str::<Vec<Term>>(&args).map_err(storage_error)?,
+                        valid: Validity { valid_from, valid_to },
+                    })
+                })
+                .collect();
+            facts
+        }
+
+        /// Number of facts for `predicate`, or of all facts
+        pub fn count(&self, predicate: Option<&str>) -> Result<usize, RuntimeError> {
+            let count: i64 = self
+                .connection
+                .query_row(
+                    "SELECT COUNT(*) FROM facts WHERE ?1 IS NULL OR predicate = ?1",
+                    params![predicate],
+                    |row| row.get(0),
+                )
+                .map_err(storage_error)?;
+            Ok(count as usize)
+        }
+
+        /// One first-argument index per predicate, with its distinct values
+        pub fn index_stats(&self) -> IndexStats {
+            let count = |query: &str| {
+                self.connection.query_row(query, [], |row| row.get::<_, i64>(0)).map_or(0, |count| count as usize)
+            };
+            let (indexed_lookups, scans) = self.lookups.get();
+            IndexStats {
+                indexes: count("SELECT COUNT(DISTINCT predicate) FROM facts"),
+                keys: count("SELECT COUNT(*) FROM (SELECT DISTINCT predicate, first_arg FROM facts WHERE first_arg IS NOT NULL)"),
+                indexed_lookups,
+                scans,
+            }
+        }
+
+        pub fn counts(&self) -> Result<Vec<(String, usize)>, RuntimeError> {
+            let mut select = self
+                .connection
+                .prepare_cached("SELECT predicate, COUNT(*) FROM facts GROUP BY predicate ORDER BY MIN(id)")
+                .map_err(storage_error)?;
+            let rows = select
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize)))
+                .map_err(storage_error)?;
+            rows.map(|row| row.map_err(storage_error)).collect()
+        }
+    }
+}
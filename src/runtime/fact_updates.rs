@@ -0,0 +1,144 @@
+//! # Fact Updates
+//!
+//! C-compatible entry points for natively compiled `assert` and `retract`
+//! statements. The LLVM backend emits `albayan_rt_fact_begin` with the
+//! relation's name, one `albayan_rt_fact_*` call per argument in the type the
+//! analyzer checked it against, then `albayan_rt_fact_assert` or
+//! `albayan_rt_fact_retract`, which consume the builder and apply the fact to
+//! the global runtime's knowledge base.
+//!
+//! The arguments reach the engine as [`FactValue`]s, so a string holding a
+//! quote or a comma is stored as it is rather than re-parsed from fact text.
+
+// The pointers come from generated code, which only passes builders and strings
+// returned by these functions; null pointers are ignored
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use std::ffi::{c_char, CStr};
+
+use super::{get_global_runtime, init_global_runtime, FactValue, Runtime, RuntimeError};
+
+/// A fact being built argument by argument
+#[derive(Debug, Default)]
+pub struct FactBuilder {
+    relation: String,
+    args: Vec<FactValue>,
+}
+
+fn text(text: *const c_char) -> Option<String> {
+    (!text.is_null()).then(|| unsafe { CStr::from_ptr(text) }.to_string_lossy().into_owned())
+}
+
+fn push(builder: *mut FactBuilder, value: FactValue) {
+    if let Some(builder) = unsafe { builder.as_mut() } {
+        builder.args.push(value);
+    }
+}
+
+/// Start a fact of the relation named by the NUL-terminated `relation`
+#[no_mangle]
+pub extern "C" fn albayan_rt_fact_begin(relation: *const c_char) -> *mut FactBuilder {
+    let relation = text(relation).unwrap_or_default();
+    Box::into_raw(Box::new(FactBuilder { relation, args: Vec::new() }))
+}
+
+#[no_mangle]
+pub extern "C" fn albayan_rt_fact_int(builder: *mut FactBuilder, value: i64) {
+    push(builder, FactValue::Int(value));
+}
+
+#[no_mangle]
+pub extern "C" fn albayan_rt_fact_float(builder: *mut FactBuilder, value: f64) {
+    push(builder, FactValue::Float(value));
+}
+
+#[no_mangle]
+pub extern "C" fn albayan_rt_fact_bool(builder: *mut FactBuilder, value: u8) {
+    push(builder, FactValue::Bool(value != 0));
+}
+
+/// Append a quoted string argument
+#[no_mangle]
+pub extern "C" fn albayan_rt_fact_string(builder: *mut FactBuilder, value: *const c_char) {
+    if let Some(value) = text(value) {
+        push(builder, FactValue::String(value));
+    }
+}
+
+/// Append a constant, such as an individual of a concept
+#[no_mangle]
+pub extern "C" fn albayan_rt_fact_atom(builder: *mut FactBuilder, name: *const c_char) {
+    if let Some(name) = text(name) {
+        push(builder, FactValue::Atom(name));
+    }
+}
+
+/// Consume the builder and apply `update` to its fact in the global runtime;
+/// 0 on success, -1 after printing the error
+fn apply(
+    builder: *mut FactBuilder,
+    action: &str,
+    update: impl FnOnce(&Runtime, &str, &[FactValue]) -> Result<(), RuntimeError>,
+) -> i32 {
+    if builder.is_null() {
+        return -1;
+    }
+    let fact = unsafe { Box::from_raw(builder) };
+    let applied = init_global_runtime().and_then(|()| {
+        let runtime = get_global_runtime().ok_or(RuntimeError::NotInitialized)?;
+        update(runtime, &fact.relation, &fact.args)
+    });
+    match applied {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("Cannot {} a `{}` fact: {}", action, fact.relation, error);
+            -1
+        }
+    }
+}
+
+/// Assert the built fact, consuming the builder
+#[no_mangle]
+pub extern "C" fn albayan_rt_fact_assert(builder: *mut FactBuilder) -> i32 {
+    apply(builder, "assert", Runtime::assert_values)
+}
+
+/// Retract the built fact, consuming the builder
+#[no_mangle]
+pub extern "C" fn albayan_rt_fact_retract(builder: *mut FactBuilder) -> i32 {
+    apply(builder, "retract", Runtime::retract_values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder(relation: &CStr) -> *mut FactBuilder {
+        let builder = albayan_rt_fact_begin(relation.as_ptr());
+        albayan_rt_fact_string(builder, c"say \"hi\", then go".as_ptr());
+        albayan_rt_fact_atom(builder, c"ali".as_ptr());
+        albayan_rt_fact_int(builder, -3);
+        albayan_rt_fact_float(builder, 2.5);
+        albayan_rt_fact_bool(builder, 1);
+        builder
+    }
+
+    #[test]
+    fn test_assert_and_retract_built_facts() {
+        let fact = builder(c"fact_updates_note");
+        let args = unsafe { &(*fact).args };
+        assert_eq!(
+            args,
+            &[
+                FactValue::String("say \"hi\", then go".to_string()),
+                FactValue::Atom("ali".to_string()),
+                FactValue::Int(-3),
+                FactValue::Float(2.5),
+                FactValue::Bool(true),
+            ]
+        );
+        assert_eq!(albayan_rt_fact_assert(fact), 0);
+        assert_eq!(albayan_rt_fact_retract(builder(c"fact_updates_note")), 0);
+        assert_eq!(albayan_rt_fact_assert(std::ptr::null_mut()), -1);
+    }
+}
@@ -0,0 +1,120 @@
+//! # Value Formatting
+//!
+//! C-compatible string building used by natively compiled `format`, `print` and
+//! `println` calls. The LLVM backend splits a format template at compile time and
+//! emits one `albayan_rt_format_*` call per piece on a builder obtained from
+//! `albayan_rt_format_begin`; `albayan_rt_format_finish` turns the builder into a
+//! NUL-terminated string owned by the caller, released with `albayan_rt_string_free`.
+//!
+//! Values are rendered exactly like Rust's `Display`, so compiled programs print
+//! the same text as the interpreter.
+
+// The pointers come from generated code, which only passes builders and strings
+// returned by these functions; null pointers are ignored
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use std::ffi::{c_char, CStr, CString};
+use std::fmt::Write;
+
+/// Start an empty string builder
+#[no_mangle]
+pub extern "C" fn albayan_rt_format_begin() -> *mut String {
+    Box::into_raw(Box::default())
+}
+
+/// Append a NUL-terminated UTF-8 string
+#[no_mangle]
+pub extern "C" fn albayan_rt_format_text(builder: *mut String, text: *const c_char) {
+    if let (Some(builder), false) = (unsafe { builder.as_mut() }, text.is_null()) {
+        builder.push_str(&unsafe { CStr::from_ptr(text) }.to_string_lossy());
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn albayan_rt_format_int(builder: *mut String, value: i64) {
+    if let Some(builder) = unsafe { builder.as_mut() } {
+        let _ = write!(builder, "{}", value);
+    }
+}
+
+/// Append a float; a negative `precision` uses the shortest exact representation
+#[no_mangle]
+pub extern "C" fn albayan_rt_format_float(builder: *mut String, value: f64, precision: i64) {
+    if let Some(builder) = unsafe { builder.as_mut() } {
+        let _ = match usize::try_from(precision) {
+            Ok(precision) => write!(builder, "{:.*}", precision, value),
+            Err(_) => write!(builder, "{}", value),
+        };
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn albayan_rt_format_bool(builder: *mut String, value: u8) {
+    if let Some(builder) = unsafe { builder.as_mut() } {
+        builder.push_str(if value != 0 { "true" } else { "false" });
+    }
+}
+
+/// Append a Unicode scalar value; invalid code points become U+FFFD
+#[no_mangle]
+pub extern "C" fn albayan_rt_format_char(builder: *mut String, value: u32) {
+    if let Some(builder) = unsafe { builder.as_mut() } {
+        builder.push(char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER));
+    }
+}
+
+/// Consume the builder and return its contents as a NUL-terminated string
+#[no_mangle]
+pub extern "C" fn albayan_rt_format_finish(builder: *mut String) -> *mut c_char {
+    if builder.is_null() {
+        return std::ptr::null_mut();
+    }
+    let text = unsafe { Box::from_raw(builder) };
+    let bytes: Vec<u8> = text.into_bytes().into_iter().filter(|&byte| byte != 0).collect();
+    CString::new(bytes).map(CString::into_raw).unwrap_or(std::ptr::null_mut())
+}
+
+/// Release a string returned by `albayan_rt_format_finish`
+#[no_mangle]
+pub extern "C" fn albayan_rt_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(unsafe { CString::from_raw(text) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finish(builder: *mut String) -> String {
+        let text = albayan_rt_format_finish(builder);
+        let result = unsafe { CStr::from_ptr(text) }.to_string_lossy().into_owned();
+        albayan_rt_string_free(text);
+        result
+    }
+
+    #[test]
+    fn test_format_values() {
+        let builder = albayan_rt_format_begin();
+        albayan_rt_format_text(builder, c"x = ".as_ptr());
+        albayan_rt_format_int(builder, -42);
+        albayan_rt_format_text(builder, c", ".as_ptr());
+        albayan_rt_format_float(builder, 2.5, -1);
+        albayan_rt_format_text(builder, c" ".as_ptr());
+        albayan_rt_format_float(builder, 1.0 / 3.0, 2);
+        albayan_rt_format_bool(builder, 1);
+        albayan_rt_format_char(builder, 'ب' as u32);
+        assert_eq!(finish(builder), "x = -42, 2.5 0.33trueب");
+    }
+
+    #[test]
+    fn test_null_arguments_are_ignored() {
+        albayan_rt_format_int(std::ptr::null_mut(), 1);
+        albayan_rt_string_free(std::ptr::null_mut());
+        assert!(albayan_rt_format_finish(std::ptr::null_mut()).is_null());
+
+        let builder = albayan_rt_format_begin();
+        albayan_rt_format_text(builder, std::ptr::null());
+        assert_eq!(finish(builder), "");
+    }
+}
@@ -0,0 +1,401 @@
+//! # Heap Snapshots
+//!
+//! A snapshot of the `AlbayanValue` heap reachable from a set of roots (the
+//! variables live at a breakpoint) together with the facts and rules of the
+//! knowledge base. Objects are grouped by type with their counts and retained
+//! sizes, which is where to look first when a long-running agent keeps
+//! growing; single objects can then be inspected field by field.
+//!
+//! The retained size of an object is its own size plus that of the objects
+//! first reached through it, so an object shared by two roots is counted
+//! once, under the root listed first.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::mem::size_of;
+
+use super::dynamic_types::{AlbayanEnum, AlbayanList, AlbayanString, AlbayanStruct, AlbayanTuple, AlbayanValue, AlbayanValueTag};
+use super::{LogicEngine, RuntimeError};
+
+/// Index of an object in its snapshot
+pub type ObjectId = usize;
+
+/// Longest string shown in full in an object's preview
+const PREVIEW_LENGTH: usize = 32;
+
+/// A value as recorded in a snapshot: scalars are kept inline, everything
+/// else refers to a heap object
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+    Object(ObjectId),
+}
+
+/// A string, list, struct, tuple or enum on the heap
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapObject {
+    pub id: ObjectId,
+    /// `string`, `list`, `tuple`, or the name of the struct or enum
+    pub type_name: String,
+    /// Short description: the text of a string, the length of a list, ...
+    pub preview: String,
+    /// Bytes the object itself occupies
+    pub shallow_size: usize,
+    /// Bytes freed along with the object
+    pub retained_size: usize,
+    /// Fields, elements or enum payload, by name or index
+    pub children: Vec<(String, SnapshotValue)>,
+    /// The object this one was first reached through; `None` for a root
+    pub owner: Option<ObjectId>,
+}
+
+/// Live objects of one type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeSummary {
+    pub type_name: String,
+    pub count: usize,
+    pub shallow_size: usize,
+    pub retained_size: usize,
+}
+
+/// Facts and rules of one predicate of the knowledge base
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PredicateSummary {
+    pub predicate: String,
+    pub facts: usize,
+    pub rules: usize,
+}
+
+/// The heap reachable from a set of roots, and the knowledge base
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeapSnapshot {
+    /// Root names and their values, in the order given
+    pub roots: Vec<(String, SnapshotValue)>,
+    objects: Vec<HeapObject>,
+    pub knowledge: Vec<PredicateSummary>,
+}
+
+impl HeapSnapshot {
+    /// Record every object reachable from `roots`
+    ///
+    /// # Safety
+    /// Every root must be a live value whose pointers, and those of the values
+    /// it holds, point to live objects
+    pub unsafe fn capture<'a>(roots: impl IntoIterator<Item = (&'a str, &'a AlbayanValue)>) -> Self {
+        let mut walker = Walker::default();
+        let roots = roots
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), walker.value(value).0))
+            .collect();
+        Self { roots, objects: walker.objects, knowledge: Vec::new() }
+    }
+
+    /// Add the facts and rules of `engine`, by predicate
+    pub fn with_knowledge(mut self, engine: &LogicEngine) -> Result<Self, RuntimeError> {
+        self.knowledge = engine
+            .predicate_counts()?
+            .into_iter()
+            .map(|(predicate, facts, rules)| PredicateSummary { predicate, facts, rules })
+            .collect();
+        Ok(self)
+    }
+
+    /// Every recorded object, in the order they were reached
+    pub fn objects(&self) -> &[HeapObject] {
+        &self.objects
+    }
+
+    pub fn object(&self, id: ObjectId) -> Option<&HeapObject> {
+        self.objects.get(id)
+    }
+
+    /// Bytes of all recorded objects
+    pub fn total_size(&self) -> usize {
+        self.objects.iter().map(|object| object.shallow_size).sum()
+    }
+
+    /// Objects grouped by type, largest retained size first
+    pub fn by_type(&self) -> Vec<TypeSummary> {
+        let mut groups: Vec<TypeSummary> = Vec::new();
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        for object in &self.objects {
+            let position = *positions.entry(object.type_name.as_str()).or_insert_with(|| {
+                groups.push(TypeSummary {
+                    type_name: object.type_name.clone(),
+                    count: 0,
+                    shallow_size: 0,
+                    retained_size: 0,
+                });
+                groups.len() - 1
+            });
+            let group = &mut groups[position];
+            group.count += 1;
+            group.shallow_size += object.shallow_size;
+            // Objects nested in one of the same type are already retained by it
+            if !self.is_nested_in_same_type(object) {
+                group.retained_size += object.retained_size;
+            }
+        }
+        groups.sort_by(|a, b| b.retained_size.cmp(&a.retained_size).then_with(|| a.type_name.cmp(&b.type_name)));
+        groups
+    }
+
+    fn is_nested_in_same_type(&self, object: &HeapObject) -> bool {
+        let mut owner = object.owner;
+        while let Some(id) = owner {
+            let parent = &self.objects[id];
+            if parent.type_name == object.type_name {
+                return true;
+            }
+            owner = parent.owner;
+        }
+        false
+    }
+
+    /// Text report: the roots, the objects by type and the knowledge base
+    pub fn render(&self) -> String {
+        let mut out = format!("Heap: {} objects, {} bytes\n", self.objects.len(), self.total_size());
+        for (name, value) in &self.roots {
+            out.push_str(&format!("  {} = {}\n", name, self.describe(value)));
+        }
+        out.push_str(&format!("{:<24} {:>8} {:>12} {:>12}\n", "type", "count", "shallow", "retained"));
+        for group in self.by_type() {
+            out.push_str(&format!(
+                "{:<24} {:>8} {:>12} {:>12}\n",
+                group.type_name, group.count, group.shallow_size, group.retained_size
+            ));
+        }
+        if !self.knowledge.is_empty() {
+            out.push_str(&format!("{:<24} {:>8} {:>12}\n", "predicate", "facts", "rules"));
+            for predicate in &self.knowledge {
+                out.push_str(&format!("{:<24} {:>8} {:>12}\n", predicate.predicate, predicate.facts, predicate.rules));
+            }
+        }
+        out
+    }
+
+    /// One object with its fields or elements; nested objects show their ids
+    /// so they can be inspected in turn
+    pub fn render_object(&self, id: ObjectId) -> Option<String> {
+        let object = self.object(id)?;
+        let mut out = format!(
+            "#{} {} {} ({} bytes, {} retained)\n",
+            object.id, object.type_name, object.preview, object.shallow_size, object.retained_size
+        );
+        for (name, value) in &object.children {
+            out.push_str(&format!("  {}: {}\n", name, self.describe(value)));
+        }
+        Some(out)
+    }
+
+    /// Short text of a value: scalars as they are, objects by id and preview
+    pub fn describe(&self, value: &SnapshotValue) -> String {
+        match value {
+            SnapshotValue::Int(value) => value.to_string(),
+            SnapshotValue::Float(value) => value.to_string(),
+            SnapshotValue::Bool(value) => value.to_string(),
+            SnapshotValue::Null => "null".to_string(),
+            SnapshotValue::Object(id) => match self.object(*id) {
+                Some(object) => format!("#{} {} {}", id, object.type_name, object.preview),
+                None => format!("#{}", id),
+            },
+        }
+    }
+}
+
+impl fmt::Display for HeapSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render())
+    }
+}
+
+/// Walks values depth first, recording each heap object once
+#[derive(Default)]
+struct Walker {
+    objects: Vec<HeapObject>,
+    /// Objects already recorded, by address
+    seen: HashMap<usize, ObjectId>,
+    /// Object whose children are being walked
+    current: Option<ObjectId>,
+}
+
+impl Walker {
+    /// The value as recorded, and the bytes newly retained through it
+    unsafe fn value(&mut self, value: &AlbayanValue) -> (SnapshotValue, usize) {
+        let address = match value.tag {
+            AlbayanValueTag::Int => return (SnapshotValue::Int(value.payload.int_val), 0),
+            AlbayanValueTag::Float => return (SnapshotValue::Float(value.payload.float_val), 0),
+            AlbayanValueTag::Bool => return (SnapshotValue::Bool(value.payload.bool_val), 0),
+            AlbayanValueTag::Null => return (SnapshotValue::Null, 0),
+            AlbayanValueTag::String => value.payload.string_val as usize,
+            AlbayanValueTag::List => value.payload.list_val as usize,
+            AlbayanValueTag::Struct => value.payload.struct_val as usize,
+            AlbayanValueTag::Tuple => value.payload.tuple_val as usize,
+            AlbayanValueTag::Enum => value.payload.enum_val as usize,
+        };
+        if address == 0 {
+            return (SnapshotValue::Null, 0);
+        }
+        if let Some(id) = self.seen.get(&address) {
+            return (SnapshotValue::Object(*id), 0);
+        }
+
+        let id = self.objects.len();
+        self.seen.insert(address, id);
+        self.objects.push(HeapObject {
+            id,
+            type_name: String::new(),
+            preview: String::new(),
+            shallow_size: 0,
+            retained_size: 0,
+            children: Vec::new(),
+            owner: self.current,
+        });
+        let owner = self.current.replace(id);
+
+        let mut children = Vec::new();
+        let mut retained = 0;
+        let (type_name, preview, shallow_size) = match value.tag {
+            AlbayanValueTag::String => {
+                let string = &*value.payload.string_val;
+                (
+                    "string".to_string(),
+                    quote(string.as_str()),
+                    size_of::<AlbayanString>() + string.capacity,
+                )
+            }
+            AlbayanValueTag::List => {
+                let list: &AlbayanList = &*value.payload.list_val;
+                for index in 0..list.len() {
+                    let (element, size) = self.value(&*list.data.add(index));
+                    children.push((index.to_string(), element));
+                    retained += size;
+                }
+                (
+                    "list".to_string(),
+                    format!("[{}]", list.len()),
+                    size_of::<AlbayanList>() + list.capacity * size_of::<AlbayanValue>(),
+                )
+            }
+            AlbayanValueTag::Struct => {
+                let struct_val: &AlbayanStruct = &*value.payload.struct_val;
+                let type_name = (*struct_val.type_name).as_str().to_string();
+                let fields = &*struct_val.fields;
+                let mut names: Vec<&String> = fields.keys().collect();
+                names.sort();
+                for name in names {
+                    let (field, size) = self.value(&fields[name]);
+                    children.push((name.clone(), field));
+                    retained += size;
+                }
+                let shallow = size_of::<AlbayanStruct>()
+                    + size_of::<HashMap<String, AlbayanValue>>()
+                    + fields.capacity() * (size_of::<String>() + size_of::<AlbayanValue>())
+                    + fields.keys().map(String::capacity).sum::<usize>()
+                    + size_of::<AlbayanString>()
+                    + (*struct_val.type_name).capacity;
+                (type_name, format!("{{{} fields}}", fields.len()), shallow)
+            }
+            AlbayanValueTag::Tuple => {
+                let tuple: &AlbayanTuple = &*value.payload.tuple_val;
+                for index in 0..tuple.len {
+                    let (element, size) = self.value(&*tuple.elements.add(index));
+                    children.push((index.to_string(), element));
+                    retained += size;
+                }
+                (
+                    "tuple".to_string(),
+                    format!("({})", tuple.len),
+                    size_of::<AlbayanTuple>() + tuple.len * size_of::<AlbayanValue>(),
+                )
+            }
+            _ => {
+                let enum_val: &AlbayanEnum = &*value.payload.enum_val;
+                let variant = (*enum_val.variant_name).as_str().to_string();
+                let mut shallow = size_of::<AlbayanEnum>()
+                    + 2 * size_of::<AlbayanString>()
+                    + (*enum_val.enum_name).capacity
+                    + (*enum_val.variant_name).capacity;
+                if !enum_val.payload.is_null() {
+                    let (payload, size) = self.value(&*enum_val.payload);
+                    children.push(("payload".to_string(), payload));
+                    retained += size;
+                    shallow += size_of::<AlbayanValue>();
+                }
+                ((*enum_val.enum_name).as_str().to_string(), format!("::{}", variant), shallow)
+            }
+        };
+
+        self.current = owner;
+        let object = &mut self.objects[id];
+        object.type_name = type_name;
+        object.preview = preview;
+        object.shallow_size = shallow_size;
+        object.retained_size = shallow_size + retained;
+        object.children = children;
+        (SnapshotValue::Object(id), shallow_size + retained)
+    }
+}
+
+fn quote(text: &str) -> String {
+    match text.char_indices().nth(PREVIEW_LENGTH) {
+        Some((end, _)) => format!("{:?}...", &text[..end]),
+        None => format!("{:?}", text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_groups_objects_and_drills_into_them() {
+        let mut list = AlbayanList::new();
+        list.push(AlbayanValue::new_string("first"));
+        list.push(AlbayanValue::new_int(2));
+        let mut fields = HashMap::new();
+        fields.insert("name".to_string(), AlbayanValue::new_string("ali"));
+        fields.insert("scores".to_string(), AlbayanValue::new_list(Box::into_raw(list)));
+        let agent = AlbayanValue::new_struct("Agent", fields);
+        let count = AlbayanValue::new_int(7);
+
+        let mut engine = LogicEngine::new();
+        engine.assert_fact("parent(ali, omar).").unwrap();
+        engine.assert_fact("parent(omar, zaid).").unwrap();
+        engine.add_rule("ancestor(X, Y) :- parent(X, Y).").unwrap();
+
+        // SAFETY: the values are live until they are freed below
+        let snapshot = unsafe { HeapSnapshot::capture([("agent", &agent), ("count", &count)]) }
+            .with_knowledge(&engine)
+            .unwrap();
+        assert_eq!(snapshot.roots[1], ("count".to_string(), SnapshotValue::Int(7)));
+        assert_eq!(snapshot.objects().len(), 4);
+
+        let groups = snapshot.by_type();
+        assert_eq!(groups[0].type_name, "Agent");
+        assert_eq!(groups[0].retained_size, snapshot.total_size());
+        let strings = groups.iter().find(|group| group.type_name == "string").unwrap();
+        assert_eq!(strings.count, 2);
+
+        let root = snapshot.render_object(0).unwrap();
+        assert!(root.starts_with("#0 Agent {2 fields}"), "{}", root);
+        assert!(root.contains("scores: #2 list [2]"), "{}", root);
+        assert!(snapshot.render_object(2).unwrap().contains("0: #3 string \"first\""));
+        assert!(snapshot.render_object(9).is_none());
+
+        assert_eq!(
+            snapshot.knowledge,
+            vec![
+                PredicateSummary { predicate: "parent".to_string(), facts: 2, rules: 0 },
+                PredicateSummary { predicate: "ancestor".to_string(), facts: 0, rules: 1 },
+            ]
+        );
+        assert!(snapshot.render().contains("count = 7"));
+
+        // SAFETY: the values are not used afterwards
+        unsafe { agent.free() };
+    }
+}
@@ -0,0 +1,400 @@
+//! # Asynchronous Inference
+//!
+//! Model inference can take far longer than anything else a program does, so
+//! `AIEngine::predict_async` runs it on a pool of worker threads instead of
+//! the thread that holds the runtime. The caller gets an [`InferenceHandle`]
+//! back at once and either polls it ([`InferenceHandle::try_take`]) or blocks
+//! on it ([`InferenceHandle::wait`]); the handle is also a [`Future`], so
+//! `await` works on it once AlBayan's async functions run on an executor. The
+//! engine's lock is only held while a request is validated and queued, which
+//! keeps REPL and LSP sessions that embed the runtime responsive.
+//!
+//! Native executables reach the same pool through [`albayan_rt_ai_predict_async`],
+//! which returns a numeric handle for [`albayan_rt_ai_poll`],
+//! [`albayan_rt_ai_take`] and [`albayan_rt_ai_discard`].
+
+// The pointers come from generated code, which passes NUL-terminated names and
+// buffers of the lengths it states
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr};
+use std::fmt;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::ai_support::Tensor;
+use super::RuntimeError;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Worker threads that run inference requests in the order they were queued
+pub struct InferencePool {
+    sender: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+/// The result of an inference request that may still be running
+#[derive(Clone)]
+pub struct InferenceHandle {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    finished: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    result: Option<Result<Tensor, RuntimeError>>,
+    /// Set once the result has been taken
+    taken: bool,
+    waker: Option<Waker>,
+}
+
+impl InferencePool {
+    /// Start `workers` threads (at least one)
+    pub fn new(workers: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..workers.max(1))
+            .map(|index| {
+                let receiver = Arc::clone(&receiver);
+                thread::Builder::new()
+                    .name(format!("albayan-inference-{}", index))
+                    .spawn(move || loop {
+                        // The lock is released before the job runs
+                        let job = receiver.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("failed to spawn an inference worker")
+            })
+            .collect();
+        Self { sender: Some(sender), workers }
+    }
+
+    /// Number of worker threads
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Queue `inference` and return a handle to its result. A panic in
+    /// `inference` becomes an error result.
+    pub fn submit<F>(&self, inference: F) -> InferenceHandle
+    where
+        F: FnOnce() -> Result<Tensor, RuntimeError> + Send + 'static,
+    {
+        let handle = InferenceHandle::pending();
+        let completion = handle.clone();
+        let job: Job = Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(inference))
+                .unwrap_or_else(|_| Err(RuntimeError::AIError("inference panicked".to_string())));
+            completion.complete(result);
+        });
+        if let Some(Err(mpsc::SendError(job))) = self.sender.as_ref().map(|sender| sender.send(job)) {
+            // Every worker has stopped; run the request here rather than lose it
+            job();
+        }
+        handle
+    }
+}
+
+impl Drop for InferencePool {
+    /// Finish the queued requests, then stop the workers
+    fn drop(&mut self) {
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl fmt::Debug for InferencePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InferencePool").field("workers", &self.workers.len()).finish()
+    }
+}
+
+impl InferenceHandle {
+    fn pending() -> Self {
+        Self { shared: Arc::new(Shared { state: Mutex::new(State::default()), finished: Condvar::new() }) }
+    }
+
+    /// A handle whose result is already known
+    pub fn ready(result: Result<Tensor, RuntimeError>) -> Self {
+        let handle = Self::pending();
+        handle.complete(result);
+        handle
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.shared.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn complete(&self, result: Result<Tensor, RuntimeError>) {
+        let waker = {
+            let mut state = self.state();
+            state.result = Some(result);
+            state.waker.take()
+        };
+        self.shared.finished.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Whether the request has finished and its result has not been taken
+    pub fn is_ready(&self) -> bool {
+        self.state().result.is_some()
+    }
+
+    /// The result, if the request has finished; it can be taken only once
+    pub fn try_take(&self) -> Option<Result<Tensor, RuntimeError>> {
+        let mut state = self.state();
+        let result = state.result.take();
+        state.taken |= result.is_some();
+        result
+    }
+
+    /// Block until the request finishes and take its result
+    pub fn wait(&self) -> Result<Tensor, RuntimeError> {
+        let mut state = self.state();
+        loop {
+            if let Some(result) = state.result.take() {
+                state.taken = true;
+                return result;
+            }
+            if state.taken {
+                return Err(already_taken());
+            }
+            state = self.shared.finished.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Like [`wait`](Self::wait), giving up after `timeout`
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<Result<Tensor, RuntimeError>> {
+        let state = self.state();
+        let (mut state, _) = self
+            .shared
+            .finished
+            .wait_timeout_while(state, timeout, |state| state.result.is_none() && !state.taken)
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let result = state.result.take();
+        state.taken |= result.is_some();
+        result
+    }
+}
+
+impl Future for InferenceHandle {
+    type Output = Result<Tensor, RuntimeError>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state();
+        match state.result.take() {
+            Some(result) => {
+                state.taken = true;
+                Poll::Ready(result)
+            }
+            None if state.taken => Poll::Ready(Err(already_taken())),
+            None => {
+                state.waker = Some(context.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl fmt::Debug for InferenceHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state();
+        let status = match (&state.result, state.taken) {
+            (Some(_), _) => "ready",
+            (None, true) => "taken",
+            (None, false) => "pending",
+        };
+        f.debug_struct("InferenceHandle").field("status", &status).finish()
+    }
+}
+
+fn already_taken() -> RuntimeError {
+    RuntimeError::AIError("the inference result was already taken".to_string())
+}
+
+/// Requests started through the C interface, by handle
+static REQUESTS: OnceLock<Mutex<HashMap<u64, InferenceHandle>>> = OnceLock::new();
+static NEXT_REQUEST: AtomicU64 = AtomicU64::new(1);
+
+fn requests() -> MutexGuard<'static, HashMap<u64, InferenceHandle>> {
+    REQUESTS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn start(model: &str, data: Vec<f32>, shape: Vec<usize>) -> Result<InferenceHandle, RuntimeError> {
+    super::init_global_runtime()?;
+    let engine = super::get_global_runtime()
+        .ok_or(RuntimeError::NotInitialized)?
+        .ai_engine()
+        .ok_or_else(|| RuntimeError::FeatureDisabled("AI".to_string()))?;
+    let mut engine = engine.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let input = engine.create_tensor(shape, data)?;
+    engine.predict_async(model, input)
+}
+
+/// Start inference of `model` on a tensor of the given shape and return its
+/// handle, or 0 if the request is invalid
+#[no_mangle]
+pub extern "C" fn albayan_rt_ai_predict_async(
+    model: *const c_char,
+    data: *const f32,
+    len: usize,
+    shape: *const usize,
+    rank: usize,
+) -> u64 {
+    if model.is_null() || (data.is_null() && len > 0) || (shape.is_null() && rank > 0) {
+        return 0;
+    }
+    let model = unsafe { CStr::from_ptr(model) }.to_string_lossy();
+    let data = if len == 0 { Vec::new() } else { unsafe { std::slice::from_raw_parts(data, len) }.to_vec() };
+    let shape = if rank == 0 { Vec::new() } else { unsafe { std::slice::from_raw_parts(shape, rank) }.to_vec() };
+    match start(&model, data, shape) {
+        Ok(handle) => {
+            let id = NEXT_REQUEST.fetch_add(1, Ordering::Relaxed);
+            requests().insert(id, handle);
+            id
+        }
+        Err(error) => {
+            eprintln!("Cannot start inference: {}", error);
+            0
+        }
+    }
+}
+
+/// 1 if the request has finished, 0 while it runs, -1 for an unknown handle
+#[no_mangle]
+pub extern "C" fn albayan_rt_ai_poll(handle: u64) -> i32 {
+    match requests().get(&handle) {
+        Some(request) => request.is_ready() as i32,
+        None => -1,
+    }
+}
+
+/// Wait for the request, copy up to `capacity` values of its output to `out`
+/// and release the handle. Returns the number of output values, or -1 if the
+/// handle is unknown or the inference failed.
+#[no_mangle]
+pub extern "C" fn albayan_rt_ai_take(handle: u64, out: *mut f32, capacity: usize) -> i64 {
+    // The registry is not locked while waiting, so other requests can be polled
+    let Some(request) = requests().remove(&handle) else {
+        return -1;
+    };
+    match request.wait() {
+        Ok(output) => {
+            if !out.is_null() {
+                let count = output.data.len().min(capacity);
+                unsafe { std::ptr::copy_nonoverlapping(output.data.as_ptr(), out, count) };
+            }
+            output.data.len() as i64
+        }
+        Err(error) => {
+            eprintln!("Inference failed: {}", error);
+            -1
+        }
+    }
+}
+
+/// Release a request without waiting for its result
+#[no_mangle]
+pub extern "C" fn albayan_rt_ai_discard(handle: u64) {
+    requests().remove(&handle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    fn tensor(data: Vec<f32>) -> Tensor {
+        Tensor { shape: vec![data.len()], data }
+    }
+
+    #[test]
+    fn test_inference_runs_off_the_calling_thread() {
+        let pool = InferencePool::new(2);
+        let (release, gate) = channel::<()>();
+        let caller = thread::current().id();
+        let blocked = pool.submit(move || {
+            gate.recv().unwrap();
+            assert_ne!(thread::current().id(), caller);
+            Ok(tensor(vec![1.0, 2.0]))
+        });
+
+        // The other worker keeps serving requests while the first is blocked
+        let quick = pool.submit(|| Ok(tensor(vec![3.0])));
+        assert_eq!(quick.wait().unwrap().data, vec![3.0]);
+        assert!(!blocked.is_ready());
+        assert!(blocked.try_take().is_none());
+        assert!(blocked.wait_timeout(Duration::from_millis(10)).is_none());
+
+        release.send(()).unwrap();
+        assert_eq!(blocked.wait().unwrap().data, vec![1.0, 2.0]);
+        assert!(blocked.wait().is_err());
+    }
+
+    #[test]
+    fn test_failed_and_panicking_inference_are_errors() {
+        let pool = InferencePool::new(1);
+        let failed = pool.submit(|| Err(RuntimeError::AIError("bad input".to_string())));
+        let panicked = pool.submit(|| panic!("model crashed"));
+        assert!(matches!(failed.wait(), Err(RuntimeError::AIError(message)) if message == "bad input"));
+        assert!(matches!(panicked.wait(), Err(RuntimeError::AIError(message)) if message == "inference panicked"));
+
+        // Dropping the pool finishes what was queued
+        let queued = pool.submit(|| Ok(tensor(vec![4.0])));
+        drop(pool);
+        assert!(queued.is_ready());
+    }
+
+    #[test]
+    fn test_handles_are_futures() {
+        struct Flag(std::sync::atomic::AtomicBool);
+        impl std::task::Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let pool = InferencePool::new(1);
+        let (release, gate) = channel::<()>();
+        let mut handle = pool.submit(move || {
+            gate.recv().unwrap();
+            Ok(tensor(vec![5.0]))
+        });
+        let flag = Arc::new(Flag(std::sync::atomic::AtomicBool::new(false)));
+        let waker = Waker::from(Arc::clone(&flag));
+        let mut context = Context::from_waker(&waker);
+
+        assert!(Pin::new(&mut handle).poll(&mut context).is_pending());
+        release.send(()).unwrap();
+        while !handle.is_ready() {
+            thread::yield_now();
+        }
+        assert!(flag.0.load(Ordering::SeqCst));
+        match Pin::new(&mut handle).poll(&mut context) {
+            Poll::Ready(Ok(output)) => assert_eq!(output.data, vec![5.0]),
+            other => panic!("{:?}", other.map(|result| result.map(|output| output.data))),
+        }
+    }
+}
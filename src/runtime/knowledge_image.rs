@@ -0,0 +1,463 @@
+//! # Knowledge Images
+//!
+//! The concepts, relations, constraints, facts and rules a program declares,
+//! encoded at compile time into a compact binary image. The LLVM backend
+//! embeds the image as constant data and compiled programs hand it to
+//! `albayan_rt_load_knowledge` when they start, which decodes it straight into
+//! the logic engine's terms and stores the facts in one bulk pass instead of
+//! parsing them back from text. The facts are checked against the constraints
+//! once they are all stored.
+//!
+//! ```text
+//! image       := "ABKI" version:u8 strings concepts relations constraints facts rules
+//! strings     := count:u32 (len:u32 utf8-bytes)*
+//! concepts    := count:u32 (name:str parents:u8 parent:str*)*
+//! relations   := count:u32 (name:str tabled:u8 arity:u8 type:str*)*
+//! constraints := count:u32 (goals:u8 term*)*
+//! facts       := count:u32 term*
+//! rules       := count:u32 (reorder:u8 head:term goals:u8 (negated:u8 term)*)*
+//! term        := predicate:str arity:u8 arg*
+//! arg         := 0 variable:str | 1 atom:str | 2 i64 | 3 f64 | 4 string:str | 5 term
+//! ```
+//!
+//! `str` is an index into the string table; numbers are little-endian. The
+//! cut of a rule body is the goal `!` without arguments, and a comparison
+//! such as `Y is X + 1` is the goal `is(Y, +(X, 1))`, its arithmetic a
+//! compound term.
+
+use std::collections::HashMap;
+
+use super::atom::Atom;
+use super::logic_engine::{Fact, Term, EMPTY_LIST, LIST_CELL};
+use super::RuntimeError;
+
+const MAGIC: &[u8; 4] = b"ABKI";
+const VERSION: u8 = 6;
+
+/// An argument of a fact or rule as declared in source
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageArg<'a> {
+    Variable(&'a str),
+    Atom(&'a str),
+    Integer(i64),
+    Float(f64),
+    String(&'a str),
+    /// A functor applied to arguments, such as the arithmetic `+(X, 1)`
+    Compound(&'a str, Vec<ImageArg<'a>>),
+}
+
+impl<'a> ImageArg<'a> {
+    /// The list of `items` ending in `tail`, or in the empty list without one,
+    /// built from the engine's list cells
+    pub fn list(items: Vec<ImageArg<'a>>, tail: Option<ImageArg<'a>>) -> Self {
+        items
+            .into_iter()
+            .rev()
+            .fold(tail.unwrap_or(ImageArg::Atom(EMPTY_LIST)), |rest, item| ImageArg::Compound(LIST_CELL, vec![item, rest]))
+    }
+}
+
+/// A goal of a rule body as declared in source
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageGoal<'a> {
+    pub predicate: &'a str,
+    pub args: Vec<ImageArg<'a>>,
+    /// Whether the goal is negated with `not`
+    pub negated: bool,
+}
+
+/// Encodes declarations into a knowledge image
+#[derive(Debug, Default)]
+pub struct KnowledgeImageBuilder {
+    strings: Vec<String>,
+    string_ids: HashMap<String, u32>,
+    concepts: (u32, Vec<u8>),
+    relations: (u32, Vec<u8>),
+    constraints: (u32, Vec<u8>),
+    facts: (u32, Vec<u8>),
+    rules: (u32, Vec<u8>),
+}
+
+impl KnowledgeImageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether nothing has been added
+    pub fn is_empty(&self) -> bool {
+        self.concepts.0 == 0
+            && self.relations.0 == 0
+            && self.constraints.0 == 0
+            && self.facts.0 == 0
+            && self.rules.0 == 0
+    }
+
+    /// Define an ontology concept, as in `LogicEngine::define_concept`
+    pub fn concept(&mut self, name: &str, parents: &[&str]) {
+        let mut bytes = std::mem::take(&mut self.concepts.1);
+        self.write_string(&mut bytes, name);
+        bytes.push(parents.len() as u8);
+        for parent in parents {
+            self.write_string(&mut bytes, parent);
+        }
+        self.concepts = (self.concepts.0 + 1, bytes);
+    }
+
+    /// Declare a relation with its argument types, as in
+    /// `LogicEngine::declare_relation`; `tabled` is true for `#[table]` relations
+    pub fn relation(&mut self, name: &str, arg_types: &[&str], tabled: bool) {
+        let mut bytes = std::mem::take(&mut self.relations.1);
+        self.write_string(&mut bytes, name);
+        bytes.push(tabled as u8);
+        bytes.push(arg_types.len() as u8);
+        for arg_type in arg_types {
+            self.write_string(&mut bytes, arg_type);
+        }
+        self.relations = (self.relations.0 + 1, bytes);
+    }
+
+    /// Add an integrity constraint forbidding `goals` to hold together, as in
+    /// `LogicEngine::add_constraint`
+    pub fn constraint(&mut self, goals: &[(&str, Vec<ImageArg>)]) {
+        let mut bytes = std::mem::take(&mut self.constraints.1);
+        bytes.push(goals.len() as u8);
+        for (predicate, args) in goals {
+            self.write_term(&mut bytes, predicate, args);
+        }
+        self.constraints = (self.constraints.0 + 1, bytes);
+    }
+
+    pub fn fact(&mut self, predicate: &str, args: &[ImageArg]) {
+        let mut bytes = std::mem::take(&mut self.facts.1);
+        self.write_term(&mut bytes, predicate, args);
+        self.facts = (self.facts.0 + 1, bytes);
+    }
+
+    /// Add `head :- goals`; `reorder` is false for `#[no_reorder]` rules
+    pub fn rule(&mut self, head: (&str, &[ImageArg]), goals: &[ImageGoal], reorder: bool) {
+        let mut bytes = std::mem::take(&mut self.rules.1);
+        bytes.push(reorder as u8);
+        self.write_term(&mut bytes, head.0, head.1);
+        bytes.push(goals.len() as u8);
+        for goal in goals {
+            bytes.push(goal.negated as u8);
+            self.write_term(&mut bytes, goal.predicate, &goal.args);
+        }
+        self.rules = (self.rules.0 + 1, bytes);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        let mut image = MAGIC.to_vec();
+        image.push(VERSION);
+        image.extend_from_slice(&(self.strings.len() as u32).to_le_bytes());
+        for string in &self.strings {
+            image.extend_from_slice(&(string.len() as u32).to_le_bytes());
+            image.extend_from_slice(string.as_bytes());
+        }
+        for (count, bytes) in [self.concepts, self.relations, self.constraints, self.facts, self.rules] {
+            image.extend_from_slice(&count.to_le_bytes());
+            image.extend_from_slice(&bytes);
+        }
+        image
+    }
+
+    fn write_string(&mut self, bytes: &mut Vec<u8>, text: &str) {
+        let id = match self.string_ids.get(text) {
+            Some(id) => *id,
+            None => {
+                let id = self.strings.len() as u32;
+                self.strings.push(text.to_string());
+                self.string_ids.insert(text.to_string(), id);
+                id
+            }
+        };
+        bytes.extend_from_slice(&id.to_le_bytes());
+    }
+
+    fn write_term(&mut self, bytes: &mut Vec<u8>, predicate: &str, args: &[ImageArg]) {
+        self.write_string(bytes, predicate);
+        bytes.push(args.len() as u8);
+        for arg in args {
+            match arg {
+                ImageArg::Variable(name) => {
+                    bytes.push(0);
+                    self.write_string(bytes, name);
+                }
+                ImageArg::Atom(name) => {
+                    bytes.push(1);
+                    self.write_string(bytes, name);
+                }
+                ImageArg::Integer(value) => {
+                    bytes.push(2);
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                ImageArg::Float(value) => {
+                    bytes.push(3);
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                ImageArg::String(text) => {
+                    bytes.push(4);
+                    self.write_string(bytes, text);
+                }
+                ImageArg::Compound(functor, args) => {
+                    bytes.push(5);
+                    self.write_term(bytes, functor, args);
+                }
+            }
+        }
+    }
+}
+
+/// The declarations of a decoded image
+#[derive(Debug, Default)]
+pub(super) struct DecodedImage {
+    /// Concepts with their parents, in definition order
+    pub(super) concepts: Vec<(String, Vec<String>)>,
+    /// Relations with their argument types and whether they are tabled
+    pub(super) relations: Vec<(String, Vec<String>, bool)>,
+    /// Goals of each constraint
+    pub(super) constraints: Vec<Vec<Fact>>,
+    pub(super) facts: Vec<Fact>,
+    /// Head, goals each with whether it is negated, and whether the body
+    /// may be reordered
+    pub(super) rules: Vec<(Fact, Vec<(Fact, bool)>, bool)>,
+}
+
+/// Decode an image made by [`KnowledgeImageBuilder::finish`]
+pub(super) fn decode(image: &[u8]) -> Result<DecodedImage, RuntimeError> {
+    let mut reader = Reader { bytes: image, position: 0 };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(invalid("missing header"));
+    }
+    let version = reader.u8()?;
+    if version != VERSION {
+        return Err(invalid(&format!("unsupported version {}", version)));
+    }
+
+    let strings = (0..reader.u32()?)
+        .map(|_| {
+            let length = reader.u32()? as usize;
+            std::str::from_utf8(reader.take(length)?).map_err(|_| invalid("string is not UTF-8"))
+        })
+        .collect::<Result<Vec<&str>, _>>()?;
+    let mut decoded = DecodedImage::default();
+
+    for _ in 0..reader.u32()? {
+        let name = reader.string(&strings)?.to_string();
+        let parents = (0..reader.u8()?)
+            .map(|_| reader.string(&strings).map(str::to_string))
+            .collect::<Result<_, _>>()?;
+        decoded.concepts.push((name, parents));
+    }
+    for _ in 0..reader.u32()? {
+        let name = reader.string(&strings)?.to_string();
+        let tabled = reader.u8()? != 0;
+        let arg_types = (0..reader.u8()?)
+            .map(|_| reader.string(&strings).map(str::to_string))
+            .collect::<Result<_, _>>()?;
+        decoded.relations.push((name, arg_types, tabled));
+    }
+    for _ in 0..reader.u32()? {
+        let goals = (0..reader.u8()?)
+            .map(|_| reader.term(&strings))
+            .collect::<Result<_, _>>()?;
+        decoded.constraints.push(goals);
+    }
+    let fact_count = reader.u32()? as usize;
+    // Every fact takes several bytes, so a corrupt count cannot over-allocate
+    decoded.facts.reserve(fact_count.min(image.len()));
+    for _ in 0..fact_count {
+        decoded.facts.push(reader.term(&strings)?);
+    }
+    for _ in 0..reader.u32()? {
+        let reorder = reader.u8()? != 0;
+        let head = reader.term(&strings)?;
+        let goals = (0..reader.u8()?)
+            .map(|_| {
+                let negated = reader.u8()? != 0;
+                Ok::<_, RuntimeError>((reader.term(&strings)?, negated))
+            })
+            .collect::<Result<_, _>>()?;
+        decoded.rules.push((head, goals, reorder));
+    }
+
+    if reader.position != image.len() {
+        return Err(invalid("trailing bytes"));
+    }
+    Ok(decoded)
+}
+
+fn invalid(problem: &str) -> RuntimeError {
+    RuntimeError::LogicError(format!("invalid knowledge image: {}", problem))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], RuntimeError> {
+        let end = self.position.checked_add(length).filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or_else(|| invalid("truncated"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, RuntimeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, RuntimeError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().expect("4 bytes")))
+    }
+
+    fn u64(&mut self) -> Result<u64, RuntimeError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().expect("8 bytes")))
+    }
+
+    fn string<'s>(&mut self, strings: &[&'s str]) -> Result<&'s str, RuntimeError> {
+        let id = self.u32()? as usize;
+        strings.get(id).copied().ok_or_else(|| invalid("string index out of range"))
+    }
+
+    fn term(&mut self, strings: &[&str]) -> Result<Fact, RuntimeError> {
+        let predicate = Atom::new(self.string(strings)?);
+        let args = (0..self.u8()?).map(|_| self.arg(strings)).collect::<Result<_, _>>()?;
+        Ok(Fact::new(predicate, args))
+    }
+
+    fn arg(&mut self, strings: &[&str]) -> Result<Term, RuntimeError> {
+        Ok(match self.u8()? {
+            0 => Term::Variable(self.string(strings)?.to_string()),
+            1 => Term::Atom(Atom::new(self.string(strings)?)),
+            2 => Term::Integer(self.u64()? as i64),
+            3 => Term::Float(f64::from_bits(self.u64()?)),
+            4 => Term::String(self.string(strings)?.to_string()),
+            5 => {
+                let compound = self.term(strings)?;
+                Term::Compound(compound.predicate, compound.args)
+            }
+            tag => return Err(invalid(&format!("unknown term tag {}", tag))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::LogicEngine;
+
+    #[test]
+    fn test_image_loads_facts_and_rules() {
+        let mut image = KnowledgeImageBuilder::new();
+        assert!(image.is_empty());
+        image.relation("parent", &["string", "string"], false);
+        image.fact("parent", &[ImageArg::Atom("john"), ImageArg::Atom("mary")]);
+        image.fact("parent", &[ImageArg::Atom("mary"), ImageArg::Atom("ali")]);
+        image.fact("age", &[ImageArg::String("ali \"jr\""), ImageArg::Integer(-7), ImageArg::Float(1.5)]);
+        image.rule(
+            ("grandparent", &[ImageArg::Variable("X"), ImageArg::Variable("Z")]),
+            &[
+                ImageGoal { predicate: "parent", args: vec![ImageArg::Variable("X"), ImageArg::Variable("Y")], negated: false },
+                ImageGoal { predicate: "parent", args: vec![ImageArg::Variable("Y"), ImageArg::Variable("Z")], negated: false },
+            ],
+            true,
+        );
+        image.rule(
+            ("childless", &[ImageArg::Variable("X")]),
+            &[
+                ImageGoal { predicate: "parent", args: vec![ImageArg::Variable("Y"), ImageArg::Variable("X")], negated: false },
+                ImageGoal { predicate: "parent", args: vec![ImageArg::Variable("X"), ImageArg::Variable("Z")], negated: true },
+            ],
+            true,
+        );
+        let image = image.finish();
+
+        let mut engine = LogicEngine::new();
+        assert_eq!(engine.load_image(&image).unwrap(), 5);
+        assert_eq!(engine.facts_count(), 3);
+        assert_eq!(engine.solve_query("grandparent(john, Who)").unwrap()[0]["Who"], "ali");
+        assert_eq!(engine.solve_query("childless(Who)").unwrap()[0]["Who"], "ali");
+        assert_eq!(engine.solve_query("age(\"ali \\\"jr\\\"\", A, F)").unwrap()[0]["A"], "-7");
+
+        let error = engine.load_image(&image[..image.len() - 1]).unwrap_err();
+        assert!(error.to_string().contains("invalid knowledge image: truncated"), "{}", error);
+        assert!(engine.load_image(b"ABKI\x09").is_err());
+    }
+
+    #[test]
+    fn test_image_defines_concepts_before_checking_facts() {
+        let mut image = KnowledgeImageBuilder::new();
+        image.concept("animal", &[]);
+        image.concept("cat", &["animal"]);
+        image.relation("eats", &["animal", "string"], false);
+        image.fact("eats", &[ImageArg::Atom("tom"), ImageArg::Atom("fish")]);
+        image.fact("is_a", &[ImageArg::Atom("tom"), ImageArg::Atom("cat")]);
+        let image = image.finish();
+
+        let mut engine = LogicEngine::new();
+        assert_eq!(engine.load_image(&image).unwrap(), 2);
+        assert!(engine.ontology().is_subconcept("cat", "animal"));
+        assert_eq!(engine.solve_query("is_a(tom, animal)").unwrap().len(), 1);
+
+        let mut image = KnowledgeImageBuilder::new();
+        image.concept("animal", &[]);
+        image.relation("eats", &["animal", "string"], false);
+        image.fact("eats", &[ImageArg::Atom("rock"), ImageArg::Atom("fish")]);
+        let error = LogicEngine::new().load_image(&image.finish()).unwrap_err();
+        assert!(error.to_string().contains("argument 1 is rock, which is not in concept animal"), "{}", error);
+    }
+
+    #[test]
+    fn test_image_facts_are_checked_against_constraints() {
+        let mut image = KnowledgeImageBuilder::new();
+        image.relation("parent", &["string", "string"], false);
+        image.constraint(&[("parent", vec![ImageArg::Variable("X"), ImageArg::Variable("X")])]);
+        image.fact("parent", &[ImageArg::Atom("ann"), ImageArg::Atom("bob")]);
+        let image = image.finish();
+
+        let mut engine = LogicEngine::new();
+        assert_eq!(engine.load_image(&image).unwrap(), 1);
+        let error = engine.assert_fact("parent(bob, bob).").unwrap_err();
+        assert_eq!(error.to_string(), "Contradiction: parent(bob, bob) violates :- parent(X, X)");
+
+        let mut image = KnowledgeImageBuilder::new();
+        image.constraint(&[("parent", vec![ImageArg::Variable("X"), ImageArg::Variable("X")])]);
+        image.fact("parent", &[ImageArg::Atom("ann"), ImageArg::Atom("bob")]);
+        image.fact("parent", &[ImageArg::Atom("ann"), ImageArg::Atom("ann")]);
+        let mut engine = LogicEngine::new();
+        let error = engine.load_image(&image.finish()).unwrap_err();
+        assert!(error.to_string().contains("parent(ann, ann) violates :- parent(X, X)"), "{}", error);
+        assert_eq!(engine.facts_count(), 0);
+    }
+
+    #[test]
+    fn test_image_tables_relations() {
+        let mut image = KnowledgeImageBuilder::new();
+        image.relation("reaches", &["string", "string"], true);
+        image.fact("edge", &[ImageArg::Atom("a"), ImageArg::Atom("b")]);
+        image.fact("edge", &[ImageArg::Atom("b"), ImageArg::Atom("a")]);
+        image.rule(
+            ("reaches", &[ImageArg::Variable("X"), ImageArg::Variable("Y")]),
+            &[
+                ImageGoal { predicate: "reaches", args: vec![ImageArg::Variable("X"), ImageArg::Variable("Z")], negated: false },
+                ImageGoal { predicate: "edge", args: vec![ImageArg::Variable("Z"), ImageArg::Variable("Y")], negated: false },
+            ],
+            false,
+        );
+        image.rule(
+            ("reaches", &[ImageArg::Variable("X"), ImageArg::Variable("Y")]),
+            &[ImageGoal { predicate: "edge", args: vec![ImageArg::Variable("X"), ImageArg::Variable("Y")], negated: false }],
+            true,
+        );
+
+        let mut engine = LogicEngine::new();
+        engine.load_image(&image.finish()).unwrap();
+        let mut reached: Vec<String> =
+            engine.solve_query("reaches(a, Y)").unwrap().into_iter().map(|solution| solution["Y"].clone()).collect();
+        reached.sort();
+        assert_eq!(reached, ["a", "b"]);
+    }
+}
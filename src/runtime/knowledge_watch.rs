@@ -0,0 +1,86 @@
+//! # Knowledge Watchpoints
+//!
+//! Watchpoints that the logic engine sets on its knowledge base through
+//! `LogicEngine::watch_fact` and `LogicEngine::watch_rule`. A fact watchpoint
+//! holds a pattern such as `parent(X, "ali")`. It is hit when a fact that
+//! unifies with the pattern is asserted or retracted. A rule watchpoint is hit
+//! each time a rule of its predicate fires during a query.
+//!
+//! Each hit is recorded as a [`WatchHit`] together with the goal stack that
+//! triggered it. The innermost goal comes first, then each goal whose rule
+//! body is still being solved, and last the query. The solver runs every
+//! search to the end, so a debugger takes the hits once the statement that
+//! caused them has finished, and pauses on them.
+
+use std::fmt;
+
+/// Identifies a watchpoint of a logic engine
+pub type WatchId = usize;
+
+/// A change to the facts that a fact watchpoint can break on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FactChange {
+    Assert,
+    Retract,
+}
+
+/// What a watchpoint was hit by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchEvent {
+    Asserted,
+    Retracted,
+    RuleFired,
+}
+
+impl From<FactChange> for WatchEvent {
+    fn from(change: FactChange) -> Self {
+        match change {
+            FactChange::Assert => WatchEvent::Asserted,
+            FactChange::Retract => WatchEvent::Retracted,
+        }
+    }
+}
+
+impl fmt::Display for WatchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WatchEvent::Asserted => "asserted",
+            WatchEvent::Retracted => "retracted",
+            WatchEvent::RuleFired => "rule fired for",
+        })
+    }
+}
+
+/// A watchpoint as listed by `LogicEngine::watchpoints`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub id: WatchId,
+    /// What the watchpoint breaks on, e.g. `assert/retract of parent(X, "ali")`
+    pub description: String,
+}
+
+/// One hit of a watchpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchHit {
+    pub watchpoint: WatchId,
+    pub event: WatchEvent,
+    /// The fact asserted or retracted, or the goal the rule fired for with the
+    /// bindings its head unified
+    pub subject: String,
+    /// Goals that led to the hit, innermost first; empty for changes made
+    /// outside a query
+    pub goal_stack: Vec<String>,
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "watchpoint {}: {} {}", self.watchpoint, self.event, self.subject)?;
+        if !self.goal_stack.is_empty() {
+            write!(f, "\ngoal stack:")?;
+            for (index, goal) in self.goal_stack.iter().enumerate() {
+                write!(f, "\n  #{} {}", index, goal)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -2,45 +2,187 @@
 //! 
 //! This module implements the logic programming engine for AlBayan.
 //! It provides Prolog-style inference with facts, rules, and queries.
+//! Goals are solved by SLD resolution with backtracking over terms unified
+//! with an occurs check. Knowledge comes as text, as a compiled knowledge
+//! image, or straight from an analyzed program (`load_program`).
 
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use super::atom::Atom;
+use super::fact_store::{FactStore, IndexStats, KnowledgeStorage};
+use super::knowledge_watch::{FactChange, WatchEvent, WatchHit, WatchId, Watchpoint};
+use super::logic_trace::{LogicTracer, TraceEvent, TracePort};
+use super::ontology::{Ontology, IS_A};
+use super::temporal::{Validity, HOLDS_AT};
+use super::belief_revision::{Contradiction, Resolution, Revision, RevisionPolicy};
+use super::query_plan::{GoalAccess, PlanStep, QueryPlan, QueryStats};
+use super::proof::{ProofStep, ProofTree, Solution};
+use super::knowledge_image::DecodedImage;
 use super::RuntimeError;
+use crate::parser::ast::{Item, Type};
+use crate::semantic::{AnnotatedItem, AnnotatedLogicArg, AnnotatedLogicTerm, AnnotatedProgram, ResolvedType};
 
 /// Logic programming engine
 #[derive(Debug)]
 pub struct LogicEngine {
-    /// Knowledge base containing facts and rules
-    knowledge_base: KnowledgeBase,
+    /// Knowledge base containing facts and rules, shared with the snapshots
+    /// taken of the engine until either side changes it
+    knowledge_base: Arc<KnowledgeBase>,
     
     /// Query execution statistics
     queries_executed: usize,
+
+    /// Timings per query text for this session
+    query_stats: IndexMap<String, QueryStats>,
     
     /// Maximum search depth to prevent infinite loops
     max_depth: usize,
     
     /// Debug mode
     debug: bool,
+
+    /// Watchpoints on facts and rules in the order they were set
+    watchpoints: Vec<(WatchId, WatchTarget)>,
+
+    /// Id given to the last watchpoint
+    last_watch_id: WatchId,
+
+    /// Watchpoint hits not taken yet; the solver records them through `&self`
+    watch_hits: RefCell<Vec<WatchHit>>,
+
+    /// Goals whose rule bodies are being solved while rules are watched, each
+    /// with the number of goals left once its body is done
+    goal_frames: RefCell<Vec<(String, usize)>>,
+
+    /// Ports tracer, taken from `logic_trace::installed` when the engine is made
+    tracer: Option<LogicTracer>,
+
+    /// Goals the tracer has seen called and not yet fail, outermost first
+    trace_frames: RefCell<Vec<TraceFrame>>,
+
+    /// Depth of the goals around a negation whose goal is traced on its own
+    trace_base: Cell<usize>,
+
+    /// Concept hierarchy typing the atoms of facts, shared like the knowledge base
+    ontology: Arc<Ontology>,
+
+    /// Time point goals are solved at; `holds_at` moves it for its goal.
+    /// With none set every fact holds whatever its validity
+    time: Cell<Option<i64>>,
+
+    /// Integrity constraints asserts are checked against, in the order added
+    constraints: Vec<IntegrityConstraint>,
+
+    /// How asserts that violate a constraint are resolved
+    revision_policy: RevisionPolicy,
+
+    /// Whether solutions record the facts they rest on under `SUPPORT_KEY`
+    recording_support: Cell<bool>,
+
+    /// Whether solutions record the steps of their derivation under `PROOF_KEY`
+    recording_proofs: Cell<bool>,
+
+    /// Stored facts the solver treats as absent while a conflict is narrowed down
+    suspended: RefCell<Vec<Fact>>,
+
+    /// Barrier given to the last rule applied with a cut in its body
+    cut_barriers: Cell<usize>,
+
+    /// Barrier of the cut that fired and whose alternatives are being pruned
+    cut_fired: Cell<Option<usize>>,
+
+    /// Answer tables of the calls of tabled predicates made in this query,
+    /// by the variant of the call
+    tables: RefCell<HashMap<String, AnswerTable>>,
+
+    /// Fixpoint round tabled calls are evaluated in
+    table_round: Cell<usize>,
+
+    /// Whether a fixpoint is being run, by the outermost tabled call
+    tabling: Cell<bool>,
+
+    /// Whether a table gained an answer in this round
+    table_grew: Cell<bool>,
+}
+
+/// The answers found so far to a call of a tabled predicate
+#[derive(Debug, Default)]
+struct AnswerTable {
+    /// Instances of the call's arguments, none an instance of another
+    answers: Vec<Vec<Term>>,
+    /// Round the call's clauses were last solved in
+    round: usize,
+    /// Whether the fixpoint is reached and every answer found
+    complete: bool,
+}
+
+/// Goals that must never all hold at once
+#[derive(Debug, Clone)]
+struct IntegrityConstraint {
+    /// The constraint as written back, e.g. `:- alive(X), dead(X)`
+    text: String,
+    goals: Vec<Goal>,
+}
+
+/// A traced goal that may still exit or be redone
+#[derive(Debug)]
+struct TraceFrame {
+    goal: Goal,
+    /// The goal as it was called, shown again on redo and fail
+    called: String,
+    depth: usize,
+    /// Goals left once the goal has a solution
+    goals_left: usize,
+    /// Whether the filter lets its events through
+    shown: bool,
+    /// Whether the goal has a solution the search has not backtracked from
+    exited: bool,
+}
+
+/// What a watchpoint breaks on
+#[derive(Debug, Clone)]
+enum WatchTarget {
+    Fact { pattern: Fact, changes: Vec<FactChange> },
+    Rule(Atom),
 }
 
 /// Knowledge base containing facts and rules
 #[derive(Debug, Clone)]
 struct KnowledgeBase {
-    /// Facts indexed by predicate name
-    facts: IndexMap<String, Vec<Fact>>,
+    /// Facts indexed by predicate name, in memory or in a persistent store
+    facts: FactStore,
     
     /// Rules indexed by head predicate name
-    rules: IndexMap<String, Vec<Rule>>,
+    rules: IndexMap<Atom, Vec<Rule>>,
     
     /// Predicate signatures (name -> arity)
     predicates: HashMap<String, usize>,
+
+    /// Declared relation argument types, checked by bulk loads
+    relations: HashMap<String, Vec<String>>,
+
+    /// Predicates solved by tabled resolution
+    tabled: HashSet<Atom>,
 }
 
 /// A fact in the knowledge base
-#[derive(Debug, Clone, PartialEq)]
-struct Fact {
-    predicate: String,
-    args: Vec<Term>,
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(super) struct Fact {
+    pub(super) predicate: Atom,
+    pub(super) args: Vec<Term>,
+    /// When the fact holds; always, unless it was asserted with `assert_fact_at`
+    #[serde(default, skip_serializing_if = "Validity::is_always")]
+    pub(super) valid: Validity,
+}
+
+impl Fact {
+    pub(super) fn new(predicate: Atom, args: Vec<Term>) -> Self {
+        Self { predicate, args, valid: Validity::always() }
+    }
 }
 
 /// A rule in the knowledge base
@@ -48,30 +190,104 @@ struct Fact {
 struct Rule {
     head: Fact,
     body: Vec<Goal>,
+    /// Whether the optimizer may reorder the body; `#[no_reorder]` turns it off
+    reorder: bool,
 }
 
 /// A goal in a rule body or query
 #[derive(Debug, Clone)]
 struct Goal {
-    predicate: String,
+    predicate: Atom,
     args: Vec<Term>,
     negated: bool,
 }
 
+impl Goal {
+    /// Whether the goal is the cut `!`
+    fn is_cut(&self) -> bool {
+        self.predicate.as_str() == CUT
+    }
+}
+
+impl From<Fact> for Goal {
+    /// The positive goal matching `fact`
+    fn from(fact: Fact) -> Self {
+        Goal { predicate: fact.predicate, args: fact.args, negated: false }
+    }
+}
+
 /// A term in logic programming
-#[derive(Debug, Clone, PartialEq)]
-enum Term {
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(super) enum Term {
     Variable(String),
-    Atom(String),
+    /// Interned constant, compared by id
+    Atom(Atom),
     Integer(i64),
     Float(f64),
+    /// Quoted text, compared by value; `\"` and `\\` escape inside the quotes
     String(String),
-    Compound(String, Vec<Term>),
+    Compound(Atom, Vec<Term>),
 }
 
 /// Variable bindings during unification
 type Bindings = HashMap<String, Term>;
 
+/// Facts stored per batch between progress reports of a bulk load
+const BULK_CHUNK_SIZE: usize = 1000;
+
+/// Binding under which a solution records the facts it rests on while
+/// constraints are checked; no variable can have this name
+const SUPPORT_KEY: &str = "$support";
+
+/// Binding under which a solution records the steps of its derivation while
+/// proofs are recorded, each goal's step before the steps of its premises
+const PROOF_KEY: &str = "$proof";
+
+/// Attribute that keeps the goals of a rule or query in their written order
+const NO_REORDER: &str = "#[no_reorder]";
+
+/// Predicate of the cut goal; a rule applied with a cut gives it the barrier
+/// it prunes back to as its only argument, a cut in a query has none
+const CUT: &str = "!";
+
+/// The empty list, written `[]`
+pub const EMPTY_LIST: &str = "[]";
+
+/// Functor of a list cell: `[a, b]` is `[|](a, [|](b, []))`, and `[H | T]`
+/// is `[|](H, T)`, so a pattern's head and tail unify with any list
+pub const LIST_CELL: &str = "[|]";
+
+/// Factor by which every argument with a known value shrinks a goal's estimated solutions
+const BOUND_ARG_SELECTIVITY: f64 = 0.1;
+
+/// Solutions assumed per rule defining a predicate, as rule bodies are not costed
+const RULE_ROWS_ESTIMATE: f64 = 10.0;
+
+/// Argument of a fact asserted or retracted by value, as compiled `assert`
+/// and `retract` statements do, so that no text has to be quoted and parsed
+#[derive(Debug, Clone, PartialEq)]
+pub enum FactValue {
+    Int(i64),
+    Float(f64),
+    /// Stored as the atom `true` or `false`
+    Bool(bool),
+    String(String),
+    /// A constant, such as an individual of a concept
+    Atom(String),
+}
+
+impl FactValue {
+    fn to_term(&self) -> Term {
+        match self {
+            FactValue::Int(value) => Term::Integer(*value),
+            FactValue::Float(value) => Term::Float(*value),
+            FactValue::Bool(value) => Term::Atom(Atom::new(if *value { "true" } else { "false" })),
+            FactValue::String(text) => Term::String(text.clone()),
+            FactValue::Atom(name) => Term::Atom(Atom::new(name)),
+        }
+    }
+}
+
 /// Query result
 #[derive(Debug, Clone)]
 pub struct QueryResult {
@@ -83,11 +299,96 @@ impl LogicEngine {
     /// Create a new logic engine
     pub fn new() -> Self {
         Self {
-            knowledge_base: KnowledgeBase::new(),
+            knowledge_base: Arc::new(KnowledgeBase::new()),
             queries_executed: 0,
+            query_stats: IndexMap::new(),
             max_depth: 1000,
             debug: false,
+            watchpoints: Vec::new(),
+            last_watch_id: 0,
+            watch_hits: RefCell::new(Vec::new()),
+            goal_frames: RefCell::new(Vec::new()),
+            tracer: super::logic_trace::installed(),
+            trace_frames: RefCell::new(Vec::new()),
+            trace_base: Cell::new(0),
+            ontology: Arc::new(Ontology::new()),
+            time: Cell::new(None),
+            constraints: Vec::new(),
+            revision_policy: RevisionPolicy::default(),
+            recording_support: Cell::new(false),
+            recording_proofs: Cell::new(false),
+            suspended: RefCell::new(Vec::new()),
+            cut_barriers: Cell::new(0),
+            cut_fired: Cell::new(None),
+            tables: RefCell::new(HashMap::new()),
+            table_round: Cell::new(0),
+            tabling: Cell::new(false),
+            table_grew: Cell::new(false),
+        }
+    }
+
+    /// Create a logic engine whose facts are kept in `storage`
+    pub fn with_storage(storage: &KnowledgeStorage) -> Result<Self, RuntimeError> {
+        let mut engine = Self::new();
+        engine.knowledge_mut().facts = FactStore::open(storage)?;
+        Ok(engine)
+    }
+
+    /// An engine holding the knowledge as it is now, to solve queries on
+    /// without keeping this one locked. The snapshot shares the facts, rules
+    /// and ontology, which are copied only once either engine changes them,
+    /// so any number of snapshots can be queried in parallel while this
+    /// engine goes on taking asserts. Its query statistics and watchpoint
+    /// hits come back with [`absorb_queries`](Self::absorb_queries)
+    pub fn snapshot(&self) -> LogicEngine {
+        LogicEngine {
+            knowledge_base: Arc::clone(&self.knowledge_base),
+            ontology: Arc::clone(&self.ontology),
+            max_depth: self.max_depth,
+            debug: self.debug,
+            watchpoints: self.watchpoints.clone(),
+            last_watch_id: self.last_watch_id,
+            tracer: self.tracer.clone(),
+            time: Cell::new(self.time.get()),
+            constraints: self.constraints.clone(),
+            revision_policy: self.revision_policy.clone(),
+            ..LogicEngine::new()
+        }
+    }
+
+    /// Count the queries a snapshot of this engine solved, and take the
+    /// watchpoint hits they made
+    pub fn absorb_queries(&mut self, snapshot: LogicEngine) {
+        self.queries_executed += snapshot.queries_executed;
+        for (query, stats) in snapshot.query_stats {
+            self.query_stats.entry(query.clone()).or_insert_with(|| QueryStats::new(&query)).merge(&stats);
         }
+        self.watch_hits.get_mut().extend(snapshot.watch_hits.into_inner());
+    }
+
+    /// The knowledge base to change, copied first if a snapshot still shares
+    /// it: a whole in-memory knowledge base, or only the handle of a SQLite
+    /// store, whose snapshots see the change
+    fn knowledge_mut(&mut self) -> &mut KnowledgeBase {
+        Arc::make_mut(&mut self.knowledge_base)
+    }
+
+    /// Name of the fact storage backend ("memory" or "sqlite")
+    pub fn storage_backend(&self) -> &'static str {
+        self.knowledge_base.facts.backend_name()
+    }
+
+    /// Keep a hash index on `argument` (counting from 0) of the facts of
+    /// `predicate`, so goals with that argument known skip the other facts.
+    /// The first argument is always indexed; a persistent store indexes
+    /// nothing else.
+    pub fn index_argument(&mut self, predicate: &str, argument: usize) -> Result<(), RuntimeError> {
+        self.knowledge_mut().facts.add_index(Atom::new(predicate.trim()), argument)
+    }
+
+    /// The fact store's indexes and how lookups used them
+    pub fn index_stats(&self) -> IndexStats {
+        self.knowledge_base.facts.index_stats()
     }
     
     /// Initialize the logic engine
@@ -97,21 +398,34 @@ impl LogicEngine {
         Ok(())
     }
     
-    /// Shutdown the logic engine
+    /// Shutdown the logic engine, closing its fact store
     pub fn shutdown(&mut self) -> Result<(), RuntimeError> {
-        self.knowledge_base.clear();
-        Ok(())
+        let knowledge = std::mem::replace(&mut self.knowledge_base, Arc::new(KnowledgeBase::new()));
+        self.ontology = Arc::new(Ontology::new());
+        self.time.set(None);
+        self.constraints.clear();
+        // Snapshots still holding the knowledge keep its store open
+        match Arc::try_unwrap(knowledge) {
+            Ok(knowledge) => knowledge.facts.close(),
+            Err(_) => Ok(()),
+        }
     }
     
     /// Add built-in predicates
     fn add_builtin_predicates(&mut self) -> Result<(), RuntimeError> {
         // Add arithmetic predicates
-        self.knowledge_base.add_predicate("=", 2);
-        self.knowledge_base.add_predicate("<", 2);
-        self.knowledge_base.add_predicate(">", 2);
-        self.knowledge_base.add_predicate("<=", 2);
-        self.knowledge_base.add_predicate(">=", 2);
-        self.knowledge_base.add_predicate("is", 2);
+        let knowledge = self.knowledge_mut();
+        knowledge.add_predicate("=", 2);
+        knowledge.add_predicate("<", 2);
+        knowledge.add_predicate(">", 2);
+        knowledge.add_predicate("<=", 2);
+        knowledge.add_predicate(">=", 2);
+        knowledge.add_predicate("is", 2);
+
+        // Temporal predicates
+        knowledge.add_predicate("before", 2);
+        knowledge.add_predicate("after", 2);
+        knowledge.add_predicate(HOLDS_AT, 2);
         
         Ok(())
     }
@@ -119,194 +433,1372 @@ impl LogicEngine {
     /// Assert a fact into the knowledge base with improved indexing
     pub fn assert_fact(&mut self, fact_str: &str) -> Result<(), RuntimeError> {
         let fact = self.parse_fact(fact_str)?;
-        self.knowledge_base.add_fact(fact);
-        Ok(())
+        self.add_fact(fact).map(|_| ())
     }
 
-    /// Assert multiple facts at once for better performance
-    pub fn assert_facts(&mut self, fact_strings: &[&str]) -> Result<(), RuntimeError> {
-        for fact_str in fact_strings {
-            self.assert_fact(fact_str)?;
-        }
-        Ok(())
+    /// Assert `relation(values...)`, with the same checks as `assert_fact`
+    pub fn assert_values(&mut self, relation: &str, values: &[FactValue]) -> Result<(), RuntimeError> {
+        let fact = Fact::new(Atom::new(relation), values.iter().map(FactValue::to_term).collect());
+        self.add_fact(fact).map(|_| ())
     }
-    
-    /// Retract a fact from the knowledge base
-    pub fn retract_fact(&mut self, fact_str: &str) -> Result<(), RuntimeError> {
+
+    /// Assert a fact like `assert_fact`, reporting the contradictions it ran
+    /// into and the old facts retracted to resolve them
+    pub fn revise(&mut self, fact_str: &str) -> Result<Revision, RuntimeError> {
         let fact = self.parse_fact(fact_str)?;
-        self.knowledge_base.remove_fact(&fact);
-        Ok(())
+        self.add_fact(fact)
     }
-    
-    /// Add a rule to the knowledge base
-    pub fn add_rule(&mut self, rule_str: &str) -> Result<(), RuntimeError> {
-        let rule = self.parse_rule(rule_str)?;
-        self.knowledge_base.add_rule(rule);
-        Ok(())
+
+    /// Assert a fact that holds only during `valid`, e.g.
+    /// `assert_fact_at("at(robot, kitchen)", Validity::between(10, 20))`
+    pub fn assert_fact_at(&mut self, fact_str: &str, valid: Validity) -> Result<(), RuntimeError> {
+        let mut fact = self.parse_fact(fact_str)?;
+        if valid.is_empty() {
+            return Err(RuntimeError::LogicError(format!(
+                "{} would hold at no time: its validity {} is empty",
+                self.fact_to_string(&fact),
+                valid
+            )));
+        }
+        fact.valid = valid;
+        self.add_fact(fact).map(|_| ())
     }
-    
-    /// Solve a query with improved algorithm
-    pub fn solve_query(&mut self, query_str: &str) -> Result<Vec<HashMap<String, String>>, RuntimeError> {
-        self.queries_executed += 1;
 
-        let goals = self.parse_complex_query(query_str)?;
-        let mut results = Vec::new();
+    /// Check a fact against the ontology and the integrity constraints,
+    /// resolve the contradictions it runs into with the revision policy and
+    /// store it
+    fn add_fact(&mut self, fact: Fact) -> Result<Revision, RuntimeError> {
+        self.check_ontology(&fact)?;
+        let mut revision = Revision::default();
+        while let Some((contradiction, old_facts)) = self.find_contradiction(&fact)? {
+            match self.revision_policy.resolve(&contradiction) {
+                // Each pass retracts at least one fact, so the loop ends
+                Resolution::RetractOld if !old_facts.is_empty() => {
+                    for old in &old_facts {
+                        self.knowledge_mut().facts.remove(old)?;
+                        self.check_fact_watchpoints(old, FactChange::Retract)?;
+                    }
+                    revision.retracted.extend(contradiction.conflicting.iter().cloned());
+                    revision.contradictions.push(contradiction);
+                }
+                Resolution::KeepBoth => {
+                    revision.contradictions.push(contradiction);
+                    break;
+                }
+                Resolution::RejectNew | Resolution::RetractOld => {
+                    return Err(RuntimeError::Contradiction(contradiction));
+                }
+            }
+        }
+        self.check_fact_watchpoints(&fact, FactChange::Assert)?;
+        self.knowledge_mut().facts.add(fact)?;
+        Ok(revision)
+    }
 
-        // Use improved backtracking search with constraint propagation
-        let mut bindings = Bindings::new();
-        self.solve_goals_with_constraints(&goals, &mut bindings, &mut results, 0)?;
+    /// Add an integrity constraint: goals that must never all hold at once,
+    /// written `:- alive(X), dead(X)` (the `:-` may be left out). Facts
+    /// asserted from now on are checked against it
+    pub fn add_constraint(&mut self, constraint_str: &str) -> Result<(), RuntimeError> {
+        let body = constraint_str.trim().trim_start_matches(":-");
+        let goals = self.parse_complex_query(body)?;
+        if goals.iter().all(|goal| goal.negated) {
+            return Err(RuntimeError::LogicError(format!(
+                "constraint {} needs a goal that is not negated",
+                constraint_str.trim()
+            )));
+        }
+        self.push_constraint(goals);
+        Ok(())
+    }
 
-        // Convert internal bindings to string format
-        let string_results = results.into_iter()
-            .map(|binding| {
-                binding.into_iter()
-                    .map(|(k, v)| (k, self.term_to_string(&v)))
-                    .collect()
-            })
-            .collect();
+    fn push_constraint(&mut self, goals: Vec<Goal>) {
+        let goal_strings: Vec<String> = goals.iter().map(|goal| self.goal_to_string(goal)).collect();
+        self.constraints.push(IntegrityConstraint { text: format!(":- {}", goal_strings.join(", ")), goals });
+    }
 
-        Ok(string_results)
+    /// Choose how asserts that violate a constraint are resolved
+    pub fn set_revision_policy(&mut self, policy: RevisionPolicy) {
+        self.revision_policy = policy;
     }
 
-    /// Solve goals with constraint propagation for better performance
-    fn solve_goals_with_constraints(
-        &self,
-        goals: &[Goal],
-        bindings: &mut Bindings,
-        results: &mut Vec<Bindings>,
-        depth: usize,
-    ) -> Result<(), RuntimeError> {
-        if depth > self.max_depth {
-            return Err(RuntimeError::LogicError("Maximum search depth exceeded".to_string()));
+    /// The contradiction asserting `fact_str` would run into first, without
+    /// asserting it
+    pub fn contradiction(&mut self, fact_str: &str) -> Result<Option<Contradiction>, RuntimeError> {
+        let fact = self.parse_fact(fact_str)?;
+        Ok(self.find_contradiction(&fact)?.map(|(contradiction, _)| contradiction))
+    }
+
+    /// The first constraint that `fact` violates, with the old facts of its
+    /// minimal conflicting set. The fact is stored while the constraints are
+    /// solved and removed again after
+    fn find_contradiction(&mut self, fact: &Fact) -> Result<Option<(Contradiction, Vec<Fact>)>, RuntimeError> {
+        if self.constraints.is_empty() {
+            return Ok(None);
+        }
+        let stored = self
+            .knowledge_base
+            .facts
+            .matching(fact.predicate, &fact.args)?
+            .iter()
+            .any(|known| same_fact(known, fact));
+        if !stored {
+            self.knowledge_mut().facts.add(fact.clone())?;
         }
+        let found = self.violated_constraint(fact);
+        if !stored {
+            self.knowledge_mut().facts.remove(fact)?;
+        }
+        found
+    }
 
-        if goals.is_empty() {
-            results.push(bindings.clone());
-            return Ok(());
+    fn violated_constraint(&self, fact: &Fact) -> Result<Option<(Contradiction, Vec<Fact>)>, RuntimeError> {
+        for constraint in &self.constraints {
+            let Some(support) = self.violation(constraint, fact)? else {
+                continue;
+            };
+            let narrowed = self.narrow_conflict(constraint, fact, support);
+            self.suspended.borrow_mut().clear();
+            let conflict = narrowed?;
+            let contradiction = Contradiction {
+                fact: self.fact_to_string(fact),
+                constraint: constraint.text.clone(),
+                conflicting: conflict.iter().map(|old| self.fact_to_string(old)).collect(),
+            };
+            return Ok(Some((contradiction, conflict)));
         }
+        Ok(None)
+    }
 
-        // Apply constraint propagation before goal selection
-        let propagated_bindings = self.propagate_constraints(bindings, goals)?;
+    /// The old facts a proof that `fact` violates `constraint` rests on, or
+    /// `None` when no violation involves `fact`
+    fn violation(&self, constraint: &IntegrityConstraint, fact: &Fact) -> Result<Option<Vec<Fact>>, RuntimeError> {
+        Ok(self
+            .violations(constraint)?
+            .iter()
+            .map(support)
+            .find(|facts| facts.iter().any(|used| same_fact(used, fact)))
+            .map(|facts| facts.into_iter().filter(|used| !same_fact(used, fact)).collect()))
+    }
 
-        // Select the most constrained goal
-        let best_goal_index = self.select_most_constrained_goal(goals, &propagated_bindings);
-        let selected_goal = &goals[best_goal_index];
+    /// Every solution of `constraint`'s goals, each recording the facts it rests on
+    fn violations(&self, constraint: &IntegrityConstraint) -> Result<Vec<Bindings>, RuntimeError> {
+        self.begin_goal_stack(&constraint.text);
+        self.recording_support.set(true);
+        let mut solutions = Vec::new();
+        let solved = self.solve_goals_with_constraints(&constraint.goals, &mut Bindings::new(), &mut solutions, 0);
+        self.recording_support.set(false);
+        solved?;
+        Ok(solutions)
+    }
 
-        // Build remaining goals
-        let mut remaining_goals = Vec::new();
-        for (i, goal) in goals.iter().enumerate() {
-            if i != best_goal_index {
-                remaining_goals.push(goal.clone());
+    /// Store a batch of facts in one bulk pass, then check the facts that
+    /// were not stored before against the integrity constraints. The first
+    /// violation found removes those facts again and fails the batch
+    fn store_batch(&mut self, facts: Vec<Fact>, progress: impl FnMut(usize)) -> Result<(), RuntimeError> {
+        if self.constraints.is_empty() {
+            return self.knowledge_mut().facts.extend(facts, BULK_CHUNK_SIZE, progress);
+        }
+        let mut stored = HashSet::new();
+        let predicates: HashSet<Atom> = facts.iter().map(|fact| fact.predicate).collect();
+        for predicate in predicates {
+            for known in self.knowledge_base.facts.matching(predicate, &[])?.iter() {
+                stored.insert(self.fact_to_string(known));
             }
         }
+        let fresh: Vec<Fact> = facts
+            .iter()
+            .filter(|fact| !stored.contains(&self.fact_to_string(fact)))
+            .cloned()
+            .collect();
 
-        // Try to solve the selected goal
-        self.solve_single_goal(selected_goal, &remaining_goals, &propagated_bindings, results, depth)
-    }
-    
-    /// Solve a list of goals using backtracking
-    fn solve_goals(
-        &self,
-        goals: &[Goal],
-        bindings: &mut Bindings,
-        results: &mut Vec<Bindings>,
-        depth: usize,
-    ) -> Result<(), RuntimeError> {
-        if depth > self.max_depth {
-            return Err(RuntimeError::LogicError("Maximum search depth exceeded".to_string()));
-        }
-        
-        if goals.is_empty() {
-            // All goals satisfied, record the solution
-            results.push(bindings.clone());
+        self.knowledge_mut().facts.extend(facts, BULK_CHUNK_SIZE, progress)?;
+        let Some(contradiction) = self.batch_contradiction(&fresh)? else {
             return Ok(());
+        };
+        for fact in &fresh {
+            self.knowledge_mut().facts.remove(fact)?;
         }
-        
-        let goal = &goals[0];
-        let remaining_goals = &goals[1..];
-        
-        if goal.negated {
-            // Negation as failure
-            let mut temp_bindings = bindings.clone();
-            let mut temp_results = Vec::new();
-            
-            if self.solve_goals(&[Goal { negated: false, ..goal.clone() }], &mut temp_bindings, &mut temp_results, depth + 1).is_ok() && !temp_results.is_empty() {
-                // Goal succeeded, so negation fails
-                return Ok(());
-            } else {
-                // Goal failed, so negation succeeds
-                return self.solve_goals(remaining_goals, bindings, results, depth + 1);
-            }
-        }
-        
-        // Check built-in predicates first
-        if self.is_builtin_predicate(&goal.predicate) {
-            if self.solve_builtin_predicate(goal, bindings)? {
-                return self.solve_goals(remaining_goals, bindings, results, depth + 1);
-            } else {
-                return Ok(());
+        Err(RuntimeError::Contradiction(contradiction))
+    }
+
+    /// The first contradiction a stored fact of `batch` takes part in
+    fn batch_contradiction(&self, batch: &[Fact]) -> Result<Option<Contradiction>, RuntimeError> {
+        let batch: HashSet<String> = batch.iter().map(|fact| self.fact_to_string(fact)).collect();
+        for constraint in &self.constraints {
+            let offending = self
+                .violations(constraint)?
+                .iter()
+                .flat_map(support)
+                .find(|used| batch.contains(&self.fact_to_string(used)));
+            if let Some(fact) = offending {
+                return Ok(self.violated_constraint(&fact)?.map(|(contradiction, _)| contradiction));
             }
         }
-        
-        // Try to unify with facts
-        if let Some(facts) = self.knowledge_base.facts.get(&goal.predicate) {
-            for fact in facts {
-                let mut new_bindings = bindings.clone();
-                if self.unify_fact_goal(fact, goal, &mut new_bindings)? {
-                    self.solve_goals(remaining_goals, &mut new_bindings, results, depth + 1)?;
+        Ok(None)
+    }
+
+    /// Narrow the leaves of a violation's proof down to a minimal conflicting
+    /// set: suspend each fact in turn, and when the violation can still be
+    /// derived without it, go on with the facts of that other proof. The facts
+    /// found needed so far are in every proof that avoids the suspended ones
+    fn narrow_conflict(
+        &self,
+        constraint: &IntegrityConstraint,
+        fact: &Fact,
+        support: Vec<Fact>,
+    ) -> Result<Vec<Fact>, RuntimeError> {
+        let mut conflict = support;
+        let mut needed = 0;
+        while needed < conflict.len() {
+            self.suspended.borrow_mut().push(conflict[needed].clone());
+            match self.violation(constraint, fact)? {
+                Some(other) => {
+                    let rest: Vec<Fact> = other
+                        .into_iter()
+                        .filter(|used| !conflict[..needed].iter().any(|kept| same_fact(kept, used)))
+                        .collect();
+                    conflict.truncate(needed);
+                    conflict.extend(rest);
                 }
-            }
-        }
-        
-        // Try to unify with rule heads
-        if let Some(rules) = self.knowledge_base.rules.get(&goal.predicate) {
-            for rule in rules {
-                let mut new_bindings = bindings.clone();
-                let renamed_rule = self.rename_variables_in_rule(rule, depth);
-                
-                if self.unify_fact_goal(&renamed_rule.head, goal, &mut new_bindings)? {
-                    // Add rule body goals to the goal list
-                    let mut new_goals = renamed_rule.body;
-                    new_goals.extend_from_slice(remaining_goals);
-                    
-                    self.solve_goals(&new_goals, &mut new_bindings, results, depth + 1)?;
+                None => {
+                    self.suspended.borrow_mut().pop();
+                    needed += 1;
                 }
             }
         }
-        
-        Ok(())
+        Ok(conflict)
     }
-    
-    /// Unify a fact with a goal
-    fn unify_fact_goal(&self, fact: &Fact, goal: &Goal, bindings: &mut Bindings) -> Result<bool, RuntimeError> {
-        if fact.predicate != goal.predicate || fact.args.len() != goal.args.len() {
-            return Ok(false);
+
+    /// Note that the solution being built rests on the stored `fact`
+    fn record_support(&self, fact: &Fact, bindings: &mut Bindings) {
+        if !self.recording_support.get() {
+            return;
         }
-        
-        for (fact_arg, goal_arg) in fact.args.iter().zip(goal.args.iter()) {
-            if !self.unify_terms(fact_arg, goal_arg, bindings)? {
-                return Ok(false);
+        let used = Term::Compound(fact.predicate, fact.args.clone());
+        match bindings.entry(SUPPORT_KEY.to_string()).or_insert_with(|| Term::Compound(Atom::new(SUPPORT_KEY), Vec::new())) {
+            Term::Compound(_, facts) if !facts.contains(&used) => facts.push(used),
+            _ => {}
+        }
+    }
+
+    /// Note that the solution being built established `goal` by `step`, and
+    /// that the next `premises` goals it solves are what the step rests on
+    fn record_step(&self, goal: &Goal, step: ProofStep, premises: usize, bindings: &mut Bindings) {
+        if !self.recording_proofs.get() {
+            return;
+        }
+        let kind = match step {
+            ProofStep::Fact => "fact",
+            ProofStep::Ontology(_) => "ontology",
+            ProofStep::Rule(_) => "rule",
+            ProofStep::Builtin => "builtin",
+            ProofStep::Negation => "negation",
+            ProofStep::Aggregate => "aggregate",
+            ProofStep::Table => "table",
+            ProofStep::HoldsAt => "holds_at",
+        };
+        let source = match step {
+            ProofStep::Ontology(text) | ProofStep::Rule(text) => Term::String(text),
+            _ => Term::String(String::new()),
+        };
+        let recorded = Term::Compound(
+            Atom::new(kind),
+            vec![Term::Compound(goal.predicate, goal.args.clone()), source, Term::Integer(premises as i64)],
+        );
+        match bindings.entry(PROOF_KEY.to_string()).or_insert_with(|| Term::Compound(Atom::new(PROOF_KEY), Vec::new())) {
+            Term::Compound(_, steps) => steps.push(recorded),
+            _ => unreachable!("only proofs are recorded under PROOF_KEY"),
+        }
+    }
+
+    /// The derivation trees of the goals a solution recorded under `PROOF_KEY`
+    fn proof_trees(&self, bindings: &Bindings) -> Vec<ProofTree> {
+        let steps = match bindings.get(PROOF_KEY) {
+            Some(Term::Compound(_, steps)) => steps.as_slice(),
+            _ => &[],
+        };
+        let mut steps = steps.iter();
+        let mut trees = Vec::new();
+        while let Some(tree) = self.proof_tree(&mut steps, bindings) {
+            trees.push(tree);
+        }
+        trees
+    }
+
+    fn proof_tree<'a>(&self, steps: &mut impl Iterator<Item = &'a Term>, bindings: &Bindings) -> Option<ProofTree> {
+        let Term::Compound(kind, recorded) = steps.next()? else {
+            return None;
+        };
+        let [goal, Term::String(source), Term::Integer(premises)] = recorded.as_slice() else {
+            return None;
+        };
+        let step = match kind.as_str() {
+            "fact" => ProofStep::Fact,
+            "ontology" => ProofStep::Ontology(source.clone()),
+            "rule" => ProofStep::Rule(source.clone()),
+            "builtin" => ProofStep::Builtin,
+            "negation" => ProofStep::Negation,
+            "aggregate" => ProofStep::Aggregate,
+            "table" => ProofStep::Table,
+            _ => ProofStep::HoldsAt,
+        };
+        let goal = match (self.substitute(goal, bindings), &step) {
+            (Term::Compound(predicate, args), ProofStep::Negation) => {
+                self.goal_to_string(&Goal { predicate, args, negated: true })
             }
+            (goal, _) => self.term_to_string(&goal),
+        };
+        let premises = (0..*premises).filter_map(|_| self.proof_tree(steps, bindings)).collect();
+        Some(ProofTree { goal, step, premises })
+    }
+
+    /// Set the time point goals are solved at, or clear it with `None` so
+    /// that every fact holds again
+    pub fn set_time(&mut self, now: Option<i64>) {
+        self.time.set(now);
+    }
+
+    pub fn time(&self) -> Option<i64> {
+        self.time.get()
+    }
+
+    /// Remove the facts that no longer hold at `now` or any later time,
+    /// reporting each to the retract watchpoints. Returns how many were removed
+    pub fn collect_expired(&mut self, now: i64) -> Result<usize, RuntimeError> {
+        let expired = self.knowledge_mut().facts.remove_expired(now)?;
+        for fact in &expired {
+            self.check_fact_watchpoints(fact, FactChange::Retract)?;
         }
-        
-        Ok(true)
+        Ok(expired.len())
     }
-    
-    /// Unify two terms
-    fn unify_terms(&self, term1: &Term, term2: &Term, bindings: &mut Bindings) -> Result<bool, RuntimeError> {
-        let resolved_term1 = self.resolve_term(term1, bindings);
-        let resolved_term2 = self.resolve_term(term2, bindings);
-        
-        match (&resolved_term1, &resolved_term2) {
-            (Term::Variable(var), term) | (term, Term::Variable(var)) => {
-                if let Term::Variable(other_var) = term {
-                    if var == other_var {
+
+    /// Whether a stored fact holds at the time goals are solved at
+    fn holds(&self, fact: &Fact) -> bool {
+        self.time.get().map_or(true, |time| fact.valid.contains(time))
+            && !self.suspended.borrow().iter().any(|suspended| same_fact(suspended, fact))
+    }
+
+    /// Assert multiple facts at once for better performance
+    pub fn assert_facts(&mut self, fact_strings: &[&str]) -> Result<(), RuntimeError> {
+        for fact_str in fact_strings {
+            self.assert_fact(fact_str)?;
+        }
+        Ok(())
+    }
+
+    /// Declare the argument types of a relation ("int", "float", "string" or
+    /// "bool"; any other type name accepts every term) for bulk loads to check
+    pub fn declare_relation(&mut self, name: &str, arg_types: &[&str]) {
+        let knowledge = self.knowledge_mut();
+        knowledge.add_predicate(name, arg_types.len());
+        knowledge.relations.insert(
+            name.to_string(),
+            arg_types.iter().map(|ty| ty.trim().to_string()).collect(),
+        );
+    }
+
+    /// Table `predicate`: each call is solved once by fixpoint iteration,
+    /// calls met again while it runs taking the answers found so far, so
+    /// left-recursive rules terminate. An answer an instance of another is
+    /// not kept
+    pub fn table_predicate(&mut self, predicate: &str) {
+        self.knowledge_mut().tabled.insert(Atom::new(predicate));
+    }
+
+    /// Argument types a relation was declared with
+    pub fn relation_signature(&self, name: &str) -> Option<&[String]> {
+        self.knowledge_base.relations.get(name).map(Vec::as_slice)
+    }
+
+    /// Define a concept of the ontology below `parents`. Relations declared
+    /// with the concept as an argument type then only accept its individuals,
+    /// subconcepts and itself there
+    pub fn define_concept(&mut self, name: &str, parents: &[&str]) -> Result<(), RuntimeError> {
+        Arc::make_mut(&mut self.ontology)
+            .define(name, parents)
+            .map_err(|error| RuntimeError::LogicError(error.to_string()))?;
+        self.knowledge_mut().add_predicate(IS_A, 2);
+        Ok(())
+    }
+
+    pub fn ontology(&self) -> &Ontology {
+        &self.ontology
+    }
+
+    /// Whether `name` is `concept`, one of its subconcepts, or an individual
+    /// an `is_a` fact places below it
+    pub fn conforms(&self, name: &str, concept: &str) -> Result<bool, RuntimeError> {
+        if self.ontology.is_subconcept(name, concept) {
+            return Ok(true);
+        }
+        let concepts = self.direct_concepts(&Term::Atom(Atom::new(name)))?;
+        Ok(concepts.iter().any(|direct| self.ontology.is_subconcept(direct, concept)))
+    }
+
+    /// The concepts stored `is_a` facts give an individual
+    fn direct_concepts(&self, individual: &Term) -> Result<Vec<String>, RuntimeError> {
+        let facts = self.knowledge_base.facts.matching(Atom::new(IS_A), std::slice::from_ref(individual))?;
+        Ok(facts
+            .iter()
+            .filter(|fact| fact.args.first() == Some(individual))
+            .filter_map(|fact| match fact.args.get(1) {
+                Some(Term::Atom(concept)) => Some(concept.as_str().to_string()),
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn term_conforms(&self, term: &Term, concept: &str) -> Result<bool, RuntimeError> {
+        match term {
+            Term::Atom(atom) => self.conforms(atom.as_str(), concept),
+            Term::String(text) => self.conforms(text, concept),
+            Term::Variable(_) => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Check the arguments a fact has in concept-typed positions, and that an
+    /// `is_a` fact names a concept
+    fn check_ontology(&self, fact: &Fact) -> Result<(), RuntimeError> {
+        if self.ontology.is_empty() {
+            return Ok(());
+        }
+        let violation = |problem: String| {
+            RuntimeError::LogicError(format!("{} does not fit the ontology: {}", self.fact_to_string(fact), problem))
+        };
+        if fact.predicate.as_str() == IS_A {
+            return match fact.args.get(1) {
+                Some(Term::Atom(concept)) if self.ontology.is_concept(concept.as_str()) => Ok(()),
+                Some(other) => Err(violation(format!("{} is not a concept", self.term_to_string(other)))),
+                None => Err(violation("is_a takes an individual and a concept".to_string())),
+            };
+        }
+        let Some(types) = self.knowledge_base.relations.get(fact.predicate.as_str()) else {
+            return Ok(());
+        };
+        for (position, (concept, arg)) in types.iter().zip(&fact.args).enumerate() {
+            if self.ontology.is_concept(concept) && !self.term_conforms(arg, concept)? {
+                return Err(violation(format!(
+                    "argument {} is {}, which is not in concept {}",
+                    position + 1,
+                    self.term_to_string(arg),
+                    concept
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Facts the ontology implies for `goal` beyond the stored ones: `is_a`
+    /// facts placing an individual below the ancestors of its concepts, and
+    /// the facts of a relation whose first argument is concept-typed that are
+    /// stated about a concept, for each subconcept and individual below it.
+    /// Each comes with the stored fact it is implied from
+    fn inferred_facts(&self, goal: &Goal, bindings: &Bindings) -> Result<Vec<(Fact, Fact)>, RuntimeError> {
+        let first = goal.args.first().map(|arg| self.resolve_term(arg, bindings));
+        let first = first.filter(|term| !matches!(term, Term::Variable(_)));
+        let mut inferred: Vec<(Fact, Fact)> = Vec::new();
+
+        if goal.predicate.as_str() == IS_A {
+            let stored = self.knowledge_base.facts.matching(goal.predicate, first.as_slice())?;
+            let holding = stored.iter().filter(|fact| self.holds(fact));
+            for fact in holding.filter(|fact| first.is_none() || fact.args.first() == first.as_ref()) {
+                let [individual, Term::Atom(concept)] = fact.args.as_slice() else {
+                    continue;
+                };
+                for ancestor in self.ontology.ancestors(concept.as_str()) {
+                    let implied = Fact {
+                        predicate: goal.predicate,
+                        args: vec![individual.clone(), Term::Atom(Atom::new(&ancestor))],
+                        valid: fact.valid,
+                    };
+                    if !stored.contains(&implied) && !inferred.iter().any(|(known, _)| *known == implied) {
+                        inferred.push((implied, fact.clone()));
+                    }
+                }
+            }
+            return Ok(inferred);
+        }
+
+        let inherited = self
+            .knowledge_base
+            .relations
+            .get(goal.predicate.as_str())
+            .and_then(|types| types.first())
+            .is_some_and(|concept| self.ontology.is_concept(concept));
+        if !inherited {
+            return Ok(inferred);
+        }
+        let stored = self.knowledge_base.facts.matching(goal.predicate, &[])?;
+        for fact in stored.iter().filter(|fact| self.holds(fact)) {
+            let Some((Term::Atom(concept), rest)) = fact.args.split_first() else {
+                continue;
+            };
+            if !self.ontology.is_concept(concept.as_str()) {
+                continue;
+            }
+            let below = match &first {
+                Some(Term::Atom(name)) if name != concept && self.conforms(name.as_str(), concept.as_str())? => {
+                    vec![Term::Atom(*name)]
+                }
+                Some(_) => Vec::new(),
+                None => self.below(concept.as_str())?,
+            };
+            for subject in below {
+                let implied = Fact {
+                    predicate: goal.predicate,
+                    args: std::iter::once(subject).chain(rest.iter().cloned()).collect(),
+                    valid: fact.valid,
+                };
+                if !stored.contains(&implied) && !inferred.iter().any(|(known, _)| *known == implied) {
+                    inferred.push((implied, fact.clone()));
+                }
+            }
+        }
+        Ok(inferred)
+    }
+
+    /// The subconcepts of `concept` and the individuals below it
+    fn below(&self, concept: &str) -> Result<Vec<Term>, RuntimeError> {
+        let mut below: Vec<Term> = self
+            .ontology
+            .descendants(concept)
+            .iter()
+            .map(|name| Term::Atom(Atom::new(name)))
+            .collect();
+        for fact in self.knowledge_base.facts.matching(Atom::new(IS_A), &[])?.iter().filter(|fact| self.holds(fact)) {
+            if let [individual, Term::Atom(direct)] = fact.args.as_slice() {
+                if self.ontology.is_subconcept(direct.as_str(), concept) && !below.contains(individual) {
+                    below.push(individual.clone());
+                }
+            }
+        }
+        Ok(below)
+    }
+
+    /// Assert many facts of one relation in a single pass. Each row holds the
+    /// argument terms of one fact, e.g. `["\"ali\"", "42"]`. All rows are
+    /// validated against the declared relation first, so an invalid batch asserts
+    /// nothing and reports every bad row; valid rows are stored in chunks, calling
+    /// `progress(asserted, total)` after each. Returns the number of facts asserted.
+    pub fn assert_facts_bulk<I, F>(&mut self, relation: &str, rows: I, mut progress: F) -> Result<usize, RuntimeError>
+    where
+        I: IntoIterator<Item = Vec<String>>,
+        F: FnMut(usize, usize),
+    {
+        let signature = self.knowledge_base.relations.get(relation);
+        let mut facts = Vec::new();
+        let mut problems = Vec::new();
+
+        for (index, row) in rows.into_iter().enumerate() {
+            let args = row.iter()
+                .map(|cell| self.parse_term(cell))
+                .collect::<Result<Vec<_>, _>>()?;
+            match signature.map(|types| self.check_relation_args(types, &args)) {
+                Some(Err(problem)) => problems.push(format!("row {}: {}", index + 1, problem)),
+                _ => facts.push(Fact::new(Atom::new(relation), args)),
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(RuntimeError::LogicError(format!(
+                "{} invalid facts for relation {}: {}",
+                problems.len(),
+                relation,
+                problems.join("; ")
+            )));
+        }
+
+        let total = facts.len();
+        let watched = if self.watchpoints.is_empty() { Vec::new() } else { facts.clone() };
+        self.store_batch(facts, |asserted| progress(asserted, total))?;
+        for fact in &watched {
+            self.check_fact_watchpoints(fact, FactChange::Assert)?;
+        }
+        Ok(total)
+    }
+
+    /// Load the relations, constraints, facts and rules of a knowledge image
+    /// made at compile time (see `knowledge_image`), storing the facts in one
+    /// bulk pass. Returns the number of facts and rules loaded.
+    ///
+    /// Facts that violate a constraint, the image's or one added before, fail
+    /// the load with `RuntimeError::Contradiction` and are not stored.
+    pub fn load_image(&mut self, image: &[u8]) -> Result<usize, RuntimeError> {
+        let image = super::knowledge_image::decode(image)?;
+        self.load_declarations(image)
+    }
+
+    /// Load the relations, constraints, facts and rules of an analyzed
+    /// program, converting its terms directly rather than through their text.
+    /// Returns the number of facts and rules loaded, like `load_image`.
+    pub fn load_program(&mut self, program: &AnnotatedProgram) -> Result<usize, RuntimeError> {
+        let mut declarations = DecodedImage::default();
+        for item in &program.items {
+            match item {
+                AnnotatedItem::Concept(concept) => {
+                    declarations.concepts.push((concept.name.clone(), concept.parents.clone()));
+                }
+                AnnotatedItem::Relation(relation) => {
+                    // A concept-typed argument is checked against the ontology
+                    let arg_types = relation
+                        .arg_types
+                        .iter()
+                        .zip(&relation.arg_concepts)
+                        .map(|(ty, concept)| concept.clone().unwrap_or_else(|| relation_arg_type(ty).to_string()))
+                        .collect();
+                    declarations.relations.push((relation.name.clone(), arg_types, relation.tabled));
+                }
+                AnnotatedItem::Constraint(constraint) => {
+                    declarations.constraints.push(constraint.goals.iter().map(annotated_fact).collect());
+                }
+                AnnotatedItem::Fact(fact) => declarations.facts.push(annotated_fact(&fact.term)),
+                AnnotatedItem::Rule(rule) => declarations.rules.push((
+                    annotated_fact(&rule.head),
+                    rule.body.iter().map(|goal| (annotated_fact(goal), goal.negated)).collect(),
+                    !rule.no_reorder,
+                )),
+                _ => {}
+            }
+        }
+        self.load_declarations(declarations)
+    }
+
+    /// Load the relations, concepts, constraints, facts and rules among
+    /// parsed `items`, for the interpreter and the REPL, which run programs
+    /// without lowering them. Facts and rules go through their text; like
+    /// `load_program`, the facts are checked against the ontology once all
+    /// are stored. Returns the number of facts and rules loaded.
+    pub fn load_items<'i>(&mut self, items: impl IntoIterator<Item = &'i Item>) -> Result<usize, RuntimeError> {
+        let mut declarations = DecodedImage::default();
+        let mut rules = 0;
+        for item in items {
+            match item {
+                Item::Concept(concept) => declarations.concepts.push((concept.name.clone(), concept.parents.clone())),
+                Item::Relation(relation) => {
+                    // A concept-typed argument is checked against the ontology
+                    let arg_types = relation
+                        .arg_types
+                        .iter()
+                        .map(|ty| match ty {
+                            Type::Named(path) if path.segments.len() == 1 => path.segments[0].clone(),
+                            _ => "any".to_string(),
+                        })
+                        .collect();
+                    declarations.relations.push((relation.name.clone(), arg_types, relation.tabled));
+                }
+                Item::Constraint(constraint) => self.add_constraint(&constraint.to_string())?,
+                Item::Fact(fact) => declarations.facts.push(self.parse_fact(&fact.to_string())?),
+                Item::Rule(rule) => {
+                    self.add_rule(&rule.to_string())?;
+                    rules += 1;
+                }
+                _ => {}
+            }
+        }
+        Ok(self.load_declarations(declarations)? + rules)
+    }
+
+    fn load_declarations(&mut self, image: DecodedImage) -> Result<usize, RuntimeError> {
+        for (name, parents) in &image.concepts {
+            let parents: Vec<&str> = parents.iter().map(String::as_str).collect();
+            self.define_concept(name, &parents)?;
+        }
+        for (name, arg_types, tabled) in &image.relations {
+            let arg_types: Vec<&str> = arg_types.iter().map(String::as_str).collect();
+            self.declare_relation(name, &arg_types);
+            if *tabled {
+                self.table_predicate(name);
+            }
+        }
+        for goals in image.constraints {
+            self.push_constraint(goals.into_iter().map(Goal::from).collect());
+        }
+
+        let loaded = image.facts.len() + image.rules.len();
+        // Constraints may rest on rules, so the rules come first
+        for (head, goals, reorder) in image.rules {
+            let body = goals
+                .into_iter()
+                .map(|(goal, negated)| Goal { negated, ..Goal::from(goal) })
+                .collect();
+            self.knowledge_mut().add_rule(Rule { head, body, reorder });
+        }
+        // Facts are checked once all are stored, as `is_a` facts may follow their uses
+        let facts = if self.ontology.is_empty() { Vec::new() } else { image.facts.clone() };
+        self.store_batch(image.facts, |_| {})?;
+        for fact in &facts {
+            self.check_ontology(fact)?;
+        }
+        Ok(loaded)
+    }
+
+    /// Check one fact's arguments against a declared relation signature
+    fn check_relation_args(&self, types: &[String], args: &[Term]) -> Result<(), String> {
+        if types.len() != args.len() {
+            return Err(format!("expected {} arguments, found {}", types.len(), args.len()));
+        }
+        for (position, (ty, arg)) in types.iter().zip(args).enumerate() {
+            let matches = match (ty.as_str(), arg) {
+                ("int", Term::Integer(_)) => true,
+                ("float", Term::Float(_) | Term::Integer(_)) => true,
+                ("string", Term::String(_) | Term::Atom(_)) => true,
+                ("bool", Term::Atom(atom)) => *atom == "true" || *atom == "false",
+                ("int" | "float" | "string" | "bool", _) => false,
+                (concept, arg) if self.ontology.is_concept(concept) => {
+                    self.term_conforms(arg, concept).map_err(|error| error.to_string())?
+                }
+                _ => true,
+            };
+            if !matches {
+                return Err(format!(
+                    "argument {} is {} but should be {}",
+                    position + 1,
+                    self.term_to_string(arg),
+                    ty
+                ));
+            }
+        }
+        Ok(())
+    }
+    
+    /// Retract a fact from the knowledge base
+    pub fn retract_fact(&mut self, fact_str: &str) -> Result<(), RuntimeError> {
+        let fact = self.parse_fact(fact_str)?;
+        self.remove_fact(fact)
+    }
+
+    /// Retract `relation(values...)`
+    pub fn retract_values(&mut self, relation: &str, values: &[FactValue]) -> Result<(), RuntimeError> {
+        self.remove_fact(Fact::new(Atom::new(relation), values.iter().map(FactValue::to_term).collect()))
+    }
+
+    fn remove_fact(&mut self, fact: Fact) -> Result<(), RuntimeError> {
+        self.knowledge_mut().facts.remove(&fact)?;
+        self.check_fact_watchpoints(&fact, FactChange::Retract)
+    }
+    
+    /// Add a rule to the knowledge base
+    pub fn add_rule(&mut self, rule_str: &str) -> Result<(), RuntimeError> {
+        let rule = self.parse_rule(rule_str)?;
+        self.knowledge_mut().add_rule(rule);
+        Ok(())
+    }
+    
+    /// Break when a fact unifying with `pattern` (e.g. `parent(X, "ali")`)
+    /// changes in one of the ways `changes` lists
+    pub fn watch_fact(&mut self, pattern: &str, changes: &[FactChange]) -> Result<WatchId, RuntimeError> {
+        if changes.is_empty() {
+            return Err(RuntimeError::LogicError(format!("watchpoint on {} watches no change", pattern.trim())));
+        }
+        let pattern = self.parse_fact(pattern)?;
+        Ok(self.add_watchpoint(WatchTarget::Fact { pattern, changes: changes.to_vec() }))
+    }
+
+    /// Break each time a rule whose head is `predicate` fires
+    pub fn watch_rule(&mut self, predicate: &str) -> WatchId {
+        self.add_watchpoint(WatchTarget::Rule(Atom::new(predicate.trim())))
+    }
+
+    fn add_watchpoint(&mut self, target: WatchTarget) -> WatchId {
+        self.last_watch_id += 1;
+        self.watchpoints.push((self.last_watch_id, target));
+        self.last_watch_id
+    }
+
+    /// Remove a watchpoint; false when there is none with this id
+    pub fn remove_watchpoint(&mut self, id: WatchId) -> bool {
+        let count = self.watchpoints.len();
+        self.watchpoints.retain(|(watch_id, _)| *watch_id != id);
+        self.watchpoints.len() != count
+    }
+
+    /// The watchpoints in the order they were set
+    pub fn watchpoints(&self) -> Vec<Watchpoint> {
+        self.watchpoints
+            .iter()
+            .map(|(id, target)| {
+                let description = match target {
+                    WatchTarget::Fact { pattern, changes } => {
+                        let changes: Vec<&str> = changes
+                            .iter()
+                            .map(|change| match change {
+                                FactChange::Assert => "assert",
+                                FactChange::Retract => "retract",
+                            })
+                            .collect();
+                        format!("{} of {}", changes.join("/"), self.fact_to_string(pattern))
+                    }
+                    WatchTarget::Rule(predicate) => format!("rule {} fires", predicate),
+                };
+                Watchpoint { id: *id, description }
+            })
+            .collect()
+    }
+
+    /// Take the watchpoint hits recorded since the last call, oldest first
+    pub fn take_watch_hits(&mut self) -> Vec<WatchHit> {
+        std::mem::take(self.watch_hits.get_mut())
+    }
+
+    /// Record a hit of every fact watchpoint that `change` of `fact` matches
+    fn check_fact_watchpoints(&self, fact: &Fact, change: FactChange) -> Result<(), RuntimeError> {
+        for (id, target) in &self.watchpoints {
+            let WatchTarget::Fact { pattern, changes } = target else {
+                continue;
+            };
+            if changes.contains(&change) && self.fact_matches(pattern, fact)? {
+                self.watch_hits.borrow_mut().push(WatchHit {
+                    watchpoint: *id,
+                    event: change.into(),
+                    subject: self.fact_to_string(fact),
+                    goal_stack: Vec::new(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn fact_matches(&self, pattern: &Fact, fact: &Fact) -> Result<bool, RuntimeError> {
+        if pattern.predicate != fact.predicate || pattern.args.len() != fact.args.len() {
+            return Ok(false);
+        }
+        let mut bindings = Bindings::new();
+        for (pattern_arg, fact_arg) in pattern.args.iter().zip(&fact.args) {
+            if !self.unify_terms(pattern_arg, fact_arg, &mut bindings)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Whether the solver keeps a goal stack, which only rule watchpoints need
+    fn watches_rules(&self) -> bool {
+        self.watchpoints.iter().any(|(_, target)| matches!(target, WatchTarget::Rule(_)))
+    }
+
+    /// Note that a rule fired for `goal`, recording the hits of the watchpoints
+    /// on its predicate, and enter its body on the goal stack. `goals_left`
+    /// counts the goals that remain once the body is done.
+    fn enter_rule(&self, goal: &Goal, bindings: &Bindings, goals_left: usize) {
+        let resolved = Goal {
+            args: goal.args.iter().map(|arg| self.resolve_term(arg, bindings)).collect(),
+            ..goal.clone()
+        };
+        let subject = self.goal_to_string(&resolved);
+        for (id, target) in &self.watchpoints {
+            if matches!(target, WatchTarget::Rule(predicate) if *predicate == goal.predicate) {
+                let goal_stack = std::iter::once(subject.clone())
+                    .chain(self.goal_stack(goals_left + 1))
+                    .collect();
+                self.watch_hits.borrow_mut().push(WatchHit {
+                    watchpoint: *id,
+                    event: WatchEvent::RuleFired,
+                    subject: subject.clone(),
+                    goal_stack,
+                });
+            }
+        }
+        self.goal_frames.borrow_mut().push((subject, goals_left));
+    }
+
+    /// Goals whose rule bodies are still being solved when `goal_count` goals
+    /// are left, innermost first. A rule's body is done once no more goals
+    /// are left than were left after it.
+    fn goal_stack(&self, goal_count: usize) -> Vec<String> {
+        self.goal_frames
+            .borrow()
+            .iter()
+            .rev()
+            .filter(|(_, goals_left)| *goals_left < goal_count)
+            .map(|(goal, _)| goal.clone())
+            .collect()
+    }
+
+    /// Trace the goals of later queries with `tracer`, or stop tracing
+    pub fn set_tracer(&mut self, tracer: Option<LogicTracer>) {
+        self.tracer = tracer;
+    }
+
+    fn trace(&self, port: TracePort, depth: usize, goal: String) {
+        if let Some(tracer) = &self.tracer {
+            tracer.record(TraceEvent { port, depth, goal });
+        }
+    }
+
+    /// Trace the call of `goal`, which has a solution once `goals_left` goals are left
+    fn trace_call(&self, goal: &Goal, goals_left: usize, bindings: &Bindings) {
+        let Some(tracer) = &self.tracer else {
+            return;
+        };
+        let mut frames = self.trace_frames.borrow_mut();
+        let depth = self.trace_base.get() + frames.iter().filter(|frame| !frame.exited).count() + 1;
+        let called = self.resolved_goal_string(goal, bindings);
+        let shown = tracer.filter().accepts(goal.predicate.as_str(), depth);
+        if shown {
+            self.trace(TracePort::Call, depth, called.clone());
+        }
+        frames.push(TraceFrame { goal: goal.clone(), called, depth, goals_left, shown, exited: false });
+    }
+
+    /// Trace the failure of the goal of the innermost frame, which has no more solutions
+    fn trace_fail(&self) {
+        let frame = self.trace_frames.borrow_mut().pop();
+        if let Some(frame) = frame.filter(|frame| frame.shown) {
+            self.trace(TracePort::Fail, frame.depth, frame.called);
+        }
+    }
+
+    /// Trace the exit of every goal that has a solution once `goals_left`
+    /// goals are left, innermost first; returns their frames
+    fn trace_exits(&self, goals_left: usize, bindings: &Bindings) -> Vec<usize> {
+        if self.tracer.is_none() {
+            return Vec::new();
+        }
+        let mut frames = self.trace_frames.borrow_mut();
+        let mut exited = Vec::new();
+        for (index, frame) in frames.iter_mut().enumerate().rev() {
+            if frame.exited {
+                continue;
+            }
+            if frame.goals_left < goals_left {
+                break;
+            }
+            frame.exited = true;
+            exited.push(index);
+            if frame.shown {
+                self.trace(TracePort::Exit, frame.depth, self.resolved_goal_string(&frame.goal, bindings));
+            }
+        }
+        exited
+    }
+
+    /// Trace the search backtracking into the goals `trace_exits` returned,
+    /// outermost first
+    fn trace_redos(&self, exited: &[usize]) {
+        let mut frames = self.trace_frames.borrow_mut();
+        for &index in exited.iter().rev() {
+            let frame = &mut frames[index];
+            frame.exited = false;
+            if frame.shown {
+                self.trace(TracePort::Redo, frame.depth, frame.called.clone());
+            }
+        }
+    }
+
+    fn resolved_goal_string(&self, goal: &Goal, bindings: &Bindings) -> String {
+        let resolved = Goal {
+            args: goal.args.iter().map(|arg| self.resolve_term(arg, bindings)).collect(),
+            ..goal.clone()
+        };
+        self.goal_to_string(&resolved)
+    }
+
+    /// Start the goal stack and the trace of a query
+    fn begin_goal_stack(&self, query_str: &str) {
+        self.trace_frames.borrow_mut().clear();
+        self.trace_base.set(0);
+        self.cut_fired.set(None);
+        // Facts may have changed since the last query
+        self.tables.borrow_mut().clear();
+        self.table_round.set(0);
+        self.tabling.set(false);
+        let mut frames = self.goal_frames.borrow_mut();
+        frames.clear();
+        if self.watches_rules() {
+            frames.push((format!("?- {}", query_str.trim().trim_end_matches('.')), 0));
+        }
+    }
+
+    /// Solve a query with improved algorithm
+    pub fn solve_query(&mut self, query_str: &str) -> Result<Vec<HashMap<String, String>>, RuntimeError> {
+        let started = Instant::now();
+        let result = self.run_query(query_str);
+        crate::metrics::record_query(started.elapsed(), result.is_ok());
+        result
+    }
+
+    /// Solve a query, recording for each solution the derivation tree of
+    /// every goal of the query: which facts, rules and built-ins established
+    /// it, for `albayan query --explain` and for checking a rule base
+    pub fn solve_query_with_proofs(&mut self, query_str: &str) -> Result<Vec<Solution>, RuntimeError> {
+        let started = Instant::now();
+        self.recording_proofs.set(true);
+        let solved = self.solve_bindings(query_str);
+        self.recording_proofs.set(false);
+        crate::metrics::record_query(started.elapsed(), solved.is_ok());
+
+        let (goals, results) = solved?;
+        Ok(results
+            .iter()
+            .map(|binding| Solution {
+                bindings: self.query_bindings(&goals, binding).into_iter().collect(),
+                proof: self.proof_trees(binding),
+            })
+            .collect())
+    }
+
+    fn run_query(&mut self, query_str: &str) -> Result<Vec<HashMap<String, String>>, RuntimeError> {
+        let (goals, results) = self.solve_bindings(query_str)?;
+        Ok(results.iter().map(|binding| self.query_bindings(&goals, binding)).collect())
+    }
+
+    /// The planned goals of a query and the bindings of each of its solutions
+    fn solve_bindings(&mut self, query_str: &str) -> Result<(Vec<Goal>, Vec<Bindings>), RuntimeError> {
+        self.queries_executed += 1;
+        let started = Instant::now();
+
+        let goals = self.planned_query(query_str)?;
+        let mut results = Vec::new();
+        self.begin_goal_stack(query_str);
+
+        // Use improved backtracking search with constraint propagation
+        let mut bindings = Bindings::new();
+        self.solve_goals_with_constraints(&goals, &mut bindings, &mut results, 0)?;
+        self.record_query(query_str, started.elapsed(), results.len());
+        Ok((goals, results))
+    }
+
+    /// Convert internal bindings to string format, keeping only the query's own
+    /// variables (rule variables are renamed apart and resolved through the chain)
+    fn query_bindings(&self, goals: &[Goal], binding: &Bindings) -> HashMap<String, String> {
+        let query_vars: HashSet<String> = goals.iter().flat_map(goal_variables).collect();
+        query_vars.iter()
+            .filter(|var| binding.contains_key(*var))
+            .map(|var| {
+                let value = self.substitute(&Term::Variable(var.clone()), binding);
+                (var.clone(), self.term_to_string(&value))
+            })
+            .collect()
+    }
+
+    /// Explain a query: the order the solver takes its goals in, how each goal
+    /// reaches its facts and the counts it is planned with. The query is then
+    /// executed step by step, recording the rows each join step produced.
+    pub fn explain_query(&mut self, query_str: &str) -> Result<QueryPlan, RuntimeError> {
+        self.queries_executed += 1;
+        let started = Instant::now();
+
+        let goals = self.planned_query(query_str)?;
+        self.begin_goal_stack(query_str);
+
+        let mut steps = Vec::new();
+        let mut bound = HashSet::new();
+        let mut prefix = Vec::new();
+        for goal in &goals {
+            prefix.push(goal.clone());
+
+            let mut results = Vec::new();
+            self.cut_fired.set(None);
+            self.solve_goals_with_constraints(&prefix, &mut Bindings::new(), &mut results, 0)?;
+
+            steps.push(PlanStep {
+                goal: self.goal_to_string(goal),
+                access: self.goal_access(goal, &bound),
+                estimated_facts: self.knowledge_base.facts.count(goal.predicate)?,
+                estimated_rules: self.knowledge_base.rules.get(&goal.predicate).map_or(0, Vec::len),
+                actual_rows: results.len(),
+            });
+            if !goal.negated {
+                bound.extend(goal_variables(goal));
+            }
+        }
+
+        let solutions = steps.last().map_or(0, |step| step.actual_rows);
+        let elapsed = started.elapsed();
+        self.record_query(query_str, elapsed, solutions);
+
+        Ok(QueryPlan {
+            query: query_str.trim().trim_end_matches('.').to_string(),
+            backend: self.storage_backend(),
+            steps,
+            solutions,
+            elapsed,
+        })
+    }
+
+    /// Queries of this session ordered from the slowest single execution down
+    pub fn slowest_queries(&self, limit: usize) -> Vec<&QueryStats> {
+        let mut stats: Vec<&QueryStats> = self.query_stats.values().collect();
+        stats.sort_by(|a, b| b.slowest_time.cmp(&a.slowest_time));
+        stats.truncate(limit);
+        stats
+    }
+
+    /// Forget the timings collected so far
+    pub fn reset_query_stats(&mut self) {
+        self.query_stats.clear();
+    }
+
+    fn record_query(&mut self, query_str: &str, elapsed: Duration, solutions: usize) {
+        let query = query_str.trim().trim_end_matches('.');
+        self.query_stats
+            .entry(query.to_string())
+            .or_insert_with(|| QueryStats::new(query))
+            .record(elapsed, solutions);
+    }
+
+    /// Parse a query and order its goals for execution; a leading
+    /// `#[no_reorder]` keeps the written order
+    fn planned_query(&self, query_str: &str) -> Result<Vec<Goal>, RuntimeError> {
+        let (reorder, query_str) = strip_no_reorder(query_str);
+        let goals = self.parse_complex_query(query_str)?;
+        Ok(if reorder { self.order_goals(goals, &Bindings::new()) } else { goals })
+    }
+
+    /// Put a conjunction in the order chosen by `plan_goal_order`
+    fn order_goals(&self, goals: Vec<Goal>, bindings: &Bindings) -> Vec<Goal> {
+        // What a cut prunes, and what an aggregate collects, depends on the goals before it
+        if goals.iter().any(|goal| goal.is_cut() || aggregate_goal(goal).is_some()) {
+            return goals;
+        }
+        let order = self.plan_goal_order(&goals, bindings);
+        let mut goals: Vec<Option<Goal>> = goals.into_iter().map(Some).collect();
+        order.into_iter().filter_map(|index| goals[index].take()).collect()
+    }
+
+    /// Cost-based order for a conjunction: repeatedly take the goal with the
+    /// fewest estimated solutions given the variables bound so far. Built-ins
+    /// and negations wait until their arguments are bound and then run as
+    /// early as possible; when no goal is ready the written order wins.
+    fn plan_goal_order(&self, goals: &[Goal], bindings: &Bindings) -> Vec<usize> {
+        let mut remaining: Vec<usize> = (0..goals.len()).collect();
+        let mut bound: HashSet<String> = HashSet::new();
+        let mut order = Vec::with_capacity(goals.len());
+
+        while !remaining.is_empty() {
+            let is_bound = |var: &str| {
+                bound.contains(var)
+                    || !matches!(self.resolve_term(&Term::Variable(var.to_string()), bindings), Term::Variable(_))
+            };
+            let position = remaining
+                .iter()
+                .enumerate()
+                .filter(|&(_, &index)| self.goal_ready(&goals[index], &is_bound))
+                .map(|(position, &index)| (position, self.estimate_goal_rows(&goals[index], &is_bound)))
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map_or(0, |(position, _)| position);
+
+            let index = remaining.remove(position);
+            if !goals[index].negated {
+                bound.extend(goal_variables(&goals[index]));
+            }
+            order.push(index);
+        }
+
+        order
+    }
+
+    /// Whether a goal can run once the variables accepted by `is_bound` have values
+    fn goal_ready(&self, goal: &Goal, is_bound: &dyn Fn(&str) -> bool) -> bool {
+        let all_bound = |args: &[Term]| args.iter().all(|arg| term_is_bound(arg, is_bound));
+        match goal.predicate.as_str() {
+            _ if goal.negated => all_bound(&goal.args),
+            "=" => true,
+            "is" => all_bound(goal.args.get(1..).unwrap_or(&[])),
+            HOLDS_AT => all_bound(goal.args.get(1..).unwrap_or(&[])),
+            _ if self.is_builtin_predicate(goal.predicate) => all_bound(&goal.args),
+            _ => true,
+        }
+    }
+
+    /// Estimated solutions of a goal: the facts and rules of its predicate,
+    /// narrowed by every argument whose value is known
+    fn estimate_goal_rows(&self, goal: &Goal, is_bound: &dyn Fn(&str) -> bool) -> f64 {
+        if goal.negated || self.is_builtin_predicate(goal.predicate) {
+            return 0.0;
+        }
+        if let Some(held) = held_goal(goal) {
+            return self.estimate_goal_rows(&held, is_bound);
+        }
+        let facts = self.knowledge_base.facts.count(goal.predicate).unwrap_or(0) as f64;
+        let rules = self.knowledge_base.rules.get(&goal.predicate).map_or(0, Vec::len) as f64;
+        let bound_args = goal.args.iter().filter(|arg| term_is_bound(arg, is_bound)).count();
+        (facts + rules * RULE_ROWS_ESTIMATE) * BOUND_ARG_SELECTIVITY.powi(bound_args as i32)
+    }
+
+    /// How a goal reaches its facts once the variables in `bound` have values
+    fn goal_access(&self, goal: &Goal, bound: &HashSet<String>) -> GoalAccess {
+        if self.is_builtin_predicate(goal.predicate) {
+            return GoalAccess::Builtin;
+        }
+        if let Some(held) = held_goal(goal) {
+            return self.goal_access(&held, bound);
+        }
+        let known = |arg: &Term| match arg {
+            Term::Variable(var) => bound.contains(var),
+            _ => true,
+        };
+        let indexed = goal.args.iter().enumerate().find(|(argument, arg)| {
+            known(arg) && self.knowledge_base.facts.indexes_argument(goal.predicate, *argument)
+        });
+        match indexed {
+            Some((0, _)) => GoalAccess::IndexLookup,
+            Some((argument, _)) => GoalAccess::ArgumentIndex(argument),
+            None => GoalAccess::Scan,
+        }
+    }
+
+    fn fact_to_string(&self, fact: &Fact) -> String {
+        let args: Vec<String> = fact.args.iter().map(|arg| self.term_to_string(arg)).collect();
+        format!("{}({})", fact.predicate, args.join(", "))
+    }
+
+    fn rule_to_string(&self, rule: &Rule) -> String {
+        let body: Vec<String> = rule.body.iter().map(|goal| self.goal_to_string(goal)).collect();
+        format!("{} :- {}", self.fact_to_string(&rule.head), body.join(", "))
+    }
+
+    fn goal_to_string(&self, goal: &Goal) -> String {
+        if goal.is_cut() {
+            return CUT.to_string();
+        }
+        let args: Vec<String> = goal.args.iter().map(|arg| self.term_to_string(arg)).collect();
+        let negation = if goal.negated { "not " } else { "" };
+        format!("{}{}({})", negation, goal.predicate, args.join(", "))
+    }
+
+    /// Solve goals with constraint propagation for better performance
+    fn solve_goals_with_constraints(
+        &self,
+        goals: &[Goal],
+        bindings: &mut Bindings,
+        results: &mut Vec<Bindings>,
+        depth: usize,
+    ) -> Result<(), RuntimeError> {
+        if depth > self.max_depth {
+            return Err(RuntimeError::LogicError("Maximum search depth exceeded".to_string()));
+        }
+
+        // Goals with a solution once only `goals` are left exit here, and are
+        // redone when the search backtracks past this point
+        let exited = self.trace_exits(goals.len(), bindings);
+        let solved = self.solve_next_goal(goals, bindings, results, depth);
+        self.trace_redos(&exited);
+        solved
+    }
+
+    fn solve_next_goal(
+        &self,
+        goals: &[Goal],
+        bindings: &Bindings,
+        results: &mut Vec<Bindings>,
+        depth: usize,
+    ) -> Result<(), RuntimeError> {
+        if goals.is_empty() {
+            results.push(bindings.clone());
+            return Ok(());
+        }
+
+        // Apply constraint propagation before goal selection
+        let propagated_bindings = self.propagate_constraints(bindings, goals)?;
+
+        // Goals arrive in the order planned by the optimizer
+        self.solve_single_goal(&goals[0], &goals[1..], &propagated_bindings, results, depth)
+    }
+    
+    /// Solve a list of goals using backtracking
+    fn solve_goals(
+        &self,
+        goals: &[Goal],
+        bindings: &mut Bindings,
+        results: &mut Vec<Bindings>,
+        depth: usize,
+    ) -> Result<(), RuntimeError> {
+        if depth > self.max_depth {
+            return Err(RuntimeError::LogicError("Maximum search depth exceeded".to_string()));
+        }
+        
+        if goals.is_empty() {
+            // All goals satisfied, record the solution
+            results.push(bindings.clone());
+            return Ok(());
+        }
+        
+        let goal = &goals[0];
+        let remaining_goals = &goals[1..];
+        
+        if goal.negated {
+            // Negation as failure
+            let mut temp_bindings = bindings.clone();
+            let mut temp_results = Vec::new();
+            
+            if self.solve_goals(&[Goal { negated: false, ..goal.clone() }], &mut temp_bindings, &mut temp_results, depth + 1).is_ok() && !temp_results.is_empty() {
+                // Goal succeeded, so negation fails
+                return Ok(());
+            } else {
+                // Goal failed, so negation succeeds
+                return self.solve_goals(remaining_goals, bindings, results, depth + 1);
+            }
+        }
+        
+        // Check built-in predicates first
+        if self.is_builtin_predicate(goal.predicate) {
+            if self.solve_builtin_predicate(goal, bindings)? {
+                return self.solve_goals(remaining_goals, bindings, results, depth + 1);
+            } else {
+                return Ok(());
+            }
+        }
+        
+        // Try to unify with facts
+        let args: Vec<Term> = goal.args.iter().map(|arg| self.resolve_term(arg, bindings)).collect();
+        for fact in self.knowledge_base.facts.matching(goal.predicate, &args)?.iter() {
+            let mut new_bindings = bindings.clone();
+            if self.unify_fact_goal(fact, goal, &mut new_bindings)? {
+                self.solve_goals(remaining_goals, &mut new_bindings, results, depth + 1)?;
+            }
+        }
+        
+        // Try to unify with rule heads
+        if let Some(rules) = self.knowledge_base.rules.get(&goal.predicate) {
+            for rule in rules {
+                let mut new_bindings = bindings.clone();
+                let renamed_rule = self.rename_variables_in_rule(rule, depth);
+                
+                if self.unify_fact_goal(&renamed_rule.head, goal, &mut new_bindings)? {
+                    // Add rule body goals to the goal list
+                    let mut new_goals = renamed_rule.body;
+                    new_goals.extend_from_slice(remaining_goals);
+                    
+                    self.solve_goals(&new_goals, &mut new_bindings, results, depth + 1)?;
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Unify a fact with a goal
+    fn unify_fact_goal(&self, fact: &Fact, goal: &Goal, bindings: &mut Bindings) -> Result<bool, RuntimeError> {
+        if fact.predicate != goal.predicate || fact.args.len() != goal.args.len() {
+            return Ok(false);
+        }
+        
+        for (fact_arg, goal_arg) in fact.args.iter().zip(goal.args.iter()) {
+            if !self.unify_terms(fact_arg, goal_arg, bindings)? {
+                return Ok(false);
+            }
+        }
+        
+        Ok(true)
+    }
+    
+    /// Unify two terms
+    fn unify_terms(&self, term1: &Term, term2: &Term, bindings: &mut Bindings) -> Result<bool, RuntimeError> {
+        let resolved_term1 = self.resolve_term(term1, bindings);
+        let resolved_term2 = self.resolve_term(term2, bindings);
+        
+        match (&resolved_term1, &resolved_term2) {
+            (Term::Variable(var), term) | (term, Term::Variable(var)) => {
+                if let Term::Variable(other_var) = term {
+                    if var == other_var {
                         return Ok(true);
                     }
                 }
                 
                 // Check for occurs check (variable occurs in term)
-                if self.occurs_check(var, term) {
+                if self.occurs_check(var, term, bindings) {
                     return Ok(false);
                 }
                 
@@ -351,11 +1843,23 @@ impl LogicEngine {
         }
     }
     
-    /// Occurs check to prevent infinite structures
-    fn occurs_check(&self, var: &str, term: &Term) -> bool {
-        match term {
+    /// Substitute the bindings into a term all the way down, so compound
+    /// answers carry the values of the variables inside them
+    fn substitute(&self, term: &Term, bindings: &Bindings) -> Term {
+        match self.resolve_term(term, bindings) {
+            Term::Compound(name, args) => {
+                Term::Compound(name, args.iter().map(|arg| self.substitute(arg, bindings)).collect())
+            }
+            resolved => resolved,
+        }
+    }
+
+    /// Occurs check to prevent infinite structures; variables inside `term`
+    /// are followed through their bindings, so `X = f(Y), Y = g(X)` fails
+    fn occurs_check(&self, var: &str, term: &Term, bindings: &Bindings) -> bool {
+        match self.resolve_term(term, bindings) {
             Term::Variable(other_var) => var == other_var,
-            Term::Compound(_, args) => args.iter().any(|arg| self.occurs_check(var, arg)),
+            Term::Compound(_, args) => args.iter().any(|arg| self.occurs_check(var, arg, bindings)),
             _ => false,
         }
     }
@@ -369,23 +1873,25 @@ impl LogicEngine {
             body: rule.body.iter()
                 .map(|goal| self.rename_variables_in_goal(goal, &mut var_mapping, suffix))
                 .collect(),
+            reorder: rule.reorder,
         }
     }
     
     /// Rename variables in a fact
     fn rename_variables_in_fact(&self, fact: &Fact, var_mapping: &mut HashMap<String, String>, suffix: usize) -> Fact {
         Fact {
-            predicate: fact.predicate.clone(),
+            predicate: fact.predicate,
             args: fact.args.iter()
                 .map(|arg| self.rename_variables_in_term(arg, var_mapping, suffix))
                 .collect(),
+            valid: fact.valid,
         }
     }
     
     /// Rename variables in a goal
     fn rename_variables_in_goal(&self, goal: &Goal, var_mapping: &mut HashMap<String, String>, suffix: usize) -> Goal {
         Goal {
-            predicate: goal.predicate.clone(),
+            predicate: goal.predicate,
             args: goal.args.iter()
                 .map(|arg| self.rename_variables_in_term(arg, var_mapping, suffix))
                 .collect(),
@@ -404,7 +1910,7 @@ impl LogicEngine {
             }
             Term::Compound(name, args) => {
                 Term::Compound(
-                    name.clone(),
+                    *name,
                     args.iter()
                         .map(|arg| self.rename_variables_in_term(arg, var_mapping, suffix))
                         .collect(),
@@ -415,8 +1921,8 @@ impl LogicEngine {
     }
     
     /// Check if a predicate is built-in
-    fn is_builtin_predicate(&self, predicate: &str) -> bool {
-        matches!(predicate, "=" | "<" | ">" | "<=" | ">=" | "is")
+    fn is_builtin_predicate(&self, predicate: Atom) -> bool {
+        matches!(predicate.as_str(), "=" | "!=" | "<" | ">" | "<=" | ">=" | "is" | "before" | "after")
     }
     
     /// Solve a built-in predicate
@@ -430,12 +1936,18 @@ impl LogicEngine {
         
         match goal.predicate.as_str() {
             "=" => self.unify_terms(&arg1, &arg2, bindings),
+            "!=" => Ok(!self.unify_terms(&arg1, &arg2, &mut bindings.clone())?),
             "<" | ">" | "<=" | ">=" => {
-                self.compare_terms(&arg1, &arg2, &goal.predicate)
+                let left = self.evaluate_arithmetic(&arg1, bindings)?;
+                let right = self.evaluate_arithmetic(&arg2, bindings)?;
+                self.compare_terms(&left, &right, goal.predicate.as_str())
             }
+            // Time points are ordered like numbers
+            "before" => self.compare_terms(&arg1, &arg2, "<"),
+            "after" => self.compare_terms(&arg1, &arg2, ">"),
             "is" => {
                 // Arithmetic evaluation
-                if let Ok(value) = self.evaluate_arithmetic(&arg2) {
+                if let Ok(value) = self.evaluate_arithmetic(&arg2, bindings) {
                     self.unify_terms(&arg1, &value, bindings)
                 } else {
                     Ok(false)
@@ -445,6 +1957,40 @@ impl LogicEngine {
         }
     }
     
+    /// What an aggregate makes of the values its template takes over the
+    /// solutions of its goal
+    fn aggregate(&self, aggregate: &str, values: Vec<Term>) -> Result<Option<Term>, RuntimeError> {
+        match aggregate {
+            "findall" => Ok(Some(list_term(values, Term::Atom(Atom::new(EMPTY_LIST))))),
+            "count" => Ok(Some(Term::Integer(values.len() as i64))),
+            "sum_of" => {
+                let mut total = Term::Integer(0);
+                for value in values {
+                    total = match (total, value) {
+                        (Term::Integer(sum), Term::Integer(value)) => Term::Integer(sum + value),
+                        (sum, value) => Term::Float(self.term_to_number(&sum)? + self.term_to_number(&value)?),
+                    };
+                }
+                Ok(Some(total))
+            }
+            _ => {
+                let mut best: Option<(f64, Term)> = None;
+                for value in values {
+                    let number = self.term_to_number(&value)?;
+                    let better = match &best {
+                        Some((current, _)) if aggregate == "min_of" => number < *current,
+                        Some((current, _)) => number > *current,
+                        None => true,
+                    };
+                    if better {
+                        best = Some((number, value));
+                    }
+                }
+                Ok(best.map(|(_, value)| value))
+            }
+        }
+    }
+
     /// Compare two terms numerically
     fn compare_terms(&self, term1: &Term, term2: &Term, op: &str) -> Result<bool, RuntimeError> {
         let val1 = self.term_to_number(term1)?;
@@ -468,19 +2014,23 @@ impl LogicEngine {
         }
     }
     
-    /// Evaluate arithmetic expression
-    fn evaluate_arithmetic(&self, term: &Term) -> Result<Term, RuntimeError> {
-        match term {
-            Term::Integer(_) | Term::Float(_) => Ok(term.clone()),
+    /// Evaluate arithmetic expression, following the bindings of the
+    /// variables in it down to the numbers
+    fn evaluate_arithmetic(&self, term: &Term, bindings: &Bindings) -> Result<Term, RuntimeError> {
+        match self.resolve_term(term, bindings) {
             Term::Compound(op, args) if args.len() == 2 => {
-                let val1 = self.term_to_number(&args[0])?;
-                let val2 = self.term_to_number(&args[1])?;
-                
+                let val1 = self.term_to_number(&self.evaluate_arithmetic(&args[0], bindings)?)?;
+                let val2 = self.term_to_number(&self.evaluate_arithmetic(&args[1], bindings)?)?;
+                if val2 == 0.0 && matches!(op.as_str(), "/" | "%") {
+                    return Err(RuntimeError::LogicError("Division by zero in arithmetic".to_string()));
+                }
+
                 let result = match op.as_str() {
                     "+" => val1 + val2,
                     "-" => val1 - val2,
                     "*" => val1 * val2,
                     "/" => val1 / val2,
+                    "%" => val1 % val2,
                     _ => return Err(RuntimeError::LogicError(format!("Unknown arithmetic operator: {}", op))),
                 };
                 
@@ -490,7 +2040,10 @@ impl LogicEngine {
                     Ok(Term::Float(result))
                 }
             }
-            _ => Err(RuntimeError::LogicError("Invalid arithmetic expression".to_string())),
+            number => {
+                self.term_to_number(&number)?;
+                Ok(number)
+            }
         }
     }
     
@@ -498,10 +2051,25 @@ impl LogicEngine {
     fn term_to_string(&self, term: &Term) -> String {
         match term {
             Term::Variable(var) => var.clone(),
-            Term::Atom(atom) => atom.clone(),
+            Term::Atom(atom) => atom.to_string(),
             Term::Integer(i) => i.to_string(),
             Term::Float(f) => f.to_string(),
-            Term::String(s) => format!("\"{}\"", s),
+            Term::String(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+            Term::Compound(name, args) if name.as_str() == LIST_CELL && args.len() == 2 => {
+                let mut items = Vec::new();
+                let mut rest = term;
+                while let Term::Compound(name, cell) = rest {
+                    if name.as_str() != LIST_CELL || cell.len() != 2 {
+                        break;
+                    }
+                    items.push(self.term_to_string(&cell[0]));
+                    rest = &cell[1];
+                }
+                match rest {
+                    Term::Atom(atom) if atom.as_str() == EMPTY_LIST => format!("[{}]", items.join(", ")),
+                    tail => format!("[{} | {}]", items.join(", "), self.term_to_string(tail)),
+                }
+            }
             Term::Compound(name, args) => {
                 let arg_strings: Vec<String> = args.iter().map(|arg| self.term_to_string(arg)).collect();
                 format!("{}({})", name, arg_strings.join(", "))
@@ -515,44 +2083,43 @@ impl LogicEngine {
         let trimmed = fact_str.trim().trim_end_matches('.');
         
         if let Some(paren_pos) = trimmed.find('(') {
-            let predicate = trimmed[..paren_pos].trim().to_string();
+            let predicate = Atom::new(trimmed[..paren_pos].trim());
             let args_str = &trimmed[paren_pos + 1..trimmed.len() - 1];
             let args = self.parse_args(args_str)?;
             
-            Ok(Fact { predicate, args })
+            Ok(Fact::new(predicate, args))
         } else {
             // Fact with no arguments
-            Ok(Fact {
-                predicate: trimmed.to_string(),
-                args: vec![],
-            })
+            Ok(Fact::new(Atom::new(trimmed), vec![]))
         }
     }
     
     /// Parse rule from string (simplified)
     fn parse_rule(&self, rule_str: &str) -> Result<Rule, RuntimeError> {
+        let (reorder, rule_str) = strip_no_reorder(rule_str);
+
         // Simplified parsing for rules like "head :- body1, body2."
         if let Some(implies_pos) = rule_str.find(":-") {
             let head_str = rule_str[..implies_pos].trim();
             let body_str = rule_str[implies_pos + 2..].trim().trim_end_matches('.');
             
             let head = self.parse_fact(head_str)?;
-            let body_goals: Result<Vec<Goal>, RuntimeError> = body_str
-                .split(',')
+            let body_goals: Result<Vec<Goal>, RuntimeError> = split_top_level(body_str)
+                .into_iter()
                 .map(|goal_str| {
                     let trimmed = goal_str.trim();
-                    let fact = self.parse_fact(trimmed)?;
-                    Ok(Goal {
-                        predicate: fact.predicate,
-                        args: fact.args,
-                        negated: false,
-                    })
+                    let (negated, goal_content) = match trimmed.strip_prefix("not ") {
+                        Some(positive) => (true, positive.trim()),
+                        None => (false, trimmed),
+                    };
+                    self.parse_goal(goal_content, negated)
                 })
                 .collect();
             
             Ok(Rule {
                 head,
                 body: body_goals?,
+                reorder,
             })
         } else {
             Err(RuntimeError::LogicError("Invalid rule format".to_string()))
@@ -561,21 +2128,34 @@ impl LogicEngine {
     
     /// Parse query from string (simplified)
     fn parse_query(&self, query_str: &str) -> Result<Vec<Goal>, RuntimeError> {
-        let goals: Result<Vec<Goal>, RuntimeError> = query_str
-            .split(',')
+        let goals: Result<Vec<Goal>, RuntimeError> = split_top_level(query_str)
+            .into_iter()
             .map(|goal_str| {
                 let trimmed = goal_str.trim().trim_end_matches('.');
-                let fact = self.parse_fact(trimmed)?;
-                Ok(Goal {
-                    predicate: fact.predicate,
-                    args: fact.args,
-                    negated: false,
-                })
+                self.parse_goal(trimmed, false)
             })
             .collect();
         
         goals
     }
+
+    /// Parse one goal: `pred(args)`, or an infix comparison such as `X = H`
+    /// or `N >= 18`
+    fn parse_goal(&self, goal_str: &str, negated: bool) -> Result<Goal, RuntimeError> {
+        if let Some((left, operator, right)) = split_infix_goal(goal_str) {
+            return Ok(Goal {
+                predicate: Atom::new(operator),
+                args: vec![self.parse_term(left)?, self.parse_term(right)?],
+                negated,
+            });
+        }
+        let fact = self.parse_fact(goal_str)?;
+        Ok(Goal {
+            predicate: fact.predicate,
+            args: fact.args,
+            negated,
+        })
+    }
     
     /// Parse arguments from string (simplified)
     fn parse_args(&self, args_str: &str) -> Result<Vec<Term>, RuntimeError> {
@@ -583,8 +2163,8 @@ impl LogicEngine {
             return Ok(vec![]);
         }
         
-        let args: Result<Vec<Term>, RuntimeError> = args_str
-            .split(',')
+        let args: Result<Vec<Term>, RuntimeError> = split_top_level(args_str)
+            .into_iter()
             .map(|arg_str| self.parse_term(arg_str.trim()))
             .collect();
         
@@ -596,8 +2176,8 @@ impl LogicEngine {
         let trimmed = term_str.trim();
         
         // Check if it's a string literal
-        if trimmed.starts_with('"') && trimmed.ends_with('"') {
-            return Ok(Term::String(trimmed[1..trimmed.len() - 1].to_string()));
+        if trimmed.len() >= 2 && trimmed.starts_with('"') && trimmed.ends_with('"') {
+            return Ok(Term::String(unescape_string(&trimmed[1..trimmed.len() - 1])));
         }
         
         // Check if it's a number
@@ -609,18 +2189,34 @@ impl LogicEngine {
             return Ok(Term::Float(f));
         }
         
+        // `[a, b]`, or `[H | T]` for a list whose tail is `T`
+        if let Some(items) = trimmed.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            let (items, tail) = match split_top_level_at(items, '|').as_slice() {
+                [items, tail] => (*items, self.parse_term(tail)?),
+                _ => (items, Term::Atom(Atom::new(EMPTY_LIST))),
+            };
+            return Ok(list_term(self.parse_args(items)?, tail));
+        }
+
+        // A compound term, such as the goal of `holds_at(at(robot, X), 5)`
+        // or the struct term `Point(1, 2)`
+        if let Some(open) = trimmed.find('(').filter(|&open| open > 0 && trimmed.ends_with(')')) {
+            let args = self.parse_args(&trimmed[open + 1..trimmed.len() - 1])?;
+            return Ok(Term::Compound(Atom::new(trimmed[..open].trim()), args));
+        }
+
         // Check if it's a variable (starts with uppercase)
         if trimmed.chars().next().unwrap_or('a').is_uppercase() {
             return Ok(Term::Variable(trimmed.to_string()));
         }
         
         // Otherwise, it's an atom
-        Ok(Term::Atom(trimmed.to_string()))
+        Ok(Term::Atom(Atom::new(trimmed)))
     }
     
     /// Get number of facts
     pub fn facts_count(&self) -> usize {
-        self.knowledge_base.facts.values().map(|facts| facts.len()).sum()
+        self.knowledge_base.facts.len().unwrap_or(0)
     }
     
     /// Get number of rules
@@ -633,6 +2229,25 @@ impl LogicEngine {
         self.queries_executed
     }
 
+    /// Facts and rules of each predicate: `(predicate, facts, rules)`, predicates
+    /// with facts first in assertion order, then those defined only by rules
+    pub fn predicate_counts(&self) -> Result<Vec<(String, usize, usize)>, RuntimeError> {
+        let mut counts: Vec<(String, usize, usize)> = self
+            .knowledge_base
+            .facts
+            .counts()?
+            .into_iter()
+            .map(|(predicate, facts)| (predicate, facts, 0))
+            .collect();
+        for (predicate, rules) in &self.knowledge_base.rules {
+            match counts.iter_mut().find(|(name, _, _)| name == predicate.as_str()) {
+                Some(entry) => entry.2 = rules.len(),
+                None => counts.push((predicate.as_str().to_string(), 0, rules.len())),
+            }
+        }
+        Ok(counts)
+    }
+
     /// Parse complex queries with logical operators
     fn parse_complex_query(&self, query_str: &str) -> Result<Vec<Goal>, RuntimeError> {
         // Handle complex queries with AND, OR, NOT operators
@@ -640,8 +2255,8 @@ impl LogicEngine {
 
         // For now, split by comma (AND operation)
         // TODO: Add support for OR and NOT operators
-        let goals: Result<Vec<Goal>, RuntimeError> = query_str
-            .split(',')
+        let goals: Result<Vec<Goal>, RuntimeError> = split_top_level(query_str)
+            .into_iter()
             .map(|goal_str| {
                 let trimmed = goal_str.trim();
                 let negated = trimmed.starts_with("not ");
@@ -651,12 +2266,7 @@ impl LogicEngine {
                     trimmed
                 };
 
-                let fact = self.parse_fact(goal_content)?;
-                Ok(Goal {
-                    predicate: fact.predicate,
-                    args: fact.args,
-                    negated,
-                })
+                self.parse_goal(goal_content, negated)
             })
             .collect();
 
@@ -687,55 +2297,6 @@ impl LogicEngine {
         Ok(propagated)
     }
 
-    /// Select the most constrained goal for better performance
-    fn select_most_constrained_goal(&self, goals: &[Goal], bindings: &Bindings) -> usize {
-        let mut best_index = 0;
-        let mut best_score = f64::INFINITY;
-
-        for (i, goal) in goals.iter().enumerate() {
-            let score = self.calculate_goal_score(goal, bindings);
-            if score < best_score {
-                best_score = score;
-                best_index = i;
-            }
-        }
-
-        best_index
-    }
-
-    /// Calculate goal constraint score
-    fn calculate_goal_score(&self, goal: &Goal, bindings: &Bindings) -> f64 {
-        let mut score = 0.0;
-
-        // Count unbound variables (penalty)
-        let unbound_vars = goal.args.iter()
-            .filter(|arg| {
-                if let Term::Variable(var_name) = arg {
-                    !bindings.contains_key(var_name)
-                } else {
-                    false
-                }
-            })
-            .count();
-
-        score += unbound_vars as f64 * 10.0;
-
-        // Count potential matches (fewer is better)
-        let fact_count = self.knowledge_base.facts
-            .get(&goal.predicate)
-            .map(|facts| facts.len())
-            .unwrap_or(0);
-
-        let rule_count = self.knowledge_base.rules
-            .get(&goal.predicate)
-            .map(|rules| rules.len())
-            .unwrap_or(0);
-
-        score += (fact_count + rule_count) as f64;
-
-        score
-    }
-
     /// Infer variable value from constraints
     fn infer_variable_value(&self, var_name: &str, goals: &[Goal], _bindings: &Bindings) -> Option<Term> {
         // Simple inference: if variable appears in only one fact, try to bind it
@@ -748,10 +2309,9 @@ impl LogicEngine {
                 }
             }) {
                 // Check if we can infer from facts
-                if let Some(facts) = self.knowledge_base.facts.get(&goal.predicate) {
-                    if facts.len() == 1 {
+                if self.knowledge_base.facts.count(goal.predicate).ok() == Some(1) {
+                    if let Some(fact) = self.knowledge_base.facts.matching(goal.predicate, &[]).ok()?.first() {
                         // Only one fact matches, try to extract value
-                        let fact = &facts[0];
                         for (goal_arg, fact_arg) in goal.args.iter().zip(&fact.args) {
                             if let Term::Variable(v) = goal_arg {
                                 if v == var_name {
@@ -769,105 +2329,689 @@ impl LogicEngine {
 
 
 
-    /// Unify a goal with a fact
-    fn unify_goal_with_fact(
-        &self,
-        goal: &Goal,
-        fact: &Fact,
-        bindings: &mut Bindings,
-    ) -> Result<bool, RuntimeError> {
-        // Simple unification - check if predicate names match
-        if goal.predicate == fact.predicate && goal.args.len() == fact.args.len() {
-            for (goal_term, fact_term) in goal.args.iter().zip(fact.args.iter()) {
-                if !self.unify_terms(goal_term, fact_term, bindings)? {
-                    return Ok(false);
-                }
-            }
-            Ok(true)
-        } else {
-            Ok(false)
+    /// Unify a goal with a fact
+    fn unify_goal_with_fact(
+        &self,
+        goal: &Goal,
+        fact: &Fact,
+        bindings: &mut Bindings,
+    ) -> Result<bool, RuntimeError> {
+        // Simple unification - check if predicate names match
+        if goal.predicate == fact.predicate && goal.args.len() == fact.args.len() {
+            for (goal_term, fact_term) in goal.args.iter().zip(fact.args.iter()) {
+                if !self.unify_terms(goal_term, fact_term, bindings)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Unify a goal with a rule head
+    fn unify_goal_with_rule_head(
+        &self,
+        goal: &Goal,
+        rule: &Rule,
+        bindings: &mut Bindings,
+    ) -> Result<bool, RuntimeError> {
+        self.unify_goal_with_fact(goal, &rule.head, bindings)
+    }
+
+
+
+    /// Solve a single goal with remaining goals
+    fn solve_single_goal(
+        &self,
+        goal: &Goal,
+        remaining_goals: &[Goal],
+        bindings: &Bindings,
+        results: &mut Vec<Bindings>,
+        depth: usize,
+    ) -> Result<(), RuntimeError> {
+        self.trace_call(goal, remaining_goals.len(), bindings);
+        let solved = self.try_goal_alternatives(goal, remaining_goals, bindings, results, depth);
+        if self.tracer.is_some() {
+            self.trace_fail();
+        }
+        solved
+    }
+
+    /// Solve `goal` on its own, for a negation or `holds_at`, returning its
+    /// solutions; the `goals_left` goals after it wait on the stack until it is done
+    fn solve_apart(
+        &self,
+        goal: &Goal,
+        goals_left: usize,
+        bindings: &Bindings,
+        depth: usize,
+    ) -> Result<Vec<Bindings>, RuntimeError> {
+        self.apart(goals_left, || {
+            let mut solutions = Vec::new();
+            self.solve_single_goal(goal, &[], bindings, &mut solutions, depth)?;
+            Ok(solutions)
+        })
+    }
+
+    /// Solve `goal` apart as a stratum below the goals around it, for a
+    /// negation or an aggregate: the tabled calls within it reach their
+    /// fixpoint before its solutions are counted
+    fn solve_stratum(
+        &self,
+        goal: &Goal,
+        goals_left: usize,
+        bindings: &Bindings,
+        depth: usize,
+    ) -> Result<Vec<Bindings>, RuntimeError> {
+        let outer = self.tabling.replace(false);
+        let solved = self.solve_apart(goal, goals_left, bindings, depth);
+        self.tabling.set(outer);
+        solved
+    }
+
+    /// Run `solve` for a goal solved on its own, with the `goals_left` goals
+    /// after it waiting on the stack
+    fn apart<T>(&self, goals_left: usize, solve: impl FnOnce() -> Result<T, RuntimeError>) -> Result<T, RuntimeError> {
+        let outer_frames = self.watches_rules().then(|| {
+            let active = self.goal_stack(1 + goals_left).into_iter().rev();
+            self.goal_frames.replace(active.map(|goal| (goal, 0)).collect())
+        });
+        let outer_trace = self.tracer.is_some().then(|| {
+            let frames = self.trace_frames.take();
+            let active = frames.iter().filter(|frame| !frame.exited).count();
+            (frames, self.trace_base.replace(self.trace_base.get() + active))
+        });
+        let solved = solve();
+        if let Some(frames) = outer_frames {
+            self.goal_frames.replace(frames);
+        }
+        if let Some((frames, base)) = outer_trace {
+            self.trace_frames.replace(frames);
+            self.trace_base.set(base);
+        }
+        solved
+    }
+
+    /// Solve `goal` by negation, `holds_at`, a built-in, its facts and its rules in turn
+    fn try_goal_alternatives(
+        &self,
+        goal: &Goal,
+        remaining_goals: &[Goal],
+        bindings: &Bindings,
+        results: &mut Vec<Bindings>,
+        depth: usize,
+    ) -> Result<(), RuntimeError> {
+        if goal.negated {
+            // Handle negation as failure
+            let positive_goal = Goal { negated: false, ..goal.clone() };
+            let solutions = self.solve_stratum(&positive_goal, remaining_goals.len(), bindings, depth + 1)?;
+
+            if !solutions.is_empty() {
+                // Goal succeeded, so negation fails
+                return Ok(());
+            } else {
+                // Goal failed, so negation succeeds
+                let mut new_bindings = bindings.clone();
+                self.record_step(&positive_goal, ProofStep::Negation, 0, &mut new_bindings);
+                return self.solve_goals_with_constraints(remaining_goals, &mut new_bindings, results, depth + 1);
+            }
+        }
+
+        // A cut succeeds once; backtracking into it prunes every alternative
+        // left since its rule was applied, the rule's other clauses included
+        if goal.is_cut() {
+            self.solve_goals_with_constraints(remaining_goals, &mut bindings.clone(), results, depth + 1)?;
+            let barrier = match goal.args.first() {
+                Some(Term::Integer(barrier)) => *barrier as usize,
+                _ => 0,
+            };
+            let fired = self.cut_fired.get().map_or(barrier, |fired| fired.min(barrier));
+            self.cut_fired.set(Some(fired));
+            return Ok(());
+        }
+        // Alternatives tried from here on are pruned by any cut whose barrier is older
+        let floor = self.cut_barriers.get() + 1;
+
+        // Solve the goal of `holds_at` at its time, then go on at the current one
+        if goal.predicate.as_str() == HOLDS_AT {
+            let (held, time) = match (held_goal(goal), goal.args.get(1).map(|arg| self.resolve_term(arg, bindings))) {
+                (Some(held), Some(Term::Integer(time))) => (held, time),
+                _ => {
+                    return Err(RuntimeError::LogicError(format!(
+                        "{} needs a goal and a time point",
+                        self.resolved_goal_string(goal, bindings)
+                    )))
+                }
+            };
+            let mut held_bindings = bindings.clone();
+            self.record_step(goal, ProofStep::HoldsAt, 1, &mut held_bindings);
+            let outer_time = self.time.replace(Some(time));
+            let solved = self.solve_apart(&held, remaining_goals.len(), &held_bindings, depth + 1);
+            self.time.set(outer_time);
+            for mut solution in solved? {
+                self.solve_goals_with_constraints(remaining_goals, &mut solution, results, depth + 1)?;
+                if self.cut_past(floor) {
+                    break;
+                }
+            }
+            return Ok(());
+        }
+
+        // Solve the goal of an aggregate apart and bind its result to what
+        // the solutions give; a minimum or maximum of no solutions fails
+        if let Some((template, inner)) = aggregate_goal(goal) {
+            let solutions = self.solve_stratum(&inner, remaining_goals.len(), bindings, depth + 1)?;
+            let values = solutions.iter().map(|solution| self.substitute(&template, solution)).collect();
+            let Some(result) = self.aggregate(goal.predicate.as_str(), values)? else {
+                return Ok(());
+            };
+            let mut new_bindings = bindings.clone();
+            if self.unify_terms(&goal.args[goal.args.len() - 1], &result, &mut new_bindings)? {
+                self.record_step(goal, ProofStep::Aggregate, 0, &mut new_bindings);
+                return self.solve_goals_with_constraints(remaining_goals, &mut new_bindings, results, depth + 1);
+            }
+            return Ok(());
+        }
+
+        // Check built-in predicates
+        if self.is_builtin_predicate(goal.predicate) {
+            let mut new_bindings = bindings.clone();
+            if self.solve_builtin_predicate(goal, &mut new_bindings)? {
+                self.record_step(goal, ProofStep::Builtin, 0, &mut new_bindings);
+                return self.solve_goals_with_constraints(remaining_goals, &mut new_bindings, results, depth + 1);
+            } else {
+                return Ok(());
+            }
+        }
+
+        // A tabled goal takes its answers from the table of its call
+        if self.knowledge_base.tabled.contains(&goal.predicate) {
+            let call: Vec<Term> = goal.args.iter().map(|arg| self.substitute(arg, bindings)).collect();
+            for answer in self.table_answers(goal.predicate, &call, remaining_goals.len(), depth)? {
+                let answer = Fact::new(goal.predicate, answer);
+                let answer = self.rename_variables_in_fact(&answer, &mut HashMap::new(), depth);
+                let mut new_bindings = bindings.clone();
+                if self.unify_fact_goal(&answer, goal, &mut new_bindings)? {
+                    self.record_step(goal, ProofStep::Table, 0, &mut new_bindings);
+                    self.solve_goals_with_constraints(remaining_goals, &mut new_bindings, results, depth + 1)?;
+                    if self.cut_past(floor) {
+                        return Ok(());
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        self.try_clauses(goal, remaining_goals, bindings, results, depth, floor)
+    }
+
+    /// Solve `goal` by its facts, the facts the ontology implies and its rules
+    /// in turn; `floor` is the barrier cuts pruning the goal are older than
+    fn try_clauses(
+        &self,
+        goal: &Goal,
+        remaining_goals: &[Goal],
+        bindings: &Bindings,
+        results: &mut Vec<Bindings>,
+        depth: usize,
+        floor: usize,
+    ) -> Result<(), RuntimeError> {
+        // Try facts; bound indexed arguments let the store skip facts that cannot unify
+        let args: Vec<Term> = goal.args.iter().map(|arg| self.resolve_term(arg, bindings)).collect();
+        for fact in self.knowledge_base.facts.matching(goal.predicate, &args)?.iter() {
+            if !self.holds(fact) {
+                continue;
+            }
+            let mut new_bindings = bindings.clone();
+            if self.unify_fact_goal(fact, goal, &mut new_bindings)? {
+                self.record_support(fact, &mut new_bindings);
+                self.record_step(goal, ProofStep::Fact, 0, &mut new_bindings);
+                self.solve_goals_with_constraints(remaining_goals, &mut new_bindings, results, depth + 1)?;
+                if self.cut_past(floor) {
+                    return Ok(());
+                }
+            }
+        }
+
+        // Then the facts the ontology implies
+        if !self.ontology.is_empty() {
+            for (fact, source) in self.inferred_facts(goal, bindings)? {
+                let mut new_bindings = bindings.clone();
+                if self.unify_fact_goal(&fact, goal, &mut new_bindings)? {
+                    self.record_support(&source, &mut new_bindings);
+                    if self.recording_proofs.get() {
+                        let step = ProofStep::Ontology(self.fact_to_string(&source));
+                        self.record_step(goal, step, 0, &mut new_bindings);
+                    }
+                    self.solve_goals_with_constraints(remaining_goals, &mut new_bindings, results, depth + 1)?;
+                    if self.cut_past(floor) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        // Try rules
+        if let Some(rules) = self.knowledge_base.rules.get(&goal.predicate) {
+            for rule in rules {
+                let mut new_bindings = bindings.clone();
+                let mut renamed_rule = self.rename_variables_in_rule(rule, depth);
+                let barrier = self.place_cut_barrier(&mut renamed_rule);
+                if self.unify_goal_with_rule_head(goal, &renamed_rule, &mut new_bindings)? {
+                    if self.recording_proofs.get() {
+                        let premises = rule.body.iter().filter(|goal| !goal.is_cut()).count();
+                        self.record_step(goal, ProofStep::Rule(self.rule_to_string(rule)), premises, &mut new_bindings);
+                    }
+                    // Plan the body with the bindings the head unification produced
+                    let mut new_goals = if renamed_rule.reorder {
+                        self.order_goals(renamed_rule.body, &new_bindings)
+                    } else {
+                        renamed_rule.body
+                    };
+                    new_goals.extend_from_slice(remaining_goals);
+
+                    let watched = self.watches_rules();
+                    if watched {
+                        self.enter_rule(goal, &new_bindings, remaining_goals.len());
+                    }
+                    let solved = self.solve_goals_with_constraints(&new_goals, &mut new_bindings, results, depth + 1);
+                    if watched {
+                        self.goal_frames.borrow_mut().pop();
+                    }
+                    solved?;
+                    if barrier.is_some() && self.cut_fired.get() == barrier {
+                        // The rule's own cut: commit to this clause
+                        self.cut_fired.set(None);
+                        return Ok(());
+                    }
+                    if self.cut_past(floor) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl LogicEngine {
+    /// Give the cuts in the body of a rule about to be applied a new barrier,
+    /// returned if it has any
+    fn place_cut_barrier(&self, rule: &mut Rule) -> Option<usize> {
+        if !rule.body.iter().any(Goal::is_cut) {
+            return None;
+        }
+        let barrier = self.cut_barriers.get() + 1;
+        self.cut_barriers.set(barrier);
+        for goal in rule.body.iter_mut().filter(|goal| goal.is_cut()) {
+            goal.args = vec![Term::Integer(barrier as i64)];
+        }
+        Some(barrier)
+    }
+
+    /// Whether a cut fired that prunes the alternatives of a goal first
+    /// tried when the next barrier was `floor`
+    fn cut_past(&self, floor: usize) -> bool {
+        self.cut_fired.get().is_some_and(|fired| fired < floor)
+    }
+
+    /// The answers to a call of a tabled predicate. A call made outside any
+    /// fixpoint runs one: the clauses of the calls it leads to are solved
+    /// round after round, a call met again within a round taking the answers
+    /// its table has so far, until no table gains an answer. Every table
+    /// solved on the way is then complete and answers straight away
+    fn table_answers(
+        &self,
+        predicate: Atom,
+        call: &[Term],
+        goals_left: usize,
+        depth: usize,
+    ) -> Result<Vec<Vec<Term>>, RuntimeError> {
+        let call = variant(call);
+        let key = format!("{}@{:?}", self.fact_to_string(&Fact::new(predicate, call.clone())), self.time.get());
+        if let Some(table) = self.tables.borrow().get(&key) {
+            if table.complete || table.round == self.table_round.get() {
+                return Ok(table.answers.clone());
+            }
+        }
+
+        if self.tabling.get() {
+            self.solve_table(&key, predicate, &call, goals_left, depth)?;
+        } else {
+            let start = self.table_round.get();
+            let outer_grew = self.table_grew.get();
+            self.tabling.set(true);
+            let fixpoint = loop {
+                self.table_round.set(self.table_round.get() + 1);
+                self.table_grew.set(false);
+                if let Err(error) = self.solve_table(&key, predicate, &call, goals_left, depth) {
+                    break Err(error);
+                }
+                if !self.table_grew.get() {
+                    break Ok(());
+                }
+            };
+            self.tabling.set(false);
+            self.table_grew.set(outer_grew);
+            fixpoint?;
+            for table in self.tables.borrow_mut().values_mut() {
+                if table.round > start {
+                    table.complete = true;
+                }
+            }
+            self.table_round.set(start);
+        }
+        Ok(self.tables.borrow()[&key].answers.clone())
+    }
+
+    /// Solve the clauses of a tabled call once, adding the answers not
+    /// already subsumed to its table; a new answer drops those it subsumes
+    fn solve_table(
+        &self,
+        key: &str,
+        predicate: Atom,
+        call: &[Term],
+        goals_left: usize,
+        depth: usize,
+    ) -> Result<(), RuntimeError> {
+        self.tables.borrow_mut().entry(key.to_string()).or_default().round = self.table_round.get();
+        let goal = Goal { predicate, args: call.to_vec(), negated: false };
+        let floor = self.cut_barriers.get() + 1;
+        let solutions = self.apart(goals_left, || {
+            let mut solutions = Vec::new();
+            self.try_clauses(&goal, &[], &Bindings::new(), &mut solutions, depth + 1, floor)?;
+            Ok(solutions)
+        })?;
+
+        let mut tables = self.tables.borrow_mut();
+        let table = tables.get_mut(key).expect("the table was made above");
+        for solution in solutions {
+            let answer: Vec<Term> = call.iter().map(|arg| self.substitute(arg, &solution)).collect();
+            let answer = variant(&answer);
+            if table.answers.iter().any(|known| subsumes(known, &answer)) {
+                continue;
+            }
+            table.answers.retain(|known| !subsumes(&answer, known));
+            table.answers.push(answer);
+            self.table_grew.set(true);
+        }
+        Ok(())
+    }
+}
+
+/// `args` with their variables renamed `_V0`, `_V1`, ... in order of first
+/// occurrence, the same for every variant of them
+fn variant(args: &[Term]) -> Vec<Term> {
+    fn rename(term: &Term, names: &mut HashMap<String, String>) -> Term {
+        match term {
+            Term::Variable(name) => {
+                let next = names.len();
+                Term::Variable(names.entry(name.clone()).or_insert_with(|| format!("_V{}", next)).clone())
+            }
+            Term::Compound(functor, args) => Term::Compound(*functor, args.iter().map(|arg| rename(arg, names)).collect()),
+            _ => term.clone(),
+        }
+    }
+    let mut names = HashMap::new();
+    args.iter().map(|arg| rename(arg, &mut names)).collect()
+}
+
+/// Whether `specific` is an instance of `general`, the variables of
+/// `specific` taken as constants
+fn subsumes(general: &[Term], specific: &[Term]) -> bool {
+    fn matches(general: &Term, specific: &Term, bound: &mut HashMap<String, Term>) -> bool {
+        match (general, specific) {
+            (Term::Variable(name), _) => match bound.get(name) {
+                Some(term) => term == specific,
+                None => {
+                    bound.insert(name.clone(), specific.clone());
+                    true
+                }
+            },
+            (Term::Compound(f, a), Term::Compound(g, b)) => {
+                f == g && a.len() == b.len() && a.iter().zip(b).all(|(a, b)| matches(a, b, bound))
+            }
+            _ => general == specific,
+        }
+    }
+    let mut bound = HashMap::new();
+    general.len() == specific.len() && general.iter().zip(specific).all(|(g, s)| matches(g, s, &mut bound))
+}
+
+/// The goal a `holds_at(Goal, T)` goal solves at time `T`
+fn held_goal(goal: &Goal) -> Option<Goal> {
+    if goal.predicate.as_str() != HOLDS_AT || goal.args.len() != 2 {
+        return None;
+    }
+    term_goal(&goal.args[0])
+}
+
+/// The template and goal of an aggregate: `findall(X, Goal, List)`,
+/// `count(Goal, N)`, and `sum_of`, `min_of` and `max_of` like `findall`.
+/// `count` has no template and counts each solution as `1`.
+fn aggregate_goal(goal: &Goal) -> Option<(Term, Goal)> {
+    match (goal.predicate.as_str(), goal.args.as_slice()) {
+        ("count", [inner, _]) => Some((Term::Integer(1), term_goal(inner)?)),
+        ("findall" | "sum_of" | "min_of" | "max_of", [template, inner, _]) => {
+            Some((template.clone(), term_goal(inner)?))
+        }
+        _ => None,
+    }
+}
+
+/// The positive goal a compound term or atom passed as an argument stands for
+fn term_goal(term: &Term) -> Option<Goal> {
+    match term {
+        Term::Compound(predicate, args) => Some(Goal { predicate: *predicate, args: args.clone(), negated: false }),
+        Term::Atom(predicate) => Some(Goal { predicate: *predicate, args: Vec::new(), negated: false }),
+        _ => None,
+    }
+}
+
+/// Whether two facts state the same thing, whatever their validity
+fn same_fact(a: &Fact, b: &Fact) -> bool {
+    a.predicate == b.predicate && a.args == b.args
+}
+
+/// The stored facts a solution recorded under `SUPPORT_KEY`
+fn support(bindings: &Bindings) -> Vec<Fact> {
+    match bindings.get(SUPPORT_KEY) {
+        Some(Term::Compound(_, facts)) => facts
+            .iter()
+            .filter_map(|used| match used {
+                Term::Compound(predicate, args) => Some(Fact::new(*predicate, args.clone())),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Names of the variables a goal mentions, including inside compound terms
+fn goal_variables(goal: &Goal) -> Vec<String> {
+    fn collect(term: &Term, variables: &mut Vec<String>) {
+        match term {
+            Term::Variable(name) => variables.push(name.clone()),
+            Term::Compound(_, args) => args.iter().for_each(|arg| collect(arg, variables)),
+            _ => {}
+        }
+    }
+    let mut variables = Vec::new();
+    goal.args.iter().for_each(|arg| collect(arg, &mut variables));
+    variables
+}
+
+/// Whether every variable in a term is accepted by `is_bound`
+fn term_is_bound(term: &Term, is_bound: &dyn Fn(&str) -> bool) -> bool {
+    match term {
+        Term::Variable(name) => is_bound(name),
+        Term::Compound(_, args) => args.iter().all(|arg| term_is_bound(arg, is_bound)),
+        _ => true,
+    }
+}
+
+/// Split a leading `#[no_reorder]` off a rule or query; the flag says
+/// whether its goals may still be reordered
+fn strip_no_reorder(text: &str) -> (bool, &str) {
+    match text.trim_start().strip_prefix(NO_REORDER) {
+        Some(rest) => (false, rest),
+        None => (true, text),
+    }
+}
+
+/// The fact, or positive goal, an analyzed logic term stands for
+fn annotated_fact(term: &AnnotatedLogicTerm) -> Fact {
+    Fact::new(Atom::new(&term.name), term.args.iter().map(annotated_term).collect())
+}
+
+/// The list of `items` ending in `tail`, which is the empty list for a
+/// closed list
+fn list_term(items: Vec<Term>, tail: Term) -> Term {
+    items
+        .into_iter()
+        .rev()
+        .fold(tail, |rest, item| Term::Compound(Atom::new(LIST_CELL), vec![item, rest]))
+}
+
+/// The term of an analyzed argument; arithmetic is a compound term `+(X, 1)`
+fn annotated_term(arg: &AnnotatedLogicArg) -> Term {
+    match arg {
+        AnnotatedLogicArg::Variable { name, .. } => Term::Variable(name.clone()),
+        AnnotatedLogicArg::Constant { name, .. } => Term::Atom(Atom::new(name)),
+        AnnotatedLogicArg::StringConstant(text) => Term::String(text.clone()),
+        AnnotatedLogicArg::IntConstant(value) => Term::Integer(*value),
+        AnnotatedLogicArg::FloatConstant(value) => Term::Float(*value),
+        AnnotatedLogicArg::Operation { op, left, right, .. } => {
+            Term::Compound(Atom::new(op), vec![annotated_term(left), annotated_term(right)])
+        }
+        AnnotatedLogicArg::Goal(goal) => {
+            let goal = annotated_fact(goal);
+            Term::Compound(goal.predicate, goal.args)
         }
+        AnnotatedLogicArg::Compound { name, args, .. } => {
+            Term::Compound(Atom::new(name), args.iter().map(annotated_term).collect())
+        }
+        AnnotatedLogicArg::List { items, tail, .. } => list_term(
+            items.iter().map(annotated_term).collect(),
+            tail.as_deref().map_or_else(|| Term::Atom(Atom::new(EMPTY_LIST)), annotated_term),
+        ),
     }
+}
 
-    /// Unify a goal with a rule head
-    fn unify_goal_with_rule_head(
-        &self,
-        goal: &Goal,
-        rule: &Rule,
-        bindings: &mut Bindings,
-    ) -> Result<bool, RuntimeError> {
-        self.unify_goal_with_fact(goal, &rule.head, bindings)
+/// Type name of a relation argument, as the knowledge image records it
+fn relation_arg_type(ty: &ResolvedType) -> &'static str {
+    match ty {
+        ResolvedType::Int => "int",
+        ResolvedType::Float => "float",
+        ResolvedType::String => "string",
+        ResolvedType::Bool => "bool",
+        _ => "any",
     }
+}
 
+/// A value bound by [`LogicEngine::solve_query`] as a program sees it: a
+/// string without its quotes and escapes, anything else as is
+pub fn binding_text(binding: &str) -> String {
+    match binding.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+        Some(quoted) => unescape_string(quoted),
+        None => binding.to_string(),
+    }
+}
 
+/// Resolve `\"` and `\\` inside a quoted string term
+fn unescape_string(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
 
-    /// Solve a single goal with remaining goals
-    fn solve_single_goal(
-        &self,
-        goal: &Goal,
-        remaining_goals: &[Goal],
-        bindings: &Bindings,
-        results: &mut Vec<Bindings>,
-        depth: usize,
-    ) -> Result<(), RuntimeError> {
-        if goal.negated {
-            // Handle negation as failure
-            let mut temp_bindings = bindings.clone();
-            let mut temp_results = Vec::new();
-            let positive_goal = Goal { negated: false, ..goal.clone() };
+/// Split on commas that are not nested inside parentheses or string literals
+fn split_top_level(text: &str) -> Vec<&str> {
+    split_top_level_at(text, ',')
+}
 
-            if self.solve_single_goal(&positive_goal, &[], &temp_bindings, &mut temp_results, depth + 1).is_ok()
-                && !temp_results.is_empty() {
-                // Goal succeeded, so negation fails
-                return Ok(());
-            } else {
-                // Goal failed, so negation succeeds
-                return self.solve_goals_with_constraints(remaining_goals, &mut bindings.clone(), results, depth + 1);
-            }
-        }
+/// Operators of the infix goals, two-character ones before the `=`, `<` and
+/// `>` they start with
+const INFIX_GOALS: [&str; 7] = [" is ", "<=", ">=", "!=", "=", "<", ">"];
 
-        // Check built-in predicates
-        if self.is_builtin_predicate(&goal.predicate) {
-            let mut new_bindings = bindings.clone();
-            if self.solve_builtin_predicate(goal, &mut new_bindings)? {
-                return self.solve_goals_with_constraints(remaining_goals, &mut new_bindings, results, depth + 1);
-            } else {
-                return Ok(());
-            }
-        }
+/// `(left, operator, right)` of an infix goal, split at the first of the
+/// [`INFIX_GOALS`] not nested inside parentheses, brackets or string
+/// literals; the prefix form `>(A, 18)` is a goal like any other
+fn split_infix_goal(text: &str) -> Option<(&str, &'static str, &str)> {
+    if text.starts_with(['<', '>', '=', '!']) {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
 
-        // Try facts
-        if let Some(facts) = self.knowledge_base.facts.get(&goal.predicate) {
-            for fact in facts {
-                let mut new_bindings = bindings.clone();
-                if self.unify_fact_goal(fact, goal, &mut new_bindings)? {
-                    self.solve_goals_with_constraints(remaining_goals, &mut new_bindings, results, depth + 1)?;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '(' | '[' if !in_string => depth += 1,
+            ')' | ']' if !in_string => depth = depth.saturating_sub(1),
+            _ if !in_string && depth == 0 => {
+                if let Some(operator) = INFIX_GOALS.iter().find(|operator| text[i..].starts_with(**operator)) {
+                    return Some((&text[..i], operator.trim(), &text[i + operator.len()..]));
                 }
             }
+            _ => {}
         }
+    }
+    None
+}
 
-        // Try rules
-        if let Some(rules) = self.knowledge_base.rules.get(&goal.predicate) {
-            for rule in rules {
-                let mut new_bindings = bindings.clone();
-                if self.unify_goal_with_rule_head(goal, rule, &mut new_bindings)? {
-                    let mut new_goals = rule.body.clone();
-                    new_goals.extend_from_slice(remaining_goals);
-                    self.solve_goals_with_constraints(&new_goals, &mut new_bindings, results, depth + 1)?;
-                }
+/// Split on `separator` where it is not nested inside parentheses, brackets
+/// or string literals
+fn split_top_level_at(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '(' | '[' if !in_string => depth += 1,
+            ')' | ']' if !in_string => depth = depth.saturating_sub(1),
+            _ if c == separator && !in_string && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
             }
+            _ => {}
         }
-
-        Ok(())
     }
+    parts.push(&text[start..]);
+    parts
 }
 
 impl KnowledgeBase {
     fn new() -> Self {
         Self {
-            facts: IndexMap::new(),
+            facts: FactStore::default(),
             rules: IndexMap::new(),
             predicates: HashMap::new(),
+            relations: HashMap::new(),
+            tabled: HashSet::new(),
         }
     }
     
@@ -875,24 +3019,8 @@ impl KnowledgeBase {
         self.predicates.insert(name.to_string(), arity);
     }
     
-    fn add_fact(&mut self, fact: Fact) {
-        self.facts.entry(fact.predicate.clone()).or_insert_with(Vec::new).push(fact);
-    }
-    
-    fn remove_fact(&mut self, fact: &Fact) {
-        if let Some(facts) = self.facts.get_mut(&fact.predicate) {
-            facts.retain(|f| f != fact);
-        }
-    }
-    
     fn add_rule(&mut self, rule: Rule) {
-        self.rules.entry(rule.head.predicate.clone()).or_insert_with(Vec::new).push(rule);
-    }
-    
-    fn clear(&mut self) {
-        self.facts.clear();
-        self.rules.clear();
-        self.predicates.clear();
+        self.rules.entry(rule.head.predicate).or_insert_with(Vec::new).push(rule);
     }
 }
 
@@ -925,4 +3053,661 @@ mod tests {
         // The actual logic engine implementation is simplified
         assert!(results.len() >= 0);
     }
+
+    #[test]
+    fn test_multi_argument_rule_query() {
+        let mut engine = LogicEngine::new();
+        engine.assert_fact("parent(john, mary).").unwrap();
+        engine.assert_fact("parent(mary, ali).").unwrap();
+        engine.add_rule("grandparent(X, Z) :- parent(X, Y), parent(Y, Z).").unwrap();
+
+        let results = engine.solve_query("grandparent(john, Who)").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].get("Who").map(String::as_str), Some("ali"));
+    }
+
+    #[test]
+    fn test_watchpoints_report_fact_changes_and_rule_firings() {
+        let mut engine = LogicEngine::new();
+        let fact_watch = engine.watch_fact("parent(X, ali)", &[FactChange::Assert, FactChange::Retract]).unwrap();
+        let rule_watch = engine.watch_rule("grandparent");
+        assert!(engine.watch_fact("parent(X, ali)", &[]).is_err());
+
+        engine.assert_fact("parent(john, mary).").unwrap();
+        engine.assert_fact("parent(mary, ali).").unwrap();
+        engine.retract_fact("parent(mary, ali).").unwrap();
+        let hits = engine.take_watch_hits();
+        let events: Vec<(WatchId, WatchEvent, &str)> =
+            hits.iter().map(|hit| (hit.watchpoint, hit.event, hit.subject.as_str())).collect();
+        assert_eq!(events, vec![
+            (fact_watch, WatchEvent::Asserted, "parent(mary, ali)"),
+            (fact_watch, WatchEvent::Retracted, "parent(mary, ali)"),
+        ]);
+
+        engine.assert_fact("parent(mary, ali).").unwrap();
+        engine.add_rule("grandparent(X, Z) :- parent(X, Y), parent(Y, Z).").unwrap();
+        engine.add_rule("related(X, Z) :- grandparent(X, Z).").unwrap();
+        engine.take_watch_hits();
+        assert_eq!(engine.solve_query("related(john, Who), parent(john, P)").unwrap().len(), 1);
+
+        let hits = engine.take_watch_hits();
+        assert_eq!(hits.len(), 1);
+        let hit = &hits[0];
+        assert_eq!(hit.watchpoint, rule_watch);
+        assert!(hit.subject.starts_with("grandparent(john, "), "{}", hit.subject);
+        assert_eq!(hit.goal_stack.len(), 3, "{:?}", hit.goal_stack);
+        assert_eq!(hit.goal_stack[0], hit.subject);
+        assert!(hit.goal_stack[1].starts_with("related(john, "), "{:?}", hit.goal_stack);
+        assert_eq!(hit.goal_stack[2], "?- related(john, Who), parent(john, P)");
+        assert!(hit.to_string().starts_with("watchpoint 2: rule fired for grandparent(john, "), "{}", hit);
+
+        assert_eq!(engine.watchpoints()[0].description, "assert/retract of parent(X, ali)");
+        assert!(engine.remove_watchpoint(rule_watch));
+        assert!(!engine.remove_watchpoint(rule_watch));
+        engine.solve_query("related(john, Who)").unwrap();
+        assert!(engine.take_watch_hits().is_empty());
+    }
+
+    #[test]
+    fn test_tracer_reports_ports_of_each_goal() {
+        use crate::runtime::logic_trace::{TraceFilter, TraceOutput};
+
+        let mut engine = LogicEngine::new();
+        engine.assert_fact("parent(ali, omar).").unwrap();
+        engine.assert_fact("parent(omar, sara).").unwrap();
+        engine.add_rule("#[no_reorder] grandparent(X, Z) :- parent(X, Y), parent(Y, Z).").unwrap();
+
+        let tracer = LogicTracer::new(TraceFilter::default(), TraceOutput::Memory);
+        engine.set_tracer(Some(tracer.clone()));
+        assert_eq!(engine.solve_query("grandparent(ali, Who)").unwrap().len(), 1);
+        let events = tracer.take_events();
+        let ports: Vec<(TracePort, usize)> = events.iter().map(|event| (event.port, event.depth)).collect();
+        assert_eq!(ports, vec![
+            (TracePort::Call, 1),
+            (TracePort::Call, 2),
+            (TracePort::Exit, 2),
+            (TracePort::Call, 2),
+            (TracePort::Exit, 2),
+            (TracePort::Exit, 1),
+            (TracePort::Redo, 1),
+            (TracePort::Redo, 2),
+            (TracePort::Fail, 2),
+            (TracePort::Redo, 2),
+            (TracePort::Fail, 2),
+            (TracePort::Fail, 1),
+        ]);
+        assert_eq!(events[0].goal, "grandparent(ali, Who)");
+        assert_eq!(events[2].goal, "parent(ali, omar)");
+        assert_eq!(events[5].goal, "grandparent(ali, sara)");
+
+        let tracer = LogicTracer::new(
+            TraceFilter { relations: vec!["parent".to_string()], max_depth: Some(1) },
+            TraceOutput::Memory,
+        );
+        engine.set_tracer(Some(tracer.clone()));
+        engine.solve_query("#[no_reorder] parent(X, omar), not parent(omar, ali)").unwrap();
+        let lines: Vec<String> = tracer.take_events().iter().map(ToString::to_string).collect();
+        assert_eq!(lines, vec![
+            "Call: (1) parent(X, omar)",
+            "Exit: (1) parent(ali, omar)",
+            "Call: (1) not parent(omar, ali)",
+            "Exit: (1) not parent(omar, ali)",
+            "Redo: (1) not parent(omar, ali)",
+            "Fail: (1) not parent(omar, ali)",
+            "Redo: (1) parent(X, omar)",
+            "Fail: (1) parent(X, omar)",
+        ]);
+    }
+
+    #[test]
+    fn test_bulk_assertion_reports_progress_and_rejects_bad_batches() {
+        let mut engine = LogicEngine::new();
+        engine.declare_relation("age", &["string", "int"]);
+
+        let rows = (0..2500).map(|i| vec![format!("\"person{}\"", i), i.to_string()]);
+        let mut reports = Vec::new();
+        let count = engine.assert_facts_bulk("age", rows, |done, total| reports.push((done, total))).unwrap();
+        assert_eq!(count, 2500);
+        assert_eq!(reports, vec![(1000, 2500), (2000, 2500), (2500, 2500)]);
+        assert_eq!(engine.solve_query("age(\"person42\", A)").unwrap()[0]["A"], "42");
+
+        let bad = vec![
+            vec!["\"ok\"".to_string(), "1".to_string()],
+            vec!["\"old\"".to_string(), "2.5".to_string()],
+            vec!["\"short\"".to_string()],
+        ];
+        let error = engine.assert_facts_bulk("age", bad, |_, _| {}).unwrap_err().to_string();
+        assert!(error.contains("2 invalid facts for relation age"), "{}", error);
+        assert!(error.contains("row 3: expected 2 arguments, found 1"), "{}", error);
+        assert_eq!(engine.facts_count(), 2500);
+    }
+
+    #[test]
+    fn test_ontology_checks_facts_and_infers_inherited_ones() {
+        let mut engine = LogicEngine::new();
+        engine.define_concept("animal", &[]).unwrap();
+        engine.define_concept("bird", &["animal"]).unwrap();
+        engine.define_concept("penguin", &["bird"]).unwrap();
+        engine.define_concept("food", &[]).unwrap();
+        engine.declare_relation("can", &["animal", "string"]);
+        engine.declare_relation("eats", &["animal", "food"]);
+        engine.assert_facts(&["is_a(tweety, bird)", "is_a(pingu, penguin)", "is_a(seed, food)"]).unwrap();
+        engine.assert_facts(&["can(animal, breathe)", "can(bird, fly)", "eats(tweety, seed)"]).unwrap();
+
+        let error = engine.assert_fact("eats(seed, tweety)").unwrap_err().to_string();
+        assert!(error.contains("argument 1 is seed, which is not in concept animal"), "{}", error);
+        assert!(engine.assert_fact("is_a(rex, dinosaur)").is_err());
+        assert!(engine.define_concept("animal", &["penguin"]).is_err());
+
+        assert_eq!(engine.solve_query("is_a(pingu, animal)").unwrap().len(), 1);
+        let mut animals: Vec<String> =
+            engine.solve_query("is_a(X, animal)").unwrap().into_iter().map(|mut s| s.remove("X").unwrap()).collect();
+        animals.sort();
+        assert_eq!(animals, ["pingu", "tweety"]);
+
+        // Inherited along the hierarchy, each fact once
+        assert_eq!(engine.solve_query("can(pingu, breathe)").unwrap().len(), 1);
+        assert_eq!(engine.solve_query("can(penguin, fly)").unwrap().len(), 1);
+        let abilities = engine.solve_query("can(tweety, A)").unwrap();
+        assert_eq!(abilities.len(), 2, "{:?}", abilities);
+        assert_eq!(engine.solve_query("can(X, fly)").unwrap().len(), 4);
+        assert!(engine.conforms("pingu", "bird").unwrap());
+        assert!(!engine.conforms("seed", "animal").unwrap());
+    }
+
+    #[test]
+    fn test_temporal_facts_hold_in_their_interval() {
+        let mut engine = LogicEngine::new();
+        engine.assert_fact_at("at(robot, kitchen)", Validity::between(0, 10)).unwrap();
+        engine.assert_fact_at("at(robot, hall)", Validity::since(10)).unwrap();
+        engine.assert_fact("room(kitchen)").unwrap();
+        engine.add_rule("busy(R) :- at(X, R), room(R)").unwrap();
+
+        // Without a time every fact holds
+        assert_eq!(engine.solve_query("at(robot, Where)").unwrap().len(), 2);
+
+        engine.set_time(Some(12));
+        let now = engine.solve_query("at(robot, Where)").unwrap();
+        assert_eq!(now.len(), 1);
+        assert_eq!(now[0]["Where"], "hall");
+        assert!(engine.solve_query("busy(kitchen)").unwrap().is_empty());
+
+        // holds_at moves the time for its goal and the rules it uses only
+        let then = engine.solve_query("holds_at(at(robot, Where), 3)").unwrap();
+        assert_eq!(then.len(), 1);
+        assert_eq!(then[0]["Where"], "kitchen");
+        assert_eq!(engine.solve_query("holds_at(busy(R), 9), at(robot, hall)").unwrap().len(), 1);
+        assert!(engine.solve_query("holds_at(at(robot, Where), T)").is_err());
+
+        assert_eq!(engine.solve_query("before(3, 9), after(9, 3)").unwrap().len(), 1);
+        assert!(engine.solve_query("before(9, 3)").unwrap().is_empty());
+        assert!(engine.assert_fact_at("at(robot, roof)", Validity::between(5, 5)).is_err());
+
+        let watch = engine.watch_fact("at(robot, X)", &[FactChange::Retract]).unwrap();
+        assert_eq!(engine.collect_expired(12).unwrap(), 1);
+        assert_eq!(engine.facts_count(), 2);
+        let hits = engine.take_watch_hits();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].watchpoint, watch);
+
+        // Retracting ignores validity
+        engine.retract_fact("at(robot, hall)").unwrap();
+        engine.set_time(None);
+        assert!(engine.solve_query("at(robot, Where)").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_explain_reports_plan_and_actual_rows() {
+        let mut engine = LogicEngine::new();
+        for fact in ["parent(john, mary).", "parent(mary, ali).", "parent(mary, sara).", "male(ali)."] {
+            engine.assert_fact(fact).unwrap();
+        }
+
+        let plan = engine.explain_query("parent(X, Y), male(Y)").unwrap();
+        assert_eq!(plan.backend, "memory");
+        let goals: Vec<&str> = plan.steps.iter().map(|step| step.goal.as_str()).collect();
+        assert_eq!(goals, vec!["male(Y)", "parent(X, Y)"]);
+        assert_eq!(plan.steps[0].estimated_facts, 1);
+        assert_eq!(plan.steps[1].estimated_facts, 3);
+        assert_eq!(plan.steps[1].access, GoalAccess::Scan);
+        assert_eq!(plan.steps.iter().map(|step| step.actual_rows).collect::<Vec<_>>(), vec![1, 1]);
+        assert_eq!(plan.solutions, engine.solve_query("parent(X, Y), male(Y)").unwrap().len());
+        assert!(plan.to_string().starts_with("EXPLAIN parent(X, Y), male(Y) (memory store)"));
+
+        engine.solve_query("parent(john, Who)").unwrap();
+        let slowest = engine.slowest_queries(10);
+        assert_eq!(slowest.len(), 2);
+        let join = slowest.iter().find(|stats| stats.query == "parent(X, Y), male(Y)").unwrap();
+        assert_eq!(join.executions, 2);
+        assert_eq!(join.last_solutions, 1);
+
+        engine.reset_query_stats();
+        assert!(engine.slowest_queries(10).is_empty());
+    }
+
+    #[test]
+    fn test_atoms_are_interned_and_strings_keep_escapes() {
+        let mut engine = LogicEngine::new();
+        engine.assert_fact("likes(ali, fish).").unwrap();
+        engine.assert_fact(r#"says(ali, "hi, \"friend\" \\ bye")."#).unwrap();
+
+        let fact = engine.parse_fact("likes(ali, fish).").unwrap();
+        assert_eq!(fact.predicate, Atom::new("likes"));
+        assert_eq!(fact.args[0], Term::Atom(Atom::new("ali")));
+        assert_eq!(engine.solve_query("likes(ali, fish)").unwrap().len(), 1);
+        assert!(engine.solve_query("likes(ali, \"fish\")").unwrap().is_empty());
+
+        let results = engine.solve_query("says(ali, S)").unwrap();
+        assert_eq!(results[0]["S"], r#""hi, \"friend\" \\ bye""#);
+        let query = format!("says(ali, {})", results[0]["S"]);
+        assert_eq!(engine.solve_query(&query).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_goals_are_reordered_by_estimated_cost() {
+        let mut engine = LogicEngine::new();
+        for i in 0..50 {
+            engine.assert_fact(&format!("person(p{}).", i)).unwrap();
+            engine.assert_fact(&format!("age(p{}, {}).", i, i + 30)).unwrap();
+        }
+        engine.assert_fact("admin(p7).").unwrap();
+
+        let order = |engine: &mut LogicEngine, query: &str| -> Vec<String> {
+            engine.explain_query(query).unwrap().steps.into_iter().map(|step| step.goal).collect()
+        };
+
+        // The single admin fact narrows the join, and the comparison waits for A
+        assert_eq!(
+            order(&mut engine, ">(A, 18), person(X), age(X, A), admin(X)"),
+            vec!["admin(X)", "person(X)", "age(X, A)", ">(A, 18)"],
+        );
+        assert_eq!(
+            order(&mut engine, "#[no_reorder] person(X), admin(X)"),
+            vec!["person(X)", "admin(X)"],
+        );
+
+        engine.add_rule("senior_admin(X) :- person(X), age(X, A), >(A, 30), admin(X).").unwrap();
+        engine.add_rule("#[no_reorder] plain_admin(X) :- person(X), admin(X).").unwrap();
+        assert_eq!(engine.solve_query("senior_admin(Who)").unwrap()[0]["Who"], "p7");
+        assert_eq!(engine.solve_query("plain_admin(Who)").unwrap()[0]["Who"], "p7");
+    }
+
+    #[test]
+    fn test_contradictions_report_minimal_conflicts() {
+        let mut engine = LogicEngine::new();
+        engine.assert_facts(&["smoker(ann).", "old(ann).", "old(bob)."]).unwrap();
+        engine.add_rule("uninsurable(X) :- smoker(X), old(X).").unwrap();
+        engine.add_rule("uninsurable(X) :- smoker(X).").unwrap();
+        engine.add_constraint(":- insured(X), uninsurable(X).").unwrap();
+
+        // The first proof rests on old(ann) too, but the second does without it
+        let error = engine.assert_fact("insured(ann).").unwrap_err();
+        let RuntimeError::Contradiction(contradiction) = &error else {
+            panic!("expected a contradiction, got {}", error);
+        };
+        assert_eq!(contradiction.constraint, ":- insured(X), uninsurable(X)");
+        assert_eq!(contradiction.conflicting, ["smoker(ann)"]);
+        assert_eq!(
+            error.to_string(),
+            "Contradiction: insured(ann) violates :- insured(X), uninsurable(X) together with smoker(ann)"
+        );
+        assert!(engine.solve_query("insured(ann)").unwrap().is_empty());
+        assert_eq!(engine.contradiction("insured(bob).").unwrap(), None);
+        assert_eq!(engine.facts_count(), 3);
+    }
+
+    #[test]
+    fn test_revision_policies_resolve_contradictions() {
+        let mut engine = LogicEngine::new();
+        engine.assert_facts(&["alive(tom).", "alive(jerry)."]).unwrap();
+        engine.add_constraint(":- alive(X), dead(X).").unwrap();
+        engine.add_constraint("banned(X)").unwrap();
+
+        engine.set_revision_policy(RevisionPolicy::RetractOld);
+        let revision = engine.revise("dead(tom).").unwrap();
+        assert_eq!(revision.retracted, ["alive(tom)"]);
+        assert_eq!(engine.solve_query("alive(X)").unwrap().len(), 1);
+        assert_eq!(engine.solve_query("dead(tom)").unwrap().len(), 1);
+        // Nothing old can make room for a fact that violates a constraint alone
+        assert!(matches!(engine.assert_fact("banned(tom)."), Err(RuntimeError::Contradiction(_))));
+
+        let asked = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = asked.clone();
+        engine.set_revision_policy(RevisionPolicy::Ask(std::sync::Arc::new(move |contradiction: &Contradiction| {
+            seen.lock().unwrap().push(contradiction.fact.clone());
+            Resolution::KeepBoth
+        })));
+        let revision = engine.revise("alive(tom).").unwrap();
+        assert_eq!(revision.contradictions[0].conflicting, ["dead(tom)"]);
+        assert!(revision.retracted.is_empty());
+        assert_eq!(*asked.lock().unwrap(), ["alive(tom)"]);
+        assert_eq!(engine.solve_query("alive(tom), dead(tom)").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_bulk_loads_are_checked_against_constraints() {
+        let mut engine = LogicEngine::new();
+        engine.assert_fact("parent(ann, bob).").unwrap();
+        engine.add_constraint(":- parent(X, X).").unwrap();
+        engine.add_constraint(":- parent(X, Y), parent(Y, X).").unwrap();
+        let rows = |rows: &[[&str; 2]]| rows.iter().map(|row| row.map(str::to_string).to_vec()).collect::<Vec<_>>();
+
+        let Err(RuntimeError::Contradiction(contradiction)) =
+            engine.assert_facts_bulk("parent", rows(&[["bob", "carl"], ["carl", "carl"]]), |_, _| {})
+        else {
+            panic!("expected a contradiction");
+        };
+        assert_eq!(contradiction.fact, "parent(carl, carl)");
+        assert_eq!(contradiction.constraint, ":- parent(X, X)");
+        // The whole batch is rolled back, the facts stored before it stay
+        assert_eq!(engine.facts_count(), 1);
+
+        let Err(RuntimeError::Contradiction(contradiction)) =
+            engine.assert_facts_bulk("parent", rows(&[["bob", "ann"]]), |_, _| {})
+        else {
+            panic!("expected a contradiction");
+        };
+        assert_eq!(contradiction.to_string(), "parent(bob, ann) violates :- parent(X, Y), parent(Y, X) together with parent(ann, bob)");
+        assert_eq!(engine.facts_count(), 1);
+
+        assert_eq!(engine.assert_facts_bulk("parent", rows(&[["bob", "carl"]]), |_, _| {}).unwrap(), 1);
+        assert_eq!(engine.facts_count(), 2);
+    }
+
+    #[test]
+    fn test_fact_lookups_use_argument_indexes() {
+        let mut engine = LogicEngine::new();
+        let rows: Vec<Vec<String>> = (0..1000).map(|n| vec![format!("p{}", n), format!("c{}", n % 10)]).collect();
+        engine.assert_facts_bulk("parent", rows, |_, _| {}).unwrap();
+        engine.assert_fact("parent(X, c3).").unwrap();
+
+        let results = engine.solve_query("parent(p42, Who)").unwrap();
+        assert_eq!(results.len(), 2);
+        let stats = engine.index_stats();
+        assert_eq!((stats.indexes, stats.keys), (1, 1000));
+        assert_eq!((stats.indexed_lookups, stats.scans), (1, 0));
+
+        let plan = engine.explain_query("parent(Who, c3)").unwrap();
+        assert_eq!(plan.steps[0].access, GoalAccess::Scan);
+        engine.index_argument("parent", 1).unwrap();
+        let plan = engine.explain_query("parent(Who, c3)").unwrap();
+        assert_eq!(plan.steps[0].access, GoalAccess::ArgumentIndex(1));
+        assert_eq!(plan.steps[0].access.to_string(), "index(arg 2)");
+        // The fact with a variable first argument is found through its second
+        let results = engine.solve_query("parent(Who, c3)").unwrap();
+        assert_eq!(results.len(), 101);
+        assert_eq!(engine.index_stats().keys, 1010);
+
+        engine.retract_fact("parent(p3, c3)").unwrap();
+        assert_eq!(engine.solve_query("parent(Who, c3)").unwrap().len(), 100);
+        assert_eq!(engine.solve_query("parent(p3, Who)").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_unification_checks_occurs_through_bindings() {
+        let mut engine = LogicEngine::new();
+        // Y = g(X) with X = f(Y) would make an infinite term
+        assert!(engine.solve_query("=(X, f(Y)), =(Y, g(X))").unwrap().is_empty());
+
+        let results = engine.solve_query("=(X, f(Y, Z)), =(Y, g(1)), =(Z, \"a\")").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["X"], "f(g(1), \"a\")");
+    }
+
+    #[test]
+    fn test_analyzed_programs_load_without_text() {
+        use crate::{CompilerOptions, Lexer, Parser, SemanticAnalyzer};
+
+        let source = "relation parent(string, string);\nrelation grandparent(string, string);\n\
+                      fact parent(\"ali\", \"sara\");\nfact parent(\"sara\", \"huda\");\n\
+                      rule grandparent(X, Z) :- parent(X, Y), parent(Y, Z);\n";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let program = SemanticAnalyzer::new(&CompilerOptions::default()).analyze(ast).unwrap();
+
+        let mut engine = LogicEngine::new();
+        assert_eq!(engine.load_program(&program).unwrap(), 3);
+        assert_eq!((engine.facts_count(), engine.rules_count()), (2, 1));
+        let results = engine.solve_query("grandparent(\"ali\", Who)").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(binding_text(&results[0]["Who"]), "huda");
+    }
+
+    #[test]
+    fn test_negation_and_cut_in_rules() {
+        let mut engine = LogicEngine::new();
+        for fact in ["person(ali).", "person(sara).", "person(huda).", "parent(ali, sara).", "parent(sara, huda).",
+                     "score(amal, 80).", "score(badr, 10)."] {
+            engine.assert_fact(fact).unwrap();
+        }
+        engine.add_rule("childless(X) :- person(X), not parent(X, Y).").unwrap();
+        engine.add_rule("first_parent(P) :- parent(P, C), !.").unwrap();
+        engine.add_rule("grade(X, high) :- score(X, S), >(S, 50), !.").unwrap();
+        engine.add_rule("grade(X, low) :- score(X, S).").unwrap();
+
+        let results = engine.solve_query("childless(Who)").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(binding_text(&results[0]["Who"]), "huda");
+
+        // The cut keeps the first parent and the clause it commits to
+        let results = engine.solve_query("first_parent(P)").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(binding_text(&results[0]["P"]), "ali");
+        let results = engine.solve_query("grade(amal, G)").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(binding_text(&results[0]["G"]), "high");
+        let results = engine.solve_query("grade(badr, G)").unwrap();
+        assert_eq!(binding_text(&results[0]["G"]), "low");
+
+        // Goals before the rule keep their alternatives
+        assert_eq!(engine.solve_query("person(X), first_parent(P)").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_comparisons_in_rule_bodies() {
+        use crate::{CompilerOptions, Lexer, Parser, SemanticAnalyzer};
+
+        let source = "relation age(string, int);\nrelation adult(string);\nrelation next_age(string, int);\n\
+                      fact age(\"ali\", 30);\nfact age(\"huda\", 12);\n\
+                      rule adult(X) :- age(X, A), A >= 18;\nrule next_age(X, N) :- age(X, A), N is (A + 1) * 2;\n";
+        let ast = Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap();
+        let program = SemanticAnalyzer::new(&CompilerOptions::default()).analyze(ast).unwrap();
+
+        let mut engine = LogicEngine::new();
+        engine.load_program(&program).unwrap();
+        let results = engine.solve_query("adult(Who)").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(binding_text(&results[0]["Who"]), "ali");
+        let results = engine.solve_query("next_age(\"huda\", N)").unwrap();
+        assert_eq!(results[0]["N"], "26");
+
+        // Arithmetic on either side of a comparison is evaluated first
+        engine.add_rule("much_older(X, Y) :- age(X, A), age(Y, B), !=(X, Y), >(A, +(B, 10)).").unwrap();
+        let results = engine.solve_query("much_older(X, Y)").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(binding_text(&results[0]["Y"]), "huda");
+    }
+
+    #[test]
+    fn test_aggregates_collect_solutions() {
+        let mut engine = LogicEngine::new();
+        for fact in ["parent(ali, sara).", "parent(ali, omar).", "parent(sara, huda).",
+                     "age(sara, 30).", "age(omar, 25).", "age(huda, 4)."] {
+            engine.assert_fact(fact).unwrap();
+        }
+
+        let results = engine.solve_query("findall(C, parent(ali, C), Children)").unwrap();
+        assert_eq!(results[0]["Children"], "[sara, omar]");
+        let results = engine.solve_query("count(parent(ali, C), N), sum_of(A, age(P, A), Total)").unwrap();
+        assert_eq!((results[0]["N"].as_str(), results[0]["Total"].as_str()), ("2", "59"));
+        let results = engine.solve_query("min_of(A, age(P, A), Youngest), max_of(B, age(Q, B), Oldest)").unwrap();
+        assert_eq!((results[0]["Youngest"].as_str(), results[0]["Oldest"].as_str()), ("4", "30"));
+        assert!(engine.solve_query("max_of(A, age(nobody, A), Oldest)").unwrap().is_empty());
+
+        // Variables bound before an aggregate narrow its goal
+        engine.add_rule("children(P, N) :- parent(P, C), count(parent(P, D), N).").unwrap();
+        let results = engine.solve_query("children(sara, N)").unwrap();
+        assert_eq!(results[0]["N"], "1");
+        let results = engine.solve_query("findall(N, children(ali, N), Counts)").unwrap();
+        assert_eq!(results[0]["Counts"], "[2, 2]");
+    }
+
+    #[test]
+    fn test_lists_and_compound_terms_unify() {
+        let mut engine = LogicEngine::new();
+        engine.assert_facts(&["route(home, [a, b, c]).", "located(robot, Point(1, 2))."]).unwrap();
+        engine.add_rule("member_of(X, [H | T]) :- X = H.").unwrap();
+        engine.add_rule("member_of(X, [H | T]) :- member_of(X, T).").unwrap();
+
+        let results = engine.solve_query("route(home, [First | Rest])").unwrap();
+        assert_eq!((results[0]["First"].as_str(), results[0]["Rest"].as_str()), ("a", "[b, c]"));
+        let stops: Vec<String> = engine
+            .solve_query("route(home, Stops), member_of(X, Stops)")
+            .unwrap()
+            .into_iter()
+            .map(|solution| solution["X"].clone())
+            .collect();
+        assert_eq!(stops, ["a", "b", "c"]);
+        assert!(engine.solve_query("route(home, [a, c | Tail])").unwrap().is_empty());
+        assert!(engine.solve_query("route(home, [])").unwrap().is_empty());
+
+        let results = engine.solve_query("located(robot, Point(X, Y))").unwrap();
+        assert_eq!((results[0]["X"].as_str(), results[0]["Y"].as_str()), ("1", "2"));
+        assert_eq!(engine.solve_query("located(robot, Point(X, Y)), X < Y").unwrap().len(), 1);
+        assert!(engine.solve_query("located(robot, Point(X, Y)), X >= Y").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tabled_left_recursion_terminates() {
+        let mut engine = LogicEngine::new();
+        for fact in ["edge(a, b).", "edge(b, c).", "edge(c, a).", "edge(d, a).",
+                     "node(a).", "node(b).", "node(c).", "node(d)."] {
+            engine.assert_fact(fact).unwrap();
+        }
+        engine.table_predicate("path");
+        engine.add_rule("path(X, Y) :- path(X, Z), edge(Z, Y).").unwrap();
+        engine.add_rule("path(X, Y) :- edge(X, Y).").unwrap();
+
+        let mut reached: Vec<String> =
+            engine.solve_query("path(a, Y)").unwrap().into_iter().map(|solution| solution["Y"].clone()).collect();
+        reached.sort();
+        assert_eq!(reached, ["a", "b", "c"]);
+        assert_eq!(engine.solve_query("path(X, Y)").unwrap().len(), 12);
+
+        // A negated tabled goal is solved to its fixpoint first
+        engine.add_rule("unreached(Y) :- node(Y), not path(a, Y).").unwrap();
+        assert_eq!(engine.solve_query("unreached(Y)").unwrap()[0]["Y"], "d");
+
+        // Answers are recomputed for each query, and an answer an instance
+        // of another is not kept
+        engine.assert_fact("edge(c, Any).").unwrap();
+        assert_eq!(engine.solve_query("path(b, Y)").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_solutions_record_their_derivation() {
+        let mut engine = LogicEngine::new();
+        for fact in ["parent(ali, sara).", "parent(sara, huda).", "parent(sara, omar).", "age(huda, 4)."] {
+            engine.assert_fact(fact).unwrap();
+        }
+        engine.add_rule("grandparent(X, Z) :- parent(X, Y), parent(Y, Z).").unwrap();
+        engine
+            .add_rule("#[no_reorder] young_grandchild(X, Z) :- grandparent(X, Z), age(Z, A), <(A, 10), not parent(Z, W).")
+            .unwrap();
+
+        let solutions = engine.solve_query_with_proofs("young_grandchild(ali, Who)").unwrap();
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].bindings["Who"], "huda");
+        let [proof] = solutions[0].proof() else { panic!("one goal, one tree") };
+        assert_eq!(proof.goal, "young_grandchild(ali, huda)");
+        assert!(matches!(&proof.step, ProofStep::Rule(rule) if rule.starts_with("young_grandchild(X, Z) :- ")));
+        assert_eq!(proof.size(), 7);
+        let premises: Vec<(&str, &ProofStep)> =
+            proof.premises.iter().map(|premise| (premise.goal.as_str(), &premise.step)).collect();
+        assert_eq!(premises[1..3], [("age(huda, 4)", &ProofStep::Fact), ("<(4, 10)", &ProofStep::Builtin)]);
+        // A variable a negation leaves unbound keeps the name it was renamed apart with
+        assert!(premises[3].0.starts_with("not parent(huda, W"), "{}", premises[3].0);
+        assert_eq!(premises[3].1, &ProofStep::Negation);
+        let grandparent = &proof.premises[0];
+        assert_eq!(grandparent.goal, "grandparent(ali, huda)");
+        let facts: Vec<&str> = grandparent.premises.iter().map(|premise| premise.goal.as_str()).collect();
+        assert_eq!(facts, ["parent(ali, sara)", "parent(sara, huda)"]);
+        assert!(proof.to_string().contains("\n    parent(sara, huda)  [fact]"), "{}", proof);
+
+        // Each goal of a query has its own tree, and plain queries record none
+        let solutions = engine.solve_query_with_proofs("#[no_reorder] parent(ali, X), count(parent(X, C), N)").unwrap();
+        let steps: Vec<&ProofStep> = solutions[0].proof().iter().map(|tree| &tree.step).collect();
+        assert_eq!(steps, [&ProofStep::Fact, &ProofStep::Aggregate]);
+        assert!(!engine.solve_query("grandparent(ali, Who)").unwrap()[0].contains_key(PROOF_KEY));
+    }
+
+    #[test]
+    fn test_snapshots_are_queried_in_parallel() {
+        let mut engine = LogicEngine::new();
+        for person in 0..101 {
+            engine.assert_fact(&format!("parent(p{}, p{}).", person, person + 1)).unwrap();
+        }
+        engine.add_rule("grandparent(X, Z) :- parent(X, Y), parent(Y, Z).").unwrap();
+        let engine = std::sync::Mutex::new(engine);
+
+        let mut before = engine.lock().unwrap().snapshot();
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let engine = &engine;
+                scope.spawn(move || {
+                    let mut snapshot = engine.lock().unwrap().snapshot();
+                    for person in thread * 25..(thread + 1) * 25 {
+                        let solutions = snapshot.solve_query(&format!("grandparent(p{}, Who)", person)).unwrap();
+                        assert_eq!(solutions[0]["Who"], format!("p{}", person + 2));
+                    }
+                    engine.lock().unwrap().absorb_queries(snapshot);
+                });
+            }
+            scope.spawn(|| engine.lock().unwrap().assert_fact("parent(p200, p201).").unwrap());
+        });
+
+        // A snapshot keeps the knowledge it was taken with
+        let mut engine = engine.into_inner().unwrap();
+        assert_eq!((before.facts_count(), engine.facts_count()), (101, 102));
+        assert!(before.solve_query("parent(p200, Child)").unwrap().is_empty());
+        assert_eq!(engine.solve_query("parent(p200, Child)").unwrap().len(), 1);
+        engine.absorb_queries(before);
+        assert_eq!(engine.queries_executed(), 102);
+        assert_eq!(engine.slowest_queries(200).len(), 101);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_storage_matches_memory() {
+        let path = std::env::temp_dir().join(format!("albayan_facts_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let storage = KnowledgeStorage::Sqlite(path.clone());
+
+        let facts = ["parent(john, mary).", "parent(mary, ali).", "parent(mary, sara).", "parent(X, adam)."];
+        let mut memory = LogicEngine::new();
+        let mut persistent = LogicEngine::with_storage(&storage).unwrap();
+        assert_eq!(persistent.storage_backend(), "sqlite");
+        for fact in facts {
+            memory.assert_fact(fact).unwrap();
+            persistent.assert_fact(fact).unwrap();
+        }
+        persistent.retract_fact("parent(mary, sara).").unwrap();
+        memory.retract_fact("parent(mary, sara).").unwrap();
+
+        for query in ["parent(mary, Who)", "parent(Who, adam)", "parent(P, C)"] {
+            assert_eq!(memory.solve_query(query).unwrap(), persistent.solve_query(query).unwrap(), "{}", query);
+        }
+
+        // Validity is stored with the fact
+        for engine in [&mut memory, &mut persistent] {
+            engine.assert_fact_at("parent(mary, omar).", Validity::until(5)).unwrap();
+            assert_eq!(engine.solve_query("holds_at(parent(mary, omar), 6)").unwrap().len(), 0);
+            assert_eq!(engine.collect_expired(5).unwrap(), 1);
+        }
+
+        // Facts outlive the engine that asserted them
+        drop(persistent);
+        let reopened = LogicEngine::with_storage(&storage).unwrap();
+        assert_eq!(reopened.facts_count(), 3);
+        std::fs::remove_file(&path).unwrap();
+    }
 }
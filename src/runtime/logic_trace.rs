@@ -0,0 +1,241 @@
+//! # Logic Tracing
+//!
+//! A Prolog-style ports tracer for the logic engine, turned on for a whole
+//! run with `albayan run --trace-logic`. Every goal the solver takes passes
+//! through four ports:
+//!
+//! - `call` when the solver starts on the goal,
+//! - `exit` for each solution, showing the goal with the bindings it found,
+//! - `redo` when the solver backtracks into the goal for another solution,
+//! - `fail` when the goal has no more solutions.
+//!
+//! Each event carries the goal's depth: query goals are at depth 1 and the
+//! goals of a rule body one deeper than the goal the rule fired for. Events
+//! can be limited to some relations and to a maximum depth.
+//!
+//! The console output indents each line by depth. A JSON trace is written in
+//! the Chrome trace event format: `call` and `redo` open a span that `exit`
+//! or `fail` closes, so the profiler UI (`chrome://tracing` or Perfetto)
+//! shows the search as a flame chart.
+
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::Instant;
+
+use serde::Serialize;
+
+/// A port of the tracer's box model of a goal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TracePort {
+    Call,
+    Exit,
+    Redo,
+    Fail,
+}
+
+impl fmt::Display for TracePort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TracePort::Call => "Call",
+            TracePort::Exit => "Exit",
+            TracePort::Redo => "Redo",
+            TracePort::Fail => "Fail",
+        })
+    }
+}
+
+/// A goal passing through a port
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TraceEvent {
+    pub port: TracePort,
+    /// 1 for the goals of a query, one more for each rule body
+    pub depth: usize,
+    pub goal: String,
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let indent = "  ".repeat(self.depth.saturating_sub(1));
+        write!(f, "{}{}: ({}) {}", indent, self.port, self.depth, self.goal)
+    }
+}
+
+/// Where trace events go
+#[derive(Debug)]
+pub enum TraceOutput {
+    Stdout,
+    Stderr,
+    /// A JSON trace file, written by [`finish`]
+    Json(PathBuf),
+    /// Kept in memory until taken with [`LogicTracer::take_events`]
+    Memory,
+}
+
+impl TraceOutput {
+    /// The output named by `--trace-logic`: `stderr` (the default), `stdout`
+    /// or `-`, or the path of a JSON trace file
+    pub fn from_target(target: &str) -> Self {
+        match target.trim() {
+            "" | "stderr" => TraceOutput::Stderr,
+            "stdout" | "-" => TraceOutput::Stdout,
+            path => TraceOutput::Json(PathBuf::from(path)),
+        }
+    }
+}
+
+/// Which events a tracer keeps
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceFilter {
+    /// Relations to trace; empty traces every relation
+    pub relations: Vec<String>,
+    /// Deepest goals to trace
+    pub max_depth: Option<usize>,
+}
+
+impl TraceFilter {
+    pub fn accepts(&self, relation: &str, depth: usize) -> bool {
+        (self.relations.is_empty() || self.relations.iter().any(|name| name == relation))
+            && self.max_depth.map_or(true, |max_depth| depth <= max_depth)
+    }
+}
+
+/// Records the events of every logic engine it is given to; clones share
+/// their output
+#[derive(Debug, Clone)]
+pub struct LogicTracer {
+    filter: TraceFilter,
+    sink: Arc<Mutex<TraceSink>>,
+}
+
+#[derive(Debug)]
+struct TraceSink {
+    output: TraceOutput,
+    started: Instant,
+    /// Events with their time in microseconds since the trace started, kept
+    /// for JSON and in-memory outputs
+    events: Vec<(TraceEvent, u128)>,
+}
+
+impl LogicTracer {
+    pub fn new(filter: TraceFilter, output: TraceOutput) -> Self {
+        Self {
+            filter,
+            sink: Arc::new(Mutex::new(TraceSink { output, started: Instant::now(), events: Vec::new() })),
+        }
+    }
+
+    pub fn filter(&self) -> &TraceFilter {
+        &self.filter
+    }
+
+    pub fn record(&self, event: TraceEvent) {
+        let mut sink = self.lock();
+        match sink.output {
+            TraceOutput::Stdout => println!("{}", event),
+            TraceOutput::Stderr => eprintln!("{}", event),
+            TraceOutput::Json(_) | TraceOutput::Memory => {
+                let time = sink.started.elapsed().as_micros();
+                sink.events.push((event, time));
+            }
+        }
+    }
+
+    /// Events kept so far, oldest first
+    pub fn take_events(&self) -> Vec<TraceEvent> {
+        std::mem::take(&mut self.lock().events).into_iter().map(|(event, _)| event).collect()
+    }
+
+    /// Write a JSON trace to its file; returns the path, or `None` for other outputs
+    pub fn write_json(&self) -> std::io::Result<Option<PathBuf>> {
+        let sink = self.lock();
+        let TraceOutput::Json(path) = &sink.output else {
+            return Ok(None);
+        };
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer(&mut file, &chrome_trace(&sink.events))?;
+        file.flush()?;
+        Ok(Some(path.clone()))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TraceSink> {
+        self.sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The events as a Chrome trace: `call` and `redo` begin a span, `exit` and
+/// `fail` end it
+fn chrome_trace(events: &[(TraceEvent, u128)]) -> serde_json::Value {
+    let trace_events: Vec<serde_json::Value> = events
+        .iter()
+        .map(|(event, time)| {
+            let phase = match event.port {
+                TracePort::Call | TracePort::Redo => "B",
+                TracePort::Exit | TracePort::Fail => "E",
+            };
+            serde_json::json!({
+                "name": event.goal,
+                "cat": "logic",
+                "ph": phase,
+                "ts": time,
+                "pid": 1,
+                "tid": 1,
+                "args": { "port": event.port, "depth": event.depth },
+            })
+        })
+        .collect();
+    serde_json::json!({ "traceEvents": trace_events, "displayTimeUnit": "ms" })
+}
+
+fn installed_tracer() -> MutexGuard<'static, Option<LogicTracer>> {
+    static TRACER: OnceLock<Mutex<Option<LogicTracer>>> = OnceLock::new();
+    TRACER
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Trace every logic engine created from now on
+pub fn install(tracer: LogicTracer) {
+    *installed_tracer() = Some(tracer);
+}
+
+/// The tracer new logic engines start with
+pub fn installed() -> Option<LogicTracer> {
+    installed_tracer().clone()
+}
+
+/// Stop tracing new engines and write the JSON trace, if that is the output;
+/// returns the path written
+pub fn finish() -> std::io::Result<Option<PathBuf>> {
+    match installed_tracer().take() {
+        Some(tracer) => tracer.write_json(),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_and_chrome_trace() {
+        let filter = TraceFilter { relations: vec!["parent".to_string()], max_depth: Some(2) };
+        assert!(filter.accepts("parent", 2));
+        assert!(!filter.accepts("parent", 3));
+        assert!(!filter.accepts("ancestor", 1));
+        assert!(TraceFilter::default().accepts("ancestor", 40));
+
+        let event = TraceEvent { port: TracePort::Exit, depth: 2, goal: "parent(ali, omar)".to_string() };
+        assert_eq!(event.to_string(), "  Exit: (2) parent(ali, omar)");
+
+        let call = TraceEvent { port: TracePort::Call, ..event.clone() };
+        let trace = chrome_trace(&[(call, 0), (event, 5)]);
+        assert_eq!(trace["traceEvents"][0]["ph"], "B");
+        assert_eq!(trace["traceEvents"][1]["ph"], "E");
+        assert_eq!(trace["traceEvents"][1]["args"]["port"], "exit");
+        assert!(matches!(TraceOutput::from_target("trace.json"), TraceOutput::Json(_)));
+    }
+}
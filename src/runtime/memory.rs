@@ -66,6 +66,7 @@ impl MemoryManager {
         self.allocated += size;
         if self.allocated > self.peak_allocated {
             self.peak_allocated = self.allocated;
+            crate::metrics::record_memory(self.allocated);
         }
         
         self.allocations.insert(ptr, size);
@@ -3,17 +3,51 @@
 //! This module implements the runtime system for the AlBayan programming language.
 //! It provides the logic engine, AI support, memory management, and system integration.
 
+pub mod atom;
 pub mod logic_engine;
+pub mod ontology;
+pub mod temporal;
+pub mod belief_revision;
+pub mod knowledge_image;
+pub mod knowledge_watch;
+pub mod logic_trace;
+pub mod heap_snapshot;
+pub mod fact_store;
+pub mod query_plan;
+pub mod proof;
 pub mod memory;
 pub mod ai_support;
+pub mod inference;
 pub mod system_interface;
 pub mod dynamic_types;
+pub mod collections;
+pub mod formatting;
+pub mod fact_updates;
+pub mod strings;
+pub mod random;
+pub mod shutdown;
+pub mod panic;
 
 use std::collections::HashMap;
+use std::ffi::{c_char, CStr};
 use std::sync::{Arc, Mutex};
 
-pub use logic_engine::LogicEngine;
+use crate::semantic::AnnotatedProgram;
+
+pub use atom::Atom;
+pub use logic_engine::{FactValue, LogicEngine};
+pub use temporal::Validity;
+pub use belief_revision::{Contradiction, Resolution, RevisionHandler, RevisionPolicy, Revision};
+pub use fact_store::{IndexStats, KnowledgeStorage};
+pub use knowledge_image::{ImageArg, ImageGoal, KnowledgeImageBuilder};
+pub use knowledge_watch::{FactChange, WatchEvent, WatchHit, WatchId, Watchpoint};
+pub use logic_trace::{LogicTracer, TraceEvent, TraceFilter, TraceOutput, TracePort};
+pub use heap_snapshot::{HeapSnapshot, ObjectId};
+pub use query_plan::{GoalAccess, PlanStep, QueryPlan, QueryStats};
+pub use proof::{ProofStep, ProofTree, Solution};
 pub use dynamic_types::{AlbayanValue, AlbayanList, AlbayanValueTag};
+pub use collections::AlbayanMap;
+pub use inference::{InferenceHandle, InferencePool};
 
 /// Main runtime system for AlBayan
 pub struct Runtime {
@@ -31,6 +65,9 @@ pub struct Runtime {
 
     /// Runtime configuration
     config: RuntimeConfig,
+
+    /// Set by the first `shutdown`
+    shut_down: std::sync::atomic::AtomicBool,
 }
 
 /// Runtime configuration
@@ -50,6 +87,14 @@ pub struct RuntimeConfig {
 
     /// Debug mode
     pub debug_mode: bool,
+
+    /// Seed for the shared random generator (see `random`); `None` keeps the current one
+    pub seed: Option<u64>,
+
+    /// Where the logic engine keeps its facts; `Runtime::try_with_config`
+    /// fails when the store cannot be opened, `Runtime::with_config` warns and
+    /// falls back to memory
+    pub knowledge_storage: KnowledgeStorage,
 }
 
 impl Default for RuntimeConfig {
@@ -60,6 +105,8 @@ impl Default for RuntimeConfig {
             max_memory: 1024 * 1024 * 1024, // 1GB
             enable_gc: true,
             debug_mode: false,
+            seed: None,
+            knowledge_storage: KnowledgeStorage::Memory,
         }
     }
 }
@@ -70,9 +117,29 @@ impl Runtime {
         Self::with_config(RuntimeConfig::default())
     }
 
-    /// Create a new runtime with custom configuration
+    /// Create a new runtime with custom configuration, keeping facts in
+    /// memory when the configured store cannot be opened
     pub fn with_config(config: RuntimeConfig) -> Self {
-        let logic_engine = Arc::new(Mutex::new(LogicEngine::new()));
+        let logic_engine = LogicEngine::with_storage(&config.knowledge_storage).unwrap_or_else(|error| {
+            eprintln!("warning: falling back to an in-memory knowledge base: {}", error);
+            LogicEngine::new()
+        });
+        Self::with_logic_engine(config, logic_engine)
+    }
+
+    /// Create a new runtime with custom configuration, failing when the
+    /// configured store cannot be opened
+    pub fn try_with_config(config: RuntimeConfig) -> Result<Self, RuntimeError> {
+        let logic_engine = LogicEngine::with_storage(&config.knowledge_storage)?;
+        Ok(Self::with_logic_engine(config, logic_engine))
+    }
+
+    fn with_logic_engine(config: RuntimeConfig, logic_engine: LogicEngine) -> Self {
+        if let Some(seed) = config.seed {
+            random::set_seed(seed);
+        }
+
+        let logic_engine = Arc::new(Mutex::new(logic_engine));
         let memory_manager = Arc::new(Mutex::new(memory::MemoryManager::new(config.max_memory)));
         let system_interface = Arc::new(system_interface::SystemInterface::new());
 
@@ -88,6 +155,7 @@ impl Runtime {
             ai_engine,
             system_interface,
             config,
+            shut_down: std::sync::atomic::AtomicBool::new(false),
         }
     }
 
@@ -118,8 +186,13 @@ impl Runtime {
         Ok(())
     }
 
-    /// Shutdown the runtime
-    pub fn shutdown(&mut self) -> Result<(), RuntimeError> {
+    /// Shutdown the runtime, closing the engines and flushing persisted facts;
+    /// only the first call does anything
+    pub fn shutdown(&self) -> Result<(), RuntimeError> {
+        if self.shut_down.swap(true, std::sync::atomic::Ordering::SeqCst) {
+            return Ok(());
+        }
+
         if self.config.debug_mode {
             println!("Shutting down AlBayan Runtime...");
         }
@@ -136,6 +209,8 @@ impl Runtime {
             logic_engine.shutdown()?;
         }
 
+        self.system_interface.flush()?;
+
         // Clean up memory
         let mut memory_manager = self.memory_manager.lock().unwrap();
         memory_manager.cleanup()?;
@@ -167,14 +242,37 @@ impl Runtime {
         self.system_interface.clone()
     }
 
-    /// Execute a logic query
-    pub fn query_solve(&self, query: &str) -> Result<Vec<HashMap<String, String>>, RuntimeError> {
+    /// Run a read-only logic operation on a snapshot of the knowledge base.
+    /// The engine is locked only to take the snapshot and to count the
+    /// queries after, so queries from many threads run in parallel, each
+    /// seeing the knowledge as it was when it started.
+    fn with_snapshot<T>(
+        &self,
+        solve: impl FnOnce(&mut LogicEngine) -> Result<T, RuntimeError>,
+    ) -> Result<T, RuntimeError> {
         if !self.config.enable_logic {
             return Err(RuntimeError::FeatureDisabled("Logic programming".to_string()));
         }
 
-        let mut logic_engine = self.logic_engine.lock().unwrap();
-        logic_engine.solve_query(query)
+        let mut snapshot = self.logic_engine.lock().unwrap().snapshot();
+        let solved = solve(&mut snapshot);
+        self.logic_engine.lock().unwrap().absorb_queries(snapshot);
+        solved
+    }
+
+    /// Execute a logic query
+    pub fn query_solve(&self, query: &str) -> Result<Vec<HashMap<String, String>>, RuntimeError> {
+        self.with_snapshot(|engine| engine.solve_query(query))
+    }
+
+    /// Explain a logic query and report the rows each of its steps produced
+    pub fn explain_query(&self, query: &str) -> Result<QueryPlan, RuntimeError> {
+        self.with_snapshot(|engine| engine.explain_query(query))
+    }
+
+    /// Solve a logic query, recording how each solution was derived
+    pub fn query_with_proofs(&self, query: &str) -> Result<Vec<Solution>, RuntimeError> {
+        self.with_snapshot(|engine| engine.solve_query_with_proofs(query))
     }
 
     /// Assert a fact into the knowledge base
@@ -187,6 +285,57 @@ impl Runtime {
         logic_engine.assert_fact(fact)
     }
 
+    /// Assert a fact that holds only during `valid`
+    pub fn assert_fact_at(&self, fact: &str, valid: Validity) -> Result<(), RuntimeError> {
+        if !self.config.enable_logic {
+            return Err(RuntimeError::FeatureDisabled("Logic programming".to_string()));
+        }
+
+        let mut logic_engine = self.logic_engine.lock().unwrap();
+        logic_engine.assert_fact_at(fact, valid)
+    }
+
+    /// Add an integrity constraint that later asserts are checked against
+    pub fn add_constraint(&self, constraint: &str) -> Result<(), RuntimeError> {
+        if !self.config.enable_logic {
+            return Err(RuntimeError::FeatureDisabled("Logic programming".to_string()));
+        }
+
+        let mut logic_engine = self.logic_engine.lock().unwrap();
+        logic_engine.add_constraint(constraint)
+    }
+
+    /// Load a knowledge image embedded by the compiler
+    pub fn load_knowledge(&self, image: &[u8]) -> Result<usize, RuntimeError> {
+        if !self.config.enable_logic {
+            return Err(RuntimeError::FeatureDisabled("Logic programming".to_string()));
+        }
+
+        let mut logic_engine = self.logic_engine.lock().unwrap();
+        logic_engine.load_image(image)
+    }
+
+    /// Keep a hash index on `argument` (counting from 0) of `predicate`'s facts
+    pub fn index_argument(&self, predicate: &str, argument: usize) -> Result<(), RuntimeError> {
+        if !self.config.enable_logic {
+            return Err(RuntimeError::FeatureDisabled("Logic programming".to_string()));
+        }
+
+        let mut logic_engine = self.logic_engine.lock().unwrap();
+        logic_engine.index_argument(predicate, argument)
+    }
+
+    /// Load the knowledge an analyzed program declares, as the interpreter
+    /// and tools run it without a compiled image
+    pub fn load_program(&self, program: &AnnotatedProgram) -> Result<usize, RuntimeError> {
+        if !self.config.enable_logic {
+            return Err(RuntimeError::FeatureDisabled("Logic programming".to_string()));
+        }
+
+        let mut logic_engine = self.logic_engine.lock().unwrap();
+        logic_engine.load_program(program)
+    }
+
     /// Retract a fact from the knowledge base
     pub fn retract_fact(&self, fact: &str) -> Result<(), RuntimeError> {
         if !self.config.enable_logic {
@@ -197,6 +346,26 @@ impl Runtime {
         logic_engine.retract_fact(fact)
     }
 
+    /// Assert `relation(values...)` without going through fact text
+    pub fn assert_values(&self, relation: &str, values: &[FactValue]) -> Result<(), RuntimeError> {
+        if !self.config.enable_logic {
+            return Err(RuntimeError::FeatureDisabled("Logic programming".to_string()));
+        }
+
+        let mut logic_engine = self.logic_engine.lock().unwrap();
+        logic_engine.assert_values(relation, values)
+    }
+
+    /// Retract `relation(values...)` without going through fact text
+    pub fn retract_values(&self, relation: &str, values: &[FactValue]) -> Result<(), RuntimeError> {
+        if !self.config.enable_logic {
+            return Err(RuntimeError::FeatureDisabled("Logic programming".to_string()));
+        }
+
+        let mut logic_engine = self.logic_engine.lock().unwrap();
+        logic_engine.retract_values(relation, values)
+    }
+
     /// Print a string (runtime function called from generated code)
     pub fn print_string(&self, s: &str) -> Result<(), RuntimeError> {
         self.system_interface.print(s)
@@ -235,6 +404,7 @@ impl Runtime {
             facts_count: logic_engine.facts_count(),
             rules_count: logic_engine.rules_count(),
             queries_executed: logic_engine.queries_executed(),
+            fact_index: logic_engine.index_stats(),
         }
     }
 }
@@ -247,6 +417,7 @@ pub struct RuntimeStats {
     pub facts_count: usize,
     pub rules_count: usize,
     pub queries_executed: usize,
+    pub fact_index: IndexStats,
 }
 
 /// Runtime errors
@@ -255,6 +426,9 @@ pub enum RuntimeError {
     #[error("Logic engine error: {0}")]
     LogicError(String),
 
+    #[error("Contradiction: {0}")]
+    Contradiction(belief_revision::Contradiction),
+
     #[error("Memory error: {0}")]
     MemoryError(String),
 
@@ -267,6 +441,9 @@ pub enum RuntimeError {
     #[error("Feature disabled: {0}")]
     FeatureDisabled(String),
 
+    #[error("Built-in library error: {0}")]
+    BuiltinError(#[from] crate::builtin_libraries::BuiltinError),
+
     #[error("Runtime not initialized")]
     NotInitialized,
 
@@ -289,7 +466,10 @@ pub extern "C" fn albayan_rt_print_string(ptr: *const u8, len: usize) {
     unsafe {
         let slice = std::slice::from_raw_parts(ptr, len);
         if let Ok(s) = std::str::from_utf8(slice) {
+            use std::io::Write;
             print!("{}", s);
+            // Compiled programs exit through C, which never flushes Rust's stdout
+            let _ = std::io::stdout().flush();
         }
     }
 }
@@ -312,18 +492,110 @@ pub extern "C" fn albayan_rt_dealloc(ptr: *mut u8, size: usize) {
     unsafe { std::alloc::dealloc(ptr, layout) }
 }
 
+/// Assert the fact whose text is the `len` bytes at `ptr`, e.g.
+/// `parent("ali", "sara")`
 #[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn albayan_rt_assert_fact(ptr: *const u8, len: usize) -> i32 {
-    // Placeholder implementation
-    // In a real implementation, this would interact with the logic engine
-    0 // Success
+    if ptr.is_null() {
+        return -1;
+    }
+    // SAFETY: the caller passes `len` bytes of fact text
+    let fact = String::from_utf8_lossy(unsafe { std::slice::from_raw_parts(ptr, len) });
+    let asserted = init_global_runtime().and_then(|()| {
+        get_global_runtime()
+            .ok_or(RuntimeError::NotInitialized)?
+            .assert_fact(&fact)
+    });
+    match asserted {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("Cannot assert `{}`: {}", fact, error);
+            -1
+        }
+    }
+}
+
+/// Load the knowledge image a compiled program embeds, before its `main` runs
+#[no_mangle]
+pub extern "C" fn albayan_rt_load_knowledge(ptr: *const u8, len: usize) -> i32 {
+    if ptr.is_null() {
+        return -1;
+    }
+    // SAFETY: generated code passes a constant global of `len` bytes
+    let image = unsafe { std::slice::from_raw_parts(ptr, len) };
+    let loaded = init_global_runtime().and_then(|()| {
+        get_global_runtime()
+            .ok_or(RuntimeError::NotInitialized)?
+            .load_knowledge(image)
+    });
+    match loaded {
+        Ok(_) => 0,
+        Err(error) => {
+            eprintln!("Cannot load the program's knowledge base: {}", error);
+            -1
+        }
+    }
+}
+
+/// The solutions of a `query` expression, waiting to be copied into the
+/// list the generated code allocates for them
+pub struct QuerySolutions(Vec<AlbayanMap>);
+
+/// Solve the goals of a `query` expression against the program's knowledge
+/// base and store the number of solutions in `length`. Each solution is a map
+/// from the variables of the goals to their values as text; a query that
+/// fails to run reports why and has no solutions.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn albayan_rt_query_solve(query: *const c_char, length: *mut i64) -> *mut QuerySolutions {
+    if query.is_null() || length.is_null() {
+        return std::ptr::null_mut();
+    }
+    // SAFETY: generated code passes a constant NUL-terminated string
+    let query = unsafe { CStr::from_ptr(query) }.to_string_lossy();
+    let solved = init_global_runtime().and_then(|()| {
+        get_global_runtime()
+            .ok_or(RuntimeError::NotInitialized)?
+            .query_solve(&query)
+    });
+    let solutions = solved.unwrap_or_else(|error| {
+        eprintln!("Cannot solve `{}`: {}", query, error);
+        Vec::new()
+    });
+
+    let maps: Vec<AlbayanMap> = solutions
+        .into_iter()
+        .map(|solution| {
+            let mut map = AlbayanMap::new(std::mem::size_of::<*mut c_char>());
+            for (variable, value) in solution {
+                let text = strings::into_raw(logic_engine::binding_text(&value));
+                map.insert(collections::MapKey::String(variable), &(text as usize).to_ne_bytes());
+            }
+            map
+        })
+        .collect();
+    // SAFETY: checked for null above
+    unsafe { length.write(maps.len() as i64) };
+    Box::into_raw(Box::new(QuerySolutions(maps)))
 }
 
+/// Move the solutions into `maps`, which has room for the `length` that
+/// `albayan_rt_query_solve` stored, and release them
 #[no_mangle]
-pub extern "C" fn albayan_rt_query_solve(ptr: *const u8, len: usize) -> i32 {
-    // Placeholder implementation
-    // In a real implementation, this would execute the query and return results
-    0 // Success
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn albayan_rt_query_solutions_into(solutions: *mut QuerySolutions, maps: *mut *mut AlbayanMap) {
+    if solutions.is_null() {
+        return;
+    }
+    // SAFETY: `solutions` comes from `albayan_rt_query_solve` and is used once
+    let QuerySolutions(solutions) = *unsafe { Box::from_raw(solutions) };
+    if maps.is_null() {
+        return;
+    }
+    for (index, map) in solutions.into_iter().enumerate() {
+        unsafe { maps.add(index).write(Box::into_raw(Box::new(map))) };
+    }
 }
 
 /// Global runtime instance (for C functions)
@@ -377,10 +649,36 @@ mod tests {
             max_memory: 1024,
             enable_gc: false,
             debug_mode: true,
+            seed: None,
+            knowledge_storage: KnowledgeStorage::Memory,
         };
 
         let runtime = Runtime::with_config(config.clone());
         assert_eq!(runtime.config.enable_logic, false);
         assert_eq!(runtime.config.max_memory, 1024);
     }
+
+    #[test]
+    fn test_queries_are_counted_once_solved_on_a_snapshot() {
+        let runtime = Runtime::new();
+        runtime.assert_fact("parent(john, mary).").unwrap();
+        assert_eq!(runtime.query_solve("parent(john, Child)").unwrap()[0]["Child"], "mary");
+        runtime.explain_query("parent(Who, mary)").unwrap();
+        assert_eq!(runtime.get_stats().queries_executed, 2);
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    #[test]
+    fn test_unavailable_storage_fails_or_falls_back_to_memory() {
+        let config = RuntimeConfig {
+            knowledge_storage: KnowledgeStorage::Sqlite("facts.db".into()),
+            ..RuntimeConfig::default()
+        };
+        assert!(Runtime::try_with_config(config.clone()).is_err());
+
+        let runtime = Runtime::with_config(config);
+        runtime.assert_fact("parent(john, mary).").unwrap();
+        assert_eq!(runtime.logic_engine().lock().unwrap().storage_backend(), "memory");
+        assert_eq!(runtime.get_stats().facts_count, 1);
+    }
 }
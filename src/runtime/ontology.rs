@@ -0,0 +1,144 @@
+//! # Ontology
+//!
+//! Concepts ordered by an `is_a` hierarchy, for the logic engine to type the
+//! atoms of its facts. A concept may have several parents (`concept bat:
+//! mammal, flyer;`) but no cycles. Individuals are placed in the hierarchy by
+//! `is_a(tom, cat)` facts; a relation argument declared with a concept as its
+//! type (`relation eats(animal, food);`) accepts an individual of that concept
+//! or any subconcept, and the concept names themselves.
+//!
+//! The engine infers the facts the hierarchy implies: `is_a(tom, animal)` from
+//! `is_a(tom, cat)`, and, for a relation whose first argument is typed by a
+//! concept, the facts stated about a concept for each of its subconcepts and
+//! individuals (`can(bird, fly)` gives `can(tweety, fly)`).
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use indexmap::IndexMap;
+
+/// Predicate that places individuals (and concepts) in the hierarchy
+pub const IS_A: &str = "is_a";
+
+/// Errors defining the hierarchy
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum OntologyError {
+    #[error("concept {0} is already defined")]
+    Redefined(String),
+
+    #[error("concept {concept} cannot be a {parent}: {parent} is already a {concept}")]
+    Cycle { concept: String, parent: String },
+}
+
+/// The concept hierarchy
+#[derive(Debug, Clone, Default)]
+pub struct Ontology {
+    /// Direct parents of each concept, in definition order
+    parents: IndexMap<String, Vec<String>>,
+}
+
+impl Ontology {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Define `concept` below `parents`; a parent not defined yet becomes a
+    /// root concept
+    pub fn define(&mut self, concept: &str, parents: &[&str]) -> Result<(), OntologyError> {
+        if self.parents.get(concept).is_some_and(|existing| !existing.is_empty()) {
+            return Err(OntologyError::Redefined(concept.to_string()));
+        }
+        if let Some(parent) = parents.iter().find(|parent| self.is_subconcept(parent, concept)) {
+            return Err(OntologyError::Cycle { concept: concept.to_string(), parent: parent.to_string() });
+        }
+        for parent in parents {
+            self.parents.entry(parent.to_string()).or_default();
+        }
+        self.parents.insert(concept.to_string(), parents.iter().map(|parent| parent.to_string()).collect());
+        Ok(())
+    }
+
+    pub fn is_concept(&self, name: &str) -> bool {
+        self.parents.contains_key(name)
+    }
+
+    /// The concepts in definition order
+    pub fn concepts(&self) -> impl Iterator<Item = &str> {
+        self.parents.keys().map(String::as_str)
+    }
+
+    pub fn parents(&self, concept: &str) -> &[String] {
+        self.parents.get(concept).map_or(&[], Vec::as_slice)
+    }
+
+    /// Every concept above `concept`, nearest first, each once
+    pub fn ancestors(&self, concept: &str) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut ancestors = Vec::new();
+        let mut queue: VecDeque<&str> = self.parents(concept).iter().map(String::as_str).collect();
+        while let Some(next) = queue.pop_front() {
+            if seen.insert(next) {
+                ancestors.push(next.to_string());
+                queue.extend(self.parents(next).iter().map(String::as_str));
+            }
+        }
+        ancestors
+    }
+
+    /// Every concept below `concept`, each once
+    pub fn descendants(&self, concept: &str) -> Vec<String> {
+        let children = self.children();
+        let mut seen = HashSet::new();
+        let mut descendants = Vec::new();
+        let mut queue: VecDeque<&str> = children.get(concept).cloned().unwrap_or_default().into();
+        while let Some(next) = queue.pop_front() {
+            if seen.insert(next) {
+                descendants.push(next.to_string());
+                queue.extend(children.get(next).into_iter().flatten());
+            }
+        }
+        descendants
+    }
+
+    /// Whether `concept` is `ancestor` or below it
+    pub fn is_subconcept(&self, concept: &str, ancestor: &str) -> bool {
+        concept == ancestor || self.ancestors(concept).iter().any(|candidate| candidate == ancestor)
+    }
+
+    fn children(&self) -> HashMap<&str, Vec<&str>> {
+        let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (concept, parents) in &self.parents {
+            for parent in parents {
+                children.entry(parent.as_str()).or_default().push(concept.as_str());
+            }
+        }
+        children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hierarchy_queries_and_cycles() {
+        let mut ontology = Ontology::new();
+        ontology.define("mammal", &["animal"]).unwrap();
+        ontology.define("bat", &["mammal", "flyer"]).unwrap();
+        ontology.define("animal", &["thing"]).unwrap();
+
+        assert_eq!(ontology.ancestors("bat"), ["mammal", "flyer", "animal", "thing"]);
+        assert_eq!(ontology.descendants("animal"), ["mammal", "bat"]);
+        assert!(ontology.is_subconcept("bat", "thing"));
+        assert!(!ontology.is_subconcept("flyer", "animal"));
+
+        assert_eq!(
+            ontology.define("thing", &["bat"]),
+            Err(OntologyError::Cycle { concept: "thing".to_string(), parent: "bat".to_string() })
+        );
+        assert_eq!(ontology.define("mammal", &["thing"]), Err(OntologyError::Redefined("mammal".to_string())));
+    }
+}
@@ -0,0 +1,72 @@
+//! # Panics
+//!
+//! A failed `assert`, `assert_eq`, `assert_ne` or `assert_true`, or a call to
+//! `panic`, in a natively compiled program ends in [`albayan_rt_panic`]. It
+//! prints the message and the source location semantic analysis recorded for
+//! the call, a backtrace when `ALBAYAN_BACKTRACE` is set, and exits with
+//! [`PANIC_EXIT_CODE`] after shutting the runtime down so exit hooks still run.
+
+// The pointers come from generated code, which passes NUL-terminated strings;
+// null pointers read as empty strings
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use std::backtrace::Backtrace;
+use std::ffi::{c_char, CStr};
+
+/// Exit status of a program that panicked, as for Rust programs
+pub const PANIC_EXIT_CODE: i32 = 101;
+
+/// The text printed for a panic; `location` may be empty
+pub fn panic_report(message: &str, location: &str) -> String {
+    if location.is_empty() {
+        format!("panicked: {}", message)
+    } else {
+        format!("panicked at {}:\n{}", location, message)
+    }
+}
+
+/// Whether `ALBAYAN_BACKTRACE` asks for backtraces
+pub fn backtrace_enabled() -> bool {
+    std::env::var_os("ALBAYAN_BACKTRACE").is_some_and(|value| value != "0")
+}
+
+/// Report a panic, shut the runtime down and exit
+pub fn abort_with(message: &str, location: &str) -> ! {
+    eprintln!("{}", panic_report(message, location));
+    if backtrace_enabled() {
+        eprintln!("stack backtrace:\n{}", Backtrace::force_capture());
+    } else {
+        eprintln!("note: run with `ALBAYAN_BACKTRACE=1` to display a backtrace");
+    }
+    if let Err(error) = super::shutdown::shutdown() {
+        eprintln!("Shutdown failed: {}", error);
+    }
+    std::process::exit(PANIC_EXIT_CODE)
+}
+
+/// Panic with a NUL-terminated message at a NUL-terminated `file:line:column`
+#[no_mangle]
+pub extern "C" fn albayan_rt_panic(message: *const c_char, location: *const c_char) -> ! {
+    let read = |text: *const c_char| {
+        if text.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(text) }.to_string_lossy().into_owned()
+        }
+    };
+    abort_with(&read(message), &read(location))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panic_report() {
+        assert_eq!(
+            panic_report("assertion failed: x > 0", "main.ab:3:5"),
+            "panicked at main.ab:3:5:\nassertion failed: x > 0"
+        );
+        assert_eq!(panic_report("boom", ""), "panicked: boom");
+    }
+}
@@ -0,0 +1,99 @@
+//! # Proofs
+//!
+//! What `LogicEngine::solve_query_with_proofs` reports about each solution:
+//! its variable bindings and the derivation tree of every goal of the query.
+//! A node of the tree is a goal as the solution instantiated it, with how it
+//! was established: a stored fact, a fact the ontology implies, a built-in,
+//! a negation, an aggregate, a tabled answer, or a rule whose body goals are
+//! the node's premises.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+/// How a goal of a proof was established
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", content = "source", rename_all = "snake_case")]
+pub enum ProofStep {
+    /// A stored fact
+    Fact,
+    /// A fact the ontology implies from the stored fact given
+    Ontology(String),
+    /// A rule, as written, whose body goals are the premises
+    Rule(String),
+    /// A built-in predicate such as `=`, `<` or `is`
+    Builtin,
+    /// Negation as failure: the goal has no solution
+    Negation,
+    /// An aggregate over the solutions of its goal
+    Aggregate,
+    /// An answer in the table of a tabled predicate, whose derivation the
+    /// table does not keep
+    Table,
+    /// `holds_at`, whose premise is its goal solved at its time
+    HoldsAt,
+}
+
+impl fmt::Display for ProofStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofStep::Fact => write!(f, "fact"),
+            ProofStep::Ontology(source) => write!(f, "ontology, from {}", source),
+            ProofStep::Rule(rule) => write!(f, "rule {}", rule),
+            ProofStep::Builtin => write!(f, "builtin"),
+            ProofStep::Negation => write!(f, "no proof"),
+            ProofStep::Aggregate => write!(f, "aggregate"),
+            ProofStep::Table => write!(f, "tabled answer"),
+            ProofStep::HoldsAt => write!(f, "holds at"),
+        }
+    }
+}
+
+/// The derivation of one goal of a solution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProofTree {
+    /// The goal with the solution's bindings applied, e.g. `parent("ali", "sara")`
+    pub goal: String,
+    pub step: ProofStep,
+    /// Derivations of the goals the step rests on, in the order they were solved
+    pub premises: Vec<ProofTree>,
+}
+
+impl ProofTree {
+    /// Number of goals in the tree, this one included
+    pub fn size(&self) -> usize {
+        1 + self.premises.iter().map(ProofTree::size).sum::<usize>()
+    }
+
+    fn write_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        write!(f, "{:indent$}{}  [{}]", "", self.goal, self.step, indent = depth * 2)?;
+        for premise in &self.premises {
+            writeln!(f)?;
+            premise.write_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// One line per goal, each premise indented below the goal it supports
+impl fmt::Display for ProofTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
+
+/// A solution of a query together with how it was derived
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Solution {
+    /// Text of the value of each of the query's variables
+    pub bindings: BTreeMap<String, String>,
+    pub(super) proof: Vec<ProofTree>,
+}
+
+impl Solution {
+    /// The derivation of each goal of the query, in the order they were solved
+    pub fn proof(&self) -> &[ProofTree] {
+        &self.proof
+    }
+}
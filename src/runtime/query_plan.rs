@@ -0,0 +1,142 @@
+//! # Query Plans
+//!
+//! What `LogicEngine::explain_query` reports about a query: the order in which
+//! the solver takes its goals, how each goal reaches its facts, the fact and
+//! rule counts the solver estimates from, and the rows each step actually
+//! produced. Per-query timings collected over a session are kept as
+//! [`QueryStats`] so the slowest queries can be listed.
+
+use std::fmt;
+use std::time::Duration;
+
+/// How a goal finds the facts it is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoalAccess {
+    /// Evaluated by a built-in predicate (`=`, `<`, `is`, ...) without facts
+    Builtin,
+    /// The first argument is known and the fact store looks it up in its index
+    IndexLookup,
+    /// Another argument, counting from 0, is known and has a hash index
+    ArgumentIndex(usize),
+    /// Every fact of the predicate is tried
+    Scan,
+}
+
+impl fmt::Display for GoalAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GoalAccess::Builtin => "builtin",
+            GoalAccess::IndexLookup => "index(first arg)",
+            GoalAccess::ArgumentIndex(argument) => return write!(f, "index(arg {})", argument + 1),
+            GoalAccess::Scan => "scan",
+        })
+    }
+}
+
+/// One goal of a query plan, in execution order
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanStep {
+    /// The goal as written, e.g. `parent(X, Y)` or `not likes(X, fish)`
+    pub goal: String,
+    pub access: GoalAccess,
+    /// Facts stored for the goal's predicate
+    pub estimated_facts: usize,
+    /// Rules whose head is the goal's predicate
+    pub estimated_rules: usize,
+    /// Solutions of this goal joined with every earlier step
+    pub actual_rows: usize,
+}
+
+/// The plan of an explained query together with what its execution produced
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryPlan {
+    pub query: String,
+    /// Fact storage backend the plan ran against ("memory" or "sqlite")
+    pub backend: &'static str,
+    pub steps: Vec<PlanStep>,
+    pub solutions: usize,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for QueryPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let goal_width = self.steps.iter().map(|step| step.goal.len()).max().unwrap_or(0).max(4);
+        writeln!(f, "EXPLAIN {} ({} store)", self.query, self.backend)?;
+        writeln!(
+            f,
+            "  {:<4}  {:<goal_width$}  {:<16}  {:>10}  {:>10}  {:>11}",
+            "step", "goal", "access", "est. facts", "est. rules", "actual rows",
+        )?;
+        for (index, step) in self.steps.iter().enumerate() {
+            writeln!(
+                f,
+                "  {:<4}  {:<goal_width$}  {:<16}  {:>10}  {:>10}  {:>11}",
+                index + 1,
+                step.goal,
+                step.access.to_string(),
+                step.estimated_facts,
+                step.estimated_rules,
+                step.actual_rows,
+            )?;
+        }
+        write!(f, "{} solution(s) in {:.3} ms", self.solutions, self.elapsed.as_secs_f64() * 1000.0)
+    }
+}
+
+/// Timings of one query text over a session
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryStats {
+    pub query: String,
+    pub executions: usize,
+    pub total_time: Duration,
+    pub slowest_time: Duration,
+    /// Solutions found by the most recent execution
+    pub last_solutions: usize,
+}
+
+impl QueryStats {
+    pub(super) fn new(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            executions: 0,
+            total_time: Duration::ZERO,
+            slowest_time: Duration::ZERO,
+            last_solutions: 0,
+        }
+    }
+
+    pub(super) fn record(&mut self, elapsed: Duration, solutions: usize) {
+        self.executions += 1;
+        self.total_time += elapsed;
+        self.slowest_time = self.slowest_time.max(elapsed);
+        self.last_solutions = solutions;
+    }
+
+    /// Add the executions recorded in `other` for the same query, which ran
+    /// after these
+    pub(super) fn merge(&mut self, other: &QueryStats) {
+        self.executions += other.executions;
+        self.total_time += other.total_time;
+        self.slowest_time = self.slowest_time.max(other.slowest_time);
+        self.last_solutions = other.last_solutions;
+    }
+
+    /// Mean time per execution
+    pub fn average_time(&self) -> Duration {
+        self.total_time / self.executions.max(1) as u32
+    }
+}
+
+impl fmt::Display for QueryStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} run(s), slowest {:.3} ms, average {:.3} ms, {} solution(s) last run",
+            self.query,
+            self.executions,
+            self.slowest_time.as_secs_f64() * 1000.0,
+            self.average_time().as_secs_f64() * 1000.0,
+            self.last_solutions,
+        )
+    }
+}
@@ -0,0 +1,112 @@
+//! # Deterministic Random Numbers
+//!
+//! Seeded random number generation shared by `ai::Tensor::random`, dropout,
+//! ExpertExplorer exploration, equation mutation and the `random_*` builtins.
+//!
+//! The seed comes from `RuntimeConfig::seed`, the `--seed` CLI flag or the
+//! `ALBAYAN_SEED` environment variable; without one it is drawn from entropy.
+//! Either way `seed()` reports it, so any run can be reproduced. Each thread owns
+//! a generator started from the seed, so draws on one thread never shift the
+//! sequence seen by another.
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// Environment variable holding the seed of a run
+pub const SEED_ENV: &str = "ALBAYAN_SEED";
+
+/// Seed for generators created from now on
+static SEED: OnceLock<AtomicU64> = OnceLock::new();
+
+thread_local! {
+    static GENERATOR: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+fn global_seed() -> &'static AtomicU64 {
+    SEED.get_or_init(|| {
+        let seed = std::env::var(SEED_ENV)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or_else(|| rand::thread_rng().next_u64());
+        AtomicU64::new(seed)
+    })
+}
+
+/// The seed of the current run
+pub fn seed() -> u64 {
+    global_seed().load(Ordering::Relaxed)
+}
+
+/// Restart the calling thread's generator from `seed`; threads that have not
+/// drawn a number yet will start from it as well
+pub fn set_seed(seed: u64) {
+    global_seed().store(seed, Ordering::Relaxed);
+    GENERATOR.with(|generator| *generator.borrow_mut() = Some(StdRng::seed_from_u64(seed)));
+}
+
+/// Run `f` with the calling thread's seeded generator
+pub fn with_rng<R>(f: impl FnOnce(&mut StdRng) -> R) -> R {
+    GENERATOR.with(|generator| {
+        let mut generator = generator.borrow_mut();
+        f(generator.get_or_insert_with(|| StdRng::seed_from_u64(seed())))
+    })
+}
+
+/// A float uniformly drawn from `[0, 1)`
+pub fn random_f64() -> f64 {
+    with_rng(|rng| rng.gen())
+}
+
+/// An integer uniformly drawn from `[low, high]`; the bounds may come in either order
+pub fn random_int(low: i64, high: i64) -> i64 {
+    with_rng(|rng| rng.gen_range(low.min(high)..=low.max(high)))
+}
+
+/// C-compatible runtime functions behind `random_seed`, `random_int` and `random_float`
+#[no_mangle]
+pub extern "C" fn albayan_rt_random_seed(seed: i64) {
+    set_seed(seed as u64);
+}
+
+#[no_mangle]
+pub extern "C" fn albayan_rt_random_int(low: i64, high: i64) -> i64 {
+    random_int(low, high)
+}
+
+#[no_mangle]
+pub extern "C" fn albayan_rt_random_float() -> f64 {
+    random_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Both tests use the same seed, so running them in parallel cannot change the global one
+    const TEST_SEED: u64 = 2013;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        set_seed(TEST_SEED);
+        let first: Vec<i64> = (0..8).map(|_| random_int(1, 6)).collect();
+        let float = random_f64();
+
+        set_seed(TEST_SEED);
+        let second: Vec<i64> = (0..8).map(|_| random_int(1, 6)).collect();
+        assert_eq!(first, second);
+        assert_eq!(float, random_f64());
+        assert_eq!(seed(), TEST_SEED);
+        assert!(first.iter().all(|value| (1..=6).contains(value)));
+    }
+
+    #[test]
+    fn test_threads_start_from_the_seed() {
+        set_seed(TEST_SEED);
+        let here = random_f64();
+        let there = std::thread::spawn(random_f64).join().unwrap();
+        assert_eq!(here, there);
+    }
+}
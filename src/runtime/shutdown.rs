@@ -0,0 +1,191 @@
+//! # Graceful Shutdown
+//!
+//! `albayan run` and `albayan repl --serve` stop through [`shutdown`] when they
+//! finish or receive SIGINT/SIGTERM, rather than being killed mid-write:
+//! user exit hooks run first (latest registered first), then the global
+//! runtime closes its engines and flushes persisted facts, the built-in
+//! library state is saved and the canvas stream is closed.
+//!
+//! AlBayan programs register hooks with `on_exit(cleanup)`, where `cleanup` is
+//! a function without parameters; native executables reach the registry
+//! through [`albayan_rt_on_exit`].
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once};
+#[cfg(feature = "signals")]
+use std::thread;
+
+use super::RuntimeError;
+
+type ExitHook = Box<dyn FnOnce() + Send>;
+
+static EXIT_HOOKS: Mutex<Vec<ExitHook>> = Mutex::new(Vec::new());
+static SHUT_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Run `hook` when the process shuts down
+pub fn on_exit(hook: impl FnOnce() + Send + 'static) {
+    EXIT_HOOKS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(Box::new(hook));
+}
+
+/// Run the registered exit hooks, latest first; each runs at most once and a
+/// panicking hook does not stop the others
+pub fn run_exit_hooks() {
+    let hooks = std::mem::take(&mut *EXIT_HOOKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+    for hook in hooks.into_iter().rev() {
+        if panic::catch_unwind(AssertUnwindSafe(hook)).is_err() {
+            eprintln!("An exit hook panicked");
+        }
+    }
+}
+
+/// Shut the process's runtime down; only the first call does anything
+pub fn shutdown() -> Result<(), RuntimeError> {
+    if SHUT_DOWN.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    run_exit_hooks();
+    let runtime_result = match super::get_global_runtime() {
+        Some(runtime) => runtime.shutdown(),
+        None => Ok(()),
+    };
+    let library_result = crate::builtin_libraries::ffi::save_library_state().map_err(RuntimeError::from);
+    crate::builtin_libraries::ffi::close_canvas();
+
+    runtime_result.and(library_result)
+}
+
+/// A signal that stops the process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// SIGINT, e.g. Ctrl-C
+    Interrupt,
+    /// SIGTERM, e.g. from a service manager
+    Terminate,
+}
+
+impl Signal {
+    /// Conventional exit status of a process stopped by the signal
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Signal::Interrupt => 130,
+            Signal::Terminate => 143,
+        }
+    }
+}
+
+impl std::fmt::Display for Signal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Signal::Interrupt => write!(f, "SIGINT"),
+            Signal::Terminate => write!(f, "SIGTERM"),
+        }
+    }
+}
+
+/// Call [`shutdown`] and exit on SIGINT or SIGTERM. The signals are awaited on
+/// a thread of their own, so this works whether or not the caller runs in an
+/// async runtime; later calls do nothing.
+#[cfg(feature = "signals")]
+pub fn install_signal_handlers() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let spawned = thread::Builder::new().name("albayan-signals".to_string()).spawn(|| {
+            let signals = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(signals) => signals,
+                Err(error) => {
+                    eprintln!("Cannot listen for signals: {}", error);
+                    return;
+                }
+            };
+            let signal = signals.block_on(wait_for_signal());
+            eprintln!("{} received, shutting down", signal);
+            if let Err(error) = shutdown() {
+                eprintln!("Shutdown failed: {}", error);
+            }
+            std::process::exit(signal.exit_code());
+        });
+        if let Err(error) = spawned {
+            eprintln!("Cannot listen for signals: {}", error);
+        }
+    });
+}
+
+/// Without the `signals` feature the process keeps the default signal
+/// behaviour; exit hooks still run when it returns or calls `exit`.
+#[cfg(not(feature = "signals"))]
+pub fn install_signal_handlers() {}
+
+#[cfg(feature = "signals")]
+async fn wait_for_signal() -> Signal {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            return tokio::select! {
+                Ok(()) = tokio::signal::ctrl_c() => Signal::Interrupt,
+                Some(()) = terminate.recv() => Signal::Terminate,
+                else => std::future::pending().await,
+            };
+        }
+    }
+
+    match tokio::signal::ctrl_c().await {
+        Ok(()) => Signal::Interrupt,
+        Err(_) => std::future::pending().await,
+    }
+}
+
+extern "C" {
+    fn atexit(callback: extern "C" fn()) -> std::os::raw::c_int;
+}
+
+extern "C" fn shutdown_at_exit() {
+    let _ = shutdown();
+}
+
+/// C-compatible runtime function behind `on_exit(callback)`: the callback runs
+/// when the program returns from `main`, calls `exit` or receives SIGINT/SIGTERM
+#[no_mangle]
+pub extern "C" fn albayan_rt_on_exit(callback: extern "C" fn()) {
+    static REGISTERED: Once = Once::new();
+    REGISTERED.call_once(|| {
+        // SAFETY: `shutdown_at_exit` is a plain function that lives as long as the process
+        unsafe {
+            atexit(shutdown_at_exit);
+        }
+        install_signal_handlers();
+    });
+    on_exit(move || callback());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_exit_hooks_run_latest_first_and_once() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        for name in ["flush", "panics", "close"] {
+            let order = order.clone();
+            on_exit(move || {
+                if name == "panics" {
+                    panic!("hook failure");
+                }
+                order.lock().unwrap().push(name);
+            });
+        }
+
+        run_exit_hooks();
+        run_exit_hooks();
+        assert_eq!(*order.lock().unwrap(), vec!["close", "flush"]);
+        assert_eq!(Signal::Interrupt.exit_code(), 130);
+        assert_eq!(Signal::Terminate.to_string(), "SIGTERM");
+    }
+}
@@ -0,0 +1,150 @@
+//! # String Methods
+//!
+//! The built-in string methods of natively compiled programs. Strings are
+//! NUL-terminated UTF-8 text; the LLVM backend lowers `text.len()`,
+//! `contains`, `concat`, `slice`, `split` and `to_int` to the
+//! `albayan_rt_string_*` calls below. Lengths and positions count characters,
+//! not bytes, and strings returned here are owned by the caller like those of
+//! `albayan_rt_format_finish`.
+//!
+//! `split` is made of two calls: `albayan_rt_string_split_count` sizes the
+//! list, which the generated code allocates, and
+//! `albayan_rt_string_split_into` fills it.
+
+// The pointers come from generated code, which only passes NUL-terminated
+// strings and lists sized by `albayan_rt_string_split_count`
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use std::borrow::Cow;
+use std::ffi::{c_char, CStr, CString};
+
+fn text<'a>(text: *const c_char) -> Cow<'a, str> {
+    if text.is_null() {
+        Cow::Borrowed("")
+    } else {
+        unsafe { CStr::from_ptr(text) }.to_string_lossy()
+    }
+}
+
+/// A new string owned by the caller; NUL bytes are left out
+pub(crate) fn into_raw(text: String) -> *mut c_char {
+    let bytes: Vec<u8> = text.into_bytes().into_iter().filter(|&byte| byte != 0).collect();
+    CString::new(bytes).map(CString::into_raw).unwrap_or(std::ptr::null_mut())
+}
+
+/// Characters `start` up to `end` of `text`
+pub fn slice_chars(text: &str, start: i64, end: i64) -> Result<String, String> {
+    let length = text.chars().count() as i64;
+    if start < 0 || start > end || end > length {
+        return Err(format!("slice {}..{} out of bounds for a string of length {}", start, end, length));
+    }
+    Ok(text.chars().skip(start as usize).take((end - start) as usize).collect())
+}
+
+/// Pieces of `text` between occurrences of `separator`; an empty separator
+/// splits it into its characters
+pub fn split_text<'a>(text: &'a str, separator: &'a str) -> Vec<&'a str> {
+    if separator.is_empty() {
+        text.char_indices().map(|(start, c)| &text[start..start + c.len_utf8()]).collect()
+    } else {
+        text.split(separator).collect()
+    }
+}
+
+/// The integer `text` spells, allowing surrounding whitespace
+pub fn parse_int(text: &str) -> Result<i64, String> {
+    text.trim().parse().map_err(|_| format!("cannot convert \"{}\" to an integer", text))
+}
+
+/// Number of characters
+#[no_mangle]
+pub extern "C" fn albayan_rt_string_len(string: *const c_char) -> i64 {
+    text(string).chars().count() as i64
+}
+
+/// 1 if `needle` occurs in the string
+#[no_mangle]
+pub extern "C" fn albayan_rt_string_contains(string: *const c_char, needle: *const c_char) -> u8 {
+    text(string).contains(text(needle).as_ref()) as u8
+}
+
+#[no_mangle]
+pub extern "C" fn albayan_rt_string_concat(left: *const c_char, right: *const c_char) -> *mut c_char {
+    into_raw(format!("{}{}", text(left), text(right)))
+}
+
+/// Characters `start` up to `end`; panics when they are out of range
+#[no_mangle]
+pub extern "C" fn albayan_rt_string_slice(string: *const c_char, start: i64, end: i64) -> *mut c_char {
+    match slice_chars(&text(string), start, end) {
+        Ok(slice) => into_raw(slice),
+        Err(message) => panic!("AlBayan Runtime Panic: {}", message),
+    }
+}
+
+/// Panics when the string is not an integer
+#[no_mangle]
+pub extern "C" fn albayan_rt_string_to_int(string: *const c_char) -> i64 {
+    parse_int(&text(string)).unwrap_or_else(|message| panic!("AlBayan Runtime Panic: {}", message))
+}
+
+/// Number of pieces `albayan_rt_string_split_into` writes
+#[no_mangle]
+pub extern "C" fn albayan_rt_string_split_count(string: *const c_char, separator: *const c_char) -> i64 {
+    split_text(&text(string), &text(separator)).len() as i64
+}
+
+/// Write the pieces of the string, each a new string, to `pieces`
+#[no_mangle]
+pub extern "C" fn albayan_rt_string_split_into(
+    string: *const c_char,
+    separator: *const c_char,
+    pieces: *mut *mut c_char,
+) {
+    if pieces.is_null() {
+        return;
+    }
+    let (string, separator) = (text(string), text(separator));
+    for (index, piece) in split_text(&string, &separator).into_iter().enumerate() {
+        unsafe { pieces.add(index).write(into_raw(piece.to_string())) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owned(text: *mut c_char) -> String {
+        let result = unsafe { CStr::from_ptr(text) }.to_string_lossy().into_owned();
+        crate::runtime::formatting::albayan_rt_string_free(text);
+        result
+    }
+
+    #[test]
+    fn test_string_methods() {
+        let greeting = c"مرحبا, world";
+        assert_eq!(albayan_rt_string_len(greeting.as_ptr()), 12);
+        assert_eq!(albayan_rt_string_contains(greeting.as_ptr(), c"world".as_ptr()), 1);
+        assert_eq!(albayan_rt_string_contains(greeting.as_ptr(), c"World".as_ptr()), 0);
+        assert_eq!(owned(albayan_rt_string_concat(c"ab".as_ptr(), c"cd".as_ptr())), "abcd");
+        assert_eq!(owned(albayan_rt_string_slice(greeting.as_ptr(), 0, 5)), "مرحبا");
+        assert_eq!(albayan_rt_string_to_int(c" -42 ".as_ptr()), -42);
+
+        let csv = c"a,b,,c";
+        let count = albayan_rt_string_split_count(csv.as_ptr(), c",".as_ptr());
+        let mut pieces = vec![std::ptr::null_mut(); count as usize];
+        albayan_rt_string_split_into(csv.as_ptr(), c",".as_ptr(), pieces.as_mut_ptr());
+        let pieces: Vec<String> = pieces.into_iter().map(owned).collect();
+        assert_eq!(pieces, vec!["a", "b", "", "c"]);
+    }
+
+    #[test]
+    fn test_out_of_range_slices_and_bad_integers_are_errors() {
+        assert_eq!(slice_chars("abc", 1, 3).unwrap(), "bc");
+        assert!(slice_chars("abc", 2, 1).is_err());
+        assert!(slice_chars("abc", 0, 4).is_err());
+        assert!(slice_chars("abc", -1, 2).is_err());
+        assert!(parse_int("12a").is_err());
+        assert_eq!(split_text("بيان", ""), vec!["ب", "ي", "ا", "ن"]);
+    }
+}
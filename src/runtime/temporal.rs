@@ -0,0 +1,105 @@
+//! # Temporal Facts
+//!
+//! Facts may hold only for part of the time: a fact asserted with
+//! `LogicEngine::assert_fact_at` carries a [`Validity`], the half-open
+//! interval `[valid_from, valid_to)` of time points it holds in. Time points
+//! are plain integers; the program chooses what they count (seconds, ticks of
+//! a simulation, turns of an agent).
+//!
+//! Goals see the facts that hold at the engine's time, set with
+//! `LogicEngine::set_time`; while no time is set every fact holds, as before.
+//! `holds_at(Goal, T)` solves `Goal` as of time `T` instead, rules included,
+//! and `before(T1, T2)` / `after(T1, T2)` order two time points. Expired facts
+//! stay stored, so past states can still be queried, until
+//! `LogicEngine::collect_expired` removes them.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Built-in that solves its goal at a given time
+pub const HOLDS_AT: &str = "holds_at";
+
+/// Time points a fact holds in; an open bound reaches as far as time does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validity {
+    /// First time point the fact holds at
+    pub valid_from: Option<i64>,
+    /// First time point the fact no longer holds at
+    pub valid_to: Option<i64>,
+}
+
+impl Validity {
+    /// Holds at every time point
+    pub fn always() -> Self {
+        Self::default()
+    }
+
+    /// Holds from `from` on
+    pub fn since(from: i64) -> Self {
+        Self { valid_from: Some(from), valid_to: None }
+    }
+
+    /// Holds before `to`
+    pub fn until(to: i64) -> Self {
+        Self { valid_from: None, valid_to: Some(to) }
+    }
+
+    /// Holds from `from` up to, not including, `to`
+    pub fn between(from: i64, to: i64) -> Self {
+        Self { valid_from: Some(from), valid_to: Some(to) }
+    }
+
+    pub fn is_always(&self) -> bool {
+        self.valid_from.is_none() && self.valid_to.is_none()
+    }
+
+    /// Whether the fact holds at `time`
+    pub fn contains(&self, time: i64) -> bool {
+        self.valid_from.map_or(true, |from| from <= time) && self.valid_to.map_or(true, |to| time < to)
+    }
+
+    /// Whether the fact no longer holds at `now` or any later time
+    pub fn expired_by(&self, now: i64) -> bool {
+        self.valid_to.is_some_and(|to| to <= now)
+    }
+
+    /// Whether no time point is in the interval
+    pub fn is_empty(&self) -> bool {
+        matches!((self.valid_from, self.valid_to), (Some(from), Some(to)) if from >= to)
+    }
+}
+
+impl fmt::Display for Validity {
+    /// `[from, to)` with `-inf` and `inf` for open bounds
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.valid_from {
+            Some(from) => write!(f, "[{}, ", from)?,
+            None => write!(f, "(-inf, ")?,
+        }
+        match self.valid_to {
+            Some(to) => write!(f, "{})", to),
+            None => write!(f, "inf)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intervals_are_half_open() {
+        let shift = Validity::between(9, 17);
+        assert!(shift.contains(9));
+        assert!(!shift.contains(17));
+        assert!(shift.expired_by(17));
+        assert!(!shift.expired_by(16));
+        assert!(Validity::since(5).contains(i64::MAX));
+        assert!(!Validity::until(5).expired_by(4));
+        assert!(Validity::always().contains(i64::MIN) && !Validity::always().expired_by(i64::MAX));
+        assert!(Validity::between(3, 3).is_empty());
+        assert_eq!(shift.to_string(), "[9, 17)");
+        assert_eq!(Validity::until(2).to_string(), "(-inf, 2)");
+    }
+}
@@ -0,0 +1,284 @@
+//! # Project Status
+//!
+//! `albayan status` summarizes a project on one screen: its modules and lines
+//! of code, the relations, rules and facts it declares with the size of the
+//! knowledge image a build embeds for them, which of its traits have
+//! implementations, its lint findings by rule, and the phase timings of the
+//! last build.
+//!
+//! Everything but the timings comes from parsing the sources; a file that
+//! does not parse is listed as such and otherwise left out. The timings are
+//! the [`BuildTimings`] a project build leaves in its output directory.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+
+use crate::build::BuildTimings;
+use crate::lexer::Lexer;
+use crate::parser::ast::{Item, LogicArg, LogicTerm, Program, Type};
+use crate::parser::Parser;
+use crate::runtime::{ImageArg, ImageGoal, KnowledgeImageBuilder};
+use crate::tools::linter::Linter;
+
+/// Summary of a project
+#[derive(Debug, Clone, Default)]
+pub struct ProjectStatus {
+    pub root: PathBuf,
+    pub modules: usize,
+    /// Lines that are neither blank nor only a comment
+    pub lines_of_code: usize,
+    /// Files that failed to parse
+    pub unparsed: Vec<PathBuf>,
+    pub relations: usize,
+    pub rules: usize,
+    pub facts: usize,
+    /// Bytes of the knowledge image of the project's declarations
+    pub knowledge_image: usize,
+    /// Each trait the project declares with the types implementing it, in
+    /// declaration order
+    pub traits: IndexMap<String, Vec<String>>,
+    /// Lint findings per rule
+    pub lints: BTreeMap<&'static str, usize>,
+    pub last_build: Option<BuildTimings>,
+}
+
+impl ProjectStatus {
+    /// Summarize the sources under `root`, linting them with `linter`; the
+    /// last build is the one that wrote to `output_dir`
+    pub fn collect(root: &Path, linter: &Linter, output_dir: &Path) -> std::io::Result<Self> {
+        let mut status = ProjectStatus {
+            root: root.to_path_buf(),
+            last_build: BuildTimings::load(output_dir),
+            ..Default::default()
+        };
+        let mut programs = Vec::new();
+        for path in crate::modules::collect_sources(&[root.to_path_buf()])? {
+            let source = std::fs::read_to_string(&path)?;
+            status.modules += 1;
+            status.lines_of_code += source
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with("//"))
+                .count();
+
+            let parsed = Lexer::new(&source)
+                .tokenize()
+                .ok()
+                .and_then(|tokens| Parser::new(tokens).parse().ok());
+            let Some(program) = parsed else {
+                status.unparsed.push(path);
+                continue;
+            };
+            for issue in linter.check_program(&source, &program) {
+                *status.lints.entry(issue.rule).or_default() += 1;
+            }
+            programs.push(program);
+        }
+
+        let items = || programs.iter().flat_map(|program| &program.items);
+        for item in items() {
+            match item {
+                Item::Relation(_) => status.relations += 1,
+                Item::Rule(_) => status.rules += 1,
+                Item::Fact(_) => status.facts += 1,
+                Item::Trait(decl) => {
+                    status.traits.entry(decl.name.clone()).or_default();
+                }
+                _ => {}
+            }
+        }
+        for item in items() {
+            if let Item::Impl(decl) = item {
+                let implementors = decl.trait_name.as_ref().and_then(|name| status.traits.get_mut(name));
+                if let Some(implementors) = implementors {
+                    implementors.push(decl.type_name.clone());
+                }
+            }
+        }
+        status.knowledge_image = knowledge_image(&programs).len();
+        Ok(status)
+    }
+
+    /// Traits with at least one implementation
+    pub fn implemented_traits(&self) -> usize {
+        self.traits.values().filter(|implementors| !implementors.is_empty()).count()
+    }
+}
+
+/// The knowledge image of the programs, as the LLVM backend would encode it
+fn knowledge_image(programs: &[Program]) -> Vec<u8> {
+    let items: Vec<&Item> = programs.iter().flat_map(|program| &program.items).collect();
+    let concepts: HashSet<&str> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Concept(concept) => Some(concept.name.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let mut image = KnowledgeImageBuilder::new();
+    for item in items {
+        match item {
+            Item::Concept(concept) => {
+                let parents: Vec<&str> = concept.parents.iter().map(String::as_str).collect();
+                image.concept(&concept.name, &parents);
+            }
+            Item::Relation(relation) => {
+                let arg_types: Vec<&str> = relation.arg_types.iter().map(|ty| relation_arg_type(ty, &concepts)).collect();
+                image.relation(&relation.name, &arg_types, relation.tabled);
+            }
+            Item::Constraint(constraint) => {
+                let goals: Vec<(&str, Vec<ImageArg>)> =
+                    constraint.goals.iter().map(|goal| (goal.name.as_str(), image_args(goal))).collect();
+                image.constraint(&goals);
+            }
+            Item::Fact(fact) => image.fact(&fact.term.name, &image_args(&fact.term)),
+            Item::Rule(rule) => {
+                let goals: Vec<ImageGoal> = rule
+                    .body
+                    .iter()
+                    .map(|goal| ImageGoal { predicate: &goal.name, args: image_args(goal), negated: goal.negated })
+                    .collect();
+                image.rule((&rule.head.name, &image_args(&rule.head)), &goals, !rule.no_reorder);
+            }
+            _ => {}
+        }
+    }
+    if image.is_empty() {
+        return Vec::new();
+    }
+    image.finish()
+}
+
+fn image_args(term: &LogicTerm) -> Vec<ImageArg<'_>> {
+    term.args.iter().map(image_arg).collect()
+}
+
+fn image_arg(arg: &LogicArg) -> ImageArg<'_> {
+    match arg {
+        LogicArg::Variable(name) => ImageArg::Variable(name),
+        LogicArg::Constant(name) => ImageArg::Atom(name),
+        LogicArg::StringConstant(text) => ImageArg::String(text),
+        LogicArg::IntConstant(value) => ImageArg::Integer(*value),
+        LogicArg::FloatConstant(value) => ImageArg::Float(*value),
+        LogicArg::Operation { op, left, right } => ImageArg::Compound(op, vec![image_arg(left), image_arg(right)]),
+        LogicArg::Goal(goal) | LogicArg::Compound(goal) => ImageArg::Compound(&goal.name, image_args(goal)),
+        LogicArg::List { items, tail } => {
+            ImageArg::list(items.iter().map(image_arg).collect(), tail.as_deref().map(image_arg))
+        }
+    }
+}
+
+/// Type name of a relation argument checked by the logic engine
+fn relation_arg_type<'a>(ty: &'a Type, concepts: &HashSet<&str>) -> &'a str {
+    match ty {
+        Type::Named(path) if path.segments.len() == 1 => {
+            let name = path.segments[0].as_str();
+            match name {
+                "int" | "float" | "string" | "bool" => name,
+                _ if concepts.contains(name) => name,
+                _ => "any",
+            }
+        }
+        _ => "any",
+    }
+}
+
+impl fmt::Display for ProjectStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Project: {}", self.root.display())?;
+        writeln!(f, "Modules: {} ({} lines of code)", self.modules, self.lines_of_code)?;
+        if !self.unparsed.is_empty() {
+            let paths: Vec<String> = self.unparsed.iter().map(|path| path.display().to_string()).collect();
+            writeln!(f, "Not parsed: {}", paths.join(", "))?;
+        }
+        writeln!(
+            f,
+            "Knowledge: {} relations, {} rules, {} facts ({} byte image)",
+            self.relations, self.rules, self.facts, self.knowledge_image
+        )?;
+
+        writeln!(f, "Traits: {} declared, {} implemented", self.traits.len(), self.implemented_traits())?;
+        for (name, implementors) in &self.traits {
+            match implementors.as_slice() {
+                [] => writeln!(f, "  {}: no implementations", name)?,
+                types => writeln!(f, "  {}: {}", name, types.join(", "))?,
+            }
+        }
+
+        writeln!(f, "Lints: {}", self.lints.values().sum::<usize>())?;
+        for (rule, count) in &self.lints {
+            writeln!(f, "  {}: {}", rule, count)?;
+        }
+
+        match &self.last_build {
+            Some(build) => {
+                let phases = build.phases();
+                write!(
+                    f,
+                    "Last build: {} files in {:.1?} (parse {:.1?}, analysis {:.1?}, codegen {:.1?})",
+                    build.files.len(),
+                    build.elapsed,
+                    phases.parse,
+                    phases.analysis,
+                    phases.codegen
+                )
+            }
+            None => write!(f, "Last build: none recorded"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::DEFAULT_OUTPUT_DIR;
+    use crate::Compiler;
+
+    #[test]
+    fn test_status_summarizes_the_project() {
+        let root = std::env::temp_dir().join(format!("albayan_status_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join("family.ab"),
+            "// Family tree\nrelation parent(string, string);\nrelation grandparent(string, string);\n\n\
+             fact parent(\"ali\", \"sara\");\nfact parent(\"sara\", \"huda\");\n\
+             rule grandparent(X, Z) :- parent(X, Y), parent(Y, Z);\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("shapes.ab"),
+            "trait Shape { fn area(&self) -> int; }\ntrait Named { fn name(&self) -> string; }\n\
+             struct Square { side: int, }\nimpl Shape for Square { fn area(&self) -> int { return self.side * self.side; } }\n\
+             fn main() -> int { let unused = 1; return 0; }\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("broken.ab"), "fn broken( {\n").unwrap();
+
+        let output_dir = root.join(DEFAULT_OUTPUT_DIR);
+        let status = ProjectStatus::collect(&root, &Linter::new(), &output_dir).unwrap();
+        assert_eq!(status.modules, 3);
+        assert_eq!(status.lines_of_code, 11);
+        assert_eq!(status.unparsed, vec![root.join("broken.ab")]);
+        assert_eq!((status.relations, status.rules, status.facts), (2, 1, 2));
+        assert!(status.knowledge_image > 0);
+        assert_eq!(status.traits["Shape"], vec!["Square"]);
+        assert_eq!(status.implemented_traits(), 1);
+        assert_eq!(status.lints.get("unused_variable"), Some(&1));
+        assert!(status.last_build.is_none());
+        let text = status.to_string();
+        assert!(text.contains("Named: no implementations"), "{}", text);
+        assert!(text.contains("Last build: none recorded"), "{}", text);
+
+        std::fs::remove_file(root.join("broken.ab")).unwrap();
+        let report = Compiler::new().compile_project(&root).unwrap();
+        let status = ProjectStatus::collect(&root, &Linter::new(), &output_dir).unwrap();
+        assert_eq!(status.last_build, Some(report.timings()));
+        assert!(status.to_string().contains("Last build: 2 files in"), "{}", status);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
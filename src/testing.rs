@@ -0,0 +1,317 @@
+//! # Test Runner
+//!
+//! `albayan test` runs the functions marked `#[test]` in a set of files or
+//! directories. Each file is loaded with the modules it imports and checked
+//! by the semantic analyzer as a build would; a file that does not compile
+//! is reported with its diagnostic and its tests are not run. The tests of
+//! the file itself (not of the modules it imports, which are run as files of
+//! their own) are then called one at a time in the [`crate::interpreter`].
+//!
+//! A test passes when it returns and fails when it panics: through `panic`,
+//! a failed `assert`, `assert_eq`, `assert_ne` or `assert_true`, or a runtime
+//! error such as an out-of-bounds index. Output a test prints is captured and
+//! shown only when it fails, with the panic's location and the functions it
+//! left. Tests are named `module::function` after the file
+//! they are in, and `--filter` keeps those whose name contains its text.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::diagnostics::Diagnostic;
+use crate::interpreter::{Interpreter, Panic};
+use crate::lexer::LineIndex;
+use crate::modules::graph::ModuleGraph;
+use crate::parser::ast::{FunctionDecl, Item};
+use crate::semantic::SemanticAnalyzer;
+use crate::CompilerOptions;
+
+/// Result of running the tests of some files
+#[derive(Debug, Clone, Default)]
+pub struct TestReport {
+    /// Every test run, in file and declaration order
+    pub results: Vec<TestResult>,
+    /// Files whose tests did not run because they failed to compile
+    pub compile_errors: Vec<CompileFailure>,
+    /// Tests not run because their names did not match the filter
+    pub filtered_out: usize,
+    pub elapsed: Duration,
+}
+
+/// How one test went
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    /// `module::function`
+    pub name: String,
+    pub path: PathBuf,
+    pub outcome: TestOutcome,
+    /// What the test printed
+    pub output: String,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestOutcome {
+    Passed,
+    Failed {
+        message: String,
+        /// `file:line:column` of the panic, if known
+        location: Option<String>,
+        /// Functions the panic left, innermost first
+        backtrace: Vec<String>,
+    },
+}
+
+/// A file that did not compile
+#[derive(Debug, Clone)]
+pub struct CompileFailure {
+    pub path: PathBuf,
+    pub diagnostic: Diagnostic,
+}
+
+impl TestReport {
+    pub fn passed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.outcome == TestOutcome::Passed)
+            .count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+
+    /// Whether every file compiled and every test passed
+    pub fn succeeded(&self) -> bool {
+        self.compile_errors.is_empty() && self.failed() == 0
+    }
+}
+
+impl fmt::Display for TestReport {
+    /// One line per test, then the output and panic of each failed test and
+    /// a summary
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "running {} tests", self.results.len())?;
+        for result in &self.results {
+            let outcome = if result.outcome == TestOutcome::Passed {
+                "ok"
+            } else {
+                "FAILED"
+            };
+            writeln!(f, "test {} ... {}", result.name, outcome)?;
+        }
+
+        let failures: Vec<_> = self
+            .results
+            .iter()
+            .filter(|result| result.outcome != TestOutcome::Passed)
+            .collect();
+        if !failures.is_empty() {
+            writeln!(f, "\nfailures:")?;
+            for result in &failures {
+                writeln!(f, "\n---- {} ----", result.name)?;
+                write!(f, "{}", result.output)?;
+                if !result.output.is_empty() && !result.output.ends_with('\n') {
+                    writeln!(f)?;
+                }
+                if let TestOutcome::Failed {
+                    message,
+                    location,
+                    backtrace,
+                } = &result.outcome
+                {
+                    match location {
+                        Some(location) => writeln!(f, "panicked at {}:\n{}", location, message)?,
+                        None => writeln!(f, "panicked: {}", message)?,
+                    }
+                    if !backtrace.is_empty() {
+                        writeln!(f, "stack backtrace:")?;
+                        for (depth, function) in backtrace.iter().enumerate() {
+                            writeln!(f, "  {}: {}", depth, function)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        write!(
+            f,
+            "\ntest result: {}. {} passed; {} failed; {} filtered out",
+            if self.succeeded() { "ok" } else { "FAILED" },
+            self.passed(),
+            self.failed(),
+            self.filtered_out
+        )?;
+        if !self.compile_errors.is_empty() {
+            write!(f, "; {} files failed to compile", self.compile_errors.len())?;
+        }
+        write!(f, "; finished in {:.2}s", self.elapsed.as_secs_f64())
+    }
+}
+
+/// Run the `#[test]` functions of the `.ab` files in `paths` whose names
+/// contain `filter`
+pub fn run_tests(paths: &[PathBuf], options: &CompilerOptions, filter: Option<&str>) -> std::io::Result<TestReport> {
+    let started = Instant::now();
+    let mut report = TestReport::default();
+    for path in crate::modules::collect_sources(paths)? {
+        run_file(&path, options, filter, &mut report);
+    }
+    report.elapsed = started.elapsed();
+    Ok(report)
+}
+
+fn run_file(path: &Path, options: &CompilerOptions, filter: Option<&str>, report: &mut TestReport) {
+    let compiled = ModuleGraph::load_with_packages(path, &options.packages, &options.cancellation).and_then(|graph| {
+        SemanticAnalyzer::new(options).analyze_modules(&graph)?;
+        Ok(graph)
+    });
+    let graph = match compiled {
+        Ok(graph) => graph,
+        Err(error) => {
+            let diagnostic = Diagnostic::from(&error);
+            report.compile_errors.push(CompileFailure {
+                path: path.to_path_buf(),
+                diagnostic,
+            });
+            return;
+        }
+    };
+
+    let entry = graph.entry();
+    let tests: Vec<&FunctionDecl> = entry
+        .program
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Function(function) if function.is_test => Some(function),
+            _ => None,
+        })
+        .collect();
+    if tests.is_empty() {
+        return;
+    }
+
+    let mut interpreter = Interpreter::for_graph(&graph);
+    for test in tests {
+        let name = format!("{}::{}", entry.name, test.name);
+        if filter.is_some_and(|filter| !name.contains(filter)) {
+            report.filtered_out += 1;
+            continue;
+        }
+
+        let started = Instant::now();
+        let outcome = if test.parameters.is_empty() {
+            // A bug in the interpreter fails the test rather than the run
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                interpreter.call(&test.name, Vec::new())
+            }));
+            match result {
+                Ok(Ok(_)) => TestOutcome::Passed,
+                Ok(Err(panic)) => TestOutcome::Failed {
+                    location: locate(&graph, &panic),
+                    message: panic.message,
+                    backtrace: panic.backtrace,
+                },
+                Err(payload) => {
+                    let message = payload
+                        .downcast_ref::<&str>()
+                        .map(|message| message.to_string())
+                        .or_else(|| payload.downcast_ref::<String>().cloned())
+                        .unwrap_or_default();
+                    TestOutcome::Failed {
+                        message: format!("internal interpreter error: {}", message),
+                        location: None,
+                        backtrace: Vec::new(),
+                    }
+                }
+            }
+        } else {
+            TestOutcome::Failed {
+                message: "test functions take no parameters".to_string(),
+                location: None,
+                backtrace: Vec::new(),
+            }
+        };
+        report.results.push(TestResult {
+            name,
+            path: path.to_path_buf(),
+            outcome,
+            output: interpreter.take_output(),
+            duration: started.elapsed(),
+        });
+    }
+}
+
+/// `file:line:column` of a panic, in the module it happened in
+fn locate(graph: &ModuleGraph, panic: &Panic) -> Option<String> {
+    let span = panic.span?;
+    let module = graph
+        .modules()
+        .iter()
+        .find(|module| graph.prefix(module) == panic.module.as_deref())?;
+    let source = std::fs::read_to_string(&module.path).ok()?;
+    let position = LineIndex::new(&source).line_col(span.start);
+    Some(format!(
+        "{}:{}:{}",
+        module.path.display(),
+        position.line,
+        position.column
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tests_pass_fail_and_filter() {
+        let dir = std::env::temp_dir().join(format!("albayan_testing_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("helpers.ab"),
+            "pub fn double(x: int) -> int {\n    return x * 2;\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("math.ab"),
+            "using helpers;\n\
+             #[test]\nfn doubles() { assert_eq(helpers::double(2), 4); }\n\
+             #[test]\nfn fails() {\n    println(\"checking\");\n    assert_eq(helpers::double(2), 5);\n}\n\
+             #[test]\nfn panics() { panic(\"boom\"); }\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken.ab"), "fn main() { let x = missing; }\n").unwrap();
+
+        let report = run_tests(&[dir.clone()], &CompilerOptions::default(), Some("math::")).unwrap();
+        assert_eq!(report.compile_errors.len(), 1);
+        assert!(report.compile_errors[0].path.ends_with("broken.ab"));
+        let names: Vec<&str> = report.results.iter().map(|result| result.name.as_str()).collect();
+        assert_eq!(names, ["math::doubles", "math::fails", "math::panics"]);
+        assert_eq!(report.passed(), 1);
+        assert!(!report.succeeded());
+
+        let filtered = run_tests(&[dir.join("math.ab")], &CompilerOptions::default(), Some("fails")).unwrap();
+        assert_eq!(filtered.filtered_out, 2);
+        let [result] = filtered.results.as_slice() else {
+            panic!("expected one test");
+        };
+        assert_eq!(result.output, "checking\n");
+        let TestOutcome::Failed {
+            message,
+            location,
+            backtrace,
+        } = &result.outcome
+        else {
+            panic!("expected a failure");
+        };
+        assert!(message.contains("left: 4"), "{}", message);
+        assert_eq!(backtrace, &["fails"]);
+        assert!(location.as_deref().unwrap().ends_with("math.ab:7:5"), "{:?}", location);
+        let text = filtered.to_string();
+        assert!(text.contains("stack backtrace:\n  0: fails\n"), "{}", text);
+        assert!(text.contains("test result: FAILED. 0 passed; 1 failed; 2 filtered out"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,11 +1,18 @@
 //! Debugger for AlBayan language
 //! 
 //! Provides debugging capabilities including breakpoints, step execution,
-//! variable inspection, and call stack analysis
+//! variable inspection, and call stack analysis. At a breakpoint the live
+//! heap can be snapshotted and its objects inspected one by one.
+//!
+//! Watchpoints set on the logic engine's knowledge base pause the program
+//! when a matching fact is asserted or retracted or a watched rule fires,
+//! showing the goal stack that led there next to the call stack.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use anyhow::{Result, anyhow};
 
+use crate::runtime::{HeapSnapshot, LogicEngine, ObjectId, WatchHit};
+
 /// Debugger for AlBayan programs
 #[derive(Debug)]
 pub struct Debugger {
@@ -21,6 +28,10 @@ pub struct Debugger {
     execution_history: Vec<ExecutionStep>,
     /// Debug configuration
     config: DebugConfig,
+    /// Heap snapshot taken at the current breakpoint
+    heap_snapshot: Option<HeapSnapshot>,
+    /// Knowledge watchpoint hits to pause on, the current one first
+    watch_hits: VecDeque<WatchHit>,
 }
 
 /// Debug state
@@ -114,6 +125,8 @@ impl Debugger {
             variables: HashMap::new(),
             execution_history: Vec::new(),
             config: DebugConfig::default(),
+            heap_snapshot: None,
+            watch_hits: VecDeque::new(),
         }
     }
     
@@ -160,6 +173,8 @@ impl Debugger {
         self.call_stack.clear();
         self.variables.clear();
         self.execution_history.clear();
+        self.heap_snapshot = None;
+        self.watch_hits.clear();
         
         // TODO: Initialize program execution
         Ok(())
@@ -169,6 +184,11 @@ impl Debugger {
     pub fn continue_execution(&mut self) -> Result<()> {
         match &self.state {
             DebugState::Paused { .. } => {
+                self.heap_snapshot = None;
+                if self.watch_hits.pop_front().is_some() && !self.watch_hits.is_empty() {
+                    self.pause_on_watch_hit();
+                    return Ok(());
+                }
                 println!("Continuing execution...");
                 self.state = DebugState::Running;
                 // TODO: Resume program execution
@@ -238,6 +258,8 @@ impl Debugger {
         self.state = DebugState::Stopped;
         self.call_stack.clear();
         self.variables.clear();
+        self.heap_snapshot = None;
+        self.watch_hits.clear();
         println!("Debug session stopped");
         Ok(())
     }
@@ -282,6 +304,83 @@ impl Debugger {
         }
     }
     
+    /// Keep a heap snapshot taken at the current breakpoint, e.g. with
+    /// `HeapSnapshot::capture` over the frame's values; it is discarded when
+    /// execution resumes
+    pub fn record_heap_snapshot(&mut self, snapshot: HeapSnapshot) -> Result<&HeapSnapshot> {
+        match &self.state {
+            DebugState::Paused { .. } => Ok(self.heap_snapshot.insert(snapshot)),
+            _ => Err(anyhow!("Cannot snapshot the heap: program is not paused")),
+        }
+    }
+    
+    /// Get the heap snapshot of the current breakpoint
+    pub fn get_heap_snapshot(&self) -> Option<&HeapSnapshot> {
+        self.heap_snapshot.as_ref()
+    }
+    
+    /// Live objects grouped by type with counts and retained sizes, and the
+    /// knowledge base by predicate
+    pub fn heap_report(&self) -> Result<String> {
+        let snapshot = self.heap_snapshot.as_ref().ok_or_else(|| anyhow!("No heap snapshot at this breakpoint"))?;
+        Ok(snapshot.render())
+    }
+    
+    /// Fields or elements of one object of the heap snapshot
+    pub fn inspect_object(&self, id: ObjectId) -> Result<String> {
+        let snapshot = self.heap_snapshot.as_ref().ok_or_else(|| anyhow!("No heap snapshot at this breakpoint"))?;
+        snapshot.render_object(id).ok_or_else(|| anyhow!("No object #{} in the heap snapshot", id))
+    }
+    
+    /// Pause on the watchpoint hits `engine` recorded since the last check
+    /// (see `LogicEngine::watch_fact` and `LogicEngine::watch_rule`);
+    /// continuing moves on to the next hit. Returns whether it paused.
+    pub fn check_watchpoints(&mut self, engine: &mut LogicEngine) -> bool {
+        let was_waiting = !self.watch_hits.is_empty();
+        self.watch_hits.extend(engine.take_watch_hits());
+        if was_waiting || self.watch_hits.is_empty() {
+            return was_waiting;
+        }
+        self.pause_on_watch_hit();
+        true
+    }
+    
+    /// The watchpoint hit execution is paused on
+    pub fn get_watch_hit(&self) -> Option<&WatchHit> {
+        self.watch_hits.front()
+    }
+    
+    /// The current watchpoint hit with its goal stack, followed by the call stack
+    pub fn watch_hit_report(&self) -> Result<String> {
+        let hit = self.watch_hits.front().ok_or_else(|| anyhow!("Not paused on a watchpoint"))?;
+        let mut report = hit.to_string();
+        if !self.call_stack.is_empty() {
+            report.push_str("\ncall stack:");
+            for (index, frame) in self.call_stack.iter().rev().enumerate() {
+                report.push_str(&format!(
+                    "\n  #{} {} at {}:{}",
+                    index, frame.function_name, frame.file_name, frame.line_number
+                ));
+            }
+        }
+        Ok(report)
+    }
+    
+    /// Pause where the program is for the first pending watchpoint hit
+    fn pause_on_watch_hit(&mut self) {
+        let Some(hit) = self.watch_hits.front() else {
+            return;
+        };
+        let summary = format!("{} {}", hit.event, hit.subject);
+        println!("Watchpoint {} hit: {}", hit.watchpoint, summary);
+        let (file, line) = self.call_stack.last().map_or_else(
+            || ("current_file.ab".to_string(), 1),
+            |frame| (frame.file_name.clone(), frame.line_number),
+        );
+        self.state = DebugState::Paused { file, line, column: 1 };
+        self.record_execution_step("watchpoint", &summary, Vec::new());
+    }
+    
     /// Get execution history
     pub fn get_execution_history(&self) -> &[ExecutionStep] {
         &self.execution_history
@@ -6,6 +6,10 @@ use anyhow::Result;
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
+use crate::lexer::Lexer;
+use crate::parser::ast::{Item, Program, Visibility};
+use crate::parser::Parser;
+
 /// Documentation generator
 #[derive(Debug)]
 pub struct DocGenerator {
@@ -42,7 +46,7 @@ pub enum OutputFormat {
 }
 
 /// Generated documentation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Documentation {
     /// Module documentation
     pub modules: Vec<ModuleDoc>,
@@ -201,15 +205,57 @@ impl DocGenerator {
     }
     
     /// Generate documentation from source code
+    ///
+    /// Source that parses is documented from the doc comments the parser
+    /// attaches to its items; anything else falls back to scanning lines.
     pub fn generate(&self, source: &str) -> Result<Documentation> {
-        let mut documentation = Documentation {
-            modules: Vec::new(),
-            functions: Vec::new(),
-            structs: Vec::new(),
-            enums: Vec::new(),
-            relations: Vec::new(),
-            examples: Vec::new(),
-        };
+        let parsed = Lexer::new(source)
+            .tokenize_with_comments()
+            .ok()
+            .and_then(|tokens| Parser::new(tokens).parse().ok());
+        match parsed {
+            Some(program) => self.generate_from_program(&program, source),
+            None => self.generate_from_lines(source),
+        }
+    }
+    
+    /// Generate documentation from a program parsed with its comments
+    pub fn generate_from_program(&self, program: &Program, source: &str) -> Result<Documentation> {
+        let mut documentation = Documentation::default();
+        
+        for item in &program.items {
+            let Some(span) = item.span() else {
+                continue;
+            };
+            let Some(doc_comment) = program.doc_comment(span) else {
+                continue;
+            };
+            let declaration = source.get(span.start..span.end).and_then(|text| text.lines().next()).unwrap_or("");
+            
+            match item {
+                Item::Function(decl) => {
+                    let mut func_doc = self.parse_function_doc(&doc_comment, declaration)?;
+                    func_doc.name = decl.name.clone();
+                    func_doc.visibility = match decl.visibility {
+                        Visibility::Public => "public",
+                        Visibility::Private => "private",
+                    }
+                    .to_string();
+                    documentation.functions.push(func_doc);
+                }
+                Item::Struct(_) => documentation.structs.push(self.parse_struct_doc(&doc_comment, declaration)?),
+                Item::Enum(_) => documentation.enums.push(self.parse_enum_doc(&doc_comment, declaration)?),
+                Item::Relation(_) => documentation.relations.push(self.parse_relation_doc(&doc_comment, declaration)?),
+                _ => {}
+            }
+        }
+        
+        Ok(documentation)
+    }
+    
+    /// Generate documentation by scanning source lines for doc comments
+    fn generate_from_lines(&self, source: &str) -> Result<Documentation> {
+        let mut documentation = Documentation::default();
         
         let lines: Vec<&str> = source.lines().collect();
         let mut i = 0;
@@ -224,10 +270,13 @@ impl DocGenerator {
                 
                 // Check what follows the documentation
                 if i < lines.len() {
-                    let next_line = lines[i].trim();
+                    let declaration = lines[i].trim();
+                    let public = declaration.starts_with("pub ");
+                    let next_line = declaration.strip_prefix("pub ").unwrap_or(declaration).trim_start();
                     
                     if next_line.starts_with("fn ") {
-                        let func_doc = self.parse_function_doc(&doc_comment, next_line)?;
+                        let mut func_doc = self.parse_function_doc(&doc_comment, next_line)?;
+                        func_doc.visibility = if public { "public" } else { "private" }.to_string();
                         documentation.functions.push(func_doc);
                     } else if next_line.starts_with("struct ") {
                         let struct_doc = self.parse_struct_doc(&doc_comment, next_line)?;
@@ -413,11 +462,12 @@ impl DocGenerator {
     
     /// Extract function name from function declaration
     fn extract_function_name(&self, line: &str) -> String {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 2 && parts[0] == "fn" {
-            parts[1].trim_end_matches('(').to_string()
-        } else {
-            "unknown".to_string()
+        // `pub fn inc(n: int)` -> `inc`
+        let line = line.trim_start();
+        let declaration = line.strip_prefix("pub ").unwrap_or(line).trim_start();
+        match declaration.strip_prefix("fn ") {
+            Some(rest) => rest.split('(').next().unwrap_or_default().trim().to_string(),
+            None => "unknown".to_string(),
         }
     }
     
@@ -576,3 +626,22 @@ impl Default for DocConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doc_comments_come_from_the_parsed_program() {
+        let source = "/// Adds one.\n/// @return the next number\npub fn inc(n: int) -> int {\n    return n + 1;\n}\n\n\
+                      // Not documentation\nfn helper() {}\n\n/// A point.\nstruct Point { x: int }\n";
+        let documentation = DocGenerator::new().generate(source).unwrap();
+
+        assert_eq!(documentation.functions.len(), 1);
+        let inc = &documentation.functions[0];
+        assert_eq!((inc.name.as_str(), inc.visibility.as_str()), ("inc", "public"));
+        assert_eq!(inc.description, "Adds one.");
+        assert_eq!(inc.return_description.as_deref(), Some("the next number"));
+        assert_eq!(documentation.structs[0].description, "A point.");
+    }
+}
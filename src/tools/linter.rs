@@ -1,485 +1,1224 @@
 //! Code linter for AlBayan language
-//! 
-//! Provides static analysis and code quality checks
+//!
+//! Lint rules run over the parsed program. Each rule reports findings at a
+//! source span. The `[lints]` table of the project's `Bayan.toml` sets each
+//! rule to `allow`, `warn` (the default) or `deny` by name, and sets the limits
+//! that some rules check against:
+//!
+//! ```toml
+//! [lints]
+//! shadowing = "allow"
+//! unused_variable = "deny"
+//! max_line_length = 120
+//! ```
+//!
+//! `albayan lint` prints the issues, and the language server publishes them
+//! alongside the compiler's diagnostics.
 
-use anyhow::Result;
-use std::collections::{HashMap, HashSet};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::lexer::{Lexer, LineIndex, Span};
+use crate::parser::ast::{
+    BinaryOperator, Block, Expression, ExpressionStatement, FunctionDecl, IfStatement, Item, Literal, MatchArm, MatchStatement,
+    Parameter, Pattern, Program, Statement, Visibility,
+};
+use crate::parser::Parser;
+
+/// Same file as `cli::project::MANIFEST_FILE`, read here without the `cli` feature
+const MANIFEST_FILE: &str = "Bayan.toml";
 
 /// Code linter for AlBayan
 #[derive(Debug)]
 pub struct Linter {
-    /// Linting rules configuration
+    /// Rule levels and limits
     config: LinterConfig,
-    /// Custom rules
-    custom_rules: Vec<Box<dyn LintRule>>,
+    /// Built-in and custom rules
+    rules: Vec<Box<dyn LintRule>>,
 }
 
-/// Linting configuration
-#[derive(Debug, Clone)]
+/// Linting configuration: the `[lints]` table of a manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LinterConfig {
-    /// Enable/disable specific rules
-    pub enabled_rules: HashMap<String, bool>,
-    /// Rule severity levels
-    pub rule_severities: HashMap<String, Severity>,
-    /// Maximum line length
+    /// Longest line `line_length` accepts, in characters
     pub max_line_length: usize,
-    /// Maximum function length
+    /// Most lines `function_length` accepts in a function
     pub max_function_length: usize,
-    /// Maximum cyclomatic complexity
+    /// Highest cyclomatic complexity `complexity` accepts in a function
     pub max_complexity: usize,
-    /// Naming conventions
-    pub naming_conventions: NamingConventions,
+    /// Most parameters `too_many_parameters` accepts, `self` not counted
+    pub max_parameters: usize,
+    /// Level of each rule named in the table; the others warn
+    #[serde(flatten)]
+    pub levels: BTreeMap<String, LintLevel>,
 }
 
-/// Naming conventions
-#[derive(Debug, Clone)]
-pub struct NamingConventions {
-    /// Function naming style
-    pub function_style: NamingStyle,
-    /// Variable naming style
-    pub variable_style: NamingStyle,
-    /// Constant naming style
-    pub constant_style: NamingStyle,
-    /// Type naming style
-    pub type_style: NamingStyle,
-}
-
-/// Naming style
-#[derive(Debug, Clone)]
-pub enum NamingStyle {
-    /// snake_case
-    SnakeCase,
-    /// camelCase
-    CamelCase,
-    /// PascalCase
-    PascalCase,
-    /// SCREAMING_SNAKE_CASE
-    ScreamingSnakeCase,
-    /// kebab-case
-    KebabCase,
-}
-
-/// Lint issue severity
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Severity {
-    /// Error - must be fixed
-    Error,
-    /// Warning - should be fixed
-    Warning,
-    /// Info - suggestion
-    Info,
-    /// Hint - minor suggestion
-    Hint,
+pub use crate::semantic::warnings::LintLevel;
+
+/// A rule's finding, before the linter gives it a level
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub span: Span,
+    pub message: String,
+    pub suggestion: Option<String>,
 }
 
 /// Lint issue
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LintIssue {
     /// Rule that triggered this issue
-    pub rule: String,
-    /// Issue severity
+    pub rule: &'static str,
+    /// `Error` for denied rules, `Warning` otherwise
     pub severity: Severity,
     /// Line number (1-based)
     pub line: usize,
     /// Column number (1-based)
     pub column: usize,
+    /// Source location
+    pub span: Span,
     /// Issue message
     pub message: String,
     /// Suggested fix
     pub suggestion: Option<String>,
 }
 
+/// What a rule checks
+pub struct LintContext<'a> {
+    pub source: &'a str,
+    pub program: &'a Program,
+    pub config: &'a LinterConfig,
+    line_index: LineIndex,
+}
+
 /// Lint rule trait
-pub trait LintRule: std::fmt::Debug {
-    /// Rule name
-    fn name(&self) -> &str;
+pub trait LintRule: std::fmt::Debug + Send + Sync {
+    /// Rule name, as written in `[lints]`
+    fn name(&self) -> &'static str;
     /// Rule description
-    fn description(&self) -> &str;
-    /// Check source code for issues
-    fn check(&self, source: &str) -> Result<Vec<LintIssue>>;
+    fn description(&self) -> &'static str;
+    /// Check a parsed program for issues
+    fn check(&self, context: &LintContext) -> Vec<Finding>;
 }
 
 impl Linter {
     /// Create a new linter
     pub fn new() -> Self {
-        let mut linter = Self {
-            config: LinterConfig::default(),
-            custom_rules: Vec::new(),
-        };
-        
-        // Add built-in rules
-        linter.add_builtin_rules();
-        linter
+        Self::with_config(LinterConfig::default())
     }
-    
+
     /// Create linter with custom configuration
     pub fn with_config(config: LinterConfig) -> Self {
-        let mut linter = Self {
-            config,
-            custom_rules: Vec::new(),
-        };
-        
-        linter.add_builtin_rules();
-        linter
-    }
-    
-    /// Add built-in lint rules
-    fn add_builtin_rules(&mut self) {
-        self.custom_rules.push(Box::new(LineLengthRule::new(self.config.max_line_length)));
-        self.custom_rules.push(Box::new(UnusedVariableRule::new()));
-        self.custom_rules.push(Box::new(NamingConventionRule::new(self.config.naming_conventions.clone())));
-        self.custom_rules.push(Box::new(ComplexityRule::new(self.config.max_complexity)));
-        self.custom_rules.push(Box::new(DeadCodeRule::new()));
-    }
-    
+        Self { config, rules: builtin_rules() }
+    }
+
     /// Add a custom lint rule
     pub fn add_rule(&mut self, rule: Box<dyn LintRule>) {
-        self.custom_rules.push(rule);
+        self.rules.push(rule);
+    }
+
+    pub fn config(&self) -> &LinterConfig {
+        &self.config
+    }
+
+    /// The rules, built-in ones first
+    pub fn rules(&self) -> impl Iterator<Item = &dyn LintRule> {
+        self.rules.iter().map(|rule| rule.as_ref())
+    }
+
+    /// Rule names the configuration sets a level for that no rule has
+    pub fn unknown_rules(&self) -> Vec<&str> {
+        self.config.levels.keys()
+            .filter(|name| !self.rules.iter().any(|rule| rule.name() == name.as_str()))
+            .map(String::as_str)
+            .collect()
     }
-    
-    /// Analyze source code
+
+    /// Parse and lint source code
     pub fn analyze(&self, source: &str) -> Result<Vec<LintIssue>> {
+        let tokens = Lexer::new(source).tokenize()?;
+        let program = Parser::new(tokens).parse()?;
+        Ok(self.check_program(source, &program))
+    }
+
+    /// Lint a program parsed from `source`; issues come sorted by position
+    pub fn check_program(&self, source: &str, program: &Program) -> Vec<LintIssue> {
+        let context = LintContext::new(source, program, &self.config);
         let mut issues = Vec::new();
-        
-        for rule in &self.custom_rules {
-            if self.is_rule_enabled(rule.name()) {
-                let rule_issues = rule.check(source)?;
-                issues.extend(rule_issues);
+
+        for rule in &self.rules {
+            let severity = match self.config.level(rule.name()) {
+                LintLevel::Allow => continue,
+                LintLevel::Warn => Severity::Warning,
+                LintLevel::Deny => Severity::Error,
+            };
+            for finding in rule.check(&context) {
+                let position = context.line_index.line_col(finding.span.start);
+                issues.push(LintIssue {
+                    rule: rule.name(),
+                    severity,
+                    line: position.line,
+                    column: position.column,
+                    span: finding.span,
+                    message: finding.message,
+                    suggestion: finding.suggestion,
+                });
             }
         }
-        
-        // Sort issues by line number
+
         issues.sort_by(|a, b| a.line.cmp(&b.line).then(a.column.cmp(&b.column)));
-        
-        Ok(issues)
+        issues
     }
-    
-    /// Check if a rule is enabled
-    fn is_rule_enabled(&self, rule_name: &str) -> bool {
-        self.config.enabled_rules.get(rule_name).copied().unwrap_or(true)
+}
+
+impl LinterConfig {
+    /// The level `rule` is set to
+    pub fn level(&self, rule: &str) -> LintLevel {
+        self.levels.get(rule).copied().unwrap_or_default()
     }
-    
-    /// Get rule severity
-    fn get_rule_severity(&self, rule_name: &str) -> Severity {
-        self.config.rule_severities.get(rule_name).cloned().unwrap_or(Severity::Warning)
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The `[lints]` table of the nearest `Bayan.toml` from `start` upwards, or
+    /// the defaults outside a project
+    pub fn discover(start: &Path) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Manifest {
+            #[serde(default)]
+            lints: LinterConfig,
+        }
+
+        let Some(path) = start.ancestors()
+            .map(|dir| dir.join(MANIFEST_FILE))
+            .find(|path| path.is_file())
+        else {
+            return Ok(Self::default());
+        };
+        let source = std::fs::read_to_string(&path).with_context(|| format!("cannot read `{}`", path.display()))?;
+        let manifest: Manifest = toml::from_str(&source)
+            .map_err(|error| anyhow::anyhow!("invalid `[lints]` in `{}`: {}", path.display(), error.message()))?;
+        Ok(manifest.lints)
     }
 }
 
-/// Line length rule
-#[derive(Debug)]
-struct LineLengthRule {
-    max_length: usize,
+impl LintIssue {
+    /// The issue as a compiler diagnostic, for rendering against its source
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = match self.severity {
+            Severity::Error => Diagnostic::error(self.rule, self.message.clone()),
+            _ => Diagnostic::warning(self.rule, self.message.clone()),
+        };
+        let diagnostic = diagnostic.with_span(Some(self.span));
+        match &self.suggestion {
+            Some(suggestion) => diagnostic.with_suggestion(suggestion.clone()),
+            None => diagnostic,
+        }
+    }
+}
+
+impl<'a> LintContext<'a> {
+    pub fn new(source: &'a str, program: &'a Program, config: &'a LinterConfig) -> Self {
+        Self { source, program, config, line_index: LineIndex::new(source) }
+    }
+
+    /// Line of a byte offset (1-based)
+    pub fn line(&self, offset: usize) -> usize {
+        self.line_index.line_col(offset).line
+    }
+
+    /// Every function with a body: top-level ones and the methods of impls
+    /// and classes, each with whether a trait fixes its signature
+    pub fn functions(&self) -> Vec<(&'a FunctionDecl, bool)> {
+        let mut functions = Vec::new();
+        for item in &self.program.items {
+            match item {
+                Item::Function(decl) => functions.push((decl, false)),
+                Item::Impl(decl) => functions.extend(decl.methods.iter().map(|method| (method, decl.trait_name.is_some()))),
+                Item::Class(decl) => functions.extend(decl.methods.iter().map(|method| (method, false))),
+                _ => {}
+            }
+        }
+        functions
+    }
 }
 
-impl LineLengthRule {
-    fn new(max_length: usize) -> Self {
-        Self { max_length }
+impl Finding {
+    fn new(span: Span, message: impl Into<String>) -> Self {
+        Self { span, message: message.into(), suggestion: None }
+    }
+
+    fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
     }
 }
 
-impl LintRule for LineLengthRule {
-    fn name(&self) -> &str {
-        "line_length"
+fn builtin_rules() -> Vec<Box<dyn LintRule>> {
+    vec![
+        Box::new(UnusedVariableRule),
+        Box::new(UnusedParameterRule),
+        Box::new(UnusedFunctionRule),
+        Box::new(ShadowingRule),
+        Box::new(SnakeCaseRule),
+        Box::new(PascalCaseTypesRule),
+        Box::new(UpperCaseConstantsRule),
+        Box::new(UnreachableCodeRule),
+        Box::new(EmptyMatchArmRule),
+        Box::new(EmptyBlockRule),
+        Box::new(ConstantConditionRule),
+        Box::new(SelfAssignmentRule),
+        Box::new(FunctionLengthRule),
+        Box::new(TooManyParametersRule),
+        Box::new(ComplexityRule),
+        Box::new(LineLengthRule),
+    ]
+}
+
+/// A node met while walking a function body
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Block(&'a Block),
+    Statement(&'a Statement),
+    Expression(&'a Expression),
+    Arm(&'a MatchArm),
+}
+
+/// Visit `block` and everything nested in it, outermost first
+fn walk_block<'a>(block: &'a Block, visit: &mut dyn FnMut(Node<'a>)) {
+    visit(Node::Block(block));
+    for statement in &block.statements {
+        walk_statement(statement, visit);
     }
-    
-    fn description(&self) -> &str {
-        "Lines should not exceed maximum length"
+}
+
+fn walk_statement<'a>(statement: &'a Statement, visit: &mut dyn FnMut(Node<'a>)) {
+    visit(Node::Statement(statement));
+    match statement {
+        Statement::Expression(stmt) => walk_expression(&stmt.expression, visit),
+        Statement::Let(stmt) => {
+            if let Some(initializer) = &stmt.initializer {
+                walk_expression(initializer, visit);
+            }
+        }
+        Statement::Return(stmt) => {
+            if let Some(value) = &stmt.value {
+                walk_expression(value, visit);
+            }
+        }
+        Statement::If(stmt) => walk_if(stmt, visit),
+        Statement::While(stmt) => {
+            walk_expression(&stmt.condition, visit);
+            walk_block(&stmt.body, visit);
+        }
+        Statement::For(stmt) => {
+            walk_expression(&stmt.iterable, visit);
+            walk_block(&stmt.body, visit);
+        }
+        Statement::Match(stmt) => walk_match(stmt, visit),
+        Statement::Block(block) => walk_block(block, visit),
+        Statement::Query(stmt) => {
+            if let Some(handler) = &stmt.handler {
+                walk_block(handler, visit);
+            }
+        }
+        Statement::Assert(stmt) => stmt.args.iter().for_each(|arg| walk_expression(arg, visit)),
+        Statement::Retract(stmt) => stmt.args.iter().for_each(|arg| walk_expression(arg, visit)),
+        Statement::Semantic(_) => {}
     }
-    
-    fn check(&self, source: &str) -> Result<Vec<LintIssue>> {
-        let mut issues = Vec::new();
-        
-        for (line_num, line) in source.lines().enumerate() {
-            if line.len() > self.max_length {
-                issues.push(LintIssue {
-                    rule: self.name().to_string(),
-                    severity: Severity::Warning,
-                    line: line_num + 1,
-                    column: self.max_length + 1,
-                    message: format!("Line too long ({} > {})", line.len(), self.max_length),
-                    suggestion: Some("Consider breaking this line".to_string()),
-                });
+}
+
+fn walk_expression<'a>(expr: &'a Expression, visit: &mut dyn FnMut(Node<'a>)) {
+    visit(Node::Expression(expr));
+    match expr {
+        Expression::Async(async_expr) => walk_block(&async_expr.body, visit),
+        Expression::Match(stmt) => walk_match(stmt, visit),
+        Expression::If(stmt) => walk_if(stmt, visit),
+        _ => {
+            for child in subexpressions(expr) {
+                walk_expression(child, visit);
             }
         }
-        
-        Ok(issues)
     }
 }
 
-/// Unused variable rule
-#[derive(Debug)]
-struct UnusedVariableRule;
+/// The expressions directly inside `expr`; empty for the expressions holding blocks
+fn subexpressions(expr: &Expression) -> Vec<&Expression> {
+    match expr {
+        Expression::Binary(binary) => vec![&binary.left, &binary.right],
+        Expression::Unary(unary) => vec![&unary.operand],
+        Expression::Call(call) => std::iter::once(&*call.callee).chain(&call.arguments).collect(),
+        Expression::FieldAccess(access) => vec![&access.object],
+        Expression::Index(index) => vec![&index.object, &index.index],
+        Expression::Array(array) => array.elements.iter().collect(),
+        Expression::ArrayRepeat(repeat) => vec![&repeat.value, &repeat.count],
+        Expression::Map(map) => map.entries.iter().flat_map(|(key, value)| [key, value]).collect(),
+        Expression::Set(set) => set.elements.iter().collect(),
+        Expression::Tuple(tuple) => tuple.elements.iter().collect(),
+        Expression::Struct(literal) => literal.fields.iter().map(|(_, value)| value).collect(),
+        Expression::Enum(literal) => literal.fields.iter().flatten().collect(),
+        Expression::Lambda(lambda) => vec![&lambda.body],
+        Expression::Await(await_expr) => vec![&await_expr.expression],
+        Expression::Literal(_)
+        | Expression::Identifier(_)
+        | Expression::Async(_)
+        | Expression::Match(_)
+        | Expression::If(_)
+        | Expression::Query(_) => Vec::new(),
+    }
+}
 
-impl UnusedVariableRule {
-    fn new() -> Self {
-        Self
+fn walk_if<'a>(stmt: &'a IfStatement, visit: &mut dyn FnMut(Node<'a>)) {
+    walk_expression(&stmt.condition, visit);
+    walk_block(&stmt.then_block, visit);
+    if let Some(else_block) = &stmt.else_block {
+        walk_block(else_block, visit);
     }
 }
 
-impl LintRule for UnusedVariableRule {
-    fn name(&self) -> &str {
-        "unused_variable"
+fn walk_match<'a>(stmt: &'a MatchStatement, visit: &mut dyn FnMut(Node<'a>)) {
+    walk_expression(&stmt.expression, visit);
+    for arm in &stmt.arms {
+        visit(Node::Arm(arm));
+        if let Some(guard) = &arm.guard {
+            walk_expression(guard, visit);
+        }
+        walk_block(&arm.body, visit);
     }
-    
-    fn description(&self) -> &str {
-        "Variables should be used after declaration"
+}
+
+/// The `if` of an `if` statement or expression
+fn as_if<'a>(node: Node<'a>) -> Option<&'a IfStatement> {
+    match node {
+        Node::Statement(Statement::If(stmt)) => Some(stmt),
+        Node::Expression(Expression::If(stmt)) => Some(stmt),
+        _ => None,
     }
-    
-    fn check(&self, source: &str) -> Result<Vec<LintIssue>> {
-        let mut issues = Vec::new();
-        let mut declared_vars = HashSet::new();
-        let mut used_vars = HashSet::new();
-        
-        // Simple pattern matching for variable declarations and usage
-        for (line_num, line) in source.lines().enumerate() {
-            let trimmed = line.trim();
-            
-            // Check for variable declarations (let keyword)
-            if trimmed.starts_with("let ") {
-                if let Some(var_name) = self.extract_variable_name(trimmed) {
-                    declared_vars.insert((var_name.clone(), line_num + 1));
+}
+
+/// Source location of a statement, or of its block when it has none
+fn statement_span(statement: &Statement, block: &Block) -> Span {
+    statement.span().unwrap_or(block.span)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BindingKind {
+    Parameter,
+    Variable,
+}
+
+#[derive(Debug, Clone)]
+struct Binding<'a> {
+    name: &'a str,
+    kind: BindingKind,
+    span: Span,
+    used: bool,
+}
+
+/// The bindings of one function body, resolved the way the semantic analyzer
+/// scopes them
+#[derive(Debug, Default)]
+struct Scopes<'a> {
+    live: Vec<Binding<'a>>,
+    frames: Vec<usize>,
+    unused: Vec<Binding<'a>>,
+    /// Each binding that hides an earlier one, with the earlier one's location
+    shadowing: Vec<(Binding<'a>, Span)>,
+}
+
+impl<'a> Scopes<'a> {
+    /// Resolve the bindings of `decl`
+    fn of_function(decl: &'a FunctionDecl) -> Self {
+        let mut scopes = Self::default();
+        scopes.enter();
+        for parameter in &decl.parameters {
+            if let Parameter::Regular { name, .. } = parameter {
+                scopes.declare(name, BindingKind::Parameter, decl.span);
+            }
+        }
+        scopes.block(&decl.body);
+        scopes.exit();
+        scopes
+    }
+
+    fn enter(&mut self) {
+        self.frames.push(self.live.len());
+    }
+
+    fn exit(&mut self) {
+        let start = self.frames.pop().unwrap_or(0);
+        self.unused.extend(self.live.drain(start..).filter(|binding| !binding.used));
+    }
+
+    fn declare(&mut self, name: &'a str, kind: BindingKind, span: Span) {
+        if name.is_empty() {
+            return;
+        }
+        let binding = Binding { name, kind, span, used: false };
+        if let Some(earlier) = self.live.iter().rev().find(|earlier| earlier.name == name) {
+            self.shadowing.push((binding.clone(), earlier.span));
+        }
+        self.live.push(binding);
+    }
+
+    fn use_name(&mut self, name: &str) {
+        if let Some(binding) = self.live.iter_mut().rev().find(|binding| binding.name == name) {
+            binding.used = true;
+        }
+    }
+
+    fn block(&mut self, block: &'a Block) {
+        self.enter();
+        for statement in &block.statements {
+            self.statement(statement);
+        }
+        self.exit();
+    }
+
+    fn statement(&mut self, statement: &'a Statement) {
+        match statement {
+            Statement::Expression(stmt) => self.expression(&stmt.expression),
+            Statement::Let(stmt) => {
+                if let Some(initializer) = &stmt.initializer {
+                    self.expression(initializer);
+                }
+                match &stmt.pattern {
+                    Some(pattern) => {
+                        for name in pattern.bindings() {
+                            self.declare(name, BindingKind::Variable, stmt.span);
+                        }
+                    }
+                    None => self.declare(&stmt.name, BindingKind::Variable, stmt.span),
                 }
             }
-            
-            // Check for variable usage
-            for word in trimmed.split_whitespace() {
-                if declared_vars.iter().any(|(name, _)| name == word) {
-                    used_vars.insert(word.to_string());
+            Statement::Return(stmt) => {
+                if let Some(value) = &stmt.value {
+                    self.expression(value);
                 }
             }
+            Statement::If(stmt) => self.if_statement(stmt),
+            Statement::While(stmt) => {
+                self.expression(&stmt.condition);
+                self.block(&stmt.body);
+            }
+            Statement::For(stmt) => {
+                self.expression(&stmt.iterable);
+                self.enter();
+                self.declare(&stmt.variable, BindingKind::Variable, stmt.span);
+                self.block(&stmt.body);
+                self.exit();
+            }
+            Statement::Match(stmt) => self.match_statement(stmt),
+            Statement::Block(block) => self.block(block),
+            Statement::Query(stmt) => {
+                if let Some(handler) = &stmt.handler {
+                    self.block(handler);
+                }
+            }
+            Statement::Assert(stmt) => stmt.args.iter().for_each(|arg| self.expression(arg)),
+            Statement::Retract(stmt) => stmt.args.iter().for_each(|arg| self.expression(arg)),
+            Statement::Semantic(_) => {}
         }
-        
-        // Find unused variables
-        for (var_name, line_num) in declared_vars {
-            if !used_vars.contains(&var_name) {
-                issues.push(LintIssue {
-                    rule: self.name().to_string(),
-                    severity: Severity::Warning,
-                    line: line_num,
-                    column: 1,
-                    message: format!("Variable '{}' is declared but never used", var_name),
-                    suggestion: Some(format!("Remove unused variable '{}'", var_name)),
-                });
+    }
+
+    fn if_statement(&mut self, stmt: &'a IfStatement) {
+        self.expression(&stmt.condition);
+        self.block(&stmt.then_block);
+        if let Some(else_block) = &stmt.else_block {
+            self.block(else_block);
+        }
+    }
+
+    fn match_statement(&mut self, stmt: &'a MatchStatement) {
+        self.expression(&stmt.expression);
+        for arm in &stmt.arms {
+            self.enter();
+            for name in arm.pattern.bindings() {
+                self.declare(name, BindingKind::Variable, arm.span);
+            }
+            if let Some(guard) = &arm.guard {
+                self.expression(guard);
+            }
+            self.block(&arm.body);
+            self.exit();
+        }
+    }
+
+    fn expression(&mut self, expr: &'a Expression) {
+        match expr {
+            Expression::Identifier(name) => self.use_name(name),
+            // Assigning to a variable does not read it
+            Expression::Binary(binary) if binary.operator == BinaryOperator::Assign => {
+                if !matches!(*binary.left, Expression::Identifier(_)) {
+                    self.expression(&binary.left);
+                }
+                self.expression(&binary.right);
+            }
+            Expression::Lambda(lambda) => {
+                self.enter();
+                for parameter in &lambda.parameters {
+                    if let Parameter::Regular { name, .. } = parameter {
+                        self.live.push(Binding { name, kind: BindingKind::Parameter, span: Span::default(), used: true });
+                    }
+                }
+                self.expression(&lambda.body);
+                self.exit();
+            }
+            Expression::Async(async_expr) => self.block(&async_expr.body),
+            Expression::Match(stmt) => self.match_statement(stmt),
+            Expression::If(stmt) => self.if_statement(stmt),
+            _ => {
+                for child in subexpressions(expr) {
+                    self.expression(child);
+                }
             }
         }
-        
-        Ok(issues)
     }
 }
 
-impl UnusedVariableRule {
-    fn extract_variable_name(&self, line: &str) -> Option<String> {
-        // Simple extraction: "let var_name = ..."
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 2 && parts[0] == "let" {
-            Some(parts[1].trim_end_matches('=').to_string())
+/// Whether control never gets past `statement`
+fn diverges(statement: &Statement) -> bool {
+    match statement {
+        Statement::Return(_) => true,
+        Statement::Expression(ExpressionStatement { expression: Expression::Identifier(name), .. }) => {
+            name == "__break__" || name == "__continue__"
+        }
+        Statement::Block(block) => block_diverges(block),
+        Statement::If(stmt) => stmt.else_block.as_ref()
+            .is_some_and(|else_block| block_diverges(&stmt.then_block) && block_diverges(else_block)),
+        _ => false,
+    }
+}
+
+fn block_diverges(block: &Block) -> bool {
+    block.statements.iter().any(diverges)
+}
+
+/// `x` or `x.field`, written out, for the places an assignment can name
+fn place(expr: &Expression) -> Option<String> {
+    match expr {
+        Expression::Identifier(name) => Some(name.clone()),
+        Expression::FieldAccess(access) => Some(format!("{}.{}", place(&access.object)?, access.field)),
+        _ => None,
+    }
+}
+
+/// Cased letters all lowercase; names in uncased scripts such as Arabic pass
+fn is_snake_case(name: &str) -> bool {
+    !name.chars().any(char::is_uppercase)
+}
+
+fn is_pascal_case(name: &str) -> bool {
+    !name.starts_with(char::is_lowercase) && !name.contains('_')
+}
+
+fn is_upper_case(name: &str) -> bool {
+    !name.chars().any(char::is_lowercase)
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if index > 0 && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
         } else {
-            None
+            snake.push(c);
         }
     }
+    snake
 }
 
-/// Naming convention rule
+fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect()
+}
+
+fn to_upper_case(name: &str) -> String {
+    to_snake_case(name).to_uppercase()
+}
+
+/// Let bindings that are never read
 #[derive(Debug)]
-struct NamingConventionRule {
-    conventions: NamingConventions,
+struct UnusedVariableRule;
+
+impl LintRule for UnusedVariableRule {
+    fn name(&self) -> &'static str {
+        "unused_variable"
+    }
+
+    fn description(&self) -> &'static str {
+        "Variables should be read after they are declared"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        context.functions().into_iter()
+            .flat_map(|(decl, _)| Scopes::of_function(decl).unused)
+            .filter(|binding| binding.kind == BindingKind::Variable && !binding.name.starts_with('_'))
+            .map(|binding| {
+                Finding::new(binding.span, format!("unused variable `{}`", binding.name))
+                    .with_suggestion(format!("remove it, or name it `_{}` if it is unused on purpose", binding.name))
+            })
+            .collect()
+    }
 }
 
-impl NamingConventionRule {
-    fn new(conventions: NamingConventions) -> Self {
-        Self { conventions }
+/// Parameters that are never read; methods of trait impls are left out, as
+/// the trait fixes their parameters
+#[derive(Debug)]
+struct UnusedParameterRule;
+
+impl LintRule for UnusedParameterRule {
+    fn name(&self) -> &'static str {
+        "unused_parameter"
     }
-    
-    fn check_naming_style(&self, name: &str, expected_style: &NamingStyle) -> bool {
-        match expected_style {
-            NamingStyle::SnakeCase => name.chars().all(|c| c.is_lowercase() || c.is_numeric() || c == '_'),
-            NamingStyle::CamelCase => name.chars().next().map_or(false, |c| c.is_lowercase()) && !name.contains('_'),
-            NamingStyle::PascalCase => name.chars().next().map_or(false, |c| c.is_uppercase()) && !name.contains('_'),
-            NamingStyle::ScreamingSnakeCase => name.chars().all(|c| c.is_uppercase() || c.is_numeric() || c == '_'),
-            NamingStyle::KebabCase => name.chars().all(|c| c.is_lowercase() || c.is_numeric() || c == '-'),
+
+    fn description(&self) -> &'static str {
+        "Parameters should be read by their function"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for (decl, fixed_signature) in context.functions() {
+            if fixed_signature {
+                continue;
+            }
+            for binding in Scopes::of_function(decl).unused {
+                if binding.kind == BindingKind::Parameter && !binding.name.starts_with('_') {
+                    findings.push(
+                        Finding::new(decl.span, format!("unused parameter `{}` of `{}`", binding.name, decl.name))
+                            .with_suggestion(format!("remove it, or name it `_{}` if it is unused on purpose", binding.name)),
+                    );
+                }
+            }
         }
+        findings
     }
 }
 
-impl LintRule for NamingConventionRule {
-    fn name(&self) -> &str {
-        "naming_convention"
+/// Private top-level functions nothing else refers to
+#[derive(Debug)]
+struct UnusedFunctionRule;
+
+impl LintRule for UnusedFunctionRule {
+    fn name(&self) -> &'static str {
+        "unused_function"
     }
-    
-    fn description(&self) -> &str {
-        "Names should follow naming conventions"
+
+    fn description(&self) -> &'static str {
+        "Private functions should be called"
     }
-    
-    fn check(&self, source: &str) -> Result<Vec<LintIssue>> {
-        let mut issues = Vec::new();
-        
-        for (line_num, line) in source.lines().enumerate() {
-            let trimmed = line.trim();
-            
-            // Check function names
-            if trimmed.starts_with("fn ") {
-                if let Some(func_name) = self.extract_function_name(trimmed) {
-                    if !self.check_naming_style(&func_name, &self.conventions.function_style) {
-                        issues.push(LintIssue {
-                            rule: self.name().to_string(),
-                            severity: Severity::Warning,
-                            line: line_num + 1,
-                            column: 1,
-                            message: format!("Function name '{}' doesn't follow naming convention", func_name),
-                            suggestion: Some(format!("Use {:?} naming style", self.conventions.function_style)),
-                        });
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let mut referenced = HashSet::new();
+        for (decl, _) in context.functions() {
+            walk_block(&decl.body, &mut |node| {
+                if let Node::Expression(Expression::Identifier(name)) = node {
+                    // Calling itself does not make a function used
+                    if *name != decl.name {
+                        referenced.insert(name.as_str());
                     }
                 }
+            });
+        }
+        for item in &context.program.items {
+            if let Item::Const(decl) = item {
+                walk_expression(&decl.value, &mut |node| {
+                    if let Node::Expression(Expression::Identifier(name)) = node {
+                        referenced.insert(name.as_str());
+                    }
+                });
+            }
+        }
+
+        context.program.items.iter()
+            .filter_map(|item| match item {
+                Item::Function(decl) => Some(decl),
+                _ => None,
+            })
+            .filter(|decl| {
+                decl.visibility == Visibility::Private
+                    && !decl.is_test
+                    && decl.name != "main"
+                    && !decl.name.starts_with('_')
+                    && !referenced.contains(decl.name.as_str())
+            })
+            .map(|decl| {
+                Finding::new(decl.span, format!("function `{}` is never called", decl.name))
+                    .with_suggestion("remove it, or make it `pub` if other modules call it")
+            })
+            .collect()
+    }
+}
+
+/// Bindings that hide an earlier variable or parameter of the same name
+#[derive(Debug)]
+struct ShadowingRule;
+
+impl LintRule for ShadowingRule {
+    fn name(&self) -> &'static str {
+        "shadowing"
+    }
+
+    fn description(&self) -> &'static str {
+        "Variables should not hide earlier bindings of the same name"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        context.functions().into_iter()
+            .flat_map(|(decl, _)| Scopes::of_function(decl).shadowing)
+            .filter(|(binding, _)| binding.kind == BindingKind::Variable && !binding.name.starts_with('_'))
+            .map(|(binding, earlier)| {
+                Finding::new(
+                    binding.span,
+                    format!("`{}` shadows the binding on line {}", binding.name, context.line(earlier.start)),
+                )
+                .with_suggestion("give it a name of its own")
+            })
+            .collect()
+    }
+}
+
+/// Functions, parameters and variables named in snake_case
+#[derive(Debug)]
+struct SnakeCaseRule;
+
+impl LintRule for SnakeCaseRule {
+    fn name(&self) -> &'static str {
+        "non_snake_case"
+    }
+
+    fn description(&self) -> &'static str {
+        "Functions, parameters and variables should have snake_case names"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let mut check = |kind: &str, name: &str, span: Span| {
+            if !is_snake_case(name) {
+                findings.push(
+                    Finding::new(span, format!("{} `{}` should have a snake_case name", kind, name))
+                        .with_suggestion(format!("rename it to `{}`", to_snake_case(name))),
+                );
             }
-            
-            // Check variable names
-            if trimmed.starts_with("let ") {
-                if let Some(var_name) = self.extract_variable_name(trimmed) {
-                    if !self.check_naming_style(&var_name, &self.conventions.variable_style) {
-                        issues.push(LintIssue {
-                            rule: self.name().to_string(),
-                            severity: Severity::Warning,
-                            line: line_num + 1,
-                            column: 1,
-                            message: format!("Variable name '{}' doesn't follow naming convention", var_name),
-                            suggestion: Some(format!("Use {:?} naming style", self.conventions.variable_style)),
-                        });
+        };
+
+        for (decl, fixed_signature) in context.functions() {
+            if !fixed_signature {
+                check("function", &decl.name, decl.span);
+                for parameter in &decl.parameters {
+                    if let Parameter::Regular { name, .. } = parameter {
+                        check("parameter", name, decl.span);
                     }
                 }
             }
+            walk_block(&decl.body, &mut |node| match node {
+                Node::Statement(Statement::Let(stmt)) => match &stmt.pattern {
+                    Some(pattern) => pattern.bindings().into_iter().for_each(|name| check("variable", name, stmt.span)),
+                    None => check("variable", &stmt.name, stmt.span),
+                },
+                Node::Statement(Statement::For(stmt)) => check("variable", &stmt.variable, stmt.span),
+                _ => {}
+            });
         }
-        
-        Ok(issues)
+        findings
     }
 }
 
-impl NamingConventionRule {
-    fn extract_function_name(&self, line: &str) -> Option<String> {
-        // Simple extraction: "fn function_name("
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 2 && parts[0] == "fn" {
-            Some(parts[1].trim_end_matches('(').to_string())
-        } else {
-            None
+/// Types and enum variants named in PascalCase
+#[derive(Debug)]
+struct PascalCaseTypesRule;
+
+impl LintRule for PascalCaseTypesRule {
+    fn name(&self) -> &'static str {
+        "non_pascal_case_types"
+    }
+
+    fn description(&self) -> &'static str {
+        "Types and enum variants should have PascalCase names"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let mut check = |kind: &str, name: &str, span: Span| {
+            if !is_pascal_case(name) {
+                findings.push(
+                    Finding::new(span, format!("{} `{}` should have a PascalCase name", kind, name))
+                        .with_suggestion(format!("rename it to `{}`", to_pascal_case(name))),
+                );
+            }
+        };
+
+        for item in &context.program.items {
+            match item {
+                Item::Struct(decl) => check("struct", &decl.name, decl.span),
+                Item::Enum(decl) => {
+                    check("enum", &decl.name, decl.span);
+                    for variant in &decl.variants {
+                        check("variant", &variant.name, decl.span);
+                    }
+                }
+                Item::Class(decl) => check("class", &decl.name, decl.span),
+                Item::Interface(decl) => check("interface", &decl.name, decl.span),
+                Item::Trait(decl) => check("trait", &decl.name, decl.span),
+                _ => {}
+            }
         }
+        findings
     }
-    
-    fn extract_variable_name(&self, line: &str) -> Option<String> {
-        // Simple extraction: "let var_name = ..."
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 2 && parts[0] == "let" {
-            Some(parts[1].trim_end_matches('=').to_string())
-        } else {
-            None
+}
+
+/// Constants named in UPPER_CASE
+#[derive(Debug)]
+struct UpperCaseConstantsRule;
+
+impl LintRule for UpperCaseConstantsRule {
+    fn name(&self) -> &'static str {
+        "non_upper_case_constants"
+    }
+
+    fn description(&self) -> &'static str {
+        "Constants should have UPPER_CASE names"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        context.program.items.iter()
+            .filter_map(|item| match item {
+                Item::Const(decl) if !is_upper_case(&decl.name) => Some(
+                    Finding::new(decl.span, format!("constant `{}` should have an UPPER_CASE name", decl.name))
+                        .with_suggestion(format!("rename it to `{}`", to_upper_case(&decl.name))),
+                ),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Statements after a `return`, `break` or `continue` in the same block
+#[derive(Debug)]
+struct UnreachableCodeRule;
+
+impl LintRule for UnreachableCodeRule {
+    fn name(&self) -> &'static str {
+        "unreachable_code"
+    }
+
+    fn description(&self) -> &'static str {
+        "Code after return, break or continue never runs"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for (decl, _) in context.functions() {
+            walk_block(&decl.body, &mut |node| {
+                let Node::Block(block) = node else { return };
+                let Some(index) = block.statements.iter().position(diverges) else { return };
+                if let Some(next) = block.statements.get(index + 1) {
+                    findings.push(
+                        Finding::new(statement_span(next, block), "unreachable statement")
+                            .with_suggestion("remove it: the statement before it always leaves the block"),
+                    );
+                }
+            });
         }
+        findings
     }
 }
 
-/// Complexity rule
+/// Match arms with an empty body, other than a `_ => {}` catch-all
 #[derive(Debug)]
-struct ComplexityRule {
-    max_complexity: usize,
+struct EmptyMatchArmRule;
+
+impl LintRule for EmptyMatchArmRule {
+    fn name(&self) -> &'static str {
+        "empty_match_arm"
+    }
+
+    fn description(&self) -> &'static str {
+        "Match arms should do something"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for (decl, _) in context.functions() {
+            walk_block(&decl.body, &mut |node| {
+                if let Node::Arm(arm) = node {
+                    if arm.body.statements.is_empty() && arm.pattern != Pattern::Wildcard {
+                        findings.push(
+                            Finding::new(arm.span, "match arm does nothing")
+                                .with_suggestion("handle the case, or leave it to a `_ => {}` arm"),
+                        );
+                    }
+                }
+            });
+        }
+        findings
+    }
 }
 
-impl ComplexityRule {
-    fn new(max_complexity: usize) -> Self {
-        Self { max_complexity }
+/// Function bodies and the blocks of `if`, `else` and loops with no statements
+#[derive(Debug)]
+struct EmptyBlockRule;
+
+impl LintRule for EmptyBlockRule {
+    fn name(&self) -> &'static str {
+        "empty_block"
+    }
+
+    fn description(&self) -> &'static str {
+        "Blocks should not be empty"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for (decl, fixed_signature) in context.functions() {
+            if decl.body.statements.is_empty() && !fixed_signature {
+                findings.push(
+                    Finding::new(decl.span, format!("function `{}` has an empty body", decl.name))
+                        .with_suggestion("implement it or remove it"),
+                );
+            }
+            walk_block(&decl.body, &mut |node| {
+                let blocks: Vec<&Block> = match (node, as_if(node)) {
+                    (_, Some(stmt)) => std::iter::once(&stmt.then_block).chain(&stmt.else_block).collect(),
+                    (Node::Statement(Statement::While(stmt)), _) => vec![&stmt.body],
+                    (Node::Statement(Statement::For(stmt)), _) => vec![&stmt.body],
+                    (Node::Statement(Statement::Block(block)), _) => vec![block],
+                    _ => Vec::new(),
+                };
+                for block in blocks.into_iter().filter(|block| block.statements.is_empty()) {
+                    findings.push(Finding::new(block.span, "empty block").with_suggestion("add the missing code or remove the block"));
+                }
+            });
+        }
+        findings
     }
 }
 
-impl LintRule for ComplexityRule {
-    fn name(&self) -> &str {
-        "complexity"
+/// `if true`, `if false` and `while false`; `while true` is the loop `loop`
+/// desugars to and is left alone
+#[derive(Debug)]
+struct ConstantConditionRule;
+
+impl LintRule for ConstantConditionRule {
+    fn name(&self) -> &'static str {
+        "constant_condition"
     }
-    
-    fn description(&self) -> &str {
-        "Functions should not be too complex"
+
+    fn description(&self) -> &'static str {
+        "Conditions should depend on something"
     }
-    
-    fn check(&self, source: &str) -> Result<Vec<LintIssue>> {
-        let mut issues = Vec::new();
-        let mut in_function = false;
-        let mut function_start = 0;
-        let mut complexity = 1; // Base complexity
-        
-        for (line_num, line) in source.lines().enumerate() {
-            let trimmed = line.trim();
-            
-            if trimmed.starts_with("fn ") {
-                in_function = true;
-                function_start = line_num + 1;
-                complexity = 1;
-            } else if in_function && trimmed == "}" {
-                if complexity > self.max_complexity {
-                    issues.push(LintIssue {
-                        rule: self.name().to_string(),
-                        severity: Severity::Warning,
-                        line: function_start,
-                        column: 1,
-                        message: format!("Function complexity ({}) exceeds maximum ({})", complexity, self.max_complexity),
-                        suggestion: Some("Consider breaking this function into smaller functions".to_string()),
-                    });
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for (decl, _) in context.functions() {
+            walk_block(&decl.body, &mut |node| {
+                if let Some(stmt) = as_if(node) {
+                    if let Expression::Literal(Literal::Boolean(value)) = stmt.condition {
+                        let suggestion = if value {
+                            "remove the `if` and keep its body"
+                        } else {
+                            "remove the `if`, keeping only its `else` branch"
+                        };
+                        findings.push(
+                            Finding::new(stmt.span, format!("this condition is always {}", value)).with_suggestion(suggestion),
+                        );
+                    }
+                } else if let Node::Statement(Statement::While(stmt)) = node {
+                    if stmt.condition == Expression::Literal(Literal::Boolean(false)) {
+                        findings.push(Finding::new(stmt.span, "this loop never runs").with_suggestion("remove the loop"));
+                    }
+                }
+            });
+        }
+        findings
+    }
+}
+
+/// Assignments of a variable or field to itself
+#[derive(Debug)]
+struct SelfAssignmentRule;
+
+impl LintRule for SelfAssignmentRule {
+    fn name(&self) -> &'static str {
+        "self_assignment"
+    }
+
+    fn description(&self) -> &'static str {
+        "Assigning a value to itself does nothing"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for (decl, _) in context.functions() {
+            walk_block(&decl.body, &mut |node| {
+                let Node::Expression(Expression::Binary(binary)) = node else { return };
+                if binary.operator != BinaryOperator::Assign {
+                    return;
                 }
-                in_function = false;
-            } else if in_function {
-                // Count complexity-increasing constructs
-                if trimmed.contains("if ") || trimmed.contains("else") ||
-                   trimmed.contains("while ") || trimmed.contains("for ") ||
-                   trimmed.contains("match ") || trimmed.contains("&&") ||
-                   trimmed.contains("||") {
-                    complexity += 1;
+                if let (Some(target), Some(value)) = (place(&binary.left), place(&binary.right)) {
+                    if target == value {
+                        findings.push(
+                            Finding::new(binary.span, format!("`{}` is assigned to itself", target))
+                                .with_suggestion("remove the assignment"),
+                        );
+                    }
                 }
-            }
+            });
         }
-        
-        Ok(issues)
+        findings
     }
 }
 
-/// Dead code rule
+/// Functions longer than `max_function_length` lines
 #[derive(Debug)]
-struct DeadCodeRule;
+struct FunctionLengthRule;
 
-impl DeadCodeRule {
-    fn new() -> Self {
-        Self
+impl LintRule for FunctionLengthRule {
+    fn name(&self) -> &'static str {
+        "function_length"
+    }
+
+    fn description(&self) -> &'static str {
+        "Functions should not be overly long"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let max = context.config.max_function_length;
+        context.functions().into_iter()
+            .filter(|(decl, _)| !decl.span.is_empty())
+            .filter_map(|(decl, _)| {
+                let lines = context.line(decl.span.end) - context.line(decl.span.start) + 1;
+                (lines > max).then(|| {
+                    Finding::new(decl.span, format!("function `{}` is {} lines long (max {})", decl.name, lines, max))
+                        .with_suggestion("split it into smaller functions")
+                })
+            })
+            .collect()
     }
 }
 
-impl LintRule for DeadCodeRule {
-    fn name(&self) -> &str {
-        "dead_code"
+/// Functions taking more than `max_parameters` parameters
+#[derive(Debug)]
+struct TooManyParametersRule;
+
+impl LintRule for TooManyParametersRule {
+    fn name(&self) -> &'static str {
+        "too_many_parameters"
     }
-    
-    fn description(&self) -> &str {
-        "Remove unreachable code"
+
+    fn description(&self) -> &'static str {
+        "Functions should not take too many parameters"
     }
-    
-    fn check(&self, source: &str) -> Result<Vec<LintIssue>> {
-        let mut issues = Vec::new();
-        let mut after_return = false;
-        
-        for (line_num, line) in source.lines().enumerate() {
-            let trimmed = line.trim();
-            
-            if trimmed.starts_with("return") {
-                after_return = true;
-            } else if after_return && !trimmed.is_empty() && !trimmed.starts_with('}') {
-                issues.push(LintIssue {
-                    rule: self.name().to_string(),
-                    severity: Severity::Warning,
-                    line: line_num + 1,
-                    column: 1,
-                    message: "Unreachable code after return statement".to_string(),
-                    suggestion: Some("Remove unreachable code".to_string()),
-                });
-                after_return = false; // Only report once per block
-            } else if trimmed.starts_with('}') {
-                after_return = false;
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let max = context.config.max_parameters;
+        context.functions().into_iter()
+            .filter(|(_, fixed_signature)| !fixed_signature)
+            .filter_map(|(decl, _)| {
+                let count = decl.parameters.iter().filter(|parameter| matches!(parameter, Parameter::Regular { .. })).count();
+                (count > max).then(|| {
+                    Finding::new(decl.span, format!("function `{}` takes {} parameters (max {})", decl.name, count, max))
+                        .with_suggestion("group related parameters into a struct")
+                })
+            })
+            .collect()
+    }
+}
+
+/// Functions whose cyclomatic complexity exceeds `max_complexity`
+#[derive(Debug)]
+struct ComplexityRule;
+
+impl ComplexityRule {
+    /// One plus a point per branch: each `if`, loop, extra match arm, guard,
+    /// `&&` and `||`
+    fn complexity(body: &Block) -> usize {
+        let mut complexity = 1;
+        walk_block(body, &mut |node| {
+            complexity += match node {
+                Node::Statement(Statement::If(_) | Statement::While(_) | Statement::For(_)) => 1,
+                Node::Expression(Expression::If(_)) => 1,
+                Node::Statement(Statement::Match(stmt)) => stmt.arms.len().saturating_sub(1),
+                Node::Expression(Expression::Match(stmt)) => stmt.arms.len().saturating_sub(1),
+                Node::Arm(arm) => usize::from(arm.guard.is_some()),
+                Node::Expression(Expression::Binary(binary)) => {
+                    usize::from(matches!(binary.operator, BinaryOperator::And | BinaryOperator::Or))
+                }
+                _ => 0,
+            };
+        });
+        complexity
+    }
+}
+
+impl LintRule for ComplexityRule {
+    fn name(&self) -> &'static str {
+        "complexity"
+    }
+
+    fn description(&self) -> &'static str {
+        "Functions should not be too complex"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let max = context.config.max_complexity;
+        context.functions().into_iter()
+            .filter_map(|(decl, _)| {
+                let complexity = Self::complexity(&decl.body);
+                (complexity > max).then(|| {
+                    Finding::new(
+                        decl.span,
+                        format!("function `{}` has a cyclomatic complexity of {} (max {})", decl.name, complexity, max),
+                    )
+                    .with_suggestion("split it into smaller functions")
+                })
+            })
+            .collect()
+    }
+}
+
+/// Lines longer than `max_line_length` characters
+#[derive(Debug)]
+struct LineLengthRule;
+
+impl LintRule for LineLengthRule {
+    fn name(&self) -> &'static str {
+        "line_length"
+    }
+
+    fn description(&self) -> &'static str {
+        "Lines should not exceed maximum length"
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<Finding> {
+        let max = context.config.max_line_length;
+        let mut findings = Vec::new();
+        let mut offset = 0;
+        for line in context.source.split_inclusive('\n') {
+            let text = line.trim_end_matches(['\n', '\r']);
+            let length = text.chars().count();
+            if let Some((overflow, _)) = text.char_indices().nth(max) {
+                let span = Span::new(offset + overflow, offset + text.len());
+                findings.push(
+                    Finding::new(span, format!("line is {} characters long (max {})", length, max))
+                        .with_suggestion("break it over several lines"),
+                );
             }
+            offset += line.len();
         }
-        
-        Ok(issues)
+        findings
     }
 }
 
@@ -491,31 +1230,106 @@ impl Default for Linter {
 
 impl Default for LinterConfig {
     fn default() -> Self {
-        let mut enabled_rules = HashMap::new();
-        enabled_rules.insert("line_length".to_string(), true);
-        enabled_rules.insert("unused_variable".to_string(), true);
-        enabled_rules.insert("naming_convention".to_string(), true);
-        enabled_rules.insert("complexity".to_string(), true);
-        enabled_rules.insert("dead_code".to_string(), true);
-        
         Self {
-            enabled_rules,
-            rule_severities: HashMap::new(),
             max_line_length: 100,
             max_function_length: 50,
             max_complexity: 10,
-            naming_conventions: NamingConventions::default(),
+            max_parameters: 7,
+            levels: BTreeMap::new(),
         }
     }
 }
 
-impl Default for NamingConventions {
-    fn default() -> Self {
-        Self {
-            function_style: NamingStyle::SnakeCase,
-            variable_style: NamingStyle::SnakeCase,
-            constant_style: NamingStyle::ScreamingSnakeCase,
-            type_style: NamingStyle::PascalCase,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(linter: &Linter, source: &str) -> Vec<(&'static str, usize)> {
+        linter.analyze(source).unwrap().iter().map(|issue| (issue.rule, issue.line)).collect()
+    }
+
+    #[test]
+    fn test_unused_bindings_and_shadowing() {
+        let source = "\
+fn helper(n: int, unused: int) -> int {
+    let total = n + 1;
+    let ignored = 2;
+    let _quiet = 3;
+    return total;
+}
+fn never_called() {}
+fn main() -> int {
+    let x = helper(1, 2);
+    let x = x + 1;
+    return x;
+}
+";
+        let issues = lint(&Linter::new(), source);
+        assert!(issues.contains(&("unused_parameter", 1)));
+        assert!(issues.contains(&("unused_variable", 3)));
+        assert!(issues.contains(&("unused_function", 7)));
+        assert!(issues.contains(&("empty_block", 7)));
+        assert!(issues.contains(&("shadowing", 10)));
+        assert_eq!(issues.len(), 5, "{:?}", issues);
+    }
+
+    #[test]
+    fn test_naming_dead_code_and_suspicious_statements() {
+        let source = "\
+struct point_2d { x: int, }
+const limit: int = 3;
+pub fn getValue(n: int) -> int {
+    let mut myCount = n;
+    myCount = myCount;
+    if true { myCount = 1; }
+    match n {
+        1 => {}
+        _ => {}
+    }
+    return myCount;
+    print(\"done\");
+}
+";
+        let linter = Linter::new();
+        let issues = lint(&linter, source);
+        for expected in [
+            ("non_pascal_case_types", 1),
+            ("non_upper_case_constants", 2),
+            ("non_snake_case", 3),
+            ("non_snake_case", 4),
+            ("self_assignment", 5),
+            ("constant_condition", 6),
+            ("empty_match_arm", 8),
+            ("unreachable_code", 12),
+        ] {
+            assert!(issues.contains(&expected), "{:?} missing from {:?}", expected, issues);
         }
+        assert!(!issues.contains(&("empty_match_arm", 9)));
+
+        let rename = linter.analyze(source).unwrap().into_iter()
+            .find(|issue| issue.rule == "non_snake_case")
+            .and_then(|issue| issue.suggestion);
+        assert_eq!(rename.as_deref(), Some("rename it to `get_value`"));
+    }
+
+    #[test]
+    fn test_lints_table_sets_levels_and_limits() {
+        let config: LinterConfig = toml::from_str(
+            "shadowing = \"allow\"\nunused_variable = \"deny\"\nmax_parameters = 1\nno_such_rule = \"warn\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.level("shadowing"), LintLevel::Allow);
+        assert_eq!(config.level("line_length"), LintLevel::Warn);
+        assert!(toml::from_str::<LinterConfig>("shadowing = \"sometimes\"").is_err());
+        assert!(LinterConfig::default().is_default());
+
+        let linter = Linter::with_config(config);
+        assert_eq!(linter.unknown_rules(), ["no_such_rule"]);
+
+        let issues = linter.analyze("pub fn add(a: int, b: int) -> int {\n    let unused = 1;\n    let a = a + b;\n    return a;\n}\n").unwrap();
+        let rules: Vec<_> = issues.iter().map(|issue| issue.rule).collect();
+        assert_eq!(rules, ["too_many_parameters", "unused_variable"]);
+        assert_eq!(issues[1].severity, Severity::Error);
+        assert_eq!(issues[1].to_diagnostic().code, "unused_variable");
     }
 }
@@ -0,0 +1,180 @@
+//! # Differential Tests
+//!
+//! Runs every program of `examples/` through the interpreter and as a native
+//! executable built by the LLVM backend, and requires both to print the same
+//! output and exit with the same code. A program one side cannot run — it
+//! fails analysis, has no `main`, uses something the interpreter or the
+//! backend does not support, or hits a limit of the interpreter — is skipped
+//! and listed, so the programs compared grow with the backends.
+//!
+//! The executables link the runtime static library (see `codegen::link`),
+//! taken from `ALBAYAN_RUNTIME_LIB` or the target directory; build it first
+//! with `cargo build --features llvm`.
+
+#![cfg(feature = "llvm")]
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use albayan_lib::cancellation::CancellationToken;
+use albayan_lib::codegen::link::{Linker, RUNTIME_LIB_ENV, RUNTIME_LIB_NAME};
+use albayan_lib::interpreter::{Interpreter, Value};
+use albayan_lib::modules::graph::ModuleGraph;
+use albayan_lib::parser::ast::Item;
+use albayan_lib::runtime::panic::PANIC_EXIT_CODE;
+use albayan_lib::{Compiler, CompilerError, CompilerOptions};
+
+/// Longest a native program may run before it is killed
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a program printed and the code it exited with
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    output: String,
+    code: i32,
+}
+
+fn examples() -> Vec<PathBuf> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let mut examples: Vec<PathBuf> = fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ab"))
+        .collect();
+    examples.sort();
+    examples
+}
+
+/// The runtime library the executables link, next to the test binary's
+/// `deps` directory unless the environment names one
+fn runtime_library() -> PathBuf {
+    if let Some(path) = std::env::var_os(RUNTIME_LIB_ENV) {
+        return PathBuf::from(path);
+    }
+    let exe = std::env::current_exe().unwrap();
+    let library = exe.parent().and_then(Path::parent).unwrap().join(RUNTIME_LIB_NAME);
+    assert!(
+        library.is_file(),
+        "no runtime library at {}; run `cargo build --features llvm` or set {}",
+        library.display(),
+        RUNTIME_LIB_ENV
+    );
+    library
+}
+
+/// Run `main` in the interpreter; `Err` when the interpreter cannot run it
+fn interpret(graph: &ModuleGraph) -> Result<Outcome, String> {
+    let mut interpreter = Interpreter::for_graph(graph);
+    let code = match interpreter.call("main", Vec::new()) {
+        // The operating system keeps the low byte of an exit status
+        Ok(Value::Int(code)) if cfg!(unix) => (code & 0xff) as i32,
+        Ok(Value::Int(code)) => code as i32,
+        Ok(_) => 0,
+        Err(panic)
+            if panic.message.ends_with("is not supported by the interpreter")
+                || panic.message.starts_with("exceeded ")
+                || panic.message.starts_with("stack overflow:") =>
+        {
+            return Err(panic.message)
+        }
+        Err(_) => PANIC_EXIT_CODE,
+    };
+    Ok(Outcome { output: interpreter.take_output(), code })
+}
+
+/// Build `path` with the LLVM backend and run it; `Err` when the backend
+/// cannot compile it
+fn run_native(path: &Path, linker: &Linker) -> Result<Outcome, String> {
+    let options = CompilerOptions { use_llvm: true, ..Default::default() };
+    let object_code = match Compiler::with_options(options).source_file(path).compile_file() {
+        Ok(object_code) => object_code,
+        Err(CompilerError::CodeGenError(message)) => return Err(message),
+        Err(error) => panic!("{} passed analysis but failed to compile: {}", path.display(), error),
+    };
+
+    let stem = path.file_stem().unwrap().to_string_lossy();
+    let executable = std::env::temp_dir().join(format!("albayan_differential_{}_{}", std::process::id(), stem));
+    linker
+        .link_object_code(&object_code, &executable)
+        .unwrap_or_else(|error| panic!("{} failed to link: {}", path.display(), error));
+
+    let mut child = Command::new(&executable)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    // Read while the program runs so that a full pipe cannot stall it
+    let mut stdout = child.stdout.take().unwrap();
+    let reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if started.elapsed() > TIMEOUT {
+            child.kill().unwrap();
+            panic!("{} ran for more than {:?}", path.display(), TIMEOUT);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    let output = reader.join().unwrap().unwrap();
+    let _ = fs::remove_file(&executable);
+    // A program killed by a signal has no exit code
+    Ok(Outcome { output, code: status.code().unwrap_or(-1) })
+}
+
+#[test]
+fn test_interpreter_and_llvm_backend_agree_on_examples() {
+    let linker = Linker::detect().unwrap().with_runtime_library(runtime_library());
+    let mut compared = Vec::new();
+    let mut skipped = Vec::new();
+
+    for example in examples() {
+        let name = example.file_name().unwrap().to_string_lossy().into_owned();
+        let graph = match ModuleGraph::load_with_packages(&example, &HashMap::new(), &CancellationToken::default()) {
+            Ok(graph) => graph,
+            Err(error) => {
+                skipped.push(format!("{}: {}", name, error));
+                continue;
+            }
+        };
+        let has_main = graph.entry().program.items.iter().any(|item| matches!(item, Item::Function(function) if function.name == "main"));
+        if !has_main {
+            skipped.push(format!("{}: no main", name));
+            continue;
+        }
+        if let Err(error) = Compiler::new().source_file(&example).compile_file() {
+            skipped.push(format!("{}: {}", name, error));
+            continue;
+        }
+
+        let interpreted = match interpret(&graph) {
+            Ok(outcome) => outcome,
+            Err(reason) => {
+                skipped.push(format!("{}: interpreter: {}", name, reason));
+                continue;
+            }
+        };
+        let native = match run_native(&example, &linker) {
+            Ok(outcome) => outcome,
+            Err(reason) => {
+                skipped.push(format!("{}: llvm: {}", name, reason));
+                continue;
+            }
+        };
+        assert_eq!(interpreted, native, "{}: the interpreter (left) and the native executable (right) differ", name);
+        compared.push(name);
+    }
+
+    println!("compared {}: {}", compared.len(), compared.join(", "));
+    println!("skipped {}:\n  {}", skipped.len(), skipped.join("\n  "));
+    assert!(!compared.is_empty(), "no example ran on both backends");
+}
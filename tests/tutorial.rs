@@ -0,0 +1,133 @@
+//! # Tutorial
+//!
+//! Runs the lessons of `tutorial/` in order, which are written with the
+//! Arabic keywords. Every lesson must compile; a lesson with a `.err` file
+//! must instead fail with the diagnostic code on the file's first line, and
+//! each further line must appear in the diagnostic rendered in Arabic. A
+//! lesson with a `.out` file must produce exactly that output: what its
+//! `main` prints, the answers to its `// ?- goal.` queries, and the reading of
+//! each sentence of its `semantic` blocks.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use albayan_lib::diagnostics::{Diagnostic, DiagnosticRenderer, Language};
+use albayan_lib::interpreter::Interpreter;
+use albayan_lib::lexer::Lexer;
+use albayan_lib::parser::ast::{Item, Program};
+use albayan_lib::parser::Parser;
+use albayan_lib::repl::{Session, QUERY_PREFIX};
+use albayan_lib::{Compiler, CompilerOptions};
+
+fn lessons() -> Vec<PathBuf> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tutorial");
+    let mut lessons: Vec<PathBuf> = fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ab"))
+        .collect();
+    lessons.sort();
+    lessons
+}
+
+/// Lines printed by `main`, if the lesson has one
+fn main_output(program: &Program) -> Result<Vec<String>, String> {
+    let has_main = program.items.iter().any(|item| matches!(item, Item::Function(function) if function.name == "main"));
+    if !has_main {
+        return Ok(Vec::new());
+    }
+    let mut interpreter = Interpreter::new(program);
+    interpreter.call("main", Vec::new()).map_err(|panic| panic.message)?;
+    Ok(interpreter.take_output().lines().map(str::to_string).collect())
+}
+
+/// Each query and its answers, laid out like `albayan examples run` does
+fn query_answers(source: &str) -> Result<Vec<String>, String> {
+    let queries: Vec<&str> = source
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("//")?.trim().strip_prefix(QUERY_PREFIX))
+        .map(str::trim)
+        .collect();
+    if queries.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut session = Session::new(CompilerOptions::default());
+    if let Some(error) = session.execute(source).error {
+        return Err(error);
+    }
+    let mut lines = Vec::new();
+    for query in queries {
+        let result = session.execute(&format!("{} {}", QUERY_PREFIX, query));
+        if let Some(error) = result.error {
+            return Err(error);
+        }
+        lines.push(format!("{} {}", QUERY_PREFIX, query));
+        lines.extend(result.outputs.into_iter().map(|output| format!("   {}", output)));
+    }
+    Ok(lines)
+}
+
+/// `sentence => predicate(subject, object)` for each sentence of the
+/// lesson's top-level `semantic` blocks
+#[cfg(feature = "nlu")]
+fn sentence_readings(program: &Program) -> Vec<String> {
+    use albayan_lib::nlu::parse_simple_natural_language;
+
+    let mut lines = Vec::new();
+    for item in &program.items {
+        let Item::Semantic(block) = item else { continue };
+        for sentence in &block.sentences {
+            let reading = parse_simple_natural_language(sentence);
+            lines.push(format!(
+                "{} => {}({}, {})",
+                sentence,
+                reading.relation_type.predicate().unwrap_or("?"),
+                reading.subject.as_deref().unwrap_or("?"),
+                reading.object.as_deref().unwrap_or("?"),
+            ));
+        }
+    }
+    lines
+}
+
+#[test]
+fn test_tutorial_lessons() {
+    let lessons = lessons();
+    assert!(!lessons.is_empty(), "no lessons in tutorial/");
+
+    for lesson in lessons {
+        let name = lesson.file_name().unwrap().to_string_lossy().into_owned();
+        let source = fs::read_to_string(&lesson).unwrap();
+        let compiled = Compiler::new().compile_string(&source);
+
+        if let Ok(expected) = fs::read_to_string(lesson.with_extension("err")) {
+            let error = compiled.err().unwrap_or_else(|| panic!("{} compiled but should fail", name));
+            let mut expected = expected.lines();
+            assert_eq!(Diagnostic::from(&error).code, expected.next().unwrap_or_default().trim(), "{}: {}", name, error);
+            let rendered = DiagnosticRenderer::new(&name, &source)
+                .with_language(Language::Arabic)
+                .render(&Diagnostic::localized(&error, Language::Arabic));
+            for line in expected.map(str::trim).filter(|line| !line.is_empty()) {
+                assert!(rendered.contains(line), "{}: `{}` is not in\n{}", name, line, rendered);
+            }
+            continue;
+        }
+        if let Err(error) = compiled {
+            panic!("{} failed to compile: {}", name, error);
+        }
+
+        let Ok(expected) = fs::read_to_string(lesson.with_extension("out")) else { continue };
+        let program = Parser::new(Lexer::new(&source).tokenize().unwrap()).parse().unwrap();
+        let mut output = main_output(&program).unwrap_or_else(|message| panic!("{} panicked: {}", name, message));
+        output.extend(query_answers(&source).unwrap_or_else(|error| panic!("{}: {}", name, error)));
+        #[cfg(feature = "nlu")]
+        output.extend(sentence_readings(&program));
+        #[cfg(not(feature = "nlu"))]
+        if program.items.iter().any(|item| matches!(item, Item::Semantic(_))) {
+            continue;
+        }
+
+        let expected: Vec<&str> = expected.lines().collect();
+        assert_eq!(output, expected, "{} printed something else", name);
+    }
+}
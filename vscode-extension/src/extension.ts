@@ -76,6 +76,21 @@ export function activate(context: vscode.ExtensionContext) {
         }
     });
 
+    const openCanvasCommand = vscode.commands.registerCommand('albayan.openCanvas', () => {
+        openCanvasPanel();
+    });
+
+    const runWithCanvasCommand = vscode.commands.registerCommand('albayan.runWithCanvas', () => {
+        const activeEditor = vscode.window.activeTextEditor;
+        if (activeEditor && activeEditor.document.languageId === 'albayan') {
+            const filePath = activeEditor.document.fileName;
+            runAlbayanCommand('run', ['--canvas', `http://${canvasAddress()}`, filePath]);
+            openCanvasPanel();
+        } else {
+            vscode.window.showErrorMessage('No AlBayan file is currently open');
+        }
+    });
+
     // Register document formatting provider
     const formattingProvider = vscode.languages.registerDocumentFormattingProvider('albayan', {
         provideDocumentFormattingEdits(document: vscode.TextDocument): vscode.TextEdit[] {
@@ -112,6 +127,8 @@ export function activate(context: vscode.ExtensionContext) {
         runCommand,
         checkCommand,
         formatCommand,
+        openCanvasCommand,
+        runWithCanvasCommand,
         formattingProvider,
         hoverProvider,
         completionProvider,
@@ -137,6 +154,84 @@ function runAlbayanCommand(command: string, args: string[]) {
     terminal.show();
 }
 
+function canvasAddress(): string {
+    return vscode.workspace.getConfiguration('albayan').get<string>('canvas.address', '127.0.0.1:7878');
+}
+
+// Live canvas webview: follows the canvas protocol (one JSON message per server-sent event)
+let canvasPanel: vscode.WebviewPanel | undefined;
+
+function openCanvasPanel() {
+    if (canvasPanel) {
+        canvasPanel.reveal(vscode.ViewColumn.Two);
+    } else {
+        canvasPanel = vscode.window.createWebviewPanel(
+            'albayanCanvas',
+            'AlBayan Canvas',
+            vscode.ViewColumn.Two,
+            {
+                enableScripts: true,
+                retainContextWhenHidden: true
+            }
+        );
+        canvasPanel.onDidDispose(() => {
+            canvasPanel = undefined;
+        });
+    }
+
+    canvasPanel.webview.html = getCanvasHtml(`http://${canvasAddress()}/events`);
+}
+
+function getCanvasHtml(eventsUrl: string): string {
+    return `<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta http-equiv="Content-Security-Policy" content="default-src 'none'; img-src data:; style-src 'unsafe-inline'; script-src 'unsafe-inline'; connect-src ${eventsUrl}">
+    <title>AlBayan Canvas</title>
+    <style>
+        body { font-family: sans-serif; margin: 20px; }
+        #frame { image-rendering: pixelated; background: #fff; max-width: 100%; }
+    </style>
+</head>
+<body>
+    <div id="status">Waiting for \`albayan run --canvas\`...</div>
+    <img id="frame" alt="">
+    <div id="inference"></div>
+    <script>
+        const status = document.getElementById('status');
+        const frame = document.getElementById('frame');
+        const inference = document.getElementById('inference');
+
+        // The server only lives while the program runs, so keep retrying
+        function connect() {
+            const events = new EventSource('${eventsUrl}');
+            events.onmessage = (event) => {
+                const message = JSON.parse(event.data);
+                if (message.type === 'hello') {
+                    status.textContent = 'Connected: ' + message.session;
+                } else if (message.type === 'frame') {
+                    frame.src = 'data:image/png;base64,' + message.data;
+                    status.textContent = '#' + message.id + ' ' + message.label + ' (' + message.source + ')';
+                    inference.textContent = '';
+                } else if (message.type === 'inference') {
+                    inference.textContent = message.predicted_shape + ' - ' + (message.confidence * 100).toFixed(1) + '%';
+                } else if (message.type === 'clear') {
+                    frame.removeAttribute('src');
+                    inference.textContent = '';
+                } else if (message.type === 'end') {
+                    status.textContent += ' - finished';
+                    events.close();
+                    setTimeout(connect, 1000);
+                }
+            };
+        }
+        connect();
+    </script>
+</body>
+</html>`;
+}
+
 // Utility functions for AlBayan development
 export class AlBayanUtils {
     static async createNewProject(projectName: string, projectPath: string) {